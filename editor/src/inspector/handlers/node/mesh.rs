@@ -19,6 +19,9 @@ pub fn handle_mesh_property_changed(
                 Mesh::CAST_SHADOWS => {
                     make_command!(SetMeshCastShadowsCommand, handle, value)
                 }
+                Mesh::RECEIVE_SHADOWS => {
+                    make_command!(SetMeshReceiveShadowsCommand, handle, value)
+                }
                 Mesh::RENDER_PATH => {
                     make_command!(SetMeshRenderPathCommand, handle, value)
                 }
@@ -109,6 +109,7 @@ use rg3d::{
         VerticalAlignment,
     },
     material::{Material, PropertyValue},
+    renderer::RenderPassStage,
     resource::texture::{CompressionOptions, Texture, TextureKind, TextureState},
     scene::{
         debug::{Line, SceneDrawingContext},
@@ -902,7 +903,7 @@ impl Editor {
                 println!("Editor settings were loaded successfully!");
 
                 match engine
-                    .renderer
+                    .renderer_mut()
                     .set_quality_settings(&settings.graphics.quality)
                 {
                     Ok(_) => {
@@ -936,8 +937,8 @@ impl Editor {
 
         let root_grid = GridBuilder::new(
             WidgetBuilder::new()
-                .with_width(engine.renderer.get_frame_size().0 as f32)
-                .with_height(engine.renderer.get_frame_size().1 as f32)
+                .with_width(engine.renderer().get_frame_size().0 as f32)
+                .with_height(engine.renderer().get_frame_size().1 as f32)
                 .with_child(menu.menu)
                 .with_child(
                     DockingManagerBuilder::new(WidgetBuilder::new().on_row(1).with_child({
@@ -1165,7 +1166,7 @@ impl Editor {
             )),
         ));
 
-        engine.renderer.flush();
+        engine.renderer_mut().flush();
     }
 
     fn set_interaction_mode(&mut self, mode: Option<InteractionModeKind>, engine: &mut GameEngine) {
@@ -1811,7 +1812,7 @@ impl Editor {
 
                     engine.resource_manager.state().destroy_unused_resources();
 
-                    engine.renderer.flush();
+                    engine.renderer_mut().flush();
 
                     self.asset_browser
                         .set_working_directory(engine, &working_directory);
@@ -2114,8 +2115,10 @@ fn main() {
         TextureImportOptions::default().with_compression(CompressionOptions::NoCompression),
     );
 
-    let overlay_pass = OverlayRenderPass::new(engine.renderer.pipeline_state());
-    engine.renderer.add_render_pass(overlay_pass);
+    let overlay_pass = OverlayRenderPass::new(engine.renderer_mut().pipeline_state());
+    engine
+        .renderer_mut()
+        .add_render_pass(RenderPassStage::AfterTransparent, overlay_pass);
 
     let mut editor = Editor::new(&mut engine);
     let clock = Instant::now();
@@ -2154,17 +2157,23 @@ fn main() {
                             format!("Failed to set renderer size! Reason: {:?}", e),
                         );
                     }
+                    // Layout of the interface is in logical units.
+                    let scale = engine.user_interface.scale();
                     engine.user_interface.send_message(WidgetMessage::width(
                         editor.root_grid,
                         MessageDirection::ToWidget,
-                        size.width as f32,
+                        size.width as f32 / scale,
                     ));
                     engine.user_interface.send_message(WidgetMessage::height(
                         editor.root_grid,
                         MessageDirection::ToWidget,
-                        size.height as f32,
+                        size.height as f32 / scale,
                     ));
                 }
+                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                    // Resized event will follow, it will update size of the root grid.
+                    engine.user_interface.set_scale(scale_factor as f32);
+                }
                 _ => (),
             }
 
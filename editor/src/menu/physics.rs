@@ -85,6 +85,7 @@ impl PhysicsMenu {
                             local_anchor2: Default::default(),
                             local_axis2: Vector3::x(),
                         }),
+                        break_limits: Default::default(),
                     })))
                     .unwrap();
             } else if message.destination() == self.create_ball_joint {
@@ -96,6 +97,7 @@ impl PhysicsMenu {
                             local_anchor1: Default::default(),
                             local_anchor2: Default::default(),
                         }),
+                        break_limits: Default::default(),
                     })))
                     .unwrap();
             } else if message.destination() == self.create_prismatic_joint {
@@ -109,6 +111,7 @@ impl PhysicsMenu {
                             local_anchor2: Default::default(),
                             local_axis2: Vector3::x(),
                         }),
+                        break_limits: Default::default(),
                     })))
                     .unwrap();
             } else if message.destination() == self.create_fixed_joint {
@@ -122,6 +125,7 @@ impl PhysicsMenu {
                             local_anchor2_translation: Default::default(),
                             local_anchor2_rotation: Default::default(),
                         }),
+                        break_limits: Default::default(),
                     })))
                     .unwrap();
             }
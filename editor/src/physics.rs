@@ -11,6 +11,7 @@ use rg3d::{
         desc::{
             ColliderDesc, ColliderShapeDesc, JointDesc, JointParamsDesc, PhysicsDesc, RigidBodyDesc,
         },
+        material::PhysicsMaterial,
         ColliderHandle, JointHandle, RigidBodyHandle,
     },
     scene::{
@@ -135,6 +136,9 @@ impl Physics {
                 friction: c.friction(),
                 density: c.density(),
                 restitution: c.restitution(),
+                friction_combine_rule: c.friction_combine_rule().into(),
+                restitution_combine_rule: c.restitution_combine_rule().into(),
+                tag: PhysicsMaterial::from_collider(c).tag,
                 is_sensor: c.is_sensor(),
                 translation: c.position_wrt_parent().unwrap().translation.vector,
                 rotation: c.position_wrt_parent().unwrap().rotation,
@@ -183,6 +187,10 @@ impl Physics {
                 body1: ErasedHandle::from(*body_map.get(&j.body1).unwrap()),
                 body2: ErasedHandle::from(*body_map.get(&j.body2).unwrap()),
                 params: JointParamsDesc::from_params(&j.params),
+                break_limits: scene
+                    .physics
+                    .joints
+                    .break_limits(scene.physics.joints.handle_map().key_of(&h).unwrap()),
             });
             joint_handle_map.insert(
                 pool_handle,
@@ -298,6 +306,9 @@ impl Physics {
                     friction: c.friction,
                     density: c.density,
                     restitution: c.restitution,
+                    friction_combine_rule: c.friction_combine_rule,
+                    restitution_combine_rule: c.restitution_combine_rule,
+                    tag: c.tag,
                     is_sensor: c.is_sensor,
                     translation: c.translation,
                     rotation: c.rotation,
@@ -358,6 +369,7 @@ impl Physics {
                     .value_of(&j.body2.into())
                     .unwrap(),
                 params: j.params.clone(),
+                break_limits: j.break_limits,
             })
             .collect();
 
@@ -371,6 +383,7 @@ impl Physics {
                 joint_handle_map: engine_joint_handle_rapier_map,
                 gravity: Default::default(),
                 integration_parameters: Default::default(),
+                solver_settings: Default::default(),
             },
             binder,
         )
@@ -116,6 +116,10 @@ define_node_command!(SetMeshCastShadowsCommand("Set Mesh Cast Shadows", bool) wh
     get_set_swap!(self, node.as_mesh_mut(), cast_shadows, set_cast_shadows);
 });
 
+define_node_command!(SetMeshReceiveShadowsCommand("Set Mesh Receive Shadows", bool) where fn swap(self, node) {
+    get_set_swap!(self, node.as_mesh_mut(), receive_shadows, set_receive_shadows);
+});
+
 define_node_command!(SetMeshRenderPathCommand("Set Mesh Render Path", RenderPath) where fn swap(self, node) {
     get_set_swap!(self, node.as_mesh_mut(), render_path, set_render_path);
 });
@@ -401,9 +401,9 @@ impl SettingsWindow {
 
         // Apply only if anything changed.
         if settings != &old_settings {
-            if settings.graphics.quality != engine.renderer.get_quality_settings() {
+            if settings.graphics.quality != engine.renderer().get_quality_settings() {
                 if let Err(e) = engine
-                    .renderer
+                    .renderer_mut()
                     .set_quality_settings(&settings.graphics.quality)
                 {
                     self.sender
@@ -379,6 +379,12 @@ impl PathFixer {
                                                 .insert(SceneResource::Texture(texture.clone()));
                                         }
                                     }
+                                    Node::Water(water) => {
+                                        if let Some(texture) = water.normal_map() {
+                                            scene_resources
+                                                .insert(SceneResource::Texture(texture.clone()));
+                                        }
+                                    }
                                     Node::ParticleSystem(particle_system) => {
                                         if let Some(texture) = particle_system.texture() {
                                             scene_resources.insert(SceneResource::Texture(texture));
@@ -408,7 +414,14 @@ impl PathFixer {
                                             }
                                         }
                                     }
-                                    Node::Base(_) => {
+                                    Node::Impostor(impostor) => {
+                                        if let Some(model) = impostor.atlas().model() {
+                                            scene_resources.insert(SceneResource::Model(model));
+                                        }
+                                    }
+                                    Node::Base(_)
+                                    | Node::EnvironmentProbe(_)
+                                    | Node::WorldText(_) => {
                                         // Nothing
                                     }
                                 }
@@ -156,7 +156,7 @@ impl GameState for Game {
         engine.user_interface.send_message(TextMessage::text(
             self.debug_text,
             MessageDirection::ToWidget,
-            format!("Example - 2D\n{}", engine.renderer.get_statistics()),
+            format!("Example - 2D\n{}", engine.renderer().get_statistics()),
         ));
     }
 
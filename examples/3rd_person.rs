@@ -16,30 +16,41 @@
 //!
 //! Known bugs: Sometimes character will jump, but jumping animations is not playing.
 //!
+//! Camera follows the character using spring arm from `rg3d::utils::camera`, it does not
+//! penetrate walls and widens field of view while the character is sprinting.
+//!
+//! Press [F3] to show skeleton of the character, animated bones are green. Mismatches between
+//! skeleton of the character and bones of walk animation are printed to the log.
+//!
+//! Press [F4] to mirror animations of the character. There are no strafe animations in example
+//! data, but the same way "strafe right" can be made of "strafe left" clip.
+//!
+//! Press [F] to make the character flinch, flinch is an additive animation that is added on top
+//! of walk or idle, so the character keeps walking while flinching.
+//!
 //! Possible improvements:
-//!  - Smart camera - camera which will not penetrate walls.
 //!  - Separate animation machines for upper and lower body - upper machine might be
 //!    for combat, lower - for locomotion.
 //!  - Tons of them, this is simple example after all.
 
 pub mod shared;
 
-use crate::shared::{create_ui, fix_shadows_distance, Game, GameScene};
+use crate::shared::{create_ui, fix_shadows_distance, Game, LoadingScreen};
 
 use rg3d::{
-    core::algebra::Vector2,
-    event::{Event, VirtualKeyCode, WindowEvent},
+    core::{algebra::Vector2, futures::executor::block_on},
+    engine::resource_manager::MaterialSearchOptions,
+    event::{ElementState, Event, VirtualKeyCode, WindowEvent},
     event_loop::ControlFlow,
-    gui::{
-        message::MessageDirection, progress_bar::ProgressBarMessage, text::TextMessage,
-        widget::WidgetMessage,
-    },
+    gui::{message::MessageDirection, text::TextMessage, widget::WidgetMessage},
     renderer::QualitySettings,
+    scene::skeleton,
     utils::{
-        log::{Log, MessageKind},
+        log::{log_error, log_warn},
         translate_event,
     },
 };
+use std::{cell::RefCell, rc::Rc};
 
 fn main() {
     let (mut game, event_loop) = Game::new("Example 03 - 3rd person");
@@ -52,13 +63,26 @@ fn main() {
         Vector2::new(screen_size.width, screen_size.height),
     );
 
+    // Loading screen is a plugin - the engine will update it automatically. The plugin will put
+    // loaded scene in this slot.
+    let loaded_scene = Rc::new(RefCell::new(None));
+    let mut loading_screen = Some(game.engine.add_plugin(Box::new(LoadingScreen::new(
+        game.scene_loader.take().unwrap(),
+        &interface,
+        loaded_scene.clone(),
+    ))));
+
     let clock = std::time::Instant::now();
     let fixed_timestep = 1.0 / 60.0;
     let mut elapsed_time = 0.0;
+    let mut show_skeleton = false;
 
     // Finally run our event loop which will respond to OS and window events and update
     // engine state accordingly.
     event_loop.run(move |event, _, control_flow| {
+        // Plugins must receive OS events too.
+        game.engine.handle_os_event(&event);
+
         match event {
             Event::MainEventsCleared => {
                 // This is main game loop - it has fixed time step which means that game
@@ -73,54 +97,12 @@ fn main() {
                     // Put your game logic here.
                     // ************************
 
-                    // Check each frame if our scene is created - here we just trying to lock context
-                    // without blocking, it is important for main thread to be functional while other
-                    // thread still loading data.
-                    if let Ok(mut load_context) = game.load_context.as_ref().unwrap().try_lock() {
-                        if let Some(load_result) = load_context.scene_data.take() {
-                            // Add scene to engine - engine will take ownership over scene and will return
-                            // you a handle to scene which can be used later on to borrow it and do some
-                            // actions you need.
-                            game.game_scene = Some(GameScene {
-                                scene: game.engine.scenes.add(load_result.scene),
-                                player: load_result.player,
-                                reverb_effect: load_result.reverb_effect,
-                            });
-
-                            // Once scene is loaded, we should hide progress bar and text.
-                            game.engine
-                                .user_interface
-                                .send_message(WidgetMessage::visibility(
-                                    interface.progress_bar,
-                                    MessageDirection::ToWidget,
-                                    false,
-                                ));
-                            game.engine
-                                .user_interface
-                                .send_message(WidgetMessage::visibility(
-                                    interface.progress_text,
-                                    MessageDirection::ToWidget,
-                                    false,
-                                ));
+                    // Once the scene is loaded, loading screen is not needed anymore.
+                    if let Some(game_scene) = loaded_scene.borrow_mut().take() {
+                        game.game_scene = Some(game_scene);
+                        if let Some(loading_screen) = loading_screen.take() {
+                            game.engine.remove_plugin(loading_screen);
                         }
-
-                        // Report progress in UI.
-                        game.engine
-                            .user_interface
-                            .send_message(ProgressBarMessage::progress(
-                                interface.progress_bar,
-                                MessageDirection::ToWidget,
-                                load_context.progress,
-                            ));
-                        game.engine.user_interface.send_message(TextMessage::text(
-                            interface.progress_text,
-                            MessageDirection::ToWidget,
-                            format!(
-                                "Loading scene: {}%\n{}",
-                                load_context.progress * 100.0,
-                                load_context.message
-                            ),
-                        ));
                     }
 
                     // Update scene only if it is loaded.
@@ -128,15 +110,35 @@ fn main() {
                         // Use stored scene handle to borrow a mutable reference of scene in
                         // engine.
                         let scene = &mut game.engine.scenes[game_scene.scene];
-                        game_scene.player.update(scene, fixed_timestep);
+                        // Paused scene is still rendered, but must not be changed.
+                        if scene.enabled {
+                            game_scene.player.update(scene, fixed_timestep);
+                        }
+
+                        if show_skeleton {
+                            scene.drawing_context.clear_lines();
+                            skeleton::draw_skeleton(
+                                &mut scene.drawing_context,
+                                &scene.graph,
+                                &scene.animations,
+                                game_scene.player.model,
+                            );
+                        }
                     }
 
+                    let paused = game
+                        .game_scene
+                        .as_ref()
+                        .map_or(false, |s| !game.engine.scenes[s.scene].enabled);
+
                     let debug_text = format!(
                         "Example 03 - 3rd Person\n\
-                        [W][S][A][D] - walk, [SPACE] - jump.\n\
-                        Use [1][2][3][4] to select graphics quality.\n\
-                        {}",
-                        game.engine.renderer.get_statistics()
+                        [W][S][A][D] - walk, [SPACE] - jump, [SHIFT] - sprint.\n\
+                        Use [1][2][3][4] to select graphics quality, [ESC] to pause.\n\
+                        [F3] - show skeleton, [F4] - mirror animations, [F] - flinch.\n\
+                        {}{}",
+                        if paused { "PAUSED\n" } else { "" },
+                        game.engine.renderer().get_statistics()
                     );
                     game.engine.user_interface.send_message(TextMessage::text(
                         interface.debug_text,
@@ -147,7 +149,9 @@ fn main() {
                     // It is very important to "pump" messages from UI. Even if don't need to
                     // respond to such message, you should call this method, otherwise UI
                     // might behave very weird.
-                    while let Some(_ui_event) = game.engine.user_interface.poll_message() {
+                    while let Some(ui_message) = game.engine.user_interface.poll_message() {
+                        game.engine.handle_ui_message(&ui_message);
+
                         // ************************
                         // Put your data model synchronization code here. It should
                         // take message and update data in your game according to
@@ -176,10 +180,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = game.engine.set_frame_size(size.into()) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Unable to set frame size: {:?}", e),
-                            );
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
 
                         // Root UI node should be resized too, otherwise progress bar will stay
@@ -204,7 +205,51 @@ fn main() {
                         if let Some(code) = input.virtual_keycode {
                             // Handle key input events via `WindowEvent`, not via `DeviceEvent` (#32)
                             if let Some(game_scene) = game.game_scene.as_mut() {
-                                game_scene.player.handle_key_event(&input, fixed_timestep);
+                                let scene = &mut game.engine.scenes[game_scene.scene];
+
+                                if code == VirtualKeyCode::Escape
+                                    && input.state == ElementState::Pressed
+                                {
+                                    // Disabled scene is frozen - it won't be updated and its
+                                    // sounds will be paused, but it still will be rendered.
+                                    scene.enabled = !scene.enabled;
+                                }
+
+                                if scene.enabled {
+                                    game_scene.player.handle_key_event(&input, fixed_timestep);
+                                }
+
+                                if code == VirtualKeyCode::F4
+                                    && input.state == ElementState::Pressed
+                                {
+                                    let machine = &game_scene.player.locomotion_machine;
+                                    machine.set_mirrored(scene, !machine.is_mirrored(scene));
+                                }
+
+                                if code == VirtualKeyCode::F3
+                                    && input.state == ElementState::Pressed
+                                {
+                                    show_skeleton = !show_skeleton;
+                                    scene.drawing_context.clear_lines();
+
+                                    if show_skeleton {
+                                        // Animation is already loaded, so it is taken from cache.
+                                        if let Ok(walk) =
+                                            block_on(game.engine.resource_manager.request_model(
+                                                "examples/data/mutant/walk.fbx",
+                                                MaterialSearchOptions::RecursiveUp,
+                                            ))
+                                        {
+                                            for report in skeleton::validate_retarget(
+                                                &scene.graph,
+                                                game_scene.player.model,
+                                                &walk,
+                                            ) {
+                                                log_warn!("{}", report.to_string());
+                                            }
+                                        }
+                                    }
+                                }
                             }
 
                             let settings = match code {
@@ -217,7 +262,7 @@ fn main() {
 
                             if let Some(settings) = settings {
                                 game.engine
-                                    .renderer
+                                    .renderer_mut()
                                     .set_quality_settings(&fix_shadows_distance(settings))
                                     .unwrap();
                             }
@@ -235,9 +280,11 @@ fn main() {
             }
             Event::DeviceEvent { event, .. } => {
                 if let Some(game_scene) = game.game_scene.as_mut() {
-                    game_scene
-                        .player
-                        .handle_device_event(&event, fixed_timestep);
+                    if game.engine.scenes[game_scene.scene].enabled {
+                        game_scene
+                            .player
+                            .handle_device_event(&event, fixed_timestep);
+                    }
                 }
             }
             _ => *control_flow = ControlFlow::Poll,
@@ -2,13 +2,11 @@
 //!
 //! Difficulty: Medium.
 //!
-//! This example shows how to load scene in separate thread and how create standard
-//! loading screen which will show progress.
+//! This example shows how to load scene in background using engine's task system and how create
+//! standard loading screen which will show progress.
 
 pub mod shared;
 
-use std::sync::{Arc, Mutex};
-
 use crate::shared::create_camera;
 use rg3d::{
     animation::Animation,
@@ -19,8 +17,11 @@ use rg3d::{
         pool::Handle,
     },
     engine::{
-        framework::prelude::*, resource_manager::MaterialSearchOptions,
-        resource_manager::ResourceManager, Engine,
+        framework::prelude::*,
+        resource_manager::MaterialSearchOptions,
+        resource_manager::ResourceManager,
+        task::{TaskContext, TaskHandle},
+        Engine,
     },
     event::{ElementState, VirtualKeyCode, WindowEvent},
     event_loop::ControlFlow,
@@ -34,6 +35,7 @@ use rg3d::{
     },
     gui::{BuildContext, UiNode},
     scene::{node::Node, Scene},
+    utils::log::{log_error, log_info},
 };
 
 struct Interface {
@@ -97,18 +99,14 @@ struct SceneLoader {
 }
 
 impl SceneLoader {
-    async fn load_with(resource_manager: ResourceManager, context: Arc<Mutex<AsyncLoaderContext>>) {
+    async fn load_with(resource_manager: ResourceManager, context: &TaskContext<'_>) -> Self {
         let mut scene = Scene::new();
 
         // Set ambient light.
         scene.ambient_lighting_color = Color::opaque(200, 200, 200);
 
-        // It is important to lock context for short period of time so other thread can
-        // read data from it as soon as possible - not when everything was loaded.
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.0, "Creating camera...");
+        // Progress is stored in the task, main thread can read it at any time.
+        report_progress(context, 0.0, "Creating camera...");
 
         // Camera is our eyes in the world - you won't see anything without it.
         create_camera(
@@ -118,10 +116,7 @@ impl SceneLoader {
         )
         .await;
 
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.33, "Loading model...");
+        report_progress(context, 0.33, "Loading model...");
 
         // Load model resource. Is does *not* adds anything to our scene - it just loads a
         // resource then can be used later on to instantiate models from it on scene. Why
@@ -148,10 +143,7 @@ impl SceneLoader {
             // Our model is too big, fix it by scale.
             .set_scale(Vector3::new(0.05, 0.05, 0.05));
 
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.66, "Loading animation...");
+        report_progress(context, 0.66, "Loading animation...");
 
         // Add simple animation for our model. Animations are loaded from model resources -
         // this is because animation is a set of skeleton bones with their own transforms.
@@ -172,47 +164,19 @@ impl SceneLoader {
             .get(0)
             .unwrap();
 
-        context.lock().unwrap().report_progress(1.0, "Done");
+        report_progress(context, 1.0, "Done");
 
-        context.lock().unwrap().data = Some(Self {
+        Self {
             scene,
             model_handle,
             walk_animation,
-        });
+        }
     }
 }
 
-struct AsyncLoaderContext {
-    data: Option<SceneLoader>,
-    message: String,
-    progress: f32,
-}
-
-impl AsyncLoaderContext {
-    fn report_progress(&mut self, progress: f32, message: &str) {
-        self.progress = progress;
-        self.message = message.to_owned();
-        println!("Loading progress: {}% - {}", progress * 100.0, message);
-    }
-
-    fn load_with(resource_manager: ResourceManager) -> Arc<Mutex<Self>> {
-        // Create load context - it will be shared with caller and loader threads.
-        let context = Arc::new(Mutex::new(Self {
-            data: None,
-            message: "Starting..".to_string(),
-            progress: 0.0,
-        }));
-        let result = context.clone();
-
-        // Spawn separate thread which will create scene by loading various assets.
-        std::thread::spawn(move || {
-            // Scene will be loaded in separate thread.
-            futures::executor::block_on(SceneLoader::load_with(resource_manager, context))
-        });
-
-        // Immediately return shared context.
-        result
-    }
+fn report_progress(context: &TaskContext, progress: f32, message: &str) {
+    context.set_progress(progress);
+    log_info!("Loading progress: {}% - {}", progress * 100.0, message);
 }
 
 struct InputController {
@@ -231,7 +195,7 @@ struct Game {
     input_controller: InputController,
     model_angle: f32,
     game_scene: Option<GameScene>,
-    scene_loader: Arc<Mutex<AsyncLoaderContext>>,
+    scene_loader: TaskHandle<SceneLoader>,
 }
 
 impl GameState for Game {
@@ -255,56 +219,64 @@ impl GameState for Game {
             },
             model_angle: 180.0f32.to_radians(),
             game_scene: None,
-            scene_loader: AsyncLoaderContext::load_with(engine.resource_manager.clone()),
+            scene_loader: {
+                let resource_manager = engine.resource_manager.clone();
+                // Scene will be loaded on one of the threads of the engine's thread pool.
+                engine.tasks.spawn(move |context| {
+                    futures::executor::block_on(SceneLoader::load_with(resource_manager, context))
+                })
+            },
         }
     }
 
     fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
-        // Check each frame if our scene is created - here we just trying to lock context
-        // without blocking, it is important for main thread to be functional while other
-        // thread still loading data.
-        if let Ok(mut loader_context) = self.scene_loader.try_lock() {
-            if let Some(loader) = loader_context.data.take() {
-                self.game_scene = Some(GameScene {
-                    scene: engine.scenes.add(loader.scene),
-                    model_handle: loader.model_handle,
-                    walk_animation: loader.walk_animation,
-                });
-
-                // Once scene is loaded, we should hide progress bar and text.
-                engine
-                    .user_interface
-                    .send_message(WidgetMessage::visibility(
-                        self.interface.progress_bar,
-                        MessageDirection::ToWidget,
-                        false,
-                    ));
-                engine
-                    .user_interface
-                    .send_message(WidgetMessage::visibility(
+        // Check each frame if our scene is created - the handle is polled without blocking, it
+        // is important for main thread to be functional while the task is still loading data.
+        if self.game_scene.is_none() {
+            match self.scene_loader.try_take() {
+                Some(Ok(loader)) => {
+                    self.game_scene = Some(GameScene {
+                        scene: engine.scenes.add(loader.scene),
+                        model_handle: loader.model_handle,
+                        walk_animation: loader.walk_animation,
+                    });
+
+                    // Once scene is loaded, we should hide progress bar and text.
+                    engine
+                        .user_interface
+                        .send_message(WidgetMessage::visibility(
+                            self.interface.progress_bar,
+                            MessageDirection::ToWidget,
+                            false,
+                        ));
+                    engine
+                        .user_interface
+                        .send_message(WidgetMessage::visibility(
+                            self.interface.progress_text,
+                            MessageDirection::ToWidget,
+                            false,
+                        ));
+                }
+                Some(Err(e)) => {
+                    log_error!("Unable to load scene. Reason: {}", e);
+                }
+                None => {
+                    // Report progress in UI.
+                    let progress = self.scene_loader.progress();
+                    engine
+                        .user_interface
+                        .send_message(ProgressBarMessage::progress(
+                            self.interface.progress_bar,
+                            MessageDirection::ToWidget,
+                            progress,
+                        ));
+                    engine.user_interface.send_message(TextMessage::text(
                         self.interface.progress_text,
                         MessageDirection::ToWidget,
-                        false,
+                        format!("Loading scene: {}%", progress * 100.0),
                     ));
+                }
             }
-
-            // Report progress in UI.
-            engine
-                .user_interface
-                .send_message(ProgressBarMessage::progress(
-                    self.interface.progress_bar,
-                    MessageDirection::ToWidget,
-                    loader_context.progress,
-                ));
-            engine.user_interface.send_message(TextMessage::text(
-                self.interface.progress_text,
-                MessageDirection::ToWidget,
-                format!(
-                    "Loading scene: {}%\n{}",
-                    loader_context.progress * 100.0,
-                    loader_context.message
-                ),
-            ));
         }
 
         // Update scene only if it is loaded.
@@ -337,7 +309,7 @@ impl GameState for Game {
         }
 
         // While scene is loading, we will update progress bar.
-        let fps = engine.renderer.get_statistics().frames_per_second;
+        let fps = engine.renderer().get_statistics().frames_per_second;
         let debug_text = format!(
             "Example 02 - Asynchronous Scene Loading\nUse [A][D] keys to rotate model.\nFPS: {}",
             fps
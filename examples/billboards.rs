@@ -0,0 +1,269 @@
+//! Example - Billboards.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows health bars over heads of units and tree cards. Health bars use full billboard
+//! mode, so they always face the camera, trees rotate only around vertical axis. The scene is
+//! rendered by two cameras at once, billboards face each camera separately.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        math::Rect,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{Material, PropertyValue},
+    scene::{
+        base::{BaseBuilder, BillboardMode},
+        camera::CameraBuilder,
+        light::{directional::DirectionalLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Unit {
+    pivot: Handle<Node>,
+    health_bar: Handle<Node>,
+    phase: f32,
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    camera_pivot: Handle<Node>,
+    units: Vec<Unit>,
+    time: f32,
+}
+
+fn make_material(color: Color) -> Arc<Mutex<Material>> {
+    let mut material = Material::standard();
+    material
+        .set_property(
+            &ImmutableString::new("diffuseColor"),
+            PropertyValue::Color(color),
+        )
+        .unwrap();
+    Arc::new(Mutex::new(material))
+}
+
+fn create_unit(scene: &mut Scene, phase: f32) -> Unit {
+    let health_bar = MeshBuilder::new(
+        BaseBuilder::new()
+            .with_billboard_mode(BillboardMode::Full)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 1.3, 0.0))
+                    .build(),
+            ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_quad(&Matrix4::new_nonuniform_scaling(&Vector3::new(
+            1.0, 0.15, 1.0,
+        ))),
+    )))
+    .with_material(make_material(Color::GREEN))
+    .build()])
+    .with_cast_shadows(false)
+    .build(&mut scene.graph);
+
+    let body = MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(Vector3::new(0.0, 0.5, 0.0))
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+            0.6, 1.0, 0.4,
+        ))),
+    )))
+    .with_material(make_material(Color::opaque(200, 120, 80)))
+    .build()])
+    .build(&mut scene.graph);
+
+    // Pivot rotates while the unit walks, health bar must not rotate with it.
+    let pivot = BaseBuilder::new()
+        .with_children(&[body, health_bar])
+        .build(&mut scene.graph);
+
+    Unit {
+        pivot,
+        health_bar,
+        phase,
+    }
+}
+
+fn create_tree(scene: &mut Scene, position: Vector3<f32>) {
+    MeshBuilder::new(
+        BaseBuilder::new()
+            .with_billboard_mode(BillboardMode::AxisAligned(Vector3::y()))
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(position)
+                    .build(),
+            ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_quad(
+            &(Matrix4::new_translation(&Vector3::new(0.0, 1.5, 0.0))
+                * Matrix4::new_nonuniform_scaling(&Vector3::new(1.5, 3.0, 1.0))),
+        ),
+    )))
+    .with_material(make_material(Color::opaque(40, 140, 40)))
+    .build()])
+    .build(&mut scene.graph);
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(100, 100, 100);
+
+        // Main camera orbits around the scene and occupies left half of the screen.
+        let camera = block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 4.0, -10.0),
+            &mut scene.graph,
+        ));
+        if let Node::Camera(camera) = &mut scene.graph[camera] {
+            camera.set_viewport(Rect::new(0.0, 0.0, 0.5, 1.0));
+        }
+        scene.graph[camera]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::x_axis(),
+                20.0f32.to_radians(),
+            ));
+        let camera_pivot = BaseBuilder::new()
+            .with_children(&[camera])
+            .build(&mut scene.graph);
+
+        // Second camera looks from above and occupies right half of the screen.
+        CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 12.0, -4.0))
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        70.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        )
+        .with_viewport(Rect::new(0.5, 0.0, 0.5, 1.0))
+        .build(&mut scene.graph);
+
+        DirectionalLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        60.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        ))
+        .build(&mut scene.graph);
+
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    20.0, 0.1, 20.0,
+                ))),
+            )))
+            .with_material(make_material(Color::opaque(120, 100, 80)))
+            .build()])
+            .build(&mut scene.graph);
+
+        let units = (0..5)
+            .map(|i| create_unit(&mut scene, i as f32 * 2.0 * std::f32::consts::PI / 5.0))
+            .collect();
+
+        for i in 0..8 {
+            let angle = i as f32 * 2.0 * std::f32::consts::PI / 8.0;
+            create_tree(
+                &mut scene,
+                Vector3::new(7.0 * angle.cos(), 0.0, 7.0 * angle.sin()),
+            );
+        }
+
+        let scene = engine.scenes.add(scene);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene,
+            camera_pivot,
+            units,
+            time: 0.0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        self.time += dt;
+
+        let graph = &mut engine.scenes[self.scene].graph;
+
+        graph[self.camera_pivot].local_transform_mut().set_rotation(
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.time * 0.2),
+        );
+
+        for unit in self.units.iter() {
+            // Units walk in a circle.
+            let angle = unit.phase + self.time * 0.5;
+            graph[unit.pivot]
+                .local_transform_mut()
+                .set_position(Vector3::new(3.0 * angle.cos(), 0.0, 3.0 * angle.sin()))
+                .set_rotation(UnitQuaternion::from_axis_angle(&Vector3::y_axis(), -angle));
+
+            // Billboard keeps scale of the node, so it can be used to show amount of health.
+            let health = 0.55 + 0.45 * (self.time + unit.phase).sin();
+            graph[unit.health_bar]
+                .local_transform_mut()
+                .set_scale(Vector3::new(health, 1.0, 1.0));
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Billboards\nFPS: {}",
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Billboards")
+        .run();
+}
@@ -26,7 +26,7 @@ impl GameState for Game {
         );
 
         engine
-            .renderer
+            .renderer_mut()
             .set_backbuffer_clear_color(Color::opaque(120, 120, 120));
 
         ImageBuilder::new(
@@ -0,0 +1,183 @@
+//! Example - Conveyor belts.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to tile and scroll textures of a surface. Both belts share the same
+//! material, but each has its own texture coordinates transform, so they move at different speeds.
+//! Press [Up]/[Down] to change speed, press [B] to change mip bias of the belts.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector2, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData, UvTransform},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+const BELT_LENGTH: f32 = 12.0;
+const MIP_BIASES: [f32; 3] = [0.0, -1.0, 2.0];
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    // Belt and its speed relative to the global one.
+    belts: Vec<(Handle<Node>, f32)>,
+    speed: f32,
+    mip_bias_index: usize,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let resource_manager = engine.resource_manager.clone();
+
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(120, 120, 120);
+
+        block_on(create_camera(
+            resource_manager.clone(),
+            Vector3::new(0.0, 5.0, -8.0),
+            &mut scene.graph,
+        ));
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 6.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(20.0)
+        .build(&mut scene.graph);
+
+        let mut material = Material::standard();
+        material
+            .set_property(
+                &ImmutableString::new("diffuseTexture"),
+                PropertyValue::Sampler {
+                    value: Some(resource_manager.request_texture("examples/data/floor.jpg", None)),
+                    fallback: SamplerFallback::White,
+                },
+            )
+            .unwrap();
+        let material = Arc::new(Mutex::new(material));
+
+        let belts = [(-1.5, 1.0), (1.5, 0.5)]
+            .iter()
+            .map(|&(x, relative_speed)| {
+                let belt = MeshBuilder::new(
+                    BaseBuilder::new().with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(Vector3::new(x, 0.0, 0.0))
+                            .build(),
+                    ),
+                )
+                .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                    SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                        2.0,
+                        0.2,
+                        BELT_LENGTH,
+                    ))),
+                )))
+                .with_material(material.clone())
+                // Repeat the texture along the belt, otherwise it will be stretched.
+                .with_uv_transform(UvTransform {
+                    scale: Vector2::new(1.0, BELT_LENGTH / 2.0),
+                    ..Default::default()
+                })
+                .build()])
+                .build(&mut scene.graph);
+                (belt, relative_speed)
+            })
+            .collect();
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            belts,
+            speed: 0.5,
+            mip_bias_index: 0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        for &(belt, relative_speed) in self.belts.iter() {
+            for surface in scene.graph[belt].as_mesh_mut().surfaces_mut() {
+                let mut uv_transform = surface.uv_transform();
+                // Keep offset in [0; 1) range to prevent precision loss over time.
+                uv_transform.offset.y =
+                    (uv_transform.offset.y + self.speed * relative_speed * dt).rem_euclid(1.0);
+                surface.set_uv_transform(uv_transform);
+                surface.set_mip_bias(MIP_BIASES[self.mip_bias_index]);
+            }
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Conveyor Belts\n[Up]/[Down] - change speed, [B] - change mip bias\n\
+                Speed: {:.2}\nMip bias: {}\nFPS: {}",
+                self.speed,
+                MIP_BIASES[self.mip_bias_index],
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::Up) => self.speed += 0.1,
+                    Some(VirtualKeyCode::Down) => self.speed -= 0.1,
+                    Some(VirtualKeyCode::B) => {
+                        self.mip_bias_index = (self.mip_bias_index + 1) % MIP_BIASES.len()
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Conveyor Belts")
+        .run();
+}
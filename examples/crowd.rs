@@ -0,0 +1,216 @@
+//! Example - Crowd.
+//!
+//! Difficulty: Easy.
+//!
+//! This example is a stress test for skinned meshes rendering. It spawns lots of mutants that play
+//! the same walk animation with different offsets. Instances of the same skinned surface are drawn
+//! by a single instanced draw call, press [I] to switch instancing on and off and compare draw
+//! calls and frame time of both modes.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    animation::Animation,
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, resource_manager::MaterialSearchOptions, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    rand::Rng,
+    renderer::Statistics,
+    scene::{
+        base::BaseBuilder,
+        light::{directional::DirectionalLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+// Total amount of mutants is a square of this value.
+const ROW_SIZE: i32 = 12;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    animations: Vec<Handle<Animation>>,
+    // Statistics of the last frame rendered with and without instancing.
+    separate_stats: Option<Statistics>,
+    instanced_stats: Option<Statistics>,
+}
+
+fn describe(stats: &Option<Statistics>) -> String {
+    match stats {
+        Some(stats) => format!(
+            "{} draw calls, {:.2} ms",
+            stats.geometry.draw_calls,
+            stats.pure_frame_time * 1000.0
+        ),
+        None => "press [I] to measure".to_owned(),
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(100, 100, 100);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 25.0, -30.0),
+            &mut scene.graph,
+        ));
+
+        DirectionalLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        45.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        ))
+        .build(&mut scene.graph);
+
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    200.0, 0.1, 200.0,
+                ))),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+
+        let (model, walk) = block_on(async {
+            rg3d::core::futures::join!(
+                engine.resource_manager.request_model(
+                    "examples/data/mutant/mutant.FBX",
+                    MaterialSearchOptions::RecursiveUp,
+                ),
+                engine.resource_manager.request_model(
+                    "examples/data/mutant/walk.fbx",
+                    MaterialSearchOptions::RecursiveUp,
+                )
+            )
+        });
+        let (model, walk) = (model.unwrap(), walk.unwrap());
+
+        let mut rng = rg3d::rand::thread_rng();
+        let mut animations = Vec::new();
+        for z in 0..ROW_SIZE {
+            for x in 0..ROW_SIZE {
+                let mutant = model.instantiate_geometry(&mut scene);
+                scene.graph[mutant]
+                    .local_transform_mut()
+                    .set_scale(Vector3::new(0.05, 0.05, 0.05))
+                    .set_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::y_axis(),
+                        180.0f32.to_radians(),
+                    ))
+                    .set_position(Vector3::new(
+                        (x - ROW_SIZE / 2) as f32 * 6.0,
+                        0.0,
+                        z as f32 * 6.0,
+                    ));
+
+                let animation = walk.retarget_animations(mutant, &mut scene)[0];
+                let animation_ref = &mut scene.animations[animation];
+                // Offset walk cycles, so the crowd won't walk in lockstep.
+                let length = animation_ref.length();
+                animation_ref.set_time_position(rng.gen_range(0.0..length));
+                animations.push(animation);
+            }
+        }
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            animations,
+            separate_stats: None,
+            instanced_stats: None,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        for &animation in self.animations.iter() {
+            scene.animations[animation]
+                .get_pose()
+                .apply(&mut scene.graph);
+        }
+
+        let use_instancing = engine
+            .renderer()
+            .get_quality_settings()
+            .use_skinned_instancing;
+        let statistics = engine.renderer().get_statistics();
+        if use_instancing {
+            self.instanced_stats = Some(statistics);
+        } else {
+            self.separate_stats = Some(statistics);
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Crowd\n\
+                Mutants: {}\n\
+                [I] - toggle instanced skinning (now: {})\n\
+                Without instancing: {}\n\
+                With instancing: {}\n\
+                {}",
+                self.animations.len(),
+                if use_instancing { "on" } else { "off" },
+                describe(&self.separate_stats),
+                describe(&self.instanced_stats),
+                statistics
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed
+                && input.virtual_keycode == Some(VirtualKeyCode::I)
+            {
+                let mut settings = engine.renderer().get_quality_settings();
+                settings.use_skinned_instancing = !settings.use_skinned_instancing;
+                engine
+                    .renderer_mut()
+                    .set_quality_settings(&settings)
+                    .unwrap();
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Crowd")
+        .run();
+}
@@ -0,0 +1,247 @@
+//! Example - Destructible level geometry.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to modify static geometry at runtime. Floor is made of tiles which are
+//! converted into single triangle mesh collider, when you press [Space] a section of the floor
+//! under the ball is removed and the ball falls through the hole. Press [R] to restore the floor.
+
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        math::aabb::AxisAlignedBoundingBox,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    physics3d::{
+        rapier::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder, na::Isometry3},
+        ColliderHandle, RigidBodyHandle,
+    },
+    scene::{
+        base::BaseBuilder,
+        camera::CameraBuilder,
+        graph::Graph,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+const HOLE_RADIUS: f32 = 1.5;
+
+struct Game {
+    scene: Handle<Scene>,
+    debug_text: Handle<UiNode>,
+    tiles: Vec<Handle<Node>>,
+    floor_body: RigidBodyHandle,
+    floor_collider: ColliderHandle,
+    ball_body: RigidBodyHandle,
+}
+
+fn make_tile(graph: &mut Graph, position: Vector3<f32>) -> Handle<Node> {
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+            0.95, 0.2, 0.95,
+        ))),
+    )))
+    .build()])
+    .build(graph)
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 8.0, -12.0))
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        30.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        )
+        .build(&mut scene.graph);
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 10.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(30.0)
+        .build(&mut scene.graph);
+
+        // Floor is a set of tiles, all of them will be merged into single trimesh.
+        let mut tiles = Vec::new();
+        for z in -5..5 {
+            for x in -5..5 {
+                tiles.push(make_tile(
+                    &mut scene.graph,
+                    Vector3::new(x as f32 + 0.5, 0.0, z as f32 + 0.5),
+                ));
+            }
+        }
+        let floor = BaseBuilder::new()
+            .with_children(&tiles)
+            .build(&mut scene.graph);
+        scene.graph.update_hierarchical_data();
+
+        let floor_body = scene.physics.mesh_to_trimesh(floor, &scene.graph);
+        // Bind floor node with its body, so the trimesh can be re-synced with the node later on.
+        scene.physics_binder.bind(floor, floor_body);
+        let floor_collider = scene.physics.bodies.get(&floor_body).unwrap().colliders()[0];
+        let floor_collider = *scene
+            .physics
+            .colliders
+            .handle_map()
+            .key_of(&floor_collider)
+            .unwrap();
+
+        // Add a ball that will rest on the floor.
+        let ball = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_sphere(16, 16, 0.5, &Matrix4::identity()),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+        let ball_body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry3::new(
+                    Vector3::new(0.0, 2.0, 0.0),
+                    Default::default(),
+                ))
+                .build(),
+        );
+        scene
+            .physics
+            .add_collider(ColliderBuilder::ball(0.5).build(), &ball_body);
+        scene.physics_binder.bind(ball, ball_body);
+
+        Self {
+            scene: engine.scenes.add(scene),
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            tiles,
+            floor_body,
+            floor_collider,
+            ball_body,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Destructible geometry\n\
+                    Press [Space] to make a hole under the ball, [R] to restore the floor.\n\
+                    {}",
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state != ElementState::Pressed {
+                return;
+            }
+
+            let scene = &mut engine.scenes[self.scene];
+
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::Space) => {
+                    let ball_position = scene
+                        .physics
+                        .bodies
+                        .get(&self.ball_body)
+                        .unwrap()
+                        .position()
+                        .translation
+                        .vector;
+
+                    // Align bounds with tiles, so every tile will be removed entirely.
+                    let bounds = AxisAlignedBoundingBox::from_min_max(
+                        Vector3::new(
+                            (ball_position.x - HOLE_RADIUS).round(),
+                            -1.0,
+                            (ball_position.z - HOLE_RADIUS).round(),
+                        ),
+                        Vector3::new(
+                            (ball_position.x + HOLE_RADIUS).round(),
+                            1.0,
+                            (ball_position.z + HOLE_RADIUS).round(),
+                        ),
+                    );
+
+                    // Remove triangles from collision and hide tiles from the same area. Ball
+                    // is sleeping at this moment, it will be woken up automatically.
+                    scene
+                        .physics
+                        .remove_trimesh_triangles(&self.floor_collider, &bounds);
+                    for &tile in self.tiles.iter() {
+                        if bounds.is_contains_point(scene.graph[tile].global_position()) {
+                            scene.graph[tile].set_visibility(false);
+                        }
+                    }
+                }
+                Some(VirtualKeyCode::R) => {
+                    for &tile in self.tiles.iter() {
+                        scene.graph[tile].set_visibility(true);
+                    }
+
+                    // Re-sync trimesh with floor node.
+                    scene.physics.rebuild_bound_trimesh(
+                        &self.floor_body,
+                        &scene.physics_binder,
+                        &scene.graph,
+                    );
+
+                    let ball = scene.physics.bodies.get_mut(&self.ball_body).unwrap();
+                    ball.set_position(
+                        Isometry3::new(Vector3::new(0.0, 2.0, 0.0), Default::default()),
+                        true,
+                    );
+                    ball.set_linvel(Default::default(), true);
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Destructible geometry")
+        .run();
+}
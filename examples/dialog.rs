@@ -0,0 +1,138 @@
+//! Example - Dialog.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to use styled text in a dialog box: colored keywords, bold numbers,
+//! bigger text and inline icons. Most of the lines are written using markup, the last one is
+//! built from spans. Click "Next" to show next line of the dialog.
+
+use rg3d::{
+    core::{algebra::Vector2, color::Color, pool::Handle},
+    engine::{framework::prelude::*, Engine},
+    gui::{
+        button::{ButtonBuilder, ButtonMessage},
+        formatted_text::{InlineImage, TextSpan, WrapMode},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        HorizontalAlignment, Thickness, UiNode,
+    },
+    utils::into_gui_texture,
+};
+
+const LINES: [&str; 4] = [
+    "Welcome, traveler! The road to the north is [color=#ff4040]dangerous[/color] these days.",
+    "Wolves took [b]12[/b] sheep last week, and the guards refuse to leave the walls.",
+    "Take this [icon=ship] and sail across the lake, it is [size=22]much[/size] safer.",
+    // Unknown tags are shown as is.
+    "Oh, and if you see a [dragon], [b][color=#ffd040]run[/color][/b].",
+];
+
+struct Game {
+    text: Handle<UiNode>,
+    next: Handle<UiNode>,
+    line: usize,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        // Register an icon for `[icon=ship]` tag.
+        let ship = engine
+            .resource_manager
+            .request_texture("examples/data/starship.png", None);
+        engine.user_interface.set_inline_icon(
+            "ship",
+            InlineImage::new(into_gui_texture(ship)).with_size(Vector2::new(20.0, 20.0)),
+        );
+
+        let ctx = &mut engine.user_interface.build_ctx();
+
+        let text;
+        let next;
+        WindowBuilder::new(
+            WidgetBuilder::new()
+                .with_width(420.0)
+                .with_height(180.0)
+                .with_desired_position(Vector2::new(50.0, 50.0)),
+        )
+        .with_title(WindowTitle::text("Old Fisherman"))
+        .can_close(false)
+        .with_content(
+            GridBuilder::new(
+                WidgetBuilder::new()
+                    .with_child({
+                        text = TextBuilder::new(
+                            WidgetBuilder::new().with_margin(Thickness::uniform(4.0)),
+                        )
+                        .with_wrap(WrapMode::Word)
+                        .with_font_size(18.0)
+                        .with_markup(LINES[0])
+                        .build(ctx);
+                        text
+                    })
+                    .with_child({
+                        next = ButtonBuilder::new(
+                            WidgetBuilder::new()
+                                .on_row(1)
+                                .with_width(80.0)
+                                .with_margin(Thickness::uniform(4.0))
+                                .with_horizontal_alignment(HorizontalAlignment::Right),
+                        )
+                        .with_text("Next")
+                        .build(ctx);
+                        next
+                    }),
+            )
+            .add_row(Row::stretch())
+            .add_row(Row::strict(30.0))
+            .add_column(Column::stretch())
+            .build(ctx),
+        )
+        .build(ctx);
+
+        Self {
+            text,
+            next,
+            line: 0,
+        }
+    }
+
+    fn on_ui_message(&mut self, engine: &mut Engine, message: UiMessage) {
+        if let Some(ButtonMessage::Click) = message.data::<ButtonMessage>() {
+            if message.destination() == self.next {
+                // The last line is built from spans.
+                self.line = (self.line + 1) % (LINES.len() + 1);
+
+                let message = if let Some(line) = LINES.get(self.line) {
+                    TextMessage::markup(self.text, MessageDirection::ToWidget, line.to_string())
+                } else {
+                    // The same styles could be set without markup.
+                    TextMessage::spans(
+                        self.text,
+                        MessageDirection::ToWidget,
+                        vec![
+                            TextSpan::new("That is all. Come back when you have "),
+                            TextSpan::new("100")
+                                .with_bold(true)
+                                .with_color(Color::opaque(255, 215, 0)),
+                            TextSpan::new(" gold."),
+                        ],
+                    )
+                };
+                engine.user_interface.send_message(message);
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Dialog")
+        .run();
+}
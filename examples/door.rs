@@ -0,0 +1,295 @@
+//! Example - Doors.
+//!
+//! Difficulty: Medium.
+//!
+//! This example shows how to make doors that the player can open. There are two doors in Sponza,
+//! the left one is a physical door on a hinge that can be pushed by the player and that is held
+//! closed by a latch, the right one is a kinematic door that moves only on request. When the player
+//! comes close to a door, a prompt is shown. Use [W][S][A][D] to move, [E] to open or close a door.
+
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector2, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, resource_manager::MaterialSearchOptions, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        UiNode,
+    },
+    physics3d::{
+        rapier::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder},
+        ColliderHandle,
+    },
+    scene::{
+        base::BaseBuilder,
+        camera::CameraBuilder,
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+    utils::door::{Door, DoorEvent, DoorMode},
+};
+use std::sync::Arc;
+
+const SPAWN_POINT: Vector3<f32> = Vector3::new(-7.5, 1.0, -2.0);
+const PLAYER_SPEED: f32 = 3.0;
+const DOOR_SIZE: Vector3<f32> = Vector3::new(1.2, 2.2, 0.1);
+
+struct InputController {
+    move_forward: bool,
+    move_backward: bool,
+    move_left: bool,
+    move_right: bool,
+    use_door: bool,
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    prompt: Handle<UiNode>,
+    scene: Handle<Scene>,
+    player: Handle<Node>,
+    doors: Vec<Door>,
+    input: InputController,
+}
+
+fn make_door(
+    scene: &mut Scene,
+    position: Vector3<f32>,
+    mode: DoorMode,
+    player: ColliderHandle,
+) -> Door {
+    let leaf = MeshBuilder::new(BaseBuilder::new())
+        .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+            SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&DOOR_SIZE)),
+        )))
+        .build()])
+        .build(&mut scene.graph);
+
+    let body = match mode {
+        DoorMode::Kinematic => RigidBodyBuilder::new_kinematic_position_based(),
+        DoorMode::Dynamic => RigidBodyBuilder::new_dynamic(),
+    }
+    .translation(position)
+    .build();
+    let body = scene.physics.add_body(body);
+    scene.physics.add_collider(
+        ColliderBuilder::cuboid(DOOR_SIZE.x * 0.5, DOOR_SIZE.y * 0.5, DOOR_SIZE.z * 0.5).build(),
+        &body,
+    );
+    scene.physics_binder.bind(leaf, body);
+
+    // Interaction trigger covers the door way from both sides.
+    let trigger_body = scene
+        .physics
+        .add_body(RigidBodyBuilder::new_static().translation(position).build());
+    let trigger = scene.physics.add_collider(
+        ColliderBuilder::cuboid(DOOR_SIZE.x, DOOR_SIZE.y * 0.5, 1.5)
+            .sensor(true)
+            .build(),
+        &trigger_body,
+    );
+
+    Door {
+        node: leaf,
+        mode,
+        // Hinge is at the left edge of the door.
+        hinge_anchor: Vector3::new(-DOOR_SIZE.x * 0.5, 0.0, 0.0),
+        hinge_axis: Vector3::y(),
+        open_angle: 100.0f32.to_radians(),
+        min_angle: -110.0f32.to_radians(),
+        max_angle: 110.0f32.to_radians(),
+        speed: 2.5,
+        trigger,
+        interactors: vec![player],
+        ..Default::default()
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(150, 150, 150);
+
+        let sponza = block_on(engine.resource_manager.request_model(
+            "examples/data/sponza/Sponza.rgs",
+            MaterialSearchOptions::RecursiveUp,
+        ))
+        .unwrap()
+        .instantiate_geometry(&mut scene);
+        scene.physics.mesh_to_trimesh(sponza, &scene.graph);
+
+        // Player is a capsule with a camera.
+        let camera = CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 0.6, 0.0))
+                    .build(),
+            ),
+        )
+        .build(&mut scene.graph);
+        let player = BaseBuilder::new()
+            .with_name("Player")
+            .with_children(&[camera])
+            .build(&mut scene.graph);
+        let player_body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(SPAWN_POINT)
+                .lock_rotations()
+                .build(),
+        );
+        let player_collider = scene
+            .physics
+            .add_collider(ColliderBuilder::capsule_y(0.5, 0.3).build(), &player_body);
+        scene.physics_binder.bind(player, player_body);
+
+        let doors = vec![
+            make_door(
+                &mut scene,
+                Vector3::new(-6.0, 1.1, 1.0),
+                DoorMode::Dynamic,
+                player_collider,
+            ),
+            make_door(
+                &mut scene,
+                Vector3::new(-9.0, 1.1, 1.0),
+                DoorMode::Kinematic,
+                player_collider,
+            ),
+        ];
+
+        let ctx = &mut engine.user_interface.build_ctx();
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new()).build(ctx),
+            prompt: TextBuilder::new(
+                WidgetBuilder::new()
+                    .with_visibility(false)
+                    .with_desired_position(Vector2::new(300.0, 300.0)),
+            )
+            .with_text("Press [E] to open or close the door")
+            .build(ctx),
+            scene: engine.scenes.add(scene),
+            player,
+            doors,
+            input: InputController {
+                move_forward: false,
+                move_backward: false,
+                move_left: false,
+                move_right: false,
+                use_door: false,
+            },
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        for door in self.doors.iter_mut() {
+            if self.input.use_door && door.has_interactors() {
+                door.request_toggle();
+            }
+
+            for event in door.update(scene, dt) {
+                let visibility = match event {
+                    DoorEvent::InteractorEntered(_) => true,
+                    DoorEvent::InteractorLeft(_) => false,
+                    _ => continue,
+                };
+                engine
+                    .user_interface
+                    .send_message(WidgetMessage::visibility(
+                        self.prompt,
+                        MessageDirection::ToWidget,
+                        visibility,
+                    ));
+            }
+        }
+        self.input.use_door = false;
+
+        // Move the player.
+        let mut velocity = Vector3::default();
+        if self.input.move_forward {
+            velocity.z += 1.0;
+        }
+        if self.input.move_backward {
+            velocity.z -= 1.0;
+        }
+        if self.input.move_left {
+            velocity.x += 1.0;
+        }
+        if self.input.move_right {
+            velocity.x -= 1.0;
+        }
+        let velocity = velocity
+            .try_normalize(f32::EPSILON)
+            .map_or(Vector3::default(), |v| v.scale(PLAYER_SPEED));
+
+        if let Some(body) = scene.physics_binder.body_of(self.player) {
+            if let Some(body) = scene.physics.bodies.get_mut(body) {
+                let vertical = body.linvel().y;
+                body.set_linvel(Vector3::new(velocity.x, vertical, velocity.z), true);
+            }
+        }
+
+        let states = self
+            .doors
+            .iter()
+            .map(|door| {
+                format!(
+                    "{:?} door: {:?}, {:.0} degrees",
+                    door.mode,
+                    door.state(),
+                    door.angle().to_degrees()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Doors\n\
+                Use [W][S][A][D] to move, [E] to use a door.\n{}\n{}",
+                states,
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            let pressed = input.state == ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::W) => self.input.move_forward = pressed,
+                Some(VirtualKeyCode::S) => self.input.move_backward = pressed,
+                Some(VirtualKeyCode::A) => self.input.move_left = pressed,
+                Some(VirtualKeyCode::D) => self.input.move_right = pressed,
+                Some(VirtualKeyCode::E) if pressed => self.input.use_door = true,
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Doors")
+        .run();
+}
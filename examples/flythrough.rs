@@ -0,0 +1,141 @@
+//! Example - Camera flythrough.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to move a camera along a spline at constant speed. Press [D] to show or
+//! hide the spline, press [P] to pause the camera.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{algebra::Vector3, color::Color, futures::executor::block_on, pool::Handle},
+    engine::{framework::prelude::*, resource_manager::MaterialSearchOptions, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    scene::{
+        spline::{PathEndBehavior, PathFollower, Spline, SplineKind, SplinePoint},
+        Scene,
+    },
+};
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    follower: PathFollower,
+    draw_spline: bool,
+    paused: bool,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(150, 150, 150);
+
+        let camera = block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(-8.0, 2.0, 0.0),
+            &mut scene.graph,
+        ));
+
+        block_on(engine.resource_manager.request_model(
+            "examples/data/sponza/Sponza.rgs",
+            MaterialSearchOptions::RecursiveUp,
+        ))
+        .unwrap()
+        .instantiate_geometry(&mut scene);
+
+        // A loop around the atrium: low along the floor and high under the arches.
+        let spline = scene.splines.add(
+            Spline::new(SplineKind::CatmullRom)
+                .with_closed(true)
+                .with_points(
+                    [
+                        Vector3::new(-8.0, 1.5, 0.0),
+                        Vector3::new(-3.0, 2.5, -2.5),
+                        Vector3::new(3.0, 2.5, -2.5),
+                        Vector3::new(8.0, 1.5, 0.0),
+                        Vector3::new(3.0, 6.0, 2.5),
+                        Vector3::new(-3.0, 6.0, 2.5),
+                    ]
+                    .iter()
+                    .map(|&p| SplinePoint::Position(p))
+                    .collect(),
+                ),
+        );
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            follower: PathFollower {
+                spline,
+                target: camera,
+                speed: 2.0,
+                end_behavior: PathEndBehavior::Loop,
+                orient_along_tangent: true,
+                ..Default::default()
+            },
+            draw_spline: true,
+            paused: false,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        if !self.paused {
+            self.follower.update(scene, dt);
+        }
+
+        scene.drawing_context.clear_lines();
+        if self.draw_spline {
+            scene
+                .splines
+                .draw(&mut scene.drawing_context, Color::opaque(255, 200, 0));
+        }
+
+        let length = scene.splines[self.follower.spline].length();
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Camera Flythrough\n[D] - show/hide spline, [P] - pause\n\
+                Distance: {:.1}/{:.1}\nFPS: {}",
+                self.follower.distance(),
+                length,
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::D) => self.draw_spline = !self.draw_spline,
+                    Some(VirtualKeyCode::P) => self.paused = !self.paused,
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Camera Flythrough")
+        .run();
+}
@@ -0,0 +1,254 @@
+//! Example - Foliage.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows a few bushes made of crossed leaf cards lit by a directional light. Cards use
+//! alpha-tested cutout transparency and two-sided rendering, so leaves cast leaf-shaped shadows and
+//! back sides of the cards are lit too. Press C to toggle alpha cutout and T to toggle two-sided
+//! rendering.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{Material, PropertyValue},
+    resource::texture::{Texture, TextureKind, TexturePixelKind},
+    scene::{
+        base::BaseBuilder,
+        light::{directional::DirectionalLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData, DEFAULT_ALPHA_CUTOFF},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+const LEAF_TEXTURE_SIZE: u32 = 128;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    pivot: Handle<Node>,
+    bushes: Vec<Handle<Node>>,
+    cutout: bool,
+    two_sided: bool,
+    time: f32,
+}
+
+// Makes a texture with a few round leaves on transparent background.
+fn make_leaf_texture() -> Texture {
+    let leaves = [(0.3, 0.3, 0.2), (0.7, 0.35, 0.22), (0.5, 0.7, 0.25)];
+    let mut bytes = Vec::with_capacity((LEAF_TEXTURE_SIZE * LEAF_TEXTURE_SIZE * 4) as usize);
+    for y in 0..LEAF_TEXTURE_SIZE {
+        for x in 0..LEAF_TEXTURE_SIZE {
+            let u = x as f32 / LEAF_TEXTURE_SIZE as f32;
+            let v = y as f32 / LEAF_TEXTURE_SIZE as f32;
+            let inside = leaves
+                .iter()
+                .any(|(cx, cy, r)| (u - cx).powi(2) + (v - cy).powi(2) < r * r);
+            let green = (140.0 + 80.0 * v) as u8;
+            bytes.extend_from_slice(&[40, green, 30, if inside { 255 } else { 0 }]);
+        }
+    }
+
+    Texture::from_bytes(
+        TextureKind::Rectangle {
+            width: LEAF_TEXTURE_SIZE,
+            height: LEAF_TEXTURE_SIZE,
+        },
+        TexturePixelKind::RGBA8,
+        bytes,
+        false,
+    )
+    .unwrap()
+}
+
+fn create_bush(scene: &mut Scene, position: Vector3<f32>, texture: Texture) -> Handle<Node> {
+    let mut material = Material::standard();
+    material
+        .set_property(
+            &ImmutableString::new("diffuseTexture"),
+            PropertyValue::Sampler {
+                value: Some(texture),
+                fallback: Default::default(),
+            },
+        )
+        .unwrap();
+    let material = Arc::new(Mutex::new(material));
+
+    // Three crossed cards, every card shares the same material.
+    let surfaces = (0..3)
+        .map(|i| {
+            let transform = Matrix4::new_translation(&Vector3::new(0.0, 1.0, 0.0))
+                * Matrix4::from_axis_angle(&Vector3::y_axis(), i as f32 * 60.0f32.to_radians())
+                * Matrix4::new_scaling(2.0);
+            SurfaceBuilder::new(Arc::new(Mutex::new(SurfaceData::make_quad(&transform))))
+                .with_material(material.clone())
+                .with_two_sided(true)
+                .build()
+        })
+        .collect();
+
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .build(),
+        ),
+    )
+    .with_surfaces(surfaces)
+    .build(&mut scene.graph)
+}
+
+impl Game {
+    fn apply_surface_flags(&self, engine: &mut Engine) {
+        let graph = &mut engine.scenes[self.scene].graph;
+        for &bush in self.bushes.iter() {
+            for surface in graph[bush].as_mesh_mut().surfaces_mut() {
+                surface.set_two_sided(self.two_sided);
+                surface.set_alpha_cutoff(if self.cutout {
+                    DEFAULT_ALPHA_CUTOFF
+                } else {
+                    0.0
+                });
+            }
+        }
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(90, 90, 100);
+
+        let camera = block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 2.0, -7.0),
+            &mut scene.graph,
+        ));
+
+        let pivot = BaseBuilder::new()
+            .with_children(&[camera])
+            .build(&mut scene.graph);
+
+        // Low sun makes long shadows, so it is easy to see their shape.
+        DirectionalLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(
+                        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 30.0f32.to_radians())
+                            * UnitQuaternion::from_axis_angle(
+                                &Vector3::x_axis(),
+                                35.0f32.to_radians(),
+                            ),
+                    )
+                    .build(),
+            ),
+        ))
+        .build(&mut scene.graph);
+
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    20.0, 0.1, 20.0,
+                ))),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+
+        let texture = make_leaf_texture();
+        let bushes = [
+            Vector3::new(-2.5, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.5),
+            Vector3::new(2.5, 0.0, -0.5),
+        ]
+        .iter()
+        .map(|&position| create_bush(&mut scene, position, texture.clone()))
+        .collect();
+
+        let scene = engine.scenes.add(scene);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene,
+            pivot,
+            bushes,
+            cutout: true,
+            two_sided: true,
+            time: 0.0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        self.time += dt;
+
+        engine.scenes[self.scene].graph[self.pivot]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                self.time * 0.3,
+            ));
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Foliage\nFPS: {}\n[C] Alpha cutout: {}\n[T] Two-sided: {}",
+                engine.renderer().get_statistics().frames_per_second,
+                self.cutout,
+                self.two_sided
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::C) => {
+                        self.cutout = !self.cutout;
+                        self.apply_surface_flags(engine);
+                    }
+                    Some(VirtualKeyCode::T) => {
+                        self.two_sided = !self.two_sided;
+                        self.apply_surface_flags(engine);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Foliage")
+        .run();
+}
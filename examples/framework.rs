@@ -24,7 +24,7 @@ impl GameState for Game {
 
         // Slowly change color of the window.
         engine
-            .renderer
+            .renderer_mut()
             .set_backbuffer_clear_color(Color::from(Hsv::new(self.hue % 360.0, 100.0, 100.0)))
     }
 }
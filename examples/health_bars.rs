@@ -0,0 +1,210 @@
+//! Example - Health bars.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to keep widgets over scene nodes using UI anchors. Every enemy has a
+//! health bar that follows it, the bar is hidden when the enemy is behind the camera and it is
+//! smaller when the enemy is far away. Press [H] to hit a random enemy, dead enemies are deleted
+//! from the scene and their health bars are removed automatically.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector2, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{
+        framework::prelude::*,
+        ui_anchor::{DistanceScale, UiAnchor},
+        Engine,
+    },
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        progress_bar::{ProgressBarBuilder, ProgressBarMessage},
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    rand::Rng,
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+const ENEMY_COUNT: usize = 8;
+
+struct Enemy {
+    node: Handle<Node>,
+    health_bar: Handle<UiNode>,
+    health: f32,
+    // Enemies walk in circles around the camera, some of them will be behind it.
+    angle: f32,
+    radius: f32,
+    speed: f32,
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    enemies: Vec<Enemy>,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(120, 120, 120);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 3.0, 0.0),
+            &mut scene.graph,
+        ));
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 8.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(40.0)
+        .build(&mut scene.graph);
+
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    60.0, 0.1, 60.0,
+                ))),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+
+        let scene_handle = engine.scenes.add(scene);
+
+        let mut rng = rg3d::rand::thread_rng();
+        let enemies = (0..ENEMY_COUNT)
+            .map(|i| {
+                let node = MeshBuilder::new(BaseBuilder::new())
+                    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                        SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                            0.8, 1.8, 0.8,
+                        ))),
+                    )))
+                    .build()])
+                    .build(&mut engine.scenes[scene_handle].graph);
+
+                let health_bar = ProgressBarBuilder::new(
+                    WidgetBuilder::new().with_width(80.0).with_height(10.0),
+                )
+                .with_progress(1.0)
+                .build(&mut engine.user_interface.build_ctx());
+
+                // Put the bar a bit above the head of the enemy.
+                engine.ui_anchors.add(
+                    UiAnchor::new(health_bar, scene_handle, node)
+                        .with_world_offset(Vector3::new(0.0, 1.2, 0.0))
+                        .with_screen_offset(Vector2::new(0.0, -4.0))
+                        .with_distance_scale(DistanceScale {
+                            reference_distance: 8.0,
+                            min_scale: 0.4,
+                            max_scale: 1.0,
+                        }),
+                );
+
+                Enemy {
+                    node,
+                    health_bar,
+                    health: 1.0,
+                    angle: i as f32 / ENEMY_COUNT as f32 * std::f32::consts::TAU,
+                    radius: rng.gen_range(6.0..20.0),
+                    speed: rng.gen_range(0.1..0.4),
+                }
+            })
+            .collect();
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: scene_handle,
+            enemies,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        for enemy in self.enemies.iter_mut() {
+            enemy.angle += enemy.speed * dt;
+            scene.graph[enemy.node]
+                .local_transform_mut()
+                .set_position(Vector3::new(
+                    enemy.angle.cos() * enemy.radius,
+                    0.9,
+                    enemy.angle.sin() * enemy.radius,
+                ));
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Health Bars\n[H] - hit random enemy\nEnemies left: {}\nFPS: {}",
+                self.enemies.len(),
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed
+                && input.virtual_keycode == Some(VirtualKeyCode::H)
+                && !self.enemies.is_empty()
+            {
+                let index = rg3d::rand::thread_rng().gen_range(0..self.enemies.len());
+                let enemy = &mut self.enemies[index];
+                enemy.health -= 0.25;
+                if enemy.health <= 0.0 {
+                    // No need to remove the health bar, the anchor will do it.
+                    engine.scenes[self.scene].remove_node(enemy.node);
+                    self.enemies.remove(index);
+                } else {
+                    engine
+                        .user_interface
+                        .send_message(ProgressBarMessage::progress(
+                            enemy.health_bar,
+                            MessageDirection::ToWidget,
+                            enemy.health,
+                        ));
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Health Bars")
+        .run();
+}
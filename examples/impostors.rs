@@ -0,0 +1,223 @@
+//! Example - Impostors.
+//!
+//! Difficulty: Easy.
+//!
+//! This example is a stress test for impostors. It plants a big forest of "pines", every pine is an
+//! impostor with a detailed model as its child. Distant pines are replaced by camera-facing quads
+//! with pictures of the model baked from a few angles, all pines share one atlas, so they're drawn
+//! by a single instanced draw call. Press [I] to switch impostors on and off and compare draw calls
+//! and frame time of both modes.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, resource_manager::MaterialSearchOptions, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    rand::Rng,
+    renderer::Statistics,
+    scene::{
+        base::BaseBuilder,
+        impostor::{ImpostorAtlas, ImpostorBuilder, DEFAULT_IMPOSTOR_ANGLE_COUNT},
+        light::{directional::DirectionalLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+// Total amount of pines is a square of this value.
+const ROW_SIZE: i32 = 48;
+
+// Distance between neighbour pines.
+const SPACING: f32 = 4.0;
+
+// Distance at which pines are replaced by impostors.
+const SWITCH_DISTANCE: f32 = 30.0;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    pines: Vec<Handle<Node>>,
+    use_impostors: bool,
+    // Statistics of the last frame rendered with and without impostors.
+    detailed_stats: Option<Statistics>,
+    impostor_stats: Option<Statistics>,
+}
+
+fn describe(stats: &Option<Statistics>) -> String {
+    match stats {
+        Some(stats) => format!(
+            "{} draw calls, {:.2} ms",
+            stats.geometry.draw_calls,
+            stats.pure_frame_time * 1000.0
+        ),
+        None => "press [I] to measure".to_owned(),
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(100, 100, 100);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 6.0, -ROW_SIZE as f32 * SPACING * 0.5 - 10.0),
+            &mut scene.graph,
+        ));
+
+        DirectionalLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        45.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        ))
+        .build(&mut scene.graph);
+
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    ROW_SIZE as f32 * SPACING,
+                    0.1,
+                    ROW_SIZE as f32 * SPACING,
+                ))),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+
+        let model = block_on(
+            engine
+                .resource_manager
+                .request_model("examples/data/cone.fbx", MaterialSearchOptions::RecursiveUp),
+        )
+        .unwrap();
+
+        // Every pine shares the same atlas, it is baked once on first frame.
+        let atlas = ImpostorAtlas::new(model.clone(), DEFAULT_IMPOSTOR_ANGLE_COUNT, 128);
+
+        let mut rng = rg3d::rand::thread_rng();
+        let mut pines = Vec::new();
+        for z in 0..ROW_SIZE {
+            for x in 0..ROW_SIZE {
+                let pine = model.instantiate_geometry(&mut scene);
+                let position = Vector3::new(
+                    (x - ROW_SIZE / 2) as f32 * SPACING + rng.gen_range(-1.0..1.0),
+                    0.0,
+                    (z - ROW_SIZE / 2) as f32 * SPACING + rng.gen_range(-1.0..1.0),
+                );
+                let impostor = ImpostorBuilder::new(
+                    BaseBuilder::new()
+                        .with_children(&[pine])
+                        .with_local_transform(
+                            TransformBuilder::new()
+                                .with_local_position(position)
+                                .with_local_rotation(UnitQuaternion::from_axis_angle(
+                                    &Vector3::y_axis(),
+                                    rng.gen_range(0.0..std::f32::consts::TAU),
+                                ))
+                                .build(),
+                        ),
+                )
+                .with_atlas(atlas.clone())
+                .with_switch_distance(SWITCH_DISTANCE)
+                .build(&mut scene.graph);
+                pines.push(impostor);
+            }
+        }
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            pines,
+            use_impostors: true,
+            detailed_stats: None,
+            impostor_stats: None,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        let statistics = engine.renderer().get_statistics();
+        if self.use_impostors {
+            self.impostor_stats = Some(statistics);
+        } else {
+            self.detailed_stats = Some(statistics);
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Impostors\n\
+                Pines: {}\n\
+                [I] - toggle impostors (now: {})\n\
+                Without impostors: {}\n\
+                With impostors: {}\n\
+                {}",
+                self.pines.len(),
+                if self.use_impostors { "on" } else { "off" },
+                describe(&self.detailed_stats),
+                describe(&self.impostor_stats),
+                statistics
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed
+                && input.virtual_keycode == Some(VirtualKeyCode::I)
+            {
+                self.use_impostors = !self.use_impostors;
+
+                // Infinite switch distance keeps detailed models visible everywhere.
+                let switch_distance = if self.use_impostors {
+                    SWITCH_DISTANCE
+                } else {
+                    f32::INFINITY
+                };
+                let graph = &mut engine.scenes[self.scene].graph;
+                for &pine in self.pines.iter() {
+                    graph[pine]
+                        .as_impostor_mut()
+                        .set_switch_distance(switch_distance);
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Impostors")
+        .run();
+}
@@ -28,10 +28,7 @@ use rg3d::{
         UiNode,
     },
     scene::{node::Node, Scene},
-    utils::{
-        log::{Log, MessageKind},
-        translate_event,
-    },
+    utils::{log::log_error, translate_event},
 };
 use std::{rc::Rc, time::Instant};
 
@@ -191,7 +188,7 @@ fn main() {
                         .get_pose()
                         .apply(&mut scene.graph);
 
-                    let fps = engine.renderer.get_statistics().frames_per_second;
+                    let fps = engine.renderer().get_statistics().frames_per_second;
                     engine.user_interface.send_message(TextMessage::text(
                         interface.debug_text,
                         MessageDirection::ToWidget,
@@ -259,10 +256,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = engine.set_frame_size(size.into()) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Unable to set frame size: {:?}", e),
-                            );
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
                     }
                     WindowEvent::KeyboardInput { input, .. } => {
@@ -3,7 +3,7 @@
 //! Difficulty: Easy.
 //!
 //! This example shows how to create simple scene with lots of animated models with low performance
-//! impact.
+//! impact. Every model has its own color, which does not affect batching.
 
 pub mod shared;
 
@@ -107,6 +107,24 @@ impl SceneLoader {
                     ))
                     .set_position(Vector3::new((x as f32) * 7.0, 0.0, (z as f32) * 7.0));
 
+                // Give every model its own color. Color is passed to shaders per mesh, so models
+                // still share the same materials and geometry.
+                let mut rng = rg3d::rand::thread_rng();
+                let color = Color::opaque(
+                    rng.gen_range(100..255),
+                    rng.gen_range(100..255),
+                    rng.gen_range(100..255),
+                );
+                let meshes = scene
+                    .graph
+                    .traverse_handle_iter(model_handle)
+                    .collect::<Vec<_>>();
+                for handle in meshes {
+                    if let Node::Mesh(mesh) = &mut scene.graph[handle] {
+                        mesh.set_color(color);
+                    }
+                }
+
                 // Add simple animation for our model. Animations are loaded from model resources -
                 // this is because animation is a set of skeleton bones with their own transforms.
                 // Once animation resource is loaded it must be re-targeted to our model instance.
@@ -200,7 +218,10 @@ impl GameState for Game {
     {
         let mut settings = QualitySettings::ultra();
         settings.point_shadows_distance = 1000.0;
-        engine.renderer.set_quality_settings(&settings).unwrap();
+        engine
+            .renderer_mut()
+            .set_quality_settings(&settings)
+            .unwrap();
 
         // Create test scene.
         let loader = rg3d::core::futures::executor::block_on(SceneLoader::load_with(
@@ -256,7 +277,7 @@ impl GameState for Game {
                     Use [A][D] keys to rotate camera.\n\
                     {}",
                 self.animations.len(),
-                engine.renderer.get_statistics()
+                engine.renderer().get_statistics()
             ),
         ));
     }
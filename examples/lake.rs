@@ -0,0 +1,220 @@
+//! Example - Lake.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows a water surface with reflections and refraction. Rocks are placed on the
+//! bottom of a lake, some of them stick out of the water, so you can see both reflections and
+//! tinted refraction of the underwater parts. Press [R] to toggle reflections and [W] to change
+//! wave speed.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector2, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{Material, PropertyValue},
+    scene::{
+        base::BaseBuilder,
+        light::{directional::DirectionalLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        water::WaterBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    camera_pivot: Handle<Node>,
+    water: Handle<Node>,
+    angle: f32,
+}
+
+fn make_material(color: Color) -> Arc<Mutex<Material>> {
+    let mut material = Material::standard();
+    material
+        .set_property(
+            &ImmutableString::new("diffuseColor"),
+            PropertyValue::Color(color),
+        )
+        .unwrap();
+    Arc::new(Mutex::new(material))
+}
+
+fn create_box(scene: &mut Scene, position: Vector3<f32>, size: Vector3<f32>, color: Color) {
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&size)),
+    )))
+    .with_material(make_material(color))
+    .build()])
+    .build(&mut scene.graph);
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(120, 120, 120);
+
+        let camera = block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 6.0, -22.0),
+            &mut scene.graph,
+        ));
+        scene.graph[camera]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::x_axis(),
+                15.0f32.to_radians(),
+            ));
+        let camera_pivot = BaseBuilder::new().build(&mut scene.graph);
+        scene.graph.link_nodes(camera, camera_pivot);
+
+        DirectionalLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        50.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        ))
+        .build(&mut scene.graph);
+
+        // Bottom of the lake.
+        create_box(
+            &mut scene,
+            Vector3::new(0.0, -4.0, 0.0),
+            Vector3::new(40.0, 0.5, 40.0),
+            Color::opaque(160, 140, 100),
+        );
+
+        // Rocks of different heights, the water surface is at zero height.
+        for (i, height) in [1.5, 3.0, 5.0, 6.5, 8.0, 2.5].iter().enumerate() {
+            let angle = i as f32 * std::f32::consts::TAU / 6.0;
+            create_box(
+                &mut scene,
+                Vector3::new(angle.cos() * 9.0, -4.0 + height * 0.5, angle.sin() * 9.0),
+                Vector3::new(2.0, *height, 2.0),
+                Color::opaque(110, 110, 120),
+            );
+        }
+
+        let water = WaterBuilder::new(BaseBuilder::new())
+            .with_size(Vector2::new(40.0, 40.0))
+            .with_normal_map(
+                engine
+                    .resource_manager
+                    .request_texture("examples/data/Rock_Normal.jpg", None),
+            )
+            .build(&mut scene.graph);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            camera_pivot,
+            water,
+            angle: 0.0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        self.angle += 0.1 * dt;
+        scene.graph[self.camera_pivot]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                self.angle,
+            ));
+
+        let water = scene.graph[self.water].as_water();
+        let reflections = engine
+            .renderer()
+            .get_quality_settings()
+            .water_reflections_enabled;
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Lake\n\
+                [R] - toggle reflections (now: {})\n\
+                [W] - change wave speed (now: {:?})\n\
+                {}",
+                if reflections { "on" } else { "off" },
+                water.wave_speed(),
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::R) => {
+                        let mut settings = engine.renderer().get_quality_settings();
+                        settings.water_reflections_enabled = !settings.water_reflections_enabled;
+                        engine
+                            .renderer_mut()
+                            .set_quality_settings(&settings)
+                            .unwrap();
+                    }
+                    Some(VirtualKeyCode::W) => {
+                        let water = engine.scenes[self.scene].graph[self.water].as_water_mut();
+                        let speed = water.wave_speed();
+                        water.set_wave_speed(if speed.x > 0.05 {
+                            Vector2::new(0.02, 0.01)
+                        } else {
+                            speed * 3.0
+                        });
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Lake")
+        .run();
+}
@@ -0,0 +1,266 @@
+//! Example - Light editor.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to use numeric fields to edit properties of scene nodes live. Select a
+//! light by its number and change its radius and intensity: type a value and press Enter, use
+//! arrow buttons or click and drag a field left or right. Hold Ctrl while dragging for fine steps,
+//! hold Shift for coarse steps.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector2, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, Engine},
+    event_loop::ControlFlow,
+    gui::{
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        numeric::{NumericUpDownBuilder, NumericUpDownMessage},
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        BuildContext, Thickness, UiNode, VerticalAlignment,
+    },
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder, Light},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    lights: Vec<Handle<Node>>,
+    selected: usize,
+    light_index: Handle<UiNode>,
+    radius: Handle<UiNode>,
+    intensity: Handle<UiNode>,
+}
+
+fn make_label(ctx: &mut BuildContext, text: &str, row: usize) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .on_row(row)
+            .on_column(0)
+            .with_vertical_alignment(VerticalAlignment::Center),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+impl Game {
+    fn selected_light<'a>(&self, engine: &'a mut Engine) -> &'a mut Light {
+        engine.scenes[self.scene].graph[self.lights[self.selected]].as_light_mut()
+    }
+
+    // Fields must show values of newly selected light.
+    fn sync_fields(&self, engine: &mut Engine) {
+        let (radius, intensity) = match self.selected_light(engine) {
+            Light::Point(point) => (point.radius(), point.intensity()),
+            _ => unreachable!(),
+        };
+
+        let ui = &mut engine.user_interface;
+        ui.send_message(NumericUpDownMessage::value(
+            self.radius,
+            MessageDirection::ToWidget,
+            radius,
+        ));
+        ui.send_message(NumericUpDownMessage::value(
+            self.intensity,
+            MessageDirection::ToWidget,
+            intensity,
+        ));
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(40, 40, 40);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 6.0, -8.0),
+            &mut scene.graph,
+        ));
+
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    20.0, 0.1, 20.0,
+                ))),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+
+        let lights = [
+            (Vector3::new(-4.0, 2.0, 0.0), Color::opaque(255, 60, 60)),
+            (Vector3::new(0.0, 2.0, 3.0), Color::opaque(60, 255, 60)),
+            (Vector3::new(4.0, 2.0, 0.0), Color::opaque(60, 60, 255)),
+        ]
+        .iter()
+        .map(|&(position, color)| {
+            PointLightBuilder::new(
+                BaseLightBuilder::new(
+                    BaseBuilder::new().with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(position)
+                            .build(),
+                    ),
+                )
+                .with_color(color),
+            )
+            .with_radius(5.0)
+            .build(&mut scene.graph)
+        })
+        .collect::<Vec<_>>();
+
+        let ctx = &mut engine.user_interface.build_ctx();
+
+        let light_index;
+        let radius;
+        let intensity;
+        WindowBuilder::new(
+            WidgetBuilder::new()
+                .with_desired_position(Vector2::new(10.0, 80.0))
+                .with_width(250.0),
+        )
+        .with_title(WindowTitle::text("Light"))
+        .can_close(false)
+        .with_content(
+            GridBuilder::new(
+                WidgetBuilder::new()
+                    .with_margin(Thickness::uniform(2.0))
+                    .with_child(make_label(ctx, "Light #", 0))
+                    .with_child({
+                        // Integer field, there is no fractional part to show.
+                        light_index = NumericUpDownBuilder::<i32>::new(
+                            WidgetBuilder::new()
+                                .on_row(0)
+                                .on_column(1)
+                                .with_margin(Thickness::uniform(1.0)),
+                        )
+                        .with_min_value(1)
+                        .with_max_value(lights.len() as i32)
+                        .with_value(1)
+                        .build(ctx);
+                        light_index
+                    })
+                    .with_child(make_label(ctx, "Radius", 1))
+                    .with_child({
+                        radius = NumericUpDownBuilder::<f32>::new(
+                            WidgetBuilder::new()
+                                .on_row(1)
+                                .on_column(1)
+                                .with_margin(Thickness::uniform(1.0)),
+                        )
+                        .with_min_value(0.0)
+                        .with_max_value(20.0)
+                        .with_step(0.5)
+                        .with_precision(1)
+                        .with_value(5.0)
+                        .build(ctx);
+                        radius
+                    })
+                    .with_child(make_label(ctx, "Intensity", 2))
+                    .with_child({
+                        intensity = NumericUpDownBuilder::<f32>::new(
+                            WidgetBuilder::new()
+                                .on_row(2)
+                                .on_column(1)
+                                .with_margin(Thickness::uniform(1.0)),
+                        )
+                        .with_min_value(0.0)
+                        .with_max_value(10.0)
+                        .with_step(0.1)
+                        .with_precision(2)
+                        .with_value(1.0)
+                        .build(ctx);
+                        intensity
+                    }),
+            )
+            .add_column(Column::strict(80.0))
+            .add_column(Column::stretch())
+            .add_row(Row::strict(24.0))
+            .add_row(Row::strict(24.0))
+            .add_row(Row::strict(24.0))
+            .build(ctx),
+        )
+        .build(ctx);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new()).build(ctx),
+            scene: engine.scenes.add(scene),
+            lights,
+            selected: 0,
+            light_index,
+            radius,
+            intensity,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Light Editor\nDrag a field to change its value, Ctrl - fine, Shift - coarse.\nFPS: {}",
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+
+    fn on_ui_message(&mut self, engine: &mut Engine, message: UiMessage) {
+        if message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        if let Some(&NumericUpDownMessage::Value(index)) =
+            message.data::<NumericUpDownMessage<i32>>()
+        {
+            if message.destination() == self.light_index {
+                self.selected = (index - 1) as usize;
+                self.sync_fields(engine);
+            }
+        } else if let Some(&NumericUpDownMessage::Value(value)) =
+            message.data::<NumericUpDownMessage<f32>>()
+        {
+            // Value messages come only when the value has actually changed.
+            if message.destination() == self.radius {
+                if let Light::Point(point) = self.selected_light(engine) {
+                    point.set_radius(value);
+                }
+            } else if message.destination() == self.intensity {
+                self.selected_light(engine).set_intensity(value);
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Light Editor")
+        .run();
+}
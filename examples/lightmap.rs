@@ -18,6 +18,7 @@ use rg3d::{
     },
     engine::{
         resource_manager::{MaterialSearchOptions, ResourceManager},
+        task::{TaskHandle, TaskManager},
         Engine,
     },
     event::{ElementState, Event, VirtualKeyCode, WindowEvent},
@@ -35,16 +36,12 @@ use rg3d::{
     },
     scene::{node::Node, Scene},
     utils::{
-        lightmap::{CancellationToken, Lightmap, ProgressIndicator, ProgressStage},
-        log::{Log, MessageKind},
+        lightmap::{Lightmap, ProgressIndicator, ProgressStage},
+        log::log_error,
         translate_event,
     },
 };
-use std::{
-    path::Path,
-    sync::{Arc, Mutex},
-    time::Instant,
-};
+use std::{path::Path, time::Instant};
 
 const LIGHTMAP_SCENE_PATH: &str = "examples/data/lightmap_scene.rgs";
 
@@ -192,32 +189,27 @@ struct GameScene {
 }
 
 struct SceneLoadContext {
-    data: Option<GameScene>,
+    // `None` if there is nothing to load. Result of the task is `None` if lightmap generation
+    // was cancelled.
+    task: Option<TaskHandle<Option<GameScene>>>,
     progress_indicator: ProgressIndicator,
-    cancellation_token: CancellationToken,
     start_time: Instant,
     generate_lightmap: bool,
 }
 
 fn create_scene_async(
+    tasks: &TaskManager,
     resource_manager: ResourceManager,
     generate_lightmap: bool,
-) -> Arc<Mutex<SceneLoadContext>> {
+) -> SceneLoadContext {
     let progress_indicator = ProgressIndicator::new();
-    let cancellation_token = CancellationToken::new();
+    let job_progress_indicator = progress_indicator.clone();
 
-    // Create load context - it will be shared with caller and loader threads.
-    let context = Arc::new(Mutex::new(SceneLoadContext {
-        data: None,
-        progress_indicator: progress_indicator.clone(),
-        cancellation_token: cancellation_token.clone(),
-        start_time: Instant::now(),
-        generate_lightmap,
-    }));
-    let result = context.clone();
-
-    // Spawn separate thread which will create scene by loading various assets.
-    std::thread::spawn(move || {
+    // Scene will be created on one of the threads of the engine's thread pool.
+    let task = tasks.spawn(move |ctx| {
+        let progress_indicator = job_progress_indicator;
+        // Lightmap generator checks the same flag, so cancellation of the task will stop it.
+        let cancellation_token = ctx.cancellation_token();
         futures::executor::block_on(async move {
             if generate_lightmap {
                 let mut scene = Scene::new();
@@ -263,7 +255,9 @@ fn create_scene_async(
                     scene.visit("Scene", &mut visitor).unwrap();
                     visitor.save_binary(LIGHTMAP_SCENE_PATH).unwrap();
 
-                    context.lock().unwrap().data = Some(GameScene { scene, root });
+                    Some(GameScene { scene, root })
+                } else {
+                    None
                 }
             } else {
                 let scene = Scene::from_file(
@@ -275,13 +269,17 @@ fn create_scene_async(
                 .unwrap();
                 let root = scene.graph[scene.graph.get_root()].children()[0];
 
-                context.lock().unwrap().data = Some(GameScene { scene, root });
+                Some(GameScene { scene, root })
             }
         })
     });
 
-    // Immediately return shared context.
-    result
+    SceneLoadContext {
+        task: Some(task),
+        progress_indicator,
+        start_time: Instant::now(),
+        generate_lightmap,
+    }
 }
 
 struct InputController {
@@ -314,15 +312,14 @@ fn main() {
                 MessageDirection::ToWidget,
                 true,
             ));
-        Arc::new(Mutex::new(SceneLoadContext {
-            data: None,
+        SceneLoadContext {
+            task: None,
             progress_indicator: ProgressIndicator::new(),
-            cancellation_token: CancellationToken::new(),
             start_time: Instant::now(),
             generate_lightmap: false,
-        }))
+        }
     } else {
-        create_scene_async(engine.resource_manager.clone(), true)
+        create_scene_async(&engine.tasks, engine.resource_manager.clone(), true)
     };
 
     // Initially these handles are None, once scene is loaded they'll be assigned.
@@ -360,68 +357,79 @@ fn main() {
                     // Put your game logic here.
                     // ************************
 
-                    // Check each frame if our scene is created - here we just trying to lock context
-                    // without blocking, it is important for main thread to be functional while other
-                    // thread still loading data.
-                    if let Ok(mut load_context) = game_scene.try_lock() {
-                        if let Some(game_scene) = load_context.data.take() {
-                            // Add scene to engine - engine will take ownership over scene and will return
-                            // you a handle to scene which can be used later on to borrow it and do some
-                            // actions you need.
-                            scene_handle = engine.scenes.add(game_scene.scene);
-                            model_handle = game_scene.root;
-
-                            // Once scene is loaded, we should hide progress bar and text.
-                            engine
-                                .user_interface
-                                .send_message(WidgetMessage::visibility(
-                                    interface.progress_grid,
+                    // Check each frame if our scene is created - the task is polled without
+                    // blocking, it is important for main thread to be functional while the task
+                    // is still loading data.
+                    if let Some(task) = game_scene.task.as_ref() {
+                        match task.try_take() {
+                            Some(Ok(Some(loaded))) => {
+                                // Add scene to engine - engine will take ownership over scene and
+                                // will return you a handle to scene which can be used later on to
+                                // borrow it and do some actions you need.
+                                scene_handle = engine.scenes.add(loaded.scene);
+                                model_handle = loaded.root;
+                                game_scene.task = None;
+
+                                // Once scene is loaded, we should hide progress bar and text.
+                                engine
+                                    .user_interface
+                                    .send_message(WidgetMessage::visibility(
+                                        interface.progress_grid,
+                                        MessageDirection::ToWidget,
+                                        false,
+                                    ));
+                                engine.user_interface.send_message(WindowMessage::close(
+                                    interface.choice_window,
                                     MessageDirection::ToWidget,
-                                    false,
                                 ));
-                            engine.user_interface.send_message(WindowMessage::close(
-                                interface.choice_window,
-                                MessageDirection::ToWidget,
-                            ));
+                            }
+                            Some(result) => {
+                                if let Err(e) = result {
+                                    log_error!("Unable to create scene. Reason: {}", e);
+                                }
+                                game_scene.task = None;
+                            }
+                            None => {
+                                let stage = match game_scene.progress_indicator.stage() {
+                                    ProgressStage::LightsCaching => "Caching Lights",
+                                    ProgressStage::UvGeneration => "Generating UVs",
+                                    ProgressStage::GeometryCaching => "Caching Geometry",
+                                    ProgressStage::CalculatingLight => "Calculating Light",
+                                };
+
+                                let message = if game_scene.generate_lightmap {
+                                    format!(
+                                        "Please wait until lightmap is fully generated.\n\
+                                        Stage {} of 4: {}\n\
+                                        Elapsed time: {:.2} s",
+                                        game_scene.progress_indicator.stage() as u32 + 1,
+                                        stage,
+                                        game_scene.start_time.elapsed().as_secs_f32(),
+                                    )
+                                } else {
+                                    format!(
+                                        "Please wait until existing lightmap is fully loaded.\n\
+                                        Elapsed time: {:.2} s",
+                                        game_scene.start_time.elapsed().as_secs_f32(),
+                                    )
+                                };
+
+                                // Report progress in UI.
+                                engine
+                                    .user_interface
+                                    .send_message(ProgressBarMessage::progress(
+                                        interface.progress_bar,
+                                        MessageDirection::ToWidget,
+                                        game_scene.progress_indicator.progress_percent() as f32
+                                            / 100.0,
+                                    ));
+                                engine.user_interface.send_message(TextMessage::text(
+                                    interface.progress_text,
+                                    MessageDirection::ToWidget,
+                                    message,
+                                ));
+                            }
                         }
-
-                        let stage = match load_context.progress_indicator.stage() {
-                            ProgressStage::LightsCaching => "Caching Lights",
-                            ProgressStage::UvGeneration => "Generating UVs",
-                            ProgressStage::GeometryCaching => "Caching Geometry",
-                            ProgressStage::CalculatingLight => "Calculating Light",
-                        };
-
-                        let message = if load_context.generate_lightmap {
-                            format!(
-                                "Please wait until lightmap is fully generated.\n\
-                                Stage {} of 4: {}\n\
-                                Elapsed time: {:.2} s",
-                                load_context.progress_indicator.stage() as u32 + 1,
-                                stage,
-                                load_context.start_time.elapsed().as_secs_f32(),
-                            )
-                        } else {
-                            format!(
-                                "Please wait until existing lightmap is fully loaded.\n\
-                                Elapsed time: {:.2} s",
-                                load_context.start_time.elapsed().as_secs_f32(),
-                            )
-                        };
-
-                        // Report progress in UI.
-                        engine
-                            .user_interface
-                            .send_message(ProgressBarMessage::progress(
-                                interface.progress_bar,
-                                MessageDirection::ToWidget,
-                                load_context.progress_indicator.progress_percent() as f32 / 100.0,
-                            ));
-                        engine.user_interface.send_message(TextMessage::text(
-                            interface.progress_text,
-                            MessageDirection::ToWidget,
-                            message,
-                        ));
                     }
 
                     // Update scene only if it is loaded.
@@ -448,7 +456,7 @@ fn main() {
                     // While scene is loading, we will update progress bar.
                     let debug_text = format!(
                         "Example 09 - Lightmap\nUse [A][D] keys to rotate model.\n{}",
-                        engine.renderer.get_statistics()
+                        engine.renderer().get_statistics()
                     );
                     engine.user_interface.send_message(TextMessage::text(
                         interface.debug_text,
@@ -459,7 +467,9 @@ fn main() {
                     while let Some(ui_event) = engine.user_interface.poll_message() {
                         if let Some(ButtonMessage::Click) = ui_event.data::<ButtonMessage>() {
                             if ui_event.destination() == interface.cancel {
-                                game_scene.lock().unwrap().cancellation_token.cancel();
+                                if let Some(task) = game_scene.task.take() {
+                                    task.cancel();
+                                }
                                 engine
                                     .user_interface
                                     .send_message(WidgetMessage::visibility(
@@ -475,8 +485,11 @@ fn main() {
                                         true,
                                     ));
                             } else if ui_event.destination() == interface.generate_new {
-                                game_scene =
-                                    create_scene_async(engine.resource_manager.clone(), true);
+                                game_scene = create_scene_async(
+                                    &engine.tasks,
+                                    engine.resource_manager.clone(),
+                                    true,
+                                );
                                 engine.user_interface.send_message(WindowMessage::close(
                                     interface.choice_window,
                                     MessageDirection::ToWidget,
@@ -489,8 +502,11 @@ fn main() {
                                         true,
                                     ));
                             } else if ui_event.destination() == interface.load_existing {
-                                game_scene =
-                                    create_scene_async(engine.resource_manager.clone(), false);
+                                game_scene = create_scene_async(
+                                    &engine.tasks,
+                                    engine.resource_manager.clone(),
+                                    false,
+                                );
                                 engine.user_interface.send_message(WindowMessage::close(
                                     interface.choice_window,
                                     MessageDirection::ToWidget,
@@ -524,10 +540,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = engine.set_frame_size(size.into()) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Unable to set frame size: {:?}", e),
-                            );
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
 
                         // Root UI node should be resized too, otherwise progress bar will stay
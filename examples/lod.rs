@@ -32,10 +32,7 @@ use rg3d::{
         node::Node,
         Scene,
     },
-    utils::{
-        log::{Log, MessageKind},
-        translate_event,
-    },
+    utils::{log::log_error, translate_event},
 };
 use std::time::Instant;
 
@@ -215,11 +212,11 @@ fn main() {
                         .local_transform_mut()
                         .set_position(Vector3::new(0.0, 1.5, -distance));
 
-                    let fps = engine.renderer.get_statistics().frames_per_second;
+                    let fps = engine.renderer().get_statistics().frames_per_second;
                     let text = format!(
                         "Example 08 - Level of Detail\nUse [A][D] keys to rotate model, [W][S] to zoom in/out.\nFPS: {}\nTriangles rendered: {}",
                         fps,
-                        engine.renderer.get_statistics().geometry.triangles_rendered
+                        engine.renderer().get_statistics().geometry.triangles_rendered
                     );
                     engine.user_interface.send_message(TextMessage::text(
                         debug_text,
@@ -256,7 +253,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = engine.set_frame_size(size.into()) {
-                            Log::writeln(MessageKind::Error, format!("Unable to set frame size: {:?}", e));
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
                     }
                     WindowEvent::KeyboardInput { input, .. } => {
@@ -128,7 +128,7 @@ impl GameState for Game {
             MessageDirection::ToWidget,
             format!(
                 "Example - Materials and Shaders\nFPS: {}",
-                engine.renderer.get_statistics().frames_per_second
+                engine.renderer().get_statistics().frames_per_second
             ),
         ));
     }
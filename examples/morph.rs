@@ -0,0 +1,212 @@
+//! Example - Morph targets.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows a procedural "face" with two morph targets (blend shapes). Weight of the
+//! "Smile" target is controlled by the slider, while the "Puff" target is animated by a morph track
+//! of an animation. Press [Space] to pause or resume the animation. Morph targets of FBX models
+//! are imported automatically, their weights can be changed the same way.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    animation::{Animation, MorphKeyFrame, MorphTrack},
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector2, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::{MessageDirection, UiMessage},
+        scroll_bar::{ScrollBarBuilder, ScrollBarMessage},
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            buffer::{VertexAttributeUsage, VertexReadTrait},
+            surface::{MorphTarget, SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    slider: Handle<UiNode>,
+    scene: Handle<Scene>,
+    face: Handle<Node>,
+    animation: Handle<Animation>,
+}
+
+// Builds a morph target by moving every vertex of the sphere with given function.
+fn make_morph_target(
+    data: &SurfaceData,
+    name: &str,
+    func: impl Fn(Vector3<f32>) -> Vector3<f32>,
+) -> MorphTarget {
+    MorphTarget {
+        name: name.to_owned(),
+        position_deltas: data
+            .vertex_buffer
+            .iter()
+            .map(|view| func(view.read_3_f32(VertexAttributeUsage::Position).unwrap()))
+            .collect(),
+        normal_deltas: Default::default(),
+    }
+}
+
+fn make_face(scene: &mut Scene) -> Handle<Node> {
+    let mut data = SurfaceData::make_sphere(32, 32, 1.0, &Matrix4::identity());
+
+    // Corners of the "mouth" on the front side of the face move up.
+    let smile = make_morph_target(&data, "Smile", |p| {
+        let mouth = (-p.z - 0.5).max(0.0) * (-p.y).max(0.0);
+        Vector3::new(0.0, mouth * p.x.abs() * 2.0, 0.0)
+    });
+    // Cheeks are inflated.
+    let puff = make_morph_target(&data, "Puff", |p| {
+        let cheeks = (1.0 - p.y.abs() * 2.0).max(0.0) * p.x.abs();
+        Vector3::new(p.x, 0.0, p.z).scale(cheeks * 0.5)
+    });
+    data.morph_targets = vec![smile, puff];
+
+    MeshBuilder::new(BaseBuilder::new().with_name("Face"))
+        .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(data))).build()])
+        .build(&mut scene.graph)
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(80, 80, 80);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 0.0, -3.5),
+            &mut scene.graph,
+        ));
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(2.0, 2.0, -3.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(10.0)
+        .build(&mut scene.graph);
+
+        let face = make_face(&mut scene);
+        scene.graph[face]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                20.0f32.to_radians(),
+            ));
+
+        // Cheeks are puffed and released in a loop.
+        let mut track = MorphTrack::new(face, 1);
+        track.add_key_frame(MorphKeyFrame::new(0.0, 0.0));
+        track.add_key_frame(MorphKeyFrame::new(1.0, 1.0));
+        track.add_key_frame(MorphKeyFrame::new(2.0, 0.0));
+        let mut animation = Animation::default();
+        animation.add_morph_track(track);
+        let animation = scene.animations.add(animation);
+
+        let ctx = &mut engine.user_interface.build_ctx();
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new()).build(ctx),
+            slider: ScrollBarBuilder::new(
+                WidgetBuilder::new()
+                    .with_width(300.0)
+                    .with_height(30.0)
+                    .with_desired_position(Vector2::new(10.0, 120.0)),
+            )
+            .with_min(0.0)
+            .with_max(1.0)
+            .with_step(0.1)
+            .with_value(0.0)
+            .show_value(true)
+            .build(ctx),
+            scene: engine.scenes.add(scene),
+            face,
+            animation,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        // Animation must be applied to the scene explicitly, it sets weights of morph targets.
+        scene
+            .animations
+            .get(self.animation)
+            .get_pose()
+            .apply(&mut scene.graph);
+
+        let face = scene.graph[self.face].as_mesh();
+        let text = format!(
+            "Example - Morph targets\n\
+            Smile: {:.2} (use the slider)\n\
+            Puff: {:.2} (animated, [Space] - pause/resume)\n{}",
+            face.morph_weight(0),
+            face.morph_weight(1),
+            engine.renderer().get_statistics()
+        );
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            text,
+        ));
+    }
+
+    fn on_ui_message(&mut self, engine: &mut Engine, message: UiMessage) {
+        if let Some(ScrollBarMessage::Value(value)) = message.data::<ScrollBarMessage>() {
+            if message.destination() == self.slider
+                && message.direction() == MessageDirection::FromWidget
+            {
+                engine.scenes[self.scene].graph[self.face]
+                    .as_mesh_mut()
+                    .set_morph_weight_by_name("Smile", *value);
+            }
+        }
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed
+                && input.virtual_keycode == Some(VirtualKeyCode::Space)
+            {
+                let animation = engine.scenes[self.scene].animations.get_mut(self.animation);
+                let enabled = animation.is_enabled();
+                animation.set_enabled(!enabled);
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Morph targets")
+        .run();
+}
@@ -40,11 +40,7 @@ use rg3d::{
         transform::TransformBuilder,
         Scene,
     },
-    utils::{
-        log::{Log, MessageKind},
-        navmesh::NavmeshAgent,
-        translate_event,
-    },
+    utils::{log::log_error, navmesh::NavmeshAgent, translate_event},
 };
 use std::{sync::Arc, time::Instant};
 
@@ -207,7 +203,7 @@ fn main() {
 
                     let ray = scene.graph[camera]
                         .as_camera()
-                        .make_ray(mouse_position, engine.renderer.get_frame_bounds());
+                        .make_ray(mouse_position, engine.renderer().get_frame_bounds());
 
                     let mut buffer = ArrayVec::<Intersection, 64>::new();
                     scene.physics.cast_ray(
@@ -259,7 +255,7 @@ fn main() {
                         });
                     }
 
-                    let fps = engine.renderer.get_statistics().frames_per_second;
+                    let fps = engine.renderer().get_statistics().frames_per_second;
                     let text = format!(
                         "Example 12 - Navigation Mesh\nFPS: {}\nAgent time: {:?}",
                         fps, agent_time
@@ -299,10 +295,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = engine.set_frame_size(size.into()) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Unable to set frame size: {:?}", e),
-                            );
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
                     }
                     WindowEvent::KeyboardInput { input, .. } => {
@@ -0,0 +1,146 @@
+//! Example - Normal mapping.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows a procedural normal-mapped sphere lit by a moving light. Tangents of the sphere
+//! are generated by the engine, any lighting artifacts (faceting, inverted lighting) here most likely
+//! mean that tangents are broken.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    light: Handle<Node>,
+    angle: f32,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(30, 30, 30);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 0.0, -3.0),
+            &mut scene.graph,
+        ));
+
+        let light = PointLightBuilder::new(BaseLightBuilder::new(BaseBuilder::new()))
+            .with_radius(5.0)
+            .build(&mut scene.graph);
+
+        let mut material = Material::standard();
+        material
+            .set_property(
+                &ImmutableString::new("diffuseTexture"),
+                PropertyValue::Sampler {
+                    value: Some(
+                        engine
+                            .resource_manager
+                            .request_texture("examples/data/Rock_DiffuseColor.jpg", None),
+                    ),
+                    fallback: SamplerFallback::White,
+                },
+            )
+            .unwrap();
+        material
+            .set_property(
+                &ImmutableString::new("normalTexture"),
+                PropertyValue::Sampler {
+                    value: Some(
+                        engine
+                            .resource_manager
+                            .request_texture("examples/data/Rock_Normal.jpg", None),
+                    ),
+                    fallback: SamplerFallback::Normal,
+                },
+            )
+            .unwrap();
+
+        // Sphere generator calls `calculate_tangents` internally, the same must be done for every
+        // procedural mesh that uses normal maps.
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_sphere(32, 32, 1.0, &Matrix4::identity()),
+            )))
+            .with_material(Arc::new(Mutex::new(material)))
+            .build()])
+            .build(&mut scene.graph);
+
+        let scene = engine.scenes.add(scene);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene,
+            light,
+            angle: 0.0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        self.angle += dt;
+
+        engine.scenes[self.scene].graph[self.light]
+            .local_transform_mut()
+            .set_position(Vector3::new(
+                2.0 * self.angle.cos(),
+                self.angle.sin(),
+                2.0 * self.angle.sin(),
+            ));
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Normal Mapping\nFPS: {}",
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Normal Mapping")
+        .run();
+}
@@ -0,0 +1,216 @@
+//! Example - Pixel art.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to make crisp pixel art 2D scene using sprites, sorting layers and
+//! pixel perfect camera. Sprites of each layer are attached to a pivot node which is moved with
+//! different speed when camera moves, which creates parallax effect. Use [A] and [D] to move
+//! the camera.
+
+use rg3d::{
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    resource::texture::{
+        Texture, TextureKind, TextureMagnificationFilter, TextureMinificationFilter,
+        TexturePixelKind,
+    },
+    scene::{
+        base::BaseBuilder, camera::CameraBuilder, node::Node, sprite::SpriteBuilder,
+        transform::TransformBuilder, Scene,
+    },
+};
+
+// Height of the screen in "virtual" pixels, actual pixels are scaled by integer factor.
+const REFERENCE_HEIGHT: u32 = 180;
+
+// Size of textures of sprites in texels.
+const SPRITE_TEXELS: u32 = 16;
+
+struct Layer {
+    pivot: Handle<Node>,
+    // 0.0 - layer is static relative to the camera, 1.0 - layer moves with the world.
+    parallax: f32,
+}
+
+// Creates procedural circle mask, sprites use red channel of a texture as a mask for their color.
+fn make_circle_texture() -> Texture {
+    let mut bytes = Vec::with_capacity((SPRITE_TEXELS * SPRITE_TEXELS) as usize);
+    let radius = SPRITE_TEXELS as f32 * 0.5;
+    for y in 0..SPRITE_TEXELS {
+        for x in 0..SPRITE_TEXELS {
+            let offset = Vector2::new(x as f32 + 0.5 - radius, y as f32 + 0.5 - radius);
+            bytes.push(if offset.norm() <= radius { 255 } else { 0 });
+        }
+    }
+
+    let texture = Texture::from_bytes(
+        TextureKind::Rectangle {
+            width: SPRITE_TEXELS,
+            height: SPRITE_TEXELS,
+        },
+        TexturePixelKind::R8,
+        bytes,
+        false,
+    )
+    .unwrap();
+
+    let mut data = texture.data_ref();
+    // Filtering blurs pixel art, so nearest texels must be used.
+    data.set_magnification_filter(TextureMagnificationFilter::Nearest);
+    data.set_minification_filter(TextureMinificationFilter::Nearest);
+    drop(data);
+
+    texture
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    camera: Handle<Node>,
+    layers: Vec<Layer>,
+    move_left: bool,
+    move_right: bool,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        // Camera is placed in front of the sprites and looks at them, sprites are at the same
+        // distance from the camera so their drawing order is defined only by sorting layers.
+        let camera = CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 0.0, -10.0))
+                    .build(),
+            ),
+        )
+        .with_pixel_perfect_2d(REFERENCE_HEIGHT)
+        .build(&mut scene.graph);
+
+        let texture = make_circle_texture();
+
+        // Size of a sprite is half of its width, one world unit is one virtual pixel.
+        let sprite_size = SPRITE_TEXELS as f32 * 0.5;
+
+        let layer_descs = [
+            // (sorting layer, parallax, color, count, vertical position)
+            (0, 0.2, Color::opaque(60, 60, 120), 12, 40.0),
+            (1, 0.5, Color::opaque(80, 140, 80), 16, 0.0),
+            (2, 1.0, Color::opaque(200, 120, 60), 20, -40.0),
+        ];
+
+        let mut layers = Vec::new();
+        for &(sorting_layer, parallax, color, count, y) in layer_descs.iter() {
+            let sprites = (0..count)
+                .map(|i| {
+                    // Sprites overlap each other, order in layer makes overlapping
+                    // deterministic.
+                    let x = (i as f32 - count as f32 * 0.5) * sprite_size * 1.5;
+                    SpriteBuilder::new(
+                        BaseBuilder::new().with_local_transform(
+                            TransformBuilder::new()
+                                .with_local_position(Vector3::new(
+                                    x.round(),
+                                    y + (i % 2) as f32 * 4.0,
+                                    0.0,
+                                ))
+                                .build(),
+                        ),
+                    )
+                    .with_texture(texture.clone())
+                    .with_size(sprite_size)
+                    .with_color(color)
+                    .with_sorting_layer(sorting_layer)
+                    .with_order_in_layer(i)
+                    .build(&mut scene.graph)
+                })
+                .collect::<Vec<_>>();
+
+            let pivot = BaseBuilder::new()
+                .with_children(&sprites)
+                .build(&mut scene.graph);
+
+            layers.push(Layer { pivot, parallax });
+        }
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            camera,
+            layers,
+            move_left: false,
+            move_right: false,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let graph = &mut engine.scenes[self.scene].graph;
+
+        let mut speed = 0.0;
+        if self.move_left {
+            speed += 60.0;
+        }
+        if self.move_right {
+            speed -= 60.0;
+        }
+
+        let camera_transform = graph[self.camera].local_transform_mut();
+        let camera_position = **camera_transform.position() + Vector3::new(speed * dt, 0.0, 0.0);
+        camera_transform.set_position(camera_position);
+
+        // Far layers follow the camera partially, so they look like they're moving slower.
+        for layer in self.layers.iter() {
+            graph[layer.pivot]
+                .local_transform_mut()
+                .set_position(Vector3::new(
+                    camera_position.x * (1.0 - layer.parallax),
+                    0.0,
+                    0.0,
+                ));
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Pixel art\nUse [A] and [D] to move the camera.\n{}",
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            let pressed = input.state == ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::A) => self.move_left = pressed,
+                Some(VirtualKeyCode::D) => self.move_right = pressed,
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Pixel art")
+        .run();
+}
@@ -0,0 +1,248 @@
+//! Example - Respawn.
+//!
+//! Difficulty: Medium.
+//!
+//! This example shows how to reuse animation blending machine when a model is destroyed and
+//! instantiated again (for example when a player dies and respawns). Animations are retargeted to
+//! new instance and the machine is rebound to new animations, so there is no need to build the
+//! machine from scratch. Press [W] to walk, [K] to kill the character, [R] to respawn it.
+
+use rg3d::{
+    animation::{
+        machine::{Machine, Parameter, PoseNode, State, Transition},
+        Animation,
+    },
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    engine::{
+        framework::prelude::*,
+        resource_manager::{MaterialSearchOptions, ModelImportOptions, ResourceManager},
+        Engine,
+    },
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    resource::model::Model,
+    scene::{
+        base::BaseBuilder, camera::CameraBuilder, node::Node, transform::TransformBuilder, Scene,
+    },
+};
+
+const IDLE_TO_WALK: &str = "IdleToWalk";
+const WALK_TO_IDLE: &str = "WalkToIdle";
+
+// Resources are loaded once, instances are made from them as many times as needed.
+struct Resources {
+    model: Model,
+    idle: Model,
+    walk: Model,
+}
+
+impl Resources {
+    async fn load(resource_manager: ResourceManager) -> Self {
+        let request = |path: &str| {
+            resource_manager.request_model_with_options(
+                path,
+                MaterialSearchOptions::RecursiveUp,
+                Some(ModelImportOptions::default().with_scale(0.0125)),
+            )
+        };
+
+        Self {
+            model: request("examples/data/mutant/mutant.FBX").await.unwrap(),
+            idle: request("examples/data/mutant/idle.fbx").await.unwrap(),
+            walk: request("examples/data/mutant/walk.fbx").await.unwrap(),
+        }
+    }
+}
+
+struct Character {
+    instance: Handle<Node>,
+    idle_animation: Handle<Animation>,
+    walk_animation: Handle<Animation>,
+}
+
+impl Character {
+    fn spawn(resources: &Resources, scene: &mut Scene) -> Self {
+        let instance = resources.model.instantiate_geometry(scene);
+        scene.graph[instance]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                180.0f32.to_radians(),
+            ));
+
+        // Each animation resource has exactly one animation.
+        Self {
+            instance,
+            idle_animation: resources.idle.retarget_animations(instance, scene)[0],
+            walk_animation: resources.walk.retarget_animations(instance, scene)[0],
+        }
+    }
+
+    fn despawn(self, scene: &mut Scene) {
+        // Retargeted animations are removed together with the instance.
+        scene.remove_node(self.instance);
+    }
+}
+
+fn create_machine(character: &Character) -> Machine {
+    let mut machine = Machine::new();
+
+    let idle_node = machine.add_node(PoseNode::make_play_animation(character.idle_animation));
+    let idle = machine.add_state(State::new("Idle", idle_node));
+
+    let walk_node = machine.add_node(PoseNode::make_play_animation(character.walk_animation));
+    let walk = machine.add_state(State::new("Walk", walk_node));
+
+    machine.add_transition(Transition::new("Idle->Walk", idle, walk, 0.3, IDLE_TO_WALK));
+    machine.add_transition(Transition::new("Walk->Idle", walk, idle, 0.3, WALK_TO_IDLE));
+
+    // Character always appears in idle state, after spawn and after respawn.
+    machine.set_entry_state(idle);
+
+    machine
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    resources: Resources,
+    character: Option<Character>,
+    // Animations of last spawned character, the machine references them even if the character
+    // was killed.
+    bound_animations: [Handle<Animation>; 2],
+    machine: Machine,
+    walk: bool,
+    respawn_count: usize,
+}
+
+impl Game {
+    fn kill(&mut self, engine: &mut Engine) {
+        if let Some(character) = self.character.take() {
+            character.despawn(&mut engine.scenes[self.scene]);
+        }
+    }
+
+    fn respawn(&mut self, engine: &mut Engine) {
+        self.kill(engine);
+
+        let scene = &mut engine.scenes[self.scene];
+        let character = Character::spawn(&self.resources, scene);
+
+        // Remap old animations to new ones, the machine keeps its states, transitions and
+        // parameters.
+        let new_animations = [character.idle_animation, character.walk_animation];
+        let map = self
+            .bound_animations
+            .iter()
+            .cloned()
+            .zip(new_animations.iter().cloned())
+            .collect();
+        self.machine.rebind_animations(&map);
+        self.machine.reset();
+        assert!(self
+            .machine
+            .dangling_animations(&scene.animations)
+            .is_empty());
+
+        self.bound_animations = new_animations;
+        self.character = Some(character);
+        self.respawn_count += 1;
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+        scene.ambient_lighting_color = Color::opaque(200, 200, 200);
+
+        CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 1.0, -3.0))
+                    .build(),
+            ),
+        )
+        .build(&mut scene.graph);
+
+        let resources = rg3d::core::futures::executor::block_on(Resources::load(
+            engine.resource_manager.clone(),
+        ));
+
+        let character = Character::spawn(&resources, &mut scene);
+        let machine = create_machine(&character);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            resources,
+            bound_animations: [character.idle_animation, character.walk_animation],
+            character: Some(character),
+            machine,
+            walk: false,
+            respawn_count: 0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        // The machine is evaluated even when the character is dead - its animations are
+        // removed at this moment, but the machine produces empty pose instead of panicking.
+        self.machine
+            .set_parameter(IDLE_TO_WALK, Parameter::Rule(self.walk))
+            .set_parameter(WALK_TO_IDLE, Parameter::Rule(!self.walk))
+            .evaluate_pose(&scene.animations, dt)
+            .apply(&mut scene.graph);
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Respawn\n\
+                [W] - walk, [K] - kill, [R] - respawn\n\
+                Character: {}, respawned {} times, machine state: {}\n{}",
+                if self.character.is_some() {
+                    "Alive"
+                } else {
+                    "Dead"
+                },
+                self.respawn_count,
+                self.machine.active_state_path(),
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            let pressed = input.state == ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::W) => self.walk = pressed,
+                Some(VirtualKeyCode::K) if pressed => self.kill(engine),
+                Some(VirtualKeyCode::R) if pressed => self.respawn(engine),
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Respawn")
+        .run();
+}
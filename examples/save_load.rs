@@ -17,7 +17,9 @@
 
 pub mod shared;
 
-use crate::shared::{create_ui, fix_shadows_distance, Game, GameScene, LocomotionMachine, Player};
+use crate::shared::{
+    create_ui, fix_shadows_distance, progress_text, Game, GameScene, LocomotionMachine, Player,
+};
 use rg3d::{
     core::{
         algebra::Vector2,
@@ -30,10 +32,7 @@ use rg3d::{
         widget::WidgetMessage,
     },
     renderer::QualitySettings,
-    utils::{
-        log::{Log, MessageKind},
-        translate_event,
-    },
+    utils::{log::log_error, translate_event},
 };
 use std::path::Path;
 
@@ -73,9 +72,10 @@ impl Visit for Player {
 
         self.model.visit("Model", visitor)?;
         self.body.visit("Body", visitor)?;
-        self.camera_pivot.visit("CameraPivot", visitor)?;
-        self.camera_hinge.visit("CameraHinge", visitor)?;
         self.camera.visit("Camera", visitor)?;
+        self.spring_arm.visit("SpringArm", visitor)?;
+        self.camera_shake.visit("CameraShake", visitor)?;
+        self.fov_animation.visit("FovAnimation", visitor)?;
         self.locomotion_machine
             .visit("LocomotionMachine", visitor)?;
         self.model_yaw.visit("ModelYaw", visitor)?;
@@ -107,8 +107,8 @@ impl Visit for Game {
         self.engine.visit("Engine", visitor)?;
 
         self.game_scene.visit("GameScene", visitor)?;
-        // self.load_context is intentionally not serialized - we abuse the fact that we can
-        // save **only** when scene was loaded, so no need to save context.
+        // self.scene_loader is intentionally not serialized - we abuse the fact that we can
+        // save **only** when scene was loaded, so no need to save loader.
 
         visitor.leave_region()
     }
@@ -158,11 +158,24 @@ fn main() {
                     // Put your game logic here.
                     // ************************
 
-                    // Check each frame if our scene is created - here we just trying to lock context
-                    // without blocking, it is important for main thread to be functional while other
-                    // thread still loading data.
-                    if let Ok(mut load_context) = game.load_context.as_ref().unwrap().try_lock() {
-                        if let Some(load_result) = load_context.scene_data.take() {
+                    // Check each frame if every resource of the scene is loaded and create the scene.
+                    if let Some(scene_loader) = game.scene_loader.as_ref() {
+                        // Report progress in UI.
+                        let progress = scene_loader.progress();
+                        game.engine
+                            .user_interface
+                            .send_message(ProgressBarMessage::progress(
+                                interface.progress_bar,
+                                MessageDirection::ToWidget,
+                                progress.fraction(),
+                            ));
+                        game.engine.user_interface.send_message(TextMessage::text(
+                            interface.progress_text,
+                            MessageDirection::ToWidget,
+                            progress_text(&progress),
+                        ));
+
+                        if let Some(load_result) = scene_loader.try_create_scene() {
                             // Add scene to engine - engine will take ownership over scene and will return
                             // you a handle to scene which can be used later on to borrow it and do some
                             // actions you need.
@@ -187,25 +200,10 @@ fn main() {
                                     MessageDirection::ToWidget,
                                     false,
                                 ));
-                        }
 
-                        // Report progress in UI.
-                        game.engine
-                            .user_interface
-                            .send_message(ProgressBarMessage::progress(
-                                interface.progress_bar,
-                                MessageDirection::ToWidget,
-                                load_context.progress,
-                            ));
-                        game.engine.user_interface.send_message(TextMessage::text(
-                            interface.progress_text,
-                            MessageDirection::ToWidget,
-                            format!(
-                                "Loading scene: {}%\n{}",
-                                load_context.progress * 100.0,
-                                load_context.message
-                            ),
-                        ));
+                            // Loader is not needed anymore.
+                            game.scene_loader = None;
+                        }
                     }
 
                     // Update scene only if it is loaded.
@@ -216,7 +214,7 @@ fn main() {
                         game_scene.player.update(scene, fixed_timestep);
                     }
 
-                    let fps = game.engine.renderer.get_statistics().frames_per_second;
+                    let fps = game.engine.renderer().get_statistics().frames_per_second;
                     let debug_text = format!(
                         "Example 06 - Save/load\n[W][S][A][D] - walk, [SPACE] - jump.\nFPS: {}\nUse [1][2][3][4] to select graphics quality.\nUse F5 to save game, F9 to load.",
                         fps
@@ -259,7 +257,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = game.engine.set_frame_size(size.into()) {
-                            Log::writeln(MessageKind::Error, format!("Unable to set frame size: {:?}", e));
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
 
                         // Root UI node should be resized too, otherwise progress bar will stay
@@ -293,7 +291,7 @@ fn main() {
 
                             if let Some(settings) = settings {
                                 game.engine
-                                    .renderer
+                                    .renderer_mut()
                                     .set_quality_settings(&fix_shadows_distance(settings))
                                     .unwrap();
                             }
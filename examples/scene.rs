@@ -28,10 +28,7 @@ use rg3d::{
         BuildContext, UiNode,
     },
     scene::{base::BaseBuilder, node::Node, Scene},
-    utils::{
-        log::{Log, MessageKind},
-        translate_event,
-    },
+    utils::{log::log_error, translate_event},
 };
 use std::time::Instant;
 
@@ -146,7 +143,7 @@ fn main() {
                         UnitQuaternion::from_axis_angle(&Vector3::y_axis(), model_angle),
                     );
 
-                    let fps = engine.renderer.get_statistics().frames_per_second;
+                    let fps = engine.renderer().get_statistics().frames_per_second;
                     let text = format!(
                         "Example 05 - Scene\nUse [A][D] keys to rotate camera.\nFPS: {}",
                         fps
@@ -186,10 +183,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = engine.set_frame_size(size.into()) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Unable to set frame size: {:?}", e),
-                            );
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
                     }
                     WindowEvent::KeyboardInput { input, .. } => {
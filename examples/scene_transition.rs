@@ -0,0 +1,336 @@
+//! Example - Scene transition.
+//!
+//! Difficulty: Medium.
+//!
+//! This example shows how to load next level in background while current one is still playing and
+//! how to move the player to the new level. There are two levels with a door at the end of each one,
+//! when the player comes close to the door, next level starts loading. When it is loaded, current
+//! level is replaced with it and the player is copied to the new level. Use [W][S][A][D] to move.
+
+use rg3d::{
+    core::{
+        algebra::{Isometry3, Matrix4, Vector3},
+        color::Color,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+        visitor::VisitError,
+    },
+    engine::{
+        framework::prelude::*, resource_manager::ResourceManager, scene_loader::PendingScene,
+        Engine,
+    },
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    physics3d::rapier::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder},
+    scene::{
+        base::BaseBuilder,
+        camera::CameraBuilder,
+        graph::Graph,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+    utils::log::log_error,
+};
+use std::sync::Arc;
+
+const DOOR: &str = "Door";
+const SPAWN_POINT: Vector3<f32> = Vector3::new(0.0, 1.0, -8.0);
+const DOOR_DISTANCE: f32 = 2.0;
+const PLAYER_SPEED: f32 = 4.0;
+
+fn make_box(
+    graph: &mut Graph,
+    name: &str,
+    position: Vector3<f32>,
+    size: Vector3<f32>,
+    material: Material,
+) -> Handle<Node> {
+    MeshBuilder::new(
+        BaseBuilder::new().with_name(name).with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&size)),
+    )))
+    .with_material(Arc::new(Mutex::new(material)))
+    .build()])
+    .build(graph)
+}
+
+fn make_color_material(color: Color) -> Material {
+    let mut material = Material::standard();
+    material
+        .set_property(
+            &ImmutableString::new("diffuseColor"),
+            PropertyValue::Color(color),
+        )
+        .unwrap();
+    material
+}
+
+// Builds a level, this function runs in background. Textures are requested from the resource
+// manager, so their loading is reported by its progress.
+async fn build_level(index: usize, resource_manager: ResourceManager) -> Result<Scene, VisitError> {
+    let mut scene = Scene::new();
+
+    let (floor_texture, light_color) = if index % 2 == 0 {
+        ("examples/data/floor.jpg", Color::opaque(255, 230, 200))
+    } else {
+        ("examples/data/concrete.jpg", Color::opaque(150, 200, 255))
+    };
+
+    let floor_texture = resource_manager.request_texture(floor_texture, None);
+    // Wait until the texture is loaded, the level is not ready without it.
+    let _ = floor_texture.clone().await;
+
+    let mut floor_material = Material::standard();
+    floor_material
+        .set_property(
+            &ImmutableString::new("diffuseTexture"),
+            PropertyValue::Sampler {
+                value: Some(floor_texture),
+                fallback: SamplerFallback::White,
+            },
+        )
+        .unwrap();
+
+    make_box(
+        &mut scene.graph,
+        "Floor",
+        Vector3::new(0.0, -0.25, 0.0),
+        Vector3::new(12.0, 0.25, 24.0),
+        floor_material,
+    );
+    let floor_body = scene.physics.add_body(
+        RigidBodyBuilder::new_static()
+            .translation(Vector3::new(0.0, -0.25, 0.0))
+            .build(),
+    );
+    scene.physics.add_collider(
+        ColliderBuilder::cuboid(6.0, 0.25, 12.0).build(),
+        &floor_body,
+    );
+
+    make_box(
+        &mut scene.graph,
+        DOOR,
+        Vector3::new(0.0, 1.5, 11.5),
+        Vector3::new(2.0, 3.0, 0.2),
+        make_color_material(Color::opaque(120, 70, 30)),
+    );
+
+    PointLightBuilder::new(
+        BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 6.0, 0.0))
+                    .build(),
+            ),
+        )
+        .with_color(light_color),
+    )
+    .with_radius(30.0)
+    .build(&mut scene.graph);
+
+    Ok(scene)
+}
+
+struct InputController {
+    move_forward: bool,
+    move_backward: bool,
+    move_left: bool,
+    move_right: bool,
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    player: Handle<Node>,
+    level: usize,
+    next_level: Option<PendingScene>,
+    input: InputController,
+}
+
+impl Game {
+    fn player_position(&self, engine: &Engine) -> Vector3<f32> {
+        engine.scenes[self.scene].graph[self.player].global_position()
+    }
+
+    fn move_player_to_spawn_point(&self, engine: &mut Engine) {
+        let scene = &mut engine.scenes[self.scene];
+        if let Some(body) = scene.physics_binder.body_of(self.player) {
+            if let Some(body) = scene.physics.bodies.get_mut(body) {
+                body.set_position(Isometry3::new(SPAWN_POINT, Default::default()), true);
+                body.set_linvel(Default::default(), true);
+            }
+        }
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        // First level is loaded synchronously, there is nothing to show while it is loading.
+        let mut scene = rg3d::core::futures::executor::block_on(build_level(
+            0,
+            engine.resource_manager.clone(),
+        ))
+        .unwrap();
+
+        // Player is a capsule with a camera, it is kept alive across levels.
+        let camera = CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 0.6, 0.0))
+                    .build(),
+            ),
+        )
+        .build(&mut scene.graph);
+        let player = BaseBuilder::new()
+            .with_name("Player")
+            .with_children(&[camera])
+            .build(&mut scene.graph);
+        let player_body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(SPAWN_POINT)
+                .lock_rotations()
+                .build(),
+        );
+        scene
+            .physics
+            .add_collider(ColliderBuilder::capsule_y(0.5, 0.3).build(), &player_body);
+        scene.physics_binder.bind(player, player_body);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            player,
+            level: 0,
+            next_level: None,
+            input: InputController {
+                move_forward: false,
+                move_backward: false,
+                move_left: false,
+                move_right: false,
+            },
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        // Start loading next level when the player comes close to the door.
+        let scene = &engine.scenes[self.scene];
+        let door = scene.graph.find_by_name_from_root(DOOR);
+        if self.next_level.is_none()
+            && (scene.graph[door].global_position() - self.player_position(engine)).norm()
+                < DOOR_DISTANCE
+        {
+            let level = self.level + 1;
+            self.next_level =
+                Some(engine.build_scene_async(move |resource_manager| {
+                    build_level(level, resource_manager)
+                }));
+        }
+
+        // Swap levels when next one is ready, current level keeps playing until then.
+        if let Some(result) = self.next_level.as_ref().and_then(|next| next.try_take()) {
+            self.next_level = None;
+            match result {
+                Ok(scene) => {
+                    let (scene, copied) = engine.scenes.swap_to(self.scene, scene, &[self.player]);
+                    self.scene = scene;
+                    self.player = copied.nodes[&self.player];
+                    self.level += 1;
+                    self.move_player_to_spawn_point(engine);
+                }
+                Err(e) => log_error!("Unable to load next level: {:?}", e),
+            }
+        }
+
+        // Move the player.
+        let mut velocity = Vector3::default();
+        if self.input.move_forward {
+            velocity.z += 1.0;
+        }
+        if self.input.move_backward {
+            velocity.z -= 1.0;
+        }
+        if self.input.move_left {
+            velocity.x += 1.0;
+        }
+        if self.input.move_right {
+            velocity.x -= 1.0;
+        }
+        let velocity = velocity
+            .try_normalize(f32::EPSILON)
+            .map_or(Vector3::default(), |v| v.scale(PLAYER_SPEED));
+
+        let scene = &mut engine.scenes[self.scene];
+        if let Some(body) = scene.physics_binder.body_of(self.player) {
+            if let Some(body) = scene.physics.bodies.get_mut(body) {
+                let vertical = body.linvel().y;
+                body.set_linvel(Vector3::new(velocity.x, vertical, velocity.z), true);
+            }
+        }
+
+        let status = match self.next_level.as_ref() {
+            Some(next_level) => format!(
+                "Loading next level: {:.0}%",
+                next_level.progress().fraction() * 100.0
+            ),
+            None => "Go to the door to load next level".to_owned(),
+        };
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Scene transition\n\
+                Use [W][S][A][D] to move.\n\
+                Level: {}\n{}\n{}",
+                self.level + 1,
+                status,
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            let pressed = input.state == ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::W) => self.input.move_forward = pressed,
+                Some(VirtualKeyCode::S) => self.input.move_backward = pressed,
+                Some(VirtualKeyCode::A) => self.input.move_left = pressed,
+                Some(VirtualKeyCode::D) => self.input.move_right = pressed,
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Scene transition")
+        .run();
+}
@@ -0,0 +1,203 @@
+//! Example - Shadow filtering.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows Sponza lit by a single point light that moves close to a wall. Press F to
+//! switch between shadow filters and compare edges of shadows. Press C to toggle shadows of small
+//! clutter objects and watch triangle count of shadow pass.
+
+pub mod shared;
+
+use crate::shared::{create_camera, fix_shadows_distance};
+use rg3d::{
+    core::{algebra::Vector3, color::Color, futures::executor::block_on, pool::Handle},
+    engine::{framework::prelude::*, resource_manager::MaterialSearchOptions, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    renderer::{QualitySettings, ShadowFilter},
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+
+const FILTERS: [ShadowFilter; 4] = [
+    ShadowFilter::Off,
+    ShadowFilter::Pcf2x2,
+    ShadowFilter::Pcf4x4,
+    ShadowFilter::Poisson,
+];
+
+// Meshes which bounding box is smaller than this size are treated as clutter.
+const CLUTTER_SIZE: f32 = 1.0;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    light: Handle<Node>,
+    filter_index: usize,
+    time: f32,
+    clutter: Vec<Handle<Node>>,
+    clutter_shadows: bool,
+}
+
+impl Game {
+    fn apply_filter(&self, engine: &mut Engine) {
+        let mut settings = fix_shadows_distance(QualitySettings::high());
+        settings.point_shadow_filter = FILTERS[self.filter_index];
+        // Lower resolution makes aliasing and seams between cube map faces easier to spot.
+        settings.point_shadow_map_size = 256;
+        engine
+            .renderer_mut()
+            .set_quality_settings(&settings)
+            .unwrap();
+    }
+
+    fn apply_clutter_shadows(&self, engine: &mut Engine) {
+        let graph = &mut engine.scenes[self.scene].graph;
+        for &handle in self.clutter.iter() {
+            let mesh = graph[handle].as_mesh_mut();
+            mesh.set_cast_shadows(self.clutter_shadows);
+            mesh.set_receive_shadows(self.clutter_shadows);
+        }
+    }
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(30, 30, 30);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(5.0, 4.0, -8.0),
+            &mut scene.graph,
+        ));
+
+        block_on(engine.resource_manager.request_model(
+            "examples/data/sponza/Sponza.rgs",
+            MaterialSearchOptions::RecursiveUp,
+        ))
+        .unwrap()
+        .instantiate_geometry(&mut scene);
+
+        // Hide lights of the level, only our light must be visible.
+        for node in scene.graph.linear_iter_mut() {
+            if let Node::Light(_) = node {
+                node.set_visibility(false);
+            }
+        }
+
+        // Global transforms are required to calculate bounds of meshes.
+        scene.graph.update_hierarchical_data();
+        let graph = &scene.graph;
+        let clutter = graph
+            .pair_iter()
+            .filter_map(|(handle, node)| match node {
+                Node::Mesh(mesh)
+                    if mesh.accurate_world_bounding_box(graph).half_extents().max() * 2.0
+                        < CLUTTER_SIZE =>
+                {
+                    Some(handle)
+                }
+                _ => None,
+            })
+            .collect();
+
+        let light = PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 2.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(8.0)
+        .build(&mut scene.graph);
+
+        let game = Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            light,
+            filter_index: 3,
+            time: 0.0,
+            clutter,
+            clutter_shadows: true,
+        };
+
+        game.apply_filter(engine);
+
+        game
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        self.time += dt;
+
+        // Light moves along the wall and comes very close to it from time to time.
+        engine.scenes[self.scene].graph[self.light]
+            .local_transform_mut()
+            .set_position(Vector3::new(
+                6.0 * (self.time * 0.3).sin(),
+                2.0,
+                1.5 + 1.0 * (self.time * 0.7).cos(),
+            ));
+
+        let statistics = engine.renderer().get_statistics();
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Shadow Filtering\n\
+                Press F to change filter\n\
+                Press C to toggle shadows of clutter\n\
+                Filter: {:?}\n\
+                Clutter Shadows: {} ({} meshes)\n\
+                Shadow Pass Triangles: {}\n\
+                FPS: {}",
+                FILTERS[self.filter_index],
+                if self.clutter_shadows { "On" } else { "Off" },
+                self.clutter.len(),
+                statistics.lighting.shadow_pass.triangles_rendered,
+                statistics.frames_per_second
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::F) => {
+                        self.filter_index = (self.filter_index + 1) % FILTERS.len();
+                        self.apply_filter(engine);
+                    }
+                    Some(VirtualKeyCode::C) => {
+                        self.clutter_shadows = !self.clutter_shadows;
+                        self.apply_clutter_shadows(engine);
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Shadow Filtering")
+        .run();
+}
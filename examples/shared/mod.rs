@@ -4,8 +4,14 @@
 // some parts can be unused in some examples.
 #![allow(dead_code)]
 
-use rg3d::engine::Engine;
-use rg3d::gui::{BuildContext, UiNode};
+use rg3d::engine::{
+    plugin::{EngineContext, Plugin},
+    Engine,
+};
+use rg3d::gui::{
+    message::MessageDirection, progress_bar::ProgressBarMessage, text::TextMessage, BuildContext,
+    UiNode,
+};
 use rg3d::physics3d::{
     rapier::{
         dynamics::RigidBodyBuilder,
@@ -16,11 +22,14 @@ use rg3d::physics3d::{
 };
 use rg3d::{
     animation::{
-        machine::{Machine, Parameter, PoseNode, State, Transition},
+        machine::{BlendCurve, Machine, Parameter, PoseNode, State, Transition},
+        mirror::{AnimationMirror, AnimationMirrorBuilder},
         Animation, AnimationSignal,
     },
-    core::{algebra::Vector2, color::Color, math::SmoothAngle, pool::Handle},
-    engine::resource_manager::{MaterialSearchOptions, ResourceManager},
+    core::{algebra::Vector2, color::Color, math::smooth::SmoothQuat, pool::Handle},
+    engine::resource_manager::{
+        LoadingProgress, MaterialSearchOptions, ModelImportOptions, ResourceManager,
+    },
     event::{DeviceEvent, ElementState, VirtualKeyCode},
     event_loop::EventLoop,
     gui::{
@@ -38,16 +47,20 @@ use rg3d::{
         camera::{CameraBuilder, SkyBoxBuilder},
         graph::Graph,
         node::Node,
+        sound::{PlaySoundParams, SoundVariation},
         transform::TransformBuilder,
         Scene,
     },
-    sound::effects::{BaseEffect, Effect},
-};
-use std::{
-    path::Path,
-    sync::{Arc, Mutex},
-    time::Duration,
+    sound::{
+        buffer::SoundBufferResource,
+        effects::{BaseEffect, Effect},
+    },
+    utils::{
+        camera::{CameraShake, FovAnimation, SpringArm, SpringArmCollision},
+        locomotion::LocomotionDriver,
+    },
 };
+use std::{cell::RefCell, path::Path, rc::Rc, sync::Arc, time::Duration};
 
 /// Creates a camera at given position with a skybox.
 pub async fn create_camera(
@@ -117,7 +130,7 @@ pub async fn create_camera(
 
 pub struct Game {
     pub game_scene: Option<GameScene>,
-    pub load_context: Option<Arc<Mutex<SceneLoadContext>>>,
+    pub scene_loader: Option<SceneLoader>,
     pub engine: Engine,
 }
 
@@ -132,16 +145,16 @@ impl Game {
         let mut engine = Engine::new(window_builder, &event_loop, false).unwrap();
 
         engine
-            .renderer
+            .renderer_mut()
             .set_quality_settings(&fix_shadows_distance(QualitySettings::high()))
             .unwrap();
 
         let game = Self {
             // Initially scene is None, once scene is loaded it'll have actual state.
             game_scene: None,
-            // Create scene asynchronously - this method immediately returns empty load context
-            // which will be filled with data over time.
-            load_context: Some(create_scene_async(engine.resource_manager.clone())),
+            // Start loading of every resource of the scene, the scene will be created once
+            // everything is loaded.
+            scene_loader: Some(SceneLoader::new(engine.resource_manager.clone())),
             engine,
         };
         (game, event_loop)
@@ -203,6 +216,86 @@ pub fn create_ui(ui: &mut BuildContext, screen_size: Vector2<f32>) -> Interface
     }
 }
 
+/// Shows progress of scene loading and adds the scene to the engine once it is loaded. Loaded
+/// scene is put in a shared slot, so the game can take it from there.
+pub struct LoadingScreen {
+    scene_loader: Option<SceneLoader>,
+    progress_bar: Handle<UiNode>,
+    progress_text: Handle<UiNode>,
+    game_scene: Rc<RefCell<Option<GameScene>>>,
+}
+
+impl LoadingScreen {
+    pub fn new(
+        scene_loader: SceneLoader,
+        interface: &Interface,
+        game_scene: Rc<RefCell<Option<GameScene>>>,
+    ) -> Self {
+        Self {
+            scene_loader: Some(scene_loader),
+            progress_bar: interface.progress_bar,
+            progress_text: interface.progress_text,
+            game_scene,
+        }
+    }
+}
+
+impl Plugin for LoadingScreen {
+    fn on_update(&mut self, _dt: f32, context: &mut EngineContext) {
+        if let Some(scene_loader) = self.scene_loader.as_ref() {
+            // Report progress in UI.
+            let progress = scene_loader.progress();
+            context
+                .user_interface
+                .send_message(ProgressBarMessage::progress(
+                    self.progress_bar,
+                    MessageDirection::ToWidget,
+                    progress.fraction(),
+                ));
+            context.user_interface.send_message(TextMessage::text(
+                self.progress_text,
+                MessageDirection::ToWidget,
+                progress_text(&progress),
+            ));
+
+            // Check each frame if every resource is loaded and create the scene.
+            if let Some(load_result) = scene_loader.try_create_scene() {
+                // Add scene to engine - engine will take ownership over scene and will return
+                // you a handle to scene which can be used later on to borrow it and do some
+                // actions you need.
+                *self.game_scene.borrow_mut() = Some(GameScene {
+                    scene: context.scenes.add(load_result.scene),
+                    player: load_result.player,
+                    reverb_effect: load_result.reverb_effect,
+                });
+                self.scene_loader = None;
+            }
+        }
+    }
+
+    fn on_remove(&mut self, context: &mut EngineContext) {
+        // Smoothly fade out progress bar and text when loading screen is not needed anymore,
+        // widgets will be hidden once animation is finished.
+        for widget in [self.progress_bar, self.progress_text] {
+            context.user_interface.fade_out(widget, 0.5);
+        }
+    }
+}
+
+/// Creates text for loading screen.
+pub fn progress_text(progress: &LoadingProgress) -> String {
+    format!(
+        "Loading scene: {:.0}% ({}/{})\n{}",
+        progress.fraction() * 100.0,
+        progress.loaded,
+        progress.total,
+        progress.current.as_ref().map_or_else(
+            || "Creating scene...".to_owned(),
+            |p| p.display().to_string()
+        )
+    )
+}
+
 pub struct SceneLoadResult {
     pub scene: Scene,
     pub player: Player,
@@ -216,17 +309,56 @@ pub struct GameScene {
     pub reverb_effect: Handle<Effect>,
 }
 
-pub struct SceneLoadContext {
-    pub scene_data: Option<SceneLoadResult>,
-    pub message: String,
-    pub progress: f32,
+/// Requests every resource of the scene at once, so resource manager knows total amount of
+/// resources from the beginning and loading progress is correct. The scene is created when
+/// every resource is loaded.
+pub struct SceneLoader {
+    resource_manager: ResourceManager,
 }
 
-impl SceneLoadContext {
-    pub fn report_progress(&mut self, progress: f32, message: &str) {
-        self.progress = progress;
-        self.message = message.to_owned();
-        println!("Loading progress: {}% - {}", progress * 100.0, message);
+impl SceneLoader {
+    pub fn new(resource_manager: ResourceManager) -> Self {
+        // Textures of the models will be requested while the models are loading, so they'll be
+        // counted too.
+        resource_manager.prefetch(&[
+            "examples/data/sponza/Sponza.rgs",
+            "examples/data/mutant/walk.fbx",
+            "examples/data/mutant/idle.fbx",
+            "examples/data/mutant/jump.fbx",
+            JUMP_SOUND_PATH,
+            "examples/data/skyboxes/DarkStormy/DarkStormyFront2048.png",
+            "examples/data/skyboxes/DarkStormy/DarkStormyBack2048.png",
+            "examples/data/skyboxes/DarkStormy/DarkStormyLeft2048.png",
+            "examples/data/skyboxes/DarkStormy/DarkStormyRight2048.png",
+            "examples/data/skyboxes/DarkStormy/DarkStormyUp2048.png",
+            "examples/data/skyboxes/DarkStormy/DarkStormyDown2048.png",
+        ]);
+
+        // The model needs custom import options (see `Player::new`) and prefetch uses default
+        // ones, so the model is requested manually - it will be counted anyway.
+        resource_manager.request_model_with_options(
+            "examples/data/mutant/mutant.FBX",
+            MaterialSearchOptions::RecursiveUp,
+            Some(ModelImportOptions::default().with_scale(0.0125)),
+        );
+
+        Self { resource_manager }
+    }
+
+    pub fn progress(&self) -> LoadingProgress {
+        self.resource_manager.progress()
+    }
+
+    /// Creates the scene if every resource is loaded.
+    pub fn try_create_scene(&self) -> Option<SceneLoadResult> {
+        if self.progress().is_done() {
+            // Every resource is loaded at this moment, so the scene will be created immediately.
+            Some(rg3d::core::futures::executor::block_on(create_scene(
+                self.resource_manager.clone(),
+            )))
+        } else {
+            None
+        }
     }
 }
 
@@ -277,11 +409,19 @@ pub struct LocomotionMachine {
     pub machine: Machine,
     pub jump_animation: Handle<Animation>,
     pub walk_animation: Handle<Animation>,
+    pub idle_animation: Handle<Animation>,
     pub walk_state: Handle<State>,
+    // Additive animation that is played on top of locomotion when a player presses [F].
+    pub flinch_animation: Handle<Animation>,
+    pub flinch_layer: usize,
+    // Left/right bone mapping of the character, it allows to play every animation mirrored.
+    pub mirror: Option<Arc<AnimationMirror>>,
+    // Sets walk/idle rules from actual velocity of the character's body, so the character won't
+    // slide in idle pose when it is pushed by something.
+    pub driver: LocomotionDriver,
 }
 
 pub struct LocomotionMachineInput {
-    is_walking: bool,
     is_jumping: bool,
 }
 
@@ -294,7 +434,17 @@ impl LocomotionMachine {
     const IDLE_TO_JUMP: &'static str = "IdleToJump";
     const JUMP_TO_IDLE: &'static str = "JumpToIdle";
 
+    // Weight parameters that are set by the locomotion driver, they're not used by the machine yet,
+    // but could be used to blend walk and run animations.
+    const SPEED: &'static str = "Speed";
+    const STRAFE_ANGLE: &'static str = "StrafeAngle";
+
     pub const JUMP_SIGNAL: u64 = 1;
+    pub const FOOTSTEP_SIGNAL: u64 = 2;
+
+    // Flinch is faded in and out to not snap from locomotion pose.
+    const FLINCH_FADE_IN: f32 = 0.1;
+    const FLINCH_FADE_OUT: f32 = 0.3;
 
     pub async fn new(
         scene: &mut Scene,
@@ -303,6 +453,11 @@ impl LocomotionMachine {
     ) -> Self {
         let mut machine = Machine::new();
 
+        // Must be built before any animation is applied, so the model is in its rest pose.
+        // Bones of the model follow "Left"/"Right" naming convention, unmatched bones (if any)
+        // are reported in the log.
+        let mirror = Arc::new(AnimationMirrorBuilder::new(model).build(&scene.graph));
+
         let (walk_animation, walk_state) = create_play_animation_state(
             "examples/data/mutant/walk.fbx",
             "Walk",
@@ -312,7 +467,7 @@ impl LocomotionMachine {
             resource_manager.clone(),
         )
         .await;
-        let (_, idle_state) = create_play_animation_state(
+        let (idle_animation, idle_state) = create_play_animation_state(
             "examples/data/mutant/idle.fbx",
             "Idle",
             &mut machine,
@@ -329,7 +484,7 @@ impl LocomotionMachine {
             &mut machine,
             scene,
             model,
-            resource_manager,
+            resource_manager.clone(),
         )
         .await;
         scene
@@ -342,36 +497,86 @@ impl LocomotionMachine {
             .add_signal(AnimationSignal::new(Self::JUMP_SIGNAL, 0.32))
             .set_loop(false);
 
+        // Feet hit the ground twice per walk cycle, approximately at these moments.
+        let walk = scene.animations.get_mut(walk_animation);
+        let walk_length = walk.length();
+        walk.add_signal(AnimationSignal::new(
+            Self::FOOTSTEP_SIGNAL,
+            0.25 * walk_length,
+        ))
+        .add_signal(AnimationSignal::new(
+            Self::FOOTSTEP_SIGNAL,
+            0.75 * walk_length,
+        ));
+
+        // There is no flinch animation in example data, so beginning of jump animation (when the
+        // character squats before pushing off the ground) is used. The animation is additive, so
+        // only difference between its frames and its first frame is added on top of locomotion.
+        let flinch_animation = load_animation(
+            "examples/data/mutant/jump.fbx",
+            scene,
+            model,
+            resource_manager,
+        )
+        .await;
+        let flinch = scene.animations.get_mut(flinch_animation);
+        let flinch_length = flinch.length();
+        flinch
+            .set_additive(true)
+            .set_loop(false)
+            .set_speed(1.5)
+            // Do not play it until the first flinch.
+            .set_time_position(flinch_length);
+        let flinch_layer = machine.add_additive_layer(flinch_animation, 0.0);
+
         // Add transitions between states. This is the "heart" of animation blending state machine
         // it defines how it will respond to input parameters.
-        machine.add_transition(Transition::new(
-            "Walk->Idle",
-            walk_state,
-            idle_state,
-            0.30,
-            Self::WALK_TO_IDLE,
-        ));
-        machine.add_transition(Transition::new(
-            "Walk->Jump",
-            walk_state,
-            jump_state,
-            0.20,
-            Self::WALK_TO_JUMP,
-        ));
-        machine.add_transition(Transition::new(
-            "Idle->Walk",
-            idle_state,
-            walk_state,
-            0.30,
-            Self::IDLE_TO_WALK,
-        ));
-        machine.add_transition(Transition::new(
-            "Idle->Jump",
-            idle_state,
-            jump_state,
-            0.25,
-            Self::IDLE_TO_JUMP,
-        ));
+        // Walk and idle transitions can interrupt each other, so the character will immediately
+        // start going back to idle if a player releases walk key in the middle of Idle->Walk
+        // transition (and vice versa). Jump transitions have higher priority, so jump can
+        // interrupt walk/idle transitions, but not vice versa.
+        machine.add_transition(
+            Transition::new(
+                "Walk->Idle",
+                walk_state,
+                idle_state,
+                0.30,
+                Self::WALK_TO_IDLE,
+            )
+            .with_curve(BlendCurve::EaseInOut)
+            .with_interruptible(true),
+        );
+        machine.add_transition(
+            Transition::new(
+                "Walk->Jump",
+                walk_state,
+                jump_state,
+                0.20,
+                Self::WALK_TO_JUMP,
+            )
+            .with_priority(1),
+        );
+        machine.add_transition(
+            Transition::new(
+                "Idle->Walk",
+                idle_state,
+                walk_state,
+                0.30,
+                Self::IDLE_TO_WALK,
+            )
+            .with_curve(BlendCurve::EaseInOut)
+            .with_interruptible(true),
+        );
+        machine.add_transition(
+            Transition::new(
+                "Idle->Jump",
+                idle_state,
+                jump_state,
+                0.25,
+                Self::IDLE_TO_JUMP,
+            )
+            .with_priority(1),
+        );
         machine.add_transition(Transition::new(
             "Jump->Idle",
             jump_state,
@@ -384,15 +589,76 @@ impl LocomotionMachine {
             machine,
             jump_animation,
             walk_animation,
+            idle_animation,
             walk_state,
+            flinch_animation,
+            flinch_layer,
+            mirror: Some(mirror),
+            driver: LocomotionDriver {
+                moving_rule: Some(Self::IDLE_TO_WALK.to_owned()),
+                idle_rule: Some(Self::WALK_TO_IDLE.to_owned()),
+                speed_weight: Some(Self::SPEED.to_owned()),
+                strafe_angle: Some(Self::STRAFE_ANGLE.to_owned()),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Plays every animation of the machine mirrored, so the character walks with the other leg
+    /// first, jumps from the other leg and so on. The same way "strafe right" can be made of
+    /// "strafe left".
+    pub fn set_mirrored(&self, scene: &mut Scene, mirrored: bool) {
+        for &animation in [
+            self.walk_animation,
+            self.idle_animation,
+            self.jump_animation,
+        ]
+        .iter()
+        {
+            scene
+                .animations
+                .get_mut(animation)
+                .set_mirror(self.mirror.clone())
+                .set_mirrored(mirrored);
         }
     }
 
-    pub fn apply(&mut self, scene: &mut Scene, dt: f32, input: LocomotionMachineInput) {
+    pub fn is_mirrored(&self, scene: &Scene) -> bool {
+        scene.animations.get(self.walk_animation).is_mirrored()
+    }
+
+    /// Plays flinch animation on top of current locomotion pose.
+    pub fn flinch(&self, scene: &mut Scene) {
+        scene.animations.get_mut(self.flinch_animation).rewind();
+    }
+
+    fn flinch_weight(&self, scene: &Scene) -> f32 {
+        let flinch = scene.animations.get(self.flinch_animation);
+        if flinch.has_ended() {
+            0.0
+        } else {
+            let time = flinch.get_time_position();
+            (time / Self::FLINCH_FADE_IN)
+                .min((flinch.length() - time) / Self::FLINCH_FADE_OUT)
+                .clamp(0.0, 1.0)
+        }
+    }
+
+    pub fn apply(
+        &mut self,
+        scene: &mut Scene,
+        body: RigidBodyHandle,
+        dt: f32,
+        input: LocomotionMachineInput,
+    ) {
+        // Walk and idle rules are set by the driver.
+        self.driver.update(scene, body, &mut self.machine, dt);
+
+        let flinch_weight = self.flinch_weight(scene);
+
         self.machine
+            .set_additive_layer_weight(self.flinch_layer, flinch_weight)
             // Update parameters which will be used by transitions.
-            .set_parameter(Self::IDLE_TO_WALK, Parameter::Rule(input.is_walking))
-            .set_parameter(Self::WALK_TO_IDLE, Parameter::Rule(!input.is_walking))
             .set_parameter(Self::WALK_TO_JUMP, Parameter::Rule(input.is_jumping))
             .set_parameter(Self::IDLE_TO_JUMP, Parameter::Rule(input.is_jumping))
             .set_parameter(
@@ -412,56 +678,39 @@ impl LocomotionMachine {
 pub struct Player {
     pub body: RigidBodyHandle,
     pub pivot: Handle<Node>,
-    pub camera_pivot: Handle<Node>,
-    pub camera_hinge: Handle<Node>,
     pub camera: Handle<Node>,
+    pub spring_arm: SpringArm,
+    pub camera_shake: CameraShake,
+    pub fov_animation: FovAnimation,
     pub model: Handle<Node>,
     pub controller: InputController,
     pub locomotion_machine: LocomotionMachine,
-    pub model_yaw: SmoothAngle,
+    pub model_yaw: SmoothQuat,
+    pub camera_rotation: SmoothQuat,
+    pub jump_sound: Option<SoundBufferResource>,
+    pub footsteps: SoundVariation,
 }
 
-impl Player {
-    pub async fn new(
-        scene: &mut Scene,
-        resource_manager: ResourceManager,
-        context: Arc<Mutex<SceneLoadContext>>,
-    ) -> Self {
-        // It is important to lock context for short period of time so other thread can
-        // read data from it as soon as possible - not when everything was loaded.
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.0, "Creating camera...");
-
-        let camera;
-        let camera_hinge;
-        let camera_pivot = BaseBuilder::new()
-            .with_children(&[{
-                camera_hinge = BaseBuilder::new()
-                    .with_local_transform(
-                        TransformBuilder::new()
-                            .with_local_position(Vector3::new(0.0, 1.0, 0.0))
-                            .build(),
-                    )
-                    .with_children(&[{
-                        camera = create_camera(
-                            resource_manager.clone(),
-                            Vector3::new(0.0, 0.0, -3.0),
-                            &mut scene.graph,
-                        )
-                        .await;
-                        camera
-                    }])
-                    .build(&mut scene.graph);
-                camera_hinge
-            }])
-            .build(&mut scene.graph);
+// There is no voice sample in example data, so a footstep that is pitched down (see
+// `Player::update`) is used as a grunt.
+const JUMP_SOUND_PATH: &str = "examples/data/sounds/FootStep_shoe_stone_step2.wav";
 
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.4, "Loading model...");
+const FOOTSTEP_SOUND_PATHS: [&str; 4] = [
+    "examples/data/sounds/FootStep_shoe_stone_step1.wav",
+    "examples/data/sounds/FootStep_shoe_stone_step2.wav",
+    "examples/data/sounds/FootStep_shoe_stone_step3.wav",
+    "examples/data/sounds/FootStep_shoe_stone_step4.wav",
+];
+
+impl Player {
+    pub async fn new(scene: &mut Scene, resource_manager: ResourceManager) -> Self {
+        // Camera is not attached to the character, it will be moved by the spring arm.
+        let camera = create_camera(
+            resource_manager.clone(),
+            Vector3::new(0.0, 0.0, -3.0),
+            &mut scene.graph,
+        )
+        .await;
 
         // Load model resource. Is does *not* adds anything to our scene - it just loads a
         // resource then can be used later on to instantiate models from it on scene. Why
@@ -470,19 +719,18 @@ impl Player {
         // much more efficient is to load it one and then make copies of it. In case of
         // models it is very efficient because single vertex and index buffer can be used
         // for all models instances, so memory footprint on GPU will be lower.
+        //
+        // Our model is too big, so we fix it by scale at import stage. Import options are stored
+        // in the resource, so every instance will have correct scale.
         let model_resource = resource_manager
-            .request_model(
+            .request_model_with_options(
                 "examples/data/mutant/mutant.FBX",
                 MaterialSearchOptions::RecursiveUp,
+                Some(ModelImportOptions::default().with_scale(0.0125)),
             )
             .await
             .unwrap();
 
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.60, "Instantiating model...");
-
         // Instantiate model on scene - but only geometry, without any animations.
         // Instantiation is a process of embedding model resource data in desired scene.
         let model_handle = model_resource.instantiate_geometry(scene);
@@ -492,9 +740,7 @@ impl Player {
         // Now we have whole sub-graph instantiated, we can start modifying model instance.
         scene.graph[model_handle]
             .local_transform_mut()
-            .set_position(Vector3::new(0.0, -body_height, 0.0))
-            // Our model is too big, fix it by scale.
-            .set_scale(Vector3::new(0.0125, 0.0125, 0.0125));
+            .set_position(Vector3::new(0.0, -body_height, 0.0));
 
         let pivot = BaseBuilder::new()
             .with_children(&[model_handle])
@@ -513,28 +759,51 @@ impl Player {
 
         scene.physics_binder.bind(pivot, body);
 
-        context
-            .lock()
-            .unwrap()
-            .report_progress(0.80, "Creating machine...");
-
         let locomotion_machine =
-            LocomotionMachine::new(scene, model_handle, resource_manager).await;
+            LocomotionMachine::new(scene, model_handle, resource_manager.clone()).await;
+
+        // Spring arm will keep camera behind the character at the head level and will pull it
+        // closer to the character if there is a wall between them.
+        let mut spring_arm = SpringArm::new(pivot, camera);
+        spring_arm.pivot_offset = Vector3::new(0.0, 1.0, 0.0);
+        spring_arm.offset = Vector3::new(0.0, 0.0, -3.0);
+        spring_arm.collision = Some(SpringArmCollision {
+            radius: 0.2,
+            exclude_body: Some(body),
+        });
+
+        let fov_animation = FovAnimation::new(camera, 75.0f32.to_radians());
+
+        let jump_sound = resource_manager
+            .request_sound_buffer(JUMP_SOUND_PATH, false)
+            .await
+            .ok();
+
+        // Slightly randomized pitch and gain make steps sound less robotic.
+        let mut footsteps = SoundVariation::new()
+            .with_pitch_range(0.9..1.1)
+            .with_gain_range(0.6..0.9);
+        for path in FOOTSTEP_SOUND_PATHS.iter() {
+            if let Ok(buffer) = resource_manager.request_sound_buffer(path, false).await {
+                footsteps.add_sound(buffer, 1.0);
+            }
+        }
 
         Self {
             body,
             pivot,
             model: model_handle,
-            camera_pivot,
             controller: Default::default(),
             locomotion_machine,
-            camera_hinge,
             camera,
-            model_yaw: SmoothAngle {
-                angle: 0.0,
-                target: 0.0,
-                speed: 10.0,
-            },
+            spring_arm,
+            camera_shake: CameraShake::new(camera),
+            fov_animation,
+            model_yaw: SmoothQuat::new(UnitQuaternion::identity(), 0.05),
+            // Slightly smoothed mouse look hides jerky mouse movements.
+            camera_rotation: SmoothQuat::new(UnitQuaternion::identity(), 0.02),
+            jump_sound,
+            footsteps,
         }
     }
 
@@ -551,8 +820,6 @@ impl Player {
             .try_normalize(f32::EPSILON)
             .unwrap_or_else(Vector3::x);
 
-        let position = **pivot.local_transform().position();
-
         let mut velocity = Vector3::default();
 
         if self.controller.walk_right {
@@ -568,7 +835,7 @@ impl Player {
             velocity -= look_vector;
         }
 
-        let speed = 2.0 * dt;
+        let speed = if self.controller.sprint { 4.0 } else { 2.0 } * dt;
         let velocity = velocity
             .try_normalize(f32::EPSILON)
             .map(|v| v.scale(speed))
@@ -585,9 +852,20 @@ impl Player {
         {
             if event.signal_id == LocomotionMachine::JUMP_SIGNAL {
                 new_y_vel = Some(6.0 * dt);
+                // Shake camera a bit when character pushes off the ground.
+                self.camera_shake.add_trauma(0.4);
             }
         }
 
+        let mut footstep = false;
+        while let Some(event) = scene
+            .animations
+            .get_mut(self.locomotion_machine.walk_animation)
+            .pop_event()
+        {
+            footstep |= event.signal_id == LocomotionMachine::FOOTSTEP_SIGNAL;
+        }
+
         let quat_yaw = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.controller.yaw);
 
         body.wake_up(true);
@@ -634,28 +912,66 @@ impl Player {
                 0.0
             };
 
-            self.model_yaw.set_target(angle.to_radians()).update(dt);
+            self.model_yaw
+                .set_target(UnitQuaternion::from_axis_angle(
+                    &Vector3::y_axis(),
+                    angle.to_radians(),
+                ))
+                .update(dt);
 
-            scene.graph[self.model].local_transform_mut().set_rotation(
-                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.model_yaw.angle),
-            );
+            scene.graph[self.model]
+                .local_transform_mut()
+                .set_rotation(self.model_yaw.value());
         }
 
-        let camera_pivot_transform = scene.graph[self.camera_pivot].local_transform_mut();
+        if new_y_vel.is_some() {
+            if let Some(jump_sound) = self.jump_sound.clone() {
+                // Fire and forget - the scene will remove the source when the sound ends.
+                let _ = scene.play_sound(
+                    jump_sound,
+                    PlaySoundParams {
+                        node: self.pivot,
+                        pitch: 0.7,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
 
-        camera_pivot_transform
-            .set_rotation(quat_yaw)
-            .set_position(position + velocity);
+        // Walk animation is playing all the time, even when the character is standing still, so
+        // its signals must be ignored while idle.
+        if footstep && is_moving && new_y_vel.is_none() {
+            let _ = scene.play_variation(
+                &self.footsteps,
+                PlaySoundParams {
+                    node: self.pivot,
+                    ..Default::default()
+                },
+            );
+        }
 
-        // Rotate camera hinge - this will make camera move up and down while look at character
-        // (well not exactly on character - on characters head)
-        scene.graph[self.camera_hinge]
-            .local_transform_mut()
-            .set_rotation(UnitQuaternion::from_axis_angle(
-                &Vector3::x_axis(),
-                self.controller.pitch,
-            ));
+        // Rotate spring arm - yaw will rotate camera around character, pitch will make camera
+        // move up and down while look at character (well not exactly on character - on characters
+        // head).
+        self.camera_rotation
+            .set_target(
+                quat_yaw
+                    * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.controller.pitch),
+            )
+            .update(dt);
+        self.spring_arm.rotation = self.camera_rotation.value();
+        self.spring_arm.update(scene, dt);
+        self.camera_shake.update(scene, dt);
+
+        // Widen field of view while sprinting to make feeling of speed.
+        if self.controller.sprint && is_moving {
+            self.fov_animation.animate_to(85.0f32.to_radians(), 0.3);
+        } else {
+            self.fov_animation.reset(0.5);
+        }
+        self.fov_animation.update(scene, dt);
 
+        let body = scene.physics.bodies.get_mut(&self.body).unwrap();
         let collider = body.colliders()[0];
         let mut has_ground_contact = false;
 
@@ -676,15 +992,16 @@ impl Player {
                 .rewind();
         }
 
+        if std::mem::take(&mut self.controller.flinch) {
+            self.locomotion_machine.flinch(scene);
+        }
+
         // Make sure to apply animation machine pose to model explicitly.
         self.locomotion_machine.apply(
             scene,
+            self.body,
             dt,
             LocomotionMachineInput {
-                is_walking: self.controller.walk_backward
-                    || self.controller.walk_forward
-                    || self.controller.walk_right
-                    || self.controller.walk_left,
                 is_jumping: has_ground_contact && self.controller.jump,
             },
         );
@@ -720,75 +1037,56 @@ impl Player {
                     self.controller.walk_right = key.state == ElementState::Pressed
                 }
                 VirtualKeyCode::Space => self.controller.jump = key.state == ElementState::Pressed,
+                VirtualKeyCode::LShift => {
+                    self.controller.sprint = key.state == ElementState::Pressed
+                }
+                VirtualKeyCode::F => self.controller.flinch |= key.state == ElementState::Pressed,
                 _ => (),
             }
         }
     }
 }
 
-pub fn create_scene_async(resource_manager: ResourceManager) -> Arc<Mutex<SceneLoadContext>> {
-    // Create load context - it will be shared with caller and loader threads.
-    let context = Arc::new(Mutex::new(SceneLoadContext {
-        scene_data: None,
-        message: "Starting..".to_string(),
-        progress: 0.0,
-    }));
-    let result = context.clone();
-
-    // Spawn separate thread which will create scene by loading various assets.
-    std::thread::spawn(move || {
-        rg3d::core::futures::executor::block_on(async move {
-            let mut scene = Scene::new();
-
-            // Set ambient light.
-            scene.ambient_lighting_color = Color::opaque(80, 80, 80);
-
-            // Create reverb effect for more natural sound - our player walks in some sort of cathedral,
-            // so there will be pretty decent echo.
-            let mut base_effect = BaseEffect::default();
-            // Make sure it won't be too loud - rg3d-sound doesn't care about energy conservation law, it
-            // just makes requested calculation.
-            base_effect.set_gain(0.7);
-            let mut reverb = rg3d::sound::effects::reverb::Reverb::new(base_effect);
-            // Set reverb time to ~3 seconds - the more time the deeper the echo.
-            reverb.set_decay_time(Duration::from_secs_f32(3.0));
-            let reverb_effect = scene
-                .sound_context
-                .state()
-                .add_effect(rg3d::sound::effects::Effect::Reverb(reverb));
-
-            context
-                .lock()
-                .unwrap()
-                .report_progress(0.25, "Loading map...");
-
-            // Load simple map.
-            resource_manager
-                .request_model(
-                    "examples/data/sponza/Sponza.rgs",
-                    MaterialSearchOptions::RecursiveUp,
-                )
-                .await
-                .unwrap()
-                .instantiate_geometry(&mut scene);
-
-            scene.graph.update_hierarchical_data();
-
-            // Finally create player.
-            let player = Player::new(&mut scene, resource_manager, context.clone()).await;
+pub async fn create_scene(resource_manager: ResourceManager) -> SceneLoadResult {
+    let mut scene = Scene::new();
+
+    // Set ambient light.
+    scene.ambient_lighting_color = Color::opaque(80, 80, 80);
+
+    // Create reverb effect for more natural sound - our player walks in some sort of cathedral,
+    // so there will be pretty decent echo.
+    let mut base_effect = BaseEffect::default();
+    // Make sure it won't be too loud - rg3d-sound doesn't care about energy conservation law, it
+    // just makes requested calculation.
+    base_effect.set_gain(0.7);
+    let mut reverb = rg3d::sound::effects::reverb::Reverb::new(base_effect);
+    // Set reverb time to ~3 seconds - the more time the deeper the echo.
+    reverb.set_decay_time(Duration::from_secs_f32(3.0));
+    let reverb_effect = scene
+        .sound_context
+        .state()
+        .add_effect(rg3d::sound::effects::Effect::Reverb(reverb));
+
+    // Load simple map.
+    resource_manager
+        .request_model(
+            "examples/data/sponza/Sponza.rgs",
+            MaterialSearchOptions::RecursiveUp,
+        )
+        .await
+        .unwrap()
+        .instantiate_geometry(&mut scene);
 
-            context.lock().unwrap().report_progress(1.0, "Done");
+    scene.graph.update_hierarchical_data();
 
-            context.lock().unwrap().scene_data = Some(SceneLoadResult {
-                scene,
-                player,
-                reverb_effect,
-            });
-        })
-    });
+    // Finally create player.
+    let player = Player::new(&mut scene, resource_manager).await;
 
-    // Immediately return shared context.
-    result
+    SceneLoadResult {
+        scene,
+        player,
+        reverb_effect,
+    }
 }
 
 pub struct InputController {
@@ -797,6 +1095,8 @@ pub struct InputController {
     walk_left: bool,
     walk_right: bool,
     jump: bool,
+    sprint: bool,
+    flinch: bool,
     yaw: f32,
     pitch: f32,
 }
@@ -809,6 +1109,8 @@ impl Default for InputController {
             walk_left: false,
             walk_right: false,
             jump: false,
+            sprint: false,
+            flinch: false,
             yaw: 0.0,
             pitch: 0.0,
         }
@@ -3,6 +3,8 @@
 //! Difficulty: Easy.
 //!
 //! This example shows how to create simple scene with animated model.
+//!
+//! Run it with `--features enable_profiler` to see per-frame profiling report.
 
 pub mod shared;
 
@@ -14,6 +16,7 @@ use rg3d::{
         color::Color,
         parking_lot::Mutex,
         pool::Handle,
+        profiler,
         sstorage::ImmutableString,
     },
     engine::{
@@ -218,12 +221,26 @@ impl GameState for Game {
                 self.model_angle,
             ));
 
+        // Show the most expensive scopes of the last frame.
+        let mut profiling_report = String::new();
+        if let Some(report) = profiler::frame::last_frame_report() {
+            for scope in report.collapsed().iter().take(8) {
+                profiling_report += &format!(
+                    "{}: {:.3} ms ({} calls)\n",
+                    scope.name,
+                    scope.self_time * 1000.0,
+                    scope.call_count
+                );
+            }
+        }
+
         engine.user_interface.send_message(TextMessage::text(
             self.debug_text,
             MessageDirection::ToWidget,
             format!(
-                "Example 01 - Simple Scene\nUse [A][D] keys to rotate model.\nFPS: {}",
-                engine.renderer.get_statistics().frames_per_second
+                "Example 01 - Simple Scene\nUse [A][D] keys to rotate model.\nFPS: {}\n{}",
+                engine.renderer().get_statistics().frames_per_second,
+                profiling_report
             ),
         ));
     }
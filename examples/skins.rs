@@ -0,0 +1,159 @@
+//! Example - Skins.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to swap textures on an instantiated model at runtime, which could be used
+//! for character skins or team colors. There are two instances of the same model, press [Space] to
+//! switch the skin of the left one - the right one keeps its original textures.
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, resource_manager::MaterialSearchOptions, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    resource::texture::Texture,
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::collections::HashMap;
+
+const ORIGINAL_SKIN: &str = "Mutant_diffuse";
+const METAL_SKIN: &str = "MetalMesh_Base_Color";
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    model: Handle<Node>,
+    original_skin: Texture,
+    metal_skin: Texture,
+    is_metal: bool,
+    replaced: usize,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let resource_manager = engine.resource_manager.clone();
+
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(200, 200, 200);
+
+        block_on(create_camera(
+            resource_manager.clone(),
+            Vector3::new(0.0, 6.0, -12.0),
+            &mut scene.graph,
+        ));
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 12.0, -4.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(30.0)
+        .build(&mut scene.graph);
+
+        let model_resource = block_on(resource_manager.request_model(
+            "examples/data/mutant/mutant.FBX",
+            MaterialSearchOptions::RecursiveUp,
+        ))
+        .unwrap();
+
+        // Both instances share materials with the resource until a texture is replaced.
+        let instances = [-3.0f32, 3.0]
+            .iter()
+            .map(|&x| {
+                let model = model_resource.instantiate_geometry(&mut scene);
+                scene.graph[model]
+                    .local_transform_mut()
+                    .set_position(Vector3::new(x, 0.0, 0.0))
+                    .set_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::y_axis(),
+                        180.0f32.to_radians(),
+                    ))
+                    .set_scale(Vector3::new(0.05, 0.05, 0.05));
+                model
+            })
+            .collect::<Vec<_>>();
+        // Camera looks along +Z, so the first instance is on the left.
+        let model = instances[0];
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            model,
+            original_skin: resource_manager
+                .request_texture("examples/data/mutant/Mutant_diffuse.png", None),
+            metal_skin: resource_manager
+                .request_texture("examples/data/MetalMesh_Base_Color.png", None),
+            is_metal: false,
+            replaced: 0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Skins\nPress [Space] to switch skin of the left model.\n\
+                Current skin: {}\nTextures replaced by last switch: {}\nFPS: {}",
+                if self.is_metal { "Metal" } else { "Original" },
+                self.replaced,
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed
+                && input.virtual_keycode == Some(VirtualKeyCode::Space)
+            {
+                // Textures are matched by file stem, so there is no need to know exact paths that
+                // were found when the model was loaded.
+                let mut skins = HashMap::new();
+                if self.is_metal {
+                    skins.insert(METAL_SKIN.to_owned(), self.original_skin.clone());
+                } else {
+                    skins.insert(ORIGINAL_SKIN.to_owned(), self.metal_skin.clone());
+                }
+
+                self.replaced = engine.scenes[self.scene]
+                    .graph
+                    .remap_textures_by_stem(self.model, &skins);
+                self.is_metal = !self.is_metal;
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Skins")
+        .run();
+}
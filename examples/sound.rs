@@ -8,7 +8,7 @@
 
 pub mod shared;
 
-use crate::shared::{create_ui, fix_shadows_distance, Game, GameScene};
+use crate::shared::{create_ui, fix_shadows_distance, progress_text, Game, GameScene};
 use rg3d::{
     animation::AnimationSignal,
     core::algebra::Vector2,
@@ -24,10 +24,7 @@ use rg3d::{
         effects::EffectInput,
         source::{generic::GenericSourceBuilder, spatial::SpatialSourceBuilder, Status},
     },
-    utils::{
-        log::{Log, MessageKind},
-        translate_event,
-    },
+    utils::{log::log_error, translate_event},
 };
 
 const FOOTSTEP_SIGNAL: u64 = 1;
@@ -84,11 +81,24 @@ fn main() {
                     // Put your game logic here.
                     // ************************
 
-                    // Check each frame if our scene is created - here we just trying to lock context
-                    // without blocking, it is important for main thread to be functional while other
-                    // thread still loading data.
-                    if let Ok(mut load_context) = game.load_context.as_ref().unwrap().try_lock() {
-                        if let Some(mut load_result) = load_context.scene_data.take() {
+                    // Check each frame if every resource of the scene is loaded and create the scene.
+                    if let Some(scene_loader) = game.scene_loader.as_ref() {
+                        // Report progress in UI.
+                        let progress = scene_loader.progress();
+                        game.engine
+                            .user_interface
+                            .send_message(ProgressBarMessage::progress(
+                                interface.progress_bar,
+                                MessageDirection::ToWidget,
+                                progress.fraction(),
+                            ));
+                        game.engine.user_interface.send_message(TextMessage::text(
+                            interface.progress_text,
+                            MessageDirection::ToWidget,
+                            progress_text(&progress),
+                        ));
+
+                        if let Some(mut load_result) = scene_loader.try_create_scene() {
                             // Once scene is fully loaded, add some signals to walking animation.
                             load_result.scene
                                 .animations
@@ -122,25 +132,10 @@ fn main() {
                                     MessageDirection::ToWidget,
                                     false,
                                 ));
-                        }
 
-                        // Report progress in UI.
-                        game.engine
-                            .user_interface
-                            .send_message(ProgressBarMessage::progress(
-                                interface.progress_bar,
-                                MessageDirection::ToWidget,
-                                load_context.progress,
-                            ));
-                        game.engine.user_interface.send_message(TextMessage::text(
-                            interface.progress_text,
-                            MessageDirection::ToWidget,
-                            format!(
-                                "Loading scene: {}%\n{}",
-                                load_context.progress * 100.0,
-                                load_context.message
-                            ),
-                        ));
+                            // Loader is not needed anymore.
+                            game.scene_loader = None;
+                        }
                     }
 
                     // Update scene only if it is loaded.
@@ -194,7 +189,7 @@ fn main() {
                         listener.set_orientation_lh(camera.look_vector(), camera.up_vector());
                     }
 
-                    let fps = game.engine.renderer.get_statistics().frames_per_second;
+                    let fps = game.engine.renderer().get_statistics().frames_per_second;
                     let debug_text = format!(
                         "Example 07 - Sound\n[W][S][A][D] - walk, [SPACE] - jump.\nFPS: {}\nUse [1][2][3][4] to select graphics quality.",
                         fps
@@ -237,7 +232,7 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = game.engine.set_frame_size(size.into()) {
-                            Log::writeln(MessageKind::Error, format!("Unable to set frame size: {:?}", e));
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
 
                         // Root UI node should be resized too, otherwise progress bar will stay
@@ -271,7 +266,7 @@ fn main() {
 
                             if let Some(settings) = settings {
                                 game.engine
-                                    .renderer
+                                    .renderer_mut()
                                     .set_quality_settings(&fix_shadows_distance(settings))
                                     .unwrap();
                             }
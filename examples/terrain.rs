@@ -224,7 +224,7 @@ impl GameState for Game {
             MessageDirection::ToWidget,
             format!(
                 "Example - Terrain\nUse [A][D] keys to rotate camera.\nFPS: {}",
-                engine.renderer.get_statistics().frames_per_second
+                engine.renderer().get_statistics().frames_per_second
             ),
         ));
     }
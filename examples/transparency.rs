@@ -0,0 +1,171 @@
+//! Example - Transparency.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows several overlapping glass panes at different depths and a camera that rotates
+//! around them. Transparent surfaces are sorted back-to-front each frame, so blending order must stay
+//! correct from any point of view. One of the panes fades in and out by changing opacity of its mesh.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{Material, PropertyValue},
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    pivot: Handle<Node>,
+    fading_pane: Handle<Node>,
+    time: f32,
+}
+
+fn create_pane(scene: &mut Scene, z: f32, color: Color) -> Handle<Node> {
+    let mut material = Material::standard();
+    material
+        .set_property(
+            &ImmutableString::new("diffuseColor"),
+            PropertyValue::Color(color),
+        )
+        .unwrap();
+
+    // Thin box instead of a quad, so the pane is visible from both sides.
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(Vector3::new(z * 0.3, 1.0, z))
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+        SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+            2.0, 2.0, 0.02,
+        ))),
+    )))
+    .with_material(Arc::new(Mutex::new(material)))
+    .with_transparent(true)
+    .build()])
+    .with_cast_shadows(false)
+    .build(&mut scene.graph)
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(120, 120, 120);
+
+        let camera = block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 1.5, -6.0),
+            &mut scene.graph,
+        ));
+
+        // Camera is attached to a pivot in the center of the scene, rotation of the pivot makes the
+        // camera to orbit around the panes.
+        let pivot = BaseBuilder::new()
+            .with_children(&[camera])
+            .build(&mut scene.graph);
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 4.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(10.0)
+        .build(&mut scene.graph);
+
+        // Opaque floor to see that panes are blended with the rest of the scene.
+        MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                    10.0, 0.1, 10.0,
+                ))),
+            )))
+            .build()])
+            .build(&mut scene.graph);
+
+        create_pane(&mut scene, -1.5, Color::from_rgba(255, 0, 0, 120));
+        create_pane(&mut scene, -0.5, Color::from_rgba(0, 255, 0, 120));
+        let fading_pane = create_pane(&mut scene, 0.5, Color::from_rgba(0, 0, 255, 120));
+        create_pane(&mut scene, 1.5, Color::from_rgba(255, 255, 0, 120));
+
+        let scene = engine.scenes.add(scene);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene,
+            pivot,
+            fading_pane,
+            time: 0.0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        self.time += dt;
+
+        let graph = &mut engine.scenes[self.scene].graph;
+
+        graph[self.pivot]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                self.time * 0.5,
+            ));
+
+        graph[self.fading_pane]
+            .as_mesh_mut()
+            .set_opacity(0.5 + 0.5 * self.time.sin());
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Transparency\nFPS: {}",
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Transparency")
+        .run();
+}
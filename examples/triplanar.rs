@@ -0,0 +1,210 @@
+//! Example - Triplanar mapping.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows triplanar mapping of steep geometry. There are two rocky "cliffs" with the
+//! same material, texture coordinates of the left one are used as is, so the texture is stretched
+//! along its steep sides, while textures of the right one are projected along world axes. Press [T]
+//! to toggle triplanar mapping of the right cliff, [Up]/[Down] to change blend sharpness and
+//! [Left]/[Right] to change tiling scale.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, resource_manager::ResourceManager, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData, TriplanarMapping},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    cliff: Handle<Node>,
+    mapping: TriplanarMapping,
+    enabled: bool,
+}
+
+fn make_rock_material(resource_manager: &ResourceManager) -> Arc<Mutex<Material>> {
+    let mut material = Material::standard();
+    for (name, path, fallback) in [
+        (
+            "diffuseTexture",
+            "examples/data/Rock_DiffuseColor.jpg",
+            SamplerFallback::White,
+        ),
+        (
+            "normalTexture",
+            "examples/data/Rock_Normal.jpg",
+            SamplerFallback::Normal,
+        ),
+    ] {
+        material
+            .set_property(
+                &ImmutableString::new(name),
+                PropertyValue::Sampler {
+                    value: Some(resource_manager.request_texture(path, None)),
+                    fallback,
+                },
+            )
+            .unwrap();
+    }
+    Arc::new(Mutex::new(material))
+}
+
+fn make_cliff(
+    scene: &mut Scene,
+    material: Arc<Mutex<Material>>,
+    position: Vector3<f32>,
+) -> Handle<Node> {
+    // Tall cone has very stretched texture coordinates on its sides.
+    let mut data = SurfaceData::make_cone(
+        16,
+        2.0,
+        6.0,
+        &Matrix4::new_nonuniform_scaling(&Vector3::new(1.0, 1.0, 0.7)),
+    );
+    data.calculate_tangents().unwrap();
+
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(data)))
+        .with_material(material)
+        .build()])
+    .build(&mut scene.graph)
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let resource_manager = engine.resource_manager.clone();
+
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(100, 100, 100);
+
+        block_on(create_camera(
+            resource_manager.clone(),
+            Vector3::new(0.0, 3.0, -9.0),
+            &mut scene.graph,
+        ));
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 5.0, -4.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(20.0)
+        .build(&mut scene.graph);
+
+        let material = make_rock_material(&resource_manager);
+        make_cliff(&mut scene, material.clone(), Vector3::new(3.0, 0.0, 0.0));
+        let cliff = make_cliff(&mut scene, material, Vector3::new(-3.0, 0.0, 0.0));
+
+        let mapping = TriplanarMapping {
+            sharpness: 4.0,
+            scale: 0.5,
+        };
+        scene.graph[cliff].as_mesh_mut().surfaces_mut()[0].set_triplanar_mapping(Some(mapping));
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            cliff,
+            mapping,
+            enabled: true,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+        let mapping = if self.enabled {
+            Some(self.mapping)
+        } else {
+            None
+        };
+        for surface in scene.graph[self.cliff].as_mesh_mut().surfaces_mut() {
+            surface.set_triplanar_mapping(mapping);
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Triplanar mapping\n\
+                [T] - toggle triplanar mapping (now: {})\n\
+                [Up]/[Down] - sharpness: {:.0}\n\
+                [Left]/[Right] - scale: {:.2}\n{}",
+                if self.enabled { "on" } else { "off" },
+                self.mapping.sharpness,
+                self.mapping.scale,
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state == ElementState::Pressed {
+                match input.virtual_keycode {
+                    Some(VirtualKeyCode::T) => self.enabled = !self.enabled,
+                    Some(VirtualKeyCode::Up) => {
+                        self.mapping.sharpness = (self.mapping.sharpness + 1.0).min(64.0)
+                    }
+                    Some(VirtualKeyCode::Down) => {
+                        self.mapping.sharpness = (self.mapping.sharpness - 1.0).max(1.0)
+                    }
+                    Some(VirtualKeyCode::Right) => self.mapping.scale += 0.05,
+                    Some(VirtualKeyCode::Left) => {
+                        self.mapping.scale = (self.mapping.scale - 0.05).max(0.05)
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Triplanar mapping")
+        .run();
+}
@@ -0,0 +1,278 @@
+//! Example - Turret.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to use transform constraints. The turret platform is moved by an
+//! animation, its head tracks the player with a look-at constraint with limited turning speed,
+//! and its "eye" aims at the player within a narrow cone. Constraints are applied after the
+//! animation, so the head keeps tracking while the platform moves.
+//! Use [W][S][A][D] to move the player, press [C] to enable/disable constraints.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    animation::{Animation, KeyFrame, Track},
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::Color,
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    scene::{
+        base::BaseBuilder,
+        constraint::{AimConstraint, Constraint, ConstraintTarget, LookAtConstraint},
+        graph::Graph,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+const PLAYER_SPEED: f32 = 3.0;
+
+fn make_mesh(surface: SurfaceData, base: BaseBuilder, graph: &mut Graph) -> Handle<Node> {
+    MeshBuilder::new(base)
+        .with_surfaces(vec![
+            SurfaceBuilder::new(Arc::new(Mutex::new(surface))).build()
+        ])
+        .build(graph)
+}
+
+#[derive(Default)]
+struct InputController {
+    move_forward: bool,
+    move_backward: bool,
+    move_left: bool,
+    move_right: bool,
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    player: Handle<Node>,
+    head: Handle<Node>,
+    eye: Handle<Node>,
+    animation: Handle<Animation>,
+    input_controller: InputController,
+    constraints_enabled: bool,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(120, 120, 120);
+
+        block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 8.0, -12.0),
+            &mut scene.graph,
+        ));
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 8.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(30.0)
+        .build(&mut scene.graph);
+
+        let graph = &mut scene.graph;
+
+        make_mesh(
+            SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                20.0, 0.1, 20.0,
+            ))),
+            BaseBuilder::new(),
+            graph,
+        );
+
+        let player = make_mesh(
+            SurfaceData::make_sphere(16, 16, 0.5, &Matrix4::identity()),
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 0.5, 5.0))
+                    .build(),
+            ),
+            graph,
+        );
+
+        // Look vector of the eye is aimed at the player, but the eye cannot turn more than 20
+        // degrees relative to the head.
+        let eye = make_mesh(
+            SurfaceData::make_cone(
+                8,
+                0.1,
+                0.5,
+                &Matrix4::new_rotation(Vector3::x() * 90.0f32.to_radians()),
+            ),
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(0.0, 0.4, 0.0))
+                        .build(),
+                )
+                .with_constraint(Constraint::new(
+                    AimConstraint::new(ConstraintTarget::Node(player))
+                        .with_max_angle(20.0f32.to_radians()),
+                )),
+            graph,
+        );
+
+        let barrel = make_mesh(
+            SurfaceData::make_cube(
+                Matrix4::new_translation(&Vector3::new(0.0, 0.0, 0.75))
+                    * Matrix4::new_nonuniform_scaling(&Vector3::new(0.15, 0.15, 1.5)),
+            ),
+            BaseBuilder::new(),
+            graph,
+        );
+
+        // The head turns to the player no faster than 90 degrees per second.
+        let head = make_mesh(
+            SurfaceData::make_cube(Matrix4::new_scaling(0.8)),
+            BaseBuilder::new()
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(0.0, 1.0, 0.0))
+                        .build(),
+                )
+                .with_children(&[barrel, eye])
+                .with_constraint(Constraint::new(
+                    LookAtConstraint::new(ConstraintTarget::Node(player))
+                        .with_max_angular_speed(Some(90.0f32.to_radians())),
+                )),
+            graph,
+        );
+
+        let platform = make_mesh(
+            SurfaceData::make_cylinder(16, 1.0, 0.5, true, &Matrix4::identity()),
+            BaseBuilder::new().with_children(&[head]),
+            graph,
+        );
+
+        // The platform is moved back and forth and tilted by an animation.
+        let mut track = Track::new();
+        track.set_node(platform);
+        for (time, x, angle) in [(0.0, -3.0, -10.0f32), (2.0, 3.0, 10.0), (4.0, -3.0, -10.0)] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::new(x, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::from_axis_angle(&Vector3::z_axis(), angle.to_radians()),
+            ));
+        }
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        let animation = scene.animations.add(animation);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene: engine.scenes.add(scene),
+            player,
+            head,
+            eye,
+            animation,
+            input_controller: Default::default(),
+            constraints_enabled: true,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        let scene = &mut engine.scenes[self.scene];
+
+        // Animation is applied first, constraints of the head and the eye are applied on top of
+        // it during the scene update.
+        scene
+            .animations
+            .get(self.animation)
+            .get_pose()
+            .apply(&mut scene.graph);
+
+        let mut offset = Vector3::default();
+        if self.input_controller.move_forward {
+            offset.z += 1.0;
+        }
+        if self.input_controller.move_backward {
+            offset.z -= 1.0;
+        }
+        if self.input_controller.move_left {
+            offset.x += 1.0;
+        }
+        if self.input_controller.move_right {
+            offset.x -= 1.0;
+        }
+        if let Some(offset) = offset.try_normalize(f32::EPSILON) {
+            scene.graph[self.player]
+                .local_transform_mut()
+                .offset(offset.scale(PLAYER_SPEED * dt));
+        }
+
+        for node in [self.head, self.eye] {
+            for constraint in scene.graph[node].constraints_mut() {
+                constraint.enabled = self.constraints_enabled;
+            }
+        }
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Turret\n[W][S][A][D] - move player, [C] - toggle constraints\n\
+                Constraints: {}\nFPS: {}",
+                if self.constraints_enabled {
+                    "On"
+                } else {
+                    "Off"
+                },
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, _engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            let pressed = input.state == ElementState::Pressed;
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::W) => self.input_controller.move_forward = pressed,
+                Some(VirtualKeyCode::S) => self.input_controller.move_backward = pressed,
+                Some(VirtualKeyCode::A) => self.input_controller.move_left = pressed,
+                Some(VirtualKeyCode::D) => self.input_controller.move_right = pressed,
+                Some(VirtualKeyCode::C) if pressed => {
+                    self.constraints_enabled = !self.constraints_enabled
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Turret")
+        .run();
+}
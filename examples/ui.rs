@@ -25,6 +25,7 @@ use rg3d::{
     gui::{
         border::BorderBuilder,
         button::{ButtonBuilder, ButtonMessage},
+        color::{ColorFieldBuilder, ColorFieldMessage},
         decorator::DecoratorBuilder,
         dropdown_list::{DropdownListBuilder, DropdownListMessage},
         formatted_text::WrapMode,
@@ -33,22 +34,21 @@ use rg3d::{
         scroll_bar::{ScrollBarBuilder, ScrollBarMessage},
         stack_panel::StackPanelBuilder,
         text::{TextBuilder, TextMessage},
+        virtual_list_view::{ItemHeight, VirtualListViewBuilder},
         widget::WidgetBuilder,
         window::{WindowBuilder, WindowTitle},
         HorizontalAlignment, Orientation, Thickness, UiNode, VerticalAlignment,
     },
     monitor::VideoMode,
     scene::{node::Node, Scene},
-    utils::{
-        log::{Log, MessageKind},
-        translate_event,
-    },
+    utils::{log::log_error, translate_event},
     window::Fullscreen,
 };
 use std::time::Instant;
 
 const DEFAULT_MODEL_ROTATION: f32 = 180.0;
 const DEFAULT_MODEL_SCALE: f32 = 0.05;
+const DEFAULT_AMBIENT_COLOR: Color = Color::opaque(200, 200, 200);
 
 struct Interface {
     debug_text: Handle<UiNode>,
@@ -57,6 +57,7 @@ struct Interface {
     reset: Handle<UiNode>,
     video_modes: Vec<VideoMode>,
     resolutions: Handle<UiNode>,
+    ambient_color: Handle<UiNode>,
 }
 
 // User interface in the engine build up on graph data structure, on tree to be
@@ -69,7 +70,8 @@ struct Interface {
 // complex layout system was borrowed from WPF framework. You can read more here:
 // https://docs.microsoft.com/en-us/dotnet/framework/wpf/advanced/layout
 fn create_ui(engine: &mut Engine) -> Interface {
-    let window_width = engine.renderer.get_frame_size().0 as f32;
+    // Widgets are positioned in logical units, so use logical size of the screen.
+    let window_width = engine.user_interface.screen_size().x;
 
     // Gather all suitable video modes, we'll use them to fill combo box of
     // available resolutions.
@@ -203,6 +205,7 @@ fn create_ui(engine: &mut Engine) -> Interface {
 
     // Create another window which will show some graphics options.
     let resolutions;
+    let ambient_color;
     WindowBuilder::new(
         WidgetBuilder::new()
             .with_desired_position(Vector2::new(window_width - 670.0, 0.0))
@@ -249,17 +252,72 @@ fn create_ui(engine: &mut Engine) -> Interface {
                             })
                             .build(ctx);
                     resolutions
+                })
+                .with_child(
+                    TextBuilder::new(WidgetBuilder::new().on_column(0).on_row(1))
+                        .with_text("Ambient Color")
+                        .build(ctx),
+                )
+                .with_child({
+                    // Color field shows a swatch which opens color picker in a popup when
+                    // clicked. It sends Preview message while the color is being changed and
+                    // Color message when new color is committed.
+                    ambient_color = ColorFieldBuilder::new(
+                        WidgetBuilder::new()
+                            .on_row(1)
+                            .on_column(1)
+                            .with_margin(Thickness::uniform(2.0)),
+                    )
+                    .with_color(DEFAULT_AMBIENT_COLOR)
+                    .build(ctx);
+                    ambient_color
                 }),
         )
         .add_column(Column::strict(120.0))
         .add_column(Column::stretch())
         .add_row(Row::strict(30.0))
+        .add_row(Row::strict(30.0))
         .build(ctx),
     )
     .with_title(WindowTitle::text("Graphics Options"))
     .can_close(false)
     .build(ctx);
 
+    // Virtual list view can show huge amount of items, because it creates widgets only for
+    // items that are visible. Widgets of items that went out of view are reused to show items
+    // that came into view, so we need a generator which creates a widget for an item and an
+    // updater which changes existing widget to show another item.
+    WindowBuilder::new(
+        WidgetBuilder::new()
+            .with_desired_position(Vector2::new(window_width - 300.0, 150.0))
+            .with_width(300.0)
+            .with_height(400.0),
+    )
+    .with_content(
+        VirtualListViewBuilder::new(WidgetBuilder::new(), |index, ctx| {
+            TextBuilder::new(
+                WidgetBuilder::new()
+                    .with_margin(Thickness::left(4.0))
+                    .with_vertical_alignment(VerticalAlignment::Center),
+            )
+            .with_text(format!("Item {}", index))
+            .build(ctx)
+        })
+        .with_updater(|index, item, ui| {
+            ui.send_message(TextMessage::text(
+                item,
+                MessageDirection::ToWidget,
+                format!("Item {}", index),
+            ));
+        })
+        .with_item_count(50_000)
+        .with_item_height(ItemHeight::Fixed(24.0))
+        .build(ctx),
+    )
+    .with_title(WindowTitle::text("50000 Items"))
+    .can_close(false)
+    .build(ctx);
+
     Interface {
         debug_text,
         yaw,
@@ -267,6 +325,7 @@ fn create_ui(engine: &mut Engine) -> Interface {
         reset,
         resolutions,
         video_modes,
+        ambient_color,
     }
 }
 
@@ -280,7 +339,7 @@ async fn create_scene(resource_manager: ResourceManager) -> GameScene {
     let mut scene = Scene::new();
 
     // Set ambient light.
-    scene.ambient_lighting_color = Color::opaque(200, 200, 200);
+    scene.ambient_lighting_color = DEFAULT_AMBIENT_COLOR;
 
     // Camera is our eyes in the world - you won't see anything without it.
     create_camera(
@@ -405,7 +464,7 @@ fn main() {
                             model_angle.to_radians(),
                         ));
 
-                    let fps = engine.renderer.get_statistics().frames_per_second;
+                    let fps = engine.renderer().get_statistics().frames_per_second;
                     engine.user_interface.send_message(TextMessage::text(
                         interface.debug_text,
                         MessageDirection::ToWidget,
@@ -449,6 +508,19 @@ fn main() {
                                 DEFAULT_MODEL_ROTATION,
                             ));
                         }
+                    } else if let Some(msg) = ui_message.data::<ColorFieldMessage>() {
+                        // Ambient color is edited live - preview is applied immediately, so
+                        // user can see the result while dragging over the picker.
+                        if ui_message.destination() == interface.ambient_color
+                            && ui_message.direction() == MessageDirection::FromWidget
+                        {
+                            match *msg {
+                                ColorFieldMessage::Color(color)
+                                | ColorFieldMessage::Preview(color) => {
+                                    engine.scenes[scene_handle].ambient_lighting_color = color;
+                                }
+                            }
+                        }
                     } else if let Some(DropdownListMessage::SelectionChanged(Some(idx))) =
                         ui_message.data::<DropdownListMessage>()
                     {
@@ -463,10 +535,7 @@ fn main() {
                             if let Err(e) = engine
                                 .set_frame_size((video_mode.size().width, video_mode.size().height))
                             {
-                                Log::writeln(
-                                    MessageKind::Error,
-                                    format!("Unable to set frame size: {:?}", e),
-                                );
+                                log_error!("Unable to set frame size: {:?}", e);
                             }
                         }
                     }
@@ -487,12 +556,14 @@ fn main() {
                         // renderer knows nothing about window size - it must be notified
                         // directly when window size has changed.
                         if let Err(e) = engine.set_frame_size(size.into()) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Unable to set frame size: {:?}", e),
-                            );
+                            log_error!("Unable to set frame size: {:?}", e);
                         }
                     }
+                    WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        // Window was moved to a monitor with different DPI, the interface is
+                        // defined in logical units, so it only needs to know new scale.
+                        engine.user_interface.set_scale(scale_factor as f32);
+                    }
                     WindowEvent::KeyboardInput { input, .. } => {
                         if let Some(key_code) = input.virtual_keycode {
                             if input.state == ElementState::Pressed
@@ -0,0 +1,178 @@
+//! Example - Vertex colors.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows procedural meshes without any textures, colored only by vertex colors and
+//! lit by deferred lights. Vertex colors are multiplied with diffuse color of the material, so with
+//! default material the colors are shown as is.
+
+pub mod shared;
+
+use crate::shared::create_camera;
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        color::{Color, Hsv},
+        futures::executor::block_on,
+        parking_lot::Mutex,
+        pool::Handle,
+    },
+    engine::{framework::prelude::*, Engine},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    scene::{
+        base::BaseBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    scene: Handle<Scene>,
+    pivot: Handle<Node>,
+    time: f32,
+}
+
+fn add_mesh(scene: &mut Scene, data: SurfaceData, position: Vector3<f32>) -> Handle<Node> {
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(data))).build()])
+    .build(&mut scene.graph)
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        scene.ambient_lighting_color = Color::opaque(60, 60, 60);
+
+        let camera = block_on(create_camera(
+            engine.resource_manager.clone(),
+            Vector3::new(0.0, 2.0, -6.0),
+            &mut scene.graph,
+        ));
+
+        let pivot = BaseBuilder::new()
+            .with_children(&[camera])
+            .build(&mut scene.graph);
+
+        for (position, color) in [
+            (Vector3::new(-3.0, 3.0, 0.0), Color::opaque(255, 220, 200)),
+            (Vector3::new(3.0, 3.0, 0.0), Color::opaque(200, 220, 255)),
+        ] {
+            PointLightBuilder::new(
+                BaseLightBuilder::new(
+                    BaseBuilder::new().with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(position)
+                            .build(),
+                    ),
+                )
+                .with_color(color),
+            )
+            .with_radius(10.0)
+            .build(&mut scene.graph);
+        }
+
+        // Cube with a gradient from red at the bottom to blue at the top.
+        let mut cube = SurfaceData::make_cube(Matrix4::identity());
+        cube.set_vertex_colors_with(|_, position| {
+            let t = position.y + 0.5;
+            Some(Color::RED.lerp(Color::BLUE, t))
+        })
+        .unwrap();
+        add_mesh(&mut scene, cube, Vector3::new(-2.0, 0.5, 0.0));
+
+        // Sphere with a rainbow around its vertical axis.
+        let mut sphere = SurfaceData::make_sphere(32, 32, 0.75, &Matrix4::identity());
+        sphere
+            .set_vertex_colors_with(|_, position| {
+                let angle = position.z.atan2(position.x).to_degrees() + 180.0;
+                Some(Color::from(Hsv::new(angle, 100.0, 100.0)))
+            })
+            .unwrap();
+        add_mesh(&mut scene, sphere, Vector3::new(0.0, 0.75, 0.0));
+
+        // Cylinder with explicit per-vertex colors, every vertex gets one of three colors.
+        let mut cylinder = SurfaceData::make_cylinder(24, 0.5, 1.5, true, &Matrix4::identity());
+        let palette = [Color::GREEN, Color::opaque(255, 255, 0), Color::WHITE];
+        let colors = (0..cylinder.vertex_buffer.vertex_count())
+            .map(|i| palette[i as usize % palette.len()])
+            .collect::<Vec<_>>();
+        cylinder.set_vertex_colors(&colors).unwrap();
+        add_mesh(&mut scene, cylinder, Vector3::new(2.0, 0.0, 0.0));
+
+        // Floor is colored too, vertex colors are interpolated across faces.
+        let mut floor = SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+            10.0, 0.1, 10.0,
+        )));
+        floor
+            .set_vertex_colors_with(|_, position| {
+                if position.x * position.z > 0.0 {
+                    Some(Color::opaque(90, 90, 90))
+                } else {
+                    Some(Color::opaque(180, 180, 180))
+                }
+            })
+            .unwrap();
+        add_mesh(&mut scene, floor, Vector3::new(0.0, -0.05, 0.0));
+
+        let scene = engine.scenes.add(scene);
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            scene,
+            pivot,
+            time: 0.0,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, dt: f32, _: &mut ControlFlow) {
+        self.time += dt;
+
+        engine.scenes[self.scene].graph[self.pivot]
+            .local_transform_mut()
+            .set_rotation(UnitQuaternion::from_axis_angle(
+                &Vector3::y_axis(),
+                self.time * 0.3,
+            ));
+
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Vertex Colors\nFPS: {}",
+                engine.renderer().get_statistics().frames_per_second
+            ),
+        ));
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Vertex Colors")
+        .run();
+}
@@ -0,0 +1,267 @@
+//! Example - Custom render pass.
+//!
+//! Difficulty: Medium.
+//!
+//! This example shows how to add a custom render pass to the renderer. The pass draws full screen
+//! vignette on top of the scene right before user interface is drawn. Press [V] to add or remove
+//! the pass, [Up] and [Down] arrows to change strength of the effect.
+
+use rg3d::{
+    core::{
+        algebra::{Matrix4, UnitQuaternion, Vector3},
+        parking_lot::Mutex,
+        pool::Handle,
+        sstorage::ImmutableString,
+    },
+    engine::{framework::prelude::*, Engine},
+    event::{ElementState, VirtualKeyCode, WindowEvent},
+    event_loop::ControlFlow,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        UiNode,
+    },
+    renderer::{
+        framework::{
+            error::FrameworkError,
+            framebuffer::DrawParameters,
+            gpu_program::{GpuProgram, UniformLocation},
+            state::{BlendFactor, BlendFunc, PipelineState},
+        },
+        make_viewport_matrix, RenderPassHandle, RenderPassStage, RenderPassStatistics,
+        SceneRenderPass, SceneRenderPassContext,
+    },
+    scene::{
+        base::BaseBuilder,
+        camera::CameraBuilder,
+        light::{point::PointLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+const VERTEX_SHADER: &str = r#"
+layout(location = 0) in vec3 vertexPosition;
+layout(location = 1) in vec2 vertexTexCoord;
+
+uniform mat4 worldViewProjection;
+
+out vec2 texCoord;
+
+void main()
+{
+    texCoord = vertexTexCoord;
+    gl_Position = worldViewProjection * vec4(vertexPosition, 1.0);
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+uniform float strength;
+
+out vec4 FragColor;
+
+in vec2 texCoord;
+
+void main()
+{
+    float distance = length(texCoord - vec2(0.5)) * 1.41421;
+    FragColor = vec4(0.0, 0.0, 0.0, smoothstep(0.4, 1.0, distance) * strength);
+}
+"#;
+
+struct VignetteShader {
+    program: GpuProgram,
+    wvp_matrix: UniformLocation,
+    strength: UniformLocation,
+}
+
+impl VignetteShader {
+    fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        let program =
+            GpuProgram::from_source(state, "VignetteShader", VERTEX_SHADER, FRAGMENT_SHADER)?;
+        Ok(Self {
+            wvp_matrix: program
+                .uniform_location(state, &ImmutableString::new("worldViewProjection"))?,
+            strength: program.uniform_location(state, &ImmutableString::new("strength"))?,
+            program,
+        })
+    }
+}
+
+struct VignettePass {
+    shader: VignetteShader,
+    strength: f32,
+}
+
+impl SceneRenderPass for VignettePass {
+    fn render(
+        &mut self,
+        ctx: SceneRenderPassContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        let shader = &self.shader;
+        let strength = self.strength;
+        let wvp_matrix = make_viewport_matrix(ctx.viewport);
+
+        let mut statistics = RenderPassStatistics::default();
+        statistics += ctx.framebuffer.draw(
+            ctx.quad,
+            ctx.pipeline_state,
+            ctx.viewport,
+            &shader.program,
+            &DrawParameters {
+                cull_face: None,
+                color_write: Default::default(),
+                depth_write: false,
+                stencil_test: None,
+                depth_test: false,
+                blend: Some(BlendFunc {
+                    sfactor: BlendFactor::SrcAlpha,
+                    dfactor: BlendFactor::OneMinusSrcAlpha,
+                }),
+                stencil_op: Default::default(),
+            },
+            |mut program_binding| {
+                program_binding
+                    .set_matrix4(&shader.wvp_matrix, &wvp_matrix)
+                    .set_f32(&shader.strength, strength);
+            },
+        );
+
+        Ok(statistics)
+    }
+}
+
+struct Game {
+    debug_text: Handle<UiNode>,
+    pass: Arc<std::sync::Mutex<VignettePass>>,
+    pass_handle: Option<RenderPassHandle>,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let mut scene = Scene::new();
+
+        CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 4.0, -8.0))
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(
+                        &Vector3::x_axis(),
+                        25.0f32.to_radians(),
+                    ))
+                    .build(),
+            ),
+        )
+        .build(&mut scene.graph);
+
+        PointLightBuilder::new(BaseLightBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 6.0, 0.0))
+                    .build(),
+            ),
+        ))
+        .with_radius(20.0)
+        .build(&mut scene.graph);
+
+        for z in -2..3 {
+            for x in -2..3 {
+                let position = Vector3::new(x as f32 * 2.0, 0.0, z as f32 * 2.0);
+                MeshBuilder::new(
+                    BaseBuilder::new().with_local_transform(
+                        TransformBuilder::new()
+                            .with_local_position(position)
+                            .build(),
+                    ),
+                )
+                .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                    SurfaceData::make_cube(Matrix4::identity()),
+                )))
+                .build()])
+                .build(&mut scene.graph);
+            }
+        }
+
+        engine.scenes.add(scene);
+
+        let renderer = engine.renderer_mut();
+        let pass = Arc::new(std::sync::Mutex::new(VignettePass {
+            shader: VignetteShader::new(renderer.pipeline_state()).unwrap(),
+            strength: 0.8,
+        }));
+        let pass_handle = renderer.add_render_pass(RenderPassStage::BeforeUi, pass.clone());
+
+        Self {
+            debug_text: TextBuilder::new(WidgetBuilder::new())
+                .build(&mut engine.user_interface.build_ctx()),
+            pass,
+            pass_handle: Some(pass_handle),
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        engine.user_interface.send_message(TextMessage::text(
+            self.debug_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - Custom render pass\n\
+                    Press [V] to toggle vignette, [Up]/[Down] to change its strength.\n\
+                    Vignette: {}, strength: {:.2}\n\
+                    {}",
+                if self.pass_handle.is_some() {
+                    "On"
+                } else {
+                    "Off"
+                },
+                self.pass.lock().unwrap().strength,
+                engine.renderer().get_statistics()
+            ),
+        ));
+    }
+
+    fn on_window_event(&mut self, engine: &mut Engine, event: WindowEvent) {
+        if let WindowEvent::KeyboardInput { input, .. } = event {
+            if input.state != ElementState::Pressed {
+                return;
+            }
+
+            match input.virtual_keycode {
+                Some(VirtualKeyCode::V) => {
+                    let renderer = engine.renderer_mut();
+                    if let Some(handle) = self.pass_handle.take() {
+                        renderer.remove_render_pass(handle);
+                    } else {
+                        self.pass_handle = Some(
+                            renderer.add_render_pass(RenderPassStage::BeforeUi, self.pass.clone()),
+                        );
+                    }
+                }
+                Some(VirtualKeyCode::Up) => {
+                    let mut pass = self.pass.lock().unwrap();
+                    pass.strength = (pass.strength + 0.1).min(1.0);
+                }
+                Some(VirtualKeyCode::Down) => {
+                    let mut pass = self.pass.lock().unwrap();
+                    pass.strength = (pass.strength - 0.1).max(0.0);
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - Custom render pass")
+        .run();
+}
@@ -294,7 +294,7 @@ pub fn main_js() {
 
     let mut engine = Engine::new(window_builder, &event_loop, true).unwrap();
     engine
-        .renderer
+        .renderer_mut()
         .set_backbuffer_clear_color(Color::opaque(150, 150, 255));
 
     // Configure resource manager.
@@ -373,7 +373,7 @@ pub fn main_js() {
                             ));
                     }
 
-                    let fps = engine.renderer.get_statistics().frames_per_second;
+                    let fps = engine.renderer().get_statistics().frames_per_second;
                     let text = format!(
                         "Example - WASM\nUse [A][D] keys to rotate model.\nFPS: {}\nAngle: {}",
                         fps, model_angle
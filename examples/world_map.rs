@@ -0,0 +1,185 @@
+//! Example - World map.
+//!
+//! Difficulty: Easy.
+//!
+//! This example shows how to make a world map with pan and zoom using PanZoomCanvas widget. Map
+//! tiles are scaled together with the view, while the icons of cities keep their size. Drag the map
+//! with left or middle mouse button, zoom with the mouse wheel or use the scroll bars. Click on a city
+//! to center the view on it.
+
+use rg3d::{
+    core::{algebra::Vector2, color::Color, pool::Handle},
+    engine::{framework::prelude::*, Engine},
+    event_loop::ControlFlow,
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        pan_zoom_canvas::{PanZoomCanvas, PanZoomCanvasBuilder, PanZoomCanvasMessage},
+        scroll_bar::ScrollBarBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::WidgetBuilder,
+        Orientation, UiNode,
+    },
+};
+
+const TILE_SIZE: f32 = 100.0;
+const MAP_SIZE: usize = 16;
+
+struct City {
+    button: Handle<UiNode>,
+    position: Vector2<f32>,
+}
+
+struct Game {
+    map: Handle<UiNode>,
+    cities: Vec<City>,
+    view_text: Handle<UiNode>,
+}
+
+impl GameState for Game {
+    fn init(engine: &mut Engine) -> Self
+    where
+        Self: Sized,
+    {
+        let ctx = &mut engine.user_interface.build_ctx();
+
+        // Map itself is made of colored tiles, their size is defined in "virtual" units.
+        let mut children = Vec::new();
+        for y in 0..MAP_SIZE {
+            for x in 0..MAP_SIZE {
+                let land = (x * 7 + y * 13) % 5 != 0;
+                let color = if land {
+                    Color::opaque(60, 120 + ((x + y) % 3) as u8 * 20, 50)
+                } else {
+                    Color::opaque(40, 70, 160)
+                };
+                children.push(
+                    BorderBuilder::new(
+                        WidgetBuilder::new()
+                            .with_min_size(Vector2::new(TILE_SIZE, TILE_SIZE))
+                            .with_desired_position(Vector2::new(
+                                x as f32 * TILE_SIZE,
+                                y as f32 * TILE_SIZE,
+                            ))
+                            .with_background(Brush::Solid(color)),
+                    )
+                    .build(ctx),
+                );
+            }
+        }
+
+        // Cities are scale-invariant, they're always of the same size on screen.
+        let cities = [
+            ("Northgate", Vector2::new(250.0, 180.0)),
+            ("Riverside", Vector2::new(830.0, 640.0)),
+            ("Stonehill", Vector2::new(1320.0, 310.0)),
+            ("Port Azure", Vector2::new(520.0, 1410.0)),
+        ]
+        .iter()
+        .map(|(name, position)| City {
+            button: ButtonBuilder::new(
+                WidgetBuilder::new()
+                    .with_width(90.0)
+                    .with_height(24.0)
+                    .with_desired_position(*position),
+            )
+            .with_text(name)
+            .build(ctx),
+            position: *position,
+        })
+        .collect::<Vec<_>>();
+
+        let city_buttons = cities.iter().map(|c| c.button).collect::<Vec<_>>();
+        children.extend_from_slice(&city_buttons);
+
+        let horizontal_scroll_bar = ScrollBarBuilder::new(WidgetBuilder::new().on_row(2))
+            .with_orientation(Orientation::Horizontal)
+            .with_step(TILE_SIZE)
+            .build(ctx);
+        let vertical_scroll_bar =
+            ScrollBarBuilder::new(WidgetBuilder::new().on_row(1).on_column(1))
+                .with_orientation(Orientation::Vertical)
+                .with_step(TILE_SIZE)
+                .build(ctx);
+
+        let map = PanZoomCanvasBuilder::new(WidgetBuilder::new().on_row(1).with_children(children))
+            .with_min_zoom(0.25)
+            .with_max_zoom(4.0)
+            .with_scale_invariant_children(city_buttons)
+            .with_horizontal_scroll_bar(horizontal_scroll_bar)
+            .with_vertical_scroll_bar(vertical_scroll_bar)
+            .build(ctx);
+
+        let view_text = TextBuilder::new(WidgetBuilder::new().on_row(0)).build(ctx);
+
+        GridBuilder::new(
+            WidgetBuilder::new()
+                .with_width(800.0)
+                .with_height(600.0)
+                .with_child(view_text)
+                .with_child(map)
+                .with_child(horizontal_scroll_bar)
+                .with_child(vertical_scroll_bar),
+        )
+        .add_row(Row::strict(50.0))
+        .add_row(Row::stretch())
+        .add_row(Row::strict(20.0))
+        .add_column(Column::stretch())
+        .add_column(Column::strict(20.0))
+        .build(ctx);
+
+        Self {
+            map,
+            cities,
+            view_text,
+        }
+    }
+
+    fn on_tick(&mut self, engine: &mut Engine, _dt: f32, _: &mut ControlFlow) {
+        let ui = &engine.user_interface;
+        let map = ui.node(self.map).cast::<PanZoomCanvas>().unwrap();
+        let view = map.view();
+        let cursor = map.screen_to_virtual(ui.cursor_position());
+
+        ui.send_message(TextMessage::text(
+            self.view_text,
+            MessageDirection::ToWidget,
+            format!(
+                "Example - World map\n\
+                Drag to pan, wheel to zoom, click a city to center on it.\n\
+                Zoom: {:.2}, cursor on map: {:.0} {:.0}",
+                view.scale, cursor.x, cursor.y
+            ),
+        ));
+    }
+
+    fn on_ui_message(&mut self, engine: &mut Engine, message: UiMessage) {
+        if let Some(ButtonMessage::Click) = message.data::<ButtonMessage>() {
+            if let Some(city) = self
+                .cities
+                .iter()
+                .find(|c| c.button == message.destination())
+            {
+                let ui = &engine.user_interface;
+                let map = ui.node(self.map).cast::<PanZoomCanvas>().unwrap();
+                let mut view = map.view();
+                view.offset = map.actual_size().scale(0.5) - city.position.scale(view.scale);
+                ui.send_message(PanZoomCanvasMessage::view(
+                    self.map,
+                    MessageDirection::ToWidget,
+                    view,
+                ));
+            }
+        }
+    }
+}
+
+fn main() {
+    Framework::<Game>::new()
+        .unwrap()
+        .title("Example - World map")
+        .run();
+}
@@ -0,0 +1,347 @@
+//! Coordinate system and units of the engine, and helpers to convert data that was made with other
+//! conventions.
+//!
+//! # Conventions
+//!
+//! - Coordinate system is right-handed.
+//! - +Y axis points up, see [`UP`].
+//! - +Z axis is "forward": it is the look vector of every scene node, cameras look along it, see
+//!   [`LOOK`].
+//! - +X axis is the side vector, see [`SIDE`]. For an observer that looks along +Z it points to the
+//!   left.
+//! - One unit of length is one meter. Physics uses meters, kilograms and seconds, default gravity
+//!   is 9.81 m/s² along -Y.
+//! - Angles are in radians.
+//!
+//! Assets from other sources could use different conventions (for example Blender uses Z axis as up
+//! and meters, while some other tools use left-handed coordinate systems and centimeters). Such assets
+//! must be converted, [`CoordinateSystem`] describes a source coordinate system and provides the
+//! conversion to the engine's one, [`LengthUnit`] describes a unit of length.
+//!
+//! # Conversion rules
+//!
+//! Z-up coordinate systems are rotated by -90 degrees around X axis, so +Z becomes +Y and +Y becomes
+//! -Z. Left-handed coordinate systems are mirrored along the forward (Z) axis of the engine after the
+//! rotation, so up axis remains the same.
+
+use crate::{
+    algebra::{Matrix3, Matrix4, Quaternion, UnitQuaternion, Vector3},
+    visitor::prelude::*,
+};
+use std::fmt::{Display, Formatter};
+
+/// "Up" direction of the engine.
+pub const UP: Vector3<f32> = Vector3::new(0.0, 1.0, 0.0);
+
+/// "Forward" (look) direction of the engine.
+pub const LOOK: Vector3<f32> = Vector3::new(0.0, 0.0, 1.0);
+
+/// "Side" direction of the engine, it points left for an observer that looks along [`LOOK`].
+pub const SIDE: Vector3<f32> = Vector3::new(1.0, 0.0, 0.0);
+
+/// Defines an axis that points up in a coordinate system.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Visit)]
+pub enum UpAxis {
+    /// Y axis points up. The engine uses this convention, so no conversion is needed.
+    Y,
+    /// Z axis points up, this is the convention of Blender and 3ds Max for example.
+    Z,
+}
+
+impl Default for UpAxis {
+    fn default() -> Self {
+        Self::Y
+    }
+}
+
+/// Defines handedness of a coordinate system.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Visit)]
+pub enum Handedness {
+    /// Right-handed coordinate system. The engine uses this convention, so no conversion is needed.
+    Right,
+    /// Left-handed coordinate system. Conversion mirrors data along forward axis of the engine.
+    Left,
+}
+
+impl Default for Handedness {
+    fn default() -> Self {
+        Self::Right
+    }
+}
+
+/// Describes a coordinate system of some data. Default value is the coordinate system of the
+/// engine.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, Visit)]
+pub struct CoordinateSystem {
+    /// An axis that points up.
+    pub up_axis: UpAxis,
+    /// Handedness of the coordinate system.
+    pub handedness: Handedness,
+}
+
+impl CoordinateSystem {
+    /// Coordinate system of the engine.
+    pub const ENGINE: Self = Self {
+        up_axis: UpAxis::Y,
+        handedness: Handedness::Right,
+    };
+
+    /// Creates new coordinate system description.
+    pub fn new(up_axis: UpAxis, handedness: Handedness) -> Self {
+        Self {
+            up_axis,
+            handedness,
+        }
+    }
+
+    /// Returns `true` if conversion to the engine's coordinate system mirrors data, in this case
+    /// winding order of triangles and handedness of tangent space must be flipped.
+    pub fn is_mirroring(&self) -> bool {
+        self.handedness != Handedness::Right
+    }
+
+    /// Decomposes conversion to the engine's coordinate system into a rotation and a scale (with
+    /// negative component if the conversion mirrors data), so it can be baked into local transform
+    /// of a scene node. Resulting transform is `rotation * scale`, which is equal to
+    /// [`Self::to_engine_matrix`].
+    pub fn to_engine_parts(&self) -> (UnitQuaternion<f32>, Vector3<f32>) {
+        let rotation = match self.up_axis {
+            UpAxis::Y => UnitQuaternion::default(),
+            UpAxis::Z => {
+                UnitQuaternion::from_axis_angle(&Vector3::x_axis(), -std::f32::consts::FRAC_PI_2)
+            }
+        };
+        // Mirror along an axis of the source coordinate system that becomes forward axis of
+        // the engine after the rotation.
+        let scale = match (self.handedness, self.up_axis) {
+            (Handedness::Right, _) => Vector3::new(1.0, 1.0, 1.0),
+            (Handedness::Left, UpAxis::Y) => Vector3::new(1.0, 1.0, -1.0),
+            (Handedness::Left, UpAxis::Z) => Vector3::new(1.0, -1.0, 1.0),
+        };
+        (rotation, scale)
+    }
+
+    /// Returns a matrix that transforms a vector from this coordinate system to the engine's one.
+    /// The matrix is orthogonal, so its inverse is equal to its transpose.
+    pub fn to_engine_matrix(&self) -> Matrix3<f32> {
+        let (rotation, scale) = self.to_engine_parts();
+        rotation.to_rotation_matrix().into_inner() * Matrix3::from_diagonal(&scale)
+    }
+
+    /// Returns a matrix that transforms a vector from this coordinate system to given one.
+    pub fn conversion_matrix(&self, to: &CoordinateSystem) -> Matrix3<f32> {
+        to.to_engine_matrix().transpose() * self.to_engine_matrix()
+    }
+}
+
+/// Converts a vector (position, direction, etc.) between coordinate systems.
+pub fn convert_vector(
+    vector: Vector3<f32>,
+    from: &CoordinateSystem,
+    to: &CoordinateSystem,
+) -> Vector3<f32> {
+    from.conversion_matrix(to) * vector
+}
+
+/// Converts a rotation between coordinate systems. The result rotates converted vectors the same
+/// way as the source rotation rotates source vectors.
+pub fn convert_rotation(
+    rotation: UnitQuaternion<f32>,
+    from: &CoordinateSystem,
+    to: &CoordinateSystem,
+) -> UnitQuaternion<f32> {
+    // C * R * C^T is a rotation around axis det(C) * C * axis by the same angle.
+    let conversion = from.conversion_matrix(to);
+    let sign = if from.is_mirroring() != to.is_mirroring() {
+        -1.0
+    } else {
+        1.0
+    };
+    let axis = conversion * rotation.imag() * sign;
+    UnitQuaternion::new_unchecked(Quaternion::from_parts(rotation.w, axis))
+}
+
+/// Converts a transform matrix between coordinate systems. The result transforms converted points
+/// the same way as the source matrix transforms source points.
+pub fn convert_matrix(
+    matrix: &Matrix4<f32>,
+    from: &CoordinateSystem,
+    to: &CoordinateSystem,
+) -> Matrix4<f32> {
+    let conversion = from.conversion_matrix(to).to_homogeneous();
+    conversion * matrix * conversion.transpose()
+}
+
+/// Unit of length.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LengthUnit {
+    /// 0.001 meters.
+    Millimeter,
+    /// 0.01 meters.
+    Centimeter,
+    /// One meter, unit of length of the engine.
+    Meter,
+    /// 1000 meters.
+    Kilometer,
+    /// 0.0254 meters.
+    Inch,
+    /// 0.3048 meters.
+    Foot,
+    /// Arbitrary unit, the value is the size of the unit in meters.
+    Custom(f32),
+}
+
+impl Default for LengthUnit {
+    fn default() -> Self {
+        Self::Meter
+    }
+}
+
+impl LengthUnit {
+    /// Returns size of the unit in meters.
+    pub fn meters(self) -> f32 {
+        match self {
+            LengthUnit::Millimeter => 0.001,
+            LengthUnit::Centimeter => 0.01,
+            LengthUnit::Meter => 1.0,
+            LengthUnit::Kilometer => 1000.0,
+            LengthUnit::Inch => 0.0254,
+            LengthUnit::Foot => 0.3048,
+            LengthUnit::Custom(meters) => meters,
+        }
+    }
+
+    /// Returns a factor that converts lengths in this unit to lengths in given unit. For example
+    /// `LengthUnit::Centimeter.factor_to(LengthUnit::Meter)` is `0.01`.
+    pub fn factor_to(self, unit: LengthUnit) -> f32 {
+        self.meters() / unit.meters()
+    }
+}
+
+/// Mismatch between units of a model and the scale factor that is used to import it, see
+/// [`check_import_scale`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ImportScaleMismatch {
+    /// Scale that converts units of the model to meters.
+    pub expected_scale: f32,
+    /// Actual import scale.
+    pub import_scale: f32,
+}
+
+impl Display for ImportScaleMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "model units need scale {} to be converted to meters, but import scale is {}",
+            self.expected_scale, self.import_scale
+        )
+    }
+}
+
+impl std::error::Error for ImportScaleMismatch {}
+
+/// Checks that given import scale converts units of a model (for example taken from metadata of a
+/// model file) to meters, relative error up to 1% is allowed.
+pub fn check_import_scale(
+    model_unit: LengthUnit,
+    import_scale: f32,
+) -> Result<(), ImportScaleMismatch> {
+    let expected_scale = model_unit.factor_to(LengthUnit::Meter);
+    if (expected_scale - import_scale).abs() <= expected_scale.abs() * 0.01 {
+        Ok(())
+    } else {
+        Err(ImportScaleMismatch {
+            expected_scale,
+            import_scale,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{Matrix4, Point3, UnitQuaternion, Vector3},
+        conventions::{
+            check_import_scale, convert_matrix, convert_rotation, convert_vector, CoordinateSystem,
+            Handedness, LengthUnit, UpAxis, LOOK, SIDE, UP,
+        },
+    };
+
+    fn assert_close(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!((a - b).norm() < 1.0e-5, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn test_conversion_to_engine() {
+        let engine = CoordinateSystem::ENGINE;
+
+        for handedness in [Handedness::Right, Handedness::Left] {
+            let z_up = CoordinateSystem::new(UpAxis::Z, handedness);
+            // Up axis is preserved regardless of handedness.
+            assert_close(convert_vector(Vector3::z(), &z_up, &engine), UP);
+            assert_eq!(
+                z_up.to_engine_matrix().determinant() < 0.0,
+                z_up.is_mirroring()
+            );
+        }
+
+        let z_up = CoordinateSystem::new(UpAxis::Z, Handedness::Right);
+        assert_close(convert_vector(Vector3::y(), &z_up, &engine), -LOOK);
+        assert_close(convert_vector(Vector3::x(), &z_up, &engine), SIDE);
+
+        let y_up_left = CoordinateSystem::new(UpAxis::Y, Handedness::Left);
+        assert_close(convert_vector(Vector3::z(), &y_up_left, &engine), -LOOK);
+        assert_close(convert_vector(Vector3::y(), &y_up_left, &engine), UP);
+
+        // Round trip.
+        let v = Vector3::new(1.0, 2.0, 3.0);
+        let converted = convert_vector(v, &z_up, &y_up_left);
+        assert_close(convert_vector(converted, &y_up_left, &z_up), v);
+    }
+
+    #[test]
+    fn test_convert_rotation_and_matrix() {
+        let engine = CoordinateSystem::ENGINE;
+        let point = Vector3::new(0.3, -1.0, 2.0);
+        let rotation = UnitQuaternion::from_euler_angles(0.1, 0.7, -0.4);
+        let transform =
+            Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0)) * rotation.to_homogeneous();
+
+        for up_axis in [UpAxis::Y, UpAxis::Z] {
+            for handedness in [Handedness::Right, Handedness::Left] {
+                let source = CoordinateSystem::new(up_axis, handedness);
+
+                // Converting a rotated point must be equal to rotating a converted point.
+                let expected = convert_vector(rotation * point, &source, &engine);
+                let converted_rotation = convert_rotation(rotation, &source, &engine);
+                assert_close(
+                    converted_rotation * convert_vector(point, &source, &engine),
+                    expected,
+                );
+
+                let expected = convert_vector(
+                    transform.transform_point(&Point3::from(point)).coords,
+                    &source,
+                    &engine,
+                );
+                let converted_transform = convert_matrix(&transform, &source, &engine);
+                let converted_point = Point3::from(convert_vector(point, &source, &engine));
+                assert_close(
+                    converted_transform.transform_point(&converted_point).coords,
+                    expected,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_length_units() {
+        assert_eq!(LengthUnit::Centimeter.factor_to(LengthUnit::Meter), 0.01);
+        assert_eq!(LengthUnit::Kilometer.factor_to(LengthUnit::Meter), 1000.0);
+        assert!(check_import_scale(LengthUnit::Centimeter, 0.01).is_ok());
+        assert!(check_import_scale(LengthUnit::Meter, 1.0).is_ok());
+
+        let mismatch = check_import_scale(LengthUnit::Centimeter, 1.0).unwrap_err();
+        assert_eq!(mismatch.expected_scale, 0.01);
+    }
+}
@@ -116,9 +116,33 @@ impl CurveKey {
     }
 }
 
+/// Defines how a curve is evaluated outside of its keys range.
+#[derive(Visit, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CurveExtrapolation {
+    /// Value of the nearest key is used.
+    Clamp,
+    /// The curve is repeated infinitely in both directions.
+    Loop,
+}
+
+impl Default for CurveExtrapolation {
+    #[inline]
+    fn default() -> Self {
+        Self::Clamp
+    }
+}
+
+/// A curve is a set of keys sorted by their location, it defines a value over some parameter
+/// (time, distance, etc.). Evaluation does not allocate and it takes `O(log n)` time.
+///
+/// Keys may have the same location, this allows you to make "jumps" in the curve: a value at
+/// the location of such keys is the value of the last added key, while the value right before
+/// the location is interpolated towards the first one.
 #[derive(Visit, Default, Clone, Debug, PartialEq)]
 pub struct Curve {
     keys: Vec<CurveKey>,
+    #[visit(optional)] // Backward compatibility.
+    extrapolation: CurveExtrapolation,
 }
 
 fn sort_keys(keys: &mut [CurveKey]) {
@@ -136,7 +160,10 @@ fn sort_keys(keys: &mut [CurveKey]) {
 impl From<Vec<CurveKey>> for Curve {
     fn from(mut keys: Vec<CurveKey>) -> Self {
         sort_keys(&mut keys);
-        Self { keys }
+        Self {
+            keys,
+            extrapolation: Default::default(),
+        }
     }
 }
 
@@ -156,10 +183,24 @@ impl Curve {
         &self.keys
     }
 
+    /// Adds new key, the keys remain sorted by location. If there are keys with the same location,
+    /// the new key is placed after them.
     #[inline]
     pub fn add_key(&mut self, new_key: CurveKey) {
-        self.keys.push(new_key);
-        sort_keys(&mut self.keys);
+        let index = self
+            .keys
+            .partition_point(|key| key.location <= new_key.location);
+        self.keys.insert(index, new_key);
+    }
+
+    /// Removes a key at given index, the rest of the keys remain sorted.
+    #[inline]
+    pub fn remove_key(&mut self, index: usize) -> Option<CurveKey> {
+        if index < self.keys.len() {
+            Some(self.keys.remove(index))
+        } else {
+            None
+        }
     }
 
     #[inline]
@@ -170,52 +211,146 @@ impl Curve {
         }
     }
 
+    /// Sets new extrapolation mode, default is [`CurveExtrapolation::Clamp`].
     #[inline]
-    pub fn value_at(&self, location: f32) -> f32 {
-        if self.keys.is_empty() {
+    pub fn set_extrapolation(&mut self, extrapolation: CurveExtrapolation) {
+        self.extrapolation = extrapolation;
+    }
+
+    /// Returns current extrapolation mode.
+    #[inline]
+    pub fn extrapolation(&self) -> CurveExtrapolation {
+        self.extrapolation
+    }
+
+    /// Calculates value of the curve at given location. Returns zero if there are no keys.
+    #[inline]
+    pub fn evaluate(&self, location: f32) -> f32 {
+        let (first, last) = match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => (first, last),
             // Stub - zero
-            return Default::default();
-        } else if self.keys.len() == 1 {
-            // Single key - just return its value
-            return self.keys.first().unwrap().value;
-        } else if self.keys.len() == 2 {
-            // Special case for two keys (much faster than generic)
-            let pt_a = self.keys.get(0).unwrap();
-            let pt_b = self.keys.get(1).unwrap();
-            if location >= pt_a.location && location <= pt_b.location {
-                let span = pt_b.location - pt_a.location;
-                let t = (location - pt_a.location) / span;
-                return pt_a.interpolate(pt_b, t);
-            } else if location < pt_a.location {
-                return pt_a.value;
-            } else {
-                return pt_b.value;
+            _ => return Default::default(),
+        };
+
+        let location = match self.extrapolation {
+            CurveExtrapolation::Clamp => location,
+            CurveExtrapolation::Loop => {
+                let span = last.location - first.location;
+                if span > 0.0 {
+                    first.location + (location - first.location).rem_euclid(span)
+                } else {
+                    location
+                }
             }
-        }
+        };
 
-        // Generic case - check for out-of-bounds
-        let first = self.keys.first().unwrap();
-        let last = self.keys.last().unwrap();
-        if location <= first.location {
+        // Amount of keys at the left of the location (or exactly at it).
+        let index = self.keys.partition_point(|key| key.location <= location);
+        if index == 0 {
             first.value
-        } else if location >= last.location {
+        } else if index == self.keys.len() {
             last.value
         } else {
-            // Find span first
-            let mut pt_a_index = 0;
-            for (i, pt) in self.keys.iter().enumerate() {
-                if location >= pt.location {
-                    pt_a_index = i;
-                }
-            }
-            let pt_b_index = pt_a_index + 1;
-
-            let pt_a = self.keys.get(pt_a_index).unwrap();
-            let pt_b = self.keys.get(pt_b_index).unwrap();
-
+            // Span cannot be zero here: left key is at or before the location, right key is
+            // strictly after it.
+            let pt_a = &self.keys[index - 1];
+            let pt_b = &self.keys[index];
             let span = pt_b.location - pt_a.location;
             let t = (location - pt_a.location) / span;
             pt_a.interpolate(pt_b, t)
         }
     }
+
+    /// Same as [`Self::evaluate`].
+    #[inline]
+    pub fn value_at(&self, location: f32) -> f32 {
+        self.evaluate(location)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::curve::{Curve, CurveExtrapolation, CurveKey, CurveKeyKind};
+
+    #[test]
+    fn test_curve_evaluation() {
+        let mut curve = Curve::default();
+        assert_eq!(curve.evaluate(1.0), 0.0);
+
+        curve.add_key(CurveKey::new(1.0, 2.0, CurveKeyKind::Linear));
+        assert_eq!(curve.evaluate(-1.0), 2.0);
+        assert_eq!(curve.evaluate(3.0), 2.0);
+
+        curve.add_key(CurveKey::new(0.0, 0.0, CurveKeyKind::Linear));
+        curve.add_key(CurveKey::new(2.0, 0.0, CurveKeyKind::Constant));
+        assert_eq!(curve.keys()[0].location(), 0.0);
+        assert_eq!(curve.evaluate(0.5), 1.0);
+        assert_eq!(curve.evaluate(1.0), 2.0);
+        assert_eq!(curve.evaluate(1.5), 1.0);
+        assert_eq!(curve.evaluate(5.0), 0.0);
+
+        curve.set_extrapolation(CurveExtrapolation::Loop);
+        assert_eq!(curve.evaluate(2.5), 1.0);
+        assert_eq!(curve.evaluate(-1.5), 1.0);
+
+        assert!(curve.remove_key(0).is_some());
+        assert!(curve.remove_key(5).is_none());
+        assert_eq!(curve.keys().len(), 2);
+    }
+
+    #[test]
+    fn test_curve_duplicate_locations() {
+        let mut curve = Curve::default();
+        curve.add_key(CurveKey::new(0.0, 0.0, CurveKeyKind::Linear));
+        curve.add_key(CurveKey::new(1.0, 1.0, CurveKeyKind::Linear));
+        curve.add_key(CurveKey::new(1.0, 5.0, CurveKeyKind::Linear));
+        curve.add_key(CurveKey::new(2.0, 5.0, CurveKeyKind::Linear));
+
+        // The last added key wins at the location of the jump.
+        assert_eq!(curve.keys()[2].value, 5.0);
+        assert_eq!(curve.evaluate(1.0), 5.0);
+        assert!((curve.evaluate(0.999) - 0.999).abs() < 1.0e-4);
+
+        // Every key at the same location, no division by zero.
+        let mut curve = Curve::default();
+        curve.add_key(CurveKey::new(1.0, 1.0, CurveKeyKind::Linear));
+        curve.add_key(CurveKey::new(1.0, 3.0, CurveKeyKind::Linear));
+        curve.set_extrapolation(CurveExtrapolation::Loop);
+        for location in [0.0, 1.0, 2.0] {
+            assert!(curve.evaluate(location).is_finite());
+        }
+        assert_eq!(curve.evaluate(0.0), 1.0);
+        assert_eq!(curve.evaluate(2.0), 3.0);
+    }
+
+    #[test]
+    fn test_curve_tangents() {
+        // Zero tangents - smooth step.
+        let curve = Curve::from(vec![
+            CurveKey::new(0.0, 0.0, CurveKeyKind::new_cubic(0.0, 0.0)),
+            CurveKey::new(1.0, 1.0, CurveKeyKind::new_cubic(0.0, 0.0)),
+        ]);
+        assert_eq!(curve.evaluate(0.0), 0.0);
+        assert_eq!(curve.evaluate(0.5), 0.5);
+        assert_eq!(curve.evaluate(1.0), 1.0);
+        assert!(curve.evaluate(0.25) < 0.25);
+
+        // Vertical tangents must not produce NaN or infinity.
+        let half_pi = std::f32::consts::FRAC_PI_2;
+        let curve = Curve::from(vec![
+            CurveKey::new(0.0, 0.0, CurveKeyKind::new_cubic(half_pi, half_pi)),
+            CurveKey::new(1.0, 1.0, CurveKeyKind::new_cubic(-half_pi, -half_pi)),
+        ]);
+        for i in 0..=10 {
+            assert!(curve.evaluate(i as f32 / 10.0).is_finite());
+        }
+
+        // Tangents are relative to value difference, so keys with equal values produce flat
+        // segment regardless of tangents.
+        let curve = Curve::from(vec![
+            CurveKey::new(0.0, 1.0, CurveKeyKind::new_cubic(1.0, 1.0)),
+            CurveKey::new(1.0, 1.0, CurveKeyKind::new_cubic(-1.0, -1.0)),
+        ]);
+        assert_eq!(curve.evaluate(0.3), 1.0);
+    }
 }
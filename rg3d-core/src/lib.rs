@@ -28,9 +28,11 @@ use std::{
 
 pub mod color;
 pub mod color_gradient;
+pub mod conventions;
 pub mod curve;
 pub mod inspect;
 pub mod io;
+pub mod log;
 pub mod math;
 pub mod numeric_range;
 pub mod octree;
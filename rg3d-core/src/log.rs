@@ -0,0 +1,692 @@
+//! Engine log with levels, targets and pluggable sinks.
+//!
+//! Every message is a [`LogRecord`] with a kind (level), a target (usually module path of the
+//! place where the message was written) and text. Records pass two filters - global verbosity
+//! and per-target verbosity (see [`Log::set_target_verbosity`]), then they're written to every
+//! [`LogSink`]. By default there are two sinks: stdout (browser console on WebAssembly) and
+//! `rg3d.log` file.
+//!
+//! Use macros to write messages, they fill the target automatically and do not format the
+//! message if it will be filtered out anyway:
+//!
+//! ```rust
+//! use rg3d_core::{log_error, log_info};
+//!
+//! log_info!("Loaded {} textures", 42);
+//! log_error!(target: "my_game::save", "Unable to write save file!");
+//! ```
+//!
+//! Messages below compile-time max level are removed completely, the level is selected by one of
+//! `max_log_level_*` features of the crate (`off`, `error`, `warning`, `info`, `debug`), every
+//! level is compiled in by default.
+//!
+//! Logs of crates that use [`log`](https://docs.rs/log) facade could be redirected to this log
+//! by [`Log::install_log_facade`].
+//!
+//! Sinks are called after the log is unlocked, so a sink can use the log too. Records that are
+//! written by a sink are queued and written to every sink after the current record.
+
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    collections::VecDeque,
+    fmt::{Debug, Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(target_arch = "wasm32")]
+use crate::wasm_bindgen::{self, prelude::*};
+
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+extern "C" {
+    // Use `js_namespace` here to bind `console.log(..)` instead of just
+    // `log(..)`
+    #[wasm_bindgen(js_namespace = console)]
+    fn log(s: &str);
+}
+
+lazy_static! {
+    static ref LOG: Mutex<Log> = Mutex::new(Log::new());
+}
+
+thread_local! {
+    // Whether sinks are called on this thread at the moment.
+    static DISPATCHING: Cell<bool> = Cell::new(false);
+    // Records that were written by sinks, they're dispatched after the current record.
+    static PENDING: RefCell<VecDeque<LogRecord>> = RefCell::new(VecDeque::new());
+}
+
+// Resets dispatching flag even if a sink panics.
+struct DispatchGuard;
+
+impl Drop for DispatchGuard {
+    fn drop(&mut self) {
+        DISPATCHING.with(|dispatching| dispatching.set(false));
+    }
+}
+
+type SharedSink = Arc<Mutex<dyn LogSink>>;
+
+/// A kind of message.
+#[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Ord, Hash, Debug)]
+#[repr(u32)]
+pub enum MessageKind {
+    /// Very detailed information for tracing of problems.
+    Trace = 0,
+    /// Information that is useful for debugging.
+    Debug = 1,
+    /// Some useful information.
+    Information = 2,
+    /// A warning.
+    Warning = 3,
+    /// An error of some kind.
+    Error = 4,
+}
+
+impl MessageKind {
+    /// Returns every kind from the most verbose to the least verbose.
+    pub fn all() -> [MessageKind; 5] {
+        [
+            MessageKind::Trace,
+            MessageKind::Debug,
+            MessageKind::Information,
+            MessageKind::Warning,
+            MessageKind::Error,
+        ]
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            MessageKind::Trace => "[TRACE]: ",
+            MessageKind::Debug => "[DEBUG]: ",
+            MessageKind::Information => "[INFO]: ",
+            MessageKind::Warning => "[WARNING]: ",
+            MessageKind::Error => "[ERROR]: ",
+        }
+    }
+}
+
+/// Messages of kinds less than this value are removed at compile time, see module docs.
+pub const STATIC_MAX_LEVEL: u32 = if cfg!(feature = "max_log_level_off") {
+    MessageKind::Error as u32 + 1
+} else if cfg!(feature = "max_log_level_error") {
+    MessageKind::Error as u32
+} else if cfg!(feature = "max_log_level_warning") {
+    MessageKind::Warning as u32
+} else if cfg!(feature = "max_log_level_info") {
+    MessageKind::Information as u32
+} else if cfg!(feature = "max_log_level_debug") {
+    MessageKind::Debug as u32
+} else {
+    MessageKind::Trace as u32
+};
+
+/// A single message of the log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogRecord {
+    /// Kind of the message.
+    pub kind: MessageKind,
+    /// Target of the message, usually it is module path, could be empty.
+    pub target: String,
+    /// Text of the message.
+    pub message: String,
+}
+
+impl Display for LogRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.target.is_empty() {
+            write!(f, "{}{}", self.kind.as_str(), self.message)
+        } else {
+            write!(f, "{}{}: {}", self.kind.as_str(), self.target, self.message)
+        }
+    }
+}
+
+/// A destination of log records. Sinks are called without lock of the log, so they can write
+/// records and change settings of the log, see module docs.
+pub trait LogSink: Send {
+    /// Writes the record.
+    fn write(&mut self, record: &LogRecord);
+
+    /// Flushes buffered records, if any.
+    fn flush(&mut self) {}
+}
+
+/// Writes records to stdout, or to browser console on WebAssembly.
+#[derive(Default)]
+pub struct StdoutSink;
+
+impl LogSink for StdoutSink {
+    fn write(&mut self, record: &LogRecord) {
+        #[cfg(target_arch = "wasm32")]
+        {
+            log(&record.to_string());
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = writeln!(io::stdout(), "{}", record);
+        }
+    }
+
+    fn flush(&mut self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+/// Writes records to a file. When the file exceeds max size, it is renamed to `<path>.1`,
+/// previous `<path>.1` is renamed to `<path>.2` and so on, up to max amount of old files.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileSink {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+    max_files: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileSink {
+    /// Creates new file (or truncates existing one), rotation is disabled.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self {
+            path: path.as_ref().to_owned(),
+            file: File::create(path)?,
+            size: 0,
+            max_size: None,
+            max_files: 0,
+        })
+    }
+
+    /// Enables rotation of the file when its size exceeds `max_size` bytes, at most `max_files`
+    /// old files are kept.
+    pub fn with_rotation(mut self, max_size: u64, max_files: usize) -> Self {
+        self.max_size = Some(max_size);
+        self.max_files = max_files;
+        self
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = std::fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let _ = std::fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = File::create(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl LogSink for FileSink {
+    fn write(&mut self, record: &LogRecord) {
+        let line = format!("{}\n", record);
+        if let Some(max_size) = self.max_size {
+            if self.size > 0 && self.size + line.len() as u64 > max_size && self.rotate().is_err() {
+                return;
+            }
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+
+    fn flush(&mut self) {
+        let _ = self.file.flush();
+    }
+}
+
+/// In-memory buffer of last records, see [`RingBufferSink`].
+#[derive(Debug)]
+pub struct LogBuffer {
+    records: VecDeque<LogRecord>,
+    capacity: usize,
+    total: u64,
+}
+
+impl LogBuffer {
+    /// Returns stored records from the oldest to the newest.
+    pub fn records(&self) -> impl DoubleEndedIterator<Item = &LogRecord> {
+        self.records.iter()
+    }
+
+    /// Returns total amount of records that were ever written to the buffer, it allows to check
+    /// whether there are new records or not.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Removes every record.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        // Readers should notice the change.
+        self.total += 1;
+    }
+}
+
+/// Shared handle to a log buffer.
+pub type SharedLogBuffer = Arc<Mutex<LogBuffer>>;
+
+/// Keeps last records in memory, for example to show them in in-game console. Records are
+/// stored in shared buffer that could be read at any time.
+pub struct RingBufferSink {
+    buffer: SharedLogBuffer,
+}
+
+impl RingBufferSink {
+    /// Creates new sink that keeps at most `capacity` last records.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buffer: Arc::new(Mutex::new(LogBuffer {
+                records: VecDeque::with_capacity(capacity),
+                capacity,
+                total: 0,
+            })),
+        }
+    }
+
+    /// Returns shared buffer of the sink.
+    pub fn buffer(&self) -> SharedLogBuffer {
+        self.buffer.clone()
+    }
+}
+
+impl LogSink for RingBufferSink {
+    fn write(&mut self, record: &LogRecord) {
+        let mut buffer = self.buffer.lock().unwrap();
+        if buffer.capacity == 0 {
+            return;
+        }
+        if buffer.records.len() == buffer.capacity {
+            buffer.records.pop_front();
+        }
+        buffer.records.push_back(record.clone());
+        buffer.total += 1;
+    }
+}
+
+/// See module docs.
+pub struct Log {
+    verbosity: MessageKind,
+    // Sorted by length of prefix, so the most specific filter is checked first.
+    target_verbosity: Vec<(String, MessageKind)>,
+    sinks: Vec<SharedSink>,
+}
+
+impl Log {
+    fn new() -> Self {
+        let mut sinks: Vec<SharedSink> = vec![Arc::new(Mutex::new(StdoutSink))];
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Ok(file) = FileSink::new("rg3d.log") {
+                sinks.push(Arc::new(Mutex::new(file)));
+            }
+        }
+        Self {
+            verbosity: MessageKind::Information,
+            target_verbosity: Default::default(),
+            sinks,
+        }
+    }
+
+    fn is_enabled_internal(&self, kind: MessageKind, target: &str) -> bool {
+        let verbosity = self
+            .target_verbosity
+            .iter()
+            .find(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .map_or(self.verbosity, |(_, verbosity)| *verbosity);
+        kind >= verbosity
+    }
+
+    // Returns sinks that should receive the record, so they can be called without the lock.
+    fn sinks_for(&self, record: &LogRecord) -> Vec<SharedSink> {
+        if self.is_enabled_internal(record.kind, &record.target) {
+            self.sinks.clone()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns `true` if a message of given kind and target passes filters of the log. Macros
+    /// use it to skip formatting of messages that will be filtered out.
+    #[allow(clippy::absurd_extreme_comparisons)]
+    pub fn is_enabled(kind: MessageKind, target: &str) -> bool {
+        kind as u32 >= STATIC_MAX_LEVEL && LOG.lock().unwrap().is_enabled_internal(kind, target)
+    }
+
+    /// Writes the record to every sink, if it passes filters.
+    pub fn write_record(record: LogRecord) {
+        if DISPATCHING.with(|dispatching| dispatching.replace(true)) {
+            // Written by a sink, the sink is busy with the current record.
+            PENDING.with(|pending| pending.borrow_mut().push_back(record));
+            return;
+        }
+
+        let _guard = DispatchGuard;
+        let mut next = Some(record);
+        while let Some(record) = next {
+            let sinks = LOG.lock().unwrap().sinks_for(&record);
+            for sink in sinks {
+                if let Ok(mut sink) = sink.lock() {
+                    sink.write(&record);
+                }
+            }
+            next = PENDING.with(|pending| pending.borrow_mut().pop_front());
+        }
+    }
+
+    /// Writes a message of given target, prefer macros instead.
+    pub fn write_target(kind: MessageKind, target: &str, msg: String) {
+        Self::write_record(LogRecord {
+            kind,
+            target: target.to_owned(),
+            message: msg,
+        })
+    }
+
+    /// Writes a message without target into every sink. Every record is a separate line.
+    pub fn write(kind: MessageKind, msg: String) {
+        Self::write_target(kind, "", msg)
+    }
+
+    /// Writes a message without target into every sink.
+    pub fn writeln(kind: MessageKind, msg: String) {
+        Self::write_target(kind, "", msg)
+    }
+
+    /// Sets global verbosity level, messages of less important kinds are ignored. Default is
+    /// [`MessageKind::Information`].
+    pub fn set_verbosity(kind: MessageKind) {
+        LOG.lock().unwrap().verbosity = kind;
+    }
+
+    /// Returns global verbosity level.
+    pub fn verbosity() -> MessageKind {
+        LOG.lock().unwrap().verbosity
+    }
+
+    /// Sets verbosity level of every target that starts with given prefix, it overrides global
+    /// verbosity. For example `Log::set_target_verbosity("rg3d::renderer", MessageKind::Trace)`
+    /// shows every message of the renderer.
+    pub fn set_target_verbosity<P: AsRef<str>>(prefix: P, kind: MessageKind) {
+        let mut log = LOG.lock().unwrap();
+        let prefix = prefix.as_ref();
+        log.target_verbosity.retain(|(p, _)| p != prefix);
+        log.target_verbosity.push((prefix.to_owned(), kind));
+        log.target_verbosity.sort_by_key(|(p, _)| Reverse(p.len()));
+    }
+
+    /// Removes verbosity level of targets with given prefix.
+    pub fn reset_target_verbosity<P: AsRef<str>>(prefix: P) {
+        LOG.lock()
+            .unwrap()
+            .target_verbosity
+            .retain(|(p, _)| p != prefix.as_ref());
+    }
+
+    /// Adds new sink, every next record will be written to it.
+    pub fn add_sink<S: LogSink + 'static>(sink: S) {
+        LOG.lock().unwrap().sinks.push(Arc::new(Mutex::new(sink)));
+    }
+
+    /// Removes every sink, including default ones.
+    pub fn clear_sinks() {
+        LOG.lock().unwrap().sinks.clear();
+    }
+
+    /// Flushes every sink.
+    pub fn flush() {
+        let sinks = LOG.lock().unwrap().sinks.clone();
+        for sink in sinks {
+            if let Ok(mut sink) = sink.lock() {
+                sink.flush();
+            }
+        }
+    }
+
+    /// Redirects messages of [`log`](https://docs.rs/log) facade to this log, it allows to see
+    /// messages of other crates in sinks of the engine. Fails if other logger was already
+    /// installed.
+    pub fn install_log_facade() -> Result<(), ::log::SetLoggerError> {
+        ::log::set_logger(&LOG_FACADE)?;
+        ::log::set_max_level(::log::LevelFilter::Trace);
+        Ok(())
+    }
+
+    /// Allows you to verify that the result of operation is Ok, or print the error in the log.
+    ///
+    /// # Use cases
+    ///
+    /// Typical use case for this method is that when you _can_ ignore errors, but want them to
+    /// be in the log.
+    pub fn verify<E>(result: Result<(), E>)
+    where
+        E: Debug,
+    {
+        if let Err(e) = result {
+            Self::writeln(
+                MessageKind::Error,
+                format!("Operation failed! Reason: {:?}", e),
+            );
+        }
+    }
+}
+
+struct LogFacade;
+
+static LOG_FACADE: LogFacade = LogFacade;
+
+impl From<::log::Level> for MessageKind {
+    fn from(level: ::log::Level) -> Self {
+        match level {
+            ::log::Level::Error => MessageKind::Error,
+            ::log::Level::Warn => MessageKind::Warning,
+            ::log::Level::Info => MessageKind::Information,
+            ::log::Level::Debug => MessageKind::Debug,
+            ::log::Level::Trace => MessageKind::Trace,
+        }
+    }
+}
+
+impl ::log::Log for LogFacade {
+    fn enabled(&self, metadata: &::log::Metadata) -> bool {
+        Log::is_enabled(metadata.level().into(), metadata.target())
+    }
+
+    fn log(&self, record: &::log::Record) {
+        Log::write_target(
+            record.level().into(),
+            record.target(),
+            record.args().to_string(),
+        );
+    }
+
+    fn flush(&self) {
+        Log::flush();
+    }
+}
+
+/// Writes a message of given kind to the log, target is module path by default.
+#[macro_export]
+macro_rules! log_message {
+    (target: $target:expr, $kind:expr, $($arg:tt)+) => {{
+        let kind = $kind;
+        let target = $target;
+        if kind as u32 >= $crate::log::STATIC_MAX_LEVEL && $crate::log::Log::is_enabled(kind, target) {
+            $crate::log::Log::write_target(kind, target, format!($($arg)+));
+        }
+    }};
+    ($kind:expr, $($arg:tt)+) => {
+        $crate::log_message!(target: module_path!(), $kind, $($arg)+)
+    };
+}
+
+/// Writes [`crate::log::MessageKind::Trace`] message to the log.
+#[macro_export]
+macro_rules! log_trace {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log_message!(target: $target, $crate::log::MessageKind::Trace, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log_message!($crate::log::MessageKind::Trace, $($arg)+)
+    };
+}
+
+/// Writes [`crate::log::MessageKind::Debug`] message to the log.
+#[macro_export]
+macro_rules! log_debug {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log_message!(target: $target, $crate::log::MessageKind::Debug, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log_message!($crate::log::MessageKind::Debug, $($arg)+)
+    };
+}
+
+/// Writes [`crate::log::MessageKind::Information`] message to the log.
+#[macro_export]
+macro_rules! log_info {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log_message!(target: $target, $crate::log::MessageKind::Information, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log_message!($crate::log::MessageKind::Information, $($arg)+)
+    };
+}
+
+/// Writes [`crate::log::MessageKind::Warning`] message to the log.
+#[macro_export]
+macro_rules! log_warn {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log_message!(target: $target, $crate::log::MessageKind::Warning, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log_message!($crate::log::MessageKind::Warning, $($arg)+)
+    };
+}
+
+/// Writes [`crate::log::MessageKind::Error`] message to the log.
+#[macro_export]
+macro_rules! log_error {
+    (target: $target:expr, $($arg:tt)+) => {
+        $crate::log_message!(target: $target, $crate::log::MessageKind::Error, $($arg)+)
+    };
+    ($($arg:tt)+) => {
+        $crate::log_message!($crate::log::MessageKind::Error, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use crate::log::{LogRecord, LogSink, MessageKind, RingBufferSink};
+
+    fn record(message: &str) -> LogRecord {
+        LogRecord {
+            kind: MessageKind::Information,
+            target: "rg3d::test".to_owned(),
+            message: message.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_last_records() {
+        let mut sink = RingBufferSink::new(2);
+        let buffer = sink.buffer();
+        sink.write(&record("a"));
+        sink.write(&record("b"));
+        sink.write(&record("c"));
+
+        let buffer = buffer.lock().unwrap();
+        let messages = buffer
+            .records()
+            .map(|r| r.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages, ["b", "c"]);
+        assert_eq!(buffer.total(), 3);
+    }
+
+    #[test]
+    fn test_record_display() {
+        assert_eq!(record("a").to_string(), "[INFO]: rg3d::test: a");
+    }
+
+    #[test]
+    fn test_file_rotation() {
+        use crate::log::FileSink;
+
+        let dir = std::env::temp_dir().join(format!("rg3d-log-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+
+        let mut sink = FileSink::new(&path).unwrap().with_rotation(40, 2);
+        for message in ["first record", "second record", "third record"] {
+            sink.write(&record(message));
+        }
+        sink.flush();
+
+        let current = std::fs::read_to_string(&path).unwrap();
+        let previous = std::fs::read_to_string(dir.join("test.log.1")).unwrap();
+        let oldest = std::fs::read_to_string(dir.join("test.log.2")).unwrap();
+        assert!(current.contains("third record"));
+        assert!(previous.contains("second record"));
+        assert!(oldest.contains("first record"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_sink_can_use_log() {
+        use crate::log::Log;
+        use std::sync::{Arc, Mutex};
+
+        const TARGET: &str = "rg3d::test::reentrant";
+
+        struct ReentrantSink {
+            messages: Arc<Mutex<Vec<String>>>,
+        }
+
+        impl LogSink for ReentrantSink {
+            fn write(&mut self, record: &LogRecord) {
+                if record.target != TARGET {
+                    return;
+                }
+                self.messages.lock().unwrap().push(record.message.clone());
+                if record.message == "outer" {
+                    // Both would deadlock if sinks were called under the lock.
+                    let _ = Log::verbosity();
+                    Log::write_target(MessageKind::Error, TARGET, "inner".to_owned());
+                }
+            }
+        }
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        Log::add_sink(ReentrantSink {
+            messages: messages.clone(),
+        });
+        Log::write_target(MessageKind::Error, TARGET, "outer".to_owned());
+
+        assert_eq!(*messages.lock().unwrap(), ["outer", "inner"]);
+    }
+}
@@ -5,6 +5,7 @@ pub mod aabb;
 pub mod frustum;
 pub mod plane;
 pub mod ray;
+pub mod smooth;
 pub mod triangulator;
 
 use crate::algebra::{RealField, SimdRealField};
@@ -809,6 +810,8 @@ pub fn get_closest_point_triangle_set<P: PositionProvider>(
     closest_index
 }
 
+/// Smooth rotation around a single axis with constant angular speed, see [`smooth`] module for
+/// frame-rate independent smoothing of scalars, vectors and rotations.
 #[derive(Debug, PartialEq)]
 pub struct SmoothAngle {
     /// Current angle in radians.
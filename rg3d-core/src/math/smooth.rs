@@ -0,0 +1,493 @@
+//! Frame-rate independent smoothing of scalars, vectors and rotations.
+//!
+//! [`SmoothValue`] moves its value towards a target exponentially: after every `half_life`
+//! seconds the distance to the target is halved. [`SmoothSpring`] is a critically damped spring,
+//! it follows a target with its velocity, so it starts and stops smoothly and can follow a
+//! moving target without lag if the velocity of the target is known.
+//!
+//! Both use exact solutions instead of numeric integration, so updating with `10 * dt` once
+//! gives the same result as updating with `dt` ten times, and very large time steps are fine:
+//! the value just arrives at the target, exponential smoothing never overshoots.
+//!
+//! ```
+//! use rg3d_core::{
+//!     algebra::{UnitQuaternion, Vector3},
+//!     math::smooth::{SmoothQuat, SmoothSpring},
+//! };
+//!
+//! let mut yaw = SmoothQuat::new(UnitQuaternion::identity(), 0.1);
+//! yaw.set_target(UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 1.0));
+//! yaw.update(1.0 / 60.0);
+//!
+//! let mut height = SmoothSpring::new(0.0, 0.25);
+//! height.set_target(1.0).update(1.0 / 60.0);
+//! assert!(height.value() > 0.0 && height.value() < 1.0);
+//! ```
+
+use crate::{
+    algebra::{UnitQuaternion, Vector2, Vector3},
+    visitor::{Visit, VisitResult, Visitor},
+};
+use std::{
+    fmt::Debug,
+    ops::{Add, Mul, Sub},
+};
+
+/// Distance at which smoothed value snaps to its target. Exponential smoothing never reaches
+/// its target exactly, and floating-point rounding may leave it stuck very close to it.
+const SNAP_DISTANCE: f32 = 1.0e-6;
+
+/// `x` such that `(1 + x) * exp(-x) = 0.5`, it is used to convert half-life of a critically
+/// damped spring to its angular frequency.
+const SPRING_HALF_LIFE_FACTOR: f32 = 1.678_347;
+
+/// A value that can be smoothed.
+pub trait Smoothable: Copy + Default + Debug + Visit {
+    /// Interpolates between `self` (`t = 0`) and `other` (`t = 1`).
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+
+    /// Returns non-negative distance between two values.
+    fn distance(&self, other: &Self) -> f32;
+}
+
+impl Smoothable for f32 {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        (other - self).abs()
+    }
+}
+
+impl Smoothable for Vector2<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.metric_distance(other)
+    }
+}
+
+impl Smoothable for Vector3<f32> {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.metric_distance(other)
+    }
+}
+
+impl Smoothable for UnitQuaternion<f32> {
+    /// Spherical interpolation along the shortest arc.
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        // Slerp fails only for opposite quaternions, which are never passed to it, because
+        // they're flipped to the same hemisphere first.
+        self.try_slerp(other, t, f32::EPSILON).unwrap_or(*other)
+    }
+
+    /// Angle between two rotations in radians.
+    fn distance(&self, other: &Self) -> f32 {
+        self.angle_to(other)
+    }
+}
+
+/// Exponential smoothing of a value, see module docs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SmoothValue<T: Smoothable> {
+    value: T,
+    target: T,
+    half_life: f32,
+}
+
+/// Exponential smoothing of a 3D vector.
+pub type SmoothVec3 = SmoothValue<Vector3<f32>>;
+
+/// Exponential smoothing of a rotation, rotation is interpolated along the shortest arc.
+pub type SmoothQuat = SmoothValue<UnitQuaternion<f32>>;
+
+impl<T: Smoothable> SmoothValue<T> {
+    /// Creates new smoothed value that is at its target. `half_life` is the time in seconds
+    /// in which the distance to the target is halved, zero disables smoothing.
+    pub fn new(value: T, half_life: f32) -> Self {
+        Self {
+            value,
+            target: value,
+            half_life,
+        }
+    }
+
+    /// Sets new target, the value will move towards it on next updates.
+    #[inline]
+    pub fn set_target(&mut self, target: T) -> &mut Self {
+        self.target = target;
+        self
+    }
+
+    /// Sets current value, the target is left unchanged.
+    #[inline]
+    pub fn set_value(&mut self, value: T) -> &mut Self {
+        self.value = value;
+        self
+    }
+
+    /// Moves the value to the target immediately.
+    #[inline]
+    pub fn reset(&mut self) -> &mut Self {
+        self.value = self.target;
+        self
+    }
+
+    #[inline]
+    pub fn set_half_life(&mut self, half_life: f32) -> &mut Self {
+        self.half_life = half_life;
+        self
+    }
+
+    #[inline]
+    pub fn half_life(&self) -> f32 {
+        self.half_life
+    }
+
+    /// Moves the value towards the target, negative time steps are ignored.
+    #[inline]
+    pub fn update(&mut self, dt: f32) -> &mut Self {
+        let dt = dt.max(0.0);
+        if self.half_life <= 0.0 {
+            self.value = self.target;
+        } else {
+            // Fraction of remaining distance that is covered during the time step, it is always
+            // in [0; 1] range, so the value never overshoots.
+            let t = 1.0 - (-dt / self.half_life).exp2();
+            self.value = self.value.interpolate(&self.target, t);
+            if self.at_target() {
+                self.value = self.target;
+            }
+        }
+        self
+    }
+
+    #[inline]
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    #[inline]
+    pub fn target(&self) -> T {
+        self.target
+    }
+
+    #[inline]
+    pub fn distance(&self) -> f32 {
+        self.value.distance(&self.target)
+    }
+
+    #[inline]
+    pub fn at_target(&self) -> bool {
+        self.distance() <= SNAP_DISTANCE
+    }
+}
+
+impl<T: Smoothable> Default for SmoothValue<T> {
+    fn default() -> Self {
+        Self::new(T::default(), 0.1)
+    }
+}
+
+impl<T: Smoothable> Visit for SmoothValue<T> {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.value.visit("Value", visitor)?;
+        self.target.visit("Target", visitor)?;
+        self.half_life.visit("HalfLife", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Critically damped spring, see module docs. It is implemented for scalars and vectors.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SmoothSpring<T: Smoothable> {
+    value: T,
+    velocity: T,
+    target: T,
+    target_velocity: T,
+    half_life: f32,
+}
+
+impl<T> SmoothSpring<T>
+where
+    T: Smoothable + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    /// Creates new spring that rests at its target. `half_life` is the time in seconds in which
+    /// the spring covers half of the distance to a static target when starting at rest, zero
+    /// disables smoothing.
+    pub fn new(value: T, half_life: f32) -> Self {
+        Self {
+            value,
+            velocity: T::default(),
+            target: value,
+            target_velocity: T::default(),
+            half_life,
+        }
+    }
+
+    /// Sets new target, the spring will move towards it on next updates.
+    #[inline]
+    pub fn set_target(&mut self, target: T) -> &mut Self {
+        self.target = target;
+        self
+    }
+
+    /// Sets velocity of the target, the target is moved with this velocity on every update, so
+    /// the spring follows a moving target without lag.
+    #[inline]
+    pub fn set_target_velocity(&mut self, velocity: T) -> &mut Self {
+        self.target_velocity = velocity;
+        self
+    }
+
+    /// Sets current value, the target and the velocity are left unchanged.
+    #[inline]
+    pub fn set_value(&mut self, value: T) -> &mut Self {
+        self.value = value;
+        self
+    }
+
+    #[inline]
+    pub fn set_velocity(&mut self, velocity: T) -> &mut Self {
+        self.velocity = velocity;
+        self
+    }
+
+    /// Moves the spring to the target immediately, its velocity becomes velocity of the target.
+    #[inline]
+    pub fn reset(&mut self) -> &mut Self {
+        self.value = self.target;
+        self.velocity = self.target_velocity;
+        self
+    }
+
+    #[inline]
+    pub fn set_half_life(&mut self, half_life: f32) -> &mut Self {
+        self.half_life = half_life;
+        self
+    }
+
+    #[inline]
+    pub fn half_life(&self) -> f32 {
+        self.half_life
+    }
+
+    /// Moves the spring towards the target, negative time steps are ignored.
+    pub fn update(&mut self, dt: f32) -> &mut Self {
+        let dt = dt.max(0.0);
+
+        let offset = self.target_velocity * dt;
+        let decay = if self.half_life <= 0.0 {
+            0.0
+        } else {
+            let omega = SPRING_HALF_LIFE_FACTOR / self.half_life;
+
+            // Exact solution of critically damped oscillator for the error relative to the target
+            // that moves with constant velocity.
+            let decay = (-omega * dt).exp();
+            if decay > 0.0 {
+                let error = self.value - self.target;
+                let error_velocity = self.velocity - self.target_velocity;
+                let j = error_velocity + error * omega;
+                self.value = self.target + offset + (error + j * dt) * decay;
+                self.velocity = self.target_velocity + (error_velocity - j * (omega * dt)) * decay;
+            }
+            decay
+        };
+
+        self.target = self.target + offset;
+        if decay == 0.0 {
+            self.reset();
+        }
+
+        self
+    }
+
+    #[inline]
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    #[inline]
+    pub fn velocity(&self) -> T {
+        self.velocity
+    }
+
+    #[inline]
+    pub fn target(&self) -> T {
+        self.target
+    }
+
+    #[inline]
+    pub fn target_velocity(&self) -> T {
+        self.target_velocity
+    }
+
+    #[inline]
+    pub fn at_target(&self) -> bool {
+        self.value.distance(&self.target) <= SNAP_DISTANCE
+            && self.velocity.distance(&self.target_velocity) <= SNAP_DISTANCE
+    }
+}
+
+impl<T> Default for SmoothSpring<T>
+where
+    T: Smoothable + Add<Output = T> + Sub<Output = T> + Mul<f32, Output = T>,
+{
+    fn default() -> Self {
+        Self::new(T::default(), 0.1)
+    }
+}
+
+impl<T: Smoothable> Visit for SmoothSpring<T> {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.value.visit("Value", visitor)?;
+        self.velocity.visit("Velocity", visitor)?;
+        self.target.visit("Target", visitor)?;
+        self.target_velocity.visit("TargetVelocity", visitor)?;
+        self.half_life.visit("HalfLife", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        algebra::{UnitQuaternion, Vector3},
+        math::smooth::{SmoothQuat, SmoothSpring, SmoothValue, SmoothVec3},
+        visitor::{Visit, Visitor},
+    };
+
+    const DT: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn test_smooth_value_frame_rate_independence() {
+        let mut once = SmoothValue::new(0.0, 0.2);
+        once.set_target(10.0);
+        let mut stepped = once;
+
+        once.update(10.0 * DT);
+        for _ in 0..10 {
+            stepped.update(DT);
+        }
+        assert!((once.value() - stepped.value()).abs() < 1.0e-4);
+
+        // Half of the distance is covered in half-life.
+        let mut value = SmoothValue::new(0.0, 0.5);
+        value.set_target(1.0).update(0.5);
+        assert!((value.value() - 0.5).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_smooth_vec_and_quat_frame_rate_independence() {
+        let mut once = SmoothVec3::new(Vector3::new(1.0, 2.0, 3.0), 0.1);
+        once.set_target(Vector3::new(-4.0, 0.0, 8.0));
+        let mut stepped = once;
+        once.update(10.0 * DT);
+        for _ in 0..10 {
+            stepped.update(DT);
+        }
+        assert!(once.value().metric_distance(&stepped.value()) < 1.0e-4);
+
+        let mut once = SmoothQuat::new(UnitQuaternion::identity(), 0.1);
+        once.set_target(UnitQuaternion::from_euler_angles(0.5, 2.5, -1.0));
+        let mut stepped = once;
+        once.update(10.0 * DT);
+        for _ in 0..10 {
+            stepped.update(DT);
+        }
+        assert!(once.value().angle_to(&stepped.value()) < 1.0e-3);
+    }
+
+    #[test]
+    fn test_smooth_value_large_dt() {
+        let mut value = SmoothValue::new(0.0, 0.1);
+        value.set_target(1.0).update(1000.0);
+        assert_eq!(value.value(), 1.0);
+        assert!(value.at_target());
+
+        // Exponential smoothing never overshoots.
+        let mut value = SmoothVec3::new(Vector3::default(), 0.1);
+        value.set_target(Vector3::new(1.0, 0.0, 0.0));
+        for &dt in [0.01, 0.3, 5.0, f32::MAX, f32::INFINITY].iter() {
+            value.update(dt);
+            assert!(value.value().x <= 1.0);
+        }
+        assert_eq!(value.value(), value.target());
+
+        // Negative time steps are ignored.
+        let mut value = SmoothValue::new(0.0, 0.1);
+        value.set_target(1.0).update(-1.0);
+        assert_eq!(value.value(), 0.0);
+    }
+
+    #[test]
+    fn test_spring_frame_rate_independence() {
+        let mut once = SmoothSpring::new(Vector3::new(0.0, 1.0, 0.0), 0.25);
+        once.set_target(Vector3::new(5.0, 0.0, 0.0))
+            .set_target_velocity(Vector3::new(0.0, 0.0, 2.0));
+        let mut stepped = once;
+
+        once.update(10.0 * DT);
+        for _ in 0..10 {
+            stepped.update(DT);
+        }
+        assert!(once.value().metric_distance(&stepped.value()) < 1.0e-4);
+        assert!(once.velocity().metric_distance(&stepped.velocity()) < 1.0e-3);
+        assert!(once.target().metric_distance(&stepped.target()) < 1.0e-5);
+
+        // Critically damped spring does not oscillate around a static target.
+        let mut spring = SmoothSpring::new(0.0, 0.1);
+        spring.set_target(1.0);
+        for _ in 0..100 {
+            spring.update(DT);
+            assert!(spring.value() <= 1.0);
+        }
+
+        // Large time step makes the spring arrive at the moving target.
+        let mut spring = SmoothSpring::new(0.0, 0.1);
+        spring
+            .set_target(1.0)
+            .set_target_velocity(2.0)
+            .update(1000.0);
+        assert_eq!(spring.value(), spring.target());
+        assert_eq!(spring.velocity(), 2.0);
+    }
+
+    #[test]
+    fn test_smooth_visit() {
+        let mut value = SmoothQuat::new(UnitQuaternion::identity(), 0.3);
+        value.set_target(UnitQuaternion::from_euler_angles(0.1, 0.2, 0.3));
+        let mut spring = SmoothSpring::new(Vector3::new(1.0, 2.0, 3.0), 0.2);
+        spring
+            .set_target_velocity(Vector3::new(0.0, 1.0, 0.0))
+            .update(DT);
+
+        let path = std::env::temp_dir().join("rg3d_smooth_test.bin");
+        let mut visitor = Visitor::new();
+        value.visit("Value", &mut visitor).unwrap();
+        spring.visit("Spring", &mut visitor).unwrap();
+        visitor.save_binary(&path).unwrap();
+
+        let mut visitor = Visitor::load_from_memory(std::fs::read(&path).unwrap()).unwrap();
+        let mut loaded_value = SmoothQuat::default();
+        loaded_value.visit("Value", &mut visitor).unwrap();
+        let mut loaded_spring = SmoothSpring::default();
+        loaded_spring.visit("Spring", &mut visitor).unwrap();
+        assert_eq!(loaded_value, value);
+        assert_eq!(loaded_spring, spring);
+    }
+}
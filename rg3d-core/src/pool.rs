@@ -19,6 +19,13 @@
 //! load portions of data into its cache piece by piece, it will be free from any
 //! indirections that might cause cache invalidation. This is the so called cache
 //! friendliness.
+//!
+//! # Debugging
+//!
+//! In debug builds every record can hold an optional debug name (see [`Pool::set_debug_name`]),
+//! the name of a freed object is remembered and shown in panic messages caused by stale handles.
+//! If `pool_free_location` feature is enabled, the messages will also contain the location of the
+//! code that freed the object. None of this information exists in release builds.
 
 #![allow(clippy::unneeded_field_pattern)]
 
@@ -219,6 +226,74 @@ struct PoolRecord<T: Sized> {
     generation: u32,
     /// Actual payload.
     payload: Option<T>,
+    /// Debug information that is shown in panic messages caused by invalid handles.
+    #[cfg(debug_assertions)]
+    debug: RecordDebugInfo,
+}
+
+#[cfg(debug_assertions)]
+#[derive(Debug, Default, Clone)]
+struct RecordDebugInfo {
+    /// Name of an object that occupies the record.
+    name: Option<String>,
+    /// Name of an object that was freed last at the record.
+    freed_name: Option<String>,
+    /// Location of the code that freed the last object at the record.
+    #[cfg(feature = "pool_free_location")]
+    freed_at: Option<&'static std::panic::Location<'static>>,
+}
+
+#[cfg(debug_assertions)]
+impl RecordDebugInfo {
+    #[cfg_attr(feature = "pool_free_location", track_caller)]
+    fn on_free(&mut self) {
+        self.freed_name = self.name.take();
+        #[cfg(feature = "pool_free_location")]
+        {
+            self.freed_at = Some(std::panic::Location::caller());
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+impl Display for RecordDebugInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(name) = self.name.as_ref() {
+            write!(f, " Record is occupied by {:?}.", name)?;
+        }
+        if let Some(name) = self.freed_name.as_ref() {
+            write!(f, " Last freed object of the record is {:?}.", name)?;
+        }
+        #[cfg(feature = "pool_free_location")]
+        if let Some(location) = self.freed_at {
+            write!(f, " Last object of the record was freed at {}.", location)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T> PoolRecord<T> {
+    fn new(generation: u32, payload: Option<T>) -> Self {
+        Self {
+            generation,
+            payload,
+            #[cfg(debug_assertions)]
+            debug: Default::default(),
+        }
+    }
+
+    // Diagnostic information for panic messages, it is empty in release builds.
+    #[cold]
+    fn diagnostics(&self) -> String {
+        #[cfg(debug_assertions)]
+        {
+            self.debug.to_string()
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            String::new()
+        }
+    }
 }
 
 impl<T: PartialEq> PartialEq for PoolRecord<T> {
@@ -229,10 +304,7 @@ impl<T: PartialEq> PartialEq for PoolRecord<T> {
 
 impl<T> Default for PoolRecord<T> {
     fn default() -> Self {
-        Self {
-            generation: INVALID_GENERATION,
-            payload: None,
-        }
+        Self::new(INVALID_GENERATION, None)
     }
 }
 
@@ -343,6 +415,8 @@ impl<T: Clone> Clone for PoolRecord<T> {
         Self {
             generation: self.generation,
             payload: self.payload.clone(),
+            #[cfg(debug_assertions)]
+            debug: self.debug.clone(),
         }
     }
 }
@@ -458,6 +532,10 @@ impl<T> Pool<T> {
 
                     record.generation = generation;
                     record.payload = Some(payload);
+                    #[cfg(debug_assertions)]
+                    {
+                        record.debug.name = None;
+                    }
 
                     Ok(Handle::new(index, generation))
                 }
@@ -465,10 +543,7 @@ impl<T> Pool<T> {
             None => {
                 // Spawn missing records to fill gaps.
                 for i in self.records_len()..index {
-                    self.records.push(PoolRecord {
-                        generation: 1,
-                        payload: None,
-                    });
+                    self.records.push(PoolRecord::new(1, None));
                     self.free_stack.push(i);
                 }
 
@@ -478,10 +553,8 @@ impl<T> Pool<T> {
                     desired_generation
                 };
 
-                self.records.push(PoolRecord {
-                    generation,
-                    payload: Some(payload),
-                });
+                self.records
+                    .push(PoolRecord::new(generation, Some(payload)));
 
                 Ok(Handle::new(index, generation))
             }
@@ -516,6 +589,10 @@ impl<T> Pool<T> {
 
             record.generation = generation;
             record.payload.replace(payload);
+            #[cfg(debug_assertions)]
+            {
+                record.debug.name = None;
+            }
             handle
         } else {
             // No free records, create new one
@@ -529,12 +606,8 @@ impl<T> Pool<T> {
 
             let payload = callback(handle);
 
-            let record = PoolRecord {
-                generation,
-                payload: Some(payload),
-            };
-
-            self.records.push(record);
+            self.records
+                .push(PoolRecord::new(generation, Some(payload)));
 
             handle
         }
@@ -571,6 +644,10 @@ impl<T> Pool<T> {
 
             record.generation = generation;
             record.payload.replace(payload);
+            #[cfg(debug_assertions)]
+            {
+                record.debug.name = None;
+            }
             handle
         } else {
             // No free records, create new one
@@ -584,12 +661,8 @@ impl<T> Pool<T> {
 
             let payload = callback(handle).await;
 
-            let record = PoolRecord {
-                generation,
-                payload: Some(payload),
-            };
-
-            self.records.push(record);
+            self.records
+                .push(PoolRecord::new(generation, Some(payload)));
 
             handle
         }
@@ -610,12 +683,18 @@ impl<T> Pool<T> {
                 if let Some(ref payload) = record.payload {
                     payload
                 } else {
-                    panic!("Attempt to borrow destroyed object at {:?} handle.", handle);
+                    panic!(
+                        "Attempt to borrow destroyed object at {:?} handle.{}",
+                        handle,
+                        record.diagnostics()
+                    );
                 }
             } else {
                 panic!(
-                    "Attempt to use dangling handle {:?}. Record has generation {}!",
-                    handle, record.generation
+                    "Attempt to use dangling handle {:?}. Record has generation {}!{}",
+                    handle,
+                    record.generation,
+                    record.diagnostics()
                 );
             }
         } else {
@@ -653,10 +732,19 @@ impl<T> Pool<T> {
                 if let Some(ref mut payload) = record.payload {
                     payload
                 } else {
-                    panic!("Attempt to borrow destroyed object at {:?} handle.", handle);
+                    panic!(
+                        "Attempt to borrow destroyed object at {:?} handle.{}",
+                        handle,
+                        record.diagnostics()
+                    );
                 }
             } else {
-                panic!("Attempt to borrow object using dangling handle {:?}. Record has {} generation!", handle, record.generation);
+                panic!(
+                    "Attempt to borrow object using dangling handle {:?}. Record has {} generation!{}",
+                    handle,
+                    record.generation,
+                    record.diagnostics()
+                );
             }
         } else {
             panic!(
@@ -846,22 +934,31 @@ impl<T> Pool<T> {
     ///
     /// Panics if the given handle is invalid.
     #[inline]
+    #[cfg_attr(all(debug_assertions, feature = "pool_free_location"), track_caller)]
     pub fn free(&mut self, handle: Handle<T>) -> T {
         let index = usize::try_from(handle.index).expect("index overflowed usize");
         if let Some(record) = self.records.get_mut(index) {
             if record.generation == handle.generation {
-                // Remember this index as free
-                self.free_stack.push(handle.index);
                 // Return current payload.
                 if let Some(payload) = record.payload.take() {
+                    // Remember this index as free
+                    self.free_stack.push(handle.index);
+                    #[cfg(debug_assertions)]
+                    record.debug.on_free();
                     payload
                 } else {
-                    panic!("Attempt to double free object at handle {:?}!", handle);
+                    panic!(
+                        "Attempt to double free object at handle {:?}!{}",
+                        handle,
+                        record.diagnostics()
+                    );
                 }
             } else {
                 panic!(
-                    "Attempt to free object using dangling handle {:?}! Record generation is {}",
-                    handle, record.generation
+                    "Attempt to free object using dangling handle {:?}! Record generation is {}.{}",
+                    handle,
+                    record.generation,
+                    record.diagnostics()
                 );
             }
         } else {
@@ -901,14 +998,17 @@ impl<T> Pool<T> {
                     (ticket, payload)
                 } else {
                     panic!(
-                        "Attempt to take already taken object at handle {:?}!",
-                        handle
+                        "Attempt to take already taken object at handle {:?}!{}",
+                        handle,
+                        record.diagnostics()
                     );
                 }
             } else {
                 panic!(
-                    "Attempt to take object using dangling handle {:?}! Record generation is {}",
-                    handle, record.generation
+                    "Attempt to take object using dangling handle {:?}! Record generation is {}.{}",
+                    handle,
+                    record.generation,
+                    record.diagnostics()
                 );
             }
         } else {
@@ -1054,13 +1154,60 @@ impl<T> Pool<T> {
 
                 record.payload.replace(payload)
             } else {
-                panic!("Attempt to replace object in pool using dangling handle! Handle is {:?}, but pool record has {} generation", handle, record.generation);
+                panic!(
+                    "Attempt to replace object in pool using dangling handle! Handle is {:?}, but pool record has {} generation.{}",
+                    handle,
+                    record.generation,
+                    record.diagnostics()
+                );
             }
         } else {
             None
         }
     }
 
+    /// Sets debug name of an object at the given handle. The name is shown in panic messages
+    /// caused by invalid handles, the name of a freed object is kept until the next object is
+    /// freed at the same record. Does nothing if the handle is invalid or in release builds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use rg3d_core::pool::Pool;
+    /// let mut pool = Pool::<u32>::new();
+    /// let handle = pool.spawn(123);
+    /// pool.set_debug_name(handle, "Counter");
+    /// ```
+    #[inline]
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub fn set_debug_name(&mut self, handle: Handle<T>, name: &str) {
+        #[cfg(debug_assertions)]
+        if let Some(record) = self.records_get_mut(handle.index) {
+            if record.generation == handle.generation && record.payload.is_some() {
+                let debug_name = record.debug.name.get_or_insert_with(String::new);
+                debug_name.clear();
+                debug_name.push_str(name);
+            }
+        }
+    }
+
+    /// Returns debug name of an object at the given handle. Always returns `None` in release
+    /// builds.
+    #[inline]
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    pub fn debug_name(&self, handle: Handle<T>) -> Option<&str> {
+        #[cfg(debug_assertions)]
+        {
+            self.records_get(handle.index)
+                .filter(|r| r.generation == handle.generation && r.payload.is_some())
+                .and_then(|r| r.debug.name.as_deref())
+        }
+        #[cfg(not(debug_assertions))]
+        {
+            None
+        }
+    }
+
     /// Checks if given handle "points" to some object.
     ///
     /// # Example
@@ -1449,4 +1596,26 @@ mod test {
         assert_eq!(pool.spawn(Payload), Handle::new(1, 2));
         assert_eq!(pool.spawn(Payload), Handle::new(0, 2));
     }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn pool_debug_name() {
+        let mut pool = Pool::new();
+        let a = pool.spawn(1u32);
+        pool.set_debug_name(a, "Player");
+        assert_eq!(pool.debug_name(a), Some("Player"));
+
+        pool.free(a);
+        assert_eq!(pool.debug_name(a), None);
+
+        let b = pool.spawn(2u32);
+        assert_eq!(pool.debug_name(b), None);
+
+        let message = std::panic::catch_unwind(|| *pool.borrow(a))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.contains(&format!("{:?}", a)));
+        assert!(message.contains("\"Player\""));
+    }
 }
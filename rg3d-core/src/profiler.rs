@@ -1,9 +1,13 @@
 //! Built-in scoped profiler. You must compile with feature "enable_profiler" to
 //! force profiler gather info! It is disabled by default because it is not cheap
 //! and takes 3-5% of performance for internal needs.
+//!
+//! See [`frame`] module for lightweight per-frame profiler.
 
 #![allow(dead_code)]
 
+pub mod frame;
+
 use fxhash::{FxHashMap, FxHashSet, FxHasher};
 use std::{
     fmt,
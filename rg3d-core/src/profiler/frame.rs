@@ -0,0 +1,385 @@
+//! Lightweight per-frame hierarchical profiler.
+//!
+//! Use [`crate::profile_scope`] macro to mark a scope, every marked scope records its begin and end
+//! on a thread-local stack, so nested scopes form a tree. Repeated scopes with the same name and
+//! the same parent are collapsed into a single node, which accumulates time and call count. Call
+//! [`next_frame`] once per frame (the engine does it at the beginning of `Engine::update`) to
+//! finish current frame and make its report available via [`last_frame_report`].
+//!
+//! # Overhead
+//!
+//! `profile_scope!` expands to nothing if the crate was compiled without `enable_profiler` feature.
+//! With the feature on, profiling can be turned off in runtime via [`set_enabled`], in this case
+//! each scope costs a single atomic load.
+//!
+//! # Limitations
+//!
+//! Only scopes of the thread that calls [`next_frame`] are included in the report. Scopes that are
+//! open when [`next_frame`] is called (i.e. the call is made inside a marked scope) are discarded.
+
+use std::{
+    cell::RefCell,
+    fmt::{self, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(cfg!(feature = "enable_profiler"));
+
+lazy_static! {
+    static ref LAST_REPORT: Mutex<Option<FrameReport>> = Mutex::new(None);
+}
+
+thread_local! {
+    static RECORDER: RefCell<FrameRecorder> = RefCell::new(FrameRecorder::default());
+}
+
+/// Enables or disables profiling in runtime. Enabled by default if `enable_profiler` feature
+/// is on.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns true if profiling is enabled.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+struct RecordNode {
+    name: &'static str,
+    parent: usize,
+    children: Vec<usize>,
+    total_time: f64,
+    call_count: u32,
+}
+
+struct FrameRecorder {
+    // First node is the root of the frame.
+    nodes: Vec<RecordNode>,
+    current: usize,
+    frame_index: u64,
+    frame_start: instant::Instant,
+}
+
+impl Default for FrameRecorder {
+    fn default() -> Self {
+        Self {
+            nodes: vec![RecordNode {
+                name: "Frame",
+                parent: 0,
+                children: Default::default(),
+                total_time: 0.0,
+                call_count: 0,
+            }],
+            current: 0,
+            frame_index: 0,
+            frame_start: instant::Instant::now(),
+        }
+    }
+}
+
+impl FrameRecorder {
+    fn enter(&mut self, name: &'static str) -> usize {
+        let nodes = &self.nodes;
+        let existing = nodes[self.current]
+            .children
+            .iter()
+            .cloned()
+            .find(|&child| nodes[child].name == name);
+
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                let index = self.nodes.len();
+                self.nodes.push(RecordNode {
+                    name,
+                    parent: self.current,
+                    children: Default::default(),
+                    total_time: 0.0,
+                    call_count: 0,
+                });
+                self.nodes[self.current].children.push(index);
+                index
+            }
+        };
+
+        self.current = index;
+        index
+    }
+
+    fn leave(&mut self, index: usize, elapsed: f64) {
+        let node = &mut self.nodes[index];
+        node.total_time += elapsed;
+        node.call_count += 1;
+        self.current = node.parent;
+    }
+
+    fn make_report(&self, index: usize) -> ScopeReport {
+        let node = &self.nodes[index];
+        let children = node
+            .children
+            .iter()
+            .map(|&child| self.make_report(child))
+            .collect::<Vec<_>>();
+        let children_time = children.iter().map(|c| c.total_time).sum::<f64>();
+        ScopeReport {
+            name: node.name,
+            total_time: node.total_time,
+            self_time: (node.total_time - children_time).max(0.0),
+            call_count: node.call_count,
+            children,
+        }
+    }
+
+    fn finish_frame(&mut self) -> FrameReport {
+        let report = FrameReport {
+            frame_time: (instant::Instant::now() - self.frame_start).as_secs_f64(),
+            scopes: self.nodes[0]
+                .children
+                .iter()
+                .map(|&child| self.make_report(child))
+                .collect(),
+        };
+
+        let frame_index = self.frame_index.wrapping_add(1);
+        *self = Default::default();
+        self.frame_index = frame_index;
+
+        report
+    }
+}
+
+/// A guard that closes profiling scope when dropped. Use [`crate::profile_scope`] macro instead
+/// of creating the guard manually.
+pub struct ScopeGuard {
+    index: usize,
+    frame_index: u64,
+    start_time: instant::Instant,
+}
+
+/// Opens new profiling scope with given name, the scope will be closed when returned guard is
+/// dropped. Returns `None` if profiling is disabled.
+#[inline]
+pub fn enter_scope(name: &'static str) -> Option<ScopeGuard> {
+    if is_enabled() {
+        RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            Some(ScopeGuard {
+                index: recorder.enter(name),
+                frame_index: recorder.frame_index,
+                start_time: instant::Instant::now(),
+            })
+        })
+    } else {
+        None
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        let elapsed = (instant::Instant::now() - self.start_time).as_secs_f64();
+        RECORDER.with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            // Ignore scopes that were opened in previous frame.
+            if recorder.frame_index == self.frame_index {
+                recorder.leave(self.index, elapsed);
+            }
+        })
+    }
+}
+
+/// Finishes current frame on calling thread and stores its report, the report then can be
+/// fetched using [`last_frame_report`]. Does nothing if profiling is disabled.
+pub fn next_frame() {
+    if !is_enabled() {
+        return;
+    }
+
+    let report = RECORDER.with(|recorder| recorder.borrow_mut().finish_frame());
+
+    *LAST_REPORT.lock().unwrap() = Some(report);
+}
+
+/// Returns report of last finished frame. Returns `None` if there was no finished frame yet.
+pub fn last_frame_report() -> Option<FrameReport> {
+    LAST_REPORT.lock().unwrap().clone()
+}
+
+/// Timings of a single scope in a frame. All times are in seconds.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScopeReport {
+    /// Name of the scope.
+    pub name: &'static str,
+    /// Time spent in the scope including its child scopes.
+    pub total_time: f64,
+    /// Time spent in the scope excluding its child scopes.
+    pub self_time: f64,
+    /// How many times the scope was entered during the frame.
+    pub call_count: u32,
+    /// Child scopes.
+    pub children: Vec<ScopeReport>,
+}
+
+impl ScopeReport {
+    fn sort_by_self_time(&mut self) {
+        sort_scopes(&mut self.children);
+    }
+
+    fn collapse(&self, flat: &mut Vec<ScopeReport>) {
+        if let Some(existing) = flat.iter_mut().find(|s| s.name == self.name) {
+            existing.total_time += self.total_time;
+            existing.self_time += self.self_time;
+            existing.call_count += self.call_count;
+        } else {
+            flat.push(ScopeReport {
+                name: self.name,
+                total_time: self.total_time,
+                self_time: self.self_time,
+                call_count: self.call_count,
+                children: Default::default(),
+            });
+        }
+
+        for child in self.children.iter() {
+            child.collapse(flat);
+        }
+    }
+
+    fn print(&self, buffer: &mut String, offset: usize) -> fmt::Result {
+        writeln!(
+            buffer,
+            "{}{} - self {:.3} ms, total {:.3} ms, {} calls",
+            "  ".repeat(offset),
+            self.name,
+            self.self_time * 1000.0,
+            self.total_time * 1000.0,
+            self.call_count
+        )?;
+
+        for child in self.children.iter() {
+            child.print(buffer, offset + 1)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn sort_scopes(scopes: &mut [ScopeReport]) {
+    scopes.sort_by(|a, b| {
+        b.self_time
+            .partial_cmp(&a.self_time)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    for scope in scopes.iter_mut() {
+        scope.sort_by_self_time();
+    }
+}
+
+/// Hierarchical report of a single frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameReport {
+    /// Time between two consecutive [`next_frame`] calls in seconds.
+    pub frame_time: f64,
+    /// Top-level scopes of the frame.
+    pub scopes: Vec<ScopeReport>,
+}
+
+impl FrameReport {
+    /// Sorts scopes on each level of the tree by self time in descending order.
+    pub fn sort_by_self_time(&mut self) {
+        sort_scopes(&mut self.scopes);
+    }
+
+    /// Collapses every scope with the same name into single entry, no matter where it is located
+    /// in the tree. Returns flat list of scopes sorted by self time in descending order. Total time
+    /// of recursive scopes will be counted multiple times.
+    pub fn collapsed(&self) -> Vec<ScopeReport> {
+        let mut flat = Vec::new();
+        for scope in self.scopes.iter() {
+            scope.collapse(&mut flat);
+        }
+        sort_scopes(&mut flat);
+        flat
+    }
+
+    /// Prints the report as an indented tree.
+    pub fn print(&self, buffer: &mut String) -> fmt::Result {
+        writeln!(buffer, "Frame - {:.3} ms", self.frame_time * 1000.0)?;
+        for scope in self.scopes.iter() {
+            scope.print(buffer, 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Marks current scope for per-frame profiler, see [`crate::profiler::frame`] module docs.
+#[cfg(feature = "enable_profiler")]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope_guard = $crate::profiler::frame::enter_scope($name);
+    };
+}
+
+/// Marks current scope for per-frame profiler, see [`crate::profiler::frame`] module docs.
+#[cfg(not(feature = "enable_profiler"))]
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {};
+}
+
+#[cfg(test)]
+mod test {
+    use crate::profiler::frame::{enter_scope, set_enabled, RECORDER};
+
+    #[test]
+    fn test_frame_profiler() {
+        set_enabled(true);
+
+        {
+            let _update = enter_scope("Update");
+            for _ in 0..3 {
+                let _physics = enter_scope("Physics");
+            }
+            let _animation = enter_scope("Animation");
+        }
+        {
+            let _render = enter_scope("Render");
+            let _physics = enter_scope("Physics");
+        }
+
+        let mut report = RECORDER.with(|recorder| recorder.borrow_mut().finish_frame());
+
+        assert_eq!(report.scopes.len(), 2);
+        let update = &report.scopes[0];
+        assert_eq!(update.name, "Update");
+        assert_eq!(update.call_count, 1);
+        assert_eq!(update.children.len(), 2);
+        assert_eq!(update.children[0].name, "Physics");
+        assert_eq!(update.children[0].call_count, 3);
+        assert!(update.self_time <= update.total_time);
+
+        let collapsed = report.collapsed();
+        assert_eq!(collapsed.len(), 4);
+        let physics = collapsed.iter().find(|s| s.name == "Physics").unwrap();
+        assert_eq!(physics.call_count, 4);
+        assert!(collapsed
+            .windows(2)
+            .all(|pair| pair[0].self_time >= pair[1].self_time));
+
+        report.sort_by_self_time();
+        assert!(report
+            .scopes
+            .windows(2)
+            .all(|pair| pair[0].self_time >= pair[1].self_time));
+
+        // Scope opened in previous frame must be ignored.
+        let stale = enter_scope("Stale");
+        RECORDER.with(|recorder| recorder.borrow_mut().finish_frame());
+        drop(stale);
+        let report = RECORDER.with(|recorder| recorder.borrow_mut().finish_frame());
+        assert!(report.scopes.is_empty());
+    }
+}
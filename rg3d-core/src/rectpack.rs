@@ -92,9 +92,9 @@ where
     /// Tries to find free place to put rectangle with given size. Returns None if there insufficient
     /// space.
     pub fn find_free(&mut self, w: T, h: T) -> Option<Rect<T>> {
-        if self.unvisited.is_empty() {
-            self.unvisited.push(self.root);
-        }
+        // Always start from root, otherwise free space in already visited nodes will be missed.
+        self.unvisited.clear();
+        self.unvisited.push(self.root);
 
         while let Some(node_handle) = self.unvisited.pop() {
             let node = self.nodes.borrow_mut(node_handle);
@@ -889,7 +889,12 @@ impl Visitor {
     }
 
     pub async fn load_binary<P: AsRef<Path>>(path: P) -> Result<Self, VisitError> {
-        let mut reader = Cursor::new(io::load_file(path).await?);
+        Self::load_from_memory(io::load_file(path).await?)
+    }
+
+    /// Creates visitor from binary data that was previously saved by [`Self::save_binary`].
+    pub fn load_from_memory(data: Vec<u8>) -> Result<Self, VisitError> {
+        let mut reader = Cursor::new(data);
         let mut magic: [u8; 4] = Default::default();
         reader.read_exact(&mut magic)?;
         if !magic.eq(Self::MAGIC.as_bytes()) {
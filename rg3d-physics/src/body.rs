@@ -10,6 +10,7 @@ use rapier3d::dynamics::{IslandManager, RigidBody, RigidBodySet};
 use rg3d_core::{uuid::Uuid, BiDirHashMap};
 
 /// See module docs.
+#[derive(Clone)]
 pub struct RigidBodyContainer {
     pub(super) set: RigidBodySet,
     pub(super) handle_map: BiDirHashMap<RigidBodyHandle, NativeRigidBodyHandle>,
@@ -14,6 +14,7 @@ use rapier3d::{
 use rg3d_core::{uuid::Uuid, BiDirHashMap};
 
 /// See module docs.
+#[derive(Clone)]
 pub struct ColliderContainer {
     pub(super) set: ColliderSet,
     pub(super) handle_map: BiDirHashMap<ColliderHandle, NativeColliderHandle>,
@@ -4,6 +4,9 @@
 //! compatibility of Rapier.
 
 use crate::{
+    joint::JointBreakLimits,
+    material::{collider_tag, CombineRule, PhysicsMaterial},
+    solver::SolverSettings,
     AngVector, ColliderHandle, Isometry, JointHandle, NativeColliderHandle, NativeJointHandle,
     NativeRigidBodyHandle, Point, RigidBodyHandle, Rotation, Translation, Vector,
 };
@@ -595,6 +598,9 @@ where
     pub density: Option<f32>,
     #[inspect(min_value = 0.0, step = 0.05)]
     pub restitution: f32,
+    pub friction_combine_rule: CombineRule,
+    pub restitution_combine_rule: CombineRule,
+    pub tag: u32,
     pub is_sensor: bool,
     pub translation: Vector<f32>,
     pub rotation: Rotation<f32>,
@@ -638,6 +644,9 @@ where
             friction: 0.5,
             density: None,
             restitution: 0.0,
+            friction_combine_rule: Default::default(),
+            restitution_combine_rule: Default::default(),
+            tag: 0,
             is_sensor: false,
             translation: Default::default(),
             #[cfg(feature = "dim3")]
@@ -668,6 +677,9 @@ where
             friction: collider.friction(),
             density: collider.density(),
             restitution: collider.restitution(),
+            friction_combine_rule: collider.friction_combine_rule().into(),
+            restitution_combine_rule: collider.restitution_combine_rule().into(),
+            tag: collider_tag(collider),
             is_sensor: collider.is_sensor(),
             translation: collider.position_wrt_parent().unwrap().translation.vector,
             rotation: collider.position_wrt_parent().unwrap().rotation,
@@ -681,6 +693,9 @@ where
         let mut builder = ColliderBuilder::new(self.shape.into_collider_shape())
             .friction(self.friction)
             .restitution(self.restitution)
+            .friction_combine_rule(self.friction_combine_rule.into())
+            .restitution_combine_rule(self.restitution_combine_rule.into())
+            .user_data(self.tag as u128)
             .position(Isometry {
                 translation: Translation {
                     vector: self.translation,
@@ -701,6 +716,17 @@ where
         }
         (builder.build(), self.parent)
     }
+
+    /// Returns physics material of the descriptor.
+    pub fn material(&self) -> PhysicsMaterial {
+        PhysicsMaterial {
+            friction: self.friction,
+            restitution: self.restitution,
+            friction_combine_rule: self.friction_combine_rule,
+            restitution_combine_rule: self.restitution_combine_rule,
+            tag: self.tag,
+        }
+    }
 }
 
 impl<R> Visit for ColliderDesc<R>
@@ -720,6 +746,13 @@ where
         let _ = self.collision_groups.visit("CollisionGroups", visitor);
         let _ = self.solver_groups.visit("SolverGroups", visitor);
         self.density.visit("Density", visitor)?;
+        let _ = self
+            .friction_combine_rule
+            .visit("FrictionCombineRule", visitor);
+        let _ = self
+            .restitution_combine_rule
+            .visit("RestitutionCombineRule", visitor);
+        let _ = self.tag.visit("Tag", visitor);
 
         visitor.leave_region()
     }
@@ -1067,6 +1100,9 @@ where
     pub body1: R,
     pub body2: R,
     pub params: JointParamsDesc,
+    #[visit(optional)] // Backward compatibility.
+    #[inspect(skip)]
+    pub break_limits: JointBreakLimits,
 }
 
 impl<R> JointDesc<R>
@@ -1079,6 +1115,7 @@ where
             body1: handle_map.key_of(&joint.body1).cloned().unwrap(),
             body2: handle_map.key_of(&joint.body2).cloned().unwrap(),
             params: JointParamsDesc::from_params(&joint.params),
+            break_limits: Default::default(),
         }
     }
 }
@@ -1087,6 +1124,7 @@ where
 #[doc(hidden)]
 pub struct PhysicsDesc {
     pub integration_parameters: IntegrationParametersDesc,
+    pub solver_settings: SolverSettings,
     pub colliders: Vec<ColliderDesc<RigidBodyHandle>>,
     pub bodies: Vec<RigidBodyDesc<ColliderHandle>>,
     pub gravity: Vector<f32>,
@@ -1102,6 +1140,7 @@ impl Visit for PhysicsDesc {
 
         self.integration_parameters
             .visit("IntegrationParameters", visitor)?;
+        let _ = self.solver_settings.visit("SolverSettings", visitor);
         self.gravity.visit("Gravity", visitor)?;
         self.colliders.visit("Colliders", visitor)?;
         self.bodies.visit("Bodies", visitor)?;
@@ -1,24 +1,145 @@
 //! A container for joints.
+//!
+//! # Breakable joints
+//!
+//! Every joint can have [`JointBreakLimits`] - max force and torque that the joint can withstand.
+//! After every substep the world compares impulses applied by the solver to keep the joint
+//! together with the limits, and removes every joint that was overloaded. Every removal is
+//! reported to [`crate::PhysicsWorld::joint_event_handler`] as [`JointBreakEvent`] during the
+//! step, so it can be processed together with contact and intersection events. Limits are stored
+//! in the container, so they are saved and restored along with the joints.
 
 use crate::{body::RigidBodyContainer, JointHandle, NativeJointHandle, RigidBodyHandle};
+use fxhash::FxHashMap;
 #[cfg(feature = "dim2")]
 use rapier2d::dynamics::{IslandManager, Joint, JointParams, JointSet};
 #[cfg(feature = "dim3")]
 use rapier3d::dynamics::{IslandManager, Joint, JointParams, JointSet};
-use rg3d_core::{uuid::Uuid, BiDirHashMap};
+use rg3d_core::{
+    inspect::{Inspect, PropertyInfo},
+    uuid::Uuid,
+    visitor::prelude::*,
+    BiDirHashMap,
+};
+
+/// Max force and torque that a joint can withstand before it breaks, see module docs.
+#[derive(Copy, Clone, Debug, PartialEq, Visit, Inspect)]
+pub struct JointBreakLimits {
+    /// Whether the joint can break at all. Default is `true`, but default limits are infinite.
+    pub enabled: bool,
+    /// Max linear force (in newtons) that the joint can apply to keep bodies together. Default is
+    /// [`f32::MAX`].
+    #[inspect(min_value = 0.0)]
+    pub max_force: f32,
+    /// Max torque (in newton-meters) that the joint can apply to keep bodies aligned. Default is
+    /// [`f32::MAX`].
+    #[inspect(min_value = 0.0)]
+    pub max_torque: f32,
+}
+
+impl Default for JointBreakLimits {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_force: f32::MAX,
+            max_torque: f32::MAX,
+        }
+    }
+}
+
+impl JointBreakLimits {
+    /// Creates new limits with given max force and torque.
+    pub fn new(max_force: f32, max_torque: f32) -> Self {
+        Self {
+            enabled: true,
+            max_force,
+            max_torque,
+        }
+    }
+
+    /// Returns `true` if the limits can ever break a joint.
+    pub fn is_breakable(&self) -> bool {
+        self.enabled && (self.max_force < f32::MAX || self.max_torque < f32::MAX)
+    }
+
+    /// Returns `true` if given force or torque exceeds the limits.
+    pub fn is_exceeded(&self, force: f32, torque: f32) -> bool {
+        self.enabled && (force > self.max_force || torque > self.max_torque)
+    }
+}
+
+/// An event that is emitted when a joint breaks, see module docs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JointBreakEvent {
+    /// A handle of the joint, it is not valid anymore.
+    pub joint: JointHandle,
+    /// A handle of the first body of the joint.
+    pub body1: RigidBodyHandle,
+    /// A handle of the second body of the joint.
+    pub body2: RigidBodyHandle,
+    /// Linear force that broke the joint.
+    pub force: f32,
+    /// Torque that broke the joint.
+    pub torque: f32,
+}
+
+/// A handler of joint events. It is implemented for `()`, which just ignores every event. The
+/// same type can implement Rapier's `EventHandler` as well to collect all events in one queue.
+pub trait JointEventHandler: Send + Sync {
+    /// Called for every joint that was broken during a step.
+    fn handle_joint_break_event(&self, event: JointBreakEvent);
+}
+
+impl JointEventHandler for () {
+    fn handle_joint_break_event(&self, _event: JointBreakEvent) {}
+}
+
+/// Returns magnitudes of linear and angular impulses that were applied by the solver to keep the
+/// joint together during the last step.
+#[cfg(feature = "dim3")]
+pub(in crate) fn joint_impulses(params: &JointParams) -> (f32, f32) {
+    match params {
+        JointParams::BallJoint(joint) => (joint.impulse.norm(), 0.0),
+        JointParams::FixedJoint(joint) => (
+            joint.impulse.fixed_rows::<3>(0).norm(),
+            joint.impulse.fixed_rows::<3>(3).norm(),
+        ),
+        JointParams::PrismaticJoint(joint) => (
+            joint.impulse.fixed_rows::<2>(0).norm(),
+            joint.impulse.fixed_rows::<3>(2).norm(),
+        ),
+        JointParams::RevoluteJoint(joint) => (
+            joint.impulse.fixed_rows::<3>(0).norm(),
+            joint.impulse.fixed_rows::<2>(3).norm(),
+        ),
+    }
+}
+
+/// Returns magnitudes of linear and angular impulses that were applied by the solver to keep the
+/// joint together during the last step.
+#[cfg(feature = "dim2")]
+pub(in crate) fn joint_impulses(params: &JointParams) -> (f32, f32) {
+    match params {
+        JointParams::BallJoint(joint) => (joint.impulse.norm(), 0.0),
+        JointParams::FixedJoint(joint) => (
+            joint.impulse.fixed_rows::<2>(0).norm(),
+            joint.impulse.z.abs(),
+        ),
+        JointParams::PrismaticJoint(joint) => (joint.impulse.x.abs(), joint.impulse.y.abs()),
+    }
+}
 
 /// See module docs.
+#[derive(Clone)]
 pub struct JointContainer {
     pub(super) set: JointSet,
     pub(super) handle_map: BiDirHashMap<JointHandle, NativeJointHandle>,
+    pub(super) break_limits: FxHashMap<JointHandle, JointBreakLimits>,
 }
 
 impl Default for JointContainer {
     fn default() -> Self {
-        Self {
-            set: JointSet::new(),
-            handle_map: Default::default(),
-        }
+        Self::new()
     }
 }
 
@@ -28,6 +149,7 @@ impl JointContainer {
         Self {
             set: JointSet::new(),
             handle_map: Default::default(),
+            break_limits: Default::default(),
         }
     }
 
@@ -44,7 +166,11 @@ impl JointContainer {
             }
         }
 
-        Ok(Self { set, handle_map })
+        Ok(Self {
+            set,
+            handle_map,
+            break_limits: Default::default(),
+        })
     }
 
     /// Adds new joint.
@@ -81,9 +207,28 @@ impl JointContainer {
             .value_of(joint_handle)
             .and_then(|&h| joints.remove(h, islands, &mut bodies.set, wake_up));
         self.handle_map.remove_by_key(joint_handle);
+        self.break_limits.remove(joint_handle);
         result
     }
 
+    /// Sets break limits of a joint, see module docs. Returns false if there is no such joint.
+    pub fn set_break_limits(&mut self, handle: &JointHandle, limits: JointBreakLimits) -> bool {
+        if self.handle_map.value_of(handle).is_none() {
+            return false;
+        }
+        if limits == JointBreakLimits::default() {
+            self.break_limits.remove(handle);
+        } else {
+            self.break_limits.insert(*handle, limits);
+        }
+        true
+    }
+
+    /// Returns break limits of a joint. Joints without explicitly set limits never break.
+    pub fn break_limits(&self, handle: &JointHandle) -> JointBreakLimits {
+        self.break_limits.get(handle).cloned().unwrap_or_default()
+    }
+
     /// Tries to borrow a joint from the container.
     pub fn get_mut(&mut self, handle: &JointHandle) -> Option<&mut Joint> {
         let joints = &mut self.set;
@@ -160,3 +305,76 @@ impl JointContainer {
         &self.set
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        joint::{JointBreakEvent, JointBreakLimits, JointEventHandler},
+        Isometry, PhysicsWorld, Vector,
+    };
+    #[cfg(feature = "dim2")]
+    use rapier2d::{
+        dynamics::{FixedJoint, RigidBodyBuilder},
+        geometry::ColliderBuilder,
+    };
+    #[cfg(feature = "dim3")]
+    use rapier3d::{
+        dynamics::{FixedJoint, RigidBodyBuilder},
+        geometry::ColliderBuilder,
+    };
+    use rg3d_core::parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[derive(Default, Clone)]
+    struct Events(Arc<Mutex<Vec<JointBreakEvent>>>);
+
+    impl JointEventHandler for Events {
+        fn handle_joint_break_event(&self, event: JointBreakEvent) {
+            self.0.lock().push(event);
+        }
+    }
+
+    // Hangs a weight of given mass under a static body on a fixed joint that can withstand 100 N
+    // and returns broken joints after a second of simulation.
+    fn hang_weight(mass: f32) -> Vec<JointBreakEvent> {
+        let mut world = PhysicsWorld::new();
+        let events = Events::default();
+        world.joint_event_handler = Box::new(events.clone());
+
+        let anchor = world.add_body(RigidBodyBuilder::new_static().build());
+        let mut offset = Vector::zeros();
+        offset.y = -1.0;
+        let weight = world.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(offset)
+                .additional_mass(mass)
+                .build(),
+        );
+        // Small collider gives the weight angular inertia, otherwise the joint can't be solved.
+        world.add_collider(ColliderBuilder::ball(0.1).build(), &weight);
+        let mut joint = FixedJoint::new(Isometry::identity(), Isometry::identity());
+        joint.local_frame1.translation.vector = offset;
+        let joint = world.add_joint(&anchor, &weight, joint);
+        assert!(world.set_joint_break_limits(&joint, JointBreakLimits::new(100.0, f32::MAX)));
+
+        for _ in 0..60 {
+            world.step();
+        }
+
+        assert_eq!(world.joints.contains(&joint), events.0.lock().is_empty());
+
+        let events = events.0.lock().clone();
+        events
+    }
+
+    #[test]
+    fn test_breakable_joint() {
+        // 5 kg weighs ~49 N, the joint holds.
+        assert!(hang_weight(5.0).is_empty());
+
+        // 20 kg weighs ~196 N, the joint breaks on the first step.
+        let events = hang_weight(20.0);
+        assert_eq!(events.len(), 1);
+        assert!(events[0].force > 100.0);
+    }
+}
@@ -6,14 +6,20 @@
 use rapier2d::{
     dynamics::{CCDSolver, IntegrationParameters, IslandManager, Joint, JointParams, RigidBody},
     geometry::{BroadPhase, Collider, InteractionGroups, NarrowPhase},
-    parry::shape::FeatureId,
+    parry::{
+        query::{self, TOIStatus},
+        shape::{Ball, Capsule, Cuboid, FeatureId, Shape},
+    },
     pipeline::{EventHandler, PhysicsPipeline, QueryPipeline},
 };
 #[cfg(feature = "dim3")]
 use rapier3d::{
     dynamics::{CCDSolver, IntegrationParameters, IslandManager, Joint, JointParams, RigidBody},
     geometry::{BroadPhase, Collider, InteractionGroups, NarrowPhase},
-    parry::shape::FeatureId,
+    parry::{
+        query::{self, TOIStatus},
+        shape::{Ball, Capsule, Cuboid, FeatureId, Shape},
+    },
     pipeline::{EventHandler, PhysicsPipeline, QueryPipeline},
 };
 
@@ -21,12 +27,15 @@ use crate::{
     body::RigidBodyContainer,
     collider::ColliderContainer,
     desc::{ColliderDesc, JointDesc, PhysicsDesc, RigidBodyDesc},
-    joint::JointContainer,
+    joint::{JointBreakEvent, JointContainer, JointEventHandler},
+    material::PhysicsMaterial,
+    solver::{SolverSettings, SubstepEventFilter},
+    statistics::PhysicsStatistics,
 };
 use fxhash::FxHashMap;
 use rg3d_core::{arrayvec::ArrayVec, instant, visitor::prelude::*, BiDirHashMap};
 use std::{
-    cell::{Cell, RefCell},
+    cell::{Cell, RefCell, RefMut},
     cmp::Ordering,
     fmt::{Debug, Display, Formatter},
     time::Duration,
@@ -36,6 +45,9 @@ pub mod body;
 pub mod collider;
 pub mod desc;
 pub mod joint;
+pub mod material;
+pub mod solver;
+pub mod statistics;
 
 #[cfg(feature = "dim3")]
 pub use rapier3d as rapier;
@@ -194,6 +206,103 @@ pub struct RayCastOptions {
     pub sort_results: bool,
 }
 
+/// A convex shape that can be used for shape casting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvexShape {
+    /// A ball with given radius.
+    Ball {
+        /// Radius of the ball.
+        radius: f32,
+    },
+    /// A box with given half extents.
+    Cuboid {
+        /// Half extents of the box along each axis.
+        half_extents: Vector<f32>,
+    },
+    /// A capsule defined by a segment and a radius.
+    Capsule {
+        /// Begin of the segment.
+        begin: Point<f32>,
+        /// End of the segment.
+        end: Point<f32>,
+        /// Radius of the capsule.
+        radius: f32,
+    },
+}
+
+impl ConvexShape {
+    fn with_shape<R, F: FnOnce(&dyn Shape) -> R>(&self, func: F) -> R {
+        match self {
+            ConvexShape::Ball { radius } => func(&Ball::new(*radius)),
+            ConvexShape::Cuboid { half_extents } => func(&Cuboid::new(*half_extents)),
+            ConvexShape::Capsule { begin, end, radius } => {
+                func(&Capsule::new(*begin, *end, *radius))
+            }
+        }
+    }
+}
+
+/// A set of options for the shape cast.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeCastOptions {
+    /// Groups to check.
+    pub groups: InteractionGroups,
+
+    /// A rigid body which colliders will be ignored. Usually it is a body of an actor that
+    /// performs the cast.
+    pub exclude_body: Option<RigidBodyHandle>,
+}
+
+/// A shape cast result.
+#[derive(Debug, Clone)]
+pub struct ShapeCastHit {
+    /// A handle of the collider that was hit.
+    pub collider: ColliderHandle,
+
+    /// A handle of the rigid body that owns the collider. Static geometry is a static rigid body
+    /// too, so this handle is always valid.
+    pub body: RigidBodyHandle,
+
+    /// Distance along the cast direction at which the shape touches the collider. It is zero if
+    /// the shape was already penetrating the collider at the initial position.
+    pub toi: f32,
+
+    /// A contact point in world coordinates, lies on the surface of the collider.
+    pub position: Point<f32>,
+
+    /// A normal of the collider's surface at the contact point in world coordinates. If the
+    /// shape was penetrating the collider, this is the direction in which the shape should be
+    /// moved to resolve the penetration.
+    pub normal: Vector<f32>,
+
+    /// True if the shape was penetrating the collider at the initial position.
+    pub penetrating: bool,
+}
+
+/// An active contact between two colliders.
+#[derive(Debug, Clone)]
+pub struct Contact {
+    /// A handle of the collider for which the contacts were requested.
+    pub collider: ColliderHandle,
+
+    /// A handle of the other collider in the contact.
+    pub other_collider: ColliderHandle,
+
+    /// Physics material of the collider.
+    pub material: PhysicsMaterial,
+
+    /// Physics material of the other collider. Its tag tells you what kind of surface is touched,
+    /// for example to pick a footstep sound.
+    pub other_material: PhysicsMaterial,
+
+    /// Contact normal in world coordinates, it points from the collider to the other collider.
+    pub normal: Vector<f32>,
+
+    /// Total normal impulse that was applied by the solver to resolve the contact during the last
+    /// substep. Divide it by the time step to get the force, it is useful to calculate damage.
+    pub impulse: f32,
+}
+
 /// Physics world.
 pub struct PhysicsWorld {
     /// Current physics pipeline.
@@ -202,6 +311,8 @@ pub struct PhysicsWorld {
     pub gravity: Vector<f32>,
     /// A set of parameters that define behavior of every rigid body.
     pub integration_parameters: IntegrationParameters,
+    /// Substeps and velocity clamps, see [`solver`] module docs.
+    pub solver_settings: SolverSettings,
     /// Broad phase performs rough intersection checks.
     pub broad_phase: BroadPhase,
     /// Narrow phase is responsible for precise contact generation.
@@ -224,10 +335,15 @@ pub struct PhysicsWorld {
     /// Event handler collects info about contacts and proximity events.
     pub event_handler: Box<dyn EventHandler>,
 
+    /// Joint event handler collects info about broken joints, see [`joint`] module docs.
+    pub joint_event_handler: Box<dyn JointEventHandler>,
+
     query: RefCell<QueryPipeline>,
 
     /// Performance statistics of a single simulation step.
     pub performance_statistics: PhysicsPerformanceStatistics,
+
+    statistics: PhysicsStatistics,
 }
 
 impl Debug for PhysicsWorld {
@@ -242,6 +358,28 @@ impl Default for PhysicsWorld {
     }
 }
 
+#[cfg(feature = "dim3")]
+fn body_angular_speed(body: &RigidBody) -> f32 {
+    body.angvel().norm()
+}
+
+#[cfg(feature = "dim2")]
+fn body_angular_speed(body: &RigidBody) -> f32 {
+    body.angvel().abs()
+}
+
+#[cfg(feature = "dim3")]
+fn scale_angvel(body: &mut RigidBody, k: f32) {
+    let angvel = body.angvel().scale(k);
+    body.set_angvel(angvel, false);
+}
+
+#[cfg(feature = "dim2")]
+fn scale_angvel(body: &mut RigidBody, k: f32) {
+    let angvel = body.angvel() * k;
+    body.set_angvel(angvel, false);
+}
+
 /// A trait for ray cast results storage. It has two implementations: Vec and ArrayVec.
 /// Latter is needed for the cases where you need to avoid runtime memory allocations
 /// and do everything on stack.
@@ -298,7 +436,7 @@ pub struct PhysicsPerformanceStatistics {
     /// A time that was needed to perform a single simulation step.
     pub step_time: Duration,
 
-    /// A time that was needed to perform all ray casts.
+    /// A time that was needed to perform all ray and shape casts.
     pub total_ray_cast_time: Cell<Duration>,
 }
 
@@ -320,17 +458,72 @@ impl PhysicsPerformanceStatistics {
     }
 }
 
+/// A snapshot of simulation state of a physics world. It contains positions and velocities of
+/// rigid bodies, colliders, joints, sleeping state and contact manifolds with their accumulated
+/// impulses, so a world restored from the snapshot will continue simulation exactly as the
+/// world from which the snapshot was taken. Shapes of colliders are shared between the snapshot
+/// and the world, so taking a snapshot is relatively cheap even for heavy triangle meshes.
+///
+/// Snapshots are useful for rollback networking, replays and "what if" simulations.
+#[derive(Clone)]
+pub struct PhysicsState {
+    gravity: Vector<f32>,
+    integration_parameters: IntegrationParameters,
+    solver_settings: SolverSettings,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    ccd_solver: CCDSolver,
+    islands: IslandManager,
+    bodies: RigidBodyContainer,
+    colliders: ColliderContainer,
+    joints: JointContainer,
+}
+
+impl Debug for PhysicsState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "PhysicsState: {} bodies, {} colliders, {} joints",
+            self.bodies.len(),
+            self.colliders.len(),
+            self.joints.len()
+        )
+    }
+}
+
+impl PhysicsState {
+    /// Returns rigid bodies at the moment when the snapshot was taken.
+    pub fn bodies(&self) -> &RigidBodyContainer {
+        &self.bodies
+    }
+
+    /// Returns colliders at the moment when the snapshot was taken.
+    pub fn colliders(&self) -> &ColliderContainer {
+        &self.colliders
+    }
+
+    /// Returns joints at the moment when the snapshot was taken.
+    pub fn joints(&self) -> &JointContainer {
+        &self.joints
+    }
+}
+
 impl PhysicsWorld {
     /// Creates a new instance of the physics world.
     pub fn new() -> Self {
+        let mut pipeline = PhysicsPipeline::new();
+        // Counters are required for timings of stages in statistics.
+        pipeline.counters.enable();
+
         Self {
-            pipeline: PhysicsPipeline::new(),
+            pipeline,
             #[cfg(feature = "dim3")]
             gravity: Vector::new(0.0, -9.81, 0.0),
             // For 2D gravity is inversed because the origin of the world is at top left corner of the screen.
             #[cfg(feature = "dim2")]
             gravity: Vector::new(0.0, 9.81),
             integration_parameters: IntegrationParameters::default(),
+            solver_settings: Default::default(),
             broad_phase: BroadPhase::new(),
             narrow_phase: NarrowPhase::new(),
             ccd_solver: CCDSolver::new(),
@@ -339,8 +532,10 @@ impl PhysicsWorld {
             colliders: ColliderContainer::new(),
             joints: JointContainer::new(),
             event_handler: Box::new(()),
+            joint_event_handler: Box::new(()),
             query: Default::default(),
             performance_statistics: Default::default(),
+            statistics: Default::default(),
         }
     }
 
@@ -352,24 +547,227 @@ impl PhysicsWorld {
     }
 
     /// Performs a single simulation step.
+    ///
+    /// If [`SolverSettings::substeps`] is more than one, the step is split into multiple
+    /// substeps with proportionally smaller time steps. Contact and intersection events are
+    /// deduplicated across substeps and only the state after the last substep is observable, so
+    /// the rest of the engine sees it as a single step. See [`solver`] module docs.
+    ///
+    /// # Determinism
+    ///
+    /// Simulation is deterministic on the same machine and the same build: two worlds that
+    /// were filled with the same bodies, colliders and joints in the same order will produce
+    /// bit-identical results after any amount of steps. Order of addition matters, because
+    /// it defines the order in which the solver processes bodies and contacts. Time step is
+    /// taken from [`Self::integration_parameters`] and it is not affected by frame rate.
+    /// Cross-platform determinism is not guaranteed.
     pub fn step(&mut self) {
         let time = instant::Instant::now();
 
-        self.pipeline.step(
-            &self.gravity,
-            &self.integration_parameters,
-            &mut self.islands,
-            &mut self.broad_phase,
-            &mut self.narrow_phase,
-            &mut self.bodies.set,
-            &mut self.colliders.set,
-            &mut self.joints.set,
-            &mut self.ccd_solver,
-            &(),
-            &*self.event_handler,
-        );
+        let substeps = self.solver_settings.substep_count();
+        let mut parameters = self.integration_parameters;
+        parameters.dt /= substeps as f32;
+
+        // Handler is moved out for the time of the step, because joints are broken (which
+        // requires mutable access to the world) between substeps.
+        let user_event_handler = std::mem::replace(&mut self.event_handler, Box::new(()));
+        let event_filter = SubstepEventFilter::new(&*user_event_handler);
+        let event_handler: &dyn EventHandler = if substeps > 1 {
+            &event_filter
+        } else {
+            &*user_event_handler
+        };
+
+        let mut statistics = PhysicsStatistics::default();
+
+        for _ in 0..substeps {
+            self.pipeline.step(
+                &self.gravity,
+                &parameters,
+                &mut self.islands,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.bodies.set,
+                &mut self.colliders.set,
+                &mut self.joints.set,
+                &mut self.ccd_solver,
+                &(),
+                event_handler,
+            );
+
+            statistics.add_substep(&self.pipeline, &parameters);
+
+            // Clamp after every substep, so the next one starts with sane velocities.
+            if self.solver_settings.clamps_velocities() {
+                Self::clamp_body_velocities(&self.solver_settings, &self.islands, &mut self.bodies);
+            }
+
+            self.break_joints(parameters.dt);
+        }
+
+        self.event_handler = user_event_handler;
+
+        statistics.count(&self.bodies.set, &self.colliders.set, &self.narrow_phase);
+
+        let step_time = instant::Instant::now() - time;
+        statistics.step_time = step_time;
+        self.statistics = statistics;
+        self.performance_statistics.step_time += step_time;
+    }
+
+    /// Returns statistics of the last simulation step, see [`statistics`] module docs.
+    pub fn statistics(&self) -> &PhysicsStatistics {
+        &self.statistics
+    }
+
+    fn break_joints(&mut self, dt: f32) {
+        if self.joints.break_limits.is_empty() || dt <= 0.0 {
+            return;
+        }
+
+        let mut broken = Vec::new();
+        for (handle, limits) in self.joints.break_limits.iter() {
+            if !limits.is_breakable() {
+                continue;
+            }
+            if let Some(joint) = self.joints.get(handle) {
+                let (linear, angular) = joint::joint_impulses(&joint.params);
+                let (force, torque) = (linear / dt, angular / dt);
+                if limits.is_exceeded(force, torque) {
+                    broken.push((*handle, joint.body1, joint.body2, force, torque));
+                }
+            }
+        }
+
+        // Limits are stored in a hash map, sort broken joints to keep removal (and events) in
+        // the same order in every run.
+        broken.sort_by_key(|(handle, ..)| {
+            self.joints
+                .handle_map
+                .value_of(handle)
+                .map(|h| h.into_raw_parts())
+        });
+
+        for (handle, body1, body2, force, torque) in broken {
+            self.remove_joint(&handle, true);
+            if let (Some(body1), Some(body2)) = (
+                self.bodies.handle_map().key_of(&body1),
+                self.bodies.handle_map().key_of(&body2),
+            ) {
+                self.joint_event_handler
+                    .handle_joint_break_event(JointBreakEvent {
+                        joint: handle,
+                        body1: *body1,
+                        body2: *body2,
+                        force,
+                        torque,
+                    });
+            }
+        }
+    }
+
+    fn clamp_body_velocities(
+        settings: &SolverSettings,
+        islands: &IslandManager,
+        bodies: &mut RigidBodyContainer,
+    ) {
+        for &handle in islands.active_dynamic_bodies() {
+            let (linear_speed, angular_speed) = match bodies.set.get(handle) {
+                Some(body) => (body.linvel().norm(), body_angular_speed(body)),
+                None => continue,
+            };
+
+            // Do not touch bodies that are fine, mutable access marks a body as modified.
+            if linear_speed > settings.max_linear_velocity {
+                let body = bodies.set.get_mut(handle).unwrap();
+                let linvel = body
+                    .linvel()
+                    .scale(settings.max_linear_velocity / linear_speed);
+                body.set_linvel(linvel, false);
+            }
+            if angular_speed > settings.max_angular_velocity {
+                let body = bodies.set.get_mut(handle).unwrap();
+                scale_angvel(body, settings.max_angular_velocity / angular_speed);
+            }
+        }
+    }
+
+    /// Sets amount of velocity and position iterations of the solver, see [`solver`] module docs.
+    pub fn set_solver_iterations(
+        &mut self,
+        velocity_iterations: usize,
+        position_iterations: usize,
+    ) {
+        self.integration_parameters.max_velocity_iterations = velocity_iterations;
+        self.integration_parameters.max_position_iterations = position_iterations;
+    }
+
+    /// Returns amount of velocity and position iterations of the solver.
+    pub fn solver_iterations(&self) -> (usize, usize) {
+        (
+            self.integration_parameters.max_velocity_iterations,
+            self.integration_parameters.max_position_iterations,
+        )
+    }
+
+    /// Sets amount of substeps per step, zero is treated as one. See [`solver`] module docs.
+    pub fn set_substeps(&mut self, substeps: u32) {
+        self.solver_settings.substeps = substeps;
+    }
+
+    /// Sets contact slop - penetration depth (in units) that is allowed without correction.
+    pub fn set_contact_slop(&mut self, slop: f32) {
+        self.integration_parameters.allowed_linear_error = slop;
+    }
+
+    /// Sets penetration correction parameters: `erp` - a fraction of penetration that is
+    /// resolved per step (in `[0; 1]` range), `max_correction` - max distance (in units) that
+    /// bodies can be moved per step to resolve penetration.
+    pub fn set_penetration_correction(&mut self, erp: f32, max_correction: f32) {
+        self.integration_parameters.erp = erp;
+        self.integration_parameters.max_linear_correction = max_correction;
+    }
+
+    /// Sets max linear (in units per second) and angular (in radians per second) velocities of
+    /// dynamic bodies. Use [`f32::MAX`] to disable clamping.
+    pub fn set_max_velocities(&mut self, linear: f32, angular: f32) {
+        self.solver_settings.max_linear_velocity = linear;
+        self.solver_settings.max_angular_velocity = angular;
+    }
+
+    /// Takes a snapshot of current simulation state, see [`PhysicsState`] docs for more info.
+    pub fn snapshot(&self) -> PhysicsState {
+        PhysicsState {
+            gravity: self.gravity,
+            integration_parameters: self.integration_parameters,
+            solver_settings: self.solver_settings,
+            broad_phase: self.broad_phase.clone(),
+            narrow_phase: self.narrow_phase.clone(),
+            ccd_solver: self.ccd_solver.clone(),
+            islands: self.islands.clone(),
+            bodies: self.bodies.clone(),
+            colliders: self.colliders.clone(),
+            joints: self.joints.clone(),
+        }
+    }
 
-        self.performance_statistics.step_time += instant::Instant::now() - time;
+    /// Restores simulation state from given snapshot. Every body, collider or joint that was
+    /// added after the snapshot was taken will be removed, and every removed one will be
+    /// restored with its previous handle. Event handler is kept as is.
+    pub fn restore(&mut self, state: &PhysicsState) {
+        self.gravity = state.gravity;
+        self.integration_parameters = state.integration_parameters;
+        self.solver_settings = state.solver_settings;
+        self.broad_phase = state.broad_phase.clone();
+        self.narrow_phase = state.narrow_phase.clone();
+        self.ccd_solver = state.ccd_solver.clone();
+        self.islands = state.islands.clone();
+        self.bodies = state.bodies.clone();
+        self.colliders = state.colliders.clone();
+        self.joints = state.joints.clone();
+        // Query pipeline might contain handles of colliders that do not exist in restored
+        // state, so it must be rebuilt from scratch.
+        *self.query.borrow_mut() = Default::default();
     }
 
     #[doc(hidden)]
@@ -415,6 +813,7 @@ impl PhysicsWorld {
 
         PhysicsDesc {
             integration_parameters: self.integration_parameters.into(),
+            solver_settings: self.solver_settings,
 
             bodies: self
                 .bodies
@@ -432,8 +831,11 @@ impl PhysicsWorld {
 
             joints: self
                 .joints
-                .iter()
-                .map(|j| JointDesc::from_joint(j, self.bodies.handle_map()))
+                .pair_iter()
+                .map(|(h, j)| JointDesc {
+                    break_limits: self.joints.break_limits(&h),
+                    ..JointDesc::from_joint(j, self.bodies.handle_map())
+                })
                 .collect::<Vec<_>>(),
 
             body_handle_map,
@@ -442,10 +844,7 @@ impl PhysicsWorld {
         }
     }
 
-    /// Casts a ray with given options.
-    pub fn cast_ray<S: QueryResultsStorage>(&self, opts: RayCastOptions, query_buffer: &mut S) {
-        let time = instant::Instant::now();
-
+    fn update_query(&self) -> RefMut<'_, QueryPipeline> {
         let mut query = self.query.borrow_mut();
 
         // TODO: Ideally this must be called once per frame, but it seems to be impossible because
@@ -455,6 +854,15 @@ impl PhysicsWorld {
         // of the frame.
         query.update(&self.islands, &self.bodies.set, &self.colliders.set);
 
+        query
+    }
+
+    /// Casts a ray with given options.
+    pub fn cast_ray<S: QueryResultsStorage>(&self, opts: RayCastOptions, query_buffer: &mut S) {
+        let time = instant::Instant::now();
+
+        let query = self.update_query();
+
         query_buffer.clear();
         let ray = NativeRay::new(
             opts.ray_origin,
@@ -502,6 +910,99 @@ impl PhysicsWorld {
         );
     }
 
+    /// Sweeps given shape from `from_transform` along `direction` up to `max_distance` and returns
+    /// the first collider on its way. Both dynamic bodies and static geometry (trimeshes,
+    /// heightfields) are checked. Can be used to check whether an actor will fit at some place,
+    /// to predict trajectories of projectiles, or to find how far a camera can be moved away from
+    /// its target without going through walls.
+    ///
+    /// If the shape penetrates some collider at `from_transform`, the collider is reported with
+    /// zero distance and the normal is a direction in which the shape should be moved to resolve
+    /// the penetration.
+    pub fn shape_cast(
+        &self,
+        shape: &ConvexShape,
+        from_transform: &Isometry<f32>,
+        direction: &Vector<f32>,
+        max_distance: f32,
+        options: ShapeCastOptions,
+    ) -> Option<ShapeCastHit> {
+        let time = instant::Instant::now();
+
+        let query = self.update_query();
+
+        let direction = direction.try_normalize(f32::EPSILON).unwrap_or_default();
+
+        let exclude_body = options
+            .exclude_body
+            .and_then(|body| self.bodies.handle_map().value_of(&body).cloned());
+        let filter = |handle: NativeColliderHandle| {
+            exclude_body.map_or(true, |exclude_body| {
+                self.colliders.set.get(handle).and_then(|c| c.parent()) != Some(exclude_body)
+            })
+        };
+
+        let result = shape.with_shape(|shape| {
+            query
+                .cast_shape(
+                    &self.colliders.set,
+                    from_transform,
+                    &direction,
+                    shape,
+                    max_distance,
+                    options.groups,
+                    Some(&filter),
+                )
+                .map(|(handle, toi)| {
+                    let collider = &self.colliders.set[handle];
+
+                    let mut hit = ShapeCastHit {
+                        collider: self
+                            .colliders
+                            .handle_map()
+                            .key_of(&handle)
+                            .cloned()
+                            .unwrap(),
+                        body: self
+                            .bodies
+                            .handle_map()
+                            .key_of(&collider.parent().unwrap())
+                            .cloned()
+                            .unwrap(),
+                        toi: toi.toi,
+                        position: toi.witness1,
+                        normal: *toi.normal1,
+                        penetrating: toi.status == TOIStatus::Penetrating,
+                    };
+
+                    // Witnesses are not reliable for penetrating shapes, so use contact query
+                    // to find the direction of minimal separation.
+                    if hit.penetrating {
+                        hit.toi = 0.0;
+                        if let Ok(Some(contact)) = query::contact(
+                            collider.position(),
+                            collider.shape(),
+                            from_transform,
+                            shape,
+                            0.0,
+                        ) {
+                            hit.position = contact.point1;
+                            hit.normal = *contact.normal1;
+                        }
+                    }
+
+                    hit
+                })
+        });
+
+        self.performance_statistics.total_ray_cast_time.set(
+            self.performance_statistics.total_ray_cast_time.get()
+                + (instant::Instant::now() - time),
+        );
+
+        result
+    }
+
     /// Adds new rigid body.
     pub fn add_body(&mut self, rigid_body: RigidBody) -> RigidBodyHandle {
         self.bodies.add(rigid_body)
@@ -546,9 +1047,157 @@ impl PhysicsWorld {
             .add(body1, body2, joint_params, &mut self.bodies)
     }
 
+    /// Sets break limits of a joint. Returns false if there is no such joint. See [`joint`] module
+    /// docs.
+    pub fn set_joint_break_limits(
+        &mut self,
+        joint_handle: &JointHandle,
+        limits: joint::JointBreakLimits,
+    ) -> bool {
+        self.joints.set_break_limits(joint_handle, limits)
+    }
+
     /// Removes a joint.
     pub fn remove_joint(&mut self, joint_handle: &JointHandle, wake_up: bool) -> Option<Joint> {
         self.joints
             .remove(joint_handle, &mut self.bodies, &mut self.islands, wake_up)
     }
+
+    /// Sets physics material of a collider. Returns false if there is no such collider.
+    pub fn set_collider_material(
+        &mut self,
+        collider: &ColliderHandle,
+        material: PhysicsMaterial,
+    ) -> bool {
+        if let Some(collider) = self.colliders.get_mut(collider) {
+            material.apply(collider);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns physics material of a collider.
+    pub fn collider_material(&self, collider: &ColliderHandle) -> Option<PhysicsMaterial> {
+        self.colliders
+            .get(collider)
+            .map(PhysicsMaterial::from_collider)
+    }
+
+    /// Sets physics material of every collider of a rigid body. It is useful for static geometry
+    /// with a single surface type.
+    pub fn set_body_material(&mut self, body: &RigidBodyHandle, material: PhysicsMaterial) {
+        if let Some(body) = self.bodies.get(body) {
+            for &collider in body.colliders() {
+                if let Some(collider) = self.colliders.native_mut(collider) {
+                    material.apply(collider);
+                }
+            }
+        }
+    }
+
+    /// Returns every active contact of a collider, with materials of both colliders. Contacts
+    /// are updated by [`Self::step`].
+    pub fn contacts_with(&self, collider: &ColliderHandle) -> Vec<Contact> {
+        let mut contacts = Vec::new();
+
+        let native = match self.colliders.handle_map().value_of(collider) {
+            Some(native) => *native,
+            None => return contacts,
+        };
+
+        for pair in self.narrow_phase.contact_pairs() {
+            if !pair.has_any_active_contact {
+                continue;
+            }
+
+            let (other, sign) = if pair.collider1 == native {
+                (pair.collider2, 1.0)
+            } else if pair.collider2 == native {
+                (pair.collider1, -1.0)
+            } else {
+                continue;
+            };
+
+            if let (Some(this_collider), Some(other_collider), Some(other_handle)) = (
+                self.colliders.native_ref(native),
+                self.colliders.native_ref(other),
+                self.colliders.handle_map().key_of(&other),
+            ) {
+                contacts.push(Contact {
+                    collider: *collider,
+                    other_collider: *other_handle,
+                    material: PhysicsMaterial::from_collider(this_collider),
+                    other_material: PhysicsMaterial::from_collider(other_collider),
+                    normal: pair
+                        .manifolds
+                        .first()
+                        .map(|m| m.data.normal.scale(sign))
+                        .unwrap_or_default(),
+                    impulse: pair
+                        .manifolds
+                        .iter()
+                        .flat_map(|m| m.points.iter())
+                        .map(|p| p.data.impulse)
+                        .sum(),
+                });
+            }
+        }
+
+        contacts
+    }
+
+    /// Returns handles of colliders that intersect given collider. It is intended to be used with
+    /// sensor colliders (triggers): intersections are reported only for pairs where at least one
+    /// collider is a sensor. Returns empty list if there is no such collider.
+    pub fn intersections_with(&self, collider: &ColliderHandle) -> Vec<ColliderHandle> {
+        let native = match self.colliders.handle_map().value_of(collider) {
+            Some(native) => *native,
+            None => return Vec::new(),
+        };
+
+        self.narrow_phase
+            .intersection_pairs()
+            .filter_map(|(collider1, collider2, intersecting)| {
+                let other = if collider1 == native {
+                    collider2
+                } else if collider2 == native {
+                    collider1
+                } else {
+                    return None;
+                };
+                if intersecting {
+                    self.colliders.handle_map().key_of(&other).cloned()
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Returns total normal impulse that was applied to every collider of a rigid body by its
+    /// contacts during the last substep. Returns zero if there is no such body or it does not
+    /// touch anything. Divide it by the time step to get the force, it is useful to calculate
+    /// damage on impacts.
+    pub fn last_step_impulse(&self, body: &RigidBodyHandle) -> f32 {
+        let body = match self.bodies.handle_map().value_of(body) {
+            Some(body) => *body,
+            None => return 0.0,
+        };
+
+        self.narrow_phase
+            .contact_pairs()
+            .filter(|pair| {
+                pair.has_any_active_contact
+                    && [pair.collider1, pair.collider2].iter().any(|collider| {
+                        self.colliders
+                            .native_ref(*collider)
+                            .map_or(false, |c| c.parent() == Some(body))
+                    })
+            })
+            .flat_map(|pair| pair.manifolds.iter())
+            .flat_map(|m| m.points.iter())
+            .map(|p| p.data.impulse)
+            .sum()
+    }
 }
@@ -0,0 +1,217 @@
+//! Physics materials define how surfaces of colliders interact with each other.
+//!
+//! Every collider has friction and restitution (bounciness) coefficients. When two colliders
+//! are in contact, their coefficients are combined using a [`CombineRule`]. If colliders have
+//! different rules, the rule with the highest priority is used, priorities are (from lowest to
+//! highest): `Average`, `Min`, `Multiply`, `Max`. For example, a rubber ball with restitution
+//! of 0.9 and `Max` rule will bounce on a concrete floor with zero restitution.
+//!
+//! Materials also have a user-defined tag, which allows you to know what kind of surface is
+//! touched by something. Typical use case is footstep sounds - you can cast a ray down from a
+//! character, take the material of the collider that was hit and pick a sound by its tag.
+//!
+//! Material is a property of a collider, so static geometry with different surfaces should
+//! be split into multiple colliders (one per surface type).
+//!
+//! # Implementation details
+//!
+//! Friction, restitution and combine rules are stored directly in Rapier colliders, the tag
+//! is stored in lower 32 bits of the user data of a collider.
+
+#[cfg(feature = "dim2")]
+use rapier2d::{dynamics::CoefficientCombineRule, geometry::Collider};
+#[cfg(feature = "dim3")]
+use rapier3d::{dynamics::CoefficientCombineRule, geometry::Collider};
+use rg3d_core::{
+    inspect::{Inspect, PropertyInfo},
+    visitor::prelude::*,
+};
+
+/// Defines how coefficients of two colliders in contact are combined.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Visit, Inspect)]
+pub enum CombineRule {
+    /// Arithmetic mean of the coefficients.
+    Average,
+    /// The smallest coefficient.
+    Min,
+    /// Product of the coefficients.
+    Multiply,
+    /// The largest coefficient.
+    Max,
+}
+
+impl Default for CombineRule {
+    fn default() -> Self {
+        Self::Average
+    }
+}
+
+impl From<CoefficientCombineRule> for CombineRule {
+    fn from(rule: CoefficientCombineRule) -> Self {
+        match rule {
+            CoefficientCombineRule::Average => Self::Average,
+            CoefficientCombineRule::Min => Self::Min,
+            CoefficientCombineRule::Multiply => Self::Multiply,
+            CoefficientCombineRule::Max => Self::Max,
+        }
+    }
+}
+
+impl From<CombineRule> for CoefficientCombineRule {
+    fn from(rule: CombineRule) -> Self {
+        match rule {
+            CombineRule::Average => Self::Average,
+            CombineRule::Min => Self::Min,
+            CombineRule::Multiply => Self::Multiply,
+            CombineRule::Max => Self::Max,
+        }
+    }
+}
+
+impl CombineRule {
+    /// Combines two coefficients using the rule. This is exactly what the contact solver does.
+    pub fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineRule::Average => (a + b) * 0.5,
+            CombineRule::Min => a.min(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Max => a.max(b),
+        }
+    }
+
+    /// Returns a rule that will be used for a pair of colliders with given rules.
+    pub fn effective(self, other: CombineRule) -> CombineRule {
+        if self.priority() >= other.priority() {
+            self
+        } else {
+            other
+        }
+    }
+
+    fn priority(self) -> u32 {
+        match self {
+            CombineRule::Average => 0,
+            CombineRule::Min => 1,
+            CombineRule::Multiply => 2,
+            CombineRule::Max => 3,
+        }
+    }
+}
+
+/// See module docs.
+#[derive(Copy, Clone, Debug, PartialEq, Visit, Inspect)]
+pub struct PhysicsMaterial {
+    /// Friction coefficient, usually in `[0; 1]` range. Default is 0.5.
+    #[inspect(min_value = 0.0, step = 0.05)]
+    pub friction: f32,
+    /// Restitution (bounciness) coefficient, 0.0 - no bounce at all, 1.0 - perfectly elastic
+    /// collision. Default is 0.0.
+    #[inspect(min_value = 0.0, step = 0.05)]
+    pub restitution: f32,
+    /// A rule to combine friction coefficients of two colliders in contact.
+    pub friction_combine_rule: CombineRule,
+    /// A rule to combine restitution coefficients of two colliders in contact.
+    pub restitution_combine_rule: CombineRule,
+    /// User-defined surface type (grass, stone, metal, etc.), it is not used by the physics.
+    pub tag: u32,
+}
+
+impl Default for PhysicsMaterial {
+    fn default() -> Self {
+        Self {
+            friction: 0.5,
+            restitution: 0.0,
+            friction_combine_rule: Default::default(),
+            restitution_combine_rule: Default::default(),
+            tag: 0,
+        }
+    }
+}
+
+impl PhysicsMaterial {
+    /// Creates new material with given friction and restitution, combine rules are set to
+    /// [`CombineRule::Average`] and tag is zero.
+    pub fn new(friction: f32, restitution: f32) -> Self {
+        Self {
+            friction,
+            restitution,
+            ..Default::default()
+        }
+    }
+
+    /// Sets desired tag.
+    pub fn with_tag(mut self, tag: u32) -> Self {
+        self.tag = tag;
+        self
+    }
+
+    /// Sets desired friction combine rule.
+    pub fn with_friction_combine_rule(mut self, rule: CombineRule) -> Self {
+        self.friction_combine_rule = rule;
+        self
+    }
+
+    /// Sets desired restitution combine rule.
+    pub fn with_restitution_combine_rule(mut self, rule: CombineRule) -> Self {
+        self.restitution_combine_rule = rule;
+        self
+    }
+
+    /// Reads material of given collider.
+    pub fn from_collider(collider: &Collider) -> Self {
+        Self {
+            friction: collider.friction(),
+            restitution: collider.restitution(),
+            friction_combine_rule: collider.friction_combine_rule().into(),
+            restitution_combine_rule: collider.restitution_combine_rule().into(),
+            tag: collider_tag(collider),
+        }
+    }
+
+    /// Applies the material to given collider.
+    pub fn apply(&self, collider: &mut Collider) {
+        collider.set_friction(self.friction);
+        collider.set_restitution(self.restitution);
+        collider.set_friction_combine_rule(self.friction_combine_rule.into());
+        collider.set_restitution_combine_rule(self.restitution_combine_rule.into());
+        collider.user_data = (collider.user_data & !(u32::MAX as u128)) | self.tag as u128;
+    }
+
+    /// Returns friction coefficient that will be used for a contact of two materials.
+    pub fn combined_friction(&self, other: &PhysicsMaterial) -> f32 {
+        self.friction_combine_rule
+            .effective(other.friction_combine_rule)
+            .combine(self.friction, other.friction)
+    }
+
+    /// Returns restitution coefficient that will be used for a contact of two materials.
+    pub fn combined_restitution(&self, other: &PhysicsMaterial) -> f32 {
+        self.restitution_combine_rule
+            .effective(other.restitution_combine_rule)
+            .combine(self.restitution, other.restitution)
+    }
+}
+
+pub(in crate) fn collider_tag(collider: &Collider) -> u32 {
+    (collider.user_data & u32::MAX as u128) as u32
+}
+
+#[cfg(test)]
+mod test {
+    use crate::material::{CombineRule, PhysicsMaterial};
+
+    #[test]
+    fn test_combine_rules() {
+        let rubber = PhysicsMaterial::new(0.9, 0.9).with_restitution_combine_rule(CombineRule::Max);
+        let concrete = PhysicsMaterial::new(0.6, 0.0).with_tag(2);
+
+        // Max has higher priority than Average, so rubber bounces on concrete.
+        assert_eq!(rubber.combined_restitution(&concrete), 0.9);
+        assert_eq!(concrete.combined_restitution(&rubber), 0.9);
+        assert!((rubber.combined_friction(&concrete) - 0.75).abs() < 1.0e-6);
+
+        let ice = PhysicsMaterial::new(0.05, 0.0).with_friction_combine_rule(CombineRule::Min);
+        assert_eq!(ice.combined_friction(&rubber), 0.05);
+        assert_eq!(CombineRule::Multiply.combine(0.5, 0.5), 0.25);
+    }
+}
@@ -0,0 +1,208 @@
+//! Solver settings define how precise (and how expensive) simulation of a physics world is.
+//!
+//! # Cost and stability
+//!
+//! Most of the settings are stored in [`crate::PhysicsWorld::integration_parameters`], the ones
+//! that are usually tweaked have shortcuts in [`crate::PhysicsWorld`]:
+//!
+//! - Velocity and position iterations - how many times the solver processes every contact and
+//!   joint per step. More iterations make tall stacks of bodies and long chains of joints stiffer,
+//!   cost grows linearly with the number of iterations. Defaults are 4 velocity and 1 position
+//!   iteration.
+//! - Substeps - how many times a single step is split. Every substep is a full simulation step
+//!   with the time step divided by the number of substeps, so it costs as much as a step (broad
+//!   phase, narrow phase, solver). Substeps help more than iterations when bodies are fast or have
+//!   high mass ratios, because errors of integration shrink along with the time step. Default is
+//!   one substep (no splitting).
+//! - Contact slop - allowed penetration depth at which the solver stops pushing bodies apart.
+//!   Small slop makes stacks jitter, large slop makes bodies visibly sink into each other.
+//! - Penetration correction - a fraction of penetration that is resolved per step (ERP) and max
+//!   correction per step. Large values resolve penetrations quickly, but add energy to the system
+//!   and make stacks "explode".
+//! - Max velocities - linear and angular velocities of dynamic bodies are clamped after every
+//!   substep, it prevents tunneling and explosions of badly configured setups. Disabled by
+//!   default.
+//!
+//! As a rule of thumb: a scene with a few bodies does not need anything but defaults, a stack of
+//! crates (see `test_crate_stack` test in the engine) needs 2-4 substeps or 8+ velocity
+//! iterations to stay still without sinking, the former is more expensive, but more robust.
+//!
+//! # Events
+//!
+//! Contact and intersection events are deduplicated when there is more than one substep: the
+//! event handler receives at most one "started" and one "stopped" event per pair of colliders
+//! per step, no matter how many substeps there are. Joints are checked against their break
+//! limits after every substep, so a joint can break in any substep of a step (see [`crate::joint`]
+//! module docs).
+
+use fxhash::FxHashSet;
+#[cfg(feature = "dim2")]
+use rapier2d::{
+    geometry::{ColliderHandle, ContactEvent, ContactPair, IntersectionEvent},
+    pipeline::EventHandler,
+};
+#[cfg(feature = "dim3")]
+use rapier3d::{
+    geometry::{ColliderHandle, ContactEvent, ContactPair, IntersectionEvent},
+    pipeline::EventHandler,
+};
+use rg3d_core::{
+    inspect::{Inspect, PropertyInfo},
+    parking_lot::Mutex,
+    visitor::prelude::*,
+};
+
+/// Settings of a simulation step that are not part of Rapier's integration parameters, see
+/// module docs for more info.
+#[derive(Copy, Clone, Debug, PartialEq, Visit, Inspect)]
+pub struct SolverSettings {
+    /// Amount of substeps per step, the time step is divided evenly between substeps. Zero is
+    /// treated as one. Default is 1.
+    #[inspect(min_value = 1.0)]
+    pub substeps: u32,
+    /// Max linear velocity (in units per second) of dynamic bodies. Default is [`f32::MAX`],
+    /// which means no clamping.
+    #[inspect(min_value = 0.0)]
+    pub max_linear_velocity: f32,
+    /// Max angular velocity (in radians per second) of dynamic bodies. Default is [`f32::MAX`],
+    /// which means no clamping.
+    #[inspect(min_value = 0.0)]
+    pub max_angular_velocity: f32,
+}
+
+impl Default for SolverSettings {
+    fn default() -> Self {
+        Self {
+            substeps: 1,
+            max_linear_velocity: f32::MAX,
+            max_angular_velocity: f32::MAX,
+        }
+    }
+}
+
+impl SolverSettings {
+    /// Returns effective amount of substeps, it is never zero.
+    pub fn substep_count(&self) -> u32 {
+        self.substeps.max(1)
+    }
+
+    /// Returns `true` if any velocity clamp is set.
+    pub fn clamps_velocities(&self) -> bool {
+        self.max_linear_velocity < f32::MAX || self.max_angular_velocity < f32::MAX
+    }
+}
+
+#[derive(Copy, Clone, Hash, PartialEq, Eq)]
+enum PairEvent {
+    ContactStarted,
+    ContactStopped,
+    IntersectionStarted,
+    IntersectionStopped,
+}
+
+/// Raw parts of handles of both colliders of a pair and the kind of event.
+type PairEventKey = (u32, u32, u32, u32, PairEvent);
+
+/// Forwards events of every substep to an event handler, but only the first event of each kind
+/// for a pair of colliders per outer step.
+pub(in crate) struct SubstepEventFilter<'a> {
+    handler: &'a dyn EventHandler,
+    sent: Mutex<FxHashSet<PairEventKey>>,
+}
+
+impl<'a> SubstepEventFilter<'a> {
+    pub(in crate) fn new(handler: &'a dyn EventHandler) -> Self {
+        Self {
+            handler,
+            sent: Default::default(),
+        }
+    }
+
+    fn is_first(&self, a: ColliderHandle, b: ColliderHandle, event: PairEvent) -> bool {
+        let (a, b) = (a.into_raw_parts(), b.into_raw_parts());
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        self.sent.lock().insert((a.0, a.1, b.0, b.1, event))
+    }
+}
+
+impl<'a> EventHandler for SubstepEventFilter<'a> {
+    fn handle_intersection_event(&self, event: IntersectionEvent) {
+        let kind = if event.intersecting {
+            PairEvent::IntersectionStarted
+        } else {
+            PairEvent::IntersectionStopped
+        };
+        if self.is_first(event.collider1, event.collider2, kind) {
+            self.handler.handle_intersection_event(event);
+        }
+    }
+
+    fn handle_contact_event(&self, event: ContactEvent, contact_pair: &ContactPair) {
+        let (a, b, kind) = match event {
+            ContactEvent::Started(a, b) => (a, b, PairEvent::ContactStarted),
+            ContactEvent::Stopped(a, b) => (a, b, PairEvent::ContactStopped),
+        };
+        if self.is_first(a, b, kind) {
+            self.handler.handle_contact_event(event, contact_pair);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::solver::SubstepEventFilter;
+    #[cfg(feature = "dim2")]
+    use rapier2d::{
+        geometry::{ColliderHandle, ContactEvent, ContactPair, IntersectionEvent},
+        pipeline::EventHandler,
+    };
+    #[cfg(feature = "dim3")]
+    use rapier3d::{
+        geometry::{ColliderHandle, ContactEvent, ContactPair, IntersectionEvent},
+        pipeline::EventHandler,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct Counter {
+        started: AtomicUsize,
+        stopped: AtomicUsize,
+    }
+
+    impl EventHandler for Counter {
+        fn handle_intersection_event(&self, event: IntersectionEvent) {
+            if event.intersecting {
+                self.started.fetch_add(1, Ordering::SeqCst);
+            } else {
+                self.stopped.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        fn handle_contact_event(&self, _event: ContactEvent, _contact_pair: &ContactPair) {}
+    }
+
+    #[test]
+    fn test_substep_events_are_deduplicated() {
+        let counter = Counter::default();
+        let a = ColliderHandle::from_raw_parts(0, 0);
+        let b = ColliderHandle::from_raw_parts(1, 0);
+        let c = ColliderHandle::from_raw_parts(2, 0);
+
+        {
+            let filter = SubstepEventFilter::new(&counter);
+            // Pair begins, ends and begins again in consecutive substeps.
+            filter.handle_intersection_event(IntersectionEvent::new(a, b, true));
+            filter.handle_intersection_event(IntersectionEvent::new(a, b, false));
+            filter.handle_intersection_event(IntersectionEvent::new(b, a, true));
+            filter.handle_intersection_event(IntersectionEvent::new(a, c, true));
+        }
+
+        assert_eq!(counter.started.load(Ordering::SeqCst), 2);
+        assert_eq!(counter.stopped.load(Ordering::SeqCst), 1);
+
+        // New outer step - new filter.
+        let filter = SubstepEventFilter::new(&counter);
+        filter.handle_intersection_event(IntersectionEvent::new(a, b, true));
+        assert_eq!(counter.started.load(Ordering::SeqCst), 3);
+    }
+}
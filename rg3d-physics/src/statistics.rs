@@ -0,0 +1,266 @@
+//! Statistics of a simulation step, they show where time of a step goes and how much work the
+//! broad phase, the narrow phase and the solver had to do.
+//!
+//! Statistics are refreshed by every [`crate::PhysicsWorld::step`] and describe the last step
+//! only, so they can be shown in the same frame. Counts are collected by iterating over bodies
+//! and pairs of colliders that are already stored in the world, so collection does not allocate
+//! memory. Timings of stages are taken from internal profiler of Rapier.
+//!
+//! - Broad phase pairs - pairs of colliders which bounding boxes intersect, each of them is a
+//!   candidate for a precise check.
+//! - Narrow phase tests - broad phase pairs where at least one body is awake and not static, only
+//!   such pairs are checked precisely every step.
+//! - Contact pairs and contacts - pairs that actually touch and their contact points, every
+//!   contact point is processed by the solver.
+//! - Solver iterations - amount of velocity and position iterations made by the solver over
+//!   every substep, each iteration processes every contact and joint once.
+
+#[cfg(feature = "dim2")]
+use rapier2d::{
+    dynamics::{IntegrationParameters, RigidBodySet},
+    geometry::{ColliderHandle, ColliderSet, NarrowPhase},
+    pipeline::PhysicsPipeline,
+};
+#[cfg(feature = "dim3")]
+use rapier3d::{
+    dynamics::{IntegrationParameters, RigidBodySet},
+    geometry::{ColliderHandle, ColliderSet, NarrowPhase},
+    pipeline::PhysicsPipeline,
+};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+/// See module docs.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct PhysicsStatistics {
+    /// Total amount of rigid bodies.
+    pub bodies: usize,
+    /// Amount of dynamic bodies that are simulated.
+    pub awake_bodies: usize,
+    /// Amount of dynamic bodies that are sleeping, they cost almost nothing.
+    pub sleeping_bodies: usize,
+    /// Amount of kinematic bodies.
+    pub kinematic_bodies: usize,
+    /// Amount of static bodies.
+    pub static_bodies: usize,
+    /// Total amount of colliders.
+    pub colliders: usize,
+    /// Amount of colliders that are sensors.
+    pub sensors: usize,
+    /// Amount of pairs of colliders found by the broad phase.
+    pub broad_phase_pairs: usize,
+    /// Amount of pairs that were checked by the narrow phase.
+    pub narrow_phase_tests: usize,
+    /// Amount of pairs of colliders that touch each other.
+    pub contact_pairs: usize,
+    /// Amount of contact points generated by the narrow phase.
+    pub contacts: usize,
+    /// Amount of substeps that were made.
+    pub substeps: u32,
+    /// Amount of velocity iterations of the solver over every substep.
+    pub velocity_iterations: usize,
+    /// Amount of position iterations of the solver over every substep.
+    pub position_iterations: usize,
+    /// Total time of the step, including every substep.
+    pub step_time: Duration,
+    /// Time of the broad phase of every substep.
+    pub broad_phase_time: Duration,
+    /// Time of the narrow phase of every substep.
+    pub narrow_phase_time: Duration,
+    /// Time of the solver (velocity and position resolution) of every substep.
+    pub solver_time: Duration,
+}
+
+impl Display for PhysicsStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Physics Statistics:\n\
+            \tBodies: {} ({} awake, {} sleeping, {} kinematic, {} static)\n\
+            \tColliders: {} ({} sensors)\n\
+            \tBroad Phase Pairs: {}\n\
+            \tNarrow Phase Tests: {}\n\
+            \tContacts: {} in {} pairs\n\
+            \tStep: {:?} ({} substeps)\n\
+            \tSolver Iterations: {} velocity, {} position\n\
+            \tBroad Phase: {:?}\n\
+            \tNarrow Phase: {:?}\n\
+            \tSolver: {:?}\n",
+            self.bodies,
+            self.awake_bodies,
+            self.sleeping_bodies,
+            self.kinematic_bodies,
+            self.static_bodies,
+            self.colliders,
+            self.sensors,
+            self.broad_phase_pairs,
+            self.narrow_phase_tests,
+            self.contacts,
+            self.contact_pairs,
+            self.step_time,
+            self.substeps,
+            self.velocity_iterations,
+            self.position_iterations,
+            self.broad_phase_time,
+            self.narrow_phase_time,
+            self.solver_time,
+        )
+    }
+}
+
+// Rapier's profiler measures time in milliseconds.
+fn profiler_time(milliseconds: f64) -> Duration {
+    Duration::from_secs_f64(milliseconds.max(0.0) / 1000.0)
+}
+
+impl PhysicsStatistics {
+    /// Adds timings and solver iterations of a substep that was just made by the pipeline.
+    pub(in crate) fn add_substep(
+        &mut self,
+        pipeline: &PhysicsPipeline,
+        parameters: &IntegrationParameters,
+    ) {
+        let counters = &pipeline.counters;
+        self.substeps += 1;
+        self.velocity_iterations += parameters.max_velocity_iterations;
+        self.position_iterations += parameters.max_position_iterations;
+        self.broad_phase_time += profiler_time(counters.cd.broad_phase_time.time());
+        self.narrow_phase_time += profiler_time(counters.cd.narrow_phase_time.time());
+        self.solver_time += profiler_time(counters.stages.solver_time.time());
+    }
+
+    /// Counts bodies, colliders and pairs of colliders after a step.
+    pub(in crate) fn count(
+        &mut self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        narrow_phase: &NarrowPhase,
+    ) {
+        self.bodies = 0;
+        self.awake_bodies = 0;
+        self.sleeping_bodies = 0;
+        self.kinematic_bodies = 0;
+        self.static_bodies = 0;
+        for (_, body) in bodies.iter() {
+            self.bodies += 1;
+            if body.is_static() {
+                self.static_bodies += 1;
+            } else if body.is_kinematic() {
+                self.kinematic_bodies += 1;
+            } else if body.is_sleeping() {
+                self.sleeping_bodies += 1;
+            } else {
+                self.awake_bodies += 1;
+            }
+        }
+
+        self.colliders = 0;
+        self.sensors = 0;
+        for (_, collider) in colliders.iter() {
+            self.colliders += 1;
+            if collider.is_sensor() {
+                self.sensors += 1;
+            }
+        }
+
+        // A pair is tested by the narrow phase if at least one of its bodies can move.
+        let is_active = |collider: ColliderHandle| {
+            colliders
+                .get(collider)
+                .and_then(|c| c.parent())
+                .and_then(|body| bodies.get(body))
+                .map_or(false, |body| !body.is_static() && !body.is_sleeping())
+        };
+
+        self.broad_phase_pairs = 0;
+        self.narrow_phase_tests = 0;
+        self.contact_pairs = 0;
+        self.contacts = 0;
+        for pair in narrow_phase.contact_pairs() {
+            self.broad_phase_pairs += 1;
+            if is_active(pair.collider1) || is_active(pair.collider2) {
+                self.narrow_phase_tests += 1;
+            }
+            if pair.has_any_active_contact {
+                self.contact_pairs += 1;
+                self.contacts += pair
+                    .manifolds
+                    .iter()
+                    .map(|m| m.data.solver_contacts.len())
+                    .sum::<usize>();
+            }
+        }
+        for (collider1, collider2, _) in narrow_phase.intersection_pairs() {
+            self.broad_phase_pairs += 1;
+            if is_active(collider1) || is_active(collider2) {
+                self.narrow_phase_tests += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{PhysicsWorld, Vector};
+    #[cfg(feature = "dim2")]
+    use rapier2d::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder};
+    #[cfg(feature = "dim3")]
+    use rapier3d::{dynamics::RigidBodyBuilder, geometry::ColliderBuilder};
+
+    #[cfg(feature = "dim2")]
+    const FLOOR_CONTACTS: usize = 2;
+    #[cfg(feature = "dim3")]
+    const FLOOR_CONTACTS: usize = 4;
+
+    #[cfg(feature = "dim2")]
+    fn cuboid(half_extents: Vector<f32>) -> ColliderBuilder {
+        ColliderBuilder::cuboid(half_extents.x, half_extents.y)
+    }
+
+    #[cfg(feature = "dim3")]
+    fn cuboid(half_extents: Vector<f32>) -> ColliderBuilder {
+        ColliderBuilder::cuboid(half_extents.x, half_extents.y, half_extents.z)
+    }
+
+    #[test]
+    fn test_box_resting_on_floor() {
+        let mut world = PhysicsWorld::new();
+        world.set_substeps(2);
+
+        let floor = world.add_body(RigidBodyBuilder::new_static().build());
+        let mut half_extents = Vector::repeat(10.0);
+        half_extents.y = 0.5;
+        world.add_collider(cuboid(half_extents).build(), &floor);
+
+        // The box lies right on top of the floor, gravity points in opposite directions in 2D and
+        // 3D.
+        let position = -world.gravity.normalize();
+        let body = world.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .translation(position)
+                .build(),
+        );
+        world.add_collider(cuboid(Vector::repeat(0.5)).build(), &body);
+
+        for _ in 0..10 {
+            world.step();
+        }
+
+        let statistics = *world.statistics();
+        assert_eq!(statistics.bodies, 2);
+        assert_eq!(statistics.static_bodies, 1);
+        assert_eq!(statistics.awake_bodies + statistics.sleeping_bodies, 1);
+        assert_eq!(statistics.colliders, 2);
+        assert_eq!(statistics.sensors, 0);
+        assert_eq!(statistics.broad_phase_pairs, 1);
+        assert_eq!(statistics.contact_pairs, 1);
+        // Face of the box touches the floor with every corner.
+        assert_eq!(statistics.contacts, FLOOR_CONTACTS);
+        assert_eq!(statistics.substeps, 2);
+        let (velocity_iterations, position_iterations) = world.solver_iterations();
+        assert_eq!(statistics.velocity_iterations, 2 * velocity_iterations);
+        assert_eq!(statistics.position_iterations, 2 * position_iterations);
+    }
+}
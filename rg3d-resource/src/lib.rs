@@ -24,6 +24,13 @@ pub trait ResourceData: 'static + Default + Debug + Visit + Send {
 
     /// Sets new path to resource data.
     fn set_path(&mut self, path: PathBuf);
+
+    /// Returns `true` if the data is a placeholder that was substituted for a resource that failed
+    /// to load. Placeholders keep the path of the original resource, so they can be replaced by
+    /// actual data when the resource is reloaded. Default implementation returns `false`.
+    fn is_placeholder(&self) -> bool {
+        false
+    }
 }
 
 /// A trait for resource load error.
@@ -177,6 +184,13 @@ impl<T: ResourceData, E: ResourceLoadError> Resource<T, E> {
         self.state.as_ref().unwrap().try_lock()
     }
 
+    /// Returns `true` if the resource is a placeholder for a resource that failed to load, see
+    /// [`ResourceData::is_placeholder`].
+    #[inline]
+    pub fn is_placeholder(&self) -> bool {
+        self.state().is_placeholder()
+    }
+
     /// Returns exact amount of users of the resource.
     #[inline]
     pub fn use_count(&self) -> usize {
@@ -309,6 +323,16 @@ impl<T: ResourceData, E: ResourceLoadError> ResourceState<T, E> {
         }
     }
 
+    /// Returns `true` if the resource is loaded and its data is a placeholder, see
+    /// [`ResourceData::is_placeholder`].
+    #[inline]
+    pub fn is_placeholder(&self) -> bool {
+        match self {
+            Self::Ok(data) => data.is_placeholder(),
+            _ => false,
+        }
+    }
+
     /// Changes ResourceState::Pending state to ResourceState::Ok(data) with given `data`.
     /// Additionally it wakes all futures.
     #[inline]
@@ -1,5 +1,6 @@
 use crate::{buffer::DataSource, error::SoundError};
 use lewton::{inside_ogg::OggStreamReader, samples::InterleavedSamples};
+use rg3d_core::log_warn;
 use std::fmt::{Debug, Formatter};
 use std::{
     io::{Read, Seek, SeekFrom},
@@ -104,7 +105,9 @@ impl OggDecoder {
             .seek_absgp_pg(sample_index as u64)
             .is_err()
         {
-            println!("Failed to seek vorbis/ogg, see https://github.com/RustAudio/lewton/issues/73")
+            log_warn!(
+                "Failed to seek vorbis/ogg, see https://github.com/RustAudio/lewton/issues/73"
+            )
         }
     }
 
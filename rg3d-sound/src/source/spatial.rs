@@ -121,14 +121,12 @@ impl SpatialSource {
         &mut self.generic
     }
 
+    /// Returns attenuation of the source for given listener and distance model, it is in
+    /// [0; 1] range where 1 means that the source is heard at full volume.
     // Distance models were taken from OpenAL Specification because it looks like they're
     // standard in industry and there is no need to reinvent it.
     // https://www.openal.org/documentation/openal-1.1-specification.pdf
-    pub(in crate) fn get_distance_gain(
-        &self,
-        listener: &Listener,
-        distance_model: DistanceModel,
-    ) -> f32 {
+    pub fn get_distance_gain(&self, listener: &Listener, distance_model: DistanceModel) -> f32 {
         let distance = self
             .position
             .metric_distance(&listener.position())
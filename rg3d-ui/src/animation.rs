@@ -0,0 +1,256 @@
+//! Simple property animations for widgets - fades, slides and resizing.
+//!
+//! Animations are enqueued on a widget by [`crate::UserInterface::animate`] and processed in
+//! [`crate::UserInterface::update`]. Animations of the same widget are played one after another
+//! in order of addition, single animation can change multiple properties at once (for example
+//! opacity and position). When an animation is finished, [`WidgetAnimationMessage::Completed`]
+//! is sent from the widget.
+//!
+//! ```no_run
+//! use rg3d_ui::{core::{algebra::Vector2, pool::Handle}, UiNode, UserInterface};
+//!
+//! fn show_menu(ui: &mut UserInterface, menu: Handle<UiNode>) {
+//!     // Slide menu from left side of the screen while fading it in.
+//!     ui.slide_in(menu, Vector2::new(-300.0, 0.0), 0.3);
+//! }
+//! ```
+
+use crate::{
+    core::{algebra::Vector2, math::lerpf, pool::Handle},
+    define_constructor,
+    message::{MessageDirection, UiMessage},
+    UiNode,
+};
+use std::collections::VecDeque;
+
+/// Defines how animated value changes over time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Easing {
+    /// Value changes linearly.
+    Linear,
+
+    /// Slow start, fast end.
+    EaseIn,
+
+    /// Fast start, slow end.
+    EaseOut,
+
+    /// Slow start and end, fast in the middle.
+    EaseInOut,
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl Easing {
+    /// Maps normalized time in [0; 1] range to interpolation factor.
+    pub fn evaluate(self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Animated property of a widget. `None` as `from` value means that current value of the
+/// property at the moment when animation starts will be used.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AnimatedProperty {
+    /// Opacity of the widget and its children.
+    Opacity { from: Option<f32>, to: f32 },
+
+    /// Offset from desired position of the widget at the moment when animation starts. Desired
+    /// position is used only by some containers (like Canvas), so offset won't have any effect
+    /// in other containers.
+    PositionOffset {
+        from: Vector2<f32>,
+        to: Vector2<f32>,
+    },
+
+    /// Explicit width of the widget.
+    Width { from: Option<f32>, to: f32 },
+
+    /// Explicit height of the widget.
+    Height { from: Option<f32>, to: f32 },
+}
+
+/// A set of property animations with common duration and easing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidgetAnimation {
+    name: String,
+    properties: Vec<AnimatedProperty>,
+    duration: f32,
+    easing: Easing,
+    hide_on_complete: bool,
+    // Runtime state.
+    elapsed: f32,
+    started: bool,
+    start_position: Vector2<f32>,
+}
+
+impl WidgetAnimation {
+    /// Creates new animation with given duration in seconds.
+    pub fn new(duration: f32) -> Self {
+        Self {
+            name: Default::default(),
+            properties: Default::default(),
+            duration,
+            easing: Default::default(),
+            hide_on_complete: false,
+            elapsed: 0.0,
+            started: false,
+            start_position: Default::default(),
+        }
+    }
+
+    /// Sets name of the animation, it will be passed in completion message.
+    pub fn with_name<P: AsRef<str>>(mut self, name: P) -> Self {
+        self.name = name.as_ref().to_owned();
+        self
+    }
+
+    /// Adds new animated property.
+    pub fn with_property(mut self, property: AnimatedProperty) -> Self {
+        self.properties.push(property);
+        self
+    }
+
+    /// Sets easing of the animation.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Sets whether the widget should be hidden when animation is finished. Useful for fading
+    /// out menus.
+    pub fn with_hide_on_complete(mut self, hide_on_complete: bool) -> Self {
+        self.hide_on_complete = hide_on_complete;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    pub fn easing(&self) -> Easing {
+        self.easing
+    }
+
+    pub fn properties(&self) -> &[AnimatedProperty] {
+        &self.properties
+    }
+
+    fn start(&mut self, node: &UiNode) {
+        self.start_position = node.desired_local_position();
+        for property in self.properties.iter_mut() {
+            match property {
+                AnimatedProperty::Opacity { from, .. } => {
+                    from.get_or_insert(node.opacity().unwrap_or(1.0));
+                }
+                AnimatedProperty::Width { from, .. } => {
+                    from.get_or_insert(if node.width().is_nan() {
+                        node.actual_size().x
+                    } else {
+                        node.width()
+                    });
+                }
+                AnimatedProperty::Height { from, .. } => {
+                    from.get_or_insert(if node.height().is_nan() {
+                        node.actual_size().y
+                    } else {
+                        node.height()
+                    });
+                }
+                AnimatedProperty::PositionOffset { .. } => (),
+            }
+        }
+        self.started = true;
+    }
+
+    fn apply(&self, node: &mut UiNode) {
+        let t = if self.duration > 0.0 {
+            self.easing.evaluate(self.elapsed / self.duration)
+        } else {
+            1.0
+        };
+
+        for property in self.properties.iter() {
+            match *property {
+                AnimatedProperty::Opacity { from, to } => {
+                    node.set_opacity(Some(lerpf(from.unwrap_or(to), to, t)));
+                }
+                AnimatedProperty::PositionOffset { from, to } => {
+                    node.set_desired_local_position(self.start_position + from.lerp(&to, t));
+                    node.invalidate_layout();
+                }
+                AnimatedProperty::Width { from, to } => {
+                    node.set_width(lerpf(from.unwrap_or(to), to, t));
+                    node.invalidate_layout();
+                }
+                AnimatedProperty::Height { from, to } => {
+                    node.set_height(lerpf(from.unwrap_or(to), to, t));
+                    node.invalidate_layout();
+                }
+            }
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WidgetAnimationMessage {
+    /// An animation with given name has finished.
+    ///
+    /// Direction: **From UI**
+    Completed(String),
+}
+
+impl WidgetAnimationMessage {
+    define_constructor!(WidgetAnimationMessage:Completed => fn completed(String), layout: false);
+}
+
+pub(in crate) struct AnimationQueue {
+    pub(in crate) widget: Handle<UiNode>,
+    pub(in crate) animations: VecDeque<WidgetAnimation>,
+}
+
+impl AnimationQueue {
+    /// Advances current animation of the queue, returns completion message if the animation
+    /// has finished.
+    pub(in crate) fn update(&mut self, node: &mut UiNode, dt: f32) -> Option<UiMessage> {
+        let animation = self.animations.front_mut()?;
+
+        if !animation.started {
+            animation.start(node);
+        }
+        animation.elapsed = (animation.elapsed + dt).min(animation.duration);
+        animation.apply(node);
+
+        if animation.is_finished() {
+            let animation = self.animations.pop_front().unwrap();
+            if animation.hide_on_complete {
+                node.set_visibility(false);
+            }
+            Some(WidgetAnimationMessage::completed(
+                self.widget,
+                MessageDirection::FromWidget,
+                animation.name,
+            ))
+        } else {
+            None
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
     brush::Brush,
     core::{color::Color, pool::Handle},
     define_constructor,
+    draw::Path,
     grid::{Column, GridBuilder, Row},
     message::{MessageDirection, UiMessage},
     vector_image::{Primitive, VectorImageBuilder},
@@ -201,18 +202,13 @@ impl CheckBoxBuilder {
                     .with_horizontal_alignment(HorizontalAlignment::Center)
                     .with_foreground(BRUSH_TEXT),
             )
-            .with_primitives(vec![
-                Primitive::Line {
-                    begin: Vector2::new(0.0, 6.0),
-                    end: Vector2::new(6.0, 12.0),
-                    thickness: 2.0,
-                },
-                Primitive::Line {
-                    begin: Vector2::new(6.0, 12.0),
-                    end: Vector2::new(12.0, 0.0),
-                    thickness: 2.0,
-                },
-            ])
+            .with_primitives(vec![Primitive::StrokedPath {
+                path: Path::new()
+                    .move_to(Vector2::new(1.0, 6.0))
+                    .line_to(Vector2::new(5.0, 10.0))
+                    .line_to(Vector2::new(11.0, 1.0)),
+                thickness: 2.0,
+            }])
             .build(ctx)
         });
         ctx[check_mark].set_visibility(self.checked.unwrap_or(false));
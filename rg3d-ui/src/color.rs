@@ -14,6 +14,7 @@ use crate::{
     numeric::{NumericUpDownBuilder, NumericUpDownMessage},
     popup::{Placement, PopupBuilder, PopupMessage},
     text::TextBuilder,
+    text_box::{TextBoxBuilder, TextBoxMessage, TextCommitMode},
     widget::{Widget, WidgetBuilder, WidgetMessage},
     BuildContext, Control, NodeHandleMapping, Orientation, Thickness, UiNode, UserInterface,
     VerticalAlignment,
@@ -81,20 +82,104 @@ pub enum ColorPickerMessage {
     ///
     /// Direction: **To Widget**.
     Hsv(Hsv),
+
+    /// Sent when user has finished editing a color - released mouse button over one of the
+    /// bars, or changed a value in one of the numeric or hex fields. Unlike `Color` message,
+    /// it is not sent when the color was set programmatically.
+    ///
+    /// Direction: **From Widget**.
+    Commit(Color),
 }
 
 impl ColorPickerMessage {
     define_constructor!(ColorPickerMessage:Color => fn color(Color), layout: false);
     define_constructor!(ColorPickerMessage:Hsv => fn hsv(Hsv), layout: false);
+    define_constructor!(ColorPickerMessage:Commit => fn commit(Color), layout: false);
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ColorFieldMessage {
+    /// Sets color of the field. The field sends this message back when user has committed
+    /// new color in the picker or when the picker was closed.
+    ///
+    /// Direction: **To/From Widget**.
     Color(Color),
+
+    /// Sent while user changes color in the picker, it could be used to preview the color
+    /// before it is committed.
+    ///
+    /// Direction: **From Widget**.
+    Preview(Color),
 }
 
 impl ColorFieldMessage {
     define_constructor!(ColorFieldMessage:Color => fn color(Color), layout: false);
+    define_constructor!(ColorFieldMessage:Preview => fn preview(Color), layout: false);
+}
+
+/// Parses a color from hexadecimal string. Parsing is lenient: whitespaces and leading `#`
+/// or `0x` are ignored and digits can be in any case. Supported formats are `RGB`, `RGBA`,
+/// `RRGGBB` and `RRGGBBAA`, if alpha is not specified `default_alpha` is used.
+pub fn parse_hex_color(text: &str, default_alpha: u8) -> Option<Color> {
+    let text = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    let digits = text
+        .strip_prefix('#')
+        .or_else(|| text.strip_prefix("0x"))
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(&text);
+
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    // Every char is an ASCII digit at this point, so it is safe to slice by bytes.
+    let component = |i: usize, len: usize| {
+        let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap();
+        if len == 1 {
+            value * 17
+        } else {
+            value
+        }
+    };
+
+    match digits.len() {
+        3 => Some(Color::from_rgba(
+            component(0, 1),
+            component(1, 1),
+            component(2, 1),
+            default_alpha,
+        )),
+        4 => Some(Color::from_rgba(
+            component(0, 1),
+            component(1, 1),
+            component(2, 1),
+            component(3, 1),
+        )),
+        6 => Some(Color::from_rgba(
+            component(0, 2),
+            component(1, 2),
+            component(2, 2),
+            default_alpha,
+        )),
+        8 => Some(Color::from_rgba(
+            component(0, 2),
+            component(1, 2),
+            component(2, 2),
+            component(3, 2),
+        )),
+        _ => None,
+    }
+}
+
+/// Formats a color as `#RRGGBBAA` string.
+pub fn format_hex_color(color: Color) -> String {
+    format!(
+        "#{:02X}{:02X}{:02X}{:02X}",
+        color.r, color.g, color.b, color.a
+    )
 }
 
 #[derive(Clone)]
@@ -639,33 +724,39 @@ impl Control for SaturationBrightnessField {
                             let clamped = hue.min(360.0).max(0.0);
                             if self.hue != clamped {
                                 self.hue = clamped;
-                                ui.send_message(SaturationBrightnessFieldMessage::hue(
+                                let response = SaturationBrightnessFieldMessage::hue(
                                     self.handle,
                                     MessageDirection::FromWidget,
                                     self.hue,
-                                ));
+                                );
+                                response.set_handled(message.handled());
+                                ui.send_message(response);
                             }
                         }
                         SaturationBrightnessFieldMessage::Saturation(saturation) => {
                             let clamped = saturation.min(100.0).max(0.0);
                             if self.saturation != clamped {
                                 self.saturation = clamped;
-                                ui.send_message(SaturationBrightnessFieldMessage::saturation(
+                                let response = SaturationBrightnessFieldMessage::saturation(
                                     self.handle,
                                     MessageDirection::FromWidget,
                                     self.saturation,
-                                ));
+                                );
+                                response.set_handled(message.handled());
+                                ui.send_message(response);
                             }
                         }
                         SaturationBrightnessFieldMessage::Brightness(brightness) => {
                             let clamped = brightness.min(100.0).max(0.0);
                             if self.brightness != clamped {
                                 self.brightness = clamped;
-                                ui.send_message(SaturationBrightnessFieldMessage::brightness(
+                                let response = SaturationBrightnessFieldMessage::brightness(
                                     self.handle,
                                     MessageDirection::FromWidget,
                                     self.brightness,
-                                ));
+                                );
+                                response.set_handled(message.handled());
+                                ui.send_message(response);
                             }
                         }
                     }
@@ -732,6 +823,7 @@ pub struct ColorPicker {
     hue: Handle<UiNode>,
     saturation: Handle<UiNode>,
     brightness: Handle<UiNode>,
+    hex: Handle<UiNode>,
     color_mark: Handle<UiNode>,
     color: Color,
     hsv: Hsv,
@@ -745,7 +837,47 @@ fn mark_handled(message: UiMessage) -> UiMessage {
 }
 
 impl ColorPicker {
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    pub fn hsv(&self) -> Hsv {
+        self.hsv
+    }
+
+    // Every message sent to sub-controls is marked as handled, so responses from them will be
+    // ignored and there won't be any feedback loops.
     fn sync_fields(&self, ui: &mut UserInterface, color: Color, hsv: Hsv) {
+        ui.send_message(mark_handled(HueBarMessage::hue(
+            self.hue_bar,
+            MessageDirection::ToWidget,
+            hsv.hue(),
+        )));
+
+        ui.send_message(mark_handled(SaturationBrightnessFieldMessage::hue(
+            self.saturation_brightness_field,
+            MessageDirection::ToWidget,
+            hsv.hue(),
+        )));
+
+        ui.send_message(mark_handled(SaturationBrightnessFieldMessage::saturation(
+            self.saturation_brightness_field,
+            MessageDirection::ToWidget,
+            hsv.saturation(),
+        )));
+
+        ui.send_message(mark_handled(SaturationBrightnessFieldMessage::brightness(
+            self.saturation_brightness_field,
+            MessageDirection::ToWidget,
+            hsv.brightness(),
+        )));
+
+        ui.send_message(mark_handled(AlphaBarMessage::alpha(
+            self.alpha_bar,
+            MessageDirection::ToWidget,
+            color.a as f32,
+        )));
+
         ui.send_message(mark_handled(NumericUpDownMessage::value(
             self.hue,
             MessageDirection::ToWidget,
@@ -788,12 +920,65 @@ impl ColorPicker {
             color.a as f32,
         )));
 
+        ui.send_message(mark_handled(TextBoxMessage::text(
+            self.hex,
+            MessageDirection::ToWidget,
+            format_hex_color(color),
+        )));
+
         ui.send_message(mark_handled(WidgetMessage::background(
             self.color_mark,
             MessageDirection::ToWidget,
             Brush::Solid(color),
         )));
     }
+
+    fn set_color(&mut self, ui: &mut UserInterface, color: Color) -> bool {
+        if self.color != color {
+            self.color = color;
+            self.hsv = Hsv::from(color);
+
+            self.sync_fields(ui, color, self.hsv);
+
+            ui.send_message(ColorPickerMessage::color(
+                self.handle,
+                MessageDirection::FromWidget,
+                color,
+            ));
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn set_hsv(&mut self, ui: &mut UserInterface, hsv: Hsv) -> bool {
+        if self.hsv != hsv {
+            self.hsv = hsv;
+            let opaque = Color::from(hsv);
+            self.color = Color::from_rgba(opaque.r, opaque.g, opaque.b, self.color.a);
+
+            self.sync_fields(ui, self.color, hsv);
+
+            ui.send_message(ColorPickerMessage::color(
+                self.handle,
+                MessageDirection::FromWidget,
+                self.color,
+            ));
+
+            true
+        } else {
+            false
+        }
+    }
+
+    fn commit(&self, ui: &mut UserInterface) {
+        ui.send_message(ColorPickerMessage::commit(
+            self.handle,
+            MessageDirection::FromWidget,
+            self.color,
+        ));
+    }
 }
 
 impl Control for ColorPicker {
@@ -816,39 +1001,31 @@ impl Control for ColorPicker {
         node_map.resolve(&mut self.hue);
         node_map.resolve(&mut self.saturation);
         node_map.resolve(&mut self.brightness);
+        node_map.resolve(&mut self.hex);
         node_map.resolve(&mut self.color_mark);
     }
 
     fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
         self.widget.handle_routed_message(ui, message);
 
+        if message.handled() && message.destination() != self.handle {
+            // Responses of sub-controls to synchronization, must be ignored.
+            return;
+        }
+
         if let Some(&HueBarMessage::Hue(hue)) = message.data::<HueBarMessage>() {
             if message.destination() == self.hue_bar
                 && message.direction() == MessageDirection::FromWidget
             {
-                ui.send_message(SaturationBrightnessFieldMessage::hue(
-                    self.saturation_brightness_field,
-                    MessageDirection::ToWidget,
-                    hue,
-                ));
-
                 let mut hsv = self.hsv;
                 hsv.set_hue(hue);
-                ui.send_message(ColorPickerMessage::hsv(
-                    self.handle,
-                    MessageDirection::ToWidget,
-                    hsv,
-                ));
+                self.set_hsv(ui, hsv);
             }
         } else if let Some(&AlphaBarMessage::Alpha(alpha)) = message.data::<AlphaBarMessage>() {
             if message.destination() == self.alpha_bar
                 && message.direction() == MessageDirection::FromWidget
             {
-                ui.send_message(ColorPickerMessage::color(
-                    self.handle,
-                    MessageDirection::ToWidget,
-                    Color::from_rgba(self.color.r, self.color.g, self.color.b, alpha as u8),
-                ));
+                self.set_color(ui, self.color.with_new_alpha(alpha as u8));
             }
         } else if let Some(msg) = message.data::<SaturationBrightnessFieldMessage>() {
             if message.destination() == self.saturation_brightness_field
@@ -858,70 +1035,75 @@ impl Control for ColorPicker {
                     SaturationBrightnessFieldMessage::Brightness(brightness) => {
                         let mut hsv = self.hsv;
                         hsv.set_brightness(brightness);
-                        ui.send_message(ColorPickerMessage::hsv(
-                            self.handle,
-                            MessageDirection::ToWidget,
-                            hsv,
-                        ));
+                        self.set_hsv(ui, hsv);
                     }
                     SaturationBrightnessFieldMessage::Saturation(saturation) => {
                         let mut hsv = self.hsv;
                         hsv.set_saturation(saturation);
-                        ui.send_message(ColorPickerMessage::hsv(
-                            self.handle,
-                            MessageDirection::ToWidget,
-                            hsv,
-                        ));
+                        self.set_hsv(ui, hsv);
                     }
                     _ => {}
                 }
             }
+        } else if let Some(&WidgetMessage::MouseUp { button, .. }) = message.data::<WidgetMessage>()
+        {
+            if button == MouseButton::Left
+                && message.direction() == MessageDirection::FromWidget
+                && (message.destination() == self.hue_bar
+                    || message.destination() == self.alpha_bar
+                    || message.destination() == self.saturation_brightness_field)
+            {
+                self.commit(ui);
+            }
         } else if let Some(&NumericUpDownMessage::Value(value)) =
             message.data::<NumericUpDownMessage<f32>>()
         {
-            if message.direction() == MessageDirection::FromWidget && !message.handled() {
-                if message.destination() == self.hue {
-                    ui.send_message(HueBarMessage::hue(
-                        self.hue_bar,
-                        MessageDirection::ToWidget,
-                        value,
-                    ));
+            if message.direction() == MessageDirection::FromWidget {
+                let mut hsv = self.hsv;
+                let color = self.color;
+                let changed = if message.destination() == self.hue {
+                    hsv.set_hue(value);
+                    self.set_hsv(ui, hsv)
                 } else if message.destination() == self.saturation {
-                    ui.send_message(SaturationBrightnessFieldMessage::saturation(
-                        self.saturation_brightness_field,
-                        MessageDirection::ToWidget,
-                        value,
-                    ));
+                    hsv.set_saturation(value);
+                    self.set_hsv(ui, hsv)
                 } else if message.destination() == self.brightness {
-                    ui.send_message(SaturationBrightnessFieldMessage::brightness(
-                        self.saturation_brightness_field,
-                        MessageDirection::ToWidget,
-                        value,
-                    ));
+                    hsv.set_brightness(value);
+                    self.set_hsv(ui, hsv)
                 } else if message.destination() == self.red {
-                    ui.send_message(ColorPickerMessage::color(
-                        self.handle,
-                        MessageDirection::ToWidget,
-                        Color::from_rgba(value as u8, self.color.g, self.color.b, self.color.a),
-                    ));
+                    self.set_color(ui, Color::from_rgba(value as u8, color.g, color.b, color.a))
                 } else if message.destination() == self.green {
-                    ui.send_message(ColorPickerMessage::color(
-                        self.handle,
-                        MessageDirection::ToWidget,
-                        Color::from_rgba(self.color.r, value as u8, self.color.b, self.color.a),
-                    ));
+                    self.set_color(ui, Color::from_rgba(color.r, value as u8, color.b, color.a))
                 } else if message.destination() == self.blue {
-                    ui.send_message(ColorPickerMessage::color(
-                        self.handle,
-                        MessageDirection::ToWidget,
-                        Color::from_rgba(self.color.r, self.color.g, value as u8, self.color.a),
-                    ));
+                    self.set_color(ui, Color::from_rgba(color.r, color.g, value as u8, color.a))
                 } else if message.destination() == self.alpha {
-                    ui.send_message(ColorPickerMessage::color(
-                        self.handle,
-                        MessageDirection::ToWidget,
-                        Color::from_rgba(self.color.r, self.color.g, self.color.b, value as u8),
-                    ));
+                    self.set_color(ui, color.with_new_alpha(value as u8))
+                } else {
+                    false
+                };
+
+                if changed {
+                    self.commit(ui);
+                }
+            }
+        } else if let Some(TextBoxMessage::Text(text)) = message.data::<TextBoxMessage>() {
+            if message.destination() == self.hex
+                && message.direction() == MessageDirection::FromWidget
+            {
+                match parse_hex_color(text, self.color.a) {
+                    Some(color) => {
+                        if self.set_color(ui, color) {
+                            self.commit(ui);
+                        }
+                    }
+                    None => {
+                        // Restore text of the field, so user will see that the input is wrong.
+                        ui.send_message(mark_handled(TextBoxMessage::text(
+                            self.hex,
+                            MessageDirection::ToWidget,
+                            format_hex_color(self.color),
+                        )));
+                    }
                 }
             }
         } else if let Some(msg) = message.data::<ColorPickerMessage>() {
@@ -930,27 +1112,12 @@ impl Control for ColorPicker {
             {
                 match *msg {
                     ColorPickerMessage::Color(color) => {
-                        if self.color != color {
-                            self.color = color;
-                            self.hsv = Hsv::from(color);
-
-                            self.sync_fields(ui, color, self.hsv);
-
-                            ui.send_message(message.reverse());
-                        }
+                        self.set_color(ui, color);
                     }
                     ColorPickerMessage::Hsv(hsv) => {
-                        if self.hsv != hsv {
-                            self.hsv = hsv;
-                            let opaque = Color::from(hsv);
-                            self.color =
-                                Color::from_rgba(opaque.r, opaque.g, opaque.b, self.color.a);
-
-                            self.sync_fields(ui, self.color, hsv);
-
-                            ui.send_message(message.reverse());
-                        }
+                        self.set_hsv(ui, hsv);
                     }
+                    ColorPickerMessage::Commit(_) => {}
                 }
             }
         }
@@ -1019,6 +1186,7 @@ impl ColorPickerBuilder {
         let brightness;
         let color_mark;
         let alpha;
+        let hex;
         let hsv = Hsv::from(self.color);
 
         let numerics_grid = GridBuilder::new(
@@ -1058,6 +1226,20 @@ impl ColorPickerBuilder {
                 .with_child({
                     alpha = make_input_field(ctx, self.color.a as f32, 255.0, 3, 1);
                     alpha
+                })
+                .with_child(make_text_mark(ctx, "#", 3, 2))
+                .with_child({
+                    hex = TextBoxBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(1.0))
+                            .on_row(3)
+                            .on_column(3),
+                    )
+                    .with_text(format_hex_color(self.color))
+                    .with_vertical_text_alignment(VerticalAlignment::Center)
+                    .with_text_commit_mode(TextCommitMode::LostFocusPlusEnter)
+                    .build(ctx);
+                    hex
                 }),
         )
         .add_column(Column::strict(10.0))
@@ -1082,6 +1264,9 @@ impl ColorPickerBuilder {
                                     .with_margin(Thickness::uniform(1.0))
                                     .on_column(0),
                             )
+                            .with_hue(hsv.hue())
+                            .with_saturation(hsv.saturation())
+                            .with_brightness(hsv.brightness())
                             .build(ctx);
                             saturation_brightness_field
                         })
@@ -1091,6 +1276,7 @@ impl ColorPickerBuilder {
                                     .with_margin(Thickness::uniform(1.0))
                                     .on_column(1),
                             )
+                            .with_hue(hsv.hue())
                             .build(ctx);
                             hue_bar
                         })
@@ -1112,7 +1298,8 @@ impl ColorPickerBuilder {
                                         color_mark = BorderBuilder::new(
                                             WidgetBuilder::new()
                                                 .on_row(0)
-                                                .with_margin(Thickness::uniform(1.0)),
+                                                .with_margin(Thickness::uniform(1.0))
+                                                .with_background(Brush::Solid(self.color)),
                                         )
                                         .build(ctx);
                                         color_mark
@@ -1149,6 +1336,7 @@ impl ColorPickerBuilder {
             hsv,
             alpha_bar,
             alpha,
+            hex,
         };
         ctx.add_node(UiNode::new(picker))
     }
@@ -1252,6 +1440,32 @@ impl Control for ColorField {
                     picker.color,
                 ));
             }
+        } else if let Some(msg) = message.data::<ColorPickerMessage>() {
+            if message.destination() == self.picker
+                && message.direction() == MessageDirection::FromWidget
+            {
+                match *msg {
+                    ColorPickerMessage::Color(color) => {
+                        // Picker is synced with the field when the field changes its color,
+                        // there is no need to send preview in this case.
+                        if color != self.color {
+                            ui.send_message(ColorFieldMessage::preview(
+                                self.handle,
+                                MessageDirection::FromWidget,
+                                color,
+                            ));
+                        }
+                    }
+                    ColorPickerMessage::Commit(color) => {
+                        ui.send_message(ColorFieldMessage::color(
+                            self.handle,
+                            MessageDirection::ToWidget,
+                            color,
+                        ));
+                    }
+                    ColorPickerMessage::Hsv(_) => {}
+                }
+            }
         }
     }
 }
@@ -1294,3 +1508,35 @@ impl ColorFieldBuilder {
         ctx.add_node(UiNode::new(field))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        color::{format_hex_color, parse_hex_color},
+        core::color::Color,
+    };
+
+    #[test]
+    fn test_parse_hex_color() {
+        let color = Color::from_rgba(0x12, 0xAB, 0xCD, 0xEF);
+        assert_eq!(parse_hex_color("#12ABCDEF", 255), Some(color));
+        assert_eq!(parse_hex_color("12abcdef", 255), Some(color));
+        assert_eq!(parse_hex_color(" 0x12 AB CD EF ", 255), Some(color));
+        assert_eq!(
+            parse_hex_color("#12ABCD", 100),
+            Some(Color::from_rgba(0x12, 0xAB, 0xCD, 100))
+        );
+        assert_eq!(
+            parse_hex_color("#F0A", 100),
+            Some(Color::from_rgba(0xFF, 0x00, 0xAA, 100))
+        );
+        assert_eq!(
+            parse_hex_color("f0a8", 100),
+            Some(Color::from_rgba(0xFF, 0x00, 0xAA, 0x88))
+        );
+        assert_eq!(parse_hex_color("#12ABC", 255), None);
+        assert_eq!(parse_hex_color("#12ABCDEG", 255), None);
+        assert_eq!(parse_hex_color("", 255), None);
+        assert_eq!(parse_hex_color(&format_hex_color(color), 0), Some(color));
+    }
+}
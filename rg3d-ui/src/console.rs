@@ -0,0 +1,364 @@
+//! In-game console that shows records of the engine log, see [`ConsoleBuilder`].
+
+use crate::{
+    border::BorderBuilder,
+    brush::Brush,
+    button::{ButtonBuilder, ButtonMessage},
+    core::{
+        color::Color,
+        log::{LogBuffer, MessageKind, SharedLogBuffer},
+        pool::Handle,
+    },
+    define_constructor,
+    dropdown_list::{DropdownListBuilder, DropdownListMessage},
+    formatted_text::{TextSpan, WrapMode},
+    grid::{Column, GridBuilder, Row},
+    message::{MessageDirection, UiMessage},
+    scroll_viewer::ScrollViewerBuilder,
+    text::{TextBuilder, TextMessage},
+    text_box::{TextBoxBuilder, TextBoxMessage, TextCommitMode},
+    widget::{Widget, WidgetBuilder},
+    BuildContext, Control, NodeHandleMapping, Thickness, UiNode, UserInterface, VerticalAlignment,
+};
+use std::{
+    any::{Any, TypeId},
+    ops::{Deref, DerefMut},
+    sync::mpsc::Sender,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConsoleMessage {
+    /// Sets minimal kind of shown records.
+    MinKind(MessageKind),
+    /// Sets search string, only records that contains it will be shown.
+    Search(String),
+    /// Removes every record from the log buffer.
+    Clear,
+}
+
+impl ConsoleMessage {
+    define_constructor!(ConsoleMessage:MinKind => fn min_kind(MessageKind), layout: false);
+    define_constructor!(ConsoleMessage:Search => fn search(String), layout: false);
+    define_constructor!(ConsoleMessage:Clear => fn clear(), layout: false);
+}
+
+/// Shows last records of a log buffer (see [`crate::core::log::RingBufferSink`]), newest
+/// records are on top. Records could be filtered by kind and by a search string.
+#[derive(Clone)]
+pub struct Console {
+    widget: Widget,
+    buffer: SharedLogBuffer,
+    min_kind: MessageKind,
+    search: String,
+    max_records: usize,
+    // Total amount of records of the buffer at the moment of last refresh, `None` forces refresh.
+    shown_total: Option<u64>,
+    kind_selector: Handle<UiNode>,
+    search_box: Handle<UiNode>,
+    clear: Handle<UiNode>,
+    text: Handle<UiNode>,
+}
+
+crate::define_widget_deref!(Console);
+
+fn kind_color(kind: MessageKind) -> Color {
+    match kind {
+        MessageKind::Trace => Color::opaque(130, 130, 130),
+        MessageKind::Debug => Color::opaque(180, 180, 180),
+        MessageKind::Information => Color::WHITE,
+        MessageKind::Warning => Color::opaque(255, 210, 0),
+        MessageKind::Error => Color::opaque(255, 70, 70),
+    }
+}
+
+/// Makes colored spans of last `max_records` records with given minimal kind which contains
+/// search string (case insensitive), newest records go first.
+pub fn make_console_spans(
+    buffer: &LogBuffer,
+    min_kind: MessageKind,
+    search: &str,
+    max_records: usize,
+) -> Vec<TextSpan> {
+    let search = search.to_lowercase();
+    buffer
+        .records()
+        .rev()
+        .filter(|r| r.kind >= min_kind)
+        .filter(|r| search.is_empty() || r.to_string().to_lowercase().contains(&search))
+        .take(max_records)
+        .map(|r| TextSpan::new(format!("{}\n", r)).with_color(kind_color(r.kind)))
+        .collect()
+}
+
+impl Console {
+    pub fn buffer(&self) -> &SharedLogBuffer {
+        &self.buffer
+    }
+
+    pub fn min_kind(&self) -> MessageKind {
+        self.min_kind
+    }
+
+    pub fn search(&self) -> &str {
+        &self.search
+    }
+}
+
+impl Control for Console {
+    fn query_component(&self, type_id: TypeId) -> Option<&dyn Any> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&mut self, node_map: &NodeHandleMapping) {
+        node_map.resolve(&mut self.kind_selector);
+        node_map.resolve(&mut self.search_box);
+        node_map.resolve(&mut self.clear);
+        node_map.resolve(&mut self.text);
+    }
+
+    fn update(&mut self, _dt: f32, sender: &Sender<UiMessage>) {
+        // Hidden console does not need to be refreshed, it will be refreshed when shown.
+        if !self.is_globally_visible() {
+            return;
+        }
+
+        let buffer = self.buffer.lock().unwrap();
+        if self.shown_total != Some(buffer.total()) {
+            self.shown_total = Some(buffer.total());
+            let spans = make_console_spans(&buffer, self.min_kind, &self.search, self.max_records);
+            let _ = sender.send(TextMessage::spans(
+                self.text,
+                MessageDirection::ToWidget,
+                spans,
+            ));
+        }
+    }
+
+    fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
+        self.widget.handle_routed_message(ui, message);
+
+        if let Some(msg) = message.data::<ConsoleMessage>() {
+            if message.destination() == self.handle
+                && message.direction() == MessageDirection::ToWidget
+            {
+                match msg {
+                    ConsoleMessage::MinKind(kind) => self.min_kind = *kind,
+                    ConsoleMessage::Search(search) => self.search = search.clone(),
+                    ConsoleMessage::Clear => self.buffer.lock().unwrap().clear(),
+                }
+                self.shown_total = None;
+            }
+        } else if let Some(DropdownListMessage::SelectionChanged(Some(index))) =
+            message.data::<DropdownListMessage>()
+        {
+            if message.destination() == self.kind_selector
+                && message.direction() == MessageDirection::FromWidget
+            {
+                if let Some(kind) = MessageKind::all().get(*index) {
+                    ui.send_message(ConsoleMessage::min_kind(
+                        self.handle,
+                        MessageDirection::ToWidget,
+                        *kind,
+                    ));
+                }
+            }
+        } else if let Some(TextBoxMessage::Text(text)) = message.data::<TextBoxMessage>() {
+            if message.destination() == self.search_box
+                && message.direction() == MessageDirection::FromWidget
+            {
+                ui.send_message(ConsoleMessage::search(
+                    self.handle,
+                    MessageDirection::ToWidget,
+                    text.clone(),
+                ));
+            }
+        } else if let Some(ButtonMessage::Click) = message.data::<ButtonMessage>() {
+            if message.destination() == self.clear {
+                ui.send_message(ConsoleMessage::clear(
+                    self.handle,
+                    MessageDirection::ToWidget,
+                ));
+            }
+        }
+    }
+}
+
+pub struct ConsoleBuilder {
+    widget_builder: WidgetBuilder,
+    buffer: SharedLogBuffer,
+    min_kind: MessageKind,
+    max_records: usize,
+}
+
+impl ConsoleBuilder {
+    pub fn new(widget_builder: WidgetBuilder, buffer: SharedLogBuffer) -> Self {
+        Self {
+            widget_builder,
+            buffer,
+            min_kind: MessageKind::Information,
+            max_records: 200,
+        }
+    }
+
+    pub fn with_min_kind(mut self, kind: MessageKind) -> Self {
+        self.min_kind = kind;
+        self
+    }
+
+    /// Sets max amount of shown records, large values could make console slow because every
+    /// record is a separate text span.
+    pub fn with_max_records(mut self, max_records: usize) -> Self {
+        self.max_records = max_records;
+        self
+    }
+
+    pub fn build(self, ctx: &mut BuildContext) -> Handle<UiNode> {
+        let items = MessageKind::all()
+            .iter()
+            .map(|kind| {
+                TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::left(2.0)))
+                    .with_vertical_text_alignment(VerticalAlignment::Center)
+                    .with_text(format!("{:?}", kind))
+                    .build(ctx)
+            })
+            .collect::<Vec<_>>();
+        let selected = MessageKind::all()
+            .iter()
+            .position(|kind| *kind == self.min_kind);
+
+        let kind_selector;
+        let search_box;
+        let clear;
+        let text;
+        let content = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_background(Brush::Solid(Color::from_rgba(20, 20, 20, 220)))
+                .with_child(
+                    GridBuilder::new(
+                        WidgetBuilder::new()
+                            .with_child(
+                                GridBuilder::new(
+                                    WidgetBuilder::new()
+                                        .with_child({
+                                            kind_selector = DropdownListBuilder::new(
+                                                WidgetBuilder::new()
+                                                    .with_margin(Thickness::uniform(1.0)),
+                                            )
+                                            .with_items(items)
+                                            .with_opt_selected(selected)
+                                            .build(ctx);
+                                            kind_selector
+                                        })
+                                        .with_child({
+                                            search_box = TextBoxBuilder::new(
+                                                WidgetBuilder::new()
+                                                    .on_column(1)
+                                                    .with_margin(Thickness::uniform(1.0)),
+                                            )
+                                            .with_vertical_text_alignment(VerticalAlignment::Center)
+                                            .with_text_commit_mode(TextCommitMode::Immediate)
+                                            .build(ctx);
+                                            search_box
+                                        })
+                                        .with_child({
+                                            clear = ButtonBuilder::new(
+                                                WidgetBuilder::new()
+                                                    .on_column(2)
+                                                    .with_margin(Thickness::uniform(1.0)),
+                                            )
+                                            .with_text("Clear")
+                                            .build(ctx);
+                                            clear
+                                        }),
+                                )
+                                .add_row(Row::stretch())
+                                .add_column(Column::strict(110.0))
+                                .add_column(Column::stretch())
+                                .add_column(Column::strict(60.0))
+                                .build(ctx),
+                            )
+                            .with_child(
+                                ScrollViewerBuilder::new(
+                                    WidgetBuilder::new()
+                                        .on_row(1)
+                                        .with_margin(Thickness::uniform(2.0)),
+                                )
+                                .with_content({
+                                    text = TextBuilder::new(WidgetBuilder::new())
+                                        .with_wrap(WrapMode::Word)
+                                        .build(ctx);
+                                    text
+                                })
+                                .with_horizontal_scroll_allowed(false)
+                                .build(ctx),
+                            ),
+                    )
+                    .add_row(Row::strict(26.0))
+                    .add_row(Row::stretch())
+                    .add_column(Column::stretch())
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+
+        let console = Console {
+            widget: self.widget_builder.with_child(content).build(),
+            buffer: self.buffer,
+            min_kind: self.min_kind,
+            search: Default::default(),
+            max_records: self.max_records,
+            shown_total: None,
+            kind_selector,
+            search_box,
+            clear,
+            text,
+        };
+
+        ctx.add_node(UiNode::new(console))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        console::make_console_spans,
+        core::log::{LogRecord, LogSink, MessageKind, RingBufferSink},
+    };
+
+    #[test]
+    fn test_console_filter() {
+        let mut sink = RingBufferSink::new(10);
+        for (kind, message) in [
+            (MessageKind::Debug, "Loading texture"),
+            (MessageKind::Error, "Unable to load texture"),
+            (MessageKind::Warning, "Physics body is too fast"),
+        ] {
+            sink.write(&LogRecord {
+                kind,
+                target: "game".to_owned(),
+                message: message.to_owned(),
+            });
+        }
+
+        let buffer = sink.buffer();
+        let buffer = buffer.lock().unwrap();
+
+        let all = make_console_spans(&buffer, MessageKind::Trace, "", 10);
+        assert_eq!(all.len(), 3);
+        // Newest first.
+        assert!(all[0].text.contains("Physics"));
+
+        let errors = make_console_spans(&buffer, MessageKind::Warning, "TEXTURE", 10);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].text.contains("Unable to load texture"));
+
+        assert_eq!(
+            make_console_spans(&buffer, MessageKind::Trace, "", 2).len(),
+            2
+        );
+    }
+}
@@ -1,9 +1,9 @@
-use crate::core::algebra::Vector2;
+use crate::core::algebra::{Vector2, Vector3};
 use crate::{
     brush::Brush,
     core::{
         color::Color,
-        math::{self, Rect, TriangleDefinition},
+        math::{self, triangulator::triangulate, Rect, TriangleDefinition},
     },
     formatted_text::FormattedText,
     ttf::SharedFont,
@@ -51,14 +51,103 @@ impl PartialEq for SharedTexture {
 pub enum CommandTexture {
     None,
     Texture(SharedTexture),
-    Font(SharedFont),
+    /// A page of font atlas.
+    Font {
+        font: SharedFont,
+        page_index: usize,
+    },
 }
 
-/// A set of triangles that will be used for clipping.
-#[derive(Clone)]
+/// Shape that clips a widget and all its descendants, see
+/// [`crate::widget::WidgetBuilder::with_clip_shape`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClipShape {
+    /// Bounds of a widget with rounded corners.
+    RoundedRect { radius: f32 },
+    /// The largest circle that fits bounds of a widget, centered in the bounds.
+    Circle,
+    /// Convex polygon, points are relative to left-top corner of bounds of a widget.
+    ConvexPolygon(Vec<Vector2<f32>>),
+}
+
+impl ClipShape {
+    /// Returns outline of the shape for given bounds.
+    pub fn path(&self, bounds: &Rect<f32>) -> Path {
+        match self {
+            ClipShape::RoundedRect { radius } => Path::rounded_rect(bounds, *radius),
+            ClipShape::Circle => Path::circle(
+                bounds.position + bounds.size.scale(0.5),
+                bounds.w().min(bounds.h()) * 0.5,
+            ),
+            ClipShape::ConvexPolygon(points) => {
+                let mut path = Path::new();
+                for (i, point) in points.iter().enumerate() {
+                    let point = bounds.position + point;
+                    path = if i == 0 {
+                        path.move_to(point)
+                    } else {
+                        path.line_to(point)
+                    };
+                }
+                path.close()
+            }
+        }
+    }
+
+    /// Checks if a point is inside of the shape with given bounds.
+    pub fn contains_point(&self, bounds: &Rect<f32>, point: Vector2<f32>) -> bool {
+        match self {
+            ClipShape::RoundedRect { radius } => {
+                if !bounds.contains(point) {
+                    return false;
+                }
+                let radius = radius.min(bounds.w() * 0.5).min(bounds.h() * 0.5).max(0.0);
+                // Distance to a rectangle that is shrunk by the radius.
+                let nearest = Vector2::new(
+                    point
+                        .x
+                        .max(bounds.x() + radius)
+                        .min(bounds.x() + bounds.w() - radius),
+                    point
+                        .y
+                        .max(bounds.y() + radius)
+                        .min(bounds.y() + bounds.h() - radius),
+                );
+                (point - nearest).norm() <= radius
+            }
+            ClipShape::Circle => {
+                let center = bounds.position + bounds.size.scale(0.5);
+                (point - center).norm() <= bounds.w().min(bounds.h()) * 0.5
+            }
+            ClipShape::ConvexPolygon(points) => {
+                let mut sign = 0.0f32;
+                for (i, a) in points.iter().enumerate() {
+                    let b = points[(i + 1) % points.len()];
+                    let edge = b - a;
+                    let to_point = point - (bounds.position + a);
+                    let cross = edge.x * to_point.y - edge.y * to_point.x;
+                    if cross != 0.0 {
+                        if sign != 0.0 && cross.signum() != sign {
+                            return false;
+                        }
+                        sign = cross.signum();
+                    }
+                }
+                points.len() >= 3
+            }
+        }
+    }
+}
+
+/// A set of triangles that will be used for clipping. Geometry could consist of multiple shapes,
+/// in this case clipping region is an intersection of the shapes.
+#[derive(Clone, Default)]
 pub struct ClippingGeometry {
     pub vertex_buffer: Vec<Vertex>,
     pub triangle_buffer: Vec<TriangleDefinition>,
+    /// Ranges of triangles of each shape. Triangles of a shape must not overlap each other. If
+    /// empty, every triangle is considered a part of the same shape.
+    pub shapes: Vec<Range<usize>>,
 }
 
 impl Draw for ClippingGeometry {
@@ -84,8 +173,71 @@ impl Draw for ClippingGeometry {
 }
 
 impl ClippingGeometry {
+    /// Adds filled contours of a path as a new shape, clipping region becomes an intersection of
+    /// existing region and the path.
+    pub fn push_shape(&mut self, path: &Path) {
+        self.make_shapes_explicit();
+        let start = self.triangle_buffer.len();
+        self.push_path_filled(path, 0.0);
+        self.shapes.push(start..self.triangle_buffer.len());
+    }
+
+    /// Makes clipping region an intersection of this and other geometries.
+    pub fn intersect(&mut self, other: &ClippingGeometry) {
+        self.make_shapes_explicit();
+        let vertex_offset = self.vertex_buffer.len() as u32;
+        let triangle_offset = self.triangle_buffer.len();
+        self.vertex_buffer
+            .extend(other.vertex_buffer.iter().cloned());
+        self.triangle_buffer
+            .extend(other.triangle_buffer.iter().map(|triangle| {
+                TriangleDefinition([
+                    triangle[0] + vertex_offset,
+                    triangle[1] + vertex_offset,
+                    triangle[2] + vertex_offset,
+                ])
+            }));
+        if other.shapes.is_empty() {
+            self.shapes
+                .push(triangle_offset..self.triangle_buffer.len());
+        } else {
+            self.shapes.extend(
+                other
+                    .shapes
+                    .iter()
+                    .map(|shape| (shape.start + triangle_offset)..(shape.end + triangle_offset)),
+            );
+        }
+    }
+
+    fn make_shapes_explicit(&mut self) {
+        if self.shapes.is_empty() && !self.triangle_buffer.is_empty() {
+            self.shapes.push(0..self.triangle_buffer.len());
+        }
+    }
+
+    /// Returns amount of shapes, every pixel of clipping region is covered by this amount of
+    /// triangles.
+    pub fn shape_count(&self) -> usize {
+        if self.shapes.is_empty() {
+            1
+        } else {
+            self.shapes.len()
+        }
+    }
+
     pub fn is_contains_point(&self, pos: Vector2<f32>) -> bool {
-        for triangle in self.triangle_buffer.iter() {
+        if self.shapes.is_empty() {
+            return self.is_range_contains_point(0..self.triangle_buffer.len(), pos);
+        }
+
+        self.shapes
+            .iter()
+            .all(|shape| self.is_range_contains_point(shape.clone(), pos))
+    }
+
+    fn is_range_contains_point(&self, range: Range<usize>, pos: Vector2<f32>) -> bool {
+        for triangle in self.triangle_buffer[range].iter() {
             if let Some((va, vb, vc)) = self.triangle_points(triangle) {
                 if math::is_point_inside_2d_triangle(pos, va.pos, vb.pos, vc.pos) {
                     return true;
@@ -122,6 +274,342 @@ pub struct Command {
     pub clipping_geometry: Option<ClippingGeometry>,
 }
 
+/// Max distance (in units) between a curve and its polyline approximation.
+const PATH_TOLERANCE: f32 = 0.1;
+
+/// A segment of a [`Path`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    /// Starts new contour at given point.
+    MoveTo(Vector2<f32>),
+    /// Straight line from current point to given point.
+    LineTo(Vector2<f32>),
+    /// Quadratic Bezier curve from current point to `end` point.
+    QuadraticTo {
+        control: Vector2<f32>,
+        end: Vector2<f32>,
+    },
+    /// Closes current contour with a straight line to its first point.
+    Close,
+}
+
+/// A polyline approximation of a contour of a [`Path`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Contour {
+    pub points: Vec<Vector2<f32>>,
+    pub closed: bool,
+}
+
+/// Vector path - a set of contours made of straight lines and quadratic curves. Paths are
+/// tessellated every time they're drawn, so they look sharp at any scale. Use
+/// [`Draw::push_path_filled`] and [`Draw::push_path_stroked`] to draw a path.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn move_to(mut self, point: Vector2<f32>) -> Self {
+        self.segments.push(PathSegment::MoveTo(point));
+        self
+    }
+
+    pub fn line_to(mut self, point: Vector2<f32>) -> Self {
+        self.segments.push(PathSegment::LineTo(point));
+        self
+    }
+
+    pub fn quadratic_to(mut self, control: Vector2<f32>, end: Vector2<f32>) -> Self {
+        self.segments
+            .push(PathSegment::QuadraticTo { control, end });
+        self
+    }
+
+    pub fn close(mut self) -> Self {
+        self.segments.push(PathSegment::Close);
+        self
+    }
+
+    /// Creates a closed path of a circle, the circle is made of eight quadratic curves.
+    pub fn circle(center: Vector2<f32>, radius: f32) -> Self {
+        let mut path = Self::new().move_to(center + Vector2::new(radius, 0.0));
+        for i in 0..8 {
+            path = path.arc_segment(center, radius, i as f32 * std::f32::consts::FRAC_PI_4);
+        }
+        path.close()
+    }
+
+    /// Creates a closed path of a rectangle with rounded corners. Radius is clamped to half of
+    /// the smallest side of the rectangle.
+    pub fn rounded_rect(rect: &Rect<f32>, radius: f32) -> Self {
+        let radius = radius.min(rect.w() * 0.5).min(rect.h() * 0.5).max(0.0);
+        let left = rect.x() + radius;
+        let top = rect.y() + radius;
+        let right = rect.x() + rect.w() - radius;
+        let bottom = rect.y() + rect.h() - radius;
+
+        let half_pi = std::f32::consts::FRAC_PI_2;
+        let quarter_pi = std::f32::consts::FRAC_PI_4;
+
+        let mut path = Self::new().move_to(Vector2::new(right + radius, top));
+        // Corners go clockwise starting from the right top one, each corner is an arc of two
+        // segments.
+        for (i, center) in [
+            Vector2::new(right, bottom),
+            Vector2::new(left, bottom),
+            Vector2::new(left, top),
+            Vector2::new(right, top),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let start_angle = i as f32 * half_pi;
+            path =
+                path.line_to(center + Vector2::new(start_angle.cos(), start_angle.sin()) * radius);
+            if radius > 0.0 {
+                path = path.arc_segment(*center, radius, start_angle).arc_segment(
+                    *center,
+                    radius,
+                    start_angle + quarter_pi,
+                );
+            }
+        }
+        path.close()
+    }
+
+    // Adds a 45 degrees arc segment from given angle, it assumes that current point is on the arc.
+    fn arc_segment(self, center: Vector2<f32>, radius: f32, start_angle: f32) -> Self {
+        let half = std::f32::consts::FRAC_PI_8;
+        let control_angle = start_angle + half;
+        let control_distance = radius / half.cos();
+        let end_angle = start_angle + 2.0 * half;
+        self.quadratic_to(
+            center + Vector2::new(control_angle.cos(), control_angle.sin()) * control_distance,
+            center + Vector2::new(end_angle.cos(), end_angle.sin()) * radius,
+        )
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Returns a copy of the path with every point moved by given offset.
+    pub fn translated(&self, offset: Vector2<f32>) -> Self {
+        Self {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| match *segment {
+                    PathSegment::MoveTo(p) => PathSegment::MoveTo(p + offset),
+                    PathSegment::LineTo(p) => PathSegment::LineTo(p + offset),
+                    PathSegment::QuadraticTo { control, end } => PathSegment::QuadraticTo {
+                        control: control + offset,
+                        end: end + offset,
+                    },
+                    PathSegment::Close => PathSegment::Close,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns min and max corners of a rectangle that contains the path. Control points of
+    /// curves are included, so the rectangle could be a bit larger than the path.
+    pub fn bounds(&self) -> (Vector2<f32>, Vector2<f32>) {
+        let mut min = Vector2::new(f32::MAX, f32::MAX);
+        let mut max = Vector2::new(-f32::MAX, -f32::MAX);
+        let mut push = |p: &Vector2<f32>| {
+            min = Vector2::new(min.x.min(p.x), min.y.min(p.y));
+            max = Vector2::new(max.x.max(p.x), max.y.max(p.y));
+        };
+        for segment in self.segments.iter() {
+            match segment {
+                PathSegment::MoveTo(p) | PathSegment::LineTo(p) => push(p),
+                PathSegment::QuadraticTo { control, end } => {
+                    push(control);
+                    push(end);
+                }
+                PathSegment::Close => (),
+            }
+        }
+        if self.segments.is_empty() {
+            Default::default()
+        } else {
+            (min, max)
+        }
+    }
+
+    /// Converts the path into a set of polylines, max distance between curves and lines is
+    /// defined by `tolerance`.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Contour> {
+        fn push_point(contour: &mut Contour, point: Vector2<f32>) {
+            if contour
+                .points
+                .last()
+                .map_or(true, |last| (last - point).norm_squared() > f32::EPSILON)
+            {
+                contour.points.push(point);
+            }
+        }
+
+        fn finish(contours: &mut Vec<Contour>, contour: &mut Contour) {
+            if contour.closed && contour.points.len() > 1 {
+                // Closing point is implicit.
+                if (contour.points[0] - contour.points[contour.points.len() - 1]).norm_squared()
+                    <= f32::EPSILON
+                {
+                    contour.points.pop();
+                }
+            }
+            let contour = std::mem::take(contour);
+            if contour.points.len() > 1 {
+                contours.push(contour);
+            }
+        }
+
+        let tolerance = tolerance.max(0.001);
+        let mut contours = Vec::new();
+        let mut contour = Contour::default();
+        let mut current = Vector2::default();
+        for segment in self.segments.iter() {
+            match *segment {
+                PathSegment::MoveTo(point) => {
+                    finish(&mut contours, &mut contour);
+                    push_point(&mut contour, point);
+                    current = point;
+                }
+                PathSegment::LineTo(point) => {
+                    if contour.points.is_empty() {
+                        push_point(&mut contour, current);
+                    }
+                    push_point(&mut contour, point);
+                    current = point;
+                }
+                PathSegment::QuadraticTo { control, end } => {
+                    if contour.points.is_empty() {
+                        push_point(&mut contour, current);
+                    }
+                    // Max deviation of a polyline with n segments is |p0 - 2c + p1| / (4 * n^2).
+                    let deviation = (current - control * 2.0 + end).norm();
+                    let count =
+                        ((deviation / (4.0 * tolerance)).sqrt().ceil() as usize).clamp(1, 64);
+                    for i in 1..=count {
+                        let t = i as f32 / count as f32;
+                        let k = 1.0 - t;
+                        push_point(
+                            &mut contour,
+                            current * (k * k) + control * (2.0 * k * t) + end * (t * t),
+                        );
+                    }
+                    current = end;
+                }
+                PathSegment::Close => {
+                    if let Some(first) = contour.points.first().cloned() {
+                        contour.closed = true;
+                        finish(&mut contours, &mut contour);
+                        current = first;
+                    }
+                }
+            }
+        }
+        finish(&mut contours, &mut contour);
+        contours
+    }
+}
+
+// Returns offset direction of each point of a polyline, directions are scaled so an offset line
+// stays parallel to segments (miter join). Directions point to the left side of segments.
+fn polyline_normals(points: &[Vector2<f32>], closed: bool) -> Vec<Vector2<f32>> {
+    let count = points.len();
+    let segment_normal = |a: usize, b: usize| get_line_thickness_vector(points[a], points[b], 2.0);
+
+    (0..count)
+        .map(|i| {
+            let prev = if i > 0 {
+                Some(segment_normal(i - 1, i))
+            } else if closed {
+                Some(segment_normal(count - 1, 0))
+            } else {
+                None
+            };
+            let next = if i + 1 < count {
+                Some(segment_normal(i, i + 1))
+            } else if closed {
+                Some(segment_normal(count - 1, 0))
+            } else {
+                None
+            };
+
+            match (prev, next) {
+                (Some(prev), Some(next)) => match (prev + next).try_normalize(f32::EPSILON) {
+                    // Limit length of miter, so sharp corners won't produce long spikes.
+                    Some(miter) => miter.scale(1.0 / miter.dot(&next).max(0.25)),
+                    None => next,
+                },
+                (Some(normal), None) | (None, Some(normal)) => normal,
+                (None, None) => Vector2::default(),
+            }
+        })
+        .collect()
+}
+
+// Pushes a triangle strip along a polyline, each column is (offset along normal, alpha). Vertex
+// colors are white even for transparent vertices, so there won't be dark fringes on edges.
+fn push_polyline_strip<D: Draw + ?Sized>(
+    drawer: &mut D,
+    points: &[Vector2<f32>],
+    closed: bool,
+    columns: &[(f32, u8)],
+) {
+    if points.len() < 2 || columns.len() < 2 {
+        return;
+    }
+
+    let normals = polyline_normals(points, closed);
+    let first = drawer.last_vertex_index();
+    for (point, normal) in points.iter().zip(normals.iter()) {
+        for &(offset, alpha) in columns {
+            drawer.push_vertex_raw(Vertex {
+                pos: point + normal.scale(offset),
+                tex_coord: Vector2::default(),
+                color: Color::from_rgba(255, 255, 255, alpha),
+            });
+        }
+    }
+
+    let stride = columns.len() as u32;
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    for segment in 0..segment_count {
+        let a = first + segment as u32 * stride;
+        let b = first + ((segment + 1) % points.len()) as u32 * stride;
+        for column in 0..stride - 1 {
+            drawer.push_triangle(a + column, b + column, a + column + 1);
+            drawer.push_triangle(a + column + 1, b + column, b + column + 1);
+        }
+    }
+}
+
+// Returns 1.0 if left normals of a closed polyline point outside, -1.0 otherwise.
+fn outward_sign(points: &[Vector2<f32>]) -> f32 {
+    let mut area = 0.0;
+    for (i, a) in points.iter().enumerate() {
+        let b = points[(i + 1) % points.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    if area > 0.0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
 pub trait Draw {
     fn push_vertex(&mut self, pos: Vector2<f32>, tex_coord: Vector2<f32>);
 
@@ -305,6 +793,100 @@ pub trait Draw {
             }
         }
     }
+
+    /// Pushes filled contours of a path, open contours are closed implicitly. Contours must not
+    /// intersect themselves. Edges of the shape are anti-aliased using a strip of `feather` width
+    /// with fading alpha, use 0.0 to disable anti-aliasing.
+    fn push_path_filled(&mut self, path: &Path, feather: f32) {
+        let mut triangles = Vec::new();
+        for contour in path.flatten(PATH_TOLERANCE) {
+            let points = contour.points;
+            if points.len() < 3 {
+                continue;
+            }
+
+            let polygon = points
+                .iter()
+                .map(|p| Vector3::new(p.x, p.y, 0.0))
+                .collect::<Vec<_>>();
+            triangulate(&polygon, &mut triangles);
+
+            let index = self.last_vertex_index();
+            for &point in points.iter() {
+                self.push_vertex(point, Default::default());
+            }
+            for triangle in triangles.iter() {
+                self.push_triangle(
+                    index + triangle[0] as u32,
+                    index + triangle[1] as u32,
+                    index + triangle[2] as u32,
+                );
+            }
+
+            if feather > 0.0 {
+                let outward = outward_sign(&points) * feather;
+                push_polyline_strip(self, &points, true, &[(0.0, 255), (outward, 0)]);
+            }
+        }
+    }
+
+    /// Pushes lines along every contour of a path. Edges of lines are anti-aliased using a strip
+    /// of `feather` width with fading alpha, use 0.0 to disable anti-aliasing.
+    fn push_path_stroked(&mut self, path: &Path, thickness: f32, feather: f32) {
+        let half_thickness = thickness * 0.5;
+        for contour in path.flatten(PATH_TOLERANCE) {
+            if feather > 0.0 {
+                push_polyline_strip(
+                    self,
+                    &contour.points,
+                    contour.closed,
+                    &[
+                        (half_thickness + feather, 0),
+                        (half_thickness, 255),
+                        (-half_thickness, 255),
+                        (-half_thickness - feather, 0),
+                    ],
+                );
+            } else {
+                push_polyline_strip(
+                    self,
+                    &contour.points,
+                    contour.closed,
+                    &[(half_thickness, 255), (-half_thickness, 255)],
+                );
+            }
+        }
+    }
+
+    fn push_rounded_rect_filled(&mut self, rect: &Rect<f32>, corner_radius: f32, feather: f32) {
+        self.push_path_filled(&Path::rounded_rect(rect, corner_radius), feather);
+    }
+
+    fn push_rounded_rect(
+        &mut self,
+        rect: &Rect<f32>,
+        corner_radius: f32,
+        thickness: f32,
+        feather: f32,
+    ) {
+        // Keep the border inside of the rectangle, the same as push_rect does.
+        let offset = thickness * 0.5;
+        let rect = Rect::new(
+            rect.x() + offset,
+            rect.y() + offset,
+            rect.w() - thickness,
+            rect.h() - thickness,
+        );
+        self.push_path_stroked(
+            &Path::rounded_rect(&rect, corner_radius - offset),
+            thickness,
+            feather,
+        );
+    }
+
+    fn push_circle_filled(&mut self, center: Vector2<f32>, radius: f32, feather: f32) {
+        self.push_path_filled(&Path::circle(center, radius), feather);
+    }
 }
 
 pub struct DrawingContext {
@@ -312,6 +894,7 @@ pub struct DrawingContext {
     triangle_buffer: Vec<TriangleDefinition>,
     command_buffer: Vec<Command>,
     opacity_stack: Vec<f32>,
+    clip_stack: Vec<ClippingGeometry>,
     triangles_to_commit: usize,
 }
 
@@ -360,6 +943,7 @@ impl DrawingContext {
             command_buffer: Vec::new(),
             triangles_to_commit: 0,
             opacity_stack: vec![1.0],
+            clip_stack: Default::default(),
         }
     }
 
@@ -370,6 +954,7 @@ impl DrawingContext {
         self.command_buffer.clear();
         self.opacity_stack.clear();
         self.opacity_stack.push(1.0);
+        self.clip_stack.clear();
         self.triangles_to_commit = 0;
     }
 
@@ -388,14 +973,34 @@ impl DrawingContext {
         &self.command_buffer
     }
 
+    /// Pushes new opacity to the stack, it is multiplied with current opacity so nested widgets
+    /// will be faded together with their parents.
     pub fn push_opacity(&mut self, opacity: f32) {
-        self.opacity_stack.push(opacity);
+        let current = *self.opacity_stack.last().unwrap();
+        self.opacity_stack.push(current * opacity);
     }
 
     pub fn pop_opacity(&mut self) {
         self.opacity_stack.pop().unwrap();
     }
 
+    /// Pushes new clip shape to the stack, every command committed after this call will be
+    /// clipped by the shape and by every other shape in the stack.
+    pub fn push_clip_shape(&mut self, shape: &ClipShape, bounds: &Rect<f32>) {
+        let mut geometry = self.clip_stack.last().cloned().unwrap_or_default();
+        geometry.push_shape(&shape.path(bounds));
+        self.clip_stack.push(geometry);
+    }
+
+    pub fn pop_clip_shape(&mut self) {
+        self.clip_stack.pop().unwrap();
+    }
+
+    /// Returns clipping geometry that is used for newly committed commands.
+    pub fn clipping_geometry(&self) -> Option<&ClippingGeometry> {
+        self.clip_stack.last()
+    }
+
     pub fn triangle_points(
         &self,
         triangle: &TriangleDefinition,
@@ -450,6 +1055,14 @@ impl DrawingContext {
             let bounds = self.bounds_of(triangles.clone());
 
             let opacity = *self.opacity_stack.last().unwrap();
+            let clipping_geometry = match (clipping_geometry, self.clip_stack.last()) {
+                (Some(mut geometry), Some(clip)) => {
+                    geometry.intersect(clip);
+                    Some(geometry)
+                }
+                (None, Some(clip)) => Some(clip.clone()),
+                (geometry, None) => geometry,
+            };
             self.command_buffer.push(Command {
                 clip_bounds,
                 bounds,
@@ -469,26 +1082,129 @@ impl DrawingContext {
         position: Vector2<f32>,
         formatted_text: &FormattedText,
     ) {
-        let font = formatted_text.get_font();
+        let fonts = formatted_text.get_fonts();
+        let glyphs = formatted_text.get_glyphs();
+
+        // Glyphs could be spread across multiple fonts, pages of font atlas and colors of runs,
+        // every combination requires separate command. In most cases there will be only one.
+        let mut batches = Vec::new();
+        for glyph in glyphs {
+            let key = (glyph.font_index(), glyph.page_index(), glyph.color());
+            if !batches.contains(&key) {
+                batches.push(key);
+            }
+        }
 
-        for element in formatted_text.get_glyphs() {
-            let bounds = element.get_bounds();
+        for (font_index, page_index, color) in batches {
+            for element in glyphs.iter().filter(|g| {
+                g.font_index() == font_index && g.page_index() == page_index && g.color() == color
+            }) {
+                let bounds = element.get_bounds();
+
+                let final_bounds = Rect::new(
+                    position.x + bounds.x(),
+                    position.y + bounds.y(),
+                    bounds.w(),
+                    bounds.h(),
+                );
+
+                self.push_rect_filled(&final_bounds, Some(element.get_tex_coords()));
+            }
 
-            let final_bounds = Rect::new(
-                position.x + bounds.x(),
-                position.y + bounds.y(),
-                bounds.w(),
-                bounds.h(),
+            self.commit(
+                clip_bounds,
+                color.map_or_else(|| formatted_text.brush(), Brush::Solid),
+                CommandTexture::Font {
+                    font: fonts[font_index].clone(),
+                    page_index,
+                },
+                None,
             );
+        }
 
-            self.push_rect_filled(&final_bounds, Some(element.get_tex_coords()));
+        for text_image in formatted_text.get_images() {
+            let bounds = text_image.bounds;
+            let r = text_image.image.uv_rect;
+            self.push_rect_filled(
+                &Rect::new(
+                    position.x + bounds.x(),
+                    position.y + bounds.y(),
+                    bounds.w(),
+                    bounds.h(),
+                ),
+                Some(&[
+                    Vector2::new(r.x(), r.y()),
+                    Vector2::new(r.x() + r.w(), r.y()),
+                    Vector2::new(r.x() + r.w(), r.y() + r.h()),
+                    Vector2::new(r.x(), r.y() + r.h()),
+                ]),
+            );
+            self.commit(
+                clip_bounds,
+                Brush::Solid(Color::WHITE),
+                CommandTexture::Texture(text_image.image.texture.clone()),
+                None,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{algebra::Vector2, math::Rect},
+        draw::{Draw, DrawingContext, Path},
+    };
+
+    #[test]
+    fn test_path_flatten() {
+        let contours = Path::new()
+            .move_to(Vector2::new(0.0, 0.0))
+            .quadratic_to(Vector2::new(5.0, 10.0), Vector2::new(10.0, 0.0))
+            .close()
+            .move_to(Vector2::new(20.0, 0.0))
+            .line_to(Vector2::new(30.0, 0.0))
+            .flatten(0.1);
+
+        assert_eq!(contours.len(), 2);
+        assert!(contours[0].closed);
+        assert!(contours[0].points.len() > 3);
+        assert_eq!(contours[0].points[0], Vector2::new(0.0, 0.0));
+        assert_eq!(*contours[0].points.last().unwrap(), Vector2::new(10.0, 0.0));
+        assert!(!contours[1].closed);
+        assert_eq!(contours[1].points.len(), 2);
+
+        // Every point of a circle must be on the circle within tolerance.
+        let circle = Path::circle(Vector2::new(5.0, 5.0), 10.0).flatten(0.1);
+        for point in circle[0].points.iter() {
+            assert!(((point - Vector2::new(5.0, 5.0)).norm() - 10.0).abs() < 0.1);
         }
+    }
 
-        self.commit(
-            clip_bounds,
-            formatted_text.brush(),
-            CommandTexture::Font(font),
-            None,
-        )
+    #[test]
+    fn test_push_path() {
+        let mut ctx = DrawingContext::new();
+        let square = Path::new()
+            .move_to(Vector2::new(0.0, 0.0))
+            .line_to(Vector2::new(10.0, 0.0))
+            .line_to(Vector2::new(10.0, 10.0))
+            .line_to(Vector2::new(0.0, 10.0))
+            .close();
+
+        // Two triangles of the square and two triangles per edge for feathering.
+        ctx.push_path_filled(&square, 1.0);
+        assert_eq!(ctx.get_triangles().len(), 2 + 4 * 2);
+        // Feather must go outside of the square.
+        assert!(ctx
+            .get_vertices()
+            .iter()
+            .any(|v| (v.pos - Vector2::new(-1.0, -1.0)).norm() < 1.0e-5));
+
+        ctx.clear();
+        ctx.push_rounded_rect_filled(&Rect::new(0.0, 0.0, 20.0, 10.0), 3.0, 0.0);
+        for vertex in ctx.get_vertices() {
+            assert!(vertex.pos.x >= -0.01 && vertex.pos.x <= 20.01);
+            assert!(vertex.pos.y >= -0.01 && vertex.pos.y <= 10.01);
+        }
     }
 }
@@ -1,20 +1,58 @@
+//! Expander is a collapsible section - a header row with an arrow that shows or hides a content
+//! area. It is useful for settings menus and property inspectors.
+//!
+//! Clicking the arrow (or any part of the header row, if enabled) or pressing Enter while the
+//! header is focused toggles the content. Expanders that are marked as exclusive work as an
+//! accordion: expanding one collapses its exclusive siblings. Collapsed expander gives back the
+//! space of its content, so a stack of expanders (in a scroll viewer for example) reflows.
+//!
+//! ```no_run
+//! use rg3d_ui::{
+//!     core::pool::Handle, expander::ExpanderBuilder, stack_panel::StackPanelBuilder,
+//!     text::TextBuilder, widget::WidgetBuilder, BuildContext, UiNode,
+//! };
+//!
+//! fn make_settings(ctx: &mut BuildContext, sections: &[(&str, Handle<UiNode>)]) -> Handle<UiNode> {
+//!     let mut children = Vec::new();
+//!     for &(name, content) in sections {
+//!         children.push(
+//!             ExpanderBuilder::new(WidgetBuilder::new())
+//!                 .with_header(TextBuilder::new(WidgetBuilder::new()).with_text(name).build(ctx))
+//!                 .with_content(content)
+//!                 .with_expanded(false)
+//!                 .with_exclusive(true)
+//!                 .with_animation_duration(0.15)
+//!                 .build(ctx),
+//!         );
+//!     }
+//!     StackPanelBuilder::new(WidgetBuilder::new().with_children(children)).build(ctx)
+//! }
+//! ```
+
 use crate::{
+    animation::{AnimatedProperty, Easing, WidgetAnimation, WidgetAnimationMessage},
     check_box::{CheckBoxBuilder, CheckBoxMessage},
     core::pool::Handle,
     define_constructor,
     grid::{Column, GridBuilder, Row},
-    message::{MessageDirection, UiMessage},
+    message::{KeyCode, MessageDirection, MouseButton, UiMessage},
     utils::{make_arrow, ArrowDirection},
     widget::{Widget, WidgetBuilder, WidgetMessage},
-    BuildContext, Control, UiNode, UserInterface, VerticalAlignment,
+    BuildContext, Control, NodeHandleMapping, UiNode, UserInterface, VerticalAlignment,
 };
 use std::{
     any::{Any, TypeId},
     ops::{Deref, DerefMut},
 };
 
+const EXPAND_ANIMATION: &str = "ExpanderExpand";
+const COLLAPSE_ANIMATION: &str = "ExpanderCollapse";
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpanderMessage {
+    /// Expands (`true`) or collapses (`false`) the expander.
+    ///
+    /// Direction: **To/From UI**
     Expand(bool),
 }
 
@@ -27,11 +65,144 @@ pub struct Expander {
     widget: Widget,
     content: Handle<UiNode>,
     expander: Handle<UiNode>,
+    header: Handle<UiNode>,
     is_expanded: bool,
+    exclusive: bool,
+    toggle_on_header: bool,
+    animation_duration: f32,
+    // Height of the content at the moment of last collapse, used as a target of expand animation.
+    content_height: f32,
+    // Explicit height of the content before animation, restored when animation is finished.
+    content_explicit_height: f32,
 }
 
 crate::define_widget_deref!(Expander);
 
+impl Expander {
+    /// Returns `true` if the content of the expander is shown.
+    pub fn is_expanded(&self) -> bool {
+        self.is_expanded
+    }
+
+    /// Returns handle of the content.
+    pub fn content(&self) -> Handle<UiNode> {
+        self.content
+    }
+
+    /// Returns `true` if the expander collapses its exclusive siblings when expanded.
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+
+    fn is_header_part(&self, node: Handle<UiNode>, ui: &UserInterface) -> bool {
+        self.header.is_some() && (node == self.header || ui.is_node_child_of(node, self.header))
+    }
+
+    fn is_arrow_part(&self, node: Handle<UiNode>, ui: &UserInterface) -> bool {
+        self.expander.is_some()
+            && (node == self.expander || ui.is_node_child_of(node, self.expander))
+    }
+
+    fn show_content(&mut self, ui: &mut UserInterface, expand: bool) {
+        if self.content.is_none() {
+            return;
+        }
+
+        let was_animating = ui.is_animating(self.content);
+        ui.stop_animations(self.content);
+        if !was_animating {
+            self.content_explicit_height = ui.node(self.content).height();
+        }
+
+        if self.animation_duration > 0.0 {
+            if expand {
+                // Height of the content is unknown until it was shown at least once.
+                if self.content_height > 0.0 {
+                    ui.send_message(WidgetMessage::visibility(
+                        self.content,
+                        MessageDirection::ToWidget,
+                        true,
+                    ));
+                    ui.animate(
+                        self.content,
+                        WidgetAnimation::new(self.animation_duration)
+                            .with_name(EXPAND_ANIMATION)
+                            .with_easing(Easing::EaseOut)
+                            .with_property(AnimatedProperty::Height {
+                                from: Some(0.0),
+                                to: self.content_height,
+                            }),
+                    );
+                    return;
+                }
+            } else {
+                let height = ui.node(self.content).actual_size().y;
+                if !was_animating {
+                    self.content_height = height;
+                }
+                if height > 0.0 {
+                    ui.animate(
+                        self.content,
+                        WidgetAnimation::new(self.animation_duration)
+                            .with_name(COLLAPSE_ANIMATION)
+                            .with_easing(Easing::EaseOut)
+                            .with_property(AnimatedProperty::Height {
+                                from: None,
+                                to: 0.0,
+                            })
+                            .with_hide_on_complete(true),
+                    );
+                    return;
+                }
+            }
+        }
+
+        if was_animating {
+            // Interrupted animation leaves its own height, restore original one.
+            ui.send_message(WidgetMessage::height(
+                self.content,
+                MessageDirection::ToWidget,
+                self.content_explicit_height,
+            ));
+        }
+        ui.send_message(WidgetMessage::visibility(
+            self.content,
+            MessageDirection::ToWidget,
+            expand,
+        ));
+    }
+
+    fn collapse_siblings(&self, ui: &mut UserInterface) {
+        let siblings = match ui.try_get_node(self.parent()) {
+            Some(parent) => parent.children().to_vec(),
+            None => return,
+        };
+
+        for sibling in siblings {
+            if sibling == self.handle {
+                continue;
+            }
+            if let Some(expander) = ui.node(sibling).cast::<Expander>() {
+                if expander.exclusive && expander.is_expanded {
+                    ui.send_message(ExpanderMessage::expand(
+                        sibling,
+                        MessageDirection::ToWidget,
+                        false,
+                    ));
+                }
+            }
+        }
+    }
+
+    fn toggle(&self, ui: &mut UserInterface) {
+        ui.send_message(ExpanderMessage::expand(
+            self.handle,
+            MessageDirection::ToWidget,
+            !self.is_expanded,
+        ));
+    }
+}
+
 impl Control for Expander {
     fn query_component(&self, type_id: TypeId) -> Option<&dyn Any> {
         if type_id == TypeId::of::<Self>() {
@@ -41,6 +212,12 @@ impl Control for Expander {
         }
     }
 
+    fn resolve(&mut self, node_map: &NodeHandleMapping) {
+        node_map.resolve(&mut self.content);
+        node_map.resolve(&mut self.expander);
+        node_map.resolve(&mut self.header);
+    }
+
     fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
         if let Some(&ExpanderMessage::Expand(expand)) = message.data::<ExpanderMessage>() {
             if message.destination() == self.handle()
@@ -54,12 +231,14 @@ impl Control for Expander {
                     Some(expand),
                 ));
                 // Show or hide content.
-                ui.send_message(WidgetMessage::visibility(
-                    self.content,
-                    MessageDirection::ToWidget,
-                    expand,
-                ));
+                self.show_content(ui, expand);
                 self.is_expanded = expand;
+
+                if expand && self.exclusive {
+                    self.collapse_siblings(ui);
+                }
+
+                ui.send_message(message.reverse());
             }
         } else if let Some(CheckBoxMessage::Check(value)) = message.data::<CheckBoxMessage>() {
             if message.destination() == self.expander
@@ -71,6 +250,47 @@ impl Control for Expander {
                     value.unwrap_or(false),
                 ));
             }
+        } else if let Some(msg) = message.data::<WidgetMessage>() {
+            if !message.handled() && message.direction() == MessageDirection::FromWidget {
+                let destination = message.destination();
+                match msg {
+                    WidgetMessage::MouseUp {
+                        button: MouseButton::Left,
+                        ..
+                    } => {
+                        // Arrow toggles the state by itself.
+                        if self.toggle_on_header
+                            && self.is_header_part(destination, ui)
+                            && !self.is_arrow_part(destination, ui)
+                        {
+                            self.toggle(ui);
+                            message.set_handled(true);
+                        }
+                    }
+                    WidgetMessage::KeyDown(KeyCode::Return | KeyCode::NumpadEnter) => {
+                        if self.is_arrow_part(destination, ui)
+                            || (self.toggle_on_header && self.is_header_part(destination, ui))
+                        {
+                            self.toggle(ui);
+                            message.set_handled(true);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        } else if let Some(WidgetAnimationMessage::Completed(name)) =
+            message.data::<WidgetAnimationMessage>()
+        {
+            if message.destination() == self.content
+                && (name == EXPAND_ANIMATION || name == COLLAPSE_ANIMATION)
+            {
+                // Restore original height, so the content can reflow.
+                ui.send_message(WidgetMessage::height(
+                    self.content,
+                    MessageDirection::ToWidget,
+                    self.content_explicit_height,
+                ));
+            }
         }
         self.widget.handle_routed_message(ui, message);
     }
@@ -83,6 +303,9 @@ pub struct ExpanderBuilder {
     check_box: Handle<UiNode>,
     is_expanded: bool,
     expander_column: Option<Column>,
+    exclusive: bool,
+    toggle_on_header: bool,
+    animation_duration: f32,
 }
 
 impl ExpanderBuilder {
@@ -94,6 +317,9 @@ impl ExpanderBuilder {
             check_box: Default::default(),
             is_expanded: true,
             expander_column: None,
+            exclusive: false,
+            toggle_on_header: true,
+            animation_duration: 0.0,
         }
     }
 
@@ -122,6 +348,28 @@ impl ExpanderBuilder {
         self
     }
 
+    /// Sets whether the expander should collapse its exclusive siblings when expanded (accordion
+    /// behavior). Default is `false`.
+    pub fn with_exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Sets whether a click on the header (or Enter key while the header is focused) toggles
+    /// the expander. Should be disabled if the header contains interactive widgets. Default is
+    /// `true`.
+    pub fn with_toggle_on_header(mut self, toggle_on_header: bool) -> Self {
+        self.toggle_on_header = toggle_on_header;
+        self
+    }
+
+    /// Sets duration (in seconds) of smooth height animation of the content on expand and
+    /// collapse. Zero disables animation, this is default.
+    pub fn with_animation_duration(mut self, duration: f32) -> Self {
+        self.animation_duration = duration.max(0.0);
+        self
+    }
+
     pub fn build(self, ctx: &mut BuildContext<'_>) -> Handle<UiNode> {
         let expander = if self.check_box.is_some() {
             self.check_box
@@ -175,8 +423,123 @@ impl ExpanderBuilder {
                 .build(),
             content: self.content,
             expander,
+            header: grid,
             is_expanded: self.is_expanded,
+            exclusive: self.exclusive,
+            toggle_on_header: self.toggle_on_header,
+            animation_duration: self.animation_duration,
+            content_height: 0.0,
+            content_explicit_height: f32::NAN,
         });
         ctx.add_node(e)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        border::BorderBuilder,
+        core::{algebra::Vector2, pool::Handle},
+        expander::{Expander, ExpanderBuilder, ExpanderMessage},
+        message::{KeyCode, MessageDirection},
+        stack_panel::StackPanelBuilder,
+        widget::{WidgetBuilder, WidgetMessage},
+        UiNode, UserInterface,
+    };
+
+    fn make_expander(ui: &mut UserInterface, expanded: bool) -> (Handle<UiNode>, Handle<UiNode>) {
+        let ctx = &mut ui.build_ctx();
+        let header = BorderBuilder::new(WidgetBuilder::new().with_height(20.0)).build(ctx);
+        let content = BorderBuilder::new(WidgetBuilder::new().with_height(100.0)).build(ctx);
+        let expander = ExpanderBuilder::new(WidgetBuilder::new())
+            .with_header(header)
+            .with_content(content)
+            .with_expanded(expanded)
+            .with_exclusive(true)
+            .build(ctx);
+        (expander, header)
+    }
+
+    fn is_expanded(ui: &UserInterface, expander: Handle<UiNode>) -> bool {
+        ui.node(expander).cast::<Expander>().unwrap().is_expanded()
+    }
+
+    fn flush(ui: &mut UserInterface, screen_size: Vector2<f32>) {
+        while ui.poll_message().is_some() {}
+        ui.update(screen_size, 0.0);
+    }
+
+    #[test]
+    fn test_accordion() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let (first, _) = make_expander(&mut ui, true);
+        let (second, second_header) = make_expander(&mut ui, false);
+        let panel = StackPanelBuilder::new(WidgetBuilder::new().with_children(vec![first, second]))
+            .build(&mut ui.build_ctx());
+        flush(&mut ui, screen_size);
+        assert_eq!(ui.node(panel).actual_size().y, 140.0);
+
+        // Expanding the second one collapses the first one, space is given back.
+        ui.send_message(ExpanderMessage::expand(
+            second,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        flush(&mut ui, screen_size);
+        assert!(!is_expanded(&ui, first));
+        assert!(is_expanded(&ui, second));
+        assert_eq!(ui.node(panel).actual_size().y, 140.0);
+
+        // Enter on header toggles.
+        ui.send_message(WidgetMessage::key_down(
+            second_header,
+            MessageDirection::FromWidget,
+            KeyCode::Return,
+        ));
+        flush(&mut ui, screen_size);
+        assert!(!is_expanded(&ui, second));
+        assert_eq!(ui.node(panel).actual_size().y, 40.0);
+    }
+
+    #[test]
+    fn test_animation() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let ctx = &mut ui.build_ctx();
+        let content = BorderBuilder::new(WidgetBuilder::new().with_height(100.0)).build(ctx);
+        let expander = ExpanderBuilder::new(WidgetBuilder::new())
+            .with_content(content)
+            .with_animation_duration(1.0)
+            .build(ctx);
+        flush(&mut ui, screen_size);
+
+        ui.send_message(ExpanderMessage::expand(
+            expander,
+            MessageDirection::ToWidget,
+            false,
+        ));
+        flush(&mut ui, screen_size);
+        ui.update(screen_size, 0.5);
+        let height = ui.node(content).actual_size().y;
+        assert!(height > 0.0 && height < 100.0);
+        assert!(ui.node(content).visibility());
+
+        ui.update(screen_size, 0.5);
+        flush(&mut ui, screen_size);
+        assert!(!ui.node(content).visibility());
+        assert_eq!(ui.node(content).height(), 100.0);
+
+        ui.send_message(ExpanderMessage::expand(
+            expander,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        flush(&mut ui, screen_size);
+        assert!(ui.is_animating(content));
+        ui.update(screen_size, 1.0);
+        flush(&mut ui, screen_size);
+        assert_eq!(ui.node(content).height(), 100.0);
+        assert_eq!(ui.node(content).actual_size().y, 100.0);
+    }
+}
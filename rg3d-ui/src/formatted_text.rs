@@ -1,15 +1,152 @@
+//! Formatted text is a text that is split on lines and converted to glyphs ready to be drawn.
+//!
+//! # Runs
+//!
+//! Parts of the text can be styled separately by runs - ranges of characters with their own
+//! color, font, size or weight. Runs could be created from a list of spans (see
+//! [`FormattedText::set_spans`]) or by markup parser (see [`crate::markup`]). Inline images are
+//! represented by a single object replacement character (`U+FFFC`) in the text, so indices of
+//! characters, selection and editing works with runs as with plain text.
+
 use crate::{
     brush::Brush,
     core::{algebra::Vector2, color::Color, math::Rect},
-    ttf::SharedFont,
-    Font, HorizontalAlignment, VerticalAlignment, DEFAULT_FONT,
+    draw::SharedTexture,
+    ttf::{Font, SharedFont},
+    HorizontalAlignment, VerticalAlignment, DEFAULT_FONT,
 };
-use std::ops::Range;
+use std::{ops::Range, sync::MutexGuard};
+
+/// A character that represents an inline image in the text.
+pub const INLINE_IMAGE_CHAR: char = '\u{FFFC}';
+
+/// An image that is shown inline with text, for example an icon in a dialog.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineImage {
+    /// A texture of the image.
+    pub texture: SharedTexture,
+    /// A rectangle of the texture (in texture coordinates) that will be shown, it allows to use
+    /// a part of a texture atlas.
+    pub uv_rect: Rect<f32>,
+    /// Size of the image (in logical units), `None` - square with side equal to ascender of the
+    /// font.
+    pub size: Option<Vector2<f32>>,
+}
+
+impl InlineImage {
+    /// Creates new inline image that shows whole texture.
+    pub fn new(texture: SharedTexture) -> Self {
+        Self {
+            texture,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+            size: None,
+        }
+    }
+
+    /// Sets a rectangle of the texture (in texture coordinates) that will be shown.
+    pub fn with_uv_rect(mut self, uv_rect: Rect<f32>) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    /// Sets size of the image (in logical units).
+    pub fn with_size(mut self, size: Vector2<f32>) -> Self {
+        self.size = Some(size);
+        self
+    }
+}
+
+/// Style of a run of text, every `None` field means that the value of formatted text is used.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextStyle {
+    /// Color of the run, overrides the brush of formatted text.
+    pub color: Option<Color>,
+    /// Font of the run.
+    pub font: Option<SharedFont>,
+    /// Size (in pixels) of the font of the run.
+    pub font_size: Option<f32>,
+    /// Whether the run is bold or not. Glyphs are emboldened by drawing them twice with one
+    /// pixel offset, so it works with any font. Use bold font for better results.
+    pub bold: bool,
+    /// An image that replaces every character of the run.
+    pub image: Option<InlineImage>,
+}
+
+/// A piece of text with its style, see [`FormattedText::set_spans`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextSpan {
+    pub text: String,
+    pub style: TextStyle,
+}
+
+impl TextSpan {
+    /// Creates new span with default style.
+    pub fn new<P: AsRef<str>>(text: P) -> Self {
+        Self {
+            text: text.as_ref().to_owned(),
+            style: Default::default(),
+        }
+    }
+
+    /// Creates new span with an inline image.
+    pub fn image(image: InlineImage) -> Self {
+        Self {
+            text: INLINE_IMAGE_CHAR.to_string(),
+            style: TextStyle {
+                image: Some(image),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn with_font(mut self, font: SharedFont) -> Self {
+        self.style.font = Some(font);
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.style.font_size = Some(font_size);
+        self
+    }
+
+    pub fn with_bold(mut self, bold: bool) -> Self {
+        self.style.bold = bold;
+        self
+    }
+
+    pub fn with_style(mut self, style: TextStyle) -> Self {
+        self.style = style;
+        self
+    }
+}
+
+/// A range of characters of formatted text with its style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+    /// Range of indices of characters.
+    pub range: Range<usize>,
+    pub style: TextStyle,
+}
 
 #[derive(Debug, Clone)]
 pub struct TextGlyph {
     bounds: Rect<f32>,
     tex_coords: [Vector2<f32>; 4],
+    page_index: usize,
+    font_index: usize,
+    color: Option<Color>,
+}
+
+/// An inline image with its final position in formatted text.
+#[derive(Debug, Clone)]
+pub struct TextImage {
+    pub bounds: Rect<f32>,
+    pub image: InlineImage,
 }
 
 impl TextGlyph {
@@ -20,6 +157,21 @@ impl TextGlyph {
     pub fn get_tex_coords(&self) -> &[Vector2<f32>; 4] {
         &self.tex_coords
     }
+
+    /// Returns index of font atlas page that contains the glyph.
+    pub fn page_index(&self) -> usize {
+        self.page_index
+    }
+
+    /// Returns index of the font of the glyph in [`FormattedText::get_fonts`].
+    pub fn font_index(&self) -> usize {
+        self.font_index
+    }
+
+    /// Returns color of the run of the glyph, `None` - brush of formatted text is used.
+    pub fn color(&self) -> Option<Color> {
+        self.color
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -30,7 +182,8 @@ pub struct TextLine {
     pub end: usize,
     /// Total width of line.
     pub width: f32,
-    /// Total height of line. Usually just ascender of a font.
+    /// Total height of line. Usually just ascender of a font, or the largest ascender of runs
+    /// of the line.
     pub height: f32,
     /// Local horizontal position of line.
     pub x_offset: f32,
@@ -75,21 +228,18 @@ pub enum WrapMode {
 #[derive(Copy, Clone, Debug)]
 pub struct Character {
     pub char_code: u32,
-    pub glyph_index: u32,
 }
 
 impl Character {
-    pub fn from_char_with_font(char_code: u32, font: &Font) -> Self {
-        Self {
-            char_code,
-            glyph_index: font.glyph_index(char_code).unwrap_or_default() as u32,
-        }
+    pub fn new(char_code: u32) -> Self {
+        Self { char_code }
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct FormattedText {
     font: SharedFont,
+    font_size: f32,
     text: Vec<Character>,
     // Temporary buffer used to split text on lines. We need it to reduce memory allocations
     // when we changing text too frequently, here we sacrifice some memory in order to get
@@ -97,12 +247,18 @@ pub struct FormattedText {
     lines: Vec<TextLine>,
     // Final glyphs for draw buffer.
     glyphs: Vec<TextGlyph>,
+    // Final inline images for draw buffer.
+    images: Vec<TextImage>,
+    runs: Vec<TextRun>,
+    // Every font used by the text, the first one is the font of formatted text.
+    fonts: Vec<SharedFont>,
     vertical_alignment: VerticalAlignment,
     horizontal_alignment: HorizontalAlignment,
     brush: Brush,
     constraint: Vector2<f32>,
     wrap: WrapMode,
     mask_char: Option<Character>,
+    scale: f32,
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -111,11 +267,183 @@ struct Word {
     length: usize,
 }
 
+// Resolved style of a character.
+#[derive(Copy, Clone, Debug)]
+struct CharStyle {
+    font_index: usize,
+    font_size: f32,
+    bold: bool,
+    color: Option<Color>,
+    // Index of a run with an image.
+    image: Option<usize>,
+}
+
+// Collects every font of the text, the font of formatted text is always first.
+fn collect_fonts(font: &SharedFont, runs: &[TextRun]) -> Vec<SharedFont> {
+    let mut fonts = vec![font.clone()];
+    for font in runs.iter().filter_map(|r| r.style.font.as_ref()) {
+        if !fonts.contains(font) {
+            fonts.push(font.clone());
+        }
+    }
+    fonts
+}
+
+fn resolve_styles(
+    length: usize,
+    font_size: f32,
+    runs: &[TextRun],
+    fonts: &[SharedFont],
+) -> Vec<CharStyle> {
+    let mut styles = vec![
+        CharStyle {
+            font_index: 0,
+            font_size,
+            bold: false,
+            color: None,
+            image: None,
+        };
+        length
+    ];
+    for (run_index, run) in runs.iter().enumerate() {
+        let font_index = run
+            .style
+            .font
+            .as_ref()
+            .and_then(|f| fonts.iter().position(|other| other == f))
+            .unwrap_or(0);
+        for style in styles.iter_mut().take(run.range.end).skip(run.range.start) {
+            *style = CharStyle {
+                font_index,
+                font_size: run.style.font_size.unwrap_or(font_size),
+                bold: run.style.bold,
+                color: run.style.color,
+                image: run.style.image.as_ref().map(|_| run_index),
+            };
+        }
+    }
+    styles
+}
+
+fn image_size(image: &InlineImage, font: &Font, font_size: f32) -> Vector2<f32> {
+    image.size.unwrap_or_else(|| {
+        let side = font.ascender(font_size);
+        Vector2::new(side, side)
+    })
+}
+
+// Calculates advance (in logical units) of each character, kerning is included in advance of
+// previous character.
+fn calculate_advances(
+    text: &[Character],
+    styles: &[CharStyle],
+    runs: &[TextRun],
+    fonts: &mut [MutexGuard<Font>],
+    scale: f32,
+) -> Vec<f32> {
+    let mut advances = Vec::with_capacity(text.len());
+    for (i, (character, style)) in text.iter().zip(styles).enumerate() {
+        let font = &mut fonts[style.font_index];
+        if let Some(image) = style.image.and_then(|r| runs[r].style.image.as_ref()) {
+            advances.push(image_size(image, font, style.font_size).x);
+            continue;
+        }
+        let raster_size = style.font_size * scale;
+        let mut advance = font.glyph_advance(character.char_code, raster_size);
+        if let (Some(next), Some(next_style)) = (text.get(i + 1), styles.get(i + 1)) {
+            if next_style.font_index == style.font_index
+                && next_style.font_size == style.font_size
+                && next_style.image.is_none()
+            {
+                advance += font.kerning(character.char_code, next.char_code, raster_size);
+            }
+        }
+        if style.bold {
+            // Emboldened glyphs are one physical pixel wider.
+            advance += 1.0;
+        }
+        advances.push(advance / scale);
+    }
+    advances
+}
+
 impl FormattedText {
     pub fn get_glyphs(&self) -> &[TextGlyph] {
         &self.glyphs
     }
 
+    /// Returns inline images of the text with their final positions.
+    pub fn get_images(&self) -> &[TextImage] {
+        &self.images
+    }
+
+    /// Returns every font used by the text after last [`Self::build`], the first one is the font
+    /// of the text, see [`TextGlyph::font_index`].
+    pub fn get_fonts(&self) -> &[SharedFont] {
+        &self.fonts
+    }
+
+    /// Returns styled runs of the text.
+    pub fn runs(&self) -> &[TextRun] {
+        &self.runs
+    }
+
+    /// Sets styled runs of the text. Runs must not overlap, otherwise the last one wins.
+    pub fn set_runs(&mut self, runs: Vec<TextRun>) -> &mut Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Replaces the text with given spans, every span becomes a run with its style. Spans with
+    /// default style do not produce runs.
+    pub fn set_spans(&mut self, spans: &[TextSpan]) -> &mut Self {
+        self.text.clear();
+        self.runs.clear();
+        for span in spans {
+            let begin = self.text.len();
+            self.text
+                .extend(span.text.chars().map(|c| Character::new(c as u32)));
+            if span.style != TextStyle::default() && self.text.len() > begin {
+                self.runs.push(TextRun {
+                    range: begin..self.text.len(),
+                    style: span.style.clone(),
+                });
+            }
+        }
+        self
+    }
+
+    // Moves runs after inserted characters, inserted characters become part of a run if they
+    // were inserted inside of it.
+    fn shift_runs_on_insert(&mut self, position: usize, count: usize) {
+        for run in self.runs.iter_mut() {
+            if run.range.start >= position {
+                run.range.start += count;
+                run.range.end += count;
+            } else if run.range.end > position {
+                run.range.end += count;
+            }
+        }
+    }
+
+    // Shrinks and moves runs on removal of characters, empty runs are removed.
+    fn shift_runs_on_remove(&mut self, range: Range<usize>) {
+        let count = range.end - range.start;
+        let shift = |index: usize| {
+            if index >= range.end {
+                index - count
+            } else if index > range.start {
+                range.start
+            } else {
+                index
+            }
+        };
+        for run in self.runs.iter_mut() {
+            run.range = shift(run.range.start)..shift(run.range.end);
+        }
+        self.runs.retain(|r| r.range.start < r.range.end);
+    }
+
     pub fn get_font(&self) -> SharedFont {
         self.font.clone()
     }
@@ -125,6 +453,28 @@ impl FormattedText {
         self
     }
 
+    /// Sets size (in pixels) of the font, glyphs of required size will be rasterized on demand.
+    pub fn set_font_size(&mut self, font_size: f32) -> &mut Self {
+        self.font_size = font_size;
+        self
+    }
+
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    /// Sets amount of physical pixels per logical unit, glyphs will be rasterized at scaled
+    /// size, while layout of the text remains in logical units. Usually it is the scale of the
+    /// user interface (see [`crate::UserInterface::set_scale`]), widgets set it automatically.
+    pub fn set_scale(&mut self, scale: f32) -> &mut Self {
+        self.scale = scale.max(f32::EPSILON);
+        self
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
     pub fn get_lines(&self) -> &[TextLine] {
         &self.lines
     }
@@ -175,27 +525,33 @@ impl FormattedText {
             .collect()
     }
 
+    /// Returns advance (in logical units) of every character of the text, styles of runs are
+    /// taken into account.
+    pub fn advances(&self) -> Vec<f32> {
+        let fonts = collect_fonts(&self.font, &self.runs);
+        let mut guards = fonts
+            .iter()
+            .map(|f| f.0.lock().unwrap())
+            .collect::<Vec<_>>();
+        let styles = resolve_styles(self.text.len(), self.font_size, &self.runs, &fonts);
+        calculate_advances(&self.text, &styles, &self.runs, &mut guards, self.scale)
+    }
+
     pub fn get_range_width<T: IntoIterator<Item = usize>>(&self, range: T) -> f32 {
-        let mut width = 0.0;
-        let font = self.font.0.lock().unwrap();
-        for index in range {
-            width += font.glyph_advance(self.text[index].glyph_index);
-        }
-        width
+        let advances = self.advances();
+        range
+            .into_iter()
+            .filter_map(|index| advances.get(index))
+            .sum()
     }
 
+    /// Sets plain text, every run of the text is removed.
     pub fn set_text<P: AsRef<str>>(&mut self, text: P) -> &mut Self {
         // Convert text to UTF32.
+        self.runs.clear();
         self.text.clear();
-
-        let font = self.font.0.lock().unwrap();
-
-        for code in text.as_ref().chars().map(|c| c as u32) {
-            self.text.push(Character::from_char_with_font(code, &font));
-        }
-
-        drop(font);
-
+        self.text
+            .extend(text.as_ref().chars().map(|c| Character::new(c as u32)));
         self
     }
 
@@ -209,43 +565,43 @@ impl FormattedText {
     }
 
     pub fn insert_char(&mut self, code: char, index: usize) -> &mut Self {
-        let font = self.font.0.lock().unwrap();
-
-        self.text
-            .insert(index, Character::from_char_with_font(code as u32, &font));
-
-        drop(font);
-
+        self.text.insert(index, Character::new(code as u32));
+        self.shift_runs_on_insert(index, 1);
         self
     }
 
     pub fn insert_str(&mut self, str: &str, position: usize) -> &mut Self {
-        let font = self.font.0.lock().unwrap();
-
+        let mut count = 0;
         for (i, code) in str.chars().enumerate() {
-            self.text.insert(
-                position + i,
-                Character::from_char_with_font(code as u32, &font),
-            );
+            self.text.insert(position + i, Character::new(code as u32));
+            count += 1;
         }
-
-        drop(font);
-
+        self.shift_runs_on_insert(position, count);
         self
     }
 
     pub fn remove_range(&mut self, range: Range<usize>) -> &mut Self {
-        self.text.drain(range);
+        self.text.drain(range.clone());
+        self.shift_runs_on_remove(range);
         self
     }
 
     pub fn remove_at(&mut self, index: usize) -> &mut Self {
         self.text.remove(index);
+        self.shift_runs_on_remove(index..index + 1);
         self
     }
 
     pub fn build(&mut self) -> Vector2<f32> {
-        let font = self.font.0.lock().unwrap();
+        let scale = self.scale;
+        // Every font is locked once, the same font could be used by several runs.
+        self.fonts = collect_fonts(&self.font, &self.runs);
+        let mut fonts = self
+            .fonts
+            .iter()
+            .map(|f| f.0.lock().unwrap())
+            .collect::<Vec<_>>();
+        let base_ascender = fonts[0].ascender(self.font_size);
 
         let masked_text;
         let text = if let Some(mask_char) = self.mask_char {
@@ -255,16 +611,29 @@ impl FormattedText {
             &self.text
         };
 
+        let styles = resolve_styles(text.len(), self.font_size, &self.runs, &self.fonts);
+        let advances = calculate_advances(text, &styles, &self.runs, &mut fonts, scale);
+
+        // Ascender of an image is its height, so the image stands on the base line.
+        let mut descender = fonts[0].descender(self.font_size);
+        let mut ascenders = Vec::with_capacity(text.len());
+        for style in styles.iter() {
+            let font = &fonts[style.font_index];
+            match style.image.and_then(|r| self.runs[r].style.image.as_ref()) {
+                Some(image) => ascenders.push(image_size(image, font, style.font_size).y),
+                None => {
+                    ascenders.push(font.ascender(style.font_size));
+                    descender = descender.min(font.descender(style.font_size));
+                }
+            }
+        }
+
         // Split on lines.
-        let mut total_height = 0.0;
         let mut current_line = TextLine::new();
         let mut word: Option<Word> = None;
         self.lines.clear();
         for (i, character) in text.iter().enumerate() {
-            let advance = match font.glyphs().get(character.glyph_index as usize) {
-                Some(glyph) => glyph.advance,
-                None => font.height(),
-            };
+            let advance = advances[i];
             let is_new_line =
                 character.char_code == u32::from(b'\n') || character.char_code == u32::from(b'\r');
             let new_width = current_line.width + advance;
@@ -296,7 +665,6 @@ impl FormattedText {
                 current_line.begin = if is_new_line { i + 1 } else { i };
                 current_line.end = current_line.begin;
                 current_line.width = advance;
-                total_height += font.ascender();
             } else {
                 match self.wrap {
                     WrapMode::NoWrap => {
@@ -309,7 +677,6 @@ impl FormattedText {
                             current_line.begin = if is_new_line { i + 1 } else { i };
                             current_line.end = current_line.begin + 1;
                             current_line.width = advance;
-                            total_height += font.ascender();
                         } else {
                             current_line.width = new_width;
                             current_line.end += 1;
@@ -326,7 +693,6 @@ impl FormattedText {
                                 self.lines.push(current_line);
                                 current_line.begin = current_line.end;
                                 current_line.width = 0.0;
-                                total_height += font.ascender();
                             } else if current_line.width + word.width > self.constraint.x {
                                 // The word will exceed horizontal constraint, we have to
                                 // commit current line and move the word in the next line.
@@ -334,7 +700,6 @@ impl FormattedText {
                                 current_line.begin = i - word.length;
                                 current_line.end = i;
                                 current_line.width = word.width;
-                                total_height += font.ascender();
                             } else {
                                 // The word does not exceed horizontal constraint, append it
                                 // to the line.
@@ -354,16 +719,21 @@ impl FormattedText {
         }
         // Commit rest of text.
         if current_line.begin != current_line.end {
-            for character in text.iter().skip(current_line.end) {
-                let advance = match font.glyphs().get(character.glyph_index as usize) {
-                    Some(glyph) => glyph.advance,
-                    None => font.height(),
-                };
+            for advance in advances.iter().skip(current_line.end) {
                 current_line.width += advance;
             }
             current_line.end = self.text.len();
             self.lines.push(current_line);
-            total_height += font.ascender();
+        }
+
+        // Height of a line is defined by the largest run of the line.
+        let mut total_height = 0.0;
+        for line in self.lines.iter_mut() {
+            line.height = ascenders
+                .get(line.begin..line.end.min(ascenders.len()))
+                .and_then(|a| a.iter().cloned().reduce(f32::max))
+                .unwrap_or(base_ascender);
+            total_height += line.height;
         }
 
         // Align lines according to desired alignment.
@@ -390,6 +760,7 @@ impl FormattedText {
 
         // Generate glyphs for each text line.
         self.glyphs.clear();
+        self.images.clear();
 
         let cursor_y_start = match self.vertical_alignment {
             VerticalAlignment::Top => 0.0,
@@ -419,50 +790,63 @@ impl FormattedText {
         let mut cursor = Vector2::new(cursor_x_start, cursor_y_start);
         for line in self.lines.iter_mut() {
             cursor.x = line.x_offset;
+            let baseline = (line.height * scale).floor();
 
-            for &character in text.iter().take(line.end).skip(line.begin) {
-                match font.glyphs().get(character.glyph_index as usize) {
-                    Some(glyph) => {
-                        // Insert glyph
-                        let rect = Rect::new(
-                            cursor.x + glyph.left.floor(),
-                            cursor.y + font.ascender().floor()
-                                - glyph.top.floor()
-                                - glyph.bitmap_height as f32,
-                            glyph.bitmap_width as f32,
-                            glyph.bitmap_height as f32,
-                        );
-                        let text_glyph = TextGlyph {
-                            bounds: rect,
-                            tex_coords: glyph.tex_coords,
-                        };
-                        self.glyphs.push(text_glyph);
-
-                        cursor.x += glyph.advance;
-                    }
-                    None => {
-                        // Insert invalid symbol
-                        let rect = Rect::new(
+            for (i, &character) in text.iter().enumerate().take(line.end).skip(line.begin) {
+                let style = styles[i];
+
+                if let Some(image) = style.image.and_then(|r| self.runs[r].style.image.as_ref()) {
+                    let height = ascenders[i];
+                    self.images.push(TextImage {
+                        bounds: Rect::new(
                             cursor.x,
-                            cursor.y + font.ascender(),
-                            font.height(),
-                            font.height(),
-                        );
-                        self.glyphs.push(TextGlyph {
-                            bounds: rect,
-                            tex_coords: [Vector2::default(); 4],
-                        });
-                        cursor.x += rect.w();
+                            cursor.y + baseline / scale - height,
+                            advances[i],
+                            height,
+                        ),
+                        image: image.clone(),
+                    });
+                    cursor.x += advances[i];
+                    continue;
+                }
+
+                let glyph =
+                    fonts[style.font_index].glyph(character.char_code, style.font_size * scale);
+
+                // Invisible glyphs (like spaces) are not needed in draw buffer.
+                if glyph.bitmap_width > 0 && glyph.bitmap_height > 0 {
+                    // Snap to physical pixels to keep glyphs sharp.
+                    let rect = Rect::new(
+                        cursor.x + glyph.left.floor() / scale,
+                        cursor.y
+                            + (baseline - glyph.top.floor() - glyph.bitmap_height as f32) / scale,
+                        glyph.bitmap_width as f32 / scale,
+                        glyph.bitmap_height as f32 / scale,
+                    );
+                    let text_glyph = TextGlyph {
+                        bounds: rect,
+                        tex_coords: glyph.tex_coords,
+                        page_index: glyph.page_index,
+                        font_index: style.font_index,
+                        color: style.color,
+                    };
+                    if style.bold {
+                        // Fake bold - the same glyph shifted by one physical pixel.
+                        let mut shifted = text_glyph.clone();
+                        shifted.bounds.position.x += 1.0 / scale;
+                        self.glyphs.push(shifted);
                     }
+                    self.glyphs.push(text_glyph);
                 }
+
+                cursor.x += advances[i];
             }
-            line.height = font.ascender();
             line.y_offset = cursor.y;
-            cursor.y += font.ascender();
+            cursor.y += line.height;
         }
 
         // Minus here is because descender has negative value.
-        let mut full_size = Vector2::new(0.0, total_height - font.descender());
+        let mut full_size = Vector2::new(0.0, total_height - descender);
         for line in self.lines.iter() {
             full_size.x = line.width.max(full_size.x);
         }
@@ -472,6 +856,7 @@ impl FormattedText {
 
 pub struct FormattedTextBuilder {
     font: SharedFont,
+    font_size: Option<f32>,
     brush: Brush,
     constraint: Vector2<f32>,
     text: String,
@@ -492,6 +877,7 @@ impl FormattedTextBuilder {
     pub fn new() -> FormattedTextBuilder {
         FormattedTextBuilder {
             font: DEFAULT_FONT.clone(),
+            font_size: None,
             text: "".to_owned(),
             horizontal_alignment: HorizontalAlignment::Left,
             vertical_alignment: VerticalAlignment::Top,
@@ -507,6 +893,12 @@ impl FormattedTextBuilder {
         self
     }
 
+    /// Sets size (in pixels) of the font, if not set - default height of the font will be used.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
     pub fn with_vertical_alignment(mut self, vertical_alignment: VerticalAlignment) -> Self {
         self.vertical_alignment = vertical_alignment;
         self
@@ -543,27 +935,78 @@ impl FormattedTextBuilder {
     }
 
     pub fn build(self) -> FormattedText {
-        let font = self.font.0.lock().unwrap();
+        let font_size = self
+            .font_size
+            .unwrap_or_else(|| self.font.0.lock().unwrap().height());
         FormattedText {
+            font: self.font,
+            font_size,
             text: self
                 .text
                 .chars()
-                .map(|c| Character::from_char_with_font(c as u32, &font))
+                .map(|c| Character::new(c as u32))
                 .collect(),
             lines: Vec::new(),
             glyphs: Vec::new(),
+            images: Vec::new(),
+            runs: Vec::new(),
+            fonts: Vec::new(),
             vertical_alignment: self.vertical_alignment,
             horizontal_alignment: self.horizontal_alignment,
             brush: self.brush,
             constraint: self.constraint,
             wrap: self.wrap,
-            mask_char: self
-                .mask_char
-                .map(|code| Character::from_char_with_font(u32::from(code), &font)),
-            font: {
-                drop(font);
-                self.font
-            },
+            mask_char: self.mask_char.map(|code| Character::new(u32::from(code))),
+            scale: 1.0,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        formatted_text::{FormattedTextBuilder, TextSpan},
+    };
+
+    #[test]
+    fn test_runs_follow_editing() {
+        let mut text = FormattedTextBuilder::new().build();
+        text.set_spans(&[
+            TextSpan::new("ab"),
+            TextSpan::new("cd").with_bold(true),
+            TextSpan::new("ef"),
+        ]);
+        assert_eq!(text.runs().len(), 1);
+        assert_eq!(text.runs()[0].range, 2..4);
+
+        text.insert_char('x', 3);
+        assert_eq!(text.runs()[0].range, 2..5);
+        text.insert_str("yy", 0);
+        assert_eq!(text.runs()[0].range, 4..7);
+        text.remove_range(0..5);
+        assert_eq!(text.text(), "xdef");
+        assert_eq!(text.runs()[0].range, 0..2);
+        text.remove_range(0..2);
+        assert!(text.runs().is_empty());
+    }
+
+    #[test]
+    fn test_line_height_of_runs() {
+        let mut text = FormattedTextBuilder::new()
+            .with_font_size(16.0)
+            .with_constraint(Vector2::new(f32::INFINITY, f32::INFINITY))
+            .build();
+        text.set_text("small");
+        let small = text.build();
+
+        text.set_spans(&[
+            TextSpan::new("small"),
+            TextSpan::new("big").with_font_size(32.0),
+        ]);
+        let big = text.build();
+        assert!(big.y > small.y);
+        assert!(big.x > small.x);
+        assert_eq!(text.get_range_width(0..8), text.get_lines()[0].width);
+    }
+}
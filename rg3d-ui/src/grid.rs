@@ -8,7 +8,7 @@ use crate::{
 use std::{
     any::{Any, TypeId},
     cell::RefCell,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
 };
 
 #[derive(Clone, Copy, PartialEq)]
@@ -168,8 +168,78 @@ fn calc_avg_size_for_stretch_dim(
     }
 }
 
+// Children that span multiple cells are measured separately after all cells.
+fn is_spanned(child: &UiNode) -> bool {
+    child.row_span() > 1 || child.column_span() > 1
+}
+
+fn span_range(dims: &[GridDimension], start: usize, span: usize) -> Option<Range<usize>> {
+    if start < dims.len() {
+        Some(start..(start + span.max(1)).min(dims.len()))
+    } else {
+        None
+    }
+}
+
+fn span_constraint(
+    dims: &[GridDimension],
+    range: Range<usize>,
+    available_size: f32,
+    stretch_size: f32,
+) -> f32 {
+    let mut size = 0.0;
+    for dim in dims[range].iter() {
+        size += match dim.size_mode {
+            SizeMode::Strict => dim.desired_size,
+            SizeMode::Auto => return available_size,
+            SizeMode::Stretch => stretch_size,
+        };
+    }
+    size
+}
+
+fn span_actual_size(dims: &[GridDimension], range: Range<usize>) -> f32 {
+    dims[range].iter().map(|dim| dim.actual_size).sum()
+}
+
+// Distributes the size that is not fit in the dimensions evenly across dimensions that can grow.
+fn expand_span(dims: &mut [GridDimension], range: Range<usize>, size: f32, available_size: f32) {
+    let can_grow = |dim: &GridDimension| match dim.size_mode {
+        SizeMode::Strict => false,
+        SizeMode::Auto => true,
+        SizeMode::Stretch => available_size.is_infinite(),
+    };
+
+    let current_size = span_actual_size(dims, range.clone());
+    let growable = dims[range.clone()]
+        .iter()
+        .filter(|dim| can_grow(dim))
+        .count();
+    if size > current_size && growable > 0 {
+        let extra = (size - current_size) / growable as f32;
+        for dim in dims[range].iter_mut() {
+            if can_grow(dim) {
+                dim.actual_size += extra;
+            }
+        }
+    }
+}
+
+fn span_bounds(dims: &[GridDimension], start: usize, span: usize) -> Option<(f32, f32)> {
+    let first = dims.get(start)?;
+    if span <= 1 {
+        Some((first.location, first.actual_size))
+    } else {
+        let last = &dims[(start + span).min(dims.len()) - 1];
+        Some((
+            first.location,
+            last.location + last.actual_size - first.location,
+        ))
+    }
+}
+
 fn fetch_width(child: &UiNode, i: usize) -> Option<f32> {
-    if child.column() == i && child.visibility() {
+    if child.column() == i && child.visibility() && !is_spanned(child) {
         Some(child.desired_size().x)
     } else {
         None
@@ -177,7 +247,7 @@ fn fetch_width(child: &UiNode, i: usize) -> Option<f32> {
 }
 
 fn fetch_height(child: &UiNode, i: usize) -> Option<f32> {
-    if child.row() == i && child.visibility() {
+    if child.row() == i && child.visibility() && !is_spanned(child) {
         Some(child.desired_size().y)
     } else {
         None
@@ -252,7 +322,10 @@ impl Control for Grid {
                         .iter()
                         .filter_map(|&c| {
                             let child_ref = ui.node(c);
-                            if child_ref.row() == row_index && child_ref.column() == column_index {
+                            if child_ref.row() == row_index
+                                && child_ref.column() == column_index
+                                && !is_spanned(child_ref)
+                            {
                                 Some(c)
                             } else {
                                 None
@@ -311,6 +384,70 @@ impl Control for Grid {
             }
         }
 
+        // Children that span multiple cells can only grow auto-sized dimensions. Such child is
+        // measured twice: first to find widths of columns, and then again with the final width
+        // to find heights of rows - it is needed for wrapped text.
+        for &child in self.children() {
+            let child_ref = ui.node(child);
+            if !is_spanned(child_ref) || !child_ref.visibility() {
+                continue;
+            }
+
+            if let (Some(column_range), Some(row_range)) = (
+                span_range(&columns, child_ref.column(), child_ref.column_span()),
+                span_range(&rows, child_ref.row(), child_ref.row_span()),
+            ) {
+                let stretch_sized_width = calc_avg_size_for_stretch_dim(
+                    &columns,
+                    self.children(),
+                    available_size.x,
+                    ui,
+                    fetch_width,
+                );
+
+                let stretch_sized_height = calc_avg_size_for_stretch_dim(
+                    &rows,
+                    self.children(),
+                    available_size.y,
+                    ui,
+                    fetch_height,
+                );
+
+                let height_constraint = span_constraint(
+                    &rows,
+                    row_range.clone(),
+                    available_size.y,
+                    stretch_sized_height,
+                );
+
+                let width_constraint = span_constraint(
+                    &columns,
+                    column_range.clone(),
+                    available_size.x,
+                    stretch_sized_width,
+                );
+
+                ui.measure_node(child, Vector2::new(width_constraint, height_constraint));
+                expand_span(
+                    &mut columns,
+                    column_range.clone(),
+                    ui.node(child).desired_size().x,
+                    available_size.x,
+                );
+
+                let final_width = span_actual_size(&columns, column_range);
+                if final_width != width_constraint {
+                    ui.measure_node(child, Vector2::new(final_width, height_constraint));
+                }
+                expand_span(
+                    &mut rows,
+                    row_range,
+                    ui.node(child).desired_size().y,
+                    available_size.y,
+                );
+            }
+        }
+
         let mut desired_size = Vector2::default();
         // Step 4. Calculate desired size of grid.
         for column in columns.iter() {
@@ -341,17 +478,9 @@ impl Control for Grid {
 
         for child_handle in self.widget.children() {
             let child = ui.nodes.borrow(*child_handle);
-            if let Some(column) = columns.get(child.column()) {
-                if let Some(row) = rows.get(child.row()) {
-                    ui.arrange_node(
-                        *child_handle,
-                        &Rect::new(
-                            column.location,
-                            row.location,
-                            column.actual_size,
-                            row.actual_size,
-                        ),
-                    );
+            if let Some((x, width)) = span_bounds(&columns, child.column(), child.column_span()) {
+                if let Some((y, height)) = span_bounds(&rows, child.row(), child.row_span()) {
+                    ui.arrange_node(*child_handle, &Rect::new(x, y, width, height));
                 }
             }
         }
@@ -437,6 +566,8 @@ impl GridBuilder {
         self
     }
 
+    /// Enables drawing of grid's border and lines between its rows and columns. Useful for
+    /// debugging of layouts.
     pub fn draw_border(mut self, value: bool) -> Self {
         self.draw_border = value;
         self
@@ -518,3 +649,65 @@ impl Grid {
         self.border_thickness
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        border::BorderBuilder,
+        core::{algebra::Vector2, pool::Handle},
+        grid::{Column, GridBuilder, Row},
+        widget::WidgetBuilder,
+        BuildContext, HorizontalAlignment, UiNode, UserInterface, VerticalAlignment,
+    };
+
+    fn make_cell(
+        ctx: &mut BuildContext,
+        row: usize,
+        column: usize,
+        column_span: usize,
+        size: Vector2<f32>,
+    ) -> Handle<UiNode> {
+        BorderBuilder::new(
+            WidgetBuilder::new()
+                .on_row(row)
+                .on_column(column)
+                .on_column_span(column_span)
+                .with_width(size.x)
+                .with_height(size.y)
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top),
+        )
+        .build(ctx)
+    }
+
+    #[test]
+    fn grid_column_span() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let ctx = &mut ui.build_ctx();
+
+        let header = make_cell(ctx, 0, 0, 2, Vector2::new(300.0, 20.0));
+        let left = make_cell(ctx, 1, 0, 1, Vector2::new(50.0, 30.0));
+        let right = make_cell(ctx, 1, 1, 1, Vector2::new(50.0, 30.0));
+        let grid = GridBuilder::new(WidgetBuilder::new().with_children([header, left, right]))
+            .add_column(Column::auto())
+            .add_column(Column::auto())
+            .add_row(Row::auto())
+            .add_row(Row::auto())
+            .build(ctx);
+
+        ui.update(screen_size, 0.0);
+
+        // Header is wider than both columns, so they must grow evenly.
+        assert_eq!(ui.node(grid).actual_size(), Vector2::new(300.0, 50.0));
+        assert_eq!(ui.node(header).actual_size(), Vector2::new(300.0, 20.0));
+        assert_eq!(
+            ui.node(left).actual_local_position(),
+            Vector2::new(0.0, 20.0)
+        );
+        assert_eq!(
+            ui.node(right).actual_local_position(),
+            Vector2::new(150.0, 20.0)
+        );
+    }
+}
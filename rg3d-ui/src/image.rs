@@ -1,8 +1,8 @@
 use crate::{
     brush::Brush,
-    core::{algebra::Vector2, color::Color, pool::Handle},
+    core::{algebra::Vector2, color::Color, math::Rect, pool::Handle},
     define_constructor,
-    draw::{CommandTexture, Draw, DrawingContext, SharedTexture},
+    draw::{ClipShape, CommandTexture, Draw, DrawingContext, SharedTexture},
     message::{MessageDirection, UiMessage},
     widget::{Widget, WidgetBuilder},
     BuildContext, Control, UiNode, UserInterface,
@@ -16,11 +16,15 @@ use std::{
 pub enum ImageMessage {
     Texture(Option<SharedTexture>),
     Flip(bool),
+    /// Sets a rectangle of the texture (in texture coordinates) that will be shown, it is used
+    /// to show a part of a texture atlas.
+    UvRect(Rect<f32>),
 }
 
 impl ImageMessage {
     define_constructor!(ImageMessage:Texture => fn texture(Option<SharedTexture>), layout: false);
     define_constructor!(ImageMessage:Flip => fn flip(bool), layout: false);
+    define_constructor!(ImageMessage:UvRect => fn uv_rect(Rect<f32>), layout: false);
 }
 
 #[derive(Clone)]
@@ -28,6 +32,7 @@ pub struct Image {
     widget: Widget,
     texture: Option<SharedTexture>,
     flip: bool,
+    uv_rect: Rect<f32>,
 }
 
 crate::define_widget_deref!(Image);
@@ -38,6 +43,7 @@ impl Image {
             widget,
             texture: None,
             flip: false,
+            uv_rect: full_uv_rect(),
         }
     }
 
@@ -48,6 +54,14 @@ impl Image {
     pub fn texture(&self) -> Option<SharedTexture> {
         self.texture.clone()
     }
+
+    pub fn uv_rect(&self) -> Rect<f32> {
+        self.uv_rect
+    }
+}
+
+fn full_uv_rect() -> Rect<f32> {
+    Rect::new(0.0, 0.0, 1.0, 1.0)
 }
 
 impl Control for Image {
@@ -61,12 +75,18 @@ impl Control for Image {
 
     fn draw(&self, drawing_context: &mut DrawingContext) {
         let bounds = self.widget.screen_bounds();
-        let tex_coords = if self.flip {
+        let tex_coords = if self.flip || self.uv_rect != full_uv_rect() {
+            let r = self.uv_rect;
+            let (top, bottom) = if self.flip {
+                (r.y() + r.h(), r.y())
+            } else {
+                (r.y(), r.y() + r.h())
+            };
             Some([
-                Vector2::new(0.0, 0.0),
-                Vector2::new(1.0, 0.0),
-                Vector2::new(1.0, -1.0),
-                Vector2::new(0.0, -1.0),
+                Vector2::new(r.x(), top),
+                Vector2::new(r.x() + r.w(), top),
+                Vector2::new(r.x() + r.w(), bottom),
+                Vector2::new(r.x(), bottom),
             ])
         } else {
             None
@@ -91,6 +111,9 @@ impl Control for Image {
                     &ImageMessage::Flip(flip) => {
                         self.flip = flip;
                     }
+                    &ImageMessage::UvRect(uv_rect) => {
+                        self.uv_rect = uv_rect;
+                    }
                 }
             }
         }
@@ -101,6 +124,7 @@ pub struct ImageBuilder {
     widget_builder: WidgetBuilder,
     texture: Option<SharedTexture>,
     flip: bool,
+    uv_rect: Rect<f32>,
 }
 
 impl ImageBuilder {
@@ -109,6 +133,7 @@ impl ImageBuilder {
             widget_builder,
             texture: None,
             flip: false,
+            uv_rect: full_uv_rect(),
         }
     }
 
@@ -117,6 +142,13 @@ impl ImageBuilder {
         self
     }
 
+    /// Sets a rectangle of the texture (in texture coordinates) that will be shown. Default is
+    /// whole texture.
+    pub fn with_uv_rect(mut self, uv_rect: Rect<f32>) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
     pub fn with_texture(mut self, texture: SharedTexture) -> Self {
         self.texture = Some(texture);
         self
@@ -127,6 +159,16 @@ impl ImageBuilder {
         self
     }
 
+    /// Makes corners of the image rounded with given radius, children of the image are clipped
+    /// too. It is a shortcut for [`WidgetBuilder::with_clip_shape`] with
+    /// [`ClipShape::RoundedRect`].
+    pub fn with_corner_radius(mut self, radius: f32) -> Self {
+        self.widget_builder = self
+            .widget_builder
+            .with_clip_shape(ClipShape::RoundedRect { radius });
+        self
+    }
+
     pub fn build_node(mut self) -> UiNode {
         if self.widget_builder.background.is_none() {
             self.widget_builder.background = Some(Brush::Solid(Color::WHITE))
@@ -136,6 +178,7 @@ impl ImageBuilder {
             widget: self.widget_builder.build(),
             texture: self.texture,
             flip: self.flip,
+            uv_rect: self.uv_rect,
         };
         UiNode::new(image)
     }
@@ -289,6 +289,7 @@ pub fn make_expander_container(
         .with_checkbox(make_expander_check_box(layer_index, property_name, ctx))
         .with_expander_column(Column::strict(NAME_COLUMN_WIDTH))
         .with_expanded(true)
+        .with_toggle_on_header(false)
         .with_header(header)
         .with_content(content)
         .build(ctx)
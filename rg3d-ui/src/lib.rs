@@ -14,12 +14,14 @@ extern crate lazy_static;
 
 pub use rg3d_core as core;
 
+pub mod animation;
 pub mod border;
 pub mod brush;
 pub mod button;
 pub mod canvas;
 pub mod check_box;
 pub mod color;
+pub mod console;
 pub mod curve;
 pub mod decorator;
 pub mod dock;
@@ -32,10 +34,12 @@ pub mod grid;
 pub mod image;
 pub mod inspector;
 pub mod list_view;
+pub mod markup;
 pub mod menu;
 pub mod message;
 pub mod messagebox;
 pub mod numeric;
+pub mod pan_zoom_canvas;
 pub mod popup;
 pub mod progress_bar;
 pub mod range;
@@ -52,11 +56,14 @@ pub mod ttf;
 pub mod utils;
 pub mod vec;
 pub mod vector_image;
+pub mod virtual_keyboard;
+pub mod virtual_list_view;
 pub mod widget;
 pub mod window;
 pub mod wrap_panel;
 
 use crate::{
+    animation::{AnimatedProperty, AnimationQueue, Easing, WidgetAnimation},
     brush::Brush,
     canvas::Canvas,
     core::{
@@ -64,12 +71,14 @@ use crate::{
         color::Color,
         math::{clampf, Rect},
         pool::{Handle, Pool},
-        scope_profile,
+        profile_scope, scope_profile,
     },
     draw::{CommandTexture, Draw, DrawingContext},
+    formatted_text::InlineImage,
+    markup::InlineIcons,
     message::{
         ButtonState, CursorIcon, KeyboardModifiers, MessageDirection, MouseButton, OsEvent,
-        UiMessage,
+        RoutingStrategy, TouchPhase, UiMessage,
     },
     popup::{Placement, PopupMessage},
     ttf::{Font, SharedFont},
@@ -372,6 +381,35 @@ impl Default for DragContext {
     }
 }
 
+#[derive(Copy, Clone, Debug)]
+struct TouchPoint {
+    id: u64,
+    position: Vector2<f32>,
+}
+
+#[derive(Default)]
+struct TouchContext {
+    touches: Vec<TouchPoint>,
+    /// Id of a finger that is mapped to the left mouse button.
+    primary: Option<u64>,
+    /// Distance between first two fingers and a point between them.
+    pinch: Option<(f32, Vector2<f32>)>,
+    /// `true` if last pointer input was made by touch.
+    active: bool,
+}
+
+impl TouchContext {
+    fn pinch_state(&self) -> Option<(f32, Vector2<f32>)> {
+        match self.touches.as_slice() {
+            [a, b, ..] => Some((
+                (a.position - b.position).norm(),
+                (a.position + b.position).scale(0.5),
+            )),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub struct MouseState {
     left: ButtonState,
@@ -436,6 +474,16 @@ impl<'a> BuildContext<'a> {
     pub fn copy(&mut self, node: Handle<UiNode>) -> Handle<UiNode> {
         self.ui.copy_node(node)
     }
+
+    /// Returns every registered inline icon, see [`UserInterface::set_inline_icon`].
+    pub fn inline_icons(&self) -> &InlineIcons {
+        &self.ui.inline_icons
+    }
+
+    /// Returns a handle to root of the built-in overlay layer, see [`UserInterface::create_layer`].
+    pub fn overlay_layer(&self) -> Handle<UiNode> {
+        self.ui.overlay_layer
+    }
 }
 
 impl<'a> Index<Handle<UiNode>> for BuildContext<'a> {
@@ -502,6 +550,41 @@ enum LayoutEvent {
     VisibilityChanged(Handle<UiNode>),
 }
 
+/// Name of the built-in overlay layer, see [`UserInterface::create_layer`].
+pub const OVERLAY_LAYER_NAME: &str = "Overlay";
+
+/// A root of an overlay layer, see [`UserInterface::create_layer`].
+struct Layer {
+    name: String,
+    priority: i32,
+    root: Handle<UiNode>,
+}
+
+struct SubscriptionEntry {
+    root: Handle<UiNode>,
+    sender: Sender<UiMessage>,
+}
+
+/// A receiver of messages that were sent to a widget or any of its descendants, see
+/// [`UserInterface::subscribe`]. The subscription is cancelled when this object is dropped.
+pub struct MessageSubscription {
+    root: Handle<UiNode>,
+    receiver: Receiver<UiMessage>,
+}
+
+impl MessageSubscription {
+    /// Returns a handle of root widget of the subscription.
+    pub fn root(&self) -> Handle<UiNode> {
+        self.root
+    }
+
+    /// Extracts next message from the subscription queue. Messages are in the same order as
+    /// they were processed by the user interface.
+    pub fn poll(&self) -> Option<UiMessage> {
+        self.receiver.try_recv().ok()
+    }
+}
+
 pub struct UserInterface {
     screen_size: Vector2<f32>,
     nodes: Pool<UiNode>,
@@ -520,6 +603,7 @@ pub struct UserInterface {
     bubble_queue: VecDeque<Handle<UiNode>>,
     drag_context: DragContext,
     mouse_state: MouseState,
+    touch_context: TouchContext,
     keyboard_modifiers: KeyboardModifiers,
     cursor_icon: CursorIcon,
     active_tooltip: Option<TooltipEntry>,
@@ -528,6 +612,13 @@ pub struct UserInterface {
     layout_events_receiver: Receiver<LayoutEvent>,
     layout_events_sender: Sender<LayoutEvent>,
     need_update_global_transform: bool,
+    animations: Vec<AnimationQueue>,
+    hit_test_opacity_threshold: f32,
+    subscriptions: Vec<SubscriptionEntry>,
+    scale: f32,
+    inline_icons: InlineIcons,
+    layers: Vec<Layer>,
+    overlay_layer: Handle<UiNode>,
 }
 
 lazy_static! {
@@ -567,7 +658,7 @@ fn draw_node(
     let start_index = drawing_context.get_commands().len();
 
     let pushed = if !is_node_enabled(nodes, node_handle) {
-        drawing_context.push_opacity(0.4);
+        drawing_context.push_opacity(0.4 * node.opacity().unwrap_or(1.0));
         true
     } else if let Some(opacity) = node.opacity() {
         drawing_context.push_opacity(opacity);
@@ -576,6 +667,11 @@ fn draw_node(
         false
     };
 
+    // Clip shape applies to the node itself and to all its descendants.
+    if let Some(clip_shape) = node.clip_shape() {
+        drawing_context.push_clip_shape(clip_shape, &bounds);
+    }
+
     node.draw(drawing_context);
 
     let end_index = drawing_context.get_commands().len();
@@ -583,19 +679,42 @@ fn draw_node(
         node.command_indices.borrow_mut().push(i);
     }
 
-    // Continue on children
+    // Continue on children, they're already sorted in drawing order.
     for &child_node in node.children().iter() {
-        // Do not continue render of top-most nodes - they'll be rendered in separate pass.
+        // Do not continue render of top-level overlays - they'll be rendered in separate pass.
         if !nodes[child_node].is_draw_on_top() {
             draw_node(nodes, child_node, drawing_context);
         }
     }
 
+    if node.clip_shape().is_some() {
+        drawing_context.pop_clip_shape();
+    }
+
     if pushed {
         drawing_context.pop_opacity();
     }
 }
 
+/// Collects top-level overlays (widgets with `draw_on_top` flag) of a sub-tree in drawing order. Only sub-trees
+/// of nodes that passes `descend` check are visited.
+fn collect_overlays(
+    nodes: &Pool<UiNode>,
+    node_handle: Handle<UiNode>,
+    descend: fn(&UiNode) -> bool,
+    overlays: &mut Vec<Handle<UiNode>>,
+) {
+    for &child_handle in nodes[node_handle].children() {
+        let child = &nodes[child_handle];
+        if descend(child) {
+            if child.is_draw_on_top() {
+                overlays.push(child_handle);
+            }
+            collect_overlays(nodes, child_handle, descend, overlays);
+        }
+    }
+}
+
 fn is_node_enabled(nodes: &Pool<UiNode>, handle: Handle<UiNode>) -> bool {
     let root_node = &nodes[handle];
     let mut enabled = root_node.enabled();
@@ -633,6 +752,7 @@ impl UserInterface {
             bubble_queue: Default::default(),
             drag_context: Default::default(),
             mouse_state: Default::default(),
+            touch_context: Default::default(),
             keyboard_modifiers: Default::default(),
             cursor_icon: Default::default(),
             active_tooltip: Default::default(),
@@ -641,15 +761,181 @@ impl UserInterface {
             layout_events_receiver,
             layout_events_sender,
             need_update_global_transform: Default::default(),
+            animations: Default::default(),
+            hit_test_opacity_threshold: 0.0,
+            subscriptions: Default::default(),
+            scale: 1.0,
+            inline_icons: Default::default(),
+            layers: Default::default(),
+            overlay_layer: Handle::NONE,
         };
         ui.root_canvas = ui.add_node(UiNode::new(Canvas::new(WidgetBuilder::new().build())));
+        ui.overlay_layer = ui.create_layer(OVERLAY_LAYER_NAME, i32::MAX);
         ui
     }
 
+    /// Creates new overlay layer with given name and priority and returns a handle to its root. Layers are
+    /// drawn on top of regular widgets of the root canvas, layers with higher priority are drawn on top of
+    /// layers with lower priority (layers with the same priority are drawn in creation order). Hit testing
+    /// works in reverse order - topmost layer is hit first. This is useful to separate parts of complex
+    /// interfaces, for example HUD, menus and debug console, no matter in which order the widgets were
+    /// created.
+    ///
+    /// Root of a layer is a canvas that occupies entire screen, link widgets to it to put them in the layer.
+    /// If a layer with the same name already exists, its priority will be changed and the handle of its
+    /// root will be returned.
+    ///
+    /// There is built-in layer with [`OVERLAY_LAYER_NAME`] name and highest possible priority, which is used
+    /// for tooltips, popups and drag previews.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use rg3d_ui::{
+    ///     message::MessageDirection, text::TextBuilder, widget::{WidgetBuilder, WidgetMessage},
+    ///     UserInterface, core::algebra::Vector2,
+    /// };
+    ///
+    /// let mut ui = UserInterface::new(Vector2::new(1024.0, 768.0));
+    /// let hud = ui.create_layer("hud", 0);
+    /// let debug = ui.create_layer("debug", 100);
+    ///
+    /// let console = TextBuilder::new(WidgetBuilder::new()).build(&mut ui.build_ctx());
+    /// ui.send_message(WidgetMessage::link(console, MessageDirection::ToWidget, debug));
+    /// # let _ = hud;
+    /// ```
+    pub fn create_layer<N: AsRef<str>>(&mut self, name: N, priority: i32) -> Handle<UiNode> {
+        let name = name.as_ref();
+        let root = if let Some(layer) = self.layers.iter_mut().find(|l| l.name == name) {
+            layer.priority = priority;
+            layer.root
+        } else {
+            let root = self.add_node(UiNode::new(Canvas::new(
+                WidgetBuilder::new()
+                    .with_name(name)
+                    .with_width(self.screen_size.x)
+                    .with_height(self.screen_size.y)
+                    .build(),
+            )));
+            self.layers.push(Layer {
+                name: name.to_owned(),
+                priority,
+                root,
+            });
+            root
+        };
+        self.sort_children(self.root_canvas);
+        root
+    }
+
+    /// Returns a handle to root of a layer with given name, or `Handle::NONE` if there is no such layer.
+    pub fn layer<N: AsRef<str>>(&self, name: N) -> Handle<UiNode> {
+        self.layers
+            .iter()
+            .find(|l| l.name == name.as_ref())
+            .map(|l| l.root)
+            .unwrap_or_default()
+    }
+
+    /// Returns priority of a layer with given name.
+    pub fn layer_priority<N: AsRef<str>>(&self, name: N) -> Option<i32> {
+        self.layers
+            .iter()
+            .find(|l| l.name == name.as_ref())
+            .map(|l| l.priority)
+    }
+
+    /// Changes priority of a layer with given name, layers will be re-sorted immediately. Does nothing if
+    /// there is no such layer.
+    pub fn set_layer_priority<N: AsRef<str>>(&mut self, name: N, priority: i32) {
+        if let Some(layer) = self.layers.iter_mut().find(|l| l.name == name.as_ref()) {
+            layer.priority = priority;
+            self.sort_children(self.root_canvas);
+        }
+    }
+
+    /// Returns a handle to root of the built-in overlay layer, see [`Self::create_layer`].
+    pub fn overlay_layer(&self) -> Handle<UiNode> {
+        self.overlay_layer
+    }
+
+    /// Returns a key that defines position of a node among its siblings. Layers are always after regular
+    /// nodes and sorted by their priority, regular nodes are sorted by their z index.
+    fn draw_order_key(&self, node: Handle<UiNode>) -> (bool, i64) {
+        if let Some(layer) = self.layers.iter().find(|l| l.root == node) {
+            (true, layer.priority as i64)
+        } else {
+            // Node could be temporarily taken out of the pool while it handles a message and builds
+            // other widgets, such node is treated as it has default z index.
+            let z_index = self.nodes.try_borrow(node).map_or(0, |n| n.z_index());
+            (false, z_index as i64)
+        }
+    }
+
+    /// Restores drawing order of children of a node. Sorting is stable, so nodes with the same z index
+    /// keep their relative order.
+    fn sort_children(&mut self, parent: Handle<UiNode>) {
+        self.stack.clear();
+        self.stack.extend_from_slice(self.nodes[parent].children());
+
+        let mut stack = std::mem::take(&mut self.stack);
+        stack.sort_by_key(|&child| self.draw_order_key(child));
+
+        let parent = &mut self.nodes[parent];
+        parent.clear_children();
+        for &child in stack.iter() {
+            parent.add_child(child, false);
+        }
+
+        self.stack = stack;
+    }
+
     pub fn keyboard_modifiers(&self) -> KeyboardModifiers {
         self.keyboard_modifiers
     }
 
+    /// Returns a handle of a widget that receives keyboard input.
+    pub fn keyboard_focus_node(&self) -> Handle<UiNode> {
+        self.keyboard_focus_node
+    }
+
+    /// Returns `true` if last pointer input was made by touch, widgets may use it to adapt their
+    /// behaviour. For example [`virtual_keyboard::VirtualKeyboard`] pops up only for touch input.
+    pub fn is_touch_input_active(&self) -> bool {
+        self.touch_context.active
+    }
+
+    /// Stops mapping of current touch to the left mouse button without mouse up message, so a
+    /// widget under the finger won't be clicked. Pressed widget receives mouse leave message
+    /// instead. Widgets that scroll their content by touch should call it when the finger has
+    /// moved far enough to be a scroll rather than a tap.
+    pub fn cancel_touch_press(&mut self) {
+        if self.touch_context.primary.take().is_some() {
+            self.mouse_state.left = ButtonState::Released;
+            self.release_mouse_capture();
+
+            if self.drag_context.is_dragging {
+                self.drag_context.is_dragging = false;
+                self.cursor_icon = CursorIcon::Default;
+            }
+            self.drag_context.drag_node = Handle::NONE;
+            if self.nodes.is_valid_handle(self.drag_context.drag_preview) {
+                self.remove_node(self.drag_context.drag_preview);
+                self.drag_context.drag_preview = Default::default();
+            }
+
+            if let Some(picked_node) = self.nodes.try_borrow_mut(self.picked_node) {
+                picked_node.is_mouse_directly_over = false;
+            }
+            if self.picked_node.is_some() {
+                self.send_message(WidgetMessage::mouse_leave(
+                    self.picked_node,
+                    MessageDirection::FromWidget,
+                ));
+            }
+        }
+    }
+
     pub fn build_ctx(&mut self) -> BuildContext<'_> {
         BuildContext { ui: self }
     }
@@ -742,10 +1028,52 @@ impl UserInterface {
         }
     }
 
+    /// Returns size of the screen in logical units (physical size divided by the scale).
     pub fn screen_size(&self) -> Vector2<f32> {
         self.screen_size
     }
 
+    /// Sets scale of the whole user interface. Scale defines how many physical pixels are in one
+    /// logical unit, every size and position of widgets is defined in logical units. Use scale
+    /// factor of a window to make the interface look the same on screens with different DPI, the
+    /// engine does this automatically. Fonts are rasterized at scaled size, so text stays sharp.
+    ///
+    /// Default is 1.0 - one logical unit is one physical pixel.
+    pub fn set_scale(&mut self, scale: f32) {
+        let scale = scale.max(f32::EPSILON);
+        if self.scale == scale {
+            return;
+        }
+
+        // Keep physical size of the screen, it will be updated on next update anyway.
+        self.screen_size = self.screen_size.scale(self.scale / scale);
+        self.cursor_position = self.cursor_position.scale(self.scale / scale);
+        self.scale = scale;
+
+        // Every widget must be re-measured, text must be rebuilt with new scale.
+        for node in self.nodes.iter() {
+            node.measure_valid.set(false);
+            node.arrange_valid.set(false);
+        }
+        self.need_update_global_transform = true;
+    }
+
+    /// Returns current scale of the user interface. See [`Self::set_scale`] for more info.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Registers an image that could be shown inline in text by `[icon=name]` tag of markup,
+    /// see [`crate::markup`]. Text that was already parsed is not affected.
+    pub fn set_inline_icon<N: AsRef<str>>(&mut self, name: N, image: InlineImage) {
+        self.inline_icons.insert(name.as_ref().to_owned(), image);
+    }
+
+    /// Returns every registered inline icon.
+    pub fn inline_icons(&self) -> &InlineIcons {
+        &self.inline_icons
+    }
+
     fn handle_layout_events(&mut self) {
         fn invalidate_recursive_up(
             nodes: &Pool<UiNode>,
@@ -780,10 +1108,24 @@ impl UserInterface {
         }
     }
 
+    /// Updates layout, animations and tooltips of the user interface. Screen size must be given in
+    /// physical pixels, layout is calculated in logical units (see [`Self::set_scale`]).
     pub fn update(&mut self, screen_size: Vector2<f32>, dt: f32) {
         scope_profile!();
+        profile_scope!("UI");
+
+        self.screen_size = screen_size.scale(1.0 / self.scale);
+        let screen_size = self.screen_size;
+
+        self.update_animations(dt);
 
-        self.screen_size = screen_size;
+        for layer in self.layers.iter() {
+            let root = &mut self.nodes[layer.root];
+            if root.width() != screen_size.x || root.height() != screen_size.y {
+                root.set_width(screen_size.x).set_height(screen_size.y);
+                root.invalidate_layout();
+            }
+        }
 
         self.handle_layout_events();
 
@@ -825,8 +1167,116 @@ impl UserInterface {
         self.cursor_icon
     }
 
+    fn update_animations(&mut self, dt: f32) {
+        // Widget might be removed while it was animated.
+        let nodes = &mut self.nodes;
+        self.animations
+            .retain(|queue| nodes.is_valid_handle(queue.widget));
+
+        for queue in self.animations.iter_mut() {
+            if let Some(message) = queue.update(&mut nodes[queue.widget], dt) {
+                let _ = self.sender.send(message);
+            }
+        }
+
+        self.animations.retain(|queue| !queue.animations.is_empty());
+    }
+
+    /// Enqueues new animation for a widget. Animations of a widget are played one after another
+    /// in order of addition. See [`animation`] module docs for more info.
+    pub fn animate(&mut self, widget: Handle<UiNode>, animation: WidgetAnimation) {
+        if let Some(queue) = self.animations.iter_mut().find(|q| q.widget == widget) {
+            queue.animations.push_back(animation);
+        } else {
+            self.animations.push(AnimationQueue {
+                widget,
+                animations: vec![animation].into(),
+            });
+        }
+    }
+
+    /// Removes every pending animation of a widget. Animated properties will keep their current
+    /// values.
+    pub fn stop_animations(&mut self, widget: Handle<UiNode>) {
+        self.animations.retain(|queue| queue.widget != widget);
+    }
+
+    /// Returns `true` if a widget has any pending animations.
+    pub fn is_animating(&self, widget: Handle<UiNode>) -> bool {
+        self.animations.iter().any(|queue| queue.widget == widget)
+    }
+
+    /// Makes a widget visible and smoothly changes its opacity from zero to one.
+    pub fn fade_in(&mut self, widget: Handle<UiNode>, duration: f32) {
+        self.send_message(WidgetMessage::visibility(
+            widget,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        self.animate(
+            widget,
+            WidgetAnimation::new(duration)
+                .with_name("FadeIn")
+                .with_property(AnimatedProperty::Opacity {
+                    from: Some(0.0),
+                    to: 1.0,
+                }),
+        );
+    }
+
+    /// Smoothly changes opacity of a widget from current value to zero and hides the widget.
+    pub fn fade_out(&mut self, widget: Handle<UiNode>, duration: f32) {
+        self.animate(
+            widget,
+            WidgetAnimation::new(duration)
+                .with_name("FadeOut")
+                .with_property(AnimatedProperty::Opacity {
+                    from: None,
+                    to: 0.0,
+                })
+                .with_hide_on_complete(true),
+        );
+    }
+
+    /// Makes a widget visible and moves it from given offset to its current desired position,
+    /// while fading it in. Works only for widgets whose position is defined by desired position
+    /// (for example children of Canvas).
+    pub fn slide_in(&mut self, widget: Handle<UiNode>, offset: Vector2<f32>, duration: f32) {
+        self.send_message(WidgetMessage::visibility(
+            widget,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        self.animate(
+            widget,
+            WidgetAnimation::new(duration)
+                .with_name("SlideIn")
+                .with_easing(Easing::EaseOut)
+                .with_property(AnimatedProperty::PositionOffset {
+                    from: offset,
+                    to: Vector2::default(),
+                })
+                .with_property(AnimatedProperty::Opacity {
+                    from: Some(0.0),
+                    to: 1.0,
+                }),
+        );
+    }
+
+    /// Sets minimum opacity of a widget at which the widget (and its children) can be picked by
+    /// mouse. Default is zero, which means that even fully transparent widgets can be picked.
+    /// Useful to prevent fading-out menus from eating clicks.
+    pub fn set_hit_test_opacity_threshold(&mut self, threshold: f32) {
+        self.hit_test_opacity_threshold = threshold;
+    }
+
+    pub fn hit_test_opacity_threshold(&self) -> f32 {
+        self.hit_test_opacity_threshold
+    }
+
     pub fn draw(&mut self) -> &DrawingContext {
         scope_profile!();
+        profile_scope!("UIDraw");
 
         self.calculate_clip_bounds(
             self.root_canvas,
@@ -838,21 +1288,20 @@ impl UserInterface {
             node.command_indices.borrow_mut().clear();
         }
 
-        // Draw everything except top-most nodes.
+        // Draw everything except top-level overlays, layers are the last children of root canvas so
+        // they're drawn on top of regular widgets.
         draw_node(&self.nodes, self.root_canvas, &mut self.drawing_context);
 
-        // Render top-most nodes in separate pass.
-        // TODO: This may give weird results because of invalid nesting.
+        // Render top-level overlays in separate pass, in the same order as they're in the tree.
         self.stack.clear();
-        self.stack.push(self.root());
-        while let Some(node_handle) = self.stack.pop() {
-            let node = &self.nodes[node_handle];
-            if node.is_draw_on_top() {
-                draw_node(&self.nodes, node_handle, &mut self.drawing_context);
-            }
-            for &child in node.children() {
-                self.stack.push(child);
-            }
+        collect_overlays(
+            &self.nodes,
+            self.root_canvas,
+            |n| n.is_globally_visible(),
+            &mut self.stack,
+        );
+        for &node_handle in self.stack.iter() {
+            draw_node(&self.nodes, node_handle, &mut self.drawing_context);
         }
 
         // Debug info rendered on top of other.
@@ -1023,6 +1472,12 @@ impl UserInterface {
         if widget.is_globally_visible() {
             clipped = !widget.screen_bounds().contains(pt);
 
+            if !clipped {
+                if let Some(clip_shape) = widget.clip_shape() {
+                    clipped = !clip_shape.contains_point(&widget.screen_bounds(), pt);
+                }
+            }
+
             if !clipped {
                 for command_index in widget.command_indices.borrow().iter() {
                     if let Some(command) = self.drawing_context.get_commands().get(*command_index) {
@@ -1079,6 +1534,9 @@ impl UserInterface {
 
         if !widget.is_hit_test_visible()
             || !widget.enabled()
+            || widget
+                .opacity()
+                .map_or(false, |opacity| opacity < self.hit_test_opacity_threshold)
             || !widget.screen_bounds().intersects(Rect {
                 position: Default::default(),
                 size: self.screen_size,
@@ -1095,6 +1553,11 @@ impl UserInterface {
         };
 
         for child_handle in widget.children() {
+            // Top-level overlays are picked separately.
+            if self.nodes[*child_handle].is_draw_on_top() {
+                continue;
+            }
+
             *level += 1;
             let picked_child = self.pick_node(*child_handle, pt, level);
             if picked_child.is_some() && *level > topmost_picked_level {
@@ -1106,6 +1569,27 @@ impl UserInterface {
         picked
     }
 
+    fn pick_sub_tree(&self, root: Handle<UiNode>, pt: Vector2<f32>) -> Handle<UiNode> {
+        // Top-level overlays are drawn after everything else, so they must be checked first.
+        let mut overlays = Vec::new();
+        collect_overlays(
+            &self.nodes,
+            root,
+            |n| n.is_globally_visible() && n.is_hit_test_visible() && n.enabled(),
+            &mut overlays,
+        );
+        for &overlay in overlays.iter().rev() {
+            let mut level = 0;
+            let picked = self.pick_node(overlay, pt, &mut level);
+            if picked.is_some() {
+                return picked;
+            }
+        }
+
+        let mut level = 0;
+        self.pick_node(root, pt, &mut level)
+    }
+
     pub fn cursor_position(&self) -> Vector2<f32> {
         self.cursor_position
     }
@@ -1117,8 +1601,7 @@ impl UserInterface {
             self.captured_node
         } else if self.picking_stack.is_empty() {
             // We're not restricted to any node, just start from root.
-            let mut level = 0;
-            self.pick_node(self.root_canvas, pt, &mut level)
+            self.pick_sub_tree(self.root_canvas, pt)
         } else {
             // We have some picking restriction chain.
             // Go over picking stack and try each entry. This will help with picking
@@ -1126,8 +1609,7 @@ impl UserInterface {
             // at the same time.
             for root in self.picking_stack.iter().rev() {
                 if self.nodes.is_valid_handle(root.handle) {
-                    let mut level = 0;
-                    let picked = self.pick_node(root.handle, pt, &mut level);
+                    let picked = self.pick_sub_tree(root.handle, pt);
                     if picked.is_some() {
                         return picked;
                     }
@@ -1309,6 +1791,46 @@ impl UserInterface {
         self.sender.send(message).unwrap()
     }
 
+    /// Creates new subscription that receives every message whose destination is given widget or
+    /// any of its descendants, messages are delivered to the subscription after they were routed
+    /// through the widget tree (see [`message`] module docs for ordering guarantees). It is useful
+    /// in complex UIs where every part of it could process only messages of its own subtree instead
+    /// of matching every message from the global queue. Messages are still returned from
+    /// [`Self::poll_message`] as well.
+    pub fn subscribe(&mut self, root: Handle<UiNode>) -> MessageSubscription {
+        let (sender, receiver) = mpsc::channel();
+        self.subscriptions.push(SubscriptionEntry { root, sender });
+        MessageSubscription { root, receiver }
+    }
+
+    // Checks whether a node is given root node or any of its descendants.
+    fn is_in_subtree(&self, mut node: Handle<UiNode>, root: Handle<UiNode>) -> bool {
+        while let Some(node_ref) = self.nodes.try_borrow(node) {
+            if node == root {
+                return true;
+            }
+            node = node_ref.parent();
+        }
+        false
+    }
+
+    fn is_interactive(&self, node: Handle<UiNode>) -> bool {
+        self.nodes
+            .try_borrow(node)
+            .map_or(false, |node| node.is_interactive())
+    }
+
+    // Non-focusable widget forbids focus for its whole subtree.
+    fn is_focusable(&self, mut node: Handle<UiNode>) -> bool {
+        while let Some(node_ref) = self.nodes.try_borrow(node) {
+            if !node_ref.is_focusable() {
+                return false;
+            }
+            node = node_ref.parent();
+        }
+        true
+    }
+
     // Puts node at the end of children list of a parent node.
     //
     // # Notes
@@ -1316,12 +1838,13 @@ impl UserInterface {
     // Node will be topmost *only* on same hierarchy level! So if you have a floating
     // window (for example) and a window embedded into some other control (yes this is
     // possible) then floating window won't be the topmost.
+    //
+    // Z index has priority over this, so node will be topmost only among the siblings with the
+    // same z index.
     fn make_topmost(&mut self, node: Handle<UiNode>) {
         let parent = self.node(node).parent();
         if parent.is_some() {
-            let parent = &mut self.nodes[parent];
-            parent.remove_child(node);
-            parent.add_child(node, false);
+            self.link_nodes_internal(node, parent, false);
         }
     }
 
@@ -1333,16 +1856,24 @@ impl UserInterface {
         // Gather chain of nodes from source to root.
         self.bubble_queue.clear();
         self.bubble_queue.push_back(message.destination());
-        let mut parent = self.nodes[message.destination()].parent();
-        while parent.is_some() && self.nodes.is_valid_handle(parent) {
-            self.bubble_queue.push_back(parent);
-            parent = self.nodes[parent].parent();
+        if message.routing_strategy() != RoutingStrategy::Direct {
+            let mut parent = self.nodes[message.destination()].parent();
+            while parent.is_some() && self.nodes.is_valid_handle(parent) {
+                self.bubble_queue.push_back(parent);
+                parent = self.nodes[parent].parent();
+            }
         }
 
         while let Some(handle) = self.bubble_queue.pop_front() {
             let (ticket, mut node) = self.nodes.take_reserve(handle);
             node.handle_routed_message(self, message);
             self.nodes.put_back(ticket, node);
+
+            if message.routing_strategy() == RoutingStrategy::BubbleUntilHandled
+                && message.handled()
+            {
+                break;
+            }
         }
     }
 
@@ -1360,9 +1891,17 @@ impl UserInterface {
                 }
 
                 if message.need_perform_layout() {
-                    self.update(self.screen_size, 0.0);
+                    self.update(self.screen_size.scale(self.scale), 0.0);
                 }
 
+                // Destination could be removed during processing, so subscribers are collected
+                // before routing.
+                let subscribers = self
+                    .subscriptions
+                    .iter()
+                    .map(|entry| self.is_in_subtree(message.destination(), entry.root))
+                    .collect::<Vec<_>>();
+
                 for &handle in self.preview_set.iter() {
                     if let Some(node_ref) = self.nodes.try_borrow(handle) {
                         node_ref.preview_message(self, &mut message);
@@ -1371,6 +1910,16 @@ impl UserInterface {
 
                 self.bubble_message(&mut message);
 
+                // Deliver the message to subscribers and forget subscriptions that were dropped.
+                let mut subscribers = subscribers.into_iter();
+                self.subscriptions.retain(|entry| {
+                    if subscribers.next().unwrap_or_default() {
+                        entry.sender.send(message.clone()).is_ok()
+                    } else {
+                        true
+                    }
+                });
+
                 if let Some(msg) = message.data::<WidgetMessage>() {
                     match msg {
                         WidgetMessage::ZIndex(_) => {
@@ -1378,23 +1927,7 @@ impl UserInterface {
                             // the same as z-index of children.
                             let parent = self.node(message.destination()).parent();
                             if parent.is_some() {
-                                self.stack.clear();
-                                for child in self.nodes.borrow(parent).children() {
-                                    self.stack.push(*child);
-                                }
-
-                                let nodes = &mut self.nodes;
-                                self.stack.sort_by(|a, b| {
-                                    let z_a = nodes.borrow(*a).z_index();
-                                    let z_b = nodes.borrow(*b).z_index();
-                                    z_a.cmp(&z_b)
-                                });
-
-                                let parent = self.nodes.borrow_mut(parent);
-                                parent.clear_children();
-                                for child in self.stack.iter() {
-                                    parent.add_child(*child, false);
-                                }
+                                self.sort_children(parent);
                             }
                         }
                         WidgetMessage::TopMost => {
@@ -1505,6 +2038,14 @@ impl UserInterface {
             MessageDirection::ToWidget,
             true,
         ));
+        // Tooltips live in the overlay layer, so they can't be occluded by other widgets.
+        if self.node(tooltip).parent() != self.overlay_layer {
+            self.send_message(WidgetMessage::link(
+                tooltip,
+                MessageDirection::ToWidget,
+                self.overlay_layer,
+            ));
+        }
         self.send_message(WidgetMessage::topmost(tooltip, MessageDirection::ToWidget));
         self.send_message(WidgetMessage::desired_position(
             tooltip,
@@ -1591,11 +2132,24 @@ impl UserInterface {
     /// Translates raw window event into some specific UI message. This is one of the
     /// most important methods of UI. You must call it each time you received a message
     /// from a window.
+    ///
+    /// # Touch input
+    ///
+    /// Every touch produces touch messages (see [`WidgetMessage::TouchStarted`]) with id of a finger.
+    /// First finger is also mapped to the cursor and the left mouse button, so every widget that
+    /// works with mouse works with touch too. Second finger cancels that mapping (see
+    /// [`Self::cancel_touch_press`]) and starts a pinch gesture, which is reported by
+    /// [`WidgetMessage::Pinch`] messages.
     pub fn process_os_event(&mut self, event: &OsEvent) -> bool {
         let mut event_processed = false;
 
         match event {
             &OsEvent::MouseInput { button, state, .. } => {
+                // Mapped touches are going through here too.
+                if self.touch_context.primary.is_none() {
+                    self.touch_context.active = false;
+                }
+
                 match button {
                     MouseButton::Left => self.mouse_state.left = state,
                     MouseButton::Right => self.mouse_state.right = state,
@@ -1607,8 +2161,11 @@ impl UserInterface {
                     ButtonState::Pressed => {
                         self.picked_node = self.hit_test(self.cursor_position);
 
+                        // Non-interactive node blocks the input, but does not react to it.
+                        let interactive = self.is_interactive(self.picked_node);
+
                         // Try to find draggable node in hierarchy starting from picked node.
-                        if self.picked_node.is_some() {
+                        if interactive {
                             self.stack.clear();
                             self.stack.push(self.picked_node);
                             while let Some(handle) = self.stack.pop() {
@@ -1624,7 +2181,10 @@ impl UserInterface {
                             self.drag_context.click_pos = self.cursor_position;
                         }
 
-                        if self.keyboard_focus_node != self.picked_node {
+                        if (interactive || self.picked_node.is_none())
+                            && self.keyboard_focus_node != self.picked_node
+                            && self.is_focusable(self.picked_node)
+                        {
                             if self.keyboard_focus_node.is_some() {
                                 self.send_message(WidgetMessage::lost_focus(
                                     self.keyboard_focus_node,
@@ -1642,13 +2202,15 @@ impl UserInterface {
                             }
                         }
 
-                        if self.picked_node.is_some() {
+                        if interactive {
                             self.send_message(WidgetMessage::mouse_down(
                                 self.picked_node,
                                 MessageDirection::FromWidget,
                                 self.cursor_position,
                                 button,
                             ));
+                        }
+                        if self.picked_node.is_some() {
                             event_processed = true;
                         }
                     }
@@ -1682,30 +2244,38 @@ impl UserInterface {
                                 self.drag_context.drag_preview = Default::default();
                             }
 
-                            self.send_message(WidgetMessage::mouse_up(
-                                self.picked_node,
-                                MessageDirection::FromWidget,
-                                self.cursor_position,
-                                button,
-                            ));
+                            if self.is_interactive(self.picked_node) {
+                                self.send_message(WidgetMessage::mouse_up(
+                                    self.picked_node,
+                                    MessageDirection::FromWidget,
+                                    self.cursor_position,
+                                    button,
+                                ));
+                            }
                             event_processed = true;
                         }
                     }
                 }
             }
             OsEvent::CursorMoved { position } => {
-                self.cursor_position = *position;
+                // Cursor position is given in physical pixels, but widgets use logical units.
+                self.cursor_position = position.scale(1.0 / self.scale);
                 self.picked_node = self.hit_test(self.cursor_position);
 
                 if !self.drag_context.is_dragging
                     && self.mouse_state.left == ButtonState::Pressed
                     && self.picked_node.is_some()
                     && self.drag_context.drag_node.is_some()
-                    && (self.drag_context.click_pos - *position).norm() > 5.0
+                    && (self.drag_context.click_pos - self.cursor_position).norm() > 5.0
                 {
                     self.drag_context.drag_preview =
                         self.copy_node_with_limit(self.drag_context.drag_node, Some(30));
                     self.nodes[self.drag_context.drag_preview].set_opacity(Some(0.5));
+                    self.link_nodes_internal(
+                        self.drag_context.drag_preview,
+                        self.overlay_layer,
+                        false,
+                    );
 
                     self.drag_context.is_dragging = true;
 
@@ -1724,7 +2294,7 @@ impl UserInterface {
                     self.send_message(WidgetMessage::desired_position(
                         self.drag_context.drag_preview,
                         MessageDirection::ToWidget,
-                        *position,
+                        self.cursor_position,
                     ));
                 }
 
@@ -1771,12 +2341,14 @@ impl UserInterface {
             }
             OsEvent::MouseWheel(_, y) => {
                 if self.picked_node.is_some() {
-                    self.send_message(WidgetMessage::mouse_wheel(
-                        self.picked_node,
-                        MessageDirection::FromWidget,
-                        self.cursor_position,
-                        *y,
-                    ));
+                    if self.is_interactive(self.picked_node) {
+                        self.send_message(WidgetMessage::mouse_wheel(
+                            self.picked_node,
+                            MessageDirection::FromWidget,
+                            self.cursor_position,
+                            *y,
+                        ));
+                    }
 
                     event_processed = true;
                 }
@@ -1814,6 +2386,110 @@ impl UserInterface {
                 // TODO: Is message needed for focused node?
                 self.keyboard_modifiers = modifiers;
             }
+            &OsEvent::Touch {
+                id,
+                phase,
+                position,
+            } => {
+                self.touch_context.active = true;
+
+                let pos = position.scale(1.0 / self.scale);
+                let picked_node = self.hit_test(pos);
+                let interactive = self.is_interactive(picked_node);
+
+                match phase {
+                    TouchPhase::Started => {
+                        self.touch_context
+                            .touches
+                            .push(TouchPoint { id, position: pos });
+
+                        if interactive {
+                            self.send_message(WidgetMessage::touch_started(
+                                picked_node,
+                                MessageDirection::FromWidget,
+                                pos,
+                                id,
+                            ));
+                        }
+
+                        if self.touch_context.touches.len() == 1 {
+                            self.touch_context.primary = Some(id);
+                            self.process_os_event(&OsEvent::CursorMoved { position });
+                            self.process_os_event(&OsEvent::MouseInput {
+                                button: MouseButton::Left,
+                                state: ButtonState::Pressed,
+                            });
+                        } else {
+                            // Multi-finger gestures must not click or drag anything.
+                            self.cancel_touch_press();
+                            self.touch_context.pinch = self.touch_context.pinch_state();
+                        }
+                    }
+                    TouchPhase::Moved => {
+                        if let Some(touch) =
+                            self.touch_context.touches.iter_mut().find(|t| t.id == id)
+                        {
+                            touch.position = pos;
+                        }
+
+                        if interactive {
+                            self.send_message(WidgetMessage::touch_moved(
+                                picked_node,
+                                MessageDirection::FromWidget,
+                                pos,
+                                id,
+                            ));
+                        }
+
+                        if self.touch_context.primary == Some(id) {
+                            self.process_os_event(&OsEvent::CursorMoved { position });
+                        }
+
+                        if let (Some((distance, center)), Some((new_distance, new_center))) =
+                            (self.touch_context.pinch, self.touch_context.pinch_state())
+                        {
+                            let target = self.hit_test(new_center);
+                            if distance > f32::EPSILON && self.is_interactive(target) {
+                                self.send_message(WidgetMessage::pinch(
+                                    target,
+                                    MessageDirection::FromWidget,
+                                    new_center,
+                                    new_distance / distance,
+                                    new_center - center,
+                                ));
+                            }
+                            self.touch_context.pinch = Some((new_distance, new_center));
+                        }
+                    }
+                    TouchPhase::Ended | TouchPhase::Cancelled => {
+                        self.touch_context.touches.retain(|t| t.id != id);
+
+                        if interactive {
+                            self.send_message(WidgetMessage::touch_ended(
+                                picked_node,
+                                MessageDirection::FromWidget,
+                                pos,
+                                id,
+                            ));
+                        }
+
+                        if self.touch_context.primary == Some(id) {
+                            self.process_os_event(&OsEvent::CursorMoved { position });
+                            self.process_os_event(&OsEvent::MouseInput {
+                                button: MouseButton::Left,
+                                state: ButtonState::Released,
+                            });
+                            self.touch_context.primary = None;
+                        }
+
+                        self.touch_context.pinch = self.touch_context.pinch_state();
+                    }
+                }
+
+                if picked_node.is_some() {
+                    event_processed = true;
+                }
+            }
         }
 
         self.prev_picked_node = self.picked_node;
@@ -1909,6 +2585,7 @@ impl UserInterface {
                 self.keyboard_focus_node = Handle::NONE;
             }
             self.remove_picking_restriction(handle);
+            self.layers.retain(|l| l.root != handle);
 
             let node_ref = self.nodes.borrow(handle);
             stack.extend_from_slice(node_ref.children());
@@ -1944,7 +2621,23 @@ impl UserInterface {
         assert_ne!(child_handle, parent_handle);
         self.unlink_node_internal(child_handle);
         self.nodes[child_handle].set_parent(parent_handle);
-        self.nodes[parent_handle].add_child(child_handle, in_front);
+
+        // Insert the child at the beginning or at the end of the siblings with the same drawing order
+        // key, so children of every node are always sorted in drawing order.
+        let key = self.draw_order_key(child_handle);
+        let children = self.nodes[parent_handle].children();
+        let index = children
+            .iter()
+            .position(|&sibling| {
+                let sibling_key = self.draw_order_key(sibling);
+                if in_front {
+                    sibling_key >= key
+                } else {
+                    sibling_key > key
+                }
+            })
+            .unwrap_or(children.len());
+        self.nodes[parent_handle].insert_child(index, child_handle);
     }
 
     /// Unlinks specified node from its parent, so node will become root.
@@ -2068,12 +2761,57 @@ impl UserInterface {
 #[cfg(test)]
 mod test {
     use crate::{
+        animation::WidgetAnimationMessage,
         border::BorderBuilder,
-        core::algebra::Vector2,
-        message::MessageDirection,
-        widget::{WidgetBuilder, WidgetMessage},
-        UserInterface,
+        core::{algebra::Vector2, pool::Handle},
+        draw::ClipShape,
+        message::{
+            ButtonState, MessageDirection, MouseButton, OsEvent, RoutingStrategy, UiMessage,
+        },
+        popup::PopupBuilder,
+        widget::{Widget, WidgetBuilder, WidgetMessage},
+        Control, UiNode, UserInterface, OVERLAY_LAYER_NAME,
     };
+    use std::{
+        any::{Any, TypeId},
+        cell::RefCell,
+        ops::{Deref, DerefMut},
+        rc::Rc,
+    };
+
+    #[derive(Debug, PartialEq)]
+    struct TestMessage(u32);
+
+    // Records every test message that reaches the widget.
+    #[derive(Clone)]
+    struct Recorder {
+        widget: Widget,
+        log: Rc<RefCell<Vec<(Handle<UiNode>, u32)>>>,
+        mark_handled: bool,
+    }
+
+    crate::define_widget_deref!(Recorder);
+
+    impl Control for Recorder {
+        fn query_component(&self, type_id: TypeId) -> Option<&dyn Any> {
+            if type_id == TypeId::of::<Self>() {
+                Some(self)
+            } else {
+                None
+            }
+        }
+
+        fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
+            self.widget.handle_routed_message(ui, message);
+
+            if let Some(TestMessage(id)) = message.data::<TestMessage>() {
+                self.log.borrow_mut().push((self.handle(), *id));
+                if self.mark_handled {
+                    message.set_handled(true);
+                }
+            }
+        }
+    }
 
     #[test]
     fn center() {
@@ -2094,4 +2832,349 @@ mod test {
         let actual_position = ui.node(widget).actual_local_position();
         assert_eq!(actual_position, expected_position);
     }
+
+    #[test]
+    fn scale() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        ui.set_scale(2.0);
+        let widget = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_desired_position(Vector2::new(300.0, 300.0))
+                .with_width(100.0)
+                .with_height(100.0),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0);
+        ui.draw();
+
+        // Layout is in logical units.
+        assert_eq!(ui.screen_size(), Vector2::new(500.0, 500.0));
+
+        // Cursor position is physical, so widget at (300; 300) is under (700; 700) pixel.
+        ui.process_os_event(&OsEvent::CursorMoved {
+            position: Vector2::new(700.0, 700.0),
+        });
+        assert_eq!(ui.cursor_position(), Vector2::new(350.0, 350.0));
+        assert_eq!(ui.hit_test(ui.cursor_position()), widget);
+    }
+
+    #[test]
+    fn drag_with_scale() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        ui.set_scale(2.0);
+        BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_desired_position(Vector2::new(300.0, 300.0))
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_allow_drag(true),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0);
+        ui.draw();
+
+        let move_cursor = |ui: &mut UserInterface, position: Vector2<f32>| {
+            ui.process_os_event(&OsEvent::CursorMoved { position });
+            while ui.poll_message().is_some() {}
+        };
+
+        move_cursor(&mut ui, Vector2::new(700.0, 700.0));
+        ui.process_os_event(&OsEvent::MouseInput {
+            button: MouseButton::Left,
+            state: ButtonState::Pressed,
+        });
+        while ui.poll_message().is_some() {}
+
+        // 4 pixels are 2 units, which is less than drag threshold.
+        move_cursor(&mut ui, Vector2::new(704.0, 704.0));
+        assert!(!ui.drag_context.is_dragging);
+
+        move_cursor(&mut ui, Vector2::new(720.0, 720.0));
+        assert!(ui.drag_context.is_dragging);
+
+        // Preview follows the cursor in logical units.
+        let preview = ui.drag_context.drag_preview;
+        assert_eq!(
+            ui.node(preview).desired_local_position(),
+            Vector2::new(360.0, 360.0)
+        );
+    }
+
+    #[test]
+    fn clip_shape() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let ctx = &mut ui.build_ctx();
+        let child = BorderBuilder::new(
+            WidgetBuilder::new().with_clip_shape(ClipShape::RoundedRect { radius: 10.0 }),
+        )
+        .build(ctx);
+        let parent = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_clip_shape(ClipShape::Circle)
+                .with_child(child),
+        )
+        .build(ctx);
+        ui.update(screen_size, 0.0);
+
+        ui.draw();
+        // Commands of the child are clipped by both shapes.
+        let command_index = *ui.node(child).command_indices.borrow().last().unwrap();
+        let clipping_geometry = ui.drawing_context.get_commands()[command_index]
+            .clipping_geometry
+            .as_ref()
+            .unwrap();
+        assert_eq!(clipping_geometry.shape_count(), 2);
+
+        // Corner is outside of the circle.
+        assert!(ui.hit_test(Vector2::new(5.0, 5.0)).is_none());
+        assert_eq!(ui.hit_test(Vector2::new(50.0, 50.0)), child);
+        assert!(ui.node(parent).clip_shape().is_some());
+    }
+
+    #[test]
+    fn fade_out() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let widget = BorderBuilder::new(WidgetBuilder::new().with_width(100.0).with_height(100.0))
+            .build(&mut ui.build_ctx());
+        ui.set_hit_test_opacity_threshold(0.5);
+        ui.fade_out(widget, 1.0);
+
+        ui.update(screen_size, 0.25);
+        ui.update(screen_size, 0.5);
+        assert_eq!(ui.node(widget).opacity(), Some(0.25));
+        ui.draw();
+        assert!(ui.hit_test(Vector2::new(50.0, 50.0)).is_none());
+
+        ui.update(screen_size, 0.5);
+        assert!(!ui.is_animating(widget));
+        assert!(!ui.node(widget).visibility());
+        let mut completed = false;
+        while let Some(message) = ui.poll_message() {
+            if let Some(WidgetAnimationMessage::Completed(name)) =
+                message.data::<WidgetAnimationMessage>()
+            {
+                assert_eq!(message.destination(), widget);
+                assert_eq!(name, "FadeOut");
+                completed = true;
+            }
+        }
+        assert!(completed);
+    }
+
+    #[test]
+    fn test_message_routing() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut add_recorder =
+            |ui: &mut UserInterface, children: &[Handle<UiNode>], mark_handled| {
+                ui.add_node(UiNode::new(Recorder {
+                    widget: WidgetBuilder::new()
+                        .with_children(children.iter().cloned())
+                        .build(),
+                    log: log.clone(),
+                    mark_handled,
+                }))
+            };
+        let leaf = add_recorder(&mut ui, &[], false);
+        let middle = add_recorder(&mut ui, &[leaf], true);
+        let root = add_recorder(&mut ui, &[middle], false);
+
+        let send = |ui: &mut UserInterface, id, destination, routing_strategy| {
+            ui.send_message(
+                UiMessage::with_data(TestMessage(id))
+                    .with_destination(destination)
+                    .with_routing_strategy(routing_strategy),
+            );
+        };
+
+        // Default routing ignores handled flag, so existing behaviour is preserved.
+        send(&mut ui, 0, leaf, RoutingStrategy::BubbleUp);
+        send(&mut ui, 1, leaf, RoutingStrategy::BubbleUntilHandled);
+        send(&mut ui, 2, leaf, RoutingStrategy::Direct);
+        send(&mut ui, 3, root, RoutingStrategy::BubbleUp);
+
+        let subscription = ui.subscribe(middle);
+
+        // Messages are processed one-by-one in FIFO order, bubbling is done before the message
+        // is delivered to subscriptions and returned to the user.
+        let mut expected_len = [3, 5, 6, 7].iter();
+        while let Some(message) = ui.poll_message() {
+            if message.data::<TestMessage>().is_some() {
+                assert_eq!(log.borrow().len(), *expected_len.next().unwrap());
+            }
+        }
+        assert_eq!(
+            *log.borrow(),
+            vec![
+                (leaf, 0),
+                (middle, 0),
+                (root, 0),
+                (leaf, 1),
+                (middle, 1),
+                (leaf, 2),
+                (root, 3)
+            ]
+        );
+
+        // Subscription receives only messages of the subtree of middle widget.
+        let mut received = Vec::new();
+        while let Some(message) = subscription.poll() {
+            if let Some(TestMessage(id)) = message.data::<TestMessage>() {
+                received.push(*id);
+            }
+        }
+        assert_eq!(received, vec![0, 1, 2]);
+
+        // Dropped subscription is forgotten.
+        drop(subscription);
+        send(&mut ui, 4, leaf, RoutingStrategy::Direct);
+        while ui.poll_message().is_some() {}
+        assert!(ui.subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_non_interactive_widget() {
+        let screen_size = Vector2::new(100.0, 100.0);
+        let mut ui = UserInterface::new(screen_size);
+        let below = BorderBuilder::new(WidgetBuilder::new().with_width(100.0).with_height(100.0))
+            .build(&mut ui.build_ctx());
+        let overlay = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_interactive(false),
+        )
+        .build(&mut ui.build_ctx());
+        ui.update(screen_size, 0.0);
+        ui.draw();
+
+        let click = |ui: &mut UserInterface| {
+            ui.process_os_event(&OsEvent::CursorMoved {
+                position: Vector2::new(50.0, 50.0),
+            });
+            let processed = ui.process_os_event(&OsEvent::MouseInput {
+                button: MouseButton::Left,
+                state: ButtonState::Pressed,
+            });
+            let mut mouse_down = Handle::NONE;
+            while let Some(message) = ui.poll_message() {
+                if let Some(WidgetMessage::MouseDown { .. }) = message.data::<WidgetMessage>() {
+                    mouse_down = message.destination();
+                }
+            }
+            (processed, mouse_down)
+        };
+
+        // Overlay blocks the input, but does not receive it.
+        assert_eq!(ui.hit_test(Vector2::new(50.0, 50.0)), overlay);
+        assert_eq!(click(&mut ui), (true, Handle::NONE));
+        assert!(ui.keyboard_focus_node.is_none());
+
+        ui.send_message(WidgetMessage::interactive(
+            overlay,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        while ui.poll_message().is_some() {}
+        assert_eq!(click(&mut ui), (true, overlay));
+        assert_eq!(ui.keyboard_focus_node, overlay);
+        assert_ne!(ui.keyboard_focus_node, below);
+    }
+
+    fn make_full_screen_border(ui: &mut UserInterface, z_index: usize) -> Handle<UiNode> {
+        BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_z_index(z_index),
+        )
+        .build(&mut ui.build_ctx())
+    }
+
+    fn pick_center(ui: &mut UserInterface) -> Handle<UiNode> {
+        ui.update(Vector2::new(100.0, 100.0), 0.0);
+        ui.draw();
+        ui.hit_test(Vector2::new(50.0, 50.0))
+    }
+
+    #[test]
+    fn test_z_index() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+        let a = make_full_screen_border(&mut ui, 2);
+        let b = make_full_screen_border(&mut ui, 0);
+        let c = make_full_screen_border(&mut ui, 1);
+        let overlay_layer = ui.overlay_layer();
+
+        assert_eq!(ui.node(ui.root()).children(), &[b, c, a, overlay_layer]);
+        assert_eq!(pick_center(&mut ui), a);
+
+        // Changing z index at runtime re-sorts siblings.
+        ui.send_message(WidgetMessage::z_index(b, MessageDirection::ToWidget, 3));
+        while ui.poll_message().is_some() {}
+        assert_eq!(ui.node(b).z_index(), 3);
+        assert_eq!(ui.node(ui.root()).children(), &[c, a, b, overlay_layer]);
+        assert_eq!(pick_center(&mut ui), b);
+
+        // Topmost node is moved to the end of the siblings with the same z index only.
+        let d = make_full_screen_border(&mut ui, 1);
+        assert_eq!(ui.node(ui.root()).children(), &[c, d, a, b, overlay_layer]);
+        ui.send_message(WidgetMessage::topmost(c, MessageDirection::ToWidget));
+        while ui.poll_message().is_some() {}
+        assert_eq!(ui.node(ui.root()).children(), &[d, c, a, b, overlay_layer]);
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+        let debug = ui.create_layer("debug", 10);
+        let hud = ui.create_layer("hud", 0);
+        let overlay_layer = ui.overlay_layer();
+        assert_eq!(ui.layer(OVERLAY_LAYER_NAME), overlay_layer);
+        assert_eq!(ui.layer("debug"), debug);
+        assert_eq!(ui.create_layer("debug", 10), debug);
+
+        let console = make_full_screen_border(&mut ui, 0);
+        ui.build_ctx().link(console, debug);
+        let health_bar = make_full_screen_border(&mut ui, 0);
+        ui.build_ctx().link(health_bar, hud);
+
+        // Regular widgets are below layers no matter when they were created.
+        let regular = make_full_screen_border(&mut ui, 100);
+        assert_eq!(
+            ui.node(ui.root()).children(),
+            &[regular, hud, debug, overlay_layer]
+        );
+        assert_eq!(ui.node(debug).actual_size(), Vector2::new(0.0, 0.0));
+        assert_eq!(pick_center(&mut ui), console);
+        assert_eq!(ui.node(debug).actual_size(), Vector2::new(100.0, 100.0));
+
+        ui.set_layer_priority("hud", 20);
+        assert_eq!(ui.layer_priority("hud"), Some(20));
+        assert_eq!(
+            ui.node(ui.root()).children(),
+            &[regular, debug, hud, overlay_layer]
+        );
+        assert_eq!(pick_center(&mut ui), health_bar);
+
+        // Top-level overlay escapes its parent's layer.
+        let marker = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(100.0)
+                .with_height(100.0)
+                .with_draw_on_top(true),
+        )
+        .build(&mut ui.build_ctx());
+        ui.build_ctx().link(marker, regular);
+        assert_eq!(pick_center(&mut ui), marker);
+
+        // Popups are put in the overlay layer.
+        let popup = PopupBuilder::new(WidgetBuilder::new()).build(&mut ui.build_ctx());
+        assert_eq!(ui.node(popup).parent(), overlay_layer);
+    }
 }
@@ -0,0 +1,240 @@
+//! Simple markup for styled text, it is converted to a list of spans for
+//! [`crate::formatted_text::FormattedText::set_spans`].
+//!
+//! Supported tags:
+//!
+//! - `[color=#rrggbb]...[/color]` or `[color=#rrggbbaa]...[/color]` - color of the text.
+//! - `[b]...[/b]` - bold text.
+//! - `[size=N]...[/size]` - size of the font in pixels.
+//! - `[icon=name]` - an inline image, see [`crate::UserInterface::set_inline_icon`].
+//!
+//! Tags could be nested, closing tag must match the last opened tag. Tags that are not closed
+//! are applied to the end of the text. Unknown or malformed tags, closing tags without matching
+//! opening tag and unknown icons are left in the text as is.
+//!
+//! ```rust
+//! use rg3d_ui::{core::color::Color, markup::parse_markup};
+//!
+//! let spans = parse_markup("Touching it is [color=#ff0000]danger[/color]!", &Default::default());
+//! assert_eq!(spans.len(), 3);
+//! assert_eq!(spans[1].text, "danger");
+//! assert_eq!(spans[1].style.color, Some(Color::opaque(255, 0, 0)));
+//! ```
+
+use crate::{
+    core::color::Color,
+    formatted_text::{InlineImage, TextSpan, TextStyle},
+};
+use fxhash::FxHashMap;
+
+/// Named inline images that could be used by `[icon=name]` tag.
+pub type InlineIcons = FxHashMap<String, InlineImage>;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum TagKind {
+    Color,
+    Bold,
+    Size,
+}
+
+impl TagKind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "color" => Some(Self::Color),
+            "b" => Some(Self::Bold),
+            "size" => Some(Self::Size),
+            _ => None,
+        }
+    }
+}
+
+enum Tag<'a> {
+    Open(TagKind, TextStyle),
+    Close(TagKind),
+    Icon(&'a InlineImage),
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        6 => Some(Color::opaque(component(0)?, component(2)?, component(4)?)),
+        8 => Some(Color::from_rgba(
+            component(0)?,
+            component(2)?,
+            component(4)?,
+            component(6)?,
+        )),
+        _ => None,
+    }
+}
+
+// Returns `None` if the tag is unknown or malformed, so it must be shown as is.
+fn parse_tag<'a>(tag: &str, current: &TextStyle, icons: &'a InlineIcons) -> Option<Tag<'a>> {
+    if let Some(name) = tag.strip_prefix('/') {
+        return TagKind::from_name(name).map(Tag::Close);
+    }
+
+    let (name, value) = match tag.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (tag, None),
+    };
+
+    let mut style = current.clone();
+    match (name, value) {
+        ("color", Some(value)) => {
+            style.color = Some(parse_color(value)?);
+            Some(Tag::Open(TagKind::Color, style))
+        }
+        ("b", None) => {
+            style.bold = true;
+            Some(Tag::Open(TagKind::Bold, style))
+        }
+        ("size", Some(value)) => {
+            let size = value.parse::<f32>().ok().filter(|s| *s > 0.0)?;
+            style.font_size = Some(size);
+            Some(Tag::Open(TagKind::Size, style))
+        }
+        ("icon", Some(value)) => icons.get(value).map(Tag::Icon),
+        _ => None,
+    }
+}
+
+fn flush(spans: &mut Vec<TextSpan>, text: &mut String, style: &TextStyle) {
+    if !text.is_empty() {
+        spans.push(TextSpan::new(&text).with_style(style.clone()));
+        text.clear();
+    }
+}
+
+/// Converts markup to a list of spans, see module docs for more info.
+pub fn parse_markup(markup: &str, icons: &InlineIcons) -> Vec<TextSpan> {
+    let mut spans = Vec::new();
+    let mut stack: Vec<(TagKind, TextStyle)> = Vec::new();
+    let mut text = String::new();
+    let mut rest = markup;
+
+    while let Some(open) = rest.find('[') {
+        text.push_str(&rest[..open]);
+        rest = &rest[open..];
+
+        let current = stack.last().map(|(_, s)| s.clone()).unwrap_or_default();
+        let tag = rest[1..]
+            .find(']')
+            .and_then(|close| parse_tag(&rest[1..close + 1], &current, icons).map(|t| (t, close)));
+
+        match tag {
+            Some((tag, close)) => {
+                let valid = match tag {
+                    Tag::Open(kind, style) => {
+                        flush(&mut spans, &mut text, &current);
+                        stack.push((kind, style));
+                        true
+                    }
+                    Tag::Close(kind) => {
+                        if stack.last().map(|(k, _)| *k) == Some(kind) {
+                            flush(&mut spans, &mut text, &current);
+                            stack.pop();
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    Tag::Icon(image) => {
+                        flush(&mut spans, &mut text, &current);
+                        let mut span = TextSpan::image(image.clone());
+                        span.style.font_size = current.font_size;
+                        spans.push(span);
+                        true
+                    }
+                };
+
+                if valid {
+                    rest = &rest[close + 2..];
+                } else {
+                    text.push('[');
+                    rest = &rest[1..];
+                }
+            }
+            None => {
+                // Not a tag, show the bracket as is.
+                text.push('[');
+                rest = &rest[1..];
+            }
+        }
+    }
+    text.push_str(rest);
+
+    let current = stack.last().map(|(_, s)| s.clone()).unwrap_or_default();
+    flush(&mut spans, &mut text, &current);
+
+    spans
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{color::Color, math::Rect},
+        draw::SharedTexture,
+        formatted_text::{InlineImage, TextSpan, INLINE_IMAGE_CHAR},
+        markup::{parse_markup, InlineIcons},
+    };
+    use std::sync::Arc;
+
+    fn texts(spans: &[TextSpan]) -> Vec<&str> {
+        spans.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_nested_tags() {
+        let spans = parse_markup(
+            "a [color=#00ff0080]b [b]c[/b] [size=20]d[/size][/color] e",
+            &Default::default(),
+        );
+        assert_eq!(texts(&spans), ["a ", "b ", "c", " ", "d", " e"]);
+        let green = Some(Color::from_rgba(0, 255, 0, 128));
+        assert_eq!(spans[0].style, Default::default());
+        assert_eq!(spans[1].style.color, green);
+        assert!(spans[2].style.bold);
+        assert_eq!(spans[2].style.color, green);
+        assert!(!spans[3].style.bold);
+        assert_eq!(spans[4].style.font_size, Some(20.0));
+        assert_eq!(spans[4].style.color, green);
+        assert_eq!(spans[5].style, Default::default());
+    }
+
+    #[test]
+    fn test_unknown_tags_are_literal() {
+        let spans = parse_markup(
+            "[foo]x[/b][color=red]y[/color][size=-1]z[icon=sword][",
+            &Default::default(),
+        );
+        assert_eq!(
+            texts(&spans),
+            ["[foo]x[/b][color=red]y[/color][size=-1]z[icon=sword]["]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_tag() {
+        let spans = parse_markup("a[b]b", &Default::default());
+        assert_eq!(texts(&spans), ["a", "b"]);
+        assert!(spans[1].style.bold);
+    }
+
+    #[test]
+    fn test_icon() {
+        let mut icons = InlineIcons::default();
+        let image = InlineImage::new(SharedTexture(Arc::new(0u32)))
+            .with_uv_rect(Rect::new(0.0, 0.0, 0.5, 0.5));
+        icons.insert("sword".to_owned(), image.clone());
+
+        let spans = parse_markup("Take [icon=sword]!", &icons);
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[1].text, INLINE_IMAGE_CHAR.to_string());
+        assert_eq!(spans[1].style.image, Some(image));
+    }
+}
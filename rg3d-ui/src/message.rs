@@ -12,6 +12,22 @@
 //! means that it can be sent either from internals of library or from user code.
 //! However [WidgetMessage::GotFocus](enum.WidgetMessage.html) has "Direction: From UI" which means that only
 //! internal library code can send such messages without a risk of breaking anything.
+//!
+//! # Routing
+//!
+//! Messages are processed strictly in FIFO order, one by one, in
+//! [UserInterface::poll_message](crate::UserInterface::poll_message). Each message is processed in
+//! the following order:
+//!
+//! 1. Widgets that preview messages receive the message first (regardless of routing strategy).
+//! 2. The message is routed to widgets of the tree according to its [RoutingStrategy]. By default
+//!    it bubbles from its destination up to the root of the tree.
+//! 3. The message is delivered to every [subscription](crate::UserInterface::subscribe) whose
+//!    subtree contains destination of the message.
+//! 4. The message is returned from `poll_message` to user code.
+//!
+//! Messages sent by widgets during processing of a message are put at the end of the queue, so they
+//! will be processed after every message that was sent before them.
 
 use crate::{
     core::{algebra::Vector2, pool::Handle},
@@ -35,7 +51,8 @@ macro_rules! define_constructor {
                 destination,
                 direction,
                 perform_layout: std::cell::Cell::new($perform_layout),
-                flags: 0
+                flags: 0,
+                routing_strategy: $crate::message::RoutingStrategy::BubbleUp,
             }
         }
     };
@@ -48,7 +65,8 @@ macro_rules! define_constructor {
                 destination,
                 direction,
                 perform_layout: std::cell::Cell::new($perform_layout),
-                flags: 0
+                flags: 0,
+                routing_strategy: $crate::message::RoutingStrategy::BubbleUp,
             }
         }
     };
@@ -61,7 +79,8 @@ macro_rules! define_constructor {
                 destination,
                 direction,
                 perform_layout: std::cell::Cell::new($perform_layout),
-                flags: 0
+                flags: 0,
+                routing_strategy: $crate::message::RoutingStrategy::BubbleUp,
             }
         }
     }
@@ -124,6 +143,29 @@ impl MessageDirection {
     }
 }
 
+/// Defines how a message is routed through the widget tree. Routing strategy does not affect
+/// widgets that preview messages, they receive every message.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RoutingStrategy {
+    /// The message goes from its destination widget up to the root of the tree, every widget in
+    /// the chain receives it regardless of handled flag. This is default strategy.
+    BubbleUp,
+
+    /// Same as [RoutingStrategy::BubbleUp], but bubbling stops as soon as a widget marks the message
+    /// as handled (see [UiMessage::set_handled]). Handled flag is checked after each widget, so the
+    /// destination widget always receives the message.
+    BubbleUntilHandled,
+
+    /// The message is delivered to its destination widget only.
+    Direct,
+}
+
+impl Default for RoutingStrategy {
+    fn default() -> Self {
+        Self::BubbleUp
+    }
+}
+
 pub trait MessageData: 'static + Debug + Any {
     fn as_any(&self) -> &dyn Any;
 
@@ -182,6 +224,10 @@ pub struct UiMessage {
 
     /// A custom user flags.
     pub flags: u64,
+
+    /// Defines how the message is routed through the widget tree, see [RoutingStrategy] docs for
+    /// more info.
+    pub routing_strategy: RoutingStrategy,
 }
 
 impl PartialEq for UiMessage {
@@ -192,6 +238,7 @@ impl PartialEq for UiMessage {
             && self.direction == other.direction
             && self.perform_layout == other.perform_layout
             && self.flags == other.flags
+            && self.routing_strategy == other.routing_strategy
     }
 }
 
@@ -204,6 +251,7 @@ impl UiMessage {
             direction: MessageDirection::ToWidget,
             perform_layout: Cell::new(false),
             flags: 0,
+            routing_strategy: RoutingStrategy::BubbleUp,
         }
     }
 
@@ -222,6 +270,12 @@ impl UiMessage {
         self
     }
 
+    /// Sets routing strategy of the message, see [RoutingStrategy] docs for more info.
+    pub fn with_routing_strategy(mut self, routing_strategy: RoutingStrategy) -> Self {
+        self.routing_strategy = routing_strategy;
+        self
+    }
+
     /// Creates a new copy of the message with reversed direction. Typical use case is
     /// to re-send messages to create "response" in widget. For example you have a float
     /// input field and it has Value message. When the input field receives Value message
@@ -240,6 +294,7 @@ impl UiMessage {
             direction: self.direction.reverse(),
             perform_layout: self.perform_layout.clone(),
             flags: self.flags,
+            routing_strategy: self.routing_strategy,
         }
     }
 
@@ -274,6 +329,10 @@ impl UiMessage {
     pub fn has_flags(&self, flags: u64) -> bool {
         self.flags & flags != 0
     }
+
+    pub fn routing_strategy(&self) -> RoutingStrategy {
+        self.routing_strategy
+    }
 }
 
 #[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
@@ -290,6 +349,15 @@ pub enum MouseButton {
     Other(u16),
 }
 
+/// A phase of a touch, see [`OsEvent::Touch`].
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    Cancelled,
+}
+
 pub enum OsEvent {
     MouseInput {
         button: MouseButton,
@@ -305,6 +373,13 @@ pub enum OsEvent {
     Character(char),
     KeyboardModifiers(KeyboardModifiers),
     MouseWheel(f32, f32),
+    Touch {
+        /// Unique id of the finger.
+        id: u64,
+        phase: TouchPhase,
+        /// Position in physical pixels, the same as for [`OsEvent::CursorMoved`].
+        position: Vector2<f32>,
+    },
 }
 
 #[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Default)]
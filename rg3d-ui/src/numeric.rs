@@ -3,6 +3,7 @@ use crate::{
     brush::Brush,
     button::{ButtonBuilder, ButtonMessage},
     core::{
+        algebra::Vector2,
         color::Color,
         num_traits::NumCast,
         num_traits::NumOps,
@@ -13,7 +14,7 @@ use crate::{
     define_constructor,
     formatted_text::WrapMode,
     grid::{Column, GridBuilder, Row},
-    message::{KeyCode, MessageDirection, UiMessage},
+    message::{KeyCode, MessageDirection, MouseButton, UiMessage},
     text_box::{TextBox, TextBoxBuilder, TextBoxMessage},
     utils::{make_arrow, ArrowDirection},
     widget::{Widget, WidgetBuilder, WidgetMessage},
@@ -72,6 +73,23 @@ impl<T: NumericType> NumericUpDownMessage<T> {
     define_constructor!(NumericUpDownMessage:Value => fn value(T), layout: false);
 }
 
+/// Distance (in pixels) the cursor must travel with pressed button before a click on the text
+/// field turns into dragging.
+const DRAG_THRESHOLD: f32 = 3.0;
+
+/// Distance (in pixels) of dragging that changes the value by one step.
+const DRAG_PIXELS_PER_STEP: f32 = 4.0;
+
+#[derive(Clone, Debug)]
+struct DragContext {
+    start_x: f32,
+    last_x: f32,
+    // Unquantized value, it accumulates sub-step changes when Ctrl is held or the value is
+    // integer.
+    value: f64,
+    dragging: bool,
+}
+
 #[derive(Clone)]
 pub struct NumericUpDown<T: NumericType> {
     widget: Widget,
@@ -83,6 +101,7 @@ pub struct NumericUpDown<T: NumericType> {
     min_value: T,
     max_value: T,
     precision: usize,
+    drag_context: Option<DragContext>,
 }
 
 impl<T: NumericType> Deref for NumericUpDown<T> {
@@ -104,19 +123,108 @@ impl<T: NumericType> NumericUpDown<T> {
         clamp(value, self.min_value, self.max_value)
     }
 
+    fn sync_text(&self, ui: &mut UserInterface) {
+        ui.send_message(TextBoxMessage::text(
+            self.field,
+            MessageDirection::ToWidget,
+            format!("{:.1$}", self.value, self.precision),
+        ));
+    }
+
     fn try_parse_value(&mut self, ui: &mut UserInterface) {
         // Parse input only when focus is lost from text field.
         if let Some(field) = ui.node(self.field).cast::<TextBox>() {
-            if let Ok(value) = field.text().parse::<T>() {
-                let value = self.clamp_value(value);
-                ui.send_message(NumericUpDownMessage::value(
-                    self.handle(),
-                    MessageDirection::ToWidget,
-                    value,
-                ));
+            match field.text().trim().parse::<T>() {
+                Ok(value) => {
+                    let value = self.clamp_value(value);
+                    if value == self.value {
+                        // Typed value may differ in formatting or be out of bounds.
+                        self.sync_text(ui);
+                    } else {
+                        ui.send_message(NumericUpDownMessage::value(
+                            self.handle(),
+                            MessageDirection::ToWidget,
+                            value,
+                        ));
+                    }
+                }
+                Err(_) => {
+                    // Invalid input is discarded.
+                    self.sync_text(ui);
+                }
             }
         }
     }
+
+    /// Returns `true` if the value is being changed by dragging the mouse over the text field.
+    pub fn is_dragging(&self) -> bool {
+        self.drag_context
+            .as_ref()
+            .map_or(false, |context| context.dragging)
+    }
+
+    pub fn value(&self) -> T {
+        self.value
+    }
+
+    fn drag(&mut self, ui: &mut UserInterface, pos: Vector2<f32>) {
+        let modifiers = ui.keyboard_modifiers();
+        let handle = self.handle();
+        let (min, max) = (
+            self.min_value.to_f64().unwrap_or(f64::MIN),
+            self.max_value.to_f64().unwrap_or(f64::MAX),
+        );
+        let step = self.step.to_f64().unwrap_or(1.0);
+        let is_integer = is_integer::<T>();
+
+        let context = match self.drag_context.as_mut() {
+            Some(context) => context,
+            None => return,
+        };
+
+        if !context.dragging {
+            if (pos.x - context.start_x).abs() < DRAG_THRESHOLD {
+                return;
+            }
+            context.dragging = true;
+            context.last_x = pos.x;
+            // Take the mouse from the text field, so it won't select text while dragging.
+            ui.capture_mouse(handle);
+            return;
+        }
+
+        // Ctrl - fine steps, Shift - coarse steps. Multiplier is applied per movement, so
+        // pressing or releasing a modifier during dragging does not make the value jump.
+        let multiplier = if modifiers.control {
+            0.1
+        } else if modifiers.shift {
+            10.0
+        } else {
+            1.0
+        };
+        let delta = (pos.x - context.last_x) / DRAG_PIXELS_PER_STEP;
+        context.last_x = pos.x;
+        context.value = (context.value + delta as f64 * step * multiplier)
+            .max(min)
+            .min(max);
+
+        let value = if is_integer {
+            context.value.round()
+        } else {
+            context.value
+        };
+        if let Some(value) = T::from(value) {
+            ui.send_message(NumericUpDownMessage::value(
+                handle,
+                MessageDirection::ToWidget,
+                value,
+            ));
+        }
+    }
+}
+
+fn is_integer<T: NumericType>() -> bool {
+    T::from(0.5).and_then(|half: T| half.to_f64()) != Some(0.5)
 }
 
 fn saturating_sub<T: NumericType>(a: T, b: T) -> T {
@@ -162,6 +270,35 @@ impl<T: NumericType> Control for NumericUpDown<T> {
                     WidgetMessage::KeyDown(KeyCode::Return) => {
                         self.try_parse_value(ui);
                     }
+                    WidgetMessage::MouseDown { pos, button } => {
+                        if *button == MouseButton::Left {
+                            self.drag_context = Some(DragContext {
+                                start_x: pos.x,
+                                last_x: pos.x,
+                                value: self.value.to_f64().unwrap_or_default(),
+                                dragging: false,
+                            });
+                        }
+                    }
+                    WidgetMessage::MouseMove { pos, .. } => {
+                        self.drag(ui, *pos);
+                    }
+                    WidgetMessage::MouseUp { .. } => {
+                        // Simple click, the text field handles it.
+                        self.drag_context = None;
+                    }
+                    _ => {}
+                }
+            } else if message.destination() == self.handle() {
+                match msg {
+                    WidgetMessage::MouseMove { pos, .. } => {
+                        self.drag(ui, *pos);
+                    }
+                    WidgetMessage::MouseUp { .. } => {
+                        if self.drag_context.take().is_some() {
+                            ui.release_mouse_capture();
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -176,11 +313,7 @@ impl<T: NumericType> Control for NumericUpDown<T> {
                     self.value = clamped;
 
                     // Sync text field.
-                    ui.send_message(TextBoxMessage::text(
-                        self.field,
-                        MessageDirection::ToWidget,
-                        format!("{:.1$}", self.value, self.precision),
-                    ));
+                    self.sync_text(ui);
 
                     let mut msg = NumericUpDownMessage::value(
                         self.handle,
@@ -345,8 +478,122 @@ impl<T: NumericType> NumericUpDownBuilder<T> {
             min_value: self.min_value,
             max_value: self.max_value,
             precision: self.precision,
+            drag_context: None,
         };
 
         ctx.add_node(UiNode::new(node))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        message::{MessageDirection, MouseButton},
+        numeric::{NumericUpDown, NumericUpDownBuilder, NumericUpDownMessage},
+        text_box::{TextBox, TextBoxMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        MouseState, UserInterface,
+    };
+
+    fn flush(ui: &mut UserInterface) -> usize {
+        let mut changes = 0;
+        while let Some(message) = ui.poll_message() {
+            if let Some(NumericUpDownMessage::<i32>::Value(_)) = message.data() {
+                if message.direction() == MessageDirection::FromWidget {
+                    changes += 1;
+                }
+            }
+        }
+        changes
+    }
+
+    #[test]
+    fn test_drag_and_commit() {
+        let mut ui = UserInterface::new(Vector2::new(100.0, 100.0));
+        let numeric = NumericUpDownBuilder::<i32>::new(WidgetBuilder::new())
+            .with_min_value(0)
+            .with_max_value(10)
+            .with_value(5)
+            .build(&mut ui.build_ctx());
+        let field = ui.node(numeric).cast::<NumericUpDown<i32>>().unwrap().field;
+        flush(&mut ui);
+
+        let pressed = MouseState::default();
+        ui.send_message(WidgetMessage::mouse_down(
+            field,
+            MessageDirection::FromWidget,
+            Vector2::new(10.0, 0.0),
+            MouseButton::Left,
+        ));
+        // Within threshold - still a click.
+        ui.send_message(WidgetMessage::mouse_move(
+            field,
+            MessageDirection::FromWidget,
+            Vector2::new(11.0, 0.0),
+            pressed,
+        ));
+        flush(&mut ui);
+        let node = ui.node(numeric).cast::<NumericUpDown<i32>>().unwrap();
+        assert!(!node.is_dragging());
+
+        ui.send_message(WidgetMessage::mouse_move(
+            field,
+            MessageDirection::FromWidget,
+            Vector2::new(20.0, 0.0),
+            pressed,
+        ));
+        // Two steps to the right.
+        ui.send_message(WidgetMessage::mouse_move(
+            numeric,
+            MessageDirection::FromWidget,
+            Vector2::new(28.0, 0.0),
+            pressed,
+        ));
+        assert_eq!(flush(&mut ui), 1);
+        let node = ui.node(numeric).cast::<NumericUpDown<i32>>().unwrap();
+        assert!(node.is_dragging());
+        assert_eq!(node.value(), 7);
+
+        // Far beyond max value - clamped, only one change.
+        ui.send_message(WidgetMessage::mouse_move(
+            numeric,
+            MessageDirection::FromWidget,
+            Vector2::new(1000.0, 0.0),
+            pressed,
+        ));
+        ui.send_message(WidgetMessage::mouse_move(
+            numeric,
+            MessageDirection::FromWidget,
+            Vector2::new(1100.0, 0.0),
+            pressed,
+        ));
+        assert_eq!(flush(&mut ui), 1);
+        ui.send_message(WidgetMessage::mouse_up(
+            numeric,
+            MessageDirection::FromWidget,
+            Vector2::new(1100.0, 0.0),
+            MouseButton::Left,
+        ));
+        flush(&mut ui);
+        let node = ui.node(numeric).cast::<NumericUpDown<i32>>().unwrap();
+        assert!(!node.is_dragging());
+        assert_eq!(node.value(), 10);
+
+        // Invalid input is reverted on commit, out of bounds input is clamped.
+        for (text, expected) in [("abc", "10"), ("-5", "0")] {
+            ui.send_message(TextBoxMessage::text(
+                field,
+                MessageDirection::ToWidget,
+                text.to_owned(),
+            ));
+            flush(&mut ui);
+            ui.send_message(WidgetMessage::lost_focus(
+                field,
+                MessageDirection::FromWidget,
+            ));
+            flush(&mut ui);
+            assert_eq!(ui.node(field).cast::<TextBox>().unwrap().text(), expected);
+        }
+    }
+}
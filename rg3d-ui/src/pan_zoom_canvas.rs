@@ -0,0 +1,660 @@
+//! Pan and zoom canvas is a canvas with a view transform. Children are positioned in a *virtual*
+//! coordinate space using their desired position, the view transform (offset + uniform scale) maps
+//! virtual coordinates to local coordinates of the canvas. It is useful for world maps, node-graph
+//! editors and any other "infinite" surfaces.
+//!
+//! The view can be moved by dragging with left or middle mouse button and zoomed around the cursor
+//! with the mouse wheel, on touch screens it is dragged by a finger and zoomed by a pinch gesture.
+//! It can also be changed by [`PanZoomCanvasMessage`]. Each change of the view is reported back by
+//! a [`PanZoomCanvasMessage::View`] message with [`MessageDirection::FromWidget`].
+//!
+//! # Layout
+//!
+//! The transform is applied during layout: a child is arranged in a rect that starts at transformed
+//! desired position of the child and has its desired size multiplied by the scale. This means that
+//! hit testing and clipping (by the bounds of the canvas) work as usual. Keep in mind that explicit
+//! width and height of a widget are always in pixels, use content or minimal size of a child to
+//! define its size in virtual space if it must be scaled together with the view.
+//!
+//! Scale-invariant children (for example icons on a map) are not scaled, they keep their desired
+//! size in pixels and are centered at transformed desired position.
+//!
+//! # Scroll bars
+//!
+//! Optionally the canvas can be linked with a pair of scroll bars, in this case it keeps range of
+//! the scroll bars in sync with bounds of the content and moves the view when a scroll bar is moved.
+
+use crate::{
+    core::{algebra::Vector2, math::Rect, pool::Handle, scope_profile},
+    define_constructor,
+    message::{MessageDirection, MouseButton, UiMessage},
+    scroll_bar::{ScrollBar, ScrollBarMessage},
+    widget::{Widget, WidgetBuilder, WidgetMessage},
+    BuildContext, Control, NodeHandleMapping, UiNode, UserInterface,
+};
+use std::{
+    any::{Any, TypeId},
+    ops::{Deref, DerefMut},
+};
+
+/// View transform of [`PanZoomCanvas`], maps virtual coordinates to local coordinates of the canvas
+/// as `local = offset + virtual * scale`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ViewTransform {
+    /// Offset of the origin of virtual space in local coordinates of the canvas.
+    pub offset: Vector2<f32>,
+    /// Uniform scale, values above 1.0 zoom in.
+    pub scale: f32,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            offset: Default::default(),
+            scale: 1.0,
+        }
+    }
+}
+
+impl ViewTransform {
+    /// Transforms a point from virtual space to local coordinates of the canvas.
+    pub fn virtual_to_local(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.offset + point.scale(self.scale)
+    }
+
+    /// Transforms a point from local coordinates of the canvas to virtual space.
+    pub fn local_to_virtual(&self, point: Vector2<f32>) -> Vector2<f32> {
+        (point - self.offset).scale(1.0 / self.scale)
+    }
+
+    /// Returns new transform with given scale which keeps given point (in local coordinates) at
+    /// the same place, i.e. zooms "around" the point.
+    pub fn zoomed_at(&self, scale: f32, point: Vector2<f32>) -> Self {
+        let virtual_point = self.local_to_virtual(point);
+        Self {
+            offset: point - virtual_point.scale(scale),
+            scale,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PanZoomCanvasMessage {
+    /// Sets new view transform, the scale will be clamped to the zoom limits of the canvas. The
+    /// canvas sends this message back with [`MessageDirection::FromWidget`] on every change of the
+    /// view, including changes made by the mouse.
+    View(ViewTransform),
+    /// Changes scale of the view keeping given point (in local coordinates of the canvas) in place.
+    ZoomAt { scale: f32, point: Vector2<f32> },
+    /// Makes a child scale-invariant or not.
+    ScaleInvariant {
+        node: Handle<UiNode>,
+        invariant: bool,
+    },
+}
+
+impl PanZoomCanvasMessage {
+    define_constructor!(PanZoomCanvasMessage:View => fn view(ViewTransform), layout: false);
+    define_constructor!(PanZoomCanvasMessage:ZoomAt => fn zoom_at(scale: f32, point: Vector2<f32>), layout: false);
+    define_constructor!(PanZoomCanvasMessage:ScaleInvariant => fn scale_invariant(node: Handle<UiNode>, invariant: bool), layout: false);
+}
+
+#[derive(Clone)]
+struct DragContext {
+    initial_mouse_pos: Vector2<f32>,
+    initial_offset: Vector2<f32>,
+}
+
+/// See module docs.
+#[derive(Clone)]
+pub struct PanZoomCanvas {
+    widget: Widget,
+    view: ViewTransform,
+    min_zoom: f32,
+    max_zoom: f32,
+    zoom_step: f32,
+    scale_invariant_children: Vec<Handle<UiNode>>,
+    horizontal_scroll_bar: Handle<UiNode>,
+    vertical_scroll_bar: Handle<UiNode>,
+    drag_context: Option<DragContext>,
+}
+
+crate::define_widget_deref!(PanZoomCanvas);
+
+impl Control for PanZoomCanvas {
+    fn query_component(&self, type_id: TypeId) -> Option<&dyn Any> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&mut self, node_map: &NodeHandleMapping) {
+        node_map.resolve_slice(&mut self.scale_invariant_children);
+        if self.horizontal_scroll_bar.is_some() {
+            node_map.resolve(&mut self.horizontal_scroll_bar);
+        }
+        if self.vertical_scroll_bar.is_some() {
+            node_map.resolve(&mut self.vertical_scroll_bar);
+        }
+    }
+
+    fn measure_override(&self, ui: &UserInterface, _available_size: Vector2<f32>) -> Vector2<f32> {
+        scope_profile!();
+
+        let size_for_child = Vector2::new(f32::INFINITY, f32::INFINITY);
+
+        for child_handle in self.widget.children() {
+            ui.measure_node(*child_handle, size_for_child);
+        }
+
+        Vector2::default()
+    }
+
+    fn arrange_override(&self, ui: &UserInterface, final_size: Vector2<f32>) -> Vector2<f32> {
+        scope_profile!();
+
+        // Bounds of the content in virtual space.
+        let mut min = Vector2::new(f32::MAX, f32::MAX);
+        let mut max = Vector2::new(-f32::MAX, -f32::MAX);
+
+        for &child_handle in self.widget.children() {
+            let child = ui.nodes.borrow(child_handle);
+            let position = child.desired_local_position();
+            let desired_size = child.desired_size();
+            let local_position = self.view.virtual_to_local(position);
+
+            let rect = if self.is_scale_invariant(child_handle) {
+                min = min.inf(&position);
+                max = max.sup(&position);
+
+                let half_size = desired_size.scale(0.5);
+                Rect::new(
+                    local_position.x - half_size.x,
+                    local_position.y - half_size.y,
+                    desired_size.x,
+                    desired_size.y,
+                )
+            } else {
+                min = min.inf(&position);
+                max = max.sup(&(position + desired_size));
+
+                let size = desired_size.scale(self.view.scale);
+                Rect::new(local_position.x, local_position.y, size.x, size.y)
+            };
+
+            ui.arrange_node(child_handle, &rect);
+        }
+
+        if self.widget.children().is_empty() {
+            min = Vector2::default();
+            max = Vector2::default();
+        }
+
+        if self.horizontal_scroll_bar.is_some() {
+            let value = -self.view.offset.x;
+            sync_scroll_bar(
+                ui,
+                self.horizontal_scroll_bar,
+                (min.x * self.view.scale).min(value),
+                (max.x * self.view.scale - final_size.x).max(value),
+                value,
+            );
+        }
+        if self.vertical_scroll_bar.is_some() {
+            let value = -self.view.offset.y;
+            sync_scroll_bar(
+                ui,
+                self.vertical_scroll_bar,
+                (min.y * self.view.scale).min(value),
+                (max.y * self.view.scale - final_size.y).max(value),
+                value,
+            );
+        }
+
+        final_size
+    }
+
+    fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
+        self.widget.handle_routed_message(ui, message);
+
+        if let Some(msg) = message.data::<WidgetMessage>() {
+            match *msg {
+                WidgetMessage::MouseDown { pos, button } => {
+                    if !message.handled()
+                        && (button == MouseButton::Left || button == MouseButton::Middle)
+                    {
+                        ui.capture_mouse(self.handle);
+                        self.drag_context = Some(DragContext {
+                            initial_mouse_pos: pos,
+                            initial_offset: self.view.offset,
+                        });
+                        message.set_handled(true);
+                    }
+                }
+                WidgetMessage::MouseMove { pos, .. } => {
+                    if let Some(drag_context) = self.drag_context.as_ref() {
+                        let view = ViewTransform {
+                            offset: drag_context.initial_offset + pos
+                                - drag_context.initial_mouse_pos,
+                            scale: self.view.scale,
+                        };
+                        self.set_view(view, ui);
+                        message.set_handled(true);
+                    }
+                }
+                WidgetMessage::MouseUp { .. } => {
+                    if self.drag_context.take().is_some() {
+                        ui.release_mouse_capture();
+                        message.set_handled(true);
+                    }
+                }
+                WidgetMessage::MouseWheel { pos, amount } => {
+                    if !message.handled() {
+                        let scale = if amount > 0.0 {
+                            self.view.scale * self.zoom_step
+                        } else {
+                            self.view.scale / self.zoom_step
+                        };
+                        let point = pos - self.screen_position();
+                        self.set_view(self.view.zoomed_at(self.clamp_scale(scale), point), ui);
+                        message.set_handled(true);
+                    }
+                }
+                WidgetMessage::Pinch {
+                    center,
+                    scale,
+                    offset,
+                } => {
+                    if !message.handled() {
+                        // Pinch replaces dragging by the first finger. Content under the fingers
+                        // is zoomed around their previous center and then follows them.
+                        self.drag_context = None;
+                        let point = center - offset - self.screen_position();
+                        let mut view = self
+                            .view
+                            .zoomed_at(self.clamp_scale(self.view.scale * scale), point);
+                        view.offset += offset;
+                        self.set_view(view, ui);
+                        message.set_handled(true);
+                    }
+                }
+                _ => (),
+            }
+        } else if let Some(msg) = message.data::<PanZoomCanvasMessage>() {
+            if message.destination() == self.handle
+                && message.direction() == MessageDirection::ToWidget
+            {
+                match *msg {
+                    PanZoomCanvasMessage::View(view) => {
+                        self.set_view(view, ui);
+                    }
+                    PanZoomCanvasMessage::ZoomAt { scale, point } => {
+                        self.set_view(self.view.zoomed_at(self.clamp_scale(scale), point), ui);
+                    }
+                    PanZoomCanvasMessage::ScaleInvariant { node, invariant } => {
+                        let position = self
+                            .scale_invariant_children
+                            .iter()
+                            .position(|&h| h == node);
+                        match (position, invariant) {
+                            (None, true) => self.scale_invariant_children.push(node),
+                            (Some(index), false) => {
+                                self.scale_invariant_children.remove(index);
+                            }
+                            _ => (),
+                        }
+                        self.invalidate_layout();
+                    }
+                }
+            }
+        }
+    }
+
+    fn preview_message(&self, ui: &UserInterface, message: &mut UiMessage) {
+        if let Some(ScrollBarMessage::Value(value)) = message.data::<ScrollBarMessage>() {
+            if message.direction() == MessageDirection::FromWidget {
+                let mut offset = self.view.offset;
+                if message.destination() == self.horizontal_scroll_bar
+                    && self.horizontal_scroll_bar.is_some()
+                {
+                    offset.x = -*value;
+                } else if message.destination() == self.vertical_scroll_bar
+                    && self.vertical_scroll_bar.is_some()
+                {
+                    offset.y = -*value;
+                }
+
+                if offset != self.view.offset {
+                    ui.send_message(PanZoomCanvasMessage::view(
+                        self.handle,
+                        MessageDirection::ToWidget,
+                        ViewTransform {
+                            offset,
+                            scale: self.view.scale,
+                        },
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn sync_scroll_bar(ui: &UserInterface, handle: Handle<UiNode>, min: f32, max: f32, value: f32) {
+    if let Some(scroll_bar) = ui.node(handle).cast::<ScrollBar>() {
+        // Scroll bar swaps its limits if they're in wrong order, so the order of messages matters.
+        let min_message = (scroll_bar.min != min)
+            .then(|| ScrollBarMessage::min_value(handle, MessageDirection::ToWidget, min));
+        let max_message = (scroll_bar.max != max)
+            .then(|| ScrollBarMessage::max_value(handle, MessageDirection::ToWidget, max));
+        let (first, second) = if min > scroll_bar.max {
+            (max_message, min_message)
+        } else {
+            (min_message, max_message)
+        };
+        for message in first.into_iter().chain(second) {
+            ui.send_message(message);
+        }
+        if scroll_bar.value != value {
+            ui.send_message(ScrollBarMessage::value(
+                handle,
+                MessageDirection::ToWidget,
+                value,
+            ));
+        }
+    }
+}
+
+impl PanZoomCanvas {
+    /// Returns current view transform.
+    pub fn view(&self) -> ViewTransform {
+        self.view
+    }
+
+    pub fn min_zoom(&self) -> f32 {
+        self.min_zoom
+    }
+
+    pub fn max_zoom(&self) -> f32 {
+        self.max_zoom
+    }
+
+    /// Returns `true` if given child keeps its pixel size regardless of the scale of the view.
+    pub fn is_scale_invariant(&self, node: Handle<UiNode>) -> bool {
+        self.scale_invariant_children.contains(&node)
+    }
+
+    /// Transforms a point in screen coordinates (i.e. mouse position) to virtual space.
+    pub fn screen_to_virtual(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.view.local_to_virtual(point - self.screen_position())
+    }
+
+    /// Transforms a point from virtual space to screen coordinates.
+    pub fn virtual_to_screen(&self, point: Vector2<f32>) -> Vector2<f32> {
+        self.view.virtual_to_local(point) + self.screen_position()
+    }
+
+    fn clamp_scale(&self, scale: f32) -> f32 {
+        scale.max(self.min_zoom).min(self.max_zoom)
+    }
+
+    fn set_view(&mut self, mut view: ViewTransform, ui: &UserInterface) {
+        view.scale = self.clamp_scale(view.scale);
+        if view != self.view {
+            self.view = view;
+            self.invalidate_layout();
+            ui.send_message(PanZoomCanvasMessage::view(
+                self.handle,
+                MessageDirection::FromWidget,
+                view,
+            ));
+        }
+    }
+}
+
+pub struct PanZoomCanvasBuilder {
+    widget_builder: WidgetBuilder,
+    view: ViewTransform,
+    min_zoom: f32,
+    max_zoom: f32,
+    zoom_step: f32,
+    scale_invariant_children: Vec<Handle<UiNode>>,
+    horizontal_scroll_bar: Handle<UiNode>,
+    vertical_scroll_bar: Handle<UiNode>,
+}
+
+impl PanZoomCanvasBuilder {
+    pub fn new(widget_builder: WidgetBuilder) -> Self {
+        Self {
+            widget_builder,
+            view: Default::default(),
+            min_zoom: 0.1,
+            max_zoom: 10.0,
+            zoom_step: 1.1,
+            scale_invariant_children: Default::default(),
+            horizontal_scroll_bar: Default::default(),
+            vertical_scroll_bar: Default::default(),
+        }
+    }
+
+    pub fn with_view(mut self, view: ViewTransform) -> Self {
+        self.view = view;
+        self
+    }
+
+    pub fn with_min_zoom(mut self, min_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self
+    }
+
+    pub fn with_max_zoom(mut self, max_zoom: f32) -> Self {
+        self.max_zoom = max_zoom;
+        self
+    }
+
+    /// Sets a multiplier of the scale which is applied on each step of the mouse wheel.
+    pub fn with_zoom_step(mut self, zoom_step: f32) -> Self {
+        self.zoom_step = zoom_step;
+        self
+    }
+
+    /// Sets children that will keep their pixel size regardless of the scale of the view. Every
+    /// handle must also be added as a child to the widget builder.
+    pub fn with_scale_invariant_children(mut self, children: Vec<Handle<UiNode>>) -> Self {
+        self.scale_invariant_children = children;
+        self
+    }
+
+    /// Links the canvas with a horizontal scroll bar.
+    pub fn with_horizontal_scroll_bar(mut self, scroll_bar: Handle<UiNode>) -> Self {
+        self.horizontal_scroll_bar = scroll_bar;
+        self
+    }
+
+    /// Links the canvas with a vertical scroll bar.
+    pub fn with_vertical_scroll_bar(mut self, scroll_bar: Handle<UiNode>) -> Self {
+        self.vertical_scroll_bar = scroll_bar;
+        self
+    }
+
+    pub fn build(self, ctx: &mut BuildContext) -> Handle<UiNode> {
+        let preview_messages =
+            self.horizontal_scroll_bar.is_some() || self.vertical_scroll_bar.is_some();
+
+        let mut canvas = PanZoomCanvas {
+            widget: self
+                .widget_builder
+                .with_preview_messages(preview_messages)
+                .build(),
+            view: self.view,
+            min_zoom: self.min_zoom.min(self.max_zoom),
+            max_zoom: self.max_zoom.max(self.min_zoom),
+            zoom_step: self.zoom_step,
+            scale_invariant_children: self.scale_invariant_children,
+            horizontal_scroll_bar: self.horizontal_scroll_bar,
+            vertical_scroll_bar: self.vertical_scroll_bar,
+            drag_context: None,
+        };
+        canvas.view.scale = canvas.clamp_scale(canvas.view.scale);
+
+        ctx.add_node(UiNode::new(canvas))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        border::BorderBuilder,
+        core::algebra::Vector2,
+        message::{MessageDirection, OsEvent, TouchPhase},
+        pan_zoom_canvas::{
+            PanZoomCanvas, PanZoomCanvasBuilder, PanZoomCanvasMessage, ViewTransform,
+        },
+        widget::WidgetBuilder,
+        UserInterface,
+    };
+
+    fn update(ui: &mut UserInterface) {
+        for _ in 0..4 {
+            ui.update(Vector2::new(1000.0, 1000.0), 0.0);
+            while ui.poll_message().is_some() {}
+        }
+    }
+
+    #[test]
+    fn test_view_transform() {
+        let view = ViewTransform {
+            offset: Vector2::new(10.0, 20.0),
+            scale: 2.0,
+        };
+        let point = Vector2::new(3.0, 4.0);
+        assert_eq!(view.virtual_to_local(point), Vector2::new(16.0, 28.0));
+        assert_eq!(view.local_to_virtual(view.virtual_to_local(point)), point);
+
+        // Point under the "cursor" must stay in place.
+        let cursor = Vector2::new(50.0, 60.0);
+        let zoomed = view.zoomed_at(4.0, cursor);
+        assert_eq!(zoomed.scale, 4.0);
+        assert_eq!(
+            zoomed.local_to_virtual(cursor),
+            view.local_to_virtual(cursor)
+        );
+    }
+
+    #[test]
+    fn test_pan_zoom_canvas() {
+        let mut ui = UserInterface::new(Vector2::new(1000.0, 1000.0));
+        let ctx = &mut ui.build_ctx();
+
+        let tile = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_min_size(Vector2::new(100.0, 100.0))
+                .with_desired_position(Vector2::new(50.0, 50.0)),
+        )
+        .build(ctx);
+        let icon = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_width(10.0)
+                .with_height(10.0)
+                .with_desired_position(Vector2::new(200.0, 200.0)),
+        )
+        .build(ctx);
+        let canvas = PanZoomCanvasBuilder::new(
+            WidgetBuilder::new()
+                .with_width(400.0)
+                .with_height(400.0)
+                .with_child(tile)
+                .with_child(icon),
+        )
+        .with_max_zoom(4.0)
+        .with_scale_invariant_children(vec![icon])
+        .build(ctx);
+
+        update(&mut ui);
+        assert_eq!(
+            ui.node(tile).screen_bounds().position,
+            Vector2::new(50.0, 50.0)
+        );
+        assert_eq!(
+            ui.node(icon).screen_bounds().position,
+            Vector2::new(195.0, 195.0)
+        );
+
+        // Scale must be clamped.
+        ui.send_message(PanZoomCanvasMessage::view(
+            canvas,
+            MessageDirection::ToWidget,
+            ViewTransform {
+                offset: Vector2::new(-100.0, 0.0),
+                scale: 100.0,
+            },
+        ));
+        update(&mut ui);
+        let view = ui.node(canvas).cast::<PanZoomCanvas>().unwrap().view();
+        assert_eq!(view.scale, 4.0);
+
+        let tile_bounds = ui.node(tile).screen_bounds();
+        assert_eq!(tile_bounds.position, Vector2::new(100.0, 200.0));
+        assert_eq!(tile_bounds.size, Vector2::new(400.0, 400.0));
+        // Icon keeps its size, but follows the view.
+        let icon_bounds = ui.node(icon).screen_bounds();
+        assert_eq!(icon_bounds.position, Vector2::new(695.0, 795.0));
+        assert_eq!(icon_bounds.size, Vector2::new(10.0, 10.0));
+
+        // Hit test goes through the transform, and the icon is clipped by the canvas.
+        ui.draw();
+        assert_eq!(ui.hit_test(Vector2::new(150.0, 250.0)), tile);
+        assert_ne!(ui.hit_test(Vector2::new(700.0, 800.0)), icon);
+    }
+
+    #[test]
+    fn test_pinch() {
+        let mut ui = UserInterface::new(Vector2::new(1000.0, 1000.0));
+        let ctx = &mut ui.build_ctx();
+        let tile = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_min_size(Vector2::new(400.0, 400.0))
+                .with_desired_position(Vector2::new(0.0, 0.0)),
+        )
+        .build(ctx);
+        let canvas = PanZoomCanvasBuilder::new(
+            WidgetBuilder::new()
+                .with_width(400.0)
+                .with_height(400.0)
+                .with_child(tile),
+        )
+        .with_max_zoom(4.0)
+        .build(ctx);
+        update(&mut ui);
+        ui.draw();
+
+        let touch = |ui: &mut UserInterface, id, phase, x| {
+            ui.process_os_event(&OsEvent::Touch {
+                id,
+                phase,
+                position: Vector2::new(x, 200.0),
+            });
+            update(ui);
+        };
+        touch(&mut ui, 0, TouchPhase::Started, 150.0);
+        touch(&mut ui, 1, TouchPhase::Started, 250.0);
+        touch(&mut ui, 1, TouchPhase::Moved, 350.0);
+
+        // Distance between fingers is doubled, and the point that was between them follows them.
+        let view = ui.node(canvas).cast::<PanZoomCanvas>().unwrap().view();
+        assert_eq!(view.scale, 2.0);
+        assert_eq!(
+            view.virtual_to_local(Vector2::new(200.0, 200.0)),
+            Vector2::new(250.0, 200.0)
+        );
+
+        // First finger does not drag the view after the pinch.
+        touch(&mut ui, 1, TouchPhase::Ended, 350.0);
+        touch(&mut ui, 0, TouchPhase::Moved, 50.0);
+        assert_eq!(
+            ui.node(canvas).cast::<PanZoomCanvas>().unwrap().view(),
+            view
+        );
+    }
+}
@@ -278,6 +278,10 @@ impl PopupBuilder {
             body,
         };
 
-        ctx.add_node(UiNode::new(popup))
+        // Popups live in the overlay layer, so they can't be occluded by other widgets.
+        let popup = ctx.add_node(UiNode::new(popup));
+        let overlay_layer = ctx.overlay_layer();
+        ctx.link(popup, overlay_layer);
+        popup
     }
 }
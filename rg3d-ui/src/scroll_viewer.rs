@@ -11,8 +11,17 @@ use crate::{
 use std::{
     any::{Any, TypeId},
     ops::{Deref, DerefMut},
+    sync::mpsc::Sender,
 };
 
+/// Distance (in units) that a finger must pass before the touch is treated as scrolling instead of
+/// a tap.
+const TOUCH_SLOP: f32 = 8.0;
+/// Kinetic scrolling stops when its speed (units per second) drops below this value.
+const MIN_FLICK_SPEED: f32 = 20.0;
+/// Rate of slowdown of kinetic scrolling.
+const FLICK_FRICTION: f32 = 4.0;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ScrollViewerMessage {
     Content(Handle<UiNode>),
@@ -26,6 +35,20 @@ impl ScrollViewerMessage {
     define_constructor!(ScrollViewerMessage:BringIntoView=> fn bring_into_view(Handle<UiNode>), layout: true);
 }
 
+// Content of scroll viewer can be dragged by a finger and flicked, so it keeps scrolling for a while
+// after the finger was lifted.
+#[derive(Clone, Default)]
+struct TouchScroll {
+    // Id and last position of the finger that scrolls the content.
+    finger: Option<(u64, Vector2<f32>)>,
+    distance: f32,
+    // Finger movement since last update, it is used to estimate velocity.
+    pending: Vector2<f32>,
+    velocity: Vector2<f32>,
+    // Scroll bars are inaccessible during update, so their values are tracked here.
+    value: Vector2<f32>,
+}
+
 #[derive(Clone)]
 pub struct ScrollViewer {
     pub widget: Widget,
@@ -33,6 +56,7 @@ pub struct ScrollViewer {
     pub scroll_panel: Handle<UiNode>,
     pub v_scroll_bar: Handle<UiNode>,
     pub h_scroll_bar: Handle<UiNode>,
+    touch_scroll: TouchScroll,
 }
 
 crate::define_widget_deref!(ScrollViewer);
@@ -51,6 +75,7 @@ impl ScrollViewer {
             scroll_panel: content_presenter,
             v_scroll_bar,
             h_scroll_bar,
+            touch_scroll: Default::default(),
         }
     }
 
@@ -65,6 +90,23 @@ impl ScrollViewer {
     pub fn set_content(&mut self, content: Handle<UiNode>) {
         self.content = content;
     }
+
+    fn scroll_by(&mut self, offset: Vector2<f32>, sender: &Sender<UiMessage>) {
+        for (axis, scroll_bar) in [self.h_scroll_bar, self.v_scroll_bar]
+            .into_iter()
+            .enumerate()
+        {
+            if scroll_bar.is_some() && offset[axis] != 0.0 {
+                // Scroll bar clamps the value and reports it back.
+                self.touch_scroll.value[axis] += offset[axis];
+                let _ = sender.send(ScrollBarMessage::value(
+                    scroll_bar,
+                    MessageDirection::ToWidget,
+                    self.touch_scroll.value[axis],
+                ));
+            }
+        }
+    }
 }
 
 impl Control for ScrollViewer {
@@ -108,23 +150,87 @@ impl Control for ScrollViewer {
         size
     }
 
+    fn update(&mut self, dt: f32, sender: &Sender<UiMessage>) {
+        if self.touch_scroll.finger.is_some() {
+            if dt > 0.0 {
+                let velocity = self.touch_scroll.pending.scale(1.0 / dt);
+                self.touch_scroll.velocity = self.touch_scroll.velocity.lerp(&velocity, 0.5);
+            }
+            self.touch_scroll.pending = Vector2::default();
+        } else if self.touch_scroll.velocity.norm() > MIN_FLICK_SPEED {
+            let offset = -self.touch_scroll.velocity.scale(dt);
+            self.scroll_by(offset, sender);
+            self.touch_scroll.velocity = self
+                .touch_scroll
+                .velocity
+                .scale((-FLICK_FRICTION * dt).exp());
+        } else {
+            self.touch_scroll.velocity = Vector2::default();
+        }
+    }
+
     fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
         self.widget.handle_routed_message(ui, message);
 
-        if let Some(WidgetMessage::MouseWheel { amount, .. }) = message.data::<WidgetMessage>() {
-            if self.v_scroll_bar.is_some() && !message.handled() {
-                if let Some(v_scroll_bar) = ui.node(self.v_scroll_bar).cast::<ScrollBar>() {
-                    let old_value = v_scroll_bar.value();
-                    let new_value = old_value - amount * 17.0;
-                    if (old_value - new_value).abs() > f32::EPSILON {
+        if let Some(msg) = message.data::<WidgetMessage>() {
+            match *msg {
+                WidgetMessage::MouseWheel { amount, .. } => {
+                    if self.v_scroll_bar.is_some() && !message.handled() {
+                        if let Some(v_scroll_bar) = ui.node(self.v_scroll_bar).cast::<ScrollBar>() {
+                            let old_value = v_scroll_bar.value();
+                            let new_value = old_value - amount * 17.0;
+                            if (old_value - new_value).abs() > f32::EPSILON {
+                                message.set_handled(true);
+                            }
+                            ui.send_message(ScrollBarMessage::value(
+                                self.v_scroll_bar,
+                                MessageDirection::ToWidget,
+                                new_value,
+                            ));
+                        }
+                    }
+                }
+                WidgetMessage::TouchStarted { pos, id } => {
+                    // Scroll bars are dragged as usual.
+                    let on_scroll_bar = [self.v_scroll_bar, self.h_scroll_bar].iter().any(|&bar| {
+                        bar == message.destination()
+                            || (bar.is_some() && ui.is_node_child_of(message.destination(), bar))
+                    });
+                    if !message.handled() && !on_scroll_bar && self.touch_scroll.finger.is_none() {
+                        self.touch_scroll.finger = Some((id, pos));
+                        self.touch_scroll.distance = 0.0;
+                        self.touch_scroll.pending = Vector2::default();
+                        // Touch stops kinetic scrolling.
+                        self.touch_scroll.velocity = Vector2::default();
                         message.set_handled(true);
                     }
-                    ui.send_message(ScrollBarMessage::value(
-                        self.v_scroll_bar,
-                        MessageDirection::ToWidget,
-                        new_value,
-                    ));
                 }
+                WidgetMessage::TouchMoved { pos, id } => {
+                    if let Some((finger, last_pos)) = self.touch_scroll.finger.as_mut() {
+                        if *finger == id {
+                            let offset = pos - *last_pos;
+                            *last_pos = pos;
+                            self.touch_scroll.distance += offset.norm();
+                            if self.touch_scroll.distance > TOUCH_SLOP {
+                                // It is a scroll, so widget under the finger must not be clicked.
+                                ui.cancel_touch_press();
+                                self.scroll_by(-offset, &ui.sender());
+                                self.touch_scroll.pending += offset;
+                            }
+                            message.set_handled(true);
+                        }
+                    }
+                }
+                WidgetMessage::TouchEnded { id, .. } => {
+                    if matches!(self.touch_scroll.finger, Some((finger, _)) if finger == id) {
+                        self.touch_scroll.finger = None;
+                        if self.touch_scroll.distance <= TOUCH_SLOP {
+                            self.touch_scroll.velocity = Vector2::default();
+                        }
+                        message.set_handled(true);
+                    }
+                }
+                _ => (),
             }
         } else if let Some(msg) = message.data::<ScrollPanelMessage>() {
             if message.destination() == self.scroll_panel {
@@ -149,6 +255,12 @@ impl Control for ScrollViewer {
             if message.direction() == MessageDirection::FromWidget {
                 match msg {
                     ScrollBarMessage::Value(new_value) => {
+                        if message.destination() == self.v_scroll_bar {
+                            self.touch_scroll.value.y = *new_value;
+                        } else if message.destination() == self.h_scroll_bar {
+                            self.touch_scroll.value.x = *new_value;
+                        }
+
                         if !message.handled() {
                             if message.destination() == self.v_scroll_bar
                                 && self.v_scroll_bar.is_some()
@@ -324,6 +436,7 @@ impl ScrollViewerBuilder {
             v_scroll_bar,
             h_scroll_bar,
             scroll_panel: content_presenter,
+            touch_scroll: Default::default(),
         };
         ctx.add_node(UiNode::new(sv))
     }
@@ -3,7 +3,8 @@ use crate::{
     core::{algebra::Vector2, color::Color, pool::Handle},
     define_constructor,
     draw::DrawingContext,
-    formatted_text::{FormattedText, FormattedTextBuilder, WrapMode},
+    formatted_text::{FormattedText, FormattedTextBuilder, TextRun, TextSpan, WrapMode},
+    markup::parse_markup,
     message::{MessageDirection, UiMessage},
     ttf::SharedFont,
     widget::{Widget, WidgetBuilder},
@@ -18,16 +19,24 @@ use std::{
 #[derive(Debug, Clone, PartialEq)]
 pub enum TextMessage {
     Text(String),
+    /// Sets styled text, see [`crate::formatted_text::FormattedText::set_spans`].
+    Spans(Vec<TextSpan>),
+    /// Sets styled text from markup, see [`crate::markup`] for supported tags.
+    Markup(String),
     Wrap(WrapMode),
     Font(SharedFont),
+    FontSize(f32),
     VerticalAlignment(VerticalAlignment),
     HorizontalAlignment(HorizontalAlignment),
 }
 
 impl TextMessage {
     define_constructor!(TextMessage:Text => fn text(String), layout: false);
+    define_constructor!(TextMessage:Spans => fn spans(Vec<TextSpan>), layout: false);
+    define_constructor!(TextMessage:Markup => fn markup(String), layout: false);
     define_constructor!(TextMessage:Wrap=> fn wrap(WrapMode), layout: false);
     define_constructor!(TextMessage:Font => fn font(SharedFont), layout: false);
+    define_constructor!(TextMessage:FontSize => fn font_size(f32), layout: false);
     define_constructor!(TextMessage:VerticalAlignment => fn vertical_alignment(VerticalAlignment), layout: false);
     define_constructor!(TextMessage:HorizontalAlignment => fn horizontal_alignment(HorizontalAlignment), layout: false);
 }
@@ -49,10 +58,11 @@ impl Control for Text {
         }
     }
 
-    fn measure_override(&self, _: &UserInterface, available_size: Vector2<f32>) -> Vector2<f32> {
+    fn measure_override(&self, ui: &UserInterface, available_size: Vector2<f32>) -> Vector2<f32> {
         self.formatted_text
             .borrow_mut()
             .set_constraint(available_size)
+            .set_scale(ui.scale())
             .set_brush(self.widget.foreground())
             .build()
     }
@@ -76,6 +86,15 @@ impl Control for Text {
                         self.formatted_text.borrow_mut().set_text(text);
                         self.invalidate_layout();
                     }
+                    TextMessage::Spans(spans) => {
+                        self.formatted_text.borrow_mut().set_spans(spans);
+                        self.invalidate_layout();
+                    }
+                    TextMessage::Markup(markup) => {
+                        let spans = parse_markup(markup, ui.inline_icons());
+                        self.formatted_text.borrow_mut().set_spans(&spans);
+                        self.invalidate_layout();
+                    }
                     &TextMessage::Wrap(wrap) => {
                         if self.formatted_text.borrow().wrap_mode() != wrap {
                             self.formatted_text.borrow_mut().set_wrap(wrap);
@@ -86,6 +105,12 @@ impl Control for Text {
                         self.formatted_text.borrow_mut().set_font(font.clone());
                         self.invalidate_layout();
                     }
+                    &TextMessage::FontSize(font_size) => {
+                        if self.formatted_text.borrow().font_size() != font_size {
+                            self.formatted_text.borrow_mut().set_font_size(font_size);
+                            self.invalidate_layout();
+                        }
+                    }
                     &TextMessage::HorizontalAlignment(horizontal_alignment) => {
                         self.formatted_text
                             .borrow_mut()
@@ -124,10 +149,19 @@ impl Text {
         self.formatted_text.borrow().text()
     }
 
+    /// Returns styled runs of the text.
+    pub fn runs(&self) -> Vec<TextRun> {
+        self.formatted_text.borrow().runs().to_vec()
+    }
+
     pub fn font(&self) -> SharedFont {
         self.formatted_text.borrow().get_font()
     }
 
+    pub fn font_size(&self) -> f32 {
+        self.formatted_text.borrow().font_size()
+    }
+
     pub fn vertical_alignment(&self) -> VerticalAlignment {
         self.formatted_text.borrow().vertical_alignment()
     }
@@ -140,7 +174,10 @@ impl Text {
 pub struct TextBuilder {
     widget_builder: WidgetBuilder,
     text: Option<String>,
+    spans: Option<Vec<TextSpan>>,
+    markup: Option<String>,
     font: Option<SharedFont>,
+    font_size: Option<f32>,
     vertical_text_alignment: VerticalAlignment,
     horizontal_text_alignment: HorizontalAlignment,
     wrap: WrapMode,
@@ -151,7 +188,10 @@ impl TextBuilder {
         Self {
             widget_builder,
             text: None,
+            spans: None,
+            markup: None,
             font: None,
+            font_size: None,
             vertical_text_alignment: VerticalAlignment::Top,
             horizontal_text_alignment: HorizontalAlignment::Left,
             wrap: WrapMode::NoWrap,
@@ -163,6 +203,19 @@ impl TextBuilder {
         self
     }
 
+    /// Sets styled text, it overrides plain text.
+    pub fn with_spans(mut self, spans: Vec<TextSpan>) -> Self {
+        self.spans = Some(spans);
+        self
+    }
+
+    /// Sets styled text from markup, it overrides plain text and spans. See [`crate::markup`]
+    /// for supported tags.
+    pub fn with_markup<P: AsRef<str>>(mut self, markup: P) -> Self {
+        self.markup = Some(markup.as_ref().to_owned());
+        self
+    }
+
     pub fn with_font(mut self, font: SharedFont) -> Self {
         self.font = Some(font);
         self
@@ -173,6 +226,12 @@ impl TextBuilder {
         self
     }
 
+    /// Sets size (in pixels) of the font, if not set - default height of the font will be used.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
     pub fn with_vertical_text_alignment(mut self, valign: VerticalAlignment) -> Self {
         self.vertical_text_alignment = valign;
         self
@@ -199,17 +258,26 @@ impl TextBuilder {
             self.widget_builder.foreground = Some(Brush::Solid(Color::opaque(220, 220, 220)));
         }
 
+        let mut formatted_text = FormattedTextBuilder::new()
+            .with_text(self.text.unwrap_or_default())
+            .with_vertical_alignment(self.vertical_text_alignment)
+            .with_horizontal_alignment(self.horizontal_text_alignment)
+            .with_font(font)
+            .with_wrap(self.wrap);
+        if let Some(font_size) = self.font_size {
+            formatted_text = formatted_text.with_font_size(font_size);
+        }
+
+        let mut formatted_text = formatted_text.build();
+        if let Some(markup) = self.markup {
+            formatted_text.set_spans(&parse_markup(&markup, ui.inline_icons()));
+        } else if let Some(spans) = self.spans {
+            formatted_text.set_spans(&spans);
+        }
+
         let text = Text {
             widget: self.widget_builder.build(),
-            formatted_text: RefCell::new(
-                FormattedTextBuilder::new()
-                    .with_text(self.text.unwrap_or_default())
-                    .with_vertical_alignment(self.vertical_text_alignment)
-                    .with_horizontal_alignment(self.horizontal_text_alignment)
-                    .with_font(font)
-                    .with_wrap(self.wrap)
-                    .build(),
-            ),
+            formatted_text: RefCell::new(formatted_text),
         };
         ui.add_node(UiNode::new(text))
     }
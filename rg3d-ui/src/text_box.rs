@@ -335,33 +335,22 @@ impl TextBox {
 
     pub fn screen_pos_to_text_pos(&self, screen_pos: Vector2<f32>) -> Option<Position> {
         let caret_pos = self.widget.screen_position;
-        let font = self.formatted_text.borrow().get_font();
-        let font = font.0.lock().unwrap();
-        for (line_index, line) in self.formatted_text.borrow().get_lines().iter().enumerate() {
+        let formatted_text = self.formatted_text.borrow();
+        // Advances are taken from formatted text, so styled runs are taken into account.
+        let advances = formatted_text.advances();
+        for (line_index, line) in formatted_text.get_lines().iter().enumerate() {
             let line_bounds = Rect::new(
                 caret_pos.x + line.x_offset,
                 caret_pos.y + line.y_offset,
                 line.width,
-                font.ascender(),
+                line.height,
             );
             if line_bounds.contains(screen_pos) {
                 let mut x = line_bounds.x();
                 // Check each character in line.
                 for (offset, index) in (line.begin..line.end).enumerate() {
-                    let character = self.formatted_text.borrow().get_raw_text()[index];
-                    let (width, height, advance) =
-                        if let Some(glyph) = font.glyphs().get(character.char_code as usize) {
-                            (
-                                glyph.bitmap_width as f32,
-                                glyph.bitmap_height as f32,
-                                glyph.advance,
-                            )
-                        } else {
-                            // Stub
-                            let h = font.height();
-                            (h, h, h)
-                        };
-                    let char_bounds = Rect::new(x, line_bounds.y(), width, height);
+                    let advance = advances[index];
+                    let char_bounds = Rect::new(x, line_bounds.y(), advance, line_bounds.h());
                     if char_bounds.contains(screen_pos) {
                         return Some(Position {
                             line: line_index,
@@ -379,6 +368,10 @@ impl TextBox {
         self.formatted_text.borrow().text()
     }
 
+    pub fn is_editable(&self) -> bool {
+        self.editable
+    }
+
     pub fn set_wrap(&mut self, wrap: WrapMode) -> &mut Self {
         self.formatted_text.borrow_mut().set_wrap(wrap);
         self
@@ -429,10 +422,11 @@ impl Control for TextBox {
         }
     }
 
-    fn measure_override(&self, _: &UserInterface, available_size: Vector2<f32>) -> Vector2<f32> {
+    fn measure_override(&self, ui: &UserInterface, available_size: Vector2<f32>) -> Vector2<f32> {
         self.formatted_text
             .borrow_mut()
             .set_constraint(available_size)
+            .set_scale(ui.scale())
             .build()
     }
 
@@ -526,26 +520,21 @@ impl Control for TextBox {
         if self.caret_visible {
             let text = self.formatted_text.borrow();
 
-            let font = text.get_font();
             let mut caret_pos = screen_position;
 
-            let font = font.0.lock().unwrap();
+            let font_size = text.font_size();
             if let Some(line) = text.get_lines().get(self.caret_position.line) {
-                let text = text.get_raw_text();
+                let advances = text.advances();
                 caret_pos += Vector2::new(line.x_offset, line.y_offset);
                 for (offset, char_index) in (line.begin..line.end).enumerate() {
                     if offset >= self.caret_position.offset {
                         break;
                     }
-                    if let Some(glyph) = font.glyphs().get(text[char_index].glyph_index as usize) {
-                        caret_pos.x += glyph.advance;
-                    } else {
-                        caret_pos.x += font.height();
-                    }
+                    caret_pos.x += advances[char_index];
                 }
             }
 
-            let caret_bounds = Rect::new(caret_pos.x, caret_pos.y, 2.0, font.height());
+            let caret_bounds = Rect::new(caret_pos.x, caret_pos.y, 2.0, font_size);
             drawing_context.push_rect_filled(&caret_bounds, None);
             drawing_context.commit(
                 self.clip_bounds(),
@@ -1,5 +1,10 @@
+//! TrueType fonts. Glyphs are rasterized on demand at any pixel size and packed into a set of
+//! atlas pages, so a single font can be used for texts of various sizes and with any characters
+//! that the font has. Characters that are missing in the font are taken from the fallback font (if
+//! any) and drawn as a box ("tofu") if there is no such character in the fallback font too.
+
 use crate::{
-    core::{algebra::Vector2, io, rectpack::RectPacker},
+    core::{algebra::Vector2, io, log_warn, math::Rect, rectpack::RectPacker},
     draw::SharedTexture,
 };
 use fxhash::FxHashMap;
@@ -10,7 +15,10 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-#[derive(Debug)]
+/// Default size (in pixels) of atlas pages of fonts.
+pub const DEFAULT_FONT_PAGE_SIZE: usize = 1024;
+
+#[derive(Debug, Clone)]
 pub struct FontGlyph {
     pub top: f32,
     pub left: f32,
@@ -18,18 +26,53 @@ pub struct FontGlyph {
     pub tex_coords: [Vector2<f32>; 4],
     pub bitmap_width: usize,
     pub bitmap_height: usize,
-    pub pixels: Vec<u8>,
+    /// Index of atlas page that contains pixels of the glyph.
+    pub page_index: usize,
 }
 
-pub struct Font {
-    height: f32,
+/// Single page of glyph atlas. Glyphs never move once they were packed into a page, so texture
+/// coordinates of a glyph stays valid for the whole lifetime of the font.
+pub struct FontPage {
+    pixels: Vec<u8>,
+    rect_packer: RectPacker<usize>,
+    /// GPU texture of the page, it is created by renderer.
+    pub texture: Option<SharedTexture>,
+    /// A flag that tells renderer that new glyphs were added to the page and texture must be
+    /// re-uploaded.
+    pub modified: bool,
+}
+
+impl FontPage {
+    fn new(size: usize) -> Self {
+        Self {
+            pixels: vec![0; size * size],
+            rect_packer: RectPacker::new(size, size),
+            texture: None,
+            modified: true,
+        }
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+// Glyphs of a font of a particular size.
+struct FontAtlas {
     glyphs: Vec<FontGlyph>,
-    ascender: f32,
-    descender: f32,
     char_map: FxHashMap<u32, usize>,
-    atlas: Vec<u8>,
-    atlas_size: usize,
-    pub texture: Option<SharedTexture>,
+    tofu: Option<usize>,
+}
+
+pub struct Font {
+    face: Arc<fontdue::Font>,
+    fallback: Option<Arc<fontdue::Font>>,
+    height: f32,
+    // Key is bit representation of height of glyphs.
+    atlases: FxHashMap<u32, FontAtlas>,
+    pages: Vec<FontPage>,
+    page_size: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -138,49 +181,32 @@ impl Font {
         ]
     }
 
+    /// Creates new font from given TTF data. `height` is default size (in pixels) of the font, it
+    /// is used by texts that has no explicit font size. Characters from `char_set` will be
+    /// rasterized at default size up front, any other character (or size) will be rasterized
+    /// on demand.
     pub fn from_memory(
         data: Vec<u8>,
         height: f32,
         char_set: &[Range<u32>],
     ) -> Result<Self, &'static str> {
-        let fontdue_font = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())?;
-        let font_metrics = fontdue_font.horizontal_line_metrics(height).unwrap();
+        let face = fontdue::Font::from_bytes(data, fontdue::FontSettings::default())?;
 
         let mut font = Font {
+            face: Arc::new(face),
+            fallback: None,
             height,
-            glyphs: Vec::new(),
-            ascender: font_metrics.ascent,
-            descender: font_metrics.descent,
-            char_map: FxHashMap::default(),
-            atlas: Vec::new(),
-            atlas_size: 0,
-            texture: None,
+            atlases: Default::default(),
+            pages: Default::default(),
+            page_size: DEFAULT_FONT_PAGE_SIZE,
         };
 
-        let mut index = 0;
         for range in char_set {
             for unicode in range.start..range.end {
-                if let Some(character) = std::char::from_u32(unicode) {
-                    let (metrics, bitmap) = fontdue_font.rasterize(character, height);
-
-                    font.glyphs.push(FontGlyph {
-                        left: metrics.xmin as f32,
-                        top: metrics.ymin as f32,
-                        pixels: bitmap,
-                        advance: metrics.advance_width,
-                        tex_coords: Default::default(),
-                        bitmap_width: metrics.width,
-                        bitmap_height: metrics.height,
-                    });
-
-                    font.char_map.insert(unicode, index);
-                    index += 1;
-                }
+                font.glyph(unicode, height);
             }
         }
 
-        font.pack();
-
         Ok(font)
     }
 
@@ -196,101 +222,305 @@ impl Font {
         }
     }
 
-    #[inline]
-    pub fn glyph(&self, unicode: u32) -> Option<&FontGlyph> {
-        match self.char_map.get(&unicode) {
-            Some(glyph_index) => self.glyphs.get(*glyph_index),
-            None => None,
+    /// Sets a font which will be used to rasterize characters that are missing in this font. Only
+    /// glyphs of the fallback font are used, its own fallback is ignored. Characters that were
+    /// already drawn as "missing" will be re-rasterized.
+    pub fn set_fallback(&mut self, fallback: Option<&Font>) {
+        self.fallback = fallback.map(|f| f.face.clone());
+
+        for atlas in self.atlases.values_mut() {
+            if let Some(tofu) = atlas.tofu {
+                atlas.char_map.retain(|_, index| *index != tofu);
+            }
         }
     }
 
+    /// Returns a glyph of given character at given size (in pixels), rasterizes the glyph if
+    /// needed. Missing characters are represented by a box.
     #[inline]
-    pub fn glyph_index(&self, unicode: u32) -> Option<usize> {
-        self.char_map.get(&unicode).cloned()
+    pub fn glyph(&mut self, unicode: u32, height: f32) -> &FontGlyph {
+        let key = height.to_bits();
+        let index = match self
+            .atlases
+            .get(&key)
+            .and_then(|atlas| atlas.char_map.get(&unicode).cloned())
+        {
+            Some(index) => index,
+            None => self.rasterize(unicode, height),
+        };
+        &self.atlases[&key].glyphs[index]
+    }
+
+    #[inline]
+    pub fn glyph_advance(&mut self, unicode: u32, height: f32) -> f32 {
+        self.glyph(unicode, height).advance
     }
 
+    /// Returns horizontal kerning (in pixels) between two characters at given size.
     #[inline]
-    pub fn glyphs(&self) -> &[FontGlyph] {
-        &self.glyphs
+    pub fn kerning(&self, left: u32, right: u32, height: f32) -> f32 {
+        match (std::char::from_u32(left), std::char::from_u32(right)) {
+            (Some(left), Some(right)) => self
+                .face
+                .horizontal_kern(left, right, height)
+                .unwrap_or_default(),
+            _ => 0.0,
+        }
     }
 
+    /// Returns default size (in pixels) of the font.
     #[inline]
     pub fn height(&self) -> f32 {
         self.height
     }
 
     #[inline]
-    pub fn ascender(&self) -> f32 {
-        self.ascender
+    pub fn ascender(&self, height: f32) -> f32 {
+        self.face
+            .horizontal_line_metrics(height)
+            .map_or(height, |m| m.ascent)
     }
 
     #[inline]
-    pub fn descender(&self) -> f32 {
-        self.descender
+    pub fn descender(&self, height: f32) -> f32 {
+        self.face
+            .horizontal_line_metrics(height)
+            .map_or(0.0, |m| m.descent)
     }
 
     #[inline]
-    pub fn atlas_pixels(&self) -> &[u8] {
-        self.atlas.as_slice()
+    pub fn pages(&self) -> &[FontPage] {
+        &self.pages
     }
 
     #[inline]
-    pub fn atlas_size(&self) -> usize {
-        self.atlas_size
+    pub fn pages_mut(&mut self) -> &mut [FontPage] {
+        &mut self.pages
     }
 
+    /// Returns size (in pixels) of each atlas page.
     #[inline]
-    pub fn glyph_advance(&self, c: u32) -> f32 {
-        self.glyph(c).map_or(self.height(), |glyph| glyph.advance)
+    pub fn page_size(&self) -> usize {
+        self.page_size
     }
 
-    #[inline]
-    fn compute_atlas_size(&self, border: usize) -> usize {
-        let mut area = 0.0;
-        for glyph in self.glyphs.iter() {
-            area += (glyph.bitmap_width + border) as f32 * (glyph.bitmap_height + border) as f32;
-        }
-        (1.3 * area.sqrt()) as usize
-    }
-
-    fn pack(&mut self) {
-        let border = 2;
-        self.atlas_size = self.compute_atlas_size(border);
-        self.atlas = vec![0; (self.atlas_size * self.atlas_size) as usize];
-        let k = 1.0 / self.atlas_size as f32;
-        let mut rect_packer = RectPacker::new(self.atlas_size, self.atlas_size);
-        for glyph in self.glyphs.iter_mut() {
-            if let Some(bounds) =
-                rect_packer.find_free(glyph.bitmap_width + border, glyph.bitmap_height + border)
-            {
-                let bw = (bounds.w() - border) as usize;
-                let bh = (bounds.h() - border) as usize;
-                let bx = (bounds.x() + border / 2) as usize;
-                let by = (bounds.y() + border / 2) as usize;
-
-                let tw = bw as f32 * k;
-                let th = bh as f32 * k;
-                let tx = bx as f32 * k;
-                let ty = by as f32 * k;
-
-                glyph.tex_coords[0] = Vector2::new(tx, ty);
-                glyph.tex_coords[1] = Vector2::new(tx + tw, ty);
-                glyph.tex_coords[2] = Vector2::new(tx + tw, ty + th);
-                glyph.tex_coords[3] = Vector2::new(tx, ty + th);
-
-                let row_end = by + bh;
-                let col_end = bx + bw;
-
-                // Copy glyph pixels to atlas pixels
-                for (src_row, row) in (by..row_end).enumerate() {
-                    for (src_col, col) in (bx..col_end).enumerate() {
-                        self.atlas[row * self.atlas_size + col] =
-                            glyph.pixels[src_row * bw + src_col];
+    fn rasterize(&mut self, unicode: u32, height: f32) -> usize {
+        let atlas = self
+            .atlases
+            .entry(height.to_bits())
+            .or_insert_with(|| FontAtlas {
+                glyphs: Default::default(),
+                char_map: Default::default(),
+                tofu: None,
+            });
+
+        let character = std::char::from_u32(unicode).unwrap_or(std::char::REPLACEMENT_CHARACTER);
+
+        let face = if character.is_whitespace() || self.face.lookup_glyph_index(character) != 0 {
+            Some(&self.face)
+        } else {
+            self.fallback
+                .as_ref()
+                .filter(|fallback| fallback.lookup_glyph_index(character) != 0)
+        };
+
+        let (mut glyph, pixels) = if character.is_control() {
+            // Control characters (like new line) are invisible and takes no space.
+            (
+                FontGlyph {
+                    top: 0.0,
+                    left: 0.0,
+                    advance: 0.0,
+                    tex_coords: Default::default(),
+                    bitmap_width: 0,
+                    bitmap_height: 0,
+                    page_index: 0,
+                },
+                Vec::new(),
+            )
+        } else if let Some(face) = face {
+            let (metrics, pixels) = face.rasterize(character, height);
+            (
+                FontGlyph {
+                    top: metrics.ymin as f32,
+                    left: metrics.xmin as f32,
+                    advance: metrics.advance_width,
+                    tex_coords: Default::default(),
+                    bitmap_width: metrics.width,
+                    bitmap_height: metrics.height,
+                    page_index: 0,
+                },
+                pixels,
+            )
+        } else if let Some(tofu) = atlas.tofu {
+            atlas.char_map.insert(unicode, tofu);
+            return tofu;
+        } else {
+            let (glyph, pixels) = make_tofu(height);
+            atlas.tofu = Some(atlas.glyphs.len());
+            (glyph, pixels)
+        };
+
+        if glyph.bitmap_width > 0 && glyph.bitmap_height > 0 {
+            match pack(
+                &mut self.pages,
+                self.page_size,
+                glyph.bitmap_width,
+                glyph.bitmap_height,
+            ) {
+                Some((page_index, bounds)) => {
+                    let page = &mut self.pages[page_index];
+
+                    // Copy glyph pixels to page pixels.
+                    for row in 0..glyph.bitmap_height {
+                        let src = row * glyph.bitmap_width;
+                        let dest = (bounds.y() + row) * self.page_size + bounds.x();
+                        page.pixels[dest..(dest + glyph.bitmap_width)]
+                            .copy_from_slice(&pixels[src..(src + glyph.bitmap_width)]);
                     }
+                    page.modified = true;
+
+                    let k = 1.0 / self.page_size as f32;
+                    let tx = bounds.x() as f32 * k;
+                    let ty = bounds.y() as f32 * k;
+                    let tw = glyph.bitmap_width as f32 * k;
+                    let th = glyph.bitmap_height as f32 * k;
+
+                    glyph.page_index = page_index;
+                    glyph.tex_coords = [
+                        Vector2::new(tx, ty),
+                        Vector2::new(tx + tw, ty),
+                        Vector2::new(tx + tw, ty + th),
+                        Vector2::new(tx, ty + th),
+                    ];
                 }
-            } else {
-                println!("Insufficient atlas size!");
+                None => {
+                    log_warn!(
+                        "Glyph {}x{} does not fit into font atlas page!",
+                        glyph.bitmap_width,
+                        glyph.bitmap_height
+                    );
+                }
+            }
+        }
+
+        let index = atlas.glyphs.len();
+        atlas.glyphs.push(glyph);
+        atlas.char_map.insert(unicode, index);
+        index
+    }
+}
+
+// Makes an outlined box, that is used for characters missing in the font.
+fn make_tofu(height: f32) -> (FontGlyph, Vec<u8>) {
+    let width = ((height * 0.5).round() as usize).max(3);
+    let bitmap_height = ((height * 0.7).round() as usize).max(3);
+    let mut pixels = vec![0; width * bitmap_height];
+    for y in 0..bitmap_height {
+        for x in 0..width {
+            if x == 0 || y == 0 || x == width - 1 || y == bitmap_height - 1 {
+                pixels[y * width + x] = 255;
+            }
+        }
+    }
+    (
+        FontGlyph {
+            top: 0.0,
+            left: 1.0,
+            advance: width as f32 + 2.0,
+            tex_coords: Default::default(),
+            bitmap_width: width,
+            bitmap_height,
+            page_index: 0,
+        },
+        pixels,
+    )
+}
+
+// Finds a place for a glyph of given size, adds new page if there is no space left on last page.
+fn pack(
+    pages: &mut Vec<FontPage>,
+    page_size: usize,
+    width: usize,
+    height: usize,
+) -> Option<(usize, Rect<usize>)> {
+    // Leave some space between glyphs to prevent bleeding of neighbour glyphs when filtering.
+    let border = 2;
+
+    if let Some(page) = pages.last_mut() {
+        if let Some(bounds) = page.rect_packer.find_free(width + border, height + border) {
+            return Some((
+                pages.len() - 1,
+                Rect::new(
+                    bounds.x() + border / 2,
+                    bounds.y() + border / 2,
+                    width,
+                    height,
+                ),
+            ));
+        }
+    }
+
+    if width + border > page_size || height + border > page_size {
+        return None;
+    }
+
+    let mut page = FontPage::new(page_size);
+    let bounds = page
+        .rect_packer
+        .find_free(width + border, height + border)?;
+    pages.push(page);
+    Some((
+        pages.len() - 1,
+        Rect::new(
+            bounds.x() + border / 2,
+            bounds.y() + border / 2,
+            width,
+            height,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ttf::Font;
+
+    fn default_font() -> Font {
+        let font_bytes = std::include_bytes!("./built_in_font.ttf").to_vec();
+        Font::from_memory(font_bytes, 16.0, Font::default_char_set()).unwrap()
+    }
+
+    #[test]
+    fn test_glyphs_of_multiple_sizes() {
+        let mut font = default_font();
+        let small = font.glyph('W' as u32, 16.0).clone();
+        let large = font.glyph('W' as u32, 32.0).clone();
+        assert!(large.advance > small.advance);
+        assert!(large.bitmap_height > small.bitmap_height);
+        assert!(font.ascender(32.0) > font.ascender(16.0));
+    }
+
+    #[test]
+    fn test_missing_glyph_is_tofu() {
+        let mut font = default_font();
+        // Built-in font has no CJK characters.
+        let a = font.glyph(0x4E2D, 16.0).clone();
+        let b = font.glyph(0x6587, 16.0).clone();
+        assert!(a.bitmap_width > 0 && a.bitmap_height > 0);
+        assert_eq!(a.tex_coords, b.tex_coords);
+        // Control characters are invisible.
+        assert_eq!(font.glyph('\n' as u32, 16.0).bitmap_width, 0);
+    }
+
+    #[test]
+    fn test_glyphs_spill_to_new_page() {
+        let mut font = default_font();
+        let pages = font.pages().len();
+        for size in 1..80 {
+            for c in 'A'..='Z' {
+                font.glyph(c as u32, 20.0 + size as f32);
             }
         }
+        assert!(font.pages().len() > pages);
     }
 }
@@ -1,6 +1,7 @@
 use crate::{
     border::BorderBuilder,
     core::{algebra::Vector2, pool::Handle},
+    draw::Path,
     formatted_text::WrapMode,
     text::TextBuilder,
     vector_image::{Primitive, VectorImageBuilder},
@@ -27,35 +28,26 @@ pub fn make_arrow(
             .with_horizontal_alignment(HorizontalAlignment::Center)
             .with_vertical_alignment(VerticalAlignment::Center),
     )
-    .with_primitives(vec![match orientation {
-        ArrowDirection::Top => Primitive::Triangle {
-            points: [
-                Vector2::new(size * 0.5, 0.0),
-                Vector2::new(size, size),
-                Vector2::new(0.0, size),
-            ],
-        },
-        ArrowDirection::Bottom => Primitive::Triangle {
-            points: [
-                Vector2::new(0.0, 0.0),
-                Vector2::new(size, 0.0),
-                Vector2::new(size * 0.5, size),
-            ],
-        },
-        ArrowDirection::Right => Primitive::Triangle {
-            points: [
-                Vector2::new(0.0, 0.0),
-                Vector2::new(size, size * 0.5),
-                Vector2::new(0.0, size),
-            ],
-        },
-        ArrowDirection::Left => Primitive::Triangle {
-            points: [
-                Vector2::new(0.0, size * 0.5),
-                Vector2::new(size, 0.0),
-                Vector2::new(size, size),
-            ],
-        },
+    .with_primitives(vec![Primitive::FilledPath {
+        path: match orientation {
+            ArrowDirection::Top => Path::new()
+                .move_to(Vector2::new(size * 0.5, 0.0))
+                .line_to(Vector2::new(size, size))
+                .line_to(Vector2::new(0.0, size)),
+            ArrowDirection::Bottom => Path::new()
+                .move_to(Vector2::new(0.0, 0.0))
+                .line_to(Vector2::new(size, 0.0))
+                .line_to(Vector2::new(size * 0.5, size)),
+            ArrowDirection::Right => Path::new()
+                .move_to(Vector2::new(0.0, 0.0))
+                .line_to(Vector2::new(size, size * 0.5))
+                .line_to(Vector2::new(0.0, size)),
+            ArrowDirection::Left => Path::new()
+                .move_to(Vector2::new(0.0, size * 0.5))
+                .line_to(Vector2::new(size, 0.0))
+                .line_to(Vector2::new(size, size)),
+        }
+        .close(),
     }])
     .build(ctx)
 }
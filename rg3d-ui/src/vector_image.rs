@@ -1,6 +1,7 @@
 use crate::{
+    core::math::Rect,
     core::{algebra::Vector2, color::Color, math::Vector2Ext, pool::Handle},
-    draw::{CommandTexture, Draw, DrawingContext},
+    draw::{CommandTexture, Draw, DrawingContext, Path},
     message::UiMessage,
     widget::{Widget, WidgetBuilder},
     BuildContext, Control, UiNode, UserInterface,
@@ -25,6 +26,20 @@ pub enum Primitive {
         radius: f32,
         segments: usize,
     },
+    /// Filled contours of a path, edges are anti-aliased.
+    FilledPath {
+        path: Path,
+    },
+    /// Lines along contours of a path, edges are anti-aliased.
+    StrokedPath {
+        path: Path,
+        thickness: f32,
+    },
+    /// Filled rectangle with rounded corners, edges are anti-aliased.
+    RoundedRectangle {
+        rect: Rect<f32>,
+        corner_radius: f32,
+    },
 }
 
 fn line_thickness_vector(a: Vector2<f32>, b: Vector2<f32>, thickness: f32) -> Vector2<f32> {
@@ -65,6 +80,15 @@ impl Primitive {
                 let radius = Vector2::new(*radius, *radius);
                 (center - radius, center + radius)
             }
+            Primitive::FilledPath { path } => path.bounds(),
+            Primitive::StrokedPath { path, thickness } => {
+                let (min, max) = path.bounds();
+                let offset = Vector2::new(*thickness * 0.5, *thickness * 0.5);
+                (min - offset, max + offset)
+            }
+            Primitive::RoundedRectangle { rect, .. } => {
+                (rect.left_top_corner(), rect.right_bottom_corner())
+            }
         }
     }
 }
@@ -73,6 +97,7 @@ impl Primitive {
 pub struct VectorImage {
     widget: Widget,
     primitives: Vec<Primitive>,
+    feather: f32,
 }
 
 crate::define_widget_deref!(VectorImage);
@@ -138,6 +163,27 @@ impl Control for VectorImage {
                     *segments,
                     Color::WHITE,
                 ),
+                Primitive::FilledPath { path } => {
+                    drawing_context
+                        .push_path_filled(&path.translated(bounds.position), self.feather);
+                }
+                Primitive::StrokedPath { path, thickness } => {
+                    drawing_context.push_path_stroked(
+                        &path.translated(bounds.position),
+                        *thickness,
+                        self.feather,
+                    );
+                }
+                Primitive::RoundedRectangle {
+                    rect,
+                    corner_radius,
+                } => {
+                    drawing_context.push_rounded_rect_filled(
+                        &rect.translate(bounds.position),
+                        *corner_radius,
+                        self.feather,
+                    );
+                }
             }
         }
         drawing_context.commit(
@@ -156,6 +202,7 @@ impl Control for VectorImage {
 pub struct VectorImageBuilder {
     widget_builder: WidgetBuilder,
     primitives: Vec<Primitive>,
+    feather: f32,
 }
 
 impl VectorImageBuilder {
@@ -163,6 +210,7 @@ impl VectorImageBuilder {
         Self {
             widget_builder,
             primitives: Default::default(),
+            feather: 1.0,
         }
     }
 
@@ -171,10 +219,18 @@ impl VectorImageBuilder {
         self
     }
 
+    /// Sets width of anti-aliased edges of paths and rounded rectangles, 0.0 disables
+    /// anti-aliasing. Default is 1.0.
+    pub fn with_feather(mut self, feather: f32) -> Self {
+        self.feather = feather.max(0.0);
+        self
+    }
+
     pub fn build_node(self) -> UiNode {
         let image = VectorImage {
             widget: self.widget_builder.build(),
             primitives: self.primitives,
+            feather: self.feather,
         };
         UiNode::new(image)
     }
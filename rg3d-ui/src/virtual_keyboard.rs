@@ -0,0 +1,542 @@
+//! On-screen keyboard for touch screens. It types into a widget that has keyboard focus by sending
+//! the same messages as a physical keyboard ([`WidgetMessage::Text`], [`WidgetMessage::KeyDown`]
+//! and [`WidgetMessage::KeyUp`]), so any widget that accepts text input works with it.
+//!
+//! The keyboard can't take keyboard focus (see [`WidgetMessage::Focusable`]), so pressing its keys
+//! does not interrupt editing. It has basic QWERTY layout with shift and a page with digits and
+//! punctuation. By default the keyboard opens when a [`TextBox`] gets focus by touch and closes
+//! when the text box loses focus, use [`VirtualKeyboardBuilder::with_auto_open`] to control it
+//! manually by [`VirtualKeyboardMessage::Open`] and [`VirtualKeyboardMessage::Close`] messages.
+//! Positioning is up to the user, usually the keyboard is placed at the bottom of the screen.
+
+use crate::{
+    button::{ButtonBuilder, ButtonMessage},
+    core::{algebra::Vector2, pool::Handle},
+    define_constructor,
+    message::{KeyCode, MessageDirection, UiMessage},
+    stack_panel::StackPanelBuilder,
+    text_box::TextBox,
+    widget::{Widget, WidgetBuilder, WidgetMessage},
+    BuildContext, Control, HorizontalAlignment, NodeHandleMapping, Orientation, Thickness, UiNode,
+    UserInterface,
+};
+use std::{
+    any::{Any, TypeId},
+    ops::{Deref, DerefMut},
+};
+
+/// A key of [`VirtualKeyboard`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VirtualKey {
+    /// Types given character.
+    Char(char),
+    /// Switches between lower and upper case letters, upper case is active only for one key.
+    Shift,
+    Backspace,
+    Enter,
+    Space,
+    /// Switches between letters and numeric pages.
+    SwitchPage,
+    /// Closes the keyboard.
+    Hide,
+}
+
+/// A page of [`VirtualKeyboard`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum KeyboardPage {
+    Letters,
+    Numeric,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VirtualKeyboardMessage {
+    /// Shows the keyboard on top of other widgets.
+    ///
+    /// Direction: **From/To UI**.
+    Open,
+
+    /// Hides the keyboard.
+    ///
+    /// Direction: **From/To UI**.
+    Close,
+
+    /// Switches current page of the keyboard.
+    ///
+    /// Direction: **From/To UI**.
+    Page(KeyboardPage),
+
+    /// Sets state of shift key.
+    ///
+    /// Direction: **From/To UI**.
+    Shift(bool),
+
+    /// Initiated when user has pressed a key, the keyboard has already sent input to the focused
+    /// widget at this moment.
+    ///
+    /// Direction: **From UI**.
+    Key(VirtualKey),
+}
+
+impl VirtualKeyboardMessage {
+    define_constructor!(VirtualKeyboardMessage:Open => fn open(), layout: false);
+    define_constructor!(VirtualKeyboardMessage:Close => fn close(), layout: false);
+    define_constructor!(VirtualKeyboardMessage:Page => fn page(KeyboardPage), layout: false);
+    define_constructor!(VirtualKeyboardMessage:Shift => fn shift(bool), layout: false);
+    define_constructor!(VirtualKeyboardMessage:Key => fn key(VirtualKey), layout: false);
+}
+
+/// See module docs.
+#[derive(Clone)]
+pub struct VirtualKeyboard {
+    widget: Widget,
+    keys: Vec<(Handle<UiNode>, VirtualKey)>,
+    lower_case_letters: Handle<UiNode>,
+    upper_case_letters: Handle<UiNode>,
+    numeric: Handle<UiNode>,
+    page: KeyboardPage,
+    shift: bool,
+    auto_open: bool,
+}
+
+crate::define_widget_deref!(VirtualKeyboard);
+
+impl VirtualKeyboard {
+    pub fn page(&self) -> KeyboardPage {
+        self.page
+    }
+
+    pub fn is_shift_active(&self) -> bool {
+        self.shift
+    }
+
+    fn sync_pages(&self, ui: &UserInterface) {
+        for (panel, visibility) in [
+            (
+                self.lower_case_letters,
+                self.page == KeyboardPage::Letters && !self.shift,
+            ),
+            (
+                self.upper_case_letters,
+                self.page == KeyboardPage::Letters && self.shift,
+            ),
+            (self.numeric, self.page == KeyboardPage::Numeric),
+        ] {
+            ui.send_message(WidgetMessage::visibility(
+                panel,
+                MessageDirection::ToWidget,
+                visibility,
+            ));
+        }
+    }
+
+    fn press_key(&self, key: VirtualKey, ui: &UserInterface) {
+        let focus = ui.keyboard_focus_node();
+
+        let send_text = |symbol| {
+            if focus.is_some() {
+                ui.send_message(WidgetMessage::text(
+                    focus,
+                    MessageDirection::FromWidget,
+                    symbol,
+                ));
+            }
+        };
+        let send_key = |code| {
+            if focus.is_some() {
+                ui.send_message(WidgetMessage::key_down(
+                    focus,
+                    MessageDirection::FromWidget,
+                    code,
+                ));
+                ui.send_message(WidgetMessage::key_up(
+                    focus,
+                    MessageDirection::FromWidget,
+                    code,
+                ));
+            }
+        };
+
+        match key {
+            VirtualKey::Char(symbol) => {
+                send_text(symbol);
+                if self.shift {
+                    ui.send_message(VirtualKeyboardMessage::shift(
+                        self.handle,
+                        MessageDirection::ToWidget,
+                        false,
+                    ));
+                }
+            }
+            VirtualKey::Space => send_text(' '),
+            VirtualKey::Backspace => send_key(KeyCode::Backspace),
+            VirtualKey::Enter => send_key(KeyCode::Return),
+            VirtualKey::Shift => ui.send_message(VirtualKeyboardMessage::shift(
+                self.handle,
+                MessageDirection::ToWidget,
+                !self.shift,
+            )),
+            VirtualKey::SwitchPage => ui.send_message(VirtualKeyboardMessage::page(
+                self.handle,
+                MessageDirection::ToWidget,
+                match self.page {
+                    KeyboardPage::Letters => KeyboardPage::Numeric,
+                    KeyboardPage::Numeric => KeyboardPage::Letters,
+                },
+            )),
+            VirtualKey::Hide => ui.send_message(VirtualKeyboardMessage::close(
+                self.handle,
+                MessageDirection::ToWidget,
+            )),
+        }
+
+        ui.send_message(VirtualKeyboardMessage::key(
+            self.handle,
+            MessageDirection::FromWidget,
+            key,
+        ));
+    }
+}
+
+impl Control for VirtualKeyboard {
+    fn query_component(&self, type_id: TypeId) -> Option<&dyn Any> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&mut self, node_map: &NodeHandleMapping) {
+        for (key, _) in self.keys.iter_mut() {
+            node_map.resolve(key);
+        }
+        node_map.resolve(&mut self.lower_case_letters);
+        node_map.resolve(&mut self.upper_case_letters);
+        node_map.resolve(&mut self.numeric);
+    }
+
+    fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
+        self.widget.handle_routed_message(ui, message);
+
+        if let Some(ButtonMessage::Click) = message.data::<ButtonMessage>() {
+            if let Some(&(_, key)) = self
+                .keys
+                .iter()
+                .find(|(button, _)| *button == message.destination())
+            {
+                self.press_key(key, ui);
+            }
+        } else if let Some(msg) = message.data::<VirtualKeyboardMessage>() {
+            if message.destination() == self.handle
+                && message.direction() == MessageDirection::ToWidget
+            {
+                match *msg {
+                    VirtualKeyboardMessage::Open => {
+                        ui.send_message(WidgetMessage::visibility(
+                            self.handle,
+                            MessageDirection::ToWidget,
+                            true,
+                        ));
+                        ui.send_message(WidgetMessage::topmost(
+                            self.handle,
+                            MessageDirection::ToWidget,
+                        ));
+                    }
+                    VirtualKeyboardMessage::Close => {
+                        ui.send_message(WidgetMessage::visibility(
+                            self.handle,
+                            MessageDirection::ToWidget,
+                            false,
+                        ));
+                    }
+                    VirtualKeyboardMessage::Page(page) => {
+                        self.page = page;
+                        self.shift = false;
+                        self.sync_pages(ui);
+                    }
+                    VirtualKeyboardMessage::Shift(shift) => {
+                        self.shift = shift;
+                        self.sync_pages(ui);
+                    }
+                    VirtualKeyboardMessage::Key(_) => (),
+                }
+                ui.send_message(message.reverse());
+            }
+        }
+    }
+
+    fn preview_message(&self, ui: &UserInterface, message: &mut UiMessage) {
+        if !self.auto_open || message.direction() != MessageDirection::FromWidget {
+            return;
+        }
+
+        if let Some(msg) = message.data::<WidgetMessage>() {
+            let is_text_box = ui
+                .try_get_node(message.destination())
+                .and_then(|node| node.cast::<TextBox>())
+                .map_or(false, |text_box| text_box.is_editable());
+            if !is_text_box {
+                return;
+            }
+
+            match msg {
+                WidgetMessage::GotFocus if ui.is_touch_input_active() => {
+                    ui.send_message(VirtualKeyboardMessage::open(
+                        self.handle,
+                        MessageDirection::ToWidget,
+                    ));
+                }
+                WidgetMessage::LostFocus => {
+                    ui.send_message(VirtualKeyboardMessage::close(
+                        self.handle,
+                        MessageDirection::ToWidget,
+                    ));
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+pub struct VirtualKeyboardBuilder {
+    widget_builder: WidgetBuilder,
+    key_size: f32,
+    auto_open: bool,
+}
+
+impl VirtualKeyboardBuilder {
+    pub fn new(widget_builder: WidgetBuilder) -> Self {
+        Self {
+            widget_builder,
+            key_size: 40.0,
+            auto_open: true,
+        }
+    }
+
+    /// Sets size of a regular key, special keys are wider. Default is 40.
+    pub fn with_key_size(mut self, key_size: f32) -> Self {
+        self.key_size = key_size;
+        self
+    }
+
+    /// Sets whether the keyboard should open when a text box gets focus by touch and close when
+    /// the text box loses focus. Default is `true`.
+    pub fn with_auto_open(mut self, auto_open: bool) -> Self {
+        self.auto_open = auto_open;
+        self
+    }
+
+    fn build_page(
+        &self,
+        rows: Vec<Vec<(String, VirtualKey)>>,
+        visibility: bool,
+        keys: &mut Vec<(Handle<UiNode>, VirtualKey)>,
+        ctx: &mut BuildContext,
+    ) -> Handle<UiNode> {
+        let rows = rows
+            .into_iter()
+            .map(|row| {
+                let buttons = row
+                    .into_iter()
+                    .map(|(label, key)| {
+                        let width = match key {
+                            VirtualKey::Char(_) => self.key_size,
+                            VirtualKey::Space => self.key_size * 5.0,
+                            _ => self.key_size * 1.5,
+                        };
+                        let button = ButtonBuilder::new(
+                            WidgetBuilder::new()
+                                .with_width(width)
+                                .with_height(self.key_size)
+                                .with_margin(Thickness::uniform(2.0)),
+                        )
+                        .with_text(&label)
+                        .build(ctx);
+                        keys.push((button, key));
+                        button
+                    })
+                    .collect::<Vec<_>>();
+
+                StackPanelBuilder::new(
+                    WidgetBuilder::new()
+                        .with_horizontal_alignment(HorizontalAlignment::Center)
+                        .with_children(buttons),
+                )
+                .with_orientation(Orientation::Horizontal)
+                .build(ctx)
+            })
+            .collect::<Vec<_>>();
+
+        StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(visibility)
+                .with_children(rows),
+        )
+        .build(ctx)
+    }
+
+    pub fn build(self, ctx: &mut BuildContext) -> Handle<UiNode> {
+        // Every page has three rows of characters surrounded by the same special keys.
+        let make_rows = |characters: [&str; 3], switch_label: &str| {
+            let mut rows = characters
+                .iter()
+                .map(|row| {
+                    row.chars()
+                        .map(|c| (c.to_string(), VirtualKey::Char(c)))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            rows[2].insert(0, ("Shift".to_owned(), VirtualKey::Shift));
+            rows[2].push(("Back".to_owned(), VirtualKey::Backspace));
+            rows.push(vec![
+                (switch_label.to_owned(), VirtualKey::SwitchPage),
+                (String::new(), VirtualKey::Space),
+                ("Enter".to_owned(), VirtualKey::Enter),
+                ("Hide".to_owned(), VirtualKey::Hide),
+            ]);
+            rows
+        };
+
+        let mut keys = Vec::new();
+        let pages = [
+            (
+                make_rows(["qwertyuiop", "asdfghjkl", "zxcvbnm"], "123"),
+                true,
+            ),
+            (
+                make_rows(["QWERTYUIOP", "ASDFGHJKL", "ZXCVBNM"], "123"),
+                false,
+            ),
+            (
+                make_rows(["1234567890", "-/:;()$&@\"", ".,?!'"], "ABC"),
+                false,
+            ),
+        ]
+        .into_iter()
+        .map(|(rows, visibility)| self.build_page(rows, visibility, &mut keys, ctx))
+        .collect::<Vec<_>>();
+
+        let keyboard = VirtualKeyboard {
+            widget: self
+                .widget_builder
+                .with_focusable(false)
+                .with_preview_messages(true)
+                .with_min_size(Vector2::new(self.key_size, self.key_size))
+                .with_children(pages.iter().cloned())
+                .build(),
+            keys,
+            lower_case_letters: pages[0],
+            upper_case_letters: pages[1],
+            numeric: pages[2],
+            page: KeyboardPage::Letters,
+            shift: false,
+            auto_open: self.auto_open,
+        };
+
+        ctx.add_node(UiNode::new(keyboard))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        message::{ButtonState, MouseButton, OsEvent, TouchPhase},
+        text_box::{TextBox, TextBoxBuilder},
+        virtual_keyboard::{VirtualKey, VirtualKeyboard, VirtualKeyboardBuilder},
+        widget::WidgetBuilder,
+        UserInterface,
+    };
+
+    #[test]
+    fn test_typing_by_touch() {
+        let screen_size = Vector2::new(1000.0, 1000.0);
+        let mut ui = UserInterface::new(screen_size);
+        let text_box = TextBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_width(200.0)
+                .with_height(30.0)
+                .with_desired_position(Vector2::new(0.0, 0.0)),
+        )
+        .build(&mut ui.build_ctx());
+        let keyboard = VirtualKeyboardBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_desired_position(Vector2::new(0.0, 500.0)),
+        )
+        .build(&mut ui.build_ctx());
+
+        let update = |ui: &mut UserInterface| {
+            while ui.poll_message().is_some() {}
+            ui.update(screen_size, 0.0);
+            ui.draw();
+        };
+        let tap = |ui: &mut UserInterface, position: Vector2<f32>| {
+            for phase in [TouchPhase::Started, TouchPhase::Ended] {
+                ui.process_os_event(&OsEvent::Touch {
+                    id: 0,
+                    phase,
+                    position,
+                });
+            }
+            update(ui);
+        };
+        let key_center = |ui: &UserInterface, key: VirtualKey| {
+            let keyboard = ui.node(keyboard).cast::<VirtualKeyboard>().unwrap();
+            let button = keyboard
+                .keys
+                .iter()
+                .find(|(button, k)| *k == key && ui.node(*button).is_globally_visible())
+                .unwrap()
+                .0;
+            let bounds = ui.node(button).screen_bounds();
+            bounds.position + bounds.size.scale(0.5)
+        };
+        update(&mut ui);
+
+        let click = |ui: &mut UserInterface, position| {
+            ui.process_os_event(&OsEvent::CursorMoved { position });
+            for state in [ButtonState::Pressed, ButtonState::Released] {
+                ui.process_os_event(&OsEvent::MouseInput {
+                    button: MouseButton::Left,
+                    state,
+                });
+            }
+            update(ui);
+        };
+
+        // Mouse click does not open the keyboard.
+        click(&mut ui, Vector2::new(10.0, 10.0));
+        assert_eq!(ui.keyboard_focus_node(), text_box);
+        assert!(!ui.is_touch_input_active());
+        assert!(!ui.node(keyboard).visibility());
+
+        // Touch opens it, focus stays in the text box while typing.
+        click(&mut ui, Vector2::new(10.0, 300.0));
+        assert!(ui.keyboard_focus_node().is_none());
+        tap(&mut ui, Vector2::new(10.0, 10.0));
+        assert!(ui.is_touch_input_active());
+        assert!(ui.node(keyboard).visibility());
+
+        for key in [VirtualKey::Shift, VirtualKey::Char('H')] {
+            let position = key_center(&ui, key);
+            tap(&mut ui, position);
+        }
+        for key in [
+            VirtualKey::Char('i'),
+            VirtualKey::Char('x'),
+            VirtualKey::Backspace,
+            VirtualKey::SwitchPage,
+            VirtualKey::Char('!'),
+        ] {
+            let position = key_center(&ui, key);
+            tap(&mut ui, position);
+        }
+        assert_eq!(ui.keyboard_focus_node(), text_box);
+        assert_eq!(ui.node(text_box).cast::<TextBox>().unwrap().text(), "Hi!");
+
+        let position = key_center(&ui, VirtualKey::Hide);
+        tap(&mut ui, position);
+        assert!(!ui.node(keyboard).visibility());
+    }
+}
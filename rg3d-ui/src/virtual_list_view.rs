@@ -0,0 +1,754 @@
+//! Virtual list view is a list view for large collections of items. Unlike [`crate::list_view::ListView`],
+//! it does not own item widgets - it owns a *source* of items (an item count and a closure that
+//! builds a widget for an item index) and creates widgets only for visible items plus a small buffer
+//! around them. Widgets of items that were scrolled out of view are recycled if an updater closure
+//! was provided, or removed otherwise.
+//!
+//! Items are identified by their indices, the data itself is stored on user side, the list view must
+//! be notified about every change of the data using [`VirtualListViewMessage`]. Insertion, removal
+//! or update of an item does not affect widgets of other items.
+
+use crate::{
+    border::BorderBuilder,
+    canvas::CanvasBuilder,
+    core::{algebra::Vector2, pool::Handle},
+    decorator::{DecoratorBuilder, DecoratorMessage},
+    define_constructor,
+    grid::{Column, GridBuilder, Row},
+    message::{MessageDirection, UiMessage},
+    scroll_bar::{ScrollBarBuilder, ScrollBarMessage},
+    widget::{Widget, WidgetBuilder, WidgetMessage},
+    BuildContext, Control, NodeHandleMapping, Orientation, Thickness, UiNode, UserInterface,
+    BRUSH_DARK, BRUSH_LIGHT,
+};
+use std::{
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    ops::{Deref, DerefMut, Range},
+    rc::Rc,
+};
+
+/// Builds a widget for an item with given index.
+pub type ItemGenerator = dyn FnMut(usize, &mut BuildContext) -> Handle<UiNode>;
+
+/// Updates a recycled item widget (which was built by [`ItemGenerator`]) to show an item with
+/// given index. The widget should be updated by sending messages to it.
+pub type ItemUpdater = dyn FnMut(usize, Handle<UiNode>, &UserInterface);
+
+/// Returns height of an item with given index.
+pub type ItemHeightProvider = dyn FnMut(usize) -> f32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VirtualListViewMessage {
+    SelectionChanged(Option<usize>),
+    /// Sets new amount of items. All item widgets are re-created (or updated if there is an
+    /// updater), use the message when the data was completely changed.
+    ItemCount(usize),
+    /// Notifies the list view that an item was inserted in the data at given index.
+    InsertItem(usize),
+    /// Notifies the list view that an item was removed from the data at given index.
+    RemoveItem(usize),
+    /// Notifies the list view that an item at given index was changed.
+    UpdateItem(usize),
+    /// Scrolls the list view so the item with given index will be fully visible.
+    BringIntoView(usize),
+    // Private, do not use. For internal needs only. Re-creates items when size of the list view
+    // has changed.
+    Relayout,
+}
+
+impl VirtualListViewMessage {
+    define_constructor!(VirtualListViewMessage:SelectionChanged => fn selection(Option<usize>), layout: false);
+    define_constructor!(VirtualListViewMessage:ItemCount => fn item_count(usize), layout: false);
+    define_constructor!(VirtualListViewMessage:InsertItem => fn insert_item(usize), layout: false);
+    define_constructor!(VirtualListViewMessage:RemoveItem => fn remove_item(usize), layout: false);
+    define_constructor!(VirtualListViewMessage:UpdateItem => fn update_item(usize), layout: false);
+    define_constructor!(VirtualListViewMessage:BringIntoView => fn bring_into_view(usize), layout: false);
+    define_constructor!(VirtualListViewMessage:Relayout => fn relayout(), layout: false);
+}
+
+/// Defines height of items.
+#[derive(Clone)]
+pub enum ItemHeight {
+    /// Every item has the same height. This is the fastest option.
+    Fixed(f32),
+    /// Every item has its own height, the provider is called once per item when the item is added
+    /// or updated.
+    Variable(Rc<RefCell<ItemHeightProvider>>),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct RealizedItem {
+    // Decorator that holds user widget, it is used for selection and hit testing.
+    container: Handle<UiNode>,
+    item: Handle<UiNode>,
+}
+
+#[derive(Clone)]
+pub struct VirtualListView {
+    widget: Widget,
+    panel: Handle<UiNode>,
+    scroll_bar: Handle<UiNode>,
+    item_count: usize,
+    item_height: ItemHeight,
+    // Top positions of items with one extra element at the end which is total height of items.
+    // Used only for variable height.
+    offsets: Vec<f32>,
+    generator: Rc<RefCell<ItemGenerator>>,
+    updater: Option<Rc<RefCell<ItemUpdater>>>,
+    selected_index: Option<usize>,
+    scroll_offset: f32,
+    viewport_size: Cell<Vector2<f32>>,
+    buffer_size: usize,
+    realized: BTreeMap<usize, RealizedItem>,
+    recycled: Vec<RealizedItem>,
+}
+
+crate::define_widget_deref!(VirtualListView);
+
+impl VirtualListView {
+    pub fn selected(&self) -> Option<usize> {
+        self.selected_index
+    }
+
+    pub fn item_count(&self) -> usize {
+        self.item_count
+    }
+
+    /// Returns handle of a widget of an item with given index, or `Handle::NONE` if the item is not
+    /// realized (it is out of view).
+    pub fn item_widget(&self, index: usize) -> Handle<UiNode> {
+        self.realized
+            .get(&index)
+            .map_or(Handle::NONE, |realized| realized.item)
+    }
+
+    /// Returns range of indices of items that currently have widgets.
+    pub fn realized_range(&self) -> Range<usize> {
+        match (
+            self.realized.keys().next(),
+            self.realized.keys().next_back(),
+        ) {
+            (Some(&first), Some(&last)) => first..last + 1,
+            _ => 0..0,
+        }
+    }
+
+    fn item_top(&self, index: usize) -> f32 {
+        match self.item_height {
+            ItemHeight::Fixed(height) => index as f32 * height,
+            ItemHeight::Variable(_) => self.offsets[index],
+        }
+    }
+
+    fn item_height(&self, index: usize) -> f32 {
+        match self.item_height {
+            ItemHeight::Fixed(height) => height,
+            ItemHeight::Variable(_) => self.offsets[index + 1] - self.offsets[index],
+        }
+    }
+
+    fn total_height(&self) -> f32 {
+        self.item_top(self.item_count)
+    }
+
+    // Returns index of an item at given vertical position, position can be outside of items.
+    fn index_at(&self, y: f32) -> usize {
+        let index = match self.item_height {
+            ItemHeight::Fixed(height) => {
+                if height > 0.0 {
+                    (y.max(0.0) / height) as usize
+                } else {
+                    0
+                }
+            }
+            ItemHeight::Variable(_) => self.offsets.partition_point(|&top| top <= y).max(1) - 1,
+        };
+        index.min(self.item_count.saturating_sub(1))
+    }
+
+    fn visible_range(&self) -> Range<usize> {
+        if self.item_count == 0 {
+            return 0..0;
+        }
+        let viewport = self.viewport_size.get();
+        let first = self.index_at(self.scroll_offset);
+        let last = self.index_at(self.scroll_offset + viewport.y);
+        first.saturating_sub(self.buffer_size)..(last + 1 + self.buffer_size).min(self.item_count)
+    }
+
+    fn rebuild_offsets(&mut self, heights: impl Iterator<Item = f32>) {
+        self.offsets.clear();
+        let mut top = 0.0;
+        self.offsets.push(top);
+        for height in heights {
+            top += height;
+            self.offsets.push(top);
+        }
+    }
+
+    fn heights(&self) -> Vec<f32> {
+        self.offsets.windows(2).map(|w| w[1] - w[0]).collect()
+    }
+
+    fn height_of(&self, index: usize) -> f32 {
+        match self.item_height {
+            ItemHeight::Fixed(height) => height,
+            ItemHeight::Variable(ref provider) => {
+                let provider = &mut *provider.borrow_mut();
+                provider(index)
+            }
+        }
+    }
+
+    fn release(&mut self, realized: RealizedItem, ui: &UserInterface) {
+        if self.updater.is_some() {
+            ui.send_message(WidgetMessage::visibility(
+                realized.container,
+                MessageDirection::ToWidget,
+                false,
+            ));
+            self.recycled.push(realized);
+        } else {
+            ui.send_message(WidgetMessage::remove(
+                realized.container,
+                MessageDirection::ToWidget,
+            ));
+        }
+    }
+
+    fn release_all(&mut self, ui: &UserInterface) {
+        for (_, realized) in std::mem::take(&mut self.realized) {
+            self.release(realized, ui);
+        }
+    }
+
+    fn acquire(&mut self, index: usize, ui: &mut UserInterface) -> RealizedItem {
+        if let Some(realized) = self.recycled.pop() {
+            ui.send_message(WidgetMessage::visibility(
+                realized.container,
+                MessageDirection::ToWidget,
+                true,
+            ));
+            if let Some(updater) = self.updater.as_ref() {
+                let updater = &mut *updater.borrow_mut();
+                updater(index, realized.item, ui);
+            }
+            realized
+        } else {
+            let ctx = &mut ui.build_ctx();
+            let generator = &mut *self.generator.borrow_mut();
+            let item = generator(index, ctx);
+            let container =
+                DecoratorBuilder::new(BorderBuilder::new(WidgetBuilder::new().with_child(item)))
+                    .build(ctx);
+            ui.send_message(WidgetMessage::link(
+                container,
+                MessageDirection::ToWidget,
+                self.panel,
+            ));
+            RealizedItem { container, item }
+        }
+    }
+
+    // Creates widgets for visible items and moves them to their positions.
+    fn realize(&mut self, ui: &mut UserInterface) {
+        let range = self.visible_range();
+
+        let out_of_view = self
+            .realized
+            .keys()
+            .filter(|index| !range.contains(index))
+            .cloned()
+            .collect::<Vec<_>>();
+        for index in out_of_view {
+            let realized = self.realized.remove(&index).unwrap();
+            self.release(realized, ui);
+        }
+
+        let width = self.viewport_size.get().x;
+        for index in range {
+            let realized = match self.realized.get(&index) {
+                Some(realized) => *realized,
+                None => {
+                    let realized = self.acquire(index, ui);
+                    ui.send_message(DecoratorMessage::select(
+                        realized.container,
+                        MessageDirection::ToWidget,
+                        self.selected_index == Some(index),
+                    ));
+                    self.realized.insert(index, realized);
+                    realized
+                }
+            };
+
+            ui.send_message(WidgetMessage::desired_position(
+                realized.container,
+                MessageDirection::ToWidget,
+                Vector2::new(0.0, self.item_top(index) - self.scroll_offset),
+            ));
+            ui.send_message(WidgetMessage::width(
+                realized.container,
+                MessageDirection::ToWidget,
+                width,
+            ));
+            ui.send_message(WidgetMessage::height(
+                realized.container,
+                MessageDirection::ToWidget,
+                self.item_height(index),
+            ));
+        }
+    }
+
+    fn refresh(&mut self, ui: &mut UserInterface) {
+        let max = (self.total_height() - self.viewport_size.get().y).max(0.0);
+        ui.send_message(ScrollBarMessage::max_value(
+            self.scroll_bar,
+            MessageDirection::ToWidget,
+            max,
+        ));
+        ui.send_message(WidgetMessage::visibility(
+            self.scroll_bar,
+            MessageDirection::ToWidget,
+            max > 0.0,
+        ));
+        // Scroll bar will clamp its value and report it back, but items must be placed correctly
+        // right now.
+        self.scroll_offset = self.scroll_offset.min(max);
+        self.realize(ui);
+    }
+
+    fn sync_decorators(&self, ui: &UserInterface) {
+        for (&index, realized) in self.realized.iter() {
+            ui.send_message(DecoratorMessage::select(
+                realized.container,
+                MessageDirection::ToWidget,
+                self.selected_index == Some(index),
+            ));
+        }
+    }
+
+    fn set_selection(&mut self, selection: Option<usize>, ui: &UserInterface) {
+        if self.selected_index != selection {
+            self.selected_index = selection;
+            self.sync_decorators(ui);
+            ui.send_message(VirtualListViewMessage::selection(
+                self.handle,
+                MessageDirection::FromWidget,
+                selection,
+            ));
+        }
+    }
+
+    fn find_item_index(&self, ui: &UserInterface, mut handle: Handle<UiNode>) -> Option<usize> {
+        while handle.is_some() && handle != self.panel {
+            if let Some((&index, _)) = self
+                .realized
+                .iter()
+                .find(|(_, realized)| realized.container == handle)
+            {
+                return Some(index);
+            }
+            handle = ui.node(handle).parent();
+        }
+        None
+    }
+
+    fn shift_realized(&mut self, from: usize, insert: bool) {
+        let shifted = self.realized.split_off(&from);
+        for (index, realized) in shifted {
+            let new_index = if insert { index + 1 } else { index - 1 };
+            self.realized.insert(new_index, realized);
+        }
+    }
+}
+
+impl Control for VirtualListView {
+    fn query_component(&self, type_id: TypeId) -> Option<&dyn Any> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn resolve(&mut self, node_map: &NodeHandleMapping) {
+        node_map.resolve(&mut self.panel);
+        node_map.resolve(&mut self.scroll_bar);
+        for realized in self.realized.values_mut().chain(self.recycled.iter_mut()) {
+            node_map.resolve(&mut realized.container);
+            node_map.resolve(&mut realized.item);
+        }
+    }
+
+    fn arrange_override(&self, ui: &UserInterface, final_size: Vector2<f32>) -> Vector2<f32> {
+        let size = self.widget.arrange_override(ui, final_size);
+
+        let viewport_size = ui.node(self.panel).actual_size();
+        if viewport_size != self.viewport_size.get() {
+            self.viewport_size.set(viewport_size);
+            ui.send_message(VirtualListViewMessage::relayout(
+                self.handle,
+                MessageDirection::ToWidget,
+            ));
+        }
+
+        size
+    }
+
+    fn handle_routed_message(&mut self, ui: &mut UserInterface, message: &mut UiMessage) {
+        self.widget.handle_routed_message(ui, message);
+
+        if let Some(msg) = message.data::<WidgetMessage>() {
+            match *msg {
+                WidgetMessage::MouseWheel { amount, .. } => {
+                    if !message.handled() && self.item_count > 0 {
+                        // Scroll three items at once.
+                        let step = 3.0 * self.total_height() / self.item_count as f32;
+                        ui.send_message(ScrollBarMessage::value(
+                            self.scroll_bar,
+                            MessageDirection::ToWidget,
+                            self.scroll_offset - amount * step,
+                        ));
+                        message.set_handled(true);
+                    }
+                }
+                WidgetMessage::MouseUp { .. } => {
+                    if !message.handled() {
+                        if let Some(index) = self.find_item_index(ui, message.destination()) {
+                            ui.send_message(VirtualListViewMessage::selection(
+                                self.handle,
+                                MessageDirection::ToWidget,
+                                Some(index),
+                            ));
+                            message.set_handled(true);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        } else if let Some(&ScrollBarMessage::Value(value)) = message.data::<ScrollBarMessage>() {
+            if message.destination() == self.scroll_bar
+                && message.direction() == MessageDirection::FromWidget
+            {
+                self.scroll_offset = value;
+                self.realize(ui);
+            }
+        } else if let Some(msg) = message.data::<VirtualListViewMessage>() {
+            if message.destination() == self.handle()
+                && message.direction() == MessageDirection::ToWidget
+            {
+                match *msg {
+                    VirtualListViewMessage::SelectionChanged(selection) => {
+                        let selection = selection.filter(|&index| index < self.item_count);
+                        self.set_selection(selection, ui);
+                    }
+                    VirtualListViewMessage::ItemCount(count) => {
+                        self.release_all(ui);
+                        self.item_count = count;
+                        if let ItemHeight::Variable(_) = self.item_height {
+                            let heights = (0..count).map(|i| self.height_of(i)).collect::<Vec<_>>();
+                            self.rebuild_offsets(heights.into_iter());
+                        }
+                        let selection = self.selected_index.filter(|&index| index < count);
+                        self.set_selection(selection, ui);
+                        self.refresh(ui);
+                    }
+                    VirtualListViewMessage::InsertItem(index) => {
+                        let index = index.min(self.item_count);
+                        self.item_count += 1;
+                        if let ItemHeight::Variable(_) = self.item_height {
+                            let mut heights = self.heights();
+                            heights.insert(index, self.height_of(index));
+                            self.rebuild_offsets(heights.into_iter());
+                        }
+                        self.shift_realized(index, true);
+                        if let Some(selected) = self.selected_index.filter(|&s| s >= index) {
+                            self.set_selection(Some(selected + 1), ui);
+                        }
+                        self.refresh(ui);
+                    }
+                    VirtualListViewMessage::RemoveItem(index) => {
+                        if index < self.item_count {
+                            self.item_count -= 1;
+                            if let ItemHeight::Variable(_) = self.item_height {
+                                let mut heights = self.heights();
+                                heights.remove(index);
+                                self.rebuild_offsets(heights.into_iter());
+                            }
+                            if let Some(realized) = self.realized.remove(&index) {
+                                self.release(realized, ui);
+                            }
+                            self.shift_realized(index + 1, false);
+                            match self.selected_index {
+                                Some(selected) if selected == index => self.set_selection(None, ui),
+                                Some(selected) if selected > index => {
+                                    self.set_selection(Some(selected - 1), ui)
+                                }
+                                _ => (),
+                            }
+                            self.refresh(ui);
+                        }
+                    }
+                    VirtualListViewMessage::UpdateItem(index) => {
+                        if index < self.item_count {
+                            if let ItemHeight::Variable(_) = self.item_height {
+                                let mut heights = self.heights();
+                                heights[index] = self.height_of(index);
+                                self.rebuild_offsets(heights.into_iter());
+                            }
+                            if let Some(realized) = self.realized.get(&index).cloned() {
+                                if let Some(updater) = self.updater.as_ref() {
+                                    let updater = &mut *updater.borrow_mut();
+                                    updater(index, realized.item, ui);
+                                } else {
+                                    // Widget will be re-created in `realize`.
+                                    self.realized.remove(&index);
+                                    self.release(realized, ui);
+                                }
+                            }
+                            self.refresh(ui);
+                        }
+                    }
+                    VirtualListViewMessage::BringIntoView(index) => {
+                        if index < self.item_count {
+                            let top = self.item_top(index);
+                            let bottom = top + self.item_height(index);
+                            let viewport_height = self.viewport_size.get().y;
+                            let offset = if top < self.scroll_offset {
+                                top
+                            } else if bottom > self.scroll_offset + viewport_height {
+                                bottom - viewport_height
+                            } else {
+                                self.scroll_offset
+                            };
+                            ui.send_message(ScrollBarMessage::value(
+                                self.scroll_bar,
+                                MessageDirection::ToWidget,
+                                offset,
+                            ));
+                        }
+                    }
+                    VirtualListViewMessage::Relayout => {
+                        self.refresh(ui);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub struct VirtualListViewBuilder {
+    widget_builder: WidgetBuilder,
+    item_count: usize,
+    item_height: ItemHeight,
+    generator: Rc<RefCell<ItemGenerator>>,
+    updater: Option<Rc<RefCell<ItemUpdater>>>,
+    buffer_size: usize,
+}
+
+impl VirtualListViewBuilder {
+    /// Creates new builder, `generator` will be used to create widgets for items.
+    pub fn new<F>(widget_builder: WidgetBuilder, generator: F) -> Self
+    where
+        F: FnMut(usize, &mut BuildContext) -> Handle<UiNode> + 'static,
+    {
+        Self {
+            widget_builder,
+            item_count: 0,
+            item_height: ItemHeight::Fixed(20.0),
+            generator: Rc::new(RefCell::new(generator)),
+            updater: None,
+            buffer_size: 2,
+        }
+    }
+
+    pub fn with_item_count(mut self, count: usize) -> Self {
+        self.item_count = count;
+        self
+    }
+
+    /// Sets height of items, default is `ItemHeight::Fixed(20.0)`.
+    pub fn with_item_height(mut self, item_height: ItemHeight) -> Self {
+        self.item_height = item_height;
+        self
+    }
+
+    /// Sets updater for recycled widgets. Without updater, widgets of items that went out of view
+    /// are removed and new widgets are created for items that came into view.
+    pub fn with_updater<F>(mut self, updater: F) -> Self
+    where
+        F: FnMut(usize, Handle<UiNode>, &UserInterface) + 'static,
+    {
+        self.updater = Some(Rc::new(RefCell::new(updater)));
+        self
+    }
+
+    /// Sets amount of extra items that will be created above and below visible items, default is 2.
+    pub fn with_buffer_size(mut self, buffer_size: usize) -> Self {
+        self.buffer_size = buffer_size;
+        self
+    }
+
+    pub fn build(self, ctx: &mut BuildContext) -> Handle<UiNode> {
+        let panel = CanvasBuilder::new(
+            WidgetBuilder::new()
+                .with_margin(Thickness::uniform(3.0))
+                .on_row(0)
+                .on_column(0),
+        )
+        .build(ctx);
+
+        let scroll_bar = ScrollBarBuilder::new(
+            WidgetBuilder::new()
+                .with_width(22.0)
+                .with_visibility(false)
+                .on_row(0)
+                .on_column(1),
+        )
+        .with_orientation(Orientation::Vertical)
+        .build(ctx);
+
+        let back = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_background(BRUSH_DARK)
+                .with_foreground(BRUSH_LIGHT)
+                .with_child(
+                    GridBuilder::new(
+                        WidgetBuilder::new()
+                            .with_child(panel)
+                            .with_child(scroll_bar),
+                    )
+                    .add_row(Row::stretch())
+                    .add_column(Column::stretch())
+                    .add_column(Column::auto())
+                    .build(ctx),
+                ),
+        )
+        .with_stroke_thickness(Thickness::uniform(1.0))
+        .build(ctx);
+
+        let mut list_view = VirtualListView {
+            widget: self.widget_builder.with_child(back).build(),
+            panel,
+            scroll_bar,
+            item_count: self.item_count,
+            item_height: self.item_height,
+            offsets: Default::default(),
+            generator: self.generator,
+            updater: self.updater,
+            selected_index: None,
+            scroll_offset: 0.0,
+            viewport_size: Cell::new(Vector2::default()),
+            buffer_size: self.buffer_size,
+            realized: Default::default(),
+            recycled: Default::default(),
+        };
+
+        if let ItemHeight::Variable(_) = list_view.item_height {
+            let heights = (0..list_view.item_count)
+                .map(|i| list_view.height_of(i))
+                .collect::<Vec<_>>();
+            list_view.rebuild_offsets(heights.into_iter());
+        }
+
+        ctx.add_node(UiNode::new(list_view))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        virtual_list_view::{
+            ItemHeight, VirtualListView, VirtualListViewBuilder, VirtualListViewMessage,
+        },
+        widget::WidgetBuilder,
+        UserInterface,
+    };
+    use std::{cell::Cell, rc::Rc};
+
+    fn update(ui: &mut UserInterface) {
+        for _ in 0..4 {
+            ui.update(Vector2::new(1000.0, 1000.0), 0.0);
+            while ui.poll_message().is_some() {}
+        }
+    }
+
+    #[test]
+    fn test_virtual_list_view() {
+        let mut ui = UserInterface::new(Vector2::new(1000.0, 1000.0));
+
+        let built = Rc::new(Cell::new(0));
+        let built_counter = built.clone();
+        let list_view = VirtualListViewBuilder::new(
+            WidgetBuilder::new().with_width(200.0).with_height(206.0),
+            move |index, ctx| {
+                built_counter.set(built_counter.get() + 1);
+                TextBuilder::new(WidgetBuilder::new())
+                    .with_text(index.to_string())
+                    .build(ctx)
+            },
+        )
+        .with_item_count(50_000)
+        .with_item_height(ItemHeight::Fixed(20.0))
+        .with_updater(|index, item, ui| {
+            ui.send_message(TextMessage::text(
+                item,
+                MessageDirection::ToWidget,
+                index.to_string(),
+            ))
+        })
+        .build(&mut ui.build_ctx());
+
+        update(&mut ui);
+
+        let view = ui.node(list_view).cast::<VirtualListView>().unwrap();
+        // 200 px of visible area fits 10 items, plus 2 items of buffer below.
+        assert_eq!(view.realized_range(), 0..12);
+        assert_eq!(built.get(), 12);
+
+        // Scrolling to the end must not create new widgets, old ones are recycled.
+        ui.send_message(VirtualListViewMessage::bring_into_view(
+            list_view,
+            MessageDirection::ToWidget,
+            49_999,
+        ));
+        update(&mut ui);
+        let view = ui.node(list_view).cast::<VirtualListView>().unwrap();
+        assert_eq!(view.realized_range(), 49_988..50_000);
+        assert_eq!(built.get(), 12);
+
+        // Insertion must keep widgets of other items.
+        let last_widget = view.item_widget(49_999);
+        ui.send_message(VirtualListViewMessage::insert_item(
+            list_view,
+            MessageDirection::ToWidget,
+            49_990,
+        ));
+        ui.send_message(VirtualListViewMessage::selection(
+            list_view,
+            MessageDirection::ToWidget,
+            Some(49_995),
+        ));
+        update(&mut ui);
+        let view = ui.node(list_view).cast::<VirtualListView>().unwrap();
+        assert_eq!(view.item_count(), 50_001);
+        assert_eq!(view.item_widget(50_000), last_widget);
+        assert_eq!(view.selected(), Some(49_995));
+
+        ui.send_message(VirtualListViewMessage::remove_item(
+            list_view,
+            MessageDirection::ToWidget,
+            0,
+        ));
+        update(&mut ui);
+        let view = ui.node(list_view).cast::<VirtualListView>().unwrap();
+        assert_eq!(view.item_count(), 50_000);
+        assert_eq!(view.selected(), Some(49_994));
+        assert_eq!(view.item_widget(49_999), last_widget);
+    }
+}
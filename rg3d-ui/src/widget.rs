@@ -2,6 +2,7 @@ use crate::{
     brush::Brush,
     core::{algebra::Vector2, math::Rect, pool::Handle},
     define_constructor,
+    draw::ClipShape,
     message::{CursorIcon, KeyCode, MessageDirection, UiMessage},
     HorizontalAlignment, LayoutEvent, MouseButton, MouseState, Thickness, UiNode, UserInterface,
     VerticalAlignment, BRUSH_FOREGROUND, BRUSH_PRIMARY,
@@ -62,6 +63,51 @@ pub enum WidgetMessage {
     /// Direction: **From UI**.
     MouseLeave,
 
+    /// Initiated when user touches a widget's geometry. Touches are also mapped to the mouse
+    /// messages, see [`crate::UserInterface::process_os_event`] for more info.
+    ///
+    /// Direction: **From UI**.
+    TouchStarted {
+        /// Position of the touch.
+        pos: Vector2<f32>,
+        /// Unique id of the finger, it stays the same until the touch has ended.
+        id: u64,
+    },
+
+    /// Initiated when user moves a finger over a widget's geometry.
+    ///
+    /// Direction: **From UI**.
+    TouchMoved {
+        /// New position of the touch.
+        pos: Vector2<f32>,
+        /// Unique id of the finger.
+        id: u64,
+    },
+
+    /// Initiated when user lifts a finger from a widget's geometry or the touch was cancelled
+    /// by the OS.
+    ///
+    /// Direction: **From UI**.
+    TouchEnded {
+        /// Last position of the touch.
+        pos: Vector2<f32>,
+        /// Unique id of the finger.
+        id: u64,
+    },
+
+    /// Initiated when user moves two fingers over a widget's geometry.
+    ///
+    /// Direction: **From UI**.
+    Pinch {
+        /// Point between the fingers.
+        center: Vector2<f32>,
+        /// Ratio between current and previous distance between the fingers, values above one
+        /// mean "zoom in".
+        scale: f32,
+        /// Movement of the center since previous pinch message.
+        offset: Vector2<f32>,
+    },
+
     /// Initiated when cursor enters geometry of a widget.
     ///
     /// Direction: **From UI**.
@@ -208,6 +254,16 @@ pub enum WidgetMessage {
     /// of this indirect attachment.
     Column(usize),
 
+    /// A request to set amount of rows of a grid that widget should occupy.
+    ///
+    /// Direction: **From/To UI**
+    RowSpan(usize),
+
+    /// A request to set amount of columns of a grid that widget should occupy.
+    ///
+    /// Direction: **From/To UI**
+    ColumnSpan(usize),
+
     /// A request to set new margin of widget. Margin could be used to add some free space around widget to make UI look less
     /// dense.
     ///
@@ -220,6 +276,22 @@ pub enum WidgetMessage {
     /// Direction: **From/To UI**
     HitTestVisibility(bool),
 
+    /// A request to make widget interactive or not. Non-interactive widget is still hit test visible, so it blocks
+    /// mouse input for widgets below it and receives mouse enter/leave/move messages (so its tooltip works), but it
+    /// does not receive mouse button and wheel messages, can't get keyboard focus and can't be dragged. Useful for
+    /// overlays that should block input without reacting to it. Unlike [`WidgetMessage::Enabled`], it does not change
+    /// look of a widget and it is not inherited by children.
+    ///
+    /// Direction: **From/To UI**
+    Interactive(bool),
+
+    /// A request to allow or forbid widget to take keyboard focus. Clicking on a non-focusable widget
+    /// (or on any of its descendants) keeps focus on current widget, which is needed for on-screen
+    /// keyboards and tool palettes that must not interrupt text input.
+    ///
+    /// Direction: **From/To UI**
+    Focusable(bool),
+
     /// A request to set new visibility of a widget. Widget can be either visible or not. Invisible widgets does not take space
     /// in layout pass and collapsed to a point.
     ///
@@ -228,7 +300,9 @@ pub enum WidgetMessage {
 
     /// A request to set new z index of a widget. Z index is used to change drawing order of widgets. Please note that it works
     /// only in same hierarchy level, which means that it is impossible to set z index to 9999 (or similar huge value) to force
-    /// widget to be drawn on top of everything.
+    /// widget to be drawn on top of everything. Widgets with higher z index are drawn later and picked first, widgets with the
+    /// same z index keep their relative order. Use layers (see [`crate::UserInterface::create_layer`]) to put widget on top of
+    /// everything.
     ///
     /// Direction: **From/To UI**
     ZIndex(usize),
@@ -279,9 +353,13 @@ impl WidgetMessage {
     define_constructor!(WidgetMessage:Name => fn name(String), layout: false);
     define_constructor!(WidgetMessage:Row => fn row(usize), layout: false);
     define_constructor!(WidgetMessage:Column => fn column(usize), layout: false);
+    define_constructor!(WidgetMessage:RowSpan => fn row_span(usize), layout: false);
+    define_constructor!(WidgetMessage:ColumnSpan => fn column_span(usize), layout: false);
     define_constructor!(WidgetMessage:Cursor => fn cursor(Option<CursorIcon>), layout: false);
     define_constructor!(WidgetMessage:ZIndex => fn z_index(usize), layout: false);
     define_constructor!(WidgetMessage:HitTestVisibility => fn hit_test_visibility(bool), layout: false);
+    define_constructor!(WidgetMessage:Interactive => fn interactive(bool), layout: false);
+    define_constructor!(WidgetMessage:Focusable => fn focusable(bool), layout: false);
     define_constructor!(WidgetMessage:Margin => fn margin(Thickness), layout: false);
     define_constructor!(WidgetMessage:MinSize => fn min_size(Vector2<f32>), layout: false);
     define_constructor!(WidgetMessage:MaxSize => fn max_size(Vector2<f32>), layout: false);
@@ -298,6 +376,10 @@ impl WidgetMessage {
     define_constructor!(WidgetMessage:MouseWheel => fn mouse_wheel(pos: Vector2<f32>, amount: f32), layout: false);
     define_constructor!(WidgetMessage:MouseLeave => fn mouse_leave(), layout: false);
     define_constructor!(WidgetMessage:MouseEnter => fn mouse_enter(), layout: false);
+    define_constructor!(WidgetMessage:TouchStarted => fn touch_started(pos: Vector2<f32>, id: u64), layout: false);
+    define_constructor!(WidgetMessage:TouchMoved => fn touch_moved(pos: Vector2<f32>, id: u64), layout: false);
+    define_constructor!(WidgetMessage:TouchEnded => fn touch_ended(pos: Vector2<f32>, id: u64), layout: false);
+    define_constructor!(WidgetMessage:Pinch => fn pinch(center: Vector2<f32>, scale: f32, offset: Vector2<f32>), layout: false);
     define_constructor!(WidgetMessage:Text => fn text(char), layout: false);
     define_constructor!(WidgetMessage:KeyDown => fn key_down(KeyCode), layout: false);
     define_constructor!(WidgetMessage:KeyUp => fn key_up(KeyCode), layout: false);
@@ -328,6 +410,10 @@ pub struct Widget {
     row: usize,
     /// Index of column to which this node belongs
     column: usize,
+    /// Amount of rows that this node occupies
+    row_span: usize,
+    /// Amount of columns that this node occupies
+    column_span: usize,
     /// Vertical alignment
     vertical_alignment: VerticalAlignment,
     /// Horizontal alignment
@@ -343,6 +429,8 @@ pub struct Widget {
     pub(in crate) command_indices: RefCell<Vec<usize>>,
     pub(in crate) is_mouse_directly_over: bool,
     hit_test_visibility: bool,
+    interactive: bool,
+    focusable: bool,
     z_index: usize,
     allow_drag: bool,
     allow_drop: bool,
@@ -351,6 +439,7 @@ pub struct Widget {
     enabled: bool,
     cursor: Option<CursorIcon>,
     opacity: Option<f32>,
+    clip_shape: Option<ClipShape>,
     tooltip: Handle<UiNode>,
     tooltip_time: f32,
     context_menu: Handle<UiNode>,
@@ -468,6 +557,20 @@ impl Widget {
         self.hit_test_visibility
     }
 
+    /// Returns `true` if the widget receives mouse button, mouse wheel and keyboard input. See
+    /// [`WidgetMessage::Interactive`] for more info.
+    #[inline]
+    pub fn is_interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Returns `true` if the widget can take keyboard focus. See [`WidgetMessage::Focusable`] for
+    /// more info.
+    #[inline]
+    pub fn is_focusable(&self) -> bool {
+        self.focusable
+    }
+
     #[inline]
     pub fn set_max_size(&mut self, value: Vector2<f32>) -> &mut Self {
         self.max_size = value;
@@ -574,6 +677,12 @@ impl Widget {
         &self.children
     }
 
+    #[inline]
+    pub(in crate) fn insert_child(&mut self, index: usize, child: Handle<UiNode>) {
+        self.invalidate_layout();
+        self.children.insert(index, child)
+    }
+
     #[inline]
     pub(in crate) fn clear_children(&mut self) {
         self.invalidate_layout();
@@ -614,6 +723,28 @@ impl Widget {
         self.row
     }
 
+    #[inline]
+    pub fn set_row_span(&mut self, row_span: usize) -> &mut Self {
+        self.row_span = row_span.max(1);
+        self
+    }
+
+    #[inline]
+    pub fn row_span(&self) -> usize {
+        self.row_span
+    }
+
+    #[inline]
+    pub fn set_column_span(&mut self, column_span: usize) -> &mut Self {
+        self.column_span = column_span.max(1);
+        self
+    }
+
+    #[inline]
+    pub fn column_span(&self) -> usize {
+        self.column_span
+    }
+
     #[inline]
     pub fn desired_size(&self) -> Vector2<f32> {
         self.desired_size.get()
@@ -725,6 +856,20 @@ impl Widget {
                             self.invalidate_layout();
                         }
                     }
+                    &WidgetMessage::RowSpan(row_span) => {
+                        let row_span = row_span.max(1);
+                        if self.row_span != row_span {
+                            self.row_span = row_span;
+                            self.invalidate_layout();
+                        }
+                    }
+                    &WidgetMessage::ColumnSpan(column_span) => {
+                        let column_span = column_span.max(1);
+                        if self.column_span != column_span {
+                            self.column_span = column_span;
+                            self.invalidate_layout();
+                        }
+                    }
                     &WidgetMessage::Margin(margin) => {
                         if self.margin != margin {
                             self.margin = margin;
@@ -734,6 +879,12 @@ impl Widget {
                     WidgetMessage::HitTestVisibility(hit_test_visibility) => {
                         self.hit_test_visibility = *hit_test_visibility
                     }
+                    &WidgetMessage::Focusable(focusable) => {
+                        self.focusable = focusable;
+                    }
+                    &WidgetMessage::Interactive(interactive) => {
+                        self.interactive = interactive;
+                    }
                     &WidgetMessage::Visibility(visibility) => {
                         self.set_visibility(visibility);
                     }
@@ -749,6 +900,9 @@ impl Widget {
                     &WidgetMessage::Cursor(icon) => {
                         self.cursor = icon;
                     }
+                    &WidgetMessage::ZIndex(z_index) => {
+                        self.z_index = z_index;
+                    }
                     _ => (),
                 }
             }
@@ -930,6 +1084,19 @@ impl Widget {
         self.opacity
     }
 
+    /// Sets a shape that clips the widget and all its descendants, both rendering and hit
+    /// testing. Nested clip shapes are intersected.
+    #[inline]
+    pub fn set_clip_shape(&mut self, clip_shape: Option<ClipShape>) -> &mut Self {
+        self.clip_shape = clip_shape;
+        self
+    }
+
+    #[inline]
+    pub fn clip_shape(&self) -> Option<&ClipShape> {
+        self.clip_shape.as_ref()
+    }
+
     #[inline]
     pub fn tooltip(&self) -> Handle<UiNode> {
         self.tooltip
@@ -997,9 +1164,13 @@ pub struct WidgetBuilder {
     pub foreground: Option<Brush>,
     pub row: usize,
     pub column: usize,
+    pub row_span: usize,
+    pub column_span: usize,
     pub margin: Thickness,
     pub children: Vec<Handle<UiNode>>,
     pub is_hit_test_visible: bool,
+    pub interactive: bool,
+    pub focusable: bool,
     pub visibility: bool,
     pub z_index: usize,
     pub allow_drag: bool,
@@ -1009,6 +1180,7 @@ pub struct WidgetBuilder {
     pub enabled: bool,
     pub cursor: Option<CursorIcon>,
     pub opacity: Option<f32>,
+    pub clip_shape: Option<ClipShape>,
     pub tooltip: Handle<UiNode>,
     pub tooltip_time: f32,
     pub context_menu: Handle<UiNode>,
@@ -1036,10 +1208,14 @@ impl WidgetBuilder {
             foreground: None,
             row: 0,
             column: 0,
+            row_span: 1,
+            column_span: 1,
             margin: Thickness::zero(),
             desired_position: Vector2::default(),
             children: Vec::new(),
             is_hit_test_visible: true,
+            interactive: true,
+            focusable: true,
             visibility: true,
             z_index: 0,
             allow_drag: false,
@@ -1049,6 +1225,7 @@ impl WidgetBuilder {
             enabled: true,
             cursor: None,
             opacity: None,
+            clip_shape: None,
             tooltip: Handle::default(),
             tooltip_time: 0.1,
             context_menu: Handle::default(),
@@ -1122,6 +1299,18 @@ impl WidgetBuilder {
         self
     }
 
+    /// Sets amount of rows of a grid that the widget will occupy, starting from its row.
+    pub fn on_row_span(mut self, row_span: usize) -> Self {
+        self.row_span = row_span.max(1);
+        self
+    }
+
+    /// Sets amount of columns of a grid that the widget will occupy, starting from its column.
+    pub fn on_column_span(mut self, column_span: usize) -> Self {
+        self.column_span = column_span.max(1);
+        self
+    }
+
     pub fn with_margin(mut self, margin: Thickness) -> Self {
         self.margin = margin;
         self
@@ -1144,6 +1333,8 @@ impl WidgetBuilder {
         self
     }
 
+    /// Makes the widget a top-level overlay - it will be drawn after every other widget (including
+    /// layers) and picked first, instead of being confined under its parent's drawing order.
     pub fn with_draw_on_top(mut self, draw_on_top: bool) -> Self {
         self.draw_on_top = draw_on_top;
         self
@@ -1168,6 +1359,20 @@ impl WidgetBuilder {
         self
     }
 
+    /// Sets whether the widget should receive mouse button, mouse wheel and keyboard input. See
+    /// [`WidgetMessage::Interactive`] for more info.
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Sets whether the widget can take keyboard focus. See [`WidgetMessage::Focusable`] for more
+    /// info.
+    pub fn with_focusable(mut self, focusable: bool) -> Self {
+        self.focusable = focusable;
+        self
+    }
+
     pub fn with_visibility(mut self, visibility: bool) -> Self {
         self.visibility = visibility;
         self
@@ -1198,6 +1403,14 @@ impl WidgetBuilder {
         self
     }
 
+    /// Sets a shape that clips the widget and all its descendants, for example
+    /// [`ClipShape::Circle`] for a minimap. Clicks outside of the shape won't hit the widget and
+    /// its descendants. Widgets that are drawn on top (popups, tooltips) are not clipped.
+    pub fn with_clip_shape(mut self, clip_shape: ClipShape) -> Self {
+        self.clip_shape = Some(clip_shape);
+        self
+    }
+
     /// Sets the desired tooltip for the node.
     ///
     /// ## Important
@@ -1243,6 +1456,8 @@ impl WidgetBuilder {
             foreground: self.foreground.unwrap_or_else(|| BRUSH_FOREGROUND.clone()),
             row: self.row,
             column: self.column,
+            row_span: self.row_span.max(1),
+            column_span: self.column_span.max(1),
             vertical_alignment: self.vertical_alignment,
             horizontal_alignment: self.horizontal_alignment,
             margin: self.margin,
@@ -1256,6 +1471,8 @@ impl WidgetBuilder {
             measure_valid: Cell::new(false),
             arrange_valid: Cell::new(false),
             hit_test_visibility: self.is_hit_test_visible,
+            interactive: self.interactive,
+            focusable: self.focusable,
             prev_measure: Default::default(),
             prev_arrange: Default::default(),
             z_index: self.z_index,
@@ -1267,6 +1484,7 @@ impl WidgetBuilder {
             cursor: self.cursor,
             clip_bounds: Cell::new(Default::default()),
             opacity: self.opacity,
+            clip_shape: self.clip_shape,
             tooltip: self.tooltip,
             tooltip_time: self.tooltip_time,
             context_menu: self.context_menu,
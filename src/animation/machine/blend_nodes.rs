@@ -91,6 +91,22 @@ impl Visit for BlendAnimations {
     }
 }
 
+impl BlendAnimations {
+    // Returns a pose source with the highest weight.
+    pub(super) fn dominant_source(&self, params: &ParameterContainer) -> Handle<PoseNode> {
+        let mut result = Handle::NONE;
+        let mut max_weight = f32::MIN;
+        for blend_pose in self.pose_sources.iter() {
+            let weight = blend_pose.weight.value(params);
+            if weight > max_weight {
+                max_weight = weight;
+                result = blend_pose.pose_source;
+            }
+        }
+        result
+    }
+}
+
 impl EvaluatePose for BlendAnimations {
     fn eval_pose(
         &self,
@@ -101,16 +117,7 @@ impl EvaluatePose for BlendAnimations {
     ) -> Ref<AnimationPose> {
         self.output_pose.borrow_mut().reset();
         for blend_pose in self.pose_sources.iter() {
-            let weight = match blend_pose.weight {
-                PoseWeight::Constant(value) => value,
-                PoseWeight::Parameter(ref param_id) => {
-                    if let Some(Parameter::Weight(weight)) = params.get(param_id) {
-                        *weight
-                    } else {
-                        0.0
-                    }
-                }
-            };
+            let weight = blend_pose.weight.value(params);
 
             let pose_source =
                 nodes[blend_pose.pose_source].eval_pose(nodes, params, animations, dt);
@@ -120,6 +127,24 @@ impl EvaluatePose for BlendAnimations {
         }
         self.output_pose.borrow()
     }
+
+    fn sample_pose(
+        &self,
+        nodes: &Pool<PoseNode>,
+        params: &ParameterContainer,
+        animations: &AnimationContainer,
+        time: f32,
+    ) -> AnimationPose {
+        let mut pose = AnimationPose::default();
+        for blend_pose in self.pose_sources.iter() {
+            let weight = blend_pose.weight.value(params);
+            pose.blend_with(
+                &nodes[blend_pose.pose_source].sample_pose(nodes, params, animations, time),
+                weight,
+            );
+        }
+        pose
+    }
 }
 
 #[derive(Default)]
@@ -173,6 +198,19 @@ impl Visit for BlendAnimationsByIndex {
     }
 }
 
+impl BlendAnimationsByIndex {
+    // Returns a pose source of current index.
+    pub(super) fn dominant_source(&self, params: &ParameterContainer) -> Handle<PoseNode> {
+        match params.get(&self.index_parameter) {
+            Some(&Parameter::Index(index)) => self
+                .inputs
+                .get(index as usize)
+                .map_or(Handle::NONE, |input| input.pose_source),
+            _ => Handle::NONE,
+        }
+    }
+}
+
 impl EvaluatePose for BlendAnimationsByIndex {
     fn eval_pose(
         &self,
@@ -228,4 +266,22 @@ impl EvaluatePose for BlendAnimationsByIndex {
 
         self.output_pose.borrow()
     }
+
+    fn sample_pose(
+        &self,
+        nodes: &Pool<PoseNode>,
+        params: &ParameterContainer,
+        animations: &AnimationContainer,
+        time: f32,
+    ) -> AnimationPose {
+        match params.get(&self.index_parameter) {
+            Some(&Parameter::Index(index)) => match self.inputs.get(index as usize) {
+                Some(input) => {
+                    nodes[input.pose_source].sample_pose(nodes, params, animations, time)
+                }
+                None => Default::default(),
+            },
+            _ => Default::default(),
+        }
+    }
 }
@@ -81,26 +81,151 @@
 //!
 //! ```
 //!
+//! # Transitions
+//!
+//! Each transition blends source and destination poses over its duration using a [`BlendCurve`].
+//! If a rule of more than one transition is active in the same frame, the transition with the
+//! highest priority wins (see [`Transition::with_priority`]), transitions with equal priority are
+//! checked in the order they were added to the machine.
+//!
+//! A transition can be marked as interruptible (see [`Transition::with_interruptible`]), in this
+//! case another transition from source or destination state with higher priority, or the reverse
+//! transition with the same priority, can take over in the middle of blending. New transition will
+//! start from the current blended pose, so there will be no "snapping". This is useful for pairs
+//! like Idle<->Walk, when a player releases the walk key in the middle of Idle->Walk transition, the
+//! machine will immediately start going back to Idle. The transition that was just interrupted can't
+//! take over back, so transitions with active rules can't interrupt each other endlessly.
+//!
+//! # Transition signals and state time
+//!
+//! A transition can have signals (see [`Transition::with_signal`]), each signal fires once when
+//! normalized progress of the transition (0.0 - start, 1.0 - end, before [`BlendCurve`] is
+//! applied) passes its time. Signals are put into the same event queue as state enter/leave
+//! events, as [`Event::TransitionSignal`], in the order they occurred. This allows to sync
+//! gameplay with blending - for example a signal at 1.0 of `Jump->Fall` transition fires exactly
+//! when the transition is finished (right before [`Event::ActiveStateChanged`]). Signals of
+//! interrupted transitions that were not passed yet are not fired.
+//!
+//! [`Machine::state_normalized_time`] returns normalized time (0..1) of animation of a state,
+//! it could be used to show progress of an action in UI (charge bar of an attack for example).
+//!
+//! # Sub-machines
+//!
+//! A state can use another machine as its pose source (see [`State::new_sub_machine`]), this
+//! allows to build hierarchical machines - for example "Combat" state of a locomotion machine can
+//! have its own Idle/Attack/Block states with transitions between them, instead of mixing them
+//! all together in one machine. Nested machine is evaluated only when its state is active or takes
+//! part in an active transition, its pose is blended by parent machine as a pose of any other
+//! state. Parameters of nested machine can be set from parent machine using path of the state,
+//! for example `machine.set_parameter("Combat/Attack", Parameter::Rule(true))`, or nested machine
+//! can share parameters of its parent (see [`SubMachine::with_shared_parameters`]).
+//!
+//! # Entry state and respawning
+//!
+//! Machine starts from its entry state (see [`Machine::set_entry_state`]), by default it is the
+//! first added state. [`Machine::reset`] puts the machine back to the entry state.
+//!
+//! Pose nodes reference animations by handles, so when a model is destroyed and instantiated
+//! again (for example when a player respawns), the machine can be reused for new instance: retarget
+//! animations to new instance, map old animation handles to new ones and call
+//! [`Machine::rebind_animations`]. A machine with references to removed animations does not
+//! panic, such animations produce empty pose and a warning is written to the log once.
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{machine::Machine, Animation},
+//!     core::pool::Handle,
+//!     scene::{node::Node, Scene},
+//!     resource::model::Model,
+//! };
+//!
+//! fn respawn(
+//!     scene: &mut Scene,
+//!     machine: &mut Machine,
+//!     model: &Model,
+//!     old_instance: Handle<Node>,
+//!     old_animations: &[Handle<Animation>],
+//! ) -> (Handle<Node>, Vec<Handle<Animation>>) {
+//!     // Animations of the instance are removed too.
+//!     scene.remove_node(old_instance);
+//!
+//!     let instance = model.instantiate_geometry(scene);
+//!     // Retargeted animations are in the same order as in the model resource.
+//!     let new_animations = model.retarget_animations(instance, scene);
+//!     machine.rebind_animations(
+//!         &old_animations
+//!             .iter()
+//!             .cloned()
+//!             .zip(new_animations.iter().cloned())
+//!             .collect(),
+//!     );
+//!     machine.reset();
+//!
+//!     (instance, new_animations)
+//! }
+//! ```
+//!
 //! You can use multiple machines to animation single model - for example one machine can be for
 //! locomotion and other is for combat. This means that locomotion machine will take control over
 //! lower body and combat machine will control upper body.
+//!
+//! # Additive layers
+//!
+//! Additive animations (see [`Animation::set_additive`]) can be applied on top of the final pose
+//! of a machine as additive layers (see [`Machine::add_additive_layer`]). Each layer has its own
+//! weight, so a flinch reaction can be faded in and out while locomotion continues underneath.
+//! Layers are applied in the order they were added, this matters because rotations are composed.
+//! The machine does not control playback of additive animations - it is up to you to rewind
+//! a flinch animation when a character was hit and to change the weight of its layer.
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{machine::Machine, AnimationContainer, Animation},
+//!     core::pool::Handle,
+//! };
+//!
+//! fn add_flinch(
+//!     machine: &mut Machine,
+//!     animations: &mut AnimationContainer,
+//!     flinch: Handle<Animation>,
+//! ) -> usize {
+//!     animations[flinch].set_additive(true).set_loop(false);
+//!     machine.add_additive_layer(flinch, 0.0)
+//! }
+//!
+//! fn flinch(machine: &mut Machine, animations: &mut AnimationContainer, layer: usize) {
+//!     let animation = machine.additive_layers()[layer].animation();
+//!     animations[animation].rewind();
+//!     machine.set_additive_layer_weight(layer, 1.0);
+//! }
+//! ```
+//!
+//! # Animation LOD
+//!
+//! Animations used by a machine can have any [`crate::animation::AnimationUpdateMode`]. The
+//! machine does not cache poses of animations between updates - every evaluation takes current
+//! poses of animations, so when an animation that was updated time-only (while its model was
+//! off-screen for example) is sampled again, the machine continues from its actual time position
+//! without any snapping. The only exception is an interrupted transition - it starts from a pose
+//! that was captured at the moment of interruption, so if it happened while animations were
+//! updated time-only, the new transition will blend from an outdated pose.
 
 use crate::{
     animation::{
         machine::blend_nodes::{
             BlendAnimations, BlendAnimationsByIndex, BlendPose, IndexedBlendInput,
         },
-        Animation, AnimationContainer, AnimationPose,
+        Animation, AnimationContainer, AnimationPose, AnimationSignal,
     },
     core::{
         pool::{Handle, Pool, PoolIterator},
         visitor::{Visit, VisitResult, Visitor},
     },
-    utils::log::{Log, MessageKind},
+    utils::log::{log_info, log_warn},
 };
 use fxhash::FxHashMap;
 use std::{
-    cell::{Ref, RefCell},
+    cell::{Cell, Ref, RefCell},
     collections::VecDeque,
 };
 
@@ -116,6 +241,22 @@ pub enum Event {
 
     /// Occurs when transition is done and new active state was set.
     ActiveStateChanged(Handle<State>),
+
+    /// Occurs when active transition was interrupted by other transition.
+    TransitionInterrupted {
+        /// A transition that was interrupted.
+        interrupted: Handle<Transition>,
+        /// New active transition.
+        by: Handle<Transition>,
+    },
+
+    /// Occurs when progress of active transition passes time of its signal.
+    TransitionSignal {
+        /// A transition that owns the signal.
+        transition: Handle<Transition>,
+        /// Id of the signal.
+        signal_id: u64,
+    },
 }
 
 /// Machine node that plays specified animation.
@@ -123,6 +264,8 @@ pub enum Event {
 pub struct PlayAnimation {
     pub animation: Handle<Animation>,
     output_pose: RefCell<AnimationPose>,
+    // Prevents log spamming when the animation was removed.
+    dangling_reported: Cell<bool>,
 }
 
 impl PlayAnimation {
@@ -131,6 +274,23 @@ impl PlayAnimation {
         Self {
             animation,
             output_pose: Default::default(),
+            dangling_reported: Cell::new(false),
+        }
+    }
+
+    /// Sets new animation for the node.
+    pub fn set_animation(&mut self, animation: Handle<Animation>) {
+        self.animation = animation;
+        self.dangling_reported.set(false);
+    }
+
+    fn report_dangling(&self) {
+        if !self.dangling_reported.replace(true) {
+            log_warn!(
+                "PlayAnimation node references invalid animation {}, empty pose is used. \
+                    Did you forget to rebind the machine after respawning a model?",
+                self.animation
+            );
         }
     }
 }
@@ -145,6 +305,34 @@ impl Visit for PlayAnimation {
     }
 }
 
+/// Additive animation that is applied on top of the final pose of a machine, see module docs.
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct AdditiveLayer {
+    animation: Handle<Animation>,
+    weight: f32,
+}
+
+impl AdditiveLayer {
+    pub fn animation(&self) -> Handle<Animation> {
+        self.animation
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+}
+
+impl Visit for AdditiveLayer {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.animation.visit("Animation", visitor)?;
+        self.weight.visit("Weight", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
 /// Machine parameter.  Machine uses various parameters for specific actions. For example
 /// Rule parameter is used to check where transition from a state to state is possible.
 /// See module docs for example.
@@ -222,6 +410,19 @@ impl Default for PoseWeight {
 }
 
 impl PoseWeight {
+    fn value(&self, params: &ParameterContainer) -> f32 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::Parameter(param_id) => {
+                if let Some(Parameter::Weight(weight)) = params.get(param_id) {
+                    *weight
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
     fn from_id(id: i32) -> Result<Self, String> {
         match id {
             0 => Ok(Self::Parameter(Default::default())),
@@ -294,6 +495,22 @@ impl PoseNode {
         Self::BlendAnimationsByIndex(BlendAnimationsByIndex::new(index_parameter, inputs))
     }
 
+    // Returns an animation that contributes most to the pose of the node.
+    fn dominant_animation(
+        &self,
+        nodes: &Pool<PoseNode>,
+        params: &ParameterContainer,
+    ) -> Handle<Animation> {
+        let source = match self {
+            Self::PlayAnimation(play_animation) => return play_animation.animation,
+            Self::BlendAnimations(blend) => blend.dominant_source(params),
+            Self::BlendAnimationsByIndex(blend) => blend.dominant_source(params),
+        };
+        nodes
+            .try_borrow(source)
+            .map_or(Handle::NONE, |node| node.dominant_animation(nodes, params))
+    }
+
     fn from_id(id: i32) -> Result<Self, String> {
         match id {
             0 => Ok(Self::PlayAnimation(Default::default())),
@@ -334,11 +551,83 @@ impl Visit for PoseNode {
     }
 }
 
-/// State is a
+/// Nested machine that is used as a pose source of a state. See module docs for more info.
+#[derive(Default)]
+pub struct SubMachine {
+    machine: Machine,
+    reset_on_enter: bool,
+    share_parameters: bool,
+}
+
+impl SubMachine {
+    /// Creates new sub-machine. By default nested machine resumes from its last state when parent
+    /// state is entered and uses its own parameters.
+    pub fn new(machine: Machine) -> Self {
+        Self {
+            machine,
+            reset_on_enter: false,
+            share_parameters: false,
+        }
+    }
+
+    /// Sets whether nested machine should be reset to its entry state each time when parent
+    /// machine enters the state.
+    pub fn with_reset_on_enter(mut self, reset_on_enter: bool) -> Self {
+        self.reset_on_enter = reset_on_enter;
+        self
+    }
+
+    /// Sets whether nested machine should use parameters of parent machine. Parent parameters
+    /// override nested parameters with the same name.
+    pub fn with_shared_parameters(mut self, share_parameters: bool) -> Self {
+        self.share_parameters = share_parameters;
+        self
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    pub fn machine_mut(&mut self) -> &mut Machine {
+        &mut self.machine
+    }
+
+    pub fn is_reset_on_enter(&self) -> bool {
+        self.reset_on_enter
+    }
+
+    pub fn set_reset_on_enter(&mut self, reset_on_enter: bool) {
+        self.reset_on_enter = reset_on_enter;
+    }
+
+    pub fn is_sharing_parameters(&self) -> bool {
+        self.share_parameters
+    }
+
+    pub fn set_share_parameters(&mut self, share_parameters: bool) {
+        self.share_parameters = share_parameters;
+    }
+}
+
+impl Visit for SubMachine {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.machine.visit("Machine", visitor)?;
+        self.reset_on_enter.visit("ResetOnEnter", visitor)?;
+        self.share_parameters.visit("ShareParameters", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// State is a named source of a pose, the pose is taken either from a pose node or from a nested
+/// machine.
 #[derive(Default)]
 pub struct State {
     name: String,
     root: Handle<PoseNode>,
+    sub_machine: Option<Box<SubMachine>>,
     pose: AnimationPose,
 }
 
@@ -352,6 +641,16 @@ trait EvaluatePose {
         animations: &AnimationContainer,
         dt: f32,
     ) -> Ref<AnimationPose>;
+
+    // Same as `eval_pose`, but takes poses of animations at given time instead of their current
+    // poses and does not change any internal state.
+    fn sample_pose(
+        &self,
+        nodes: &Pool<PoseNode>,
+        params: &ParameterContainer,
+        animations: &AnimationContainer,
+        time: f32,
+    ) -> AnimationPose;
 }
 
 impl EvaluatePose for PlayAnimation {
@@ -362,12 +661,33 @@ impl EvaluatePose for PlayAnimation {
         animations: &AnimationContainer,
         _dt: f32,
     ) -> Ref<AnimationPose> {
-        animations
-            .get(self.animation)
-            .get_pose()
-            .clone_into(&mut self.output_pose.borrow_mut());
+        match animations.try_get(self.animation) {
+            Some(animation) => animation
+                .get_pose()
+                .clone_into(&mut self.output_pose.borrow_mut()),
+            None => {
+                self.report_dangling();
+                self.output_pose.borrow_mut().reset();
+            }
+        }
         self.output_pose.borrow()
     }
+
+    fn sample_pose(
+        &self,
+        _nodes: &Pool<PoseNode>,
+        _params: &ParameterContainer,
+        animations: &AnimationContainer,
+        time: f32,
+    ) -> AnimationPose {
+        match animations.try_get(self.animation) {
+            Some(animation) => animation.sample_pose(time),
+            None => {
+                self.report_dangling();
+                Default::default()
+            }
+        }
+    }
 }
 
 impl EvaluatePose for PoseNode {
@@ -380,6 +700,16 @@ impl EvaluatePose for PoseNode {
     ) -> Ref<AnimationPose> {
         static_dispatch!(self, eval_pose, nodes, params, animations, dt)
     }
+
+    fn sample_pose(
+        &self,
+        nodes: &Pool<PoseNode>,
+        params: &ParameterContainer,
+        animations: &AnimationContainer,
+        time: f32,
+    ) -> AnimationPose {
+        static_dispatch!(self, sample_pose, nodes, params, animations, time)
+    }
 }
 
 impl State {
@@ -388,6 +718,17 @@ impl State {
         Self {
             name: name.to_owned(),
             root,
+            sub_machine: None,
+            pose: Default::default(),
+        }
+    }
+
+    /// Creates new instance of state which takes its pose from a nested machine.
+    pub fn new_sub_machine(name: &str, sub_machine: SubMachine) -> Self {
+        Self {
+            name: name.to_owned(),
+            root: Handle::NONE,
+            sub_machine: Some(Box::new(sub_machine)),
             pose: Default::default(),
         }
     }
@@ -400,15 +741,35 @@ impl State {
         dt: f32,
     ) {
         self.pose.reset();
-        nodes
-            .borrow(self.root)
-            .eval_pose(nodes, params, animations, dt)
-            .clone_into(&mut self.pose);
+        if let Some(sub_machine) = self.sub_machine.as_mut() {
+            if sub_machine.share_parameters {
+                for (id, value) in params.iter() {
+                    sub_machine.machine.parameters.insert(id.clone(), *value);
+                }
+            }
+            sub_machine
+                .machine
+                .evaluate_pose(animations, dt)
+                .clone_into(&mut self.pose);
+        } else {
+            nodes
+                .borrow(self.root)
+                .eval_pose(nodes, params, animations, dt)
+                .clone_into(&mut self.pose);
+        }
     }
 
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    pub fn sub_machine(&self) -> Option<&SubMachine> {
+        self.sub_machine.as_deref()
+    }
+
+    pub fn sub_machine_mut(&mut self) -> Option<&mut SubMachine> {
+        self.sub_machine.as_deref_mut()
+    }
 }
 
 impl Visit for State {
@@ -417,6 +778,90 @@ impl Visit for State {
 
         self.name.visit("Name", visitor)?;
         self.root.visit("Root", visitor)?;
+        let _ = self.sub_machine.visit("SubMachine", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+/// Defines how blend factor of a transition changes over time.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BlendCurve {
+    /// Blend factor changes linearly.
+    Linear,
+
+    /// Slow start, fast end.
+    EaseIn,
+
+    /// Fast start, slow end.
+    EaseOut,
+
+    /// Slow start and end, fast in the middle.
+    EaseInOut,
+
+    /// One-dimensional cubic Bezier curve that starts at 0 and ends at 1 with two given
+    /// control values. For example `Cubic(0.0, 1.0)` is similar to [`BlendCurve::EaseInOut`].
+    Cubic(f32, f32),
+}
+
+impl Default for BlendCurve {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl BlendCurve {
+    /// Maps normalized time of a transition in [0; 1] range to blend factor.
+    pub fn evaluate(&self, t: f32) -> f32 {
+        let t = t.max(0.0).min(1.0);
+        match *self {
+            Self::Linear => t,
+            Self::EaseIn => t * t * t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+            Self::Cubic(p1, p2) => {
+                let it = 1.0 - t;
+                3.0 * it * it * t * p1 + 3.0 * it * t * t * p2 + t * t * t
+            }
+        }
+    }
+
+    fn from_id(id: u32) -> Result<Self, String> {
+        match id {
+            0 => Ok(Self::Linear),
+            1 => Ok(Self::EaseIn),
+            2 => Ok(Self::EaseOut),
+            3 => Ok(Self::EaseInOut),
+            4 => Ok(Self::Cubic(0.0, 1.0)),
+            _ => Err(format!("Invalid blend curve id {}", id)),
+        }
+    }
+
+    fn id(&self) -> u32 {
+        match self {
+            Self::Linear => 0,
+            Self::EaseIn => 1,
+            Self::EaseOut => 2,
+            Self::EaseInOut => 3,
+            Self::Cubic(_, _) => 4,
+        }
+    }
+}
+
+impl Visit for BlendCurve {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let mut id = self.id();
+        id.visit("Id", visitor)?;
+        if visitor.is_reading() {
+            *self = Self::from_id(id)?;
+        }
+
+        if let Self::Cubic(p1, p2) = self {
+            p1.visit("P1", visitor)?;
+            p2.visit("P2", visitor)?;
+        }
 
         visitor.leave_region()
     }
@@ -436,6 +881,12 @@ pub struct Transition {
     rule: String,
     /// 0 - evaluates `src` pose, 1 - `dest`, 0..1 - blends `src` and `dest`
     blend_factor: f32,
+    curve: BlendCurve,
+    /// Transitions with higher priority are checked first.
+    priority: i32,
+    interruptible: bool,
+    /// Signal times are normalized progress of the transition.
+    signals: Vec<AnimationSignal>,
 }
 
 impl Visit for Transition {
@@ -449,6 +900,10 @@ impl Visit for Transition {
         self.dest.visit("Dest", visitor)?;
         self.rule.visit("Rule", visitor)?;
         self.blend_factor.visit("BlendFactor", visitor)?;
+        let _ = self.curve.visit("Curve", visitor);
+        let _ = self.priority.visit("Priority", visitor);
+        let _ = self.interruptible.visit("Interruptible", visitor);
+        let _ = self.signals.visit("Signals", visitor);
 
         visitor.leave_region()
     }
@@ -470,9 +925,96 @@ impl Transition {
             dest,
             rule: rule.to_owned(),
             blend_factor: 0.0,
+            curve: Default::default(),
+            priority: 0,
+            interruptible: false,
+            signals: Default::default(),
         }
     }
 
+    /// Sets desired blend curve of the transition.
+    pub fn with_curve(mut self, curve: BlendCurve) -> Self {
+        self.curve = curve;
+        self
+    }
+
+    /// Sets desired priority of the transition. If rules of multiple transitions are active
+    /// at the same time, the transition with the highest priority will be activated. Default
+    /// priority is zero.
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets whether the transition can be interrupted by another transition from its source or
+    /// destination state with higher priority, or by the reverse transition with the same priority.
+    /// Default is `false`.
+    pub fn with_interruptible(mut self, interruptible: bool) -> Self {
+        self.interruptible = interruptible;
+        self
+    }
+
+    /// Adds a signal that fires when normalized progress of the transition passes time of the
+    /// signal, see module docs for more info.
+    pub fn with_signal(mut self, signal: AnimationSignal) -> Self {
+        self.signals.push(signal);
+        self
+    }
+
+    /// Adds new signal, see [`Self::with_signal`].
+    pub fn add_signal(&mut self, signal: AnimationSignal) -> &mut Self {
+        self.signals.push(signal);
+        self
+    }
+
+    /// Returns signals of the transition.
+    pub fn signals(&self) -> &[AnimationSignal] {
+        &self.signals
+    }
+
+    /// Returns signals of the transition, could be used to enable or disable them.
+    pub fn signals_mut(&mut self) -> &mut [AnimationSignal] {
+        &mut self.signals
+    }
+
+    /// Returns normalized progress of the transition in 0..1 range, unlike
+    /// [`Self::blend_factor`] blend curve is not applied.
+    pub fn progress(&self) -> f32 {
+        if self.transition_time > 0.0 {
+            self.elapsed_time / self.transition_time
+        } else {
+            1.0
+        }
+    }
+
+    pub fn curve(&self) -> BlendCurve {
+        self.curve
+    }
+
+    pub fn set_curve(&mut self, curve: BlendCurve) {
+        self.curve = curve;
+    }
+
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    pub fn set_priority(&mut self, priority: i32) {
+        self.priority = priority;
+    }
+
+    pub fn is_interruptible(&self) -> bool {
+        self.interruptible
+    }
+
+    pub fn set_interruptible(&mut self, interruptible: bool) {
+        self.interruptible = interruptible;
+    }
+
+    pub fn blend_factor(&self) -> f32 {
+        self.blend_factor
+    }
+
     pub fn name(&self) -> &str {
         self.name.as_str()
     }
@@ -498,12 +1040,27 @@ impl Transition {
         self.blend_factor = 0.0;
     }
 
-    fn update(&mut self, dt: f32) {
+    fn update(&mut self, self_handle: Handle<Transition>, dt: f32, events: &mut LimitedEventQueue) {
+        let started = self.elapsed_time == 0.0;
+        let prev_t = self.progress();
         self.elapsed_time += dt;
         if self.elapsed_time > self.transition_time {
             self.elapsed_time = self.transition_time;
         }
-        self.blend_factor = self.elapsed_time / self.transition_time;
+        let t = self.progress();
+        self.blend_factor = self.curve.evaluate(t);
+
+        // Signals at the very beginning of the transition fire on first update that moves the
+        // transition forward.
+        let first_step = started && (self.elapsed_time > 0.0 || self.transition_time <= 0.0);
+        for signal in self.signals.iter() {
+            if signal.is_enabled() && (first_step || signal.time() > prev_t) && signal.time() <= t {
+                events.push(Event::TransitionSignal {
+                    transition: self_handle,
+                    signal_id: signal.id(),
+                });
+            }
+        }
     }
 
     pub fn is_done(&self) -> bool {
@@ -520,9 +1077,17 @@ pub struct Machine {
     active_state: Handle<State>,
     entry_state: Handle<State>,
     active_transition: Handle<Transition>,
+    // A pose that is used as a source pose of active transition, instead of a pose of source
+    // state, when the transition has interrupted another transition.
+    interrupted_pose: Option<AnimationPose>,
+    // A transition that was interrupted by active transition, it can't take over back.
+    interrupted_transition: Handle<Transition>,
     parameters: ParameterContainer,
     events: LimitedEventQueue,
     debug: bool,
+    // Whether the machine was evaluated since creation or last reset.
+    started: bool,
+    additive_layers: Vec<AdditiveLayer>,
 }
 
 struct LimitedEventQueue {
@@ -568,9 +1133,13 @@ impl Machine {
             active_state: Default::default(),
             entry_state: Default::default(),
             active_transition: Default::default(),
+            interrupted_pose: None,
+            interrupted_transition: Handle::NONE,
             parameters: Default::default(),
             events: LimitedEventQueue::new(2048),
             debug: false,
+            started: false,
+            additive_layers: Default::default(),
         }
     }
 
@@ -578,7 +1147,22 @@ impl Machine {
         self.nodes.spawn(node)
     }
 
+    /// Sets new value of a parameter. Parameters of nested machines can be set using path of a
+    /// state with nested machine, for example `Combat/Attack` sets `Attack` parameter of a machine
+    /// of `Combat` state.
     pub fn set_parameter(&mut self, id: &str, new_value: Parameter) -> &mut Self {
+        if let Some((state_name, nested_id)) = id.split_once('/') {
+            if let Some(sub_machine) = self
+                .states
+                .iter_mut()
+                .find(|state| state.name == state_name)
+                .and_then(|state| state.sub_machine.as_mut())
+            {
+                sub_machine.machine.set_parameter(nested_id, new_value);
+                return self;
+            }
+        }
+
         match self.parameters.get_mut(id) {
             Some(parameter) => {
                 *parameter = new_value;
@@ -591,20 +1175,144 @@ impl Machine {
         self
     }
 
+    /// Returns current value of a parameter of the machine (nested machines are not searched).
+    pub fn parameter(&self, id: &str) -> Option<&Parameter> {
+        self.parameters.get(id)
+    }
+
+    /// Sets a state from which the machine starts. It is used on first evaluation of the machine
+    /// and after [`Self::reset`], so changing entry state of running machine does not change its
+    /// active state. By default entry state is the first added state.
     pub fn set_entry_state(&mut self, entry_state: Handle<State>) {
-        self.active_state = entry_state;
+        if !self.started {
+            self.active_state = entry_state;
+        }
         self.entry_state = entry_state;
     }
 
+    /// Returns current entry state of the machine.
+    pub fn entry_state(&self) -> Handle<State> {
+        self.entry_state
+    }
+
+    /// Replaces animation handles in every pose node of the machine (and its nested machines)
+    /// using given map of old handles to new ones. Handles that are not in the map are left
+    /// untouched. This allows to reuse the machine after a model was destroyed and instantiated
+    /// again, see module docs for example. Returns amount of remapped pose nodes.
+    pub fn rebind_animations(
+        &mut self,
+        map: &FxHashMap<Handle<Animation>, Handle<Animation>>,
+    ) -> usize {
+        let mut count = 0;
+        for node in self.nodes.iter_mut() {
+            if let PoseNode::PlayAnimation(play_animation) = node {
+                if let Some(&new_animation) = map.get(&play_animation.animation) {
+                    play_animation.set_animation(new_animation);
+                    count += 1;
+                }
+            }
+        }
+        for layer in self.additive_layers.iter_mut() {
+            if let Some(&new_animation) = map.get(&layer.animation) {
+                layer.animation = new_animation;
+                count += 1;
+            }
+        }
+        for state in self.states.iter_mut() {
+            if let Some(sub_machine) = state.sub_machine.as_mut() {
+                count += sub_machine.machine.rebind_animations(map);
+            }
+        }
+        count
+    }
+
+    /// Returns handles of animations that are used by pose nodes of the machine (and its nested
+    /// machines), but do not exist in given container. Such animations produce empty poses.
+    pub fn dangling_animations(&self, animations: &AnimationContainer) -> Vec<Handle<Animation>> {
+        let mut dangling = Vec::new();
+        self.collect_dangling_animations(animations, &mut dangling);
+        dangling
+    }
+
+    fn collect_dangling_animations(
+        &self,
+        animations: &AnimationContainer,
+        dangling: &mut Vec<Handle<Animation>>,
+    ) {
+        for node in self.nodes.iter() {
+            if let PoseNode::PlayAnimation(play_animation) = node {
+                if animations.try_get(play_animation.animation).is_none()
+                    && !dangling.contains(&play_animation.animation)
+                {
+                    dangling.push(play_animation.animation);
+                }
+            }
+        }
+        for layer in self.additive_layers.iter() {
+            if animations.try_get(layer.animation).is_none() && !dangling.contains(&layer.animation)
+            {
+                dangling.push(layer.animation);
+            }
+        }
+        for state in self.states.iter() {
+            if let Some(sub_machine) = state.sub_machine.as_ref() {
+                sub_machine
+                    .machine
+                    .collect_dangling_animations(animations, dangling);
+            }
+        }
+    }
+
     pub fn debug(&mut self, state: bool) {
         self.debug = state;
     }
 
+    /// Adds new additive layer with given animation and weight, returns index of the layer. The
+    /// animation must be additive, see module docs for more info.
+    pub fn add_additive_layer(&mut self, animation: Handle<Animation>, weight: f32) -> usize {
+        self.additive_layers
+            .push(AdditiveLayer { animation, weight });
+        self.additive_layers.len() - 1
+    }
+
+    /// Sets weight of an additive layer at given index, 0.0 - the layer has no effect, 1.0 - the
+    /// layer is applied fully. Panics if the index is out of bounds.
+    pub fn set_additive_layer_weight(&mut self, index: usize, weight: f32) -> &mut Self {
+        self.additive_layers[index].weight = weight;
+        self
+    }
+
+    /// Removes additive layer at given index, indices of next layers are shifted down.
+    pub fn remove_additive_layer(&mut self, index: usize) -> AdditiveLayer {
+        self.additive_layers.remove(index)
+    }
+
+    pub fn additive_layers(&self) -> &[AdditiveLayer] {
+        &self.additive_layers
+    }
+
+    fn apply_additive_layers(&mut self, animations: &AnimationContainer) {
+        for layer in self.additive_layers.iter() {
+            if layer.weight <= 0.0 {
+                continue;
+            }
+            if let Some(animation) = animations.try_get(layer.animation) {
+                if animation.is_enabled() && animation.is_additive() {
+                    self.final_pose
+                        .add_additive(animation.get_pose(), layer.weight);
+                }
+            }
+        }
+    }
+
     pub fn add_state(&mut self, state: State) -> Handle<State> {
         let state = self.states.spawn(state);
         if self.active_state.is_none() {
             self.active_state = state;
         }
+        if self.entry_state.is_none() {
+            self.entry_state = state;
+        }
         state
     }
 
@@ -616,6 +1324,10 @@ impl Machine {
         &self.states[state]
     }
 
+    pub fn get_state_mut(&mut self, state: Handle<State>) -> &mut State {
+        &mut self.states[state]
+    }
+
     pub fn get_transition(&self, transition: Handle<Transition>) -> &Transition {
         &self.transitions[transition]
     }
@@ -624,12 +1336,24 @@ impl Machine {
         self.events.pop()
     }
 
+    /// Puts the machine (and its nested machines) back to entry state, active transition is
+    /// discarded.
     pub fn reset(&mut self) {
         for transition in self.transitions.iter_mut() {
             transition.reset();
         }
 
+        for state in self.states.iter_mut() {
+            if let Some(sub_machine) = state.sub_machine.as_mut() {
+                sub_machine.machine.reset();
+            }
+        }
+
         self.active_state = self.entry_state;
+        self.active_transition = Handle::NONE;
+        self.interrupted_pose = None;
+        self.interrupted_transition = Handle::NONE;
+        self.started = false;
     }
 
     pub fn nodes(&self) -> PoolIterator<PoseNode> {
@@ -644,88 +1368,281 @@ impl Machine {
         self.active_transition
     }
 
+    /// Returns names of active states of the machine and every nested machine separated by `/`,
+    /// for example `Combat/Attack`. While a transition is active, destination state of the
+    /// transition is used.
+    pub fn active_state_path(&self) -> String {
+        let mut path = String::new();
+        self.write_active_state_path(&mut path);
+        path
+    }
+
+    fn write_active_state_path(&self, path: &mut String) {
+        let (_, state) = self.used_states();
+        if let Some(state) = self.states.try_borrow(state) {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(&state.name);
+            if let Some(sub_machine) = state.sub_machine.as_ref() {
+                sub_machine.machine.write_active_state_path(path);
+            }
+        }
+    }
+
+    /// Returns states whose poses are used by the machine at the moment - source and destination
+    /// of active transition or active state.
+    fn used_states(&self) -> (Handle<State>, Handle<State>) {
+        if let Some(transition) = self.transitions.try_borrow(self.active_transition) {
+            (transition.source, transition.dest)
+        } else {
+            (Handle::NONE, self.active_state)
+        }
+    }
+
+    fn enter_state(&mut self, handle: Handle<State>, animations: &AnimationContainer, dt: f32) {
+        let state = &mut self.states[handle];
+        if let Some(sub_machine) = state.sub_machine.as_mut() {
+            if sub_machine.reset_on_enter {
+                sub_machine.machine.reset();
+            }
+            // Nested machine was not evaluated while its state was inactive.
+            state.update(&self.nodes, &self.parameters, animations, dt);
+        }
+    }
+
     pub fn transitions(&self) -> &Pool<Transition> {
         &self.transitions
     }
 
+    /// Returns states of the machine.
+    pub fn states(&self) -> &Pool<State> {
+        &self.states
+    }
+
+    /// Returns normalized time (0..1) of animation of given state. If the state blends multiple
+    /// animations, the animation with the highest weight is used, a state with nested machine
+    /// uses active state of the nested machine. Backward playback (negative speed) also goes from
+    /// 0 to 1. Returns `None` if the state has no valid animation or the animation has zero
+    /// length.
+    pub fn state_normalized_time(
+        &self,
+        state: Handle<State>,
+        animations: &AnimationContainer,
+    ) -> Option<f32> {
+        let state = self.states.try_borrow(state)?;
+        if let Some(sub_machine) = state.sub_machine.as_ref() {
+            let machine = &sub_machine.machine;
+            let (_, nested_state) = machine.used_states();
+            return machine.state_normalized_time(nested_state, animations);
+        }
+
+        let animation = self
+            .nodes
+            .try_borrow(state.root)?
+            .dominant_animation(&self.nodes, &self.parameters);
+        let animation = animations.try_get(animation)?;
+        if animation.length() <= 0.0 {
+            return None;
+        }
+        let t = (animation.get_time_position() / animation.length()).clamp(0.0, 1.0);
+        Some(if animation.get_speed() < 0.0 {
+            1.0 - t
+        } else {
+            t
+        })
+    }
+
+    fn is_rule_active(&self, rule: &str) -> bool {
+        matches!(self.parameters.get(rule), Some(Parameter::Rule(true)))
+    }
+
+    /// Searches for a transition with active rule and the highest priority among the transitions
+    /// that pass given filter. Transitions with equal priority are checked in the order of addition.
+    fn find_transition<F>(&self, mut filter: F) -> Handle<Transition>
+    where
+        F: FnMut(Handle<Transition>, &Transition) -> bool,
+    {
+        let mut result = Handle::NONE;
+        let mut result_priority = i32::MIN;
+        for (handle, transition) in self.transitions.pair_iter() {
+            if (result.is_none() || transition.priority > result_priority)
+                && filter(handle, transition)
+                && self.is_rule_active(&transition.rule)
+            {
+                result = handle;
+                result_priority = transition.priority;
+            }
+        }
+        result
+    }
+
+    /// Evaluates pose of given state at given time without changing state of the machine, its
+    /// animations or the scene. Every animation used by the state is sampled at `time` (see
+    /// [`Animation::sample_pose`]), `parameters` override current values of parameters of the
+    /// machine only for this evaluation. Transitions are ignored, blend-by-index nodes take pose of
+    /// current index immediately. A state with nested machine takes pose of active state of the
+    /// nested machine, nested machine receives overridden parameters only if it shares parameters
+    /// with parent machine. Could be useful for AI planning or kill-cams, when you need to know
+    /// where some bone will be at some moment of the state.
+    pub fn evaluate_pose_at(
+        &self,
+        state: Handle<State>,
+        animations: &AnimationContainer,
+        time: f32,
+        parameters: &[(&str, Parameter)],
+    ) -> AnimationPose {
+        let mut params = self.parameters.clone();
+        for (id, value) in parameters {
+            params.insert((*id).to_owned(), *value);
+        }
+        self.sample_state_pose(state, &params, animations, time)
+    }
+
+    fn sample_state_pose(
+        &self,
+        state: Handle<State>,
+        params: &ParameterContainer,
+        animations: &AnimationContainer,
+        time: f32,
+    ) -> AnimationPose {
+        let state = match self.states.try_borrow(state) {
+            Some(state) => state,
+            None => return Default::default(),
+        };
+
+        if let Some(sub_machine) = state.sub_machine.as_ref() {
+            let machine = &sub_machine.machine;
+            let mut nested_params = machine.parameters.clone();
+            if sub_machine.share_parameters {
+                for (id, value) in params.iter() {
+                    nested_params.insert(id.clone(), *value);
+                }
+            }
+            let (_, nested_state) = machine.used_states();
+            machine.sample_state_pose(nested_state, &nested_params, animations, time)
+        } else if let Some(root) = self.nodes.try_borrow(state.root) {
+            root.sample_pose(&self.nodes, params, animations, time)
+        } else {
+            Default::default()
+        }
+    }
+
+    fn blend_transition(&self, transition: &Transition, pose: &mut AnimationPose) {
+        let source_pose = self
+            .interrupted_pose
+            .as_ref()
+            .unwrap_or(&self.states[transition.source].pose);
+        pose.blend_with(source_pose, 1.0 - transition.blend_factor);
+        pose.blend_with(&self.states[transition.dest].pose, transition.blend_factor);
+    }
+
     pub fn evaluate_pose(&mut self, animations: &AnimationContainer, dt: f32) -> &AnimationPose {
         self.final_pose.reset();
+        self.started = true;
 
         if self.active_state.is_some() || self.active_transition.is_some() {
-            // Gather actual poses for each state.
-            for state in self.states.iter_mut() {
-                state.update(&self.nodes, &self.parameters, animations, dt);
+            // Gather actual poses for each state. Nested machines are evaluated only when their
+            // states are in use, otherwise they would advance in background.
+            let (source, dest) = self.used_states();
+            for (handle, state) in self.states.pair_iter_mut() {
+                if state.sub_machine.is_none() || handle == source || handle == dest {
+                    state.update(&self.nodes, &self.parameters, animations, dt);
+                }
             }
 
             if self.active_transition.is_none() {
                 // Find transition.
-                for (handle, transition) in self.transitions.pair_iter_mut() {
-                    if transition.dest == self.active_state
-                        || transition.source != self.active_state
-                    {
-                        continue;
+                let active_state = self.active_state;
+                let handle = self.find_transition(|_, transition| {
+                    transition.source == active_state && transition.dest != active_state
+                });
+                if handle.is_some() {
+                    let transition = &self.transitions[handle];
+
+                    self.events.push(Event::StateLeave(self.active_state));
+                    if self.debug {
+                        log_info!("Leaving state: {}", self.states[self.active_state].name);
                     }
-                    if let Some(Parameter::Rule(active)) = self.parameters.get(&transition.rule) {
-                        if *active {
-                            self.events.push(Event::StateLeave(self.active_state));
-                            if self.debug {
-                                Log::writeln(
-                                    MessageKind::Information,
-                                    format!(
-                                        "Leaving state: {}",
-                                        self.states[self.active_state].name
-                                    ),
-                                );
-                            }
-
-                            self.events.push(Event::StateEnter(transition.source));
-                            if self.debug {
-                                Log::writeln(
-                                    MessageKind::Information,
-                                    format!(
-                                        "Entering state: {}",
-                                        self.states[transition.source].name
-                                    ),
-                                );
-                            }
-
-                            self.active_state = Handle::NONE;
-                            self.active_transition = handle;
-
-                            break;
-                        }
+
+                    self.events.push(Event::StateEnter(transition.source));
+                    if self.debug {
+                        log_info!("Entering state: {}", self.states[transition.source].name);
+                    }
+
+                    let dest = transition.dest;
+                    self.active_state = Handle::NONE;
+                    self.active_transition = handle;
+                    self.enter_state(dest, animations, dt);
+                }
+            } else if self.transitions[self.active_transition].interruptible {
+                // Check if there is a transition that can take over the active one.
+                let active_handle = self.active_transition;
+                let active = &self.transitions[active_handle];
+                let (source, dest, priority) = (active.source, active.dest, active.priority);
+                let interrupted = self.interrupted_transition;
+                let handle = self.find_transition(|handle, transition| {
+                    let is_reverse = transition.source == dest && transition.dest == source;
+                    handle != active_handle
+                        && handle != interrupted
+                        && (transition.priority > priority
+                            || (transition.priority == priority && is_reverse))
+                        && (transition.source == source || transition.source == dest)
+                        && transition.dest != transition.source
+                });
+                if handle.is_some() {
+                    // New transition starts from current blended pose.
+                    let mut interrupted_pose = AnimationPose::default();
+                    self.blend_transition(&self.transitions[active_handle], &mut interrupted_pose);
+                    self.interrupted_pose = Some(interrupted_pose);
+                    self.interrupted_transition = active_handle;
+
+                    self.transitions[active_handle].reset();
+                    self.transitions[handle].reset();
+                    self.active_transition = handle;
+
+                    let new_dest = self.transitions[handle].dest;
+                    if new_dest != source && new_dest != dest {
+                        self.enter_state(new_dest, animations, dt);
+                    }
+                    self.events.push(Event::TransitionInterrupted {
+                        interrupted: active_handle,
+                        by: handle,
+                    });
+
+                    if self.debug {
+                        log_info!(
+                            "Transition {} was interrupted by {}",
+                            self.transitions[active_handle].name,
+                            self.transitions[handle].name
+                        );
                     }
                 }
             }
 
             // Double check for active transition because we can have empty machine.
             if self.active_transition.is_some() {
-                let transition = &mut self.transitions[self.active_transition];
+                let mut final_pose = std::mem::take(&mut self.final_pose);
+                self.blend_transition(&self.transitions[self.active_transition], &mut final_pose);
+                self.final_pose = final_pose;
 
-                // Blend between source and dest states.
-                self.final_pose.blend_with(
-                    &self.states[transition.source].pose,
-                    1.0 - transition.blend_factor,
-                );
-                self.final_pose
-                    .blend_with(&self.states[transition.dest].pose, transition.blend_factor);
+                let transition = &mut self.transitions[self.active_transition];
 
-                transition.update(dt);
+                transition.update(self.active_transition, dt, &mut self.events);
 
                 if transition.is_done() {
                     transition.reset();
                     self.active_transition = Handle::NONE;
+                    self.interrupted_pose = None;
+                    self.interrupted_transition = Handle::NONE;
                     self.active_state = transition.dest;
                     self.events
                         .push(Event::ActiveStateChanged(self.active_state));
 
                     if self.debug {
-                        Log::writeln(
-                            MessageKind::Information,
-                            format!(
-                                "Active state changed: {}",
-                                self.states[self.active_state].name
-                            ),
+                        log_info!(
+                            "Active state changed: {}",
+                            self.states[self.active_state].name
                         );
                     }
                 }
@@ -738,6 +1655,8 @@ impl Machine {
             }
         }
 
+        self.apply_additive_layers(animations);
+
         &self.final_pose
     }
 }
@@ -753,7 +1672,479 @@ impl Visit for Machine {
         self.active_state.visit("ActiveState", visitor)?;
         self.entry_state.visit("EntryState", visitor)?;
         self.active_transition.visit("ActiveTransition", visitor)?;
+        let _ = self.additive_layers.visit("AdditiveLayers", visitor);
+
+        if visitor.is_reading() {
+            // Loaded machine continues from saved state.
+            self.started = true;
+        }
 
         visitor.leave_region()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            machine::{
+                blend_nodes::BlendPose, BlendCurve, Event, Machine, Parameter, PoseNode, State,
+                SubMachine, Transition,
+            },
+            Animation, AnimationContainer, AnimationSignal, AnimationUpdateMode, KeyFrame, Track,
+        },
+        core::{
+            algebra::{Unit, UnitQuaternion, Vector3},
+            pool::Handle,
+        },
+    };
+
+    #[test]
+    fn test_blend_curves() {
+        for curve in [
+            BlendCurve::Linear,
+            BlendCurve::EaseIn,
+            BlendCurve::EaseOut,
+            BlendCurve::EaseInOut,
+            BlendCurve::Cubic(0.2, 0.8),
+        ] {
+            assert_eq!(curve.evaluate(0.0), 0.0);
+            assert!((curve.evaluate(1.0) - 1.0).abs() <= f32::EPSILON);
+        }
+        assert!(BlendCurve::EaseIn.evaluate(0.5) < 0.5);
+        assert!(BlendCurve::EaseOut.evaluate(0.5) > 0.5);
+    }
+
+    #[test]
+    fn test_transition_priority_and_interruption() {
+        let mut animations = AnimationContainer::new();
+        let mut machine = Machine::new();
+
+        let mut add_state = |name: &str| {
+            let node = machine.add_node(PoseNode::make_play_animation(
+                animations.add(Animation::default()),
+            ));
+            machine.add_state(State::new(name, node))
+        };
+        let idle = add_state("Idle");
+        let walk = add_state("Walk");
+        let jump = add_state("Jump");
+        machine.set_entry_state(idle);
+
+        let idle_to_walk = machine.add_transition(
+            Transition::new("Idle->Walk", idle, walk, 1.0, "IdleToWalk").with_interruptible(true),
+        );
+        let walk_to_idle = machine.add_transition(
+            Transition::new("Walk->Idle", walk, idle, 1.0, "WalkToIdle").with_interruptible(true),
+        );
+        let idle_to_jump = machine.add_transition(
+            Transition::new("Idle->Jump", idle, jump, 1.0, "IdleToJump").with_priority(1),
+        );
+
+        // Both rules are active, transition with higher priority must win.
+        machine
+            .set_parameter("IdleToWalk", Parameter::Rule(true))
+            .set_parameter("IdleToJump", Parameter::Rule(true));
+        machine.evaluate_pose(&animations, 0.25);
+        assert_eq!(machine.active_transition(), idle_to_jump);
+
+        machine.reset();
+        machine.set_parameter("IdleToJump", Parameter::Rule(false));
+        machine.evaluate_pose(&animations, 0.25);
+        assert_eq!(machine.active_transition(), idle_to_walk);
+
+        // Walk key released in the middle of the transition.
+        machine
+            .set_parameter("IdleToWalk", Parameter::Rule(false))
+            .set_parameter("WalkToIdle", Parameter::Rule(true));
+        machine.evaluate_pose(&animations, 0.25);
+        assert_eq!(machine.active_transition(), walk_to_idle);
+
+        // Rule of the interrupted transition is active again, but it can't take over back.
+        machine.set_parameter("IdleToWalk", Parameter::Rule(true));
+        machine.evaluate_pose(&animations, 0.25);
+        assert_eq!(machine.active_transition(), walk_to_idle);
+        machine.set_parameter("IdleToWalk", Parameter::Rule(false));
+
+        for _ in 0..5 {
+            machine.evaluate_pose(&animations, 0.25);
+        }
+        assert!(machine.active_transition().is_none());
+        assert_eq!(machine.active_state(), idle);
+    }
+
+    #[test]
+    fn test_equal_priority_siblings_do_not_interrupt_each_other() {
+        let mut animations = AnimationContainer::new();
+        let mut machine = Machine::new();
+
+        let mut add_state = |name: &str| {
+            let node = machine.add_node(PoseNode::make_play_animation(
+                animations.add(Animation::default()),
+            ));
+            machine.add_state(State::new(name, node))
+        };
+        let idle = add_state("Idle");
+        let walk = add_state("Walk");
+        let run = add_state("Run");
+        machine.set_entry_state(idle);
+
+        let idle_to_walk = machine.add_transition(
+            Transition::new("Idle->Walk", idle, walk, 1.0, "IdleToWalk").with_interruptible(true),
+        );
+        machine.add_transition(
+            Transition::new("Idle->Run", idle, run, 1.0, "IdleToRun").with_interruptible(true),
+        );
+
+        machine
+            .set_parameter("IdleToWalk", Parameter::Rule(true))
+            .set_parameter("IdleToRun", Parameter::Rule(true));
+
+        // Transition that was added first wins and finishes blending without interruptions.
+        let mut interruptions = 0;
+        for _ in 0..6 {
+            machine.evaluate_pose(&animations, 0.25);
+            assert!(machine.active_transition() == idle_to_walk || machine.active_state() == walk);
+            while let Some(event) = machine.pop_event() {
+                if let Event::TransitionInterrupted { .. } = event {
+                    interruptions += 1;
+                }
+            }
+        }
+        assert_eq!(interruptions, 0);
+        assert!(machine.active_transition().is_none());
+        assert_eq!(machine.active_state(), walk);
+    }
+
+    #[test]
+    fn test_sub_machine() {
+        let mut animations = AnimationContainer::new();
+
+        let mut add_state = |machine: &mut Machine, name: &str| {
+            let node = machine.add_node(PoseNode::make_play_animation(
+                animations.add(Animation::default()),
+            ));
+            machine.add_state(State::new(name, node))
+        };
+
+        let mut combat = Machine::new();
+        let ready = add_state(&mut combat, "Ready");
+        let attack = add_state(&mut combat, "Attack");
+        combat.set_entry_state(ready);
+        combat.add_transition(Transition::new(
+            "Ready->Attack",
+            ready,
+            attack,
+            0.5,
+            "Attack",
+        ));
+
+        let mut machine = Machine::new();
+        let idle = add_state(&mut machine, "Idle");
+        let combat = machine.add_state(State::new_sub_machine(
+            "Combat",
+            SubMachine::new(combat).with_reset_on_enter(true),
+        ));
+        machine.set_entry_state(idle);
+        machine.add_transition(Transition::new(
+            "Idle->Combat",
+            idle,
+            combat,
+            0.5,
+            "IdleToCombat",
+        ));
+        machine.add_transition(Transition::new(
+            "Combat->Idle",
+            combat,
+            idle,
+            0.5,
+            "CombatToIdle",
+        ));
+
+        machine.evaluate_pose(&animations, 0.25);
+        assert_eq!(machine.active_state_path(), "Idle");
+
+        machine
+            .set_parameter("IdleToCombat", Parameter::Rule(true))
+            .set_parameter("Combat/Attack", Parameter::Rule(true));
+        for _ in 0..4 {
+            machine.evaluate_pose(&animations, 0.25);
+        }
+        assert_eq!(machine.active_state(), combat);
+        assert_eq!(machine.active_state_path(), "Combat/Attack");
+
+        // Nested machine must be reset when its state is entered again.
+        machine
+            .set_parameter("IdleToCombat", Parameter::Rule(false))
+            .set_parameter("CombatToIdle", Parameter::Rule(true))
+            .set_parameter("Combat/Attack", Parameter::Rule(false));
+        for _ in 0..3 {
+            machine.evaluate_pose(&animations, 0.25);
+        }
+        assert_eq!(machine.active_state_path(), "Idle");
+        machine
+            .set_parameter("CombatToIdle", Parameter::Rule(false))
+            .set_parameter("IdleToCombat", Parameter::Rule(true));
+        machine.evaluate_pose(&animations, 0.25);
+        assert_eq!(machine.active_state_path(), "Combat/Ready");
+    }
+
+    #[test]
+    fn test_time_only_animation_resume() {
+        let node = Handle::new(1, 1);
+        let mut track = Track::new();
+        track.set_node(node);
+        for time in [0.0, 1.0] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::new(time, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::identity(),
+            ));
+        }
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        animation.set_update_mode(AnimationUpdateMode::TimeOnly);
+
+        let mut animations = AnimationContainer::new();
+        let animation = animations.add(animation);
+
+        let mut machine = Machine::new();
+        let play = machine.add_node(PoseNode::make_play_animation(animation));
+        machine.add_state(State::new("Play", play));
+
+        for _ in 0..4 {
+            animations.update_animations(0.1);
+            machine.evaluate_pose(&animations, 0.1);
+        }
+
+        animations[animation].set_update_mode(AnimationUpdateMode::Always);
+        animations.update_animations(0.1);
+        let pose = machine.evaluate_pose(&animations, 0.1);
+        assert!((pose.local_pose(node).unwrap().position().x - 0.4).abs() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_evaluate_pose_at() {
+        let node = Handle::new(1, 1);
+        let make_animation = |x: f32| {
+            let mut track = Track::new();
+            track.set_node(node);
+            for time in [0.0, 1.0] {
+                track.add_key_frame(KeyFrame::new(
+                    time,
+                    Vector3::new(x + time, 0.0, 0.0),
+                    Vector3::new(1.0, 1.0, 1.0),
+                    UnitQuaternion::identity(),
+                ));
+            }
+            let mut animation = Animation::default();
+            animation.add_track(track);
+            animation
+        };
+
+        let mut animations = AnimationContainer::new();
+        let a = animations.add(make_animation(0.0));
+        let b = animations.add(make_animation(10.0));
+
+        let mut machine = Machine::new();
+        let play_a = machine.add_node(PoseNode::make_play_animation(a));
+        let play_b = machine.add_node(PoseNode::make_play_animation(b));
+        let blend = machine.add_node(PoseNode::make_blend_animations(vec![
+            BlendPose::with_param_weight("WeightA", play_a),
+            BlendPose::with_param_weight("WeightB", play_b),
+        ]));
+        let state = machine.add_state(State::new("Blend", blend));
+        machine
+            .set_parameter("WeightA", Parameter::Weight(1.0))
+            .set_parameter("WeightB", Parameter::Weight(0.0));
+
+        let position =
+            |pose: &crate::animation::AnimationPose| pose.local_pose(node).unwrap().position().x;
+
+        let pose = machine.evaluate_pose_at(state, &animations, 0.5, &[]);
+        assert!((position(&pose) - 0.5).abs() < 1.0e-5);
+
+        let pose = machine.evaluate_pose_at(
+            state,
+            &animations,
+            0.5,
+            &[
+                ("WeightA", Parameter::Weight(0.5)),
+                ("WeightB", Parameter::Weight(0.5)),
+            ],
+        );
+        assert!((position(&pose) - 5.5).abs() < 1.0e-5);
+
+        // Neither animations nor machine parameters are changed.
+        assert_eq!(animations[a].get_time_position(), 0.0);
+        assert!(matches!(
+            machine.parameters.get("WeightA"),
+            Some(Parameter::Weight(w)) if *w == 1.0
+        ));
+    }
+
+    #[test]
+    fn test_entry_state_and_rebind() {
+        let mut animations = AnimationContainer::new();
+        let mut machine = Machine::new();
+
+        let old_idle = animations.add(Animation::default());
+        let old_walk = animations.add(Animation::default());
+        let idle_node = machine.add_node(PoseNode::make_play_animation(old_idle));
+        let walk_node = machine.add_node(PoseNode::make_play_animation(old_walk));
+        let idle = machine.add_state(State::new("Idle", idle_node));
+        let walk = machine.add_state(State::new("Walk", walk_node));
+        assert_eq!(machine.entry_state(), idle);
+
+        machine.set_entry_state(walk);
+        assert_eq!(machine.active_state(), walk);
+        machine.evaluate_pose(&animations, 0.1);
+
+        // Entry state of running machine is used only after reset.
+        machine.set_entry_state(idle);
+        assert_eq!(machine.active_state(), walk);
+        machine.reset();
+        assert_eq!(machine.active_state(), idle);
+
+        // Model was destroyed, machine must not panic.
+        animations.clear();
+        assert_eq!(machine.dangling_animations(&animations).len(), 2);
+        machine.evaluate_pose(&animations, 0.1);
+
+        // Model was instantiated again.
+        let new_idle = animations.add(Animation::default());
+        let new_walk = animations.add(Animation::default());
+        let map = [(old_idle, new_idle), (old_walk, new_walk)]
+            .iter()
+            .cloned()
+            .collect();
+        assert_eq!(machine.rebind_animations(&map), 2);
+        assert!(machine.dangling_animations(&animations).is_empty());
+        machine.evaluate_pose(&animations, 0.1);
+        assert_eq!(machine.active_state(), idle);
+    }
+
+    #[test]
+    fn test_transition_signals_and_normalized_time() {
+        let mut animations = AnimationContainer::new();
+        let mut track = Track::new();
+        for time in [0.0, 2.0] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::default(),
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::identity(),
+            ));
+        }
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        let jump_animation = animations.add(animation);
+
+        let mut machine = Machine::new();
+        let idle_node = machine.add_node(PoseNode::make_play_animation(
+            animations.add(Animation::default()),
+        ));
+        let jump_node = machine.add_node(PoseNode::make_play_animation(jump_animation));
+        let idle = machine.add_state(State::new("Idle", idle_node));
+        let jump = machine.add_state(State::new("Jump", jump_node));
+        let idle_to_jump = machine.add_transition(
+            Transition::new("Idle->Jump", idle, jump, 1.0, "Jump")
+                .with_signal(AnimationSignal::new(0, 0.0))
+                .with_signal(AnimationSignal::new(1, 0.5))
+                .with_signal(AnimationSignal::new(2, 1.0)),
+        );
+
+        machine.set_parameter("Jump", Parameter::Rule(true));
+        let mut events = Vec::new();
+        for _ in 0..4 {
+            machine.evaluate_pose(&animations, 0.25);
+            while let Some(event) = machine.pop_event() {
+                events.push(match event {
+                    Event::StateEnter(_) => "Enter".to_owned(),
+                    Event::StateLeave(_) => "Leave".to_owned(),
+                    Event::ActiveStateChanged(_) => "Changed".to_owned(),
+                    Event::TransitionInterrupted { .. } => "Interrupted".to_owned(),
+                    Event::TransitionSignal {
+                        transition,
+                        signal_id,
+                    } => {
+                        assert_eq!(transition, idle_to_jump);
+                        format!("Signal{}", signal_id)
+                    }
+                });
+            }
+        }
+        assert_eq!(
+            events,
+            ["Leave", "Enter", "Signal0", "Signal1", "Signal2", "Changed"]
+        );
+        assert_eq!(machine.active_state(), jump);
+
+        animations[jump_animation].set_time_position(0.5);
+        assert_eq!(machine.state_normalized_time(jump, &animations), Some(0.25));
+        animations[jump_animation].set_speed(-1.0);
+        assert_eq!(machine.state_normalized_time(jump, &animations), Some(0.75));
+        // Animation of zero length.
+        assert_eq!(machine.state_normalized_time(idle, &animations), None);
+    }
+
+    #[test]
+    fn test_additive_layers() {
+        let node = Handle::new(1, 1);
+        let make_animation = |axis: Vector3<f32>, angle: f32| {
+            let mut track = Track::new();
+            track.set_node(node);
+            for (time, k) in [(0.0, 0.0), (1.0, 1.0)] {
+                track.add_key_frame(KeyFrame::new(
+                    time,
+                    Vector3::default(),
+                    Vector3::new(1.0, 1.0, 1.0),
+                    UnitQuaternion::from_axis_angle(&Unit::new_normalize(axis), angle * k),
+                ));
+            }
+            let mut animation = Animation::default();
+            animation.add_track(track);
+            animation.set_loop(false).set_time_position(1.0);
+            animation
+        };
+
+        let mut animations = AnimationContainer::new();
+        let base = animations.add(make_animation(Vector3::z(), 0.3));
+        let mut first = make_animation(Vector3::x(), 1.0);
+        first.set_additive(true);
+        let first = animations.add(first);
+        let mut second = make_animation(Vector3::y(), 1.0);
+        second.set_additive(true);
+        let second = animations.add(second);
+        animations.update_animations(0.0);
+
+        let mut machine = Machine::new();
+        let play = machine.add_node(PoseNode::make_play_animation(base));
+        machine.add_state(State::new("Base", play));
+        machine.add_additive_layer(first, 1.0);
+        let second_layer = machine.add_additive_layer(second, 1.0);
+
+        let base_rotation = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), 0.3);
+        let x = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 1.0);
+        let y = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 1.0);
+
+        // Layers are composed in order they were added.
+        let rotation = machine
+            .evaluate_pose(&animations, 0.0)
+            .local_pose(node)
+            .unwrap()
+            .rotation();
+        assert!(rotation.angle_to(&(base_rotation * x * y)) < 1.0e-4);
+        assert!(rotation.angle_to(&(base_rotation * y * x)) > 1.0e-2);
+
+        // Layer with zero weight has no effect.
+        machine.set_additive_layer_weight(second_layer, 0.0);
+        let rotation = machine
+            .evaluate_pose(&animations, 0.0)
+            .local_pose(node)
+            .unwrap()
+            .rotation();
+        assert!(rotation.angle_to(&(base_rotation * x)) < 1.0e-4);
+    }
+}
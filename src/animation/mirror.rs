@@ -0,0 +1,496 @@
+//! Animation mirroring allows you to get "strafe right" from "strafe left", "turn right" from
+//! "turn left" and so on, without authoring both clips.
+//!
+//! # How it works
+//!
+//! [`AnimationMirror`] pairs left and right bones of a model, it is built once from the skeleton
+//! of a model instance in its rest pose. Pairs are found by names - `Left`/`Right`, `_L`/`_R`,
+//! `.L`/`.R`, `L_`/`R_` and ` L `/` R ` conventions are recognized, names that do not follow any
+//! of them can be paired explicitly. Bones without a pair (spine, head, etc.) are mirrored onto
+//! themselves.
+//!
+//! Mirroring is done in the space of the model root: transform of every bone is reflected across
+//! the sagittal plane of the model (the plane with normal [`MirrorAxis`], which is X by default)
+//! relative to the rest pose and assigned to the paired bone. This way it works with any
+//! orientation of local axes of bones. Root motion is mirrored as well, since the translation of
+//! a hip bone or the root itself is reflected too. Scale of bones is copied as is, non-uniform
+//! scale in the hierarchy is not supported.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{mirror::AnimationMirrorBuilder, Animation},
+//!     core::pool::Handle,
+//!     scene::{node::Node, Scene},
+//! };
+//! use std::sync::Arc;
+//!
+//! fn make_strafe_right(scene: &mut Scene, model: Handle<Node>, strafe_left: Handle<Animation>) {
+//!     // Must be built while the model is in its rest pose.
+//!     let mirror = Arc::new(AnimationMirrorBuilder::new(model).build(&scene.graph));
+//!
+//!     let mut strafe_right = scene.animations.get(strafe_left).clone();
+//!     strafe_right.set_mirror(Some(mirror)).set_mirrored(true);
+//!     scene.animations.add(strafe_right);
+//! }
+//! ```
+
+use crate::{
+    animation::{AnimationPose, LocalPose},
+    core::{
+        algebra::{Quaternion, UnitQuaternion, Vector3},
+        pool::Handle,
+    },
+    scene::{graph::Graph, node::Node},
+    utils::log::log_warn,
+};
+use fxhash::FxHashMap;
+use std::fmt::{Display, Formatter};
+
+/// Normal of the mirroring plane in the space of the model root.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MirrorAxis {
+    /// Left and right sides of the model are along X axis, it is the most common case.
+    X,
+    /// Left and right sides of the model are along Y axis.
+    Y,
+    /// Left and right sides of the model are along Z axis.
+    Z,
+}
+
+impl Default for MirrorAxis {
+    fn default() -> Self {
+        Self::X
+    }
+}
+
+impl MirrorAxis {
+    fn index(self) -> usize {
+        match self {
+            MirrorAxis::X => 0,
+            MirrorAxis::Y => 1,
+            MirrorAxis::Z => 2,
+        }
+    }
+
+    fn mirror_vector(self, mut v: Vector3<f32>) -> Vector3<f32> {
+        v[self.index()] = -v[self.index()];
+        v
+    }
+
+    // Reflection flips handedness, so rotation axis is reflected and angle is negated.
+    fn mirror_rotation(self, q: UnitQuaternion<f32>) -> UnitQuaternion<f32> {
+        let axis = -self.mirror_vector(q.imag());
+        UnitQuaternion::new_unchecked(Quaternion::new(q.w, axis.x, axis.y, axis.z))
+    }
+}
+
+/// A problem that was found while building a mirror.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MirrorWarning {
+    /// Name of a bone follows left/right naming convention, but there is no bone with the
+    /// mirrored name. The bone will be mirrored onto itself.
+    UnmatchedBone {
+        /// Name of the bone.
+        bone: String,
+        /// Name of the counterpart that was expected.
+        expected: String,
+    },
+    /// A bone from the override table does not exist in the skeleton.
+    MissingOverride {
+        /// Name of the bone.
+        bone: String,
+    },
+}
+
+impl Display for MirrorWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MirrorWarning::UnmatchedBone { bone, expected } => write!(
+                f,
+                "Bone {} has no mirrored counterpart (expected {})!",
+                bone, expected
+            ),
+            MirrorWarning::MissingOverride { bone } => {
+                write!(f, "Mirror override bone {} does not exist!", bone)
+            }
+        }
+    }
+}
+
+// Pairs of markers of left and right sides, the first match wins.
+const SIDE_MARKERS: [(&str, &str); 5] = [
+    ("Left", "Right"),
+    ("left", "right"),
+    ("LEFT", "RIGHT"),
+    ("_L_", "_R_"),
+    (" L ", " R "),
+];
+
+// Markers that are valid only at the beginning or at the end of a name.
+const SIDE_PREFIXES: [(&str, &str); 2] = [("L_", "R_"), ("l_", "r_")];
+const SIDE_SUFFIXES: [(&str, &str); 4] = [("_L", "_R"), ("_l", "_r"), (".L", ".R"), (".l", ".r")];
+
+/// Returns the name of a bone on the other side of a model, or `None` if the name does not follow
+/// any of known naming conventions.
+pub fn mirrored_bone_name(name: &str) -> Option<String> {
+    for &(left, right) in SIDE_MARKERS.iter() {
+        if name.contains(left) {
+            return Some(name.replacen(left, right, 1));
+        } else if name.contains(right) {
+            return Some(name.replacen(right, left, 1));
+        }
+    }
+    for &(left, right) in SIDE_PREFIXES.iter() {
+        if let Some(rest) = name.strip_prefix(left) {
+            return Some(format!("{}{}", right, rest));
+        } else if let Some(rest) = name.strip_prefix(right) {
+            return Some(format!("{}{}", left, rest));
+        }
+    }
+    for &(left, right) in SIDE_SUFFIXES.iter() {
+        if let Some(rest) = name.strip_suffix(left) {
+            return Some(format!("{}{}", rest, right));
+        } else if let Some(rest) = name.strip_suffix(right) {
+            return Some(format!("{}{}", rest, left));
+        }
+    }
+    None
+}
+
+#[derive(Clone, Debug)]
+struct MirrorBone {
+    node: Handle<Node>,
+    // Index of parent bone, `None` if the parent is the model root.
+    parent: Option<usize>,
+    // Index of the bone on the other side, it is the index of the bone itself for central bones.
+    counterpart: usize,
+    rest_position: Vector3<f32>,
+    rest_rotation: UnitQuaternion<f32>,
+    // Rotation in the space of the model root in rest pose.
+    rest_model_rotation: UnitQuaternion<f32>,
+}
+
+/// Builds [`AnimationMirror`], see module docs.
+pub struct AnimationMirrorBuilder {
+    root: Handle<Node>,
+    axis: MirrorAxis,
+    overrides: Vec<(String, String)>,
+}
+
+impl AnimationMirrorBuilder {
+    /// Creates new builder for a model with given root.
+    pub fn new(root: Handle<Node>) -> Self {
+        Self {
+            root,
+            axis: Default::default(),
+            overrides: Default::default(),
+        }
+    }
+
+    /// Sets normal of the mirroring plane. Default is X.
+    pub fn with_axis(mut self, axis: MirrorAxis) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Pairs two bones explicitly, it takes precedence over naming conventions.
+    pub fn with_override<A: AsRef<str>, B: AsRef<str>>(mut self, a: A, b: B) -> Self {
+        self.overrides
+            .push((a.as_ref().to_owned(), b.as_ref().to_owned()));
+        self
+    }
+
+    /// Creates the mirror from current local transforms of the model, so the model must be in its
+    /// rest pose. Every problem with the skeleton is written to the log and is available later via
+    /// [`AnimationMirror::warnings`].
+    pub fn build(self, graph: &Graph) -> AnimationMirror {
+        let mut bones = Vec::<MirrorBone>::new();
+        let mut indices = FxHashMap::default();
+        let mut names = FxHashMap::default();
+
+        // Depth-first order guarantees that parents are processed before their children.
+        for handle in graph.traverse_handle_iter(self.root) {
+            if handle == self.root {
+                continue;
+            }
+            let node = &graph[handle];
+            let parent = indices.get(&node.parent()).cloned();
+            let transform = node.local_transform();
+            let rest_rotation = **transform.rotation();
+            let rest_model_rotation = match parent {
+                Some(parent) => bones[parent].rest_model_rotation * rest_rotation,
+                None => rest_rotation,
+            };
+            let index = bones.len();
+            indices.insert(handle, index);
+            names.insert(node.name_owned(), index);
+            bones.push(MirrorBone {
+                node: handle,
+                parent,
+                counterpart: index,
+                rest_position: **transform.position(),
+                rest_rotation,
+                rest_model_rotation,
+            });
+        }
+
+        let mut warnings = Vec::new();
+
+        for (a, b) in self.overrides.iter() {
+            match (names.get(a), names.get(b)) {
+                (Some(&a), Some(&b)) => {
+                    bones[a].counterpart = b;
+                    bones[b].counterpart = a;
+                }
+                (a_index, _) => warnings.push(MirrorWarning::MissingOverride {
+                    bone: if a_index.is_none() {
+                        a.clone()
+                    } else {
+                        b.clone()
+                    },
+                }),
+            }
+        }
+
+        for index in 0..bones.len() {
+            // Already paired by an override.
+            if bones[index].counterpart != index {
+                continue;
+            }
+            let name = graph[bones[index].node].name();
+            if let Some(expected) = mirrored_bone_name(name) {
+                match names.get(&expected) {
+                    Some(&other) => bones[index].counterpart = other,
+                    None => warnings.push(MirrorWarning::UnmatchedBone {
+                        bone: name.to_owned(),
+                        expected,
+                    }),
+                }
+            }
+        }
+
+        for warning in warnings.iter() {
+            log_warn!("{}", warning.to_string());
+        }
+
+        AnimationMirror {
+            root: self.root,
+            axis: self.axis,
+            bones,
+            warnings,
+        }
+    }
+}
+
+/// Mapping between left and right bones of a model, see module docs.
+#[derive(Clone, Debug)]
+pub struct AnimationMirror {
+    root: Handle<Node>,
+    axis: MirrorAxis,
+    bones: Vec<MirrorBone>,
+    warnings: Vec<MirrorWarning>,
+}
+
+impl AnimationMirror {
+    /// Returns root of the model.
+    pub fn root(&self) -> Handle<Node> {
+        self.root
+    }
+
+    /// Returns normal of the mirroring plane.
+    pub fn axis(&self) -> MirrorAxis {
+        self.axis
+    }
+
+    /// Returns every problem that was found while building the mirror.
+    pub fn warnings(&self) -> &[MirrorWarning] {
+        &self.warnings
+    }
+
+    /// Returns the bone on the other side of the model. Central bones are mapped onto themselves,
+    /// `None` is returned for nodes that are not part of the model.
+    pub fn counterpart(&self, node: Handle<Node>) -> Option<Handle<Node>> {
+        self.bones
+            .iter()
+            .find(|bone| bone.node == node)
+            .map(|bone| self.bones[bone.counterpart].node)
+    }
+
+    /// Mirrors given pose. Every bone of the result is animated if its counterpart is animated in
+    /// the source pose.
+    pub fn mirror_pose(&self, pose: &AnimationPose) -> AnimationPose {
+        let mut result = AnimationPose::default();
+
+        // Pose of every bone in the space of the model root.
+        let mut model = Vec::with_capacity(self.bones.len());
+        for bone in self.bones.iter() {
+            let (position, rotation) = pose
+                .local_pose(bone.node)
+                .map_or((bone.rest_position, bone.rest_rotation), |local| {
+                    (local.position, local.rotation)
+                });
+            model.push(match bone.parent {
+                Some(parent) => {
+                    let (parent_position, parent_rotation) = model[parent];
+                    (
+                        parent_position + parent_rotation * position,
+                        parent_rotation * rotation,
+                    )
+                }
+                None => (position, rotation),
+            });
+        }
+
+        // Reflect deviation of each bone from its rest pose and apply it to its counterpart.
+        let mirrored = self
+            .bones
+            .iter()
+            .map(|bone| {
+                let source = &self.bones[bone.counterpart];
+                let (position, rotation) = model[bone.counterpart];
+                let deviation = rotation * source.rest_model_rotation.inverse();
+                (
+                    self.axis.mirror_vector(position),
+                    self.axis.mirror_rotation(deviation) * bone.rest_model_rotation,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Back to local space of each bone.
+        for (index, bone) in self.bones.iter().enumerate() {
+            if let Some(source) = pose.local_pose(self.bones[bone.counterpart].node) {
+                let (position, rotation) = mirrored[index];
+                let (position, rotation) = match bone.parent {
+                    Some(parent) => {
+                        let (parent_position, parent_rotation) = mirrored[parent];
+                        let inv_parent_rotation = parent_rotation.inverse();
+                        (
+                            inv_parent_rotation * (position - parent_position),
+                            inv_parent_rotation * rotation,
+                        )
+                    }
+                    None => (position, rotation),
+                };
+                result.add_local_pose(LocalPose {
+                    node: bone.node,
+                    position,
+                    rotation,
+                    scale: source.scale,
+                });
+            }
+        }
+
+        // The root itself has no counterpart, reflect its motion in the space of its parent.
+        if let Some(root) = pose.local_pose(self.root) {
+            result.add_local_pose(LocalPose {
+                node: self.root,
+                position: self.axis.mirror_vector(root.position),
+                rotation: self.axis.mirror_rotation(root.rotation),
+                scale: root.scale,
+            });
+        }
+
+        // Weights of morph targets are not mirrored, there is no mapping of left and right ones.
+        result.morph_weights = pose.morph_weights.clone();
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            mirror::{mirrored_bone_name, AnimationMirrorBuilder, MirrorWarning},
+            AnimationPose, LocalPose,
+        },
+        core::algebra::{UnitQuaternion, Vector3},
+        scene::{base::BaseBuilder, graph::Graph, transform::TransformBuilder},
+    };
+
+    #[test]
+    fn test_mirrored_bone_name() {
+        assert_eq!(
+            mirrored_bone_name("mixamorig:LeftArm").as_deref(),
+            Some("mixamorig:RightArm")
+        );
+        assert_eq!(mirrored_bone_name("hand_R").as_deref(), Some("hand_L"));
+        assert_eq!(
+            mirrored_bone_name("Bip01 L Thigh").as_deref(),
+            Some("Bip01 R Thigh")
+        );
+        assert_eq!(
+            mirrored_bone_name("upper_arm.L").as_deref(),
+            Some("upper_arm.R")
+        );
+        assert_eq!(mirrored_bone_name("Spine"), None);
+        // Legs are not left sides.
+        assert_eq!(mirrored_bone_name("Leg"), None);
+    }
+
+    #[test]
+    fn test_mirror_pose() {
+        let mut graph = Graph::new();
+        let make_bone = |graph: &mut Graph, name: &str, x: f32| {
+            BaseBuilder::new()
+                .with_name(name)
+                .with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(x, 1.0, 0.0))
+                        .build(),
+                )
+                .build(graph)
+        };
+        let left = make_bone(&mut graph, "LeftArm", 1.0);
+        let right = make_bone(&mut graph, "RightArm", -1.0);
+        let tail = make_bone(&mut graph, "Tail_L", 0.0);
+        let root = BaseBuilder::new()
+            .with_children(&[left, right, tail])
+            .build(&mut graph);
+
+        let mirror = AnimationMirrorBuilder::new(root).build(&graph);
+        assert_eq!(mirror.counterpart(left), Some(right));
+        assert_eq!(mirror.counterpart(tail), Some(tail));
+        assert_eq!(
+            mirror.warnings(),
+            &[MirrorWarning::UnmatchedBone {
+                bone: "Tail_L".to_owned(),
+                expected: "Tail_R".to_owned(),
+            }]
+        );
+
+        // Left arm is raised forward, only the left arm is animated.
+        let mut pose = AnimationPose::default();
+        pose.add_local_pose(LocalPose {
+            node: left,
+            position: Vector3::new(1.0, 1.0, 0.5),
+            rotation: UnitQuaternion::from_euler_angles(0.3, 0.2, 0.1),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        });
+
+        let mirrored = mirror.mirror_pose(&pose);
+        assert!(mirrored.local_pose(left).is_none());
+        let right_pose = mirrored.local_pose(right).unwrap();
+        assert!(
+            right_pose
+                .position
+                .metric_distance(&Vector3::new(-1.0, 1.0, 0.5))
+                < 1.0e-5
+        );
+        // Rotation about X stays, rotations about Y and Z are negated.
+        let expected = UnitQuaternion::from_euler_angles(0.3, -0.2, -0.1);
+        assert!(right_pose.rotation.angle_to(&expected) < 1.0e-5);
+
+        // Mirroring twice gives the original pose.
+        let twice = mirror.mirror_pose(&mirrored);
+        let left_pose = twice.local_pose(left).unwrap();
+        assert!(
+            left_pose
+                .position
+                .metric_distance(&Vector3::new(1.0, 1.0, 0.5))
+                < 1.0e-5
+        );
+    }
+}
@@ -1,24 +1,51 @@
+//! Animations, their poses and blending machine.
+//!
+//! # Additive animations
+//!
+//! An animation can be marked as additive (see [`Animation::set_additive`]), in this case its pose
+//! is not an absolute pose, but a difference between sampled pose and a reference pose: first
+//! frame of the animation or an explicitly provided pose (see
+//! [`Animation::set_additive_reference`]). Such pose is applied on top of another pose using
+//! [`AnimationPose::add_additive`] with some weight, this allows to add breathing, weapon sway or
+//! flinch reactions on top of whatever locomotion produces instead of replacing it. Usually
+//! additive animations are used as additive layers of an animation blending machine, see
+//! [`machine`] module docs.
+//!
+//! # Morph target animation
+//!
+//! Besides transforms of nodes, an animation can change weights of morph targets of meshes (see
+//! [`crate::scene::mesh::Mesh::set_morph_weight`]) using [`MorphTrack`]s. Sampled weights are
+//! stored in animation poses together with local transforms, so they're blended by the machine
+//! the same way as transforms and they're applied to meshes by [`AnimationPose::apply`].
+
 pub mod machine;
+pub mod mirror;
+pub mod retarget;
 
 use crate::{
+    animation::{mirror::AnimationMirror, retarget::RetargetData},
     asset::ResourceState,
     core::{
-        algebra::{UnitQuaternion, Vector3},
+        algebra::{Matrix4, UnitQuaternion, Vector3},
         math::{clampf, wrapf},
         pool::{
             Handle, Pool, PoolIterator, PoolIteratorMut, PoolPairIterator, PoolPairIteratorMut,
             Ticket,
         },
+        profile_scope,
         visitor::{Visit, VisitResult, Visitor},
     },
     resource::model::Model,
     scene::{graph::Graph, node::Node},
-    utils::log::{Log, MessageKind},
+    utils::log::{log_error, log_info},
 };
 use fxhash::FxHashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use rayon::prelude::*;
 use std::{
     collections::VecDeque,
     ops::{Index, IndexMut},
+    sync::Arc,
 };
 
 #[derive(Copy, Clone, Debug)]
@@ -257,6 +284,129 @@ impl Track {
     }
 }
 
+/// Key frame of a weight of a morph target.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Visit)]
+pub struct MorphKeyFrame {
+    pub time: f32,
+    pub weight: f32,
+}
+
+impl MorphKeyFrame {
+    pub fn new(time: f32, weight: f32) -> Self {
+        Self { time, weight }
+    }
+}
+
+/// A track that animates weight of a morph target of a mesh node, see module docs for more info.
+/// Key frames are not saved (the same as key frames of [`Track`]), they're restored from the
+/// animation resource when a scene is loaded.
+#[derive(Clone, Debug)]
+pub struct MorphTrack {
+    frames: Vec<MorphKeyFrame>,
+    enabled: bool,
+    max_time: f32,
+    node: Handle<Node>,
+    target: usize,
+}
+
+impl Default for MorphTrack {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            enabled: true,
+            max_time: 0.0,
+            node: Default::default(),
+            target: 0,
+        }
+    }
+}
+
+impl Visit for MorphTrack {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.enabled.visit("Enabled", visitor)?;
+        self.max_time.visit("MaxTime", visitor)?;
+        self.node.visit("Node", visitor)?;
+        self.target.visit("Target", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+impl MorphTrack {
+    /// Creates new track for a morph target with given index of given mesh node.
+    pub fn new(node: Handle<Node>, target: usize) -> Self {
+        Self {
+            node,
+            target,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_node(&mut self, node: Handle<Node>) {
+        self.node = node;
+    }
+
+    pub fn node(&self) -> Handle<Node> {
+        self.node
+    }
+
+    /// Returns index of the morph target that is animated by the track.
+    pub fn target(&self) -> usize {
+        self.target
+    }
+
+    pub fn add_key_frame(&mut self, key_frame: MorphKeyFrame) {
+        let index = self
+            .frames
+            .iter()
+            .position(|k| key_frame.time < k.time)
+            .unwrap_or_else(|| self.frames.len());
+        self.frames.insert(index, key_frame);
+        self.max_time = self.max_time.max(key_frame.time);
+    }
+
+    pub fn set_key_frames(&mut self, key_frames: &[MorphKeyFrame]) {
+        self.frames = key_frames.to_vec();
+        self.max_time = self.frames.iter().fold(0.0, |max, k| k.time.max(max));
+    }
+
+    pub fn key_frames(&self) -> &[MorphKeyFrame] {
+        &self.frames
+    }
+
+    pub fn enable(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns linearly interpolated weight at given time, `None` if the track has no key frames.
+    pub fn weight_at(&self, time: f32) -> Option<f32> {
+        let first = self.frames.first()?;
+        if time <= first.time {
+            return Some(first.weight);
+        }
+        match self.frames.iter().position(|k| k.time >= time) {
+            Some(right_index) => {
+                let left = &self.frames[right_index - 1];
+                let right = &self.frames[right_index];
+                let span = right.time - left.time;
+                let t = if span > 0.0 {
+                    (time - left.time) / span
+                } else {
+                    1.0
+                };
+                Some(left.weight + (right.weight - left.weight) * t)
+            }
+            None => self.frames.last().map(|k| k.weight),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct AnimationEvent {
     pub signal_id: u64,
@@ -317,10 +467,268 @@ impl Visit for AnimationSignal {
     }
 }
 
+/// A component of a local pose that is checked by a [`ConditionSignal`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Visit)]
+#[repr(u32)]
+pub enum PoseComponent {
+    /// X coordinate of local position.
+    PositionX = 0,
+    /// Y coordinate of local position.
+    PositionY = 1,
+    /// Z coordinate of local position.
+    PositionZ = 2,
+    /// Scale along local X axis.
+    ScaleX = 3,
+    /// Scale along local Y axis.
+    ScaleY = 4,
+    /// Scale along local Z axis.
+    ScaleZ = 5,
+    /// Angle (in radians, `[0; pi]` range) of local rotation around its axis.
+    RotationAngle = 6,
+    /// Roll (rotation around X axis) Euler angle of local rotation, in radians.
+    RotationX = 7,
+    /// Pitch (rotation around Y axis) Euler angle of local rotation, in radians.
+    RotationY = 8,
+    /// Yaw (rotation around Z axis) Euler angle of local rotation, in radians.
+    RotationZ = 9,
+}
+
+impl Default for PoseComponent {
+    fn default() -> Self {
+        Self::PositionY
+    }
+}
+
+impl PoseComponent {
+    /// Extracts the component from given local pose.
+    pub fn value(self, pose: &LocalPose) -> f32 {
+        match self {
+            Self::PositionX => pose.position.x,
+            Self::PositionY => pose.position.y,
+            Self::PositionZ => pose.position.z,
+            Self::ScaleX => pose.scale.x,
+            Self::ScaleY => pose.scale.y,
+            Self::ScaleZ => pose.scale.z,
+            Self::RotationAngle => pose.rotation.angle(),
+            Self::RotationX => pose.rotation.euler_angles().0,
+            Self::RotationY => pose.rotation.euler_angles().1,
+            Self::RotationZ => pose.rotation.euler_angles().2,
+        }
+    }
+}
+
+/// Comparison of a pose component with a threshold of a [`ConditionSignal`].
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Visit)]
+#[repr(u32)]
+pub enum Comparison {
+    /// Condition is true when the value is greater than the threshold.
+    Greater = 0,
+    /// Condition is true when the value is less than the threshold.
+    Less = 1,
+}
+
+impl Default for Comparison {
+    fn default() -> Self {
+        Self::Greater
+    }
+}
+
+/// Defines which changes of a condition emit an event.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Visit)]
+#[repr(u32)]
+pub enum SignalEdge {
+    /// An event is emitted when the condition becomes true.
+    Rising = 0,
+    /// An event is emitted when the condition becomes false.
+    Falling = 1,
+    /// An event is emitted on any change of the condition.
+    Both = 2,
+}
+
+impl Default for SignalEdge {
+    fn default() -> Self {
+        Self::Rising
+    }
+}
+
+/// A signal that emits an event when an animated value crosses a threshold, for example when
+/// a hand bone rises above some height during a throw. Unlike [`AnimationSignal`], it does not
+/// depend on timing of the clip, so it keeps working when the clip is changed or retimed.
+///
+/// The condition is evaluated against the local pose of the track of given node at current
+/// time position of the animation after every update, events are put in the same queue as
+/// events of timestamp signals (see [`Animation::pop_event`]). The condition is compared with
+/// its value at previous update, so it works the same when the animation is played backwards
+/// and when it wraps around - a jump of the value at wrap point is treated as any other change.
+/// Nothing is emitted on first update and after the time position is set explicitly.
+#[derive(Clone, Debug, Visit)]
+pub struct ConditionSignal {
+    id: u64,
+    node: Handle<Node>,
+    component: PoseComponent,
+    comparison: Comparison,
+    threshold: f32,
+    edge: SignalEdge,
+    enabled: bool,
+    #[visit(skip)]
+    state: Option<bool>,
+}
+
+impl Default for ConditionSignal {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            node: Default::default(),
+            component: Default::default(),
+            comparison: Default::default(),
+            threshold: 0.0,
+            edge: Default::default(),
+            enabled: true,
+            state: None,
+        }
+    }
+}
+
+impl ConditionSignal {
+    /// Creates new signal that emits events with given id when the condition
+    /// `component(pose of node) comparison threshold` changes according to given edge.
+    pub fn new(
+        id: u64,
+        node: Handle<Node>,
+        component: PoseComponent,
+        comparison: Comparison,
+        threshold: f32,
+        edge: SignalEdge,
+    ) -> Self {
+        Self {
+            id,
+            node,
+            component,
+            comparison,
+            threshold,
+            edge,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_enabled(&mut self, value: bool) {
+        self.enabled = value;
+        self.state = None;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn node(&self) -> Handle<Node> {
+        self.node
+    }
+
+    pub fn component(&self) -> PoseComponent {
+        self.component
+    }
+
+    pub fn comparison(&self) -> Comparison {
+        self.comparison
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    pub fn edge(&self) -> SignalEdge {
+        self.edge
+    }
+
+    /// Returns `true` if the condition was true at last update, `None` if it was not evaluated
+    /// yet.
+    pub fn state(&self) -> Option<bool> {
+        self.state
+    }
+
+    fn is_met(&self, pose: &LocalPose) -> bool {
+        let value = self.component.value(pose);
+        match self.comparison {
+            Comparison::Greater => value > self.threshold,
+            Comparison::Less => value < self.threshold,
+        }
+    }
+
+    // Evaluates the condition and returns `true` if an event must be emitted.
+    fn update(&mut self, tracks: &[Track], time: f32) -> bool {
+        let pose = match tracks
+            .iter()
+            .find(|track| track.node == self.node)
+            .and_then(|track| track.get_local_pose(time))
+        {
+            Some(pose) => pose,
+            None => return false,
+        };
+
+        let new_state = self.is_met(&pose);
+        let fire = match self.state {
+            Some(old_state) if old_state != new_state => match self.edge {
+                SignalEdge::Rising => new_state,
+                SignalEdge::Falling => !new_state,
+                SignalEdge::Both => true,
+            },
+            _ => false,
+        };
+        self.state = Some(new_state);
+        fire
+    }
+}
+
+/// Defines when animation pose should be sampled.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Visit)]
+#[repr(u32)]
+pub enum AnimationUpdateMode {
+    /// Pose is sampled on every update.
+    Always = 0,
+
+    /// Pose is sampled only if at least one animated node is visible, its position is inside
+    /// the frustum of any enabled camera of the scene and it is closer to that camera than
+    /// [`Animation::lod_distance`]. Time position is still advanced (and signals are still
+    /// emitted) for animations that are not visible, so they stay in sync.
+    OnlyWhenVisible = 1,
+
+    /// Pose is never sampled, only time position is advanced and signals are emitted. Could be
+    /// useful for characters that are known to be off-screen, but whose animation timing still
+    /// matters for gameplay (attack signals, footsteps, etc.). When update mode is changed back,
+    /// pose is sampled at current time position on next update.
+    TimeOnly = 2,
+}
+
+impl Default for AnimationUpdateMode {
+    fn default() -> Self {
+        Self::Always
+    }
+}
+
+/// Shows how many animations were processed during last update of [`AnimationContainer`].
+/// Only enabled animations are counted.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct AnimationStatistics {
+    /// Amount of animations whose pose was sampled.
+    pub sampled: usize,
+
+    /// Amount of animations whose pose was not sampled, because its time position didn't change.
+    pub skipped: usize,
+
+    /// Amount of animations whose time position was advanced, but pose was not sampled because of
+    /// their update mode (see [`AnimationUpdateMode`]).
+    pub time_only: usize,
+}
+
 #[derive(Debug)]
 pub struct Animation {
     // TODO: Extract into separate struct AnimationTimeline
     tracks: Vec<Track>,
+    morph_tracks: Vec<MorphTrack>,
     length: f32,
     time_position: f32,
     ///////////////////////////////////////////////////////
@@ -330,7 +738,25 @@ pub struct Animation {
     pub(in crate) resource: Option<Model>,
     pose: AnimationPose,
     signals: Vec<AnimationSignal>,
+    condition_signals: Vec<ConditionSignal>,
     events: VecDeque<AnimationEvent>,
+    update_mode: AnimationUpdateMode,
+    // Time position at which current pose was sampled.
+    pose_time: f32,
+    // Whether tracks were changed after last sampling and the pose must be sampled again.
+    pose_dirty: bool,
+    lod_distance: f32,
+    // Whether last update was time-only and the pose does not match time position.
+    pose_outdated: bool,
+    mirror: Option<Arc<AnimationMirror>>,
+    mirrored: bool,
+    additive: bool,
+    additive_reference: Option<AnimationPose>,
+    // Cached first frame pose of additive animation, it is used as reference pose when there is
+    // no explicit one.
+    first_frame_pose: Option<AnimationPose>,
+    // Set when the animation was retargeted with non-default options, see [`retarget`] module.
+    pub(in crate) retarget: Option<RetargetData>,
 }
 
 /// Snapshot of scene node local transform state.
@@ -369,6 +795,37 @@ impl LocalPose {
         self.scale += other.scale.scale(weight);
     }
 
+    // Turns the pose into a difference between it and given reference pose.
+    fn make_relative(&mut self, reference: &LocalPose) {
+        self.position -= reference.position;
+        let rotation = reference.rotation.inverse() * self.rotation;
+        // Make sure that the delta takes the shortest path, otherwise its fraction would rotate
+        // in opposite direction.
+        self.rotation = if rotation.w < 0.0 {
+            UnitQuaternion::new_unchecked(-rotation.into_inner())
+        } else {
+            rotation
+        };
+        self.scale = self.scale.zip_map(&reference.scale, |scale, reference| {
+            if reference.abs() > f32::EPSILON {
+                scale / reference
+            } else {
+                1.0
+            }
+        });
+    }
+
+    /// Applies difference pose (see [`Animation::set_additive`]) on top of the pose with given
+    /// weight. Rotations are composed, so the result does not depend on how far the rotation
+    /// of the difference pose is from the rotation of the pose.
+    pub fn add_additive(&mut self, delta: &LocalPose, weight: f32) {
+        self.position += delta.position.scale(weight);
+        self.rotation *= delta.rotation.powf(weight);
+        self.scale = self
+            .scale
+            .component_mul(&Vector3::new(1.0, 1.0, 1.0).lerp(&delta.scale, weight));
+    }
+
     pub fn position(&self) -> Vector3<f32> {
         self.position
     }
@@ -382,9 +839,11 @@ impl LocalPose {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct AnimationPose {
     local_poses: FxHashMap<Handle<Node>, LocalPose>,
+    // Weights of morph targets, key is a pair of a mesh node and an index of its morph target.
+    morph_weights: FxHashMap<(Handle<Node>, usize), f32>,
 }
 
 impl AnimationPose {
@@ -393,6 +852,7 @@ impl AnimationPose {
         for (handle, local_pose) in self.local_poses.iter() {
             dest.local_poses.insert(*handle, local_pose.clone());
         }
+        dest.morph_weights.extend(self.morph_weights.iter());
     }
 
     pub fn blend_with(&mut self, other: &AnimationPose, weight: f32) {
@@ -405,20 +865,107 @@ impl AnimationPose {
                 self.add_local_pose(other_pose.weighted_clone(weight));
             }
         }
+        for (key, other_weight) in other.morph_weights.iter() {
+            *self.morph_weights.entry(*key).or_default() += other_weight * weight;
+        }
+    }
+
+    fn set_morph_weight(&mut self, node: Handle<Node>, target: usize, weight: f32) {
+        self.morph_weights.insert((node, target), weight);
+    }
+
+    /// Returns weight of a morph target of given mesh node, `None` if the pose does not animate
+    /// the target.
+    pub fn morph_weight(&self, node: Handle<Node>, target: usize) -> Option<f32> {
+        self.morph_weights.get(&(node, target)).cloned()
+    }
+
+    fn apply_morph_weights(&self, graph: &mut Graph) {
+        for (&(node, target), &weight) in self.morph_weights.iter() {
+            if let Some(Node::Mesh(mesh)) = graph.try_get_mut(node) {
+                mesh.set_morph_weight(target, weight);
+            }
+        }
     }
 
     fn add_local_pose(&mut self, local_pose: LocalPose) {
         self.local_poses.insert(local_pose.node, local_pose);
     }
 
+    /// Turns the pose into a difference between it and given reference pose, nodes that are not
+    /// animated by the reference pose are left untouched (so they're relative to identity pose).
+    pub fn make_relative(&mut self, reference: &AnimationPose) {
+        for (handle, local_pose) in self.local_poses.iter_mut() {
+            if let Some(reference) = reference.local_poses.get(handle) {
+                local_pose.make_relative(reference);
+            }
+        }
+        for (key, weight) in self.morph_weights.iter_mut() {
+            if let Some(reference) = reference.morph_weights.get(key) {
+                *weight -= reference;
+            }
+        }
+    }
+
+    /// Applies difference pose of an additive animation on top of the pose with given weight,
+    /// see [`LocalPose::add_additive`]. Nodes that are not animated by the pose are not affected.
+    pub fn add_additive(&mut self, delta: &AnimationPose, weight: f32) {
+        for (handle, delta) in delta.local_poses.iter() {
+            if let Some(local_pose) = self.local_poses.get_mut(handle) {
+                local_pose.add_additive(delta, weight);
+            }
+        }
+        for (key, delta) in delta.morph_weights.iter() {
+            if let Some(morph_weight) = self.morph_weights.get_mut(key) {
+                *morph_weight += delta * weight;
+            }
+        }
+    }
+
     pub fn reset(&mut self) {
         self.local_poses.clear();
+        self.morph_weights.clear();
+    }
+
+    /// Returns local pose of given node, `None` if the pose does not animate the node.
+    pub fn local_pose(&self, node: Handle<Node>) -> Option<&LocalPose> {
+        self.local_poses.get(&node)
+    }
+
+    /// Calculates local transform matrix of given node as if the pose was applied to the graph.
+    /// Nodes that are not animated by the pose use their current local transform. The graph is
+    /// not modified.
+    pub fn local_transform(&self, node: Handle<Node>, graph: &Graph) -> Matrix4<f32> {
+        let transform = graph[node].local_transform();
+        match self.local_poses.get(&node) {
+            Some(local_pose) => {
+                let mut transform = transform.clone();
+                transform
+                    .set_position(local_pose.position)
+                    .set_rotation(local_pose.rotation)
+                    .set_scale(local_pose.scale);
+                transform.matrix()
+            }
+            None => transform.matrix(),
+        }
+    }
+
+    /// Calculates global transform matrix of given node as if the pose was applied to the graph
+    /// and hierarchical data was updated. The graph is not modified.
+    pub fn global_transform(&self, node: Handle<Node>, graph: &Graph) -> Matrix4<f32> {
+        let mut transform = self.local_transform(node, graph);
+        let mut parent = graph[node].parent();
+        while parent.is_some() {
+            transform = self.local_transform(parent, graph) * transform;
+            parent = graph[parent].parent();
+        }
+        transform
     }
 
     pub fn apply(&self, graph: &mut Graph) {
         for (node, local_pose) in self.local_poses.iter() {
             if node.is_none() {
-                Log::writeln(MessageKind::Error, "Invalid node handle found for animation pose, most likely it means that animation retargeting failed!".to_owned());
+                log_error!("Invalid node handle found for animation pose, most likely it means that animation retargeting failed!");
             } else {
                 graph[*node]
                     .local_transform_mut()
@@ -427,21 +974,24 @@ impl AnimationPose {
                     .set_scale(local_pose.scale);
             }
         }
+        self.apply_morph_weights(graph);
     }
 
     /// Calls given callback function for each node and allows you to apply pose with your own
     /// rules. This could be useful if you need to ignore transform some part of pose for a node.
+    /// Weights of morph targets are applied as usual.
     pub fn apply_with<C>(&self, graph: &mut Graph, mut callback: C)
     where
         C: FnMut(&mut Node, Handle<Node>, &LocalPose),
     {
         for (node, local_pose) in self.local_poses.iter() {
             if node.is_none() {
-                Log::writeln(MessageKind::Error, "Invalid node handle found for animation pose, most likely it means that animation retargeting failed!".to_owned());
+                log_error!("Invalid node handle found for animation pose, most likely it means that animation retargeting failed!");
             } else {
                 callback(&mut graph[*node], *node, local_pose);
             }
         }
+        self.apply_morph_weights(graph);
     }
 }
 
@@ -449,6 +999,7 @@ impl Clone for Animation {
     fn clone(&self) -> Self {
         Self {
             tracks: self.tracks.clone(),
+            morph_tracks: self.morph_tracks.clone(),
             speed: self.speed,
             length: self.length,
             time_position: self.time_position,
@@ -457,7 +1008,19 @@ impl Clone for Animation {
             resource: self.resource.clone(),
             pose: Default::default(),
             signals: self.signals.clone(),
+            condition_signals: self.condition_signals.clone(),
             events: Default::default(),
+            update_mode: self.update_mode,
+            pose_time: 0.0,
+            pose_dirty: true,
+            lod_distance: self.lod_distance,
+            pose_outdated: false,
+            mirror: self.mirror.clone(),
+            mirrored: self.mirrored,
+            additive: self.additive,
+            additive_reference: self.additive_reference.clone(),
+            first_frame_pose: None,
+            retarget: self.retarget.clone(),
         }
     }
 }
@@ -465,6 +1028,7 @@ impl Clone for Animation {
 impl Animation {
     pub fn add_track(&mut self, track: Track) {
         self.tracks.push(track);
+        self.pose_dirty = true;
 
         for track in self.tracks.iter_mut() {
             if track.max_time > self.length {
@@ -477,13 +1041,39 @@ impl Animation {
         &self.tracks
     }
 
+    /// Adds new track for a weight of a morph target, see module docs for more info.
+    pub fn add_morph_track(&mut self, track: MorphTrack) {
+        self.length = self.length.max(track.max_time);
+        self.morph_tracks.push(track);
+        self.pose_dirty = true;
+    }
+
+    pub fn morph_tracks(&self) -> &[MorphTrack] {
+        &self.morph_tracks
+    }
+
+    pub fn morph_tracks_mut(&mut self) -> &mut [MorphTrack] {
+        self.pose_dirty = true;
+        &mut self.morph_tracks
+    }
+
     pub fn set_time_position(&mut self, time: f32) -> &mut Self {
+        self.time_position = self.remap_time(time);
+        // Explicit jump is not a playback, conditions must not emit events because of it.
+        for signal in self.condition_signals.iter_mut() {
+            signal.state = None;
+        }
+        self
+    }
+
+    // Maps arbitrary time to time position in the animation, looped animations are wrapped
+    // around and others are clamped.
+    fn remap_time(&self, time: f32) -> f32 {
         if self.looped {
-            self.time_position = wrapf(time, 0.0, self.length);
+            wrapf(time, 0.0, self.length)
         } else {
-            self.time_position = clampf(time, 0.0, self.length);
+            clampf(time, 0.0, self.length)
         }
-        self
     }
 
     pub fn rewind(&mut self) -> &mut Self {
@@ -494,9 +1084,8 @@ impl Animation {
         self.length
     }
 
-    fn tick(&mut self, dt: f32) {
-        self.update_pose();
-
+    // Advances time position and emits events of passed signals. Pose must be sampled before.
+    fn advance(&mut self, dt: f32) {
         let current_time_position = self.get_time_position();
         let new_time_position = current_time_position + dt * self.get_speed();
 
@@ -515,7 +1104,18 @@ impl Animation {
             }
         }
 
-        self.set_time_position(new_time_position);
+        self.time_position = self.remap_time(new_time_position);
+
+        for signal in self.condition_signals.iter_mut() {
+            if signal.enabled
+                && signal.update(&self.tracks, self.time_position)
+                && self.events.len() < 32
+            {
+                self.events.push_back(AnimationEvent {
+                    signal_id: signal.id,
+                });
+            }
+        }
     }
 
     pub fn pop_event(&mut self) -> Option<AnimationEvent> {
@@ -558,6 +1158,7 @@ impl Animation {
     }
 
     pub fn get_tracks_mut(&mut self) -> &mut [Track] {
+        self.pose_dirty = true;
         &mut self.tracks
     }
 
@@ -573,6 +1174,7 @@ impl Animation {
     where
         F: FnMut(&Track) -> bool,
     {
+        self.pose_dirty = true;
         self.tracks.retain(filter)
     }
 
@@ -581,6 +1183,51 @@ impl Animation {
         self
     }
 
+    /// Adds new condition signal, see [`ConditionSignal`] docs for more info. Ids of condition
+    /// signals share the same space with ids of timestamp signals.
+    pub fn add_condition_signal(&mut self, signal: ConditionSignal) -> &mut Self {
+        self.condition_signals.push(signal);
+        self
+    }
+
+    pub fn condition_signals(&self) -> &[ConditionSignal] {
+        &self.condition_signals
+    }
+
+    pub fn condition_signals_mut(&mut self) -> &mut [ConditionSignal] {
+        &mut self.condition_signals
+    }
+
+    /// Removes every condition signal with given id.
+    pub fn remove_condition_signal(&mut self, id: u64) {
+        self.condition_signals.retain(|signal| signal.id != id);
+    }
+
+    // Remaps nodes of condition signals when tracks are remapped to other nodes, signals of
+    // nodes that are not mapped will do nothing.
+    pub(in crate) fn remap_condition_signals<F>(&mut self, mut map: F)
+    where
+        F: FnMut(Handle<Node>) -> Option<Handle<Node>>,
+    {
+        for signal in self.condition_signals.iter_mut() {
+            signal.node = map(signal.node).unwrap_or_default();
+            signal.state = None;
+        }
+    }
+
+    // Remaps nodes of morph tracks when tracks are remapped to other nodes, tracks of nodes that
+    // are not mapped are removed.
+    pub(in crate) fn remap_morph_tracks<F>(&mut self, mut map: F)
+    where
+        F: FnMut(Handle<Node>) -> Option<Handle<Node>>,
+    {
+        self.pose_dirty = true;
+        for track in self.morph_tracks.iter_mut() {
+            track.node = map(track.node).unwrap_or_default();
+        }
+        self.morph_tracks.retain(|track| track.node.is_some());
+    }
+
     /// Enables or disables animation tracks for nodes in hierarchy starting from given root.
     /// Could be useful to enable or disable animation for skeleton parts, i.e. you don't want
     /// legs to be animated and you know that legs starts from torso bone, then you could do
@@ -600,6 +1247,7 @@ impl Animation {
     /// After this legs won't be animated and animation could be blended together with run
     /// animation so it will produce new animation - run and aim.
     pub fn set_tracks_enabled_from(&mut self, handle: Handle<Node>, enabled: bool, graph: &Graph) {
+        self.pose_dirty = true;
         let mut stack = vec![handle];
         while let Some(node) = stack.pop() {
             for track in self.tracks.iter_mut() {
@@ -615,6 +1263,7 @@ impl Animation {
     }
 
     pub fn set_node_track_enabled(&mut self, handle: Handle<Node>, enabled: bool) {
+        self.pose_dirty = true;
         for track in self.tracks.iter_mut() {
             if track.node == handle {
                 track.enabled = enabled;
@@ -632,6 +1281,7 @@ impl Animation {
     }
 
     pub fn track_of_mut(&mut self, handle: Handle<Node>) -> Option<&mut Track> {
+        self.pose_dirty = true;
         for track in self.tracks.iter_mut() {
             if track.node == handle {
                 return Some(track);
@@ -649,6 +1299,7 @@ impl Animation {
             if let ResourceState::Ok(ref data) = *resource {
                 // TODO: Here we assume that resource contains only *one* animation.
                 if let Some(ref_animation) = data.get_scene().animations.pool.at(0) {
+                    let retarget = self.retarget.take();
                     for track in self.get_tracks_mut() {
                         // This may panic if animation has track that refers to a deleted node,
                         // it can happen if you deleted a node but forgot to remove animation
@@ -665,26 +1316,47 @@ impl Animation {
                         // you did animation retargeting from animation resource to your character
                         // instantiated model, which is essentially copies key frames to new
                         // animation targeted to character instance.
+                        //
+                        // Retargeted tracks may have different names and modified key frames.
+                        let source_name = retarget
+                            .as_ref()
+                            .map_or(track_node.name(), |r| r.source_name(track_node.name()));
                         let mut found = false;
                         for ref_track in ref_animation.get_tracks().iter() {
-                            if track_node.name()
-                                == data.get_scene().graph[ref_track.get_node()].name()
-                            {
-                                track.set_key_frames(ref_track.get_key_frames());
+                            if source_name == data.get_scene().graph[ref_track.get_node()].name() {
+                                let mut key_frames = ref_track.get_key_frames().to_vec();
+                                if let Some(retarget) = retarget.as_ref() {
+                                    retarget.adjust_key_frames(track_node.name(), &mut key_frames);
+                                }
+                                track.set_key_frames(&key_frames);
                                 found = true;
                                 break;
                             }
                         }
                         if !found {
-                            Log::write(
-                                MessageKind::Error,
-                                format!(
-                                    "Failed to copy key frames for node {}!",
-                                    track_node.name()
-                                ),
-                            );
+                            log_error!("Failed to copy key frames for node {}!", track_node.name());
                         }
                     }
+
+                    // Morph tracks are matched the same way, plus index of the morph target.
+                    for track in self.morph_tracks.iter_mut() {
+                        let name = match graph.try_get(track.node) {
+                            Some(node) => node.name(),
+                            None => continue,
+                        };
+                        let name = retarget.as_ref().map_or(name, |r| r.source_name(name));
+                        match ref_animation.morph_tracks().iter().find(|ref_track| {
+                            ref_track.target == track.target
+                                && data.get_scene().graph[ref_track.node].name() == name
+                        }) {
+                            Some(ref_track) => track.set_key_frames(ref_track.key_frames()),
+                            None => {
+                                log_error!("Failed to copy morph key frames for node {}!", name)
+                            }
+                        }
+                    }
+
+                    self.retarget = retarget;
                 }
             } else {
                 unreachable!()
@@ -692,15 +1364,148 @@ impl Animation {
         }
     }
 
+    /// Sets update mode of the animation, see [`AnimationUpdateMode`] docs for more info.
+    pub fn set_update_mode(&mut self, update_mode: AnimationUpdateMode) -> &mut Self {
+        self.update_mode = update_mode;
+        self
+    }
+
+    pub fn update_mode(&self) -> AnimationUpdateMode {
+        self.update_mode
+    }
+
+    /// Sets max distance from a camera at which the animation is still sampled, it is used only
+    /// with [`AnimationUpdateMode::OnlyWhenVisible`]. Default is [`f32::MAX`] - only visibility
+    /// matters.
+    pub fn set_lod_distance(&mut self, distance: f32) -> &mut Self {
+        self.lod_distance = distance.max(0.0);
+        self
+    }
+
+    pub fn lod_distance(&self) -> f32 {
+        self.lod_distance
+    }
+
+    /// Returns true if pose of the animation was not sampled on last update because of its
+    /// update mode. In this case the pose is left from some earlier time position.
+    pub fn is_pose_outdated(&self) -> bool {
+        self.pose_outdated
+    }
+
+    // Returns true if current pose does not match current time position.
+    fn needs_sampling(&self) -> bool {
+        self.pose_dirty || self.pose_outdated || self.pose_time != self.time_position
+    }
+
     fn update_pose(&mut self) {
-        self.pose.reset();
-        for track in self.tracks.iter() {
-            if track.is_enabled() {
-                if let Some(local_pose) = track.get_local_pose(self.time_position) {
-                    self.pose.add_local_pose(local_pose);
-                }
+        if self.pose_dirty {
+            self.first_frame_pose = None;
+        }
+        self.pose_time = self.time_position;
+        self.pose_dirty = false;
+        self.pose_outdated = false;
+        sample_tracks(
+            &self.tracks,
+            &self.morph_tracks,
+            self.time_position,
+            &mut self.pose,
+        );
+        // Fields are borrowed separately here, so `active_mirror` can't be used.
+        if let Some(mirror) = self.mirror.as_ref().filter(|_| self.mirrored) {
+            self.pose = mirror.mirror_pose(&self.pose);
+        }
+        if self.additive {
+            if self.additive_reference.is_none() && self.first_frame_pose.is_none() {
+                self.first_frame_pose = Some(self.sample_absolute_pose(0.0));
+            }
+            if let Some(reference) = self
+                .additive_reference
+                .as_ref()
+                .or_else(|| self.first_frame_pose.as_ref())
+            {
+                self.pose.make_relative(reference);
+            }
+        }
+    }
+
+    // Samples pose at given time position as if the animation is not additive.
+    fn sample_absolute_pose(&self, time: f32) -> AnimationPose {
+        let mut pose = AnimationPose::default();
+        sample_tracks(&self.tracks, &self.morph_tracks, time, &mut pose);
+        match self.active_mirror() {
+            Some(mirror) => mirror.mirror_pose(&pose),
+            None => pose,
+        }
+    }
+
+    fn active_mirror(&self) -> Option<&AnimationMirror> {
+        self.mirror.as_deref().filter(|_| self.mirrored)
+    }
+
+    /// Sets left/right bone mapping that is used when the animation is mirrored, see
+    /// [`mirror`] module docs. The mirror is not saved, it must be set again after loading.
+    pub fn set_mirror(&mut self, mirror: Option<Arc<AnimationMirror>>) -> &mut Self {
+        self.mirror = mirror;
+        self.pose_dirty = true;
+        self
+    }
+
+    pub fn mirror(&self) -> Option<&Arc<AnimationMirror>> {
+        self.mirror.as_ref()
+    }
+
+    /// Mirrors the animation (left side of a model plays right side animation and vice versa).
+    /// Has no effect until a mirror is set by [`Self::set_mirror`].
+    pub fn set_mirrored(&mut self, mirrored: bool) -> &mut Self {
+        self.mirrored = mirrored;
+        self.pose_dirty = true;
+        self
+    }
+
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Samples pose of the animation at given time without changing playback state of the
+    /// animation and without touching the scene. Time is remapped the same way as in
+    /// [`Self::set_time_position`] - looped animations are wrapped around, others are clamped.
+    /// Could be useful to find where some bone will be at some moment of the animation, for
+    /// example use [`AnimationPose::global_transform`] on the returned pose.
+    pub fn sample_pose(&self, time: f32) -> AnimationPose {
+        let mut pose = self.sample_absolute_pose(self.remap_time(time));
+        if self.additive {
+            match self.additive_reference.as_ref() {
+                Some(reference) => pose.make_relative(reference),
+                None => pose.make_relative(&self.sample_absolute_pose(0.0)),
             }
         }
+        pose
+    }
+
+    /// Makes the animation additive, its pose will be a difference between sampled pose and
+    /// reference pose (first frame of the animation, unless another pose is set by
+    /// [`Self::set_additive_reference`]). See module docs for more info.
+    pub fn set_additive(&mut self, additive: bool) -> &mut Self {
+        self.additive = additive;
+        self.pose_dirty = true;
+        self
+    }
+
+    pub fn is_additive(&self) -> bool {
+        self.additive
+    }
+
+    /// Sets explicit reference pose of additive animation, for example a pose of some other
+    /// animation at its first frame - `idle.sample_pose(0.0)`. `None` means that first frame of
+    /// the animation is used. Reference pose is not saved, it must be set again after loading.
+    pub fn set_additive_reference(&mut self, reference: Option<AnimationPose>) -> &mut Self {
+        self.additive_reference = reference;
+        self.pose_dirty = true;
+        self
+    }
+
+    pub fn additive_reference(&self) -> Option<&AnimationPose> {
+        self.additive_reference.as_ref()
     }
 
     pub fn get_pose(&self) -> &AnimationPose {
@@ -708,10 +1513,34 @@ impl Animation {
     }
 }
 
+fn sample_tracks(
+    tracks: &[Track],
+    morph_tracks: &[MorphTrack],
+    time: f32,
+    pose: &mut AnimationPose,
+) {
+    pose.reset();
+    for track in tracks.iter() {
+        if track.is_enabled() {
+            if let Some(local_pose) = track.get_local_pose(time) {
+                pose.add_local_pose(local_pose);
+            }
+        }
+    }
+    for track in morph_tracks.iter() {
+        if track.is_enabled() {
+            if let Some(weight) = track.weight_at(time) {
+                pose.set_morph_weight(track.node, track.target, weight);
+            }
+        }
+    }
+}
+
 impl Default for Animation {
     fn default() -> Self {
         Self {
             tracks: Vec::new(),
+            morph_tracks: Vec::new(),
             speed: 1.0,
             length: 0.0,
             time_position: 0.0,
@@ -720,7 +1549,19 @@ impl Default for Animation {
             resource: Default::default(),
             pose: Default::default(),
             signals: Default::default(),
+            condition_signals: Default::default(),
             events: Default::default(),
+            update_mode: Default::default(),
+            pose_time: 0.0,
+            pose_dirty: true,
+            lod_distance: f32::MAX,
+            pose_outdated: false,
+            mirror: None,
+            mirrored: false,
+            additive: false,
+            additive_reference: None,
+            first_frame_pose: None,
+            retarget: None,
         }
     }
 }
@@ -737,6 +1578,13 @@ impl Visit for Animation {
         self.looped.visit("Looped", visitor)?;
         self.enabled.visit("Enabled", visitor)?;
         self.signals.visit("Signals", visitor)?;
+        let _ = self.condition_signals.visit("ConditionSignals", visitor);
+        let _ = self.update_mode.visit("UpdateMode", visitor);
+        let _ = self.lod_distance.visit("LodDistance", visitor);
+        let _ = self.mirrored.visit("Mirrored", visitor);
+        let _ = self.additive.visit("Additive", visitor);
+        let _ = self.morph_tracks.visit("MorphTracks", visitor);
+        let _ = self.retarget.visit("Retarget", visitor);
 
         visitor.leave_region()
     }
@@ -823,6 +1671,19 @@ impl AnimationContainer {
         self.pool.borrow_mut(handle)
     }
 
+    /// Tries to borrow an animation, returns `None` if the handle is invalid (for example if the
+    /// animation was removed).
+    #[inline]
+    pub fn try_get(&self, handle: Handle<Animation>) -> Option<&Animation> {
+        self.pool.try_borrow(handle)
+    }
+
+    /// Tries to borrow an animation mutably, returns `None` if the handle is invalid.
+    #[inline]
+    pub fn try_get_mut(&mut self, handle: Handle<Animation>) -> Option<&mut Animation> {
+        self.pool.try_borrow_mut(handle)
+    }
+
     #[inline]
     pub fn retain<P>(&mut self, pred: P)
     where
@@ -832,23 +1693,74 @@ impl AnimationContainer {
     }
 
     pub fn resolve(&mut self, graph: &Graph) {
-        Log::writeln(
-            MessageKind::Information,
-            "Resolving animations...".to_owned(),
-        );
+        log_info!("Resolving animations...");
         for animation in self.pool.iter_mut() {
             animation.resolve(graph)
         }
-        Log::writeln(
-            MessageKind::Information,
-            "Animations resolved successfully!".to_owned(),
-        );
+        log_info!("Animations resolved successfully!");
     }
 
-    pub fn update_animations(&mut self, dt: f32) {
+    /// Samples poses of every enabled animation and advances their time positions. Poses are
+    /// sampled in parallel, animations with [`AnimationUpdateMode::OnlyWhenVisible`] are treated
+    /// as visible, animations with [`AnimationUpdateMode::TimeOnly`] are never sampled. Poses are not applied to a graph, it should be done by a user (or an animation
+    /// blending machine) on the main thread.
+    pub fn update_animations(&mut self, dt: f32) -> AnimationStatistics {
+        self.update_animations_with_visibility(dt, |_| true)
+    }
+
+    /// Same as [`Self::update_animations`], but uses given predicate to check whether an
+    /// animation with [`AnimationUpdateMode::OnlyWhenVisible`] is visible. The predicate should
+    /// take [`Animation::lod_distance`] into account.
+    pub fn update_animations_with_visibility<F>(
+        &mut self,
+        dt: f32,
+        is_visible: F,
+    ) -> AnimationStatistics
+    where
+        F: Fn(&Animation) -> bool,
+    {
+        profile_scope!("Animation");
+
+        let mut statistics = AnimationStatistics::default();
+
+        // Pose of an animation depends only on its own tracks and time position, so sampling
+        // can be done in parallel. Time position is advanced afterwards, this gives exactly the
+        // same results as serial sampling.
+        let mut to_sample = Vec::new();
         for animation in self.pool.iter_mut().filter(|anim| anim.enabled) {
-            animation.tick(dt);
+            let should_sample = match animation.update_mode {
+                AnimationUpdateMode::Always => true,
+                AnimationUpdateMode::OnlyWhenVisible => is_visible(animation),
+                AnimationUpdateMode::TimeOnly => false,
+            };
+
+            if !should_sample {
+                statistics.time_only += 1;
+                animation.pose_outdated = true;
+            } else if animation.needs_sampling() {
+                statistics.sampled += 1;
+                to_sample.push(animation);
+            } else {
+                statistics.skipped += 1;
+            }
         }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        to_sample
+            .par_iter_mut()
+            .for_each(|animation| animation.update_pose());
+
+        // There are no threads on WebAssembly.
+        #[cfg(target_arch = "wasm32")]
+        to_sample
+            .iter_mut()
+            .for_each(|animation| animation.update_pose());
+
+        for animation in self.pool.iter_mut().filter(|anim| anim.enabled) {
+            animation.advance(dt);
+        }
+
+        statistics
     }
 
     /// Removes queued animation events from every animation in the container.
@@ -894,3 +1806,360 @@ impl IndexMut<Handle<Animation>> for AnimationContainer {
         &mut self.pool[index]
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            Animation, AnimationContainer, AnimationPose, AnimationSignal, AnimationStatistics,
+            AnimationUpdateMode, Comparison, ConditionSignal, KeyFrame, LocalPose, MorphKeyFrame,
+            MorphTrack, PoseComponent, SignalEdge, Track,
+        },
+        core::{
+            algebra::{UnitQuaternion, Vector3},
+            math::Matrix4Ext,
+            pool::Handle,
+        },
+        scene::{base::BaseBuilder, graph::Graph, node::Node, transform::TransformBuilder},
+    };
+
+    fn make_animation(index: usize) -> Animation {
+        let mut animation = Animation::default();
+        for i in 0..4 {
+            let mut track = Track::new();
+            track.set_node(Handle::new(i + 1, 1));
+            for k in 0..5 {
+                let t = k as f32 * 0.25 + index as f32 * 0.1;
+                track.add_key_frame(KeyFrame::new(
+                    k as f32 * 0.3,
+                    Vector3::new(t.sin(), t.cos(), t * i as f32),
+                    Vector3::new(1.0, 1.0 + t, 1.0),
+                    UnitQuaternion::from_euler_angles(t, t * 0.5, i as f32),
+                ));
+            }
+            animation.add_track(track);
+        }
+        animation.set_speed(1.0 + index as f32 * 0.13);
+        animation
+    }
+
+    fn assert_poses_equal(a: &AnimationPose, b: &AnimationPose) {
+        assert_eq!(a.local_poses.len(), b.local_poses.len());
+        for (handle, pose) in a.local_poses.iter() {
+            let other = b.local_poses.get(handle).unwrap();
+            assert_eq!(pose.position, other.position);
+            assert_eq!(pose.scale, other.scale);
+            assert_eq!(pose.rotation, other.rotation);
+        }
+    }
+
+    #[test]
+    fn test_parallel_update_matches_serial() {
+        let mut container = AnimationContainer::new();
+        let mut reference = Vec::new();
+        let mut handles = Vec::new();
+        for i in 0..16 {
+            let mut animation = make_animation(i);
+            if i % 4 == 0 {
+                // Paused animation must be skipped after first sampling.
+                animation.set_speed(0.0);
+            }
+            reference.push(animation.clone());
+            handles.push(container.add(animation));
+        }
+
+        for step in 0..50 {
+            let statistics = container.update_animations(1.0 / 60.0);
+
+            // Serial path - every animation is sampled and then advanced.
+            for animation in reference.iter_mut() {
+                animation.update_pose();
+                animation.advance(1.0 / 60.0);
+            }
+
+            for (handle, animation) in handles.iter().zip(reference.iter()) {
+                assert_poses_equal(container[*handle].get_pose(), animation.get_pose());
+                assert_eq!(
+                    container[*handle].get_time_position(),
+                    animation.get_time_position()
+                );
+            }
+
+            if step == 0 {
+                assert_eq!(
+                    statistics,
+                    AnimationStatistics {
+                        sampled: 16,
+                        skipped: 0,
+                        time_only: 0
+                    }
+                );
+            } else {
+                assert_eq!(
+                    statistics,
+                    AnimationStatistics {
+                        sampled: 12,
+                        skipped: 4,
+                        time_only: 0
+                    }
+                );
+            }
+        }
+
+        // Modification of tracks must invalidate cached pose.
+        container[handles[0]].get_tracks_mut()[0].enable(false);
+        let statistics = container.update_animations(1.0 / 60.0);
+        assert_eq!(statistics.sampled, 13);
+        assert_eq!(container[handles[0]].get_pose().local_poses.len(), 3);
+    }
+
+    #[test]
+    fn test_invisible_animation_skipped() {
+        let mut container = AnimationContainer::new();
+        let handle = container.add(make_animation(0));
+        container[handle].set_update_mode(AnimationUpdateMode::OnlyWhenVisible);
+
+        let statistics = container.update_animations_with_visibility(0.1, |_| false);
+        assert_eq!(statistics.time_only, 1);
+        assert!(container[handle].is_pose_outdated());
+        assert!(container[handle].get_pose().local_poses.is_empty());
+        // Time must advance even if the animation is invisible.
+        assert!(container[handle].get_time_position() > 0.0);
+
+        let statistics = container.update_animations_with_visibility(0.1, |_| true);
+        assert_eq!(statistics.sampled, 1);
+        assert_eq!(container[handle].get_pose().local_poses.len(), 4);
+        assert!(!container[handle].is_pose_outdated());
+    }
+
+    #[test]
+    fn test_time_only_animation() {
+        let mut container = AnimationContainer::new();
+        let mut animation = make_animation(0);
+        animation.add_signal(AnimationSignal::new(1, 0.15));
+        let handle = container.add(animation);
+        container[handle].set_update_mode(AnimationUpdateMode::TimeOnly);
+
+        for _ in 0..2 {
+            let statistics = container.update_animations(0.1);
+            assert_eq!(statistics.time_only, 1);
+            assert_eq!(statistics.sampled, 0);
+        }
+        assert!(container[handle].get_pose().local_poses.is_empty());
+        // Signals must be emitted even if pose is not sampled.
+        assert_eq!(container[handle].pop_event().unwrap().signal_id, 1);
+
+        // Pose must be sampled at current time position, not where the animation was paused.
+        container[handle].set_update_mode(AnimationUpdateMode::Always);
+        let time = container[handle].get_time_position();
+        let statistics = container.update_animations(0.1);
+        assert_eq!(statistics.sampled, 1);
+        assert_poses_equal(
+            container[handle].get_pose(),
+            &container[handle].sample_pose(time),
+        );
+    }
+
+    #[test]
+    fn test_sample_pose() {
+        let mut animation = make_animation(0);
+        animation.set_time_position(0.2);
+        let length = animation.length();
+
+        // Sampling must not change playback state.
+        let pose = animation.sample_pose(0.7);
+        assert_eq!(animation.get_time_position(), 0.2);
+
+        let mut reference = animation.clone();
+        reference.set_time_position(0.7);
+        reference.update_pose();
+        assert_poses_equal(&pose, reference.get_pose());
+
+        // Looped animation wraps around.
+        assert_poses_equal(&animation.sample_pose(length + 0.7), &pose);
+
+        // Non-looped animation is clamped.
+        animation.set_loop(false);
+        reference.set_loop(false);
+        reference.set_time_position(length);
+        reference.update_pose();
+        assert_poses_equal(&animation.sample_pose(length + 0.7), reference.get_pose());
+    }
+
+    #[test]
+    fn test_pose_global_transform() {
+        let mut graph = Graph::new();
+        let child = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 1.0, 0.0))
+                    .build(),
+            )
+            .build(&mut graph);
+        let parent = BaseBuilder::new().with_children(&[child]).build(&mut graph);
+
+        let mut track = Track::new();
+        track.set_node(parent);
+        for (time, x) in [(0.0, 0.0), (1.0, 2.0)] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::new(x, 0.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::identity(),
+            ));
+        }
+        let mut animation = Animation::default();
+        animation.add_track(track);
+
+        let pose = animation.sample_pose(0.5);
+        assert!(pose.local_pose(child).is_none());
+        let position = pose.global_transform(child, &graph).position();
+        assert!((position - Vector3::new(1.0, 1.0, 0.0)).norm() < 1.0e-5);
+
+        // Graph must stay untouched.
+        assert_eq!(
+            graph[parent].local_transform().position().clone_inner(),
+            Vector3::new(0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_condition_signals() {
+        let node = Handle::new(1, 1);
+        let mut track = Track::new();
+        track.set_node(node);
+        for (time, y) in [(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::new(0.0, y, 0.0),
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::identity(),
+            ));
+        }
+        let mut animation = Animation::default();
+        animation.add_track(track);
+        for (id, edge) in [(1, SignalEdge::Rising), (2, SignalEdge::Falling)] {
+            animation.add_condition_signal(ConditionSignal::new(
+                id,
+                node,
+                PoseComponent::PositionY,
+                Comparison::Greater,
+                0.5,
+                edge,
+            ));
+        }
+
+        let run = |animation: &mut Animation, steps: usize| {
+            let mut events = Vec::new();
+            for _ in 0..steps {
+                animation.advance(0.1);
+                while let Some(event) = animation.pop_event() {
+                    events.push(event.signal_id);
+                }
+            }
+            events
+        };
+
+        // Two loops - value rises and falls twice, wrap does not produce extra events.
+        assert_eq!(run(&mut animation, 20), vec![1, 2, 1, 2]);
+
+        // Backwards playback crosses the threshold in opposite order.
+        animation.set_speed(-1.0);
+        assert_eq!(run(&mut animation, 10), vec![1, 2]);
+
+        // Explicit jump is not a crossing.
+        animation.set_time_position(0.5);
+        assert!(run(&mut animation, 1).is_empty());
+    }
+
+    fn make_rotation_track(node: Handle<Node>, angle: f32) -> Track {
+        let mut track = Track::new();
+        track.set_node(node);
+        for (time, k) in [(0.0, 0.0), (1.0, 1.0)] {
+            track.add_key_frame(KeyFrame::new(
+                time,
+                Vector3::new(0.0, k, 0.0),
+                Vector3::new(1.0 + k, 1.0 + k, 1.0 + k),
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle * k),
+            ));
+        }
+        track
+    }
+
+    #[test]
+    fn test_additive_animation() {
+        let node = Handle::new(1, 1);
+        let angle = 90.0f32.to_radians();
+        let mut animation = Animation::default();
+        animation.add_track(make_rotation_track(node, angle));
+        animation.set_additive(true);
+
+        // Delta at first frame is identity.
+        let delta = animation.sample_pose(0.0);
+        let local = delta.local_pose(node).unwrap();
+        assert!(local.position.norm() < 1.0e-5);
+        assert!(local.rotation.angle() < 1.0e-5);
+        assert!((local.scale - Vector3::new(1.0, 1.0, 1.0)).norm() < 1.0e-5);
+
+        // Sampled pose must match the pose sampled by the container.
+        let mut container = AnimationContainer::new();
+        let handle = container.add(animation.clone());
+        container[handle].set_time_position(1.0).set_loop(false);
+        container.update_animations(0.0);
+        assert_poses_equal(container[handle].get_pose(), &animation.sample_pose(1.0));
+
+        let delta = animation.sample_pose(1.0);
+        let base_rotation = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), angle);
+        let mut base = AnimationPose::default();
+        base.add_local_pose(LocalPose {
+            node,
+            position: Vector3::new(1.0, 0.0, 0.0),
+            scale: Vector3::new(2.0, 2.0, 2.0),
+            rotation: base_rotation,
+        });
+
+        let mut half = base.clone();
+        half.add_additive(&delta, 0.5);
+        let local = half.local_pose(node).unwrap();
+        assert!((local.position - Vector3::new(1.0, 0.5, 0.0)).norm() < 1.0e-5);
+        assert!((local.scale - Vector3::new(3.0, 3.0, 3.0)).norm() < 1.0e-5);
+        let expected =
+            base_rotation * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), angle * 0.5);
+        assert!(local.rotation.angle_to(&expected) < 1.0e-4);
+
+        // Explicit reference pose.
+        animation.set_additive(false);
+        let reference = animation.sample_pose(1.0);
+        animation
+            .set_additive_reference(Some(reference))
+            .set_additive(true);
+        let local = animation.sample_pose(1.0);
+        assert!(local.local_pose(node).unwrap().rotation.angle() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_morph_track() {
+        let node = Handle::new(1, 1);
+        let mut track = MorphTrack::new(node, 2);
+        assert_eq!(track.weight_at(0.0), None);
+
+        // Frames are sorted on insertion.
+        track.add_key_frame(MorphKeyFrame::new(1.0, 1.0));
+        track.add_key_frame(MorphKeyFrame::new(0.0, 0.0));
+        assert_eq!(track.weight_at(-1.0), Some(0.0));
+        assert_eq!(track.weight_at(0.25), Some(0.25));
+        assert_eq!(track.weight_at(2.0), Some(1.0));
+
+        let mut animation = Animation::default();
+        animation.set_loop(false);
+        animation.add_morph_track(track);
+        let a = animation.sample_pose(0.5);
+        assert_eq!(a.morph_weight(node, 2), Some(0.5));
+        assert_eq!(a.morph_weight(node, 1), None);
+
+        let mut blended = AnimationPose::default();
+        blended.blend_with(&a, 0.5);
+        blended.blend_with(&animation.sample_pose(1.0), 0.5);
+        assert_eq!(blended.morph_weight(node, 2), Some(0.75));
+    }
+}
@@ -0,0 +1,476 @@
+//! Retargeting of animations between skeletons with different names of bones and different
+//! proportions.
+//!
+//! By default [`crate::resource::model::Model::retarget_animations`] matches animated bones of a
+//! model resource with nodes of an instance by exact names and copies key frames as is. It works
+//! perfectly only when both skeletons are the same. [`RetargetOptions`] allows to retarget a clip
+//! made for one humanoid onto a slightly different one:
+//!
+//! - [`BoneMap`] maps names of source bones to names of target bones, bones that are not listed
+//! in the map are matched by exact names.
+//! - Translation of the root bone (usually hips) can be scaled by the ratio of heights of the
+//! skeletons, so a shorter character does not float above the ground and its feet do not slide.
+//! - Translation of every other bone can be replaced with the rest position of the target bone
+//! (rotation-only retargeting), so proportions of the target skeleton are preserved.
+//!
+//! Height of a skeleton is the height of its root bone above the model root in rest pose, so
+//! retargeting must be done while the target instance is in its rest pose.
+//!
+//! # Usage
+//!
+//! ```no_run
+//! use rg3d::{
+//!     animation::{
+//!         retarget::{BoneMap, RetargetOptions},
+//!         Animation,
+//!     },
+//!     core::pool::Handle,
+//!     resource::model::Model,
+//!     scene::{node::Node, Scene},
+//! };
+//!
+//! fn retarget_walk(
+//!     walk: &Model,
+//!     character: Handle<Node>,
+//!     scene: &mut Scene,
+//! ) -> Vec<Handle<Animation>> {
+//!     let bone_map = BoneMap::new()
+//!         .with_bone("mixamorig:Hips", "Pelvis")
+//!         .with_bone("mixamorig:Spine", "Spine01");
+//!
+//!     let options = RetargetOptions::new()
+//!         .with_bone_map(bone_map)
+//!         .with_root_translation_scaling(true)
+//!         .with_rotation_only(true);
+//!
+//!     walk.retarget_animations_with(character, scene, &options)
+//! }
+//! ```
+
+use crate::{
+    animation::{Animation, KeyFrame},
+    core::{
+        algebra::{Matrix4, Vector3},
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    scene::{graph::Graph, node::Node},
+    utils::log::log_warn,
+};
+use fxhash::{FxHashMap, FxHashSet};
+
+/// Maps names of bones of a source skeleton to names of bones of a target skeleton.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoneMap {
+    map: FxHashMap<String, String>,
+}
+
+impl BoneMap {
+    /// Creates an empty bone map, every bone will be matched by its exact name.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a pair of bones to the map.
+    pub fn with_bone<S: AsRef<str>, T: AsRef<str>>(mut self, source: S, target: T) -> Self {
+        self.insert(source, target);
+        self
+    }
+
+    /// Adds a pair of bones to the map, previous target of the source bone is replaced.
+    pub fn insert<S: AsRef<str>, T: AsRef<str>>(&mut self, source: S, target: T) {
+        self.map
+            .insert(source.as_ref().to_owned(), target.as_ref().to_owned());
+    }
+
+    /// Removes source bone from the map, so it will be matched by its exact name.
+    pub fn remove(&mut self, source: &str) -> Option<String> {
+        self.map.remove(source)
+    }
+
+    /// Returns name of a target bone for given source bone. It is the name of the source bone if
+    /// the bone is not in the map.
+    pub fn target_of<'a>(&'a self, source: &'a str) -> &'a str {
+        self.map
+            .get(source)
+            .map_or(source, |target| target.as_str())
+    }
+
+    /// Returns an iterator over pairs of names of source and target bones.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.map
+            .iter()
+            .map(|(source, target)| (source.as_str(), target.as_str()))
+    }
+
+    /// Returns amount of pairs in the map.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the map has no pairs.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+/// Options of animation retargeting, see module docs. Default options match bones by exact
+/// names and copy key frames as is.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetargetOptions {
+    bone_map: Option<BoneMap>,
+    scale_root_translation: bool,
+    rotation_only: bool,
+}
+
+impl RetargetOptions {
+    /// Creates default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets a map that is consulted before matching bones by exact names.
+    pub fn with_bone_map(mut self, bone_map: BoneMap) -> Self {
+        self.bone_map = Some(bone_map);
+        self
+    }
+
+    /// Whether translation of the root bone should be scaled by the ratio of heights of target
+    /// and source skeletons.
+    pub fn with_root_translation_scaling(mut self, scale: bool) -> Self {
+        self.scale_root_translation = scale;
+        self
+    }
+
+    /// Whether translation of non-root bones should be replaced with rest positions of target
+    /// bones, so only rotations (and translation of the root bone) are retargeted.
+    pub fn with_rotation_only(mut self, rotation_only: bool) -> Self {
+        self.rotation_only = rotation_only;
+        self
+    }
+
+    /// Returns the bone map.
+    pub fn bone_map(&self) -> Option<&BoneMap> {
+        self.bone_map.as_ref()
+    }
+
+    /// Returns true if translation of the root bone is scaled.
+    pub fn is_root_translation_scaling(&self) -> bool {
+        self.scale_root_translation
+    }
+
+    /// Returns true if only rotations of non-root bones are retargeted.
+    pub fn is_rotation_only(&self) -> bool {
+        self.rotation_only
+    }
+
+    fn target_of<'a>(&'a self, source: &'a str) -> &'a str {
+        match self.bone_map {
+            Some(ref bone_map) => bone_map.target_of(source),
+            None => source,
+        }
+    }
+}
+
+// Everything that is needed to repeat retargeting of key frames when they're copied from the
+// resource again on load (key frames are not stored in save files).
+#[derive(Clone, Debug, Default, PartialEq, Visit)]
+pub(in crate) struct RetargetData {
+    // Target name -> source name, only for bones with different names.
+    source_names: FxHashMap<String, String>,
+    // Name of the root bone of the target skeleton.
+    root: String,
+    root_scale: f32,
+    // Target name -> rest position, only for rotation-only retargeting.
+    rest_positions: FxHashMap<String, Vector3<f32>>,
+}
+
+impl RetargetData {
+    pub(in crate) fn source_name<'a>(&'a self, target: &'a str) -> &'a str {
+        self.source_names
+            .get(target)
+            .map_or(target, |source| source.as_str())
+    }
+
+    pub(in crate) fn adjust_key_frames(&self, target: &str, key_frames: &mut [KeyFrame]) {
+        if target == self.root {
+            for key_frame in key_frames.iter_mut() {
+                key_frame.position = key_frame.position.scale(self.root_scale);
+            }
+        } else if let Some(rest_position) = self.rest_positions.get(target) {
+            for key_frame in key_frames.iter_mut() {
+                key_frame.position = *rest_position;
+            }
+        }
+    }
+}
+
+// Position of a node in the space of its ancestor. Only local transforms are used, because
+// global transforms of resource scenes are never calculated.
+fn position_in_space_of(graph: &Graph, node: Handle<Node>, ancestor: Handle<Node>) -> Vector3<f32> {
+    let mut transform = Matrix4::identity();
+    let mut current = node;
+    while current.is_some() && current != ancestor {
+        let node = &graph[current];
+        transform = node.local_transform().matrix() * transform;
+        current = node.parent();
+    }
+    Vector3::new(transform[(0, 3)], transform[(1, 3)], transform[(2, 3)])
+}
+
+// Root bone is the first animated bone that has no animated ancestors.
+fn root_bone(graph: &Graph, animated: &[Handle<Node>]) -> Handle<Node> {
+    let set = animated.iter().cloned().collect::<FxHashSet<_>>();
+    animated
+        .iter()
+        .cloned()
+        .find(|&bone| {
+            let mut parent = graph[bone].parent();
+            while parent.is_some() {
+                if set.contains(&parent) {
+                    return false;
+                }
+                parent = graph[parent].parent();
+            }
+            true
+        })
+        .unwrap_or_default()
+}
+
+/// Makes a copy of an animation from `source_graph` retargeted to a hierarchy starting from
+/// `dest_root` in `dest_graph`. Tracks of bones that have no match in the target hierarchy are
+/// removed with a warning.
+pub(in crate) fn retarget_animation(
+    source_graph: &Graph,
+    source: &Animation,
+    dest_graph: &Graph,
+    dest_root: Handle<Node>,
+    options: &RetargetOptions,
+) -> Animation {
+    let mut animation = source.clone();
+
+    let find_target = |node: Handle<Node>| {
+        source_graph
+            .try_get(node)
+            .map(|node| dest_graph.find_by_name(dest_root, options.target_of(node.name())))
+    };
+
+    let mut data = RetargetData {
+        root_scale: 1.0,
+        ..Default::default()
+    };
+    let mut sources = Vec::new();
+    for track in animation.get_tracks_mut() {
+        let source_node = track.get_node();
+        let target = find_target(source_node).unwrap_or_default();
+        if target.is_some() {
+            let source_name = source_graph[source_node].name();
+            let target_name = dest_graph[target].name();
+            if source_name != target_name {
+                data.source_names
+                    .insert(target_name.to_owned(), source_name.to_owned());
+            }
+            sources.push(source_node);
+        } else if let Some(node) = source_graph.try_get(source_node) {
+            log_warn!(
+                "Unable to retarget track of bone {}, there is no bone {} in the target skeleton.",
+                node.name(),
+                options.target_of(node.name())
+            );
+        }
+        track.set_node(target);
+    }
+    animation.retain_tracks(|track| track.get_node().is_some());
+
+    animation.remap_condition_signals(find_target);
+    animation.remap_morph_tracks(find_target);
+
+    if options.scale_root_translation || options.rotation_only {
+        let source_root = root_bone(source_graph, &sources);
+        if source_root.is_some() {
+            let target_root = dest_graph.find_by_name(
+                dest_root,
+                options.target_of(source_graph[source_root].name()),
+            );
+            data.root = dest_graph[target_root].name_owned();
+
+            if options.scale_root_translation {
+                let source_height =
+                    position_in_space_of(source_graph, source_root, source_graph.get_root()).y;
+                let target_height = position_in_space_of(dest_graph, target_root, dest_root).y;
+                if source_height.abs() > f32::EPSILON {
+                    data.root_scale = target_height / source_height;
+                } else {
+                    log_warn!(
+                        "Unable to scale translation of root bone {}, it is on the ground in rest pose.",
+                        data.root
+                    );
+                }
+            }
+        }
+
+        if options.rotation_only {
+            for track in animation.get_tracks() {
+                let node = &dest_graph[track.get_node()];
+                if node.name() != data.root {
+                    data.rest_positions
+                        .insert(node.name_owned(), **node.local_transform().position());
+                }
+            }
+        }
+
+        for track in animation.get_tracks_mut() {
+            let mut key_frames = track.get_key_frames().to_vec();
+            data.adjust_key_frames(dest_graph[track.get_node()].name(), &mut key_frames);
+            track.set_key_frames(&key_frames);
+        }
+    }
+
+    if !data.source_names.is_empty() || data.root_scale != 1.0 || !data.rest_positions.is_empty() {
+        animation.retarget = Some(data);
+    }
+
+    animation
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{
+            retarget::{retarget_animation, BoneMap, RetargetOptions},
+            Animation, KeyFrame, Track,
+        },
+        core::{
+            algebra::{UnitQuaternion, Vector3},
+            pool::Handle,
+        },
+        scene::{base::BaseBuilder, graph::Graph, node::Node, transform::TransformBuilder},
+    };
+
+    fn make_bone(
+        graph: &mut Graph,
+        name: &str,
+        height: f32,
+        children: &[Handle<Node>],
+    ) -> Handle<Node> {
+        BaseBuilder::new()
+            .with_name(name)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, height, 0.0))
+                    .build(),
+            )
+            .with_children(children)
+            .build(graph)
+    }
+
+    fn make_track(node: Handle<Node>, positions: &[Vector3<f32>], angle: f32) -> Track {
+        let mut track = Track::new();
+        track.set_node(node);
+        for (i, &position) in positions.iter().enumerate() {
+            track.add_key_frame(KeyFrame::new(
+                i as f32,
+                position,
+                Vector3::new(1.0, 1.0, 1.0),
+                UnitQuaternion::from_euler_angles(angle * i as f32, 0.0, 0.0),
+            ));
+        }
+        track
+    }
+
+    #[test]
+    fn test_retarget_different_proportions() {
+        // Tall source rig with a walk clip.
+        let mut source_graph = Graph::new();
+        let tail = make_bone(&mut source_graph, "Tail", 0.1, &[]);
+        let spine = make_bone(&mut source_graph, "Spine", 0.4, &[]);
+        let hips = make_bone(&mut source_graph, "Hips", 1.0, &[spine, tail]);
+
+        let mut clip = Animation::default();
+        clip.add_track(make_track(
+            hips,
+            &[Vector3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.9, 2.0)],
+            0.0,
+        ));
+        clip.add_track(make_track(
+            spine,
+            &[Vector3::new(0.0, 0.4, 0.0), Vector3::new(0.0, 0.4, 0.0)],
+            0.5,
+        ));
+        clip.add_track(make_track(
+            tail,
+            &[Vector3::new(0.0, 0.1, 0.0), Vector3::new(0.0, 0.1, 0.0)],
+            0.2,
+        ));
+
+        // Short target rig with different names of bones and without a tail.
+        let mut dest_graph = Graph::new();
+        let spine01 = make_bone(&mut dest_graph, "Spine01", 0.25, &[]);
+        let pelvis = make_bone(&mut dest_graph, "Pelvis", 0.5, &[spine01]);
+        let character = BaseBuilder::new()
+            .with_children(&[pelvis])
+            .build(&mut dest_graph);
+
+        // Nothing matches by exact names.
+        let plain = retarget_animation(
+            &source_graph,
+            &clip,
+            &dest_graph,
+            character,
+            &RetargetOptions::default(),
+        );
+        assert!(plain.get_tracks().is_empty());
+        assert!(plain.retarget.is_none());
+
+        let options = RetargetOptions::new()
+            .with_bone_map(
+                BoneMap::new()
+                    .with_bone("Hips", "Pelvis")
+                    .with_bone("Spine", "Spine01"),
+            )
+            .with_root_translation_scaling(true)
+            .with_rotation_only(true);
+        let retargeted = retarget_animation(&source_graph, &clip, &dest_graph, character, &options);
+
+        // Tail is unmapped, its track is removed.
+        let tracks = retargeted.get_tracks();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].get_node(), pelvis);
+        assert_eq!(tracks[1].get_node(), spine01);
+
+        // Hips are twice lower, so the translation is halved.
+        let pelvis_frames = tracks[0].get_key_frames();
+        assert!(
+            pelvis_frames[0]
+                .position
+                .metric_distance(&Vector3::new(0.0, 0.5, 0.0))
+                < 1.0e-5
+        );
+        assert!(
+            pelvis_frames[1]
+                .position
+                .metric_distance(&Vector3::new(0.0, 0.45, 1.0))
+                < 1.0e-5
+        );
+
+        // Spine keeps its own length, but rotation is taken from the clip.
+        let spine_frames = tracks[1].get_key_frames();
+        for (frame, source_frame) in spine_frames
+            .iter()
+            .zip(clip.get_tracks()[1].get_key_frames())
+        {
+            assert_eq!(frame.position, Vector3::new(0.0, 0.25, 0.0));
+            assert_eq!(frame.rotation, source_frame.rotation);
+        }
+
+        // The same adjustments are applied when key frames are restored from the resource.
+        let data = retargeted.retarget.as_ref().unwrap();
+        assert_eq!(data.source_name("Pelvis"), "Hips");
+        assert_eq!(data.source_name("Spine01"), "Spine");
+        let mut restored = clip.get_tracks()[0].get_key_frames().to_vec();
+        data.adjust_key_frames("Pelvis", &mut restored);
+        for (restored, retargeted) in restored.iter().zip(pelvis_frames) {
+            assert_eq!(restored.position, retargeted.position);
+        }
+    }
+}
@@ -3,15 +3,25 @@
 //!
 //! Once you get familiar with the engine, you should **not** use the framework because it is too
 //! limiting and may slow you down.
+//!
+//! The framework has in-game console that shows messages of the log, it could be opened by
+//! `~` key.
 
-use crate::gui::message::UiMessage;
-use crate::utils::log::{Log, MessageKind};
 use crate::{
-    core::instant::Instant,
+    core::{algebra::Vector2, instant::Instant, pool::Handle},
     engine::{error::EngineError, Engine},
-    event::{DeviceEvent, DeviceId, Event, WindowEvent},
+    event::{DeviceEvent, DeviceId, ElementState, Event, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
-    utils::translate_event,
+    gui::{
+        console::ConsoleBuilder,
+        message::{MessageDirection, UiMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        UiNode,
+    },
+    utils::{
+        log::{log_error, Log, RingBufferSink},
+        translate_event,
+    },
     window::WindowBuilder,
 };
 
@@ -53,6 +63,7 @@ pub struct Framework<State: GameState> {
     title: String,
     event_loop: EventLoop<()>,
     state: State,
+    console: Handle<UiNode>,
 }
 
 impl<State: GameState> Framework<State> {
@@ -65,11 +76,32 @@ impl<State: GameState> Framework<State> {
 
         let mut engine = Engine::new(window_builder, &event_loop, false)?;
 
+        // Sink must be added before initialization of the game to catch its messages too.
+        let sink = RingBufferSink::new(512);
+        let buffer = sink.buffer();
+        Log::add_sink(sink);
+        // Game could install its own logger, it is fine.
+        let _ = Log::install_log_facade();
+
+        let state = State::init(&mut engine);
+
+        // Console is created after the game's UI, so it will be on top of it.
+        let console = ConsoleBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_width(700.0)
+                .with_height(300.0)
+                .with_desired_position(Vector2::new(5.0, 5.0)),
+            buffer,
+        )
+        .build(&mut engine.user_interface.build_ctx());
+
         Ok(Self {
             title: "Game".to_owned(),
-            state: State::init(&mut engine),
+            state,
             engine,
             event_loop,
+            console,
         })
     }
 
@@ -85,12 +117,15 @@ impl<State: GameState> Framework<State> {
         let mut engine = self.engine;
         engine.get_window().set_title(&self.title);
         let mut state = self.state;
+        let console = self.console;
         let clock = Instant::now();
         let fixed_timestep = 1.0 / 60.0;
         let mut elapsed_time = 0.0;
 
-        self.event_loop
-            .run(move |event, _, control_flow| match event {
+        self.event_loop.run(move |event, _, control_flow| {
+            engine.handle_os_event(&event);
+
+            match event {
                 Event::MainEventsCleared => {
                     let mut dt = clock.elapsed().as_secs_f32() - elapsed_time;
                     while dt >= fixed_timestep {
@@ -103,6 +138,7 @@ impl<State: GameState> Framework<State> {
                     }
 
                     while let Some(ui_msg) = engine.user_interface.poll_message() {
+                        engine.handle_ui_message(&ui_msg);
                         state.on_ui_message(&mut engine, ui_msg);
                     }
 
@@ -116,10 +152,31 @@ impl<State: GameState> Framework<State> {
                         WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
                         WindowEvent::Resized(size) => {
                             if let Err(e) = engine.set_frame_size(size.into()) {
-                                Log::writeln(
-                                    MessageKind::Error,
-                                    format!("Unable to set frame size: {:?}", e),
-                                );
+                                log_error!("Unable to set frame size: {:?}", e);
+                            }
+                        }
+                        WindowEvent::ScaleFactorChanged {
+                            scale_factor,
+                            ref new_inner_size,
+                        } => {
+                            // Window was moved to a monitor with different DPI.
+                            engine.user_interface.set_scale(scale_factor as f32);
+                            if let Err(e) = engine.set_frame_size((**new_inner_size).into()) {
+                                log_error!("Unable to set frame size: {:?}", e);
+                            }
+                        }
+                        WindowEvent::KeyboardInput { input, .. } => {
+                            if let (ElementState::Pressed, Some(VirtualKeyCode::Grave)) =
+                                (input.state, input.virtual_keycode)
+                            {
+                                let visible = engine.user_interface.node(console).visibility();
+                                engine
+                                    .user_interface
+                                    .send_message(WidgetMessage::visibility(
+                                        console,
+                                        MessageDirection::ToWidget,
+                                        !visible,
+                                    ));
                             }
                         }
                         _ => (),
@@ -136,6 +193,7 @@ impl<State: GameState> Framework<State> {
                 }
                 Event::LoopDestroyed => state.on_exit(&mut engine),
                 _ => *control_flow = ControlFlow::Poll,
-            })
+            }
+        })
     }
 }
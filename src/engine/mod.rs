@@ -1,45 +1,87 @@
 //! Engine is container for all subsystems (renderer, ui, sound, resource manager). It also
 //! creates a window and an OpenGL context.
+//!
+//! # Headless mode
+//!
+//! The engine can also be created without a window, OpenGL context, renderer and sound output
+//! device using [`Engine::new_headless`]. Such engine is still capable to update scenes (graph,
+//! physics, animations), load resources, etc. This is useful for dedicated servers and tests.
+//! Textures are still loaded in headless mode, but they're never uploaded to GPU.
 
 #![warn(missing_docs)]
 
 pub mod error;
 pub mod framework;
+pub mod plugin;
 pub mod resource_manager;
+pub mod scene_loader;
+pub mod settings;
+pub mod task;
+pub mod ui_anchor;
 
 use crate::{
     core::{
         algebra::Vector2,
         instant,
         pool::Handle,
-        visitor::{Visit, VisitResult, Visitor},
+        profile_scope, profiler,
+        visitor::{Visit, VisitError, VisitResult, Visitor},
     },
-    engine::{error::EngineError, resource_manager::ResourceManager},
+    engine::{
+        error::EngineError,
+        plugin::{EngineContext, Plugin, PluginEntry, PluginHandle},
+        resource_manager::ResourceManager,
+        scene_loader::PendingScene,
+        task::TaskManager,
+        ui_anchor::UiAnchors,
+    },
+    event::Event,
     event_loop::EventLoop,
-    gui::UserInterface,
+    gui::{message::UiMessage, UserInterface},
     renderer::{framework::error::FrameworkError, Renderer},
     resource::texture::TextureKind,
-    scene::SceneContainer,
+    scene::{Scene, SceneContainer},
     scene2d::Scene2dContainer,
     sound::engine::SoundEngine,
     window::{Window, WindowBuilder},
 };
 use fxhash::FxHashMap;
 use std::{
+    future::Future,
     hash::Hash,
+    path::Path,
     sync::{Arc, Mutex},
     time::Duration,
 };
 
-/// See module docs.
-pub struct Engine {
+/// Size of virtual frame that is used to update scenes and user interface in headless mode.
+pub const HEADLESS_FRAME_SIZE: Vector2<f32> = Vector2::new(1024.0, 768.0);
+
+struct GraphicsContext {
     #[cfg(not(target_arch = "wasm32"))]
     context: glutin::WindowedContext<glutin::PossiblyCurrent>,
     #[cfg(target_arch = "wasm32")]
     window: winit::window::Window,
-    /// Current renderer. You should call at least [render](Self::render) method to see your scene on
-    /// screen.
-    pub renderer: Renderer,
+    renderer: Renderer,
+}
+
+impl GraphicsContext {
+    fn window(&self) -> &Window {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.context.window()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            &self.window
+        }
+    }
+}
+
+/// See module docs.
+pub struct Engine {
+    // Graphics context is `None` in headless mode.
+    graphics_context: Option<GraphicsContext>,
     /// User interface allows you to build interface of any kind. UI itself is *not* thread-safe,
     /// but it uses messages to "talk" with outside world and message queue (MPSC) *is* thread-safe
     /// so its sender part can be shared across threads.
@@ -61,6 +103,12 @@ pub struct Engine {
     pub ui_time: Duration,
     /// All available 2d scenes.
     pub scenes2d: Scene2dContainer,
+    /// Widgets that follow scene nodes, see [`ui_anchor`] module docs.
+    pub ui_anchors: UiAnchors,
+    /// Shared thread pool for background work, see [`task`] module docs.
+    pub tasks: TaskManager,
+    plugins: Vec<PluginEntry>,
+    next_plugin_id: u64,
 }
 
 impl Engine {
@@ -147,58 +195,195 @@ impl Engine {
 
         let renderer = Renderer::new(glow_context, (client_size.x as u32, client_size.y as u32))?;
 
-        Ok(Self {
-            resource_manager: ResourceManager::new(renderer.upload_sender()),
-            renderer,
+        let mut engine = Self {
+            resource_manager: ResourceManager::new(Some(renderer.upload_sender())),
+            graphics_context: Some(GraphicsContext {
+                renderer,
+                #[cfg(not(target_arch = "wasm32"))]
+                context,
+                #[cfg(target_arch = "wasm32")]
+                window,
+            }),
             scenes: SceneContainer::new(sound_engine.clone()),
             scenes2d: Scene2dContainer::new(sound_engine.clone()),
             sound_engine,
             user_interface: UserInterface::new(client_size),
             ui_time: Default::default(),
-            #[cfg(not(target_arch = "wasm32"))]
-            context,
-            #[cfg(target_arch = "wasm32")]
-            window,
+            ui_anchors: Default::default(),
+            tasks: Default::default(),
+            plugins: Default::default(),
+            next_plugin_id: 0,
+        };
+
+        // Use system scale by default, so the user interface has the same size on any screen.
+        let scale_factor = engine.get_window().scale_factor() as f32;
+        engine.user_interface.set_scale(scale_factor);
+
+        Ok(engine)
+    }
+
+    /// Creates new instance of engine without a window, OpenGL context, renderer and sound output
+    /// device. Scenes, physics, animations, user interface and resource manager are fully functional,
+    /// but nothing will be rendered or played. The engine must be driven manually by calling
+    /// [`Self::update`]. Scenes without a render target and user interface will use
+    /// [`HEADLESS_FRAME_SIZE`] as frame size.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use rg3d::engine::Engine;
+    ///
+    /// let mut engine = Engine::new_headless().unwrap();
+    ///
+    /// // Simulate one second of game time.
+    /// for _ in 0..60 {
+    ///     engine.update(1.0 / 60.0);
+    /// }
+    /// ```
+    pub fn new_headless() -> Result<Self, EngineError> {
+        let sound_engine = SoundEngine::without_device();
+
+        Ok(Self {
+            resource_manager: ResourceManager::new(None),
+            graphics_context: None,
+            scenes: SceneContainer::new(sound_engine.clone()),
+            scenes2d: Scene2dContainer::new(sound_engine.clone()),
+            sound_engine,
+            user_interface: UserInterface::new(HEADLESS_FRAME_SIZE),
+            ui_time: Default::default(),
+            ui_anchors: Default::default(),
+            tasks: Default::default(),
+            plugins: Default::default(),
+            next_plugin_id: 0,
         })
     }
 
+    /// Returns `true` if the engine was created by [`Self::new_headless`] and has no window and
+    /// renderer.
+    pub fn is_headless(&self) -> bool {
+        self.graphics_context.is_none()
+    }
+
+    /// Returns a reference to current renderer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the engine is running in headless mode, use [`Self::try_renderer`] if you need
+    /// to support headless mode.
+    pub fn renderer(&self) -> &Renderer {
+        self.try_renderer()
+            .expect("Renderer is not available in headless mode!")
+    }
+
+    /// Returns a reference to current renderer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the engine is running in headless mode, use [`Self::try_renderer_mut`] if you need
+    /// to support headless mode.
+    pub fn renderer_mut(&mut self) -> &mut Renderer {
+        self.try_renderer_mut()
+            .expect("Renderer is not available in headless mode!")
+    }
+
+    /// Returns a reference to current renderer, or `None` if the engine is running in headless mode.
+    /// You should call at least [render](Self::render) method to see your scene on screen.
+    pub fn try_renderer(&self) -> Option<&Renderer> {
+        self.graphics_context.as_ref().map(|ctx| &ctx.renderer)
+    }
+
+    /// Returns a reference to current renderer, or `None` if the engine is running in headless mode.
+    pub fn try_renderer_mut(&mut self) -> Option<&mut Renderer> {
+        self.graphics_context.as_mut().map(|ctx| &mut ctx.renderer)
+    }
+
     /// Adjust size of the frame to be rendered. Must be called after the window size changes.
     /// Will update the renderer and GL context frame size.
     /// When using the [`framework::Framework`], you don't need to call this yourself.
+    ///
+    /// Returns an error in headless mode.
     pub fn set_frame_size(&mut self, new_size: (u32, u32)) -> Result<(), FrameworkError> {
-        self.renderer.set_frame_size(new_size)?;
+        let graphics_context = self
+            .graphics_context
+            .as_mut()
+            .ok_or_else(|| headless_error("set frame size"))?;
+
+        graphics_context.renderer.set_frame_size(new_size)?;
 
         #[cfg(not(target_arch = "wasm32"))]
-        self.context.resize(new_size.into());
+        graphics_context.context.resize(new_size.into());
 
         Ok(())
     }
 
     /// Returns reference to main window. Could be useful to set fullscreen mode, change
     /// size of window, its title, etc.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the engine is running in headless mode, use [`Self::try_get_window`] if you need
+    /// to support headless mode.
     #[inline]
     pub fn get_window(&self) -> &Window {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.context.window()
-        }
-        #[cfg(target_arch = "wasm32")]
-        {
-            &self.window
-        }
+        self.try_get_window()
+            .expect("Window is not available in headless mode!")
+    }
+
+    /// Returns reference to main window, or `None` if the engine is running in headless mode.
+    #[inline]
+    pub fn try_get_window(&self) -> Option<&Window> {
+        self.graphics_context.as_ref().map(|ctx| ctx.window())
     }
 
     /// Performs single update tick with given time delta. Engine internally will perform update
     /// of all scenes, sub-systems, user interface, etc. Must be called in order to get engine
     /// functioning.
     pub fn update(&mut self, dt: f32) {
-        let inner_size = self.get_window().inner_size();
-        let window_size = Vector2::new(inner_size.width as f32, inner_size.height as f32);
+        // Update is the first thing that happens in a frame, so this is the right place to
+        // finish previous frame of the profiler.
+        profiler::frame::next_frame();
+        profile_scope!("Update");
+
+        let window_size = self.try_get_window().map_or(HEADLESS_FRAME_SIZE, |window| {
+            let inner_size = window.inner_size();
+            Vector2::new(inner_size.width as f32, inner_size.height as f32)
+        });
 
         self.resource_manager.state().update(dt);
-        self.renderer.update(dt);
+        if let Some(renderer) = self.try_renderer_mut() {
+            renderer.update(dt);
+        }
+
+        // Callbacks of finished tasks are called before plugins, so plugins will see results of the
+        // tasks in the same frame.
+        let completed = self.tasks.take_completed();
+        if !completed.is_empty() {
+            profile_scope!("TaskCallbacks");
+            self.with_plugins(|_, context| {
+                for callback in completed {
+                    callback(context);
+                }
+            });
+        }
+
+        {
+            profile_scope!("Plugins");
+            self.with_plugins(|plugins, context| {
+                for entry in plugins.iter_mut() {
+                    entry.plugin.on_update(dt, context);
+                }
+            });
+        }
+
+        for scene in self.scenes.iter_mut() {
+            // Disabled scene must be silent, every scene has its own sound context with its own
+            // listener, so there is no need to do anything else with sound.
+            scene.sync_sound_pause();
+
+            if !scene.enabled {
+                continue;
+            }
 
-        for scene in self.scenes.iter_mut().filter(|s| s.enabled) {
             let frame_size = scene.render_target.as_ref().map_or(window_size, |rt| {
                 if let TextureKind::Rectangle { width, height } = rt.data_ref().kind() {
                     Vector2::new(width as f32, height as f32)
@@ -222,6 +407,11 @@ impl Engine {
             scene.update(render_target_size, dt);
         }
 
+        // Anchored widgets must follow their nodes in the same frame, so the anchors are updated
+        // after scenes, but before layout of the user interface.
+        self.ui_anchors
+            .update(&self.scenes, &mut self.user_interface, window_size);
+
         let time = instant::Instant::now();
         self.user_interface.update(window_size, dt);
         self.ui_time = instant::Instant::now() - time;
@@ -229,28 +419,140 @@ impl Engine {
 
     /// Performs rendering of single frame, must be called from your game loop, otherwise you won't
     /// see anything.
+    ///
+    /// Returns an error in headless mode.
     #[inline]
     pub fn render(&mut self) -> Result<(), FrameworkError> {
+        let graphics_context = self
+            .graphics_context
+            .as_mut()
+            .ok_or_else(|| headless_error("render a frame"))?;
+
         self.user_interface.draw();
 
         #[cfg(not(target_arch = "wasm32"))]
         {
-            self.renderer.render_and_swap_buffers(
+            graphics_context.renderer.render_and_swap_buffers(
                 &self.scenes,
                 self.user_interface.get_drawing_context(),
+                self.user_interface.scale(),
                 &self.scenes2d,
-                &self.context,
+                &graphics_context.context,
             )
         }
         #[cfg(target_arch = "wasm32")]
         {
-            self.renderer.render_and_swap_buffers(
+            graphics_context.renderer.render_and_swap_buffers(
                 &self.scenes,
                 &self.user_interface.get_drawing_context(),
+                self.user_interface.scale(),
                 &self.scenes2d,
             )
         }
     }
+
+    fn with_plugins<R, F>(&mut self, func: F) -> R
+    where
+        F: FnOnce(&mut Vec<PluginEntry>, &mut EngineContext) -> R,
+    {
+        let (renderer, window) = match self.graphics_context.as_mut() {
+            Some(graphics_context) => {
+                #[cfg(not(target_arch = "wasm32"))]
+                let window = graphics_context.context.window();
+                #[cfg(target_arch = "wasm32")]
+                let window = &graphics_context.window;
+                (Some(&mut graphics_context.renderer), Some(window))
+            }
+            None => (None, None),
+        };
+
+        let mut context = EngineContext {
+            scenes: &mut self.scenes,
+            scenes2d: &mut self.scenes2d,
+            resource_manager: &self.resource_manager,
+            renderer,
+            window,
+            user_interface: &mut self.user_interface,
+            sound_engine: &self.sound_engine,
+            tasks: &mut self.tasks,
+        };
+
+        func(&mut self.plugins, &mut context)
+    }
+
+    /// Starts loading of a scene from given file in background, current scenes continue to play
+    /// while the scene is loading. Use [`crate::scene::SceneContainer::swap_to`] to replace current
+    /// scene with the loaded one. See [`scene_loader`] module docs for more info.
+    pub fn load_scene_async<P: AsRef<Path>>(&self, path: P) -> PendingScene {
+        PendingScene::from_file(self.resource_manager.clone(), path)
+    }
+
+    /// Same as [`Self::load_scene_async`], but the scene is created by given builder. The builder
+    /// runs in background, it could request resources and wait for them.
+    pub fn build_scene_async<B, F>(&self, builder: B) -> PendingScene
+    where
+        B: FnOnce(ResourceManager) -> F,
+        F: Future<Output = Result<Scene, VisitError>> + Send + 'static,
+    {
+        PendingScene::new(
+            self.resource_manager.clone(),
+            builder(self.resource_manager.clone()),
+        )
+    }
+
+    /// Adds new plugin to the engine and immediately calls its [`Plugin::on_init`] method.
+    /// Plugins are called in order of addition, see [`plugin`] module docs for more info.
+    pub fn add_plugin(&mut self, plugin: Box<dyn Plugin>) -> PluginHandle {
+        let handle = PluginHandle(self.next_plugin_id);
+        self.next_plugin_id += 1;
+
+        self.plugins.push(PluginEntry { handle, plugin });
+        self.with_plugins(|plugins, context| plugins.last_mut().unwrap().plugin.on_init(context));
+
+        handle
+    }
+
+    /// Removes a plugin from the engine and calls its [`Plugin::on_remove`] method. Returns
+    /// `None` if there is no such plugin.
+    pub fn remove_plugin(&mut self, handle: PluginHandle) -> Option<Box<dyn Plugin>> {
+        let index = self.plugins.iter().position(|e| e.handle == handle)?;
+        let mut plugin = self.plugins.remove(index).plugin;
+        self.with_plugins(|_, context| plugin.on_remove(context));
+        Some(plugin)
+    }
+
+    /// Returns true if the engine has a plugin with given handle.
+    pub fn has_plugin(&self, handle: PluginHandle) -> bool {
+        self.plugins.iter().any(|e| e.handle == handle)
+    }
+
+    /// Passes an OS event to every plugin. [`framework::Framework`] calls this method
+    /// automatically, if you have your own game loop, call it for every event.
+    pub fn handle_os_event(&mut self, event: &Event<()>) {
+        self.with_plugins(|plugins, context| {
+            for entry in plugins.iter_mut() {
+                entry.plugin.on_os_event(event, context);
+            }
+        });
+    }
+
+    /// Passes an UI message to every plugin. [`framework::Framework`] calls this method
+    /// automatically, if you have your own game loop, call it for every message that was
+    /// polled from the user interface.
+    pub fn handle_ui_message(&mut self, message: &UiMessage) {
+        self.with_plugins(|plugins, context| {
+            for entry in plugins.iter_mut() {
+                entry.plugin.on_ui_message(message, context);
+            }
+        });
+    }
+}
+
+fn headless_error(action: &str) -> FrameworkError {
+    FrameworkError::Custom(format!(
+        "Unable to {}: the engine is running in headless mode!",
+        action
+    ))
 }
 
 impl Visit for Engine {
@@ -258,7 +560,9 @@ impl Visit for Engine {
         visitor.enter_region(name)?;
 
         if visitor.is_reading() {
-            self.renderer.flush();
+            if let Some(renderer) = self.try_renderer_mut() {
+                renderer.flush();
+            }
             self.resource_manager.state().update(0.0);
             self.scenes.clear();
             self.scenes2d.clear();
@@ -270,7 +574,8 @@ impl Visit for Engine {
         self.scenes2d.visit("Scenes2d", visitor)?;
 
         if visitor.is_reading() {
-            self.resource_manager.state().upload_sender = Some(self.renderer.upload_sender());
+            self.resource_manager.state().upload_sender =
+                self.try_renderer().map(|renderer| renderer.upload_sender());
 
             crate::core::futures::executor::block_on(self.resource_manager.reload_resources());
             for scene in self.scenes.iter_mut() {
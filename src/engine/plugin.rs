@@ -0,0 +1,78 @@
+//! Plugins are reusable systems (character controllers, day/night cycles, FPS counters, etc.)
+//! that can be registered in the engine once and then will be called by the engine at defined
+//! points of each frame, instead of being wired manually into the game loop.
+//!
+//! # Call order
+//!
+//! - [`Plugin::on_init`] - once, when the plugin is added by [`crate::engine::Engine::add_plugin`].
+//! - [`Plugin::on_update`] - in [`crate::engine::Engine::update`], after resource manager and
+//! renderer were updated and callbacks of finished tasks were called, but before scenes and user
+//! interface. So any changes made by a plugin will be processed in the same frame.
+//! - [`Plugin::on_os_event`] - in [`crate::engine::Engine::handle_os_event`].
+//! - [`Plugin::on_ui_message`] - in [`crate::engine::Engine::handle_ui_message`].
+//! - [`Plugin::on_remove`] - once, when the plugin is removed by
+//! [`crate::engine::Engine::remove_plugin`].
+//!
+//! Plugins are always called in order of addition. [`crate::engine::framework::Framework`]
+//! passes OS events and UI messages to plugins automatically, if you have your own game loop,
+//! you have to call `handle_os_event` and `handle_ui_message` by yourself.
+
+use crate::{
+    engine::{resource_manager::ResourceManager, task::TaskManager},
+    event::Event,
+    gui::{message::UiMessage, UserInterface},
+    renderer::Renderer,
+    scene::SceneContainer,
+    scene2d::Scene2dContainer,
+    sound::engine::SoundEngine,
+    window::Window,
+};
+use std::sync::{Arc, Mutex};
+
+/// A set of references to the engine sub-systems, that is passed to every plugin callback.
+pub struct EngineContext<'a> {
+    /// All available scenes in the engine.
+    pub scenes: &'a mut SceneContainer,
+    /// All available 2d scenes.
+    pub scenes2d: &'a mut Scene2dContainer,
+    /// Current resource manager.
+    pub resource_manager: &'a ResourceManager,
+    /// Current renderer, `None` if the engine is running in headless mode.
+    pub renderer: Option<&'a mut Renderer>,
+    /// Main window, `None` if the engine is running in headless mode.
+    pub window: Option<&'a Window>,
+    /// User interface of the engine.
+    pub user_interface: &'a mut UserInterface,
+    /// Sound engine.
+    pub sound_engine: &'a Arc<Mutex<SoundEngine>>,
+    /// Shared thread pool for background work.
+    pub tasks: &'a mut TaskManager,
+}
+
+/// A reusable system that is called by the engine, see module docs for more info. Every method is
+/// optional.
+pub trait Plugin: 'static {
+    /// Called once when the plugin is added to the engine.
+    fn on_init(&mut self, _context: &mut EngineContext) {}
+
+    /// Called once per engine update with the same time step that was passed to the engine.
+    fn on_update(&mut self, _dt: f32, _context: &mut EngineContext) {}
+
+    /// Called for every OS event that was passed to the engine.
+    fn on_os_event(&mut self, _event: &Event<()>, _context: &mut EngineContext) {}
+
+    /// Called for every UI message that was passed to the engine.
+    fn on_ui_message(&mut self, _message: &UiMessage, _context: &mut EngineContext) {}
+
+    /// Called once when the plugin is removed from the engine.
+    fn on_remove(&mut self, _context: &mut EngineContext) {}
+}
+
+/// A handle of a plugin that was added to the engine. Can be used to remove the plugin.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PluginHandle(pub(in crate) u64);
+
+pub(in crate) struct PluginEntry {
+    pub(in crate) handle: PluginHandle,
+    pub(in crate) plugin: Box<dyn Plugin>,
+}
@@ -3,27 +3,42 @@
 use crate::resource::curve::{CurveResource, CurveResourceState};
 use crate::{
     asset::{Resource, ResourceData, ResourceLoadError, ResourceState},
-    core::{futures::executor::ThreadPool, instant, visitor::prelude::*, VecExtensions},
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        conventions::CoordinateSystem,
+        futures::executor::ThreadPool,
+        instant,
+        io::FileLoadError,
+        parking_lot, profile_scope,
+        visitor::prelude::*,
+        VecExtensions,
+    },
     material::shader::{Shader, ShaderState},
     renderer::TextureUploadSender,
     resource::{
+        io::{ResourceIo, ResourceSource},
         model::{Model, ModelData},
         texture::{
-            CompressionOptions, Texture, TextureData, TextureError, TextureMagnificationFilter,
-            TextureMinificationFilter, TexturePixelKind, TextureState, TextureWrapMode,
+            CompressionOptions, Texture, TextureData, TextureError, TextureKind,
+            TextureMagnificationFilter, TextureMinificationFilter, TexturePixelKind, TextureState,
+            TextureWrapMode,
         },
     },
+    scene::mesh::surface::SurfaceData,
     sound::buffer::{
         DataSource, SoundBufferResource, SoundBufferResourceLoadError, SoundBufferState,
     },
-    utils::log::{Log, MessageKind},
+    utils::log::{log_error, log_info, log_warn},
 };
 use std::{
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
     sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
 };
 
+pub use crate::core::conventions::{Handedness, UpAxis};
+
 /// Lifetime of orphaned resource in seconds (with only one strong ref which is resource manager itself)
 pub const DEFAULT_RESOURCE_LIFETIME: f32 = 60.0;
 
@@ -135,12 +150,9 @@ where
             if resource.use_count() <= 1 {
                 resource.time_to_live -= dt;
                 if resource.time_to_live <= 0.0 {
-                    Log::writeln(
-                        MessageKind::Information,
-                        format!(
-                            "Resource {:?} destroyed because it not used anymore!",
-                            resource.state().path()
-                        ),
+                    log_info!(
+                        "Resource {:?} destroyed because it not used anymore!",
+                        resource.state().path()
                     );
 
                     false
@@ -233,6 +245,116 @@ where
     }
 }
 
+/// Progress of loading of resources, see [`ResourceManager::progress`] for more info.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LoadingProgress {
+    /// Amount of resources that finished loading (successfully or not).
+    pub loaded: usize,
+    /// Amount of resources that failed to load, they are counted in `loaded` too.
+    pub failed: usize,
+    /// Total amount of resources in current batch.
+    pub total: usize,
+    /// Path of a resource that is still loading, `None` if everything is loaded.
+    pub current: Option<PathBuf>,
+}
+
+impl LoadingProgress {
+    /// Returns progress in `[0; 1]` range.
+    pub fn fraction(&self) -> f32 {
+        if self.total > 0 {
+            self.loaded as f32 / self.total as f32
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns true if every resource of the batch is loaded (or failed to load).
+    pub fn is_done(&self) -> bool {
+        self.loaded == self.total
+    }
+}
+
+enum TrackedStatus {
+    Pending,
+    Loaded,
+    Failed,
+}
+
+trait TrackedResource: Send {
+    fn status(&self) -> TrackedStatus;
+
+    fn path(&self) -> PathBuf;
+}
+
+impl<R, E> TrackedResource for Resource<R, E>
+where
+    R: ResourceData,
+    E: ResourceLoadError,
+{
+    fn status(&self) -> TrackedStatus {
+        match *self.state() {
+            ResourceState::Pending { .. } => TrackedStatus::Pending,
+            ResourceState::LoadError { .. } => TrackedStatus::Failed,
+            // Placeholders are substituted for resources that failed to load.
+            ResourceState::Ok(ref data) if data.is_placeholder() => TrackedStatus::Failed,
+            ResourceState::Ok(_) => TrackedStatus::Loaded,
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.state().path().to_path_buf()
+    }
+}
+
+// Tracks every resource that was requested since last moment when there were no pending
+// resources. Resources that are requested by other resources (for example textures of a model)
+// are requested while the "parent" resource is still loading, so they're included in the same
+// batch.
+#[derive(Default)]
+struct LoadingTracker {
+    pending: Vec<Box<dyn TrackedResource>>,
+    total: usize,
+    failed: usize,
+    // Prevents starting new batch, see `ResourceManager::prefetch`.
+    keep_batch: bool,
+}
+
+impl LoadingTracker {
+    fn refresh(&mut self) {
+        let mut failed = 0;
+        self.pending.retain(|resource| match resource.status() {
+            TrackedStatus::Pending => true,
+            TrackedStatus::Loaded => false,
+            TrackedStatus::Failed => {
+                failed += 1;
+                false
+            }
+        });
+        self.failed += failed;
+    }
+
+    fn track(&mut self, resource: Box<dyn TrackedResource>) {
+        self.refresh();
+        // Start new batch if previous one is finished.
+        if self.pending.is_empty() && !self.keep_batch {
+            self.total = 0;
+            self.failed = 0;
+        }
+        self.total += 1;
+        self.pending.push(resource);
+    }
+
+    fn progress(&mut self) -> LoadingProgress {
+        self.refresh();
+        LoadingProgress {
+            loaded: self.total - self.pending.len(),
+            failed: self.failed,
+            total: self.total,
+            current: self.pending.first().map(|r| r.path()),
+        }
+    }
+}
+
 /// See module docs.
 pub struct ResourceManagerState {
     textures: ResourceContainer<Texture>,
@@ -241,9 +363,17 @@ pub struct ResourceManagerState {
     shaders: ResourceContainer<Shader>,
     curves: ResourceContainer<CurveResource>,
     textures_import_options: TextureImportOptions,
+    models_import_options: ModelImportOptions,
+    loading_tracker: LoadingTracker,
     #[cfg(not(target_arch = "wasm32"))]
     thread_pool: ThreadPool,
     pub(in crate) upload_sender: Option<TextureUploadSender>,
+    resource_io: Option<Arc<dyn ResourceIo>>,
+    filesystem_fallback: bool,
+    texture_placeholders: bool,
+    model_placeholders: bool,
+    memory_resource_provider: Option<Arc<dyn MemoryResourceProvider>>,
+    embed_memory_resources: bool,
 }
 
 impl Default for ResourceManagerState {
@@ -255,9 +385,17 @@ impl Default for ResourceManagerState {
             shaders: Default::default(),
             curves: Default::default(),
             textures_import_options: Default::default(),
+            models_import_options: Default::default(),
+            loading_tracker: Default::default(),
             #[cfg(not(target_arch = "wasm32"))]
             thread_pool: ThreadPool::new().unwrap(),
             upload_sender: None,
+            resource_io: None,
+            filesystem_fallback: true,
+            texture_placeholders: true,
+            model_placeholders: true,
+            memory_resource_provider: None,
+            embed_memory_resources: true,
         }
     }
 }
@@ -341,6 +479,140 @@ impl TextureImportOptions {
     }
 }
 
+/// Defines when the engine should generate tangents for meshes of imported models.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Visit)]
+pub enum TangentGeneration {
+    /// Always keep tangents from a model file, even if they're missing or invalid.
+    Never,
+    /// Generate tangents only if a model file does not have them or they are invalid
+    /// (NaNs, zero-length, parallel to normals, etc.). This is default option.
+    IfMissing,
+    /// Always generate tangents, tangents from a model file will be discarded.
+    Always,
+}
+
+impl Default for TangentGeneration {
+    fn default() -> Self {
+        Self::IfMissing
+    }
+}
+
+/// Allows you to define a set of defaults for every imported model.
+///
+/// Options are stored in a model resource, so hot reloading of the resource will use the same
+/// settings.
+///
+/// # Coordinate system conversion
+///
+/// Scale and axis conversion are baked into local transform of the root node of a model resource,
+/// so the conversion is applied to the entire hierarchy (including skinned meshes and animations)
+/// and every instance of the model. Keep in mind that if you change scale or rotation of the root
+/// node of an instance, the conversion will be lost. See [`crate::core::conventions`] for the
+/// conventions of the engine and conversion rules.
+///
+/// If a model file has unit or up axis metadata that disagrees with the options, a warning is
+/// written to the log.
+#[derive(Clone, Debug, PartialEq, Visit)]
+pub struct ModelImportOptions {
+    tangent_generation: TangentGeneration,
+    scale: f32,
+    up_axis: UpAxis,
+    handedness: Handedness,
+    animation_resampling_rate: Option<f32>,
+}
+
+impl Default for ModelImportOptions {
+    fn default() -> Self {
+        Self {
+            tangent_generation: Default::default(),
+            scale: 1.0,
+            up_axis: Default::default(),
+            handedness: Default::default(),
+            animation_resampling_rate: None,
+        }
+    }
+}
+
+impl ModelImportOptions {
+    /// Sets uniform scale factor that will be applied to every imported model. It is useful to fix
+    /// models that were made in different units, for example to convert centimeters to meters use
+    /// `0.01`.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Returns current scale factor.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Sets an axis that points up in source model files. Models will be rotated so Y axis will
+    /// point up.
+    pub fn with_up_axis(mut self, up_axis: UpAxis) -> Self {
+        self.up_axis = up_axis;
+        self
+    }
+
+    /// Returns current up axis of source model files.
+    pub fn up_axis(&self) -> UpAxis {
+        self.up_axis
+    }
+
+    /// Sets handedness of coordinate system of source model files. Left-handed models will be
+    /// converted to right-handed coordinate system used by the engine.
+    pub fn with_handedness(mut self, handedness: Handedness) -> Self {
+        self.handedness = handedness;
+        self
+    }
+
+    /// Returns current handedness of coordinate system of source model files.
+    pub fn handedness(&self) -> Handedness {
+        self.handedness
+    }
+
+    /// Sets desired sampling rate (amount of key frames per second) of imported animations.
+    /// `None` means that key frames will be imported as is. This is useful to reduce memory
+    /// usage of animations that were keyed with high frame rate (e.g. 240 fps).
+    pub fn with_animation_resampling_rate(mut self, rate: Option<f32>) -> Self {
+        self.animation_resampling_rate = rate;
+        self
+    }
+
+    /// Returns current sampling rate of imported animations.
+    pub fn animation_resampling_rate(&self) -> Option<f32> {
+        self.animation_resampling_rate
+    }
+
+    /// Returns coordinate system of source model files.
+    pub fn coordinate_system(&self) -> CoordinateSystem {
+        CoordinateSystem::new(self.up_axis, self.handedness)
+    }
+
+    /// Returns `true` if a model will be mirrored by the coordinate system conversion.
+    pub fn is_mirroring(&self) -> bool {
+        self.coordinate_system().is_mirroring()
+    }
+
+    /// Returns a transform that converts a model from its source coordinate system to the coordinate
+    /// system of the engine (including scale).
+    pub fn conversion_transform(&self) -> (UnitQuaternion<f32>, Vector3<f32>) {
+        let (rotation, mirror) = self.coordinate_system().to_engine_parts();
+        (rotation, mirror.scale(self.scale))
+    }
+
+    /// Sets new tangent generation mode which will be applied to every imported model.
+    pub fn with_tangent_generation(mut self, tangent_generation: TangentGeneration) -> Self {
+        self.tangent_generation = tangent_generation;
+        self
+    }
+
+    /// Returns current tangent generation mode.
+    pub fn tangent_generation(&self) -> TangentGeneration {
+        self.tangent_generation
+    }
+}
+
 /// An error that may occur during texture registration.
 #[derive(Debug, thiserror::Error)]
 pub enum TextureRegistrationError {
@@ -361,19 +633,68 @@ impl From<TextureError> for TextureRegistrationError {
     }
 }
 
+/// An error that may occur during registration or modification of in-memory resources.
+#[derive(Debug, thiserror::Error)]
+pub enum MemoryResourceError {
+    /// Name of a resource is empty.
+    #[error("A name of in-memory resource must not be empty!")]
+    EmptyName,
+    /// A resource with the same name is already registered.
+    #[error("A resource {0:?} is already registered!")]
+    AlreadyRegistered(PathBuf),
+    /// There is a real file with the same path as the name of a resource.
+    #[error("A name {0:?} of in-memory resource collides with a real path!")]
+    PathCollision(PathBuf),
+    /// Size of the data does not match with the size of a texture.
+    #[error("Size of the data does not match with the size of the texture!")]
+    InvalidData,
+    /// There is no registered texture with given name.
+    #[error("There is no texture {0:?}!")]
+    NotFound(PathBuf),
+    /// A texture is in invalid state (Pending, LoadErr).
+    #[error("A texture was in invalid state!")]
+    InvalidState,
+    /// Texture modification has failed.
+    #[error(transparent)]
+    Texture(TextureError),
+}
+
+impl From<TextureError> for MemoryResourceError {
+    fn from(e: TextureError) -> Self {
+        Self::Texture(e)
+    }
+}
+
+/// Restores in-memory resources by their names when they are requested, but they are not
+/// registered, for example when a saved scene that uses them is loaded. See
+/// [`ResourceManager::register_texture_from_memory`] for more info.
+///
+/// Methods of the provider are called while the resource manager is locked, so they must not
+/// use the resource manager.
+pub trait MemoryResourceProvider: Send + Sync {
+    /// Returns content of a texture with given name, or `None` if the name is unknown.
+    fn provide_texture(&self, _name: &Path) -> Option<TextureData> {
+        None
+    }
+
+    /// Returns surfaces of a model with given name, or `None` if the name is unknown.
+    fn provide_model(&self, _name: &Path) -> Option<Vec<Arc<parking_lot::Mutex<SurfaceData>>>> {
+        None
+    }
+}
+
 async fn load_texture(
     texture: Texture,
     path: PathBuf,
     options: TextureImportOptions,
-    upload_sender: TextureUploadSender,
+    upload_sender: Option<TextureUploadSender>,
+    source: ResourceSource,
+    placeholder: bool,
 ) {
     let time = instant::Instant::now();
-    match TextureData::load_from_file(&path, options.compression).await {
+    match TextureData::load_from_file(&path, options.compression, &source).await {
         Ok(mut raw_texture) => {
-            Log::writeln(
-                MessageKind::Information,
-                format!("Texture {:?} is loaded in {:?}!", path, time.elapsed()),
-            );
+            log_info!("Texture {:?} is loaded in {:?}!", path, time.elapsed());
 
             raw_texture.set_magnification_filter(options.magnification_filter);
             raw_texture.set_minification_filter(options.minification_filter);
@@ -383,19 +704,28 @@ async fn load_texture(
 
             texture.state().commit(ResourceState::Ok(raw_texture));
 
-            // Ask renderer to upload texture to GPU.
-            upload_sender.request_upload(texture);
+            // Ask renderer to upload texture to GPU. There is no renderer in headless mode.
+            if let Some(upload_sender) = upload_sender {
+                upload_sender.request_upload(texture);
+            }
         }
         Err(error) => {
-            Log::writeln(
-                MessageKind::Error,
-                format!("Unable to load texture {:?}! Reason {:?}", &path, &error),
-            );
+            log_error!("Unable to load texture {:?}! Reason {:?}", &path, &error);
 
-            texture.state().commit(ResourceState::LoadError {
-                path,
-                error: Some(Arc::new(error)),
-            });
+            if placeholder {
+                texture
+                    .state()
+                    .commit(ResourceState::Ok(TextureData::placeholder(path)));
+
+                if let Some(upload_sender) = upload_sender {
+                    upload_sender.request_upload(texture);
+                }
+            } else {
+                texture.state().commit(ResourceState::LoadError {
+                    path,
+                    error: Some(Arc::new(error)),
+                });
+            }
         }
     }
 }
@@ -405,45 +735,55 @@ async fn load_model(
     path: PathBuf,
     resource_manager: ResourceManager,
     material_search_options: MaterialSearchOptions,
+    import_options: ModelImportOptions,
+    placeholder: bool,
+    report_error: bool,
 ) {
-    match ModelData::load(&path, resource_manager, material_search_options).await {
+    match ModelData::load(
+        &path,
+        resource_manager,
+        material_search_options.clone(),
+        import_options.clone(),
+    )
+    .await
+    {
         Ok(raw_model) => {
-            Log::writeln(
-                MessageKind::Information,
-                format!("Model {:?} is loaded!", path),
-            );
+            log_info!("Model {:?} is loaded!", path);
 
             model.state().commit(ResourceState::Ok(raw_model));
         }
         Err(error) => {
-            Log::writeln(
-                MessageKind::Error,
-                format!("Unable to load model from {:?}! Reason {:?}", path, error),
-            );
+            if report_error {
+                log_error!("Unable to load model from {:?}! Reason {:?}", path, error);
+            }
 
-            model.state().commit(ResourceState::LoadError {
-                path,
-                error: Some(Arc::new(error)),
-            });
+            if placeholder {
+                model
+                    .state()
+                    .commit(ResourceState::Ok(ModelData::placeholder(
+                        path,
+                        material_search_options,
+                        import_options,
+                    )));
+            } else {
+                model.state().commit(ResourceState::LoadError {
+                    path,
+                    error: Some(Arc::new(error)),
+                });
+            }
         }
     }
 }
 
-async fn load_shader(shader: Shader, path: PathBuf) {
-    match ShaderState::from_file(&path).await {
+async fn load_shader(shader: Shader, path: PathBuf, source: ResourceSource) {
+    match ShaderState::from_file(&path, &source).await {
         Ok(shader_state) => {
-            Log::writeln(
-                MessageKind::Information,
-                format!("Shader {:?} is loaded!", path),
-            );
+            log_info!("Shader {:?} is loaded!", path);
 
             shader.state().commit(ResourceState::Ok(shader_state));
         }
         Err(error) => {
-            Log::writeln(
-                MessageKind::Error,
-                format!("Unable to load model from {:?}! Reason {:?}", path, error),
-            );
+            log_error!("Unable to load model from {:?}! Reason {:?}", path, error);
 
             shader.state().commit(ResourceState::LoadError {
                 path,
@@ -453,21 +793,15 @@ async fn load_shader(shader: Shader, path: PathBuf) {
     }
 }
 
-async fn load_curve_resource(curve: CurveResource, path: PathBuf) {
-    match CurveResourceState::from_file(&path).await {
+async fn load_curve_resource(curve: CurveResource, path: PathBuf, source: ResourceSource) {
+    match CurveResourceState::from_source(&path, &source).await {
         Ok(curve_state) => {
-            Log::writeln(
-                MessageKind::Information,
-                format!("Curve {:?} is loaded!", path),
-            );
+            log_info!("Curve {:?} is loaded!", path);
 
             curve.state().commit(ResourceState::Ok(curve_state));
         }
         Err(error) => {
-            Log::writeln(
-                MessageKind::Error,
-                format!("Unable to load curve from {:?}! Reason {:?}", path, error),
-            );
+            log_error!("Unable to load curve from {:?}! Reason {:?}", path, error);
 
             curve.state().commit(ResourceState::LoadError {
                 path,
@@ -477,8 +811,26 @@ async fn load_curve_resource(curve: CurveResource, path: PathBuf) {
     }
 }
 
-async fn load_sound_buffer(resource: SoundBufferResource, path: PathBuf, stream: bool) {
-    match DataSource::from_file(&path).await {
+// Sound buffers are streamed directly from files, so only buffers that are provided by custom
+// data source are loaded in memory.
+async fn load_sound_data_source(
+    path: &Path,
+    source: &ResourceSource,
+) -> Result<DataSource, FileLoadError> {
+    if source.is_custom(path) {
+        Ok(DataSource::from_memory(source.load(path).await?))
+    } else {
+        DataSource::from_file(path).await
+    }
+}
+
+async fn load_sound_buffer(
+    resource: SoundBufferResource,
+    path: PathBuf,
+    stream: bool,
+    source: ResourceSource,
+) {
+    match load_sound_data_source(&path, &source).await {
         Ok(source) => {
             let buffer = if stream {
                 SoundBufferState::raw_streaming(source)
@@ -486,19 +838,16 @@ async fn load_sound_buffer(resource: SoundBufferResource, path: PathBuf, stream:
                 SoundBufferState::raw_generic(source)
             };
             match buffer {
-                Ok(sound_buffer) => {
-                    Log::writeln(
-                        MessageKind::Information,
-                        format!("Sound buffer {:?} is loaded!", path),
-                    );
+                Ok(mut sound_buffer) => {
+                    // Memory data source has no path.
+                    sound_buffer.set_path(path.clone());
+
+                    log_info!("Sound buffer {:?} is loaded!", path);
 
                     resource.state().commit(ResourceState::Ok(sound_buffer));
                 }
                 Err(_) => {
-                    Log::writeln(
-                        MessageKind::Error,
-                        format!("Unable to load sound buffer from {:?}!", path),
-                    );
+                    log_error!("Unable to load sound buffer from {:?}!", path);
 
                     resource.state().commit(ResourceState::LoadError {
                         path: path.clone(),
@@ -508,10 +857,7 @@ async fn load_sound_buffer(resource: SoundBufferResource, path: PathBuf, stream:
             }
         }
         Err(e) => {
-            Log::writeln(
-                MessageKind::Error,
-                format!("Invalid data source for sound buffer: {:?}", e),
-            );
+            log_error!("Invalid data source for sound buffer: {:?}", e);
 
             resource.state().commit(ResourceState::LoadError {
                 path: path.clone(),
@@ -521,50 +867,60 @@ async fn load_sound_buffer(resource: SoundBufferResource, path: PathBuf, stream:
     }
 }
 
-async fn reload_texture(texture: Texture, path: PathBuf, compression: CompressionOptions) {
-    match TextureData::load_from_file(&path, compression).await {
+async fn reload_texture(
+    texture: Texture,
+    path: PathBuf,
+    compression: CompressionOptions,
+    source: ResourceSource,
+    placeholder: bool,
+    report_error: bool,
+) {
+    match TextureData::load_from_file(&path, compression, &source).await {
         Ok(data) => {
-            Log::writeln(
-                MessageKind::Information,
-                format!("Texture {:?} successfully reloaded!", path,),
-            );
+            log_info!("Texture {:?} successfully reloaded!", path);
 
             texture.state().commit(ResourceState::Ok(data));
         }
         Err(e) => {
-            Log::writeln(
-                MessageKind::Error,
-                format!("Unable to reload {:?} texture! Reason: {:?}", path, e),
-            );
+            if report_error {
+                log_error!("Unable to reload {:?} texture! Reason: {:?}", path, e);
+            }
 
-            texture.state().commit(ResourceState::LoadError {
-                path,
-                error: Some(Arc::new(e)),
-            });
+            if placeholder {
+                texture
+                    .state()
+                    .commit(ResourceState::Ok(TextureData::placeholder(path)));
+            } else {
+                texture.state().commit(ResourceState::LoadError {
+                    path,
+                    error: Some(Arc::new(e)),
+                });
+            }
         }
     };
 }
 
-async fn reload_sound_buffer(resource: SoundBufferResource, path: PathBuf, stream: bool) {
-    if let Ok(data_source) = DataSource::from_file(&path).await {
+async fn reload_sound_buffer(
+    resource: SoundBufferResource,
+    path: PathBuf,
+    stream: bool,
+    source: ResourceSource,
+) {
+    if let Ok(data_source) = load_sound_data_source(&path, &source).await {
         let new_sound_buffer = match stream {
             false => SoundBufferState::raw_generic(data_source),
             true => SoundBufferState::raw_streaming(data_source),
         };
         match new_sound_buffer {
-            Ok(new_sound_buffer) => {
-                Log::writeln(
-                    MessageKind::Information,
-                    format!("Sound buffer {:?} successfully reloaded!", path,),
-                );
+            Ok(mut new_sound_buffer) => {
+                new_sound_buffer.set_path(path.clone());
+
+                log_info!("Sound buffer {:?} successfully reloaded!", path);
 
                 resource.state().commit(ResourceState::Ok(new_sound_buffer));
             }
             Err(_) => {
-                Log::writeln(
-                    MessageKind::Error,
-                    format!("Unable to reload {:?} sound buffer!", path),
-                );
+                log_error!("Unable to reload {:?} sound buffer!", path);
 
                 resource.state().commit(ResourceState::LoadError {
                     path,
@@ -576,7 +932,7 @@ async fn reload_sound_buffer(resource: SoundBufferResource, path: PathBuf, strea
 }
 
 impl ResourceManager {
-    pub(in crate) fn new(upload_sender: TextureUploadSender) -> Self {
+    pub(in crate) fn new(upload_sender: Option<TextureUploadSender>) -> Self {
         Self {
             state: Some(Arc::new(Mutex::new(ResourceManagerState::new(
                 upload_sender,
@@ -618,32 +974,31 @@ impl ResourceManager {
     ) -> Texture {
         let mut state = self.state();
 
-        if let Some(texture) = state.textures.find(path.as_ref()) {
-            return texture.clone();
+        if let Some(texture) = state.find_memory_texture(path.as_ref()) {
+            return texture;
         }
 
         let texture = Texture(Resource::new(ResourceState::new_pending(
             path.as_ref().to_owned(),
         )));
         state.textures.push(texture.clone());
+        state.loading_tracker.track(Box::new(texture.0.clone()));
 
         let result = texture.clone();
         let options = import_options.unwrap_or_else(|| state.textures_import_options.clone());
         let path = path.as_ref().to_owned();
-        let upload_sender = state
-            .upload_sender
-            .as_ref()
-            .expect("Upload sender must be set!")
-            .clone();
+        let upload_sender = state.upload_sender.clone();
+        let source = state.resource_source();
+        let placeholder = state.texture_placeholders;
 
         #[cfg(target_arch = "wasm32")]
         crate::core::wasm_bindgen_futures::spawn_local(async move {
-            load_texture(texture, path, options, upload_sender).await;
+            load_texture(texture, path, options, upload_sender, source, placeholder).await;
         });
 
         #[cfg(not(target_arch = "wasm32"))]
         state.thread_pool.spawn_ok(async move {
-            load_texture(texture, path, options, upload_sender).await;
+            load_texture(texture, path, options, upload_sender, source, placeholder).await;
         });
 
         result
@@ -677,6 +1032,149 @@ impl ResourceManager {
         }
     }
 
+    /// Creates new 2D texture from raw pixels and registers it under given synthetic name, so
+    /// it can be requested by the name (see [`Self::request_texture`]) and shared like any other
+    /// texture. `bytes` must contain `height` rows of `width` pixels each in given format. The
+    /// name must not collide with names of other textures or with real paths of resources.
+    ///
+    /// # Serialization
+    ///
+    /// Saved scenes keep the name of the texture. Content of the texture is saved too, unless
+    /// embedding is disabled (see [`ResourceManagerState::set_memory_resources_embedding`]).
+    /// When a scene is loaded, the texture is restored by its name in the following order:
+    /// registered texture with the same name, [`MemoryResourceProvider`] (see
+    /// [`ResourceManagerState::set_memory_resource_provider`]), embedded content.
+    ///
+    /// # Lifetime
+    ///
+    /// In-memory textures are destroyed when they're not used anymore like any other resource,
+    /// they're never reloaded from the file system.
+    pub fn register_texture_from_memory<P: AsRef<Path>>(
+        &self,
+        name: P,
+        width: u32,
+        height: u32,
+        format: TexturePixelKind,
+        bytes: Vec<u8>,
+    ) -> Result<Texture, MemoryResourceError> {
+        let mut state = self.state();
+        state.check_memory_resource_name(name.as_ref(), state.textures.find(name.as_ref()))?;
+        let data = TextureData::from_bytes(
+            TextureKind::Rectangle { width, height },
+            format,
+            bytes,
+            false,
+        )
+        .ok_or(MemoryResourceError::InvalidData)?;
+        Ok(state.add_memory_texture(name.as_ref().to_owned(), data))
+    }
+
+    /// Creates new model with a single mesh with given surfaces and registers it under given
+    /// synthetic name, so it can be requested by the name (see [`Self::request_model`]),
+    /// instantiated and shared like any other model. The name must not collide with names of
+    /// other models or with real paths of resources. Serialization and lifetime rules are the
+    /// same as for [`Self::register_texture_from_memory`], surfaces are saved with their
+    /// content when embedding is enabled.
+    pub fn register_model_from_memory<P: AsRef<Path>>(
+        &self,
+        name: P,
+        surfaces: Vec<Arc<parking_lot::Mutex<SurfaceData>>>,
+    ) -> Result<Model, MemoryResourceError> {
+        let mut state = self.state();
+        state.check_memory_resource_name(name.as_ref(), state.models.find(name.as_ref()))?;
+        Ok(state.add_memory_model(name.as_ref().to_owned(), surfaces))
+    }
+
+    /// Replaces pixels of a rectangular region of a registered texture with given name, see
+    /// [`TextureData::update_region`]. It is useful for textures painted on CPU, for example
+    /// a minimap. Changes are uploaded to GPU when the texture is used next time.
+    pub fn update_texture_region<P: AsRef<Path>>(
+        &self,
+        name: P,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        bytes: &[u8],
+    ) -> Result<(), MemoryResourceError> {
+        let texture = self
+            .state()
+            .textures
+            .find(name.as_ref())
+            .cloned()
+            .ok_or_else(|| MemoryResourceError::NotFound(name.as_ref().to_owned()))?;
+        let mut texture_state = texture.state();
+        match &mut *texture_state {
+            TextureState::Ok(data) => Ok(data.update_region(x, y, width, height, bytes)?),
+            _ => Err(MemoryResourceError::InvalidState),
+        }
+    }
+
+    /// Returns actual instance of a texture that was read from a saved scene. In-memory textures
+    /// are restored by their names, procedural textures are kept as is, other textures are
+    /// requested by their paths.
+    pub(in crate) fn resolve_texture(&self, texture: &Texture) -> Texture {
+        let texture_state = texture.state();
+        let path = texture_state.path().to_path_buf();
+        match &*texture_state {
+            ResourceState::Ok(data) if data.is_in_memory() => {
+                let embedded = data.is_procedural();
+                drop(texture_state);
+                let mut state = self.state();
+                if let Some(registered) = state.find_memory_texture(&path) {
+                    return registered;
+                }
+                if embedded {
+                    state.textures.push(texture.clone());
+                    if let Some(upload_sender) = state.upload_sender.as_ref() {
+                        upload_sender.request_upload(texture.clone());
+                    }
+                    return texture.clone();
+                }
+                log_warn!(
+                    "In-memory texture {:?} cannot be restored, it has no embedded content \
+                    and no memory resource provider knows it!",
+                    path
+                );
+            }
+            // Do not resolve procedural textures.
+            ResourceState::Ok(data) if data.is_procedural() => return texture.clone(),
+            ResourceState::Pending { .. } => return texture.clone(),
+            _ => (),
+        }
+        self.request_texture(path, None)
+    }
+
+    /// Same as [`Self::resolve_texture`], but for models.
+    pub(in crate) fn resolve_model(
+        &self,
+        model: &Model,
+        material_search_options: MaterialSearchOptions,
+    ) -> Model {
+        let path = model.state().path().to_path_buf();
+        let embedded_surfaces = match *model.state() {
+            ResourceState::Ok(ref data) if data.is_in_memory() => {
+                Some(data.embedded_surfaces().to_vec())
+            }
+            _ => None,
+        };
+        if let Some(surfaces) = embedded_surfaces {
+            let mut state = self.state();
+            if let Some(registered) = state.find_memory_model(&path) {
+                return registered;
+            }
+            if !surfaces.is_empty() {
+                return state.add_memory_model(path, surfaces);
+            }
+            log_warn!(
+                "In-memory model {:?} cannot be restored, it has no embedded content \
+                and no memory resource provider knows it!",
+                path
+            );
+        }
+        self.request_model(path, material_search_options)
+    }
+
     /// Tries to load new model resource from given path or get instance of existing, if any.
     /// This method is asynchronous, it immediately returns a model which can be shared across
     /// multiple places, the loading may fail, but it is internal state of the model. If you need
@@ -695,30 +1193,66 @@ impl ResourceManager {
         &self,
         path: P,
         material_search_options: MaterialSearchOptions,
+    ) -> Model {
+        self.request_model_with_options(path, material_search_options, None)
+    }
+
+    /// Same as [`Self::request_model`], but allows you to define custom import options. **IMPORTANT:**
+    /// Import options take effect **only** at first loading of a model, this means that if you try to
+    /// pass options when requesting loaded model, they won't take effect.
+    ///
+    /// If None is passed as import options, then default import options from resource manager will
+    /// be used.
+    pub fn request_model_with_options<P: AsRef<Path>>(
+        &self,
+        path: P,
+        material_search_options: MaterialSearchOptions,
+        import_options: Option<ModelImportOptions>,
     ) -> Model {
         let mut state = self.state();
 
-        if let Some(model) = state.models.find(path.as_ref()) {
-            return model.clone();
+        if let Some(model) = state.find_memory_model(path.as_ref()) {
+            return model;
         }
 
         let model = Model(Resource::new(ResourceState::new_pending(
             path.as_ref().to_owned(),
         )));
         state.models.push(model.clone());
+        state.loading_tracker.track(Box::new(model.0.clone()));
 
         let result = model.clone();
         let path = path.as_ref().to_owned();
         let resource_manager = self.clone();
+        let import_options = import_options.unwrap_or_else(|| state.models_import_options.clone());
+        let placeholder = state.model_placeholders;
 
         #[cfg(target_arch = "wasm32")]
         crate::core::wasm_bindgen_futures::spawn_local(async move {
-            load_model(model, path, resource_manager, material_search_options).await;
+            load_model(
+                model,
+                path,
+                resource_manager,
+                material_search_options,
+                import_options,
+                placeholder,
+                true,
+            )
+            .await;
         });
 
         #[cfg(not(target_arch = "wasm32"))]
         state.thread_pool.spawn_ok(async move {
-            load_model(model, path, resource_manager, material_search_options).await;
+            load_model(
+                model,
+                path,
+                resource_manager,
+                material_search_options,
+                import_options,
+                placeholder,
+                true,
+            )
+            .await;
         });
 
         result
@@ -746,17 +1280,19 @@ impl ResourceManager {
             path.as_ref().to_owned(),
         )));
         state.sound_buffers.push(resource.clone());
+        state.loading_tracker.track(Box::new(resource.0.clone()));
         let result = resource.clone();
         let path = path.as_ref().to_owned();
+        let source = state.resource_source();
 
         #[cfg(target_arch = "wasm32")]
         crate::core::wasm_bindgen_futures::spawn_local(async move {
-            load_sound_buffer(resource, path, stream).await;
+            load_sound_buffer(resource, path, stream, source).await;
         });
 
         #[cfg(not(target_arch = "wasm32"))]
         state.thread_pool.spawn_ok(async move {
-            load_sound_buffer(resource, path, stream).await;
+            load_sound_buffer(resource, path, stream, source).await;
         });
 
         result
@@ -780,18 +1316,20 @@ impl ResourceManager {
             path.as_ref().to_owned(),
         )));
         state.shaders.push(shader.clone());
+        state.loading_tracker.track(Box::new(shader.0.clone()));
 
         let result = shader.clone();
         let path = path.as_ref().to_owned();
+        let source = state.resource_source();
 
         #[cfg(target_arch = "wasm32")]
         crate::core::wasm_bindgen_futures::spawn_local(async move {
-            load_shader(shader, path).await;
+            load_shader(shader, path, source).await;
         });
 
         #[cfg(not(target_arch = "wasm32"))]
         state.thread_pool.spawn_ok(async move {
-            load_shader(shader, path).await;
+            load_shader(shader, path, source).await;
         });
 
         result
@@ -815,23 +1353,106 @@ impl ResourceManager {
             path.as_ref().to_owned(),
         )));
         state.curves.push(curve.clone());
+        state.loading_tracker.track(Box::new(curve.0.clone()));
 
         let result = curve.clone();
         let path = path.as_ref().to_owned();
+        let source = state.resource_source();
 
         #[cfg(target_arch = "wasm32")]
         crate::core::wasm_bindgen_futures::spawn_local(async move {
-            load_curve_resource(curve, path).await;
+            load_curve_resource(curve, path, source).await;
         });
 
         #[cfg(not(target_arch = "wasm32"))]
         state.thread_pool.spawn_ok(async move {
-            load_curve_resource(curve, path).await;
+            load_curve_resource(curve, path, source).await;
         });
 
         result
     }
 
+    /// Requests every resource from given list, type of a resource is defined by extension of
+    /// its path. The method does not wait until resources are loaded, use [`Self::progress`] to
+    /// check loading progress. Resources are kept alive by resource manager for
+    /// [`DEFAULT_RESOURCE_LIFETIME`] seconds, so you need to request them "for real" during this
+    /// time, otherwise they'll be unloaded. Models are requested with default material search
+    /// options and default import options, if you need custom options - request such models
+    /// manually, they will be tracked too.
+    ///
+    /// Supported extensions: textures - png, jpg, jpeg, tga, bmp, dds, gif, tif, tiff; models - fbx,
+    /// rgs; sound buffers - wav, ogg; shaders - shader.
+    pub fn prefetch<P: AsRef<Path>>(&self, paths: &[P]) {
+        let mut requested = false;
+        for path in paths {
+            // Put every prefetched resource in the same batch, even if some of them were loaded
+            // before the rest was requested.
+            self.state().loading_tracker.keep_batch = requested;
+
+            let path = path.as_ref();
+            let extension = path
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            match extension.as_ref() {
+                "png" | "jpg" | "jpeg" | "tga" | "bmp" | "dds" | "gif" | "tif" | "tiff" => {
+                    self.request_texture(path, None);
+                }
+                "fbx" | "rgs" => {
+                    self.request_model(path, MaterialSearchOptions::default());
+                }
+                "wav" | "ogg" => {
+                    self.request_sound_buffer(path, false);
+                }
+                "shader" => {
+                    self.request_shader(path);
+                }
+                _ => {
+                    log_warn!("Unable to prefetch {:?} - unknown resource type!", path);
+                    continue;
+                }
+            }
+
+            requested = true;
+        }
+
+        self.state().loading_tracker.keep_batch = false;
+    }
+
+    /// Returns loading progress of current batch of resources. Batch includes every resource
+    /// that was requested since last moment when every resource was loaded, including resources
+    /// that are requested by other resources (for example textures of a model). This method is
+    /// useful for loading screens, it can be called each frame.
+    ///
+    /// Keep in mind that total amount of resources may grow while a model is loading, because
+    /// textures of a model are requested during the loading. Use [`Self::prefetch`] to request
+    /// everything you need at once.
+    pub fn progress(&self) -> LoadingProgress {
+        self.state().loading_tracker.progress()
+    }
+
+    /// Blocks current thread until every resource in current batch is loaded or until the
+    /// timeout is reached. Returns true if everything is loaded. Useful for tools and tests.
+    ///
+    /// # Platform specific
+    ///
+    /// WebAssembly - resources are loaded on the main thread, so this method will always wait
+    /// until timeout.
+    pub fn wait_until_loaded(&self, timeout: Duration) -> bool {
+        let start = instant::Instant::now();
+        loop {
+            if self.progress().is_done() {
+                return true;
+            }
+            if start.elapsed() >= timeout {
+                return false;
+            }
+            #[cfg(not(target_arch = "wasm32"))]
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
     /// Reloads every loaded texture. This method is asynchronous, internally it uses thread pool
     /// to run reload on separate thread per texture.
     pub async fn reload_textures(&self) {
@@ -843,6 +1464,15 @@ impl ResourceManager {
 
             for resource in textures.iter().cloned() {
                 let path = resource.state().path().to_path_buf();
+                if matches!(*resource.state(), ResourceState::Ok(ref data) if data.is_in_memory()) {
+                    // There is no file for in-memory textures, only the provider can refresh them.
+                    if let Some(mut data) = state.provide_texture(&path) {
+                        data.set_path(&path);
+                        data.set_in_memory(true, state.embed_memory_resources);
+                        *resource.state() = ResourceState::Ok(data);
+                    }
+                    continue;
+                }
                 let compression = if let ResourceState::Ok(ref data) = *resource.state() {
                     match data.pixel_kind() {
                         TexturePixelKind::DXT1RGB => CompressionOptions::Speed,
@@ -854,16 +1484,36 @@ impl ResourceManager {
                 } else {
                     CompressionOptions::NoCompression
                 };
+                // Missing files were already reported, do not spam the log on every reload.
+                let report_error = !resource.is_placeholder();
                 *resource.state() = ResourceState::new_pending(path.clone());
+                let source = state.resource_source();
+                let placeholder = state.texture_placeholders;
 
                 #[cfg(target_arch = "wasm32")]
                 crate::core::wasm_bindgen_futures::spawn_local(async move {
-                    reload_texture(resource, path, compression).await;
+                    reload_texture(
+                        resource,
+                        path,
+                        compression,
+                        source,
+                        placeholder,
+                        report_error,
+                    )
+                    .await;
                 });
 
                 #[cfg(not(target_arch = "wasm32"))]
                 state.thread_pool.spawn_ok(async move {
-                    reload_texture(resource, path, compression).await;
+                    reload_texture(
+                        resource,
+                        path,
+                        compression,
+                        source,
+                        placeholder,
+                        report_error,
+                    )
+                    .await;
                 });
             }
 
@@ -885,17 +1535,52 @@ impl ResourceManager {
             for model in models.iter().cloned() {
                 let this = this.clone();
                 let path = model.state().path().to_path_buf();
-                let material_search_options = model.data_ref().material_search_options().clone();
+                if matches!(*model.state(), ResourceState::Ok(ref data) if data.is_in_memory()) {
+                    // There is no file for in-memory models, only the provider can refresh them.
+                    if let Some(surfaces) = state.provide_model(&path) {
+                        let embed = state.embed_memory_resources;
+                        *model.state() =
+                            ResourceState::Ok(ModelData::from_surfaces(path, surfaces, embed));
+                    }
+                    continue;
+                }
+                let (material_search_options, import_options) = match *model.state() {
+                    ResourceState::Ok(ref data) => (
+                        data.material_search_options().clone(),
+                        data.import_options().clone(),
+                    ),
+                    _ => (Default::default(), state.models_import_options.clone()),
+                };
+                let report_error = !model.is_placeholder();
+                let placeholder = state.model_placeholders;
                 *model.state() = ResourceState::new_pending(path.clone());
 
                 #[cfg(target_arch = "wasm32")]
                 crate::core::wasm_bindgen_futures::spawn_local(async move {
-                    load_model(model, path, this, material_search_options).await;
+                    load_model(
+                        model,
+                        path,
+                        this,
+                        material_search_options,
+                        import_options,
+                        placeholder,
+                        report_error,
+                    )
+                    .await;
                 });
 
                 #[cfg(not(target_arch = "wasm32"))]
                 state.thread_pool.spawn_ok(async move {
-                    load_model(model, path, this, material_search_options).await;
+                    load_model(
+                        model,
+                        path,
+                        this,
+                        material_search_options,
+                        import_options,
+                        placeholder,
+                        report_error,
+                    )
+                    .await;
                 })
             }
 
@@ -904,10 +1589,7 @@ impl ResourceManager {
 
         crate::core::futures::future::join_all(models).await;
 
-        Log::writeln(
-            MessageKind::Information,
-            "All model resources reloaded!".to_owned(),
-        );
+        log_info!("All model resources reloaded!");
     }
 
     /// Reloads every loaded shader. This method is asynchronous, internally it uses thread pool
@@ -921,15 +1603,16 @@ impl ResourceManager {
             for shader in shaders.iter().cloned() {
                 let path = shader.state().path().to_path_buf();
                 *shader.state() = ResourceState::new_pending(path.clone());
+                let source = state.resource_source();
 
                 #[cfg(target_arch = "wasm32")]
                 crate::core::wasm_bindgen_futures::spawn_local(async move {
-                    load_shader(shader, path).await;
+                    load_shader(shader, path, source).await;
                 });
 
                 #[cfg(not(target_arch = "wasm32"))]
                 state.thread_pool.spawn_ok(async move {
-                    load_shader(shader, path).await;
+                    load_shader(shader, path, source).await;
                 })
             }
 
@@ -938,10 +1621,7 @@ impl ResourceManager {
 
         crate::core::futures::future::join_all(shaders).await;
 
-        Log::writeln(
-            MessageKind::Information,
-            "All shader resources are reloaded!".to_owned(),
-        );
+        log_info!("All shader resources are reloaded!");
     }
 
     /// Reloads every loaded curve resource. This method is asynchronous, internally it uses thread pool
@@ -955,15 +1635,16 @@ impl ResourceManager {
             for curve in curves.iter().cloned() {
                 let path = curve.state().path().to_path_buf();
                 *curve.state() = ResourceState::new_pending(path.clone());
+                let source = state.resource_source();
 
                 #[cfg(target_arch = "wasm32")]
                 crate::core::wasm_bindgen_futures::spawn_local(async move {
-                    load_curve_resource(curve, path).await;
+                    load_curve_resource(curve, path, source).await;
                 });
 
                 #[cfg(not(target_arch = "wasm32"))]
                 state.thread_pool.spawn_ok(async move {
-                    load_curve_resource(curve, path).await;
+                    load_curve_resource(curve, path, source).await;
                 })
             }
 
@@ -972,10 +1653,7 @@ impl ResourceManager {
 
         crate::core::futures::future::join_all(curves).await;
 
-        Log::writeln(
-            MessageKind::Information,
-            "All curve resources are reloaded!".to_owned(),
-        );
+        log_info!("All curve resources are reloaded!");
     }
 
     /// Reloads every loaded sound buffer. This method is asynchronous, internally it uses thread pool
@@ -1001,15 +1679,16 @@ impl ResourceManager {
                 };
                 if path != PathBuf::default() {
                     *resource.state() = ResourceState::new_pending(path.clone());
+                    let source = state.resource_source();
 
                     #[cfg(target_arch = "wasm32")]
                     crate::core::wasm_bindgen_futures::spawn_local(async move {
-                        reload_sound_buffer(resource, path, stream).await;
+                        reload_sound_buffer(resource, path, stream, source).await;
                     });
 
                     #[cfg(not(target_arch = "wasm32"))]
                     state.thread_pool.spawn_ok(async move {
-                        reload_sound_buffer(resource, path, stream).await;
+                        reload_sound_buffer(resource, path, stream, source).await;
                     });
                 }
             }
@@ -1086,7 +1765,7 @@ impl MaterialSearchOptions {
 }
 
 impl ResourceManagerState {
-    pub(in crate::engine) fn new(upload_sender: TextureUploadSender) -> Self {
+    pub(in crate::engine) fn new(upload_sender: Option<TextureUploadSender>) -> Self {
         Self {
             textures: Default::default(),
             models: Default::default(),
@@ -1094,18 +1773,202 @@ impl ResourceManagerState {
             shaders: Default::default(),
             curves: Default::default(),
             textures_import_options: Default::default(),
+            models_import_options: Default::default(),
+            loading_tracker: Default::default(),
             #[cfg(not(target_arch = "wasm32"))]
             thread_pool: ThreadPool::new().unwrap(),
-            upload_sender: Some(upload_sender),
+            upload_sender,
+            resource_io: None,
+            filesystem_fallback: true,
+            texture_placeholders: true,
+            model_placeholders: true,
+            memory_resource_provider: None,
+            embed_memory_resources: true,
         }
     }
 
+    /// Sets a data source from which resources will be loaded, `None` - resources are loaded from
+    /// the file system. See [`crate::resource::io`] module docs for more info. Already loaded
+    /// resources are not affected, use `reload_*` methods of the resource manager if needed.
+    pub fn set_resource_io(&mut self, resource_io: Option<Arc<dyn ResourceIo>>) {
+        self.resource_io = resource_io;
+    }
+
+    /// Returns current data source of resources.
+    pub fn resource_io(&self) -> Option<&Arc<dyn ResourceIo>> {
+        self.resource_io.as_ref()
+    }
+
+    /// Sets whether resources that are missing in the data source (see [`Self::set_resource_io`])
+    /// should be loaded from the file system. Default is `true`.
+    pub fn set_filesystem_fallback(&mut self, enabled: bool) {
+        self.filesystem_fallback = enabled;
+    }
+
+    /// Returns `true` if resources that are missing in the data source are loaded from the file
+    /// system.
+    pub fn is_filesystem_fallback_enabled(&self) -> bool {
+        self.filesystem_fallback
+    }
+
+    /// Sets whether textures that failed to load should be replaced with a checkerboard
+    /// placeholder (see [`TextureData::placeholder`]). Otherwise such textures stay in
+    /// [`ResourceState::LoadError`] state and they are not rendered. Placeholders keep paths of
+    /// original textures, so [`ResourceManager::reload_textures`] replaces them with actual data
+    /// when files appear. Loading errors are written to the log in both cases. Default is `true`.
+    pub fn set_texture_placeholders_enabled(&mut self, enabled: bool) {
+        self.texture_placeholders = enabled;
+    }
+
+    /// Returns `true` if textures that failed to load are replaced with placeholders.
+    pub fn is_texture_placeholders_enabled(&self) -> bool {
+        self.texture_placeholders
+    }
+
+    /// Sets whether models that failed to load should be replaced with a unit cube with
+    /// checkerboard texture (its name is [`crate::resource::model::PLACEHOLDER_MODEL_NAME`]).
+    /// Otherwise such models stay in [`ResourceState::LoadError`] state and any attempt to
+    /// instantiate them will panic. Placeholders keep paths and import options of original models,
+    /// so [`ResourceManager::reload_models`] replaces them with actual data when files appear.
+    /// Loading errors are written to the log in both cases. Default is `true`.
+    pub fn set_model_placeholders_enabled(&mut self, enabled: bool) {
+        self.model_placeholders = enabled;
+    }
+
+    /// Returns `true` if models that failed to load are replaced with placeholders.
+    pub fn is_model_placeholders_enabled(&self) -> bool {
+        self.model_placeholders
+    }
+
+    /// Sets a provider that restores in-memory resources by their names, see
+    /// [`ResourceManager::register_texture_from_memory`] for more info.
+    pub fn set_memory_resource_provider(
+        &mut self,
+        provider: Option<Arc<dyn MemoryResourceProvider>>,
+    ) {
+        self.memory_resource_provider = provider;
+    }
+
+    /// Returns current provider of in-memory resources.
+    pub fn memory_resource_provider(&self) -> Option<&Arc<dyn MemoryResourceProvider>> {
+        self.memory_resource_provider.as_ref()
+    }
+
+    /// Sets whether content of in-memory resources should be saved together with scenes that
+    /// use them. Disable embedding if every in-memory resource can be restored by a
+    /// [`MemoryResourceProvider`], it makes saved scenes smaller. Affects resources registered
+    /// after the call only. Default is `true`.
+    pub fn set_memory_resources_embedding(&mut self, enabled: bool) {
+        self.embed_memory_resources = enabled;
+    }
+
+    /// Returns `true` if content of in-memory resources is saved together with scenes.
+    pub fn is_memory_resources_embedding_enabled(&self) -> bool {
+        self.embed_memory_resources
+    }
+
+    fn check_memory_resource_name<T>(
+        &self,
+        name: &Path,
+        registered: Option<T>,
+    ) -> Result<(), MemoryResourceError> {
+        if name.as_os_str().is_empty() {
+            Err(MemoryResourceError::EmptyName)
+        } else if registered.is_some() {
+            Err(MemoryResourceError::AlreadyRegistered(name.to_owned()))
+        } else if self.resource_source().exists_sync(name) {
+            Err(MemoryResourceError::PathCollision(name.to_owned()))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn provide_texture(&self, name: &Path) -> Option<TextureData> {
+        self.memory_resource_provider
+            .as_ref()
+            .and_then(|provider| provider.provide_texture(name))
+    }
+
+    fn provide_model(&self, name: &Path) -> Option<Vec<Arc<parking_lot::Mutex<SurfaceData>>>> {
+        self.memory_resource_provider
+            .as_ref()
+            .and_then(|provider| provider.provide_model(name))
+    }
+
+    fn add_memory_texture(&mut self, name: PathBuf, mut data: TextureData) -> Texture {
+        data.set_path(name);
+        data.set_in_memory(true, self.embed_memory_resources);
+        let texture = Texture(Resource::new(ResourceState::Ok(data)));
+        self.textures.push(texture.clone());
+        if let Some(upload_sender) = self.upload_sender.as_ref() {
+            upload_sender.request_upload(texture.clone());
+        }
+        texture
+    }
+
+    fn add_memory_model(
+        &mut self,
+        name: PathBuf,
+        surfaces: Vec<Arc<parking_lot::Mutex<SurfaceData>>>,
+    ) -> Model {
+        let model = Model(Resource::new(ResourceState::Ok(ModelData::from_surfaces(
+            name,
+            surfaces,
+            self.embed_memory_resources,
+        ))));
+        self.models.push(model.clone());
+        model
+    }
+
+    // Returns registered texture with given path or registers in-memory texture from the provider.
+    fn find_memory_texture(&mut self, path: &Path) -> Option<Texture> {
+        if let Some(texture) = self.textures.find(path) {
+            return Some(texture.clone());
+        }
+        self.provide_texture(path)
+            .map(|data| self.add_memory_texture(path.to_owned(), data))
+    }
+
+    // Returns registered model with given path or registers in-memory model from the provider.
+    fn find_memory_model(&mut self, path: &Path) -> Option<Model> {
+        if let Some(model) = self.models.find(path) {
+            return Some(model.clone());
+        }
+        self.provide_model(path)
+            .map(|surfaces| self.add_memory_model(path.to_owned(), surfaces))
+    }
+
+    pub(in crate) fn resource_source(&self) -> ResourceSource {
+        ResourceSource {
+            io: self.resource_io.clone(),
+            filesystem_fallback: self.filesystem_fallback,
+        }
+    }
+
+    // Runs a task on the thread pool of the resource manager, or on the main thread on WebAssembly.
+    pub(in crate) fn spawn_task<F>(&self, task: F)
+    where
+        F: std::future::Future<Output = ()> + Send + 'static,
+    {
+        #[cfg(target_arch = "wasm32")]
+        crate::core::wasm_bindgen_futures::spawn_local(task);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        self.thread_pool.spawn_ok(task);
+    }
+
     /// Sets new import options for textures. Previously loaded textures won't be affected by the
     /// new settings.
     pub fn set_textures_import_options(&mut self, options: TextureImportOptions) {
         self.textures_import_options = options;
     }
 
+    /// Sets new import options for models. Previously loaded models won't be affected by the
+    /// new settings.
+    pub fn set_models_import_options(&mut self, options: ModelImportOptions) {
+        self.models_import_options = options;
+    }
+
     /// Returns a reference to textures container.
     #[inline]
     pub fn textures(&self) -> &ResourceContainer<Texture> {
@@ -1186,6 +2049,12 @@ impl ResourceManagerState {
     }
 
     pub(in crate) fn update(&mut self, dt: f32) {
+        profile_scope!("ResourceManager");
+
+        // Tracker keeps pending resources alive, let go finished ones so they can be unloaded
+        // even if nobody asks for the progress.
+        self.loading_tracker.refresh();
+
         self.textures.update(dt);
         self.models.update(dt);
         self.sound_buffers.update(dt);
@@ -1213,3 +2082,213 @@ impl Visit for ResourceManagerState {
         visitor.leave_region()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{algebra::Matrix4, futures::executor::block_on, parking_lot},
+        engine::resource_manager::{
+            MaterialSearchOptions, MemoryResourceError, MemoryResourceProvider, ResourceManager,
+            DEFAULT_RESOURCE_LIFETIME,
+        },
+        resource::{
+            model::ModelData,
+            texture::{TextureData, TextureKind, TexturePixelKind},
+        },
+        scene::mesh::surface::SurfaceData,
+    };
+    use std::{
+        path::Path,
+        sync::{atomic::AtomicUsize, atomic::Ordering, Arc},
+        time::Duration,
+    };
+
+    #[test]
+    fn test_loading_progress() {
+        let resource_manager = ResourceManager::new(None);
+        assert!(resource_manager.progress().is_done());
+
+        resource_manager.prefetch(&["foo/missing.png", "foo/missing.ogg", "foo/missing.xyz"]);
+        assert_eq!(resource_manager.progress().total, 2);
+
+        assert!(resource_manager.wait_until_loaded(Duration::from_secs(10)));
+        let progress = resource_manager.progress();
+        assert_eq!(progress.loaded, 2);
+        assert_eq!(progress.failed, 2);
+        assert_eq!(progress.current, None);
+        assert_eq!(progress.fraction(), 1.0);
+
+        // Previous batch is finished, so new request starts new batch.
+        resource_manager.request_shader("foo/missing.shader");
+        assert_eq!(resource_manager.progress().total, 1);
+    }
+
+    #[test]
+    fn test_loaded_resources_are_unloaded() {
+        let resource_manager = ResourceManager::new(None);
+
+        let texture = block_on(resource_manager.request_texture("foo/missing.png", None)).unwrap();
+        drop(texture);
+
+        // Progress is never requested, so only update lets the tracker release the texture.
+        resource_manager
+            .state()
+            .update(DEFAULT_RESOURCE_LIFETIME + 1.0);
+        assert!(resource_manager.state().textures().is_empty());
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let resource_manager = ResourceManager::new(None);
+
+        let texture = block_on(resource_manager.request_texture("foo/missing.png", None)).unwrap();
+        assert!(texture.is_placeholder());
+        // Original path is kept, so the texture can be reloaded when the file appears.
+        assert_eq!(texture.state().path(), Path::new("foo/missing.png"));
+
+        let model = block_on(
+            resource_manager.request_model("foo/missing.fbx", MaterialSearchOptions::RecursiveUp),
+        )
+        .unwrap();
+        assert!(model.is_placeholder());
+        assert_eq!(model.state().path(), Path::new("foo/missing.fbx"));
+
+        resource_manager
+            .state()
+            .set_texture_placeholders_enabled(false);
+        assert!(block_on(resource_manager.request_texture("bar/missing.png", None)).is_err());
+    }
+
+    #[test]
+    fn test_texture_from_memory() {
+        let resource_manager = ResourceManager::new(None);
+
+        let texture = resource_manager
+            .register_texture_from_memory("minimap", 4, 4, TexturePixelKind::R8, vec![0; 16])
+            .unwrap();
+        assert!(texture.data_ref().is_in_memory());
+        assert_eq!(
+            resource_manager.request_texture("minimap", None).key(),
+            texture.key()
+        );
+
+        assert!(matches!(
+            resource_manager.register_texture_from_memory(
+                "minimap",
+                4,
+                4,
+                TexturePixelKind::R8,
+                vec![0; 16]
+            ),
+            Err(MemoryResourceError::AlreadyRegistered(_))
+        ));
+        assert!(matches!(
+            resource_manager.register_texture_from_memory(
+                "Cargo.toml",
+                4,
+                4,
+                TexturePixelKind::R8,
+                vec![0; 16]
+            ),
+            Err(MemoryResourceError::PathCollision(_))
+        ));
+        assert!(matches!(
+            resource_manager.register_texture_from_memory(
+                "wrong",
+                4,
+                4,
+                TexturePixelKind::R8,
+                vec![0; 15]
+            ),
+            Err(MemoryResourceError::InvalidData)
+        ));
+
+        let hash = texture.data_ref().data_hash();
+        resource_manager
+            .update_texture_region("minimap", 1, 2, 2, 1, &[7, 8])
+            .unwrap();
+        assert_ne!(texture.data_ref().data_hash(), hash);
+        assert_eq!(&texture.data_ref().data()[8..12], &[0, 7, 8, 0]);
+        assert!(resource_manager
+            .update_texture_region("minimap", 3, 3, 2, 1, &[7, 8])
+            .is_err());
+
+        // Purged like any other resource.
+        drop(texture);
+        resource_manager.state().destroy_unused_resources();
+        assert!(resource_manager.state().textures().is_empty());
+    }
+
+    #[derive(Default)]
+    struct Provider {
+        calls: AtomicUsize,
+    }
+
+    impl MemoryResourceProvider for Provider {
+        fn provide_texture(&self, name: &Path) -> Option<TextureData> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if name == Path::new("noise") {
+                TextureData::from_bytes(
+                    TextureKind::Rectangle {
+                        width: 2,
+                        height: 2,
+                    },
+                    TexturePixelKind::R8,
+                    vec![1, 2, 3, 4],
+                    false,
+                )
+            } else {
+                None
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_resource_provider() {
+        let resource_manager = ResourceManager::new(None);
+        let provider = Arc::new(Provider::default());
+        resource_manager
+            .state()
+            .set_memory_resource_provider(Some(provider.clone()));
+
+        let texture = resource_manager.request_texture("noise", None);
+        assert!(texture.data_ref().is_in_memory());
+        assert_eq!(texture.data_ref().data(), &[1, 2, 3, 4]);
+        // Registered texture is used by subsequent requests.
+        resource_manager.request_texture("noise", None);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_model_from_memory() {
+        let resource_manager = ResourceManager::new(None);
+        let surface = Arc::new(parking_lot::Mutex::new(SurfaceData::make_cube(
+            Matrix4::identity(),
+        )));
+
+        let model = resource_manager
+            .register_model_from_memory("generated/rock", vec![surface.clone()])
+            .unwrap();
+        assert_eq!(
+            resource_manager
+                .request_model("generated/rock", MaterialSearchOptions::RecursiveUp)
+                .key(),
+            model.key()
+        );
+
+        // A model that was read from a saved scene is restored from embedded data.
+        let shallow = crate::resource::model::Model(crate::asset::Resource::new(
+            crate::asset::ResourceState::Ok(ModelData::from_surfaces(
+                "generated/rock".into(),
+                vec![surface],
+                true,
+            )),
+        ));
+        drop(model);
+        resource_manager.state().destroy_unused_resources();
+        assert!(resource_manager.state().models().is_empty());
+        let restored = resource_manager.resolve_model(&shallow, MaterialSearchOptions::RecursiveUp);
+        assert!(restored.data_ref().is_in_memory());
+        assert_eq!(resource_manager.state().models().len(), 1);
+    }
+}
@@ -0,0 +1,114 @@
+//! Asynchronous scene loading, it allows you to load next level while current one is still
+//! playing, without loading screens and manual thread management.
+//!
+//! # Usage
+//!
+//! Start loading with [`crate::engine::Engine::load_scene_async`] (or
+//! [`crate::engine::Engine::build_scene_async`] if a scene is made in code), then poll returned
+//! [`PendingScene`] each frame. When the scene is ready, replace current scene with it using
+//! [`crate::scene::SceneContainer::swap_to`], which can also copy a set of nodes (for example
+//! a player) to the new scene.
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::pool::Handle,
+//!     engine::{scene_loader::PendingScene, Engine},
+//!     scene::{node::Node, Scene},
+//! };
+//!
+//! struct Level {
+//!     scene: Handle<Scene>,
+//!     player: Handle<Node>,
+//!     next: Option<PendingScene>,
+//! }
+//!
+//! impl Level {
+//!     fn on_door_reached(&mut self, engine: &Engine) {
+//!         self.next = Some(engine.load_scene_async("data/levels/level2.rgs"));
+//!     }
+//!
+//!     fn update(&mut self, engine: &mut Engine) {
+//!         if let Some(Ok(scene)) = self.next.as_ref().and_then(|next| next.try_take()) {
+//!             let (scene, copied) = engine.scenes.swap_to(self.scene, scene, &[self.player]);
+//!             self.scene = scene;
+//!             self.player = copied.nodes[&self.player];
+//!             self.next = None;
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! # Progress
+//!
+//! Every resource of a scene is loaded by the resource manager, so loading progress is the same as
+//! [`ResourceManager::progress`], see [`PendingScene::progress`].
+
+use crate::{
+    core::visitor::VisitError,
+    engine::resource_manager::{LoadingProgress, MaterialSearchOptions, ResourceManager},
+    scene::Scene,
+};
+use std::{
+    future::Future,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+type SceneLoadResult = Result<Scene, VisitError>;
+
+/// A scene that is being loaded in background, see module docs.
+pub struct PendingScene {
+    result: Arc<Mutex<Option<SceneLoadResult>>>,
+    resource_manager: ResourceManager,
+}
+
+impl PendingScene {
+    pub(in crate) fn new<F>(resource_manager: ResourceManager, future: F) -> Self
+    where
+        F: Future<Output = SceneLoadResult> + Send + 'static,
+    {
+        let result = Arc::new(Mutex::new(None));
+
+        let task_result = result.clone();
+        resource_manager.state().spawn_task(async move {
+            let scene = future.await;
+            *task_result.lock().unwrap() = Some(scene);
+        });
+
+        Self {
+            result,
+            resource_manager,
+        }
+    }
+
+    pub(in crate) fn from_file<P: AsRef<Path>>(resource_manager: ResourceManager, path: P) -> Self {
+        let path = path.as_ref().to_owned();
+        let loader_resource_manager = resource_manager.clone();
+        Self::new(resource_manager, async move {
+            Scene::from_file(
+                path,
+                loader_resource_manager,
+                &MaterialSearchOptions::UsePathDirectly,
+            )
+            .await
+        })
+    }
+
+    /// Returns true if the scene is loaded (or failed to load) and can be taken by
+    /// [`Self::try_take`].
+    pub fn is_ready(&self) -> bool {
+        self.result.lock().unwrap().is_some()
+    }
+
+    /// Takes loaded scene or an error that happened during the loading. Returns `None` if the scene
+    /// is still loading or if it was already taken.
+    pub fn try_take(&self) -> Option<SceneLoadResult> {
+        self.result.lock().unwrap().take()
+    }
+
+    /// Returns loading progress of resources, it includes every resource that is loading at the
+    /// moment, not only the resources of the scene. See [`ResourceManager::progress`] for more info.
+    pub fn progress(&self) -> LoadingProgress {
+        self.resource_manager.progress()
+    }
+}
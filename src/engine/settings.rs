@@ -0,0 +1,448 @@
+//! Persistent settings of the engine - graphics quality, resolution, sound volume, etc.
+//!
+//! Settings are stored using [`Visitor`], so every field is optional - if a field is missing in a
+//! file (for example, file was saved by older version of your game) or has wrong type, it keeps
+//! its default value, instead of discarding whole file. If a file cannot be read at all (it is
+//! missing or corrupted), [`Settings::load_or_detect`] falls back to defaults that are picked by
+//! [`Settings::detect_defaults`].
+//!
+//! # Game-specific settings
+//!
+//! Settings do not know anything about your game (key bindings, difficulty, etc.), if you need to
+//! store such data, wrap `Settings` in your own structure and implement `Visit` for it.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::futures::executor::block_on,
+//!     engine::{settings::Settings, Engine},
+//! };
+//!
+//! fn init(engine: &mut Engine) -> Settings {
+//!     let path = Settings::default_path("MyGame").unwrap();
+//!     let mut settings = block_on(Settings::load_or_detect(&path, engine));
+//!     settings.apply(engine);
+//!     settings.save(&path).unwrap();
+//!     settings
+//! }
+//! ```
+
+#![warn(missing_docs)]
+
+use crate::{
+    core::visitor::prelude::*,
+    dpi::PhysicalSize,
+    engine::Engine,
+    renderer::{GpuCapabilities, QualitySettings},
+    utils::log::{log_error, log_info, log_warn},
+    window::Fullscreen,
+};
+use std::path::{Path, PathBuf};
+
+/// An error that may occur during save or load of settings.
+#[derive(Debug, thiserror::Error)]
+pub enum SettingsError {
+    /// An i/o error has occurred.
+    #[error(transparent)]
+    Io(std::io::Error),
+    /// Settings file is corrupted or has unsupported format.
+    #[error("Visit error: {0}")]
+    Visit(VisitError),
+}
+
+impl From<std::io::Error> for SettingsError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<VisitError> for SettingsError {
+    fn from(e: VisitError) -> Self {
+        Self::Visit(e)
+    }
+}
+
+/// Size of the main window in pixels.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl Default for Resolution {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+        }
+    }
+}
+
+impl Visit for Resolution {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let _ = self.width.visit("Width", visitor);
+        let _ = self.height.visit("Height", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+/// Graphics settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphicsSettings {
+    /// Quality settings of the renderer.
+    pub quality: QualitySettings,
+    /// Size of the main window, `None` - keep size of the window as is.
+    pub resolution: Option<Resolution>,
+    /// Whether the main window should be in fullscreen (borderless) mode or not.
+    pub fullscreen: bool,
+    /// Vertical synchronization. It can be set only when the engine is created, so
+    /// [`Settings::apply`] ignores it - pass it to [`Engine::new`] instead.
+    pub vsync: bool,
+}
+
+impl Default for GraphicsSettings {
+    fn default() -> Self {
+        Self {
+            quality: Default::default(),
+            resolution: None,
+            fullscreen: false,
+            vsync: true,
+        }
+    }
+}
+
+impl Visit for GraphicsSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let _ = self.quality.visit("Quality", visitor);
+        let _ = self.resolution.visit("Resolution", visitor);
+        let _ = self.fullscreen.visit("Fullscreen", visitor);
+        let _ = self.vsync.visit("VSync", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+/// Sound settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundSettings {
+    /// Global sound volume in `[0; 1]` range.
+    pub master_volume: f32,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self { master_volume: 1.0 }
+    }
+}
+
+impl Visit for SoundSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let _ = self.master_volume.visit("MasterVolume", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+/// See module docs.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    /// Graphics settings.
+    pub graphics: GraphicsSettings,
+    /// Sound settings.
+    pub sound: SoundSettings,
+}
+
+impl Visit for Settings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let _ = self.graphics.visit("Graphics", visitor);
+        let _ = self.sound.visit("Sound", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+impl Settings {
+    const FILE_NAME: &'static str = "settings.bin";
+
+    /// Returns path to the settings file in a platform-specific config directory for an
+    /// application with given name. For example `~/.config/<app_name>/settings.bin` on Linux,
+    /// `%APPDATA%\<app_name>\config\settings.bin` on Windows. Returns `None` if there is no
+    /// such directory on current platform.
+    pub fn default_path(app_name: &str) -> Option<PathBuf> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            directories::ProjectDirs::from("", "", app_name)
+                .map(|dirs| dirs.config_dir().join(Self::FILE_NAME))
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        {
+            let _ = app_name;
+            None
+        }
+    }
+
+    /// Picks settings that suits given GPU. Software rasterizers and old GPUs will get low
+    /// quality, integrated GPUs - medium, everything else - high. Ultra quality is never picked
+    /// automatically, because it is too heavy even for modern GPUs.
+    pub fn from_capabilities(capabilities: &GpuCapabilities) -> Self {
+        let quality = if capabilities.is_software() || capabilities.max_texture_size < 4096 {
+            QualitySettings::low()
+        } else if capabilities.is_integrated() || capabilities.max_texture_size < 16384 {
+            QualitySettings::medium()
+        } else {
+            QualitySettings::high()
+        };
+
+        Self {
+            graphics: GraphicsSettings {
+                quality,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Picks settings that suits current GPU, see [`Self::from_capabilities`]. Returns default
+    /// settings in headless mode.
+    pub fn detect_defaults(engine: &Engine) -> Self {
+        match engine.try_renderer() {
+            Some(renderer) => {
+                let capabilities = renderer.gpu_capabilities();
+
+                log_info!(
+                    "Picking default settings for {} ({}), max texture size is {}.",
+                    capabilities.renderer,
+                    capabilities.vendor,
+                    capabilities.max_texture_size
+                );
+
+                Self::from_capabilities(&capabilities)
+            }
+            None => Default::default(),
+        }
+    }
+
+    /// Resets every invalid value (which could come from a malformed file) to its default value.
+    fn validate(&mut self, defaults: &Settings) {
+        let quality = &mut self.graphics.quality;
+        let default_quality = &defaults.graphics.quality;
+        if quality.point_shadow_map_size == 0 {
+            quality.point_shadow_map_size = default_quality.point_shadow_map_size;
+        }
+        if quality.spot_shadow_map_size == 0 {
+            quality.spot_shadow_map_size = default_quality.spot_shadow_map_size;
+        }
+        if quality.csm_settings.size == 0 {
+            quality.csm_settings.size = default_quality.csm_settings.size;
+        }
+        if !quality.point_shadows_distance.is_finite() {
+            quality.point_shadows_distance = default_quality.point_shadows_distance;
+        }
+        if !quality.spot_shadows_distance.is_finite() {
+            quality.spot_shadows_distance = default_quality.spot_shadows_distance;
+        }
+        if !quality.ssao_radius.is_finite() {
+            quality.ssao_radius = default_quality.ssao_radius;
+        }
+
+        if let Some(resolution) = self.graphics.resolution {
+            if resolution.width == 0 || resolution.height == 0 {
+                self.graphics.resolution = defaults.graphics.resolution;
+            }
+        }
+
+        if self.sound.master_volume.is_finite() {
+            self.sound.master_volume = self.sound.master_volume.min(1.0).max(0.0);
+        } else {
+            self.sound.master_volume = defaults.sound.master_volume;
+        }
+    }
+
+    /// Loads settings from given file, every missing or invalid value is taken from `defaults`.
+    /// Fails only if the file cannot be read at all.
+    pub async fn load<P: AsRef<Path>>(path: P, defaults: Settings) -> Result<Self, SettingsError> {
+        let mut visitor = Visitor::load_binary(path).await?;
+        let mut settings = defaults.clone();
+        let _ = settings.visit("Settings", &mut visitor);
+        settings.validate(&defaults);
+        Ok(settings)
+    }
+
+    /// Loads settings from given file, or detects defaults if the file is missing or corrupted.
+    /// Missing values in the file are taken from detected defaults too.
+    pub async fn load_or_detect<P: AsRef<Path>>(path: P, engine: &Engine) -> Self {
+        let path = path.as_ref();
+        let defaults = Self::detect_defaults(engine);
+
+        if !path.exists() {
+            return defaults;
+        }
+
+        match Self::load(path, defaults.clone()).await {
+            Ok(settings) => settings,
+            Err(e) => {
+                log_warn!(
+                    "Unable to load settings from {}, fallback to defaults. Reason: {}",
+                    path.display(),
+                    e
+                );
+                defaults
+            }
+        }
+    }
+
+    /// Saves settings to given file, creates all missing directories.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<(), SettingsError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut visitor = Visitor::new();
+        self.visit("Settings", &mut visitor)?;
+        visitor.save_binary(path)?;
+
+        Ok(())
+    }
+
+    /// Applies settings to the engine - sets quality settings of the renderer, resizes the main
+    /// window, sets global sound volume. Graphics settings are ignored in headless mode.
+    pub fn apply(&self, engine: &mut Engine) {
+        if let Some(renderer) = engine.try_renderer_mut() {
+            if let Err(e) = renderer.set_quality_settings(&self.graphics.quality) {
+                log_error!("Unable to apply quality settings. Reason: {:?}", e);
+            }
+        }
+
+        if let Some(window) = engine.try_get_window() {
+            if let Some(resolution) = self.graphics.resolution {
+                window.set_inner_size(PhysicalSize::new(resolution.width, resolution.height));
+            }
+
+            window.set_fullscreen(if self.graphics.fullscreen {
+                Some(Fullscreen::Borderless(None))
+            } else {
+                None
+            });
+        }
+
+        engine
+            .sound_engine
+            .lock()
+            .unwrap()
+            .set_master_gain(self.sound.master_volume);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            futures::executor::block_on,
+            visitor::{Visit, Visitor},
+        },
+        engine::settings::{Resolution, Settings},
+        renderer::{GpuCapabilities, QualitySettings, ShadowFilter},
+    };
+
+    fn save_and_load(mut visitor: Visitor, file_name: &str, defaults: Settings) -> Settings {
+        let path = std::env::temp_dir().join(file_name);
+        visitor.save_binary(&path).unwrap();
+        let settings = block_on(Settings::load(&path, defaults)).unwrap();
+        std::fs::remove_file(path).unwrap();
+        settings
+    }
+
+    #[test]
+    fn test_settings_round_trip() {
+        let mut settings = Settings::default();
+        settings.graphics.quality = QualitySettings::medium();
+        settings.graphics.quality.point_shadow_filter = ShadowFilter::Pcf2x2;
+        settings.graphics.resolution = Some(Resolution {
+            width: 1920,
+            height: 1080,
+        });
+        settings.graphics.fullscreen = true;
+        settings.sound.master_volume = 0.25;
+
+        let mut visitor = Visitor::new();
+        settings.visit("Settings", &mut visitor).unwrap();
+
+        let loaded = save_and_load(visitor, "rg3d_settings_round_trip.bin", Default::default());
+        assert_eq!(loaded, settings);
+    }
+
+    #[test]
+    fn test_settings_per_field_fallback() {
+        // Write only a part of settings, like it was done by an older version.
+        let mut visitor = Visitor::new();
+        visitor.enter_region("Settings").unwrap();
+        visitor.enter_region("Sound").unwrap();
+        let mut volume = 7.0f32;
+        volume.visit("MasterVolume", &mut visitor).unwrap();
+        visitor.leave_region().unwrap();
+        visitor.enter_region("Graphics").unwrap();
+        // Wrong type of a field.
+        let mut fullscreen = 123.0f32;
+        fullscreen.visit("Fullscreen", &mut visitor).unwrap();
+        visitor.leave_region().unwrap();
+        visitor.leave_region().unwrap();
+
+        let mut defaults = Settings::default();
+        defaults.graphics.quality = QualitySettings::low();
+        defaults.graphics.fullscreen = true;
+
+        let loaded = save_and_load(visitor, "rg3d_settings_fallback.bin", defaults.clone());
+        // Out-of-range value is clamped.
+        assert_eq!(loaded.sound.master_volume, 1.0);
+        assert_eq!(loaded.graphics, defaults.graphics);
+    }
+
+    #[test]
+    fn test_detect_defaults() {
+        let software = GpuCapabilities {
+            renderer: "llvmpipe (LLVM 12.0.0, 256 bits)".to_owned(),
+            max_texture_size: 16384,
+            ..Default::default()
+        };
+        assert_eq!(
+            Settings::from_capabilities(&software).graphics.quality,
+            QualitySettings::low()
+        );
+
+        let integrated = GpuCapabilities {
+            vendor: "Intel".to_owned(),
+            renderer: "Intel(R) UHD Graphics 620".to_owned(),
+            max_texture_size: 16384,
+            ..Default::default()
+        };
+        assert_eq!(
+            Settings::from_capabilities(&integrated).graphics.quality,
+            QualitySettings::medium()
+        );
+
+        let discrete = GpuCapabilities {
+            vendor: "NVIDIA Corporation".to_owned(),
+            renderer: "GeForce GTX 1060/PCIe/SSE2".to_owned(),
+            max_texture_size: 32768,
+            ..Default::default()
+        };
+        assert_eq!(
+            Settings::from_capabilities(&discrete).graphics.quality,
+            QualitySettings::high()
+        );
+    }
+}
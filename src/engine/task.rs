@@ -0,0 +1,494 @@
+//! Task system for CPU-heavy background work (navmesh building, lightmap baking, scene
+//! generation, etc.) without manual thread management.
+//!
+//! # Usage
+//!
+//! Every engine has a [`TaskManager`] in [`crate::engine::Engine::tasks`], it owns a shared thread
+//! pool. A job is a closure that runs on one of the threads of the pool and receives a
+//! [`TaskContext`], which can be used to check cancellation flag and to report progress. There
+//! are two ways to get result of a job:
+//!
+//! - [`TaskManager::spawn`] returns [`TaskHandle`], poll it each frame by
+//! [`TaskHandle::try_take`] or block by [`TaskHandle::wait`].
+//! - [`TaskManager::spawn_with_callback`] registers a callback that is called on the main thread
+//! by [`crate::engine::Engine::update`] once the job has finished, the callback gets
+//! [`EngineContext`] so it can modify scenes, user interface, etc.
+//!
+//! ```no_run
+//! use rg3d::engine::Engine;
+//!
+//! fn count_primes(engine: &mut Engine) {
+//!     engine.tasks.spawn_with_callback(
+//!         |ctx| {
+//!             let mut count = 0;
+//!             for n in 2..1_000_000u32 {
+//!                 // Jobs must check cancellation flag by themselves.
+//!                 if ctx.is_cancelled() {
+//!                     break;
+//!                 }
+//!                 if (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0) {
+//!                     count += 1;
+//!                 }
+//!             }
+//!             count
+//!         },
+//!         |result, _context| match result {
+//!             Ok(count) => println!("There are {} primes", count),
+//!             Err(e) => println!("Unable to count primes: {}", e),
+//!         },
+//!     );
+//! }
+//! ```
+//!
+//! # Cancellation and panics
+//!
+//! Cancellation is cooperative: [`TaskHandle::cancel`] only raises a flag. A job that was not
+//! started yet is skipped, a running job should check [`TaskContext::is_cancelled`] from time to
+//! time and return early. Result of a cancelled task is always [`TaskError::Cancelled`].
+//!
+//! Panics inside jobs are caught, they do not kill threads of the pool, the task finishes with
+//! [`TaskError::Panicked`] instead.
+//!
+//! # Platforms without threads
+//!
+//! If the thread pool cannot be created (for example on WebAssembly), jobs run immediately in
+//! [`TaskManager::spawn`] and [`TaskManager::parallel_for`] runs sequentially. Callbacks are still
+//! called from [`crate::engine::Engine::update`].
+
+use crate::{
+    engine::plugin::EngineContext,
+    utils::{
+        lightmap::CancellationToken,
+        log::{log_error, log_warn},
+    },
+};
+use fxhash::FxHashMap;
+use rayon::prelude::*;
+use std::{
+    any::Any,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{self, AtomicU32},
+        mpsc::{self, Receiver, Sender},
+        Arc, Condvar, Mutex,
+    },
+};
+
+/// An error that can happen during execution of a task.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TaskError {
+    /// Task was cancelled before it has finished.
+    #[error("Task was cancelled.")]
+    Cancelled,
+    /// Job of a task has panicked, the panic message is stored inside.
+    #[error("Task panicked: {0}")]
+    Panicked(String),
+}
+
+enum TaskSlot<T> {
+    Pending,
+    Finished(Result<T, TaskError>),
+    Taken,
+}
+
+// Non-generic part of a task state that is accessible from a job.
+struct TaskControl {
+    cancellation_token: CancellationToken,
+    // Bits of f32.
+    progress: AtomicU32,
+}
+
+struct TaskState<T> {
+    control: TaskControl,
+    slot: Mutex<TaskSlot<T>>,
+    finished: Condvar,
+}
+
+impl<T> TaskState<T> {
+    fn finish(&self, result: Result<T, TaskError>) {
+        *self.slot.lock().unwrap() = TaskSlot::Finished(result);
+        self.finished.notify_all();
+    }
+}
+
+/// Gives a job access to the state of its task.
+pub struct TaskContext<'a> {
+    control: &'a TaskControl,
+}
+
+impl<'a> TaskContext<'a> {
+    /// Returns true if the task was cancelled, the job should return as soon as possible in this
+    /// case, its result will be discarded anyway.
+    pub fn is_cancelled(&self) -> bool {
+        self.control.cancellation_token.is_cancelled()
+    }
+
+    /// Returns cancellation token of the task. It can be passed to engine functions that support
+    /// cancellation, for example [`crate::utils::lightmap::Lightmap::new`].
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.control.cancellation_token.clone()
+    }
+
+    /// Sets progress of the task, it will be clamped to `[0; 1]` range. See
+    /// [`TaskHandle::progress`].
+    pub fn set_progress(&self, progress: f32) {
+        self.control.progress.store(
+            progress.clamp(0.0, 1.0).to_bits(),
+            atomic::Ordering::Relaxed,
+        );
+    }
+}
+
+/// A handle of a task that was spawned by [`TaskManager::spawn`]. Handles are cheap to clone, all
+/// clones refer to the same task. Dropping every handle does **not** cancel the task.
+pub struct TaskHandle<T> {
+    state: Arc<TaskState<T>>,
+}
+
+impl<T> Clone for TaskHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<T> TaskHandle<T> {
+    /// Raises cancellation flag of the task, see module docs for more info.
+    pub fn cancel(&self) {
+        self.state.control.cancellation_token.cancel();
+    }
+
+    /// Returns true if the task was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.control.cancellation_token.is_cancelled()
+    }
+
+    /// Returns true if the task has finished (successfully or not).
+    pub fn is_finished(&self) -> bool {
+        !matches!(*self.state.slot.lock().unwrap(), TaskSlot::Pending)
+    }
+
+    /// Returns last progress reported by the job using [`TaskContext::set_progress`], progress is
+    /// in `[0; 1]` range.
+    pub fn progress(&self) -> f32 {
+        f32::from_bits(self.state.control.progress.load(atomic::Ordering::Relaxed))
+    }
+
+    /// Takes result of the task. Returns `None` if the task is still running or if the result was
+    /// already taken.
+    pub fn try_take(&self) -> Option<Result<T, TaskError>> {
+        let mut slot = self.state.slot.lock().unwrap();
+        match std::mem::replace(&mut *slot, TaskSlot::Taken) {
+            TaskSlot::Finished(result) => Some(result),
+            other => {
+                *slot = other;
+                None
+            }
+        }
+    }
+
+    /// Blocks current thread until the task is finished and returns its result. Returns `None`
+    /// if the result was already taken.
+    pub fn wait(&self) -> Option<Result<T, TaskError>> {
+        let mut slot = self.state.slot.lock().unwrap();
+        while let TaskSlot::Pending = *slot {
+            slot = self.state.finished.wait(slot).unwrap();
+        }
+        match std::mem::replace(&mut *slot, TaskSlot::Taken) {
+            TaskSlot::Finished(result) => Some(result),
+            _ => None,
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic payload".to_owned()
+    }
+}
+
+fn run_job<T, F>(job: F, state: &TaskState<T>)
+where
+    F: FnOnce(&TaskContext) -> T,
+{
+    let cancellation_token = &state.control.cancellation_token;
+
+    let result = if cancellation_token.is_cancelled() {
+        Err(TaskError::Cancelled)
+    } else {
+        let ctx = TaskContext {
+            control: &state.control,
+        };
+        match panic::catch_unwind(AssertUnwindSafe(|| job(&ctx))) {
+            Ok(_) if cancellation_token.is_cancelled() => Err(TaskError::Cancelled),
+            Ok(value) => Ok(value),
+            Err(payload) => {
+                let message = panic_message(&*payload);
+                log_error!("A job of a task has panicked: {}", message);
+                Err(TaskError::Panicked(message))
+            }
+        }
+    };
+
+    state.finish(result);
+}
+
+type Callback = Box<dyn FnOnce(&mut EngineContext)>;
+
+/// Owns the shared thread pool, see module docs.
+pub struct TaskManager {
+    pool: Option<rayon::ThreadPool>,
+    callbacks: FxHashMap<u64, Callback>,
+    completion_sender: Sender<u64>,
+    completion_receiver: Receiver<u64>,
+    next_id: u64,
+}
+
+impl Default for TaskManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskManager {
+    /// Creates new task manager with a thread pool that has a thread per logical CPU core.
+    pub fn new() -> Self {
+        let pool = match rayon::ThreadPoolBuilder::new()
+            .thread_name(|i| format!("rg3d-task-{}", i))
+            .build()
+        {
+            Ok(pool) => Some(pool),
+            Err(e) => {
+                log_warn!(
+                    "Unable to create thread pool for tasks, jobs will run on the main thread. \
+                        Reason: {:?}",
+                    e
+                );
+                None
+            }
+        };
+
+        let (completion_sender, completion_receiver) = mpsc::channel();
+
+        Self {
+            pool,
+            callbacks: Default::default(),
+            completion_sender,
+            completion_receiver,
+            next_id: 0,
+        }
+    }
+
+    /// Returns amount of threads in the pool, zero means that jobs run on the main thread.
+    pub fn thread_count(&self) -> usize {
+        self.pool
+            .as_ref()
+            .map_or(0, |pool| pool.current_num_threads())
+    }
+
+    fn spawn_internal<T, F>(&self, job: F, completion: Option<(Sender<u64>, u64)>) -> TaskHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&TaskContext) -> T + Send + 'static,
+    {
+        let state = Arc::new(TaskState {
+            control: TaskControl {
+                cancellation_token: CancellationToken::new(),
+                progress: AtomicU32::new(0.0f32.to_bits()),
+            },
+            slot: Mutex::new(TaskSlot::Pending),
+            finished: Condvar::new(),
+        });
+
+        let job_state = state.clone();
+        let execute = move || {
+            run_job(job, &job_state);
+            if let Some((sender, id)) = completion {
+                // Receiver could be already dropped together with the engine.
+                let _ = sender.send(id);
+            }
+        };
+
+        match self.pool.as_ref() {
+            Some(pool) => pool.spawn(execute),
+            None => execute(),
+        }
+
+        TaskHandle { state }
+    }
+
+    /// Runs the job on the thread pool and returns a handle that can be used to get the result
+    /// of the job or to cancel it.
+    pub fn spawn<T, F>(&self, job: F) -> TaskHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&TaskContext) -> T + Send + 'static,
+    {
+        self.spawn_internal(job, None)
+    }
+
+    /// Runs the job on the thread pool, `callback` will be called on the main thread with the
+    /// result of the job by [`crate::engine::Engine::update`] once the job has finished. Returned
+    /// handle can be used to cancel the task or to check its progress, the result is always passed
+    /// to the callback and cannot be taken from the handle.
+    pub fn spawn_with_callback<T, F, C>(&mut self, job: F, callback: C) -> TaskHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&TaskContext) -> T + Send + 'static,
+        C: FnOnce(Result<T, TaskError>, &mut EngineContext) + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let handle = self.spawn_internal(job, Some((self.completion_sender.clone(), id)));
+
+        let callback_handle = handle.clone();
+        self.callbacks.insert(
+            id,
+            Box::new(move |context| {
+                // Result is always available here, the job sends its id after it has finished.
+                if let Some(result) = callback_handle.try_take() {
+                    callback(result, context)
+                }
+            }),
+        );
+
+        handle
+    }
+
+    /// Calls `func` for every item of the slice in parallel using the thread pool and blocks
+    /// until every item is processed. The first argument of `func` is the index of an item.
+    pub fn parallel_for<T, F>(&self, items: &mut [T], func: F)
+    where
+        T: Send,
+        F: Fn(usize, &mut T) + Send + Sync,
+    {
+        match self.pool.as_ref() {
+            Some(pool) => pool.install(|| {
+                items
+                    .par_iter_mut()
+                    .enumerate()
+                    .for_each(|(i, item)| func(i, item))
+            }),
+            None => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    func(i, item);
+                }
+            }
+        }
+    }
+
+    /// Returns callbacks of tasks that were finished since last call, in order of completion.
+    pub(in crate) fn take_completed(&mut self) -> Vec<Callback> {
+        let mut completed = Vec::new();
+        while let Ok(id) = self.completion_receiver.try_recv() {
+            if let Some(callback) = self.callbacks.remove(&id) {
+                completed.push(callback);
+            }
+        }
+        completed
+    }
+
+    /// Returns amount of tasks with callbacks that were not called yet.
+    pub fn pending_callbacks(&self) -> usize {
+        self.callbacks.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::engine::task::{TaskError, TaskManager};
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_spawn_and_wait() {
+        let tasks = TaskManager::new();
+
+        let handle = tasks.spawn(|ctx| {
+            ctx.set_progress(2.0);
+            (0..100u32).sum::<u32>()
+        });
+
+        assert_eq!(handle.wait(), Some(Ok(4950)));
+        assert!(handle.is_finished());
+        assert_eq!(handle.progress(), 1.0);
+        // Result can be taken only once.
+        assert_eq!(handle.try_take(), None);
+        assert_eq!(handle.wait(), None);
+    }
+
+    #[test]
+    fn test_panic_is_reported_as_error() {
+        let tasks = TaskManager::new();
+
+        let handle = tasks.spawn::<(), _>(|_| panic!("Something went wrong"));
+        assert_eq!(
+            handle.wait(),
+            Some(Err(TaskError::Panicked("Something went wrong".to_owned())))
+        );
+
+        // Threads of the pool must survive the panic.
+        assert_eq!(tasks.spawn(|_| 42).wait(), Some(Ok(42)));
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let tasks = TaskManager::new();
+
+        let started = Arc::new(AtomicBool::new(false));
+        let job_started = started.clone();
+        let handle = tasks.spawn(move |ctx| {
+            job_started.store(true, Ordering::SeqCst);
+            while !ctx.is_cancelled() {
+                std::thread::yield_now();
+            }
+            1
+        });
+
+        while !started.load(Ordering::SeqCst) {
+            std::thread::yield_now();
+        }
+        handle.cancel();
+
+        assert_eq!(handle.wait(), Some(Err(TaskError::Cancelled)));
+    }
+
+    #[test]
+    fn test_completion_queue() {
+        let mut tasks = TaskManager::new();
+
+        let handle = tasks.spawn_with_callback(|_| 1, |_, _| ());
+        assert_eq!(tasks.pending_callbacks(), 1);
+
+        // Wait until the job is finished without taking the result, it belongs to the callback.
+        while !handle.is_finished() {
+            std::thread::yield_now();
+        }
+        // The job sends its id right after it has finished.
+        let mut completed = tasks.take_completed();
+        while completed.is_empty() {
+            completed = tasks.take_completed();
+        }
+
+        assert_eq!(completed.len(), 1);
+        assert_eq!(tasks.pending_callbacks(), 0);
+    }
+
+    #[test]
+    fn test_parallel_for() {
+        let tasks = TaskManager::new();
+
+        let mut items = vec![0usize; 1000];
+        tasks.parallel_for(&mut items, |i, item| *item = i * 2);
+
+        assert!(items.iter().enumerate().all(|(i, item)| *item == i * 2));
+    }
+}
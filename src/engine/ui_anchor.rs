@@ -0,0 +1,411 @@
+//! UI anchors keep widgets positioned over scene nodes: health bars, name tags, objective markers
+//! and so on.
+//!
+//! # Usage
+//!
+//! Create a widget as usual (it must be a child of a [`crate::gui::canvas::Canvas`], root canvas
+//! of the user interface is fine) and register it in [`crate::engine::Engine::ui_anchors`]:
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::{algebra::Vector3, pool::Handle},
+//!     engine::{ui_anchor::UiAnchor, Engine},
+//!     gui::{progress_bar::ProgressBarBuilder, widget::WidgetBuilder},
+//!     scene::{node::Node, Scene},
+//! };
+//!
+//! fn add_health_bar(engine: &mut Engine, scene: Handle<Scene>, enemy: Handle<Node>) {
+//!     let bar = ProgressBarBuilder::new(WidgetBuilder::new().with_width(60.0).with_height(8.0))
+//!         .build(&mut engine.user_interface.build_ctx());
+//!
+//!     engine.ui_anchors.add(
+//!         UiAnchor::new(bar, scene, enemy).with_world_offset(Vector3::new(0.0, 2.0, 0.0)),
+//!     );
+//! }
+//! ```
+//!
+//! Anchors are updated by [`crate::engine::Engine::update`] after every scene was updated and
+//! before layout of the user interface, so widgets never lag behind their nodes. Position of a
+//! node is projected by the first enabled camera of its scene (or by a specific camera), using
+//! [`crate::scene::camera::Camera::project`].
+//!
+//! # Lifetime
+//!
+//! When an anchored node (or its scene) is deleted, the anchor is removed and its widget is either
+//! hidden or removed from the user interface, see [`OrphanBehavior`]. When the widget is deleted,
+//! the anchor is removed silently.
+
+use crate::{
+    core::{
+        algebra::{Vector2, Vector3},
+        pool::{Handle, Pool},
+    },
+    gui::{message::MessageDirection, widget::WidgetMessage, UiNode, UserInterface},
+    scene::{node::Node, Scene, SceneContainer},
+};
+
+/// Defines what happens with a widget when its node is deleted.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OrphanBehavior {
+    /// The widget is hidden, it can be reused later.
+    Hide,
+    /// The widget is removed from the user interface.
+    Remove,
+}
+
+impl Default for OrphanBehavior {
+    fn default() -> Self {
+        Self::Remove
+    }
+}
+
+/// Scales a widget depending on distance between a camera and an anchored node. Scaling is
+/// applied to explicitly set width and height of a widget, widgets with automatic size are not
+/// scaled.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct DistanceScale {
+    /// Distance at which the widget has its original size.
+    pub reference_distance: f32,
+    /// Min scale of the widget, it is used for far nodes.
+    pub min_scale: f32,
+    /// Max scale of the widget, it is used for close nodes.
+    pub max_scale: f32,
+}
+
+impl Default for DistanceScale {
+    fn default() -> Self {
+        Self {
+            reference_distance: 10.0,
+            min_scale: 0.25,
+            max_scale: 1.0,
+        }
+    }
+}
+
+impl DistanceScale {
+    /// Returns scale of a widget at given distance.
+    pub fn scale_at(&self, distance: f32) -> f32 {
+        (self.reference_distance / distance.max(f32::EPSILON))
+            .max(self.min_scale)
+            .min(self.max_scale)
+    }
+}
+
+/// Binding between a widget and a scene node, see module docs.
+#[derive(Clone, Debug)]
+pub struct UiAnchor {
+    /// A widget that follows the node.
+    pub widget: Handle<UiNode>,
+    /// A scene with the node.
+    pub scene: Handle<Scene>,
+    /// A node to follow.
+    pub node: Handle<Node>,
+    /// A camera that is used for projection. Default is [`Handle::NONE`], which means the first
+    /// enabled camera of the scene.
+    pub camera: Handle<Node>,
+    /// Offset in world coordinates, that is added to global position of the node. It is useful
+    /// to put a widget above head of a character.
+    pub world_offset: Vector3<f32>,
+    /// Offset in logical units of the user interface, that is added to projected position.
+    pub screen_offset: Vector2<f32>,
+    /// Point of the widget that is placed at projected position, in fractions of widget size.
+    /// Default is `(0.5, 1.0)` - bottom center of the widget.
+    pub pivot: Vector2<f32>,
+    /// Hide the widget when the node is behind the camera. Default is `true`.
+    pub hide_when_behind: bool,
+    /// Optional distance-based scale of the widget. Default is `None`.
+    pub distance_scale: Option<DistanceScale>,
+    /// What to do with the widget when the node is deleted.
+    pub orphan_behavior: OrphanBehavior,
+    // Size of the widget before scaling.
+    base_size: Option<Vector2<f32>>,
+    // True if the widget is hidden by the anchor, visibility of widgets hidden by user code is
+    // not restored.
+    hidden: bool,
+}
+
+impl UiAnchor {
+    /// Creates new anchor with default settings.
+    pub fn new(widget: Handle<UiNode>, scene: Handle<Scene>, node: Handle<Node>) -> Self {
+        Self {
+            widget,
+            scene,
+            node,
+            camera: Handle::NONE,
+            world_offset: Default::default(),
+            screen_offset: Default::default(),
+            pivot: Vector2::new(0.5, 1.0),
+            hide_when_behind: true,
+            distance_scale: None,
+            orphan_behavior: Default::default(),
+            base_size: None,
+            hidden: false,
+        }
+    }
+
+    /// Sets a camera that will be used for projection.
+    pub fn with_camera(mut self, camera: Handle<Node>) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    /// Sets offset in world coordinates.
+    pub fn with_world_offset(mut self, offset: Vector3<f32>) -> Self {
+        self.world_offset = offset;
+        self
+    }
+
+    /// Sets offset in logical units of the user interface.
+    pub fn with_screen_offset(mut self, offset: Vector2<f32>) -> Self {
+        self.screen_offset = offset;
+        self
+    }
+
+    /// Sets point of the widget that is placed at projected position.
+    pub fn with_pivot(mut self, pivot: Vector2<f32>) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    /// Sets whether the widget should be hidden when the node is behind the camera.
+    pub fn with_hide_when_behind(mut self, hide: bool) -> Self {
+        self.hide_when_behind = hide;
+        self
+    }
+
+    /// Sets distance-based scale of the widget.
+    pub fn with_distance_scale(mut self, scale: DistanceScale) -> Self {
+        self.distance_scale = Some(scale);
+        self
+    }
+
+    /// Sets what to do with the widget when the node is deleted.
+    pub fn with_orphan_behavior(mut self, behavior: OrphanBehavior) -> Self {
+        self.orphan_behavior = behavior;
+        self
+    }
+}
+
+/// Top-left position of a widget of given size, whose pivot must be at projected position.
+fn widget_position(
+    projected: Vector2<f32>,
+    ui_scale: f32,
+    screen_offset: Vector2<f32>,
+    pivot: Vector2<f32>,
+    size: Vector2<f32>,
+) -> Vector2<f32> {
+    // Projection gives physical pixels, but widgets are positioned in logical units.
+    projected.scale(1.0 / ui_scale) + screen_offset - size.component_mul(&pivot)
+}
+
+fn set_hidden(ui: &mut UserInterface, anchor: &mut UiAnchor, hidden: bool) {
+    if anchor.hidden != hidden {
+        anchor.hidden = hidden;
+        ui.build_ctx()[anchor.widget].set_visibility(!hidden);
+    }
+}
+
+/// A set of anchors, see module docs.
+#[derive(Default, Debug)]
+pub struct UiAnchors {
+    anchors: Pool<UiAnchor>,
+}
+
+impl UiAnchors {
+    /// Adds new anchor.
+    pub fn add(&mut self, anchor: UiAnchor) -> Handle<UiAnchor> {
+        self.anchors.spawn(anchor)
+    }
+
+    /// Removes an anchor, the widget is left as is.
+    pub fn remove(&mut self, handle: Handle<UiAnchor>) -> Option<UiAnchor> {
+        if self.anchors.is_valid_handle(handle) {
+            Some(self.anchors.free(handle))
+        } else {
+            None
+        }
+    }
+
+    /// Removes every anchor of a widget, the widget is left as is.
+    pub fn remove_by_widget(&mut self, widget: Handle<UiNode>) {
+        let handles = self
+            .anchors
+            .pair_iter()
+            .filter(|(_, a)| a.widget == widget)
+            .map(|(h, _)| h)
+            .collect::<Vec<_>>();
+        for handle in handles {
+            self.anchors.free(handle);
+        }
+    }
+
+    /// Tries to borrow an anchor.
+    pub fn try_get(&self, handle: Handle<UiAnchor>) -> Option<&UiAnchor> {
+        self.anchors.try_borrow(handle)
+    }
+
+    /// Tries to borrow an anchor.
+    pub fn try_get_mut(&mut self, handle: Handle<UiAnchor>) -> Option<&mut UiAnchor> {
+        self.anchors.try_borrow_mut(handle)
+    }
+
+    /// Returns an iterator over anchors.
+    pub fn iter(&self) -> impl Iterator<Item = &UiAnchor> {
+        self.anchors.iter()
+    }
+
+    /// Returns amount of anchors.
+    pub fn len(&self) -> usize {
+        self.anchors.alive_count() as usize
+    }
+
+    /// Returns true if there are no anchors.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes every anchor, widgets are left as is.
+    pub fn clear(&mut self) {
+        self.anchors.clear();
+    }
+
+    /// Updates positions of every widget. Called automatically by the engine, `frame_size` is
+    /// size of the window in physical pixels.
+    pub fn update(
+        &mut self,
+        scenes: &SceneContainer,
+        ui: &mut UserInterface,
+        frame_size: Vector2<f32>,
+    ) {
+        let mut dead = Vec::new();
+
+        for (handle, anchor) in self.anchors.pair_iter_mut() {
+            if ui.try_get_node(anchor.widget).is_none() {
+                dead.push(handle);
+                continue;
+            }
+
+            let node = if scenes.is_valid_handle(anchor.scene) {
+                let scene = &scenes[anchor.scene];
+                scene
+                    .graph
+                    .try_get(anchor.node)
+                    .map(|node| (scene, node.global_position() + anchor.world_offset))
+            } else {
+                None
+            };
+
+            let (scene, position) = match node {
+                Some(node) => node,
+                None => {
+                    match anchor.orphan_behavior {
+                        OrphanBehavior::Hide => set_hidden(ui, anchor, true),
+                        OrphanBehavior::Remove => ui.send_message(WidgetMessage::remove(
+                            anchor.widget,
+                            MessageDirection::ToWidget,
+                        )),
+                    }
+                    dead.push(handle);
+                    continue;
+                }
+            };
+
+            // Keep the widget where it was while the scene is paused.
+            if !scene.enabled {
+                continue;
+            }
+
+            let camera = if anchor.camera.is_some() {
+                scene.graph.try_get(anchor.camera)
+            } else {
+                scene
+                    .graph
+                    .linear_iter()
+                    .find(|n| matches!(n, Node::Camera(c) if c.is_enabled()))
+            };
+            let camera = match camera {
+                Some(Node::Camera(camera)) => camera,
+                _ => continue,
+            };
+
+            let projected = match camera.project(position, frame_size) {
+                Some(projected) => projected,
+                None => {
+                    if anchor.hide_when_behind {
+                        set_hidden(ui, anchor, true);
+                    }
+                    continue;
+                }
+            };
+            set_hidden(ui, anchor, false);
+
+            let ui_scale = ui.scale();
+            let mut ctx = ui.build_ctx();
+            let widget = &mut ctx[anchor.widget];
+
+            if let Some(distance_scale) = anchor.distance_scale {
+                let base_size = *anchor
+                    .base_size
+                    .get_or_insert_with(|| Vector2::new(widget.width(), widget.height()));
+                let scale =
+                    distance_scale.scale_at(camera.global_position().metric_distance(&position));
+                if base_size.x.is_finite() {
+                    widget.set_width(base_size.x * scale);
+                }
+                if base_size.y.is_finite() {
+                    widget.set_height(base_size.y * scale);
+                }
+            }
+
+            let new_position = widget_position(
+                projected,
+                ui_scale,
+                anchor.screen_offset,
+                anchor.pivot,
+                widget.actual_size(),
+            );
+            if widget.desired_local_position() != new_position {
+                widget.set_desired_local_position(new_position);
+                widget.invalidate_layout();
+            }
+        }
+
+        for handle in dead {
+            self.anchors.free(handle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector2,
+        engine::ui_anchor::{widget_position, DistanceScale},
+    };
+
+    #[test]
+    fn test_distance_scale() {
+        let scale = DistanceScale {
+            reference_distance: 10.0,
+            min_scale: 0.25,
+            max_scale: 1.0,
+        };
+        assert_eq!(scale.scale_at(5.0), 1.0);
+        assert_eq!(scale.scale_at(20.0), 0.5);
+        assert_eq!(scale.scale_at(1000.0), 0.25);
+        assert_eq!(scale.scale_at(0.0), 1.0);
+    }
+
+    #[test]
+    fn test_widget_position() {
+        // Bottom center of 100x20 widget at (400; 300) physical pixels with UI scale 2.
+        let position = widget_position(
+            Vector2::new(400.0, 300.0),
+            2.0,
+            Vector2::new(0.0, -5.0),
+            Vector2::new(0.5, 1.0),
+            Vector2::new(100.0, 20.0),
+        );
+        assert_eq!(position, Vector2::new(150.0, 125.0));
+    }
+}
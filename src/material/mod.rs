@@ -5,7 +5,6 @@
 #![warn(missing_docs)]
 
 use crate::{
-    asset::ResourceState,
     core::{
         algebra::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4},
         color::Color,
@@ -502,23 +501,8 @@ impl Material {
                 ..
             } = value
             {
-                let data = texture.state();
-                let path = data.path().to_path_buf();
-                match &*data {
-                    // Try to reload texture even if it failed to load.
-                    ResourceState::LoadError { .. } => {
-                        drop(data);
-                        *texture = resource_manager.request_texture(path, None);
-                    }
-                    ResourceState::Ok(texture_state) => {
-                        // Do not resolve procedural textures.
-                        if !texture_state.is_procedural() {
-                            drop(data);
-                            *texture = resource_manager.request_texture(path, None);
-                        }
-                    }
-                    ResourceState::Pending { .. } => {}
-                }
+                // Textures that failed to load are requested again, procedural textures are kept.
+                *texture = resource_manager.resolve_texture(texture);
             }
         }
     }
@@ -6,7 +6,7 @@ use crate::{
     asset::{define_new_resource, Resource, ResourceData, ResourceState},
     core::{
         algebra::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4},
-        io::{self, FileLoadError},
+        io::FileLoadError,
         sparse::AtomicIndex,
         visitor::prelude::*,
     },
@@ -15,6 +15,7 @@ use crate::{
         cache::{shader::ShaderSet, CacheEntry},
         framework::framebuffer::DrawParameters,
     },
+    resource::io::ResourceSource,
 };
 use ron::Error;
 use serde::Deserialize;
@@ -237,8 +238,11 @@ impl ShaderDefinition {
 }
 
 impl ShaderState {
-    pub(in crate) async fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ShaderError> {
-        let content = io::load_file(path.as_ref()).await?;
+    pub(in crate) async fn from_file<P: AsRef<Path>>(
+        path: P,
+        source: &ResourceSource,
+    ) -> Result<Self, ShaderError> {
+        let content = source.load(path.as_ref()).await?;
         Ok(Self {
             path: path.as_ref().to_owned(),
             definition: ShaderDefinition::from_buf(content)?,
@@ -441,6 +445,17 @@ define_new_resource!(
     /// | rg3d_cameraPosition       | `Vector3`       | Position of the camera.
     /// | rg3d_usePOM               | `bool`          | Whether to use parallax mapping or not.
     /// | rg3d_lightPosition        | `Vector3`       | Light position.
+    /// | rg3d_opacity              | `f32`           | Opacity of a mesh, see `Mesh::set_opacity`.
+    /// | rg3d_color                | `Vector4`       | Color of a mesh, see `Mesh::set_color`.
+    /// | rg3d_uvTransform          | `Matrix3`       | Texture coordinates transform, see `Surface::set_uv_transform`.
+    /// | rg3d_mipBias              | `f32`           | Mip level bias, see `Surface::set_mip_bias`.
+    /// | rg3d_receiveShadows       | `bool`          | Whether a mesh receives shadows or not, see `Mesh::set_receive_shadows`. Must be written to the alpha channel of the material output of the GBuffer pass.
+    /// | rg3d_alphaCutoff          | `f32`           | Alpha threshold of cutout transparency, see `Surface::set_alpha_cutoff`. Fragments with smaller alpha should be discarded in the GBuffer and shadow passes.
+    /// | rg3d_triplanarSharpness   | `f32`           | Blend sharpness of triplanar mapping, see `Surface::set_triplanar_mapping`. Available only in the triplanar variant of a pass.
+    /// | rg3d_triplanarScale       | `f32`           | Texture repeats per world unit of triplanar mapping. Available only in the triplanar variant of a pass.
+    /// | rg3d_useInstancedSkinning | `bool`          | Whether bone matrices must be fetched from `rg3d_boneMatricesTexture` or not.
+    /// | rg3d_boneMatricesTexture  | `sampler2D`     | Bone matrices of instanced skinned meshes, one instance per row, one matrix column per texel. Color of an instance is stored in the texel 256 of its row, `rg3d_color` is white in this case.
+    /// | rg3d_boneMatricesRowOffset| `int`           | Row of `rg3d_boneMatricesTexture` of the first instance, add `gl_InstanceID` to it.
     ///
     /// To use any of the variables, just define a uniform with appropriate name:
     ///
@@ -451,6 +466,22 @@ define_new_resource!(
     ///
     /// This list will be extended in future releases.
     ///
+    /// # Triplanar variant
+    ///
+    /// If source code of a render pass mentions `RG3D_TRIPLANAR`, the pass is compiled twice: as is
+    /// and with `#define RG3D_TRIPLANAR`. The second variant is used for surfaces with triplanar
+    /// mapping (see `Surface::set_triplanar_mapping`), it should ignore texture coordinates and
+    /// project textures along world axes:
+    ///
+    /// ```glsl
+    /// #ifdef RG3D_TRIPLANAR
+    /// uniform float rg3d_triplanarSharpness;
+    /// uniform float rg3d_triplanarScale;
+    /// #endif
+    /// ```
+    ///
+    /// Passes that do not mention the define are compiled once and used for every surface.
+    ///
     /// # Drawing parameters
     ///
     /// Drawing parameters defines which GPU functions to use and at which state. For example, to render
@@ -84,6 +84,7 @@
                 layout(location = 4) in vec4 boneWeights;
                 layout(location = 5) in vec4 boneIndices;
                 layout(location = 6) in vec2 vertexSecondTexCoord;
+                layout(location = 7) in vec4 vertexColor;
 
                 // Define uniforms with reserved names. rg3d will automatically provide
                 // required data to these uniforms.
@@ -91,6 +92,10 @@
                 uniform mat4 rg3d_worldViewProjection;
                 uniform mat4 rg3d_boneMatrices[60];
                 uniform bool rg3d_useSkeletalAnimation;
+                uniform mat3 rg3d_uvTransform;
+                uniform bool rg3d_useInstancedSkinning;
+                uniform sampler2D rg3d_boneMatricesTexture;
+                uniform int rg3d_boneMatricesRowOffset;
 
                 out vec3 position;
                 out vec3 normal;
@@ -98,6 +103,27 @@
                 out vec3 tangent;
                 out vec3 binormal;
                 out vec2 secondTexCoord;
+                out vec4 color;
+
+                mat4 fetchBoneMatrix(int index)
+                {
+                    if (rg3d_useInstancedSkinning)
+                    {
+                        // Every row of the texture contains bone matrices of one instance,
+                        // every texel is a column of a matrix.
+                        int row = rg3d_boneMatricesRowOffset + gl_InstanceID;
+                        int column = index * 4;
+
+                        return mat4(
+                            texelFetch(rg3d_boneMatricesTexture, ivec2(column, row), 0),
+                            texelFetch(rg3d_boneMatricesTexture, ivec2(column + 1, row), 0),
+                            texelFetch(rg3d_boneMatricesTexture, ivec2(column + 2, row), 0),
+                            texelFetch(rg3d_boneMatricesTexture, ivec2(column + 3, row), 0)
+                        );
+                    }
+
+                    return rg3d_boneMatrices[index];
+                }
 
                 void main()
                 {
@@ -114,10 +140,10 @@
                         int i2 = int(boneIndices.z);
                         int i3 = int(boneIndices.w);
 
-                        mat4 m0 = rg3d_boneMatrices[i0];
-                        mat4 m1 = rg3d_boneMatrices[i1];
-                        mat4 m2 = rg3d_boneMatrices[i2];
-                        mat4 m3 = rg3d_boneMatrices[i3];
+                        mat4 m0 = fetchBoneMatrix(i0);
+                        mat4 m1 = fetchBoneMatrix(i1);
+                        mat4 m2 = fetchBoneMatrix(i2);
+                        mat4 m3 = fetchBoneMatrix(i3);
 
                         localPosition += m0 * vertex * boneWeights.x;
                         localPosition += m1 * vertex * boneWeights.y;
@@ -145,9 +171,17 @@
                     normal = normalize(nm * localNormal);
                     tangent = normalize(nm * localTangent);
                     binormal = normalize(vertexTangent.w * cross(tangent, normal));
-                    texCoord = vertexTexCoord;
+                    texCoord = (rg3d_uvTransform * vec3(vertexTexCoord, 1.0)).xy;
                     position = vec3(rg3d_worldMatrix * localPosition);
                     secondTexCoord = vertexSecondTexCoord;
+                    color = vertexColor;
+
+                    if (rg3d_useInstancedSkinning)
+                    {
+                        // Color of an instance is stored right after its bone matrices.
+                        int row = rg3d_boneMatricesRowOffset + gl_InstanceID;
+                        color.rgb *= texelFetch(rg3d_boneMatricesTexture, ivec2(256, row), 0).rgb;
+                    }
 
                     gl_Position = rg3d_worldViewProjection * localPosition;
                 }
@@ -178,6 +212,14 @@
                 // required data to these uniforms.
                 uniform vec3 rg3d_cameraPosition;
                 uniform bool rg3d_usePOM;
+                uniform vec4 rg3d_color;
+                uniform float rg3d_mipBias;
+                uniform bool rg3d_receiveShadows;
+                uniform float rg3d_alphaCutoff;
+                #ifdef RG3D_TRIPLANAR
+                uniform float rg3d_triplanarSharpness;
+                uniform float rg3d_triplanarScale;
+                #endif
 
                 in vec3 position;
                 in vec3 normal;
@@ -185,9 +227,18 @@
                 in vec3 tangent;
                 in vec3 binormal;
                 in vec2 secondTexCoord;
+                in vec4 color;
 
                 void main()
                 {
+                    #ifdef RG3D_TRIPLANAR
+                    // Texture coordinates of the mesh are ignored, textures are projected along
+                    // world axes.
+                    vec3 surfaceNormal = normalize(normal);
+                    vec3 triplanarPosition = position * rg3d_triplanarScale;
+                    vec3 triplanarWeights = S_TriplanarWeights(surfaceNormal, rg3d_triplanarSharpness);
+                    #define SAMPLE(tex) S_TriplanarTexture(tex, triplanarPosition, surfaceNormal, triplanarWeights, rg3d_mipBias)
+                    #else
                     mat3 tangentSpace = mat3(tangent, binormal, normal);
                     vec3 toFragment = normalize(position - rg3d_cameraPosition);
 
@@ -198,24 +249,39 @@
                     } else {
                         tc = texCoord * texCoordScale;
                     }
+                    #define SAMPLE(tex) texture(tex, tc, rg3d_mipBias)
+                    #endif
 
-                    outColor = diffuseColor * texture(diffuseTexture, tc);
+                    outColor = color * diffuseColor * SAMPLE(diffuseTexture);
+                    outColor.rgb *= rg3d_color.rgb;
 
                     // Alpha test.
-                    if (outColor.a < 0.5) {
+                    if (outColor.a < rg3d_alphaCutoff) {
                         discard;
                     }
                     outColor.a = 1.0;
 
-                    vec4 n = normalize(texture(normalTexture, tc) * 2.0 - 1.0);
-                    outNormal = vec4(normalize(tangentSpace * n.xyz) * 0.5 + 0.5, 1.0);
+                    #ifdef RG3D_TRIPLANAR
+                    vec3 worldNormal = S_TriplanarNormal(normalTexture, triplanarPosition, surfaceNormal, triplanarWeights, rg3d_mipBias);
+                    #else
+                    vec4 n = normalize(texture(normalTexture, tc, rg3d_mipBias) * 2.0 - 1.0);
+                    vec3 worldNormal = normalize(tangentSpace * n.xyz);
+                    #endif
+                    // Back faces are visible only for two-sided surfaces, flip the normal to
+                    // light them correctly.
+                    if (!gl_FrontFacing) {
+                        worldNormal = -worldNormal;
+                    }
+                    outNormal = vec4(worldNormal * 0.5 + 0.5, 1.0);
 
-                    outMaterial.x = texture(metallicTexture, tc).r;
-                    outMaterial.y = texture(roughnessTexture, tc).r;
-                    outMaterial.z = texture(aoTexture, tc).r;
-                    outMaterial.a = 1.0;
+                    outMaterial.x = SAMPLE(metallicTexture).r;
+                    outMaterial.y = SAMPLE(roughnessTexture).r;
+                    outMaterial.z = SAMPLE(aoTexture).r;
+                    // Alpha of material is used as a flag, lighting shaders skip shadows for
+                    // fragments with zero alpha.
+                    outMaterial.a = rg3d_receiveShadows ? 1.0 : 0.0;
 
-                    outAmbient.xyz = emissionStrength * texture(emissionTexture, tc).rgb + texture(lightmapTexture, secondTexCoord).rgb;
+                    outAmbient.xyz = emissionStrength * SAMPLE(emissionTexture).rgb + texture(lightmapTexture, secondTexCoord).rgb;
                     outAmbient.a = 1.0;
 
                     outDecalMask = layerIndex;
@@ -250,15 +316,18 @@
                r#"
                 layout(location = 0) in vec3 vertexPosition;
                 layout(location = 1) in vec2 vertexTexCoord;
-                layout(location = 5) in vec4 boneWeights;
-                layout(location = 6) in vec4 boneIndices;
+                layout(location = 4) in vec4 boneWeights;
+                layout(location = 5) in vec4 boneIndices;
+                layout(location = 7) in vec4 vertexColor;
 
                 uniform mat4 rg3d_worldViewProjection;
                 uniform bool rg3d_useSkeletalAnimation;
+                uniform mat3 rg3d_uvTransform;
                 uniform mat4 rg3d_boneMatrices[60];
 
                 out vec3 position;
                 out vec2 texCoord;
+                out vec4 color;
 
                 void main()
                 {
@@ -282,7 +351,8 @@
                         localPosition = vec4(vertexPosition, 1.0);
                     }
                     gl_Position = rg3d_worldViewProjection * localPosition;
-                    texCoord = vertexTexCoord;
+                    texCoord = (rg3d_uvTransform * vec3(vertexTexCoord, 1.0)).xy;
+                    color = vertexColor;
                 }
                "#,
 
@@ -290,14 +360,20 @@
                r#"
                 uniform sampler2D diffuseTexture;
                 uniform vec4 diffuseColor;
+                uniform float rg3d_opacity;
+                uniform vec4 rg3d_color;
+                uniform float rg3d_mipBias;
 
                 out vec4 FragColor;
 
                 in vec2 texCoord;
+                in vec4 color;
 
                 void main()
                 {
-                    FragColor = diffuseColor * texture(diffuseTexture, texCoord);
+                    FragColor = color * diffuseColor * texture(diffuseTexture, texCoord, rg3d_mipBias);
+                    FragColor.rgb *= rg3d_color.rgb;
+                    FragColor.a *= rg3d_opacity;
                 }
                "#,
         ),
@@ -333,6 +409,7 @@
 
                 uniform mat4 rg3d_worldViewProjection;
                 uniform bool rg3d_useSkeletalAnimation;
+                uniform mat3 rg3d_uvTransform;
                 uniform mat4 rg3d_boneMatrices[60];
 
                 out vec2 texCoord;
@@ -356,19 +433,21 @@
                     }
 
                     gl_Position = rg3d_worldViewProjection * localPosition;
-                    texCoord = vertexTexCoord;
+                    texCoord = (rg3d_uvTransform * vec3(vertexTexCoord, 1.0)).xy;
                 }
                 "#,
 
             fragment_shader:
                 r#"
                 uniform sampler2D diffuseTexture;
+                uniform vec4 diffuseColor;
+                uniform float rg3d_alphaCutoff;
 
                 in vec2 texCoord;
 
                 void main()
                 {
-                    if (texture(diffuseTexture, texCoord).a < 0.2) discard;
+                    if (diffuseColor.a * texture(diffuseTexture, texCoord).a < rg3d_alphaCutoff) discard;
                 }
                 "#,
         ),
@@ -404,6 +483,7 @@
 
                 uniform mat4 rg3d_worldViewProjection;
                 uniform bool rg3d_useSkeletalAnimation;
+                uniform mat3 rg3d_uvTransform;
                 uniform mat4 rg3d_boneMatrices[60];
 
                 out vec2 texCoord;
@@ -427,19 +507,21 @@
                     }
 
                     gl_Position = rg3d_worldViewProjection * localPosition;
-                    texCoord = vertexTexCoord;
+                    texCoord = (rg3d_uvTransform * vec3(vertexTexCoord, 1.0)).xy;
                 }
                 "#,
 
             fragment_shader:
                 r#"
                 uniform sampler2D diffuseTexture;
+                uniform vec4 diffuseColor;
+                uniform float rg3d_alphaCutoff;
 
                 in vec2 texCoord;
 
                 void main()
                 {
-                    if (texture(diffuseTexture, texCoord).a < 0.2) discard;
+                    if (diffuseColor.a * texture(diffuseTexture, texCoord).a < rg3d_alphaCutoff) discard;
                 }
                 "#,
         ),
@@ -476,6 +558,7 @@
                 uniform mat4 rg3d_worldMatrix;
                 uniform mat4 rg3d_worldViewProjection;
                 uniform bool rg3d_useSkeletalAnimation;
+                uniform mat3 rg3d_uvTransform;
                 uniform mat4 rg3d_boneMatrices[60];
 
                 out vec2 texCoord;
@@ -501,15 +584,17 @@
 
                     gl_Position = rg3d_worldViewProjection * localPosition;
                     worldPosition = (rg3d_worldMatrix * localPosition).xyz;
-                    texCoord = vertexTexCoord;
+                    texCoord = (rg3d_uvTransform * vec3(vertexTexCoord, 1.0)).xy;
                 }
                 "#,
 
             fragment_shader:
                 r#"
                 uniform sampler2D diffuseTexture;
+                uniform vec4 diffuseColor;
 
                 uniform vec3 rg3d_lightPosition;
+                uniform float rg3d_alphaCutoff;
 
                 in vec2 texCoord;
                 in vec3 worldPosition;
@@ -518,7 +603,7 @@
 
                 void main()
                 {
-                    if (texture(diffuseTexture, texCoord).a < 0.2) discard;
+                    if (diffuseColor.a * texture(diffuseTexture, texCoord).a < rg3d_alphaCutoff) discard;
                     depth = length(rg3d_lightPosition - worldPosition);
                 }
                 "#,
@@ -1,18 +1,28 @@
 use crate::{
     core::{
-        algebra::Matrix4, arrayvec::ArrayVec, parking_lot::Mutex, pool::Handle, scope_profile,
+        algebra::{Matrix3, Matrix4, Vector3},
+        arrayvec::ArrayVec,
+        color::Color,
+        parking_lot::Mutex,
+        pool::Handle,
+        profile_scope, scope_profile,
         sstorage::ImmutableString,
     },
     material::{Material, PropertyValue},
+    renderer::framework::framebuffer::CullFace,
     scene::{
         graph::Graph,
-        mesh::{surface::SurfaceData, RenderPath},
+        mesh::{
+            surface::{SurfaceData, TriplanarMapping, DEFAULT_ALPHA_CUTOFF},
+            RenderPath,
+        },
         node::Node,
     },
-    utils::log::{Log, MessageKind},
+    utils::log::log_error,
 };
 use fxhash::{FxHashMap, FxHasher};
 use std::{
+    collections::hash_map::Entry,
     fmt::{Debug, Formatter},
     hash::Hasher,
     sync::Arc,
@@ -25,6 +35,36 @@ pub struct SurfaceInstance {
     pub world_transform: Matrix4<f32>,
     pub bone_matrices: ArrayVec<Matrix4<f32>, BONE_MATRICES_COUNT>,
     pub depth_offset: f32,
+    pub opacity: f32,
+    pub color: Color,
+    /// Transparent instances are rendered in a separate sorted queue by forward renderer.
+    pub is_transparent: bool,
+    /// Center of world bounding box of the owner, it is used for depth sorting.
+    pub world_center: Vector3<f32>,
+    /// Transform of texture coordinates, it is taken from a surface of a mesh, so every instance
+    /// in a batch can have its own tiling and scrolling.
+    pub uv_transform: Matrix3<f32>,
+    pub mip_bias: f32,
+    /// Whether the instance should be shadowed by other objects or not.
+    pub receive_shadows: bool,
+    /// Back faces of two-sided instances are not culled.
+    pub two_sided: bool,
+    /// Alpha threshold of cutout transparency, zero disables alpha test.
+    pub alpha_cutoff: f32,
+    /// Parameters of triplanar mapping, `None` if texture coordinates are used.
+    pub triplanar_mapping: Option<TriplanarMapping>,
+}
+
+impl SurfaceInstance {
+    /// Returns face culling mode of a render pass adjusted for the instance, back faces of
+    /// two-sided instances are never culled.
+    pub fn cull_face(&self, cull_face: Option<CullFace>) -> Option<CullFace> {
+        if self.two_sided {
+            None
+        } else {
+            cull_face
+        }
+    }
 }
 
 pub struct Batch {
@@ -48,6 +88,90 @@ impl Debug for Batch {
     }
 }
 
+struct BoneMatrixCacheEntry {
+    bones: Vec<Handle<Node>>,
+    matrices: ArrayVec<Matrix4<f32>, BONE_MATRICES_COUNT>,
+    generation: u64,
+}
+
+/// Stores bone matrices of skinned surfaces of a scene, so they won't be recomputed for
+/// surfaces whose bones did not move since the last update of the scene graph.
+#[derive(Default)]
+pub struct BoneMatrixCache {
+    entries: FxHashMap<(Handle<Node>, usize), BoneMatrixCacheEntry>,
+    generation: u64,
+    /// Amount of surfaces which used cached bone matrices during last batch generation.
+    pub hits: usize,
+    /// Amount of surfaces which bone matrices were recomputed during last batch generation.
+    pub misses: usize,
+}
+
+impl BoneMatrixCache {
+    fn begin(&mut self) {
+        self.generation += 1;
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    fn bone_matrices(
+        &mut self,
+        graph: &Graph,
+        owner: Handle<Node>,
+        surface_index: usize,
+        bones: &[Handle<Node>],
+    ) -> ArrayVec<Matrix4<f32>, BONE_MATRICES_COUNT> {
+        let generation = self.generation;
+
+        let compute = || {
+            bones
+                .iter()
+                .map(|&bone_handle| {
+                    let bone_node = &graph[bone_handle];
+                    bone_node.global_transform() * bone_node.inv_bind_pose_transform()
+                })
+                .collect()
+        };
+
+        match self.entries.entry((owner, surface_index)) {
+            Entry::Occupied(mut entry) => {
+                let entry = entry.get_mut();
+                entry.generation = generation;
+                if entry.bones == bones
+                    && bones
+                        .iter()
+                        .all(|&bone| !graph[bone].global_transform_changed())
+                {
+                    self.hits += 1;
+                } else {
+                    entry.bones.clear();
+                    entry.bones.extend_from_slice(bones);
+                    entry.matrices = compute();
+                    self.misses += 1;
+                }
+                entry.matrices.clone()
+            }
+            Entry::Vacant(entry) => {
+                self.misses += 1;
+                entry
+                    .insert(BoneMatrixCacheEntry {
+                        bones: bones.to_vec(),
+                        matrices: compute(),
+                        generation,
+                    })
+                    .matrices
+                    .clone()
+            }
+        }
+    }
+
+    fn end(&mut self) {
+        // Remove entries of meshes that were deleted or lost their skin.
+        let generation = self.generation;
+        self.entries
+            .retain(|_, entry| entry.generation == generation);
+    }
+}
+
 #[derive(Default)]
 pub struct BatchStorage {
     buffers: Vec<Vec<SurfaceInstance>>,
@@ -57,8 +181,15 @@ pub struct BatchStorage {
 }
 
 impl BatchStorage {
-    pub(in crate) fn generate_batches(&mut self, graph: &Graph) {
+    pub(in crate) fn generate_batches(
+        &mut self,
+        graph: &Graph,
+        bone_cache: &mut BoneMatrixCache,
+    ) {
         scope_profile!();
+        profile_scope!("BatchGeneration");
+
+        bone_cache.begin();
 
         for batch in self.batches.iter_mut() {
             batch.instances.clear();
@@ -71,7 +202,7 @@ impl BatchStorage {
         for (handle, node) in graph.pair_iter() {
             match node {
                 Node::Mesh(mesh) => {
-                    for surface in mesh.surfaces().iter() {
+                    for (surface_index, surface) in mesh.surfaces().iter().enumerate() {
                         let is_skinned = !surface.bones.is_empty();
 
                         let world = if is_skinned {
@@ -80,7 +211,7 @@ impl BatchStorage {
                             mesh.global_transform()
                         };
 
-                        let data = surface.data();
+                        let data = surface.render_data();
                         let batch_id = surface.batch_id();
 
                         let batch = if let Some(&batch_index) = self.batch_map.get(&batch_id) {
@@ -106,17 +237,28 @@ impl BatchStorage {
 
                         batch.instances.push(SurfaceInstance {
                             world_transform: world,
-                            bone_matrices: surface
-                                .bones
-                                .iter()
-                                .map(|&bone_handle| {
-                                    let bone_node = &graph[bone_handle];
-                                    bone_node.global_transform()
-                                        * bone_node.inv_bind_pose_transform()
-                                })
-                                .collect(),
+                            bone_matrices: if is_skinned {
+                                bone_cache.bone_matrices(
+                                    graph,
+                                    handle,
+                                    surface_index,
+                                    &surface.bones,
+                                )
+                            } else {
+                                Default::default()
+                            },
                             owner: handle,
                             depth_offset: mesh.depth_offset_factor(),
+                            opacity: mesh.opacity(),
+                            color: mesh.color(),
+                            is_transparent: surface.is_transparent() || mesh.is_translucent(),
+                            world_center: mesh.world_bounding_box().center(),
+                            uv_transform: surface.uv_transform().matrix(),
+                            mip_bias: surface.mip_bias(),
+                            receive_shadows: mesh.receive_shadows(),
+                            two_sided: surface.is_two_sided(),
+                            alpha_cutoff: surface.alpha_cutoff(),
+                            triplanar_mapping: surface.triplanar_mapping(),
                         });
                     }
                 }
@@ -169,15 +311,22 @@ impl BatchStorage {
                                         bone_matrices: Default::default(),
                                         owner: handle,
                                         depth_offset: terrain.depth_offset_factor(),
+                                        opacity: 1.0,
+                                        color: Color::WHITE,
+                                        is_transparent: false,
+                                        world_center: terrain.world_bounding_box().center(),
+                                        uv_transform: Matrix3::identity(),
+                                        mip_bias: 0.0,
+                                        receive_shadows: true,
+                                        two_sided: false,
+                                        alpha_cutoff: DEFAULT_ALPHA_CUTOFF,
+                                        triplanar_mapping: None,
                                     });
                                 }
-                                Err(e) => Log::writeln(
-                                    MessageKind::Error,
-                                    format!(
-                                        "Failed to prepare batch for terrain chunk.\
+                                Err(e) => log_error!(
+                                    "Failed to prepare batch for terrain chunk.\
                                  Unable to set mask texture for terrain material. Reason: {:?}",
-                                        e
-                                    ),
+                                    e
                                 ),
                             }
                         }
@@ -187,6 +336,8 @@ impl BatchStorage {
             }
         }
 
+        bone_cache.end();
+
         for batch in self.batches.iter_mut() {
             batch.instances.shrink_to_fit();
         }
@@ -194,3 +345,64 @@ impl BatchStorage {
         self.batches.sort_unstable_by_key(|b| b.sort_index);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Matrix4, Vector3},
+            parking_lot::Mutex,
+        },
+        renderer::batch::{BatchStorage, BoneMatrixCache},
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            mesh::{
+                surface::{SurfaceBuilder, SurfaceData},
+                MeshBuilder,
+            },
+        },
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn test_bone_matrix_cache() {
+        let mut graph = Graph::new();
+        let bone = BaseBuilder::new().build(&mut graph);
+        let mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::identity()),
+            )))
+            .with_bones(vec![bone])
+            .build()])
+            .build(&mut graph);
+
+        let mut storage = BatchStorage::default();
+        let mut cache = BoneMatrixCache::default();
+
+        graph.update_hierarchical_data();
+        storage.generate_batches(&graph, &mut cache);
+        assert_eq!((cache.hits, cache.misses), (0, 1));
+
+        // Nothing has moved, matrices must be taken from the cache.
+        graph.update_hierarchical_data();
+        storage.generate_batches(&graph, &mut cache);
+        assert_eq!((cache.hits, cache.misses), (1, 0));
+
+        graph[bone]
+            .local_transform_mut()
+            .set_position(Vector3::new(1.0, 0.0, 0.0));
+        graph.update_hierarchical_data();
+        storage.generate_batches(&graph, &mut cache);
+        assert_eq!((cache.hits, cache.misses), (0, 1));
+        assert_eq!(
+            storage.batches[0].instances[0].bone_matrices[0],
+            graph[bone].global_transform()
+        );
+
+        // Entries of deleted meshes must be removed.
+        graph.remove_node(mesh);
+        storage.generate_batches(&graph, &mut cache);
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::core::sstorage::ImmutableString;
 use crate::{
-    core::{math::Rect, scope_profile},
+    core::{math::Rect, profile_scope, scope_profile},
     renderer::{
         bloom::blur::GaussianBlur,
         framework::{
@@ -106,6 +106,7 @@ impl BloomRenderer {
         hdr_scene_frame: Rc<RefCell<GpuTexture>>,
     ) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("Bloom");
 
         let mut stats = RenderPassStatistics::default();
 
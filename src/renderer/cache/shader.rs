@@ -8,16 +8,63 @@ use crate::{
         cache::CacheEntry,
         framework::{framebuffer::DrawParameters, gpu_program::GpuProgram, state::PipelineState},
     },
-    utils::log::{Log, MessageKind},
+    utils::log::log_error,
 };
 use fxhash::FxHashMap;
 use std::ops::Deref;
 
+/// Name of the define that is set for triplanar variant of a render pass.
+pub const TRIPLANAR_DEFINE: &str = "RG3D_TRIPLANAR";
+
 pub struct RenderPassData {
     pub program: GpuProgram,
+    /// Variant of the program with triplanar mapping, it is compiled only if the source code of
+    /// the pass checks [`TRIPLANAR_DEFINE`].
+    pub triplanar_program: Option<GpuProgram>,
     pub draw_params: DrawParameters,
 }
 
+impl RenderPassData {
+    /// Returns program that should be used to draw a surface, triplanar variant is used only if
+    /// the pass supports it.
+    pub fn program(&self, triplanar: bool) -> &GpuProgram {
+        match self.triplanar_program.as_ref() {
+            Some(triplanar_program) if triplanar => triplanar_program,
+            _ => &self.program,
+        }
+    }
+}
+
+fn create_triplanar_program(
+    state: &mut PipelineState,
+    program_name: &str,
+    vertex_shader: &str,
+    fragment_shader: &str,
+) -> Option<GpuProgram> {
+    if !vertex_shader.contains(TRIPLANAR_DEFINE) && !fragment_shader.contains(TRIPLANAR_DEFINE) {
+        return None;
+    }
+
+    let define = format!("#define {}\n", TRIPLANAR_DEFINE);
+    match GpuProgram::from_source(
+        state,
+        &format!("{}_Triplanar", program_name),
+        &(define.clone() + vertex_shader),
+        &(define + fragment_shader),
+    ) {
+        Ok(gpu_program) => Some(gpu_program),
+        Err(e) => {
+            // Surfaces with triplanar mapping will fall back to the usual program.
+            log_error!(
+                "Failed to create triplanar variant of {} shader' GPU program. Reason: {:?}",
+                program_name,
+                e
+            );
+            None
+        }
+    }
+}
+
 pub struct ShaderSet {
     pub render_passes: FxHashMap<ImmutableString, RenderPassData>,
 }
@@ -38,17 +85,21 @@ impl ShaderSet {
                         ImmutableString::new(&render_pass.name),
                         RenderPassData {
                             program: gpu_program,
+                            triplanar_program: create_triplanar_program(
+                                state,
+                                &program_name,
+                                &render_pass.vertex_shader,
+                                &render_pass.fragment_shader,
+                            ),
                             draw_params: render_pass.draw_parameters.clone(),
                         },
                     );
                 }
                 Err(e) => {
-                    Log::writeln(
-                        MessageKind::Error,
-                        format!(
-                            "Failed to create {} shader' GPU program. Reason: {:?}",
-                            program_name, e
-                        ),
+                    log_error!(
+                        "Failed to create {} shader' GPU program. Reason: {:?}",
+                        program_name,
+                        e
                     );
                     return None;
                 }
@@ -1,33 +1,196 @@
+//! Texture cache uploads textures to GPU on first use and keeps them there while they're used.
+//!
+//! # Texture memory budget
+//!
+//! Cache tracks how much GPU memory is occupied by textures. When a budget is set (see
+//! [`crate::renderer::Renderer::set_texture_memory_budget`]) and textures occupy more memory,
+//! the cache drops top mip levels of textures that weren't used for the longest time (larger
+//! textures go first among equally old ones) by re-uploading a shorter mip chain. Textures that
+//! were used during last frame get their mip levels back when there is enough free memory.
+//! Usage is recorded every time a texture is fetched from the cache for rendering.
+//!
+//! Only textures that have their mip levels in CPU memory (DDS textures for example) can be
+//! streamed, mip levels generated by GPU can't be restored. Small textures and textures marked
+//! as "never stream" (see [`crate::resource::texture::TextureData::set_never_stream`]) are never
+//! touched. Amount of re-uploads per frame is limited, so it may take a few frames to fit into
+//! the budget. When there is no budget, the cache does nothing.
+
 use crate::{
     core::scope_profile,
     engine::resource_manager::DEFAULT_RESOURCE_LIFETIME,
     renderer::{
         cache::CacheEntry,
         framework::{
-            gpu_texture::{Coordinate, GpuTexture, PixelKind},
+            error::FrameworkError,
+            gpu_texture::{
+                mip_chain_size_bytes, Coordinate, GpuTexture, GpuTextureKind, PixelKind,
+            },
             state::PipelineState,
         },
+        TextureMemoryStatistics,
     },
-    resource::texture::{Texture, TextureState},
-    utils::log::{Log, MessageKind},
+    resource::texture::{Texture, TextureData, TextureMinificationFilter, TextureState},
+    utils::log::log_error,
 };
 use fxhash::FxHashMap;
-use std::{cell::RefCell, collections::hash_map::Entry, ops::Deref, rc::Rc};
+use std::{cell::RefCell, cmp::Reverse, collections::hash_map::Entry, ops::Deref, rc::Rc};
+
+// Textures that become smaller than this size (largest side of top mip level in pixels) are
+// not demoted, they do not save much memory.
+const MIN_STREAMED_SIZE: usize = 256;
+
+// Max amount of textures that are re-uploaded because of streaming per frame.
+const MAX_STREAMING_UPLOADS: usize = 4;
+
+struct StreamingState {
+    texture: Texture,
+    // Amount of memory occupied by the texture for every amount of skipped mip levels, so it
+    // is the max amount of skipped mip levels too.
+    level_memory: Vec<usize>,
+    skipped_mips: usize,
+    last_used_frame: u64,
+}
+
+impl StreamingState {
+    fn new(texture: Texture, data: &TextureData) -> Self {
+        let max_skipped_mips = max_skipped_mips(data);
+        Self {
+            texture,
+            level_memory: (0..=max_skipped_mips)
+                .map(|skipped_mips| texture_memory(data, skipped_mips))
+                .collect(),
+            skipped_mips: 0,
+            last_used_frame: 0,
+        }
+    }
+
+    fn memory(&self) -> usize {
+        self.level_memory[self.skipped_mips]
+    }
+
+    fn max_skipped_mips(&self) -> usize {
+        self.level_memory.len() - 1
+    }
+}
+
+fn largest_side(kind: GpuTextureKind) -> usize {
+    match kind {
+        GpuTextureKind::Line { length } => length,
+        GpuTextureKind::Rectangle { width, height } | GpuTextureKind::Cube { width, height } => {
+            width.max(height)
+        }
+        GpuTextureKind::Volume {
+            width,
+            height,
+            depth,
+        } => width.max(height).max(depth),
+    }
+}
+
+// Amount of GPU memory occupied by a texture without given amount of top mip levels.
+fn texture_memory(data: &TextureData, skipped_mips: usize) -> usize {
+    let pixel_kind = PixelKind::from(data.pixel_kind());
+    let mip_count = data.mip_count() as usize;
+    match GpuTextureKind::from(data.kind()).mip_level(skipped_mips) {
+        Some(kind) => {
+            let uploaded_mips = if mip_count > 1 {
+                mip_count - skipped_mips
+            } else if matches!(
+                data.minification_filter(),
+                TextureMinificationFilter::Nearest | TextureMinificationFilter::Linear
+            ) {
+                1
+            } else {
+                // Mip levels are generated by GPU.
+                (usize::BITS - largest_side(kind).leading_zeros()) as usize
+            };
+            mip_chain_size_bytes(kind, pixel_kind, uploaded_mips)
+        }
+        None => 0,
+    }
+}
+
+fn max_skipped_mips(data: &TextureData) -> usize {
+    let mip_count = data.mip_count() as usize;
+    if data.is_never_stream() || data.is_render_target() || mip_count <= 1 {
+        return 0;
+    }
+
+    let kind = GpuTextureKind::from(data.kind());
+    let mut skipped_mips = 0;
+    while skipped_mips + 1 < mip_count
+        && kind
+            .mip_level(skipped_mips + 1)
+            .map_or(false, |kind| largest_side(kind) >= MIN_STREAMED_SIZE)
+    {
+        skipped_mips += 1;
+    }
+    skipped_mips
+}
+
+// Uploads mip chain of a texture without given amount of top mip levels.
+fn upload_mips(
+    texture: &mut GpuTexture,
+    state: &mut PipelineState,
+    data: &TextureData,
+    skipped_mips: usize,
+) -> Result<(), FrameworkError> {
+    let kind = GpuTextureKind::from(data.kind());
+    let pixel_kind = PixelKind::from(data.pixel_kind());
+    let mip_count = data.mip_count() as usize;
+    let skipped_mips = skipped_mips.min(mip_count.saturating_sub(1));
+    let offset = mip_chain_size_bytes(kind, pixel_kind, skipped_mips);
+
+    let binding = texture.bind_mut(state, 0).set_data(
+        kind.mip_level(skipped_mips).unwrap_or(kind),
+        pixel_kind,
+        mip_count - skipped_mips,
+        Some(&data.data()[offset..]),
+    )?;
+    if mip_count > 1 {
+        binding.set_max_level(mip_count - skipped_mips - 1);
+    }
+    Ok(())
+}
 
 #[derive(Default)]
 pub struct TextureCache {
     pub(in crate) map: FxHashMap<usize, CacheEntry<Rc<RefCell<GpuTexture>>>>,
+    streaming: FxHashMap<usize, StreamingState>,
+    budget: Option<usize>,
+    frame: u64,
+    statistics: TextureMemoryStatistics,
 }
 
 impl TextureCache {
+    /// Returns GPU texture for given texture and uploads it if needed. Every call counts as usage
+    /// of the texture for rendering, so it should be used only when the texture will be bound.
     pub fn get(
         &mut self,
         state: &mut PipelineState,
         texture: &Texture,
+    ) -> Option<Rc<RefCell<GpuTexture>>> {
+        let result = self.upload(state, texture);
+        // Usage is needed only to fit into the budget.
+        if result.is_some() && self.budget.is_some() {
+            if let Some(streaming) = self.streaming.get_mut(&texture.key()) {
+                streaming.last_used_frame = self.frame;
+            }
+        }
+        result
+    }
+
+    /// Uploads the texture to GPU if it is not uploaded yet, this does not count as usage of the
+    /// texture.
+    pub(in crate) fn upload(
+        &mut self,
+        state: &mut PipelineState,
+        texture: &Texture,
     ) -> Option<Rc<RefCell<GpuTexture>>> {
         scope_profile!();
 
         let key = texture.key();
+        let resource = texture;
         let texture = texture.state();
 
         if let TextureState::Ok(texture) = texture.deref() {
@@ -43,24 +206,26 @@ impl TextureCache {
                     // Data might change from last frame, so we have to check it and upload new if so.
                     let data_hash = texture.data_hash();
                     if entry.value_hash != data_hash {
+                        let streaming = self.streaming.get_mut(&key);
+                        let skipped_mips = streaming.as_ref().map_or(0, |s| s.skipped_mips);
                         let mut tex = entry.borrow_mut();
-                        if let Err(e) = tex.bind_mut(state, 0).set_data(
-                            texture.kind().into(),
-                            texture.pixel_kind().into(),
-                            texture.mip_count() as usize,
-                            Some(texture.data()),
-                        ) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!(
-                                    "Unable to upload new texture data to GPU. Reason: {:?}",
-                                    e
-                                ),
-                            )
+                        if let Err(e) = upload_mips(&mut tex, state, texture, skipped_mips) {
+                            log_error!("Unable to upload new texture data to GPU. Reason: {:?}", e)
                         } else {
                             drop(tex);
                             // TODO: Is this correct to overwrite hash only if we've succeeded?
                             entry.value_hash = data_hash;
+
+                            // Size of the texture might change too.
+                            if let Some(streaming) = streaming {
+                                let last_used_frame = streaming.last_used_frame;
+                                self.statistics.used -= streaming.memory();
+                                *streaming = StreamingState::new(resource.clone(), texture);
+                                streaming.skipped_mips =
+                                    skipped_mips.min(streaming.max_skipped_mips());
+                                streaming.last_used_frame = last_used_frame;
+                                self.statistics.used += streaming.memory();
+                            }
                         }
                     }
 
@@ -111,14 +276,15 @@ impl TextureCache {
                     ) {
                         Ok(texture) => texture,
                         Err(e) => {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!("Failed to create GPU texture. Reason: {:?}", e),
-                            );
+                            log_error!("Failed to create GPU texture. Reason: {:?}", e);
                             return None;
                         }
                     };
 
+                    let streaming = StreamingState::new(resource.clone(), texture);
+                    self.statistics.used += streaming.memory();
+                    self.streaming.insert(key, streaming);
+
                     e.insert(CacheEntry {
                         value: Rc::new(RefCell::new(gpu_texture)),
                         time_to_live: DEFAULT_RESOURCE_LIFETIME,
@@ -133,6 +299,131 @@ impl TextureCache {
         }
     }
 
+    /// Sets max amount of GPU memory (in bytes) that can be occupied by textures, `None` - no
+    /// limit. See module docs for more info.
+    pub fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    pub fn budget(&self) -> Option<usize> {
+        self.budget
+    }
+
+    pub fn statistics(&self) -> TextureMemoryStatistics {
+        self.statistics
+    }
+
+    // Re-uploads the texture without given amount of top mip levels.
+    fn set_skipped_mips(&mut self, state: &mut PipelineState, key: usize, skipped_mips: usize) {
+        let (entry, streaming) = match (self.map.get(&key), self.streaming.get_mut(&key)) {
+            (Some(entry), Some(streaming)) => (entry, streaming),
+            _ => return,
+        };
+
+        let resource = streaming.texture.clone();
+        let data = resource.state();
+        if let TextureState::Ok(data) = data.deref() {
+            match upload_mips(&mut entry.borrow_mut(), state, data, skipped_mips) {
+                Ok(()) => {
+                    self.statistics.used -= streaming.memory();
+                    if skipped_mips > streaming.skipped_mips {
+                        self.statistics.demotions += 1;
+                    } else {
+                        self.statistics.promotions += 1;
+                    }
+                    streaming.skipped_mips = skipped_mips;
+                    self.statistics.used += streaming.memory();
+                }
+                Err(e) => {
+                    log_error!("Failed to stream texture mip levels. Reason: {:?}", e);
+                    if skipped_mips > streaming.skipped_mips {
+                        // Do not try to demote the texture again.
+                        streaming.level_memory.truncate(streaming.skipped_mips + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Drops or restores top mip levels of textures to fit into memory budget, based on usage of
+    /// textures during previous frame. Must be called once per frame before rendering.
+    pub(in crate) fn update_streaming(&mut self, state: &mut PipelineState) {
+        scope_profile!();
+
+        self.statistics.demotions = 0;
+        self.statistics.promotions = 0;
+        self.statistics.budget = self.budget;
+
+        if self.budget.is_none() && self.statistics.demoted_textures == 0 {
+            // Streaming is disabled and there is nothing to restore.
+            return;
+        }
+
+        let mut uploads = 0;
+        match self.budget {
+            None => {
+                // Restore textures that were demoted when there was a budget.
+                let demoted = self
+                    .streaming
+                    .iter()
+                    .filter(|(_, s)| s.skipped_mips > 0)
+                    .map(|(key, _)| *key)
+                    .take(MAX_STREAMING_UPLOADS)
+                    .collect::<Vec<_>>();
+                for key in demoted {
+                    self.set_skipped_mips(state, key, 0);
+                }
+            }
+            Some(budget) => {
+                // Drop a mip level of the least recently used texture, larger textures first.
+                while self.statistics.used > budget && uploads < MAX_STREAMING_UPLOADS {
+                    let candidate = self
+                        .streaming
+                        .iter()
+                        .filter(|(_, s)| s.skipped_mips < s.max_skipped_mips())
+                        .min_by_key(|(_, s)| (s.last_used_frame, Reverse(s.memory())))
+                        .map(|(key, s)| (*key, s.skipped_mips));
+                    match candidate {
+                        Some((key, skipped_mips)) => {
+                            self.set_skipped_mips(state, key, skipped_mips + 1);
+                            uploads += 1;
+                        }
+                        None => break,
+                    }
+                }
+
+                // Restore a mip level of textures that were used during last frame, if it fits
+                // into the budget.
+                while uploads < MAX_STREAMING_UPLOADS {
+                    let candidate = self
+                        .streaming
+                        .iter()
+                        .filter(|(_, s)| s.skipped_mips > 0 && s.last_used_frame == self.frame)
+                        .map(|(key, s)| {
+                            let growth = s.level_memory[s.skipped_mips - 1] - s.memory();
+                            (*key, s.skipped_mips, growth)
+                        })
+                        .filter(|(_, _, growth)| self.statistics.used + growth <= budget)
+                        .min_by_key(|(_, _, growth)| *growth);
+                    match candidate {
+                        Some((key, skipped_mips, _)) => {
+                            self.set_skipped_mips(state, key, skipped_mips - 1);
+                            uploads += 1;
+                        }
+                        None => break,
+                    }
+                }
+            }
+        }
+
+        self.statistics.demoted_textures = self
+            .streaming
+            .values()
+            .filter(|s| s.skipped_mips > 0)
+            .count();
+        self.frame += 1;
+    }
+
     pub fn update(&mut self, dt: f32) {
         scope_profile!();
 
@@ -141,13 +432,168 @@ impl TextureCache {
         }
 
         self.map.retain(|_, v| v.time_to_live > 0.0);
+
+        let map = &self.map;
+        let used = &mut self.statistics.used;
+        self.streaming.retain(|key, streaming| {
+            let alive = map.contains_key(key);
+            if !alive {
+                *used -= streaming.memory();
+            }
+            alive
+        });
     }
 
     pub fn clear(&mut self) {
         self.map.clear();
+        self.streaming.clear();
+        self.statistics.used = 0;
     }
 
     pub fn unload(&mut self, texture: Texture) {
         self.map.remove(&texture.key());
+        if let Some(streaming) = self.streaming.remove(&texture.key()) {
+            self.statistics.used -= streaming.memory();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        renderer::{
+            cache::texture::max_skipped_mips,
+            framework::gpu_texture::{mip_chain_size_bytes, GpuTextureKind, PixelKind},
+        },
+        resource::texture::{Texture, TextureData, TextureKind, TexturePixelKind, TextureState},
+    };
+    use std::ops::Deref;
+
+    fn size(kind: GpuTextureKind) -> (usize, usize, usize) {
+        match kind {
+            GpuTextureKind::Line { length } => (length, 1, 1),
+            GpuTextureKind::Rectangle { width, height }
+            | GpuTextureKind::Cube { width, height } => (width, height, 1),
+            GpuTextureKind::Volume {
+                width,
+                height,
+                depth,
+            } => (width, height, depth),
+        }
+    }
+
+    fn mip_chain(width: u32, height: u32, mip_count: u32) -> TextureData {
+        let mut data = TextureData::from_mip_chain(
+            TextureKind::Rectangle { width, height },
+            TexturePixelKind::RGBA8,
+            mip_count,
+            Vec::new(),
+        );
+        data.set_never_stream(false);
+        data
+    }
+
+    #[test]
+    fn test_mip_level() {
+        let kind = GpuTextureKind::Rectangle {
+            width: 1024,
+            height: 512,
+        };
+        assert_eq!(size(kind.mip_level(0).unwrap()), (1024, 512, 1));
+        assert_eq!(size(kind.mip_level(1).unwrap()), (512, 256, 1));
+        assert_eq!(size(kind.mip_level(10).unwrap()), (1, 0, 1));
+        assert!(kind.mip_level(usize::BITS as usize).is_none());
+
+        let kind = GpuTextureKind::Volume {
+            width: 16,
+            height: 8,
+            depth: 4,
+        };
+        assert_eq!(size(kind.mip_level(2).unwrap()), (4, 2, 1));
+
+        let kind = GpuTextureKind::Line { length: 7 };
+        assert_eq!(size(kind.mip_level(1).unwrap()), (3, 1, 1));
+    }
+
+    #[test]
+    fn test_mip_chain_size_bytes() {
+        // Expected values are the sizes that were calculated by the texture upload code before
+        // the calculation was moved into a separate function.
+        let rectangle = GpuTextureKind::Rectangle {
+            width: 4,
+            height: 4,
+        };
+        assert_eq!(mip_chain_size_bytes(rectangle, PixelKind::RGBA8, 0), 0);
+        assert_eq!(mip_chain_size_bytes(rectangle, PixelKind::RGBA8, 1), 64);
+        assert_eq!(
+            mip_chain_size_bytes(rectangle, PixelKind::RGBA8, 3),
+            64 + 16 + 4
+        );
+        // Levels past 1x1 have zero size.
+        assert_eq!(
+            mip_chain_size_bytes(rectangle, PixelKind::RGBA8, 5),
+            64 + 16 + 4
+        );
+
+        // Compressed levels occupy at least one block.
+        let rectangle = GpuTextureKind::Rectangle {
+            width: 8,
+            height: 8,
+        };
+        assert_eq!(
+            mip_chain_size_bytes(rectangle, PixelKind::DXT1RGB, 4),
+            4 * 8 + 8 + 8 + 8
+        );
+        assert_eq!(
+            mip_chain_size_bytes(rectangle, PixelKind::DXT5RGBA, 2),
+            4 * 16 + 16
+        );
+
+        let cube = GpuTextureKind::Cube {
+            width: 2,
+            height: 2,
+        };
+        assert_eq!(
+            mip_chain_size_bytes(cube, PixelKind::RGBA8, 2),
+            6 * (16 + 4)
+        );
+
+        let volume = GpuTextureKind::Volume {
+            width: 4,
+            height: 4,
+            depth: 4,
+        };
+        assert_eq!(mip_chain_size_bytes(volume, PixelKind::R8, 3), 64 + 8 + 1);
+
+        let line = GpuTextureKind::Line { length: 8 };
+        assert_eq!(
+            mip_chain_size_bytes(line, PixelKind::RG8, 4),
+            2 * (8 + 4 + 2 + 1)
+        );
+    }
+
+    #[test]
+    fn test_max_skipped_mips() {
+        // Top mip level must not become smaller than 256 pixels.
+        assert_eq!(max_skipped_mips(&mip_chain(1024, 1024, 11)), 2);
+        assert_eq!(max_skipped_mips(&mip_chain(1024, 128, 11)), 2);
+        assert_eq!(max_skipped_mips(&mip_chain(300, 300, 9)), 0);
+        assert_eq!(max_skipped_mips(&mip_chain(256, 256, 9)), 0);
+        // At least one mip level must remain.
+        assert_eq!(max_skipped_mips(&mip_chain(1024, 1024, 2)), 1);
+        // Mip levels are generated by GPU.
+        assert_eq!(max_skipped_mips(&mip_chain(1024, 1024, 1)), 0);
+
+        let mut data = mip_chain(1024, 1024, 11);
+        data.set_never_stream(true);
+        assert_eq!(max_skipped_mips(&data), 0);
+
+        let render_target = Texture::new_render_target(1024, 1024);
+        let state = render_target.state();
+        if let TextureState::Ok(data) = state.deref() {
+            assert_eq!(max_skipped_mips(data), 0);
+        } else {
+            unreachable!()
+        }
     }
 }
@@ -5,7 +5,7 @@
 
 use crate::core::sstorage::ImmutableString;
 use crate::{
-    core::{algebra::Vector3, math::Rect, scope_profile},
+    core::{algebra::Vector3, math::Rect, profile_scope, scope_profile},
     renderer::framework::{
         error::FrameworkError,
         framebuffer::{DrawParameters, FrameBuffer},
@@ -90,6 +90,7 @@ impl DebugRenderer {
         camera: &Camera,
     ) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("DebugRenderer");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -5,23 +5,42 @@
 //! This renderer eventually will replace deferred renderer, because deferred renderer is too restrictive.
 //! For now it is used **only** to render transparent meshes (or any other mesh that has Forward render
 //! path).
+//!
+//! # Transparency
+//!
+//! Opaque instances of meshes with Forward render path are rendered first in batch order. Then every
+//! transparent instance (a surface marked as transparent or a mesh with opacity less than 1.0) is put
+//! into transparent render queue, the queue is sorted back-to-front by view-space depth of world
+//! bounding box center of an instance and rendered with depth test on and depth write off. Sorting is
+//! done per instance, so intersecting transparent geometry may still be blended in wrong order.
 
 use crate::core::sstorage::ImmutableString;
 use crate::{
-    core::{math::Rect, scope_profile},
+    core::{math::Rect, profile_scope, scope_profile},
     renderer::{
         apply_material,
-        batch::BatchStorage,
+        batch::{Batch, BatchStorage, SurfaceInstance},
         cache::{shader::ShaderCache, texture::TextureCache},
-        framework::{framebuffer::FrameBuffer, gpu_texture::GpuTexture, state::PipelineState},
+        framework::{
+            framebuffer::{DrawParameters, FrameBuffer},
+            gpu_texture::GpuTexture,
+            state::PipelineState,
+        },
         GeometryCache, MaterialContext, QualitySettings, RenderPassStatistics,
     },
     scene::{camera::Camera, mesh::RenderPath},
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 
 pub(in crate) struct ForwardRenderer {
     render_pass_name: ImmutableString,
+    transparent_queue: Vec<TransparentEntry>,
+}
+
+struct TransparentEntry {
+    batch_index: usize,
+    instance_index: usize,
+    depth: f32,
 }
 
 pub(in crate) struct ForwardRenderContext<'a, 'b> {
@@ -39,15 +58,22 @@ pub(in crate) struct ForwardRenderContext<'a, 'b> {
     pub black_dummy: Rc<RefCell<GpuTexture>>,
 }
 
+/// Sorts transparent entries back-to-front, so the farthest entry will be rendered first.
+fn sort_back_to_front(queue: &mut [TransparentEntry]) {
+    queue.sort_by(|a, b| b.depth.partial_cmp(&a.depth).unwrap_or(Ordering::Equal));
+}
+
 impl ForwardRenderer {
     pub(in crate) fn new() -> Self {
         Self {
             render_pass_name: ImmutableString::new("Forward"),
+            transparent_queue: Default::default(),
         }
     }
 
-    pub(in crate) fn render(&self, args: ForwardRenderContext) -> RenderPassStatistics {
+    pub(in crate) fn render(&mut self, args: ForwardRenderContext) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("Forward");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -66,59 +92,115 @@ impl ForwardRenderer {
             black_dummy,
         } = args;
 
-        let initial_view_projection = camera.view_projection_matrix();
+        let mut draw_instance =
+            |batch: &Batch, instance: &SurfaceInstance, force_no_depth_write: bool| {
+                let material = batch.material.lock();
+                let geometry = geom_cache.get(state, &batch.data);
 
-        for batch in batch_storage
-            .batches
-            .iter()
-            .filter(|b| b.render_path == RenderPath::Forward)
-        {
-            let material = batch.material.lock();
-            let geometry = geom_cache.get(state, &batch.data);
-
-            if let Some(render_pass) = shader_cache
-                .get(state, material.shader())
-                .and_then(|shader_set| shader_set.render_passes.get(&self.render_pass_name))
-            {
-                for instance in batch.instances.iter() {
-                    if camera.visibility_cache.is_visible(instance.owner) {
-                        let view_projection = if instance.depth_offset != 0.0 {
-                            let mut projection = camera.projection_matrix();
-                            projection[14] -= instance.depth_offset;
-                            projection * camera.view_matrix()
-                        } else {
-                            initial_view_projection
-                        };
-
-                        statistics += framebuffer.draw(
-                            geometry,
-                            state,
-                            viewport,
-                            &render_pass.program,
-                            &render_pass.draw_params,
-                            |mut program_binding| {
-                                apply_material(MaterialContext {
-                                    material: &*material,
-                                    program_binding: &mut program_binding,
-                                    texture_cache,
-                                    world_matrix: &instance.world_transform,
-                                    wvp_matrix: &(view_projection * instance.world_transform),
-                                    bone_matrices: &instance.bone_matrices,
-                                    use_skeletal_animation: batch.is_skinned,
-                                    camera_position: &camera.global_position(),
-                                    use_pom: quality_settings.use_parallax_mapping,
-                                    light_position: &Default::default(),
-                                    normal_dummy: normal_dummy.clone(),
-                                    white_dummy: white_dummy.clone(),
-                                    black_dummy: black_dummy.clone(),
-                                });
-                            },
-                        );
-                    }
+                if let Some(render_pass) = shader_cache
+                    .get(state, material.shader())
+                    .and_then(|shader_set| shader_set.render_passes.get(&self.render_pass_name))
+                {
+                    let mut projection = camera.projection_matrix();
+                    projection[14] -= instance.depth_offset;
+                    let view_projection = projection * camera.view_matrix();
+
+                    let draw_params = DrawParameters {
+                        cull_face: instance.cull_face(render_pass.draw_params.cull_face),
+                        depth_write: render_pass.draw_params.depth_write && !force_no_depth_write,
+                        ..render_pass.draw_params.clone()
+                    };
+
+                    statistics += framebuffer.draw(
+                        geometry,
+                        state,
+                        viewport,
+                        render_pass.program(instance.triplanar_mapping.is_some()),
+                        &draw_params,
+                        |mut program_binding| {
+                            apply_material(MaterialContext {
+                                material: &*material,
+                                program_binding: &mut program_binding,
+                                texture_cache,
+                                world_matrix: &instance.world_transform,
+                                wvp_matrix: &(view_projection * instance.world_transform),
+                                bone_matrices: &instance.bone_matrices,
+                                use_skeletal_animation: batch.is_skinned,
+                                camera_position: &camera.global_position(),
+                                use_pom: quality_settings.use_parallax_mapping,
+                                opacity: instance.opacity,
+                                color: instance.color,
+                                light_position: &Default::default(),
+                                uv_transform: &instance.uv_transform,
+                                mip_bias: instance.mip_bias,
+                                receive_shadows: instance.receive_shadows,
+                                alpha_cutoff: instance.alpha_cutoff,
+                                triplanar_mapping: instance.triplanar_mapping,
+                                instanced_bones: None,
+                                normal_dummy: normal_dummy.clone(),
+                                white_dummy: white_dummy.clone(),
+                                black_dummy: black_dummy.clone(),
+                            });
+                        },
+                    );
+                }
+            };
+
+        // Render opaque instances of forward batches first and collect transparent instances
+        // at the same time.
+        self.transparent_queue.clear();
+        let camera_position = camera.global_position();
+        let look = camera.look_vector();
+        for (batch_index, batch) in batch_storage.batches.iter().enumerate() {
+            for (instance_index, instance) in batch.instances.iter().enumerate() {
+                if !camera.visibility_cache.is_visible(instance.owner) {
+                    continue;
+                }
+
+                if instance.is_transparent {
+                    self.transparent_queue.push(TransparentEntry {
+                        batch_index,
+                        instance_index,
+                        depth: (instance.world_center - camera_position).dot(&look),
+                    });
+                } else if batch.render_path == RenderPath::Forward {
+                    draw_instance(batch, instance, false);
                 }
             }
         }
 
+        sort_back_to_front(&mut self.transparent_queue);
+
+        for entry in self.transparent_queue.iter() {
+            let batch = &batch_storage.batches[entry.batch_index];
+            draw_instance(batch, &batch.instances[entry.instance_index], true);
+        }
+
         statistics
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::renderer::forward_renderer::{sort_back_to_front, TransparentEntry};
+
+    #[test]
+    fn test_transparent_queue_sorting() {
+        let mut queue = [1.0, 5.0, -2.0, 3.0]
+            .iter()
+            .enumerate()
+            .map(|(i, &depth)| TransparentEntry {
+                batch_index: i,
+                instance_index: 0,
+                depth,
+            })
+            .collect::<Vec<_>>();
+
+        sort_back_to_front(&mut queue);
+
+        assert_eq!(
+            queue.iter().map(|e| e.batch_index).collect::<Vec<_>>(),
+            vec![1, 3, 0, 2]
+        );
+    }
+}
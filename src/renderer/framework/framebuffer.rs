@@ -191,6 +191,18 @@ impl FrameBuffer {
         state: &mut PipelineState,
         attachment_index: usize,
         face: CubeMapFace,
+    ) -> &mut Self {
+        self.set_cubemap_face_mip(state, attachment_index, face, 0)
+    }
+
+    /// Attaches given mip level of given face of a cube map to the color attachment, the
+    /// viewport must match the size of the mip level.
+    pub fn set_cubemap_face_mip(
+        &mut self,
+        state: &mut PipelineState,
+        attachment_index: usize,
+        face: CubeMapFace,
+        mip: usize,
     ) -> &mut Self {
         unsafe {
             state.set_framebuffer(self.fbo);
@@ -201,13 +213,37 @@ impl FrameBuffer {
                 glow::COLOR_ATTACHMENT0 + attachment_index as u32,
                 face.into_gl_value(),
                 Some(attachment.texture.borrow().id()),
-                0,
+                mip as i32,
             );
         }
 
         self
     }
 
+    /// Reads pixels of the first color attachment in given region, the attachment must have
+    /// RGBA8 format. Rows are stored from bottom to top, this is the same order that is used
+    /// to upload texture data, so the pixels can be uploaded back as is.
+    pub fn read_pixels_rgba8(&self, state: &mut PipelineState, region: Rect<i32>) -> Vec<u8> {
+        let mut pixels = vec![0; region.w().max(0) as usize * region.h().max(0) as usize * 4];
+
+        unsafe {
+            state.set_framebuffer(self.fbo);
+            state.gl.read_buffer(glow::COLOR_ATTACHMENT0);
+            state.gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+            state.gl.read_pixels(
+                region.x(),
+                region.y(),
+                region.w(),
+                region.h(),
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::Slice(&mut pixels),
+            );
+        }
+
+        pixels
+    }
+
     /// None is possible only for back buffer.
     pub fn id(&self) -> Option<glow::Framebuffer> {
         self.fbo
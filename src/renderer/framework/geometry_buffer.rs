@@ -2,7 +2,7 @@ use crate::scene::mesh::surface::SurfaceData;
 use crate::{
     core::{math::TriangleDefinition, scope_profile},
     renderer::framework::{error::FrameworkError, state::PipelineState},
-    scene::mesh::buffer::{VertexAttributeDataType, VertexBuffer},
+    scene::mesh::buffer::{VertexAttributeDataType, VertexAttributeUsage, VertexBuffer},
     utils::array_as_u8_slice,
 };
 use glow::HasContext;
@@ -335,6 +335,11 @@ impl GeometryBuffer {
         buffer.size_bytes = size;
     }
 
+    /// Returns amount of elements (triangles or lines) in the buffer.
+    pub fn element_count(&self) -> usize {
+        self.element_count.get()
+    }
+
     pub fn bind<'a>(&'a self, state: &'a mut PipelineState) -> GeometryBufferBinding<'a> {
         scope_profile!();
 
@@ -421,7 +426,8 @@ impl BufferBuilder {
                         (VertexAttributeDataType::U8, 4) => AttributeKind::UnsignedByte4,
                         _ => unreachable!(),
                     },
-                    normalized: false,
+                    // Colors are stored as u8 and must be mapped to [0; 1] range.
+                    normalized: a.usage == VertexAttributeUsage::Color,
                     divisor: 0,
                 })
                 .collect(),
@@ -6,7 +6,7 @@ use crate::{
         color::Color,
     },
     renderer::framework::{error::FrameworkError, gpu_texture::GpuTexture, state::PipelineState},
-    utils::log::{Log, MessageKind},
+    utils::log::{log_error, log_info},
 };
 use fxhash::FxHashMap;
 use glow::HasContext;
@@ -29,9 +29,20 @@ pub enum BuiltInUniform {
     WorldViewProjectionMatrix,
     BoneMatrices,
     UseSkeletalAnimation,
+    UseInstancedSkinning,
+    BoneMatricesTexture,
+    BoneMatricesRowOffset,
     CameraPosition,
     UsePOM,
     LightPosition,
+    Opacity,
+    Color,
+    UvTransform,
+    MipBias,
+    ReceiveShadows,
+    AlphaCutoff,
+    TriplanarSharpness,
+    TriplanarScale,
     // Must be last.
     Count,
 }
@@ -59,10 +70,7 @@ unsafe fn create_shader(
     let compilation_message = state.gl.get_shader_info_log(shader);
 
     if !status {
-        Log::writeln(
-            MessageKind::Error,
-            format!("Failed to compile {} shader: {}", name, compilation_message),
-        );
+        log_error!("Failed to compile {} shader: {}", name, compilation_message);
         Err(FrameworkError::ShaderCompilationFailed {
             shader_name: name,
             error_message: compilation_message,
@@ -79,7 +87,7 @@ unsafe fn create_shader(
             )
         };
 
-        Log::writeln(MessageKind::Information, msg);
+        log_info!("{}", msg);
 
         Ok(shader)
     }
@@ -406,12 +414,34 @@ fn fetch_built_in_uniform_locations(
         fetch_uniform_location(state, program, "rg3d_boneMatrices");
     locations[BuiltInUniform::UseSkeletalAnimation as usize] =
         fetch_uniform_location(state, program, "rg3d_useSkeletalAnimation");
+    locations[BuiltInUniform::UseInstancedSkinning as usize] =
+        fetch_uniform_location(state, program, "rg3d_useInstancedSkinning");
+    locations[BuiltInUniform::BoneMatricesTexture as usize] =
+        fetch_uniform_location(state, program, "rg3d_boneMatricesTexture");
+    locations[BuiltInUniform::BoneMatricesRowOffset as usize] =
+        fetch_uniform_location(state, program, "rg3d_boneMatricesRowOffset");
     locations[BuiltInUniform::CameraPosition as usize] =
         fetch_uniform_location(state, program, "rg3d_cameraPosition");
     locations[BuiltInUniform::UsePOM as usize] =
         fetch_uniform_location(state, program, "rg3d_usePOM");
     locations[BuiltInUniform::LightPosition as usize] =
         fetch_uniform_location(state, program, "rg3d_lightPosition");
+    locations[BuiltInUniform::Opacity as usize] =
+        fetch_uniform_location(state, program, "rg3d_opacity");
+    locations[BuiltInUniform::Color as usize] =
+        fetch_uniform_location(state, program, "rg3d_color");
+    locations[BuiltInUniform::UvTransform as usize] =
+        fetch_uniform_location(state, program, "rg3d_uvTransform");
+    locations[BuiltInUniform::MipBias as usize] =
+        fetch_uniform_location(state, program, "rg3d_mipBias");
+    locations[BuiltInUniform::ReceiveShadows as usize] =
+        fetch_uniform_location(state, program, "rg3d_receiveShadows");
+    locations[BuiltInUniform::AlphaCutoff as usize] =
+        fetch_uniform_location(state, program, "rg3d_alphaCutoff");
+    locations[BuiltInUniform::TriplanarSharpness as usize] =
+        fetch_uniform_location(state, program, "rg3d_triplanarSharpness");
+    locations[BuiltInUniform::TriplanarScale as usize] =
+        fetch_uniform_location(state, program, "rg3d_triplanarScale");
 
     locations
 }
@@ -446,10 +476,7 @@ impl GpuProgram {
             let link_message = state.gl.get_program_info_log(program);
 
             if !status {
-                Log::writeln(
-                    MessageKind::Error,
-                    format!("Failed to link {} shader: {}", name, link_message),
-                );
+                log_error!("Failed to link {} shader: {}", name, link_message);
                 Err(FrameworkError::ShaderLinkingFailed {
                     shader_name: name.to_owned(),
                     error_message: link_message,
@@ -465,7 +492,7 @@ impl GpuProgram {
                         )
                     };
 
-                Log::writeln(MessageKind::Information, msg);
+                log_info!("{}", msg);
 
                 Ok(Self {
                     state,
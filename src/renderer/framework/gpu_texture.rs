@@ -57,6 +57,33 @@ impl From<TextureKind> for GpuTextureKind {
 }
 
 impl GpuTextureKind {
+    /// Returns kind of given mip level of a texture, `None` if the level does not exist.
+    pub fn mip_level(&self, level: usize) -> Option<Self> {
+        let level = level as u32;
+        match *self {
+            Self::Line { length } => Some(Self::Line {
+                length: length.checked_shr(level)?,
+            }),
+            Self::Rectangle { width, height } => Some(Self::Rectangle {
+                width: width.checked_shr(level)?,
+                height: height.checked_shr(level)?,
+            }),
+            Self::Cube { width, height } => Some(Self::Cube {
+                width: width.checked_shr(level)?,
+                height: height.checked_shr(level)?,
+            }),
+            Self::Volume {
+                width,
+                height,
+                depth,
+            } => Some(Self::Volume {
+                width: width.checked_shr(level)?,
+                height: height.checked_shr(level)?,
+                depth: depth.checked_shr(level)?,
+            }),
+        }
+    }
+
     fn gl_texture_target(&self) -> u32 {
         match self {
             Self::Line { .. } => glow::TEXTURE_1D,
@@ -331,6 +358,65 @@ fn image_1d_size_bytes(pixel_kind: PixelKind, length: usize) -> usize {
     }
 }
 
+/// Calculates amount of bytes that is needed to store given amount of mip levels of a texture.
+/// It is used to validate texture data and to track GPU memory used by textures.
+pub fn mip_chain_size_bytes(
+    kind: GpuTextureKind,
+    pixel_kind: PixelKind,
+    mip_count: usize,
+) -> usize {
+    let mut desired_byte_count = 0;
+
+    'mip_loop: for mip in 0..mip_count {
+        match kind {
+            GpuTextureKind::Line { length } => {
+                if let Some(length) = length.checked_shr(mip as u32) {
+                    desired_byte_count += image_1d_size_bytes(pixel_kind, length);
+                } else {
+                    break 'mip_loop;
+                }
+            }
+            GpuTextureKind::Rectangle { width, height } => {
+                if let (Some(width), Some(height)) = (
+                    width.checked_shr(mip as u32),
+                    height.checked_shr(mip as u32),
+                ) {
+                    desired_byte_count += image_2d_size_bytes(pixel_kind, width, height);
+                } else {
+                    break 'mip_loop;
+                }
+            }
+            GpuTextureKind::Cube { width, height } => {
+                if let (Some(width), Some(height)) = (
+                    width.checked_shr(mip as u32),
+                    height.checked_shr(mip as u32),
+                ) {
+                    desired_byte_count += 6 * image_2d_size_bytes(pixel_kind, width, height);
+                } else {
+                    break 'mip_loop;
+                }
+            }
+            GpuTextureKind::Volume {
+                width,
+                height,
+                depth,
+            } => {
+                if let (Some(width), Some(height), Some(depth)) = (
+                    width.checked_shr(mip as u32),
+                    height.checked_shr(mip as u32),
+                    depth.checked_shr(mip as u32),
+                ) {
+                    desired_byte_count += image_3d_size_bytes(pixel_kind, width, height, depth);
+                } else {
+                    break 'mip_loop;
+                }
+            }
+        };
+    }
+
+    desired_byte_count
+}
+
 #[derive(Copy, Clone, PartialOrd, PartialEq, Eq, Hash)]
 #[repr(u32)]
 pub enum MagnificationFilter {
@@ -541,6 +627,19 @@ impl<'a> TextureBinding<'a> {
         self
     }
 
+    /// Sets index of the last mip level that can be sampled, it must be set after new data with
+    /// less mip levels was uploaded, otherwise the texture will be incomplete.
+    pub fn set_max_level(self, max_level: usize) -> Self {
+        unsafe {
+            self.state.gl.tex_parameter_i32(
+                self.texture.kind.gl_texture_target(),
+                glow::TEXTURE_MAX_LEVEL,
+                max_level as i32,
+            );
+        }
+        self
+    }
+
     pub fn generate_mip_maps(self) -> Self {
         unsafe {
             self.state
@@ -559,54 +658,7 @@ impl<'a> TextureBinding<'a> {
     ) -> Result<Self, FrameworkError> {
         let mip_count = mip_count.max(1);
 
-        let mut desired_byte_count = 0;
-
-        'mip_loop: for mip in 0..mip_count {
-            match kind {
-                GpuTextureKind::Line { length } => {
-                    if let Some(length) = length.checked_shr(mip as u32) {
-                        desired_byte_count += image_1d_size_bytes(pixel_kind, length);
-                    } else {
-                        break 'mip_loop;
-                    }
-                }
-                GpuTextureKind::Rectangle { width, height } => {
-                    if let (Some(width), Some(height)) = (
-                        width.checked_shr(mip as u32),
-                        height.checked_shr(mip as u32),
-                    ) {
-                        desired_byte_count += image_2d_size_bytes(pixel_kind, width, height);
-                    } else {
-                        break 'mip_loop;
-                    }
-                }
-                GpuTextureKind::Cube { width, height } => {
-                    if let (Some(width), Some(height)) = (
-                        width.checked_shr(mip as u32),
-                        height.checked_shr(mip as u32),
-                    ) {
-                        desired_byte_count += 6 * image_2d_size_bytes(pixel_kind, width, height);
-                    } else {
-                        break 'mip_loop;
-                    }
-                }
-                GpuTextureKind::Volume {
-                    width,
-                    height,
-                    depth,
-                } => {
-                    if let (Some(width), Some(height), Some(depth)) = (
-                        width.checked_shr(mip as u32),
-                        height.checked_shr(mip as u32),
-                        depth.checked_shr(mip as u32),
-                    ) {
-                        desired_byte_count += image_3d_size_bytes(pixel_kind, width, height, depth);
-                    } else {
-                        break 'mip_loop;
-                    }
-                }
-            };
-        }
+        let desired_byte_count = mip_chain_size_bytes(kind, pixel_kind, mip_count);
 
         if let Some(data) = data {
             let actual_data_size = data.len();
@@ -35,6 +35,23 @@ float S_LightDistanceAttenuation(float distance, float radius)
     return attenuation;
 }
 
+// Returns attenuation of a point or spot light depending on its falloff mode (must be in sync
+// with LightFalloff):
+// 0 - (1 - d² / r²) ^ exponent, exponent of 1.0 gives the same result as S_LightDistanceAttenuation.
+// 1 - physically-based inverse square falloff smoothly clipped at the radius.
+float S_LightFalloff(float distance, float radius, int mode, float exponent)
+{
+    if (mode == 1) {
+        float ratio = distance / radius;
+        float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+        float clampedDistance = max(distance, 0.01);
+        return window * window / (clampedDistance * clampedDistance);
+    }
+    float attenuation = S_LightDistanceAttenuation(distance, radius);
+    // Keep default falloff bit-exact, pow is not exact on some GPUs.
+    return exponent == 1.0 ? attenuation : pow(attenuation, exponent);
+}
+
 // Projects world space position (typical use case) by given matrix.
 vec3 S_Project(vec3 worldPosition, mat4 matrix)
 {
@@ -179,53 +196,96 @@ bool S_RaySphereIntersection(vec3 origin, vec3 dir, vec3 center, float radius, o
     return S_SolveQuadraticEq(a, b, c, minT, maxT);
 }
 
+// Shadow filters, must be in sync with ShadowFilter enum.
+const int S_SHADOW_FILTER_OFF = 0;
+const int S_SHADOW_FILTER_PCF_2X2 = 1;
+const int S_SHADOW_FILTER_PCF_4X4 = 2;
+const int S_SHADOW_FILTER_POISSON = 3;
+
+const vec2 Internal_PoissonDisk[16] = vec2[16] (
+    vec2(-0.94201624, -0.39906216), vec2(0.94558609, -0.76890725),
+    vec2(-0.09418410, -0.92938870), vec2(0.34495938, 0.29387760),
+    vec2(-0.91588581, 0.45771432), vec2(-0.81544232, -0.87912464),
+    vec2(-0.38277543, 0.27676845), vec2(0.97484398, 0.75648379),
+    vec2(0.44323325, -0.97511554), vec2(0.53742981, -0.47373420),
+    vec2(-0.26496911, -0.41893023), vec2(0.79197514, 0.19090188),
+    vec2(-0.24188840, 0.99706507), vec2(-0.81409955, 0.91437590),
+    vec2(0.19984126, 0.78641367), vec2(0.14383161, -0.14100790)
+);
+
+int Internal_ShadowFilterTapCount(int shadowFilter)
+{
+    if (shadowFilter == S_SHADOW_FILTER_PCF_2X2) {
+        return 4;
+    } else if (shadowFilter == S_SHADOW_FILTER_PCF_4X4 || shadowFilter == S_SHADOW_FILTER_POISSON) {
+        return 16;
+    } else {
+        return 1;
+    }
+}
+
+// Returns offset of i-th tap of a filter kernel in texels.
+vec2 Internal_ShadowFilterTap(int shadowFilter, int i)
+{
+    if (shadowFilter == S_SHADOW_FILTER_PCF_2X2) {
+        return vec2(float(i % 2), float(i / 2)) - 0.5;
+    } else if (shadowFilter == S_SHADOW_FILTER_PCF_4X4) {
+        return vec2(float(i % 4), float(i / 4)) - 1.5;
+    } else if (shadowFilter == S_SHADOW_FILTER_POISSON) {
+        return Internal_PoissonDisk[i] * 2.0;
+    } else {
+        return vec2(0.0);
+    }
+}
+
+// Scales shadow bias by the slope of a surface relative to the light, surfaces that are almost parallel
+// to light rays need much bigger bias to not have "shadow acne". Both vectors must be normalized.
+float S_SlopeScaledBias(float shadowBias, vec3 normal, vec3 toLight)
+{
+    float cosAngle = clamp(dot(normal, toLight), 0.01, 1.0);
+    float tanAngle = sqrt(1.0 - cosAngle * cosAngle) / cosAngle;
+    return shadowBias * (1.0 + min(tanAngle, 5.0));
+}
+
 // Calculates point shadow factor where 1.0 - no shadow, 0.0 - fully in shadow.
 // Why value is inversed? To be able to directly multiply color to shadow factor.
 float S_PointShadow(
     bool shadowsEnabled,
-    bool softShadows,
+    int shadowFilter,
     float fragmentDistance,
     float shadowBias,
+    vec3 fragmentNormal,
     vec3 toLight,
+    float shadowMapInvSize,
     in samplerCube shadowMap)
 {
     if (shadowsEnabled)
     {
-        float biasedFragmentDistance = fragmentDistance - shadowBias;
-
-        if (softShadows)
-        {
-            const int samples = 20;
+        float biasedFragmentDistance = fragmentDistance - S_SlopeScaledBias(shadowBias, fragmentNormal, toLight);
 
-            const vec3 directions[samples] = vec3[samples] (
-            vec3(1, 1, 1), vec3(1, -1, 1), vec3(-1, -1, 1), vec3(-1, 1, 1),
-            vec3(1, 1, -1), vec3(1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
-            vec3(1, 1, 0), vec3(1, -1, 0), vec3(-1, -1, 0), vec3(-1, 1, 0),
-            vec3(1, 0, 1), vec3(-1, 0, 1), vec3(1, 0, -1), vec3(-1, 0, -1),
-            vec3(0, 1, 1), vec3(0, -1, 1), vec3(0, -1, -1), vec3(0, 1, -1)
-            );
+        // Taps are made on a plane that is perpendicular to fetch direction, this way kernel has the same
+        // shape on every face of the cube map and taps near edges of a face correctly fall on adjacent faces.
+        vec3 direction = -toLight;
+        vec3 up = abs(direction.y) < 0.99 ? vec3(0.0, 1.0, 0.0) : vec3(1.0, 0.0, 0.0);
+        vec3 tangent = normalize(cross(up, direction));
+        vec3 bitangent = cross(direction, tangent);
 
-            const float diskRadius = 0.0025;
+        // Size of a texel of a cube map face at unit distance from the center of the cube.
+        float texelSize = 2.0 * shadowMapInvSize;
 
-            float accumulator = 0.0;
-
-            for (int i = 0; i < samples; ++i)
+        int tapCount = Internal_ShadowFilterTapCount(shadowFilter);
+        float accumulator = 0.0;
+        for (int i = 0; i < tapCount; ++i)
+        {
+            vec2 offset = Internal_ShadowFilterTap(shadowFilter, i) * texelSize;
+            vec3 fetchDirection = direction + tangent * offset.x + bitangent * offset.y;
+            if (biasedFragmentDistance > texture(shadowMap, fetchDirection).r)
             {
-                vec3 fetchDirection = -toLight + directions[i] * diskRadius;
-                float shadowDistanceToLight = texture(shadowMap, fetchDirection).r;
-                if (biasedFragmentDistance > shadowDistanceToLight)
-                {
-                    accumulator += 1.0;
-                }
+                accumulator += 1.0;
             }
-
-            return clamp(1.0 - accumulator / float(samples), 0.0, 1.0);
-        }
-        else
-        {
-            float shadowDistanceToLight = texture(shadowMap, -toLight).r;
-            return biasedFragmentDistance > shadowDistanceToLight ? 0.0 : 1.0;
         }
+
+        return clamp(1.0 - accumulator / float(tapCount), 0.0, 1.0);
     } else {
         return 1.0; // No shadow
     }
@@ -235,9 +295,11 @@ float S_PointShadow(
 // Why value is inversed? To be able to directly multiply color to shadow factor.
 float S_SpotShadowFactor(
     bool shadowsEnabled,
-    bool softShadows,
+    int shadowFilter,
     float shadowBias,
     vec3 fragmentPosition,
+    vec3 fragmentNormal,
+    vec3 toLight,
     mat4 lightViewProjMatrix,
     float shadowMapInvSize,
     in sampler2D spotShadowTexture)
@@ -246,30 +308,20 @@ float S_SpotShadowFactor(
     {
         vec3 lightSpacePosition = S_Project(fragmentPosition, lightViewProjMatrix);
 
-        float biasedLightSpaceFragmentDepth = lightSpacePosition.z - shadowBias;
+        float biasedLightSpaceFragmentDepth = lightSpacePosition.z - S_SlopeScaledBias(shadowBias, fragmentNormal, toLight);
 
-        if (softShadows)
+        int tapCount = Internal_ShadowFilterTapCount(shadowFilter);
+        float accumulator = 0.0;
+        for (int i = 0; i < tapCount; ++i)
         {
-            float accumulator = 0.0;
-
-            for (float y = -0.5; y <= 0.5; y += 0.5)
+            vec2 fetchTexCoord = lightSpacePosition.xy + Internal_ShadowFilterTap(shadowFilter, i) * shadowMapInvSize;
+            if (biasedLightSpaceFragmentDepth > texture(spotShadowTexture, fetchTexCoord).r)
             {
-                for (float x = -0.5; x <= 0.5; x += 0.5)
-                {
-                    vec2 fetchTexCoord = lightSpacePosition.xy + vec2(x, y) * shadowMapInvSize;
-                    if (biasedLightSpaceFragmentDepth > texture(spotShadowTexture, fetchTexCoord).r)
-                    {
-                        accumulator += 1.0;
-                    }
-                }
+                accumulator += 1.0;
             }
-
-            return clamp(1.0 - accumulator / 9.0, 0.0, 1.0);
-        }
-        else
-        {
-            return biasedLightSpaceFragmentDepth > texture(spotShadowTexture, lightSpacePosition.xy).r ? 0.0 : 1.0;
         }
+
+        return clamp(1.0 - accumulator / float(tapCount), 0.0, 1.0);
     } else {
         return 1.0; // No shadow
     }
@@ -314,6 +366,57 @@ vec2 S_ComputeParallaxTextureCoordinates(in sampler2D heightTexture, vec3 eyeVec
     return prev * weight + currentTexCoords * (1.0 - weight);
 }
 
+// Blend weights of three planar projections along world axes, higher sharpness makes transitions
+// between projections narrower.
+vec3 S_TriplanarWeights(vec3 normal, float sharpness) {
+    vec3 weights = pow(abs(normal), vec3(sharpness));
+    return weights / max(weights.x + weights.y + weights.z, 0.00001);
+}
+
+// Texture coordinates of the projections along X, Y and Z axes. Coordinates of projections on
+// negative sides are mirrored, so textures are not flipped on opposite faces.
+void Internal_TriplanarCoords(vec3 position, vec3 normal, out vec2 uvX, out vec2 uvY, out vec2 uvZ) {
+    vec3 axisSign = vec3(normal.x < 0.0 ? -1.0 : 1.0, normal.y < 0.0 ? -1.0 : 1.0, normal.z < 0.0 ? -1.0 : 1.0);
+    uvX = vec2(position.z * axisSign.x, position.y);
+    uvY = vec2(position.x * axisSign.y, position.z);
+    uvZ = vec2(-position.x * axisSign.z, position.y);
+}
+
+vec4 S_TriplanarTexture(in sampler2D tex, vec3 position, vec3 normal, vec3 weights, float mipBias) {
+    vec2 uvX, uvY, uvZ;
+    Internal_TriplanarCoords(position, normal, uvX, uvY, uvZ);
+    return texture(tex, uvX, mipBias) * weights.x
+        + texture(tex, uvY, mipBias) * weights.y
+        + texture(tex, uvZ, mipBias) * weights.z;
+}
+
+// Samples tangent space normal map with triplanar projection and returns world space normal. Each
+// projection has its own tangent frame (projection axes swizzled into world space), normals are
+// combined with the surface normal using whiteout blend, which keeps lighting continuous across
+// blend regions.
+vec3 S_TriplanarNormal(in sampler2D normalTexture, vec3 position, vec3 normal, vec3 weights, float mipBias) {
+    vec2 uvX, uvY, uvZ;
+    Internal_TriplanarCoords(position, normal, uvX, uvY, uvZ);
+
+    vec3 tnX = texture(normalTexture, uvX, mipBias).xyz * 2.0 - 1.0;
+    vec3 tnY = texture(normalTexture, uvY, mipBias).xyz * 2.0 - 1.0;
+    vec3 tnZ = texture(normalTexture, uvZ, mipBias).xyz * 2.0 - 1.0;
+
+    // Mirrored projections have mirrored tangents.
+    vec3 axisSign = vec3(normal.x < 0.0 ? -1.0 : 1.0, normal.y < 0.0 ? -1.0 : 1.0, normal.z < 0.0 ? -1.0 : 1.0);
+    tnX.x *= axisSign.x;
+    tnY.x *= axisSign.y;
+    tnZ.x *= -axisSign.z;
+
+    // Whiteout blend with the surface normal swizzled into space of each projection.
+    tnX = vec3(tnX.xy + normal.zy, abs(tnX.z) * normal.x);
+    tnY = vec3(tnY.xy + normal.xz, abs(tnY.z) * normal.y);
+    tnZ = vec3(tnZ.xy + normal.xy, abs(tnZ.z) * normal.z);
+
+    // Swizzle back to world space.
+    return normalize(tnX.zyx * weights.x + tnY.xzy * weights.y + tnZ.xyz * weights.z);
+}
+
 vec4 S_LinearToSRGB(vec4 color) {
     vec3 a = 12.92 * color.rgb;
     vec3 b = 1.055 * pow(color.rgb, vec3(1.0 / 2.4)) - 0.055;
@@ -1,7 +1,8 @@
 use crate::{
     core::{color::Color, math::Rect, visitor::prelude::*},
     renderer::framework::framebuffer::{CullFace, DrawParameters},
-    utils::log::{Log, MessageKind},
+    scene::mesh::vertex::VERTEX_COLOR_DESCRIPTOR,
+    utils::log::log_error,
 };
 use glow::{Framebuffer, HasContext};
 use serde::Deserialize;
@@ -278,6 +279,22 @@ impl PipelineState {
     pub fn new(context: glow::Context) -> Self {
         unsafe {
             context.depth_func(CompareFunc::default() as u32);
+
+            // Vertex colors are optional, when a mesh does not have them, shaders will receive
+            // this value. White color does not affect anything when multiplied with other colors.
+            context.vertex_attrib_4_f32(
+                VERTEX_COLOR_DESCRIPTOR.shader_location as u32,
+                1.0,
+                1.0,
+                1.0,
+                1.0,
+            );
+
+            // Shadow maps of point lights are filtered across edges of cube map faces, without
+            // seamless filtering there will be visible seams. Cube maps are always seamless in
+            // WebGL 2.
+            #[cfg(not(target_arch = "wasm32"))]
+            context.enable(glow::TEXTURE_CUBE_MAP_SEAMLESS);
         }
 
         Self {
@@ -692,15 +709,12 @@ impl PipelineState {
                     _ => "Unknown",
                 };
 
-                Log::writeln(
-                    MessageKind::Error,
-                    format!("{} error has occurred! Stability is not guaranteed!", code),
-                );
+                log_error!("{} error has occurred! Stability is not guaranteed!", code);
 
                 #[cfg(not(target_arch = "wasm32"))]
                 {
                     for entry in self.gl.get_debug_message_log(64) {
-                        Log::writeln(MessageKind::Error, format!("OpenGL message: {:?}", entry))
+                        log_error!("OpenGL message: {:?}", entry)
                     }
                 }
 
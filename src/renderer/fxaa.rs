@@ -2,6 +2,7 @@ use crate::{
     core::{
         algebra::{Matrix4, Vector2, Vector3},
         math::Rect,
+        profile_scope,
         sstorage::ImmutableString,
     },
     renderer::{
@@ -68,6 +69,8 @@ impl FxaaRenderer {
         frame_texture: Rc<RefCell<GpuTexture>>,
         frame_buffer: &mut FrameBuffer,
     ) -> RenderPassStatistics {
+        profile_scope!("FXAA");
+
         let mut statistics = RenderPassStatistics::default();
 
         let frame_matrix = Matrix4::new_orthographic(
@@ -16,24 +16,25 @@ use crate::{
         algebra::{Matrix4, Vector2},
         color::Color,
         math::Rect,
-        scope_profile,
+        profile_scope, scope_profile,
     },
     renderer::{
         apply_material,
-        batch::BatchStorage,
+        batch::{BatchStorage, SurfaceInstance},
         cache::shader::ShaderCache,
         framework::{
             error::FrameworkError,
             framebuffer::{Attachment, AttachmentKind, DrawParameters, FrameBuffer},
-            gpu_program::GpuProgramBinding,
+            gpu_program::{BuiltInUniform, GpuProgramBinding},
             gpu_texture::{
                 Coordinate, GpuTexture, GpuTextureKind, MagnificationFilter, MinificationFilter,
                 PixelKind, WrapMode,
             },
             state::{BlendFactor, BlendFunc, PipelineState},
         },
-        gbuffer::decal::DecalShader,
-        GeometryCache, MaterialContext, RenderPassStatistics, TextureCache,
+        gbuffer::{decal::DecalShader, skinning::SkinnedInstancing},
+        GeometryCache, InstancedBones, MaterialContext, RenderPassStatistics, SkinningStatistics,
+        TextureCache,
     },
     scene::{
         camera::Camera, graph::Graph, mesh::surface::SurfaceData, mesh::RenderPath, node::Node,
@@ -42,6 +43,7 @@ use crate::{
 use std::{cell::RefCell, rc::Rc};
 
 mod decal;
+mod skinning;
 
 pub struct GBuffer {
     framebuffer: FrameBuffer,
@@ -51,6 +53,7 @@ pub struct GBuffer {
     cube: GeometryBuffer,
     decal_shader: DecalShader,
     render_pass_name: ImmutableString,
+    skinned_instancing: SkinnedInstancing,
 }
 
 pub(in crate) struct GBufferRenderContext<'a, 'b> {
@@ -66,6 +69,8 @@ pub(in crate) struct GBufferRenderContext<'a, 'b> {
     pub normal_dummy: Rc<RefCell<GpuTexture>>,
     pub black_dummy: Rc<RefCell<GpuTexture>>,
     pub use_parallax_mapping: bool,
+    pub use_skinned_instancing: bool,
+    pub skinning_statistics: &'a mut SkinningStatistics,
     pub graph: &'b Graph,
 }
 
@@ -222,6 +227,7 @@ impl GBuffer {
             ),
             decal_framebuffer,
             render_pass_name: ImmutableString::new("GBuffer"),
+            skinned_instancing: SkinnedInstancing::new(state)?,
         })
     }
 
@@ -253,9 +259,26 @@ impl GBuffer {
         self.framebuffer.color_attachments()[4].texture.clone()
     }
 
+    pub(in crate) fn framebuffer_mut(&mut self) -> &mut FrameBuffer {
+        &mut self.framebuffer
+    }
+
+    /// Clears every attachment of the G-Buffer, must be called before [`Self::fill`].
+    pub(in crate) fn clear(&mut self, state: &mut PipelineState) {
+        let viewport = Rect::new(0, 0, self.width, self.height);
+        self.framebuffer.clear(
+            state,
+            viewport,
+            Some(Color::from_rgba(0, 0, 0, 0)),
+            Some(1.0),
+            Some(0),
+        );
+    }
+
     #[must_use]
     pub(in crate) fn fill(&mut self, args: GBufferRenderContext) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("GBuffer");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -267,6 +290,8 @@ impl GBuffer {
             texture_cache,
             shader_cache,
             use_parallax_mapping,
+            use_skinned_instancing,
+            skinning_statistics,
             white_dummy,
             normal_dummy,
             black_dummy,
@@ -275,20 +300,19 @@ impl GBuffer {
         } = args;
 
         let viewport = Rect::new(0, 0, self.width, self.height);
-        self.framebuffer.clear(
-            state,
-            viewport,
-            Some(Color::from_rgba(0, 0, 0, 0)),
-            Some(1.0),
-            Some(0),
-        );
 
         let initial_view_projection = camera.view_projection_matrix();
 
-        for batch in batch_storage
+        if use_skinned_instancing {
+            self.skinned_instancing
+                .prepare(state, batch_storage, camera);
+        }
+
+        for (batch_index, batch) in batch_storage
             .batches
             .iter()
-            .filter(|b| b.render_path == RenderPath::Deferred)
+            .enumerate()
+            .filter(|(_, b)| b.render_path == RenderPath::Deferred)
         {
             let material = batch.material.lock();
             let geometry = geom_cache.get(state, &batch.data);
@@ -297,8 +321,15 @@ impl GBuffer {
                 .get(state, material.shader())
                 .and_then(|shader_set| shader_set.render_passes.get(&self.render_pass_name))
             {
-                for instance in batch.instances.iter() {
-                    if camera.visibility_cache.is_visible(instance.owner) {
+                let framebuffer = &mut self.framebuffer;
+
+                // Draws either a single instance or `count` instances which bone matrices are
+                // stored in the bone matrices texture.
+                let mut draw =
+                    |state: &mut PipelineState,
+                     instance: &SurfaceInstance,
+                     count: usize,
+                     instanced_bones: Option<InstancedBones>| {
                         let apply_uniforms = |mut program_binding: GpuProgramBinding| {
                             let view_projection = if instance.depth_offset != 0.0 {
                                 let mut projection = camera.projection_matrix();
@@ -318,21 +349,86 @@ impl GBuffer {
                                 use_skeletal_animation: batch.is_skinned,
                                 camera_position: &camera.global_position(),
                                 use_pom: use_parallax_mapping,
+                                opacity: instance.opacity,
+                                // Color of instanced skinned meshes is stored in the bone
+                                // matrices texture.
+                                color: if instanced_bones.is_some() {
+                                    Color::WHITE
+                                } else {
+                                    instance.color
+                                },
                                 light_position: &Default::default(),
+                                uv_transform: &instance.uv_transform,
+                                mip_bias: instance.mip_bias,
+                                receive_shadows: instance.receive_shadows,
+                                alpha_cutoff: instance.alpha_cutoff,
+                                triplanar_mapping: instance.triplanar_mapping,
+                                instanced_bones,
                                 normal_dummy: normal_dummy.clone(),
                                 white_dummy: white_dummy.clone(),
                                 black_dummy: black_dummy.clone(),
                             });
                         };
 
-                        statistics += self.framebuffer.draw(
-                            geometry,
-                            state,
-                            viewport,
-                            &render_pass.program,
-                            &render_pass.draw_params,
-                            apply_uniforms,
-                        );
+                        let draw_params = DrawParameters {
+                            cull_face: instance.cull_face(render_pass.draw_params.cull_face),
+                            ..render_pass.draw_params.clone()
+                        };
+
+                        if count > 1 {
+                            framebuffer.draw_instances(
+                                count,
+                                geometry,
+                                state,
+                                viewport,
+                                render_pass.program(instance.triplanar_mapping.is_some()),
+                                &draw_params,
+                                apply_uniforms,
+                            )
+                        } else {
+                            framebuffer.draw(
+                                geometry,
+                                state,
+                                viewport,
+                                render_pass.program(instance.triplanar_mapping.is_some()),
+                                &draw_params,
+                                apply_uniforms,
+                            )
+                        }
+                    };
+
+                // Custom shaders may not support instanced skinning.
+                let supports_instancing = render_pass.program.built_in_uniform_locations
+                    [BuiltInUniform::UseInstancedSkinning as usize]
+                    .is_some();
+
+                if use_skinned_instancing && batch.is_skinned && supports_instancing {
+                    for group in self.skinned_instancing.groups(batch_index) {
+                        if let Some(row_offset) = group.row_offset {
+                            let count = group.instances.len();
+                            statistics += draw(
+                                state,
+                                &batch.instances[group.instances[0]],
+                                count,
+                                Some(InstancedBones {
+                                    texture: self.skinned_instancing.texture(),
+                                    row_offset,
+                                }),
+                            );
+                            skinning_statistics.instanced_draw_calls += 1;
+                            skinning_statistics.instances_drawn += count;
+                        } else {
+                            for &index in group.instances.iter() {
+                                statistics += draw(state, &batch.instances[index], 1, None);
+                            }
+                        }
+                    }
+                } else {
+                    // Transparent instances are rendered by forward renderer.
+                    for instance in batch.instances.iter().filter(|i| !i.is_transparent) {
+                        if camera.visibility_cache.is_visible(instance.owner) {
+                            statistics += draw(state, instance, 1, None);
+                        }
                     }
                 }
             }
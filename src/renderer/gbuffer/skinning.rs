@@ -0,0 +1,182 @@
+//! Instanced rendering of skinned surfaces.
+//!
+//! Bone matrices of every visible instance of skinned surfaces are packed into a single float
+//! texture each frame: one row per instance, one texel per column of a matrix. Color of an
+//! instance is stored in the last texel of its row. Instances of the same surface that share
+//! the rest of their uniforms are then drawn by a single instanced draw call, vertex shader
+//! fetches bone matrices from the row `rg3d_boneMatricesRowOffset + gl_InstanceID`.
+
+use crate::{
+    core::scope_profile,
+    renderer::{
+        batch::{BatchStorage, SurfaceInstance, BONE_MATRICES_COUNT},
+        framework::{
+            error::FrameworkError,
+            gpu_texture::{
+                GpuTexture, GpuTextureKind, MagnificationFilter, MinificationFilter, PixelKind,
+            },
+            state::PipelineState,
+        },
+    },
+    scene::{camera::Camera, mesh::RenderPath},
+    utils::{array_as_u8_slice, log::log_error},
+};
+use std::{cell::RefCell, ops::Range, rc::Rc};
+
+/// Maximum amount of instances which bone matrices can be stored in the texture. Instances over
+/// the limit are drawn one-by-one.
+pub const MAX_INSTANCES: usize = 1024;
+
+// Four texels per bone matrix plus one texel for color.
+const TEXELS_PER_ROW: usize = BONE_MATRICES_COUNT * 4 + 1;
+
+pub struct InstanceGroup {
+    /// Indices of instances in a batch.
+    pub instances: Vec<usize>,
+    /// Row of the bone matrices texture of the first instance of the group, `None` if the
+    /// instances of the group must be drawn one-by-one.
+    pub row_offset: Option<usize>,
+}
+
+pub struct SkinnedInstancing {
+    texture: Rc<RefCell<GpuTexture>>,
+    pixels: Vec<f32>,
+    groups: Vec<InstanceGroup>,
+    // Range of groups of each batch, indexed by batch index.
+    batch_groups: Vec<Range<usize>>,
+}
+
+fn can_share_draw_call(a: &SurfaceInstance, b: &SurfaceInstance) -> bool {
+    a.world_transform == b.world_transform
+        && a.depth_offset == b.depth_offset
+        && a.opacity == b.opacity
+        && a.uv_transform == b.uv_transform
+        && a.mip_bias == b.mip_bias
+        && a.receive_shadows == b.receive_shadows
+        && a.two_sided == b.two_sided
+        && a.alpha_cutoff == b.alpha_cutoff
+        && a.triplanar_mapping == b.triplanar_mapping
+}
+
+impl SkinnedInstancing {
+    pub fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        Ok(Self {
+            texture: Rc::new(RefCell::new(GpuTexture::new(
+                state,
+                GpuTextureKind::Rectangle {
+                    width: TEXELS_PER_ROW,
+                    height: 1,
+                },
+                PixelKind::RGBA32F,
+                MinificationFilter::Nearest,
+                MagnificationFilter::Nearest,
+                1,
+                None,
+            )?)),
+            pixels: Default::default(),
+            groups: Default::default(),
+            batch_groups: Default::default(),
+        })
+    }
+
+    pub fn texture(&self) -> &Rc<RefCell<GpuTexture>> {
+        &self.texture
+    }
+
+    /// Returns groups of visible instances of a skinned batch with given index.
+    pub fn groups(&self, batch_index: usize) -> &[InstanceGroup] {
+        self.batch_groups
+            .get(batch_index)
+            .map_or(&[], |range| &self.groups[range.clone()])
+    }
+
+    /// Groups visible opaque instances of deferred skinned batches and uploads their bone
+    /// matrices to the texture.
+    pub fn prepare(
+        &mut self,
+        state: &mut PipelineState,
+        batch_storage: &BatchStorage,
+        camera: &Camera,
+    ) {
+        scope_profile!();
+
+        self.groups.clear();
+        self.batch_groups.clear();
+        self.pixels.clear();
+
+        let mut rows = 0;
+
+        for batch in batch_storage.batches.iter() {
+            let first_group = self.groups.len();
+
+            if batch.is_skinned && batch.render_path == RenderPath::Deferred {
+                for (index, instance) in batch.instances.iter().enumerate() {
+                    if instance.is_transparent
+                        || !camera.visibility_cache.is_visible(instance.owner)
+                    {
+                        continue;
+                    }
+
+                    if let Some(group) = self.groups[first_group..]
+                        .iter_mut()
+                        .find(|g| can_share_draw_call(&batch.instances[g.instances[0]], instance))
+                    {
+                        group.instances.push(index);
+                    } else {
+                        self.groups.push(InstanceGroup {
+                            instances: vec![index],
+                            row_offset: None,
+                        });
+                    }
+                }
+
+                for group in self.groups[first_group..].iter_mut() {
+                    // There is no need to use instancing for a single instance.
+                    if group.instances.len() > 1 && rows + group.instances.len() <= MAX_INSTANCES {
+                        group.row_offset = Some(rows);
+                        rows += group.instances.len();
+
+                        for &index in group.instances.iter() {
+                            let instance = &batch.instances[index];
+                            for matrix in instance.bone_matrices.iter() {
+                                self.pixels.extend_from_slice(matrix.as_slice());
+                            }
+                            // Pad unused bone matrices, so color is always in the last texel.
+                            self.pixels.extend(
+                                std::iter::repeat(0.0).take(
+                                    (BONE_MATRICES_COUNT - instance.bone_matrices.len()) * 16,
+                                ),
+                            );
+                            self.pixels
+                                .extend_from_slice(instance.color.as_frgba().as_slice());
+                        }
+                    }
+                }
+            }
+
+            self.batch_groups.push(first_group..self.groups.len());
+        }
+
+        if rows > 0 {
+            if let Err(e) = self.texture.borrow_mut().bind_mut(state, 0).set_data(
+                GpuTextureKind::Rectangle {
+                    width: TEXELS_PER_ROW,
+                    height: rows,
+                },
+                PixelKind::RGBA32F,
+                1,
+                Some(array_as_u8_slice(&self.pixels)),
+            ) {
+                log_error!(
+                    "Unable to upload bone matrices, instancing of skinned meshes \
+                        will be disabled for this frame. Reason: {:?}",
+                    e
+                );
+
+                for group in self.groups.iter_mut() {
+                    group.row_offset = None;
+                }
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@ use crate::{
         algebra::{Matrix4, Vector2, Vector3},
         color::Color,
         math::Rect,
+        profile_scope,
     },
     renderer::{
         cache::texture::TextureCache,
@@ -329,6 +330,8 @@ impl HighDynamicRangeRenderer {
         use_color_grading: bool,
         texture_cache: &mut TextureCache,
     ) -> RenderPassStatistics {
+        profile_scope!("HDR");
+
         let mut stats = RenderPassStatistics::default();
         stats += self.calculate_frame_luminance(state, hdr_scene_frame.clone(), quad);
         stats += self.calculate_avg_frame_luminance(state, quad);
@@ -0,0 +1,552 @@
+//! Impostor renderer bakes atlases of impostors and draws impostors as instanced camera-facing
+//! quads.
+//!
+//! An atlas is baked in a single frame, only one atlas is baked per frame. Model of an atlas is
+//! rendered from every angle into a cell of an offscreen frame buffer using `Forward` render pass
+//! of materials (so pictures are unlit, as in environment probes), then the frame buffer is read
+//! back to CPU memory and stored in a texture of the atlas. Impostors that share an atlas are
+//! drawn with one draw call.
+
+use crate::{
+    asset::{Resource, ResourceState},
+    core::{
+        algebra::{Matrix4, Point3, Vector2, Vector3, Vector4},
+        color::Color,
+        math::{aabb::AxisAlignedBoundingBox, Rect, TriangleDefinition},
+        profile_scope, scope_profile,
+        sstorage::ImmutableString,
+    },
+    renderer::{
+        apply_material,
+        batch::{BatchStorage, BoneMatrixCache},
+        cache::{shader::ShaderCache, texture::TextureCache},
+        framework::{
+            error::FrameworkError,
+            framebuffer::{Attachment, AttachmentKind, DrawParameters, FrameBuffer},
+            geometry_buffer::{
+                AttributeDefinition, AttributeKind, BufferBuilder, ElementKind, GeometryBuffer,
+                GeometryBufferBuilder, GeometryBufferKind,
+            },
+            gpu_program::{GpuProgram, UniformLocation},
+            gpu_texture::{
+                GpuTexture, GpuTextureKind, MagnificationFilter, MinificationFilter, PixelKind,
+            },
+            state::{BlendFactor, BlendFunc, PipelineState},
+        },
+        GeometryCache, MaterialContext, RenderPassStatistics,
+    },
+    resource::texture::{Texture, TextureData, TextureKind, TexturePixelKind, TextureState},
+    scene::{
+        camera::Camera,
+        graph::Graph,
+        impostor::{self, ImpostorAtlas},
+        light::{self, LightingMode},
+        node::Node,
+    },
+};
+use fxhash::FxHashMap;
+use std::{cell::RefCell, rc::Rc};
+
+struct ImpostorShader {
+    program: GpuProgram,
+    view_projection_matrix: UniformLocation,
+    camera_position: UniformLocation,
+    bounds_center: UniformLocation,
+    frame_half_size: UniformLocation,
+    angle_count: UniformLocation,
+    grid_size: UniformLocation,
+    atlas_texture: UniformLocation,
+    alpha_cutoff: UniformLocation,
+}
+
+impl ImpostorShader {
+    fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        let fragment_source = include_str!("shaders/impostor_fs.glsl");
+        let vertex_source = include_str!("shaders/impostor_vs.glsl");
+        let program =
+            GpuProgram::from_source(state, "ImpostorShader", vertex_source, fragment_source)?;
+        Ok(Self {
+            view_projection_matrix: program
+                .uniform_location(state, &ImmutableString::new("viewProjectionMatrix"))?,
+            camera_position: program
+                .uniform_location(state, &ImmutableString::new("cameraPosition"))?,
+            bounds_center: program
+                .uniform_location(state, &ImmutableString::new("boundsCenter"))?,
+            frame_half_size: program
+                .uniform_location(state, &ImmutableString::new("frameHalfSize"))?,
+            angle_count: program.uniform_location(state, &ImmutableString::new("angleCount"))?,
+            grid_size: program.uniform_location(state, &ImmutableString::new("gridSize"))?,
+            atlas_texture: program
+                .uniform_location(state, &ImmutableString::new("atlasTexture"))?,
+            alpha_cutoff: program.uniform_location(state, &ImmutableString::new("alphaCutoff"))?,
+            program,
+        })
+    }
+}
+
+#[derive(Clone)]
+#[repr(C)]
+struct InstanceData {
+    world_matrix: Matrix4<f32>,
+    // Linear color with lighting applied, alpha is the fade factor.
+    color: Vector4<f32>,
+}
+
+// Impostors of the same atlas that are visible in current frame.
+struct AtlasInstances {
+    atlas: ImpostorAtlas,
+    alpha_cutoff: f32,
+    instances: Vec<InstanceData>,
+}
+
+pub(in crate) struct ImpostorRenderer {
+    shader: ImpostorShader,
+    quad: GeometryBuffer,
+    // Batches of a model that is being baked, they're separate from batches of the scene.
+    batch_storage: BatchStorage,
+    bone_matrix_cache: BoneMatrixCache,
+    render_pass_name: ImmutableString,
+}
+
+pub(in crate) struct ImpostorBakeContext<'a, 'b> {
+    pub state: &'a mut PipelineState,
+    pub graph: &'b Graph,
+    pub geom_cache: &'a mut GeometryCache,
+    pub texture_cache: &'a mut TextureCache,
+    pub shader_cache: &'a mut ShaderCache,
+    pub white_dummy: Rc<RefCell<GpuTexture>>,
+    pub normal_dummy: Rc<RefCell<GpuTexture>>,
+    pub black_dummy: Rc<RefCell<GpuTexture>>,
+}
+
+pub(in crate) struct ImpostorRenderContext<'a, 'b, 'c> {
+    pub state: &'a mut PipelineState,
+    pub framebuffer: &'b mut FrameBuffer,
+    pub graph: &'c Graph,
+    pub camera: &'c Camera,
+    pub viewport: Rect<i32>,
+    pub texture_cache: &'a mut TextureCache,
+    pub ambient_color: Color,
+}
+
+fn make_quad(state: &mut PipelineState) -> Result<GeometryBuffer, FrameworkError> {
+    let corners = [
+        Vector2::new(-1.0f32, -1.0),
+        Vector2::new(1.0, -1.0),
+        Vector2::new(1.0, 1.0),
+        Vector2::new(-1.0, 1.0),
+    ];
+
+    let mut instance_buffer =
+        BufferBuilder::new::<InstanceData>(GeometryBufferKind::DynamicDraw, None);
+    // World matrix takes four locations, one per column, and color takes the last one.
+    for location in 1..=5 {
+        instance_buffer = instance_buffer.with_attribute(AttributeDefinition {
+            location,
+            kind: AttributeKind::Float4,
+            normalized: false,
+            divisor: 1,
+        });
+    }
+
+    let quad = GeometryBufferBuilder::new(ElementKind::Triangle)
+        .with_buffer_builder(
+            BufferBuilder::new(GeometryBufferKind::StaticDraw, Some(&corners)).with_attribute(
+                AttributeDefinition {
+                    location: 0,
+                    kind: AttributeKind::Float2,
+                    normalized: false,
+                    divisor: 0,
+                },
+            ),
+        )
+        .with_buffer_builder(instance_buffer)
+        .build(state)?;
+
+    quad.bind(state)
+        .set_triangles(&[TriangleDefinition([0, 1, 2]), TriangleDefinition([0, 2, 3])]);
+
+    Ok(quad)
+}
+
+fn make_atlas_framebuffer(
+    state: &mut PipelineState,
+    width: usize,
+    height: usize,
+) -> Result<FrameBuffer, FrameworkError> {
+    let depth_stencil = GpuTexture::new(
+        state,
+        GpuTextureKind::Rectangle { width, height },
+        PixelKind::D24S8,
+        MinificationFilter::Nearest,
+        MagnificationFilter::Nearest,
+        1,
+        None,
+    )?;
+
+    let color = GpuTexture::new(
+        state,
+        GpuTextureKind::Rectangle { width, height },
+        PixelKind::RGBA8,
+        MinificationFilter::Nearest,
+        MagnificationFilter::Nearest,
+        1,
+        None,
+    )?;
+
+    FrameBuffer::new(
+        state,
+        Some(Attachment {
+            kind: AttachmentKind::DepthStencil,
+            texture: Rc::new(RefCell::new(depth_stencil)),
+        }),
+        vec![Attachment {
+            kind: AttachmentKind::Color,
+            texture: Rc::new(RefCell::new(color)),
+        }],
+    )
+}
+
+impl ImpostorRenderer {
+    pub(in crate) fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        Ok(Self {
+            shader: ImpostorShader::new(state)?,
+            quad: make_quad(state)?,
+            batch_storage: Default::default(),
+            bone_matrix_cache: Default::default(),
+            render_pass_name: ImmutableString::new("Forward"),
+        })
+    }
+
+    /// Bakes first atlas in the scene that needs baking and which model is loaded.
+    pub(in crate) fn bake(
+        &mut self,
+        args: ImpostorBakeContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        scope_profile!();
+        profile_scope!("ImpostorBake");
+
+        let mut statistics = RenderPassStatistics::default();
+
+        let ImpostorBakeContext {
+            state,
+            graph,
+            geom_cache,
+            texture_cache,
+            shader_cache,
+            white_dummy,
+            normal_dummy,
+            black_dummy,
+        } = args;
+
+        let mut next = None;
+        for node in graph.linear_iter() {
+            if let Node::Impostor(impostor) = node {
+                let atlas = impostor.atlas();
+                if !atlas.needs_bake() {
+                    continue;
+                }
+                if let Some(model) = atlas.model() {
+                    match *model.state() {
+                        ResourceState::Ok(_) => {
+                            next = Some((atlas.clone(), model.clone()));
+                            break;
+                        }
+                        ResourceState::LoadError { .. } => atlas.fail_bake(),
+                        ResourceState::Pending { .. } => (),
+                    }
+                }
+            }
+        }
+
+        let (atlas, model) = match next {
+            Some(next) => next,
+            None => return Ok(statistics),
+        };
+
+        let model_state = model.state();
+        let model_graph = match *model_state {
+            ResourceState::Ok(ref data) => &data.get_scene().graph,
+            _ => return Ok(statistics),
+        };
+
+        let mut bounds = AxisAlignedBoundingBox::default();
+        let mut has_meshes = false;
+        for node in model_graph.linear_iter() {
+            if let Node::Mesh(mesh) = node {
+                bounds.add_box(
+                    mesh.local_bounding_box()
+                        .transform(&mesh.global_transform()),
+                );
+                has_meshes = true;
+            }
+        }
+        if !has_meshes {
+            atlas.fail_bake();
+            return Ok(statistics);
+        }
+
+        self.batch_storage
+            .generate_batches(model_graph, &mut self.bone_matrix_cache);
+
+        let angle_count = atlas.angle_count();
+        let resolution = atlas.resolution() as usize;
+        let (columns, rows) = impostor::grid_size(angle_count);
+        let width = columns as usize * resolution;
+        let height = rows as usize * resolution;
+
+        let mut framebuffer = make_atlas_framebuffer(state, width, height)?;
+        let full_viewport = Rect::new(0, 0, width as i32, height as i32);
+        framebuffer.clear(
+            state,
+            full_viewport,
+            Some(Color::from_rgba(0, 0, 0, 0)),
+            Some(1.0),
+            Some(0),
+        );
+
+        let center = bounds.center();
+        let half_size = impostor::frame_half_size(&bounds);
+        let radius = half_size.x;
+        let projection = Matrix4::new_orthographic(
+            -radius,
+            radius,
+            -half_size.y,
+            half_size.y,
+            0.5,
+            2.0 * radius + 1.5,
+        );
+
+        for index in 0..angle_count {
+            let angle = index as f32 * std::f32::consts::TAU / angle_count as f32;
+            let direction = Vector3::new(angle.sin(), 0.0, angle.cos());
+            let eye = center + direction.scale(radius + 1.0);
+            let view =
+                Matrix4::look_at_rh(&Point3::from(eye), &Point3::from(center), &Vector3::y());
+            let view_projection = projection * view;
+
+            let column = (index % columns) as i32;
+            let row = (index / columns) as i32;
+            let size = resolution as i32;
+            let viewport = Rect::new(column * size, row * size, size, size);
+
+            for batch in self.batch_storage.batches.iter() {
+                let material = batch.material.lock();
+                let geometry = geom_cache.get(state, &batch.data);
+
+                let render_pass = match shader_cache
+                    .get(state, material.shader())
+                    .and_then(|shader_set| shader_set.render_passes.get(&self.render_pass_name))
+                {
+                    Some(render_pass) => render_pass,
+                    None => continue,
+                };
+
+                for instance in batch.instances.iter() {
+                    if !model_graph[instance.owner].global_visibility() {
+                        continue;
+                    }
+
+                    let draw_params = DrawParameters {
+                        cull_face: instance.cull_face(render_pass.draw_params.cull_face),
+                        depth_write: true,
+                        depth_test: true,
+                        blend: None,
+                        ..render_pass.draw_params.clone()
+                    };
+
+                    let wvp = view_projection * instance.world_transform;
+
+                    statistics += framebuffer.draw(
+                        geometry,
+                        state,
+                        viewport,
+                        render_pass.program(instance.triplanar_mapping.is_some()),
+                        &draw_params,
+                        |mut program_binding| {
+                            apply_material(MaterialContext {
+                                material: &*material,
+                                program_binding: &mut program_binding,
+                                texture_cache,
+                                world_matrix: &instance.world_transform,
+                                wvp_matrix: &wvp,
+                                bone_matrices: &instance.bone_matrices,
+                                use_skeletal_animation: batch.is_skinned,
+                                camera_position: &eye,
+                                use_pom: false,
+                                opacity: instance.opacity,
+                                color: instance.color,
+                                light_position: &Default::default(),
+                                uv_transform: &instance.uv_transform,
+                                mip_bias: instance.mip_bias,
+                                receive_shadows: instance.receive_shadows,
+                                alpha_cutoff: instance.alpha_cutoff,
+                                triplanar_mapping: instance.triplanar_mapping,
+                                instanced_bones: None,
+                                normal_dummy: normal_dummy.clone(),
+                                white_dummy: white_dummy.clone(),
+                                black_dummy: black_dummy.clone(),
+                            });
+                        },
+                    );
+                }
+            }
+        }
+
+        let bytes = framebuffer.read_pixels_rgba8(state, full_viewport);
+        let texture = Texture(Resource::new(TextureState::Ok(
+            TextureData::from_mip_chain(
+                TextureKind::Rectangle {
+                    width: width as u32,
+                    height: height as u32,
+                },
+                TexturePixelKind::RGBA8,
+                1,
+                bytes,
+            ),
+        )));
+        atlas.finish_bake(texture, bounds);
+
+        Ok(statistics)
+    }
+
+    /// Draws every visible impostor of the scene, impostors that share an atlas are drawn with
+    /// one draw call.
+    pub(in crate) fn render(
+        &mut self,
+        args: ImpostorRenderContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        scope_profile!();
+        profile_scope!("Impostor");
+
+        let mut statistics = RenderPassStatistics::default();
+
+        let ImpostorRenderContext {
+            state,
+            framebuffer,
+            graph,
+            camera,
+            viewport,
+            texture_cache,
+            ambient_color,
+        } = args;
+
+        let camera_position = camera.global_position();
+
+        // Lights are collected once, there could be thousands of lit impostors.
+        let lights = graph
+            .linear_iter()
+            .filter_map(|node| {
+                if let Node::Light(light) = node {
+                    Some(light)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut groups = FxHashMap::<usize, AtlasInstances>::default();
+        for (handle, node) in graph.pair_iter() {
+            let impostor = if let Node::Impostor(impostor) = node {
+                impostor
+            } else {
+                continue;
+            };
+
+            if !camera.visibility_cache.is_visible(handle) {
+                continue;
+            }
+
+            let distance = camera_position.metric_distance(&impostor.global_position());
+            let fade = impostor.fade_factor(distance);
+            if fade <= 0.0 {
+                continue;
+            }
+
+            let lighting = match impostor.lighting_mode() {
+                LightingMode::Unlit => Vector3::new(1.0, 1.0, 1.0),
+                LightingMode::Lit => light::evaluate_lighting_from(
+                    lights.iter().copied(),
+                    impostor.global_position(),
+                    ambient_color,
+                    light::DEFAULT_MAX_LIGHTS_PER_SPRITE,
+                ),
+            };
+            let color = impostor.color().srgb_to_linear_f32();
+
+            let atlas = impostor.atlas();
+            groups
+                .entry(atlas.key())
+                .or_insert_with(|| AtlasInstances {
+                    atlas: atlas.clone(),
+                    alpha_cutoff: impostor.alpha_cutoff(),
+                    instances: Vec::new(),
+                })
+                .instances
+                .push(InstanceData {
+                    world_matrix: impostor.global_transform(),
+                    color: Vector4::new(
+                        color.x * lighting.x,
+                        color.y * lighting.y,
+                        color.z * lighting.z,
+                        color.w * fade,
+                    ),
+                });
+        }
+
+        let view_projection = camera.view_projection_matrix();
+        let shader = &self.shader;
+
+        for group in groups.values() {
+            let atlas_texture = match group
+                .atlas
+                .texture()
+                .and_then(|texture| texture_cache.get(state, &texture))
+            {
+                Some(texture) => texture,
+                None => continue,
+            };
+
+            let bounds_center = group.atlas.bounds().center();
+            let frame_half_size = group.atlas.frame_half_size();
+            let (columns, rows) = group.atlas.grid_size();
+            let grid_size = Vector2::new(columns as f32, rows as f32);
+            let angle_count = group.atlas.angle_count() as i32;
+
+            self.quad.set_buffer_data(state, 1, &group.instances);
+
+            statistics += framebuffer.draw_instances(
+                group.instances.len(),
+                &self.quad,
+                state,
+                viewport,
+                &shader.program,
+                &DrawParameters {
+                    cull_face: None,
+                    color_write: Default::default(),
+                    depth_write: true,
+                    stencil_test: None,
+                    depth_test: true,
+                    blend: Some(BlendFunc {
+                        sfactor: BlendFactor::SrcAlpha,
+                        dfactor: BlendFactor::OneMinusSrcAlpha,
+                    }),
+                    stencil_op: Default::default(),
+                },
+                |mut program_binding| {
+                    program_binding
+                        .set_matrix4(&shader.view_projection_matrix, &view_projection)
+                        .set_vector3(&shader.camera_position, &camera_position)
+                        .set_vector3(&shader.bounds_center, &bounds_center)
+                        .set_vector2(&shader.frame_half_size, &frame_half_size)
+                        .set_i32(&shader.angle_count, angle_count)
+                        .set_vector2(&shader.grid_size, &grid_size)
+                        .set_texture(&shader.atlas_texture, &atlas_texture)
+                        .set_f32(&shader.alpha_cutoff, group.alpha_cutoff);
+                },
+            );
+        }
+
+        Ok(statistics)
+    }
+}
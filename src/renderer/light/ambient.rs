@@ -12,6 +12,19 @@ pub struct AmbientLightShader {
     pub ambient_color: UniformLocation,
     pub ao_sampler: UniformLocation,
     pub ambient_texture: UniformLocation,
+    pub depth_texture: UniformLocation,
+    pub normal_texture: UniformLocation,
+    pub material_texture: UniformLocation,
+    pub environment_map: UniformLocation,
+    pub probe_enabled: UniformLocation,
+    pub inv_view_proj_matrix: UniformLocation,
+    pub camera_position: UniformLocation,
+    pub probe_transform: UniformLocation,
+    pub probe_inv_transform: UniformLocation,
+    pub probe_half_size: UniformLocation,
+    pub probe_position: UniformLocation,
+    pub probe_max_lod: UniformLocation,
+    pub probe_intensity: UniformLocation,
 }
 
 impl AmbientLightShader {
@@ -30,6 +43,31 @@ impl AmbientLightShader {
             ao_sampler: program.uniform_location(state, &ImmutableString::new("aoSampler"))?,
             ambient_texture: program
                 .uniform_location(state, &ImmutableString::new("ambientTexture"))?,
+            depth_texture: program
+                .uniform_location(state, &ImmutableString::new("depthTexture"))?,
+            normal_texture: program
+                .uniform_location(state, &ImmutableString::new("normalTexture"))?,
+            material_texture: program
+                .uniform_location(state, &ImmutableString::new("materialTexture"))?,
+            environment_map: program
+                .uniform_location(state, &ImmutableString::new("environmentMap"))?,
+            probe_enabled: program
+                .uniform_location(state, &ImmutableString::new("probeEnabled"))?,
+            inv_view_proj_matrix: program
+                .uniform_location(state, &ImmutableString::new("invViewProj"))?,
+            camera_position: program
+                .uniform_location(state, &ImmutableString::new("cameraPosition"))?,
+            probe_transform: program
+                .uniform_location(state, &ImmutableString::new("probeTransform"))?,
+            probe_inv_transform: program
+                .uniform_location(state, &ImmutableString::new("probeInvTransform"))?,
+            probe_half_size: program
+                .uniform_location(state, &ImmutableString::new("probeHalfSize"))?,
+            probe_position: program
+                .uniform_location(state, &ImmutableString::new("probePosition"))?,
+            probe_max_lod: program.uniform_location(state, &ImmutableString::new("probeMaxLod"))?,
+            probe_intensity: program
+                .uniform_location(state, &ImmutableString::new("probeIntensity"))?,
             program,
         })
     }
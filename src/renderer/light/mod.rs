@@ -5,7 +5,7 @@ use crate::{
         algebra::{Matrix4, Point3, Vector3},
         color::Color,
         math::{frustum::Frustum, Matrix4Ext, Rect, TriangleDefinition},
-        scope_profile,
+        profile_scope, scope_profile,
     },
     renderer::{
         batch::BatchStorage,
@@ -33,10 +33,11 @@ use crate::{
         },
         skybox_shader::SkyboxShader,
         ssao::ScreenSpaceAmbientOcclusionRenderer,
-        GeometryCache, QualitySettings, RenderPassStatistics, TextureCache,
+        GeometryCache, LightLodSettings, QualitySettings, RenderPassStatistics, TextureCache,
     },
+    resource::texture::TextureState,
     scene::{
-        camera::Camera,
+        camera::{Camera, Projection},
         light::Light,
         mesh::{
             buffer::{TriangleBuffer, VertexBuffer},
@@ -44,11 +45,13 @@ use crate::{
             vertex::SimpleVertex,
         },
         node::Node,
+        probe::EnvironmentProbe,
         Scene,
     },
 };
 use std::{
     cell::RefCell,
+    cmp::Ordering,
     fmt::{Display, Formatter},
     ops::AddAssign,
     rc::Rc,
@@ -67,6 +70,15 @@ pub struct LightingStatistics {
     pub spot_lights_rendered: usize,
     pub spot_shadow_maps_rendered: usize,
     pub directional_lights_rendered: usize,
+    /// Amount of lights in the view frustum that were sent to rendering.
+    pub lights_submitted: usize,
+    /// Amount of lights in the view frustum that were culled by light LOD.
+    pub lights_culled: usize,
+    /// Amount of submitted lights that were rendered with reduced intensity by light LOD.
+    pub lights_faded: usize,
+    /// Draw calls and triangles of all shadow map passes, meshes that do not cast shadows are
+    /// not rendered into shadow maps at all.
+    pub shadow_pass: RenderPassStatistics,
 }
 
 impl AddAssign for LightingStatistics {
@@ -77,6 +89,10 @@ impl AddAssign for LightingStatistics {
         self.spot_shadow_maps_rendered += rhs.spot_shadow_maps_rendered;
         self.directional_lights_rendered += rhs.directional_lights_rendered;
         self.csm_rendered += rhs.csm_rendered;
+        self.lights_submitted += rhs.lights_submitted;
+        self.lights_culled += rhs.lights_culled;
+        self.lights_faded += rhs.lights_faded;
+        self.shadow_pass += rhs.shadow_pass;
     }
 }
 
@@ -90,17 +106,98 @@ impl Display for LightingStatistics {
             \tDirectional Lights: {}\n\
             \tPoint Shadow Maps: {}\n\
             \tSpot Shadow Maps: {}\n\
-            \tSpot Shadow Maps: {}\n",
+            \tSpot Shadow Maps: {}\n\
+            \tLights Submitted: {}\n\
+            \tLights Culled: {}\n\
+            \tLights Faded: {}\n\
+            \tShadow Pass Draw Calls: {}\n\
+            \tShadow Pass Triangles: {}\n",
             self.point_lights_rendered,
             self.spot_lights_rendered,
             self.directional_lights_rendered,
             self.point_shadow_maps_rendered,
             self.spot_shadow_maps_rendered,
-            self.csm_rendered
+            self.csm_rendered,
+            self.lights_submitted,
+            self.lights_culled,
+            self.lights_faded,
+            self.shadow_pass.draw_calls,
+            self.shadow_pass.triangles_rendered
         )
     }
 }
 
+// World-space radius of the light's volume.
+fn light_radius(light: &Light) -> f32 {
+    let raw_radius = match light {
+        Light::Spot(spot_light) => spot_light.distance(),
+        Light::Point(point_light) => point_light.radius(),
+        Light::Directional(_) => f32::MAX,
+    };
+    let scale = light.local_transform().scale();
+    scale.x.max(scale.y).max(scale.z) * raw_radius
+}
+
+/// Returns part of the screen height covered by a sphere of given radius.
+fn light_screen_size(camera: &Camera, position: &Vector3<f32>, radius: f32) -> f32 {
+    let size = match camera.projection() {
+        Projection::Perspective => {
+            let distance = (position - camera.global_position()).norm();
+            if distance <= radius {
+                // Camera is inside of the light volume.
+                return 1.0;
+            }
+            // Element (1, 1) of perspective projection matrix is 1 / tan(fov / 2).
+            radius * camera.projection_matrix()[(1, 1)] / distance
+        }
+        Projection::Orthographic { vertical_size } => radius / vertical_size.max(f32::EPSILON),
+    };
+    size.min(1.0)
+}
+
+struct LightLodEntry<T> {
+    item: T,
+    screen_size: f32,
+    always_on: bool,
+    // Multiplier for light intensity, it is set by `apply_light_lod`.
+    fade: f32,
+}
+
+// Removes lights that are too small on screen or does not fit in the budget and calculates fade
+// factors for the rest. Returns amount of removed lights.
+fn apply_light_lod<T>(entries: &mut Vec<LightLodEntry<T>>, settings: &LightLodSettings) -> usize {
+    if !settings.enabled {
+        return 0;
+    }
+
+    let count = entries.len();
+
+    entries.retain(|e| e.always_on || e.screen_size >= settings.cull_screen_size);
+
+    if settings.max_lights > 0 && entries.len() > settings.max_lights {
+        // Always-on lights go first, then the biggest ones.
+        entries.sort_by(|a, b| {
+            b.always_on.cmp(&a.always_on).then(
+                b.screen_size
+                    .partial_cmp(&a.screen_size)
+                    .unwrap_or(Ordering::Equal),
+            )
+        });
+        let always_on = entries.iter().filter(|e| e.always_on).count();
+        entries.truncate(settings.max_lights.max(always_on));
+    }
+
+    let fade_range = settings.fade_screen_size - settings.cull_screen_size;
+    if fade_range > 0.0 {
+        for entry in entries.iter_mut().filter(|e| !e.always_on) {
+            entry.fade =
+                ((entry.screen_size - settings.cull_screen_size) / fade_range).clamp(0.0, 1.0);
+        }
+    }
+
+    count - entries.len()
+}
+
 pub struct DeferredLightRenderer {
     pub ssao_renderer: ScreenSpaceAmbientOcclusionRenderer,
     spot_light_shader: SpotLightShader,
@@ -133,6 +230,38 @@ pub(in crate) struct DeferredRendererContext<'a> {
     pub normal_dummy: Rc<RefCell<GpuTexture>>,
     pub white_dummy: Rc<RefCell<GpuTexture>>,
     pub black_dummy: Rc<RefCell<GpuTexture>>,
+    pub environment_dummy: Rc<RefCell<GpuTexture>>,
+}
+
+// Selects environment probe that lights the scene for given camera: the probe that contains the
+// camera or the nearest one.
+fn select_environment_probe<'a>(scene: &'a Scene, camera: &Camera) -> Option<&'a EnvironmentProbe> {
+    let camera_position = camera.global_position();
+    scene
+        .graph
+        .linear_iter()
+        .filter_map(|node| {
+            if let Node::EnvironmentProbe(probe) = node {
+                Some(probe)
+            } else {
+                None
+            }
+        })
+        .filter(|probe| {
+            probe.global_visibility()
+                && probe.is_baked()
+                && probe.is_visible_by_mask(camera.visibility_mask())
+        })
+        .min_by(|a, b| {
+            let key = |probe: &EnvironmentProbe| {
+                let contains = probe
+                    .world_bounding_box()
+                    .is_contains_point(camera_position);
+                let distance = probe.global_position().metric_distance(&camera_position);
+                (!contains, distance)
+            };
+            key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal)
+        })
 }
 
 impl DeferredLightRenderer {
@@ -294,6 +423,7 @@ impl DeferredLightRenderer {
         args: DeferredRendererContext,
     ) -> (RenderPassStatistics, LightingStatistics) {
         scope_profile!();
+        profile_scope!("Lighting");
 
         let mut pass_stats = RenderPassStatistics::default();
         let mut light_stats = LightingStatistics::default();
@@ -313,6 +443,7 @@ impl DeferredLightRenderer {
             batch_storage,
             frame_buffer,
             black_dummy,
+            environment_dummy,
         } = args;
 
         let viewport = Rect::new(0, 0, gbuffer.width, gbuffer.height);
@@ -390,6 +521,25 @@ impl DeferredLightRenderer {
         let gbuffer_ambient_map = gbuffer.ambient_texture();
         let ao_map = self.ssao_renderer.ao_map();
 
+        let probe = if settings.use_environment_probes {
+            select_environment_probe(scene, camera).and_then(|probe| {
+                let environment = probe.environment();
+                let max_lod = match *environment.state() {
+                    TextureState::Ok(ref data) => data.mip_count().saturating_sub(1) as f32,
+                    _ => return None,
+                };
+                textures
+                    .get(state, &environment)
+                    .map(|texture| (probe, texture, max_lod))
+            })
+        } else {
+            None
+        };
+        let probe_transform = probe
+            .map(|(probe, _, _)| probe.global_transform())
+            .unwrap_or_default();
+        let probe_inv_transform = probe_transform.try_inverse().unwrap_or_default();
+
         frame_buffer.draw(
             &self.quad,
             state,
@@ -426,31 +576,115 @@ impl DeferredLightRenderer {
                     .set_texture(
                         &self.ambient_light_shader.ambient_texture,
                         &gbuffer_ambient_map,
+                    )
+                    .set_texture(&self.ambient_light_shader.depth_texture, &gbuffer_depth_map)
+                    .set_texture(
+                        &self.ambient_light_shader.normal_texture,
+                        &gbuffer_normal_map,
+                    )
+                    .set_texture(
+                        &self.ambient_light_shader.material_texture,
+                        &gbuffer_material_map,
+                    )
+                    .set_matrix4(
+                        &self.ambient_light_shader.inv_view_proj_matrix,
+                        &inv_view_projection,
+                    )
+                    .set_vector3(
+                        &self.ambient_light_shader.camera_position,
+                        &camera_global_position,
+                    )
+                    .set_matrix4(&self.ambient_light_shader.probe_transform, &probe_transform)
+                    .set_matrix4(
+                        &self.ambient_light_shader.probe_inv_transform,
+                        &probe_inv_transform,
                     );
+
+                if let Some((probe, environment, max_lod)) = probe.as_ref() {
+                    program_binding
+                        .set_bool(&self.ambient_light_shader.probe_enabled, true)
+                        .set_texture(&self.ambient_light_shader.environment_map, environment)
+                        .set_vector3(
+                            &self.ambient_light_shader.probe_half_size,
+                            &probe.size().scale(0.5),
+                        )
+                        .set_vector3(
+                            &self.ambient_light_shader.probe_position,
+                            &probe.global_position(),
+                        )
+                        .set_f32(&self.ambient_light_shader.probe_max_lod, *max_lod)
+                        .set_f32(
+                            &self.ambient_light_shader.probe_intensity,
+                            probe.intensity(),
+                        );
+                } else {
+                    program_binding
+                        .set_bool(&self.ambient_light_shader.probe_enabled, false)
+                        .set_texture(
+                            &self.ambient_light_shader.environment_map,
+                            &environment_dummy,
+                        )
+                        .set_vector3(
+                            &self.ambient_light_shader.probe_half_size,
+                            &Vector3::default(),
+                        )
+                        .set_vector3(
+                            &self.ambient_light_shader.probe_position,
+                            &Vector3::default(),
+                        )
+                        .set_f32(&self.ambient_light_shader.probe_max_lod, 0.0)
+                        .set_f32(&self.ambient_light_shader.probe_intensity, 0.0);
+                }
             },
         );
 
-        for (light_handle, light) in scene.graph.pair_iter().filter_map(|(handle, node)| {
-            if let Node::Light(light) = node {
-                Some((handle, light))
-            } else {
-                None
-            }
-        }) {
-            if !light.global_visibility() {
-                continue;
-            }
+        let mut lights = scene
+            .graph
+            .pair_iter()
+            .filter_map(|(handle, node)| {
+                if let Node::Light(light) = node {
+                    Some((handle, light))
+                } else {
+                    None
+                }
+            })
+            .filter(|(_, light)| light.global_visibility())
+            .filter_map(|(handle, light)| {
+                let light_position = light.global_position();
+                let light_radius = light_radius(light);
 
-            let raw_radius = match light {
-                Light::Spot(spot_light) => spot_light.distance(),
-                Light::Point(point_light) => point_light.radius(),
-                Light::Directional(_) => f32::MAX,
-            };
+                if !frustum.is_intersects_sphere(light_position, light_radius) {
+                    return None;
+                }
+
+                let (screen_size, always_on) = match light {
+                    Light::Directional(_) => (1.0, true),
+                    _ => (
+                        light_screen_size(camera, &light_position, light_radius),
+                        light.is_always_on(),
+                    ),
+                };
+
+                Some(LightLodEntry {
+                    item: (handle, light),
+                    screen_size,
+                    always_on,
+                    fade: 1.0,
+                })
+            })
+            .collect::<Vec<_>>();
 
+        light_stats.lights_culled += apply_light_lod(&mut lights, &settings.light_lod);
+        light_stats.lights_submitted += lights.len();
+
+        for LightLodEntry {
+            item: (light_handle, light),
+            fade,
+            ..
+        } in lights
+        {
             let light_position = light.global_position();
-            let scl = light.local_transform().scale();
-            let light_radius_scale = scl.x.max(scl.y).max(scl.z);
-            let light_radius = light_radius_scale * raw_radius;
+            let light_radius = light_radius(light);
             let light_r_inflate = 1.05 * light_radius;
             let light_radius_vec = Vector3::new(light_r_inflate, light_r_inflate, light_r_inflate);
             let emit_direction = light
@@ -458,8 +692,8 @@ impl DeferredLightRenderer {
                 .try_normalize(f32::EPSILON)
                 .unwrap_or_else(Vector3::z);
 
-            if !frustum.is_intersects_sphere(light_position, light_radius) {
-                continue;
+            if fade < 1.0 {
+                light_stats.lights_faded += 1;
             }
 
             let distance_to_camera = (light.global_position() - camera.global_position()).norm();
@@ -509,7 +743,7 @@ impl DeferredLightRenderer {
 
                         light_view_projection = light_projection_matrix * light_view_matrix;
 
-                        pass_stats += self.spot_shadow_map_renderer.render(
+                        light_stats.shadow_pass += self.spot_shadow_map_renderer.render(
                             state,
                             &scene.graph,
                             &light_view_projection,
@@ -521,6 +755,7 @@ impl DeferredLightRenderer {
                             normal_dummy.clone(),
                             white_dummy.clone(),
                             black_dummy.clone(),
+                            camera.visibility_mask(),
                         );
 
                         light_stats.spot_shadow_maps_rendered += 1;
@@ -531,7 +766,7 @@ impl DeferredLightRenderer {
                         if distance_to_camera <= settings.point_shadows_distance
                             && settings.point_shadows_enabled =>
                     {
-                        pass_stats +=
+                        light_stats.shadow_pass +=
                             self.point_shadow_map_renderer
                                 .render(PointShadowMapRenderContext {
                                     state,
@@ -546,6 +781,7 @@ impl DeferredLightRenderer {
                                     normal_dummy: normal_dummy.clone(),
                                     white_dummy: white_dummy.clone(),
                                     black_dummy: black_dummy.clone(),
+                                    visibility_mask: camera.visibility_mask(),
                                 });
 
                         light_stats.point_shadow_maps_rendered += 1;
@@ -553,7 +789,7 @@ impl DeferredLightRenderer {
                         true
                     }
                     Light::Directional(directional) if settings.csm_settings.enabled => {
-                        pass_stats += self.csm_renderer.render(CsmRenderContext {
+                        light_stats.shadow_pass += self.csm_renderer.render(CsmRenderContext {
                             aspect: viewport.w() as f32 / viewport.h() as f32,
                             state,
                             graph: &scene.graph,
@@ -687,7 +923,7 @@ impl DeferredLightRenderer {
                             program_binding
                                 .set_bool(&shader.shadows_enabled, shadows_enabled)
                                 .set_matrix4(&shader.light_view_proj_matrix, &light_view_projection)
-                                .set_bool(&shader.soft_shadows, settings.spot_soft_shadows)
+                                .set_i32(&shader.shadow_filter, settings.spot_shadow_filter as i32)
                                 .set_vector3(&shader.light_position, &light_position)
                                 .set_vector3(&shader.light_direction, &emit_direction)
                                 .set_f32(&shader.light_radius, light_radius)
@@ -719,7 +955,15 @@ impl DeferredLightRenderer {
                                 .set_texture(&shader.cookie_texture, &cookie_texture)
                                 .set_bool(&shader.cookie_enabled, cookie_enabled)
                                 .set_f32(&shader.shadow_bias, spot_light.shadow_bias())
-                                .set_f32(&shader.light_intensity, spot_light.intensity());
+                                .set_f32(&shader.light_intensity, spot_light.intensity() * fade)
+                                .set_i32(
+                                    &shader.light_falloff_mode,
+                                    spot_light.falloff().shader_mode(),
+                                )
+                                .set_f32(
+                                    &shader.light_falloff_exponent,
+                                    spot_light.falloff().shader_exponent(),
+                                );
                         },
                     )
                 }
@@ -737,7 +981,14 @@ impl DeferredLightRenderer {
                         |mut program_binding| {
                             program_binding
                                 .set_bool(&shader.shadows_enabled, shadows_enabled)
-                                .set_bool(&shader.soft_shadows, settings.point_soft_shadows)
+                                .set_i32(&shader.shadow_filter, settings.point_shadow_filter as i32)
+                                .set_f32(
+                                    &shader.shadow_map_inv_size,
+                                    1.0 / (self
+                                        .point_shadow_map_renderer
+                                        .cascade_size(cascade_index)
+                                        as f32),
+                                )
                                 .set_vector3(&shader.light_position, &light_position)
                                 .set_f32(&shader.light_radius, light_radius)
                                 .set_matrix4(&shader.inv_view_proj_matrix, &inv_view_projection)
@@ -745,7 +996,15 @@ impl DeferredLightRenderer {
                                 .set_matrix4(&shader.wvp_matrix, &frame_matrix)
                                 .set_vector3(&shader.camera_position, &camera_global_position)
                                 .set_f32(&shader.shadow_bias, point_light.shadow_bias())
-                                .set_f32(&shader.light_intensity, point_light.intensity())
+                                .set_f32(&shader.light_intensity, point_light.intensity() * fade)
+                                .set_i32(
+                                    &shader.light_falloff_mode,
+                                    point_light.falloff().shader_mode(),
+                                )
+                                .set_f32(
+                                    &shader.light_falloff_exponent,
+                                    point_light.falloff().shader_exponent(),
+                                )
                                 .set_texture(&shader.depth_sampler, &gbuffer_depth_map)
                                 .set_texture(&shader.color_sampler, &gbuffer_diffuse_map)
                                 .set_texture(&shader.normal_sampler, &gbuffer_normal_map)
@@ -843,6 +1102,66 @@ impl DeferredLightRenderer {
             }
         }
 
+        pass_stats += light_stats.shadow_pass;
+
         (pass_stats, light_stats)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::renderer::{
+        light::{apply_light_lod, LightLodEntry},
+        LightLodSettings,
+    };
+
+    fn entries(sizes: &[(f32, bool)]) -> Vec<LightLodEntry<usize>> {
+        sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &(screen_size, always_on))| LightLodEntry {
+                item: i,
+                screen_size,
+                always_on,
+                fade: 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_light_lod() {
+        let settings = LightLodSettings {
+            enabled: true,
+            cull_screen_size: 0.1,
+            fade_screen_size: 0.2,
+            max_lights: 0,
+        };
+
+        let mut lights = entries(&[(0.5, false), (0.15, false), (0.05, false), (0.01, true)]);
+        assert_eq!(apply_light_lod(&mut lights, &settings), 1);
+        let items = lights.iter().map(|e| e.item).collect::<Vec<_>>();
+        assert_eq!(items, vec![0, 1, 3]);
+        assert_eq!(lights[0].fade, 1.0);
+        assert!((lights[1].fade - 0.5).abs() < 0.001);
+        // Always-on lights are not faded.
+        assert_eq!(lights[2].fade, 1.0);
+
+        // Budget keeps always-on lights and the biggest ones.
+        let settings = LightLodSettings {
+            max_lights: 2,
+            ..settings
+        };
+        let mut lights = entries(&[(0.3, false), (0.9, false), (0.01, true), (0.5, false)]);
+        assert_eq!(apply_light_lod(&mut lights, &settings), 2);
+        let items = lights.iter().map(|e| e.item).collect::<Vec<_>>();
+        assert_eq!(items, vec![2, 1]);
+
+        let settings = LightLodSettings {
+            enabled: false,
+            ..settings
+        };
+        let mut lights = entries(&[(0.0, false); 4]);
+        assert_eq!(apply_light_lod(&mut lights, &settings), 0);
+        assert_eq!(lights.len(), 4);
+    }
+}
@@ -14,9 +14,12 @@ pub struct PointLightShader {
     pub material_sampler: UniformLocation,
     pub point_shadow_texture: UniformLocation,
     pub shadows_enabled: UniformLocation,
-    pub soft_shadows: UniformLocation,
+    pub shadow_filter: UniformLocation,
+    pub shadow_map_inv_size: UniformLocation,
     pub light_position: UniformLocation,
     pub light_radius: UniformLocation,
+    pub light_falloff_mode: UniformLocation,
+    pub light_falloff_exponent: UniformLocation,
     pub light_color: UniformLocation,
     pub inv_view_proj_matrix: UniformLocation,
     pub camera_position: UniformLocation,
@@ -45,9 +48,16 @@ impl PointLightShader {
                 .uniform_location(state, &ImmutableString::new("pointShadowTexture"))?,
             shadows_enabled: program
                 .uniform_location(state, &ImmutableString::new("shadowsEnabled"))?,
-            soft_shadows: program.uniform_location(state, &ImmutableString::new("softShadows"))?,
+            shadow_filter: program
+                .uniform_location(state, &ImmutableString::new("shadowFilter"))?,
+            shadow_map_inv_size: program
+                .uniform_location(state, &ImmutableString::new("shadowMapInvSize"))?,
             light_position: program.uniform_location(state, &ImmutableString::new("lightPos"))?,
             light_radius: program.uniform_location(state, &ImmutableString::new("lightRadius"))?,
+            light_falloff_mode: program
+                .uniform_location(state, &ImmutableString::new("lightFalloffMode"))?,
+            light_falloff_exponent: program
+                .uniform_location(state, &ImmutableString::new("lightFalloffExponent"))?,
             light_color: program.uniform_location(state, &ImmutableString::new("lightColor"))?,
             inv_view_proj_matrix: program
                 .uniform_location(state, &ImmutableString::new("invViewProj"))?,
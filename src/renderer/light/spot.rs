@@ -17,10 +17,12 @@ pub struct SpotLightShader {
     pub cookie_texture: UniformLocation,
     pub light_view_proj_matrix: UniformLocation,
     pub shadows_enabled: UniformLocation,
-    pub soft_shadows: UniformLocation,
+    pub shadow_filter: UniformLocation,
     pub shadow_map_inv_size: UniformLocation,
     pub light_position: UniformLocation,
     pub light_radius: UniformLocation,
+    pub light_falloff_mode: UniformLocation,
+    pub light_falloff_exponent: UniformLocation,
     pub light_color: UniformLocation,
     pub light_direction: UniformLocation,
     pub half_hotspot_cone_angle_cos: UniformLocation,
@@ -58,11 +60,16 @@ impl SpotLightShader {
                 .uniform_location(state, &ImmutableString::new("lightViewProjMatrix"))?,
             shadows_enabled: program
                 .uniform_location(state, &ImmutableString::new("shadowsEnabled"))?,
-            soft_shadows: program.uniform_location(state, &ImmutableString::new("softShadows"))?,
+            shadow_filter: program
+                .uniform_location(state, &ImmutableString::new("shadowFilter"))?,
             shadow_map_inv_size: program
                 .uniform_location(state, &ImmutableString::new("shadowMapInvSize"))?,
             light_position: program.uniform_location(state, &ImmutableString::new("lightPos"))?,
             light_radius: program.uniform_location(state, &ImmutableString::new("lightRadius"))?,
+            light_falloff_mode: program
+                .uniform_location(state, &ImmutableString::new("lightFalloffMode"))?,
+            light_falloff_exponent: program
+                .uniform_location(state, &ImmutableString::new("lightFalloffExponent"))?,
             light_color: program.uniform_location(state, &ImmutableString::new("lightColor"))?,
             light_direction: program
                 .uniform_location(state, &ImmutableString::new("lightDirection"))?,
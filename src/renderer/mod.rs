@@ -24,30 +24,35 @@ mod forward_renderer;
 mod fxaa;
 mod gbuffer;
 mod hdr;
+mod impostor_renderer;
 mod light;
 mod light_volume;
 mod particle_system_renderer;
+mod probe;
 mod shadow;
 mod skybox_shader;
 mod sprite_renderer;
 mod ssao;
 mod ui_renderer;
+mod water;
+mod world_text_renderer;
 
 use crate::renderer::framework::geometry_buffer::GeometryBufferKind;
 use crate::renderer::framework::gpu_program::BuiltInUniform;
 use crate::{
     core::{
-        algebra::{Matrix4, Vector2, Vector3},
+        algebra::{Matrix3, Matrix4, Vector2, Vector3},
         color::Color,
         instant,
         math::Rect,
         pool::Handle,
-        scope_profile,
+        profile_scope, scope_profile,
+        visitor::prelude::*,
     },
     gui::{draw::DrawingContext, UserInterface},
     material::{shader::SamplerFallback, Material, PropertyValue},
     renderer::{
-        batch::BatchStorage,
+        batch::{BatchStorage, BoneMatrixCache},
         bloom::BloomRenderer,
         cache::shader::ShaderCache,
         cache::{geometry::GeometryCache, texture::TextureCache, CacheEntry},
@@ -68,17 +73,27 @@ use crate::{
         fxaa::FxaaRenderer,
         gbuffer::{GBuffer, GBufferRenderContext},
         hdr::HighDynamicRangeRenderer,
+        impostor_renderer::{ImpostorBakeContext, ImpostorRenderContext, ImpostorRenderer},
         light::{DeferredLightRenderer, DeferredRendererContext, LightingStatistics},
         particle_system_renderer::{ParticleSystemRenderContext, ParticleSystemRenderer},
+        probe::{ProbeBake, ProbeRenderContext, ProbeRenderer},
         renderer2d::Renderer2d,
         sprite_renderer::{SpriteRenderContext, SpriteRenderer},
         ui_renderer::{UiRenderContext, UiRenderer},
+        water::{WaterRenderContext, WaterRenderer},
+        world_text_renderer::{WorldTextRenderContext, WorldTextRenderer},
     },
     resource::texture::{Texture, TextureKind},
-    scene::{camera::Camera, mesh::surface::SurfaceData, node::Node, Scene, SceneContainer},
+    scene::{
+        camera::Camera,
+        mesh::surface::{SurfaceData, TriplanarMapping},
+        node::Node,
+        Scene, SceneContainer,
+    },
     scene2d::Scene2dContainer,
 };
 use fxhash::FxHashMap;
+use glow::HasContext;
 use serde::{Deserialize, Serialize};
 use std::{
     cell::RefCell,
@@ -101,6 +116,10 @@ pub struct Statistics {
     pub lighting: LightingStatistics,
     /// Shows how many draw calls was made and how many triangles were rendered.
     pub geometry: RenderPassStatistics,
+    /// Shows how skinned meshes were rendered.
+    pub skinning: SkinningStatistics,
+    /// Shows how much GPU memory is occupied by textures.
+    pub texture_memory: TextureMemoryStatistics,
     /// Real time consumed to render frame. Time given in **seconds**.
     pub pure_frame_time: f32,
     /// Total time renderer took to process single frame, usually includes
@@ -123,12 +142,16 @@ impl Display for Statistics {
             Capped Frame Time: {:.2} ms\n\
             {}\n\
             {}\n\
+            {}\n\
+            {}\n\
             {}\n",
             self.frames_per_second,
             self.pure_frame_time * 1000.0,
             self.capped_frame_time * 1000.0,
             self.geometry,
+            self.skinning,
             self.lighting,
+            self.texture_memory,
             self.pipeline
         )
     }
@@ -168,6 +191,83 @@ impl std::ops::AddAssign<DrawCallStatistics> for RenderPassStatistics {
     }
 }
 
+/// Statistics of skinned meshes rendering for single frame.
+#[derive(Copy, Clone, Default)]
+pub struct SkinningStatistics {
+    /// Amount of instanced draw calls that were made for skinned surfaces.
+    pub instanced_draw_calls: usize,
+    /// Amount of skinned surface instances that were rendered by instanced draw calls.
+    pub instances_drawn: usize,
+    /// Amount of skinned surfaces that reused bone matrices from previous frame, because
+    /// their bones did not move.
+    pub bone_cache_hits: usize,
+    /// Amount of skinned surfaces that had their bone matrices recomputed.
+    pub bone_cache_misses: usize,
+}
+
+/// Shows how much GPU memory is occupied by textures and how textures were streamed during last
+/// frame, see [`Renderer::set_texture_memory_budget`].
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub struct TextureMemoryStatistics {
+    /// Max amount of bytes that textures can occupy, `None` - no limit.
+    pub budget: Option<usize>,
+    /// Amount of bytes that is occupied by textures.
+    pub used: usize,
+    /// Amount of textures that are uploaded without some of their top mip levels.
+    pub demoted_textures: usize,
+    /// Amount of times when top mip level of a texture was dropped.
+    pub demotions: usize,
+    /// Amount of times when top mip level of a texture was restored.
+    pub promotions: usize,
+}
+
+impl Display for TextureMemoryStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        const MEGABYTE: f32 = 1024.0 * 1024.0;
+        write!(f, "Texture Memory: {:.1} MiB", self.used as f32 / MEGABYTE)?;
+        if let Some(budget) = self.budget {
+            write!(
+                f,
+                " of {:.1} MiB\n\
+                \tDemoted Textures: {}\n\
+                \tDemotions: {}\n\
+                \tPromotions: {}",
+                budget as f32 / MEGABYTE,
+                self.demoted_textures,
+                self.demotions,
+                self.promotions
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for SkinningStatistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Skinning Statistics:\n\
+            \tInstanced Draw Calls: {}\n\
+            \tInstances Drawn: {}\n\
+            \tBone Cache Hits: {}\n\
+            \tBone Cache Misses: {}",
+            self.instanced_draw_calls,
+            self.instances_drawn,
+            self.bone_cache_hits,
+            self.bone_cache_misses
+        )
+    }
+}
+
+impl std::ops::AddAssign for SkinningStatistics {
+    fn add_assign(&mut self, rhs: Self) {
+        self.instanced_draw_calls += rhs.instanced_draw_calls;
+        self.instances_drawn += rhs.instances_drawn;
+        self.bone_cache_hits += rhs.bone_cache_hits;
+        self.bone_cache_misses += rhs.bone_cache_misses;
+    }
+}
+
 impl std::ops::AddAssign<RenderPassStatistics> for Statistics {
     fn add_assign(&mut self, rhs: RenderPassStatistics) {
         self.geometry += rhs;
@@ -175,7 +275,9 @@ impl std::ops::AddAssign<RenderPassStatistics> for Statistics {
 }
 
 /// Shadow map precision allows you to select compromise between quality and performance.
-#[derive(Copy, Clone, Hash, PartialOrd, PartialEq, Eq, Ord, Debug, Serialize, Deserialize)]
+#[derive(
+    Copy, Clone, Hash, PartialOrd, PartialEq, Eq, Ord, Debug, Serialize, Deserialize, Visit,
+)]
 pub enum ShadowMapPrecision {
     /// Shadow map will use 2 times less memory by switching to 16bit pixel format,
     /// but "shadow acne" may occur.
@@ -185,6 +287,22 @@ pub enum ShadowMapPrecision {
     Full,
 }
 
+/// Filter that is used to smooth edges of point and spot light shadows. Each filter makes
+/// specified amount of fetches from a shadow map per pixel, so better filters are slower.
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug, Serialize, Deserialize, Visit)]
+#[repr(i32)]
+pub enum ShadowFilter {
+    /// No filtering, shadows will have hard aliased edges. Single fetch.
+    Off = 0,
+    /// Percentage-closer filtering with 2x2 kernel. 4 fetches.
+    Pcf2x2 = 1,
+    /// Percentage-closer filtering with 4x4 kernel. 16 fetches.
+    Pcf4x4 = 2,
+    /// Percentage-closer filtering with 16 samples distributed over a Poisson disk. Gives softer
+    /// edges than `Pcf4x4` without visible banding. 16 fetches.
+    Poisson = 3,
+}
+
 /// Cascaded-shadow maps settings.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CsmSettings {
@@ -213,6 +331,41 @@ impl Default for CsmSettings {
     }
 }
 
+/// Settings of automatic culling of point and spot lights that are too small on screen to make
+/// a visible contribution. Size of a light on screen is the diameter of its volume projected on
+/// the screen divided by the screen height, so it is 1.0 for lights that cover the whole screen.
+/// Directional lights and lights marked as always on (see
+/// [`crate::scene::light::BaseLight::set_always_on`]) are never culled.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightLodSettings {
+    /// Whether light culling is enabled or not.
+    pub enabled: bool,
+
+    /// Lights that are smaller than this on screen are not rendered.
+    pub cull_screen_size: f32,
+
+    /// Lights that are smaller than this on screen (but bigger than `cull_screen_size`) are
+    /// faded out smoothly, so they won't pop when culled. Set it equal to `cull_screen_size` to
+    /// disable fading.
+    pub fade_screen_size: f32,
+
+    /// Maximum amount of lights rendered per frame, zero means no limit. When there are more
+    /// lights on screen, the smallest ones are not rendered. Always-on lights count towards the
+    /// limit, but they're never culled.
+    pub max_lights: usize,
+}
+
+impl Default for LightLodSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            cull_screen_size: 0.005,
+            fade_screen_size: 0.015,
+            max_lights: 0,
+        }
+    }
+}
+
 /// Quality settings allows you to find optimal balance between performance and
 /// graphics quality.
 #[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
@@ -220,8 +373,8 @@ pub struct QualitySettings {
     /// Point shadows
     /// Size of cube map face of shadow map texture in pixels.
     pub point_shadow_map_size: usize,
-    /// Use or not percentage close filtering (smoothing) for point shadows.
-    pub point_soft_shadows: bool,
+    /// Filter that is used to smooth edges of point shadows.
+    pub point_shadow_filter: ShadowFilter,
     /// Point shadows enabled or not.
     pub point_shadows_enabled: bool,
     /// Maximum distance from camera to draw shadows.
@@ -233,8 +386,8 @@ pub struct QualitySettings {
     /// Spot shadows
     /// Size of square shadow map texture in pixels
     pub spot_shadow_map_size: usize,
-    /// Use or not percentage close filtering (smoothing) for spot shadows.
-    pub spot_soft_shadows: bool,
+    /// Filter that is used to smooth edges of spot shadows.
+    pub spot_shadow_filter: ShadowFilter,
     /// Spot shadows enabled or not.
     pub spot_shadows_enabled: bool,
     /// Maximum distance from camera to draw shadows.
@@ -264,6 +417,26 @@ pub struct QualitySettings {
 
     /// Whether to use bloom effect.
     pub use_bloom: bool,
+
+    /// Culling of lights that are too small on screen.
+    pub light_lod: LightLodSettings,
+
+    /// Whether to draw instances of the same skinned surface with a single draw call or not.
+    /// Bone matrices of all such instances are packed into one texture per frame. The result
+    /// is visually identical to separate draw calls, but it is much faster for crowds of
+    /// characters sharing the same model. Only G-Buffer pass is affected.
+    pub use_skinned_instancing: bool,
+
+    /// Whether water surfaces reflect the scene or not. Every water surface with reflections
+    /// requires an additional render of the scene, when disabled water shows only refraction.
+    pub water_reflections_enabled: bool,
+
+    /// Size of reflection map of water surfaces (in texels).
+    pub water_reflection_map_size: usize,
+
+    /// Whether environment probes are baked and used for reflections or not. When disabled,
+    /// probes are not baked and shiny surfaces reflect nothing.
+    pub use_environment_probes: bool,
 }
 
 impl Default for QualitySettings {
@@ -279,12 +452,12 @@ impl QualitySettings {
             point_shadow_map_size: 2048,
             point_shadows_distance: 20.0,
             point_shadows_enabled: true,
-            point_soft_shadows: true,
+            point_shadow_filter: ShadowFilter::Poisson,
 
             spot_shadow_map_size: 2048,
             spot_shadows_distance: 20.0,
             spot_shadows_enabled: true,
-            spot_soft_shadows: true,
+            spot_shadow_filter: ShadowFilter::Poisson,
 
             use_ssao: true,
             ssao_radius: 0.5,
@@ -301,6 +474,18 @@ impl QualitySettings {
             use_parallax_mapping: false, // TODO: Enable when it is fixed!
 
             csm_settings: Default::default(),
+
+            light_lod: LightLodSettings {
+                enabled: false,
+                ..Default::default()
+            },
+
+            use_skinned_instancing: true,
+
+            water_reflections_enabled: true,
+            water_reflection_map_size: 1024,
+
+            use_environment_probes: true,
         }
     }
 
@@ -310,12 +495,12 @@ impl QualitySettings {
             point_shadow_map_size: 1024,
             point_shadows_distance: 15.0,
             point_shadows_enabled: true,
-            point_soft_shadows: true,
+            point_shadow_filter: ShadowFilter::Pcf4x4,
 
             spot_shadow_map_size: 1024,
             spot_shadows_distance: 15.0,
             spot_shadows_enabled: true,
-            spot_soft_shadows: true,
+            spot_shadow_filter: ShadowFilter::Pcf4x4,
 
             use_ssao: true,
             ssao_radius: 0.5,
@@ -337,6 +522,15 @@ impl QualitySettings {
                 precision: ShadowMapPrecision::Full,
                 pcf: true,
             },
+
+            light_lod: Default::default(),
+
+            use_skinned_instancing: true,
+
+            water_reflections_enabled: true,
+            water_reflection_map_size: 512,
+
+            use_environment_probes: true,
         }
     }
 
@@ -346,12 +540,12 @@ impl QualitySettings {
             point_shadow_map_size: 512,
             point_shadows_distance: 5.0,
             point_shadows_enabled: true,
-            point_soft_shadows: false,
+            point_shadow_filter: ShadowFilter::Off,
 
             spot_shadow_map_size: 512,
             spot_shadows_distance: 5.0,
             spot_shadows_enabled: true,
-            spot_soft_shadows: false,
+            spot_shadow_filter: ShadowFilter::Off,
 
             use_ssao: true,
             ssao_radius: 0.5,
@@ -373,6 +567,20 @@ impl QualitySettings {
                 precision: ShadowMapPrecision::Full,
                 pcf: false,
             },
+
+            light_lod: LightLodSettings {
+                enabled: true,
+                cull_screen_size: 0.01,
+                fade_screen_size: 0.03,
+                max_lights: 128,
+            },
+
+            use_skinned_instancing: true,
+
+            water_reflections_enabled: true,
+            water_reflection_map_size: 256,
+
+            use_environment_probes: true,
         }
     }
 
@@ -382,12 +590,12 @@ impl QualitySettings {
             point_shadow_map_size: 1, // Zero is unsupported.
             point_shadows_distance: 0.0,
             point_shadows_enabled: false,
-            point_soft_shadows: false,
+            point_shadow_filter: ShadowFilter::Off,
 
             spot_shadow_map_size: 1,
             spot_shadows_distance: 0.0,
             spot_shadows_enabled: false,
-            spot_soft_shadows: false,
+            spot_shadow_filter: ShadowFilter::Off,
 
             use_ssao: false,
             ssao_radius: 0.5,
@@ -409,15 +617,175 @@ impl QualitySettings {
                 precision: ShadowMapPrecision::Half,
                 pcf: false,
             },
+
+            light_lod: LightLodSettings {
+                enabled: true,
+                cull_screen_size: 0.02,
+                fade_screen_size: 0.05,
+                max_lights: 32,
+            },
+
+            use_skinned_instancing: true,
+
+            water_reflections_enabled: false,
+            water_reflection_map_size: 256,
+
+            use_environment_probes: false,
         }
     }
 }
 
+// Every field is optional, missing or malformed field keeps its current value. This allows
+// to load settings that were saved by older versions of the engine.
+fn visit_size(size: &mut usize, name: &str, visitor: &mut Visitor) {
+    let mut value = *size as u32;
+    if value.visit(name, visitor).is_ok() && visitor.is_reading() {
+        *size = value as usize;
+    }
+}
+
+impl Visit for CsmSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let _ = self.enabled.visit("Enabled", visitor);
+        visit_size(&mut self.size, "Size", visitor);
+        let _ = self.precision.visit("Precision", visitor);
+        let _ = self.pcf.visit("Pcf", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+impl Visit for LightLodSettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        let _ = self.enabled.visit("Enabled", visitor);
+        let _ = self.cull_screen_size.visit("CullScreenSize", visitor);
+        let _ = self.fade_screen_size.visit("FadeScreenSize", visitor);
+        visit_size(&mut self.max_lights, "MaxLights", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+impl Visit for QualitySettings {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        visit_size(
+            &mut self.point_shadow_map_size,
+            "PointShadowMapSize",
+            visitor,
+        );
+        let _ = self.point_shadow_filter.visit("PointShadowFilter", visitor);
+        let _ = self
+            .point_shadows_enabled
+            .visit("PointShadowsEnabled", visitor);
+        let _ = self
+            .point_shadows_distance
+            .visit("PointShadowsDistance", visitor);
+        let _ = self
+            .point_shadow_map_precision
+            .visit("PointShadowMapPrecision", visitor);
+
+        visit_size(&mut self.spot_shadow_map_size, "SpotShadowMapSize", visitor);
+        let _ = self.spot_shadow_filter.visit("SpotShadowFilter", visitor);
+        let _ = self
+            .spot_shadows_enabled
+            .visit("SpotShadowsEnabled", visitor);
+        let _ = self
+            .spot_shadows_distance
+            .visit("SpotShadowsDistance", visitor);
+        let _ = self
+            .spot_shadow_map_precision
+            .visit("SpotShadowMapPrecision", visitor);
+
+        let _ = self.csm_settings.visit("CsmSettings", visitor);
+
+        let _ = self.use_ssao.visit("UseSsao", visitor);
+        let _ = self.ssao_radius.visit("SsaoRadius", visitor);
+        let _ = self
+            .light_scatter_enabled
+            .visit("LightScatterEnabled", visitor);
+        let _ = self.fxaa.visit("Fxaa", visitor);
+        let _ = self
+            .use_parallax_mapping
+            .visit("UseParallaxMapping", visitor);
+        let _ = self.use_bloom.visit("UseBloom", visitor);
+        let _ = self.light_lod.visit("LightLod", visitor);
+        let _ = self
+            .use_skinned_instancing
+            .visit("UseSkinnedInstancing", visitor);
+        let _ = self
+            .water_reflections_enabled
+            .visit("WaterReflectionsEnabled", visitor);
+        let _ = self
+            .water_reflection_map_size
+            .visit("WaterReflectionMapSize", visitor);
+        let _ = self
+            .use_environment_probes
+            .visit("UseEnvironmentProbes", visitor);
+
+        visitor.leave_region()
+    }
+}
+
+/// A set of GPU capabilities, could be used to select quality settings that suits current GPU.
+#[derive(Debug, Clone, Default)]
+pub struct GpuCapabilities {
+    /// Name of the GPU vendor.
+    pub vendor: String,
+    /// Name of the GPU (or driver, if the driver does not tell the real name).
+    pub renderer: String,
+    /// Maximum width and height of a 2D texture in pixels.
+    pub max_texture_size: u32,
+    /// Maximum width and height of a cube map face in pixels.
+    pub max_cube_map_size: u32,
+    /// A list of supported OpenGL extensions.
+    pub extensions: Vec<String>,
+}
+
+impl GpuCapabilities {
+    /// Returns `true` if given extension is supported.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|e| e == name)
+    }
+
+    /// Returns `true` if the GPU is a software rasterizer (llvmpipe, SwiftShader, etc.). Software
+    /// rasterizers are very slow.
+    pub fn is_software(&self) -> bool {
+        let renderer = self.renderer.to_lowercase();
+        ["llvmpipe", "softpipe", "swiftshader", "software"]
+            .iter()
+            .any(|name| renderer.contains(name))
+    }
+
+    /// Returns `true` if the GPU is most likely integrated into CPU.
+    pub fn is_integrated(&self) -> bool {
+        let renderer = self.renderer.to_lowercase();
+        self.vendor.to_lowercase().contains("intel")
+            || [
+                "intel",
+                "uhd graphics",
+                "hd graphics",
+                "iris",
+                "vega 8",
+                "mali",
+                "adreno",
+            ]
+            .iter()
+            .any(|name| renderer.contains(name))
+    }
+}
+
 impl Statistics {
     /// Must be called before render anything.
     fn begin_frame(&mut self) {
         self.frame_start_time = instant::Instant::now();
         self.geometry = Default::default();
+        self.skinning = Default::default();
         self.lighting = Default::default();
     }
 
@@ -455,6 +823,8 @@ impl Default for Statistics {
             pipeline: Default::default(),
             lighting: Default::default(),
             geometry: Default::default(),
+            skinning: Default::default(),
+            texture_memory: Default::default(),
             pure_frame_time: 0.0,
             capped_frame_time: 0.0,
             frames_per_second: 0,
@@ -500,6 +870,12 @@ struct AssociatedSceneData {
     /// Bloom contains only overly bright pixels that creates light
     /// bleeding effect (glow effect).
     pub bloom_renderer: BloomRenderer,
+
+    /// Bone matrices of skinned surfaces of the scene from previous frame.
+    pub bone_matrix_cache: BoneMatrixCache,
+
+    /// Environment probe of the scene that is being baked.
+    pub probe_bake: Option<ProbeBake>,
 }
 
 impl AssociatedSceneData {
@@ -600,6 +976,8 @@ impl AssociatedSceneData {
             hdr_scene_framebuffer,
             ldr_scene_framebuffer,
             ldr_temp_framebuffer,
+            bone_matrix_cache: Default::default(),
+            probe_bake: None,
         })
     }
 
@@ -640,7 +1018,8 @@ impl AssociatedSceneData {
     }
 }
 
-pub(in crate) fn make_viewport_matrix(viewport: Rect<i32>) -> Matrix4<f32> {
+/// Creates world-view-projection matrix that stretches a unit quad to the whole viewport.
+pub fn make_viewport_matrix(viewport: Rect<i32>) -> Matrix4<f32> {
     Matrix4::new_orthographic(
         0.0,
         viewport.w() as f32,
@@ -655,14 +1034,67 @@ pub(in crate) fn make_viewport_matrix(viewport: Rect<i32>) -> Matrix4<f32> {
     ))
 }
 
+// Calls every custom render pass of given stage that should be called for the scene. This is a
+// macro, because the passes have to borrow separate fields of the renderer.
+macro_rules! render_custom_passes {
+    (
+        $renderer:ident,
+        $stage:expr,
+        $state:expr,
+        $scene:expr,
+        $scene_handle:expr,
+        $camera:expr,
+        $viewport:expr,
+        $scene_data:expr,
+        $framebuffer:expr
+    ) => {
+        for entry in $renderer
+            .scene_render_passes
+            .iter()
+            .filter(|entry| entry.stage == $stage && entry.scope.includes($scene_handle))
+        {
+            let depth_texture = $scene_data.gbuffer.depth();
+            let normal_texture = $scene_data.gbuffer.normal_texture();
+            let ambient_texture = $scene_data.gbuffer.ambient_texture();
+            $renderer.statistics += entry.pass.lock().unwrap().render(SceneRenderPassContext {
+                stage: $stage,
+                pipeline_state: $state,
+                texture_cache: &mut $renderer.texture_cache,
+                geometry_cache: &mut $renderer.geometry_cache,
+                quality_settings: &$renderer.quality_settings,
+                batch_storage: &$renderer.batch_storage,
+                viewport: $viewport,
+                scene: $scene,
+                camera: $camera,
+                scene_handle: $scene_handle,
+                white_dummy: $renderer.white_dummy.clone(),
+                normal_dummy: $renderer.normal_dummy.clone(),
+                metallic_dummy: $renderer.metallic_dummy.clone(),
+                environment_dummy: $renderer.environment_dummy.clone(),
+                black_dummy: $renderer.black_dummy.clone(),
+                depth_texture,
+                normal_texture,
+                ambient_texture,
+                quad: &$renderer.quad,
+                framebuffer: $framebuffer,
+            })?;
+        }
+    };
+}
+
 /// See module docs.
 pub struct Renderer {
     backbuffer: FrameBuffer,
-    scene_render_passes: Vec<Arc<Mutex<dyn SceneRenderPass>>>,
+    scene_render_passes: Vec<RenderPassEntry>,
+    render_pass_counter: u64,
     deferred_light_renderer: DeferredLightRenderer,
     flat_shader: FlatShader,
     sprite_renderer: SpriteRenderer,
     particle_system_renderer: ParticleSystemRenderer,
+    water_renderer: WaterRenderer,
+    probe_renderer: ProbeRenderer,
+    impostor_renderer: ImpostorRenderer,
+    world_text_renderer: WorldTextRenderer,
     // Dummy white one pixel texture which will be used as stub when rendering
     // something without texture specified.
     white_dummy: Rc<RefCell<GpuTexture>>,
@@ -743,8 +1175,66 @@ fn make_ui_frame_buffer(
     )
 }
 
+/// Defines a point of a frame at which custom render pass will be called.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RenderPassStage {
+    /// Right after G-Buffer was cleared, but before any geometry was written to it. Frame buffer
+    /// is the G-Buffer itself, so the pass can write custom geometry into it, this geometry will
+    /// be lit as usual. Fragment shader must write to every G-Buffer attachment (diffuse, normal,
+    /// ambient, material and decal mask) to get correct results.
+    BeforeGBuffer,
+
+    /// Right after deferred lighting was done, but before particle systems, sprites and
+    /// forward-rendered meshes. Frame buffer is HDR frame buffer of the scene.
+    AfterLighting,
+
+    /// After all scene geometry (including transparent) was rendered, but before bloom and tone
+    /// mapping. Frame buffer is HDR frame buffer of the scene.
+    AfterTransparent,
+
+    /// After tone mapping, FXAA and debug geometry, right before the frame is shown on screen
+    /// and user interface is drawn on top of it. Frame buffer is LDR (sRGB) frame buffer of the
+    /// scene. This is the best place for full screen effects like vignette.
+    BeforeUi,
+}
+
+/// Defines which scenes a custom render pass will be called for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RenderPassScope {
+    /// The pass will be called for every scene.
+    Global,
+
+    /// The pass will be called only for given scene.
+    Scene(Handle<Scene>),
+}
+
+impl RenderPassScope {
+    /// Returns `true` if the scope includes given scene.
+    pub fn includes(&self, scene: Handle<Scene>) -> bool {
+        match *self {
+            RenderPassScope::Global => true,
+            RenderPassScope::Scene(handle) => handle == scene,
+        }
+    }
+}
+
+/// A handle of a custom render pass that was added to the renderer. Can be used to remove the
+/// pass.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderPassHandle(u64);
+
+struct RenderPassEntry {
+    handle: RenderPassHandle,
+    stage: RenderPassStage,
+    scope: RenderPassScope,
+    pass: Arc<Mutex<dyn SceneRenderPass>>,
+}
+
 /// A context for custom scene render passes.
 pub struct SceneRenderPassContext<'a, 'b> {
+    /// A stage at which the pass is being called.
+    pub stage: RenderPassStage,
+
     /// A pipeline state that is used as a wrapper to underlying graphics API.
     pub pipeline_state: &'a mut PipelineState,
 
@@ -763,9 +1253,14 @@ pub struct SceneRenderPassContext<'a, 'b> {
     /// Current quality settings of the renderer.
     pub quality_settings: &'a QualitySettings,
 
-    /// Current framebuffer to which scene is being rendered to.
+    /// Current framebuffer to which scene is being rendered to. It depends on the stage, see
+    /// [`RenderPassStage`] docs.
     pub framebuffer: &'a mut FrameBuffer,
 
+    /// A unit quad in XY plane that can be used to draw full screen effects, use
+    /// [`make_viewport_matrix`] to get world-view-projection matrix for it.
+    pub quad: &'a GeometryBuffer,
+
     /// A scene being rendered.
     pub scene: &'b Scene,
 
@@ -824,8 +1319,9 @@ pub struct SceneRenderPassContext<'a, 'b> {
 
 /// A trait for custom scene rendering pass. It could be used to add your own rendering techniques.
 pub trait SceneRenderPass {
-    /// Main rendering method. It will be called for **each** scene registered in the engine, but
-    /// you are able to filter out scene by its handle.
+    /// Main rendering method. It will be called for **each** scene registered in the engine (or
+    /// only for a specific scene, see [`RenderPassScope`]) and for each camera of the scene at
+    /// the stage the pass was added with.
     fn render(
         &mut self,
         ctx: SceneRenderPassContext,
@@ -887,7 +1383,15 @@ pub(in crate) struct MaterialContext<'a, 'b, 'c> {
     pub use_skeletal_animation: bool,
     pub camera_position: &'a Vector3<f32>,
     pub use_pom: bool,
+    pub opacity: f32,
+    pub color: Color,
     pub light_position: &'a Vector3<f32>,
+    pub uv_transform: &'a Matrix3<f32>,
+    pub mip_bias: f32,
+    pub receive_shadows: bool,
+    pub alpha_cutoff: f32,
+    pub triplanar_mapping: Option<TriplanarMapping>,
+    pub instanced_bones: Option<InstancedBones<'a>>,
 
     // Fallback samplers.
     pub normal_dummy: Rc<RefCell<GpuTexture>>,
@@ -895,6 +1399,14 @@ pub(in crate) struct MaterialContext<'a, 'b, 'c> {
     pub black_dummy: Rc<RefCell<GpuTexture>>,
 }
 
+/// Location of bone matrices of skinned instances that are drawn by single instanced draw call.
+pub(in crate) struct InstancedBones<'a> {
+    /// Texture with bone matrices, every row contains matrices of one instance.
+    pub texture: &'a Rc<RefCell<GpuTexture>>,
+    /// Row of the texture with bone matrices of the first instance.
+    pub row_offset: usize,
+}
+
 pub(in crate) fn apply_material(ctx: MaterialContext) {
     let built_in_uniforms = &ctx.program_binding.program.built_in_uniform_locations;
 
@@ -913,6 +1425,27 @@ pub(in crate) fn apply_material(ctx: MaterialContext) {
         ctx.program_binding
             .set_bool(location, ctx.use_skeletal_animation);
     }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::UseInstancedSkinning as usize] {
+        ctx.program_binding
+            .set_bool(location, ctx.instanced_bones.is_some());
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::BoneMatricesTexture as usize] {
+        // Sampler must be bound even if it is not used, otherwise it will point to the same
+        // texture unit as the first sampler of the material.
+        let texture = ctx
+            .instanced_bones
+            .as_ref()
+            .map_or(&ctx.black_dummy, |bones| bones.texture);
+        ctx.program_binding.set_texture(location, texture);
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::BoneMatricesRowOffset as usize] {
+        ctx.program_binding.set_i32(
+            location,
+            ctx.instanced_bones
+                .as_ref()
+                .map_or(0, |bones| bones.row_offset as i32),
+        );
+    }
     if let Some(location) = &built_in_uniforms[BuiltInUniform::CameraPosition as usize] {
         ctx.program_binding
             .set_vector3(location, ctx.camera_position);
@@ -920,6 +1453,34 @@ pub(in crate) fn apply_material(ctx: MaterialContext) {
     if let Some(location) = &built_in_uniforms[BuiltInUniform::UsePOM as usize] {
         ctx.program_binding.set_bool(location, ctx.use_pom);
     }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::Opacity as usize] {
+        ctx.program_binding.set_f32(location, ctx.opacity);
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::Color as usize] {
+        ctx.program_binding.set_srgb_color(location, &ctx.color);
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::UvTransform as usize] {
+        ctx.program_binding.set_matrix3(location, ctx.uv_transform);
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::MipBias as usize] {
+        ctx.program_binding.set_f32(location, ctx.mip_bias);
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::ReceiveShadows as usize] {
+        ctx.program_binding.set_bool(location, ctx.receive_shadows);
+    }
+    if let Some(location) = &built_in_uniforms[BuiltInUniform::AlphaCutoff as usize] {
+        ctx.program_binding.set_f32(location, ctx.alpha_cutoff);
+    }
+    if let Some(triplanar_mapping) = ctx.triplanar_mapping {
+        if let Some(location) = &built_in_uniforms[BuiltInUniform::TriplanarSharpness as usize] {
+            ctx.program_binding
+                .set_f32(location, triplanar_mapping.sharpness);
+        }
+        if let Some(location) = &built_in_uniforms[BuiltInUniform::TriplanarScale as usize] {
+            ctx.program_binding
+                .set_f32(location, triplanar_mapping.scale);
+        }
+    }
     if let Some(location) = &built_in_uniforms[BuiltInUniform::LightPosition as usize] {
         ctx.program_binding
             .set_vector3(location, ctx.light_position);
@@ -1025,6 +1586,10 @@ impl Renderer {
             deferred_light_renderer: DeferredLightRenderer::new(&mut state, frame_size, &settings)?,
             flat_shader: FlatShader::new(&mut state)?,
             sprite_renderer: SpriteRenderer::new(&mut state)?,
+            water_renderer: WaterRenderer::new(&mut state)?,
+            probe_renderer: ProbeRenderer::new(&mut state)?,
+            impostor_renderer: ImpostorRenderer::new(&mut state)?,
+            world_text_renderer: WorldTextRenderer::new(&mut state)?,
             white_dummy: Rc::new(RefCell::new(GpuTexture::new(
                 &mut state,
                 GpuTextureKind::Rectangle {
@@ -1116,12 +1681,61 @@ impl Renderer {
             state,
             shader_cache: ShaderCache::default(),
             scene_render_passes: Default::default(),
+            render_pass_counter: 0,
         })
     }
 
-    /// Adds a custom render pass.
-    pub fn add_render_pass(&mut self, pass: Arc<Mutex<dyn SceneRenderPass>>) {
-        self.scene_render_passes.push(pass);
+    /// Adds a custom render pass that will be called at given stage for every scene. Passes of
+    /// the same stage are called in order of addition. Returned handle can be used to remove
+    /// the pass.
+    pub fn add_render_pass(
+        &mut self,
+        stage: RenderPassStage,
+        pass: Arc<Mutex<dyn SceneRenderPass>>,
+    ) -> RenderPassHandle {
+        self.add_render_pass_with_scope(stage, RenderPassScope::Global, pass)
+    }
+
+    /// Adds a custom render pass that will be called at given stage only for specified scene.
+    /// See [`Self::add_render_pass`] for more info.
+    pub fn add_scene_render_pass(
+        &mut self,
+        scene: Handle<Scene>,
+        stage: RenderPassStage,
+        pass: Arc<Mutex<dyn SceneRenderPass>>,
+    ) -> RenderPassHandle {
+        self.add_render_pass_with_scope(stage, RenderPassScope::Scene(scene), pass)
+    }
+
+    /// Adds a custom render pass with given stage and scope.
+    pub fn add_render_pass_with_scope(
+        &mut self,
+        stage: RenderPassStage,
+        scope: RenderPassScope,
+        pass: Arc<Mutex<dyn SceneRenderPass>>,
+    ) -> RenderPassHandle {
+        let handle = RenderPassHandle(self.render_pass_counter);
+        self.render_pass_counter += 1;
+        self.scene_render_passes.push(RenderPassEntry {
+            handle,
+            stage,
+            scope,
+            pass,
+        });
+        handle
+    }
+
+    /// Removes a custom render pass, returns `true` if the pass was removed.
+    pub fn remove_render_pass(&mut self, handle: RenderPassHandle) -> bool {
+        let count = self.scene_render_passes.len();
+        self.scene_render_passes
+            .retain(|entry| entry.handle != handle);
+        self.scene_render_passes.len() != count
+    }
+
+    /// Returns total amount of registered custom render passes.
+    pub fn render_pass_count(&self) -> usize {
+        self.scene_render_passes.len()
     }
 
     /// Returns statistics for last frame.
@@ -1134,6 +1748,19 @@ impl Renderer {
         self.texture_cache.unload(texture)
     }
 
+    /// Sets max amount of GPU memory (in bytes) that can be occupied by textures. When textures
+    /// occupy more memory, top mip levels of the least recently used textures are dropped and
+    /// restored back when there is enough memory. `None` (default) disables the budget. See
+    /// [`cache::texture`] module docs for more info.
+    pub fn set_texture_memory_budget(&mut self, budget: Option<usize>) {
+        self.texture_cache.set_budget(budget);
+    }
+
+    /// Returns current texture memory budget.
+    pub fn texture_memory_budget(&self) -> Option<usize> {
+        self.texture_cache.budget()
+    }
+
     /// Sets color which will be used to fill screen when there is nothing to render.
     pub fn set_backbuffer_clear_color(&mut self, color: Color) {
         self.backbuffer_clear_color = color;
@@ -1195,12 +1822,35 @@ impl Renderer {
         self.quality_settings
     }
 
+    /// Queries capabilities of current GPU.
+    pub fn gpu_capabilities(&self) -> GpuCapabilities {
+        let gl = &self.state.gl;
+        unsafe {
+            let mut extensions = gl
+                .supported_extensions()
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>();
+            extensions.sort();
+
+            GpuCapabilities {
+                vendor: gl.get_parameter_string(glow::VENDOR),
+                renderer: gl.get_parameter_string(glow::RENDERER),
+                max_texture_size: gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE).max(0) as u32,
+                max_cube_map_size: gl.get_parameter_i32(glow::MAX_CUBE_MAP_TEXTURE_SIZE).max(0)
+                    as u32,
+                extensions,
+            }
+        }
+    }
+
     /// Removes all cached GPU data, forces renderer to re-upload data to GPU.
     /// Do not call this method until you absolutely need! It may cause **significant**
     /// performance lag!
     pub fn flush(&mut self) {
         self.texture_cache.clear();
         self.geometry_cache.clear();
+        self.world_text_renderer.clear();
         self.renderer2d.flush();
     }
 
@@ -1248,8 +1898,9 @@ impl Renderer {
             state: &mut self.state,
             viewport,
             frame_buffer,
-            frame_width: ui.screen_size().x,
-            frame_height: ui.screen_size().y,
+            frame_width: ui.screen_size().x * ui.scale(),
+            frame_height: ui.screen_size().y * ui.scale(),
+            scale: ui.scale(),
             drawing_context: ui.draw(),
             white_dummy: self.white_dummy.clone(),
             texture_cache: &mut self.texture_cache,
@@ -1283,7 +1934,11 @@ impl Renderer {
         let mut uploaded = 0;
         while let Ok(texture) = self.texture_upload_receiver.try_recv() {
             // Just "touch" texture in the cache and it will load texture to GPU.
-            if self.texture_cache.get(&mut self.state, &texture).is_some() {
+            if self
+                .texture_cache
+                .upload(&mut self.state, &texture)
+                .is_some()
+            {
                 uploaded += 1;
                 if uploaded >= THROUGHPUT {
                     break;
@@ -1298,6 +1953,7 @@ impl Renderer {
         // Update caches - this will remove timed out resources.
         self.update_texture_cache(dt);
         self.geometry_cache.update(dt);
+        self.world_text_renderer.update(dt);
         self.renderer2d.update(dt);
     }
 
@@ -1305,9 +1961,11 @@ impl Renderer {
         &mut self,
         scenes: &SceneContainer,
         drawing_context: &DrawingContext,
+        ui_scale: f32,
         scenes2d: &Scene2dContainer,
     ) -> Result<(), FrameworkError> {
         scope_profile!();
+        profile_scope!("Render");
 
         // Make sure to drop associated data for destroyed scenes.
         self.scene_data_map
@@ -1321,6 +1979,9 @@ impl Renderer {
         let dt = self.statistics.capped_frame_time;
         self.statistics.begin_frame();
 
+        self.texture_cache.update_streaming(&mut self.state);
+        self.statistics.texture_memory = self.texture_cache.statistics();
+
         let window_viewport = Rect::new(0, 0, self.frame_size.0 as i32, self.frame_size.1 as i32);
         self.backbuffer.clear(
             &mut self.state,
@@ -1333,7 +1994,7 @@ impl Renderer {
         let backbuffer_width = self.frame_size.0 as f32;
         let backbuffer_height = self.frame_size.1 as f32;
 
-        for (scene_handle, scene) in scenes.pair_iter().filter(|(_, s)| s.enabled) {
+        for (scene_handle, scene) in scenes.render_queue() {
             let graph = &scene.graph;
 
             let frame_size = scene
@@ -1356,8 +2017,6 @@ impl Renderer {
 
             let state = &mut self.state;
 
-            self.batch_storage.generate_batches(graph);
-
             let scene_associated_data = self
                 .scene_data_map
                 .entry(scene_handle)
@@ -1376,6 +2035,13 @@ impl Renderer {
                     AssociatedSceneData::new(state, width, height).unwrap()
                 });
 
+            self.batch_storage
+                .generate_batches(graph, &mut scene_associated_data.bone_matrix_cache);
+            self.statistics.skinning.bone_cache_hits +=
+                scene_associated_data.bone_matrix_cache.hits;
+            self.statistics.skinning.bone_cache_misses +=
+                scene_associated_data.bone_matrix_cache.misses;
+
             // If we specified a texture to draw to, we have to register it in texture cache
             // so it can be used in later on as texture. This is useful in case if you need
             // to draw something on offscreen and then draw it on some mesh.
@@ -1392,6 +2058,34 @@ impl Renderer {
                 );
             }
 
+            if self.quality_settings.use_environment_probes {
+                self.statistics += self.probe_renderer.render(ProbeRenderContext {
+                    state,
+                    graph,
+                    bake: &mut scene_associated_data.probe_bake,
+                    geom_cache: &mut self.geometry_cache,
+                    texture_cache: &mut self.texture_cache,
+                    shader_cache: &mut self.shader_cache,
+                    batch_storage: &self.batch_storage,
+                    white_dummy: self.white_dummy.clone(),
+                    normal_dummy: self.normal_dummy.clone(),
+                    black_dummy: self.black_dummy.clone(),
+                })?;
+            }
+
+            self.statistics += self.impostor_renderer.bake(ImpostorBakeContext {
+                state,
+                graph,
+                geom_cache: &mut self.geometry_cache,
+                texture_cache: &mut self.texture_cache,
+                shader_cache: &mut self.shader_cache,
+                white_dummy: self.white_dummy.clone(),
+                normal_dummy: self.normal_dummy.clone(),
+                black_dummy: self.black_dummy.clone(),
+            })?;
+
+            let mut has_billboards = false;
+
             for camera in graph.linear_iter().filter_map(|node| {
                 if let Node::Camera(camera) = node {
                     if camera.is_enabled() {
@@ -1405,6 +2099,29 @@ impl Renderer {
             }) {
                 let viewport = camera.viewport_pixels(frame_size);
 
+                // Billboards face each camera separately, so batches have to be regenerated
+                // with new transforms.
+                if graph.update_billboards(Some(&camera.global_transform())) {
+                    self.batch_storage
+                        .generate_batches(graph, &mut scene_associated_data.bone_matrix_cache);
+                    has_billboards = true;
+                }
+
+                scene_associated_data.gbuffer.clear(state);
+
+                render_custom_passes!(
+                    self,
+                    RenderPassStage::BeforeGBuffer,
+                    state,
+                    scene,
+                    scene_handle,
+                    camera,
+                    viewport,
+                    scene_associated_data,
+                    scene_associated_data.gbuffer.framebuffer_mut()
+                );
+
+                let mut skinning_statistics = SkinningStatistics::default();
                 self.statistics += scene_associated_data.gbuffer.fill(GBufferRenderContext {
                     state,
                     camera,
@@ -1414,11 +2131,14 @@ impl Renderer {
                     shader_cache: &mut self.shader_cache,
                     environment_dummy: self.environment_dummy.clone(),
                     use_parallax_mapping: self.quality_settings.use_parallax_mapping,
+                    use_skinned_instancing: self.quality_settings.use_skinned_instancing,
+                    skinning_statistics: &mut skinning_statistics,
                     normal_dummy: self.normal_dummy.clone(),
                     white_dummy: self.white_dummy.clone(),
                     black_dummy: self.black_dummy.clone(),
                     graph,
                 });
+                self.statistics.skinning += skinning_statistics;
 
                 scene_associated_data.copy_depth_stencil_to_scene_framebuffer(state);
 
@@ -1447,13 +2167,54 @@ impl Renderer {
                             shader_cache: &mut self.shader_cache,
                             normal_dummy: self.normal_dummy.clone(),
                             black_dummy: self.black_dummy.clone(),
+                            environment_dummy: self.environment_dummy.clone(),
                         });
 
                 self.statistics.lighting += light_stats;
                 self.statistics.geometry += pass_stats;
 
+                render_custom_passes!(
+                    self,
+                    RenderPassStage::AfterLighting,
+                    state,
+                    scene,
+                    scene_handle,
+                    camera,
+                    viewport,
+                    scene_associated_data,
+                    &mut scene_associated_data.hdr_scene_framebuffer
+                );
+
                 let depth = scene_associated_data.gbuffer.depth();
 
+                self.statistics += self.water_renderer.render(WaterRenderContext {
+                    state,
+                    camera,
+                    graph,
+                    framebuffer: &mut scene_associated_data.hdr_scene_framebuffer,
+                    depth: depth.clone(),
+                    viewport,
+                    frame_size,
+                    geom_cache: &mut self.geometry_cache,
+                    texture_cache: &mut self.texture_cache,
+                    shader_cache: &mut self.shader_cache,
+                    batch_storage: &self.batch_storage,
+                    quality_settings: &self.quality_settings,
+                    white_dummy: self.white_dummy.clone(),
+                    normal_dummy: self.normal_dummy.clone(),
+                    black_dummy: self.black_dummy.clone(),
+                })?;
+
+                self.statistics += self.impostor_renderer.render(ImpostorRenderContext {
+                    state,
+                    framebuffer: &mut scene_associated_data.hdr_scene_framebuffer,
+                    graph,
+                    camera,
+                    viewport,
+                    texture_cache: &mut self.texture_cache,
+                    ambient_color: scene.ambient_lighting_color,
+                })?;
+
                 self.statistics +=
                     self.particle_system_renderer
                         .render(ParticleSystemRenderContext {
@@ -1467,6 +2228,7 @@ impl Renderer {
                             frame_height: frame_size.y,
                             viewport,
                             texture_cache: &mut self.texture_cache,
+                            ambient_color: scene.ambient_lighting_color,
                         });
 
                 self.statistics += self.sprite_renderer.render(SpriteRenderContext {
@@ -1477,6 +2239,7 @@ impl Renderer {
                     white_dummy: self.white_dummy.clone(),
                     viewport,
                     textures: &mut self.texture_cache,
+                    ambient_color: scene.ambient_lighting_color,
                 });
 
                 self.statistics += self.forward_renderer.render(ForwardRenderContext {
@@ -1494,29 +2257,26 @@ impl Renderer {
                     black_dummy: self.black_dummy.clone(),
                 });
 
-                for render_pass in self.scene_render_passes.iter() {
-                    self.statistics +=
-                        render_pass.lock().unwrap().render(SceneRenderPassContext {
-                            pipeline_state: state,
-                            texture_cache: &mut self.texture_cache,
-                            geometry_cache: &mut self.geometry_cache,
-                            quality_settings: &self.quality_settings,
-                            batch_storage: &self.batch_storage,
-                            viewport,
-                            scene,
-                            camera,
-                            scene_handle,
-                            white_dummy: self.white_dummy.clone(),
-                            normal_dummy: self.normal_dummy.clone(),
-                            metallic_dummy: self.metallic_dummy.clone(),
-                            environment_dummy: self.environment_dummy.clone(),
-                            black_dummy: self.black_dummy.clone(),
-                            depth_texture: scene_associated_data.gbuffer.depth(),
-                            normal_texture: scene_associated_data.gbuffer.normal_texture(),
-                            ambient_texture: scene_associated_data.gbuffer.ambient_texture(),
-                            framebuffer: &mut scene_associated_data.hdr_scene_framebuffer,
-                        })?;
-                }
+                self.statistics += self.world_text_renderer.render(WorldTextRenderContext {
+                    state,
+                    framebuffer: &mut scene_associated_data.hdr_scene_framebuffer,
+                    graph,
+                    camera,
+                    viewport,
+                    texture_cache: &mut self.texture_cache,
+                })?;
+
+                render_custom_passes!(
+                    self,
+                    RenderPassStage::AfterTransparent,
+                    state,
+                    scene,
+                    scene_handle,
+                    camera,
+                    viewport,
+                    scene_associated_data,
+                    &mut scene_associated_data.hdr_scene_framebuffer
+                );
 
                 let quad = &self.quad;
 
@@ -1572,6 +2332,18 @@ impl Renderer {
                     camera,
                 );
 
+                render_custom_passes!(
+                    self,
+                    RenderPassStage::BeforeUi,
+                    state,
+                    scene,
+                    scene_handle,
+                    camera,
+                    viewport,
+                    scene_associated_data,
+                    &mut scene_associated_data.ldr_scene_framebuffer
+                );
+
                 // Optionally render everything into back buffer.
                 if scene.render_target.is_none() {
                     let quad = &self.quad;
@@ -1585,6 +2357,12 @@ impl Renderer {
                     );
                 }
             }
+
+            // Restore transforms without billboard rotation, so they do not depend on the
+            // last rendered camera.
+            if has_billboards {
+                graph.update_billboards(None);
+            }
         }
 
         // TODO: 2D renderer requires its own HDR pipeline.
@@ -1604,6 +2382,7 @@ impl Renderer {
             frame_buffer: &mut self.backbuffer,
             frame_width: backbuffer_width,
             frame_height: backbuffer_height,
+            scale: ui_scale,
             drawing_context,
             white_dummy: self.white_dummy.clone(),
             texture_cache: &mut self.texture_cache,
@@ -1617,10 +2396,11 @@ impl Renderer {
         &mut self,
         scenes: &SceneContainer,
         drawing_context: &DrawingContext,
+        ui_scale: f32,
         scenes2d: &Scene2dContainer,
         context: &glutin::WindowedContext<glutin::PossiblyCurrent>,
     ) -> Result<(), FrameworkError> {
-        self.render_frame(scenes, drawing_context, scenes2d)?;
+        self.render_frame(scenes, drawing_context, ui_scale, scenes2d)?;
         self.statistics.end_frame();
         context.swap_buffers()?;
         self.state.check_error();
@@ -1634,9 +2414,10 @@ impl Renderer {
         &mut self,
         scenes: &SceneContainer,
         drawing_context: &DrawingContext,
+        ui_scale: f32,
         scenes2d: &Scene2dContainer,
     ) -> Result<(), FrameworkError> {
-        self.render_frame(scenes, drawing_context, scenes2d)?;
+        self.render_frame(scenes, drawing_context, ui_scale, scenes2d)?;
         self.statistics.end_frame();
         self.state.check_error();
         self.statistics.finalize();
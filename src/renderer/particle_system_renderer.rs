@@ -1,7 +1,13 @@
 use crate::core::sstorage::ImmutableString;
 use crate::renderer::framework::state::{BlendFactor, BlendFunc};
 use crate::{
-    core::{algebra::Vector2, math::Matrix4Ext, math::Rect, scope_profile},
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        math::Matrix4Ext,
+        math::Rect,
+        profile_scope, scope_profile,
+    },
     renderer::framework::{
         error::FrameworkError,
         framebuffer::{DrawParameters, FrameBuffer},
@@ -14,7 +20,13 @@ use crate::{
         state::PipelineState,
     },
     renderer::{RenderPassStatistics, TextureCache},
-    scene::{camera::Camera, graph::Graph, node::Node, particle_system},
+    scene::{
+        camera::Camera,
+        graph::Graph,
+        light::{self, LightingMode},
+        node::Node,
+        particle_system,
+    },
 };
 use std::{cell::RefCell, rc::Rc};
 
@@ -29,6 +41,7 @@ struct ParticleSystemShader {
     inv_screen_size: UniformLocation,
     proj_params: UniformLocation,
     soft_boundary_sharpness_factor: UniformLocation,
+    lighting: UniformLocation,
 }
 
 impl ParticleSystemShader {
@@ -58,6 +71,7 @@ impl ParticleSystemShader {
             proj_params: program.uniform_location(state, &ImmutableString::new("projParams"))?,
             soft_boundary_sharpness_factor: program
                 .uniform_location(state, &ImmutableString::new("softBoundarySharpnessFactor"))?,
+            lighting: program.uniform_location(state, &ImmutableString::new("lighting"))?,
             program,
         })
     }
@@ -81,6 +95,7 @@ pub(in crate) struct ParticleSystemRenderContext<'a, 'b, 'c> {
     pub frame_height: f32,
     pub viewport: Rect<i32>,
     pub texture_cache: &'a mut TextureCache,
+    pub ambient_color: Color,
 }
 
 impl ParticleSystemRenderer {
@@ -135,6 +150,7 @@ impl ParticleSystemRenderer {
     #[must_use]
     pub(in crate) fn render(&mut self, args: ParticleSystemRenderContext) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("ParticleSystems");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -149,6 +165,7 @@ impl ParticleSystemRenderer {
             frame_height,
             viewport,
             texture_cache,
+            ambient_color,
         } = args;
 
         let inv_view = camera.inv_view_matrix().unwrap();
@@ -161,10 +178,13 @@ impl ParticleSystemRenderer {
         let proj_params = Vector2::new(camera.z_far(), camera.z_near());
 
         for node in graph.linear_iter() {
-            let particle_system = if let Node::ParticleSystem(particle_system) = node {
-                particle_system
-            } else {
-                continue;
+            let particle_system = match node {
+                Node::ParticleSystem(particle_system)
+                    if particle_system.is_visible_by_mask(camera.visibility_mask()) =>
+                {
+                    particle_system
+                }
+                _ => continue,
             };
 
             particle_system.generate_draw_data(
@@ -199,6 +219,16 @@ impl ParticleSystemRenderer {
                 .and_then(|t| texture_cache.get(state, t))
                 .unwrap_or_else(|| white_dummy.clone());
 
+            let lighting = match particle_system.lighting_mode() {
+                LightingMode::Unlit => Vector3::new(1.0, 1.0, 1.0),
+                LightingMode::Lit => light::evaluate_lighting(
+                    graph,
+                    particle_system.global_position(),
+                    ambient_color,
+                    light::DEFAULT_MAX_LIGHTS_PER_SPRITE,
+                ),
+            };
+
             statistics += framebuffer.draw(
                 &self.geometry_buffer,
                 state,
@@ -218,7 +248,8 @@ impl ParticleSystemRenderer {
                         .set_f32(
                             &self.shader.soft_boundary_sharpness_factor,
                             particle_system.soft_boundary_sharpness_factor(),
-                        );
+                        )
+                        .set_vector3(&self.shader.lighting, &lighting);
                 },
             );
         }
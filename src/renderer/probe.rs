@@ -0,0 +1,576 @@
+//! Probe renderer bakes cube maps of environment probes.
+//!
+//! Baking of a probe is spread over a few frames: the scene is rendered into a face of a capture
+//! cube map on each of first six frames, on the next frame the capture is convolved into mip
+//! levels of increasing roughness and the result is read back to CPU memory and stored in the
+//! environment texture of the probe. Only one probe per scene is baked at a time. The scene is
+//! rendered using `Forward` render pass of materials, as water reflections do, so the captured
+//! environment is unlit.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Point3, Vector3},
+        color::Color,
+        math::{frustum::Frustum, Rect},
+        pool::Handle,
+        profile_scope, scope_profile,
+        sstorage::ImmutableString,
+    },
+    renderer::{
+        apply_material,
+        batch::BatchStorage,
+        cache::{shader::ShaderCache, texture::TextureCache},
+        framework::{
+            error::FrameworkError,
+            framebuffer::{Attachment, AttachmentKind, DrawParameters, FrameBuffer},
+            geometry_buffer::{GeometryBuffer, GeometryBufferKind},
+            gpu_program::{GpuProgram, UniformLocation},
+            gpu_texture::{
+                Coordinate, CubeMapFace, GpuTexture, GpuTextureKind, MagnificationFilter,
+                MinificationFilter, PixelKind, WrapMode,
+            },
+            state::PipelineState,
+        },
+        skybox_shader::SkyboxShader,
+        GeometryCache, MaterialContext, RenderPassStatistics,
+    },
+    resource::texture::{TextureData, TextureKind, TexturePixelKind},
+    scene::{
+        graph::Graph, mesh::surface::SurfaceData, node::Node, probe::EnvironmentProbe,
+        visibility::VisibilityCache,
+    },
+};
+use std::{cell::RefCell, rc::Rc};
+
+// Max amount of roughness mip levels of a probe, the last level is used for the roughest
+// surfaces.
+const MAX_PROBE_MIPS: usize = 5;
+
+// Max size of a face of a cube map, larger resolutions do not make sense for probes.
+const MAX_PROBE_RESOLUTION: usize = 2048;
+
+// One step per face and one step for convolution.
+const BAKE_STEP_COUNT: usize = 7;
+
+// Near clipping plane of a capture camera.
+const Z_NEAR: f32 = 0.025;
+
+struct PrefilterShader {
+    program: GpuProgram,
+    wvp_matrix: UniformLocation,
+    environment_texture: UniformLocation,
+    face_index: UniformLocation,
+    face_size: UniformLocation,
+    source_size: UniformLocation,
+    roughness: UniformLocation,
+}
+
+impl PrefilterShader {
+    fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        let fragment_source = include_str!("shaders/probe_prefilter_fs.glsl");
+        let vertex_source = include_str!("shaders/probe_prefilter_vs.glsl");
+        let program = GpuProgram::from_source(
+            state,
+            "ProbePrefilterShader",
+            vertex_source,
+            fragment_source,
+        )?;
+        Ok(Self {
+            wvp_matrix: program
+                .uniform_location(state, &ImmutableString::new("worldViewProjection"))?,
+            environment_texture: program
+                .uniform_location(state, &ImmutableString::new("environmentTexture"))?,
+            face_index: program.uniform_location(state, &ImmutableString::new("faceIndex"))?,
+            face_size: program.uniform_location(state, &ImmutableString::new("faceSize"))?,
+            source_size: program.uniform_location(state, &ImmutableString::new("sourceSize"))?,
+            roughness: program.uniform_location(state, &ImmutableString::new("roughness"))?,
+            program,
+        })
+    }
+}
+
+struct ProbeCubeMapFace {
+    face: CubeMapFace,
+    look: Vector3<f32>,
+    up: Vector3<f32>,
+}
+
+/// State of a probe that is being baked.
+pub(in crate) struct ProbeBake {
+    probe: Handle<Node>,
+    resolution: usize,
+    capture: FrameBuffer,
+    next_face: usize,
+}
+
+pub(in crate) struct ProbeRenderer {
+    prefilter_shader: PrefilterShader,
+    skybox_shader: SkyboxShader,
+    quad: GeometryBuffer,
+    skybox: GeometryBuffer,
+    // Must be in the same order as faces of cube maps in OpenGL.
+    faces: [ProbeCubeMapFace; 6],
+    visibility: VisibilityCache,
+    render_pass_name: ImmutableString,
+}
+
+pub(in crate) struct ProbeRenderContext<'a, 'b> {
+    pub state: &'a mut PipelineState,
+    pub graph: &'b Graph,
+    pub bake: &'a mut Option<ProbeBake>,
+    pub geom_cache: &'a mut GeometryCache,
+    pub texture_cache: &'a mut TextureCache,
+    pub shader_cache: &'a mut ShaderCache,
+    pub batch_storage: &'a BatchStorage,
+    pub white_dummy: Rc<RefCell<GpuTexture>>,
+    pub normal_dummy: Rc<RefCell<GpuTexture>>,
+    pub black_dummy: Rc<RefCell<GpuTexture>>,
+}
+
+/// Returns amount of mip levels in a full chain for given resolution.
+fn full_mip_count(resolution: usize) -> usize {
+    (usize::BITS - resolution.max(1).leading_zeros()) as usize
+}
+
+/// Returns amount of roughness mip levels for given resolution of a probe.
+fn probe_mip_count(resolution: usize) -> usize {
+    full_mip_count(resolution).min(MAX_PROBE_MIPS)
+}
+
+fn make_capture(
+    state: &mut PipelineState,
+    resolution: usize,
+) -> Result<FrameBuffer, FrameworkError> {
+    let depth_stencil = GpuTexture::new(
+        state,
+        GpuTextureKind::Rectangle {
+            width: resolution,
+            height: resolution,
+        },
+        PixelKind::D24S8,
+        MinificationFilter::Nearest,
+        MagnificationFilter::Nearest,
+        1,
+        None,
+    )?;
+
+    // Convolution samples lower mip levels of the capture, they're generated when every face
+    // is rendered.
+    let mut color = GpuTexture::new(
+        state,
+        GpuTextureKind::Cube {
+            width: resolution,
+            height: resolution,
+        },
+        PixelKind::RGBA8,
+        MinificationFilter::LinearMipMapLinear,
+        MagnificationFilter::Linear,
+        full_mip_count(resolution),
+        None,
+    )?;
+    color
+        .bind_mut(state, 0)
+        .set_wrap(Coordinate::S, WrapMode::ClampToEdge)
+        .set_wrap(Coordinate::T, WrapMode::ClampToEdge)
+        .set_wrap(Coordinate::R, WrapMode::ClampToEdge);
+
+    FrameBuffer::new(
+        state,
+        Some(Attachment {
+            kind: AttachmentKind::DepthStencil,
+            texture: Rc::new(RefCell::new(depth_stencil)),
+        }),
+        vec![Attachment {
+            kind: AttachmentKind::Color,
+            texture: Rc::new(RefCell::new(color)),
+        }],
+    )
+}
+
+impl ProbeRenderer {
+    pub(in crate) fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        Ok(Self {
+            prefilter_shader: PrefilterShader::new(state)?,
+            skybox_shader: SkyboxShader::new(state)?,
+            quad: GeometryBuffer::from_surface_data(
+                &SurfaceData::make_unit_xy_quad(),
+                GeometryBufferKind::StaticDraw,
+                state,
+            ),
+            skybox: GeometryBuffer::from_surface_data(
+                &SurfaceData::make_cube(Matrix4::identity()),
+                GeometryBufferKind::StaticDraw,
+                state,
+            ),
+            faces: [
+                ProbeCubeMapFace {
+                    face: CubeMapFace::PositiveX,
+                    look: Vector3::new(1.0, 0.0, 0.0),
+                    up: Vector3::new(0.0, -1.0, 0.0),
+                },
+                ProbeCubeMapFace {
+                    face: CubeMapFace::NegativeX,
+                    look: Vector3::new(-1.0, 0.0, 0.0),
+                    up: Vector3::new(0.0, -1.0, 0.0),
+                },
+                ProbeCubeMapFace {
+                    face: CubeMapFace::PositiveY,
+                    look: Vector3::new(0.0, 1.0, 0.0),
+                    up: Vector3::new(0.0, 0.0, 1.0),
+                },
+                ProbeCubeMapFace {
+                    face: CubeMapFace::NegativeY,
+                    look: Vector3::new(0.0, -1.0, 0.0),
+                    up: Vector3::new(0.0, 0.0, -1.0),
+                },
+                ProbeCubeMapFace {
+                    face: CubeMapFace::PositiveZ,
+                    look: Vector3::new(0.0, 0.0, 1.0),
+                    up: Vector3::new(0.0, -1.0, 0.0),
+                },
+                ProbeCubeMapFace {
+                    face: CubeMapFace::NegativeZ,
+                    look: Vector3::new(0.0, 0.0, -1.0),
+                    up: Vector3::new(0.0, -1.0, 0.0),
+                },
+            ],
+            visibility: Default::default(),
+            render_pass_name: ImmutableString::new("Forward"),
+        })
+    }
+
+    // Renders the scene into next face of the capture cube map.
+    #[allow(clippy::too_many_arguments)]
+    fn render_face(
+        &mut self,
+        state: &mut PipelineState,
+        graph: &Graph,
+        probe: &EnvironmentProbe,
+        bake: &mut ProbeBake,
+        geom_cache: &mut GeometryCache,
+        texture_cache: &mut TextureCache,
+        shader_cache: &mut ShaderCache,
+        batch_storage: &BatchStorage,
+        dummies: [&Rc<RefCell<GpuTexture>>; 3],
+    ) -> RenderPassStatistics {
+        let mut statistics = RenderPassStatistics::default();
+
+        let [white_dummy, normal_dummy, black_dummy] = dummies;
+        let face = &self.faces[bake.next_face];
+        let size = bake.resolution as i32;
+        let viewport = Rect::new(0, 0, size, size);
+
+        bake.capture.set_cubemap_face(state, 0, face.face).clear(
+            state,
+            viewport,
+            Some(Color::BLACK),
+            Some(1.0),
+            Some(0),
+        );
+
+        let position = probe.global_position();
+        let z_far = probe.draw_distance().max(2.0 * Z_NEAR);
+        let projection = Matrix4::new_perspective(1.0, std::f32::consts::FRAC_PI_2, Z_NEAR, z_far);
+        let view = Matrix4::look_at_rh(
+            &Point3::from(position),
+            &Point3::from(position + face.look),
+            &face.up,
+        );
+        let view_projection = projection * view;
+
+        let frustum = Frustum::from(view_projection).unwrap_or_default();
+        self.visibility.update(
+            graph,
+            position,
+            Z_NEAR,
+            z_far,
+            probe.visibility_mask(),
+            Some(&[&frustum]),
+        );
+
+        // Use skybox of the first camera that has one.
+        let skybox = graph.linear_iter().find_map(|node| {
+            if let Node::Camera(camera) = node {
+                camera.skybox_ref()
+            } else {
+                None
+            }
+        });
+        if let Some(gpu_texture) = skybox
+            .and_then(|skybox| skybox.cubemap())
+            .and_then(|cubemap| texture_cache.get(state, &cubemap))
+        {
+            let wvp = view_projection
+                * Matrix4::new_translation(&position)
+                * Matrix4::new_scaling(z_far / 2.0f32.sqrt());
+            let shader = &self.skybox_shader;
+            statistics += bake.capture.draw(
+                &self.skybox,
+                state,
+                viewport,
+                &shader.program,
+                &DrawParameters {
+                    cull_face: None,
+                    color_write: Default::default(),
+                    depth_write: false,
+                    stencil_test: None,
+                    depth_test: false,
+                    blend: None,
+                    stencil_op: Default::default(),
+                },
+                |mut program_binding| {
+                    program_binding
+                        .set_texture(&shader.cubemap_texture, &gpu_texture)
+                        .set_matrix4(&shader.wvp_matrix, &wvp);
+                },
+            );
+        }
+
+        for batch in batch_storage.batches.iter() {
+            let material = batch.material.lock();
+            let geometry = geom_cache.get(state, &batch.data);
+
+            let render_pass = match shader_cache
+                .get(state, material.shader())
+                .and_then(|shader_set| shader_set.render_passes.get(&self.render_pass_name))
+            {
+                Some(render_pass) => render_pass,
+                None => continue,
+            };
+
+            for instance in batch.instances.iter() {
+                if instance.is_transparent || !self.visibility.is_visible(instance.owner) {
+                    continue;
+                }
+
+                let draw_params = DrawParameters {
+                    cull_face: instance.cull_face(render_pass.draw_params.cull_face),
+                    depth_write: true,
+                    depth_test: true,
+                    blend: None,
+                    ..render_pass.draw_params.clone()
+                };
+
+                let wvp = view_projection * instance.world_transform;
+
+                statistics += bake.capture.draw(
+                    geometry,
+                    state,
+                    viewport,
+                    render_pass.program(instance.triplanar_mapping.is_some()),
+                    &draw_params,
+                    |mut program_binding| {
+                        apply_material(MaterialContext {
+                            material: &*material,
+                            program_binding: &mut program_binding,
+                            texture_cache,
+                            world_matrix: &instance.world_transform,
+                            wvp_matrix: &wvp,
+                            bone_matrices: &instance.bone_matrices,
+                            use_skeletal_animation: batch.is_skinned,
+                            camera_position: &position,
+                            use_pom: false,
+                            opacity: instance.opacity,
+                            color: instance.color,
+                            light_position: &Default::default(),
+                            uv_transform: &instance.uv_transform,
+                            mip_bias: instance.mip_bias,
+                            receive_shadows: instance.receive_shadows,
+                            alpha_cutoff: instance.alpha_cutoff,
+                            triplanar_mapping: instance.triplanar_mapping,
+                            instanced_bones: None,
+                            normal_dummy: normal_dummy.clone(),
+                            white_dummy: white_dummy.clone(),
+                            black_dummy: black_dummy.clone(),
+                        });
+                    },
+                );
+            }
+        }
+
+        statistics
+    }
+
+    // Convolves the capture into roughness mip levels and reads them back.
+    fn convolve(
+        &mut self,
+        state: &mut PipelineState,
+        bake: &ProbeBake,
+        statistics: &mut RenderPassStatistics,
+    ) -> Result<TextureData, FrameworkError> {
+        let resolution = bake.resolution;
+        let mip_count = probe_mip_count(resolution);
+
+        let source = bake.capture.color_attachments()[0].texture.clone();
+        source.borrow_mut().bind_mut(state, 0).generate_mip_maps();
+
+        let output_texture = GpuTexture::new(
+            state,
+            GpuTextureKind::Cube {
+                width: resolution,
+                height: resolution,
+            },
+            PixelKind::RGBA8,
+            MinificationFilter::LinearMipMapLinear,
+            MagnificationFilter::Linear,
+            mip_count,
+            None,
+        )?;
+        let mut output = FrameBuffer::new(
+            state,
+            None,
+            vec![Attachment {
+                kind: AttachmentKind::Color,
+                texture: Rc::new(RefCell::new(output_texture)),
+            }],
+        )?;
+
+        let frame_matrix = Matrix4::new_orthographic(0.0, 1.0, 1.0, 0.0, -1.0, 1.0);
+        let shader = &self.prefilter_shader;
+
+        let mut bytes = Vec::new();
+        for mip in 0..mip_count {
+            let size = (resolution >> mip).max(1);
+            let viewport = Rect::new(0, 0, size as i32, size as i32);
+            let roughness = if mip_count > 1 {
+                mip as f32 / (mip_count - 1) as f32
+            } else {
+                0.0
+            };
+
+            for (index, face) in self.faces.iter().enumerate() {
+                output.set_cubemap_face_mip(state, 0, face.face, mip);
+
+                *statistics += output.draw(
+                    &self.quad,
+                    state,
+                    viewport,
+                    &shader.program,
+                    &DrawParameters {
+                        cull_face: None,
+                        color_write: Default::default(),
+                        depth_write: false,
+                        stencil_test: None,
+                        depth_test: false,
+                        blend: None,
+                        stencil_op: Default::default(),
+                    },
+                    |mut program_binding| {
+                        program_binding
+                            .set_matrix4(&shader.wvp_matrix, &frame_matrix)
+                            .set_texture(&shader.environment_texture, &source)
+                            .set_i32(&shader.face_index, index as i32)
+                            .set_f32(&shader.face_size, size as f32)
+                            .set_f32(&shader.source_size, resolution as f32)
+                            .set_f32(&shader.roughness, roughness);
+                    },
+                );
+
+                bytes.extend_from_slice(&output.read_pixels_rgba8(state, viewport));
+            }
+        }
+
+        Ok(TextureData::from_mip_chain(
+            TextureKind::Cube {
+                width: resolution as u32,
+                height: resolution as u32,
+            },
+            TexturePixelKind::RGBA8,
+            mip_count as u32,
+            bytes,
+        ))
+    }
+
+    /// Makes one step of baking of a probe in the scene, starts baking of next probe that needs
+    /// it if there is no baking in progress.
+    pub(in crate) fn render(
+        &mut self,
+        args: ProbeRenderContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        scope_profile!();
+        profile_scope!("EnvironmentProbe");
+
+        let mut statistics = RenderPassStatistics::default();
+
+        let ProbeRenderContext {
+            state,
+            graph,
+            bake,
+            geom_cache,
+            texture_cache,
+            shader_cache,
+            batch_storage,
+            white_dummy,
+            normal_dummy,
+            black_dummy,
+        } = args;
+
+        // Probe could be removed while it was baked.
+        if let Some(current) = bake.as_ref() {
+            if !matches!(
+                graph.try_get(current.probe),
+                Some(Node::EnvironmentProbe(_))
+            ) {
+                *bake = None;
+            }
+        }
+
+        if bake.is_none() {
+            let next = graph.pair_iter().find_map(|(handle, node)| match node {
+                Node::EnvironmentProbe(probe) if probe.needs_bake() => Some((handle, probe)),
+                _ => None,
+            });
+            if let Some((handle, probe)) = next {
+                let resolution = (probe.resolution() as usize).clamp(1, MAX_PROBE_RESOLUTION);
+                probe.begin_bake();
+                *bake = Some(ProbeBake {
+                    probe: handle,
+                    resolution,
+                    capture: make_capture(state, resolution)?,
+                    next_face: 0,
+                });
+            }
+        }
+
+        let current = match bake.as_mut() {
+            Some(current) => current,
+            None => return Ok(statistics),
+        };
+        let probe = graph[current.probe].as_environment_probe();
+
+        if current.next_face < self.faces.len() {
+            statistics += self.render_face(
+                state,
+                graph,
+                probe,
+                current,
+                geom_cache,
+                texture_cache,
+                shader_cache,
+                batch_storage,
+                [&white_dummy, &normal_dummy, &black_dummy],
+            );
+            current.next_face += 1;
+            probe.set_bake_progress(current.next_face as f32 / BAKE_STEP_COUNT as f32);
+        } else {
+            let data = self.convolve(state, current, &mut statistics)?;
+            probe.finish_bake(data);
+            *bake = None;
+        }
+
+        Ok(statistics)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::renderer::probe::probe_mip_count;
+
+    #[test]
+    fn test_probe_mip_count() {
+        assert_eq!(probe_mip_count(1), 1);
+        assert_eq!(probe_mip_count(4), 3);
+        assert_eq!(probe_mip_count(128), 5);
+        assert_eq!(probe_mip_count(100), 5);
+    }
+}
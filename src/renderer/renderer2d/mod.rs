@@ -5,6 +5,7 @@ use crate::{
         algebra::{Matrix4, Vector2, Vector4},
         math::Rect,
         pool::Handle,
+        profile_scope,
         sstorage::ImmutableString,
     },
     renderer::{
@@ -22,7 +23,7 @@ use crate::{
     },
     resource::texture::TextureKind,
     scene2d::{light::Light, node::Node, Scene2d, Scene2dContainer},
-    utils::log::{Log, MessageKind},
+    utils::log::log_error,
 };
 use fxhash::FxHashMap;
 use std::{cell::RefCell, collections::hash_map::Entry, rc::Rc};
@@ -214,6 +215,8 @@ impl Renderer2d {
         texture_cache: &mut TextureCache,
         white_dummy: Rc<RefCell<GpuTexture>>,
     ) -> Result<RenderPassStatistics, FrameworkError> {
+        profile_scope!("Renderer2d");
+
         let mut stats = RenderPassStatistics::default();
         let quad = self.geometry_cache.get(state, &self.quad);
 
@@ -227,7 +230,7 @@ impl Renderer2d {
                 let (width, height) = match kind {
                     TextureKind::Rectangle { width, height } => (width, height),
                     _ => {
-                        Log::write(MessageKind::Error, format!("Attempt to use {:?} as render target, only rectangular render targets are supported!", kind));
+                        log_error!("Attempt to use {:?} as render target, only rectangular render targets are supported!", kind);
                         continue;
                     }
                 };
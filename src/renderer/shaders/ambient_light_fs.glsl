@@ -3,16 +3,90 @@ uniform sampler2D aoSampler;
 uniform sampler2D ambientTexture;
 uniform vec4 ambientColor;
 
+// Environment probe.
+uniform sampler2D depthTexture;
+uniform sampler2D normalTexture;
+uniform sampler2D materialTexture;
+uniform samplerCube environmentMap;
+uniform bool probeEnabled;
+uniform mat4 invViewProj;
+uniform vec3 cameraPosition;
+uniform mat4 probeTransform;
+uniform mat4 probeInvTransform;
+uniform vec3 probeHalfSize;
+uniform vec3 probePosition;
+uniform float probeMaxLod;
+uniform float probeIntensity;
+
 out vec4 FragColor;
 in vec2 texCoord;
 
+// Intersects a ray that starts inside the box of the probe with the box and returns direction
+// from the center of the probe to the intersection point (so called box projection). Calculations
+// are made in local space of the probe, so rotated probes are supported too.
+vec3 BoxProject(vec3 localPosition, vec3 direction)
+{
+    vec3 localDirection = normalize((probeInvTransform * vec4(direction, 0.0)).xyz);
+    vec3 firstPlane = (probeHalfSize - localPosition) / localDirection;
+    vec3 secondPlane = (-probeHalfSize - localPosition) / localDirection;
+    vec3 furthestPlane = max(firstPlane, secondPlane);
+    float distance = min(min(furthestPlane.x, furthestPlane.y), furthestPlane.z);
+    vec3 localIntersection = localPosition + localDirection * distance;
+    vec3 intersection = (probeTransform * vec4(localIntersection, 1.0)).xyz;
+    return intersection - probePosition;
+}
+
+// Analytical approximation of split-sum environment BRDF by Brian Karis.
+vec2 EnvBRDFApprox(float roughness, float NdotV)
+{
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * NdotV)) * r.x + r.y;
+    return vec2(-1.04, 1.04) * a004 + r.zw;
+}
+
+vec3 ProbeLighting(vec3 albedo)
+{
+    vec3 fragmentPosition = S_UnProject(vec3(texCoord, texture(depthTexture, texCoord).r), invViewProj);
+    vec3 localPosition = (probeInvTransform * vec4(fragmentPosition, 1.0)).xyz;
+
+    // Only fragments inside the box of the probe are lit by it.
+    if (any(greaterThan(abs(localPosition), probeHalfSize)))
+    {
+        return vec3(0.0);
+    }
+
+    vec4 material = texture(materialTexture, texCoord);
+    float metallic = material.x;
+    float roughness = material.y;
+
+    vec3 N = normalize(texture(normalTexture, texCoord).xyz * 2.0 - 1.0);
+    vec3 V = normalize(cameraPosition - fragmentPosition);
+    vec3 R = reflect(-V, N);
+    float NdotV = max(dot(N, V), 0.0001);
+
+    vec3 F0 = mix(vec3(0.04), albedo, metallic);
+    vec2 ab = EnvBRDFApprox(roughness, NdotV);
+    vec3 specularColor = F0 * ab.x + ab.y;
+    vec3 kD = (vec3(1.0) - specularColor) * (1.0 - metallic);
+
+    vec3 specular = textureLod(environmentMap, BoxProject(localPosition, R), roughness * probeMaxLod).rgb;
+    vec3 diffuse = textureLod(environmentMap, BoxProject(localPosition, N), probeMaxLod).rgb;
+
+    return probeIntensity * (specular * specularColor + kD * diffuse * albedo);
+}
+
 void main()
 {
     float ambientOcclusion = texture(aoSampler, texCoord).r;
     vec4 ambientPixel = texture(ambientTexture, texCoord);
-    FragColor = (ambientColor + ambientPixel) * texture(diffuseTexture, texCoord);
+    vec4 albedo = texture(diffuseTexture, texCoord);
+    FragColor = (ambientColor + ambientPixel) * albedo;
+    if (probeEnabled)
+    {
+        FragColor.rgb += ProbeLighting(albedo.rgb);
+    }
     FragColor.rgb *= ambientOcclusion;
     FragColor.a = ambientPixel.a;
-
-    // TODO: Implement IBL.
-}
\ No newline at end of file
+}
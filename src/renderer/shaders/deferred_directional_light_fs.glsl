@@ -51,7 +51,9 @@ float CsmGetShadow(in sampler2D sampler, in vec3 fragmentPosition, in mat4 light
 
 void main()
 {
-    vec3 material = texture(materialTexture, texCoord).rgb;
+    vec4 material = texture(materialTexture, texCoord);
+    // Zero alpha of material means that fragment does not receive shadows.
+    bool receiveShadows = material.a > 0.0;
 
     vec3 fragmentPosition = S_UnProject(vec3(texCoord, texture(depthTexture, texCoord).r), invViewProj);
 
@@ -69,12 +71,14 @@ void main()
     float fragmentZViewSpace = abs((viewMatrix * vec4(fragmentPosition, 1.0)).z);
 
     float shadow = 1.0;
-    if (fragmentZViewSpace <= cascadeDistances[0]) {
-        shadow = CsmGetShadow(shadowCascade0, fragmentPosition, lightViewProjMatrices[0]);
-    } else if (fragmentZViewSpace <= cascadeDistances[1]) {
-        shadow = CsmGetShadow(shadowCascade1, fragmentPosition, lightViewProjMatrices[1]);
-    } else if (fragmentZViewSpace <= cascadeDistances[2]) {
-        shadow = CsmGetShadow(shadowCascade2, fragmentPosition, lightViewProjMatrices[2]);
+    if (receiveShadows) {
+        if (fragmentZViewSpace <= cascadeDistances[0]) {
+            shadow = CsmGetShadow(shadowCascade0, fragmentPosition, lightViewProjMatrices[0]);
+        } else if (fragmentZViewSpace <= cascadeDistances[1]) {
+            shadow = CsmGetShadow(shadowCascade1, fragmentPosition, lightViewProjMatrices[1]);
+        } else if (fragmentZViewSpace <= cascadeDistances[2]) {
+            shadow = CsmGetShadow(shadowCascade2, fragmentPosition, lightViewProjMatrices[2]);
+        }
     }
 
     FragColor = shadow * vec4(lightIntensity * lighting, 1.0);
@@ -6,10 +6,13 @@ uniform samplerCube pointShadowTexture;
 
 uniform vec3 lightPos;
 uniform float lightRadius;
+uniform int lightFalloffMode;
+uniform float lightFalloffExponent;
 uniform vec4 lightColor;
 uniform mat4 invViewProj;
 uniform vec3 cameraPosition;
-uniform bool softShadows;
+uniform int shadowFilter;
+uniform float shadowMapInvSize;
 uniform bool shadowsEnabled;
 uniform float shadowBias;
 uniform float lightIntensity;
@@ -19,7 +22,9 @@ out vec4 FragColor;
 
 void main()
 {
-    vec3 material = texture(materialTexture, texCoord).rgb;
+    vec4 material = texture(materialTexture, texCoord);
+    // Zero alpha of material means that fragment does not receive shadows.
+    bool receiveShadows = material.a > 0.0;
 
     vec3 fragmentPosition = S_UnProject(vec3(texCoord, texture(depthTexture, texCoord).r), invViewProj);
     vec3 fragmentToLight = lightPos - fragmentPosition;
@@ -36,10 +41,11 @@ void main()
 
     vec3 lighting = S_PBR_CalculateLight(ctx);
 
-    float distanceAttenuation = S_LightDistanceAttenuation(distance, lightRadius);
+    float distanceAttenuation = S_LightFalloff(distance, lightRadius, lightFalloffMode, lightFalloffExponent);
 
     float shadow = S_PointShadow(
-        shadowsEnabled, softShadows, distance, shadowBias, ctx.fragmentToLight, pointShadowTexture);
+        shadowsEnabled && receiveShadows, shadowFilter, distance, shadowBias, ctx.fragmentNormal,
+        ctx.fragmentToLight, shadowMapInvSize, pointShadowTexture);
 
     FragColor = vec4(lightIntensity * distanceAttenuation * shadow * lighting, 1.0);
 }
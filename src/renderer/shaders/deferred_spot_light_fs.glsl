@@ -8,6 +8,8 @@ uniform sampler2D cookieTexture;
 uniform mat4 lightViewProjMatrix;
 uniform vec3 lightPos;
 uniform float lightRadius;
+uniform int lightFalloffMode;
+uniform float lightFalloffExponent;
 uniform vec4 lightColor;
 uniform vec3 lightDirection;
 uniform float halfHotspotConeAngleCos;
@@ -15,7 +17,7 @@ uniform float halfConeAngleCos;
 uniform mat4 invViewProj;
 uniform vec3 cameraPosition;
 uniform bool shadowsEnabled;
-uniform bool softShadows;
+uniform int shadowFilter;
 uniform float shadowMapInvSize;
 uniform float shadowBias;
 uniform bool cookieEnabled;
@@ -26,7 +28,9 @@ out vec4 FragColor;
 
 void main()
 {
-    vec3 material = texture(materialTexture, texCoord).rgb;
+    vec4 material = texture(materialTexture, texCoord);
+    // Zero alpha of material means that fragment does not receive shadows.
+    bool receiveShadows = material.a > 0.0;
 
     vec3 fragmentPosition = S_UnProject(vec3(texCoord, texture(depthTexture, texCoord).r), invViewProj);
     vec3 fragmentToLight = lightPos - fragmentPosition;
@@ -43,14 +47,14 @@ void main()
 
     vec3 lighting = S_PBR_CalculateLight(ctx);
 
-    float distanceAttenuation = S_LightDistanceAttenuation(distance, lightRadius);
+    float distanceAttenuation = S_LightFalloff(distance, lightRadius, lightFalloffMode, lightFalloffExponent);
 
     float spotAngleCos = dot(lightDirection, ctx.fragmentToLight);
     float coneFactor = smoothstep(halfConeAngleCos, halfHotspotConeAngleCos, spotAngleCos);
 
     float shadow = S_SpotShadowFactor(
-        shadowsEnabled, softShadows, shadowBias, fragmentPosition,
-            lightViewProjMatrix, shadowMapInvSize, spotShadowTexture);
+        shadowsEnabled && receiveShadows, shadowFilter, shadowBias, fragmentPosition, ctx.fragmentNormal,
+            ctx.fragmentToLight, lightViewProjMatrix, shadowMapInvSize, spotShadowTexture);
 
     vec4 cookieAttenuation = vec4(1.0);
     if (cookieEnabled) {
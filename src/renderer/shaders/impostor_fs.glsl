@@ -0,0 +1,16 @@
+uniform sampler2D atlasTexture;
+uniform float alphaCutoff;
+
+out vec4 FragColor;
+
+in vec2 texCoord;
+in vec4 color;
+
+void main()
+{
+    vec4 texel = texture(atlasTexture, texCoord);
+    if (texel.a < alphaCutoff) {
+        discard;
+    }
+    FragColor = vec4(texel.rgb * color.rgb, color.a);
+}
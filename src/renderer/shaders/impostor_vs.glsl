@@ -0,0 +1,49 @@
+layout(location = 0) in vec2 vertexPosition;
+layout(location = 1) in mat4 worldMatrix;
+layout(location = 5) in vec4 instanceColor;
+
+uniform mat4 viewProjectionMatrix;
+uniform vec3 cameraPosition;
+// Center of bounds of the model in local space of the impostor.
+uniform vec3 boundsCenter;
+// Horizontal radius and vertical half-size of the model.
+uniform vec2 frameHalfSize;
+uniform int angleCount;
+// Amount of columns and rows of frames in the atlas.
+uniform vec2 gridSize;
+
+out vec2 texCoord;
+out vec4 color;
+
+void main()
+{
+    color = instanceColor;
+
+    vec3 origin = (worldMatrix * vec4(boundsCenter, 1.0)).xyz;
+    vec3 sideAxis = worldMatrix[0].xyz;
+    vec3 upAxis = worldMatrix[1].xyz;
+    vec3 lookAxis = worldMatrix[2].xyz;
+    float upLength = length(upAxis);
+    vec3 up = upAxis / upLength;
+
+    // Quad rotates only around up axis of the impostor.
+    vec3 toCamera = cameraPosition - origin;
+    vec3 horizontal = toCamera - up * dot(toCamera, up);
+    if (dot(horizontal, horizontal) < 0.000001) {
+        horizontal = lookAxis;
+    }
+    horizontal = normalize(horizontal);
+    vec3 right = normalize(cross(up, horizontal));
+
+    // Select the frame that was baked from the closest angle, angles are measured in local space.
+    float angle = atan(dot(horizontal, normalize(sideAxis)), dot(horizontal, normalize(lookAxis)));
+    float angleStep = 2.0 * 3.14159265 / float(angleCount);
+    float frame = mod(floor(angle / angleStep + 0.5), float(angleCount));
+    vec2 cell = vec2(mod(frame, gridSize.x), floor(frame / gridSize.x));
+
+    texCoord = (cell + (vertexPosition + 1.0) * 0.5) / gridSize;
+
+    vec2 halfSize = frameHalfSize * vec2(length(sideAxis), upLength);
+    vec3 worldPosition = origin + right * vertexPosition.x * halfSize.x + up * vertexPosition.y * halfSize.y;
+    gl_Position = viewProjectionMatrix * vec4(worldPosition, 1.0);
+}
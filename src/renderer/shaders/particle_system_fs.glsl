@@ -3,6 +3,7 @@ uniform sampler2D depthBufferTexture;
 uniform vec2 invScreenSize;
 uniform vec2 projParams;
 uniform float softBoundarySharpnessFactor;
+uniform vec3 lighting;
 
 out vec4 FragColor;
 in vec2 texCoord;
@@ -21,5 +22,6 @@ void main()
     float fragmentDepth = toProjSpace(gl_FragCoord.z);
     float depthOpacity = smoothstep((sceneDepth - fragmentDepth) * softBoundarySharpnessFactor, 0.0, 1.0);
     FragColor = color * S_SRGBToLinear(texture(diffuseTexture, texCoord)).r;
+    FragColor.rgb *= lighting;
     FragColor.a *= depthOpacity;
 }
\ No newline at end of file
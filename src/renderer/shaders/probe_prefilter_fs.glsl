@@ -0,0 +1,97 @@
+// Convolves environment cube map with GGX distribution of given roughness, the result is
+// written into a mip level of the probe cube map. Uses importance sampling and samples
+// lower mip levels of the source for sparse samples to avoid aliasing.
+
+uniform samplerCube environmentTexture;
+uniform int faceIndex;
+uniform float faceSize;
+uniform float sourceSize;
+uniform float roughness;
+
+out vec4 FragColor;
+
+const uint SAMPLE_COUNT = 64u;
+
+// Must be in sync with the order of faces of cube maps in OpenGL.
+vec3 FaceDirection(int face, vec2 uv)
+{
+    vec2 p = uv * 2.0 - 1.0;
+    if (face == 0) {
+        return vec3(1.0, -p.y, -p.x);
+    } else if (face == 1) {
+        return vec3(-1.0, -p.y, p.x);
+    } else if (face == 2) {
+        return vec3(p.x, 1.0, p.y);
+    } else if (face == 3) {
+        return vec3(p.x, -1.0, -p.y);
+    } else if (face == 4) {
+        return vec3(p.x, -p.y, 1.0);
+    } else {
+        return vec3(-p.x, -p.y, -1.0);
+    }
+}
+
+float RadicalInverse(uint bits)
+{
+    bits = (bits << 16u) | (bits >> 16u);
+    bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+    bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+    bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+    bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+    return float(bits) * 2.3283064365386963e-10;
+}
+
+vec3 ImportanceSampleGGX(vec2 xi, vec3 N, float roughness)
+{
+    float a = roughness * roughness;
+
+    float phi = 2.0 * PI * xi.x;
+    float cosTheta = sqrt((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y));
+    float sinTheta = sqrt(1.0 - cosTheta * cosTheta);
+
+    vec3 H = vec3(cos(phi) * sinTheta, sin(phi) * sinTheta, cosTheta);
+
+    vec3 up = abs(N.z) < 0.999 ? vec3(0.0, 0.0, 1.0) : vec3(1.0, 0.0, 0.0);
+    vec3 tangent = normalize(cross(up, N));
+    vec3 bitangent = cross(N, tangent);
+
+    return normalize(tangent * H.x + bitangent * H.y + N * H.z);
+}
+
+void main()
+{
+    vec3 N = normalize(FaceDirection(faceIndex, gl_FragCoord.xy / faceSize));
+
+    if (roughness <= 0.0) {
+        FragColor = vec4(textureLod(environmentTexture, N, 0.0).rgb, 1.0);
+        return;
+    }
+
+    // Assume that view direction is equal to the normal.
+    vec3 V = N;
+
+    // Solid angle of a texel of the top level of the source.
+    float texelSolidAngle = 4.0 * PI / (6.0 * sourceSize * sourceSize);
+
+    vec3 color = vec3(0.0);
+    float totalWeight = 0.0;
+    for (uint i = 0u; i < SAMPLE_COUNT; ++i) {
+        vec2 xi = vec2(float(i) / float(SAMPLE_COUNT), RadicalInverse(i));
+        vec3 H = ImportanceSampleGGX(xi, N, roughness);
+        vec3 L = normalize(2.0 * dot(V, H) * H - V);
+
+        float NdotL = dot(N, L);
+        if (NdotL > 0.0) {
+            float NdotH = max(dot(N, H), 0.0);
+            float HdotV = max(dot(H, V), 0.0);
+            float pdf = S_DistributionGGX(N, H, roughness) * NdotH / (4.0 * HdotV) + 0.0001;
+            float sampleSolidAngle = 1.0 / (float(SAMPLE_COUNT) * pdf + 0.0001);
+            float lod = 0.5 * log2(sampleSolidAngle / texelSolidAngle);
+
+            color += textureLod(environmentTexture, L, max(lod, 0.0)).rgb * NdotL;
+            totalWeight += NdotL;
+        }
+    }
+
+    FragColor = vec4(color / max(totalWeight, 0.0001), 1.0);
+}
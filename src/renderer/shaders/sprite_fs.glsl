@@ -1,5 +1,6 @@
 uniform sampler2D diffuseTexture;
 uniform vec4 color;
+uniform vec3 lighting;
 
 out vec4 FragColor;
 
@@ -8,4 +9,5 @@ in vec2 texCoord;
 void main()
 {
     FragColor = color * S_SRGBToLinear(texture(diffuseTexture, texCoord)).r;
+    FragColor.rgb *= lighting;
 }
\ No newline at end of file
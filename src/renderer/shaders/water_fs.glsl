@@ -0,0 +1,73 @@
+uniform sampler2D reflectionTexture;
+uniform sampler2D refractionTexture;
+uniform sampler2D depthTexture;
+uniform sampler2D normalTexture;
+uniform mat4 reflectionViewProjection;
+uniform mat4 invViewProjection;
+uniform vec2 invScreenSize;
+uniform vec3 cameraPosition;
+uniform vec3 surfaceNormal;
+uniform vec3 surfaceTangent;
+uniform vec3 surfaceBinormal;
+uniform float time;
+uniform float waveScale;
+uniform vec2 waveSpeed;
+uniform float distortion;
+uniform vec4 waterColor;
+uniform float depthDensity;
+uniform float fresnelPower;
+uniform bool reflectionsEnabled;
+
+in vec3 worldPosition;
+
+out vec4 FragColor;
+
+// Reflectance of water at normal incidence.
+const float F0 = 0.02;
+
+vec3 fetchNormal(vec2 texCoord)
+{
+    return texture(normalTexture, texCoord).xyz * 2.0 - 1.0;
+}
+
+void main()
+{
+    // Waves are made of two layers of the normal map scrolling in different directions, it hides
+    // repetition of the normal map.
+    vec2 planePosition = vec2(dot(worldPosition, surfaceTangent), dot(worldPosition, surfaceBinormal)) * waveScale;
+    vec3 n1 = fetchNormal(planePosition + waveSpeed * time);
+    vec3 n2 = fetchNormal(planePosition * 0.7 - waveSpeed.yx * time);
+    vec3 tangentNormal = normalize(vec3(n1.xy + n2.xy, n1.z * n2.z));
+    vec3 normal = normalize(surfaceTangent * tangentNormal.x + surfaceBinormal * tangentNormal.y + surfaceNormal * tangentNormal.z);
+    vec2 offset = tangentNormal.xy * distortion;
+
+    vec2 screenTexCoord = gl_FragCoord.xy * invScreenSize;
+
+    // Refraction. Distorted coordinates may point to an object in front of the surface, in this case
+    // undistorted coordinates are used.
+    vec2 refractionTexCoord = screenTexCoord + offset;
+    float sceneDepth = texture(depthTexture, refractionTexCoord).r;
+    if (sceneDepth < gl_FragCoord.z)
+    {
+        refractionTexCoord = screenTexCoord;
+        sceneDepth = texture(depthTexture, refractionTexCoord).r;
+    }
+    vec3 scenePosition = S_UnProject(vec3(refractionTexCoord, sceneDepth), invViewProjection);
+    float waterDepth = distance(scenePosition, worldPosition);
+    float tint = 1.0 - exp(-waterDepth * depthDensity);
+    vec3 refraction = mix(texture(refractionTexture, refractionTexCoord).rgb, waterColor.rgb, tint);
+
+    // Reflection.
+    vec3 reflection = waterColor.rgb;
+    if (reflectionsEnabled)
+    {
+        vec2 reflectionTexCoord = S_Project(worldPosition, reflectionViewProjection).xy;
+        reflection = texture(reflectionTexture, reflectionTexCoord + offset).rgb;
+    }
+
+    // Schlick's approximation of Fresnel term.
+    vec3 toCamera = normalize(cameraPosition - worldPosition);
+    float fresnel = F0 + (1.0 - F0) * pow(1.0 - clamp(dot(toCamera, normal), 0.0, 1.0), fresnelPower);
+
+    FragColor = vec4(mix(refraction, reflection, fresnel), 1.0);
+}
@@ -0,0 +1,12 @@
+layout(location = 0) in vec3 vertexPosition;
+
+uniform mat4 worldMatrix;
+uniform mat4 viewProjectionMatrix;
+
+out vec3 worldPosition;
+
+void main()
+{
+    worldPosition = (worldMatrix * vec4(vertexPosition, 1.0)).xyz;
+    gl_Position = viewProjectionMatrix * vec4(worldPosition, 1.0);
+}
@@ -0,0 +1,12 @@
+uniform sampler2D fontTexture;
+uniform vec4 color;
+
+out vec4 FragColor;
+
+in vec2 texCoord;
+
+void main()
+{
+    FragColor = color;
+    FragColor.a *= texture(fontTexture, texCoord).r;
+}
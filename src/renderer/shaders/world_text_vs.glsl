@@ -0,0 +1,26 @@
+layout(location = 0) in vec2 vertexPosition;
+layout(location = 1) in vec2 vertexTexCoord;
+
+uniform mat4 viewProjectionMatrix;
+uniform mat4 worldMatrix;
+uniform vec3 cameraUpVector;
+uniform vec3 cameraSideVector;
+uniform bool billboard;
+// Offset (in world units) in the plane of the text, it is used to draw outline.
+uniform vec2 offset;
+
+out vec2 texCoord;
+
+void main()
+{
+    texCoord = vertexTexCoord;
+    vec2 position = vertexPosition + offset;
+    vec4 worldPosition;
+    if (billboard) {
+        vec3 origin = vec3(worldMatrix[3][0], worldMatrix[3][1], worldMatrix[3][2]);
+        worldPosition = vec4(origin + position.x * cameraSideVector + position.y * cameraUpVector, 1.0);
+    } else {
+        worldPosition = worldMatrix * vec4(position, 0.0, 1.0);
+    }
+    gl_Position = viewProjectionMatrix * worldPosition;
+}
@@ -1,7 +1,9 @@
 use crate::{
     core::{
         algebra::{Matrix4, Point3, Vector3},
+        color::Color,
         math::{aabb::AxisAlignedBoundingBox, frustum::Frustum, Rect},
+        profile_scope,
         sstorage::ImmutableString,
     },
     renderer::{
@@ -17,6 +19,7 @@ use crate::{
             },
             state::{ColorMask, PipelineState},
         },
+        shadow::is_shadow_caster_by_mask,
         MaterialContext, RenderPassStatistics, ShadowMapPrecision,
     },
     scene::{
@@ -139,6 +142,8 @@ impl CsmRenderer {
     }
 
     pub(in crate) fn render(&mut self, ctx: CsmRenderContext) -> RenderPassStatistics {
+        profile_scope!("DirectionalShadowMap");
+
         let mut stats = RenderPassStatistics::default();
 
         let CsmRenderContext {
@@ -233,13 +238,14 @@ impl CsmRenderer {
                     for instance in batch.instances.iter() {
                         let node = &graph[instance.owner];
 
-                        let visible = match node {
-                            Node::Mesh(mesh) => mesh.global_visibility() && mesh.cast_shadows(),
-                            Node::Terrain(terrain) => {
-                                terrain.global_visibility() && terrain.cast_shadows()
-                            }
-                            _ => false,
-                        };
+                        let visible = is_shadow_caster_by_mask(node, camera.visibility_mask())
+                            && match node {
+                                Node::Mesh(mesh) => mesh.global_visibility() && mesh.cast_shadows(),
+                                Node::Terrain(terrain) => {
+                                    terrain.global_visibility() && terrain.cast_shadows()
+                                }
+                                _ => false,
+                            };
 
                         if !visible {
                             continue;
@@ -249,9 +255,9 @@ impl CsmRenderer {
                             geometry,
                             state,
                             viewport,
-                            &render_pass.program,
+                            render_pass.program(instance.triplanar_mapping.is_some()),
                             &DrawParameters {
-                                cull_face: Some(CullFace::Back),
+                                cull_face: instance.cull_face(Some(CullFace::Back)),
                                 color_write: ColorMask::all(false),
                                 depth_write: true,
                                 stencil_test: None,
@@ -270,7 +276,15 @@ impl CsmRenderer {
                                     use_skeletal_animation: batch.is_skinned,
                                     camera_position: &camera.global_position(),
                                     use_pom: false,
+                                    opacity: 1.0,
+                                    color: Color::WHITE,
                                     light_position: &Default::default(),
+                                    uv_transform: &instance.uv_transform,
+                                    mip_bias: instance.mip_bias,
+                                    receive_shadows: instance.receive_shadows,
+                                    alpha_cutoff: instance.alpha_cutoff,
+                                    triplanar_mapping: instance.triplanar_mapping,
+                                    instanced_bones: None,
                                     normal_dummy: normal_dummy.clone(),
                                     white_dummy: white_dummy.clone(),
                                     black_dummy: black_dummy.clone(),
@@ -16,8 +16,13 @@ pub fn cascade_size(base_size: usize, cascade: usize) -> usize {
     }
 }
 
-fn should_cast_shadows(node: &Node, light_frustum: &Frustum) -> bool {
-    node.global_visibility() && {
+/// Checks whether a node that is hidden by render mask of a camera should still cast shadows.
+fn is_shadow_caster_by_mask(node: &Node, visibility_mask: u32) -> bool {
+    node.is_visible_by_mask(visibility_mask) || node.cast_shadows_when_masked()
+}
+
+fn should_cast_shadows(node: &Node, light_frustum: &Frustum, visibility_mask: u32) -> bool {
+    node.global_visibility() && is_shadow_caster_by_mask(node, visibility_mask) && {
         match node {
             Node::Mesh(mesh) => {
                 mesh.cast_shadows() && light_frustum.is_intersects_aabb(&mesh.world_bounding_box())
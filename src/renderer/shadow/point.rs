@@ -3,7 +3,7 @@ use crate::{
         algebra::{Matrix4, Point3, Vector3},
         color::Color,
         math::{frustum::Frustum, Rect},
-        scope_profile,
+        profile_scope, scope_profile,
         sstorage::ImmutableString,
     },
     renderer::{
@@ -12,7 +12,7 @@ use crate::{
         cache::{shader::ShaderCache, texture::TextureCache},
         framework::{
             error::FrameworkError,
-            framebuffer::{Attachment, AttachmentKind, FrameBuffer},
+            framebuffer::{Attachment, AttachmentKind, DrawParameters, FrameBuffer},
             gpu_texture::{
                 Coordinate, CubeMapFace, GpuTexture, GpuTextureKind, MagnificationFilter,
                 MinificationFilter, PixelKind, WrapMode,
@@ -53,6 +53,7 @@ pub(in crate) struct PointShadowMapRenderContext<'a, 'c> {
     pub normal_dummy: Rc<RefCell<GpuTexture>>,
     pub white_dummy: Rc<RefCell<GpuTexture>>,
     pub black_dummy: Rc<RefCell<GpuTexture>>,
+    pub visibility_mask: u32,
 }
 
 impl PointShadowMapRenderer {
@@ -179,6 +180,10 @@ impl PointShadowMapRenderer {
         self.precision
     }
 
+    pub fn cascade_size(&self, cascade: usize) -> usize {
+        cascade_size(self.size, cascade)
+    }
+
     pub fn cascade_texture(&self, cascade: usize) -> Rc<RefCell<GpuTexture>> {
         self.cascades[cascade].color_attachments()[0]
             .texture
@@ -187,6 +192,7 @@ impl PointShadowMapRenderer {
 
     pub(in crate) fn render(&mut self, args: PointShadowMapRenderContext) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("PointShadowMap");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -203,6 +209,7 @@ impl PointShadowMapRenderer {
             normal_dummy,
             white_dummy,
             black_dummy,
+            visibility_mask,
         } = args;
 
         let framebuffer = &mut self.cascades[cascade];
@@ -243,13 +250,17 @@ impl PointShadowMapRenderer {
                     for instance in batch.instances.iter() {
                         let node = &graph[instance.owner];
 
-                        if should_cast_shadows(node, &frustum) {
+                        if should_cast_shadows(node, &frustum, visibility_mask) {
                             statistics += framebuffer.draw(
                                 geometry,
                                 state,
                                 viewport,
-                                &render_pass.program,
-                                &render_pass.draw_params,
+                                render_pass.program(instance.triplanar_mapping.is_some()),
+                                &DrawParameters {
+                                    cull_face: instance
+                                        .cull_face(render_pass.draw_params.cull_face),
+                                    ..render_pass.draw_params.clone()
+                                },
                                 |mut program_binding| {
                                     apply_material(MaterialContext {
                                         material: &*material,
@@ -262,7 +273,15 @@ impl PointShadowMapRenderer {
                                         use_skeletal_animation: batch.is_skinned,
                                         camera_position: &Default::default(),
                                         use_pom: false,
+                                        opacity: 1.0,
+                                        color: Color::WHITE,
                                         light_position: &light_pos,
+                                        uv_transform: &instance.uv_transform,
+                                        mip_bias: instance.mip_bias,
+                                        receive_shadows: instance.receive_shadows,
+                                        alpha_cutoff: instance.alpha_cutoff,
+                                        triplanar_mapping: instance.triplanar_mapping,
+                                        instanced_bones: None,
                                         normal_dummy: normal_dummy.clone(),
                                         white_dummy: white_dummy.clone(),
                                         black_dummy: black_dummy.clone(),
@@ -3,7 +3,7 @@ use crate::{
         algebra::Matrix4,
         color::Color,
         math::{frustum::Frustum, Rect},
-        scope_profile,
+        profile_scope, scope_profile,
         sstorage::ImmutableString,
     },
     renderer::{
@@ -129,8 +129,10 @@ impl SpotShadowMapRenderer {
         normal_dummy: Rc<RefCell<GpuTexture>>,
         white_dummy: Rc<RefCell<GpuTexture>>,
         black_dummy: Rc<RefCell<GpuTexture>>,
+        visibility_mask: u32,
     ) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("SpotShadowMap");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -153,14 +155,14 @@ impl SpotShadowMapRenderer {
                 for instance in batch.instances.iter() {
                     let node = &graph[instance.owner];
 
-                    if should_cast_shadows(node, &frustum) {
+                    if should_cast_shadows(node, &frustum, visibility_mask) {
                         statistics += framebuffer.draw(
                             geometry,
                             state,
                             viewport,
-                            &render_pass.program,
+                            render_pass.program(instance.triplanar_mapping.is_some()),
                             &DrawParameters {
-                                cull_face: Some(CullFace::Back),
+                                cull_face: instance.cull_face(Some(CullFace::Back)),
                                 color_write: ColorMask::all(false),
                                 depth_write: true,
                                 stencil_test: None,
@@ -179,7 +181,15 @@ impl SpotShadowMapRenderer {
                                     use_skeletal_animation: batch.is_skinned,
                                     camera_position: &Default::default(),
                                     use_pom: false,
+                                    opacity: 1.0,
+                                    color: Color::WHITE,
                                     light_position: &Default::default(),
+                                    uv_transform: &instance.uv_transform,
+                                    mip_bias: instance.mip_bias,
+                                    receive_shadows: instance.receive_shadows,
+                                    alpha_cutoff: instance.alpha_cutoff,
+                                    triplanar_mapping: instance.triplanar_mapping,
+                                    instanced_bones: None,
                                     normal_dummy: normal_dummy.clone(),
                                     white_dummy: white_dummy.clone(),
                                     black_dummy: black_dummy.clone(),
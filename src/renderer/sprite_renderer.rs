@@ -1,7 +1,9 @@
 use crate::{
     core::{
+        algebra::{Vector3, Vector4},
+        color::Color,
         math::{Matrix4Ext, Rect},
-        scope_profile,
+        profile_scope, scope_profile,
         sstorage::ImmutableString,
     },
     renderer::{
@@ -15,9 +17,15 @@ use crate::{
         },
         RenderPassStatistics, TextureCache,
     },
-    scene::{camera::Camera, graph::Graph, mesh::surface::SurfaceData, node::Node},
+    scene::{
+        camera::Camera,
+        graph::Graph,
+        light::{self, LightingMode},
+        mesh::surface::SurfaceData,
+        node::Node,
+    },
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, cmp::Ordering, rc::Rc};
 
 struct SpriteShader {
     program: GpuProgram,
@@ -29,6 +37,8 @@ struct SpriteShader {
     diffuse_texture: UniformLocation,
     size: UniformLocation,
     rotation: UniformLocation,
+    uv_rect: UniformLocation,
+    lighting: UniformLocation,
 }
 
 impl SpriteShader {
@@ -50,6 +60,8 @@ impl SpriteShader {
                 .uniform_location(state, &ImmutableString::new("diffuseTexture"))?,
             color: program.uniform_location(state, &ImmutableString::new("color"))?,
             rotation: program.uniform_location(state, &ImmutableString::new("rotation"))?,
+            uv_rect: program.uniform_location(state, &ImmutableString::new("uvRect"))?,
+            lighting: program.uniform_location(state, &ImmutableString::new("lighting"))?,
             program,
         })
     }
@@ -68,6 +80,7 @@ pub(in crate) struct SpriteRenderContext<'a, 'b, 'c> {
     pub white_dummy: Rc<RefCell<GpuTexture>>,
     pub viewport: Rect<i32>,
     pub textures: &'a mut TextureCache,
+    pub ambient_color: Color,
 }
 
 impl SpriteRenderer {
@@ -87,6 +100,7 @@ impl SpriteRenderer {
     #[must_use]
     pub(in crate) fn render(&mut self, args: SpriteRenderContext) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("Sprites");
 
         let mut statistics = RenderPassStatistics::default();
 
@@ -98,6 +112,7 @@ impl SpriteRenderer {
             white_dummy,
             viewport,
             textures,
+            ambient_color,
         } = args;
 
         let initial_view_projection = camera.view_projection_matrix();
@@ -107,17 +122,34 @@ impl SpriteRenderer {
         let camera_up = inv_view.up();
         let camera_side = inv_view.side();
 
-        for sprite in graph.linear_iter().filter_map(|node| {
-            if !node.global_visibility() {
-                return None;
-            }
+        let camera_position = camera.global_position();
+        let camera_look = camera.look_vector();
 
-            if let Node::Sprite(sprite) = node {
-                Some(sprite)
-            } else {
-                None
-            }
-        }) {
+        let mut sprites = graph
+            .linear_iter()
+            .filter_map(|node| {
+                if !node.global_visibility() || !node.is_visible_by_mask(camera.visibility_mask()) {
+                    return None;
+                }
+
+                if let Node::Sprite(sprite) = node {
+                    let depth = (sprite.global_position() - camera_position).dot(&camera_look);
+                    Some((sprite, depth))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Sort by layers first, then back-to-front inside a layer. Sort is stable, so sprites
+        // with equal keys are drawn in order of the graph.
+        sprites.sort_by(|(a, a_depth), (b, b_depth)| {
+            (a.sorting_layer(), a.order_in_layer())
+                .cmp(&(b.sorting_layer(), b.order_in_layer()))
+                .then_with(|| b_depth.partial_cmp(a_depth).unwrap_or(Ordering::Equal))
+        });
+
+        for (sprite, _) in sprites {
             let view_projection = if sprite.depth_offset_factor() != 0.0 {
                 let mut projection = camera.projection_matrix();
                 projection[14] -= sprite.depth_offset_factor();
@@ -136,6 +168,19 @@ impl SpriteRenderer {
                 white_dummy.clone()
             };
 
+            let uv_rect = sprite.uv_rect();
+            let uv_rect = Vector4::new(uv_rect.x(), uv_rect.y(), uv_rect.w(), uv_rect.h());
+
+            let lighting = match sprite.lighting_mode() {
+                LightingMode::Unlit => Vector3::new(1.0, 1.0, 1.0),
+                LightingMode::Lit => light::evaluate_lighting(
+                    graph,
+                    sprite.global_position(),
+                    ambient_color,
+                    light::DEFAULT_MAX_LIGHTS_PER_SPRITE,
+                ),
+            };
+
             statistics += framebuffer.draw(
                 &self.collapsed_quad,
                 state,
@@ -162,7 +207,9 @@ impl SpriteRenderer {
                         .set_vector3(&self.shader.camera_side_vector, &camera_side)
                         .set_f32(&self.shader.size, sprite.size())
                         .set_linear_color(&self.shader.color, &sprite.color())
-                        .set_f32(&self.shader.rotation, sprite.rotation());
+                        .set_f32(&self.shader.rotation, sprite.rotation())
+                        .set_vector4(&self.shader.uv_rect, &uv_rect)
+                        .set_vector3(&self.shader.lighting, &lighting);
                 },
             );
         }
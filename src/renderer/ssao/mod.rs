@@ -3,7 +3,7 @@ use crate::{
         algebra::{Matrix3, Matrix4, Vector2, Vector3},
         color::Color,
         math::{lerpf, Rect},
-        scope_profile,
+        profile_scope, scope_profile,
         sstorage::ImmutableString,
     },
     rand::Rng,
@@ -206,6 +206,7 @@ impl ScreenSpaceAmbientOcclusionRenderer {
         view_matrix: Matrix3<f32>,
     ) -> RenderPassStatistics {
         scope_profile!();
+        profile_scope!("SSAO");
 
         let mut stats = RenderPassStatistics::default();
 
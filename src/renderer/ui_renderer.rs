@@ -6,11 +6,12 @@ use crate::{
         color::Color,
         math::Rect,
         parking_lot::Mutex,
-        scope_profile,
+        profile_scope, scope_profile,
     },
     gui::{
         brush::Brush,
         draw::{CommandTexture, DrawingContext, SharedTexture},
+        ttf::Font,
     },
     renderer::{
         framework::{
@@ -82,6 +83,37 @@ impl UiShader {
     }
 }
 
+/// Returns GPU texture of given atlas page of the font. Pixels of the page are re-uploaded when
+/// new glyphs were added to it, old texture will be removed from the cache automatically.
+pub(in crate) fn font_page_texture(
+    state: &mut PipelineState,
+    texture_cache: &mut TextureCache,
+    font: &mut Font,
+    page_index: usize,
+) -> Option<Rc<RefCell<GpuTexture>>> {
+    let size = font.page_size() as u32;
+    let page = font.pages_mut().get_mut(page_index)?;
+    if page.texture.is_none() || page.modified {
+        if let Some(details) = TextureData::from_bytes(
+            TextureKind::Rectangle {
+                width: size,
+                height: size,
+            },
+            TexturePixelKind::R8,
+            page.pixels().to_vec(),
+            false,
+        ) {
+            page.texture = Some(SharedTexture(Arc::new(Mutex::new(TextureState::Ok(
+                details,
+            )))));
+        }
+        page.modified = false;
+    }
+    let texture = page.texture.clone()?;
+    let texture = texture.0.downcast::<Mutex<TextureState>>().ok()?;
+    texture_cache.get(state, &Texture(Resource::from(texture)))
+}
+
 pub struct UiRenderer {
     shader: UiShader,
     geometry_buffer: GeometryBuffer,
@@ -94,6 +126,8 @@ pub(in crate) struct UiRenderContext<'a, 'b, 'c> {
     pub frame_buffer: &'b mut FrameBuffer,
     pub frame_width: f32,
     pub frame_height: f32,
+    /// Amount of physical pixels per logical unit of the user interface.
+    pub scale: f32,
     pub drawing_context: &'c DrawingContext,
     pub white_dummy: Rc<RefCell<GpuTexture>>,
     pub texture_cache: &'a mut TextureCache,
@@ -156,6 +190,7 @@ impl UiRenderer {
         args: UiRenderContext,
     ) -> Result<RenderPassStatistics, FrameworkError> {
         scope_profile!();
+        profile_scope!("UiRenderer");
 
         let UiRenderContext {
             state,
@@ -163,6 +198,7 @@ impl UiRenderer {
             frame_buffer: backbuffer,
             frame_width,
             frame_height,
+            scale,
             drawing_context,
             white_dummy,
             texture_cache,
@@ -176,7 +212,15 @@ impl UiRenderer {
         let geometry_buffer = self.geometry_buffer.bind(state);
         geometry_buffer.set_triangles(drawing_context.get_triangles());
 
-        let ortho = Matrix4::new_orthographic(0.0, frame_width, frame_height, 0.0, -1.0, 1.0);
+        // Drawing context is in logical units, scale it to fill the whole frame.
+        let ortho = Matrix4::new_orthographic(
+            0.0,
+            frame_width / scale,
+            frame_height / scale,
+            0.0,
+            -1.0,
+            1.0,
+        );
         let resolution = Vector2::new(frame_width, frame_height);
 
         state.set_scissor_test(true);
@@ -185,7 +229,10 @@ impl UiRenderer {
             let mut diffuse_texture = white_dummy.clone();
             let mut is_font_texture = false;
 
+            // Scissor box is in physical pixels.
             let mut clip_bounds = cmd.clip_bounds;
+            clip_bounds.position = clip_bounds.position.scale(scale);
+            clip_bounds.size = clip_bounds.size.scale(scale);
             clip_bounds.position.x = clip_bounds.position.x.floor();
             clip_bounds.position.y = clip_bounds.position.y.floor();
             clip_bounds.size.x = clip_bounds.size.x.ceil();
@@ -238,41 +285,22 @@ impl UiRenderer {
                     },
                 );
 
-                // Make sure main geometry will be drawn only on marked pixels.
+                // Make sure main geometry will be drawn only on marked pixels. Each shape of the
+                // geometry increments stencil value once, so pixels inside of intersection of the
+                // shapes have the value equal to the amount of shapes.
                 stencil_test = Some(StencilFunc {
                     func: CompareFunc::Equal,
-                    ref_value: 1,
+                    ref_value: clipping_geometry.shape_count() as u32,
                     ..Default::default()
                 });
             }
 
             match &cmd.texture {
-                CommandTexture::Font(font_arc) => {
-                    let mut font = font_arc.0.lock().unwrap();
-                    if font.texture.is_none() {
-                        let size = font.atlas_size() as u32;
-                        if let Some(details) = TextureData::from_bytes(
-                            TextureKind::Rectangle {
-                                width: size,
-                                height: size,
-                            },
-                            TexturePixelKind::R8,
-                            font.atlas_pixels().to_vec(),
-                            false,
-                        ) {
-                            font.texture = Some(SharedTexture(Arc::new(Mutex::new(
-                                TextureState::Ok(details),
-                            ))));
-                        }
-                    }
-                    let tex = font
-                        .texture
-                        .clone()
-                        .unwrap()
-                        .0
-                        .downcast::<Mutex<TextureState>>()
-                        .unwrap();
-                    if let Some(texture) = texture_cache.get(state, &Texture(Resource::from(tex))) {
+                CommandTexture::Font { font, page_index } => {
+                    let mut font = font.0.lock().unwrap();
+                    if let Some(texture) =
+                        font_page_texture(state, texture_cache, &mut font, *page_index)
+                    {
                         diffuse_texture = texture;
                     }
                     is_font_texture = true;
@@ -290,7 +318,9 @@ impl UiRenderer {
 
             let mut raw_stops = [0.0; 16];
             let mut raw_colors = [Vector4::default(); 16];
-            let bounds_max = cmd.bounds.right_bottom_corner();
+            // Bounds are compared with fragment coordinates, which are in physical pixels.
+            let bounds_min = cmd.bounds.position.scale(scale);
+            let bounds_max = cmd.bounds.right_bottom_corner().scale(scale);
 
             let (gradient_origin, gradient_end) = match cmd.brush {
                 Brush::Solid(_) => (Vector2::default(), Vector2::default()),
@@ -325,7 +355,7 @@ impl UiRenderer {
                         .set_texture(&shader.diffuse_texture, &diffuse_texture)
                         .set_matrix4(&shader.wvp_matrix, &ortho)
                         .set_vector2(&shader.resolution, &resolution)
-                        .set_vector2(&shader.bounds_min, &cmd.bounds.position)
+                        .set_vector2(&shader.bounds_min, &bounds_min)
                         .set_vector2(&shader.bounds_max, &bounds_max)
                         .set_bool(&shader.is_font, is_font_texture)
                         .set_i32(
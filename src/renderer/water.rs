@@ -0,0 +1,653 @@
+//! Water renderer draws water surfaces on top of lit scene.
+//!
+//! # Reflection
+//!
+//! For every visible water surface with enabled reflections the scene is rendered one more time
+//! into an off-screen render target by a camera that is mirrored relative to the plane of the
+//! surface. Near clipping plane of the mirrored camera is replaced with the plane of the surface
+//! (so called oblique near plane), so everything below the surface is clipped. Only skybox and
+//! opaque meshes are rendered using `Forward` render pass of their materials, which means that
+//! reflections are unlit.
+//!
+//! # Refraction
+//!
+//! Lit scene behind the surface is copied into a separate texture, then the surface samples it
+//! using distorted screen-space coordinates. Depth of the water is calculated using depth buffer
+//! of the G-Buffer.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Vector2, Vector3, Vector4},
+        color::Color,
+        math::{frustum::Frustum, Matrix4Ext, Rect},
+        profile_scope, scope_profile,
+        sstorage::ImmutableString,
+    },
+    renderer::{
+        apply_material,
+        batch::BatchStorage,
+        cache::{shader::ShaderCache, texture::TextureCache},
+        framework::{
+            error::FrameworkError,
+            framebuffer::{Attachment, AttachmentKind, DrawParameters, FrameBuffer},
+            geometry_buffer::{GeometryBuffer, GeometryBufferKind},
+            gpu_program::{GpuProgram, UniformLocation},
+            gpu_texture::{
+                Coordinate, GpuTexture, GpuTextureKind, MagnificationFilter, MinificationFilter,
+                PixelKind, WrapMode,
+            },
+            state::PipelineState,
+        },
+        skybox_shader::SkyboxShader,
+        GeometryCache, MaterialContext, QualitySettings, RenderPassStatistics,
+    },
+    scene::{
+        camera::Camera, graph::Graph, mesh::surface::SurfaceData, node::Node,
+        visibility::VisibilityCache, water::Water,
+    },
+};
+use std::{cell::RefCell, rc::Rc};
+
+struct WaterShader {
+    program: GpuProgram,
+    world_matrix: UniformLocation,
+    view_projection_matrix: UniformLocation,
+    reflection_texture: UniformLocation,
+    refraction_texture: UniformLocation,
+    depth_texture: UniformLocation,
+    normal_texture: UniformLocation,
+    reflection_view_projection: UniformLocation,
+    inv_view_projection: UniformLocation,
+    inv_screen_size: UniformLocation,
+    camera_position: UniformLocation,
+    surface_normal: UniformLocation,
+    surface_tangent: UniformLocation,
+    surface_binormal: UniformLocation,
+    time: UniformLocation,
+    wave_scale: UniformLocation,
+    wave_speed: UniformLocation,
+    distortion: UniformLocation,
+    water_color: UniformLocation,
+    depth_density: UniformLocation,
+    fresnel_power: UniformLocation,
+    reflections_enabled: UniformLocation,
+}
+
+impl WaterShader {
+    fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        let fragment_source = include_str!("shaders/water_fs.glsl");
+        let vertex_source = include_str!("shaders/water_vs.glsl");
+        let program =
+            GpuProgram::from_source(state, "WaterShader", vertex_source, fragment_source)?;
+        Ok(Self {
+            world_matrix: program.uniform_location(state, &ImmutableString::new("worldMatrix"))?,
+            view_projection_matrix: program
+                .uniform_location(state, &ImmutableString::new("viewProjectionMatrix"))?,
+            reflection_texture: program
+                .uniform_location(state, &ImmutableString::new("reflectionTexture"))?,
+            refraction_texture: program
+                .uniform_location(state, &ImmutableString::new("refractionTexture"))?,
+            depth_texture: program
+                .uniform_location(state, &ImmutableString::new("depthTexture"))?,
+            normal_texture: program
+                .uniform_location(state, &ImmutableString::new("normalTexture"))?,
+            reflection_view_projection: program
+                .uniform_location(state, &ImmutableString::new("reflectionViewProjection"))?,
+            inv_view_projection: program
+                .uniform_location(state, &ImmutableString::new("invViewProjection"))?,
+            inv_screen_size: program
+                .uniform_location(state, &ImmutableString::new("invScreenSize"))?,
+            camera_position: program
+                .uniform_location(state, &ImmutableString::new("cameraPosition"))?,
+            surface_normal: program
+                .uniform_location(state, &ImmutableString::new("surfaceNormal"))?,
+            surface_tangent: program
+                .uniform_location(state, &ImmutableString::new("surfaceTangent"))?,
+            surface_binormal: program
+                .uniform_location(state, &ImmutableString::new("surfaceBinormal"))?,
+            time: program.uniform_location(state, &ImmutableString::new("time"))?,
+            wave_scale: program.uniform_location(state, &ImmutableString::new("waveScale"))?,
+            wave_speed: program.uniform_location(state, &ImmutableString::new("waveSpeed"))?,
+            distortion: program.uniform_location(state, &ImmutableString::new("distortion"))?,
+            water_color: program.uniform_location(state, &ImmutableString::new("waterColor"))?,
+            depth_density: program
+                .uniform_location(state, &ImmutableString::new("depthDensity"))?,
+            fresnel_power: program
+                .uniform_location(state, &ImmutableString::new("fresnelPower"))?,
+            reflections_enabled: program
+                .uniform_location(state, &ImmutableString::new("reflectionsEnabled"))?,
+            program,
+        })
+    }
+}
+
+struct RenderTarget {
+    framebuffer: FrameBuffer,
+    width: usize,
+    height: usize,
+}
+
+impl RenderTarget {
+    fn new(
+        state: &mut PipelineState,
+        width: usize,
+        height: usize,
+        with_depth: bool,
+    ) -> Result<Self, FrameworkError> {
+        let depth = if with_depth {
+            let depth_stencil = GpuTexture::new(
+                state,
+                GpuTextureKind::Rectangle { width, height },
+                PixelKind::D24S8,
+                MinificationFilter::Nearest,
+                MagnificationFilter::Nearest,
+                1,
+                None,
+            )?;
+            Some(Attachment {
+                kind: AttachmentKind::DepthStencil,
+                texture: Rc::new(RefCell::new(depth_stencil)),
+            })
+        } else {
+            None
+        };
+
+        let mut color = GpuTexture::new(
+            state,
+            GpuTextureKind::Rectangle { width, height },
+            PixelKind::RGBA16F,
+            MinificationFilter::Linear,
+            MagnificationFilter::Linear,
+            1,
+            None,
+        )?;
+        color
+            .bind_mut(state, 0)
+            .set_wrap(Coordinate::S, WrapMode::ClampToEdge)
+            .set_wrap(Coordinate::T, WrapMode::ClampToEdge);
+
+        Ok(Self {
+            framebuffer: FrameBuffer::new(
+                state,
+                depth,
+                vec![Attachment {
+                    kind: AttachmentKind::Color,
+                    texture: Rc::new(RefCell::new(color)),
+                }],
+            )?,
+            width,
+            height,
+        })
+    }
+
+    fn texture(&self) -> Rc<RefCell<GpuTexture>> {
+        self.framebuffer.color_attachments()[0].texture.clone()
+    }
+
+    fn viewport(&self) -> Rect<i32> {
+        Rect::new(0, 0, self.width as i32, self.height as i32)
+    }
+}
+
+// Re-creates render target if its size does not match requested.
+fn ensure_render_target(
+    target: &mut Option<RenderTarget>,
+    state: &mut PipelineState,
+    width: usize,
+    height: usize,
+    with_depth: bool,
+) -> Result<(), FrameworkError> {
+    if target
+        .as_ref()
+        .map_or(true, |t| t.width != width || t.height != height)
+    {
+        *target = Some(RenderTarget::new(state, width, height, with_depth)?);
+    }
+    Ok(())
+}
+
+/// Returns a plane of the surface in `(normal, distance)` form, oriented so the given
+/// observer is on its positive side.
+fn surface_plane(water: &Water, observer_position: Vector3<f32>) -> Vector4<f32> {
+    let transform = water.global_transform();
+    let normal = transform
+        .up()
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(Vector3::y);
+    let distance = -normal.dot(&water.global_position());
+    let plane = Vector4::new(normal.x, normal.y, normal.z, distance);
+    if normal.dot(&observer_position) + distance < 0.0 {
+        -plane
+    } else {
+        plane
+    }
+}
+
+/// Creates a matrix that mirrors points relative to the given plane.
+fn reflection_matrix(plane: &Vector4<f32>) -> Matrix4<f32> {
+    let (a, b, c, d) = (plane.x, plane.y, plane.z, plane.w);
+    Matrix4::new(
+        1.0 - 2.0 * a * a,
+        -2.0 * a * b,
+        -2.0 * a * c,
+        -2.0 * a * d,
+        -2.0 * b * a,
+        1.0 - 2.0 * b * b,
+        -2.0 * b * c,
+        -2.0 * b * d,
+        -2.0 * c * a,
+        -2.0 * c * b,
+        1.0 - 2.0 * c * c,
+        -2.0 * c * d,
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    )
+}
+
+/// Modifies the projection matrix so its near plane matches the given view-space clip plane.
+/// Points on the positive side of the plane are kept. See "Oblique View Frustum Depth Projection
+/// and Clipping" by Eric Lengyel for details.
+fn oblique_projection(projection: &Matrix4<f32>, clip_plane: &Vector4<f32>) -> Matrix4<f32> {
+    let inv_projection = match projection.try_inverse() {
+        Some(inv_projection) => inv_projection,
+        None => return *projection,
+    };
+
+    // Corner of the view frustum opposite to the plane, in view space.
+    let q = inv_projection * Vector4::new(clip_plane.x.signum(), clip_plane.y.signum(), 1.0, 1.0);
+
+    let denominator = clip_plane.dot(&q);
+    if denominator.abs() <= f32::EPSILON {
+        return *projection;
+    }
+
+    let c = clip_plane.scale(2.0 * projection.row(3).transpose().dot(&q) / denominator);
+    let mut oblique = *projection;
+    let row = c - projection.row(3).transpose();
+    oblique.set_row(2, &row.transpose());
+    oblique
+}
+
+pub(in crate) struct WaterRenderer {
+    shader: WaterShader,
+    skybox_shader: SkyboxShader,
+    quad: GeometryBuffer,
+    skybox: GeometryBuffer,
+    reflection: Option<RenderTarget>,
+    refraction: Option<RenderTarget>,
+    reflection_visibility: VisibilityCache,
+    render_pass_name: ImmutableString,
+}
+
+pub(in crate) struct WaterRenderContext<'a, 'b> {
+    pub state: &'a mut PipelineState,
+    pub camera: &'b Camera,
+    pub graph: &'b Graph,
+    pub framebuffer: &'a mut FrameBuffer,
+    pub depth: Rc<RefCell<GpuTexture>>,
+    pub viewport: Rect<i32>,
+    pub frame_size: Vector2<f32>,
+    pub geom_cache: &'a mut GeometryCache,
+    pub texture_cache: &'a mut TextureCache,
+    pub shader_cache: &'a mut ShaderCache,
+    pub batch_storage: &'a BatchStorage,
+    pub quality_settings: &'a QualitySettings,
+    pub white_dummy: Rc<RefCell<GpuTexture>>,
+    pub normal_dummy: Rc<RefCell<GpuTexture>>,
+    pub black_dummy: Rc<RefCell<GpuTexture>>,
+}
+
+impl WaterRenderer {
+    pub(in crate) fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        Ok(Self {
+            shader: WaterShader::new(state)?,
+            skybox_shader: SkyboxShader::new(state)?,
+            quad: GeometryBuffer::from_surface_data(
+                &SurfaceData::make_quad(&Matrix4::new_rotation(Vector3::new(
+                    std::f32::consts::FRAC_PI_2,
+                    0.0,
+                    0.0,
+                ))),
+                GeometryBufferKind::StaticDraw,
+                state,
+            ),
+            skybox: GeometryBuffer::from_surface_data(
+                &SurfaceData::make_cube(Matrix4::identity()),
+                GeometryBufferKind::StaticDraw,
+                state,
+            ),
+            reflection: None,
+            refraction: None,
+            reflection_visibility: Default::default(),
+            render_pass_name: ImmutableString::new("Forward"),
+        })
+    }
+
+    // Renders the scene mirrored relative to the plane of the surface, returns view-projection
+    // matrix that was used for rendering.
+    fn render_reflection(
+        &mut self,
+        args: &mut WaterRenderContext,
+        water: &Water,
+        statistics: &mut RenderPassStatistics,
+    ) -> Result<Matrix4<f32>, FrameworkError> {
+        let WaterRenderContext {
+            state,
+            camera,
+            graph,
+            geom_cache,
+            texture_cache,
+            shader_cache,
+            batch_storage,
+            quality_settings,
+            white_dummy,
+            normal_dummy,
+            black_dummy,
+            ..
+        } = args;
+
+        let size = quality_settings.water_reflection_map_size.max(1);
+        ensure_render_target(&mut self.reflection, state, size, size, true)?;
+        let target = self.reflection.as_mut().unwrap();
+        let viewport = target.viewport();
+
+        let camera_position = camera.global_position();
+        let plane = surface_plane(water, camera_position);
+        let reflection = reflection_matrix(&plane);
+        let view = camera.view_matrix() * reflection;
+        let reflected_camera_position = reflection.transform_point(&camera_position.into()).coords;
+
+        let clip_plane = view.try_inverse().unwrap_or_default().transpose() * plane;
+        let projection = oblique_projection(&camera.projection_matrix(), &clip_plane);
+        let view_projection = projection * view;
+
+        // Oblique projection distorts far plane, so culling and skybox use regular projection.
+        let regular_view_projection = camera.projection_matrix() * view;
+        let frustum = Frustum::from(regular_view_projection).unwrap_or_default();
+        self.reflection_visibility.update(
+            graph,
+            reflected_camera_position,
+            camera.z_near(),
+            camera.z_far(),
+            camera.visibility_mask() & water.reflection_mask(),
+            Some(&[&frustum]),
+        );
+
+        target
+            .framebuffer
+            .clear(state, viewport, Some(Color::BLACK), Some(1.0), Some(0));
+
+        if let Some(skybox) = camera.skybox_ref() {
+            if let Some(gpu_texture) = skybox
+                .cubemap()
+                .and_then(|cubemap| texture_cache.get(state, &cubemap))
+            {
+                let size = camera.z_far() / 2.0f32.sqrt();
+                let wvp = regular_view_projection
+                    * Matrix4::new_translation(&reflected_camera_position)
+                    * Matrix4::new_scaling(size);
+                let shader = &self.skybox_shader;
+                *statistics += target.framebuffer.draw(
+                    &self.skybox,
+                    state,
+                    viewport,
+                    &shader.program,
+                    &DrawParameters {
+                        cull_face: None,
+                        color_write: Default::default(),
+                        depth_write: false,
+                        stencil_test: None,
+                        depth_test: false,
+                        blend: None,
+                        stencil_op: Default::default(),
+                    },
+                    |mut program_binding| {
+                        program_binding
+                            .set_texture(&shader.cubemap_texture, &gpu_texture)
+                            .set_matrix4(&shader.wvp_matrix, &wvp);
+                    },
+                );
+            }
+        }
+
+        for batch in batch_storage.batches.iter() {
+            let material = batch.material.lock();
+            let geometry = geom_cache.get(state, &batch.data);
+
+            let render_pass = match shader_cache
+                .get(state, material.shader())
+                .and_then(|shader_set| shader_set.render_passes.get(&self.render_pass_name))
+            {
+                Some(render_pass) => render_pass,
+                None => continue,
+            };
+
+            // Mirroring flips winding order of triangles, so culling is disabled.
+            let draw_params = DrawParameters {
+                cull_face: None,
+                depth_write: true,
+                depth_test: true,
+                blend: None,
+                ..render_pass.draw_params.clone()
+            };
+
+            for instance in batch.instances.iter() {
+                if instance.is_transparent || !self.reflection_visibility.is_visible(instance.owner)
+                {
+                    continue;
+                }
+
+                let wvp = view_projection * instance.world_transform;
+
+                *statistics += target.framebuffer.draw(
+                    geometry,
+                    state,
+                    viewport,
+                    render_pass.program(instance.triplanar_mapping.is_some()),
+                    &draw_params,
+                    |mut program_binding| {
+                        apply_material(MaterialContext {
+                            material: &*material,
+                            program_binding: &mut program_binding,
+                            texture_cache,
+                            world_matrix: &instance.world_transform,
+                            wvp_matrix: &wvp,
+                            bone_matrices: &instance.bone_matrices,
+                            use_skeletal_animation: batch.is_skinned,
+                            camera_position: &reflected_camera_position,
+                            use_pom: false,
+                            opacity: instance.opacity,
+                            color: instance.color,
+                            light_position: &Default::default(),
+                            uv_transform: &instance.uv_transform,
+                            mip_bias: instance.mip_bias,
+                            receive_shadows: instance.receive_shadows,
+                            alpha_cutoff: instance.alpha_cutoff,
+                            triplanar_mapping: instance.triplanar_mapping,
+                            instanced_bones: None,
+                            normal_dummy: normal_dummy.clone(),
+                            white_dummy: white_dummy.clone(),
+                            black_dummy: black_dummy.clone(),
+                        });
+                    },
+                );
+            }
+        }
+
+        Ok(view_projection)
+    }
+
+    pub(in crate) fn render(
+        &mut self,
+        mut args: WaterRenderContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        scope_profile!();
+        profile_scope!("Water");
+
+        let mut statistics = RenderPassStatistics::default();
+
+        let graph = args.graph;
+        let camera = args.camera;
+
+        let surfaces = graph
+            .pair_iter()
+            .filter_map(|(handle, node)| {
+                if let Node::Water(water) = node {
+                    if camera.visibility_cache.is_visible(handle) {
+                        return Some(water);
+                    }
+                }
+                None
+            })
+            .collect::<Vec<_>>();
+
+        if surfaces.is_empty() {
+            return Ok(statistics);
+        }
+
+        // Copy lit scene, it will be used for refraction.
+        let width = args.frame_size.x as usize;
+        let height = args.frame_size.y as usize;
+        ensure_render_target(&mut self.refraction, args.state, width, height, false)?;
+        let refraction = self.refraction.as_ref().unwrap();
+        args.state.blit_framebuffer(
+            args.framebuffer.id(),
+            refraction.framebuffer.id(),
+            0,
+            0,
+            width as i32,
+            height as i32,
+            0,
+            0,
+            width as i32,
+            height as i32,
+            true,
+            false,
+            false,
+        );
+        let refraction_texture = refraction.texture();
+
+        let view_projection = camera.view_projection_matrix();
+        let inv_view_projection = view_projection.try_inverse().unwrap_or_default();
+        let inv_screen_size = Vector2::new(1.0 / args.frame_size.x, 1.0 / args.frame_size.y);
+        let camera_position = camera.global_position();
+
+        for water in surfaces {
+            let reflections_enabled =
+                args.quality_settings.water_reflections_enabled && water.reflections_enabled();
+
+            let (reflection_view_projection, reflection_texture) = if reflections_enabled {
+                let view_projection = self.render_reflection(&mut args, water, &mut statistics)?;
+                (view_projection, self.reflection.as_ref().unwrap().texture())
+            } else {
+                (Matrix4::identity(), args.black_dummy.clone())
+            };
+
+            let normal_texture = water
+                .normal_map()
+                .and_then(|texture| args.texture_cache.get(args.state, texture))
+                .unwrap_or_else(|| args.normal_dummy.clone());
+
+            let transform = water.global_transform();
+            let surface_normal = transform
+                .up()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::y);
+            let surface_tangent = transform
+                .side()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::x);
+            let surface_binormal = transform
+                .look()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::z);
+
+            let shader = &self.shader;
+            statistics += args.framebuffer.draw(
+                &self.quad,
+                args.state,
+                args.viewport,
+                &shader.program,
+                &DrawParameters {
+                    cull_face: None,
+                    color_write: Default::default(),
+                    depth_write: true,
+                    stencil_test: None,
+                    depth_test: true,
+                    blend: None,
+                    stencil_op: Default::default(),
+                },
+                |mut program_binding| {
+                    program_binding
+                        .set_matrix4(&shader.world_matrix, &water.surface_transform())
+                        .set_matrix4(&shader.view_projection_matrix, &view_projection)
+                        .set_texture(&shader.reflection_texture, &reflection_texture)
+                        .set_texture(&shader.refraction_texture, &refraction_texture)
+                        .set_texture(&shader.depth_texture, &args.depth)
+                        .set_texture(&shader.normal_texture, &normal_texture)
+                        .set_matrix4(
+                            &shader.reflection_view_projection,
+                            &reflection_view_projection,
+                        )
+                        .set_matrix4(&shader.inv_view_projection, &inv_view_projection)
+                        .set_vector2(&shader.inv_screen_size, &inv_screen_size)
+                        .set_vector3(&shader.camera_position, &camera_position)
+                        .set_vector3(&shader.surface_normal, &surface_normal)
+                        .set_vector3(&shader.surface_tangent, &surface_tangent)
+                        .set_vector3(&shader.surface_binormal, &surface_binormal)
+                        .set_f32(&shader.time, water.time())
+                        .set_f32(&shader.wave_scale, water.wave_scale())
+                        .set_vector2(&shader.wave_speed, &water.wave_speed())
+                        .set_f32(&shader.distortion, water.distortion())
+                        .set_linear_color(&shader.water_color, &water.color())
+                        .set_f32(&shader.depth_density, water.depth_density())
+                        .set_f32(&shader.fresnel_power, water.fresnel_power())
+                        .set_bool(&shader.reflections_enabled, reflections_enabled);
+                },
+            );
+        }
+
+        Ok(statistics)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Matrix4, Point3, Vector3, Vector4},
+        renderer::water::{oblique_projection, reflection_matrix},
+    };
+
+    #[test]
+    fn test_reflection_matrix() {
+        // Horizontal plane at y = 2.
+        let plane = Vector4::new(0.0, 1.0, 0.0, -2.0);
+        let reflection = reflection_matrix(&plane);
+
+        let point = reflection.transform_point(&Point3::new(1.0, 5.0, -3.0));
+        assert!((point.coords - Vector3::new(1.0, -1.0, -3.0)).norm() < 1.0e-5);
+
+        // Mirroring twice gives identity.
+        assert!((reflection * reflection - Matrix4::identity()).norm() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_oblique_projection_near_plane() {
+        let projection = Matrix4::new_perspective(1.0, 60.0f32.to_radians(), 0.1, 100.0);
+        // View-space plane `y = -1`, points above it are kept.
+        let clip_plane = Vector4::new(0.0, 1.0, 0.0, 1.0);
+        let oblique = oblique_projection(&projection, &clip_plane);
+
+        let depth = |p: Vector4<f32>| {
+            let clip = oblique * p;
+            clip.z / clip.w
+        };
+
+        // A point on the plane lies on the near plane, points below it are clipped.
+        assert!((depth(Vector4::new(0.0, -1.0, -10.0, 1.0)) + 1.0).abs() < 1.0e-3);
+        assert!(depth(Vector4::new(0.0, -2.0, -10.0, 1.0)) < -1.0);
+        assert!(depth(Vector4::new(0.0, 0.0, -10.0, 1.0)) > -1.0);
+    }
+}
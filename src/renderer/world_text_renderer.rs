@@ -0,0 +1,305 @@
+//! World text renderer draws [`WorldText`] nodes in the transparent pass, glyphs are taken from
+//! atlas pages of fonts of the user interface.
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Vector2},
+        color::Color,
+        math::{frustum::Frustum, Matrix4Ext, Rect, TriangleDefinition},
+        profile_scope, scope_profile,
+        sparse::SparseBuffer,
+        sstorage::ImmutableString,
+    },
+    engine::resource_manager::DEFAULT_RESOURCE_LIFETIME,
+    renderer::{
+        cache::CacheEntry,
+        framework::{
+            error::FrameworkError,
+            framebuffer::{DrawParameters, FrameBuffer},
+            geometry_buffer::{
+                AttributeDefinition, AttributeKind, BufferBuilder, ElementKind, GeometryBuffer,
+                GeometryBufferBuilder, GeometryBufferKind,
+            },
+            gpu_program::{GpuProgram, UniformLocation},
+            state::{BlendFactor, BlendFunc, PipelineState},
+        },
+        ui_renderer::font_page_texture,
+        RenderPassStatistics, TextureCache,
+    },
+    scene::{
+        camera::Camera,
+        graph::Graph,
+        node::Node,
+        world_text::{WorldText, WorldTextOrientation, WorldTextVertex},
+    },
+};
+
+struct WorldTextShader {
+    program: GpuProgram,
+    view_projection_matrix: UniformLocation,
+    world_matrix: UniformLocation,
+    camera_side_vector: UniformLocation,
+    camera_up_vector: UniformLocation,
+    billboard: UniformLocation,
+    offset: UniformLocation,
+    color: UniformLocation,
+    font_texture: UniformLocation,
+}
+
+impl WorldTextShader {
+    pub fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        let fragment_source = include_str!("shaders/world_text_fs.glsl");
+        let vertex_source = include_str!("shaders/world_text_vs.glsl");
+        let program =
+            GpuProgram::from_source(state, "WorldTextShader", vertex_source, fragment_source)?;
+        Ok(Self {
+            view_projection_matrix: program
+                .uniform_location(state, &ImmutableString::new("viewProjectionMatrix"))?,
+            world_matrix: program.uniform_location(state, &ImmutableString::new("worldMatrix"))?,
+            camera_side_vector: program
+                .uniform_location(state, &ImmutableString::new("cameraSideVector"))?,
+            camera_up_vector: program
+                .uniform_location(state, &ImmutableString::new("cameraUpVector"))?,
+            billboard: program.uniform_location(state, &ImmutableString::new("billboard"))?,
+            offset: program.uniform_location(state, &ImmutableString::new("offset"))?,
+            color: program.uniform_location(state, &ImmutableString::new("color"))?,
+            font_texture: program.uniform_location(state, &ImmutableString::new("fontTexture"))?,
+            program,
+        })
+    }
+}
+
+// Directions of outline passes, outline is made of copies of the text shifted in these directions.
+const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+    (-1.0, 0.0),
+    (1.0, 0.0),
+    (0.0, -1.0),
+    (0.0, 1.0),
+    (-0.707, -0.707),
+    (0.707, -0.707),
+    (-0.707, 0.707),
+    (0.707, 0.707),
+];
+
+pub(in crate) struct WorldTextRenderer {
+    shader: WorldTextShader,
+    // Every text node has its own buffer that is re-used while the text fits in it.
+    buffers: SparseBuffer<CacheEntry<GeometryBuffer>>,
+}
+
+pub(in crate) struct WorldTextRenderContext<'a, 'b, 'c> {
+    pub state: &'a mut PipelineState,
+    pub framebuffer: &'b mut FrameBuffer,
+    pub graph: &'c Graph,
+    pub camera: &'c Camera,
+    pub viewport: Rect<i32>,
+    pub texture_cache: &'a mut TextureCache,
+}
+
+fn make_buffer(
+    state: &mut PipelineState,
+    glyph_capacity: usize,
+) -> Result<GeometryBuffer, FrameworkError> {
+    let vertices = vec![WorldTextVertex::default(); glyph_capacity * 4];
+    let geometry_buffer = GeometryBufferBuilder::new(ElementKind::Triangle)
+        .with_buffer_builder(
+            BufferBuilder::new(GeometryBufferKind::DynamicDraw, Some(&vertices))
+                .with_attribute(AttributeDefinition {
+                    location: 0,
+                    kind: AttributeKind::Float2,
+                    normalized: false,
+                    divisor: 0,
+                })
+                .with_attribute(AttributeDefinition {
+                    location: 1,
+                    kind: AttributeKind::Float2,
+                    normalized: false,
+                    divisor: 0,
+                }),
+        )
+        .build(state)?;
+
+    let triangles = (0..glyph_capacity as u32)
+        .flat_map(|i| {
+            let k = i * 4;
+            [
+                TriangleDefinition([k, k + 1, k + 2]),
+                TriangleDefinition([k, k + 2, k + 3]),
+            ]
+        })
+        .collect::<Vec<_>>();
+    geometry_buffer.bind(state).set_triangles(&triangles);
+
+    Ok(geometry_buffer)
+}
+
+/// Returns GPU buffer of the text, the buffer is re-created only if the text does not fit in it.
+fn text_buffer<'a>(
+    buffers: &'a mut SparseBuffer<CacheEntry<GeometryBuffer>>,
+    state: &mut PipelineState,
+    text: &WorldText,
+) -> Result<&'a GeometryBuffer, FrameworkError> {
+    let geometry = text.geometry();
+    let glyph_count = geometry.glyph_count();
+
+    let fits = buffers
+        .get(&text.cache_entry)
+        .map_or(false, |entry| entry.element_count() >= glyph_count * 2);
+
+    if !fits {
+        // Reserve some space to not re-create the buffer when text grows by a few glyphs.
+        let buffer = make_buffer(state, glyph_count.next_power_of_two().max(8))?;
+        if buffers.is_index_valid(&text.cache_entry) {
+            buffers.free(&text.cache_entry);
+        }
+        let index = buffers.spawn(CacheEntry {
+            value: buffer,
+            value_hash: 0,
+            time_to_live: DEFAULT_RESOURCE_LIFETIME,
+        });
+        text.cache_entry.set(index.get());
+    }
+
+    let entry = buffers.get_mut(&text.cache_entry).unwrap();
+    if !fits || entry.value_hash != geometry.hash {
+        entry.value_hash = geometry.hash;
+        entry.value.set_buffer_data(state, 0, &geometry.vertices);
+    }
+    entry.time_to_live = DEFAULT_RESOURCE_LIFETIME;
+
+    Ok(&entry.value)
+}
+
+impl WorldTextRenderer {
+    pub fn new(state: &mut PipelineState) -> Result<Self, FrameworkError> {
+        Ok(Self {
+            shader: WorldTextShader::new(state)?,
+            buffers: Default::default(),
+        })
+    }
+
+    pub(in crate) fn update(&mut self, dt: f32) {
+        for entry in self.buffers.iter_mut() {
+            entry.time_to_live -= dt;
+        }
+
+        for i in 0..self.buffers.len() {
+            if let Some(entry) = self.buffers.get_raw(i) {
+                if entry.time_to_live <= 0.0 {
+                    self.buffers.free_raw(i);
+                }
+            }
+        }
+    }
+
+    pub(in crate) fn clear(&mut self) {
+        self.buffers.clear();
+    }
+
+    pub(in crate) fn render(
+        &mut self,
+        args: WorldTextRenderContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        scope_profile!();
+        profile_scope!("WorldText");
+
+        let mut statistics = RenderPassStatistics::default();
+
+        let WorldTextRenderContext {
+            state,
+            framebuffer,
+            graph,
+            camera,
+            viewport,
+            texture_cache,
+        } = args;
+
+        let view_projection = camera.view_projection_matrix();
+        let frustum = Frustum::from(view_projection).unwrap_or_default();
+
+        let inv_view = camera.inv_view_matrix().unwrap_or_else(Matrix4::identity);
+        let camera_up = inv_view.up();
+        let camera_side = inv_view.side();
+
+        for node in graph.linear_iter() {
+            let text = if let Node::WorldText(text) = node {
+                text
+            } else {
+                continue;
+            };
+
+            if !text.global_visibility()
+                || !text.is_visible_by_mask(camera.visibility_mask())
+                || text.geometry().glyph_count() == 0
+                || !frustum.is_intersects_aabb(&text.world_bounding_box())
+            {
+                continue;
+            }
+
+            let billboard = text.orientation() == WorldTextOrientation::Billboard;
+            let world_matrix = text.global_transform();
+            let params = DrawParameters {
+                cull_face: None,
+                color_write: Default::default(),
+                depth_write: false,
+                stencil_test: None,
+                depth_test: !text.is_always_on_top(),
+                blend: Some(BlendFunc {
+                    sfactor: BlendFactor::SrcAlpha,
+                    dfactor: BlendFactor::OneMinusSrcAlpha,
+                }),
+                stencil_op: Default::default(),
+            };
+
+            // Outline is drawn first, so the text will be on top of it.
+            let mut passes = Vec::with_capacity(OUTLINE_DIRECTIONS.len() + 1);
+            if text.outline_thickness() > 0.0 {
+                for (x, y) in OUTLINE_DIRECTIONS {
+                    passes.push((
+                        Vector2::new(x, y).scale(text.outline_thickness()),
+                        text.outline_color(),
+                    ));
+                }
+            }
+            passes.push((Vector2::default(), text.color()));
+
+            let font = text.font();
+            let mut font = font.0.lock().unwrap();
+
+            let geometry_buffer = text_buffer(&mut self.buffers, state, text)?;
+
+            for (page_index, range) in text.geometry().pages.iter() {
+                let texture = match font_page_texture(state, texture_cache, &mut font, *page_index)
+                {
+                    Some(texture) => texture,
+                    None => continue,
+                };
+
+                for (offset, color) in passes.iter() {
+                    statistics += framebuffer.draw_part(
+                        geometry_buffer,
+                        state,
+                        viewport,
+                        &self.shader.program,
+                        params.clone(),
+                        range.start * 2,
+                        range.len() * 2,
+                        |mut program_binding| {
+                            program_binding
+                                .set_texture(&self.shader.font_texture, &texture)
+                                .set_matrix4(&self.shader.view_projection_matrix, &view_projection)
+                                .set_matrix4(&self.shader.world_matrix, &world_matrix)
+                                .set_vector3(&self.shader.camera_up_vector, &camera_up)
+                                .set_vector3(&self.shader.camera_side_vector, &camera_side)
+                                .set_bool(&self.shader.billboard, billboard)
+                                .set_vector2(&self.shader.offset, offset)
+                                .set_linear_color(&self.shader.color, color);
+                        },
+                    )?;
+                }
+            }
+        }
+
+        Ok(statistics)
+    }
+}
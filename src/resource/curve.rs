@@ -3,6 +3,7 @@
 use crate::{
     asset::{define_new_resource, Resource, ResourceData},
     core::{curve::Curve, io::FileLoadError, visitor::prelude::*},
+    resource::io::ResourceSource,
 };
 use std::{
     borrow::Cow,
@@ -54,7 +55,14 @@ impl ResourceData for CurveResourceState {
 impl CurveResourceState {
     /// Load a curve resource from the specific file path.
     pub async fn from_file(path: &Path) -> Result<Self, CurveResourceError> {
-        let mut visitor = Visitor::load_binary(path).await?;
+        Self::from_source(path, &Default::default()).await
+    }
+
+    pub(in crate) async fn from_source(
+        path: &Path,
+        source: &ResourceSource,
+    ) -> Result<Self, CurveResourceError> {
+        let mut visitor = Visitor::load_from_memory(source.load(path).await?)?;
         let mut curve = Curve::default();
         curve.visit("Curve", &mut visitor)?;
         Ok(Self {
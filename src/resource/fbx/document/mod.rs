@@ -3,13 +3,11 @@ pub mod attribute;
 mod binary;
 
 use crate::core::algebra::Vector3;
-use crate::core::io;
 use crate::{
     core::pool::{Handle, Pool},
     resource::fbx::{document::attribute::FbxAttribute, error::FbxError},
 };
 use std::io::Cursor;
-use std::path::Path;
 
 pub struct FbxNode {
     name: String,
@@ -120,9 +118,7 @@ fn is_binary(data: &[u8]) -> bool {
 }
 
 impl FbxDocument {
-    pub async fn new<P: AsRef<Path>>(path: P) -> Result<FbxDocument, FbxError> {
-        let data = io::load_file(path).await?;
-
+    pub fn from_bytes(data: Vec<u8>) -> Result<FbxDocument, FbxError> {
         let is_bin = is_binary(&data);
 
         let mut reader = Cursor::new(data);
@@ -11,33 +11,38 @@ pub mod error;
 mod scene;
 
 use crate::{
-    animation::{Animation, AnimationContainer, KeyFrame, Track},
+    animation::{Animation, AnimationContainer, KeyFrame, MorphKeyFrame, MorphTrack, Track},
     core::{
         algebra::{Matrix4, Point3, UnitQuaternion, Vector2, Vector3, Vector4},
+        color::Color,
+        conventions::check_import_scale,
         instant::Instant,
-        io,
         math::{self, triangulator::triangulate, RotationOrder},
         parking_lot::Mutex,
         pool::Handle,
         sstorage::ImmutableString,
     },
-    engine::resource_manager::{MaterialSearchOptions, ResourceManager},
+    engine::resource_manager::{
+        MaterialSearchOptions, ModelImportOptions, ResourceManager, TangentGeneration,
+    },
     material::{shader::SamplerFallback, PropertyValue},
     resource::fbx::{
         document::FbxDocument,
         error::FbxError,
         scene::{
-            animation::FbxAnimationCurveNodeType, geometry::FbxGeometry, model::FbxModel,
-            FbxComponent, FbxMapping, FbxScene,
+            animation::{FbxAnimationCurveNode, FbxAnimationCurveNodeType},
+            geometry::FbxGeometry,
+            model::FbxModel,
+            FbxComponent, FbxGlobalSettings, FbxMapping, FbxScene,
         },
     },
     scene::{
         base::BaseBuilder,
         graph::Graph,
         mesh::{
-            buffer::{VertexAttributeUsage, VertexWriteTrait},
+            buffer::{VertexAttributeUsage, VertexReadTrait, VertexWriteTrait},
             surface::{Surface, SurfaceData, VertexWeightSet},
-            vertex::{AnimatedVertex, StaticVertex},
+            vertex::{AnimatedVertex, ColoredVertex, StaticVertex},
             MeshBuilder,
         },
         node::Node,
@@ -45,7 +50,7 @@ use crate::{
         Scene,
     },
     utils::{
-        log::{Log, MessageKind},
+        log::{log_error, log_info, log_warn},
         raw_mesh::RawMeshBuilder,
     },
 };
@@ -141,6 +146,7 @@ struct UnpackedVertex {
     normal: Vector3<f32>,
     tangent: Vector3<f32>,
     uv: Vector2<f32>,
+    color: Color,
     // Set of weights for skinning.
     weights: Option<VertexWeightSet>,
 }
@@ -171,6 +177,24 @@ impl Into<StaticVertex> for UnpackedVertex {
     }
 }
 
+impl Into<ColoredVertex<StaticVertex>> for UnpackedVertex {
+    fn into(self) -> ColoredVertex<StaticVertex> {
+        ColoredVertex {
+            color: [self.color.r, self.color.g, self.color.b, self.color.a],
+            vertex: self.into(),
+        }
+    }
+}
+
+impl Into<ColoredVertex<AnimatedVertex>> for UnpackedVertex {
+    fn into(self) -> ColoredVertex<AnimatedVertex> {
+        ColoredVertex {
+            color: [self.color.r, self.color.g, self.color.b, self.color.a],
+            vertex: self.into(),
+        }
+    }
+}
+
 fn convert_vertex(
     geom: &FbxGeometry,
     geometric_transform: &Matrix4<f32>,
@@ -196,6 +220,11 @@ fn convert_vertex(
         None => Vector2::default(),
     };
 
+    let color = match geom.colors.as_ref() {
+        Some(colors) => *colors.get(index, index_in_polygon)?,
+        None => Color::WHITE,
+    };
+
     let material = match geom.materials.as_ref() {
         Some(materials) => *materials.get(material_index, index_in_polygon)?,
         None => 0,
@@ -208,6 +237,7 @@ fn convert_vertex(
         normal: geometric_transform.transform_vector(&normal),
         tangent: geometric_transform.transform_vector(&tangent),
         uv: Vector2::new(uv.x, 1.0 - uv.y), // Invert Y because OpenGL has origin at left *bottom* corner.
+        color,
         surface: material as usize,
         weights: if geom.deformers.is_empty() {
             None
@@ -221,6 +251,8 @@ fn convert_vertex(
 enum FbxMeshBuilder {
     Static(RawMeshBuilder<StaticVertex>),
     Animated(RawMeshBuilder<AnimatedVertex>),
+    ColoredStatic(RawMeshBuilder<ColoredVertex<StaticVertex>>),
+    ColoredAnimated(RawMeshBuilder<ColoredVertex<AnimatedVertex>>),
 }
 
 impl FbxMeshBuilder {
@@ -232,6 +264,16 @@ impl FbxMeshBuilder {
             FbxMeshBuilder::Animated(builder) => {
                 SurfaceData::from_raw_mesh(builder.build(), AnimatedVertex::layout(), false)
             }
+            FbxMeshBuilder::ColoredStatic(builder) => SurfaceData::from_raw_mesh(
+                builder.build(),
+                &ColoredVertex::<StaticVertex>::layout(StaticVertex::layout()),
+                false,
+            ),
+            FbxMeshBuilder::ColoredAnimated(builder) => SurfaceData::from_raw_mesh(
+                builder.build(),
+                &ColoredVertex::<AnimatedVertex>::layout(AnimatedVertex::layout()),
+                false,
+            ),
         }
     }
 }
@@ -240,6 +282,8 @@ impl FbxMeshBuilder {
 struct FbxSurfaceData {
     builder: FbxMeshBuilder,
     skin_data: Vec<VertexWeightSet>,
+    // Index of control point of each unique vertex, it is used to build morph targets.
+    control_points: Vec<usize>,
 }
 
 async fn create_surfaces(
@@ -251,6 +295,9 @@ async fn create_surfaces(
     material_search_options: &MaterialSearchOptions,
 ) -> Result<Vec<Surface>, FbxError> {
     let mut surfaces = Vec::new();
+    // Textures could be stored in a pack, so their existence is checked using the same data
+    // source as for the model.
+    let source = resource_manager.state().resource_source();
 
     // Create surfaces per material
     if model.materials.is_empty() {
@@ -265,16 +312,14 @@ async fn create_surfaces(
             let mut surface = Surface::new(Arc::new(Mutex::new(data.builder.build())));
             surface.vertex_weights = data.skin_data;
             let material = fbx_scene.get(material_handle).as_material()?;
+            surface.set_two_sided(material.two_sided);
             if let Err(e) = surface.material().lock().set_property(
                 &ImmutableString::new("diffuseColor"),
                 PropertyValue::Color(material.diffuse_color),
             ) {
-                Log::writeln(
-                    MessageKind::Error,
-                    format!(
-                        "Failed to set diffuseColor property for material. Reason: {:?}",
-                        e,
-                    ),
+                log_error!(
+                    "Failed to set diffuseColor property for material. Reason: {:?}",
+                    e
                 )
             }
             for (name, texture_handle) in material.textures.iter() {
@@ -290,7 +335,7 @@ async fn create_surfaces(
                             let mut path = model_path.to_owned();
                             while let Some(parent) = path.parent() {
                                 let candidate = parent.join(filename);
-                                if io::exists(&candidate).await {
+                                if source.exists(&candidate).await {
                                     texture_path = Some(candidate);
                                     break;
                                 }
@@ -303,7 +348,7 @@ async fn create_surfaces(
                             for dir in WalkDir::new(".").into_iter().flatten() {
                                 if dir.path().is_dir() {
                                     let candidate = dir.path().join(filename);
-                                    if candidate.exists() {
+                                    if source.exists(&candidate).await {
                                         texture_path = Some(candidate);
                                         break;
                                     }
@@ -355,23 +400,20 @@ async fn create_surfaces(
                                     fallback: usage,
                                 },
                             ) {
-                                Log::writeln(
-                                    MessageKind::Error,
-                                    format!(
-                                        "Unable to set material property {}\
+                                log_error!(
+                                    "Unable to set material property {}\
                                  for FBX material! Reason: {:?}",
-                                        property_name, e
-                                    ),
+                                    property_name,
+                                    e
                                 );
                             }
                         }
                     } else {
-                        Log::writeln(
-                            MessageKind::Warning,
-                            format!(
-                                "Unable to find a texture {:?} for 3D model {:?} using {:?} option!",
-                                filename, model_path, material_search_options
-                            ),
+                        log_warn!(
+                            "Unable to find a texture {:?} for 3D model {:?} using {:?} option!",
+                            filename,
+                            model_path,
+                            material_search_options
                         );
                     }
                 }
@@ -391,6 +433,7 @@ async fn convert_mesh(
     graph: &mut Graph,
     model_path: &Path,
     material_search_options: &MaterialSearchOptions,
+    import_options: &ModelImportOptions,
 ) -> Result<Handle<Node>, FbxError> {
     let geometric_transform = Matrix4::new_translation(&model.geometric_translation)
         * quat_from_euler(model.geometric_rotation).to_homogeneous()
@@ -404,18 +447,32 @@ async fn convert_mesh(
     let mut face_triangles = Vec::new();
 
     let mut mesh_surfaces = Vec::new();
+    let mut morph_weights = Vec::new();
     for &geom_handle in &model.geoms {
         let geom = fbx_scene.get(geom_handle).as_geometry()?;
         let skin_data = geom.get_skin_data(fbx_scene)?;
 
+        let channels = geom.blend_shape_channels(fbx_scene)?;
+        if morph_weights.is_empty() {
+            morph_weights = channels
+                .iter()
+                .map(|channel| channel.deform_percent / 100.0)
+                .collect();
+        }
+
         let mut data_set = vec![
             FbxSurfaceData {
-                builder: if geom.deformers.is_empty() {
-                    FbxMeshBuilder::Static(RawMeshBuilder::new(1024, 1024))
-                } else {
-                    FbxMeshBuilder::Animated(RawMeshBuilder::new(1024, 1024))
+                // Vertex colors are stored in a separate stream only if the geometry has them.
+                builder: match (geom.deformers.is_empty(), geom.colors.is_some()) {
+                    (true, false) => FbxMeshBuilder::Static(RawMeshBuilder::new(1024, 1024)),
+                    (false, false) => FbxMeshBuilder::Animated(RawMeshBuilder::new(1024, 1024)),
+                    (true, true) => FbxMeshBuilder::ColoredStatic(RawMeshBuilder::new(1024, 1024)),
+                    (false, true) => {
+                        FbxMeshBuilder::ColoredAnimated(RawMeshBuilder::new(1024, 1024))
+                    }
                 },
                 skin_data: Default::default(),
+                control_points: Default::default(),
             };
             model.materials.len().max(1)
         ];
@@ -447,11 +504,18 @@ async fn convert_mesh(
                     let is_unique_vertex = match data.builder {
                         FbxMeshBuilder::Static(ref mut builder) => builder.insert(vertex.into()),
                         FbxMeshBuilder::Animated(ref mut builder) => builder.insert(vertex.into()),
+                        FbxMeshBuilder::ColoredStatic(ref mut builder) => {
+                            builder.insert(vertex.into())
+                        }
+                        FbxMeshBuilder::ColoredAnimated(ref mut builder) => {
+                            builder.insert(vertex.into())
+                        }
                     };
                     if is_unique_vertex {
                         if let Some(skin_data) = weights {
                             data.skin_data.push(skin_data);
                         }
+                        data.control_points.push(index);
                     }
                 }
             }
@@ -462,6 +526,11 @@ async fn convert_mesh(
             }
         }
 
+        let control_points = data_set
+            .iter_mut()
+            .map(|data| std::mem::take(&mut data.control_points))
+            .collect::<Vec<_>>();
+
         let mut surfaces = create_surfaces(
             fbx_scene,
             data_set,
@@ -472,9 +541,44 @@ async fn convert_mesh(
         )
         .await?;
 
-        if geom.tangents.is_none() {
-            for surface in surfaces.iter_mut() {
-                surface.data().lock().calculate_tangents().unwrap();
+        for (surface, control_points) in surfaces.iter_mut().zip(control_points.iter()) {
+            let data = surface.data();
+            let mut data = data.lock();
+            if !channels.is_empty() {
+                data.morph_targets =
+                    geom.make_morph_targets(fbx_scene, control_points, &geometric_transform)?;
+            }
+            let generate_tangents = match import_options.tangent_generation() {
+                TangentGeneration::Never => false,
+                TangentGeneration::IfMissing => {
+                    if geom.tangents.is_none() {
+                        true
+                    } else if !data.has_valid_tangents() {
+                        log_warn!(
+                            "Mesh {} in {} has invalid tangents, they will be re-generated.",
+                            model.name,
+                            model_path.display()
+                        );
+                        true
+                    } else {
+                        false
+                    }
+                }
+                TangentGeneration::Always => true,
+            };
+            if generate_tangents {
+                if let Err(e) = data.calculate_tangents() {
+                    log_error!(
+                        "Unable to generate tangents for mesh {} in {}, tangents are skipped. \
+                        Reason: {:?}",
+                        model.name,
+                        model_path.display(),
+                        e
+                    );
+                }
+            }
+            if import_options.is_mirroring() {
+                compensate_mirroring(&mut data);
             }
         }
 
@@ -485,9 +589,101 @@ async fn convert_mesh(
 
     Ok(MeshBuilder::new(base)
         .with_surfaces(mesh_surfaces)
+        .with_morph_weights(morph_weights)
         .build(graph))
 }
 
+/// Mirrored transform of the root node flips winding order of triangles and handedness of tangent
+/// space, this function compensates both.
+fn compensate_mirroring(data: &mut SurfaceData) {
+    for triangle in data.geometry_buffer.modify().iter_mut() {
+        triangle.0.swap(1, 2);
+    }
+    for mut view in data.vertex_buffer.modify().iter_mut() {
+        if let Ok(tangent) = view.read_4_f32(VertexAttributeUsage::Tangent) {
+            view.write_4_f32(
+                VertexAttributeUsage::Tangent,
+                Vector4::new(tangent.x, tangent.y, tangent.z, -tangent.w),
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Writes a warning if metadata of a file disagrees with import options, it is a common source of
+/// wrongly scaled or rotated models.
+fn check_global_settings(
+    settings: &FbxGlobalSettings,
+    import_options: &ModelImportOptions,
+    model_path: &Path,
+) {
+    if let Some(unit) = settings.length_unit() {
+        if let Err(mismatch) = check_import_scale(unit, import_options.scale()) {
+            log_warn!(
+                "Units of {} disagree with import options: {}. Set scale of model import options \
+                to {} to import the model in meters.",
+                model_path.display(),
+                mismatch,
+                mismatch.expected_scale
+            );
+        }
+    }
+    if let Some(up_axis) = settings.up_axis {
+        if up_axis != import_options.up_axis() {
+            log_warn!(
+                "Up axis of {} is {:?}, but up axis of model import options is {:?}.",
+                model_path.display(),
+                up_axis,
+                import_options.up_axis()
+            );
+        }
+    }
+}
+
+/// Collects times of every key of given curve nodes.
+fn collect_key_times(fbx_scene: &FbxScene, curve_nodes: &[&FbxAnimationCurveNode]) -> Vec<f32> {
+    let mut times = vec![0.0];
+    let mut time = 0.0;
+    loop {
+        let mut next_time = f32::MAX;
+        for node in curve_nodes.iter() {
+            for &curve_handle in node.curves.iter() {
+                let curve_component = fbx_scene.get(curve_handle);
+                if let FbxComponent::AnimationCurve(curve) = curve_component {
+                    for key in curve.keys.iter() {
+                        if key.time > time {
+                            let distance = key.time - time;
+                            if distance < next_time - key.time {
+                                next_time = key.time;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if next_time >= f32::MAX {
+            break;
+        }
+
+        time = next_time;
+        times.push(time);
+    }
+    times
+}
+
+/// Replaces given key times with uniformly distributed times with given rate (keys per second),
+/// first and last key times are preserved.
+fn resample_key_times(times: Vec<f32>, rate: f32) -> Vec<f32> {
+    let end = times.last().cloned().unwrap_or_default();
+    if rate <= 0.0 || end <= 0.0 {
+        return times;
+    }
+    let step = 1.0 / rate;
+    let count = (end / step).ceil() as usize;
+    (0..=count).map(|i| (i as f32 * step).min(end)).collect()
+}
+
 fn convert_model_to_base(model: &FbxModel) -> BaseBuilder {
     BaseBuilder::new()
         .with_inv_bind_pose_transform(model.inv_bind_transform)
@@ -516,6 +712,7 @@ async fn convert_model(
     animation_handle: Handle<Animation>,
     model_path: &Path,
     material_search_options: &MaterialSearchOptions,
+    import_options: &ModelImportOptions,
 ) -> Result<Handle<Node>, FbxError> {
     let base = convert_model_to_base(model);
 
@@ -529,6 +726,7 @@ async fn convert_model(
             graph,
             model_path,
             material_search_options,
+            import_options,
         )
         .await?
     } else if model.light.is_some() {
@@ -562,8 +760,17 @@ async fn convert_model(
 
         let node_local_rotation = quat_from_euler(model.rotation);
 
-        let mut time = 0.0;
-        loop {
+        let curve_nodes = [lcl_translation, lcl_rotation, lcl_scale]
+            .iter()
+            .flatten()
+            .copied()
+            .collect::<Vec<_>>();
+        let mut key_times = collect_key_times(fbx_scene, &curve_nodes);
+        if let Some(rate) = import_options.animation_resampling_rate() {
+            key_times = resample_key_times(key_times, rate);
+        }
+
+        for time in key_times {
             let translation = lcl_translation
                 .map(|curve| curve.eval_vec3(fbx_scene, time))
                 .unwrap_or(model.translation);
@@ -577,32 +784,36 @@ async fn convert_model(
                 .unwrap_or(model.scale);
 
             track.add_key_frame(KeyFrame::new(time, translation, scale, rotation));
+        }
 
-            let mut next_time = f32::MAX;
-            for node in [lcl_translation, lcl_rotation, lcl_scale].iter().flatten() {
-                for &curve_handle in node.curves.iter() {
-                    let curve_component = fbx_scene.get(curve_handle);
-                    if let FbxComponent::AnimationCurve(curve) = curve_component {
-                        for key in curve.keys.iter() {
-                            if key.time > time {
-                                let distance = key.time - time;
-                                if distance < next_time - key.time {
-                                    next_time = key.time;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+        animations.get_mut(animation_handle).add_track(track);
+    }
 
-            if next_time >= f32::MAX {
-                break;
+    // Convert animations of blend shape weights, weights are stored in percents.
+    if let Some(&geom_handle) = model.geoms.first() {
+        let geom = fbx_scene.get(geom_handle).as_geometry()?;
+        for (target, channel) in geom.blend_shape_channels(fbx_scene)?.iter().enumerate() {
+            if channel.deform_percent_curve_node.is_none() {
+                continue;
             }
+            if let FbxComponent::AnimationCurveNode(curve_node) =
+                fbx_scene.get(channel.deform_percent_curve_node)
+            {
+                let mut key_times = collect_key_times(fbx_scene, &[curve_node]);
+                if let Some(rate) = import_options.animation_resampling_rate() {
+                    key_times = resample_key_times(key_times, rate);
+                }
 
-            time = next_time;
+                let mut track = MorphTrack::new(node_handle, target);
+                for time in key_times {
+                    track.add_key_frame(MorphKeyFrame::new(
+                        time,
+                        curve_node.eval_f32(fbx_scene, time) / 100.0,
+                    ));
+                }
+                animations.get_mut(animation_handle).add_morph_track(track);
+            }
         }
-
-        animations.get_mut(animation_handle).add_track(track);
     }
 
     Ok(node_handle)
@@ -617,6 +828,7 @@ async fn convert(
     scene: &mut Scene,
     model_path: &Path,
     material_search_options: &MaterialSearchOptions,
+    import_options: &ModelImportOptions,
 ) -> Result<(), FbxError> {
     let root = scene.graph.get_root();
     let animation_handle = scene.animations.add(Animation::default());
@@ -632,6 +844,7 @@ async fn convert(
                 animation_handle,
                 model_path,
                 material_search_options,
+                import_options,
             )
             .await?;
             scene.graph.link_nodes(node, root);
@@ -648,6 +861,14 @@ async fn convert(
             }
         }
     }
+    // Bake coordinate system conversion into the root node. This must be done before skinning
+    // data is built, so bone matrices will remain consistent.
+    let (rotation, scale) = import_options.conversion_transform();
+    scene.graph[root]
+        .local_transform_mut()
+        .set_rotation(rotation)
+        .set_scale(scale);
+
     scene.graph.update_hierarchical_data();
 
     // Remap handles from fbx model to handles of instantiated nodes
@@ -709,22 +930,23 @@ pub async fn load_to_scene<P: AsRef<Path>>(
     resource_manager: ResourceManager,
     path: P,
     material_search_options: &MaterialSearchOptions,
+    import_options: &ModelImportOptions,
 ) -> Result<(), FbxError> {
     let start_time = Instant::now();
 
-    Log::writeln(
-        MessageKind::Information,
-        format!("Trying to load {:?}", path.as_ref()),
-    );
+    log_info!("Trying to load {:?}", path.as_ref());
 
     let now = Instant::now();
-    let fbx = FbxDocument::new(path.as_ref()).await?;
+    let source = resource_manager.state().resource_source();
+    let fbx = FbxDocument::from_bytes(source.load(path.as_ref()).await?)?;
     let parsing_time = now.elapsed().as_millis();
 
     let now = Instant::now();
     let fbx_scene = FbxScene::new(&fbx)?;
     let dom_prepare_time = now.elapsed().as_millis();
 
+    check_global_settings(fbx_scene.global_settings(), import_options, path.as_ref());
+
     let now = Instant::now();
     convert(
         &fbx_scene,
@@ -732,13 +954,13 @@ pub async fn load_to_scene<P: AsRef<Path>>(
         scene,
         path.as_ref(),
         material_search_options,
+        import_options,
     )
     .await?;
     let conversion_time = now.elapsed().as_millis();
 
-    Log::writeln(MessageKind::Information,
-                 format!("FBX {:?} loaded in {} ms\n\t- Parsing - {} ms\n\t- DOM Prepare - {} ms\n\t- Conversion - {} ms",
-                         path.as_ref(), start_time.elapsed().as_millis(), parsing_time, dom_prepare_time, conversion_time));
+    log_info!("FBX {:?} loaded in {} ms\n\t- Parsing - {} ms\n\t- DOM Prepare - {} ms\n\t- Conversion - {} ms",
+                         path.as_ref(), start_time.elapsed().as_millis(), parsing_time, dom_prepare_time, conversion_time);
 
     // Check for multiple nodes with same name and throw a warning if any.
     // It seems that FBX was designed using ass, not brains. It has no unique **persistent**
@@ -747,15 +969,12 @@ pub async fn load_to_scene<P: AsRef<Path>>(
     let mut hash_set = FxHashSet::<String>::default();
     for node in scene.graph.linear_iter() {
         if hash_set.contains(node.name()) {
-            Log::writeln(
-                MessageKind::Error,
-                format!(
-                    "A node with existing name {} was found during the load of {} resource! \
+            log_error!(
+                "A node with existing name {} was found during the load of {} resource! \
                     Do **NOT IGNORE** this message, please fix names in your model, otherwise \
                     engine won't be able to correctly restore data from your resource!",
-                    node.name(),
-                    path.as_ref().display()
-                ),
+                node.name(),
+                path.as_ref().display()
             );
         } else {
             hash_set.insert(node.name_owned());
@@ -764,3 +983,22 @@ pub async fn load_to_scene<P: AsRef<Path>>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use crate::resource::fbx::resample_key_times;
+
+    #[test]
+    fn test_resample_key_times() {
+        // 240 keys per second.
+        let times = (0..=240).map(|i| i as f32 / 240.0).collect::<Vec<_>>();
+
+        let resampled = resample_key_times(times.clone(), 30.0);
+        assert_eq!(resampled.len(), 31);
+        assert_eq!(resampled.first().cloned(), Some(0.0));
+        assert_eq!(resampled.last().cloned(), times.last().cloned());
+
+        // Invalid rate must keep keys as is.
+        assert_eq!(resample_key_times(times.clone(), 0.0), times);
+    }
+}
@@ -1,5 +1,4 @@
 use crate::core::algebra::{UnitQuaternion, Vector3};
-use crate::utils::log::MessageKind;
 use crate::{
     core::pool::Handle,
     resource::fbx::{
@@ -7,7 +6,7 @@ use crate::{
         quat_from_euler,
         scene::{FbxComponent, FbxScene, FBX_TIME_UNIT},
     },
-    utils::log::Log,
+    utils::log::log_warn,
 };
 
 pub struct FbxTimeValuePair {
@@ -50,10 +49,7 @@ impl FbxAnimationCurve {
 
     fn eval(&self, time: f32) -> f32 {
         if self.keys.is_empty() {
-            Log::writeln(
-                MessageKind::Warning,
-                "FBX: Trying to evaluate curve with no keys!".to_owned(),
-            );
+            log_warn!("FBX: Trying to evaluate curve with no keys!");
 
             return 0.0;
         }
@@ -140,6 +136,15 @@ impl FbxAnimationCurveNode {
         }
     }
 
+    /// Evaluates first curve of the node, it is used for scalar properties like weights of blend
+    /// shapes.
+    pub fn eval_f32(&self, scene: &FbxScene, time: f32) -> f32 {
+        match self.curves.first().map(|&curve| scene.get(curve)) {
+            Some(FbxComponent::AnimationCurve(curve)) => curve.eval(time),
+            _ => 0.0,
+        }
+    }
+
     pub fn eval_quat(&self, scene: &FbxScene, time: f32) -> UnitQuaternion<f32> {
         quat_from_euler(self.eval_vec3(scene, time))
     }
@@ -1,15 +1,15 @@
-use crate::core::algebra::{Vector2, Vector3};
+use crate::core::algebra::{Matrix4, Vector2, Vector3};
 use crate::{
-    core::pool::Handle,
+    core::{color::Color, pool::Handle},
     resource::{
         fbx::scene,
         fbx::{
             document::{FbxNode, FbxNodeContainer},
             error::FbxError,
-            scene::{FbxComponent, FbxContainer, FbxScene},
+            scene::{FbxBlendShapeChannel, FbxComponent, FbxContainer, FbxScene},
         },
     },
-    scene::mesh::surface::{VertexWeight, VertexWeightSet},
+    scene::mesh::surface::{MorphTarget, VertexWeight, VertexWeightSet},
 };
 
 pub struct FbxGeometry {
@@ -23,8 +23,49 @@ pub struct FbxGeometry {
     pub materials: Option<FbxContainer<i32>>,
     pub tangents: Option<FbxContainer<Vector3<f32>>>,
     pub binormals: Option<FbxContainer<Vector3<f32>>>,
+    pub colors: Option<FbxContainer<Color>>,
 
     pub deformers: Vec<Handle<FbxComponent>>,
+    pub blend_shapes: Vec<Handle<FbxComponent>>,
+}
+
+/// Shape is a sparse set of offsets of control points of a geometry.
+pub struct FbxShape {
+    pub indices: Vec<i32>,
+    pub vertices: Vec<Vector3<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+}
+
+impl FbxShape {
+    pub(in crate::resource::fbx) fn read(
+        shape_node_handle: Handle<FbxNode>,
+        nodes: &FbxNodeContainer,
+    ) -> Result<FbxShape, FbxError> {
+        let indices_node_handle = nodes.find(shape_node_handle, "Indexes")?;
+        let indices_array_node = nodes.get_by_name(indices_node_handle, "a")?;
+        let mut indices = Vec::with_capacity(indices_array_node.attrib_count());
+        for index in indices_array_node.attributes() {
+            indices.push(index.as_i32()?);
+        }
+
+        let mut normals = Vec::new();
+        if let Ok(normals_node_handle) = nodes.find(shape_node_handle, "Normals") {
+            let normals_array_node = nodes.get_by_name(normals_node_handle, "a")?;
+            for normal in normals_array_node.attributes().chunks_exact(3) {
+                normals.push(Vector3::new(
+                    normal[0].as_f32()?,
+                    normal[1].as_f32()?,
+                    normal[2].as_f32()?,
+                ));
+            }
+        }
+
+        Ok(FbxShape {
+            indices,
+            vertices: read_vertices(shape_node_handle, nodes)?,
+            normals,
+        })
+    }
 }
 
 fn read_vertices(
@@ -125,6 +166,37 @@ fn read_uvs(
     }
 }
 
+fn read_colors(
+    geom_node_handle: Handle<FbxNode>,
+    nodes: &FbxNodeContainer,
+) -> Result<Option<FbxContainer<Color>>, FbxError> {
+    if let Ok(layer_element_color) = nodes.find(geom_node_handle, "LayerElementColor") {
+        Ok(Some(FbxContainer::new(
+            nodes,
+            layer_element_color,
+            "Colors",
+            |attributes| {
+                let mut colors = Vec::with_capacity(attributes.len() / 4);
+                for color in attributes.chunks_exact(4) {
+                    let mut components = [0; 4];
+                    for (component, attribute) in components.iter_mut().zip(color) {
+                        *component = (attribute.as_f32()?.clamp(0.0, 1.0) * 255.0) as u8;
+                    }
+                    colors.push(Color::from_rgba(
+                        components[0],
+                        components[1],
+                        components[2],
+                        components[3],
+                    ));
+                }
+                Ok(colors)
+            },
+        )?))
+    } else {
+        Ok(None)
+    }
+}
+
 fn read_materials(
     geom_node_handle: Handle<FbxNode>,
     nodes: &FbxNodeContainer,
@@ -162,7 +234,9 @@ impl FbxGeometry {
             materials: read_materials(geom_node_handle, nodes)?,
             tangents: read_tangents(geom_node_handle, nodes)?,
             binormals: read_binormals(geom_node_handle, nodes)?,
+            colors: read_colors(geom_node_handle, nodes)?,
             deformers: Vec::new(),
+            blend_shapes: Vec::new(),
         })
     }
 
@@ -195,4 +269,62 @@ impl FbxGeometry {
         }
         Ok(out)
     }
+
+    pub(in crate::resource::fbx) fn blend_shape_channels<'a>(
+        &self,
+        scene: &'a FbxScene,
+    ) -> Result<Vec<&'a FbxBlendShapeChannel>, FbxError> {
+        let mut channels = Vec::new();
+        for &blend_shape_handle in self.blend_shapes.iter() {
+            for &channel_handle in scene
+                .get(blend_shape_handle)
+                .as_blend_shape()?
+                .channels
+                .iter()
+            {
+                channels.push(scene.get(channel_handle).as_blend_shape_channel()?);
+            }
+        }
+        Ok(channels)
+    }
+
+    /// Creates morph target per each blend shape channel. `control_points` maps vertices of a
+    /// surface to control points of the geometry.
+    pub(in crate::resource::fbx) fn make_morph_targets(
+        &self,
+        scene: &FbxScene,
+        control_points: &[usize],
+        transform: &Matrix4<f32>,
+    ) -> Result<Vec<MorphTarget>, FbxError> {
+        let mut targets = Vec::new();
+        for channel in self.blend_shape_channels(scene)? {
+            let mut position_deltas = vec![Vector3::default(); self.vertices.len()];
+            let mut normal_deltas = vec![Vector3::default(); self.vertices.len()];
+            if let Some(&shape_handle) = channel.shapes.first() {
+                let shape = scene.get(shape_handle).as_shape()?;
+                for (i, &index) in shape.indices.iter().enumerate() {
+                    *position_deltas
+                        .get_mut(index as usize)
+                        .ok_or(FbxError::IndexOutOfBounds)? =
+                        shape.vertices.get(i).cloned().unwrap_or_default();
+                    if let Some(normal) = shape.normals.get(i) {
+                        normal_deltas[index as usize] = *normal;
+                    }
+                }
+            }
+
+            targets.push(MorphTarget {
+                name: channel.name.clone(),
+                position_deltas: control_points
+                    .iter()
+                    .map(|&i| transform.transform_vector(&position_deltas[i]))
+                    .collect(),
+                normal_deltas: control_points
+                    .iter()
+                    .map(|&i| transform.transform_vector(&normal_deltas[i]))
+                    .collect(),
+            });
+        }
+        Ok(targets)
+    }
 }
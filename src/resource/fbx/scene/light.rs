@@ -10,7 +10,7 @@ use crate::{
         },
         node::Node,
     },
-    utils::log::{Log, MessageKind},
+    utils::log::log_warn,
 };
 
 pub enum FbxLightType {
@@ -70,13 +70,7 @@ impl FbxLight {
                         3 => FbxLightType::Area,
                         4 => FbxLightType::Volume,
                         _ => {
-                            Log::writeln(
-                                MessageKind::Warning,
-                                format!(
-                                    "FBX: Unknown light type {}, fallback to Point!",
-                                    type_code
-                                ),
-                            );
+                            log_warn!("FBX: Unknown light type {}, fallback to Point!", type_code);
                             FbxLightType::Point
                         }
                     };
@@ -2,6 +2,7 @@ use crate::core::color::Color;
 use crate::{
     core::{
         algebra::{Matrix4, Vector3},
+        conventions::{LengthUnit, UpAxis},
         pool::{Handle, Pool, PoolPairIterator},
     },
     resource::fbx::{
@@ -10,7 +11,7 @@ use crate::{
         fix_index,
         scene::{
             animation::{FbxAnimationCurve, FbxAnimationCurveNode},
-            geometry::FbxGeometry,
+            geometry::{FbxGeometry, FbxShape},
             light::FbxLight,
             model::FbxModel,
             texture::FbxTexture,
@@ -27,6 +28,51 @@ pub mod texture;
 
 pub struct FbxScene {
     components: Pool<FbxComponent>,
+    global_settings: FbxGlobalSettings,
+}
+
+/// Metadata of a file, every value is `None` if it is missing in the file.
+#[derive(Default)]
+pub struct FbxGlobalSettings {
+    /// Size of a unit of the file in centimeters.
+    pub unit_scale_factor: Option<f32>,
+    pub up_axis: Option<UpAxis>,
+}
+
+impl FbxGlobalSettings {
+    fn read(document: &FbxDocument) -> Result<Self, FbxError> {
+        let nodes = document.nodes();
+        let mut settings = Self::default();
+        let global_settings_handle = match nodes.find(document.root(), "GlobalSettings") {
+            Ok(handle) => handle,
+            // Settings are optional.
+            Err(_) => return Ok(settings),
+        };
+        let properties70_node = nodes.get_by_name(global_settings_handle, "Properties70")?;
+        for property_handle in properties70_node.children() {
+            let property_node = nodes.get(*property_handle);
+            match property_node.get_attrib(0)?.as_string().as_str() {
+                "UnitScaleFactor" => {
+                    settings.unit_scale_factor = Some(property_node.get_attrib(4)?.as_f32()?)
+                }
+                "UpAxis" => {
+                    settings.up_axis = match property_node.get_attrib(4)?.as_i32()? {
+                        1 => Some(UpAxis::Y),
+                        2 => Some(UpAxis::Z),
+                        _ => None,
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(settings)
+    }
+
+    /// Returns unit of length of the file.
+    pub fn length_unit(&self) -> Option<LengthUnit> {
+        self.unit_scale_factor
+            .map(|centimeters| LengthUnit::Custom(centimeters * 0.01))
+    }
 }
 
 impl FbxScene {
@@ -54,9 +100,16 @@ impl FbxScene {
             let mut component_handle: Handle<FbxComponent> = Handle::NONE;
             match object.name() {
                 "Geometry" => {
-                    component_handle = components.spawn(FbxComponent::Geometry(Box::new(
-                        FbxGeometry::read(*object_handle, nodes)?,
-                    )));
+                    // Shapes are alternative vertex positions of blend shapes, they're stored as
+                    // geometries without polygons.
+                    if object.attrib_count() > 2 && object.get_attrib(2)?.as_string() == "Shape" {
+                        component_handle = components
+                            .spawn(FbxComponent::Shape(FbxShape::read(*object_handle, nodes)?));
+                    } else {
+                        component_handle = components.spawn(FbxComponent::Geometry(Box::new(
+                            FbxGeometry::read(*object_handle, nodes)?,
+                        )));
+                    }
                 }
                 "Model" => {
                     component_handle = components.spawn(FbxComponent::Model(Box::new(
@@ -102,6 +155,17 @@ impl FbxScene {
                             FbxDeformer::read(*object_handle, nodes),
                         ));
                     }
+                    "BlendShape" => {
+                        component_handle =
+                            components.spawn(FbxComponent::BlendShape(FbxBlendShape {
+                                channels: Default::default(),
+                            }));
+                    }
+                    "BlendShapeChannel" => {
+                        component_handle = components.spawn(FbxComponent::BlendShapeChannel(
+                            FbxBlendShapeChannel::read(*object_handle, nodes)?,
+                        ));
+                    }
                     _ => (),
                 },
                 _ => (),
@@ -130,7 +194,14 @@ impl FbxScene {
             }
         }
 
-        Ok(Self { components })
+        Ok(Self {
+            components,
+            global_settings: FbxGlobalSettings::read(document)?,
+        })
+    }
+
+    pub fn global_settings(&self) -> &FbxGlobalSettings {
+        &self.global_settings
     }
 
     pub fn pair_iter(&self) -> PoolPairIterator<FbxComponent> {
@@ -177,11 +248,25 @@ fn link_child_with_parent_component(
             }
         }
         // Link geometry with deformers
-        FbxComponent::Geometry(geometry) => {
-            if let FbxComponent::Deformer(_) = child {
-                geometry.deformers.push(child_handle);
+        FbxComponent::Geometry(geometry) => match child {
+            FbxComponent::Deformer(_) => geometry.deformers.push(child_handle),
+            FbxComponent::BlendShape(_) => geometry.blend_shapes.push(child_handle),
+            _ => (),
+        },
+        // Link blend shape with its channels
+        FbxComponent::BlendShape(blend_shape) => {
+            if let FbxComponent::BlendShapeChannel(_) = child {
+                blend_shape.channels.push(child_handle);
             }
         }
+        // Link blend shape channel with shapes and animation of its weight
+        FbxComponent::BlendShapeChannel(channel) => match child {
+            FbxComponent::Shape(_) => channel.shapes.push(child_handle),
+            FbxComponent::AnimationCurveNode(_) if property == "DeformPercent" => {
+                channel.deform_percent_curve_node = child_handle
+            }
+            _ => (),
+        },
         // Link sub-deformer with model
         FbxComponent::SubDeformer(sub_deformer) => {
             if let FbxComponent::Model(model) = child {
@@ -197,6 +282,9 @@ fn link_child_with_parent_component(
 pub enum FbxComponent {
     Deformer(FbxDeformer),
     SubDeformer(FbxSubDeformer),
+    BlendShape(FbxBlendShape),
+    BlendShapeChannel(FbxBlendShapeChannel),
+    Shape(FbxShape),
     Texture(FbxTexture),
     Light(FbxLight),
     Model(Box<FbxModel>),
@@ -221,6 +309,14 @@ macro_rules! define_as {
 impl FbxComponent {
     define_as!(self, as_deformer, FbxDeformer, Deformer);
     define_as!(self, as_sub_deformer, FbxSubDeformer, SubDeformer);
+    define_as!(self, as_blend_shape, FbxBlendShape, BlendShape);
+    define_as!(
+        self,
+        as_blend_shape_channel,
+        FbxBlendShapeChannel,
+        BlendShapeChannel
+    );
+    define_as!(self, as_shape, FbxShape, Shape);
     define_as!(self, as_texture, FbxTexture, Texture);
     define_as!(self, as_light, FbxLight, Light);
     define_as!(self, as_material, FbxMaterial, Material);
@@ -298,6 +394,7 @@ impl FbxSubDeformer {
 pub struct FbxMaterial {
     pub textures: Vec<(String, Handle<FbxComponent>)>,
     pub diffuse_color: Color,
+    pub two_sided: bool,
 }
 
 impl FbxMaterial {
@@ -306,21 +403,31 @@ impl FbxMaterial {
         nodes: &FbxNodeContainer,
     ) -> Result<FbxMaterial, FbxError> {
         let mut diffuse_color = Color::WHITE;
+        let mut two_sided = false;
 
         let props = nodes.get_by_name(material_node_handle, "Properties70")?;
         for prop_handle in props.children() {
             let prop = nodes.get(*prop_handle);
-            if let "DiffuseColor" = prop.get_attrib(0)?.as_string().as_str() {
+            let name = prop.get_attrib(0)?.as_string();
+            if name == "DiffuseColor" {
                 let r = (prop.get_attrib(4)?.as_f64()? * 255.0) as u8;
                 let g = (prop.get_attrib(5)?.as_f64()? * 255.0) as u8;
                 let b = (prop.get_attrib(6)?.as_f64()? * 255.0) as u8;
                 diffuse_color = Color::from_rgba(r, g, b, 255);
+            } else {
+                // There is no standard property for two-sided materials, but some exporters write
+                // custom ones, like `3dsMax|Parameters|two_sided` or `DoubleSided`.
+                let name = name.to_lowercase().replace('_', "");
+                if name.ends_with("twosided") || name.ends_with("doublesided") {
+                    two_sided = prop.get_attrib(4)?.as_f64()? != 0.0;
+                }
             }
         }
 
         Ok(FbxMaterial {
             textures: Default::default(),
             diffuse_color,
+            two_sided,
         })
     }
 }
@@ -337,6 +444,47 @@ impl FbxDeformer {
     }
 }
 
+pub struct FbxBlendShape {
+    pub channels: Vec<Handle<FbxComponent>>,
+}
+
+/// Blend shape channel is a morph target, it holds shapes and weight of the target.
+pub struct FbxBlendShapeChannel {
+    pub name: String,
+    /// Weight in percents.
+    pub deform_percent: f32,
+    /// In-between shapes are not supported, only first shape is used.
+    pub shapes: Vec<Handle<FbxComponent>>,
+    pub deform_percent_curve_node: Handle<FbxComponent>,
+}
+
+impl FbxBlendShapeChannel {
+    fn read(channel_handle: Handle<FbxNode>, nodes: &FbxNodeContainer) -> Result<Self, String> {
+        let channel_node = nodes.get(channel_handle);
+
+        let mut name = match channel_node.get_attrib(1) {
+            Ok(name_attrib) => name_attrib.as_string(),
+            Err(_) => String::from("Unnamed"),
+        };
+        // Remove prefix
+        if name.starts_with("SubDeformer::") {
+            name = name.chars().skip(13).collect();
+        }
+
+        let deform_percent = match nodes.get_by_name(channel_handle, "DeformPercent") {
+            Ok(deform_percent) => deform_percent.get_attrib(0)?.as_f32()?,
+            Err(_) => 0.0,
+        };
+
+        Ok(Self {
+            name,
+            deform_percent,
+            shapes: Default::default(),
+            deform_percent_curve_node: Default::default(),
+        })
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub enum FbxMapping {
     ByPolygon,
@@ -409,10 +557,12 @@ impl<T> FbxContainer<T> {
         // See: https://developer.blender.org/D402
         if data_name.as_ref() != "Materials" {
             if reference == FbxReference::IndexToDirect {
-                let index_node = nodes.find(
-                    container_node,
-                    format!("{}Index", data_name.as_ref()).as_str(),
-                )?;
+                // Index array of colors is called "ColorIndex", not "ColorsIndex".
+                let index_name = match data_name.as_ref() {
+                    "Colors" => "ColorIndex".to_owned(),
+                    name => format!("{}Index", name),
+                };
+                let index_node = nodes.find(container_node, index_name.as_str())?;
                 let index_array_node = nodes.get_by_name(index_node, "a")?;
                 for attribute in index_array_node.attributes() {
                     let idx = attribute.as_i32()?;
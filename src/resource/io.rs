@@ -0,0 +1,430 @@
+//! Sources of resource data.
+//!
+//! By default resource manager loads resources from the file system, but a game can be shipped
+//! with its data in a single pack file instead of a folder of loose files. To do that, implement
+//! [`ResourceIo`] (or use built-in [`PackResourceIo`]) and register it in the resource manager
+//! using [`crate::engine::resource_manager::ResourceManagerState::set_resource_io`]. Every loader
+//! (textures, models, including textures discovered from FBX materials, shaders, curves and sound
+//! buffers) will read data through it.
+//!
+//! Resource manager can fall back to the file system if a resource is not found in the registered
+//! source (this is default behavior, see
+//! [`crate::engine::resource_manager::ResourceManagerState::set_filesystem_fallback`]), this is
+//! useful during development - loose files are used as overrides for packed data.
+//!
+//! # Pack format
+//!
+//! Pack is a simple uncompressed archive: a header with the table of entries (path, offset and
+//! size of each file) followed by contents of the files. Use [`pack_directory`] to build a pack.
+//! Paths in a pack are stored as they are seen from working directory (for example
+//! `data/models/tree.fbx`), so build a pack from the same working directory the game runs in,
+//! using relative path to data directory.
+//!
+//! ```no_run
+//! use rg3d::{engine::resource_manager::ResourceManager, resource::io::PackResourceIo};
+//! use std::sync::Arc;
+//!
+//! fn use_pack(resource_manager: &ResourceManager) {
+//!     // Built once, for example by a build script:
+//!     // rg3d::resource::io::pack_directory("data", "data.pack").unwrap();
+//!     let pack = PackResourceIo::from_file("data.pack").unwrap();
+//!     resource_manager
+//!         .state()
+//!         .set_resource_io(Some(Arc::new(pack)));
+//!
+//!     // Loaded from the pack.
+//!     let _ = resource_manager.request_texture("data/textures/grass.png", None);
+//! }
+//! ```
+
+use crate::core::io::{self, FileLoadError};
+use fxhash::FxHashMap;
+use std::{
+    borrow::Cow,
+    fmt::{Debug, Formatter},
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+use walkdir::WalkDir;
+
+/// Information about a resource in a data source.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ResourceMetadata {
+    /// Size of the resource in bytes.
+    pub size: u64,
+}
+
+/// Source of resource data, see module docs.
+pub trait ResourceIo: Send + Sync {
+    /// Reads whole content of a resource at given path.
+    fn load(&self, path: &Path) -> Result<Vec<u8>, FileLoadError>;
+
+    /// Returns `true` if the source has a resource at given path.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Returns metadata of a resource at given path, or `None` if there is no such resource.
+    fn metadata(&self, path: &Path) -> Option<ResourceMetadata>;
+}
+
+/// File system data source.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default, Debug)]
+pub struct FsResourceIo;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ResourceIo for FsResourceIo {
+    fn load(&self, path: &Path) -> Result<Vec<u8>, FileLoadError> {
+        Ok(std::fs::read(path)?)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn metadata(&self, path: &Path) -> Option<ResourceMetadata> {
+        std::fs::metadata(path)
+            .ok()
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| ResourceMetadata {
+                size: metadata.len(),
+            })
+    }
+}
+
+/// An error that may occur during building or reading a pack.
+#[derive(Debug, thiserror::Error)]
+pub enum PackError {
+    /// An i/o error has occurred.
+    #[error("An i/o error has occurred {0:?}")]
+    Io(std::io::Error),
+
+    /// Data is not a pack or it is corrupted.
+    #[error("Invalid pack: {0}")]
+    InvalidFormat(String),
+}
+
+impl From<std::io::Error> for PackError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<walkdir::Error> for PackError {
+    fn from(e: walkdir::Error) -> Self {
+        Self::Io(e.into())
+    }
+}
+
+const PACK_MAGIC: &[u8; 8] = b"RG3DPACK";
+const PACK_VERSION: u32 = 1;
+
+// Makes paths like `./data/../data/a.png` and `data\a.png` equal to `data/a.png`.
+fn normalize_path(path: &Path) -> String {
+    let mut components: Vec<Cow<str>> = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if matches!(components.last(), Some(last) if last != "..") {
+                    components.pop();
+                } else {
+                    components.push(Cow::Borrowed(".."));
+                }
+            }
+            Component::RootDir => components.push(Cow::Borrowed("")),
+            Component::Prefix(prefix) => components.push(prefix.as_os_str().to_string_lossy()),
+            Component::Normal(name) => components.push(name.to_string_lossy()),
+        }
+    }
+    components.join("/").replace('\\', "/")
+}
+
+#[derive(Copy, Clone)]
+struct PackEntry {
+    offset: usize,
+    size: usize,
+}
+
+/// Data source that reads resources from a pack, see module docs. Whole pack is kept in memory,
+/// so it could be created from bytes embedded in the executable as well.
+pub struct PackResourceIo {
+    data: Cow<'static, [u8]>,
+    entries: FxHashMap<String, PackEntry>,
+}
+
+impl Debug for PackResourceIo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PackResourceIo")
+            .field("size", &self.data.len())
+            .field("entries", &self.entries.len())
+            .finish()
+    }
+}
+
+impl PackResourceIo {
+    /// Reads pack from a file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, PackError> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Creates pack data source from its content, could be used with `include_bytes!`.
+    pub fn from_bytes<D: Into<Cow<'static, [u8]>>>(data: D) -> Result<Self, PackError> {
+        let data = data.into();
+        let entries = Self::read_table(&data)?;
+        Ok(Self { data, entries })
+    }
+
+    fn read_table(data: &[u8]) -> Result<FxHashMap<String, PackEntry>, PackError> {
+        let mut reader = data;
+
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != PACK_MAGIC {
+            return Err(PackError::InvalidFormat("Invalid magic".to_owned()));
+        }
+
+        let version = read_u32(&mut reader)?;
+        if version != PACK_VERSION {
+            return Err(PackError::InvalidFormat(format!(
+                "Unsupported version {}",
+                version
+            )));
+        }
+
+        let count = read_u32(&mut reader)?;
+        let mut entries = FxHashMap::default();
+        for _ in 0..count {
+            let path_len = read_u32(&mut reader)? as usize;
+            let mut path = vec![0; path_len];
+            reader.read_exact(&mut path)?;
+            let path = String::from_utf8(path)
+                .map_err(|_| PackError::InvalidFormat("Path is not UTF-8".to_owned()))?;
+            let entry = PackEntry {
+                offset: read_u64(&mut reader)? as usize,
+                size: read_u64(&mut reader)? as usize,
+            };
+            if entry
+                .offset
+                .checked_add(entry.size)
+                .map_or(true, |end| end > data.len())
+            {
+                return Err(PackError::InvalidFormat(format!(
+                    "Entry {} is out of bounds",
+                    path
+                )));
+            }
+            entries.insert(path, entry);
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns paths of every resource in the pack.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|path| path.as_str())
+    }
+
+    fn entry(&self, path: &Path) -> Option<PackEntry> {
+        self.entries.get(&normalize_path(path)).cloned()
+    }
+}
+
+impl ResourceIo for PackResourceIo {
+    fn load(&self, path: &Path) -> Result<Vec<u8>, FileLoadError> {
+        match self.entry(path) {
+            Some(entry) => Ok(self.data[entry.offset..(entry.offset + entry.size)].to_vec()),
+            None => Err(FileLoadError::Custom(format!(
+                "There is no {} in the pack!",
+                path.display()
+            ))),
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.entry(path).is_some()
+    }
+
+    fn metadata(&self, path: &Path) -> Option<ResourceMetadata> {
+        self.entry(path).map(|entry| ResourceMetadata {
+            size: entry.size as u64,
+        })
+    }
+}
+
+fn read_u32(reader: &mut &[u8]) -> Result<u32, PackError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut &[u8]) -> Result<u64, PackError> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Packs every file of a directory (recursively) into a pack file at `output` path. Paths of
+/// the files are stored as they are seen from working directory, see module docs. Returns amount
+/// of packed files.
+pub fn pack_directory<P: AsRef<Path>, Q: AsRef<Path>>(
+    directory: P,
+    output: Q,
+) -> Result<usize, PackError> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(directory.as_ref()).sort_by_file_name() {
+        let entry = entry?;
+        // Do not pack the pack itself if it is written into the same directory.
+        if entry.file_type().is_file() && entry.path() != output.as_ref() {
+            files.push((entry.path().to_path_buf(), entry.metadata()?.len()));
+        }
+    }
+
+    let mut writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+    write_pack(&mut writer, &files)?;
+    Ok(files.len())
+}
+
+fn write_pack<W: Write>(writer: &mut W, files: &[(PathBuf, u64)]) -> Result<(), PackError> {
+    let paths = files
+        .iter()
+        .map(|(path, _)| normalize_path(path))
+        .collect::<Vec<_>>();
+
+    let table_size = paths.iter().map(|path| 4 + path.len() + 16).sum::<usize>();
+    let mut offset = (PACK_MAGIC.len() + 4 + 4 + table_size) as u64;
+
+    writer.write_all(PACK_MAGIC)?;
+    writer.write_all(&PACK_VERSION.to_le_bytes())?;
+    writer.write_all(&(files.len() as u32).to_le_bytes())?;
+    for (path, (_, size)) in paths.iter().zip(files.iter()) {
+        writer.write_all(&(path.len() as u32).to_le_bytes())?;
+        writer.write_all(path.as_bytes())?;
+        writer.write_all(&offset.to_le_bytes())?;
+        writer.write_all(&size.to_le_bytes())?;
+        offset += size;
+    }
+
+    for (path, size) in files.iter() {
+        let data = std::fs::read(path)?;
+        if data.len() as u64 != *size {
+            return Err(PackError::InvalidFormat(format!(
+                "{} was changed while packing",
+                path.display()
+            )));
+        }
+        writer.write_all(&data)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Data source used by resource loaders: registered [`ResourceIo`] with optional fallback to the
+/// file system.
+#[derive(Clone, Default)]
+pub(in crate) struct ResourceSource {
+    pub(in crate) io: Option<Arc<dyn ResourceIo>>,
+    pub(in crate) filesystem_fallback: bool,
+}
+
+impl ResourceSource {
+    /// Returns `true` if a resource at given path is provided by registered data source.
+    pub(in crate) fn is_custom(&self, path: &Path) -> bool {
+        self.io.as_ref().map_or(false, |io| io.exists(path))
+    }
+
+    fn use_filesystem(&self, path: &Path) -> bool {
+        self.io.is_none() || (self.filesystem_fallback && !self.is_custom(path))
+    }
+
+    pub(in crate) async fn load(&self, path: &Path) -> Result<Vec<u8>, FileLoadError> {
+        match self.io.as_ref() {
+            Some(io) if !self.use_filesystem(path) => io.load(path),
+            _ => io::load_file(path).await,
+        }
+    }
+
+    pub(in crate) async fn exists(&self, path: &Path) -> bool {
+        self.is_custom(path) || (self.use_filesystem(path) && io::exists(path).await)
+    }
+
+    /// Same as [`Self::exists`], but checks the file system synchronously. There is no file
+    /// system on WebAssembly, so only registered data source is checked there.
+    pub(in crate) fn exists_sync(&self, path: &Path) -> bool {
+        self.is_custom(path)
+            || (self.use_filesystem(path) && !cfg!(target_arch = "wasm32") && path.exists())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::futures::executor::block_on,
+        resource::io::{
+            normalize_path, pack_directory, PackResourceIo, ResourceIo, ResourceMetadata,
+            ResourceSource,
+        },
+    };
+    use std::{path::Path, sync::Arc};
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("./data/../data/a.png")),
+            "data/a.png"
+        );
+        assert_eq!(
+            normalize_path(Path::new("data/models/a.fbx")),
+            "data/models/a.fbx"
+        );
+        assert_eq!(normalize_path(Path::new("../a.png")), "../a.png");
+    }
+
+    #[test]
+    fn test_pack() {
+        let root = std::env::temp_dir().join("rg3d_pack_test");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("textures")).unwrap();
+        std::fs::write(root.join("a.txt"), b"first").unwrap();
+        std::fs::write(root.join("textures/b.txt"), b"second file").unwrap();
+
+        let pack_path = root.join("data.pack");
+        assert_eq!(pack_directory(&root, &pack_path).unwrap(), 2);
+
+        let pack = PackResourceIo::from_file(&pack_path).unwrap();
+        assert_eq!(pack.load(&root.join("a.txt")).unwrap(), b"first");
+        assert_eq!(
+            pack.load(&root.join("textures/../textures/b.txt")).unwrap(),
+            b"second file"
+        );
+        assert_eq!(
+            pack.metadata(&root.join("textures/b.txt")),
+            Some(ResourceMetadata { size: 11 })
+        );
+        assert!(!pack.exists(&root.join("c.txt")));
+        assert!(pack.load(&root.join("c.txt")).is_err());
+
+        // Mixed mode: overrides from the file system.
+        std::fs::write(root.join("c.txt"), b"override").unwrap();
+        let mut source = ResourceSource {
+            io: Some(Arc::new(pack)),
+            filesystem_fallback: true,
+        };
+        assert!(block_on(source.exists(&root.join("c.txt"))));
+        assert_eq!(
+            block_on(source.load(&root.join("c.txt"))).unwrap(),
+            b"override"
+        );
+        source.filesystem_fallback = false;
+        assert!(!block_on(source.exists(&root.join("c.txt"))));
+        assert_eq!(
+            block_on(source.load(&root.join("a.txt"))).unwrap(),
+            b"first"
+        );
+
+        assert!(PackResourceIo::from_bytes(&b"garbage"[..]).is_err());
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -18,22 +18,44 @@
 //! Currently only FBX (common format in game industry for storing complex 3d models)
 //! and RGS (native rusty-editor format) formats are supported.
 use crate::{
-    animation::Animation,
+    animation::{
+        retarget::{self, RetargetOptions},
+        Animation,
+    },
     asset::{define_new_resource, Resource, ResourceData},
     core::{
+        algebra::Matrix4,
+        parking_lot::Mutex,
         pool::Handle,
+        sstorage::ImmutableString,
         visitor::{Visit, VisitError, VisitResult, Visitor},
     },
-    engine::resource_manager::{MaterialSearchOptions, ResourceManager},
-    resource::fbx::{self, error::FbxError},
-    scene::{node::Node, Scene},
-    utils::log::{Log, MessageKind},
+    engine::resource_manager::{MaterialSearchOptions, ModelImportOptions, ResourceManager},
+    material::{shader::SamplerFallback, Material, PropertyValue},
+    resource::{
+        fbx::{self, error::FbxError},
+        texture::{Texture, TextureData, TextureState},
+    },
+    scene::{
+        base::BaseBuilder,
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        node::Node,
+        Scene,
+    },
 };
 use std::{
     borrow::Cow,
     path::{Path, PathBuf},
+    sync::Arc,
 };
 
+/// Name of the cube node of a placeholder model, see
+/// [`crate::engine::resource_manager::ResourceManagerState::set_model_placeholders_enabled`].
+pub const PLACEHOLDER_MODEL_NAME: &str = "MissingModel";
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 #[repr(u32)]
 pub(in crate) enum NodeMapping {
@@ -47,7 +69,13 @@ pub struct ModelData {
     pub(in crate) path: PathBuf,
     pub(in crate) mapping: NodeMapping,
     material_search_options: MaterialSearchOptions,
+    import_options: ModelImportOptions,
     scene: Scene,
+    is_placeholder: bool,
+    in_memory: bool,
+    // Data of in-memory model that is saved together with the model to be able to restore the
+    // model when a saved scene is loaded, see `ResourceManager::register_model_from_memory`.
+    embedded_surfaces: Vec<Arc<Mutex<SurfaceData>>>,
 }
 
 define_new_resource!(
@@ -91,6 +119,13 @@ impl Model {
             dest_scene.navmeshes.add(navmesh.clone());
         }
 
+        // Embed splines, their node control points are remapped to instantiated nodes.
+        for spline in data.scene.splines.iter() {
+            let mut spline = spline.clone();
+            spline.remap_handles(&old_to_new);
+            dest_scene.splines.add(spline);
+        }
+
         std::mem::drop(data);
 
         dest_scene.physics.embed_resource(
@@ -134,42 +169,47 @@ impl Model {
     ///
     /// Most of the 3d model formats can contain only one animation, so in most cases
     /// this function will return vector with only one animation.
+    ///
+    /// Bones are matched by exact names and key frames are copied as is, use
+    /// [`Self::retarget_animations_with`] to retarget animations onto a skeleton with different
+    /// names of bones or different proportions.
     pub fn retarget_animations(
         &self,
         root: Handle<Node>,
         dest_scene: &mut Scene,
+    ) -> Vec<Handle<Animation>> {
+        self.retarget_animations_with(root, dest_scene, &RetargetOptions::default())
+    }
+
+    /// Same as [`Self::retarget_animations`], but allows to map bones with different names and
+    /// to compensate difference in proportions of skeletons, see [`crate::animation::retarget`]
+    /// module docs for more info. Tracks of bones that have no match in the target hierarchy are
+    /// removed with a warning. Target hierarchy must be in its rest pose.
+    pub fn retarget_animations_with(
+        &self,
+        root: Handle<Node>,
+        dest_scene: &mut Scene,
+        options: &RetargetOptions,
     ) -> Vec<Handle<Animation>> {
         let data = self.data_ref();
         let mut animation_handles = Vec::new();
 
         for ref_anim in data.scene.animations.iter() {
-            let mut anim_copy = ref_anim.clone();
+            // Remap animation track nodes from resource to instance. This is required
+            // because we've made a plain copy and it has tracks with node handles mapped
+            // to nodes of internal scene.
+            let mut anim_copy = retarget::retarget_animation(
+                &data.scene.graph,
+                ref_anim,
+                &dest_scene.graph,
+                root,
+                options,
+            );
 
             // Keep reference to resource from which this animation was taken from. This will help
             // us to correctly reload keyframes for each track when we'll be loading a save file.
             anim_copy.resource = Some(self.clone());
 
-            // Remap animation track nodes from resource to instance. This is required
-            // because we've made a plain copy and it has tracks with node handles mapped
-            // to nodes of internal scene.
-            for (i, ref_track) in ref_anim.get_tracks().iter().enumerate() {
-                let ref_node = &data.scene.graph[ref_track.get_node()];
-                // Find instantiated node that corresponds to node in resource
-                let instance_node = dest_scene.graph.find_by_name(root, ref_node.name());
-                if instance_node.is_none() {
-                    Log::writeln(
-                        MessageKind::Error,
-                        format!(
-                            "Failed to retarget animation {:?} for node {}",
-                            data.path(),
-                            ref_node.name()
-                        ),
-                    );
-                }
-                // One-to-one track mapping so there is [i] indexing.
-                anim_copy.get_tracks_mut()[i].set_node(instance_node);
-            }
-
             animation_handles.push(dest_scene.animations.add(anim_copy));
         }
 
@@ -185,6 +225,10 @@ impl ResourceData for ModelData {
     fn set_path(&mut self, path: PathBuf) {
         self.path = path;
     }
+
+    fn is_placeholder(&self) -> bool {
+        self.is_placeholder
+    }
 }
 
 impl Default for ModelData {
@@ -193,7 +237,11 @@ impl Default for ModelData {
             path: PathBuf::new(),
             mapping: NodeMapping::UseNames,
             material_search_options: Default::default(),
+            import_options: Default::default(),
             scene: Scene::new(),
+            is_placeholder: false,
+            in_memory: false,
+            embedded_surfaces: Default::default(),
         }
     }
 }
@@ -206,6 +254,11 @@ impl Visit for ModelData {
         let _ = self
             .material_search_options
             .visit("MaterialSearchOptions", visitor);
+        let _ = self.import_options.visit("ImportOptions", visitor);
+        let _ = self.in_memory.visit("InMemory", visitor);
+        if self.in_memory {
+            let _ = self.embedded_surfaces.visit("EmbeddedSurfaces", visitor);
+        }
 
         visitor.leave_region()
     }
@@ -255,6 +308,7 @@ impl ModelData {
         path: P,
         resource_manager: ResourceManager,
         material_search_options: MaterialSearchOptions,
+        import_options: ModelImportOptions,
     ) -> Result<Self, ModelLoadError> {
         let extension = path
             .as_ref()
@@ -275,6 +329,7 @@ impl ModelData {
                     resource_manager,
                     path.as_ref(),
                     &material_search_options,
+                    &import_options,
                 )
                 .await?;
                 // Set NodeMapping::UseNames as mapping here because FBX does not have
@@ -301,9 +356,105 @@ impl ModelData {
             scene,
             mapping,
             material_search_options,
+            import_options,
+            is_placeholder: false,
+            in_memory: false,
+            embedded_surfaces: Default::default(),
         })
     }
 
+    /// Creates a model with a single mesh with given surfaces, see
+    /// [`ResourceManager::register_model_from_memory`]. Surfaces are embedded in saved scenes
+    /// only if `embed` is set.
+    pub(in crate) fn from_surfaces(
+        name: PathBuf,
+        surfaces: Vec<Arc<Mutex<SurfaceData>>>,
+        embed: bool,
+    ) -> Self {
+        let mut scene = Scene::new();
+
+        let mesh_name = name
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        MeshBuilder::new(BaseBuilder::new().with_name(&mesh_name))
+            .with_surfaces(
+                surfaces
+                    .iter()
+                    .map(|data| {
+                        if embed {
+                            data.lock().set_procedural(true);
+                        }
+                        SurfaceBuilder::new(data.clone()).build()
+                    })
+                    .collect(),
+            )
+            .build(&mut scene.graph);
+
+        let root = scene.graph.get_root();
+        scene.graph[root].set_name(mesh_name);
+
+        Self {
+            path: name,
+            // The model is built in the same way every time, so handles are persistent.
+            mapping: NodeMapping::UseHandles,
+            material_search_options: Default::default(),
+            import_options: Default::default(),
+            scene,
+            is_placeholder: false,
+            in_memory: true,
+            embedded_surfaces: if embed { surfaces } else { Default::default() },
+        }
+    }
+
+    /// Creates a model with a single unit cube with checkerboard texture, it is used instead of a
+    /// model that failed to load. Options are kept, so the model can be reloaded with the same
+    /// options when the file appears.
+    pub(in crate) fn placeholder(
+        path: PathBuf,
+        material_search_options: MaterialSearchOptions,
+        import_options: ModelImportOptions,
+    ) -> Self {
+        let mut scene = Scene::new();
+
+        let texture = Texture(Resource::new(TextureState::Ok(TextureData::placeholder(
+            "",
+        ))));
+        let mut material = Material::standard();
+        // Cannot fail, standard material has diffuse texture.
+        let _ = material.set_property(
+            &ImmutableString::new("diffuseTexture"),
+            PropertyValue::Sampler {
+                value: Some(texture),
+                fallback: SamplerFallback::White,
+            },
+        );
+
+        MeshBuilder::new(BaseBuilder::new().with_name(PLACEHOLDER_MODEL_NAME))
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_cube(Matrix4::identity()),
+            )))
+            .with_material(Arc::new(Mutex::new(material)))
+            .build()])
+            .build(&mut scene.graph);
+
+        let root = scene.graph.get_root();
+        if let Some(filename) = path.file_name() {
+            scene.graph[root].set_name(filename.to_string_lossy().to_string());
+        }
+
+        Self {
+            path,
+            mapping: NodeMapping::UseNames,
+            material_search_options,
+            import_options,
+            scene,
+            is_placeholder: true,
+            in_memory: false,
+            embedded_surfaces: Default::default(),
+        }
+    }
+
     /// Returns shared reference to internal scene, there is no way to obtain
     /// mutable reference to inner scene because resource is immutable source
     /// of data.
@@ -322,4 +473,19 @@ impl ModelData {
     pub fn material_search_options(&self) -> &MaterialSearchOptions {
         &self.material_search_options
     }
+
+    /// Returns import options that were used to load the model resource.
+    pub fn import_options(&self) -> &ModelImportOptions {
+        &self.import_options
+    }
+
+    /// Returns true if the model was created from memory by
+    /// [`ResourceManager::register_model_from_memory`]. Path of such model is its synthetic name.
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    pub(in crate) fn embedded_surfaces(&self) -> &[Arc<Mutex<SurfaceData>>] {
+        &self.embedded_surfaces
+    }
 }
@@ -24,9 +24,10 @@ use crate::{
     asset::{define_new_resource, Resource, ResourceData, ResourceState},
     core::{
         futures::io::Error,
-        io::{self, FileLoadError},
+        io::FileLoadError,
         visitor::{PodVecView, Visit, VisitError, VisitResult, Visitor},
     },
+    resource::io::ResourceSource,
 };
 use ddsfile::{Caps2, D3DFormat};
 use fxhash::FxHasher;
@@ -195,6 +196,9 @@ pub struct TextureData {
     serialize_content: bool,
     data_hash: u64,
     is_render_target: bool,
+    is_placeholder: bool,
+    never_stream: bool,
+    in_memory: bool,
 }
 
 impl ResourceData for TextureData {
@@ -205,6 +209,10 @@ impl ResourceData for TextureData {
     fn set_path(&mut self, path: PathBuf) {
         self.path = path;
     }
+
+    fn is_placeholder(&self) -> bool {
+        self.is_placeholder
+    }
 }
 
 impl Visit for TextureData {
@@ -229,6 +237,8 @@ impl Visit for TextureData {
         self.mip_count.visit("MipCount", visitor)?;
         self.kind.visit("Kind", visitor)?;
         let _ = self.serialize_content.visit("SerializeContent", visitor);
+        let _ = self.never_stream.visit("NeverStream", visitor);
+        let _ = self.in_memory.visit("InMemory", visitor);
 
         if self.serialize_content {
             let mut bytes_view = PodVecView::from_pod_vec(&mut self.bytes);
@@ -261,6 +271,9 @@ impl Default for TextureData {
             serialize_content: false,
             data_hash: 0,
             is_render_target: false,
+            is_placeholder: false,
+            never_stream: false,
+            in_memory: false,
         }
     }
 }
@@ -293,6 +306,9 @@ impl Texture {
             serialize_content: false,
             data_hash: 0,
             is_render_target: true,
+            is_placeholder: false,
+            never_stream: false,
+            in_memory: false,
         })))
     }
 
@@ -585,6 +601,9 @@ pub enum TextureError {
     /// An error occurred during file loading.
     #[error("A file load error has occurred {0:?}")]
     FileLoadError(FileLoadError),
+    /// A region is out of bounds of a texture or size of data does not match the region.
+    #[error("Invalid texture region!")]
+    InvalidRegion,
 }
 
 impl From<FileLoadError> for TextureError {
@@ -855,7 +874,7 @@ impl TextureData {
         Ok(texture)
     }
 
-    /// Tries to load a texture from a file.
+    /// Tries to load a texture from a file using given data source.
     ///
     /// # Notes
     ///
@@ -864,8 +883,9 @@ impl TextureData {
     pub(in crate) async fn load_from_file<P: AsRef<Path>>(
         path: P,
         compression: CompressionOptions,
+        source: &ResourceSource,
     ) -> Result<Self, TextureError> {
-        let data = io::load_file(path.as_ref()).await?;
+        let data = source.load(path.as_ref()).await?;
         let mut texture = Self::load_from_memory(&data, compression)?;
         texture.path = path.as_ref().to_path_buf();
         Ok(texture)
@@ -946,6 +966,71 @@ impl TextureData {
         }
     }
 
+    /// Creates new texture from a mip chain that was rendered by the engine, mip levels must be
+    /// stored one after another starting from the largest one, faces of cube maps are stored one
+    /// after another inside each level. The content is saved together with the texture and the
+    /// texture is never streamed.
+    pub(in crate) fn from_mip_chain(
+        kind: TextureKind,
+        pixel_kind: TexturePixelKind,
+        mip_count: u32,
+        bytes: Vec<u8>,
+    ) -> Self {
+        Self {
+            kind,
+            data_hash: data_hash(&bytes),
+            bytes: bytes.into(),
+            pixel_kind,
+            minification_filter: if mip_count > 1 {
+                TextureMinificationFilter::LinearMipMapLinear
+            } else {
+                TextureMinificationFilter::Linear
+            },
+            s_wrap_mode: TextureWrapMode::ClampToEdge,
+            t_wrap_mode: TextureWrapMode::ClampToEdge,
+            mip_count,
+            anisotropy: 1.0,
+            serialize_content: true,
+            never_stream: true,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a magenta-black checkerboard texture that is used instead of a texture that failed
+    /// to load. The texture keeps given path, so reloading of textures will replace the
+    /// placeholder with actual data when the file appears. See
+    /// [`crate::engine::resource_manager::ResourceManagerState::set_texture_placeholders_enabled`].
+    pub fn placeholder<P: AsRef<Path>>(path: P) -> Self {
+        const SIZE: u32 = 8;
+
+        let mut bytes = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                if (x + y) % 2 == 0 {
+                    bytes.extend_from_slice(&[255, 0, 255, 255]);
+                } else {
+                    bytes.extend_from_slice(&[0, 0, 0, 255]);
+                }
+            }
+        }
+
+        Self {
+            path: path.as_ref().to_owned(),
+            kind: TextureKind::Rectangle {
+                width: SIZE,
+                height: SIZE,
+            },
+            data_hash: data_hash(&bytes),
+            bytes: bytes.into(),
+            pixel_kind: TexturePixelKind::RGBA8,
+            // Keep cells sharp, so the placeholder is clearly visible.
+            minification_filter: TextureMinificationFilter::Nearest,
+            magnification_filter: TextureMagnificationFilter::Nearest,
+            is_placeholder: true,
+            ..Default::default()
+        }
+    }
+
     /// Sets new minification filter. It is used when texture becomes smaller.
     pub fn set_minification_filter(&mut self, filter: TextureMinificationFilter) {
         self.minification_filter = filter;
@@ -1022,6 +1107,18 @@ impl TextureData {
         self.serialize_content
     }
 
+    /// Returns true if the texture was created from memory by
+    /// [`crate::engine::resource_manager::ResourceManager::register_texture_from_memory`]. Path of
+    /// such texture is its synthetic name.
+    pub fn is_in_memory(&self) -> bool {
+        self.in_memory
+    }
+
+    pub(in crate) fn set_in_memory(&mut self, in_memory: bool, serialize_content: bool) {
+        self.in_memory = in_memory;
+        self.serialize_content = serialize_content;
+    }
+
     /// Returns true if the texture is used as render target.
     pub fn is_render_target(&self) -> bool {
         self.is_render_target
@@ -1040,6 +1137,18 @@ impl TextureData {
         self.anisotropy
     }
 
+    /// Forbids renderer to drop top mip levels of the texture when texture memory budget is
+    /// exceeded (see [`crate::renderer::Renderer::set_texture_memory_budget`]). Should be used
+    /// for UI and other textures that must always be sharp.
+    pub fn set_never_stream(&mut self, never_stream: bool) {
+        self.never_stream = never_stream;
+    }
+
+    /// Returns true if the texture must be always kept at full resolution on GPU.
+    pub fn is_never_stream(&self) -> bool {
+        self.never_stream
+    }
+
     /// Sets new path to source file.
     pub fn set_path<P: AsRef<Path>>(&mut self, path: P) {
         self.path = path.as_ref().to_owned();
@@ -1083,6 +1192,59 @@ impl TextureData {
     pub fn modify(&mut self) -> TextureDataRefMut<'_> {
         TextureDataRefMut { texture: self }
     }
+
+    /// Replaces pixels of a rectangular region of a 2D texture. `bytes` must contain `height`
+    /// rows of `width` pixels each in the pixel format of the texture. Only the first mip level
+    /// is changed and compressed formats are not supported. The renderer uploads the changed
+    /// texture to GPU when the texture is used next time.
+    pub fn update_region(
+        &mut self,
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        bytes: &[u8],
+    ) -> Result<(), TextureError> {
+        let pixel_size = match self.pixel_kind {
+            TexturePixelKind::R8 => 1,
+            TexturePixelKind::R16 | TexturePixelKind::RG8 => 2,
+            TexturePixelKind::RGB8 | TexturePixelKind::BGR8 => 3,
+            TexturePixelKind::RGBA8 | TexturePixelKind::BGRA8 | TexturePixelKind::RG16 => 4,
+            TexturePixelKind::RGB16 => 6,
+            TexturePixelKind::RGBA16 => 8,
+            TexturePixelKind::DXT1RGB
+            | TexturePixelKind::DXT1RGBA
+            | TexturePixelKind::DXT3RGBA
+            | TexturePixelKind::DXT5RGBA
+            | TexturePixelKind::R8RGTC
+            | TexturePixelKind::RG8RGTC => return Err(TextureError::UnsupportedFormat),
+        };
+        let (texture_width, texture_height) = match self.kind {
+            TextureKind::Rectangle { width, height } => (width, height),
+            _ => return Err(TextureError::UnsupportedFormat),
+        };
+        if x.checked_add(width)
+            .map_or(true, |right| right > texture_width)
+            || y.checked_add(height)
+                .map_or(true, |bottom| bottom > texture_height)
+            || bytes.len() != width as usize * height as usize * pixel_size
+        {
+            return Err(TextureError::InvalidRegion);
+        }
+
+        let row_size = width as usize * pixel_size;
+        if row_size == 0 {
+            return Ok(());
+        }
+        let mut data = self.modify();
+        let data = data.data_mut();
+        for (row, source) in bytes.chunks_exact(row_size).enumerate() {
+            let start = ((y as usize + row) * texture_width as usize + x as usize) * pixel_size;
+            data[start..(start + row_size)].copy_from_slice(source);
+        }
+
+        Ok(())
+    }
 }
 
 /// A special reference holder that provides mutable access to content of the
@@ -49,6 +49,10 @@ fn bounding_box(node: &Node) -> AxisAlignedBoundingBox {
             AxisAlignedBoundingBox::unit()
         }
         Node::Terrain(terrain) => terrain.local_bounding_box(),
+        Node::Water(water) => water.local_bounding_box(),
+        Node::EnvironmentProbe(probe) => probe.local_bounding_box(),
+        Node::WorldText(text) => text.local_bounding_box(),
+        Node::Impostor(impostor) => impostor.local_bounding_box(),
     };
 
     local_aabb.transform(&node.global_transform.get())
@@ -11,7 +11,7 @@ use crate::{
         visitor::{Visit, VisitError, VisitResult, Visitor},
     },
     resource::model::Model,
-    scene::{graph::Graph, node::Node, transform::Transform},
+    scene::{constraint::Constraint, graph::Graph, node::Node, transform::Transform},
 };
 use std::cell::Cell;
 use std::ops::{Deref, DerefMut};
@@ -226,6 +226,85 @@ impl Visit for Mobility {
     }
 }
 
+/// Defines how a node is rotated towards a camera. Billboard rotation is applied on top of the
+/// global transform of a node (which is calculated as usual from local transforms), so position
+/// and scale of the node are kept, and its descendants inherit the rotation.
+#[derive(Copy, Clone, PartialEq, Debug, Visit, Inspect)]
+pub enum BillboardMode {
+    /// Node is not rotated towards a camera. This is default mode.
+    None,
+    /// Axes of the node are parallel to axes of a camera, so local XY plane of the node is always
+    /// parallel to the screen. Suitable for impostor cards, health bars, etc.
+    Full,
+    /// Node rotates only around given axis (in world coordinates) to face a camera. For example
+    /// `Vector3::y()` is suitable for trees or grass cards, that must stay vertical.
+    AxisAligned(Vector3<f32>),
+}
+
+impl Default for BillboardMode {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl BillboardMode {
+    /// Calculates new global transform of a node for a camera with given global transform.
+    pub fn apply(self, global_transform: Matrix4<f32>, camera: &Matrix4<f32>) -> Matrix4<f32> {
+        let (side, up, look) = match self {
+            BillboardMode::None => return global_transform,
+            BillboardMode::Full => (
+                camera.side().try_normalize(f32::EPSILON),
+                camera.up().try_normalize(f32::EPSILON),
+                camera.look().try_normalize(f32::EPSILON),
+            ),
+            BillboardMode::AxisAligned(axis) => {
+                let up = axis.try_normalize(f32::EPSILON).unwrap_or_else(Vector3::y);
+                // Direction from camera to the node projected on a plane perpendicular to the axis.
+                let to_node = global_transform.position() - camera.position();
+                let look = (to_node - up.scale(to_node.dot(&up)))
+                    .try_normalize(f32::EPSILON)
+                    .or_else(|| {
+                        // Camera is on the axis, use its look vector instead.
+                        let look = camera.look();
+                        (look - up.scale(look.dot(&up))).try_normalize(f32::EPSILON)
+                    });
+                (look.map(|look| up.cross(&look)), Some(up), look)
+            }
+        };
+
+        match (side, up, look) {
+            (Some(side), Some(up), Some(look)) => {
+                let scale = Vector3::new(
+                    global_transform.side().norm(),
+                    global_transform.up().norm(),
+                    global_transform.look().norm(),
+                );
+                let position = global_transform.position();
+                Matrix4::new(
+                    side.x * scale.x,
+                    up.x * scale.y,
+                    look.x * scale.z,
+                    position.x,
+                    side.y * scale.x,
+                    up.y * scale.y,
+                    look.y * scale.z,
+                    position.y,
+                    side.z * scale.x,
+                    up.z * scale.y,
+                    look.z * scale.z,
+                    position.z,
+                    0.0,
+                    0.0,
+                    0.0,
+                    1.0,
+                )
+            }
+            // Degenerated camera transform, keep node as is.
+            _ => global_transform,
+        }
+    }
+}
+
 /// A property value.
 #[derive(Debug, Visit, Inspect, Clone)]
 pub enum PropertyValue {
@@ -314,6 +393,9 @@ pub struct Base {
     pub(in crate) children: Vec<Handle<Node>>,
     #[inspect(skip)]
     pub(in crate) global_transform: Cell<Matrix4<f32>>,
+    // Non-serializable, set by the graph during update of hierarchical data.
+    #[inspect(skip)]
+    pub(in crate) global_transform_changed: Cell<bool>,
     // Bone-specific matrix. Non-serializable.
     #[inspect(skip)]
     pub(in crate) inv_bind_pose_transform: Matrix4<f32>,
@@ -339,9 +421,18 @@ pub struct Base {
     mobility: Mobility,
     tag: String,
     pub(in crate) physics_binding: PhysicsBinding,
+    // Bit mask that defines which cameras will render the node, `None` means that the mask
+    // is inherited from parent node.
+    render_mask: Option<u32>,
+    #[inspect(skip)]
+    pub(in crate) global_render_mask: Cell<u32>,
+    cast_shadows_when_masked: bool,
+    billboard_mode: BillboardMode,
     /// A set of custom properties that can hold almost any data. It can be used to set additional
     /// properties to scene nodes.
     pub properties: Vec<Property>,
+    #[inspect(skip)]
+    constraints: Vec<Constraint>,
 }
 
 impl Base {
@@ -376,6 +467,9 @@ impl Base {
     /// Sets new local transform of a node.
     pub fn set_local_transform(&mut self, transform: Transform) -> &mut Self {
         self.local_transform = transform;
+        // Transform could be taken from a node that was already updated, it must be treated as
+        // changed anyway.
+        self.local_transform.mark_changed();
         self
     }
 
@@ -428,6 +522,20 @@ impl Base {
         self.global_transform.get()
     }
 
+    /// Returns `true` if global transform of the node was recalculated during last update of
+    /// hierarchical data of the graph, because local transform of the node or of any of its
+    /// ancestors was changed. Systems that depend on node location (sounds, caches, etc.) can
+    /// use it to skip work for static nodes.
+    ///
+    /// # Notes
+    ///
+    /// Changes made after the last update are not reflected until next update, use
+    /// [`crate::scene::transform::Transform::is_changed`] to check local transform for pending
+    /// changes.
+    pub fn global_transform_changed(&self) -> bool {
+        self.global_transform_changed.get()
+    }
+
     /// Returns inverse of bind pose matrix. Bind pose matrix - is special matrix
     /// for bone nodes, it stores initial transform of bone node at the moment
     /// of "binding" vertices to bones.
@@ -483,6 +591,77 @@ impl Base {
         self.mobility
     }
 
+    /// Sets new render mask of the node, `None` means that the node will inherit the mask of its
+    /// parent node. The node will be rendered only by cameras whose
+    /// [visibility mask](super::camera::Camera::set_visibility_mask) has at least one common bit
+    /// with the global render mask of the node. For example you can put a first-person weapon in
+    /// a separate "layer" and hide it from every camera except the main one.
+    ///
+    /// Root node of a graph has every bit set, so by default every node is visible for every camera.
+    pub fn set_render_mask(&mut self, render_mask: Option<u32>) -> &mut Self {
+        self.render_mask = render_mask;
+        self
+    }
+
+    /// Returns local render mask of the node, `None` means that the mask is inherited from parent.
+    pub fn render_mask(&self) -> Option<u32> {
+        self.render_mask
+    }
+
+    /// Returns final render mask of the node, it is either its own mask or the mask of the closest
+    /// ancestor that has one. The value is calculated on each frame.
+    pub fn global_render_mask(&self) -> u32 {
+        self.global_render_mask.get()
+    }
+
+    /// Sets whether the node should still cast shadows in a camera, that does not render the node
+    /// because of [render mask](Self::set_render_mask). Default is `false`.
+    pub fn set_cast_shadows_when_masked(&mut self, cast_shadows: bool) -> &mut Self {
+        self.cast_shadows_when_masked = cast_shadows;
+        self
+    }
+
+    /// Returns `true` if the node casts shadows in a camera that does not render the node.
+    pub fn cast_shadows_when_masked(&self) -> bool {
+        self.cast_shadows_when_masked
+    }
+
+    /// Sets billboard mode of the node, see [`BillboardMode`] docs for more info. Billboard rotation
+    /// is camera-dependent and it is applied by the renderer for each camera separately, so global
+    /// transform of the node includes the rotation only while the scene is being rendered.
+    pub fn set_billboard_mode(&mut self, mode: BillboardMode) -> &mut Self {
+        self.billboard_mode = mode;
+        self
+    }
+
+    /// Returns current billboard mode of the node.
+    pub fn billboard_mode(&self) -> BillboardMode {
+        self.billboard_mode
+    }
+
+    /// Adds new constraint to the node. Constraints are applied in order of their addition, see
+    /// [`crate::scene::constraint`] module docs for more info.
+    pub fn add_constraint(&mut self, constraint: Constraint) -> &mut Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Returns a list of constraints of the node.
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// Returns a list of constraints of the node, it can be used to change parameters of
+    /// constraints or to remove them.
+    pub fn constraints_mut(&mut self) -> &mut Vec<Constraint> {
+        &mut self.constraints
+    }
+
+    /// Checks if the node can be rendered by a camera with given visibility mask.
+    pub fn is_visible_by_mask(&self, visibility_mask: u32) -> bool {
+        self.global_render_mask() & visibility_mask != 0
+    }
+
     /// Returns combined visibility of an node. This is the final visibility of a node. Global visibility calculated
     /// using visibility of all parent nodes until root one, so if some parent node upper on tree is invisible then
     /// all its children will be invisible. It defines if object will be rendered. It is *not* the same as real
@@ -591,6 +770,7 @@ impl Base {
             name: self.name.clone(),
             local_transform: self.local_transform.clone(),
             global_transform: self.global_transform.clone(),
+            global_transform_changed: Cell::new(true),
             visibility: self.visibility,
             global_visibility: self.global_visibility.clone(),
             inv_bind_pose_transform: self.inv_bind_pose_transform,
@@ -602,6 +782,11 @@ impl Base {
             physics_binding: self.physics_binding,
             lod_group: self.lod_group.clone(),
             properties: self.properties.clone(),
+            render_mask: self.render_mask,
+            global_render_mask: self.global_render_mask.clone(),
+            cast_shadows_when_masked: self.cast_shadows_when_masked,
+            billboard_mode: self.billboard_mode,
+            constraints: self.constraints.clone(),
 
             // Rest of data is *not* copied!
             original_handle_in_resource: Default::default(),
@@ -639,6 +824,12 @@ impl Visit for Base {
         self.tag.visit("Tag", visitor)?;
         self.physics_binding.visit("PhysicsBinding", visitor)?;
         let _ = self.properties.visit("Properties", visitor);
+        let _ = self.render_mask.visit("RenderMask", visitor);
+        let _ = self
+            .cast_shadows_when_masked
+            .visit("CastShadowsWhenMasked", visitor);
+        let _ = self.billboard_mode.visit("BillboardMode", visitor);
+        let _ = self.constraints.visit("Constraints", visitor);
 
         visitor.leave_region()
     }
@@ -656,6 +847,10 @@ pub struct BaseBuilder {
     mobility: Mobility,
     inv_bind_pose_transform: Matrix4<f32>,
     tag: String,
+    render_mask: Option<u32>,
+    cast_shadows_when_masked: bool,
+    billboard_mode: BillboardMode,
+    constraints: Vec<Constraint>,
 }
 
 impl Default for BaseBuilder {
@@ -678,6 +873,10 @@ impl BaseBuilder {
             mobility: Mobility::Dynamic,
             inv_bind_pose_transform: Matrix4::identity(),
             tag: Default::default(),
+            render_mask: None,
+            cast_shadows_when_masked: false,
+            billboard_mode: BillboardMode::None,
+            constraints: Default::default(),
         }
     }
 
@@ -748,6 +947,37 @@ impl BaseBuilder {
         self
     }
 
+    /// Sets desired render mask. See [`Base::set_render_mask`] for more info.
+    pub fn with_render_mask(mut self, render_mask: u32) -> Self {
+        self.render_mask = Some(render_mask);
+        self
+    }
+
+    /// Sets whether the node should cast shadows in cameras that do not render it because of render
+    /// mask. See [`Base::set_cast_shadows_when_masked`] for more info.
+    pub fn with_cast_shadows_when_masked(mut self, cast_shadows: bool) -> Self {
+        self.cast_shadows_when_masked = cast_shadows;
+        self
+    }
+
+    /// Sets desired billboard mode. See [`Base::set_billboard_mode`] for more info.
+    pub fn with_billboard_mode(mut self, mode: BillboardMode) -> Self {
+        self.billboard_mode = mode;
+        self
+    }
+
+    /// Adds a constraint to the node. See [`Base::add_constraint`] for more info.
+    pub fn with_constraint(mut self, constraint: Constraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Sets desired list of constraints.
+    pub fn with_constraints(mut self, constraints: Vec<Constraint>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
     pub(in crate) fn build_base(self) -> Base {
         Base {
             name: self.name,
@@ -758,6 +988,7 @@ impl BaseBuilder {
             global_visibility: Cell::new(true),
             parent: Handle::NONE,
             global_transform: Cell::new(Matrix4::identity()),
+            global_transform_changed: Cell::new(true),
             inv_bind_pose_transform: self.inv_bind_pose_transform,
             resource: None,
             original_handle_in_resource: Handle::NONE,
@@ -768,6 +999,11 @@ impl BaseBuilder {
             tag: self.tag,
             physics_binding: PhysicsBinding::NodeWithBody,
             properties: Default::default(),
+            render_mask: self.render_mask,
+            global_render_mask: Cell::new(u32::MAX),
+            cast_shadows_when_masked: self.cast_shadows_when_masked,
+            billboard_mode: self.billboard_mode,
+            constraints: self.constraints,
         }
     }
 
@@ -1,7 +1,13 @@
 //! Contains all methods and structures to create and manage cameras.
 //!
-//! Camera allows you to see world from specific point in world. Currently only
-//! perspective projection is supported.
+//! Camera allows you to see world from specific point in world. Perspective and
+//! orthographic projections are supported, see [`Projection`].
+//!
+//! # 2D games
+//!
+//! [`CameraBuilder::with_pixel_perfect_2d`] configures orthographic projection in which
+//! every texel of sprites is mapped to an integer amount of screen pixels, so the sprites
+//! are rendered crisply on any window size.
 //!
 //! # Multiple cameras
 //!
@@ -72,6 +78,50 @@ impl Default for Exposure {
     }
 }
 
+/// Defines how the camera projects the world on the screen.
+#[derive(Visit, Copy, Clone, PartialEq, Debug, Inspect)]
+pub enum Projection {
+    /// Perspective projection with camera's field of view. This is default option.
+    Perspective,
+
+    /// Orthographic projection, objects have the same size regardless of the distance to the
+    /// camera. Width of the view volume is defined by aspect ratio of the viewport.
+    Orthographic {
+        /// Half of height of the view volume in world units.
+        #[inspect(min_value = 0.0, step = 0.1)]
+        vertical_size: f32,
+    },
+
+    /// Orthographic projection for pixel-art 2D games. The projection is scaled by integer
+    /// factor, so at least `reference_height` "virtual" pixels fit vertically into the viewport
+    /// and every virtual pixel covers same amount of screen pixels. Half-pixel offset is applied
+    /// for odd viewport sizes and camera position is snapped to screen pixels, so texels are
+    /// always aligned with pixels.
+    PixelPerfect2d {
+        /// Height of the viewport in virtual pixels.
+        #[inspect(min_value = 1.0, step = 1.0)]
+        reference_height: f32,
+        /// Amount of virtual pixels in one world unit. Sprite with size of `n` texels must have
+        /// size of `n / pixels_per_unit` world units to be pixel perfect (remember that size
+        /// of a sprite is half of its width).
+        #[inspect(min_value = 0.0, step = 1.0)]
+        pixels_per_unit: f32,
+    },
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self::Perspective
+    }
+}
+
+impl Projection {
+    /// Returns `true` if the projection is orthographic.
+    pub fn is_orthographic(&self) -> bool {
+        !matches!(self, Self::Perspective)
+    }
+}
+
 /// See module docs.
 #[derive(Debug, Visit, Inspect)]
 pub struct Camera {
@@ -98,6 +148,10 @@ pub struct Camera {
     color_grading_lut: Option<ColorGradingLut>,
     #[visit(optional)] // Backward compatibility.
     color_grading_enabled: bool,
+    #[visit(optional)] // Backward compatibility.
+    visibility_mask: u32,
+    #[visit(optional)] // Backward compatibility.
+    projection: Projection,
     /// Visibility cache allows you to quickly check if object is visible from the camera or not.
     #[visit(skip)]
     #[inspect(skip)]
@@ -137,8 +191,64 @@ impl Camera {
 
         let viewport = self.viewport_pixels(frame_size);
         let aspect = viewport.w() as f32 / viewport.h() as f32;
-        self.projection_matrix =
-            Matrix4::new_perspective(aspect, self.fov, self.z_near, self.z_far);
+        self.projection_matrix = match self.projection {
+            Projection::Perspective => {
+                Matrix4::new_perspective(aspect, self.fov, self.z_near, self.z_far)
+            }
+            Projection::Orthographic { vertical_size } => {
+                let vertical_size = vertical_size.max(f32::EPSILON);
+                let horizontal_size = vertical_size * aspect;
+                Matrix4::new_orthographic(
+                    -horizontal_size,
+                    horizontal_size,
+                    -vertical_size,
+                    vertical_size,
+                    self.z_near,
+                    self.z_far,
+                )
+            }
+            Projection::PixelPerfect2d {
+                reference_height,
+                pixels_per_unit,
+            } => {
+                // Integer zoom guarantees that each virtual pixel covers the same amount of
+                // screen pixels.
+                let zoom = (viewport.h() as f32 / reference_height.max(1.0))
+                    .floor()
+                    .max(1.0);
+                // Size of a screen pixel in world units.
+                let pixel_size = 1.0 / (zoom * pixels_per_unit.max(f32::EPSILON));
+
+                // Snap camera to screen pixels, otherwise sprites will "swim" when camera moves.
+                self.view_matrix[(0, 3)] =
+                    (self.view_matrix[(0, 3)] / pixel_size).round() * pixel_size;
+                self.view_matrix[(1, 3)] =
+                    (self.view_matrix[(1, 3)] / pixel_size).round() * pixel_size;
+
+                // Center of a viewport with odd size is in the middle of a pixel, shift the
+                // projection by half of a pixel to align texel edges with pixel edges.
+                let half_pixel_offset = |size: i32| {
+                    if size % 2 == 1 {
+                        0.5 * pixel_size
+                    } else {
+                        0.0
+                    }
+                };
+                let dx = half_pixel_offset(viewport.w());
+                let dy = half_pixel_offset(viewport.h());
+
+                let half_width = 0.5 * viewport.w() as f32 * pixel_size;
+                let half_height = 0.5 * viewport.h() as f32 * pixel_size;
+                Matrix4::new_orthographic(
+                    -half_width + dx,
+                    half_width + dx,
+                    -half_height + dy,
+                    half_height + dy,
+                    self.z_near,
+                    self.z_far,
+                )
+            }
+        };
     }
 
     /// Sets new viewport in resolution-independent format. In other words
@@ -244,6 +354,19 @@ impl Camera {
         self.fov
     }
 
+    /// Sets new projection of the camera. See [`Projection`] docs for more info.
+    #[inline]
+    pub fn set_projection(&mut self, projection: Projection) -> &mut Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Returns current projection of the camera.
+    #[inline]
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
     /// Returns state of camera: enabled or not.
     #[inline]
     pub fn is_enabled(&self) -> bool {
@@ -301,6 +424,20 @@ impl Camera {
         self.environment.clone()
     }
 
+    /// Sets new visibility mask of the camera. The camera will render only the nodes whose
+    /// [render mask](crate::scene::base::Base::set_render_mask) has at least one common bit with
+    /// the visibility mask. Default value is `u32::MAX` which means that the camera renders
+    /// everything.
+    pub fn set_visibility_mask(&mut self, visibility_mask: u32) -> &mut Self {
+        self.visibility_mask = visibility_mask;
+        self
+    }
+
+    /// Returns current visibility mask of the camera.
+    pub fn visibility_mask(&self) -> u32 {
+        self.visibility_mask
+    }
+
     /// Returns current **local-space** bounding box.
     #[inline]
     pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
@@ -340,7 +477,12 @@ impl Camera {
         let viewport = self.viewport_pixels(screen_size);
         let proj = self.view_projection_matrix()
             * Vector4::new(world_pos.x, world_pos.y, world_pos.z, 1.0);
-        if proj.w != 0.0 && proj.z >= 0.0 {
+        let in_front = if self.projection.is_orthographic() {
+            proj.z >= -1.0 && proj.z <= 1.0
+        } else {
+            proj.z >= 0.0
+        };
+        if proj.w != 0.0 && in_front {
             let k = (1.0 / proj.w) * 0.5;
             Some(Vector2::new(
                 viewport.x() as f32 + viewport.w() as f32 * (proj.x * k + 0.5),
@@ -368,6 +510,8 @@ impl Camera {
             exposure: self.exposure,
             color_grading_lut: self.color_grading_lut.clone(),
             color_grading_enabled: self.color_grading_enabled,
+            visibility_mask: self.visibility_mask,
+            projection: self.projection,
             // No need to copy cache. It is valid only for one frame.
             visibility_cache: Default::default(),
         }
@@ -595,6 +739,8 @@ pub struct CameraBuilder {
     exposure: Exposure,
     color_grading_lut: Option<ColorGradingLut>,
     color_grading_enabled: bool,
+    visibility_mask: u32,
+    projection: Projection,
 }
 
 impl CameraBuilder {
@@ -612,6 +758,8 @@ impl CameraBuilder {
             exposure: Exposure::Manual(std::f32::consts::E),
             color_grading_lut: None,
             color_grading_enabled: false,
+            visibility_mask: u32::MAX,
+            projection: Projection::Perspective,
         }
     }
 
@@ -675,6 +823,34 @@ impl CameraBuilder {
         self
     }
 
+    /// Sets desired visibility mask. See [`Camera::set_visibility_mask`] for more info.
+    pub fn with_visibility_mask(mut self, visibility_mask: u32) -> Self {
+        self.visibility_mask = visibility_mask;
+        self
+    }
+
+    /// Sets desired projection. See [`Projection`] docs for more info.
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
+
+    /// Configures pixel perfect orthographic projection for 2D games, at least
+    /// `reference_height_in_pixels` virtual pixels will fit vertically into the viewport, one
+    /// world unit is one virtual pixel. Use [`Projection::PixelPerfect2d`] directly to set other
+    /// amount of pixels per unit.
+    ///
+    /// The camera looks along its look vector as usual, so it should be placed in front of
+    /// sprites (for example at `(0, 0, -10)` looking along Z axis), sprites on the same distance
+    /// are ordered by their [sorting layers](crate::scene::sprite::Sprite::set_sorting_layer).
+    pub fn with_pixel_perfect_2d(mut self, reference_height_in_pixels: u32) -> Self {
+        self.projection = Projection::PixelPerfect2d {
+            reference_height: reference_height_in_pixels.max(1) as f32,
+            pixels_per_unit: 1.0,
+        };
+        self
+    }
+
     /// Creates new instance of camera.
     pub fn build_camera(self) -> Camera {
         Camera {
@@ -694,6 +870,8 @@ impl CameraBuilder {
             exposure: self.exposure,
             color_grading_lut: self.color_grading_lut,
             color_grading_enabled: self.color_grading_enabled,
+            visibility_mask: self.visibility_mask,
+            projection: self.projection,
         }
     }
 
@@ -949,3 +1127,49 @@ impl Visit for SkyBox {
         visitor.leave_region()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{Vector2, Vector3},
+        scene::{
+            base::BaseBuilder, camera::CameraBuilder, graph::Graph, transform::TransformBuilder,
+        },
+    };
+
+    #[test]
+    fn test_pixel_perfect_2d_projection() {
+        let mut graph = Graph::new();
+        let camera = CameraBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.3, -1.1, -10.0))
+                    .build(),
+            ),
+        )
+        .with_pixel_perfect_2d(90)
+        .build(&mut graph);
+        graph.update_hierarchical_data();
+
+        // Odd sizes, zoom is 2 so every virtual pixel is 2x2 screen pixels.
+        for &frame_size in [Vector2::new(321.0, 181.0), Vector2::new(320.0, 180.0)].iter() {
+            let camera = graph[camera].as_camera_mut();
+            camera.calculate_matrices(frame_size);
+
+            // Edges of virtual pixels must be exactly on edges of screen pixels.
+            for &(x, y) in [(0.0, 0.0), (3.0, -2.0), (-7.0, 5.0)].iter() {
+                let screen = camera.project(Vector3::new(x, y, 0.0), frame_size).unwrap();
+                assert!((screen.x - screen.x.round()).abs() < 1.0e-3);
+                assert!((screen.y - screen.y.round()).abs() < 1.0e-3);
+            }
+
+            let a = camera
+                .project(Vector3::new(0.0, 0.0, 0.0), frame_size)
+                .unwrap();
+            let b = camera
+                .project(Vector3::new(1.0, 0.0, 0.0), frame_size)
+                .unwrap();
+            assert!(((a - b).norm() - 2.0).abs() < 1.0e-3);
+        }
+    }
+}
@@ -0,0 +1,775 @@
+//! Transform constraints change transforms of nodes after animations, so a turret can track a
+//! player, eyes can follow a target and a shield bone can copy a hand bone.
+//!
+//! # Overview
+//!
+//! Constraints are data of a node (see [`crate::scene::base::Base::add_constraint`] and
+//! [`crate::scene::base::BaseBuilder::with_constraint`]), they're saved together with the node.
+//! There are three kinds of constraints:
+//!
+//! - [`LookAtConstraint`] - fully orients the node, so its axis points at a target and its up
+//! axis is aligned with an up vector. Turning speed can be limited.
+//! - [`CopyTransformConstraint`] - copies global position, rotation and scale of another node,
+//! every channel can be disabled.
+//! - [`AimConstraint`] - turns the node by the shortest arc, so its axis points at a target, but
+//! the axis can deviate from its unconstrained direction by a limited angle only. Suitable for
+//! heads and eyes.
+//!
+//! Every constraint has a weight in `[0; 1]` range, which blends the constrained transform with
+//! the transform the node had before the constraint. Constraints of a node are applied one after
+//! another in order of their addition.
+//!
+//! # Evaluation order
+//!
+//! Constraints are evaluated by [`crate::scene::graph::Graph::update_constraints`] at the very
+//! beginning of [`crate::scene::Scene::update`], so poses of animation machines applied during a
+//! game tick are already there, and bodies bound to nodes with
+//! [`crate::scene::base::PhysicsBinding::BodyWithNode`] get constrained transforms.
+//!
+//! A constrained node depends on constrained ancestors and on constrained nodes that move its
+//! targets. Nodes are evaluated in dependency order, so a constraint of a node always sees final
+//! transforms of the nodes it depends on. Nodes that form a dependency cycle (for example two
+//! nodes that look at each other from inside each other's hierarchy, or a node that looks at its
+//! own descendant) are not evaluated and a warning is written to the log.
+//!
+//! # Limitations
+//!
+//! Rotation and scale pivots and offsets of constrained nodes are ignored. Scale is copied as a
+//! global scale of a node, non-uniform scale in the hierarchy is not supported.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::{algebra::Vector3, pool::Handle},
+//!     scene::{
+//!         base::BaseBuilder,
+//!         constraint::{Constraint, ConstraintTarget, LookAtConstraint},
+//!         node::Node,
+//!         Scene,
+//!     },
+//! };
+//!
+//! fn create_turret_head(scene: &mut Scene, player: Handle<Node>) -> Handle<Node> {
+//!     BaseBuilder::new()
+//!         .with_constraint(Constraint::new(
+//!             LookAtConstraint::new(ConstraintTarget::Node(player))
+//!                 .with_max_angular_speed(Some(90.0f32.to_radians())),
+//!         ))
+//!         .build(&mut scene.graph)
+//! }
+//! ```
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Point3, Rotation3, UnitQuaternion, Vector3},
+        math::Matrix4Ext,
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    scene::{graph::Graph, node::Node},
+};
+use fxhash::{FxHashMap, FxHashSet};
+
+/// A point constraints are aimed at.
+#[derive(Copy, Clone, PartialEq, Debug, Visit)]
+pub enum ConstraintTarget {
+    /// Global position of a node. Constraint does nothing if the node does not exist.
+    Node(Handle<Node>),
+    /// Fixed position in world coordinates.
+    Point(Vector3<f32>),
+}
+
+impl Default for ConstraintTarget {
+    fn default() -> Self {
+        Self::Point(Default::default())
+    }
+}
+
+impl ConstraintTarget {
+    fn position(&self, graph: &Graph) -> Option<Vector3<f32>> {
+        match *self {
+            ConstraintTarget::Node(node) => graph
+                .try_get(node)
+                .map(|_| global_transform(graph, node).position()),
+            ConstraintTarget::Point(point) => Some(point),
+        }
+    }
+}
+
+/// Orients a node so its axis points at a target, see module docs.
+#[derive(Clone, PartialEq, Debug, Visit)]
+pub struct LookAtConstraint {
+    /// A point to look at.
+    pub target: ConstraintTarget,
+    /// Axis of the node in its local coordinates that must point at the target, it is Z axis by
+    /// default - the look vector of the node.
+    pub axis: Vector3<f32>,
+    /// Axis of the node in its local coordinates that must be aligned with [`Self::up`] as much
+    /// as possible, it is Y axis by default.
+    pub up_axis: Vector3<f32>,
+    /// Up vector in world coordinates, it is Y axis by default.
+    pub up: Vector3<f32>,
+    /// Maximum turning speed in radians per second, `None` means that the node turns to the
+    /// target immediately.
+    pub max_angular_speed: Option<f32>,
+    // Global rotation at last evaluation, it is used to limit turning speed.
+    #[visit(skip)]
+    last_rotation: Option<UnitQuaternion<f32>>,
+}
+
+impl Default for LookAtConstraint {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl LookAtConstraint {
+    /// Creates new constraint that makes look vector of a node to point at the target.
+    pub fn new(target: ConstraintTarget) -> Self {
+        Self {
+            target,
+            axis: Vector3::z(),
+            up_axis: Vector3::y(),
+            up: Vector3::y(),
+            max_angular_speed: None,
+            last_rotation: None,
+        }
+    }
+
+    /// Sets axis of the node that must point at the target.
+    pub fn with_axis(mut self, axis: Vector3<f32>) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Sets axis of the node that must be aligned with the up vector.
+    pub fn with_up_axis(mut self, up_axis: Vector3<f32>) -> Self {
+        self.up_axis = up_axis;
+        self
+    }
+
+    /// Sets up vector in world coordinates.
+    pub fn with_up(mut self, up: Vector3<f32>) -> Self {
+        self.up = up;
+        self
+    }
+
+    /// Sets maximum turning speed in radians per second.
+    pub fn with_max_angular_speed(mut self, speed: Option<f32>) -> Self {
+        self.max_angular_speed = speed;
+        self
+    }
+
+    // Global rotation that makes the axis to point in given direction. `None` if the direction
+    // is parallel to the up vector or the axes are degenerated.
+    fn rotation(&self, direction: Vector3<f32>) -> Option<UnitQuaternion<f32>> {
+        let direction = direction.try_normalize(f32::EPSILON)?;
+        let up = self.up.try_normalize(f32::EPSILON)?;
+        let axis = self.axis.try_normalize(f32::EPSILON)?;
+        let up_axis = self.up_axis.try_normalize(f32::EPSILON)?;
+        if direction.cross(&up).norm_squared() <= f32::EPSILON
+            || axis.cross(&up_axis).norm_squared() <= f32::EPSILON
+        {
+            return None;
+        }
+        // Both rotations map Z axis to the first argument.
+        let world = UnitQuaternion::face_towards(&direction, &up);
+        let local = UnitQuaternion::face_towards(&axis, &up_axis);
+        Some(world * local.inverse())
+    }
+}
+
+/// Copies global transform of another node, see module docs.
+#[derive(Clone, PartialEq, Debug, Visit)]
+pub struct CopyTransformConstraint {
+    /// A node to copy transform from. Constraint does nothing if the node does not exist.
+    pub source: Handle<Node>,
+    /// Whether global position should be copied.
+    pub copy_position: bool,
+    /// Whether global rotation should be copied.
+    pub copy_rotation: bool,
+    /// Whether global scale should be copied.
+    pub copy_scale: bool,
+}
+
+impl Default for CopyTransformConstraint {
+    fn default() -> Self {
+        Self::new(Handle::NONE)
+    }
+}
+
+impl CopyTransformConstraint {
+    /// Creates new constraint that copies position and rotation of given node.
+    pub fn new(source: Handle<Node>) -> Self {
+        Self {
+            source,
+            copy_position: true,
+            copy_rotation: true,
+            copy_scale: false,
+        }
+    }
+
+    /// Sets whether global position should be copied.
+    pub fn with_position(mut self, copy: bool) -> Self {
+        self.copy_position = copy;
+        self
+    }
+
+    /// Sets whether global rotation should be copied.
+    pub fn with_rotation(mut self, copy: bool) -> Self {
+        self.copy_rotation = copy;
+        self
+    }
+
+    /// Sets whether global scale should be copied.
+    pub fn with_scale(mut self, copy: bool) -> Self {
+        self.copy_scale = copy;
+        self
+    }
+}
+
+/// Turns an axis of a node to a target within a cone, see module docs.
+#[derive(Clone, PartialEq, Debug, Visit)]
+pub struct AimConstraint {
+    /// A point to aim at.
+    pub target: ConstraintTarget,
+    /// Axis of the node in its local coordinates that must point at the target, it is Z axis by
+    /// default - the look vector of the node.
+    pub axis: Vector3<f32>,
+    /// Maximum angle in radians between the axis before and after the constraint, in other words
+    /// half of the angle of the cone in which the axis can move.
+    pub max_angle: f32,
+}
+
+impl Default for AimConstraint {
+    fn default() -> Self {
+        Self::new(Default::default())
+    }
+}
+
+impl AimConstraint {
+    /// Creates new constraint that turns look vector of a node to the target without limits.
+    pub fn new(target: ConstraintTarget) -> Self {
+        Self {
+            target,
+            axis: Vector3::z(),
+            max_angle: std::f32::consts::PI,
+        }
+    }
+
+    /// Sets axis of the node that must point at the target.
+    pub fn with_axis(mut self, axis: Vector3<f32>) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Sets maximum angle of deviation of the axis in radians.
+    pub fn with_max_angle(mut self, max_angle: f32) -> Self {
+        self.max_angle = max_angle;
+        self
+    }
+
+    // Rotation that turns the axis (already rotated by the global rotation of the node) towards
+    // given direction within the cone.
+    fn delta(&self, rotation: UnitQuaternion<f32>, direction: Vector3<f32>) -> UnitQuaternion<f32> {
+        let axis = rotation * self.axis;
+        match UnitQuaternion::rotation_between(&axis, &direction) {
+            Some(delta) => match delta.axis_angle() {
+                Some((rotation_axis, angle)) if angle > self.max_angle.max(0.0) => {
+                    UnitQuaternion::from_axis_angle(&rotation_axis, self.max_angle.max(0.0))
+                }
+                _ => delta,
+            },
+            // Opposite vectors or degenerated input.
+            None => UnitQuaternion::identity(),
+        }
+    }
+}
+
+/// All supported kinds of constraints.
+#[derive(Clone, PartialEq, Debug, Visit)]
+pub enum ConstraintKind {
+    /// See [`LookAtConstraint`].
+    LookAt(LookAtConstraint),
+    /// See [`CopyTransformConstraint`].
+    CopyTransform(CopyTransformConstraint),
+    /// See [`AimConstraint`].
+    Aim(AimConstraint),
+}
+
+impl Default for ConstraintKind {
+    fn default() -> Self {
+        Self::LookAt(Default::default())
+    }
+}
+
+impl From<LookAtConstraint> for ConstraintKind {
+    fn from(constraint: LookAtConstraint) -> Self {
+        Self::LookAt(constraint)
+    }
+}
+
+impl From<CopyTransformConstraint> for ConstraintKind {
+    fn from(constraint: CopyTransformConstraint) -> Self {
+        Self::CopyTransform(constraint)
+    }
+}
+
+impl From<AimConstraint> for ConstraintKind {
+    fn from(constraint: AimConstraint) -> Self {
+        Self::Aim(constraint)
+    }
+}
+
+impl ConstraintKind {
+    fn targets(&self) -> impl Iterator<Item = Handle<Node>> {
+        let target = match self {
+            ConstraintKind::LookAt(LookAtConstraint {
+                target: ConstraintTarget::Node(node),
+                ..
+            })
+            | ConstraintKind::Aim(AimConstraint {
+                target: ConstraintTarget::Node(node),
+                ..
+            }) => *node,
+            ConstraintKind::CopyTransform(copy) => copy.source,
+            _ => Handle::NONE,
+        };
+        std::iter::once(target).filter(|target| target.is_some())
+    }
+
+    pub(in crate) fn remap_handles(
+        &mut self,
+        old_new_mapping: &FxHashMap<Handle<Node>, Handle<Node>>,
+    ) {
+        let handle = match self {
+            ConstraintKind::LookAt(LookAtConstraint {
+                target: ConstraintTarget::Node(node),
+                ..
+            })
+            | ConstraintKind::Aim(AimConstraint {
+                target: ConstraintTarget::Node(node),
+                ..
+            }) => node,
+            ConstraintKind::CopyTransform(copy) => &mut copy.source,
+            _ => return,
+        };
+        if let Some(new_handle) = old_new_mapping.get(handle) {
+            *handle = *new_handle;
+        }
+    }
+}
+
+/// A constraint of a node with its weight, see module docs.
+#[derive(Clone, PartialEq, Debug, Visit)]
+pub struct Constraint {
+    /// Kind of the constraint and its parameters.
+    pub kind: ConstraintKind,
+    /// Weight of the constraint in `[0; 1]` range, zero weight disables the constraint.
+    pub weight: f32,
+    /// Whether the constraint is enabled or not.
+    pub enabled: bool,
+}
+
+impl Default for Constraint {
+    fn default() -> Self {
+        Self::new(ConstraintKind::default())
+    }
+}
+
+impl Constraint {
+    /// Creates new enabled constraint with full weight.
+    pub fn new<K: Into<ConstraintKind>>(kind: K) -> Self {
+        Self {
+            kind: kind.into(),
+            weight: 1.0,
+            enabled: true,
+        }
+    }
+
+    /// Sets weight of the constraint.
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets whether the constraint is enabled or not.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    fn is_active(&self) -> bool {
+        self.enabled && self.weight > 0.0
+    }
+}
+
+// Global transform calculated from local transforms. Global transforms stored in nodes are
+// outdated at the moment when constraints are evaluated (animations are already applied to local
+// transforms, and constraints change local transforms too).
+fn global_transform(graph: &Graph, node: Handle<Node>) -> Matrix4<f32> {
+    let mut transform = Matrix4::identity();
+    let mut current = node;
+    while let Some(node) = graph.try_get(current) {
+        transform = node.local_transform().matrix() * transform;
+        current = node.parent();
+    }
+    transform
+}
+
+fn decompose(transform: &Matrix4<f32>) -> (Vector3<f32>, UnitQuaternion<f32>, Vector3<f32>) {
+    let scale = Vector3::new(
+        transform.side().norm(),
+        transform.up().norm(),
+        transform.look().norm(),
+    );
+    let mut basis = transform.basis();
+    for (mut column, &scale) in basis.column_iter_mut().zip(scale.iter()) {
+        if scale > f32::EPSILON {
+            column /= scale;
+        }
+    }
+    (
+        transform.position(),
+        UnitQuaternion::from(Rotation3::from_matrix(&basis)),
+        scale,
+    )
+}
+
+fn blend_rotation(
+    from: UnitQuaternion<f32>,
+    to: UnitQuaternion<f32>,
+    t: f32,
+) -> UnitQuaternion<f32> {
+    from.try_slerp(&to, t, f32::EPSILON).unwrap_or(to)
+}
+
+/// Returns constrained nodes in the order of evaluation and the nodes that cannot be evaluated,
+/// because they form a dependency cycle (or depend on such nodes).
+pub(in crate) fn evaluation_order(graph: &Graph) -> (Vec<Handle<Node>>, Vec<Handle<Node>>) {
+    let constrained = graph
+        .pair_iter()
+        .filter(|(_, node)| node.constraints().iter().any(|c| c.is_active()))
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    if constrained.is_empty() {
+        return Default::default();
+    }
+    let constrained_set = constrained.iter().cloned().collect::<FxHashSet<_>>();
+
+    // Constrained node depends on its constrained ancestors, and on constrained targets and
+    // their constrained ancestors. A target that is a descendant of the node (or the node itself)
+    // makes the node to depend on itself.
+    let chain = |node: Handle<Node>, owner: Handle<Node>| {
+        let mut chain = Vec::new();
+        let mut current = node;
+        while let Some(node) = graph.try_get(current) {
+            if current == owner || constrained_set.contains(&current) {
+                chain.push(current);
+            }
+            current = node.parent();
+        }
+        chain
+    };
+
+    let mut dependents = FxHashMap::<Handle<Node>, Vec<Handle<Node>>>::default();
+    let mut dependency_count = FxHashMap::default();
+    for &handle in constrained.iter() {
+        let node = &graph[handle];
+        let mut dependencies = chain(node.parent(), Handle::NONE);
+        for constraint in node.constraints().iter().filter(|c| c.is_active()) {
+            for target in constraint.kind.targets() {
+                dependencies.extend(chain(target, handle));
+            }
+        }
+        dependencies.sort_unstable();
+        dependencies.dedup();
+        dependency_count.insert(handle, dependencies.len());
+        for dependency in dependencies {
+            dependents.entry(dependency).or_default().push(handle);
+        }
+    }
+
+    // Kahn's algorithm, nodes that are left unvisited are in cycles or depend on cycles.
+    let mut order = Vec::with_capacity(constrained.len());
+    let mut queue = constrained
+        .iter()
+        .cloned()
+        .filter(|handle| dependency_count[handle] == 0)
+        .collect::<Vec<_>>();
+    while let Some(handle) = queue.pop() {
+        order.push(handle);
+        if let Some(dependents) = dependents.get(&handle) {
+            for dependent in dependents {
+                let count = dependency_count.get_mut(dependent).unwrap();
+                *count -= 1;
+                if *count == 0 {
+                    queue.push(*dependent);
+                }
+            }
+        }
+    }
+
+    let cycle = constrained
+        .into_iter()
+        .filter(|handle| dependency_count[handle] != 0)
+        .collect();
+
+    (order, cycle)
+}
+
+/// Applies every constraint of a node to its local transform.
+pub(in crate) fn solve(graph: &mut Graph, handle: Handle<Node>, dt: f32) {
+    let parent_transform = global_transform(graph, graph[handle].parent());
+    let (position, rotation, scale) =
+        decompose(&(parent_transform * graph[handle].local_transform().matrix()));
+    let (mut new_position, mut new_rotation, mut new_scale) = (None, None, None);
+
+    // Constraints are taken out of the node to evaluate them with access to the graph.
+    let mut constraints = std::mem::take(graph[handle].constraints_mut());
+    for constraint in constraints.iter_mut().filter(|c| c.is_active()) {
+        let weight = constraint.weight.min(1.0);
+        let current_position = new_position.unwrap_or(position);
+        let current_rotation = new_rotation.unwrap_or(rotation);
+        match constraint.kind {
+            ConstraintKind::LookAt(ref mut look_at) => {
+                let desired = look_at
+                    .target
+                    .position(graph)
+                    .and_then(|target| look_at.rotation(target - current_position))
+                    .or(look_at.last_rotation);
+                if let Some(mut desired) = desired {
+                    if let (Some(speed), Some(last)) =
+                        (look_at.max_angular_speed, look_at.last_rotation)
+                    {
+                        let max_angle = speed.max(0.0) * dt;
+                        let angle = last.angle_to(&desired);
+                        if angle > max_angle {
+                            desired = blend_rotation(last, desired, max_angle / angle);
+                        }
+                    }
+                    look_at.last_rotation = Some(desired);
+                    new_rotation = Some(blend_rotation(current_rotation, desired, weight));
+                }
+            }
+            ConstraintKind::CopyTransform(ref copy) => {
+                if graph.try_get(copy.source).is_some() {
+                    let (source_position, source_rotation, source_scale) =
+                        decompose(&global_transform(graph, copy.source));
+                    if copy.copy_position {
+                        new_position = Some(current_position.lerp(&source_position, weight));
+                    }
+                    if copy.copy_rotation {
+                        new_rotation =
+                            Some(blend_rotation(current_rotation, source_rotation, weight));
+                    }
+                    if copy.copy_scale {
+                        new_scale = Some(new_scale.unwrap_or(scale).lerp(&source_scale, weight));
+                    }
+                }
+            }
+            ConstraintKind::Aim(ref aim) => {
+                if let Some(target) = aim.target.position(graph) {
+                    let delta = aim.delta(current_rotation, target - current_position);
+                    let delta = blend_rotation(UnitQuaternion::identity(), delta, weight);
+                    new_rotation = Some(delta * current_rotation);
+                }
+            }
+        }
+    }
+    *graph[handle].constraints_mut() = constraints;
+
+    // Convert global values back to local space of the node.
+    let (_, parent_rotation, parent_scale) = decompose(&parent_transform);
+    let transform = graph[handle].local_transform_mut();
+    if let Some(new_position) = new_position {
+        if let Some(inverse) = parent_transform.try_inverse() {
+            transform.set_position(inverse.transform_point(&Point3::from(new_position)).coords);
+        }
+    }
+    if let Some(new_rotation) = new_rotation {
+        let local = transform.pre_rotation().inverse()
+            * parent_rotation.inverse()
+            * new_rotation
+            * transform.post_rotation().inverse();
+        transform.set_rotation(local);
+    }
+    if let Some(new_scale) = new_scale {
+        let local = new_scale.zip_map(&parent_scale, |scale, parent_scale| {
+            if parent_scale > f32::EPSILON {
+                scale / parent_scale
+            } else {
+                scale
+            }
+        });
+        transform.set_scale(local);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{UnitQuaternion, Vector3},
+            math::Matrix4Ext,
+        },
+        scene::{
+            base::BaseBuilder,
+            constraint::{
+                AimConstraint, Constraint, ConstraintKind, ConstraintTarget,
+                CopyTransformConstraint, LookAtConstraint,
+            },
+            graph::Graph,
+            transform::TransformBuilder,
+        },
+    };
+
+    fn at(position: Vector3<f32>) -> TransformBuilder {
+        TransformBuilder::new().with_local_position(position)
+    }
+
+    #[test]
+    fn test_look_at() {
+        let mut graph = Graph::new();
+        let target = BaseBuilder::new()
+            .with_local_transform(at(Vector3::new(10.0, 0.0, 0.0)).build())
+            .build(&mut graph);
+        let head = BaseBuilder::new()
+            .with_constraint(Constraint::new(LookAtConstraint::new(
+                ConstraintTarget::Node(target),
+            )))
+            .build(&mut graph);
+        // Parent is rotated, constraint must work in world space anyway.
+        BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_rotation(UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 1.0))
+                    .build(),
+            )
+            .with_children(&[head])
+            .build(&mut graph);
+
+        graph.update_constraints(0.0);
+        graph.update_hierarchical_data();
+
+        let look = graph[head].global_transform().look();
+        assert!(look.metric_distance(&Vector3::x()) < 1.0e-5);
+        let up = graph[head].global_transform().up();
+        assert!(up.metric_distance(&Vector3::y()) < 1.0e-5);
+    }
+
+    #[test]
+    fn test_look_at_max_angular_speed() {
+        let mut graph = Graph::new();
+        let head = BaseBuilder::new()
+            .with_constraint(Constraint::new(
+                LookAtConstraint::new(ConstraintTarget::Point(Vector3::new(0.0, 0.0, 1.0)))
+                    .with_max_angular_speed(Some(1.0)),
+            ))
+            .build(&mut graph);
+
+        graph.update_constraints(0.1);
+        if let ConstraintKind::LookAt(look_at) = &mut graph[head].constraints_mut()[0].kind {
+            look_at.target = ConstraintTarget::Point(Vector3::new(1.0, 0.0, 0.0));
+        }
+        graph.update_constraints(0.5);
+        graph.update_hierarchical_data();
+
+        let look = graph[head].global_transform().look();
+        assert!((look.angle(&Vector3::z()) - 0.5).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_copy_transform_after_constrained_source() {
+        let mut graph = Graph::new();
+        // The copying node is created first, so it must be evaluated after its source anyway.
+        let copy = BaseBuilder::new().build(&mut graph);
+        let source = BaseBuilder::new()
+            .with_local_transform(at(Vector3::new(1.0, 2.0, 3.0)).build())
+            .with_constraint(Constraint::new(AimConstraint::new(
+                ConstraintTarget::Point(Vector3::new(11.0, 2.0, 3.0)),
+            )))
+            .build(&mut graph);
+        graph[copy].add_constraint(Constraint::new(
+            CopyTransformConstraint::new(source).with_scale(true),
+        ));
+
+        graph.update_constraints(0.0);
+        graph.update_hierarchical_data();
+
+        let copy_transform = graph[copy].global_transform();
+        let source_transform = graph[source].global_transform();
+        assert!(
+            copy_transform
+                .position()
+                .metric_distance(&Vector3::new(1.0, 2.0, 3.0))
+                < 1.0e-5
+        );
+        assert!(copy_transform.look().metric_distance(&Vector3::x()) < 1.0e-5);
+        assert!((copy_transform - source_transform).norm() < 1.0e-5);
+    }
+
+    #[test]
+    fn test_aim_max_angle_and_weight() {
+        let mut graph = Graph::new();
+        let eye = BaseBuilder::new()
+            .with_constraint(
+                Constraint::new(
+                    AimConstraint::new(ConstraintTarget::Point(Vector3::new(1.0, 0.0, 0.0)))
+                        .with_max_angle(0.5),
+                )
+                .with_weight(0.5),
+            )
+            .build(&mut graph);
+
+        graph.update_constraints(0.0);
+        graph.update_hierarchical_data();
+
+        let look = graph[eye].global_transform().look();
+        assert!((look.angle(&Vector3::z()) - 0.25).abs() < 1.0e-4);
+    }
+
+    #[test]
+    fn test_cycle_is_skipped() {
+        let mut graph = Graph::new();
+        let a = BaseBuilder::new().build(&mut graph);
+        let b = BaseBuilder::new()
+            .with_local_transform(at(Vector3::new(0.0, 0.0, -5.0)).build())
+            .with_constraint(Constraint::new(LookAtConstraint::new(
+                ConstraintTarget::Node(a),
+            )))
+            .build(&mut graph);
+        graph[a].add_constraint(Constraint::new(LookAtConstraint::new(
+            ConstraintTarget::Node(b),
+        )));
+        // Independent node must be evaluated.
+        let c = BaseBuilder::new()
+            .with_constraint(Constraint::new(LookAtConstraint::new(
+                ConstraintTarget::Point(Vector3::new(0.0, 0.0, -1.0)),
+            )))
+            .build(&mut graph);
+
+        let (order, cycle) = super::evaluation_order(&graph);
+        assert_eq!(order, vec![c]);
+        assert_eq!(cycle.len(), 2);
+        assert!(cycle.contains(&a) && cycle.contains(&b));
+
+        graph.update_constraints(0.0);
+        graph.update_hierarchical_data();
+        assert!(
+            graph[a]
+                .global_transform()
+                .look()
+                .metric_distance(&Vector3::z())
+                < 1.0e-5
+        );
+        assert!(
+            graph[c]
+                .global_transform()
+                .look()
+                .metric_distance(&-Vector3::z())
+                < 1.0e-5
+        );
+    }
+}
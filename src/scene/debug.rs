@@ -775,6 +775,54 @@ impl SceneDrawingContext {
         }
     }
 
+    /// Draws a bone as an octahedron that points from `begin` to `end`. It is used to visualize
+    /// skeletons, see [`crate::scene::skeleton::draw_skeleton`].
+    pub fn draw_bone(&mut self, begin: Vector3<f32>, end: Vector3<f32>, color: Color) {
+        let dir = end - begin;
+        let length = dir.norm();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let axis = dir.scale(1.0 / length);
+
+        // Any vector that is not collinear with the bone will do.
+        let up = if axis.y.abs() < 0.99 {
+            Vector3::y()
+        } else {
+            Vector3::x()
+        };
+        let u = axis.cross(&up).normalize();
+        let v = axis.cross(&u);
+
+        let center = begin + dir.scale(0.1);
+        let width = length * 0.1;
+        let points = [
+            center + u.scale(width),
+            center + v.scale(width),
+            center - u.scale(width),
+            center - v.scale(width),
+        ];
+
+        for (i, &point) in points.iter().enumerate() {
+            let next = points[(i + 1) % points.len()];
+            self.add_line(Line {
+                begin,
+                end: point,
+                color,
+            });
+            self.add_line(Line {
+                begin: point,
+                end,
+                color,
+            });
+            self.add_line(Line {
+                begin: point,
+                end: next,
+                color,
+            });
+        }
+    }
+
     /// Adds single line into internal buffer.
     pub fn add_line(&mut self, line: Line) {
         self.lines.push(line);
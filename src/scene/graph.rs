@@ -22,7 +22,7 @@
 //! just by linking nodes to each other. Good example of this is skeleton which
 //! is used in skinning (animating 3d model by set of bones).
 
-use crate::scene::base::PropertyValue;
+use crate::scene::base::{BillboardMode, PropertyValue};
 use crate::{
     asset::ResourceState,
     core::{
@@ -32,15 +32,21 @@ use crate::{
             Handle, Pool, PoolIterator, PoolIteratorMut, PoolPairIterator, PoolPairIteratorMut,
             Ticket,
         },
+        profile_scope,
         visitor::{Visit, VisitResult, Visitor},
         VecExtensions,
     },
-    resource::model::NodeMapping,
-    scene::{node::Node, transform::TransformBuilder, visibility::VisibilityCache},
-    utils::log::{Log, MessageKind},
+    resource::{model::NodeMapping, texture::Texture},
+    scene::{constraint, node::Node, transform::TransformBuilder, visibility::VisibilityCache},
+    utils::log::{log_error, log_info, log_warn},
 };
 use fxhash::FxHashMap;
-use std::ops::{Index, IndexMut};
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    ops::{Index, IndexMut},
+    path::{Path, PathBuf},
+};
 
 /// See module docs.
 #[derive(Debug)]
@@ -48,6 +54,10 @@ pub struct Graph {
     root: Handle<Node>,
     pool: Pool<Node>,
     stack: Vec<Handle<Node>>,
+    recomputed_transforms: usize,
+    // Nodes that were skipped during last update of constraints, it is used to avoid spamming
+    // the log with the same warning every frame.
+    constraint_cycle: Vec<Handle<Node>>,
 }
 
 impl Default for Graph {
@@ -56,6 +66,8 @@ impl Default for Graph {
             root: Handle::NONE,
             pool: Pool::new(),
             stack: Vec::new(),
+            recomputed_transforms: 0,
+            constraint_cycle: Default::default(),
         }
     }
 }
@@ -98,6 +110,10 @@ fn remap_handles(old_new_mapping: &FxHashMap<Handle<Node>, Handle<Node>>, dest_g
             }
         }
 
+        for constraint in new_node.constraints_mut() {
+            constraint.kind.remap_handles(old_new_mapping);
+        }
+
         // LODs also have handles that must be remapped too.
         if let Some(lod_group) = new_node.lod_group_mut() {
             for level in lod_group.levels.iter_mut() {
@@ -127,6 +143,8 @@ impl Graph {
             stack: Vec::new(),
             root,
             pool,
+            recomputed_transforms: 0,
+            constraint_cycle: Default::default(),
         }
     }
 
@@ -138,6 +156,7 @@ impl Graph {
         let children = node.children.clone();
         node.children.clear();
         let handle = self.pool.spawn(node);
+        self.register_debug_name(handle);
         if self.root.is_some() {
             self.link_nodes(handle, self.root);
         }
@@ -147,6 +166,18 @@ impl Graph {
         handle
     }
 
+    // Remembers current name of a node in the pool, so it could be shown in panic messages caused
+    // by stale handles. Does nothing in release builds.
+    #[inline]
+    #[cfg_attr(not(debug_assertions), allow(unused_variables))]
+    fn register_debug_name(&mut self, handle: Handle<Node>) {
+        #[cfg(debug_assertions)]
+        {
+            let name = self.pool[handle].name_owned();
+            self.pool.set_debug_name(handle, &name);
+        }
+    }
+
     /// Tries to borrow mutable references to two nodes at the same time by given handles. Will
     /// panic if handles overlaps (points to same node).
     pub fn get_two_mut(&mut self, nodes: (Handle<Node>, Handle<Node>)) -> (&mut Node, &mut Node) {
@@ -194,6 +225,7 @@ impl Graph {
     /// physics, etc. You should prefer to use [Scene::remove_node](crate::scene::Scene::remove_node) -
     /// it automatically breaks all associations between nodes.
     #[inline]
+    #[cfg_attr(all(debug_assertions, feature = "pool_free_location"), track_caller)]
     pub fn remove_node(&mut self, node_handle: Handle<Node>) {
         self.unlink_internal(node_handle);
 
@@ -203,6 +235,8 @@ impl Graph {
             for &child in self.pool[handle].children().iter() {
                 self.stack.push(child);
             }
+            // Node could be renamed after it was added to the graph.
+            self.register_debug_name(handle);
             self.pool.free(handle);
         }
     }
@@ -226,6 +260,8 @@ impl Graph {
         self.unlink_internal(child);
         self.pool[child].parent = parent;
         self.pool[parent].children.push(child);
+        // Global transform of the child depends on its new parent now.
+        self.pool[child].local_transform().mark_changed();
     }
 
     /// Unlinks specified node from its parent and attaches it to root graph node.
@@ -302,6 +338,47 @@ impl Graph {
         self.find(self.root, cmp)
     }
 
+    /// Replaces textures on every mesh in the subtree starting from given node (including the node
+    /// itself). A texture is replaced if its path is in the map. Returns the amount of replaced
+    /// textures. This could be used for character skins or team colors, see
+    /// [`crate::scene::mesh::Mesh::remap_textures_with`] for details.
+    pub fn remap_textures<S: BuildHasher>(
+        &mut self,
+        root: Handle<Node>,
+        textures: &HashMap<PathBuf, Texture, S>,
+    ) -> usize {
+        self.remap_textures_with(root, |path, _| textures.get(path).cloned())
+    }
+
+    /// Does the same as [`Self::remap_textures`], but textures are matched by file stem (file name
+    /// without extension), so `data/skins/Mutant_diffuse.png` is matched by `Mutant_diffuse` key.
+    pub fn remap_textures_by_stem<S: BuildHasher>(
+        &mut self,
+        root: Handle<Node>,
+        textures: &HashMap<String, Texture, S>,
+    ) -> usize {
+        self.remap_textures_with(root, |path, _| {
+            path.file_stem()
+                .and_then(|stem| textures.get(&*stem.to_string_lossy()))
+                .cloned()
+        })
+    }
+
+    /// Replaces textures on every mesh in the subtree starting from given node using given
+    /// closure, see [`crate::scene::mesh::Mesh::remap_textures_with`].
+    pub fn remap_textures_with<F>(&mut self, root: Handle<Node>, mut func: F) -> usize
+    where
+        F: FnMut(&Path, &Texture) -> Option<Texture>,
+    {
+        let mut count = 0;
+        for handle in self.traverse_handle_iter(root).collect::<Vec<_>>() {
+            if let Node::Mesh(mesh) = &mut self.pool[handle] {
+                count += mesh.remap_textures_with(&mut func);
+            }
+        }
+        count
+    }
+
     /// Creates deep copy of node with all children. This is relatively heavy operation!
     /// In case if any error happened it returns `Handle::NONE`. This method can be used
     /// to create exact copy of given node hierarchy. For example you can prepare rocket
@@ -474,7 +551,7 @@ impl Graph {
     }
 
     pub(in crate) fn resolve(&mut self) {
-        Log::writeln(MessageKind::Information, "Resolving graph...".to_owned());
+        log_info!("Resolving graph...");
 
         self.update_hierarchical_data();
 
@@ -580,10 +657,25 @@ impl Graph {
                             if let (Node::Mesh(mesh), Node::Mesh(resource_mesh)) =
                                 (node, resource_node)
                             {
+                                // Texture coordinates transform and mip bias are set per
+                                // instance, keep them from the saved surfaces.
+                                let uv_settings = mesh
+                                    .surfaces()
+                                    .iter()
+                                    .map(|s| (s.uv_transform(), s.mip_bias()))
+                                    .collect::<Vec<_>>();
                                 mesh.clear_surfaces();
-                                for resource_surface in resource_mesh.surfaces() {
-                                    mesh.add_surface(resource_surface.clone());
+                                for (i, resource_surface) in
+                                    resource_mesh.surfaces().iter().enumerate()
+                                {
+                                    let mut surface = resource_surface.clone();
+                                    if let Some(&(uv_transform, mip_bias)) = uv_settings.get(i) {
+                                        surface.set_uv_transform(uv_transform);
+                                        surface.set_mip_bias(mip_bias);
+                                    }
+                                    mesh.add_surface(surface);
                                 }
+                                mesh.apply_texture_overrides();
                             }
                         }
                     }
@@ -595,12 +687,9 @@ impl Graph {
             }
         }
 
-        Log::writeln(
-            MessageKind::Information,
-            "Original handles resolved!".to_owned(),
-        );
+        log_info!("Original handles resolved!");
 
-        Log::writeln(MessageKind::Information, "Checking integrity...".to_owned());
+        log_info!("Checking integrity...");
 
         // Check integrity - if a node was added in resource, it must be also added in the graph.
         // However if a node was deleted in resource, we must leave it the graph because there
@@ -630,14 +719,11 @@ impl Graph {
 
                 if original.is_none() {
                     let instance = &self.pool[instance];
-                    Log::writeln(
-                        MessageKind::Warning,
-                        format!(
-                            "There is an instance of resource {} \
+                    log_warn!(
+                        "There is an instance of resource {} \
                     but original node {} cannot be found!",
-                            data.path.display(),
-                            instance.name()
-                        ),
+                        data.path.display(),
+                        instance.name()
                     );
 
                     continue;
@@ -652,12 +738,9 @@ impl Graph {
                     if resource_node_handle != resource_graph.root
                         && self.find_by_name(instance, resource_node.name()).is_none()
                     {
-                        Log::writeln(
-                            MessageKind::Warning,
-                            format!(
-                                "Instance of node {} is missing. Restoring integrity...",
-                                resource_node.name()
-                            ),
+                        log_warn!(
+                            "Instance of node {} is missing. Restoring integrity...",
+                            resource_node.name()
                         );
 
                         // Instantiate missing node.
@@ -697,12 +780,10 @@ impl Graph {
             }
         }
 
-        Log::writeln(
-            MessageKind::Information,
-            format!(
-                "Integrity restored for {} instances! {} new nodes were added!",
-                instance_count, restored_count
-            ),
+        log_info!(
+            "Integrity restored for {} instances! {} new nodes were added!",
+            instance_count,
+            restored_count
         );
 
         // Taking second reference to self is safe here because we need it only
@@ -726,15 +807,10 @@ impl Graph {
                         let copy = graph.find_copy_of(root_handle, *bone_handle);
 
                         if copy.is_none() {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!(
-                                    "Unable to find bone with name {} \
+                            log_error!("Unable to find bone with name {} \
                                                  starting from node {} in the graph! Bone handle will be removed!",
                                     graph[*bone_handle].name(),
-                                    graph[root_handle].name()
-                                ),
-                            );
+                                    graph[root_handle].name());
                         }
 
                         *bone_handle = copy;
@@ -755,39 +831,146 @@ impl Graph {
             }
         }
 
-        Log::writeln(
-            MessageKind::Information,
-            "Graph resolved successfully!".to_owned(),
-        );
+        log_info!("Graph resolved successfully!");
     }
 
-    /// Calculates local and global transform, global visibility for each node in graph.
+    /// Calculates local and global transform, global visibility and render mask for each node in graph.
     /// Normally you not need to call this method directly, it will be called automatically
     /// on each frame. However there is one use case - when you setup complex hierarchy and
     /// need to know global transform of nodes before entering update loop, then you can call
     /// this method.
+    ///
+    /// Global transforms are recalculated only for nodes whose local transform (or local transform
+    /// of any of their ancestors) was changed since last call, see
+    /// [`Base::global_transform_changed`](crate::scene::base::Base::global_transform_changed).
+    /// Billboard nodes and their descendants are always recalculated, because the renderer
+    /// modifies their global transforms for each camera.
     pub fn update_hierarchical_data(&mut self) {
-        fn update_recursively(graph: &Graph, node_handle: Handle<Node>) {
+        fn update_recursively(
+            graph: &Graph,
+            node_handle: Handle<Node>,
+            parent_changed: bool,
+            recomputed: &mut usize,
+        ) {
             let node = &graph.pool[node_handle];
 
-            let (parent_global_transform, parent_visibility) =
+            let (parent_global_transform, parent_visibility, parent_render_mask) =
                 if let Some(parent) = graph.pool.try_borrow(node.parent()) {
-                    (parent.global_transform(), parent.global_visibility())
+                    (
+                        parent.global_transform(),
+                        parent.global_visibility(),
+                        parent.global_render_mask(),
+                    )
                 } else {
-                    (Matrix4::identity(), true)
+                    (Matrix4::identity(), true, u32::MAX)
                 };
 
-            node.global_transform
-                .set(parent_global_transform * node.local_transform().matrix());
+            // Flag must be reset even if parent has changed.
+            let changed = node.local_transform().take_changed()
+                || parent_changed
+                || node.billboard_mode() != BillboardMode::None;
+            if changed {
+                node.global_transform
+                    .set(parent_global_transform * node.local_transform().matrix());
+                *recomputed += 1;
+            }
+            node.global_transform_changed.set(changed);
             node.global_visibility
                 .set(parent_visibility && node.visibility());
+            node.global_render_mask
+                .set(node.render_mask().unwrap_or(parent_render_mask));
 
             for &child in node.children() {
-                update_recursively(graph, child);
+                update_recursively(graph, child, changed, recomputed);
+            }
+        }
+
+        let mut recomputed = 0;
+        update_recursively(self, self.root, false, &mut recomputed);
+        self.recomputed_transforms = recomputed;
+    }
+
+    /// Applies [constraints](crate::scene::constraint) of every node to local transforms of nodes
+    /// in dependency order. Nodes that form a dependency cycle are skipped, and a warning is
+    /// written to the log when the set of such nodes changes.
+    ///
+    /// The scene calls this method at the beginning of its update, so there is no need to call it
+    /// manually.
+    pub fn update_constraints(&mut self, dt: f32) {
+        let (order, cycle) = constraint::evaluation_order(self);
+
+        if cycle != self.constraint_cycle {
+            if !cycle.is_empty() {
+                log_warn!(
+                    "Constraints of nodes {:?} form a dependency cycle and will be ignored!",
+                    cycle
+                        .iter()
+                        .map(|&handle| self.pool[handle].name())
+                        .collect::<Vec<_>>()
+                );
             }
+            self.constraint_cycle = cycle;
         }
 
-        update_recursively(self, self.root);
+        for handle in order {
+            constraint::solve(self, handle, dt);
+        }
+    }
+
+    /// Returns amount of nodes whose global transform was recalculated during last update of
+    /// hierarchical data.
+    pub fn recomputed_transforms(&self) -> usize {
+        self.recomputed_transforms
+    }
+
+    /// Applies [billboard modes](crate::scene::base::BillboardMode) of nodes for a camera with
+    /// given global transform, or removes billboard rotation if there is no camera. Only global
+    /// transforms of billboard nodes and their descendants are changed, they are calculated from
+    /// local transforms, so it is safe to call the method for multiple cameras in a row. Returns
+    /// `true` if the graph has at least one billboard node.
+    ///
+    /// The renderer calls this method for each camera, so there is no need to call it manually.
+    pub fn update_billboards(&self, camera_transform: Option<&Matrix4<f32>>) -> bool {
+        fn update_recursively(
+            graph: &Graph,
+            node_handle: Handle<Node>,
+            camera_transform: Option<&Matrix4<f32>>,
+            parent_is_billboard: bool,
+        ) -> bool {
+            let node = &graph.pool[node_handle];
+
+            let is_billboard = node.billboard_mode() != BillboardMode::None;
+            if parent_is_billboard || is_billboard {
+                let parent_global_transform = graph
+                    .pool
+                    .try_borrow(node.parent())
+                    .map_or_else(Matrix4::identity, |p| p.global_transform());
+                let global_transform = parent_global_transform * node.local_transform().matrix();
+                node.global_transform.set(match camera_transform {
+                    Some(camera_transform) => node
+                        .billboard_mode()
+                        .apply(global_transform, camera_transform),
+                    None => global_transform,
+                });
+                // Bounding box depends on global transform and it is used for culling.
+                if let Node::Mesh(mesh) = node {
+                    mesh.update(graph);
+                }
+            }
+
+            let mut has_billboards = is_billboard;
+            for &child in node.children() {
+                has_billboards |= update_recursively(
+                    graph,
+                    child,
+                    camera_transform,
+                    parent_is_billboard || is_billboard,
+                );
+            }
+            has_billboards
+        }
+
+        update_recursively(self, self.root, camera_transform, false)
     }
 
     /// Checks whether given node handle is valid or not.
@@ -797,6 +980,8 @@ impl Graph {
 
     /// Updates nodes in graph using given delta time. There is no need to call it manually.
     pub fn update_nodes(&mut self, frame_size: Vector2<f32>, dt: f32) {
+        profile_scope!("Graph");
+
         self.update_hierarchical_data();
 
         for i in 0..self.pool.get_capacity() {
@@ -820,6 +1005,7 @@ impl Graph {
                             let observer_position = camera.global_position();
                             let z_near = camera.z_near();
                             let z_far = camera.z_far();
+                            let visibility_mask = camera.visibility_mask();
                             let frustum =
                                 Frustum::from(camera.view_projection_matrix()).unwrap_or_default();
                             new_cache.update(
@@ -827,6 +1013,7 @@ impl Graph {
                                 observer_position,
                                 z_near,
                                 z_far,
+                                visibility_mask,
                                 Some(&[&frustum]),
                             );
                             // We have to re-borrow camera again because borrow check cannot proof that
@@ -841,7 +1028,12 @@ impl Graph {
                         }
                         Node::ParticleSystem(particle_system) => particle_system.update(dt),
                         Node::Terrain(terrain) => terrain.update(),
-                        Node::Mesh(_) => self.pool.at(i).unwrap().as_mesh().update(self),
+                        Node::Water(water) => water.update(dt),
+                        Node::WorldText(text) => text.update(),
+                        Node::Mesh(mesh) => {
+                            mesh.update_morph_targets();
+                            self.pool.at(i).unwrap().as_mesh().update(self)
+                        }
                         _ => (),
                     }
                 }
@@ -1191,9 +1383,25 @@ impl Visit for Graph {
 #[cfg(test)]
 mod test {
     use crate::{
-        core::pool::Handle,
-        scene::{base::Base, graph::Graph, node::Node},
+        core::{
+            algebra::{Matrix4, UnitQuaternion, Vector3},
+            math::Matrix4Ext,
+            parking_lot::Mutex,
+            pool::Handle,
+        },
+        resource::texture::{Texture, TextureKind, TexturePixelKind},
+        scene::{
+            base::{Base, BaseBuilder, BillboardMode},
+            graph::Graph,
+            mesh::{
+                surface::{SurfaceBuilder, SurfaceData},
+                MeshBuilder,
+            },
+            node::Node,
+            transform::TransformBuilder,
+        },
     };
+    use std::{collections::HashMap, panic::AssertUnwindSafe, path::PathBuf, sync::Arc};
 
     #[test]
     fn graph_init_test() {
@@ -1202,6 +1410,22 @@ mod test {
         assert_eq!(graph.pool.alive_count(), 1);
     }
 
+    #[test]
+    #[cfg(debug_assertions)]
+    fn graph_stale_handle_diagnostics() {
+        let mut graph = Graph::new();
+        let node = BaseBuilder::new().with_name("Crate").build(&mut graph);
+        graph[node].set_name("Barrel");
+        graph.remove_node(node);
+        assert!(graph.try_get(node).is_none());
+
+        let message = std::panic::catch_unwind(AssertUnwindSafe(|| graph[node].name_owned()))
+            .unwrap_err()
+            .downcast::<String>()
+            .unwrap();
+        assert!(message.contains("\"Barrel\""));
+    }
+
     #[test]
     fn graph_node_test() {
         let mut graph = Graph::new();
@@ -1210,4 +1434,232 @@ mod test {
         graph.add_node(Node::Base(Base::default()));
         assert_eq!(graph.pool.alive_count(), 4);
     }
+
+    #[test]
+    fn graph_render_mask_inheritance_test() {
+        let mut graph = Graph::new();
+        let child = BaseBuilder::new().build(&mut graph);
+        let masked_child = BaseBuilder::new().with_render_mask(0b10).build(&mut graph);
+        let parent = BaseBuilder::new()
+            .with_render_mask(0b01)
+            .with_children(&[child, masked_child])
+            .build(&mut graph);
+        let unmasked = BaseBuilder::new().build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        assert_eq!(graph[parent].global_render_mask(), 0b01);
+        assert_eq!(graph[child].global_render_mask(), 0b01);
+        assert_eq!(graph[masked_child].global_render_mask(), 0b10);
+        assert_eq!(graph[unmasked].global_render_mask(), u32::MAX);
+        assert!(!graph[masked_child].is_visible_by_mask(0b01));
+        assert!(graph[child].is_visible_by_mask(0b01));
+    }
+
+    #[test]
+    fn graph_billboard_test() {
+        let mut graph = Graph::new();
+        let child = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 1.0, 0.0))
+                    .build(),
+            )
+            .build(&mut graph);
+        let billboard = BaseBuilder::new()
+            .with_billboard_mode(BillboardMode::Full)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(1.0, 2.0, 3.0))
+                    .with_local_scale(Vector3::new(2.0, 2.0, 2.0))
+                    .build(),
+            )
+            .with_children(&[child])
+            .build(&mut graph);
+        let vertical = BaseBuilder::new()
+            .with_billboard_mode(BillboardMode::AxisAligned(Vector3::y()))
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(5.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build(&mut graph);
+
+        graph.update_hierarchical_data();
+
+        let camera = Matrix4::new_translation(&Vector3::new(0.0, 5.0, 0.0))
+            * UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 90.0f32.to_radians())
+                .to_homogeneous()
+            * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 30.0f32.to_radians())
+                .to_homogeneous();
+
+        assert!(graph.update_billboards(Some(&camera)));
+
+        let eq = |a: Vector3<f32>, b: Vector3<f32>| (a - b).norm() < 1.0e-4;
+
+        // Position and scale are kept, rotation is taken from camera.
+        let transform = graph[billboard].global_transform();
+        assert!(eq(transform.position(), Vector3::new(1.0, 2.0, 3.0)));
+        assert!(eq(transform.side(), camera.side().scale(2.0)));
+        assert!(eq(transform.up(), camera.up().scale(2.0)));
+        assert!(eq(transform.look(), camera.look().scale(2.0)));
+
+        // Children inherit the rotation.
+        assert!(eq(
+            graph[child].global_position(),
+            Vector3::new(1.0, 2.0, 3.0) + camera.up().scale(2.0)
+        ));
+
+        // Axis-aligned billboard stays vertical and looks away from camera.
+        let transform = graph[vertical].global_transform();
+        assert!(eq(transform.up(), Vector3::y()));
+        assert!(eq(transform.look(), Vector3::x()));
+
+        // Without camera billboard rotation is removed.
+        assert!(graph.update_billboards(None));
+        assert!(eq(
+            graph[billboard].side_vector(),
+            Vector3::new(2.0, 0.0, 0.0)
+        ));
+        assert!(eq(
+            graph[child].global_position(),
+            Vector3::new(1.0, 4.0, 3.0)
+        ));
+    }
+
+    #[test]
+    fn graph_transform_change_tracking_test() {
+        let mut graph = Graph::new();
+        let child = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(0.0, 1.0, 0.0))
+                    .build(),
+            )
+            .build(&mut graph);
+        let parent = BaseBuilder::new()
+            .with_visibility(false)
+            .with_children(&[child])
+            .build(&mut graph);
+        let other = BaseBuilder::new().build(&mut graph);
+
+        graph.update_hierarchical_data();
+        assert!(graph[child].global_transform_changed());
+        assert_eq!(graph.recomputed_transforms(), 4);
+
+        // Nothing has changed, so nothing is recalculated.
+        graph.update_hierarchical_data();
+        assert!(!graph[parent].global_transform_changed());
+        assert!(!graph[child].global_transform_changed());
+        assert_eq!(graph.recomputed_transforms(), 0);
+
+        // Hidden parent is moved, change must be propagated to its children.
+        graph[parent]
+            .local_transform_mut()
+            .set_position(Vector3::new(2.0, 0.0, 0.0));
+        assert!(graph[parent].local_transform().is_changed());
+        graph.update_hierarchical_data();
+        assert!(!graph[parent].local_transform().is_changed());
+        assert!(!graph[parent].global_visibility());
+        assert!(graph[parent].global_transform_changed());
+        assert!(graph[child].global_transform_changed());
+        assert!(!graph[other].global_transform_changed());
+        assert_eq!(graph.recomputed_transforms(), 2);
+        assert_eq!(graph[child].global_position(), Vector3::new(2.0, 1.0, 0.0));
+
+        // Setting the same value is not a change.
+        graph[parent]
+            .local_transform_mut()
+            .set_position(Vector3::new(2.0, 0.0, 0.0));
+        graph.update_hierarchical_data();
+        assert!(!graph[child].global_transform_changed());
+
+        // Re-linking changes global transform too.
+        graph.link_nodes(child, other);
+        graph.update_hierarchical_data();
+        assert!(graph[child].global_transform_changed());
+        assert_eq!(graph.recomputed_transforms(), 1);
+        assert_eq!(graph[child].global_position(), Vector3::new(0.0, 1.0, 0.0));
+
+        // Transform copied from an updated node is not marked as changed by itself, but setting it
+        // to another node is a change.
+        graph.update_hierarchical_data();
+        let transform = graph[parent].local_transform().clone();
+        assert!(!transform.is_changed());
+        graph[other].set_local_transform(transform);
+        graph.update_hierarchical_data();
+        assert!(graph[other].global_transform_changed());
+        assert!(graph[child].global_transform_changed());
+        assert_eq!(graph[child].global_position(), Vector3::new(2.0, 1.0, 0.0));
+    }
+
+    fn make_texture(path: &str) -> Texture {
+        let texture = Texture::from_bytes(
+            TextureKind::Rectangle {
+                width: 1,
+                height: 1,
+            },
+            TexturePixelKind::R8,
+            vec![0],
+            false,
+        )
+        .unwrap();
+        texture.data_ref().set_path(path);
+        texture
+    }
+
+    fn diffuse_path(graph: &Graph, mesh: Handle<Node>) -> PathBuf {
+        graph[mesh].as_mesh().surfaces()[0]
+            .diffuse_texture()
+            .unwrap()
+            .state()
+            .path()
+            .to_path_buf()
+    }
+
+    #[test]
+    fn test_remap_textures() {
+        let mut graph = Graph::new();
+
+        // Two "instances" that share the same material, like instances of a model do.
+        let data = Arc::new(Mutex::new(SurfaceData::make_cube(Matrix4::identity())));
+        let mut surface = SurfaceBuilder::new(data).build();
+        surface
+            .set_diffuse_texture(Some(make_texture("skins/red.png")))
+            .unwrap();
+
+        let a = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![surface.clone()])
+            .build(&mut graph);
+        let a_root = BaseBuilder::new().with_children(&[a]).build(&mut graph);
+        let b = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![surface])
+            .build(&mut graph);
+
+        let mut skins = HashMap::new();
+        skins.insert(
+            PathBuf::from("skins/red.png"),
+            make_texture("skins/blue.png"),
+        );
+        assert_eq!(graph.remap_textures(a_root, &skins), 1);
+        assert_eq!(diffuse_path(&graph, a), PathBuf::from("skins/blue.png"));
+        // Other instance is not affected.
+        assert_eq!(diffuse_path(&graph, b), PathBuf::from("skins/red.png"));
+        assert_eq!(graph[a].as_mesh().texture_overrides().len(), 1);
+
+        // Surfaces restored from a resource get the replacement back.
+        let restored = graph[b].as_mesh().surfaces()[0].clone();
+        let mesh = graph[a].as_mesh_mut();
+        mesh.clear_surfaces();
+        mesh.add_surface(restored);
+        mesh.apply_texture_overrides();
+        assert_eq!(diffuse_path(&graph, a), PathBuf::from("skins/blue.png"));
+
+        // Switching back to the original texture removes the override.
+        let mut skins = HashMap::new();
+        skins.insert("blue".to_owned(), make_texture("skins/red.png"));
+        assert_eq!(graph.remap_textures_by_stem(a_root, &skins), 1);
+        assert_eq!(diffuse_path(&graph, a), PathBuf::from("skins/red.png"));
+        assert!(graph[a].as_mesh().texture_overrides().is_empty());
+    }
 }
@@ -0,0 +1,499 @@
+//! Impostor is a cheap replacement of a distant object: a camera-facing quad with a picture of the
+//! object, that was baked from a few angles around it.
+//!
+//! For more info see [`Impostor`].
+
+use crate::{
+    core::{
+        algebra::Vector2,
+        color::Color,
+        inspect::{Inspect, PropertyInfo},
+        math::aabb::AxisAlignedBoundingBox,
+        parking_lot::Mutex,
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    resource::{model::Model, texture::Texture},
+    scene::{
+        base::{Base, BaseBuilder},
+        graph::Graph,
+        light::LightingMode,
+        node::Node,
+    },
+};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+/// Default amount of angles from which an object is baked into an atlas.
+pub const DEFAULT_IMPOSTOR_ANGLE_COUNT: u32 = 8;
+
+/// Max amount of angles from which an object can be baked into an atlas.
+pub const MAX_IMPOSTOR_ANGLE_COUNT: u32 = 64;
+
+/// Default size (in texels) of a single frame of an atlas.
+pub const DEFAULT_IMPOSTOR_RESOLUTION: u32 = 128;
+
+/// Max size (in texels) of a single frame of an atlas.
+pub const MAX_IMPOSTOR_RESOLUTION: u32 = 512;
+
+/// Inner data of an [`ImpostorAtlas`].
+#[derive(Debug, Visit)]
+pub struct ImpostorAtlasData {
+    model: Option<Model>,
+    angle_count: u32,
+    resolution: u32,
+    // Baked data is not saved, atlases are re-baked when a scene is loaded.
+    #[visit(skip)]
+    texture: Option<Texture>,
+    #[visit(skip)]
+    bounds: AxisAlignedBoundingBox,
+    #[visit(skip)]
+    failed: bool,
+}
+
+impl Default for ImpostorAtlasData {
+    fn default() -> Self {
+        Self {
+            model: None,
+            angle_count: DEFAULT_IMPOSTOR_ANGLE_COUNT,
+            resolution: DEFAULT_IMPOSTOR_RESOLUTION,
+            texture: None,
+            bounds: Default::default(),
+            failed: false,
+        }
+    }
+}
+
+/// Atlas of pictures of a model, taken from a number of evenly distributed horizontal angles around
+/// the model. Atlases are shared: every clone of an atlas points to the same data, so a forest of
+/// a thousand trees of the same kind needs only one atlas and it is baked only once.
+///
+/// Atlases are baked by the renderer, when the model is fully loaded. Baking is done once, baked
+/// pictures are not saved together with a scene, atlas is re-baked on load instead.
+#[derive(Debug, Clone, Default)]
+pub struct ImpostorAtlas(Arc<Mutex<ImpostorAtlasData>>);
+
+impl Visit for ImpostorAtlas {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        self.0.visit(name, visitor)
+    }
+}
+
+impl ImpostorAtlas {
+    /// Creates new atlas of the given model. `angle_count` defines how many pictures will be
+    /// taken around the model, more angles gives smoother transitions between pictures when
+    /// camera moves around an impostor. `resolution` is the size of each picture in texels.
+    pub fn new(model: Model, angle_count: u32, resolution: u32) -> Self {
+        Self(Arc::new(Mutex::new(ImpostorAtlasData {
+            model: Some(model),
+            angle_count: angle_count.clamp(1, MAX_IMPOSTOR_ANGLE_COUNT),
+            resolution: resolution.clamp(1, MAX_IMPOSTOR_RESOLUTION),
+            ..Default::default()
+        })))
+    }
+
+    /// Returns the model from which the atlas is baked.
+    pub fn model(&self) -> Option<Model> {
+        self.0.lock().model.clone()
+    }
+
+    /// Returns amount of angles (pictures) in the atlas.
+    pub fn angle_count(&self) -> u32 {
+        self.0.lock().angle_count
+    }
+
+    /// Returns size of a single picture in texels.
+    pub fn resolution(&self) -> u32 {
+        self.0.lock().resolution
+    }
+
+    /// Returns `true` if the atlas is baked and can be used for rendering.
+    pub fn is_baked(&self) -> bool {
+        self.0.lock().texture.is_some()
+    }
+
+    /// Discards baked pictures, the atlas will be baked again on next frame. Use it when the
+    /// model was changed.
+    pub fn request_rebake(&self) {
+        let mut data = self.0.lock();
+        data.texture = None;
+        data.failed = false;
+    }
+
+    /// Returns texture with baked pictures, if any.
+    pub fn texture(&self) -> Option<Texture> {
+        self.0.lock().texture.clone()
+    }
+
+    /// Returns local-space bounds of the model, that were calculated during baking.
+    pub fn bounds(&self) -> AxisAlignedBoundingBox {
+        self.0.lock().bounds
+    }
+
+    /// Returns amount of columns and rows of pictures in the atlas texture.
+    pub fn grid_size(&self) -> (u32, u32) {
+        grid_size(self.angle_count())
+    }
+
+    pub(in crate) fn key(&self) -> usize {
+        &*self.0 as *const _ as usize
+    }
+
+    pub(in crate) fn needs_bake(&self) -> bool {
+        let data = self.0.lock();
+        data.texture.is_none() && !data.failed && data.model.is_some()
+    }
+
+    pub(in crate) fn finish_bake(&self, texture: Texture, bounds: AxisAlignedBoundingBox) {
+        let mut data = self.0.lock();
+        data.texture = Some(texture);
+        data.bounds = bounds;
+    }
+
+    /// Marks the atlas as not bakeable, for example when its model failed to load.
+    pub(in crate) fn fail_bake(&self) {
+        self.0.lock().failed = true;
+    }
+
+    /// Returns half-extents of a frame of the atlas: the first component is horizontal radius of
+    /// the model, the second one is vertical half-size of the model.
+    pub(in crate) fn frame_half_size(&self) -> Vector2<f32> {
+        frame_half_size(&self.bounds())
+    }
+}
+
+pub(in crate) fn grid_size(angle_count: u32) -> (u32, u32) {
+    let angle_count = angle_count.max(1);
+    let columns = (angle_count as f32).sqrt().ceil() as u32;
+    let rows = (angle_count + columns - 1) / columns;
+    (columns, rows)
+}
+
+pub(in crate) fn frame_half_size(bounds: &AxisAlignedBoundingBox) -> Vector2<f32> {
+    let half = bounds.half_extents();
+    Vector2::new(
+        (half.x * half.x + half.z * half.z).sqrt().max(0.01),
+        half.y.max(0.01),
+    )
+}
+
+/// Impostor replaces a distant object with a camera-facing quad textured with a picture of the
+/// object. Pictures are taken from a number of horizontal angles around the object and stored in
+/// a shared [`ImpostorAtlas`], the impostor shows the picture that was taken from the angle that
+/// is closest to the direction to the camera. This is mostly useful for vegetation and other
+/// static props that are scattered in large amounts over big open areas.
+///
+/// # Detailed object
+///
+/// Children of the impostor are the detailed object, usually it is an instance of the same model
+/// the atlas was baked from, placed with identity local transform. When the distance to the
+/// camera is less than [switch distance](Impostor::set_switch_distance), children are drawn as
+/// usual. Farther than that, the children are hidden and only the quad is drawn.
+///
+/// # Transition
+///
+/// The quad fades in over the detailed object in the band of [fade distance](Impostor::set_fade_distance)
+/// before the switch distance, so there is no sudden "pop" when the object is replaced. The detailed
+/// object stays opaque in the band and is hidden at the switch distance.
+///
+/// # Lighting
+///
+/// Pictures are baked using `Forward` render pass of materials, so they are unlit. The impostor is
+/// then lit the same way as lit sprites: by ambient light and the strongest lights at its position,
+/// shadows are ignored. Use [`LightingMode::Unlit`] if the pictures should be drawn as is.
+///
+/// # Limitations
+///
+/// Pictures are taken around vertical axis of the object only, so the impostor looks correct when it
+/// is seen from the side, but not from above. Shadows are still cast by the detailed object, shadow
+/// maps ignore distance-based hiding.
+///
+/// # Example
+///
+/// ```no_run
+/// use rg3d::{
+///     core::pool::Handle,
+///     resource::model::Model,
+///     scene::{
+///         base::BaseBuilder,
+///         impostor::{ImpostorAtlas, ImpostorBuilder},
+///         node::Node,
+///         Scene,
+///     },
+/// };
+///
+/// fn create_tree(scene: &mut Scene, model: Model, atlas: ImpostorAtlas) -> Handle<Node> {
+///     let tree = model.instantiate_geometry(scene);
+///     ImpostorBuilder::new(BaseBuilder::new().with_children(&[tree]))
+///         .with_atlas(atlas)
+///         .with_switch_distance(40.0)
+///         .build(&mut scene.graph)
+/// }
+/// ```
+#[derive(Debug, Visit, Inspect)]
+pub struct Impostor {
+    base: Base,
+    #[inspect(skip)]
+    atlas: ImpostorAtlas,
+    #[inspect(min_value = 0.0, step = 0.1)]
+    switch_distance: f32,
+    #[inspect(min_value = 0.0, step = 0.1)]
+    fade_distance: f32,
+    #[inspect(min_value = 0.0, max_value = 1.0, step = 0.01)]
+    alpha_cutoff: f32,
+    lighting_mode: LightingMode,
+    color: Color,
+}
+
+impl Deref for Impostor {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for Impostor {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl Default for Impostor {
+    fn default() -> Self {
+        ImpostorBuilder::new(BaseBuilder::new()).build_impostor()
+    }
+}
+
+impl Impostor {
+    /// Creates a raw copy of the impostor, the copy shares the atlas with the original.
+    pub fn raw_copy(&self) -> Self {
+        Self {
+            base: self.base.raw_copy(),
+            atlas: self.atlas.clone(),
+            switch_distance: self.switch_distance,
+            fade_distance: self.fade_distance,
+            alpha_cutoff: self.alpha_cutoff,
+            lighting_mode: self.lighting_mode,
+            color: self.color,
+        }
+    }
+
+    /// Sets new atlas of the impostor.
+    pub fn set_atlas(&mut self, atlas: ImpostorAtlas) {
+        self.atlas = atlas;
+    }
+
+    /// Returns atlas of the impostor.
+    pub fn atlas(&self) -> &ImpostorAtlas {
+        &self.atlas
+    }
+
+    /// Sets distance to a camera at which the detailed object is replaced with the impostor.
+    /// Default value is 50.0.
+    pub fn set_switch_distance(&mut self, distance: f32) {
+        self.switch_distance = distance.max(0.0);
+    }
+
+    /// Returns current switch distance.
+    pub fn switch_distance(&self) -> f32 {
+        self.switch_distance
+    }
+
+    /// Sets width of the band before switch distance in which the impostor fades in. Default
+    /// value is 5.0.
+    pub fn set_fade_distance(&mut self, distance: f32) {
+        self.fade_distance = distance.max(0.0);
+    }
+
+    /// Returns current fade distance.
+    pub fn fade_distance(&self) -> f32 {
+        self.fade_distance
+    }
+
+    /// Sets alpha threshold, pixels of pictures with alpha less than the threshold are discarded.
+    /// Default value is 0.5.
+    pub fn set_alpha_cutoff(&mut self, alpha_cutoff: f32) {
+        self.alpha_cutoff = alpha_cutoff.clamp(0.0, 1.0);
+    }
+
+    /// Returns current alpha threshold.
+    pub fn alpha_cutoff(&self) -> f32 {
+        self.alpha_cutoff
+    }
+
+    /// Sets lighting mode of the impostor. Default is [`LightingMode::Lit`].
+    pub fn set_lighting_mode(&mut self, lighting_mode: LightingMode) {
+        self.lighting_mode = lighting_mode;
+    }
+
+    /// Returns current lighting mode.
+    pub fn lighting_mode(&self) -> LightingMode {
+        self.lighting_mode
+    }
+
+    /// Sets color that is multiplied with pictures of the atlas.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns current color.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns opacity of the impostor at given distance to a camera: 0.0 - only the detailed object
+    /// is visible, 1.0 - the impostor is fully opaque. Always 0.0 if the atlas is not baked yet.
+    pub fn fade_factor(&self, distance: f32) -> f32 {
+        if !self.atlas.is_baked() {
+            return 0.0;
+        }
+        fade_factor(distance, self.switch_distance, self.fade_distance)
+    }
+
+    /// Returns `true` if the detailed object (children of the impostor) should be drawn at given
+    /// distance to a camera. The detailed object is visible until the atlas is baked.
+    pub fn is_detail_visible(&self, distance: f32) -> bool {
+        distance < self.switch_distance || !self.atlas.is_baked()
+    }
+
+    /// Returns current **local-space** bounding box of the impostor.
+    pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        if self.atlas.is_baked() {
+            self.atlas.bounds()
+        } else {
+            AxisAlignedBoundingBox::unit()
+        }
+    }
+
+    /// Returns current **world-space** bounding box of the impostor.
+    pub fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.local_bounding_box()
+            .transform(&self.global_transform())
+    }
+}
+
+fn fade_factor(distance: f32, switch_distance: f32, fade_distance: f32) -> f32 {
+    if fade_distance <= f32::EPSILON {
+        if distance >= switch_distance {
+            1.0
+        } else {
+            0.0
+        }
+    } else {
+        ((distance - (switch_distance - fade_distance)) / fade_distance).clamp(0.0, 1.0)
+    }
+}
+
+/// Allows you to create an impostor in a declarative manner.
+pub struct ImpostorBuilder {
+    base_builder: BaseBuilder,
+    atlas: ImpostorAtlas,
+    switch_distance: f32,
+    fade_distance: f32,
+    alpha_cutoff: f32,
+    lighting_mode: LightingMode,
+    color: Color,
+}
+
+impl ImpostorBuilder {
+    /// Creates new builder with default state.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            atlas: Default::default(),
+            switch_distance: 50.0,
+            fade_distance: 5.0,
+            alpha_cutoff: 0.5,
+            lighting_mode: LightingMode::Lit,
+            color: Color::WHITE,
+        }
+    }
+
+    /// Sets desired atlas.
+    pub fn with_atlas(mut self, atlas: ImpostorAtlas) -> Self {
+        self.atlas = atlas;
+        self
+    }
+
+    /// Sets desired switch distance.
+    pub fn with_switch_distance(mut self, distance: f32) -> Self {
+        self.switch_distance = distance;
+        self
+    }
+
+    /// Sets desired fade distance.
+    pub fn with_fade_distance(mut self, distance: f32) -> Self {
+        self.fade_distance = distance;
+        self
+    }
+
+    /// Sets desired alpha threshold.
+    pub fn with_alpha_cutoff(mut self, alpha_cutoff: f32) -> Self {
+        self.alpha_cutoff = alpha_cutoff;
+        self
+    }
+
+    /// Sets desired lighting mode.
+    pub fn with_lighting_mode(mut self, lighting_mode: LightingMode) -> Self {
+        self.lighting_mode = lighting_mode;
+        self
+    }
+
+    /// Sets desired color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn build_impostor(self) -> Impostor {
+        Impostor {
+            base: self.base_builder.build_base(),
+            atlas: self.atlas,
+            switch_distance: self.switch_distance.max(0.0),
+            fade_distance: self.fade_distance.max(0.0),
+            alpha_cutoff: self.alpha_cutoff.clamp(0.0, 1.0),
+            lighting_mode: self.lighting_mode,
+            color: self.color,
+        }
+    }
+
+    /// Creates new Impostor node.
+    pub fn build_node(self) -> Node {
+        Node::Impostor(self.build_impostor())
+    }
+
+    /// Creates new instance of Impostor node and puts it in the given graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Node> {
+        graph.add_node(self.build_node())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::scene::impostor::{fade_factor, grid_size};
+
+    #[test]
+    fn test_impostor_fade_factor() {
+        assert_eq!(fade_factor(10.0, 50.0, 5.0), 0.0);
+        assert_eq!(fade_factor(45.0, 50.0, 5.0), 0.0);
+        assert!((fade_factor(47.5, 50.0, 5.0) - 0.5).abs() < 1e-6);
+        assert_eq!(fade_factor(50.0, 50.0, 5.0), 1.0);
+        assert_eq!(fade_factor(100.0, 50.0, 5.0), 1.0);
+
+        // Without fading the impostor is switched instantly.
+        assert_eq!(fade_factor(49.9, 50.0, 0.0), 0.0);
+        assert_eq!(fade_factor(50.0, 50.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn test_impostor_grid_size() {
+        assert_eq!(grid_size(1), (1, 1));
+        assert_eq!(grid_size(4), (2, 2));
+        assert_eq!(grid_size(8), (3, 3));
+        assert_eq!(grid_size(12), (4, 3));
+        assert_eq!(grid_size(16), (4, 4));
+    }
+}
@@ -15,6 +15,24 @@
 //! Most of light sources supports shadows (via shadows maps) and light scattering,
 //! these are common effects for modern games but still can significantly impact
 //! performance.
+//!
+//! # Falloff
+//!
+//! Point and spot lights fade out with distance, the way they fade is defined by
+//! [`LightFalloff`]. Default falloff is the same as in previous versions of the
+//! engine, it is not physically correct but it is easy to tune. If you need to match
+//! reference lighting, use [`LightFalloff::InverseSquare`] - in this mode intensity of
+//! the light is its luminous intensity in candela (see
+//! [`point::PointLight::set_luminous_flux`] and [`spot::SpotLight::set_luminous_flux`]
+//! to set it in lumens).
+//!
+//! # Lit sprites and particles
+//!
+//! Sprites and particle systems are not part of deferred lighting pass, by default they're
+//! drawn "as is" (see [`LightingMode`]). If lighting mode is set to [`LightingMode::Lit`],
+//! lighting is evaluated on CPU once per node at its position (see [`evaluate_lighting`]), so
+//! the whole sprite gets the same amount of light. Such lighting does not take shadows into
+//! account.
 
 use crate::{
     core::{
@@ -26,7 +44,9 @@ use crate::{
     },
     scene::{
         base::{Base, BaseBuilder},
+        graph::Graph,
         light::{directional::DirectionalLight, point::PointLight, spot::SpotLight},
+        node::Node,
     },
 };
 use std::ops::{Deref, DerefMut};
@@ -47,6 +67,192 @@ pub const DEFAULT_SCATTER_G: f32 = 0.03;
 /// significant value and you'll clearly see light volume with such settings.
 pub const DEFAULT_SCATTER_B: f32 = 0.03;
 
+/// Defines how intensity of point and spot lights decreases with distance.
+#[derive(Visit, Copy, Clone, PartialEq, Debug, Inspect)]
+pub enum LightFalloff {
+    /// Attenuation is `(1 - d² / r²) ^ exponent`, where `d` is distance to the light
+    /// and `r` - its radius. Larger exponent makes light "tighter" around its source,
+    /// smaller - makes it softer. Exponent of 1.0 is default falloff of the engine.
+    Radius {
+        /// Exponent of the falloff curve, must be positive.
+        #[inspect(min_value = 0.0, step = 0.1)]
+        exponent: f32,
+    },
+
+    /// Physically-based inverse square falloff `1 / d²`, smoothly clipped to zero at the
+    /// radius of the light. Intensity of the light is treated as luminous intensity in
+    /// candela, so scenes with this falloff usually need adjusted exposure.
+    InverseSquare,
+}
+
+impl Default for LightFalloff {
+    fn default() -> Self {
+        Self::Radius { exponent: 1.0 }
+    }
+}
+
+impl LightFalloff {
+    /// Returns attenuation factor at given distance from a light with given radius. This
+    /// is CPU counterpart of the function used in lighting shaders.
+    pub fn attenuation(&self, distance: f32, radius: f32) -> f32 {
+        let ratio = distance / radius;
+        match *self {
+            LightFalloff::Radius { exponent } => (1.0 - ratio * ratio).max(0.0).powf(exponent),
+            LightFalloff::InverseSquare => {
+                let window = (1.0 - ratio.powi(4)).max(0.0);
+                window * window / distance.max(MIN_FALLOFF_DISTANCE).powi(2)
+            }
+        }
+    }
+
+    pub(in crate) fn shader_mode(&self) -> i32 {
+        match self {
+            LightFalloff::Radius { .. } => 0,
+            LightFalloff::InverseSquare => 1,
+        }
+    }
+
+    pub(in crate) fn shader_exponent(&self) -> f32 {
+        match *self {
+            LightFalloff::Radius { exponent } => exponent,
+            LightFalloff::InverseSquare => 1.0,
+        }
+    }
+}
+
+// Prevents singularity of inverse square falloff near a light, must match the value in shaders.
+const MIN_FALLOFF_DISTANCE: f32 = 0.01;
+
+/// Default amount of lights that affect a lit sprite or particle system, see [`evaluate_lighting`].
+pub const DEFAULT_MAX_LIGHTS_PER_SPRITE: usize = 4;
+
+/// Defines whether a sprite or a particle system is affected by lights of a scene.
+#[derive(Visit, Copy, Clone, PartialEq, Eq, Debug, Inspect)]
+pub enum LightingMode {
+    /// Node is drawn with its own color only, lights have no effect on it. This is the
+    /// default mode, it is suitable for "emissive" things like fire, sparks, muzzle flashes.
+    Unlit,
+
+    /// Node is lit by ambient light of the scene and by few of the strongest lights around it
+    /// (see [`DEFAULT_MAX_LIGHTS_PER_SPRITE`]). Lighting is evaluated at the position of the node,
+    /// shadows are ignored.
+    Lit,
+}
+
+impl Default for LightingMode {
+    fn default() -> Self {
+        Self::Unlit
+    }
+}
+
+/// Evaluates simple lighting at given point in world space. Result is linear color of the light
+/// (ambient color plus contribution of `max_lights` strongest lights) which can be multiplied with
+/// color of an object at the point.
+///
+/// Only distance attenuation and spot cones are taken into account, there is no normal (lit objects
+/// are assumed to face a light) and no shadows. This is "good enough" approximation for sprites and
+/// particles, which are used with [`LightingMode::Lit`].
+pub fn evaluate_lighting(
+    graph: &Graph,
+    position: Vector3<f32>,
+    ambient_color: Color,
+    max_lights: usize,
+) -> Vector3<f32> {
+    evaluate_lighting_from(
+        graph.linear_iter().filter_map(|node| {
+            if let Node::Light(light) = node {
+                Some(light)
+            } else {
+                None
+            }
+        }),
+        position,
+        ambient_color,
+        max_lights,
+    )
+}
+
+/// Same as [`evaluate_lighting`], but takes lights from given iterator. It allows to collect lights
+/// of a graph once and then use them for many objects.
+pub fn evaluate_lighting_from<'a, I>(
+    lights: I,
+    position: Vector3<f32>,
+    ambient_color: Color,
+    max_lights: usize,
+) -> Vector3<f32>
+where
+    I: IntoIterator<Item = &'a Light>,
+{
+    let mut contributions = lights
+        .into_iter()
+        .filter_map(|light| {
+            if light.global_visibility() {
+                light_contribution(light, position)
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Strongest lights go first.
+    contributions.sort_by(|a: &Vector3<f32>, b| {
+        b.sum()
+            .partial_cmp(&a.sum())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    contributions
+        .into_iter()
+        .take(max_lights)
+        .fold(ambient_color.srgb_to_linear_f32().xyz(), |acc, c| acc + c)
+}
+
+fn light_contribution(light: &Light, position: Vector3<f32>) -> Option<Vector3<f32>> {
+    let color = light.color().srgb_to_linear_f32().xyz() * light.intensity();
+
+    let scale = light.local_transform().scale();
+    let radius_scale = scale.x.max(scale.y).max(scale.z);
+
+    let factor = match light {
+        Light::Directional(_) => 1.0,
+        Light::Point(point) => {
+            let distance = (light.global_position() - position).norm();
+            point
+                .falloff()
+                .attenuation(distance, point.radius() * radius_scale)
+        }
+        Light::Spot(spot) => {
+            let to_light = light.global_position() - position;
+            let distance = to_light.norm();
+            let emit_direction = light
+                .up_vector()
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::z);
+            let cos = emit_direction.dot(&to_light.scale(1.0 / distance.max(f32::EPSILON)));
+            let cone_factor = smoothstep(
+                (spot.full_cone_angle() * 0.5).cos(),
+                (spot.hotspot_cone_angle() * 0.5).cos(),
+                cos,
+            );
+            cone_factor
+                * spot
+                    .falloff()
+                    .attenuation(distance, spot.distance() * radius_scale)
+        }
+    };
+
+    if factor > 0.0 {
+        Some(color.scale(factor))
+    } else {
+        None
+    }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let k = ((x - edge0) / (edge1 - edge0)).max(0.0).min(1.0);
+    k * k * (3.0 - 2.0 * k)
+}
+
 /// Engine supports limited amount of light source kinds
 #[derive(Debug)]
 pub enum Light {
@@ -162,6 +368,7 @@ pub struct BaseLight {
     scatter_enabled: bool,
     #[inspect(min_value = 0.0, step = 0.1)]
     intensity: f32,
+    always_on: bool,
 }
 
 impl Deref for BaseLight {
@@ -187,6 +394,7 @@ impl Default for BaseLight {
             scatter: Vector3::new(DEFAULT_SCATTER_R, DEFAULT_SCATTER_G, DEFAULT_SCATTER_B),
             scatter_enabled: true,
             intensity: 1.0,
+            always_on: false,
         }
     }
 }
@@ -201,6 +409,7 @@ impl Visit for BaseLight {
         self.scatter.visit("ScatterFactor", visitor)?;
         self.scatter_enabled.visit("ScatterEnabled", visitor)?;
         let _ = self.intensity.visit("Intensity", visitor); // Backward compatibility.
+        let _ = self.always_on.visit("AlwaysOn", visitor);
 
         visitor.leave_region()
     }
@@ -282,6 +491,19 @@ impl BaseLight {
         self.scatter_enabled
     }
 
+    /// Sets whether the light should be rendered regardless of its size on screen. By default,
+    /// renderer skips point and spot lights that are too small on screen or don't fit in the
+    /// light budget, see [`crate::renderer::LightLodSettings`]. Use it for important lights, for
+    /// example for a distant lighthouse.
+    pub fn set_always_on(&mut self, always_on: bool) {
+        self.always_on = always_on;
+    }
+
+    /// Returns true if the light is never culled by light LOD.
+    pub fn is_always_on(&self) -> bool {
+        self.always_on
+    }
+
     /// Creates a raw copy of a base light node.
     pub fn raw_copy(&self) -> Self {
         Self {
@@ -291,6 +513,7 @@ impl BaseLight {
             scatter: self.scatter,
             scatter_enabled: self.scatter_enabled,
             intensity: self.intensity,
+            always_on: self.always_on,
         }
     }
 }
@@ -304,6 +527,7 @@ pub struct BaseLightBuilder {
     scatter_factor: Vector3<f32>,
     scatter_enabled: bool,
     intensity: f32,
+    always_on: bool,
 }
 
 impl BaseLightBuilder {
@@ -319,6 +543,7 @@ impl BaseLightBuilder {
             scatter_factor: Vector3::new(DEFAULT_SCATTER_R, DEFAULT_SCATTER_G, DEFAULT_SCATTER_B),
             scatter_enabled: true,
             intensity: 1.0,
+            always_on: false,
         }
     }
 
@@ -352,6 +577,12 @@ impl BaseLightBuilder {
         self
     }
 
+    /// Sets whether the light should be rendered regardless of its size on screen.
+    pub fn with_always_on(mut self, always_on: bool) -> Self {
+        self.always_on = always_on;
+        self
+    }
+
     /// Creates new instance of base light.
     pub fn build(self) -> BaseLight {
         BaseLight {
@@ -361,6 +592,74 @@ impl BaseLightBuilder {
             scatter: self.scatter_factor,
             scatter_enabled: self.scatter_enabled,
             intensity: self.intensity,
+            always_on: self.always_on,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{algebra::Vector3, color::Color},
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            light::{evaluate_lighting, point::PointLightBuilder, BaseLightBuilder, LightFalloff},
+            transform::TransformBuilder,
+        },
+    };
+
+    #[test]
+    fn test_light_falloff() {
+        // Default falloff must match legacy `1 - d² / r²` curve exactly.
+        let default = LightFalloff::default();
+        for &(distance, radius) in [(0.0, 10.0), (2.5, 10.0), (7.0, 10.0), (12.0, 10.0)].iter() {
+            let legacy = (1.0f32 - distance * distance / (radius * radius)).clamp(0.0, 1.0);
+            assert_eq!(default.attenuation(distance, radius), legacy);
         }
+
+        let tight = LightFalloff::Radius { exponent: 2.0 };
+        assert!(tight.attenuation(5.0, 10.0) < default.attenuation(5.0, 10.0));
+
+        let physical = LightFalloff::InverseSquare;
+        assert!((physical.attenuation(1.0, 100.0) - 1.0).abs() < 1.0e-6);
+        assert!((physical.attenuation(2.0, 100.0) - 0.25).abs() < 1.0e-4);
+        assert_eq!(physical.attenuation(100.0, 100.0), 0.0);
+        assert!(physical.attenuation(0.0, 100.0).is_finite());
+    }
+
+    #[test]
+    fn test_evaluate_lighting() {
+        let mut graph = Graph::new();
+
+        let ambient = Color::opaque(0, 0, 0);
+        assert_eq!(
+            evaluate_lighting(&graph, Vector3::default(), ambient, 4),
+            Vector3::default()
+        );
+
+        for x in [0.0, 4.0].iter() {
+            PointLightBuilder::new(BaseLightBuilder::new(
+                BaseBuilder::new().with_local_transform(
+                    TransformBuilder::new()
+                        .with_local_position(Vector3::new(*x, 0.0, 0.0))
+                        .build(),
+                ),
+            ))
+            .with_radius(5.0)
+            .build(&mut graph);
+        }
+        graph.update_hierarchical_data();
+
+        // Nearest light only.
+        let near = evaluate_lighting(&graph, Vector3::default(), ambient, 1);
+        assert!((near.x - 1.0).abs() < 1.0e-5);
+
+        let both = evaluate_lighting(&graph, Vector3::default(), ambient, 4);
+        assert!(both.x > near.x);
+
+        // Out of range of both lights.
+        let far = evaluate_lighting(&graph, Vector3::new(-10.0, 0.0, 0.0), ambient, 4);
+        assert_eq!(far, Vector3::default());
     }
 }
@@ -1,7 +1,7 @@
 //! Point light can be represented as light bulb which hangs on wire - it is
 //! spherical light source which emits light in all directions. It has single
-//! parameter - radius at which intensity will be zero. Intensity of light will
-//! be calculated using inverse square root law.
+//! parameter - radius at which intensity will be zero. The way intensity decreases
+//! with distance is defined by [`LightFalloff`].
 //!
 //! # Light scattering
 //!
@@ -25,7 +25,7 @@ use crate::{
     },
     scene::{
         graph::Graph,
-        light::{BaseLight, BaseLightBuilder, Light},
+        light::{BaseLight, BaseLightBuilder, Light, LightFalloff},
         node::Node,
     },
 };
@@ -39,6 +39,7 @@ pub struct PointLight {
     shadow_bias: f32,
     #[inspect(min_value = 0.0, step = 0.1)]
     radius: f32,
+    falloff: LightFalloff,
 }
 
 impl Deref for PointLight {
@@ -80,12 +81,30 @@ impl PointLight {
         self.shadow_bias
     }
 
+    /// Sets new distance falloff of the light, see [`LightFalloff`] docs for more info.
+    pub fn set_falloff(&mut self, falloff: LightFalloff) {
+        self.falloff = falloff;
+    }
+
+    /// Returns current distance falloff of the light.
+    pub fn falloff(&self) -> LightFalloff {
+        self.falloff
+    }
+
+    /// Sets intensity of the light from its luminous flux in lumens. Point light emits
+    /// light in all directions, so its luminous intensity is `flux / 4π` candela. It
+    /// makes sense only with [`LightFalloff::InverseSquare`].
+    pub fn set_luminous_flux(&mut self, lumens: f32) {
+        self.set_intensity(lumens / (4.0 * std::f32::consts::PI));
+    }
+
     /// Creates a raw copy of a point light node.
     pub fn raw_copy(&self) -> Self {
         Self {
             base_light: self.base_light.raw_copy(),
             radius: self.radius,
             shadow_bias: self.shadow_bias,
+            falloff: self.falloff,
         }
     }
 }
@@ -97,6 +116,7 @@ impl Visit for PointLight {
         self.base_light.visit("BaseLight", visitor)?;
         self.radius.visit("Radius", visitor)?;
         self.shadow_bias.visit("ShadowBias", visitor)?;
+        let _ = self.falloff.visit("Falloff", visitor); // Backward compatibility.
 
         visitor.leave_region()
     }
@@ -108,6 +128,7 @@ impl Default for PointLight {
             base_light: Default::default(),
             shadow_bias: 0.025,
             radius: 10.0,
+            falloff: Default::default(),
         }
     }
 }
@@ -117,6 +138,7 @@ pub struct PointLightBuilder {
     base_light_builder: BaseLightBuilder,
     shadow_bias: f32,
     radius: f32,
+    falloff: LightFalloff,
 }
 
 impl PointLightBuilder {
@@ -126,6 +148,7 @@ impl PointLightBuilder {
             base_light_builder,
             shadow_bias: 0.025,
             radius: 10.0,
+            falloff: Default::default(),
         }
     }
 
@@ -141,12 +164,19 @@ impl PointLightBuilder {
         self
     }
 
+    /// Sets desired distance falloff.
+    pub fn with_falloff(mut self, falloff: LightFalloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
     /// Builds new instance of point light.
     pub fn build_point_light(self) -> PointLight {
         PointLight {
             base_light: self.base_light_builder.build(),
             radius: self.radius,
             shadow_bias: self.shadow_bias,
+            falloff: self.falloff,
         }
     }
 
@@ -6,8 +6,8 @@
 //! two angles will have smooth transition.
 //!
 //! Same as point lights, spot lights have distance attenuation which defines
-//! how intensity of light changes over distance to point in world. Currently
-//! engine uses inverse square root law of distance attenuation.
+//! how intensity of light changes over distance to point in world. The way
+//! intensity decreases is defined by [`LightFalloff`].
 //!
 //! # Light scattering
 //!
@@ -31,7 +31,7 @@ use crate::{
     resource::texture::Texture,
     scene::{
         graph::Graph,
-        light::{BaseLight, BaseLightBuilder, Light},
+        light::{BaseLight, BaseLightBuilder, Light, LightFalloff},
         node::Node,
     },
 };
@@ -50,6 +50,7 @@ pub struct SpotLight {
     #[inspect(min_value = 0.0, step = 0.1)]
     distance: f32,
     cookie_texture: Option<Texture>,
+    falloff: LightFalloff,
 }
 
 impl Deref for SpotLight {
@@ -75,6 +76,7 @@ impl Default for SpotLight {
             shadow_bias: 0.00005,
             distance: 10.0,
             cookie_texture: None,
+            falloff: Default::default(),
         }
     }
 }
@@ -159,6 +161,27 @@ impl SpotLight {
         self.cookie_texture.as_ref()
     }
 
+    /// Sets new distance falloff of the light, see [`LightFalloff`] docs for more info.
+    pub fn set_falloff(&mut self, falloff: LightFalloff) -> &mut Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Returns current distance falloff of the light.
+    pub fn falloff(&self) -> LightFalloff {
+        self.falloff
+    }
+
+    /// Sets intensity of the light from its luminous flux in lumens. The flux is distributed
+    /// over solid angle of the full cone, so luminous intensity is `flux / (2π(1 - cos(θ/2)))`
+    /// candela, where `θ` is full cone angle. Call it after changing cone angles, it makes
+    /// sense only with [`LightFalloff::InverseSquare`].
+    pub fn set_luminous_flux(&mut self, lumens: f32) -> &mut Self {
+        let solid_angle = 2.0 * std::f32::consts::PI * (1.0 - (self.full_cone_angle() * 0.5).cos());
+        self.set_intensity(lumens / solid_angle.max(f32::EPSILON));
+        self
+    }
+
     /// Creates a raw copy of a light node.
     pub fn raw_copy(&self) -> Self {
         Self {
@@ -168,6 +191,7 @@ impl SpotLight {
             shadow_bias: self.shadow_bias,
             distance: self.distance,
             cookie_texture: self.cookie_texture.clone(),
+            falloff: self.falloff,
         }
     }
 }
@@ -183,6 +207,7 @@ impl Visit for SpotLight {
         self.distance.visit("Distance", visitor)?;
         self.shadow_bias.visit("ShadowBias", visitor)?;
         self.cookie_texture.visit("CookieTexture", visitor)?;
+        let _ = self.falloff.visit("Falloff", visitor); // Backward compatibility.
 
         visitor.leave_region()
     }
@@ -196,6 +221,7 @@ pub struct SpotLightBuilder {
     shadow_bias: f32,
     distance: f32,
     cookie_texture: Option<Texture>,
+    falloff: LightFalloff,
 }
 
 impl SpotLightBuilder {
@@ -208,6 +234,7 @@ impl SpotLightBuilder {
             shadow_bias: 0.00005,
             distance: 10.0,
             cookie_texture: None,
+            falloff: Default::default(),
         }
     }
 
@@ -241,6 +268,12 @@ impl SpotLightBuilder {
         self
     }
 
+    /// Sets desired distance falloff.
+    pub fn with_falloff(mut self, falloff: LightFalloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
     /// Creates new spot light.
     pub fn build_spot_light(self) -> SpotLight {
         SpotLight {
@@ -250,6 +283,7 @@ impl SpotLightBuilder {
             shadow_bias: self.shadow_bias,
             distance: self.distance,
             cookie_texture: self.cookie_texture,
+            falloff: self.falloff,
         }
     }
 
@@ -88,6 +88,9 @@ pub enum VertexAttributeUsage {
     BoneWeight = 11,
     /// Bone indices. Usually Vector4<u8>.
     BoneIndices = 12,
+    /// Vertex color. Usually Vector4<u8>, the renderer treats `u8` components of the attribute
+    /// as normalized values.
+    Color = 13,
     /// Maximum amount of attribute kinds.
     Count,
 }
@@ -99,7 +102,7 @@ impl Default for VertexAttributeUsage {
 }
 
 /// Input vertex attribute descriptor used to construct layouts and feed vertex buffer.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug)]
 pub struct VertexAttributeDescriptor {
     /// Claimed usage of the attribute. It could be Position, Normal, etc.
     pub usage: VertexAttributeUsage,
@@ -140,16 +143,39 @@ pub struct VertexAttribute {
 }
 
 /// See module docs.
-#[derive(Clone, Visit, Default, Debug)]
+#[derive(Clone, Default, Debug)]
 pub struct VertexBuffer {
     dense_layout: Vec<VertexAttribute>,
-    sparse_layout: [Option<VertexAttribute>; 13],
+    sparse_layout: [Option<VertexAttribute>; VertexAttributeUsage::Count as usize],
     vertex_size: u8,
     vertex_count: u32,
     data: Vec<u8>,
     data_hash: u64,
 }
 
+impl Visit for VertexBuffer {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.dense_layout.visit("DenseLayout", visitor)?;
+        self.vertex_size.visit("VertexSize", visitor)?;
+        self.vertex_count.visit("VertexCount", visitor)?;
+        self.data.visit("Data", visitor)?;
+        self.data_hash.visit("DataHash", visitor)?;
+
+        // Sparse layout is not serialized, it is restored from dense layout. This way amount of
+        // attribute usages can grow without breaking backward compatibility.
+        if visitor.is_reading() {
+            self.sparse_layout = Default::default();
+            for attribute in self.dense_layout.iter() {
+                self.sparse_layout[attribute.usage as usize] = Some(*attribute);
+            }
+        }
+
+        visitor.leave_region()
+    }
+}
+
 fn calculate_data_hash(data: &[u8]) -> u64 {
     let mut hasher = FxHasher::default();
     data.hash(&mut hasher);
@@ -11,11 +11,13 @@
 use crate::{
     core::{
         algebra::{Matrix4, Point3, Vector3},
+        color::Color,
         inspect::{Inspect, PropertyInfo},
         math::aabb::AxisAlignedBoundingBox,
         pool::Handle,
         visitor::{Visit, VisitResult, Visitor},
     },
+    resource::texture::Texture,
     scene::{
         base::{Base, BaseBuilder},
         graph::Graph,
@@ -29,6 +31,7 @@ use crate::{
 use std::{
     cell::Cell,
     ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
 };
 
 pub mod buffer;
@@ -65,6 +68,24 @@ impl RenderPath {
     }
 }
 
+/// A texture that replaces a texture of the model resource on every surface of a mesh, see
+/// [`Mesh::remap_textures_with`].
+#[derive(Debug, Clone, Default, Visit)]
+pub struct TextureOverride {
+    /// Path of the replaced texture.
+    pub original: PathBuf,
+    /// A texture that is used instead of the original one.
+    pub replacement: Option<Texture>,
+}
+
+impl TextureOverride {
+    fn replacement_path(&self) -> Option<PathBuf> {
+        self.replacement
+            .as_ref()
+            .map(|texture| texture.state().path().to_path_buf())
+    }
+}
+
 /// See module docs.
 #[derive(Debug, Inspect)]
 pub struct Mesh {
@@ -77,8 +98,18 @@ pub struct Mesh {
     #[inspect(skip)]
     world_bounding_box: Cell<AxisAlignedBoundingBox>,
     cast_shadows: bool,
+    receive_shadows: bool,
     render_path: RenderPath,
     decal_layer_index: u8,
+    opacity: f32,
+    color: Color,
+    #[inspect(skip)]
+    texture_overrides: Vec<TextureOverride>,
+    #[inspect(skip)]
+    morph_weights: Vec<f32>,
+    // Whether morph targets must be blended again before rendering.
+    #[inspect(skip)]
+    morph_dirty: bool,
 }
 
 impl Default for Mesh {
@@ -90,8 +121,14 @@ impl Default for Mesh {
             world_bounding_box: Default::default(),
             local_bounding_box_dirty: Cell::new(true),
             cast_shadows: true,
+            receive_shadows: true,
             render_path: RenderPath::Deferred,
             decal_layer_index: 0,
+            opacity: 1.0,
+            color: Color::WHITE,
+            texture_overrides: Default::default(),
+            morph_weights: Default::default(),
+            morph_dirty: false,
         }
     }
 }
@@ -116,7 +153,15 @@ impl Visit for Mesh {
 
         self.base.visit("Common", visitor)?;
         self.cast_shadows.visit("CastShadows", visitor)?;
+        let _ = self.receive_shadows.visit("ReceiveShadows", visitor);
         let _ = self.decal_layer_index.visit("DecalLayerIndex", visitor);
+        let _ = self.opacity.visit("Opacity", visitor);
+        let _ = self.color.visit("Color", visitor);
+        let _ = self.texture_overrides.visit("TextureOverrides", visitor);
+        let _ = self.morph_weights.visit("MorphWeights", visitor);
+        if visitor.is_reading() {
+            self.morph_dirty = true;
+        }
 
         let mut render_path = self.render_path as u32;
         render_path.visit("RenderPath", visitor)?;
@@ -150,6 +195,7 @@ impl Mesh {
     pub fn clear_surfaces(&mut self) {
         self.surfaces.clear();
         self.local_bounding_box_dirty.set(true);
+        self.morph_dirty = true;
     }
 
     /// Adds new surface into mesh, can be used to procedurally generate meshes.
@@ -157,6 +203,7 @@ impl Mesh {
     pub fn add_surface(&mut self, surface: Surface) {
         self.surfaces.push(surface);
         self.local_bounding_box_dirty.set(true);
+        self.morph_dirty = true;
     }
 
     /// Returns true if mesh should cast shadows, false - otherwise.
@@ -171,6 +218,20 @@ impl Mesh {
         self.cast_shadows = cast_shadows;
     }
 
+    /// Returns true if mesh should be shadowed by other objects, false - otherwise.
+    #[inline]
+    pub fn receive_shadows(&self) -> bool {
+        self.receive_shadows
+    }
+
+    /// Sets whether mesh should be shadowed by other objects or not. Lighting of a mesh that does
+    /// not receive shadows is computed without shadow maps, this is useful for small clutter which
+    /// shadows are barely visible anyway.
+    #[inline]
+    pub fn set_receive_shadows(&mut self, receive_shadows: bool) {
+        self.receive_shadows = receive_shadows;
+    }
+
     /// Returns current bounding box. Bounding box presented in *local coordinates*
     /// WARNING: This method does *not* includes bounds of bones!
     pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
@@ -186,7 +247,7 @@ impl Mesh {
         if self.local_bounding_box_dirty.get() {
             let mut bounding_box = AxisAlignedBoundingBox::default();
             for surface in self.surfaces.iter() {
-                let data = surface.data();
+                let data = surface.render_data();
                 let data = data.lock();
                 for view in data.vertex_buffer.iter() {
                     bounding_box
@@ -298,18 +359,251 @@ impl Mesh {
         self.decal_layer_index
     }
 
+    /// Sets new opacity of the mesh, the value will be clamped to `[0; 1]` range. The value is
+    /// passed to shaders as `rg3d_opacity` built-in uniform and it could be used for fade-out
+    /// effects.
+    ///
+    /// # Transparency
+    ///
+    /// A mesh with opacity less than 1.0 is treated as transparent as a whole: all its surfaces
+    /// are excluded from deferred rendering and put into transparent render queue. The same
+    /// happens with surfaces that are marked as transparent (see [`Surface::set_transparent`]).
+    /// Surfaces from the queue are rendered after lighting with depth test on and depth write off,
+    /// they're sorted back-to-front using view-space depth of world bounding box center of their
+    /// meshes. This means that sorting is done per-mesh, not per-triangle, so intersecting
+    /// transparent meshes (or overlapping surfaces of a single mesh) may be blended in wrong order.
+    /// Sprites and particle systems have their own sorting and are rendered before transparent
+    /// meshes. Split such geometry into smaller meshes if it causes visible artifacts.
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity.clamp(0.0, 1.0);
+    }
+
+    /// Returns current opacity of the mesh.
+    pub fn opacity(&self) -> f32 {
+        self.opacity
+    }
+
+    /// Returns true if the mesh has partial opacity.
+    pub fn is_translucent(&self) -> bool {
+        self.opacity < 1.0
+    }
+
+    /// Sets new color of the mesh, the color is multiplied with diffuse color of every surface
+    /// of the mesh. White color (default) does not change the look of the mesh. The value is
+    /// passed to shaders as `rg3d_color` built-in uniform, so it is very cheap to change it every
+    /// frame and it does not break instancing - this could be used for "hit flash" effects,
+    /// fade-ins, team colors, etc.
+    ///
+    /// Keep in mind that alpha channel of the color does not make the mesh transparent, use
+    /// [`Self::set_opacity`] for that.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns current color of the mesh.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Returns amount of morph targets of the mesh, see [`Self::set_morph_weight`].
+    pub fn morph_target_count(&self) -> usize {
+        self.surfaces
+            .iter()
+            .filter_map(|surface| surface.try_data())
+            .map(|data| data.lock().morph_targets.len())
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns index of a morph target with given name.
+    pub fn morph_target_index(&self, name: &str) -> Option<usize> {
+        self.surfaces
+            .iter()
+            .filter_map(|surface| surface.try_data())
+            .find_map(|data| data.lock().morph_target_index(name))
+    }
+
+    /// Sets weight of a morph target with given index. Morph targets are stored in surface data
+    /// (see [`surface::MorphTarget`]), while weights belong to the mesh node, so each instance of
+    /// a model can have its own expression. Weight 0.0 means that the target does not affect the
+    /// mesh, 1.0 means that the target is fully applied; values outside of this range are allowed
+    /// too, they exaggerate the target.
+    ///
+    /// Morph targets are blended on CPU into a copy of surface data, which is done only when
+    /// weights are changed. Up to [`surface::MAX_ACTIVE_MORPH_TARGETS`] targets with non-zero
+    /// weights are applied at once. A mesh with all weights equal to zero has no overhead.
+    ///
+    /// Weights are saved with the scene and they can be animated, see
+    /// [`crate::animation::MorphTrack`].
+    pub fn set_morph_weight(&mut self, index: usize, weight: f32) {
+        if index >= self.morph_weights.len() {
+            if weight == 0.0 {
+                return;
+            }
+            self.morph_weights.resize(index + 1, 0.0);
+        }
+        if self.morph_weights[index] != weight {
+            self.morph_weights[index] = weight;
+            self.morph_dirty = true;
+        }
+    }
+
+    /// Sets weight of a morph target with given name, returns `false` if there is no such target.
+    /// See [`Self::set_morph_weight`] for more info.
+    pub fn set_morph_weight_by_name(&mut self, name: &str, weight: f32) -> bool {
+        match self.morph_target_index(name) {
+            Some(index) => {
+                self.set_morph_weight(index, weight);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns weight of a morph target with given index.
+    pub fn morph_weight(&self, index: usize) -> f32 {
+        self.morph_weights.get(index).cloned().unwrap_or_default()
+    }
+
+    /// Returns weights of morph targets, the array could be shorter than the amount of targets,
+    /// missing weights are zero.
+    pub fn morph_weights(&self) -> &[f32] {
+        &self.morph_weights
+    }
+
+    /// Resets weights of every morph target to zero.
+    pub fn clear_morph_weights(&mut self) {
+        if !self.morph_weights.is_empty() {
+            self.morph_weights.clear();
+            self.morph_dirty = true;
+        }
+    }
+
+    // Blends active morph targets of every surface if weights were changed.
+    pub(in crate) fn update_morph_targets(&mut self) {
+        if self.morph_dirty {
+            self.morph_dirty = false;
+            for surface in self.surfaces.iter_mut() {
+                surface.apply_morph_weights(&self.morph_weights);
+            }
+            self.local_bounding_box_dirty.set(true);
+        }
+    }
+
     /// Creates a raw copy of a mesh node.
     pub fn raw_copy(&self) -> Self {
+        let mut surfaces = self.surfaces.clone();
+        // Blended data is unique for each node, the copy will blend its own.
+        for surface in surfaces.iter_mut() {
+            surface.reset_morph();
+        }
+
         Self {
             base: self.base.raw_copy(),
-            surfaces: self.surfaces.clone(),
+            surfaces,
             local_bounding_box: self.local_bounding_box.clone(),
             local_bounding_box_dirty: self.local_bounding_box_dirty.clone(),
             world_bounding_box: Default::default(),
             cast_shadows: self.cast_shadows,
+            receive_shadows: self.receive_shadows,
             render_path: self.render_path,
             decal_layer_index: self.decal_layer_index,
+            opacity: self.opacity,
+            color: self.color,
+            texture_overrides: self.texture_overrides.clone(),
+            morph_weights: self.morph_weights.clone(),
+            morph_dirty: true,
+        }
+    }
+
+    /// Replaces textures on every surface of the mesh. `func` is called for every texture used by
+    /// materials of the surfaces, if it returns a new texture, the old one is replaced. Materials
+    /// are copied on first change, so other instances of the same model keep their textures.
+    /// Returns the amount of replaced textures.
+    ///
+    /// Replacements are recorded and saved with the scene. Surfaces of model instances are
+    /// restored from the model resource when a scene is loaded, recorded replacements are applied
+    /// to them after that, so the mesh looks the same after loading.
+    ///
+    /// Changes are visible on next frame, the renderer does not cache materials between frames.
+    pub fn remap_textures_with<F>(&mut self, mut func: F) -> usize
+    where
+        F: FnMut(&Path, &Texture) -> Option<Texture>,
+    {
+        let mut replaced = Vec::new();
+        let mut count = 0;
+        for surface in self.surfaces.iter_mut() {
+            count += surface.replace_textures(|texture| {
+                let path = texture.state().path().to_path_buf();
+                let new_texture = func(&path, texture)?;
+                replaced.push((path, new_texture.clone()));
+                Some(new_texture)
+            });
+        }
+
+        for (path, texture) in replaced {
+            self.record_texture_override(path, texture);
         }
+
+        count
+    }
+
+    fn record_texture_override(&mut self, current: PathBuf, texture: Texture) {
+        let new_path = texture.state().path().to_path_buf();
+
+        // Replacement of a replacement - keep the original path, so the override can be applied
+        // to surfaces restored from the resource.
+        if let Some(index) = self
+            .texture_overrides
+            .iter()
+            .position(|o| o.replacement_path().as_deref() == Some(current.as_path()))
+        {
+            if self.texture_overrides[index].original == new_path {
+                self.texture_overrides.remove(index);
+            } else {
+                self.texture_overrides[index].replacement = Some(texture);
+            }
+        } else if let Some(existing) = self
+            .texture_overrides
+            .iter_mut()
+            .find(|o| o.original == current)
+        {
+            existing.replacement = Some(texture);
+        } else {
+            self.texture_overrides.push(TextureOverride {
+                original: current,
+                replacement: Some(texture),
+            });
+        }
+    }
+
+    /// Returns a list of recorded texture replacements, see [`Self::remap_textures_with`].
+    pub fn texture_overrides(&self) -> &[TextureOverride] {
+        &self.texture_overrides
+    }
+
+    /// Removes recorded texture replacements. Textures of surfaces are not changed, but the
+    /// replacements will not be applied when the scene is loaded next time.
+    pub fn clear_texture_overrides(&mut self) {
+        self.texture_overrides.clear();
+    }
+
+    pub(in crate) fn apply_texture_overrides(&mut self) {
+        let overrides = std::mem::take(&mut self.texture_overrides);
+        for surface in self.surfaces.iter_mut() {
+            surface.replace_textures(|texture| {
+                let path = texture.state().path();
+                overrides
+                    .iter()
+                    .find(|o| o.original.as_path() == &*path)
+                    .and_then(|o| o.replacement.clone())
+            });
+        }
+        self.texture_overrides = overrides;
+    }
+
+    pub(in crate) fn texture_overrides_mut(&mut self) -> &mut [TextureOverride] {
+        &mut self.texture_overrides
     }
 }
 
@@ -318,8 +612,12 @@ pub struct MeshBuilder {
     base_builder: BaseBuilder,
     surfaces: Vec<Surface>,
     cast_shadows: bool,
+    receive_shadows: bool,
     render_path: RenderPath,
     decal_layer_index: u8,
+    opacity: f32,
+    color: Color,
+    morph_weights: Vec<f32>,
 }
 
 impl MeshBuilder {
@@ -329,8 +627,12 @@ impl MeshBuilder {
             base_builder,
             surfaces: Default::default(),
             cast_shadows: true,
+            receive_shadows: true,
             render_path: RenderPath::Deferred,
             decal_layer_index: 0,
+            opacity: 1.0,
+            color: Color::WHITE,
+            morph_weights: Default::default(),
         }
     }
 
@@ -346,6 +648,12 @@ impl MeshBuilder {
         self
     }
 
+    /// Sets whether mesh should be shadowed by other objects or not.
+    pub fn with_receive_shadows(mut self, receive_shadows: bool) -> Self {
+        self.receive_shadows = receive_shadows;
+        self
+    }
+
     /// Sets desired render path. Keep in mind that RenderPath::Forward is not fully
     /// implemented and only used to render transparent objects!
     pub fn with_render_path(mut self, render_path: RenderPath) -> Self {
@@ -359,17 +667,41 @@ impl MeshBuilder {
         self
     }
 
+    /// Sets desired opacity of the mesh. See [`Mesh::set_opacity`] for more info.
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets desired color of the mesh. See [`Mesh::set_color`] for more info.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets desired weights of morph targets. See [`Mesh::set_morph_weight`] for more info.
+    pub fn with_morph_weights(mut self, weights: Vec<f32>) -> Self {
+        self.morph_weights = weights;
+        self
+    }
+
     /// Creates new mesh.
     pub fn build_node(self) -> Node {
         Node::Mesh(Mesh {
             base: self.base_builder.build_base(),
             cast_shadows: self.cast_shadows,
+            receive_shadows: self.receive_shadows,
             surfaces: self.surfaces,
             local_bounding_box: Default::default(),
             local_bounding_box_dirty: Cell::new(true),
             render_path: self.render_path,
             decal_layer_index: self.decal_layer_index,
+            opacity: self.opacity,
+            color: self.color,
             world_bounding_box: Default::default(),
+            texture_overrides: Default::default(),
+            morph_dirty: !self.morph_weights.is_empty(),
+            morph_weights: self.morph_weights,
         })
     }
 
@@ -6,31 +6,34 @@
 
 use crate::{
     core::{
-        algebra::{Matrix4, Point3, Vector2, Vector3, Vector4},
+        algebra::{Matrix3, Matrix4, Point3, Vector2, Vector3, Vector4},
+        color::Color,
         hash_combine,
         inspect::{Inspect, PropertyInfo},
         math::TriangleDefinition,
         parking_lot::Mutex,
         pool::{ErasedHandle, Handle},
         sparse::AtomicIndex,
+        sstorage::ImmutableString,
         visitor::{Visit, VisitResult, Visitor},
     },
-    material::Material,
+    material::{shader::SamplerFallback, Material, MaterialError, PropertyValue},
     renderer::{cache::CacheEntry, framework},
+    resource::texture::Texture,
     scene::{
         mesh::{
             buffer::{
                 TriangleBuffer, VertexAttributeDescriptor, VertexAttributeUsage, VertexBuffer,
                 VertexFetchError, VertexReadTrait, VertexWriteTrait,
             },
-            vertex::{OldVertex, StaticVertex},
+            vertex::{OldVertex, StaticVertex, VERTEX_COLOR_DESCRIPTOR},
         },
         node::Node,
     },
     utils::raw_mesh::{RawMesh, RawMeshBuilder},
 };
 use fxhash::FxHasher;
-use std::{hash::Hasher, sync::Arc};
+use std::{cmp::Ordering, hash::Hasher, sync::Arc};
 
 /// Data source of a surface. Each surface can share same data source, this is used
 /// in instancing technique to render multiple instances of same model at different
@@ -41,6 +44,8 @@ pub struct SurfaceData {
     pub vertex_buffer: VertexBuffer,
     /// Current geometry buffer.
     pub geometry_buffer: TriangleBuffer,
+    /// Morph targets (blend shapes) of the surface, see [`MorphTarget`] docs.
+    pub morph_targets: Vec<MorphTarget>,
     // If true - indicates that surface was generated and does not have reference
     // resource. Procedural data will be serialized.
     is_procedural: bool,
@@ -57,11 +62,17 @@ impl SurfaceData {
         Self {
             vertex_buffer,
             geometry_buffer: triangles,
+            morph_targets: Default::default(),
             is_procedural,
             cache_entry: AtomicIndex::unassigned(),
         }
     }
 
+    // Forces the data to be serialized together with its content.
+    pub(in crate) fn set_procedural(&mut self, procedural: bool) {
+        self.is_procedural = procedural;
+    }
+
     /// Applies given transform for every spatial part of the data (vertex position, normal, tangent).
     pub fn transform_geometry(&mut self, transform: &Matrix4<f32>) -> Result<(), VertexFetchError> {
         // Discard scale by inverse and transpose given transform (M^-1)^T
@@ -111,26 +122,39 @@ impl SurfaceData {
     /// a mesh from "untrusted" source, it automatically calculates tangents for you, so
     /// there is no need to call this manually in this case. However if you making your
     /// mesh procedurally, you have to use this method!
+    ///
+    /// Contribution of each triangle is weighted by the angle of its corner at a vertex, which
+    /// gives results very close to MikkTSpace. Mirrored texture coordinates are handled by the
+    /// handedness sign stored in the `w` component of the tangent. Degenerate triangles (the ones
+    /// with zero area in world space or in texture space) are ignored, a vertex that does not
+    /// receive any contribution will get an arbitrary tangent orthogonal to its normal.
     pub fn calculate_tangents(&mut self) -> Result<(), VertexFetchError> {
-        let mut tan1 = vec![Vector3::default(); self.vertex_buffer.vertex_count() as usize];
-        let mut tan2 = vec![Vector3::default(); self.vertex_buffer.vertex_count() as usize];
+        let vertex_count = self.vertex_buffer.vertex_count() as usize;
+        let mut tan1 = vec![Vector3::default(); vertex_count];
+        let mut tan2 = vec![Vector3::default(); vertex_count];
 
         for triangle in self.geometry_buffer.iter() {
             let i1 = triangle[0] as usize;
             let i2 = triangle[1] as usize;
             let i3 = triangle[2] as usize;
 
-            let view1 = &self.vertex_buffer.get(i1).unwrap();
-            let view2 = &self.vertex_buffer.get(i2).unwrap();
-            let view3 = &self.vertex_buffer.get(i3).unwrap();
+            let (view1, view2, view3) = match (
+                self.vertex_buffer.get(i1),
+                self.vertex_buffer.get(i2),
+                self.vertex_buffer.get(i3),
+            ) {
+                (Some(view1), Some(view2), Some(view3)) => (view1, view2, view3),
+                // Silently ignore triangles with invalid indices.
+                _ => continue,
+            };
 
             let v1 = view1.read_3_f32(VertexAttributeUsage::Position)?;
             let v2 = view2.read_3_f32(VertexAttributeUsage::Position)?;
             let v3 = view3.read_3_f32(VertexAttributeUsage::Position)?;
 
-            let w1 = view1.read_3_f32(VertexAttributeUsage::TexCoord0)?;
-            let w2 = view2.read_3_f32(VertexAttributeUsage::TexCoord0)?;
-            let w3 = view3.read_3_f32(VertexAttributeUsage::TexCoord0)?;
+            let w1 = view1.read_2_f32(VertexAttributeUsage::TexCoord0)?;
+            let w2 = view2.read_2_f32(VertexAttributeUsage::TexCoord0)?;
+            let w3 = view3.read_2_f32(VertexAttributeUsage::TexCoord0)?;
 
             let x1 = v2.x - v1.x;
             let x2 = v3.x - v1.x;
@@ -144,37 +168,54 @@ impl SurfaceData {
             let t1 = w2.y - w1.y;
             let t2 = w3.y - w1.y;
 
-            let r = 1.0 / (s1 * t2 - s2 * t1);
+            let uv_area = s1 * t2 - s2 * t1;
+            if uv_area.abs() <= f32::EPSILON {
+                // Zero-area triangle in texture space, it does not define any direction.
+                continue;
+            }
+            let r = 1.0 / uv_area;
 
             let sdir = Vector3::new(
                 (t2 * x1 - t1 * x2) * r,
                 (t2 * y1 - t1 * y2) * r,
                 (t2 * z1 - t1 * z2) * r,
             );
-
-            tan1[i1] += sdir;
-            tan1[i2] += sdir;
-            tan1[i3] += sdir;
-
             let tdir = Vector3::new(
                 (s1 * x2 - s2 * x1) * r,
                 (s1 * y2 - s2 * y1) * r,
                 (s1 * z2 - s2 * z1) * r,
             );
-            tan2[i1] += tdir;
-            tan2[i2] += tdir;
-            tan2[i3] += tdir;
+
+            if !sdir.iter().chain(tdir.iter()).all(|c| c.is_finite()) {
+                continue;
+            }
+
+            for (index, angle) in [
+                (i1, corner_angle(v1, v2, v3)),
+                (i2, corner_angle(v2, v3, v1)),
+                (i3, corner_angle(v3, v1, v2)),
+            ] {
+                tan1[index] += sdir.scale(angle);
+                tan2[index] += tdir.scale(angle);
+            }
         }
 
         let mut vertex_buffer_mut = self.vertex_buffer.modify();
         for (mut view, (t1, t2)) in vertex_buffer_mut.iter_mut().zip(tan1.into_iter().zip(tan2)) {
-            let normal = view.read_3_f32(VertexAttributeUsage::Normal)?;
+            let normal = view
+                .read_3_f32(VertexAttributeUsage::Normal)?
+                .try_normalize(f32::EPSILON)
+                .unwrap_or_else(Vector3::y);
 
             // Gram-Schmidt orthogonalize
             let tangent = (t1 - normal.scale(normal.dot(&t1)))
                 .try_normalize(f32::EPSILON)
-                .unwrap_or_else(|| Vector3::new(0.0, 1.0, 0.0));
-            let handedness = normal.cross(&t1).dot(&t2).signum();
+                .unwrap_or_else(|| orthogonal_vector(&normal));
+            let handedness = if normal.cross(&tangent).dot(&t2) < 0.0 {
+                -1.0
+            } else {
+                1.0
+            };
             view.write_4_f32(
                 VertexAttributeUsage::Tangent,
                 Vector4::new(tangent.x, tangent.y, tangent.z, handedness),
@@ -184,6 +225,92 @@ impl SurfaceData {
         Ok(())
     }
 
+    /// Checks if every vertex of the surface has a usable tangent - finite, non-zero, not parallel
+    /// to the normal and with handedness sign of either `1.0` or `-1.0`. It can be used to validate
+    /// tangents of a mesh that came from "untrusted" source, missing tangent attribute is treated
+    /// as invalid tangents.
+    pub fn has_valid_tangents(&self) -> bool {
+        self.vertex_buffer.iter().all(|view| {
+            match (
+                view.read_3_f32(VertexAttributeUsage::Normal),
+                view.read_4_f32(VertexAttributeUsage::Tangent),
+            ) {
+                (Ok(normal), Ok(tangent)) => {
+                    match (
+                        normal.try_normalize(f32::EPSILON),
+                        tangent.xyz().try_normalize(f32::EPSILON),
+                    ) {
+                        (Some(normal), Some(direction)) => {
+                            normal.dot(&direction).abs() < 0.1
+                                && (tangent.w.abs() - 1.0).abs() <= f32::EPSILON
+                        }
+                        // Zero-length or NaN vectors.
+                        _ => false,
+                    }
+                }
+                _ => false,
+            }
+        })
+    }
+
+    /// Returns true if the surface has vertex colors.
+    pub fn has_vertex_colors(&self) -> bool {
+        self.vertex_buffer
+            .has_attribute(VertexAttributeUsage::Color)
+    }
+
+    /// Returns colors of every vertex of the surface, or `None` if the surface has no vertex colors.
+    pub fn vertex_colors(&self) -> Option<Vec<Color>> {
+        if self.has_vertex_colors() {
+            Some(
+                self.vertex_buffer
+                    .iter()
+                    .map(|view| {
+                        let color = view.read_4_u8(VertexAttributeUsage::Color).unwrap();
+                        Color::from_rgba(color.x, color.y, color.z, color.w)
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Sets colors of vertices of the surface, `colors[i]` will be assigned to i-th vertex. Extra colors
+    /// are ignored, vertices without a matching color keep their current color. Vertex colors are
+    /// stored in optional attribute stream, which is added on first call and filled with white color.
+    /// Vertex colors are multiplied with diffuse color of the standard material.
+    pub fn set_vertex_colors(&mut self, colors: &[Color]) -> Result<(), VertexFetchError> {
+        self.set_vertex_colors_with(|i, _| colors.get(i).cloned())
+    }
+
+    /// Sets colors of vertices of the surface using given function, the function receives index and
+    /// position of a vertex and returns its new color, `None` keeps the current color of the vertex.
+    /// It is useful to color procedural meshes, for example by height of vertices. See
+    /// [`Self::set_vertex_colors`] for more info.
+    pub fn set_vertex_colors_with<F>(&mut self, mut func: F) -> Result<(), VertexFetchError>
+    where
+        F: FnMut(usize, Vector3<f32>) -> Option<Color>,
+    {
+        let mut vertex_buffer = self.vertex_buffer.modify();
+        if !vertex_buffer.has_attribute(VertexAttributeUsage::Color) {
+            // Does not fail, because there is no such attribute.
+            vertex_buffer
+                .add_attribute(VERTEX_COLOR_DESCRIPTOR, [255u8; 4])
+                .unwrap();
+        }
+        for (i, mut view) in vertex_buffer.iter_mut().enumerate() {
+            let position = view.read_3_f32(VertexAttributeUsage::Position)?;
+            if let Some(color) = func(i, position) {
+                view.write_4_u8(
+                    VertexAttributeUsage::Color,
+                    Vector4::new(color.r, color.g, color.b, color.a),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// Creates a quad oriented on oXY plane with unit width and height.
     pub fn make_unit_xy_quad() -> Self {
         let vertices = vec![
@@ -788,6 +915,63 @@ impl SurfaceData {
         self.geometry_buffer.modify().clear();
         self.vertex_buffer.modify().clear();
     }
+
+    /// Returns index of a morph target with given name.
+    pub fn morph_target_index(&self, name: &str) -> Option<usize> {
+        self.morph_targets
+            .iter()
+            .position(|target| target.name == name)
+    }
+
+    // Writes base vertices with weighted offsets of given morph targets into `dest`, which must
+    // have the same layout as the data.
+    fn blend_morph_targets(&self, targets: &[(usize, f32)], dest: &mut VertexBuffer) {
+        let mut dest = dest.modify();
+        for (i, (mut view, base)) in dest.iter_mut().zip(self.vertex_buffer.iter()).enumerate() {
+            if let Ok(mut position) = base.read_3_f32(VertexAttributeUsage::Position) {
+                for &(target, weight) in targets {
+                    if let Some(delta) = self.morph_targets[target].position_deltas.get(i) {
+                        position += delta.scale(weight);
+                    }
+                }
+                view.write_3_f32(VertexAttributeUsage::Position, position)
+                    .unwrap();
+            }
+            if let Ok(mut normal) = base.read_3_f32(VertexAttributeUsage::Normal) {
+                for &(target, weight) in targets {
+                    if let Some(delta) = self.morph_targets[target].normal_deltas.get(i) {
+                        normal += delta.scale(weight);
+                    }
+                }
+                view.write_3_f32(
+                    VertexAttributeUsage::Normal,
+                    normal.try_normalize(f32::EPSILON).unwrap_or(normal),
+                )
+                .unwrap();
+            }
+        }
+    }
+}
+
+/// Returns angle of a triangle corner at `a`, zero for degenerate triangles.
+fn corner_angle(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> f32 {
+    match (
+        (b - a).try_normalize(f32::EPSILON),
+        (c - a).try_normalize(f32::EPSILON),
+    ) {
+        (Some(ab), Some(ac)) => ab.dot(&ac).clamp(-1.0, 1.0).acos(),
+        _ => 0.0,
+    }
+}
+
+/// Returns arbitrary unit vector orthogonal to a given unit vector.
+fn orthogonal_vector(v: &Vector3<f32>) -> Vector3<f32> {
+    let axis = if v.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    (axis - v.scale(v.dot(&axis))).normalize()
 }
 
 impl Visit for SurfaceData {
@@ -815,12 +999,34 @@ impl Visit for SurfaceData {
                 triangles.visit("Triangles", visitor)?;
                 self.geometry_buffer = TriangleBuffer::new(triangles);
             }
+            let _ = self.morph_targets.visit("MorphTargets", visitor);
         }
 
         visitor.leave_region()
     }
 }
 
+/// Max amount of morph targets that are applied to a surface at once, targets with the largest
+/// weights are used if there are more active targets.
+pub const MAX_ACTIVE_MORPH_TARGETS: usize = 8;
+
+/// Morph target (blend shape) is an alternative shape of a surface, it is defined as offsets of
+/// vertices relative to the base shape. Shape of a mesh is a sum of the base shape and offsets of
+/// its morph targets multiplied by their weights, see [`Mesh::set_morph_weight`] for more info.
+///
+/// [`Mesh::set_morph_weight`]: crate::scene::mesh::Mesh::set_morph_weight
+#[derive(Clone, Debug, Default, Visit)]
+pub struct MorphTarget {
+    /// Name of the target, for example "Smile". Morph targets of every surface of a mesh should
+    /// have the same names in the same order.
+    pub name: String,
+    /// Position offset of each vertex of the surface.
+    pub position_deltas: Vec<Vector3<f32>>,
+    /// Normal offset of each vertex of the surface, could be empty if the target does not change
+    /// normals.
+    pub normal_deltas: Vec<Vector3<f32>>,
+}
+
 /// Vertex weight is a pair of (bone; weight) that affects vertex.
 #[derive(Copy, Clone, Debug)]
 pub struct VertexWeight {
@@ -899,6 +1105,64 @@ impl VertexWeightSet {
     }
 }
 
+/// Transform of texture coordinates of a surface. It is applied to the first texture coordinates
+/// in the vertex shader, so it affects every texture of the material except the lightmap. Change
+/// `offset` every frame to make the texture scroll (conveyor belts, water, lava, etc.).
+#[derive(Copy, Clone, Debug, PartialEq, Visit, Inspect)]
+pub struct UvTransform {
+    /// Tiling of the texture, values larger than 1.0 make the texture repeat more times.
+    pub scale: Vector2<f32>,
+    /// Offset of the texture coordinates, it is applied after scale and rotation.
+    pub offset: Vector2<f32>,
+    /// Rotation angle (in radians) around the center of the texture.
+    pub rotation: f32,
+}
+
+impl Default for UvTransform {
+    fn default() -> Self {
+        Self {
+            scale: Vector2::new(1.0, 1.0),
+            offset: Default::default(),
+            rotation: 0.0,
+        }
+    }
+}
+
+impl UvTransform {
+    /// Builds a matrix that transforms homogeneous texture coordinates: scale first, then
+    /// rotation around (0.5, 0.5) and then offset.
+    pub fn matrix(&self) -> Matrix3<f32> {
+        let center = Vector2::new(0.5, 0.5);
+        Matrix3::new_translation(&(self.offset + center))
+            * Matrix3::new_rotation(self.rotation)
+            * Matrix3::new_translation(&-center)
+            * Matrix3::new_nonuniform_scaling(&self.scale)
+    }
+}
+
+/// Default alpha threshold of surfaces, see [`Surface::set_alpha_cutoff`].
+pub const DEFAULT_ALPHA_CUTOFF: f32 = 0.5;
+
+/// Parameters of triplanar mapping of a surface, see [`Surface::set_triplanar_mapping`].
+#[derive(Copy, Clone, Debug, PartialEq, Visit, Inspect)]
+pub struct TriplanarMapping {
+    /// Sharpness of transitions between projections, larger values make blend regions narrower.
+    #[inspect(min_value = 1.0, max_value = 64.0, step = 1.0)]
+    pub sharpness: f32,
+    /// Amount of texture repeats per world unit.
+    #[inspect(min_value = 0.0, step = 0.05)]
+    pub scale: f32,
+}
+
+impl Default for TriplanarMapping {
+    fn default() -> Self {
+        Self {
+            sharpness: 4.0,
+            scale: 1.0,
+        }
+    }
+}
+
 /// See module docs.
 #[derive(Debug, Clone, Inspect)]
 pub struct Surface {
@@ -916,6 +1180,16 @@ pub struct Surface {
     pub vertex_weights: Vec<VertexWeightSet>,
     /// Array of handle to scene nodes which are used as bones.
     pub bones: Vec<Handle<Node>>,
+    transparent: bool,
+    uv_transform: UvTransform,
+    mip_bias: f32,
+    two_sided: bool,
+    #[inspect(min_value = 0.0, max_value = 1.0, step = 0.05)]
+    alpha_cutoff: f32,
+    triplanar_mapping: Option<TriplanarMapping>,
+    // Copy of the data with active morph targets applied, it is unique for each mesh node.
+    #[inspect(skip)]
+    morphed: Option<Arc<Mutex<SurfaceData>>>,
 }
 
 impl Default for Surface {
@@ -925,6 +1199,13 @@ impl Default for Surface {
             material: Arc::new(Mutex::new(Material::standard())),
             vertex_weights: Default::default(),
             bones: Default::default(),
+            transparent: false,
+            uv_transform: Default::default(),
+            mip_bias: 0.0,
+            two_sided: false,
+            alpha_cutoff: DEFAULT_ALPHA_CUTOFF,
+            triplanar_mapping: None,
+            morphed: None,
         }
     }
 }
@@ -948,7 +1229,7 @@ impl Surface {
     pub fn batch_id(&self) -> u64 {
         let mut hasher = FxHasher::default();
         hasher.write_u64(self.material_id());
-        hasher.write_u64(&**self.data.as_ref().unwrap() as *const _ as u64);
+        hasher.write_u64(&*self.render_data() as *const _ as u64);
         hasher.finish()
     }
 
@@ -958,6 +1239,79 @@ impl Surface {
         self.data.as_ref().unwrap().clone()
     }
 
+    /// Sets new data of the surface. The mesh of the surface must be notified about the change
+    /// (for example by re-adding its surfaces), otherwise its bounds and blended morph targets
+    /// will remain outdated.
+    #[inline]
+    pub fn set_data(&mut self, data: Arc<Mutex<SurfaceData>>) {
+        self.data = Some(data);
+        self.morphed = None;
+    }
+
+    /// Returns data that is used to render the surface: shared data with active morph targets of
+    /// the mesh applied, or just shared data if there are no active morph targets.
+    #[inline]
+    pub fn render_data(&self) -> Arc<Mutex<SurfaceData>> {
+        match self.morphed.as_ref() {
+            Some(morphed) => morphed.clone(),
+            None => self.data(),
+        }
+    }
+
+    // Applies given weights of morph targets, blended vertices are stored in a copy of shared
+    // data that is used only by this surface.
+    pub(in crate) fn apply_morph_weights(&mut self, weights: &[f32]) {
+        let data = match self.data.as_ref() {
+            Some(data) => data.clone(),
+            None => return,
+        };
+        let data = data.lock();
+
+        let mut active = weights
+            .iter()
+            .take(data.morph_targets.len())
+            .enumerate()
+            .filter(|(_, weight)| **weight != 0.0)
+            .map(|(index, weight)| (index, *weight))
+            .collect::<Vec<_>>();
+        if active.is_empty() {
+            self.morphed = None;
+            return;
+        }
+        active.sort_by(|a, b| b.1.abs().partial_cmp(&a.1.abs()).unwrap_or(Ordering::Equal));
+        active.truncate(MAX_ACTIVE_MORPH_TARGETS);
+
+        let morphed = self.morphed.get_or_insert_with(|| {
+            Arc::new(Mutex::new(SurfaceData::new(
+                data.vertex_buffer.clone(),
+                data.geometry_buffer.clone(),
+                true,
+            )))
+        });
+        let mut morphed = morphed.lock();
+        if morphed.vertex_buffer.vertex_count() != data.vertex_buffer.vertex_count() {
+            // Shared data was changed, start from scratch.
+            *morphed = SurfaceData::new(
+                data.vertex_buffer.clone(),
+                data.geometry_buffer.clone(),
+                true,
+            );
+        }
+        data.blend_morph_targets(&active, &mut morphed.vertex_buffer);
+    }
+
+    // Detaches the surface from blended data of other surface, it is used when a mesh is copied.
+    pub(in crate) fn reset_morph(&mut self) {
+        self.morphed = None;
+    }
+
+    /// Returns current data used by surface, or `None` if the surface was created without data
+    /// (it is possible only with `Default` implementation).
+    #[inline]
+    pub fn try_data(&self) -> Option<&Arc<Mutex<SurfaceData>>> {
+        self.data.as_ref()
+    }
+
     /// Returns current material of the surface.
     pub fn material(&self) -> &Arc<Mutex<Material>> {
         &self.material
@@ -968,11 +1322,178 @@ impl Surface {
         self.material = material;
     }
 
+    /// Makes sure that the material of the surface is not shared with any other surface by
+    /// copying it if needed. Surfaces of model instances share materials with the surfaces of
+    /// the model resource, call this method before changing the material of a single instance.
+    pub fn make_material_unique(&mut self) {
+        if Arc::strong_count(&self.material) > 1 {
+            let material = self.material.lock().clone();
+            self.material = Arc::new(Mutex::new(material));
+        }
+    }
+
+    /// Returns every texture used by the material of the surface along with names of the
+    /// sampler properties that use them.
+    pub fn textures(&self) -> Vec<(ImmutableString, Texture)> {
+        self.material
+            .lock()
+            .properties()
+            .iter()
+            .filter_map(|(name, value)| value.as_sampler().map(|texture| (name.clone(), texture)))
+            .collect()
+    }
+
+    /// Returns a texture of a sampler property with given name, if any.
+    pub fn texture(&self, property: &ImmutableString) -> Option<Texture> {
+        self.material
+            .lock()
+            .property_ref(property)
+            .and_then(|value| value.as_sampler())
+    }
+
+    /// Sets a texture of a sampler property with given name, fallback value of the sampler is
+    /// kept as is. The material is copied if it is shared (see [`Self::make_material_unique`]),
+    /// so other instances of the same model are not affected.
+    pub fn set_texture(
+        &mut self,
+        property: &ImmutableString,
+        texture: Option<Texture>,
+    ) -> Result<(), MaterialError> {
+        let fallback = match self.material.lock().property_ref(property) {
+            Some(PropertyValue::Sampler { fallback, .. }) => *fallback,
+            _ => SamplerFallback::White,
+        };
+        self.make_material_unique();
+        self.material.lock().set_property(
+            property,
+            PropertyValue::Sampler {
+                value: texture,
+                fallback,
+            },
+        )
+    }
+
+    /// Returns diffuse texture of the surface, if any.
+    pub fn diffuse_texture(&self) -> Option<Texture> {
+        self.texture(&ImmutableString::new("diffuseTexture"))
+    }
+
+    /// Sets diffuse texture of the surface, see [`Self::set_texture`] for more info.
+    pub fn set_diffuse_texture(&mut self, texture: Option<Texture>) -> Result<(), MaterialError> {
+        self.set_texture(&ImmutableString::new("diffuseTexture"), texture)
+    }
+
+    /// Replaces every texture of the material for which `func` returns a new texture. Returns the
+    /// amount of replaced textures. The material is copied only if at least one texture was
+    /// replaced.
+    pub fn replace_textures<F>(&mut self, mut func: F) -> usize
+    where
+        F: FnMut(&Texture) -> Option<Texture>,
+    {
+        let replacements = self
+            .textures()
+            .into_iter()
+            .filter_map(|(name, texture)| func(&texture).map(|new_texture| (name, new_texture)))
+            .collect::<Vec<_>>();
+
+        for (name, texture) in replacements.iter() {
+            // Cannot fail, the property is a sampler.
+            let _ = self.set_texture(name, Some(texture.clone()));
+        }
+
+        replacements.len()
+    }
+
     /// Returns list of bones that affects the surface.
     #[inline]
     pub fn bones(&self) -> &[Handle<Node>] {
         &self.bones
     }
+
+    /// Sets whether the surface is transparent or not. Transparent surfaces are excluded from
+    /// deferred rendering and put into transparent render queue, which is sorted back-to-front
+    /// and rendered after lighting using `Forward` render pass of surface's material. See
+    /// [`crate::scene::mesh::Mesh::set_opacity`] docs for more info.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
+    /// Returns true if the surface is transparent.
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    /// Returns current transform of texture coordinates.
+    pub fn uv_transform(&self) -> UvTransform {
+        self.uv_transform
+    }
+
+    /// Sets new transform of texture coordinates. The transform is stored in the surface, not in
+    /// the material, so every instance of a model can have its own tiling and scrolling without
+    /// breaking batching of instances that share the same material.
+    pub fn set_uv_transform(&mut self, uv_transform: UvTransform) {
+        self.uv_transform = uv_transform;
+    }
+
+    /// Returns current mip level bias.
+    pub fn mip_bias(&self) -> f32 {
+        self.mip_bias
+    }
+
+    /// Sets mip level bias which is added to the mip level selected by the GPU when sampling the
+    /// textures of the surface. Negative values make textures sharper (at the cost of aliasing),
+    /// positive values make them blurrier. Default value is 0.0.
+    pub fn set_mip_bias(&mut self, mip_bias: f32) {
+        self.mip_bias = mip_bias;
+    }
+
+    /// Sets whether both sides of the surface should be rendered or not. Back-face culling is
+    /// disabled for two-sided surfaces in every pass (including shadow passes) and normals of back
+    /// faces are flipped by the standard shader, so back faces are lit correctly. Use it for thin
+    /// geometry like foliage cards, fences, cloth, etc. Default value is `false`.
+    pub fn set_two_sided(&mut self, two_sided: bool) {
+        self.two_sided = two_sided;
+    }
+
+    /// Returns true if both sides of the surface are rendered.
+    pub fn is_two_sided(&self) -> bool {
+        self.two_sided
+    }
+
+    /// Sets alpha threshold of cutout transparency: fragments which alpha (diffuse color alpha
+    /// multiplied by diffuse texture alpha) is below the threshold are discarded in the GBuffer
+    /// pass and in shadow map passes, so the surface casts shadows of its visible part only.
+    /// Zero disables alpha test. Default value is [`DEFAULT_ALPHA_CUTOFF`].
+    ///
+    /// Cutout transparency works for opaque (non-transparent) surfaces and does not require any
+    /// sorting, use it instead of blended transparency for leaves, grass, chain-link fences, etc.
+    pub fn set_alpha_cutoff(&mut self, alpha_cutoff: f32) {
+        self.alpha_cutoff = alpha_cutoff.clamp(0.0, 1.0);
+    }
+
+    /// Returns current alpha threshold of cutout transparency.
+    pub fn alpha_cutoff(&self) -> f32 {
+        self.alpha_cutoff
+    }
+
+    /// Enables (`Some`) or disables (`None`) triplanar mapping of the surface. Textures of a
+    /// triplanar-mapped surface are projected along world axes and blended by the world normal,
+    /// texture coordinates of the mesh (as well as UV transform and parallax mapping) are ignored,
+    /// so steep slopes and cliffs have no stretched textures. Normal maps are supported.
+    ///
+    /// Every texture is sampled three times, so use it only where it is needed. Surfaces without
+    /// triplanar mapping use the usual shader, there is no overhead for them. It works for the
+    /// standard shader in the GBuffer pass, custom shaders could support it by checking the
+    /// `RG3D_TRIPLANAR` define (see [`crate::material::shader::Shader`] docs).
+    pub fn set_triplanar_mapping(&mut self, triplanar_mapping: Option<TriplanarMapping>) {
+        self.triplanar_mapping = triplanar_mapping;
+    }
+
+    /// Returns current parameters of triplanar mapping, `None` if the surface uses texture
+    /// coordinates.
+    pub fn triplanar_mapping(&self) -> Option<TriplanarMapping> {
+        self.triplanar_mapping
+    }
 }
 
 impl Visit for Surface {
@@ -983,6 +1504,12 @@ impl Visit for Surface {
         self.data.visit("Data", visitor)?;
         self.bones.visit("Bones", visitor)?;
         let _ = self.material.visit("Material", visitor); // Backward compatibility.
+        let _ = self.transparent.visit("Transparent", visitor);
+        let _ = self.uv_transform.visit("UvTransform", visitor);
+        let _ = self.mip_bias.visit("MipBias", visitor);
+        let _ = self.two_sided.visit("TwoSided", visitor);
+        let _ = self.alpha_cutoff.visit("AlphaCutoff", visitor);
+        let _ = self.triplanar_mapping.visit("TriplanarMapping", visitor);
 
         visitor.leave_region()
     }
@@ -993,6 +1520,12 @@ pub struct SurfaceBuilder {
     data: Arc<Mutex<SurfaceData>>,
     material: Option<Arc<Mutex<Material>>>,
     bones: Vec<Handle<Node>>,
+    transparent: bool,
+    uv_transform: UvTransform,
+    mip_bias: f32,
+    two_sided: bool,
+    alpha_cutoff: f32,
+    triplanar_mapping: Option<TriplanarMapping>,
 }
 
 impl SurfaceBuilder {
@@ -1002,6 +1535,12 @@ impl SurfaceBuilder {
             data,
             material: None,
             bones: Default::default(),
+            transparent: false,
+            uv_transform: Default::default(),
+            mip_bias: 0.0,
+            two_sided: false,
+            alpha_cutoff: DEFAULT_ALPHA_CUTOFF,
+            triplanar_mapping: None,
         }
     }
 
@@ -1017,6 +1556,42 @@ impl SurfaceBuilder {
         self
     }
 
+    /// Sets whether the surface should be rendered as transparent or not.
+    pub fn with_transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Sets desired transform of texture coordinates.
+    pub fn with_uv_transform(mut self, uv_transform: UvTransform) -> Self {
+        self.uv_transform = uv_transform;
+        self
+    }
+
+    /// Sets desired mip level bias.
+    pub fn with_mip_bias(mut self, mip_bias: f32) -> Self {
+        self.mip_bias = mip_bias;
+        self
+    }
+
+    /// Sets whether both sides of the surface should be rendered or not.
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    /// Sets desired alpha threshold of cutout transparency.
+    pub fn with_alpha_cutoff(mut self, alpha_cutoff: f32) -> Self {
+        self.alpha_cutoff = alpha_cutoff.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets desired parameters of triplanar mapping.
+    pub fn with_triplanar_mapping(mut self, triplanar_mapping: TriplanarMapping) -> Self {
+        self.triplanar_mapping = Some(triplanar_mapping);
+        self
+    }
+
     /// Creates new instance of surface.
     pub fn build(self) -> Surface {
         Surface {
@@ -1026,6 +1601,304 @@ impl SurfaceBuilder {
                 .unwrap_or_else(|| Arc::new(Mutex::new(Material::standard()))),
             vertex_weights: Default::default(),
             bones: self.bones,
+            transparent: self.transparent,
+            uv_transform: self.uv_transform,
+            mip_bias: self.mip_bias,
+            two_sided: self.two_sided,
+            alpha_cutoff: self.alpha_cutoff,
+            triplanar_mapping: self.triplanar_mapping,
+            morphed: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Matrix3, Matrix4, Vector2, Vector3},
+            color::Color,
+            futures::executor::block_on,
+            math::TriangleDefinition,
+            parking_lot::Mutex,
+            visitor::{Visit, Visitor},
+        },
+        scene::mesh::{
+            buffer::{TriangleBuffer, VertexAttributeUsage, VertexBuffer, VertexReadTrait},
+            surface::{
+                MorphTarget, SurfaceBuilder, SurfaceData, TriplanarMapping, UvTransform,
+                DEFAULT_ALPHA_CUTOFF,
+            },
+            vertex::StaticVertex,
+        },
+    };
+    use std::sync::Arc;
+
+    fn make_data(vertices: Vec<StaticVertex>, triangles: Vec<TriangleDefinition>) -> SurfaceData {
+        SurfaceData::new(
+            VertexBuffer::new(vertices.len(), StaticVertex::layout(), vertices).unwrap(),
+            TriangleBuffer::new(triangles),
+            true,
+        )
+    }
+
+    #[test]
+    fn test_calculate_tangents() {
+        let mut data = make_data(
+            vec![
+                StaticVertex::from_pos_uv_normal(
+                    Vector3::default(),
+                    Vector2::default(),
+                    Vector3::z(),
+                ),
+                StaticVertex::from_pos_uv_normal(Vector3::x(), Vector2::x(), Vector3::z()),
+                StaticVertex::from_pos_uv_normal(Vector3::y(), Vector2::y(), Vector3::z()),
+            ],
+            vec![TriangleDefinition([0, 1, 2])],
+        );
+
+        assert!(!data.has_valid_tangents());
+        data.calculate_tangents().unwrap();
+        assert!(data.has_valid_tangents());
+
+        for view in data.vertex_buffer.iter() {
+            let tangent = view.read_4_f32(VertexAttributeUsage::Tangent).unwrap();
+            assert!((tangent.xyz() - Vector3::x()).norm() < 0.001);
+            assert_eq!(tangent.w, 1.0);
+        }
+    }
+
+    #[test]
+    fn test_calculate_tangents_mirrored_uv() {
+        let mut data = make_data(
+            vec![
+                StaticVertex::from_pos_uv_normal(Vector3::default(), Vector2::x(), Vector3::z()),
+                StaticVertex::from_pos_uv_normal(Vector3::x(), Vector2::default(), Vector3::z()),
+                StaticVertex::from_pos_uv_normal(
+                    Vector3::y(),
+                    Vector2::new(1.0, 1.0),
+                    Vector3::z(),
+                ),
+            ],
+            vec![TriangleDefinition([0, 1, 2])],
+        );
+
+        data.calculate_tangents().unwrap();
+
+        for view in data.vertex_buffer.iter() {
+            let tangent = view.read_4_f32(VertexAttributeUsage::Tangent).unwrap();
+            assert!((tangent.xyz() + Vector3::x()).norm() < 0.001);
+            assert_eq!(tangent.w, -1.0);
+        }
+    }
+
+    #[test]
+    fn test_calculate_tangents_degenerate() {
+        let mut data = make_data(
+            vec![
+                // Zero area in world space.
+                StaticVertex::from_pos_uv_normal(
+                    Vector3::default(),
+                    Vector2::default(),
+                    Vector3::z(),
+                ),
+                StaticVertex::from_pos_uv_normal(Vector3::default(), Vector2::x(), Vector3::z()),
+                StaticVertex::from_pos_uv_normal(Vector3::default(), Vector2::y(), Vector3::z()),
+                // Zero area in texture space.
+                StaticVertex::from_pos_uv_normal(
+                    Vector3::default(),
+                    Vector2::default(),
+                    Vector3::y(),
+                ),
+                StaticVertex::from_pos_uv_normal(Vector3::x(), Vector2::default(), Vector3::y()),
+                StaticVertex::from_pos_uv_normal(Vector3::z(), Vector2::default(), Vector3::y()),
+            ],
+            vec![TriangleDefinition([0, 1, 2]), TriangleDefinition([3, 4, 5])],
+        );
+
+        data.calculate_tangents().unwrap();
+
+        assert!(data.has_valid_tangents());
+    }
+
+    #[test]
+    fn test_vertex_colors() {
+        let mut data = SurfaceData::make_cube(Matrix4::identity());
+        assert!(!data.has_vertex_colors());
+        assert!(data.vertex_colors().is_none());
+
+        let vertex_count = data.vertex_buffer.vertex_count() as usize;
+
+        // Color only top vertices.
+        data.set_vertex_colors_with(|_, position| {
+            if position.y > 0.0 {
+                Some(Color::RED)
+            } else {
+                None
+            }
+        })
+        .unwrap();
+
+        let colors = data.vertex_colors().unwrap();
+        assert_eq!(colors.len(), vertex_count);
+        for (view, color) in data.vertex_buffer.iter().zip(colors.iter()) {
+            let position = view.read_3_f32(VertexAttributeUsage::Position).unwrap();
+            if position.y > 0.0 {
+                assert_eq!(*color, Color::RED);
+            } else {
+                assert_eq!(*color, Color::WHITE);
+            }
         }
+
+        // Other attributes must be untouched.
+        assert!(data.has_valid_tangents());
+
+        // Colors must survive serialization.
+        let path = std::env::temp_dir().join("rg3d_vertex_colors_test.bin");
+        let mut visitor = Visitor::new();
+        data.vertex_buffer
+            .visit("VertexBuffer", &mut visitor)
+            .unwrap();
+        visitor.save_binary(&path).unwrap();
+        let mut visitor = block_on(Visitor::load_binary(&path)).unwrap();
+        let mut loaded = VertexBuffer::default();
+        loaded.visit("VertexBuffer", &mut visitor).unwrap();
+        assert!(loaded.has_attribute(VertexAttributeUsage::Color));
+        assert_eq!(loaded.raw_data(), data.vertex_buffer.raw_data());
+    }
+
+    #[test]
+    fn test_uv_transform_matrix() {
+        let apply = |transform: &UvTransform, uv: Vector2<f32>| {
+            transform.matrix().transform_point(&uv.into()).coords
+        };
+
+        let identity = UvTransform::default();
+        assert!((identity.matrix() - Matrix3::identity()).norm() < 0.0001);
+
+        let tiled = UvTransform {
+            scale: Vector2::new(2.0, 3.0),
+            offset: Vector2::new(0.25, 0.0),
+            ..Default::default()
+        };
+        assert!((apply(&tiled, Vector2::new(1.0, 1.0)) - Vector2::new(2.25, 3.0)).norm() < 0.0001);
+
+        // Rotation is done around the center of the texture.
+        let rotated = UvTransform {
+            rotation: std::f32::consts::FRAC_PI_2,
+            ..Default::default()
+        };
+        assert!((apply(&rotated, Vector2::new(0.5, 0.5)) - Vector2::new(0.5, 0.5)).norm() < 0.0001);
+        assert!((apply(&rotated, Vector2::new(1.0, 0.5)) - Vector2::new(0.5, 1.0)).norm() < 0.0001);
+    }
+
+    #[test]
+    fn test_two_sided_alpha_cutoff() {
+        let mut surface = SurfaceBuilder::new(Arc::new(Mutex::new(SurfaceData::make_quad(
+            &Matrix4::identity(),
+        ))))
+        .build();
+        assert!(!surface.is_two_sided());
+        assert_eq!(surface.alpha_cutoff(), DEFAULT_ALPHA_CUTOFF);
+
+        surface.set_alpha_cutoff(2.0);
+        assert_eq!(surface.alpha_cutoff(), 1.0);
+        surface.set_alpha_cutoff(-1.0);
+        assert_eq!(surface.alpha_cutoff(), 0.0);
+
+        let surface = SurfaceBuilder::new(surface.data())
+            .with_two_sided(true)
+            .with_alpha_cutoff(0.25)
+            .build();
+        assert!(surface.is_two_sided());
+        assert_eq!(surface.alpha_cutoff(), 0.25);
+    }
+
+    #[test]
+    fn test_morph_targets() {
+        let mut data = make_data(
+            vec![
+                StaticVertex::from_pos_uv_normal(
+                    Vector3::default(),
+                    Vector2::default(),
+                    Vector3::z(),
+                ),
+                StaticVertex::from_pos_uv_normal(Vector3::x(), Vector2::x(), Vector3::z()),
+                StaticVertex::from_pos_uv_normal(Vector3::y(), Vector2::y(), Vector3::z()),
+            ],
+            vec![TriangleDefinition([0, 1, 2])],
+        );
+        data.morph_targets.push(MorphTarget {
+            name: "Raise".to_owned(),
+            position_deltas: vec![Vector3::default(), Vector3::default(), Vector3::z()],
+            normal_deltas: Default::default(),
+        });
+        assert_eq!(data.morph_target_index("Raise"), Some(0));
+        assert_eq!(data.morph_target_index("Lower"), None);
+
+        let data = Arc::new(Mutex::new(data));
+        let mut surface = SurfaceBuilder::new(data.clone()).build();
+
+        surface.apply_morph_weights(&[0.5]);
+        let morphed = surface.render_data();
+        assert!(!Arc::ptr_eq(&morphed, &data));
+        let position = morphed
+            .lock()
+            .vertex_buffer
+            .get(2)
+            .unwrap()
+            .read_3_f32(VertexAttributeUsage::Position)
+            .unwrap();
+        assert_eq!(position, Vector3::new(0.0, 1.0, 0.5));
+
+        // Shared data must stay intact.
+        let position = data
+            .lock()
+            .vertex_buffer
+            .get(2)
+            .unwrap()
+            .read_3_f32(VertexAttributeUsage::Position)
+            .unwrap();
+        assert_eq!(position, Vector3::y());
+
+        surface.apply_morph_weights(&[0.0]);
+        assert!(Arc::ptr_eq(&surface.render_data(), &data));
+    }
+
+    #[test]
+    fn test_triplanar_mapping() {
+        let mut surface = SurfaceBuilder::new(Arc::new(Mutex::new(SurfaceData::make_quad(
+            &Matrix4::identity(),
+        ))))
+        .build();
+        assert_eq!(surface.triplanar_mapping(), None);
+
+        let mapping = TriplanarMapping {
+            sharpness: 8.0,
+            scale: 0.25,
+        };
+        surface.set_triplanar_mapping(Some(mapping));
+        assert_eq!(surface.triplanar_mapping(), Some(mapping));
+
+        // Parameters must survive serialization.
+        let path = std::env::temp_dir().join("rg3d_triplanar_mapping_test.bin");
+        let mut visitor = Visitor::new();
+        surface
+            .triplanar_mapping
+            .visit("TriplanarMapping", &mut visitor)
+            .unwrap();
+        visitor.save_binary(&path).unwrap();
+        let mut visitor = block_on(Visitor::load_binary(&path)).unwrap();
+        let mut loaded: Option<TriplanarMapping> = None;
+        loaded.visit("TriplanarMapping", &mut visitor).unwrap();
+        assert_eq!(loaded, Some(mapping));
+
+        let surface = SurfaceBuilder::new(surface.data())
+            .with_triplanar_mapping(Default::default())
+            .build();
+        assert_eq!(
+            surface.triplanar_mapping(),
+            Some(TriplanarMapping::default())
+        );
     }
 }
@@ -109,6 +109,49 @@ impl Hash for StaticVertex {
     }
 }
 
+/// Descriptor of optional vertex color attribute. Colors are stored as four `u8` components (RGBA),
+/// which are treated as normalized values by the renderer. The attribute is not a part of any
+/// built-in vertex format, it should be added to a vertex buffer only when needed - see
+/// [`crate::scene::mesh::surface::SurfaceData::set_vertex_colors`].
+pub const VERTEX_COLOR_DESCRIPTOR: VertexAttributeDescriptor = VertexAttributeDescriptor {
+    usage: VertexAttributeUsage::Color,
+    data_type: VertexAttributeDataType::U8,
+    size: 4,
+    divisor: 0,
+    shader_location: 7,
+};
+
+/// A wrapper that adds color attribute to any other vertex format. It is used to import meshes with
+/// vertex colors, vertex color takes part in comparison and hashing so vertices with the same
+/// position, but different color won't be merged. Use [`ColoredVertex::layout`] to get layout of the
+/// vertex.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)] // OpenGL expects this structure packed as in C
+pub struct ColoredVertex<V> {
+    /// Base vertex.
+    pub vertex: V,
+    /// Color of the vertex (RGBA).
+    pub color: [u8; 4],
+}
+
+impl<V: Hash> Hash for ColoredVertex<V> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.vertex.hash(state);
+        self.color.hash(state);
+    }
+}
+
+impl<V> ColoredVertex<V> {
+    /// Creates layout of the vertex from layout of base vertex format.
+    pub fn layout(base_layout: &[VertexAttributeDescriptor]) -> Vec<VertexAttributeDescriptor> {
+        base_layout
+            .iter()
+            .cloned()
+            .chain(std::iter::once(VERTEX_COLOR_DESCRIPTOR))
+            .collect()
+    }
+}
+
 /// A vertex for animated (via skinning) mesh.
 #[derive(Copy, Clone, Debug, Default)]
 #[repr(C)] // OpenGL expects this structure packed as in C
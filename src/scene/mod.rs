@@ -7,29 +7,42 @@
 pub mod accel;
 pub mod base;
 pub mod camera;
+pub mod constraint;
 pub mod debug;
 pub mod decal;
 pub mod graph;
+pub mod impostor;
 pub mod light;
 pub mod mesh;
 pub mod node;
 pub mod particle_system;
 pub mod physics;
+pub mod probe;
+pub mod skeleton;
+pub mod sound;
+pub mod spline;
 pub mod sprite;
 pub mod terrain;
 pub mod transform;
+pub mod units;
+pub mod validation;
 pub mod variable;
 pub mod visibility;
+pub mod water;
+pub mod world_text;
 
 use crate::core::sstorage::ImmutableString;
 use crate::physics3d::{PhysicsPerformanceStatistics, RigidBodyHandle};
 use crate::{
-    animation::AnimationContainer,
+    animation::{Animation, AnimationContainer, AnimationStatistics},
     core::{
         algebra::{Isometry3, Translation, Vector2},
         color::Color,
         instant,
+        math::frustum::Frustum,
         pool::{Handle, Pool, PoolIterator, PoolIteratorMut, Ticket},
+        profile_scope,
+        rand::Rng,
         visitor::{Visit, VisitError, VisitResult, Visitor},
     },
     engine::{
@@ -48,9 +61,20 @@ use crate::{
         },
         node::Node,
         physics::Physics,
+        sound::{OneShotSounds, PlaySoundParams, SoundVariation},
+        spline::SplineContainer,
+        validation::{PeriodicValidation, ValidationError},
+        world_text::{FloatingTextParams, FloatingTexts},
+    },
+    sound::{
+        buffer::SoundBufferResource, context::SoundContext, engine::SoundEngine, error::SoundError,
+        source::SoundSource,
+    },
+    utils::{
+        lightmap::Lightmap,
+        log::{log_error, log_info, log_warn},
+        navmesh::Navmesh,
     },
-    sound::{context::SoundContext, engine::SoundEngine},
-    utils::{lightmap::Lightmap, log::Log, log::MessageKind, navmesh::Navmesh},
 };
 use fxhash::FxHashMap;
 use std::{
@@ -127,6 +151,16 @@ impl Visit for NavMeshContainer {
     }
 }
 
+/// Result of [`Scene::copy_nodes_to`], contains old-to-new mapping of everything that was copied.
+#[derive(Default, Debug, Clone)]
+pub struct CopiedNodes {
+    /// Maps nodes of source scene to their copies in destination scene, includes descendants
+    /// of requested nodes.
+    pub nodes: FxHashMap<Handle<Node>, Handle<Node>>,
+    /// Maps animations of source scene to their copies in destination scene.
+    pub animations: FxHashMap<Handle<Animation>, Handle<Animation>>,
+}
+
 /// See module docs.
 #[derive(Debug)]
 pub struct Scene {
@@ -162,9 +196,18 @@ pub struct Scene {
     /// A sound context that holds all sound sources, effects, etc. belonging to the scene.
     pub sound_context: SoundContext,
 
+    /// Fire-and-forget sounds that are playing at the moment, see [`Scene::play_sound`].
+    pub one_shot_sounds: OneShotSounds,
+
+    /// Fire-and-forget texts that are shown at the moment, see [`Scene::spawn_floating_text`].
+    pub floating_texts: FloatingTexts,
+
     /// A container for navigational meshes.
     pub navmeshes: NavMeshContainer,
 
+    /// A container for splines, see [`spline`] module docs for more info.
+    pub splines: SplineContainer,
+
     /// Current lightmap.
     lightmap: Option<Lightmap>,
 
@@ -174,14 +217,32 @@ pub struct Scene {
     /// Color of ambient lighting.
     pub ambient_lighting_color: Color,
 
-    /// Whether the scene will be updated and rendered or not. Default is true.
-    /// This flag allowing you to build a scene manager for your game. For example,
-    /// you may have a scene for menu and one per level. Menu's scene is persistent,
-    /// however you don't want it to be updated and renderer while you have a level
-    /// loaded and playing a game. When you're start playing, just set `enabled` flag
-    /// to false for menu's scene and when you need to open a menu - set it to true and
-    /// set `enabled` flag to false for level's scene.
+    /// Whether the scene will be updated or not. Default is true. Disabled scene is "frozen":
+    /// its physics, animations and graph won't be updated and its sound context will be paused,
+    /// but it still will be rendered if `render` flag is set. This allows you to build a scene
+    /// manager for your game. For example, you may have a scene for pause menu and one per
+    /// level. When you need to open a menu - set `enabled` flag to false for level's scene and
+    /// set both `enabled` and `render` flags to true for menu's scene, frozen level will be
+    /// drawn behind the menu.
+    ///
+    /// Engine updates scenes with fixed time step, so there will be no huge time step when the
+    /// scene is enabled back after long pause.
     pub enabled: bool,
+
+    /// Whether the scene will be rendered or not. Default is true. Set it to false to hide a
+    /// scene completely, for example if you have a level loaded in background.
+    pub render: bool,
+
+    /// Defines order of rendering of the scene relative to other scenes, scenes with lower value
+    /// will be rendered first. Scenes with the same value are rendered in order of addition.
+    /// Default is 0. Every scene is rendered using its own cameras, so a scene on top (a menu for
+    /// example) will overdraw scenes below it in areas covered by viewports of its cameras.
+    pub render_order: i32,
+
+    // Whether the sound context was paused because the scene was disabled.
+    sound_paused: bool,
+
+    validation: PeriodicValidation,
 }
 
 impl Default for Scene {
@@ -195,21 +256,23 @@ impl Default for Scene {
             lightmap: None,
             drawing_context: Default::default(),
             sound_context: Default::default(),
+            one_shot_sounds: Default::default(),
+            floating_texts: Default::default(),
             navmeshes: Default::default(),
+            splines: Default::default(),
             performance_statistics: Default::default(),
             ambient_lighting_color: Color::opaque(100, 100, 100),
             enabled: true,
+            render: true,
+            render_order: 0,
+            sound_paused: false,
+            validation: Default::default(),
         }
     }
 }
 
 fn map_texture(tex: Option<Texture>, rm: ResourceManager) -> Option<Texture> {
-    if let Some(shallow_texture) = tex {
-        let shallow_texture = shallow_texture.state();
-        Some(rm.request_texture(shallow_texture.path(), None))
-    } else {
-        None
-    }
+    tex.map(|shallow_texture| rm.resolve_texture(&shallow_texture))
 }
 
 /// A structure that holds times that specific update step took.
@@ -221,9 +284,16 @@ pub struct PerformanceStatistics {
     /// A time (in seconds) which was required to update graph.
     pub graph_update_time: f32,
 
+    /// Amount of nodes whose global transform was recalculated during last graph update.
+    pub recomputed_transforms: usize,
+
     /// A time (in seconds) which was required to update animations.
     pub animations_update_time: f32,
 
+    /// Amount of animations that were fully updated, skipped or updated time-only during last
+    /// update.
+    pub animations: AnimationStatistics,
+
     /// A time (in seconds) which was required to render sounds.
     pub sound_update_time: f32,
 }
@@ -232,10 +302,14 @@ impl Display for PerformanceStatistics {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "{}\nGraph: {} ms\nAnimations: {} ms\nSounds: {} ms",
+            "{}\nGraph: {} ms ({} transforms recomputed)\nAnimations: {} ms ({} sampled, {} skipped, {} time only)\nSounds: {} ms",
             self.physics,
             self.graph_update_time * 1000.0,
+            self.recomputed_transforms,
             self.animations_update_time * 1000.0,
+            self.animations.sampled,
+            self.animations.skipped,
+            self.animations.time_only,
             self.sound_update_time * 1000.0
         )
     }
@@ -260,10 +334,17 @@ impl Scene {
             lightmap: None,
             drawing_context: Default::default(),
             sound_context: SoundContext::new(),
+            one_shot_sounds: Default::default(),
+            floating_texts: Default::default(),
             navmeshes: Default::default(),
+            splines: Default::default(),
             performance_statistics: Default::default(),
             ambient_lighting_color: Color::opaque(100, 100, 100),
             enabled: true,
+            render: true,
+            render_order: 0,
+            sound_paused: false,
+            validation: Default::default(),
         }
     }
 
@@ -282,7 +363,8 @@ impl Scene {
     ) -> Result<Self, VisitError> {
         let mut scene = Scene::default();
         {
-            let mut visitor = Visitor::load_binary(path.as_ref()).await?;
+            let source = resource_manager.state().resource_source();
+            let mut visitor = Visitor::load_from_memory(source.load(path.as_ref()).await?)?;
             scene.visit("Scene", &mut visitor)?;
         }
 
@@ -299,9 +381,7 @@ impl Scene {
                     } else {
                         material_search_options.clone()
                     };
-                let resource = resource_manager
-                    .clone()
-                    .request_model(&shallow_resource.state().path(), search_options);
+                let resource = resource_manager.resolve_model(&shallow_resource, search_options);
                 node.resource = Some(resource.clone());
                 resources.push(resource);
             }
@@ -318,6 +398,12 @@ impl Scene {
                     for surface in mesh.surfaces_mut() {
                         surface.material().lock().resolve(resource_manager.clone());
                     }
+                    for texture_override in mesh.texture_overrides_mut() {
+                        texture_override.replacement = map_texture(
+                            texture_override.replacement.clone(),
+                            resource_manager.clone(),
+                        );
+                    }
                 }
                 Node::Sprite(sprite) => {
                     sprite.set_texture(map_texture(sprite.texture(), resource_manager.clone()));
@@ -359,6 +445,12 @@ impl Scene {
                         resource_manager.clone(),
                     ));
                 }
+                Node::Water(water) => {
+                    water.set_normal_map(map_texture(
+                        water.normal_map_value(),
+                        resource_manager.clone(),
+                    ));
+                }
                 _ => (),
             }
         }
@@ -390,6 +482,8 @@ impl Scene {
     }
 
     fn update_physics(&mut self) {
+        profile_scope!("Physics");
+
         self.physics.step();
 
         self.performance_statistics.physics = self.physics.performance_statistics.clone();
@@ -413,6 +507,8 @@ impl Scene {
                             .set_rotation(body.position().rotation);
                     }
                     PhysicsBinding::BodyWithNode => {
+                        // Global transform of the node is not updated yet at this point, so
+                        // calculate it from local transforms to not use stale one.
                         let (r, p) = self.graph.isometric_global_rotation_position(node_handle);
                         body.set_position(
                             Isometry3 {
@@ -433,6 +529,7 @@ impl Scene {
     /// # Panics
     ///
     /// Panics if handle is invalid.
+    #[cfg_attr(all(debug_assertions, feature = "pool_free_location"), track_caller)]
     pub fn remove_node(&mut self, handle: Handle<Node>) {
         for descendant in self.graph.traverse_handle_iter(handle) {
             // Remove all associated animations.
@@ -456,7 +553,7 @@ impl Scene {
     }
 
     pub(in crate) fn resolve(&mut self) {
-        Log::writeln(MessageKind::Information, "Starting resolve...".to_owned());
+        log_info!("Starting resolve...");
 
         self.graph.resolve();
         self.animations.resolve(&self.graph);
@@ -524,11 +621,11 @@ impl Scene {
                             .unwrap();
                     }
                 } else {
-                    Log::writeln(
-                        MessageKind::Warning,
+                    log_warn!(
+                        "{}",
                         "Failed to get surface data patch while resolving lightmap!\
                     This means that surface has changed and lightmap must be regenerated!"
-                            .to_owned(),
+                            .to_owned()
                     );
                 }
             }
@@ -544,12 +641,9 @@ impl Scene {
                                 fallback: SamplerFallback::Black,
                             },
                         ) {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!(
-                                    "Failed to apply light map texture to material. Reason {:?}",
-                                    e
-                                ),
+                            log_error!(
+                                "Failed to apply light map texture to material. Reason {:?}",
+                                e
                             )
                         }
                     }
@@ -557,7 +651,7 @@ impl Scene {
             }
         }
 
-        Log::writeln(MessageKind::Information, "Resolve succeeded!".to_owned());
+        log_info!("Resolve succeeded!");
     }
 
     /// Tries to set new lightmap to scene.
@@ -580,12 +674,9 @@ impl Scene {
                             fallback: SamplerFallback::Black,
                         },
                     ) {
-                        Log::writeln(
-                            MessageKind::Error,
-                            format!(
-                                "Failed to apply light map texture to material. Reason {:?}",
-                                e
-                            ),
+                        log_error!(
+                            "Failed to apply light map texture to material. Reason {:?}",
+                            e
                         )
                     }
                 }
@@ -594,27 +685,224 @@ impl Scene {
         Ok(std::mem::replace(&mut self.lightmap, Some(lightmap)))
     }
 
+    /// Pauses or resumes sound context of the scene depending on `enabled` flag. Sound context
+    /// is resumed only if it was paused by this method, so manual pause is preserved.
+    pub(in crate) fn sync_sound_pause(&mut self) {
+        if !self.enabled && !self.sound_paused {
+            let mut state = self.sound_context.state();
+            if !state.is_paused() {
+                state.pause(true);
+                self.sound_paused = true;
+            }
+        } else if self.enabled && self.sound_paused {
+            self.sound_context.state().pause(false);
+            self.sound_paused = false;
+        }
+    }
+
     /// Performs single update tick with given delta time from last frame. Internally
-    /// it updates physics, animations, and each graph node. In most cases there is
+    /// it updates constraints, physics, animations, and each graph node. In most cases there is
     /// no need to call it directly, engine automatically updates all available scenes.
     pub fn update(&mut self, frame_size: Vector2<f32>, dt: f32) {
+        profile_scope!("Scene");
+
+        // Constraints are applied on top of animation poses, but before physics to let bodies
+        // bound to nodes follow constrained transforms.
+        self.graph.update_constraints(dt);
+
         self.update_physics();
 
         let last = instant::Instant::now();
-        self.animations.update_animations(dt);
+        // Camera matrices are from previous frame at this moment, which is fine for visibility
+        // checks.
+        let frustums = self
+            .graph
+            .linear_iter()
+            .filter_map(|node| {
+                if let Node::Camera(camera) = node {
+                    if camera.is_enabled() {
+                        return Frustum::from(camera.view_projection_matrix())
+                            .map(|frustum| (frustum, camera.global_position()));
+                    }
+                }
+                None
+            })
+            .collect::<Vec<_>>();
+        let graph = &self.graph;
+        self.performance_statistics.animations =
+            self.animations
+                .update_animations_with_visibility(dt, |animation| {
+                    let lod_distance = animation.lod_distance();
+                    let tracks = animation.get_tracks().iter().map(|track| track.get_node());
+                    let morph_tracks = animation.morph_tracks().iter().map(|track| track.node());
+                    tracks.chain(morph_tracks).any(|node| {
+                        graph.try_get(node).map_or(false, |node| {
+                            let position = node.global_position();
+                            node.global_visibility()
+                                && frustums.iter().any(|(frustum, camera_position)| {
+                                    frustum.is_contains_point(position)
+                                        && position.metric_distance(camera_position) <= lod_distance
+                                })
+                        })
+                    })
+                });
         self.performance_statistics.animations_update_time =
             (instant::Instant::now() - last).as_secs_f32();
 
+        // Texts are moved before the graph update to get actual transforms in this frame.
+        self.floating_texts.update(&mut self.graph, dt);
+
         let last = instant::Instant::now();
         self.graph.update_nodes(frame_size, dt);
         self.performance_statistics.graph_update_time =
             (instant::Instant::now() - last).as_secs_f32();
+        self.performance_statistics.recomputed_transforms = self.graph.recomputed_transforms();
+
+        self.one_shot_sounds
+            .update(&self.sound_context, &self.graph);
+
+        self.splines.update(&self.graph);
 
         self.performance_statistics.sound_update_time = self
             .sound_context
             .state()
             .full_render_duration()
             .as_secs_f32();
+
+        if cfg!(debug_assertions) && self.validation.interval.is_some() {
+            let mut validation = std::mem::take(&mut self.validation);
+            validation.update(self, dt);
+            self.validation = validation;
+        }
+    }
+
+    /// Checks the scene for corrupted state: handle consistency of the graph hierarchy, cycles,
+    /// animation tracks and physics bindings that refer to freed objects, non-finite transforms
+    /// and mesh surfaces without data. Returns a list of found problems, empty list means that
+    /// the scene is fine.
+    ///
+    /// # Performance
+    ///
+    /// The method checks every node, animation track and physics binding, so it has linear
+    /// complexity. It is cheap enough to be called every few seconds, but it should not be
+    /// called every frame.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        validation::validate(self)
+    }
+
+    /// Enables or disables periodic validation of the scene, see [`Scene::validate`]. When
+    /// enabled, the scene is validated with given interval (in seconds) and every found problem
+    /// is written to the log as a warning, each problem is logged only once. Periodic validation
+    /// works only in debug builds, in release builds the interval is ignored.
+    pub fn set_validation_interval(&mut self, interval: Option<f32>) {
+        self.validation.interval = interval;
+    }
+
+    /// Returns current interval of periodic validation.
+    pub fn validation_interval(&self) -> Option<f32> {
+        self.validation.interval
+    }
+
+    /// Returns a readable text tree of the graph with names, types and local transforms of
+    /// nodes. Nodes that are not reachable from the root (which is possible only in corrupted
+    /// graph) are listed separately at the end.
+    pub fn dump_hierarchy(&self) -> String {
+        validation::dump_hierarchy(&self.graph)
+    }
+
+    /// Spawns a text that moves with given velocity and fades out, the text will be removed from
+    /// the graph automatically when its lifetime ends. It is useful for damage numbers, picked up
+    /// items, etc. See [`FloatingTexts`] docs for more info.
+    pub fn spawn_floating_text(&mut self, params: FloatingTextParams) -> Handle<Node> {
+        self.floating_texts.spawn(&mut self.graph, params)
+    }
+
+    /// Plays given sound buffer once, the sound source will be removed from the sound context
+    /// automatically when playback ends. See [`sound`] module docs for more info.
+    pub fn play_sound(
+        &mut self,
+        buffer: SoundBufferResource,
+        params: PlaySoundParams,
+    ) -> Result<Handle<SoundSource>, SoundError> {
+        self.one_shot_sounds
+            .play(&self.sound_context, &self.graph, buffer, params)
+    }
+
+    /// Picks a sound from given variation and plays it once, pitch and gain of the pick are
+    /// multiplied by the pitch and gain from `params`. Returns [`Handle::NONE`] if the variation
+    /// has no sounds to pick. See [`SoundVariation`] docs for more info.
+    pub fn play_variation(
+        &mut self,
+        variation: &SoundVariation,
+        params: PlaySoundParams,
+    ) -> Result<Handle<SoundSource>, SoundError> {
+        self.play_variation_with_rng(variation, params, &mut crate::rand::thread_rng())
+    }
+
+    /// Same as [`Self::play_variation`], but uses given random number generator. Use a seeded
+    /// generator to get the same sequence of sounds every time (for replays, networking, etc.).
+    pub fn play_variation_with_rng<R: Rng + ?Sized>(
+        &mut self,
+        variation: &SoundVariation,
+        mut params: PlaySoundParams,
+        rng: &mut R,
+    ) -> Result<Handle<SoundSource>, SoundError> {
+        match variation.pick(rng) {
+            Some(pick) => {
+                params.pitch *= pick.pitch;
+                params.gain *= pick.gain;
+                self.play_sound(pick.buffer, params)
+            }
+            None => Ok(Handle::NONE),
+        }
+    }
+
+    /// Copies given nodes with all their descendants to other scene, it is useful to move a player
+    /// from one level to another. Copies are attached to the root of the destination graph and have
+    /// the same local transforms as originals. Animations of copied nodes are copied too (only tracks
+    /// of copied nodes are kept), as well as rigid bodies with their colliders. Joints are not copied.
+    pub fn copy_nodes_to(&self, nodes: &[Handle<Node>], dest: &mut Scene) -> CopiedNodes {
+        let mut copied = CopiedNodes::default();
+
+        for &node in nodes {
+            let (_, old_new_map) = self
+                .graph
+                .copy_node(node, &mut dest.graph, &mut |_, _| true);
+            copied.nodes.extend(old_new_map);
+        }
+
+        for (handle, animation) in self.animations.pair_iter() {
+            if animation
+                .get_tracks()
+                .iter()
+                .any(|track| copied.nodes.contains_key(&track.get_node()))
+                || animation
+                    .morph_tracks()
+                    .iter()
+                    .any(|track| copied.nodes.contains_key(&track.node()))
+            {
+                let mut animation = animation.clone();
+                animation.retain_tracks(|track| copied.nodes.contains_key(&track.get_node()));
+                for track in animation.get_tracks_mut() {
+                    track.set_node(copied.nodes[&track.get_node()]);
+                }
+                animation.remap_condition_signals(|node| copied.nodes.get(&node).cloned());
+                animation.remap_morph_tracks(|node| copied.nodes.get(&node).cloned());
+                copied
+                    .animations
+                    .insert(handle, dest.animations.add(animation));
+            }
+        }
+
+        for (node, body) in self.physics_binder.forward_map().iter() {
+            if let Some(&new_node) = copied.nodes.get(node) {
+                if let Some(new_body) = self.physics.copy_body_to(body, &mut dest.physics) {
+                    dest.physics_binder.bind(new_node, new_body);
+                }
+            }
+        }
+
+        copied
     }
 
     /// Creates deep copy of a scene, filter predicate allows you to filter out nodes
@@ -632,6 +920,8 @@ impl Scene {
             for track in animation.get_tracks_mut() {
                 track.set_node(old_new_map[&track.get_node()]);
             }
+            animation.remap_condition_signals(|node| old_new_map.get(&node).cloned());
+            animation.remap_morph_tracks(|node| old_new_map.get(&node).cloned());
         }
         // It is ok to use old binder here, because handles maps one-to-one.
         let physics = self
@@ -646,6 +936,10 @@ impl Scene {
                 physics_binder.bind(new_node, body);
             }
         }
+        let mut splines = self.splines.clone();
+        splines.remap_handles(&old_new_map);
+        let mut floating_texts = self.floating_texts.clone();
+        floating_texts.remap_handles(&old_new_map);
         (
             Self {
                 graph,
@@ -658,10 +952,18 @@ impl Scene {
                 lightmap: self.lightmap.clone(),
                 drawing_context: self.drawing_context.clone(),
                 sound_context: self.sound_context.deep_clone(),
+                // Sources have the same handles in the copy of the context.
+                one_shot_sounds: self.one_shot_sounds.clone(),
+                floating_texts,
                 navmeshes: self.navmeshes.clone(),
+                splines,
                 performance_statistics: Default::default(),
                 ambient_lighting_color: self.ambient_lighting_color,
                 enabled: self.enabled,
+                render: self.render,
+                render_order: self.render_order,
+                sound_paused: false,
+                validation: Default::default(),
             },
             old_new_map,
         )
@@ -678,9 +980,12 @@ impl Visit for Scene {
         self.lightmap.visit("Lightmap", visitor)?;
         self.sound_context.visit("SoundContext", visitor)?;
         self.navmeshes.visit("NavMeshes", visitor)?;
+        let _ = self.splines.visit("Splines", visitor);
         self.ambient_lighting_color
             .visit("AmbientLightingColor", visitor)?;
         self.enabled.visit("Enabled", visitor)?;
+        let _ = self.render.visit("Render", visitor);
+        let _ = self.render_order.visit("RenderOrder", visitor);
         visitor.leave_region()
     }
 }
@@ -710,6 +1015,19 @@ impl SceneContainer {
         self.pool.pair_iter()
     }
 
+    /// Returns scenes that should be rendered (with `render` flag set) in order of rendering,
+    /// see [`Scene::render_order`] for more info.
+    pub fn render_queue(&self) -> Vec<(Handle<Scene>, &Scene)> {
+        let mut queue = self
+            .pool
+            .pair_iter()
+            .filter(|(_, s)| s.render)
+            .collect::<Vec<_>>();
+        // Sort is stable, so scenes with the same order will be rendered in order of addition.
+        queue.sort_by_key(|(_, s)| s.render_order);
+        queue
+    }
+
     /// Creates new iterator over scenes in container.
     #[inline]
     pub fn iter(&self) -> PoolIterator<Scene> {
@@ -748,6 +1066,25 @@ impl SceneContainer {
         self.pool.free(handle);
     }
 
+    /// Replaces a scene with a new one (usually loaded by [`crate::engine::Engine::load_scene_async`]).
+    /// Nodes from `keep` list (for example a player) are copied to the new scene before the old scene
+    /// is removed, see [`Scene::copy_nodes_to`] for details. Sound context of the old scene is removed
+    /// from the sound engine, GPU data associated with the old scene is released by the renderer on
+    /// next frame, resources that are not used anymore are unloaded by resource manager after their
+    /// lifetime is over.
+    ///
+    /// Returns handle of the new scene and mapping of copied nodes.
+    pub fn swap_to(
+        &mut self,
+        current: Handle<Scene>,
+        mut new_scene: Scene,
+        keep: &[Handle<Node>],
+    ) -> (Handle<Scene>, CopiedNodes) {
+        let copied = self.pool[current].copy_nodes_to(keep, &mut new_scene);
+        self.remove(current);
+        (self.add(new_scene), copied)
+    }
+
     /// Takes scene from the container and transfers ownership to caller. You must either
     /// put scene back using ticket or call `forget_ticket` to make memory used by scene
     /// vacant again.
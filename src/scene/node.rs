@@ -13,8 +13,9 @@ use crate::{
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::{
-        base::Base, camera::Camera, decal::Decal, light::Light, mesh::Mesh,
-        particle_system::ParticleSystem, sprite::Sprite, terrain::Terrain,
+        base::Base, camera::Camera, decal::Decal, impostor::Impostor, light::Light, mesh::Mesh,
+        particle_system::ParticleSystem, probe::EnvironmentProbe, sprite::Sprite, terrain::Terrain,
+        water::Water, world_text::WorldText,
     },
 };
 use std::ops::{Deref, DerefMut};
@@ -32,6 +33,10 @@ macro_rules! static_dispatch {
             Node::Sprite(v) => v.$func($($args),*),
             Node::Terrain(v) => v.$func($($args),*),
             Node::Decal(v) => v.$func($($args),*),
+            Node::Water(v) => v.$func($($args),*),
+            Node::EnvironmentProbe(v) => v.$func($($args),*),
+            Node::WorldText(v) => v.$func($($args),*),
+            Node::Impostor(v) => v.$func($($args),*),
         }
     };
 }
@@ -151,6 +156,26 @@ pub enum Node {
     ///
     /// For more info see Decal node docs.
     Decal(Decal),
+
+    /// A flat water surface with reflections and refraction.
+    ///
+    /// For more info see [`Water`] node docs.
+    Water(Water),
+
+    /// A probe that captures surroundings into a cube map for reflections.
+    ///
+    /// For more info see [`EnvironmentProbe`] node docs.
+    EnvironmentProbe(EnvironmentProbe),
+
+    /// A text in the 3D scene, for example a name of a character or a damage number.
+    ///
+    /// For more info see [`WorldText`] node docs.
+    WorldText(WorldText),
+
+    /// A camera-facing quad that replaces a distant object with a picture of it.
+    ///
+    /// For more info see [`Impostor`] node docs.
+    Impostor(Impostor),
 }
 
 macro_rules! static_dispatch_deref {
@@ -164,6 +189,10 @@ macro_rules! static_dispatch_deref {
             Node::Sprite(v) => v,
             Node::Terrain(v) => v,
             Node::Decal(v) => v,
+            Node::Water(v) => v,
+            Node::EnvironmentProbe(v) => v,
+            Node::WorldText(v) => v,
+            Node::Impostor(v) => v,
         }
     };
 }
@@ -210,6 +239,10 @@ impl Node {
             5 => Ok(Self::ParticleSystem(Default::default())),
             6 => Ok(Self::Terrain(Default::default())),
             7 => Ok(Self::Decal(Default::default())),
+            8 => Ok(Self::Water(Default::default())),
+            9 => Ok(Self::EnvironmentProbe(Default::default())),
+            10 => Ok(Self::WorldText(Default::default())),
+            11 => Ok(Self::Impostor(Default::default())),
             _ => Err(format!("Invalid node kind {}", id)),
         }
     }
@@ -225,6 +258,10 @@ impl Node {
             Self::ParticleSystem(_) => 5,
             Self::Terrain(_) => 6,
             Self::Decal(_) => 7,
+            Self::Water(_) => 8,
+            Self::EnvironmentProbe(_) => 9,
+            Self::WorldText(_) => 10,
+            Self::Impostor(_) => 11,
         }
     }
 
@@ -241,6 +278,10 @@ impl Node {
             Node::ParticleSystem(v) => Node::ParticleSystem(v.raw_copy()),
             Node::Terrain(v) => Node::Terrain(v.raw_copy()),
             Node::Decal(v) => Node::Decal(v.raw_copy()),
+            Node::Water(v) => Node::Water(v.raw_copy()),
+            Node::EnvironmentProbe(v) => Node::EnvironmentProbe(v.raw_copy()),
+            Node::WorldText(v) => Node::WorldText(v.raw_copy()),
+            Node::Impostor(v) => Node::Impostor(v.raw_copy()),
         }
     }
 
@@ -251,4 +292,8 @@ impl Node {
     define_is_as!(Node : Sprite -> ref Sprite => fn is_sprite, fn as_sprite, fn as_sprite_mut);
     define_is_as!(Node : Terrain -> ref Terrain => fn is_terrain, fn as_terrain, fn as_terrain_mut);
     define_is_as!(Node : Decal -> ref Decal => fn is_decal, fn as_decal, fn as_decal_mut);
+    define_is_as!(Node : Water -> ref Water => fn is_water, fn as_water, fn as_water_mut);
+    define_is_as!(Node : EnvironmentProbe -> ref EnvironmentProbe => fn is_environment_probe, fn as_environment_probe, fn as_environment_probe_mut);
+    define_is_as!(Node : WorldText -> ref WorldText => fn is_world_text, fn as_world_text, fn as_world_text_mut);
+    define_is_as!(Node : Impostor -> ref Impostor => fn is_impostor, fn as_impostor, fn as_impostor_mut);
 }
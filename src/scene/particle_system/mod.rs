@@ -12,6 +12,19 @@
 //! Particle system can contain multiple particle emitters, each emitter has its own
 //! set of properties and it defines law of change of particle parameters over time.
 //!
+//! # Size over lifetime
+//!
+//! Size of particles can be changed over their lifetime using a [`Curve`], for example sparks
+//! can shrink before they disappear, while smoke puffs usually grow. See
+//! [`ParticleSystem::set_size_over_lifetime`].
+//!
+//! # Lighting
+//!
+//! Particle systems are not affected by lights by default, which is what you want for fire,
+//! sparks and so on. Smoke or dust, however, may look better when lit - use
+//! [`ParticleSystem::set_lighting_mode`] for that. Lighting is calculated once per particle
+//! system at its position, so every particle gets the same amount of light.
+//!
 //! # Performance
 //!
 //! In general particle system can be considered as heavy visual effect, but total impact
@@ -75,6 +88,7 @@ use crate::{
         algebra::{Vector2, Vector3},
         color::Color,
         color_gradient::ColorGradient,
+        curve::Curve,
         inspect::{Inspect, PropertyInfo},
         math::TriangleDefinition,
         pool::Handle,
@@ -84,6 +98,7 @@ use crate::{
     scene::{
         base::{Base, BaseBuilder},
         graph::Graph,
+        light::LightingMode,
         node::Node,
         particle_system::{
             draw::{DrawData, Vertex},
@@ -152,6 +167,10 @@ pub struct ParticleSystem {
     soft_boundary_sharpness_factor: f32,
     #[visit(optional)] // Backward compatibility.
     enabled: bool,
+    #[visit(optional)] // Backward compatibility.
+    lighting_mode: LightingMode,
+    #[visit(optional)] // Backward compatibility.
+    size_over_lifetime: Option<Curve>,
 }
 
 impl Deref for ParticleSystem {
@@ -181,6 +200,8 @@ impl ParticleSystem {
             color_over_lifetime: self.color_over_lifetime.clone(),
             soft_boundary_sharpness_factor: self.soft_boundary_sharpness_factor,
             enabled: self.enabled,
+            lighting_mode: self.lighting_mode,
+            size_over_lifetime: self.size_over_lifetime.clone(),
         }
     }
 
@@ -200,6 +221,19 @@ impl ParticleSystem {
         self.color_over_lifetime = Some(gradient)
     }
 
+    /// Sets new curve that defines size multiplier of particles over their lifetime. The curve
+    /// is evaluated in `[0; 1]` range, where 0 - particle was just born, 1 - end of particle's
+    /// life. The multiplier does not affect actual size of particles, it is applied only when
+    /// rendering.
+    pub fn set_size_over_lifetime(&mut self, curve: Option<Curve>) {
+        self.size_over_lifetime = curve;
+    }
+
+    /// Returns current size over lifetime curve.
+    pub fn size_over_lifetime(&self) -> Option<&Curve> {
+        self.size_over_lifetime.as_ref()
+    }
+
     /// Return current soft boundary sharpness factor.
     pub fn soft_boundary_sharpness_factor(&self) -> f32 {
         self.soft_boundary_sharpness_factor
@@ -216,6 +250,16 @@ impl ParticleSystem {
         self.enabled
     }
 
+    /// Sets lighting mode of the particle system. Default is [`LightingMode::Unlit`].
+    pub fn set_lighting_mode(&mut self, lighting_mode: LightingMode) {
+        self.lighting_mode = lighting_mode;
+    }
+
+    /// Returns current lighting mode of the particle system.
+    pub fn lighting_mode(&self) -> LightingMode {
+        self.lighting_mode
+    }
+
     /// Sets soft boundary sharpness factor. This value defines how wide soft boundary will be.
     /// The greater the factor is the more thin the boundary will be, and vice versa. This
     /// parameter allows you to manipulate particle "softness" - the engine automatically adds
@@ -334,10 +378,20 @@ impl ParticleSystem {
 
             let linear_color = particle.color.srgb_to_linear();
 
+            let size = match self.size_over_lifetime.as_ref() {
+                Some(size_over_lifetime) if particle.initial_lifetime > 0.0 => {
+                    particle.size
+                        * size_over_lifetime
+                            .evaluate(particle.lifetime / particle.initial_lifetime)
+                            .max(0.0)
+                }
+                _ => particle.size,
+            };
+
             draw_data.vertices.push(Vertex {
                 position: particle.position,
                 tex_coord: Vector2::default(),
-                size: particle.size,
+                size,
                 rotation: particle.rotation,
                 color: linear_color,
             });
@@ -345,7 +399,7 @@ impl ParticleSystem {
             draw_data.vertices.push(Vertex {
                 position: particle.position,
                 tex_coord: Vector2::new(1.0, 0.0),
-                size: particle.size,
+                size,
                 rotation: particle.rotation,
                 color: linear_color,
             });
@@ -353,7 +407,7 @@ impl ParticleSystem {
             draw_data.vertices.push(Vertex {
                 position: particle.position,
                 tex_coord: Vector2::new(1.0, 1.0),
-                size: particle.size,
+                size,
                 rotation: particle.rotation,
                 color: linear_color,
             });
@@ -361,7 +415,7 @@ impl ParticleSystem {
             draw_data.vertices.push(Vertex {
                 position: particle.position,
                 tex_coord: Vector2::new(0.0, 1.0),
-                size: particle.size,
+                size,
                 rotation: particle.rotation,
                 color: linear_color,
             });
@@ -414,6 +468,8 @@ pub struct ParticleSystemBuilder {
     color_over_lifetime: Option<ColorGradient>,
     soft_boundary_sharpness_factor: f32,
     enabled: bool,
+    lighting_mode: LightingMode,
+    size_over_lifetime: Option<Curve>,
 }
 
 impl ParticleSystemBuilder {
@@ -428,6 +484,8 @@ impl ParticleSystemBuilder {
             color_over_lifetime: None,
             soft_boundary_sharpness_factor: 2.5,
             enabled: true,
+            lighting_mode: LightingMode::Unlit,
+            size_over_lifetime: None,
         }
     }
 
@@ -467,6 +525,13 @@ impl ParticleSystemBuilder {
         self
     }
 
+    /// Sets size over lifetime curve for particle system, see
+    /// [`ParticleSystem::set_size_over_lifetime`] for more info.
+    pub fn with_size_over_lifetime(mut self, curve: Curve) -> Self {
+        self.size_over_lifetime = Some(curve);
+        self
+    }
+
     /// Sets an initial set of particles that not belongs to any emitter. This method
     /// could be useful if you need a custom position/velocity/etc. of each particle.
     pub fn with_particles(mut self, particles: Vec<Particle>) -> Self {
@@ -480,6 +545,12 @@ impl ParticleSystemBuilder {
         self
     }
 
+    /// Sets desired lighting mode.
+    pub fn with_lighting_mode(mut self, lighting_mode: LightingMode) -> Self {
+        self.lighting_mode = lighting_mode;
+        self
+    }
+
     fn build_particle_system(self) -> ParticleSystem {
         ParticleSystem {
             base: self.base_builder.build_base(),
@@ -491,6 +562,8 @@ impl ParticleSystemBuilder {
             color_over_lifetime: self.color_over_lifetime,
             soft_boundary_sharpness_factor: self.soft_boundary_sharpness_factor,
             enabled: self.enabled,
+            lighting_mode: self.lighting_mode,
+            size_over_lifetime: self.size_over_lifetime,
         }
     }
 
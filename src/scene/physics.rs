@@ -12,11 +12,11 @@ use crate::{
         desc::{ColliderDesc, ColliderShapeDesc, JointDesc, PhysicsDesc, RigidBodyDesc},
         joint::JointContainer,
         rapier::{
-            dynamics::{JointSet, RigidBodyBuilder, RigidBodySet, RigidBodyType},
+            dynamics::{JointSet, RigidBody, RigidBodyBuilder, RigidBodySet, RigidBodyType},
             geometry::{Collider, ColliderBuilder, ColliderSet},
             na::{
-                DMatrix, Dynamic, Isometry3, Point3, Translation, UnitQuaternion, VecStorage,
-                Vector3,
+                DMatrix, Dynamic, Isometry3, Matrix4, Point3, Translation, UnitQuaternion,
+                VecStorage, Vector3,
             },
             parry::shape::{SharedShape, TriMesh},
         },
@@ -24,14 +24,14 @@ use crate::{
     },
     resource::model::Model,
     scene::{
-        debug::SceneDrawingContext,
+        debug::{Line, SceneDrawingContext},
         graph::Graph,
         mesh::buffer::{VertexAttributeUsage, VertexReadTrait},
         node::Node,
         terrain::Terrain,
     },
     utils::{
-        log::{Log, MessageKind},
+        log::{log_error, log_info, log_warn},
         raw_mesh::{RawMeshBuilder, RawVertex},
     },
 };
@@ -66,6 +66,24 @@ impl Visit for ResourceLink {
     }
 }
 
+/// Color of colliders of awake dynamic bodies in [`Physics::draw_debug`].
+pub const AWAKE_BODY_COLOR: Color = Color::opaque(0, 220, 0);
+/// Color of colliders of sleeping dynamic bodies in [`Physics::draw_debug`].
+pub const SLEEPING_BODY_COLOR: Color = Color::opaque(60, 90, 200);
+/// Color of colliders of kinematic bodies in [`Physics::draw_debug`].
+pub const KINEMATIC_BODY_COLOR: Color = Color::opaque(230, 200, 0);
+/// Color of colliders of static bodies in [`Physics::draw_debug`].
+pub const STATIC_BODY_COLOR: Color = Color::opaque(200, 200, 200);
+/// Color of sensors in [`Physics::draw_debug`], state of their bodies is ignored.
+pub const SENSOR_COLOR: Color = Color::opaque(220, 0, 220);
+/// Color of contact points and normals in [`Physics::draw_debug`].
+pub const CONTACT_COLOR: Color = Color::opaque(255, 60, 0);
+/// Color of lines between colliders that were paired by the broad phase, but do not touch.
+pub const BROAD_PHASE_PAIR_COLOR: Color = Color::opaque(100, 100, 100);
+
+const CONTACT_POINT_SIZE: f32 = 0.05;
+const CONTACT_NORMAL_LENGTH: f32 = 0.5;
+
 /// Physics world.
 #[derive(Debug)]
 pub struct Physics {
@@ -161,119 +179,177 @@ impl Physics {
             context.draw_transform(body.position().to_homogeneous());
         }
 
+        for collider in self.colliders.iter() {
+            Self::draw_collider(
+                context,
+                collider,
+                self.bodies.native_ref(collider.parent().unwrap()).unwrap(),
+                Color::opaque(200, 200, 200),
+            );
+        }
+    }
+
+    /// Draws colliders colored by state of their bodies (see [`AWAKE_BODY_COLOR`] and other
+    /// colors), contact points and contact normals. Pairs found by the broad phase that do
+    /// not touch are shown as lines between their colliders. Together with
+    /// [`crate::physics3d::PhysicsWorld::statistics`] it allows you to see where the time of a
+    /// simulation step goes: only awake bodies and their contacts are actually simulated.
+    pub fn draw_debug(&self, context: &mut SceneDrawingContext) {
         for collider in self.colliders.iter() {
             let body = self.bodies.native_ref(collider.parent().unwrap()).unwrap();
-            let collider_local_transform = collider.position_wrt_parent().unwrap().to_homogeneous();
-            let transform = body.position().to_homogeneous() * collider_local_transform;
-            if let Some(trimesh) = collider.shape().as_trimesh() {
-                let trimesh: &TriMesh = trimesh;
-                for triangle in trimesh.triangles() {
-                    let a = transform.transform_point(&triangle.a);
-                    let b = transform.transform_point(&triangle.b);
-                    let c = transform.transform_point(&triangle.c);
-                    context.draw_triangle(
-                        a.coords,
-                        b.coords,
-                        c.coords,
-                        Color::opaque(200, 200, 200),
-                    );
+
+            let color = if collider.is_sensor() {
+                SENSOR_COLOR
+            } else if body.is_static() {
+                STATIC_BODY_COLOR
+            } else if body.is_kinematic() {
+                KINEMATIC_BODY_COLOR
+            } else if body.is_sleeping() {
+                SLEEPING_BODY_COLOR
+            } else {
+                AWAKE_BODY_COLOR
+            };
+
+            Self::draw_collider(context, collider, body, color);
+        }
+
+        for pair in self.narrow_phase.contact_pairs() {
+            if !pair.has_any_active_contact {
+                if let (Some(collider1), Some(collider2)) = (
+                    self.colliders.native_ref(pair.collider1),
+                    self.colliders.native_ref(pair.collider2),
+                ) {
+                    context.add_line(Line {
+                        begin: collider1.position().translation.vector,
+                        end: collider2.position().translation.vector,
+                        color: BROAD_PHASE_PAIR_COLOR,
+                    });
                 }
-            } else if let Some(cuboid) = collider.shape().as_cuboid() {
-                let min = -cuboid.half_extents;
-                let max = cuboid.half_extents;
-                context.draw_oob(
-                    &AxisAlignedBoundingBox::from_min_max(min, max),
-                    transform,
-                    Color::opaque(200, 200, 200),
-                );
-            } else if let Some(ball) = collider.shape().as_ball() {
-                context.draw_sphere(
-                    body.position().translation.vector,
-                    10,
-                    10,
-                    ball.radius,
-                    Color::opaque(200, 200, 200),
-                );
-            } else if let Some(cone) = collider.shape().as_cone() {
-                context.draw_cone(
-                    10,
-                    cone.radius,
-                    cone.half_height * 2.0,
-                    transform,
-                    Color::opaque(200, 200, 200),
-                );
-            } else if let Some(cylinder) = collider.shape().as_cylinder() {
-                context.draw_cylinder(
-                    10,
-                    cylinder.radius,
-                    cylinder.half_height * 2.0,
-                    true,
-                    transform,
-                    Color::opaque(200, 200, 200),
-                );
-            } else if let Some(round_cylinder) = collider.shape().as_round_cylinder() {
-                context.draw_cylinder(
-                    10,
-                    round_cylinder.base_shape.radius,
-                    round_cylinder.base_shape.half_height * 2.0,
-                    false,
-                    transform,
-                    Color::opaque(200, 200, 200),
-                );
-            } else if let Some(triangle) = collider.shape().as_triangle() {
-                context.draw_triangle(
-                    triangle.a.coords,
-                    triangle.b.coords,
-                    triangle.c.coords,
-                    Color::opaque(200, 200, 200),
-                );
-            } else if let Some(capsule) = collider.shape().as_capsule() {
-                context.draw_segment_capsule(
-                    capsule.segment.a.coords,
-                    capsule.segment.b.coords,
-                    capsule.radius,
-                    10,
-                    10,
-                    transform,
-                    Color::opaque(200, 200, 200),
+                continue;
+            }
+
+            for manifold in pair.manifolds.iter() {
+                let normal = Vector3::new(
+                    manifold.data.normal.x,
+                    manifold.data.normal.y,
+                    manifold.data.normal.z,
                 );
-            } else if let Some(heightfield) = collider.shape().as_heightfield() {
-                for triangle in heightfield.triangles() {
-                    let a = transform.transform_point(&triangle.a);
-                    let b = transform.transform_point(&triangle.b);
-                    let c = transform.transform_point(&triangle.c);
-                    context.draw_triangle(
-                        a.coords,
-                        b.coords,
-                        c.coords,
-                        Color::opaque(200, 200, 200),
-                    );
+
+                for contact in manifold.data.solver_contacts.iter() {
+                    let point = contact.point.coords;
+
+                    for axis in [Vector3::x(), Vector3::y(), Vector3::z()] {
+                        context.add_line(Line {
+                            begin: point - axis.scale(CONTACT_POINT_SIZE),
+                            end: point + axis.scale(CONTACT_POINT_SIZE),
+                            color: CONTACT_COLOR,
+                        });
+                    }
+
+                    context.add_line(Line {
+                        begin: point,
+                        end: point + normal.scale(CONTACT_NORMAL_LENGTH),
+                        color: CONTACT_COLOR,
+                    });
                 }
             }
         }
     }
 
-    /// Creates new trimesh collider shape from given mesh node. It also bakes scale into
-    /// vertices of trimesh because rapier does not support collider scaling yet.
-    pub fn make_trimesh(root: Handle<Node>, graph: &Graph) -> SharedShape {
-        let mut mesh_builder = RawMeshBuilder::new(0, 0);
-
-        // Create inverse transform that will discard rotation and translation, but leave scaling and
-        // other parameters of global transform.
-        // When global transform of node is combined with this transform, we'll get relative transform
-        // with scale baked in. We need to do this because root's transform will be synced with body's
-        // but we don't want to bake entire transform including root's transform.
-        let root_inv_transform = graph
-            .isometric_global_transform(root)
-            .try_inverse()
-            .unwrap();
+    fn draw_collider(
+        context: &mut SceneDrawingContext,
+        collider: &Collider,
+        body: &RigidBody,
+        color: Color,
+    ) {
+        let collider_local_transform = collider.position_wrt_parent().unwrap().to_homogeneous();
+        let transform = body.position().to_homogeneous() * collider_local_transform;
+        if let Some(trimesh) = collider.shape().as_trimesh() {
+            let trimesh: &TriMesh = trimesh;
+            for triangle in trimesh.triangles() {
+                let a = transform.transform_point(&triangle.a);
+                let b = transform.transform_point(&triangle.b);
+                let c = transform.transform_point(&triangle.c);
+                context.draw_triangle(a.coords, b.coords, c.coords, color);
+            }
+        } else if let Some(cuboid) = collider.shape().as_cuboid() {
+            let min = -cuboid.half_extents;
+            let max = cuboid.half_extents;
+            context.draw_oob(
+                &AxisAlignedBoundingBox::from_min_max(min, max),
+                transform,
+                color,
+            );
+        } else if let Some(ball) = collider.shape().as_ball() {
+            context.draw_sphere(
+                body.position().translation.vector,
+                10,
+                10,
+                ball.radius,
+                color,
+            );
+        } else if let Some(cone) = collider.shape().as_cone() {
+            context.draw_cone(10, cone.radius, cone.half_height * 2.0, transform, color);
+        } else if let Some(cylinder) = collider.shape().as_cylinder() {
+            context.draw_cylinder(
+                10,
+                cylinder.radius,
+                cylinder.half_height * 2.0,
+                true,
+                transform,
+                color,
+            );
+        } else if let Some(round_cylinder) = collider.shape().as_round_cylinder() {
+            context.draw_cylinder(
+                10,
+                round_cylinder.base_shape.radius,
+                round_cylinder.base_shape.half_height * 2.0,
+                false,
+                transform,
+                color,
+            );
+        } else if let Some(triangle) = collider.shape().as_triangle() {
+            context.draw_triangle(
+                triangle.a.coords,
+                triangle.b.coords,
+                triangle.c.coords,
+                color,
+            );
+        } else if let Some(capsule) = collider.shape().as_capsule() {
+            context.draw_segment_capsule(
+                capsule.segment.a.coords,
+                capsule.segment.b.coords,
+                capsule.radius,
+                10,
+                10,
+                transform,
+                color,
+            );
+        } else if let Some(heightfield) = collider.shape().as_heightfield() {
+            for triangle in heightfield.triangles() {
+                let a = transform.transform_point(&triangle.a);
+                let b = transform.transform_point(&triangle.b);
+                let c = transform.transform_point(&triangle.c);
+                context.draw_triangle(a.coords, b.coords, c.coords, color);
+            }
+        }
+    }
 
-        // Iterate over hierarchy of nodes and build one single trimesh.
+    /// Calls given closure for every triangle of every mesh in given hierarchy. Vertices are
+    /// transformed by global transform of their mesh and then by given transform.
+    fn visit_mesh_triangles<F>(
+        root: Handle<Node>,
+        graph: &Graph,
+        transform: &Matrix4<f32>,
+        mut func: F,
+    ) where
+        F: FnMut([Point3<f32>; 3]),
+    {
         let mut stack = vec![root];
         while let Some(handle) = stack.pop() {
             let node = &graph[handle];
             if let Node::Mesh(mesh) = node {
-                let global_transform = root_inv_transform * mesh.global_transform();
+                let global_transform = transform * mesh.global_transform();
 
                 for surface in mesh.surfaces() {
                     let shared_data = surface.data();
@@ -281,48 +357,44 @@ impl Physics {
 
                     let vertices = &shared_data.vertex_buffer;
                     for triangle in shared_data.geometry_buffer.iter() {
-                        let a = RawVertex::from(
-                            global_transform
-                                .transform_point(&Point3::from(
-                                    vertices
-                                        .get(triangle[0] as usize)
-                                        .unwrap()
-                                        .read_3_f32(VertexAttributeUsage::Position)
-                                        .unwrap(),
-                                ))
-                                .coords,
-                        );
-                        let b = RawVertex::from(
-                            global_transform
-                                .transform_point(&Point3::from(
-                                    vertices
-                                        .get(triangle[1] as usize)
-                                        .unwrap()
-                                        .read_3_f32(VertexAttributeUsage::Position)
-                                        .unwrap(),
-                                ))
-                                .coords,
-                        );
-                        let c = RawVertex::from(
-                            global_transform
-                                .transform_point(&Point3::from(
-                                    vertices
-                                        .get(triangle[2] as usize)
-                                        .unwrap()
-                                        .read_3_f32(VertexAttributeUsage::Position)
-                                        .unwrap(),
-                                ))
-                                .coords,
-                        );
+                        let vertex = |i: usize| {
+                            global_transform.transform_point(&Point3::from(
+                                vertices
+                                    .get(triangle[i] as usize)
+                                    .unwrap()
+                                    .read_3_f32(VertexAttributeUsage::Position)
+                                    .unwrap(),
+                            ))
+                        };
 
-                        mesh_builder.insert(a);
-                        mesh_builder.insert(b);
-                        mesh_builder.insert(c);
+                        func([vertex(0), vertex(1), vertex(2)]);
                     }
                 }
             }
             stack.extend_from_slice(node.children.as_slice());
         }
+    }
+
+    /// Creates new trimesh collider shape from given mesh node. It also bakes scale into
+    /// vertices of trimesh because rapier does not support collider scaling yet.
+    pub fn make_trimesh(root: Handle<Node>, graph: &Graph) -> SharedShape {
+        let mut mesh_builder = RawMeshBuilder::new(0, 0);
+
+        // Create inverse transform that will discard rotation and translation, but leave scaling and
+        // other parameters of global transform.
+        // When global transform of node is combined with this transform, we'll get relative transform
+        // with scale baked in. We need to do this because root's transform will be synced with body's
+        // but we don't want to bake entire transform including root's transform.
+        let root_inv_transform = graph
+            .isometric_global_transform(root)
+            .try_inverse()
+            .unwrap();
+
+        Self::visit_mesh_triangles(root, graph, &root_inv_transform, |triangle| {
+            for vertex in triangle {
+                mesh_builder.insert(RawVertex::from(vertex.coords));
+            }
+        });
 
         let raw_mesh = mesh_builder.build();
 
@@ -339,18 +411,13 @@ impl Physics {
             .collect::<Vec<_>>();
 
         if indices.is_empty() {
-            Log::writeln(
-                MessageKind::Warning,
-                format!(
-                    "Failed to create triangle mesh collider for {}, it has no vertices!",
-                    graph[root].name()
-                ),
+            log_warn!(
+                "Failed to create triangle mesh collider for {}, it has no vertices!",
+                graph[root].name()
             );
-
-            SharedShape::trimesh(vec![Point3::new(0.0, 0.0, 0.0)], vec![[0, 0, 0]])
-        } else {
-            SharedShape::trimesh(vertices, indices)
         }
+
+        Self::make_trimesh_shape(vertices, indices)
     }
 
     /// Creates height field shape from given terrain.
@@ -406,6 +473,15 @@ impl Physics {
     /// data. So if given mesh was at some position with any rotation and scale
     /// resulting static geometry will have vertices that exactly matches given
     /// mesh.
+    ///
+    /// # Performance
+    ///
+    /// Triangles of the trimesh are organized in a bounding volume hierarchy once, when the
+    /// collider is created. Contact generation and ray casts use the hierarchy to test only
+    /// triangles near a query, so large level meshes do not need to be split manually. If the
+    /// mesh was changed, use [`Self::rebuild_trimesh`] to update the collider. Parts of the trimesh
+    /// can be removed or added at runtime, see [`Self::remove_trimesh_triangles`] and
+    /// [`Self::add_trimesh_triangles`].
     pub fn mesh_to_trimesh(&mut self, root: Handle<Node>, graph: &Graph) -> RigidBodyHandle {
         let shape = Self::make_trimesh(root, graph);
         let tri_mesh = ColliderBuilder::new(shape).friction(0.0).build();
@@ -423,6 +499,239 @@ impl Physics {
         handle
     }
 
+    /// Replaces shape of given trimesh collider with a new one made from current state of given
+    /// mesh hierarchy. Use it when geometry of a mesh, that was converted to static geometry with
+    /// [`Self::mesh_to_trimesh`], has changed. Returns `false` if there is no such collider.
+    pub fn rebuild_trimesh(
+        &mut self,
+        collider: &ColliderHandle,
+        root: Handle<Node>,
+        graph: &Graph,
+    ) -> bool {
+        let shape = Self::make_trimesh(root, graph);
+        if let Some(bounds) = self.set_trimesh_shape(collider, shape) {
+            self.wake_up_bodies(&bounds);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Rebuilds every trimesh collider of given rigid body from a scene node that is bound to the
+    /// body. This is the same process that is used to restore trimeshes when a scene is loaded,
+    /// so it is the way to "re-sync" static geometry with its mesh after the mesh was modified.
+    /// Returns `false` if there is no node bound to the body.
+    pub fn rebuild_bound_trimesh(
+        &mut self,
+        body: &RigidBodyHandle,
+        binder: &PhysicsBinder<Node, RigidBodyHandle>,
+        graph: &Graph,
+    ) -> bool {
+        let node = match binder.node_of(*body) {
+            Some(node) if graph.is_valid_handle(node) => node,
+            _ => return false,
+        };
+
+        let colliders = match self.bodies.get(body) {
+            Some(body) => body
+                .colliders()
+                .iter()
+                .filter_map(|c| self.colliders.handle_map().key_of(c).cloned())
+                .collect::<Vec<_>>(),
+            None => return false,
+        };
+
+        let shape = Self::make_trimesh(node, graph);
+        for collider in colliders.iter() {
+            if self
+                .colliders
+                .get(collider)
+                .map_or(false, |c| c.shape().as_trimesh().is_some())
+            {
+                if let Some(bounds) = self.set_trimesh_shape(collider, shape.clone()) {
+                    self.wake_up_bodies(&bounds);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Removes triangles of given trimesh collider, whose centers lie inside given bounds in world
+    /// coordinates. It could be used to make a hole in level geometry, for example when a wall was
+    /// destroyed. Sleeping bodies near removed triangles are woken up, so they'll fall through the
+    /// hole, contacts with removed triangles will be dropped on next simulation step. Returns
+    /// amount of removed triangles.
+    ///
+    /// # Notes
+    ///
+    /// Trimeshes are not serialized, they're restored from associated meshes on load, so you
+    /// should modify the mesh as well (or use [`Self::rebuild_bound_trimesh`] after modification
+    /// of the mesh) if changes must survive save/load.
+    pub fn remove_trimesh_triangles(
+        &mut self,
+        collider: &ColliderHandle,
+        bounds: &AxisAlignedBoundingBox,
+    ) -> usize {
+        let (vertices, indices, position) = match self.trimesh_data(collider) {
+            Some(data) => data,
+            None => return 0,
+        };
+
+        let mut affected_bounds = AxisAlignedBoundingBox::default();
+        let count = indices.len();
+        let indices = indices
+            .into_iter()
+            .filter(|triangle| {
+                let points = triangle.map(|i| (position * vertices[i as usize]).coords);
+                let center = (points[0] + points[1] + points[2]).scale(1.0 / 3.0);
+                if bounds.is_contains_point(center) {
+                    for point in points {
+                        affected_bounds.add_point(point);
+                    }
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect::<Vec<_>>();
+        let removed = count - indices.len();
+
+        if removed > 0 {
+            self.set_trimesh_shape(collider, Self::make_trimesh_shape(vertices, indices));
+            self.wake_up_bodies(&affected_bounds);
+        }
+
+        removed
+    }
+
+    /// Adds triangles of every mesh in given hierarchy to given trimesh collider. Meshes are
+    /// added at their current positions in the world. Sleeping bodies near new triangles are
+    /// woken up. Returns `false` if there is no such trimesh collider.
+    ///
+    /// See notes of [`Self::remove_trimesh_triangles`] about serialization.
+    pub fn add_trimesh_triangles(
+        &mut self,
+        collider: &ColliderHandle,
+        root: Handle<Node>,
+        graph: &Graph,
+    ) -> bool {
+        let (mut vertices, mut indices, position) = match self.trimesh_data(collider) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        let mut affected_bounds = AxisAlignedBoundingBox::default();
+        let inv_position = position.inverse().to_homogeneous();
+        Self::visit_mesh_triangles(root, graph, &inv_position, |triangle| {
+            let base = vertices.len() as u32;
+            for vertex in triangle {
+                affected_bounds.add_point((position * vertex).coords);
+                vertices.push(vertex);
+            }
+            indices.push([base, base + 1, base + 2]);
+        });
+
+        self.set_trimesh_shape(collider, Self::make_trimesh_shape(vertices, indices));
+        self.wake_up_bodies(&affected_bounds);
+
+        true
+    }
+
+    // Returns copy of vertices and indices of a trimesh collider with its position.
+    fn trimesh_data(
+        &self,
+        collider: &ColliderHandle,
+    ) -> Option<(Vec<Point3<f32>>, Vec<[u32; 3]>, Isometry3<f32>)> {
+        let collider = self.colliders.get(collider)?;
+        let trimesh = collider.shape().as_trimesh()?;
+        Some((
+            trimesh.vertices().to_vec(),
+            trimesh.indices().to_vec(),
+            *collider.position(),
+        ))
+    }
+
+    fn make_trimesh_shape(vertices: Vec<Point3<f32>>, indices: Vec<[u32; 3]>) -> SharedShape {
+        if indices.is_empty() {
+            // Trimesh can't be empty, use degenerated triangle instead.
+            SharedShape::trimesh(vec![Point3::new(0.0, 0.0, 0.0)], vec![[0, 0, 0]])
+        } else {
+            SharedShape::trimesh(vertices, indices)
+        }
+    }
+
+    // Sets new shape for a trimesh collider, returns bounds of both old and new shapes in world
+    // coordinates.
+    fn set_trimesh_shape(
+        &mut self,
+        collider: &ColliderHandle,
+        shape: SharedShape,
+    ) -> Option<AxisAlignedBoundingBox> {
+        let collider = self.colliders.get_mut(collider)?;
+        let old_aabb = collider.compute_aabb();
+        let new_aabb = shape.compute_aabb(collider.position());
+        collider.set_shape(shape);
+
+        let mut bounds = AxisAlignedBoundingBox::default();
+        for aabb in [old_aabb, new_aabb] {
+            bounds.add_point(aabb.mins.coords);
+            bounds.add_point(aabb.maxs.coords);
+        }
+        Some(bounds)
+    }
+
+    /// Wakes up every dynamic rigid body which has a collider that intersects given bounds.
+    pub fn wake_up_bodies(&mut self, bounds: &AxisAlignedBoundingBox) {
+        let world = &mut self.world;
+        let mut bounds = *bounds;
+        // Resting bodies are slightly above the surface.
+        bounds.inflate(Vector3::new(0.1, 0.1, 0.1));
+
+        let bodies = world
+            .bodies
+            .pair_iter()
+            .filter(|(_, body)| body.is_dynamic() && body.is_sleeping())
+            .filter(|(_, body)| {
+                body.colliders().iter().any(|c| {
+                    world.colliders.native_ref(*c).map_or(false, |c| {
+                        let aabb = c.compute_aabb();
+                        bounds.intersect_aabb(&AxisAlignedBoundingBox::from_min_max(
+                            aabb.mins.coords,
+                            aabb.maxs.coords,
+                        ))
+                    })
+                })
+            })
+            .map(|(handle, _)| handle)
+            .collect::<Vec<_>>();
+
+        for handle in bodies {
+            if let Some(body) = world.bodies.get_mut(&handle) {
+                body.wake_up(true);
+            }
+        }
+    }
+
+    /// Copies a rigid body with all its colliders to other physics world. Joints are not copied.
+    /// Returns handle of the copy in the other world or `None` if there is no such body.
+    pub fn copy_body_to(
+        &self,
+        body: &RigidBodyHandle,
+        dest: &mut Physics,
+    ) -> Option<RigidBodyHandle> {
+        let body = self.world.bodies.get(body)?;
+        // Rapier resets internal references of a body and its colliders on insertion, so
+        // clones could be safely inserted in other world.
+        let new_body = dest.add_body(body.clone());
+        for &collider in body.colliders() {
+            if let Some(collider) = self.world.colliders.native_ref(collider) {
+                dest.add_collider(collider.clone(), &new_body);
+            }
+        }
+        Some(new_body)
+    }
+
     /// Creates new height field collider from given terrain scene node.
     pub fn terrain_to_heightfield_collider(
         &mut self,
@@ -484,6 +793,7 @@ impl Physics {
         assert_eq!(phys_desc.joints.len(), phys_desc.joint_handle_map.len());
 
         self.integration_parameters = phys_desc.integration_parameters.into();
+        self.solver_settings = phys_desc.solver_settings;
 
         let mut bodies = RigidBodySet::new();
         let mut colliders = ColliderSet::new();
@@ -507,9 +817,10 @@ impl Physics {
 
                         if graph.is_valid_handle(associated_node) {
                             // Restore data only for trimeshes.
-                            let collider =
+                            let mut collider =
                                 ColliderBuilder::new(Self::make_trimesh(associated_node, graph))
                                     .build();
+                            desc.material().apply(&mut collider);
                             colliders.insert_with_parent(
                                 collider,
                                 phys_desc
@@ -520,21 +831,16 @@ impl Physics {
                                 &mut bodies,
                             );
 
-                            Log::writeln(
-                                MessageKind::Information,
-                                format!(
-                                    "Geometry for trimesh {:?} was restored from node at handle {:?}!",
-                                    desc.parent, associated_node
-                                ),
+                            log_info!(
+                                "Geometry for trimesh {:?} was restored from node at handle {:?}!",
+                                desc.parent,
+                                associated_node
                             )
                         } else {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!(
-                                    "Unable to get geometry for trimesh,\
+                            log_error!(
+                                "Unable to get geometry for trimesh,\
                              node at handle {:?} does not exists!",
-                                    associated_node
-                                ),
+                                associated_node
                             )
                         }
                     }
@@ -551,8 +857,9 @@ impl Physics {
 
                         if graph.is_valid_handle(associated_node) {
                             if let Node::Terrain(_) = &graph[associated_node] {
-                                let collider =
+                                let mut collider =
                                     self.terrain_to_heightfield_collider(associated_node, graph);
+                                desc.material().apply(&mut collider);
 
                                 colliders.insert_with_parent(
                                     collider,
@@ -564,31 +871,20 @@ impl Physics {
                                     &mut bodies,
                                 );
 
-                                Log::writeln(
-                                    MessageKind::Information,
-                                    format!(
-                                        "Geometry for height field {:?} was restored from node at handle {:?}!",
-                                        desc.parent, associated_node
-                                    ),
-                                )
+                                log_info!("Geometry for height field {:?} was restored from node at handle {:?}!",
+                                        desc.parent, associated_node)
                             } else {
-                                Log::writeln(
-                                    MessageKind::Error,
-                                    format!(
-                                        "Unable to get geometry for height field,\
+                                log_error!(
+                                    "Unable to get geometry for height field,\
                                  node at handle {:?} is not a terrain!",
-                                        associated_node
-                                    ),
+                                    associated_node
                                 )
                             }
                         } else {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!(
-                                    "Unable to get geometry for height field,\
+                            log_error!(
+                                "Unable to get geometry for height field,\
                             node at handle {:?} does not exists!",
-                                    associated_node
-                                ),
+                                associated_node
                             )
                         }
                     }
@@ -609,6 +905,7 @@ impl Physics {
             }
         }
 
+        let mut break_limits = Vec::new();
         for desc in phys_desc.joints.drain(..) {
             let b1 = phys_desc
                 .body_handle_map
@@ -620,7 +917,8 @@ impl Physics {
                 .value_of(&desc.body2)
                 .cloned()
                 .unwrap();
-            joints.insert(b1, b2, desc.params);
+            let native = joints.insert(b1, b2, desc.params);
+            break_limits.push((native, desc.break_limits));
         }
 
         self.bodies =
@@ -628,6 +926,11 @@ impl Physics {
         self.colliders =
             ColliderContainer::from_raw_parts(colliders, phys_desc.collider_handle_map).unwrap();
         self.joints = JointContainer::from_raw_parts(joints, phys_desc.joint_handle_map).unwrap();
+        for (native, limits) in break_limits {
+            if let Some(handle) = self.joints.handle_map().key_of(&native).cloned() {
+                self.joints.set_break_limits(&handle, limits);
+            }
+        }
     }
 
     pub(in crate) fn embed_resource(
@@ -678,11 +981,12 @@ impl Physics {
                 ColliderShapeDesc::Trimesh(_) => {
                     if let Some(associated_node) = target_binder.node_of(remapped_parent) {
                         if target_graph.is_valid_handle(associated_node) {
-                            let collider = ColliderBuilder::new(Self::make_trimesh(
+                            let mut collider = ColliderBuilder::new(Self::make_trimesh(
                                 associated_node,
                                 target_graph,
                             ))
                             .build();
+                            desc.material().apply(&mut collider);
                             let new_handle = self.add_collider(collider, &remapped_parent);
                             link.colliders.insert(
                                 new_handle,
@@ -694,23 +998,22 @@ impl Physics {
                                     .unwrap(),
                             );
 
-                            Log::writeln(
-                                MessageKind::Information,
-                                format!(
-                                    "Geometry for trimesh {:?} was restored from node at handle {:?}!",
-                                    desc.parent, associated_node
-                                ),
+                            log_info!(
+                                "Geometry for trimesh {:?} was restored from node at handle {:?}!",
+                                desc.parent,
+                                associated_node
                             )
                         } else {
-                            Log::writeln(MessageKind::Error, format!("Unable to get geometry for trimesh, node at handle {:?} does not exists!", associated_node))
+                            log_error!("Unable to get geometry for trimesh, node at handle {:?} does not exists!", associated_node)
                         }
                     }
                 }
                 ColliderShapeDesc::Heightfield(_) => {
                     if let Some(associated_node) = target_binder.node_of(remapped_parent) {
                         if let Some(Node::Terrain(_)) = target_graph.try_get(associated_node) {
-                            let collider =
+                            let mut collider =
                                 self.terrain_to_heightfield_collider(associated_node, target_graph);
+                            desc.material().apply(&mut collider);
 
                             let new_handle = self.add_collider(collider, &remapped_parent);
                             link.colliders.insert(
@@ -723,31 +1026,18 @@ impl Physics {
                                     .unwrap(),
                             );
 
-                            Log::writeln(
-                                MessageKind::Information,
-                                format!(
-                                    "Geometry for height field {:?} was restored from node at handle {:?}!",
-                                    desc.parent, associated_node
-                                ),
-                            )
+                            log_info!("Geometry for height field {:?} was restored from node at handle {:?}!",
+                                    desc.parent, associated_node)
                         } else {
-                            Log::writeln(
-                                MessageKind::Error,
-                                format!(
-                                    "Unable to get geometry for height field,\
+                            log_error!(
+                                "Unable to get geometry for height field,\
                              node at handle {:?} does not exists!",
-                                    associated_node
-                                ),
+                                associated_node
                             )
                         }
                     } else {
-                        Log::writeln(
-                            MessageKind::Information,
-                            format!(
-                                "Unable to restore geometry for height field {:?} because it has no associated node in the scene!",
-                                desc.parent
-                            ),
-                        )
+                        log_info!("Unable to restore geometry for height field {:?} because it has no associated node in the scene!",
+                                desc.parent)
                     }
                 }
                 _ => {
@@ -781,24 +1071,413 @@ impl Physics {
                 .get(self.bodies.handle_map().key_of(&joint.body2).unwrap())
                 .unwrap();
             let new_handle = self.add_joint(new_body1_handle, new_body2_handle, desc.params);
-            link.joints.insert(
-                *resource_physics
-                    .joints
-                    .handle_map()
-                    .key_of(&resource_handle)
-                    .unwrap(),
-                new_handle,
+            let resource_joint_handle = *resource_physics
+                .joints
+                .handle_map()
+                .key_of(&resource_handle)
+                .unwrap();
+            self.set_joint_break_limits(
+                &new_handle,
+                resource_physics.joints.break_limits(&resource_joint_handle),
             );
+            link.joints.insert(resource_joint_handle, new_handle);
         }
 
         self.embedded_resources.push(link);
 
-        Log::writeln(
-            MessageKind::Information,
-            format!(
-                "Resource {} was successfully embedded into physics world!",
-                data.path.display()
+        log_info!(
+            "Resource {} was successfully embedded into physics world!",
+            data.path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::{Matrix4, Point3, Vector3},
+            math::{aabb::AxisAlignedBoundingBox, ray::Ray},
+            parking_lot::Mutex,
+        },
+        physics3d::{
+            rapier::dynamics::{RigidBodyBuilder, RigidBodyType},
+            rapier::geometry::ColliderBuilder,
+            ConvexShape, Intersection, Isometry, RayCastOptions, RigidBodyHandle, ShapeCastOptions,
+        },
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            mesh::{
+                surface::{SurfaceBuilder, SurfaceData},
+                MeshBuilder,
+            },
+            physics::Physics,
+        },
+    };
+    use std::sync::Arc;
+
+    // Static floor with a few layers of boxes above it, boxes are slightly shifted so the pile
+    // will collapse and produce lots of contacts.
+    fn make_box_pile() -> (Physics, Vec<RigidBodyHandle>) {
+        let mut physics = Physics::new();
+
+        let floor = physics.add_body(RigidBodyBuilder::new(RigidBodyType::Static).build());
+        physics.add_collider(ColliderBuilder::cuboid(10.0, 0.1, 10.0).build(), &floor);
+
+        let mut boxes = Vec::new();
+        for y in 0..4 {
+            for z in 0..3 {
+                for x in 0..3 {
+                    let body = physics.add_body(
+                        RigidBodyBuilder::new(RigidBodyType::Dynamic)
+                            .translation(Vector3::new(
+                                x as f32 * 1.05 + y as f32 * 0.13,
+                                0.6 + y as f32 * 1.1,
+                                z as f32 * 1.05 - y as f32 * 0.07,
+                            ))
+                            .build(),
+                    );
+                    physics.add_collider(ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(), &body);
+                    boxes.push(body);
+                }
+            }
+        }
+
+        (physics, boxes)
+    }
+
+    // Bit representation of positions and velocities of given bodies.
+    fn bodies_state(physics: &Physics, bodies: &[RigidBodyHandle]) -> Vec<u32> {
+        let mut state = Vec::new();
+        for handle in bodies {
+            let body = physics.bodies.get(handle).unwrap();
+            let position = body.position();
+            state.extend(position.translation.vector.iter().map(|v| v.to_bits()));
+            state.extend(position.rotation.coords.iter().map(|v| v.to_bits()));
+            state.extend(body.linvel().iter().map(|v| v.to_bits()));
+            state.extend(body.angvel().iter().map(|v| v.to_bits()));
+        }
+        state
+    }
+
+    fn simulate(physics: &mut Physics, steps: usize) {
+        for _ in 0..steps {
+            physics.step();
+        }
+    }
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        let (mut a, a_boxes) = make_box_pile();
+        let (mut b, b_boxes) = make_box_pile();
+
+        simulate(&mut a, 1000);
+        simulate(&mut b, 1000);
+
+        let a_state = bodies_state(&a, &a_boxes);
+        assert_eq!(a_state, bodies_state(&b, &b_boxes));
+        // Make sure the pile has actually moved.
+        let (initial, initial_boxes) = make_box_pile();
+        assert_ne!(a_state, bodies_state(&initial, &initial_boxes));
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let (mut physics, boxes) = make_box_pile();
+        simulate(&mut physics, 100);
+
+        let snapshot = physics.snapshot();
+        assert_eq!(snapshot.bodies().len(), boxes.len() + 1);
+        let snapshot_state = bodies_state(&physics, &boxes);
+
+        simulate(&mut physics, 200);
+        let expected = bodies_state(&physics, &boxes);
+
+        // Modify the world after the snapshot, restore must undo everything.
+        physics.remove_body(&boxes[0]);
+        physics.add_body(RigidBodyBuilder::new(RigidBodyType::Dynamic).build());
+        simulate(&mut physics, 10);
+
+        physics.restore(&snapshot);
+        assert_eq!(physics.bodies.len(), boxes.len() + 1);
+        assert_eq!(bodies_state(&physics, &boxes), snapshot_state);
+
+        simulate(&mut physics, 200);
+        assert_eq!(bodies_state(&physics, &boxes), expected);
+    }
+
+    // A tall stack of crates on a static floor, the top crate must stay where it was.
+    fn crate_stack_top_height(configure: impl FnOnce(&mut Physics)) -> f32 {
+        let mut physics = Physics::new();
+        configure(&mut physics);
+
+        let floor = physics.add_body(RigidBodyBuilder::new(RigidBodyType::Static).build());
+        physics.add_collider(ColliderBuilder::cuboid(10.0, 0.1, 10.0).build(), &floor);
+
+        let mut top = Default::default();
+        for y in 0..8 {
+            top = physics.add_body(
+                RigidBodyBuilder::new(RigidBodyType::Dynamic)
+                    .translation(Vector3::new(0.0, 0.6 + y as f32, 0.0))
+                    .build(),
+            );
+            physics.add_collider(ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(), &top);
+        }
+
+        simulate(&mut physics, 300);
+
+        physics.bodies.get(&top).unwrap().position().translation.y
+    }
+
+    #[test]
+    fn test_crate_stack() {
+        let initial = 7.6;
+
+        let substepped = crate_stack_top_height(|physics| {
+            physics.set_substeps(4);
+            physics.set_solver_iterations(8, 2);
+        });
+        assert!((substepped - initial).abs() < 0.1);
+
+        // Substeps must not change the total amount of simulated time.
+        let mut physics = Physics::new();
+        physics.set_substeps(4);
+        let body = physics.add_body(RigidBodyBuilder::new(RigidBodyType::Dynamic).build());
+        physics.add_collider(ColliderBuilder::ball(0.5).build(), &body);
+        simulate(&mut physics, 60);
+        let velocity = physics.bodies.get(&body).unwrap().linvel().y;
+        assert!((velocity + 9.81).abs() < 0.05);
+
+        // Clamping.
+        physics.set_max_velocities(2.0, f32::MAX);
+        simulate(&mut physics, 1);
+        let velocity = physics.bodies.get(&body).unwrap().linvel().norm();
+        assert!(velocity <= 2.0 + 1.0e-4);
+    }
+
+    // Reference implementation that tests every triangle of the trimesh.
+    fn brute_force_ray_cast(
+        triangles: &[[Vector3<f32>; 3]],
+        origin: Vector3<f32>,
+        dir: Vector3<f32>,
+    ) -> Option<Vector3<f32>> {
+        let ray = Ray::new(origin, dir);
+        triangles
+            .iter()
+            .filter_map(|triangle| ray.triangle_intersection(triangle))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .map(|(_, point)| point)
+    }
+
+    #[test]
+    fn test_trimesh_ray_cast_matches_brute_force() {
+        let mut graph = Graph::new();
+        let mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_sphere(24, 24, 1.0, &Matrix4::identity()),
+            )))
+            .build()])
+            .build(&mut graph);
+        graph.update_hierarchical_data();
+
+        let mut physics = Physics::new();
+        let body = physics.mesh_to_trimesh(mesh, &graph);
+        let collider = physics.bodies.get(&body).unwrap().colliders()[0];
+        let collider = physics
+            .colliders
+            .handle_map()
+            .key_of(&collider)
+            .cloned()
+            .unwrap();
+
+        let shape = Physics::make_trimesh(mesh, &graph);
+        let triangles = shape
+            .as_trimesh()
+            .unwrap()
+            .triangles()
+            .map(|t| [t.a.coords, t.b.coords, t.c.coords])
+            .collect::<Vec<_>>();
+
+        let dir = Vector3::new(0.0, 0.0, 10.0);
+        let mut hits = 0;
+        for i in 0..20 {
+            for j in 0..20 {
+                // Odd offsets to not hit edges of triangles exactly.
+                let origin = Vector3::new(-1.2 + i as f32 * 0.1231, -1.2 + j as f32 * 0.1197, -5.0);
+
+                let mut buffer = Vec::<Intersection>::new();
+                physics.cast_ray(
+                    RayCastOptions {
+                        ray_origin: Point3::from(origin),
+                        ray_direction: dir,
+                        max_len: dir.norm(),
+                        groups: Default::default(),
+                        sort_results: true,
+                    },
+                    &mut buffer,
+                );
+
+                let expected = brute_force_ray_cast(&triangles, origin, dir);
+                match (buffer.first(), expected) {
+                    (Some(actual), Some(expected)) => {
+                        assert!((actual.position.coords - expected).norm() < 1.0e-3);
+                        hits += 1;
+                    }
+                    (None, None) => (),
+                    (actual, expected) => panic!(
+                        "Mismatch for ray from {:?}: {:?} vs {:?}",
+                        origin, actual, expected
+                    ),
+                }
+            }
+        }
+        assert!(hits > 0);
+
+        // Make the sphere bigger and rebuild the trimesh, ray must hit new surface.
+        let data = graph[mesh].as_mesh().surfaces()[0].data();
+        *data.lock() = SurfaceData::make_sphere(24, 24, 2.0, &Matrix4::identity());
+        assert!(physics.rebuild_trimesh(&collider, mesh, &graph));
+
+        let mut buffer = Vec::<Intersection>::new();
+        physics.cast_ray(
+            RayCastOptions {
+                ray_origin: Point3::new(0.0, 0.0, -5.0),
+                ray_direction: dir,
+                max_len: dir.norm(),
+                groups: Default::default(),
+                sort_results: true,
+            },
+            &mut buffer,
+        );
+        assert!((buffer[0].position.z + 2.0).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn test_shape_cast() {
+        let mut physics = Physics::new();
+
+        // Wall with its front face at z = 4.
+        let wall = physics.add_body(
+            RigidBodyBuilder::new(RigidBodyType::Static)
+                .translation(Vector3::new(0.0, 0.0, 5.0))
+                .build(),
+        );
+        physics.add_collider(ColliderBuilder::cuboid(2.0, 2.0, 1.0).build(), &wall);
+
+        // Player capsule, it is excluded from own casts.
+        let player = physics.add_body(RigidBodyBuilder::new(RigidBodyType::Dynamic).build());
+        physics.add_collider(ColliderBuilder::capsule_y(0.5, 0.3).build(), &player);
+
+        let ball = ConvexShape::Ball { radius: 0.5 };
+        let forward = Vector3::new(0.0, 0.0, 1.0);
+        let options = ShapeCastOptions {
+            exclude_body: Some(player),
+            ..Default::default()
+        };
+
+        let hit = physics
+            .shape_cast(
+                &ball,
+                &Isometry::identity(),
+                &forward,
+                10.0,
+                options.clone(),
+            )
+            .unwrap();
+        assert_eq!(hit.body, wall);
+        assert!(!hit.penetrating);
+        assert!((hit.toi - 3.5).abs() < 1.0e-3);
+        assert!((hit.position.z - 4.0).abs() < 1.0e-3);
+        assert!((hit.normal - Vector3::new(0.0, 0.0, -1.0)).norm() < 1.0e-3);
+
+        // Too short cast.
+        assert!(physics
+            .shape_cast(&ball, &Isometry::identity(), &forward, 3.0, options.clone())
+            .is_none());
+
+        // Without exclusion the player capsule is hit immediately.
+        let hit = physics
+            .shape_cast(
+                &ball,
+                &Isometry::identity(),
+                &forward,
+                10.0,
+                Default::default(),
+            )
+            .unwrap();
+        assert_eq!(hit.body, player);
+        assert!(hit.penetrating);
+
+        // Box that starts inside of the wall near its front face must be pushed out.
+        let cuboid = ConvexShape::Cuboid {
+            half_extents: Vector3::new(0.5, 0.5, 0.5),
+        };
+        let hit = physics
+            .shape_cast(
+                &cuboid,
+                &Isometry::translation(0.0, 0.0, 4.2),
+                &forward,
+                10.0,
+                options,
+            )
+            .unwrap();
+        assert!(hit.penetrating);
+        assert_eq!(hit.toi, 0.0);
+        assert!((hit.normal - Vector3::new(0.0, 0.0, -1.0)).norm() < 1.0e-3);
+    }
+
+    #[test]
+    fn test_trimesh_modification() {
+        let mut graph = Graph::new();
+        let mesh = MeshBuilder::new(BaseBuilder::new())
+            .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+                SurfaceData::make_sphere(24, 24, 1.0, &Matrix4::identity()),
+            )))
+            .build()])
+            .build(&mut graph);
+        graph.update_hierarchical_data();
+
+        let mut physics = Physics::new();
+        let body = physics.mesh_to_trimesh(mesh, &graph);
+        let collider = physics.bodies.get(&body).unwrap().colliders()[0];
+        let collider = physics
+            .colliders
+            .handle_map()
+            .key_of(&collider)
+            .cloned()
+            .unwrap();
+
+        let cast_down = |physics: &Physics| {
+            let mut buffer = Vec::<Intersection>::new();
+            physics.cast_ray(
+                RayCastOptions {
+                    ray_origin: Point3::new(0.01, 5.0, 0.01),
+                    ray_direction: Vector3::new(0.0, -10.0, 0.0),
+                    max_len: 10.0,
+                    groups: Default::default(),
+                    sort_results: true,
+                },
+                &mut buffer,
+            );
+            buffer.first().map(|i| i.position.y)
+        };
+
+        assert!((cast_down(&physics).unwrap() - 1.0).abs() < 1.0e-2);
+
+        // Remove upper half of the sphere, ray must hit lower half.
+        let removed = physics.remove_trimesh_triangles(
+            &collider,
+            &AxisAlignedBoundingBox::from_min_max(
+                Vector3::new(-2.0, 0.0, -2.0),
+                Vector3::new(2.0, 2.0, 2.0),
             ),
         );
+        assert!(removed > 0);
+        assert!((cast_down(&physics).unwrap() + 1.0).abs() < 1.0e-2);
+
+        // Add the sphere back.
+        assert!(physics.add_trimesh_triangles(&collider, mesh, &graph));
+        assert!((cast_down(&physics).unwrap() - 1.0).abs() < 1.0e-2);
     }
 }
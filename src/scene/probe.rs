@@ -0,0 +1,486 @@
+//! Environment probe captures surroundings of a point into a cube map, that is used for
+//! reflections on shiny surfaces.
+//!
+//! For more info see [`EnvironmentProbe`]
+
+use crate::{
+    asset::Resource,
+    core::{
+        algebra::Vector3,
+        inspect::{Inspect, PropertyInfo},
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    resource::texture::{Texture, TextureData, TextureKind, TexturePixelKind, TextureState},
+    scene::{
+        base::{Base, BaseBuilder},
+        graph::Graph,
+        node::Node,
+    },
+};
+use std::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+};
+
+/// Defines when an environment probe is baked.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Visit, Inspect)]
+pub enum ProbeUpdateMode {
+    /// Probe is baked automatically when it does not have baked environment, for example when
+    /// it was just created or when it was loaded from a scene that was saved without baked
+    /// environment. This is default mode.
+    Once,
+    /// Probe is baked only when [`EnvironmentProbe::request_bake`] is called.
+    OnDemand,
+}
+
+impl Default for ProbeUpdateMode {
+    fn default() -> Self {
+        Self::Once
+    }
+}
+
+/// Environment probe captures surroundings of its position into a cube map, the cube map is then
+/// used by the lighting pass for reflections (specular term) and ambient lighting of surfaces
+/// that are inside the extent box of the probe. The box is centered at the position of the node
+/// and lies along its local axes.
+///
+/// # Baking
+///
+/// Baking is done by the renderer and it is spread over a few frames: every face of the cube map
+/// is rendered in a separate frame, then roughness mip levels are convolved and the result is
+/// copied back to CPU memory. Use [`EnvironmentProbe::bake_progress`] to track the progress.
+/// The scene is rendered from the position of the probe using `Forward` render pass of
+/// materials, so the reflections are unlit - the same way as in water reflections. Skybox of
+/// the first camera of the scene is rendered as background. Only the nodes whose
+/// [render mask](crate::scene::base::Base::set_render_mask) has at least one common bit with
+/// [visibility mask](EnvironmentProbe::set_visibility_mask) of the probe are captured, use it
+/// to exclude dynamic characters and other moving objects.
+///
+/// Baked environment is stored in a texture, which content is saved together with the scene, so
+/// the probe is not re-rendered when the scene is loaded. The probe must be re-baked after it
+/// was moved or the scene around it has changed.
+///
+/// # Lighting
+///
+/// Reflections use box projection: a reflected ray is intersected with the extent box of the
+/// probe, so reflections of nearby objects are in correct place instead of being "infinitely"
+/// far. This works best when the extent box matches the bounds of a room. The probe is applied
+/// only in cameras whose visibility mask has at least one common bit with render mask of the
+/// probe.
+///
+/// # Limitations
+///
+/// Only one probe is applied in a frame: the one that contains the camera, or the closest one
+/// if there is no such probe. Surfaces outside of the extent box of the probe do not receive
+/// reflections. Blending of multiple probes is not supported yet.
+///
+/// # Example
+///
+/// ```
+/// use rg3d::{
+///     core::{algebra::Vector3, pool::Handle},
+///     scene::{base::BaseBuilder, graph::Graph, node::Node, probe::EnvironmentProbeBuilder},
+/// };
+///
+/// fn create_room_probe(graph: &mut Graph) -> Handle<Node> {
+///     EnvironmentProbeBuilder::new(BaseBuilder::new())
+///         .with_size(Vector3::new(8.0, 3.0, 6.0))
+///         .with_resolution(128)
+///         // Do not capture nodes in the second "layer" (characters for example).
+///         .with_visibility_mask(!0b10)
+///         .build(graph)
+/// }
+/// ```
+#[derive(Debug, Visit, Inspect)]
+pub struct EnvironmentProbe {
+    base: Base,
+    size: Vector3<f32>,
+    #[inspect(min_value = 1.0, step = 1.0)]
+    resolution: u32,
+    #[inspect(min_value = 0.0, step = 0.1)]
+    draw_distance: f32,
+    #[inspect(min_value = 0.0, step = 0.1)]
+    intensity: f32,
+    update_mode: ProbeUpdateMode,
+    visibility_mask: u32,
+    #[inspect(skip)]
+    environment: Texture,
+    #[inspect(skip)]
+    baked: Cell<bool>,
+    #[visit(skip)]
+    #[inspect(skip)]
+    bake_requested: Cell<bool>,
+    #[visit(skip)]
+    #[inspect(skip)]
+    bake_progress: Cell<Option<f32>>,
+}
+
+impl Default for EnvironmentProbe {
+    fn default() -> Self {
+        EnvironmentProbeBuilder::new(BaseBuilder::new()).build_probe()
+    }
+}
+
+impl Deref for EnvironmentProbe {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for EnvironmentProbe {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+// A black 1x1 cube map that is used before the probe is baked.
+fn empty_environment() -> Texture {
+    Texture(Resource::new(TextureState::Ok(
+        TextureData::from_mip_chain(
+            TextureKind::Cube {
+                width: 1,
+                height: 1,
+            },
+            TexturePixelKind::RGBA8,
+            1,
+            vec![0; 6 * 4],
+        ),
+    )))
+}
+
+impl EnvironmentProbe {
+    /// Creates a raw copy of the probe. Baked environment is copied too, so the copy does not
+    /// share it with the original probe.
+    pub fn raw_copy(&self) -> Self {
+        let environment = match *self.environment.state() {
+            TextureState::Ok(ref data) => Texture(Resource::new(TextureState::Ok(
+                TextureData::from_mip_chain(
+                    data.kind(),
+                    data.pixel_kind(),
+                    data.mip_count(),
+                    data.data().to_vec(),
+                ),
+            ))),
+            _ => empty_environment(),
+        };
+
+        Self {
+            base: self.base.raw_copy(),
+            size: self.size,
+            resolution: self.resolution,
+            draw_distance: self.draw_distance,
+            intensity: self.intensity,
+            update_mode: self.update_mode,
+            visibility_mask: self.visibility_mask,
+            environment,
+            baked: self.baked.clone(),
+            bake_requested: self.bake_requested.clone(),
+            bake_progress: Default::default(),
+        }
+    }
+
+    /// Sets new size of the extent box in local coordinates. Only the surfaces inside the box
+    /// receive reflections from the probe.
+    pub fn set_size(&mut self, size: Vector3<f32>) {
+        self.size = size.sup(&Vector3::default());
+    }
+
+    /// Returns current size of the extent box.
+    pub fn size(&self) -> Vector3<f32> {
+        self.size
+    }
+
+    /// Sets new size (in texels) of a face of the cube map. The probe must be re-baked to apply
+    /// new resolution. Default value is 128.
+    pub fn set_resolution(&mut self, resolution: u32) {
+        self.resolution = resolution.max(1);
+    }
+
+    /// Returns current resolution of the cube map.
+    pub fn resolution(&self) -> u32 {
+        self.resolution
+    }
+
+    /// Sets max distance at which objects are captured by the probe.
+    pub fn set_draw_distance(&mut self, draw_distance: f32) {
+        self.draw_distance = draw_distance.max(0.0);
+    }
+
+    /// Returns current draw distance.
+    pub fn draw_distance(&self) -> f32 {
+        self.draw_distance
+    }
+
+    /// Sets how much the environment contributes to lighting. Default value is 1.0.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = intensity.max(0.0);
+    }
+
+    /// Returns current intensity.
+    pub fn intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Sets new update mode, see [`ProbeUpdateMode`] docs for more info.
+    pub fn set_update_mode(&mut self, update_mode: ProbeUpdateMode) {
+        self.update_mode = update_mode;
+    }
+
+    /// Returns current update mode.
+    pub fn update_mode(&self) -> ProbeUpdateMode {
+        self.update_mode
+    }
+
+    /// Sets new visibility mask. Only the nodes whose
+    /// [render mask](crate::scene::base::Base::set_render_mask) has at least one common bit
+    /// with the visibility mask will be captured by the probe.
+    pub fn set_visibility_mask(&mut self, visibility_mask: u32) {
+        self.visibility_mask = visibility_mask;
+    }
+
+    /// Returns current visibility mask.
+    pub fn visibility_mask(&self) -> u32 {
+        self.visibility_mask
+    }
+
+    /// Requests baking of the probe, the renderer will start baking on next frame (or when
+    /// current baking in the scene is finished).
+    pub fn request_bake(&mut self) {
+        self.bake_requested.set(true);
+    }
+
+    /// Returns progress of baking in `[0; 1]` range, `None` - the probe is not being baked.
+    pub fn bake_progress(&self) -> Option<f32> {
+        self.bake_progress.get()
+    }
+
+    /// Returns `true` if the probe has baked environment.
+    pub fn is_baked(&self) -> bool {
+        self.baked.get()
+    }
+
+    /// Returns a texture with baked environment, it is a cube map with roughness mip levels:
+    /// first level is a sharp reflection and the last one is a reflection on the roughest
+    /// surface.
+    pub fn environment(&self) -> Texture {
+        self.environment.clone()
+    }
+
+    /// Returns current **local-space** bounding box of the extent box.
+    #[inline]
+    pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        let half_size = self.size.scale(0.5);
+        AxisAlignedBoundingBox::from_min_max(-half_size, half_size)
+    }
+
+    /// Returns current **world-space** bounding box of the extent box.
+    pub fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.local_bounding_box()
+            .transform(&self.global_transform())
+    }
+
+    /// Returns `true` if the renderer should start baking of the probe.
+    pub(in crate) fn needs_bake(&self) -> bool {
+        self.bake_requested.get()
+            // Baking could be interrupted, for example when the renderer was re-created.
+            || self.bake_progress.get().is_some()
+            || (self.update_mode == ProbeUpdateMode::Once && !self.baked.get())
+    }
+
+    pub(in crate) fn begin_bake(&self) {
+        self.bake_requested.set(false);
+        self.bake_progress.set(Some(0.0));
+    }
+
+    pub(in crate) fn set_bake_progress(&self, progress: f32) {
+        self.bake_progress.set(Some(progress.min(1.0).max(0.0)));
+    }
+
+    /// Replaces environment with baked data and finishes baking.
+    pub(in crate) fn finish_bake(&self, data: TextureData) {
+        *self.environment.state() = TextureState::Ok(data);
+        self.baked.set(true);
+        self.bake_progress.set(None);
+    }
+}
+
+/// Allows you to create an environment probe in a declarative manner.
+pub struct EnvironmentProbeBuilder {
+    base_builder: BaseBuilder,
+    size: Vector3<f32>,
+    resolution: u32,
+    draw_distance: f32,
+    intensity: f32,
+    update_mode: ProbeUpdateMode,
+    visibility_mask: u32,
+}
+
+impl EnvironmentProbeBuilder {
+    /// Creates a new instance of the builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            size: Vector3::new(10.0, 10.0, 10.0),
+            resolution: 128,
+            draw_distance: 100.0,
+            intensity: 1.0,
+            update_mode: Default::default(),
+            visibility_mask: u32::MAX,
+        }
+    }
+
+    /// Sets desired size of the extent box.
+    pub fn with_size(mut self, size: Vector3<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets desired resolution of the cube map.
+    pub fn with_resolution(mut self, resolution: u32) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// Sets desired draw distance.
+    pub fn with_draw_distance(mut self, draw_distance: f32) -> Self {
+        self.draw_distance = draw_distance;
+        self
+    }
+
+    /// Sets desired intensity.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity;
+        self
+    }
+
+    /// Sets desired update mode.
+    pub fn with_update_mode(mut self, update_mode: ProbeUpdateMode) -> Self {
+        self.update_mode = update_mode;
+        self
+    }
+
+    /// Sets desired visibility mask.
+    pub fn with_visibility_mask(mut self, visibility_mask: u32) -> Self {
+        self.visibility_mask = visibility_mask;
+        self
+    }
+
+    fn build_probe(self) -> EnvironmentProbe {
+        EnvironmentProbe {
+            base: self.base_builder.build_base(),
+            size: self.size.sup(&Vector3::default()),
+            resolution: self.resolution.max(1),
+            draw_distance: self.draw_distance.max(0.0),
+            intensity: self.intensity.max(0.0),
+            update_mode: self.update_mode,
+            visibility_mask: self.visibility_mask,
+            environment: empty_environment(),
+            baked: Cell::new(false),
+            bake_requested: Cell::new(false),
+            bake_progress: Cell::new(None),
+        }
+    }
+
+    /// Creates new EnvironmentProbe node.
+    pub fn build_node(self) -> Node {
+        Node::EnvironmentProbe(self.build_probe())
+    }
+
+    /// Creates new instance of EnvironmentProbe node and puts it in the given graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Node> {
+        graph.add_node(self.build_node())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{algebra::Vector3, visitor::prelude::*},
+        resource::texture::{TextureData, TextureKind, TexturePixelKind},
+        scene::{
+            base::BaseBuilder,
+            probe::{EnvironmentProbe, EnvironmentProbeBuilder, ProbeUpdateMode},
+        },
+    };
+
+    fn baked_data() -> TextureData {
+        // 2x2 cube map with two mip levels.
+        TextureData::from_mip_chain(
+            TextureKind::Cube {
+                width: 2,
+                height: 2,
+            },
+            TexturePixelKind::RGBA8,
+            2,
+            (0..(6 * 4 + 6) * 4).map(|i| i as u8).collect(),
+        )
+    }
+
+    #[test]
+    fn test_probe_bake_state() {
+        let mut probe = EnvironmentProbeBuilder::new(BaseBuilder::new())
+            .with_update_mode(ProbeUpdateMode::OnDemand)
+            .build_probe();
+        assert!(!probe.needs_bake());
+
+        probe.request_bake();
+        assert!(probe.needs_bake());
+
+        probe.begin_bake();
+        assert_eq!(probe.bake_progress(), Some(0.0));
+        // Interrupted baking must be restarted.
+        assert!(probe.needs_bake());
+
+        probe.set_bake_progress(0.5);
+        probe.finish_bake(baked_data());
+        assert!(probe.is_baked());
+        assert_eq!(probe.bake_progress(), None);
+        assert!(!probe.needs_bake());
+
+        // Probes in default mode are baked automatically.
+        assert!(EnvironmentProbe::default().needs_bake());
+    }
+
+    #[test]
+    fn test_probe_visit_keeps_environment() {
+        let mut probe = EnvironmentProbeBuilder::new(BaseBuilder::new())
+            .with_size(Vector3::new(4.0, 2.0, 3.0))
+            .with_resolution(64)
+            .with_visibility_mask(0b01)
+            .build_probe();
+        probe.finish_bake(baked_data());
+
+        let mut visitor = Visitor::new();
+        probe.visit("Probe", &mut visitor).unwrap();
+
+        let mut loaded = EnvironmentProbe::default();
+        visitor.set_reading();
+        loaded.visit("Probe", &mut visitor).unwrap();
+
+        assert_eq!(loaded.size(), Vector3::new(4.0, 2.0, 3.0));
+        assert_eq!(loaded.resolution(), 64);
+        assert_eq!(loaded.visibility_mask(), 0b01);
+        assert!(loaded.is_baked());
+        assert!(!loaded.needs_bake());
+
+        let environment = loaded.environment();
+        let data = environment.data_ref();
+        assert_eq!(data.mip_count(), 2);
+        assert_eq!(data.data(), baked_data().data());
+    }
+
+    #[test]
+    fn test_probe_raw_copy_does_not_share_environment() {
+        let probe = EnvironmentProbe::default();
+        probe.finish_bake(baked_data());
+
+        let copy = probe.raw_copy();
+        assert!(copy.is_baked());
+        assert_ne!(copy.environment(), probe.environment());
+        assert_eq!(copy.environment().data_ref().data(), baked_data().data());
+    }
+}
@@ -0,0 +1,273 @@
+//! Skeleton debugging utilities.
+//!
+//! Animations are applied to bones by names, so retargeting an animation onto a model with
+//! different skeleton does not produce any errors - it just produces garbage. This module
+//! allows you to see what is going on: [`draw_skeleton`] draws bones of a model using debug
+//! drawing context, [`bone_labels`] gives you on-screen positions of bones to show their names
+//! and [`validate_retarget`] lists every bone that does not match between a model and an
+//! animation.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::pool::Handle,
+//!     resource::model::Model,
+//!     scene::{node::Node, skeleton, Scene},
+//! };
+//!
+//! fn debug_skeleton(scene: &mut Scene, model: Handle<Node>, animation: &Model) {
+//!     for report in skeleton::validate_retarget(&scene.graph, model, animation) {
+//!         println!("{}", report);
+//!     }
+//!
+//!     scene.drawing_context.clear_lines();
+//!     skeleton::draw_skeleton(
+//!         &mut scene.drawing_context,
+//!         &scene.graph,
+//!         &scene.animations,
+//!         model,
+//!     );
+//! }
+//! ```
+
+use crate::{
+    animation::AnimationContainer,
+    core::{algebra::Vector2, color::Color, pool::Handle},
+    resource::model::Model,
+    scene::{camera::Camera, debug::SceneDrawingContext, graph::Graph, node::Node},
+};
+use fxhash::FxHashSet;
+use std::fmt::{Display, Formatter};
+
+/// Color of bones that are animated by at least one active animation track.
+pub const ANIMATED_BONE_COLOR: Color = Color::opaque(0, 255, 0);
+
+/// Color of bones that are not animated at all.
+pub const STATIC_BONE_COLOR: Color = Color::opaque(255, 127, 0);
+
+/// Radius of a sphere that is drawn at the end of bone chains.
+const LEAF_BONE_RADIUS: f32 = 0.02;
+
+/// Returns every bone of a model in depth-first order. Bones are the nodes that are used for
+/// skinning by meshes of the model. If there are no skinned meshes in the model, every
+/// descendant of the root is considered a bone.
+pub fn skeleton_bones(graph: &Graph, root: Handle<Node>) -> Vec<Handle<Node>> {
+    let mut skinning_bones = FxHashSet::default();
+    for node in graph.traverse_iter(root) {
+        if let Node::Mesh(mesh) = node {
+            for surface in mesh.surfaces() {
+                skinning_bones.extend(surface.bones().iter().cloned());
+            }
+        }
+    }
+
+    graph
+        .traverse_handle_iter(root)
+        .filter(|&handle| {
+            if skinning_bones.is_empty() {
+                handle != root
+            } else {
+                skinning_bones.contains(&handle)
+            }
+        })
+        .collect()
+}
+
+/// Returns a set of nodes that are animated by enabled tracks of enabled animations.
+pub fn animated_nodes(animations: &AnimationContainer) -> FxHashSet<Handle<Node>> {
+    animations
+        .iter()
+        .filter(|animation| animation.is_enabled())
+        .flat_map(|animation| animation.get_tracks())
+        .filter(|track| track.is_enabled())
+        .map(|track| track.get_node())
+        .collect()
+}
+
+/// Draws bones of a model as octahedrons between each bone and its child bones, bones without
+/// children are drawn as small spheres. Bones that are animated by an active animation track
+/// are drawn with [`ANIMATED_BONE_COLOR`], others - with [`STATIC_BONE_COLOR`].
+pub fn draw_skeleton(
+    drawing_context: &mut SceneDrawingContext,
+    graph: &Graph,
+    animations: &AnimationContainer,
+    root: Handle<Node>,
+) {
+    let bones = skeleton_bones(graph, root);
+    let bone_set = bones.iter().cloned().collect::<FxHashSet<_>>();
+    let animated = animated_nodes(animations);
+
+    for &bone in bones.iter() {
+        let node = &graph[bone];
+
+        let color = if animated.contains(&bone) {
+            ANIMATED_BONE_COLOR
+        } else {
+            STATIC_BONE_COLOR
+        };
+
+        let begin = node.global_position();
+
+        let mut has_child_bones = false;
+        for &child in node.children() {
+            if bone_set.contains(&child) {
+                drawing_context.draw_bone(begin, graph[child].global_position(), color);
+                has_child_bones = true;
+            }
+        }
+
+        if !has_child_bones {
+            drawing_context.draw_sphere(begin, 6, 6, LEAF_BONE_RADIUS, color);
+        }
+    }
+}
+
+/// On-screen position of a bone, see [`bone_labels`].
+#[derive(Clone, Debug)]
+pub struct BoneLabel {
+    /// Handle of the bone.
+    pub bone: Handle<Node>,
+    /// Name of the bone.
+    pub name: String,
+    /// Position of the bone in screen coordinates.
+    pub position: Vector2<f32>,
+}
+
+/// Projects every bone of a model on screen, bones behind the camera are skipped. Use it to put
+/// text widgets with names of bones over the skeleton drawn by [`draw_skeleton`].
+pub fn bone_labels(
+    graph: &Graph,
+    root: Handle<Node>,
+    camera: &Camera,
+    screen_size: Vector2<f32>,
+) -> Vec<BoneLabel> {
+    skeleton_bones(graph, root)
+        .into_iter()
+        .filter_map(|bone| {
+            let node = &graph[bone];
+            camera
+                .project(node.global_position(), screen_size)
+                .map(|position| BoneLabel {
+                    bone,
+                    name: node.name_owned(),
+                    position,
+                })
+        })
+        .collect()
+}
+
+/// A mismatch between skeleton of a model and bones animated by an animation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MismatchReport {
+    /// An animation has a track for a bone, but the model has no node with such name. The track
+    /// will do nothing after retargeting.
+    MissingOnModel {
+        /// Name of the bone.
+        bone: String,
+    },
+    /// A bone of the model is not animated by any track of an animation. It is fine for some
+    /// bones (helpers, attachment points), but if many bones are listed, most likely the animation
+    /// was made for a different skeleton.
+    MissingInAnimation {
+        /// Name of the bone.
+        bone: String,
+    },
+}
+
+impl Display for MismatchReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MismatchReport::MissingOnModel { bone } => {
+                write!(
+                    f,
+                    "Bone {} is animated, but it is missing on the model",
+                    bone
+                )
+            }
+            MismatchReport::MissingInAnimation { bone } => {
+                write!(f, "Bone {} of the model is not animated", bone)
+            }
+        }
+    }
+}
+
+/// Compares bones animated by every animation of a model resource with skeleton of a model
+/// instance and reports every mismatch. Empty result means that retargeting will work
+/// perfectly. See [`crate::resource::model::Model::retarget_animations`].
+pub fn validate_retarget(
+    graph: &Graph,
+    model_root: Handle<Node>,
+    animation_resource: &Model,
+) -> Vec<MismatchReport> {
+    let data = animation_resource.data_ref();
+    let animation_scene = data.get_scene();
+
+    let mut animated_names = Vec::<String>::new();
+    for animation in animation_scene.animations.iter() {
+        for track in animation.get_tracks() {
+            if let Some(node) = animation_scene.graph.try_get(track.get_node()) {
+                if !animated_names.iter().any(|name| name == node.name()) {
+                    animated_names.push(node.name_owned());
+                }
+            }
+        }
+    }
+
+    let mut reports = Vec::new();
+
+    // Retargeting searches nodes by names in the whole hierarchy, not only in bones.
+    for name in animated_names.iter() {
+        if graph.find_by_name(model_root, name).is_none() {
+            reports.push(MismatchReport::MissingOnModel { bone: name.clone() });
+        }
+    }
+
+    for bone in skeleton_bones(graph, model_root) {
+        let name = graph[bone].name();
+        if !animated_names.iter().any(|animated| animated == name) {
+            reports.push(MismatchReport::MissingInAnimation {
+                bone: name.to_owned(),
+            });
+        }
+    }
+
+    reports
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{Animation, AnimationContainer, Track},
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            skeleton::{animated_nodes, skeleton_bones},
+        },
+    };
+
+    #[test]
+    fn test_skeleton_bones() {
+        let mut graph = Graph::new();
+        let hand = BaseBuilder::new().with_name("Hand").build(&mut graph);
+        let arm = BaseBuilder::new()
+            .with_name("Arm")
+            .with_children(&[hand])
+            .build(&mut graph);
+        let root = BaseBuilder::new().with_children(&[arm]).build(&mut graph);
+
+        // No skinned meshes - every descendant is a bone.
+        assert_eq!(skeleton_bones(&graph, root), vec![arm, hand]);
+
+        let mut animations = AnimationContainer::new();
+        let mut animation = Animation::default();
+        let mut track = Track::new();
+        track.set_node(arm);
+        animation.add_track(track);
+        animations.add(animation);
+
+        let animated = animated_nodes(&animations);
+        assert!(animated.contains(&arm));
+        assert!(!animated.contains(&hand));
+    }
+}
@@ -0,0 +1,714 @@
+//! Fire-and-forget sounds.
+//!
+//! Short sounds like footsteps, shots or impacts do not need to be managed manually: use
+//! [`crate::scene::Scene::play_sound`] and the scene will create a sound source, play it once
+//! and remove it from the sound context when playback ends. Such sources could be spatialized
+//! at fixed position or follow a scene node.
+//!
+//! To prevent mixer from saturation, amount of simultaneously playing one-shot sounds is limited
+//! (see [`OneShotSounds::set_max_sounds`]), when the limit is reached, one of playing sounds is
+//! evicted using [`SoundEvictionPolicy`].
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::algebra::Vector3,
+//!     scene::{sound::PlaySoundParams, Scene},
+//!     sound::buffer::SoundBufferResource,
+//! };
+//!
+//! fn play_shot(scene: &mut Scene, buffer: SoundBufferResource, position: Vector3<f32>) {
+//!     let _ = scene.play_sound(
+//!         buffer,
+//!         PlaySoundParams {
+//!             position: Some(position),
+//!             group: "Weapons".to_owned(),
+//!             ..Default::default()
+//!         },
+//!     );
+//! }
+//! ```
+//!
+//! Playing the very same sample over and over sounds robotic, [`SoundVariation`] picks one of
+//! a few samples with randomized pitch and gain every time it is played, use it with
+//! [`crate::scene::Scene::play_variation`].
+
+use crate::{
+    core::{algebra::Vector3, pool::Handle, rand::Rng, visitor::prelude::*},
+    engine::resource_manager::ResourceManager,
+    scene::{graph::Graph, node::Node},
+    sound::{
+        buffer::SoundBufferResource,
+        context::SoundContext,
+        error::SoundError,
+        source::{
+            generic::GenericSourceBuilder, spatial::SpatialSourceBuilder, SoundSource, Status,
+        },
+    },
+};
+use std::{
+    cell::Cell,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+/// Defines which one-shot sound is stopped when the limit of simultaneously playing sounds is
+/// reached.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SoundEvictionPolicy {
+    /// The sound that was started first is stopped.
+    Oldest,
+
+    /// The sound that is heard at lowest volume by the listener is stopped, distance
+    /// attenuation is taken into account for spatial sounds.
+    Quietest,
+}
+
+impl Default for SoundEvictionPolicy {
+    fn default() -> Self {
+        Self::Oldest
+    }
+}
+
+/// A set of parameters for [`crate::scene::Scene::play_sound`].
+#[derive(Clone, Debug)]
+pub struct PlaySoundParams {
+    /// Position of the sound in world coordinates. If set, the sound will be spatial, otherwise
+    /// it is heard the same regardless of listener position (if there is no `node`). Default is
+    /// `None`.
+    pub position: Option<Vector3<f32>>,
+
+    /// A node to which the sound is attached. If set, the sound will be spatial and it will follow
+    /// global position of the node until playback ends, `position` is ignored in this case.
+    /// Default is [`Handle::NONE`].
+    pub node: Handle<Node>,
+
+    /// Gain of the sound. Default is 1.0.
+    pub gain: f32,
+
+    /// Pitch of the sound. Default is 1.0.
+    pub pitch: f32,
+
+    /// Name of a group of the sound, it could be used to stop every sound of the group at once,
+    /// see [`OneShotSounds::stop_group`]. Default is empty string.
+    pub group: String,
+}
+
+impl Default for PlaySoundParams {
+    fn default() -> Self {
+        Self {
+            position: None,
+            node: Handle::NONE,
+            gain: 1.0,
+            pitch: 1.0,
+            group: Default::default(),
+        }
+    }
+}
+
+/// A sound of [`SoundVariation`] with its weight.
+#[derive(Clone, Debug, Visit)]
+pub struct SoundVariationEntry {
+    path: PathBuf,
+    weight: f32,
+    // Requested from the path on resolve.
+    #[visit(skip)]
+    buffer: Option<SoundBufferResource>,
+}
+
+impl Default for SoundVariationEntry {
+    fn default() -> Self {
+        Self {
+            path: Default::default(),
+            weight: 1.0,
+            buffer: None,
+        }
+    }
+}
+
+impl SoundVariationEntry {
+    /// Returns path of the sound buffer, it is used to request the buffer when a variation is
+    /// loaded, see [`SoundVariation::resolve`].
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns weight of the entry.
+    pub fn weight(&self) -> f32 {
+        self.weight
+    }
+
+    /// Returns sound buffer of the entry, it is `None` for deserialized variations until they're
+    /// resolved.
+    pub fn buffer(&self) -> Option<&SoundBufferResource> {
+        self.buffer.as_ref()
+    }
+}
+
+/// A result of [`SoundVariation::pick`].
+#[derive(Clone, Debug)]
+pub struct SoundVariationPick {
+    /// Index of the picked entry.
+    pub index: usize,
+    /// Sound buffer of the picked entry.
+    pub buffer: SoundBufferResource,
+    /// Random pitch from the pitch range of the variation.
+    pub pitch: f32,
+    /// Random gain from the gain range of the variation.
+    pub gain: f32,
+}
+
+/// A set of interchangeable sounds (footsteps, impacts, etc.). Every time the variation is played,
+/// one of its sounds is picked randomly according to weights and played with random pitch and gain
+/// from given ranges.
+///
+/// By default, the same sound is never picked twice in a row (unless it is the only one). In
+/// round-robin mode sounds are picked one after another in order and weights are ignored. Entries
+/// with zero weight are never picked in any mode.
+///
+/// Variations could be serialized, sounds are stored as paths, call [`Self::resolve`] after
+/// loading to request the buffers. Picks are deterministic if a seeded random number generator
+/// is used, see [`crate::scene::Scene::play_variation_with_rng`].
+#[derive(Clone, Debug, Visit)]
+pub struct SoundVariation {
+    entries: Vec<SoundVariationEntry>,
+    pitch: Range<f32>,
+    gain: Range<f32>,
+    no_repeat: bool,
+    round_robin: bool,
+    #[visit(skip)]
+    last: Cell<Option<usize>>,
+}
+
+impl Default for SoundVariation {
+    fn default() -> Self {
+        Self {
+            entries: Default::default(),
+            pitch: 1.0..1.0,
+            gain: 1.0..1.0,
+            no_repeat: true,
+            round_robin: false,
+            last: Cell::new(None),
+        }
+    }
+}
+
+fn sample_range<R: Rng + ?Sized>(range: &Range<f32>, rng: &mut R) -> f32 {
+    let (min, max) = if range.start < range.end {
+        (range.start, range.end)
+    } else {
+        (range.end, range.start)
+    };
+    if min < max {
+        rng.gen_range(min..max)
+    } else {
+        min
+    }
+}
+
+impl SoundVariation {
+    /// Creates new empty variation.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds new sound with given weight. Weight is relative to weights of other sounds, it
+    /// can't be negative.
+    pub fn with_sound(mut self, buffer: SoundBufferResource, weight: f32) -> Self {
+        self.add_sound(buffer, weight);
+        self
+    }
+
+    /// Sets range of random pitch.
+    pub fn with_pitch_range(mut self, pitch: Range<f32>) -> Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Sets range of random gain.
+    pub fn with_gain_range(mut self, gain: Range<f32>) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Sets whether the same sound can be picked twice in a row or not.
+    pub fn with_no_repeat(mut self, no_repeat: bool) -> Self {
+        self.no_repeat = no_repeat;
+        self
+    }
+
+    /// Sets whether sounds should be picked one after another or randomly.
+    pub fn with_round_robin(mut self, round_robin: bool) -> Self {
+        self.round_robin = round_robin;
+        self
+    }
+
+    /// Adds new sound with given weight, see [`Self::with_sound`].
+    pub fn add_sound(&mut self, buffer: SoundBufferResource, weight: f32) {
+        self.entries.push(SoundVariationEntry {
+            path: buffer.state().path().to_path_buf(),
+            weight: weight.max(0.0),
+            buffer: Some(buffer),
+        });
+    }
+
+    /// Returns every sound of the variation.
+    pub fn entries(&self) -> &[SoundVariationEntry] {
+        &self.entries
+    }
+
+    /// Removes every sound from the variation.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.last.set(None);
+    }
+
+    /// Returns range of random pitch.
+    pub fn pitch_range(&self) -> Range<f32> {
+        self.pitch.clone()
+    }
+
+    /// Sets range of random pitch.
+    pub fn set_pitch_range(&mut self, pitch: Range<f32>) {
+        self.pitch = pitch;
+    }
+
+    /// Returns range of random gain.
+    pub fn gain_range(&self) -> Range<f32> {
+        self.gain.clone()
+    }
+
+    /// Sets range of random gain.
+    pub fn set_gain_range(&mut self, gain: Range<f32>) {
+        self.gain = gain;
+    }
+
+    /// Returns true if the same sound is never picked twice in a row.
+    pub fn is_no_repeat(&self) -> bool {
+        self.no_repeat
+    }
+
+    /// Sets whether the same sound can be picked twice in a row or not.
+    pub fn set_no_repeat(&mut self, no_repeat: bool) {
+        self.no_repeat = no_repeat;
+    }
+
+    /// Returns true if sounds are picked one after another.
+    pub fn is_round_robin(&self) -> bool {
+        self.round_robin
+    }
+
+    /// Sets whether sounds should be picked one after another or randomly.
+    pub fn set_round_robin(&mut self, round_robin: bool) {
+        self.round_robin = round_robin;
+    }
+
+    /// Requests sound buffers of entries that does not have them (for example after
+    /// deserialization).
+    pub fn resolve(&mut self, resource_manager: &ResourceManager) {
+        for entry in self.entries.iter_mut() {
+            if entry.buffer.is_none() && entry.path != Path::new("") {
+                entry.buffer = Some(resource_manager.request_sound_buffer(&entry.path, false));
+            }
+        }
+    }
+
+    /// Picks next sound with random pitch and gain. Returns `None` if there is nothing to pick.
+    pub fn pick<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<SoundVariationPick> {
+        let available = |i: &usize| {
+            let entry = &self.entries[*i];
+            entry.buffer.is_some() && entry.weight > 0.0
+        };
+        let count = self.entries.len();
+        let last = self.last.get();
+
+        let index = if self.round_robin {
+            let start = last.map_or(0, |last| last + 1);
+            (0..count).map(|k| (start + k) % count).find(available)?
+        } else {
+            let mut candidates = (0..count)
+                .filter(available)
+                .filter(|&i| !self.no_repeat || Some(i) != last)
+                .collect::<Vec<_>>();
+            if candidates.is_empty() {
+                // Repeat is the only option.
+                candidates = (0..count).filter(available).collect();
+            }
+            let (&last_candidate, _) = candidates.split_last()?;
+
+            let total = candidates
+                .iter()
+                .map(|&i| self.entries[i].weight)
+                .sum::<f32>();
+            let mut value = rng.gen_range(0.0..total);
+            let mut index = last_candidate;
+            for &i in candidates.iter() {
+                let weight = self.entries[i].weight;
+                if value < weight {
+                    index = i;
+                    break;
+                }
+                value -= weight;
+            }
+            index
+        };
+
+        self.last.set(Some(index));
+
+        Some(SoundVariationPick {
+            index,
+            buffer: self.entries[index].buffer.clone()?,
+            pitch: sample_range(&self.pitch, rng),
+            gain: sample_range(&self.gain, rng),
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct OneShotSound {
+    source: Handle<SoundSource>,
+    node: Handle<Node>,
+    group: String,
+}
+
+/// A set of one-shot sounds that are played at the moment, see module docs.
+#[derive(Clone, Debug)]
+pub struct OneShotSounds {
+    // Sorted by start time.
+    sounds: Vec<OneShotSound>,
+    max_sounds: usize,
+    eviction_policy: SoundEvictionPolicy,
+}
+
+impl Default for OneShotSounds {
+    fn default() -> Self {
+        Self {
+            sounds: Default::default(),
+            max_sounds: Self::DEFAULT_MAX_SOUNDS,
+            eviction_policy: Default::default(),
+        }
+    }
+}
+
+impl OneShotSounds {
+    /// Default limit of simultaneously playing one-shot sounds.
+    pub const DEFAULT_MAX_SOUNDS: usize = 32;
+
+    /// Sets maximum amount of simultaneously playing one-shot sounds, it can't be less than one.
+    /// Sounds that are playing above new limit are stopped.
+    pub fn set_max_sounds(&mut self, context: &SoundContext, max_sounds: usize) {
+        self.max_sounds = max_sounds.max(1);
+        self.evict(context, self.max_sounds);
+    }
+
+    /// Returns maximum amount of simultaneously playing one-shot sounds.
+    pub fn max_sounds(&self) -> usize {
+        self.max_sounds
+    }
+
+    /// Sets eviction policy, see [`SoundEvictionPolicy`] docs for more info.
+    pub fn set_eviction_policy(&mut self, eviction_policy: SoundEvictionPolicy) {
+        self.eviction_policy = eviction_policy;
+    }
+
+    /// Returns current eviction policy.
+    pub fn eviction_policy(&self) -> SoundEvictionPolicy {
+        self.eviction_policy
+    }
+
+    /// Returns amount of one-shot sounds that were playing at the moment of last scene update.
+    pub fn count(&self) -> usize {
+        self.sounds.len()
+    }
+
+    /// Returns handles of sound sources of one-shot sounds that are playing at the moment.
+    pub fn sources(&self) -> impl Iterator<Item = Handle<SoundSource>> + '_ {
+        self.sounds.iter().map(|s| s.source)
+    }
+
+    /// Stops every one-shot sound of given group and removes their sources from the context.
+    pub fn stop_group(&mut self, context: &SoundContext, group: &str) {
+        let mut state = context.state();
+        self.sounds.retain(|sound| {
+            if sound.group == group {
+                if state.is_valid_handle(sound.source) {
+                    state.sources_mut().free(sound.source);
+                }
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    pub(in crate) fn play(
+        &mut self,
+        context: &SoundContext,
+        graph: &Graph,
+        buffer: SoundBufferResource,
+        params: PlaySoundParams,
+    ) -> Result<Handle<SoundSource>, SoundError> {
+        let generic = GenericSourceBuilder::new()
+            .with_buffer(buffer)
+            .with_gain(params.gain)
+            .with_pitch(params.pitch)
+            .with_play_once(true)
+            .with_status(Status::Playing)
+            .build()?;
+
+        let position = graph
+            .try_get(params.node)
+            .map(|node| node.global_position())
+            .or(params.position);
+        let source = match position {
+            Some(position) => SpatialSourceBuilder::new(generic)
+                .with_position(position)
+                .build_source(),
+            None => SoundSource::Generic(generic),
+        };
+
+        // Make room for new sound.
+        self.evict(context, self.max_sounds - 1);
+
+        let handle = context.state().add_source(source);
+        self.sounds.push(OneShotSound {
+            source: handle,
+            node: params.node,
+            group: params.group,
+        });
+        Ok(handle)
+    }
+
+    // Removes sounds that have finished playing and moves attached sounds to their nodes.
+    pub(in crate) fn update(&mut self, context: &SoundContext, graph: &Graph) {
+        if self.sounds.is_empty() {
+            return;
+        }
+
+        let mut state = context.state();
+        // Sources of finished sounds are removed by the sound context automatically, because
+        // they're marked as play-once.
+        self.sounds
+            .retain(|sound| state.is_valid_handle(sound.source));
+
+        for sound in self.sounds.iter() {
+            if let Some(node) = graph
+                .try_get(sound.node)
+                .filter(|node| node.global_transform_changed())
+            {
+                if let SoundSource::Spatial(spatial) = state.source_mut(sound.source) {
+                    spatial.set_position(node.global_position());
+                }
+            }
+        }
+    }
+
+    // Stops sounds until there is no more than `max` sounds.
+    fn evict(&mut self, context: &SoundContext, max: usize) {
+        let mut state = context.state();
+        self.sounds
+            .retain(|sound| state.is_valid_handle(sound.source));
+
+        while self.sounds.len() > max {
+            let index = match self.eviction_policy {
+                SoundEvictionPolicy::Oldest => 0,
+                SoundEvictionPolicy::Quietest => {
+                    let listener = state.listener();
+                    let distance_model = state.distance_model();
+                    let audible_gain = |sound: &OneShotSound| {
+                        let source = state.source(sound.source);
+                        match source {
+                            SoundSource::Generic(generic) => generic.gain(),
+                            SoundSource::Spatial(spatial) => {
+                                spatial.gain() * spatial.get_distance_gain(listener, distance_model)
+                            }
+                        }
+                    };
+                    // The oldest one wins among equally quiet sounds.
+                    let mut index = 0;
+                    let mut min_gain = f32::MAX;
+                    for (i, sound) in self.sounds.iter().enumerate() {
+                        let gain = audible_gain(sound);
+                        if gain < min_gain {
+                            min_gain = gain;
+                            index = i;
+                        }
+                    }
+                    index
+                }
+            };
+
+            let sound = self.sounds.remove(index);
+            state.sources_mut().free(sound.source);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{
+            algebra::Vector3,
+            futures::executor::block_on,
+            pool::Handle,
+            rand::{rngs::StdRng, SeedableRng},
+            visitor::{Visit, Visitor},
+        },
+        scene::{
+            graph::Graph,
+            sound::{OneShotSounds, PlaySoundParams, SoundEvictionPolicy, SoundVariation},
+        },
+        sound::{
+            buffer::{DataSource, SoundBufferResource},
+            context::SoundContext,
+            source::SoundSource,
+        },
+    };
+
+    fn make_buffer() -> SoundBufferResource {
+        SoundBufferResource::new_generic(DataSource::Raw {
+            sample_rate: 44100,
+            channel_count: 1,
+            samples: vec![0.0; 44100],
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_one_shot_sounds() {
+        let context = SoundContext::new();
+        let graph = Graph::new();
+        let mut sounds = OneShotSounds::default();
+        sounds.set_max_sounds(&context, 3);
+
+        let buffer = make_buffer();
+        let play = |sounds: &mut OneShotSounds, gain: f32, group: &str| {
+            sounds
+                .play(
+                    &context,
+                    &graph,
+                    buffer.clone(),
+                    PlaySoundParams {
+                        position: Some(Vector3::new(1.0, 0.0, 0.0)),
+                        gain,
+                        group: group.to_owned(),
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+        };
+
+        let first = play(&mut sounds, 1.0, "A");
+        let quiet = play(&mut sounds, 0.1, "A");
+        play(&mut sounds, 1.0, "B");
+        assert!(matches!(
+            context.state().source(first),
+            SoundSource::Spatial(_)
+        ));
+
+        // Oldest sound is evicted.
+        play(&mut sounds, 1.0, "B");
+        assert_eq!(sounds.count(), 3);
+        assert!(!context.state().is_valid_handle(first));
+
+        // Quietest sound is evicted.
+        sounds.set_eviction_policy(SoundEvictionPolicy::Quietest);
+        play(&mut sounds, 1.0, "A");
+        assert_eq!(sounds.count(), 3);
+        assert!(!context.state().is_valid_handle(quiet));
+
+        sounds.stop_group(&context, "B");
+        assert_eq!(sounds.count(), 1);
+        assert_eq!(context.state().sources().alive_count(), 1);
+
+        // Sources removed by the context are forgotten on update.
+        context.state().sources_mut().clear();
+        sounds.update(&context, &graph);
+        assert_eq!(sounds.count(), 0);
+
+        // Non-spatial sound.
+        let handle = sounds
+            .play(
+                &context,
+                &graph,
+                buffer,
+                PlaySoundParams {
+                    node: Handle::NONE,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(matches!(
+            context.state().source(handle),
+            SoundSource::Generic(_)
+        ));
+    }
+
+    #[test]
+    fn test_sound_variation() {
+        let variation = SoundVariation::new()
+            .with_sound(make_buffer(), 1.0)
+            .with_sound(make_buffer(), 3.0)
+            .with_sound(make_buffer(), 0.0)
+            .with_pitch_range(0.9..1.1)
+            .with_gain_range(0.5..1.0);
+
+        let picks = |variation: &SoundVariation, seed: u64| {
+            variation.last.set(None);
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..64)
+                .map(|_| variation.pick(&mut rng).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let first = picks(&variation, 42);
+        for (prev, next) in first.iter().zip(first.iter().skip(1)) {
+            assert_ne!(prev.index, next.index);
+        }
+        for pick in first.iter() {
+            // Zero weight - never picked.
+            assert_ne!(pick.index, 2);
+            assert!((0.9..1.1).contains(&pick.pitch));
+            assert!((0.5..1.0).contains(&pick.gain));
+        }
+
+        // Same seed - same sequence.
+        let second = picks(&variation, 42);
+        for (a, b) in first.iter().zip(second.iter()) {
+            assert_eq!(a.index, b.index);
+            assert_eq!(a.pitch, b.pitch);
+            assert_eq!(a.gain, b.gain);
+        }
+
+        // Weights are respected when repeats are allowed.
+        let mut variation = variation.with_no_repeat(false);
+        let heavy = picks(&variation, 1).iter().filter(|p| p.index == 1).count();
+        assert!(heavy > 32);
+
+        variation.set_round_robin(true);
+        let order = picks(&variation, 7)
+            .iter()
+            .take(4)
+            .map(|p| p.index)
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec![0, 1, 0, 1]);
+
+        // Sounds are serialized as paths.
+        variation.entries[0].path = "a.wav".into();
+        variation.entries[1].path = "b.wav".into();
+        let path = std::env::temp_dir().join("rg3d_sound_variation_test.bin");
+        let mut visitor = Visitor::new();
+        variation.visit("Variation", &mut visitor).unwrap();
+        visitor.save_binary(&path).unwrap();
+        let mut visitor = block_on(Visitor::load_binary(&path)).unwrap();
+        let mut loaded = SoundVariation::default();
+        loaded.visit("Variation", &mut visitor).unwrap();
+        assert_eq!(loaded.entries().len(), 3);
+        assert_eq!(loaded.entries()[1].path().to_str(), Some("b.wav"));
+        assert_eq!(loaded.entries()[1].weight(), 3.0);
+        assert!(loaded.entries()[1].buffer().is_none());
+        assert_eq!(loaded.pitch_range(), 0.9..1.1);
+        assert!(loaded.is_round_robin() && !loaded.is_no_repeat());
+        // Nothing to pick until resolved.
+        assert!(loaded.pick(&mut StdRng::seed_from_u64(0)).is_none());
+    }
+}
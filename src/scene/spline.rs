@@ -0,0 +1,838 @@
+//! Splines are smooth paths through a set of control points, they are used for moving platforms,
+//! patrol routes, cinematic camera moves and so on.
+//!
+//! # Overview
+//!
+//! A [`Spline`] is stored in [`crate::scene::Scene::splines`] container, its control points are
+//! either fixed positions or graph nodes (markers that can be moved in the editor or at runtime).
+//! Positions of node control points are fetched every frame after graph update, so a spline
+//! follows its markers.
+//!
+//! Parameter `t` of [`Spline::evaluate`] is not proportional to distance along the spline - each
+//! segment takes equal part of `[0; 1]` range no matter how long it is. Use
+//! [`Spline::evaluate_at_distance`] when you need constant speed, it uses a table of arc lengths
+//! which is rebuilt when control points change.
+//!
+//! [`PathFollower`] moves a node along a spline at given speed. It is not updated automatically,
+//! call [`PathFollower::update`] every frame.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::{algebra::Vector3, pool::Handle},
+//!     scene::{
+//!         node::Node,
+//!         spline::{PathEndBehavior, PathFollower, Spline, SplineKind, SplinePoint},
+//!         Scene,
+//!     },
+//! };
+//!
+//! fn create_patrol(scene: &mut Scene, guard: Handle<Node>) -> PathFollower {
+//!     let spline = scene.splines.add(
+//!         Spline::new(SplineKind::CatmullRom)
+//!             .with_closed(true)
+//!             .with_points(vec![
+//!                 SplinePoint::Position(Vector3::new(0.0, 0.0, 0.0)),
+//!                 SplinePoint::Position(Vector3::new(10.0, 0.0, 0.0)),
+//!                 SplinePoint::Position(Vector3::new(10.0, 0.0, 10.0)),
+//!                 SplinePoint::Position(Vector3::new(0.0, 0.0, 10.0)),
+//!             ]),
+//!     );
+//!
+//!     PathFollower {
+//!         spline,
+//!         target: guard,
+//!         speed: 2.0,
+//!         end_behavior: PathEndBehavior::Loop,
+//!         orient_along_tangent: true,
+//!         ..Default::default()
+//!     }
+//! }
+//!
+//! fn update(scene: &mut Scene, patrol: &mut PathFollower, dt: f32) {
+//!     patrol.update(scene, dt);
+//! }
+//! ```
+
+#![warn(missing_docs)]
+
+use crate::{
+    core::{
+        algebra::{Isometry3, Matrix3, Rotation3, Translation3, UnitQuaternion, Vector3},
+        color::Color,
+        math::Matrix4Ext,
+        pool::{Handle, Pool},
+        visitor::prelude::*,
+    },
+    scene::{
+        debug::{Line, SceneDrawingContext},
+        graph::Graph,
+        node::Node,
+        Scene,
+    },
+};
+use fxhash::FxHashMap;
+use std::ops::{Index, IndexMut};
+
+/// Amount of samples per segment in arc length table.
+const SAMPLES_PER_SEGMENT: usize = 16;
+
+/// Interpolation method of a spline.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Visit)]
+pub enum SplineKind {
+    /// Curve passes through every control point. Tangents are calculated from neighbour points,
+    /// so there is nothing to tweak except positions of points.
+    CatmullRom,
+    /// Piecewise cubic Bezier curve. Control points go in groups: anchor, outgoing handle,
+    /// incoming handle of next anchor, next anchor and so on. Curve passes only through anchors.
+    /// Open spline needs `3 * n + 1` points, closed - `3 * n` (the last segment ends at the first
+    /// anchor), excess points are ignored.
+    Bezier,
+}
+
+impl Default for SplineKind {
+    fn default() -> Self {
+        Self::CatmullRom
+    }
+}
+
+/// Control point of a spline.
+#[derive(Copy, Clone, PartialEq, Debug, Visit)]
+pub enum SplinePoint {
+    /// Fixed position in world coordinates.
+    Position(Vector3<f32>),
+    /// Global position of a graph node.
+    Node(Handle<Node>),
+}
+
+impl Default for SplinePoint {
+    fn default() -> Self {
+        Self::Position(Default::default())
+    }
+}
+
+/// See module docs.
+#[derive(Clone, Debug, Default)]
+pub struct Spline {
+    kind: SplineKind,
+    closed: bool,
+    points: Vec<SplinePoint>,
+    // Resolved positions of control points.
+    positions: Vec<Vector3<f32>>,
+    // Arc length at `i / (lengths.len() - 1)` parameter.
+    lengths: Vec<f32>,
+}
+
+impl Spline {
+    /// Creates new empty spline of given kind.
+    pub fn new(kind: SplineKind) -> Self {
+        Self {
+            kind,
+            ..Default::default()
+        }
+    }
+
+    /// Sets whether the spline is closed or not.
+    pub fn with_closed(mut self, closed: bool) -> Self {
+        self.set_closed(closed);
+        self
+    }
+
+    /// Sets control points of the spline.
+    pub fn with_points(mut self, points: Vec<SplinePoint>) -> Self {
+        self.set_points(points);
+        self
+    }
+
+    /// Returns interpolation method of the spline.
+    pub fn kind(&self) -> SplineKind {
+        self.kind
+    }
+
+    /// Sets interpolation method of the spline.
+    pub fn set_kind(&mut self, kind: SplineKind) {
+        self.kind = kind;
+        self.rebuild();
+    }
+
+    /// Returns `true` if the spline is closed, closed spline connects its last control point
+    /// with the first one.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Sets whether the spline is closed or not.
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+        self.rebuild();
+    }
+
+    /// Returns control points of the spline.
+    pub fn points(&self) -> &[SplinePoint] {
+        &self.points
+    }
+
+    /// Sets control points of the spline. Node points have zero positions until next
+    /// [`Self::update`] call.
+    pub fn set_points(&mut self, points: Vec<SplinePoint>) {
+        self.points = points;
+        self.positions = self
+            .points
+            .iter()
+            .map(|point| match *point {
+                SplinePoint::Position(position) => position,
+                SplinePoint::Node(_) => Default::default(),
+            })
+            .collect();
+        self.rebuild();
+    }
+
+    /// Returns positions of control points (in world coordinates) which were used for the last
+    /// rebuild of the spline.
+    pub fn positions(&self) -> &[Vector3<f32>] {
+        &self.positions
+    }
+
+    /// Fetches positions of node control points from the graph and rebuilds the spline if any of
+    /// them has moved. It is called automatically by the scene after graph update. Dangling node
+    /// handles keep their last positions.
+    pub fn update(&mut self, graph: &Graph) {
+        let mut changed = false;
+        for (point, position) in self.points.iter().zip(self.positions.iter_mut()) {
+            if let SplinePoint::Node(handle) = *point {
+                if let Some(node) = graph.try_get(handle) {
+                    let new_position = node.global_position();
+                    if new_position != *position {
+                        *position = new_position;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        if changed {
+            self.rebuild();
+        }
+    }
+
+    /// Replaces node handles of control points using given old-to-new mapping, handles that
+    /// are not in the map are kept as is.
+    pub(in crate) fn remap_handles(&mut self, old_new_map: &FxHashMap<Handle<Node>, Handle<Node>>) {
+        for point in self.points.iter_mut() {
+            if let SplinePoint::Node(handle) = point {
+                if let Some(&new_handle) = old_new_map.get(handle) {
+                    *handle = new_handle;
+                }
+            }
+        }
+    }
+
+    /// Returns amount of curve segments in the spline.
+    pub fn segment_count(&self) -> usize {
+        let n = self.positions.len();
+        match self.kind {
+            SplineKind::CatmullRom => {
+                if n < 2 {
+                    0
+                } else if self.closed {
+                    n
+                } else {
+                    n - 1
+                }
+            }
+            SplineKind::Bezier => {
+                if self.closed {
+                    n / 3
+                } else {
+                    n.saturating_sub(1) / 3
+                }
+            }
+        }
+    }
+
+    /// Returns length of the spline.
+    pub fn length(&self) -> f32 {
+        self.lengths.last().cloned().unwrap_or_default()
+    }
+
+    fn position(&self, i: isize) -> Vector3<f32> {
+        let n = self.positions.len() as isize;
+        let i = if self.closed {
+            i.rem_euclid(n)
+        } else {
+            i.max(0).min(n - 1)
+        };
+        self.positions[i as usize]
+    }
+
+    fn segment_points(&self, segment: usize) -> [Vector3<f32>; 4] {
+        let i = segment as isize;
+        match self.kind {
+            SplineKind::CatmullRom => {
+                let (p1, p2) = (self.position(i), self.position(i + 1));
+                // End points of open spline are extrapolated, otherwise the curve would have
+                // zero tangent at the ends.
+                let p0 = if !self.closed && i == 0 {
+                    p1 * 2.0 - p2
+                } else {
+                    self.position(i - 1)
+                };
+                let p3 = if !self.closed && segment + 2 >= self.positions.len() {
+                    p2 * 2.0 - p1
+                } else {
+                    self.position(i + 2)
+                };
+                // Convert to Bezier form, so both kinds share evaluation code.
+                [p1, p1 + (p2 - p0) / 6.0, p2 - (p3 - p1) / 6.0, p2]
+            }
+            SplineKind::Bezier => [
+                self.position(3 * i),
+                self.position(3 * i + 1),
+                self.position(3 * i + 2),
+                self.position(3 * i + 3),
+            ],
+        }
+    }
+
+    fn evaluate_raw(&self, t: f32) -> Option<(Vector3<f32>, Vector3<f32>)> {
+        let segments = self.segment_count();
+        if segments == 0 {
+            return None;
+        }
+
+        let scaled = t.max(0.0).min(1.0) * segments as f32;
+        let segment = (scaled as usize).min(segments - 1);
+        let u = scaled - segment as f32;
+        let [p0, p1, p2, p3] = self.segment_points(segment);
+
+        let v = 1.0 - u;
+        let position = p0.scale(v * v * v)
+            + p1.scale(3.0 * v * v * u)
+            + p2.scale(3.0 * v * u * u)
+            + p3.scale(u * u * u);
+        let derivative = (p1 - p0).scale(3.0 * v * v)
+            + (p2 - p1).scale(6.0 * v * u)
+            + (p3 - p2).scale(3.0 * u * u);
+
+        Some((position, derivative))
+    }
+
+    /// Returns position and normalized tangent of the spline at given parameter in `[0; 1]`
+    /// range. Tangent is zero at degenerated points. Spline without segments returns position of
+    /// its first control point (or zero if there are no points) and zero tangent.
+    pub fn evaluate(&self, t: f32) -> (Vector3<f32>, Vector3<f32>) {
+        match self.evaluate_raw(t) {
+            Some((position, derivative)) => (
+                position,
+                derivative.try_normalize(f32::EPSILON).unwrap_or_default(),
+            ),
+            None => (
+                self.positions.first().cloned().unwrap_or_default(),
+                Default::default(),
+            ),
+        }
+    }
+
+    /// Converts distance along the spline to parameter of [`Self::evaluate`]. Distance is clamped
+    /// to `[0; length]` range.
+    pub fn distance_to_parameter(&self, distance: f32) -> f32 {
+        if self.lengths.len() < 2 || self.length() <= 0.0 {
+            return 0.0;
+        }
+
+        let distance = distance.max(0.0).min(self.length());
+        let i = match self
+            .lengths
+            .binary_search_by(|length| length.partial_cmp(&distance).unwrap())
+        {
+            Ok(i) => return i as f32 / (self.lengths.len() - 1) as f32,
+            Err(i) => i.max(1).min(self.lengths.len() - 1),
+        };
+
+        let (a, b) = (self.lengths[i - 1], self.lengths[i]);
+        let k = if b > a { (distance - a) / (b - a) } else { 0.0 };
+        ((i - 1) as f32 + k) / (self.lengths.len() - 1) as f32
+    }
+
+    /// Returns position and normalized tangent of the spline at given distance from its
+    /// beginning. Unlike [`Self::evaluate`], equal steps of distance give equal steps along the
+    /// spline, use it for constant speed movement.
+    pub fn evaluate_at_distance(&self, distance: f32) -> (Vector3<f32>, Vector3<f32>) {
+        self.evaluate(self.distance_to_parameter(distance))
+    }
+
+    fn rebuild(&mut self) {
+        self.lengths.clear();
+
+        let samples = self.segment_count() * SAMPLES_PER_SEGMENT;
+        if samples == 0 {
+            return;
+        }
+
+        let mut length = 0.0;
+        let mut prev = self.evaluate(0.0).0;
+        self.lengths.push(0.0);
+        for i in 1..=samples {
+            let position = self.evaluate(i as f32 / samples as f32).0;
+            length += position.metric_distance(&prev);
+            self.lengths.push(length);
+            prev = position;
+        }
+    }
+
+    /// Draws the spline as a set of lines and its control points as small crosses.
+    pub fn draw(&self, context: &mut SceneDrawingContext, color: Color) {
+        let samples = self.lengths.len();
+        if samples > 1 {
+            let mut prev = self.evaluate(0.0).0;
+            for i in 1..samples {
+                let position = self.evaluate(i as f32 / (samples - 1) as f32).0;
+                context.add_line(Line {
+                    begin: prev,
+                    end: position,
+                    color,
+                });
+                prev = position;
+            }
+        }
+
+        const SIZE: f32 = 0.1;
+        for position in self.positions.iter() {
+            for axis in [Vector3::x(), Vector3::y(), Vector3::z()] {
+                context.add_line(Line {
+                    begin: position - axis.scale(SIZE),
+                    end: position + axis.scale(SIZE),
+                    color,
+                });
+            }
+        }
+    }
+}
+
+impl Visit for Spline {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.kind.visit("Kind", visitor)?;
+        self.closed.visit("Closed", visitor)?;
+        self.points.visit("Points", visitor)?;
+
+        if visitor.is_reading() {
+            // Node points will be resolved on first update.
+            let points = std::mem::take(&mut self.points);
+            self.set_points(points);
+        }
+
+        visitor.leave_region()
+    }
+}
+
+/// A container for splines.
+#[derive(Default, Clone, Debug)]
+pub struct SplineContainer {
+    pool: Pool<Spline>,
+}
+
+impl SplineContainer {
+    /// Adds new spline to the container and returns its handle.
+    pub fn add(&mut self, spline: Spline) -> Handle<Spline> {
+        self.pool.spawn(spline)
+    }
+
+    /// Removes spline by its handle.
+    pub fn remove(&mut self, handle: Handle<Spline>) -> Spline {
+        self.pool.free(handle)
+    }
+
+    /// Tries to borrow a spline by its handle.
+    pub fn try_get(&self, handle: Handle<Spline>) -> Option<&Spline> {
+        self.pool.try_borrow(handle)
+    }
+
+    /// Tries to mutably borrow a spline by its handle.
+    pub fn try_get_mut(&mut self, handle: Handle<Spline>) -> Option<&mut Spline> {
+        self.pool.try_borrow_mut(handle)
+    }
+
+    /// Creates new immutable iterator.
+    pub fn iter(&self) -> impl Iterator<Item = &Spline> {
+        self.pool.iter()
+    }
+
+    /// Creates new mutable iterator.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Spline> {
+        self.pool.iter_mut()
+    }
+
+    /// Destroys all splines. All handles will become invalid.
+    pub fn clear(&mut self) {
+        self.pool.clear()
+    }
+
+    /// Checks if given handle is valid.
+    pub fn is_valid_handle(&self, handle: Handle<Spline>) -> bool {
+        self.pool.is_valid_handle(handle)
+    }
+
+    /// Fetches positions of node control points of every spline, see [`Spline::update`].
+    pub fn update(&mut self, graph: &Graph) {
+        for spline in self.pool.iter_mut() {
+            spline.update(graph);
+        }
+    }
+
+    /// Draws every spline, see [`Spline::draw`].
+    pub fn draw(&self, context: &mut SceneDrawingContext, color: Color) {
+        for spline in self.pool.iter() {
+            spline.draw(context, color);
+        }
+    }
+
+    /// Replaces node handles of control points of every spline, see [`Spline::remap_handles`].
+    pub(in crate) fn remap_handles(&mut self, old_new_map: &FxHashMap<Handle<Node>, Handle<Node>>) {
+        for spline in self.pool.iter_mut() {
+            spline.remap_handles(old_new_map);
+        }
+    }
+}
+
+impl Index<Handle<Spline>> for SplineContainer {
+    type Output = Spline;
+
+    fn index(&self, index: Handle<Spline>) -> &Self::Output {
+        &self.pool[index]
+    }
+}
+
+impl IndexMut<Handle<Spline>> for SplineContainer {
+    fn index_mut(&mut self, index: Handle<Spline>) -> &mut Self::Output {
+        &mut self.pool[index]
+    }
+}
+
+impl Visit for SplineContainer {
+    fn visit(&mut self, name: &str, visitor: &mut Visitor) -> VisitResult {
+        visitor.enter_region(name)?;
+
+        self.pool.visit("Pool", visitor)?;
+
+        visitor.leave_region()
+    }
+}
+
+/// Defines what [`PathFollower`] does when it reaches an end of a spline.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Visit)]
+pub enum PathEndBehavior {
+    /// Follower stops at the end.
+    Stop,
+    /// Follower jumps back to the beginning. It is seamless for closed splines.
+    Loop,
+    /// Follower changes its direction and moves back.
+    PingPong,
+}
+
+impl Default for PathEndBehavior {
+    fn default() -> Self {
+        Self::Stop
+    }
+}
+
+/// Moves a node along a spline at constant speed, see module docs.
+///
+/// If the node is bound to a kinematic rigid body, the follower sets next kinematic position of
+/// the body, so it pushes dynamic bodies on its way (moving platforms). Otherwise local transform
+/// of the node is changed directly.
+#[derive(Visit, Clone, Debug)]
+pub struct PathFollower {
+    /// Spline to follow.
+    pub spline: Handle<Spline>,
+
+    /// A node to move.
+    pub target: Handle<Node>,
+
+    /// Speed in units per second.
+    pub speed: f32,
+
+    /// What to do at the end of the spline.
+    pub end_behavior: PathEndBehavior,
+
+    /// Whether the node should be rotated so its local Z axis looks along the spline.
+    pub orient_along_tangent: bool,
+
+    /// Up vector that is used to orient the node along the spline.
+    pub up: Vector3<f32>,
+
+    distance: f32,
+    reversed: bool,
+}
+
+impl Default for PathFollower {
+    fn default() -> Self {
+        Self {
+            spline: Default::default(),
+            target: Default::default(),
+            speed: 1.0,
+            end_behavior: Default::default(),
+            orient_along_tangent: false,
+            up: Vector3::y(),
+            distance: 0.0,
+            reversed: false,
+        }
+    }
+}
+
+impl PathFollower {
+    /// Returns current distance from the beginning of the spline.
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Teleports the follower to given distance from the beginning of the spline, the target
+    /// will be moved on next update.
+    pub fn set_distance(&mut self, distance: f32) {
+        self.distance = distance;
+    }
+
+    /// Returns `true` if the follower moves from the end of the spline to the beginning, it is
+    /// possible only with [`PathEndBehavior::PingPong`].
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Returns `true` if the follower has stopped at an end of the spline.
+    pub fn is_finished(&self, splines: &SplineContainer) -> bool {
+        self.end_behavior == PathEndBehavior::Stop
+            && splines
+                .try_get(self.spline)
+                .map_or(true, |spline| self.distance >= spline.length())
+    }
+
+    /// Advances the follower and moves the target node. Does nothing if the spline does not
+    /// exist.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        let spline = match scene.splines.try_get(self.spline) {
+            Some(spline) => spline,
+            None => return,
+        };
+
+        let length = spline.length();
+        let step = self.speed * dt;
+
+        if length <= 0.0 {
+            self.distance = 0.0;
+        } else {
+            match self.end_behavior {
+                PathEndBehavior::Stop => {
+                    self.distance = (self.distance + step).max(0.0).min(length);
+                    self.reversed = false;
+                }
+                PathEndBehavior::Loop => {
+                    self.distance = (self.distance + step).rem_euclid(length);
+                    self.reversed = false;
+                }
+                PathEndBehavior::PingPong => {
+                    // Moving back is treated as moving forward along the second half of a
+                    // "period", so any amount of bounces per step is handled correctly.
+                    let period = 2.0 * length;
+                    let phase = if self.reversed {
+                        period - self.distance
+                    } else {
+                        self.distance
+                    };
+                    let phase = (phase + step).rem_euclid(period);
+                    self.reversed = phase > length;
+                    self.distance = if self.reversed { period - phase } else { phase };
+                }
+            }
+        }
+
+        let (position, mut tangent) = spline.evaluate_at_distance(self.distance);
+        if self.reversed {
+            tangent = -tangent;
+        }
+
+        let rotation =
+            if self.orient_along_tangent && tangent.cross(&self.up).norm_squared() > f32::EPSILON {
+                Some(UnitQuaternion::face_towards(&tangent, &self.up))
+            } else {
+                None
+            };
+
+        self.move_target(scene, position, rotation);
+    }
+
+    fn move_target(
+        &self,
+        scene: &mut Scene,
+        position: Vector3<f32>,
+        rotation: Option<UnitQuaternion<f32>>,
+    ) {
+        if let Some(body) = scene
+            .physics_binder
+            .body_of(self.target)
+            .and_then(|body| scene.physics.bodies.get_mut(body))
+        {
+            if body.is_kinematic() {
+                let rotation = rotation.unwrap_or(body.position().rotation);
+                body.set_next_kinematic_position(Isometry3 {
+                    translation: Translation3::from(position),
+                    rotation,
+                });
+                return;
+            }
+        }
+
+        let parent = match scene.graph.try_get(self.target) {
+            Some(node) => node.parent(),
+            None => return,
+        };
+
+        // Convert world space position and rotation to parent space.
+        let (local_position, local_rotation) = match scene.graph.try_get(parent) {
+            Some(parent) => {
+                let transform = parent.global_transform();
+                let local_position = transform
+                    .try_inverse()
+                    .map(|inv| inv.transform_point(&position.into()).coords)
+                    .unwrap_or(position);
+                let local_rotation = rotation.map(|rotation| {
+                    let basis = Matrix3::from_columns(&[
+                        transform
+                            .side()
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(Vector3::x),
+                        transform
+                            .up()
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(Vector3::y),
+                        transform
+                            .look()
+                            .try_normalize(f32::EPSILON)
+                            .unwrap_or_else(Vector3::z),
+                    ]);
+                    let parent_rotation = UnitQuaternion::from_rotation_matrix(
+                        &Rotation3::from_matrix_unchecked(basis),
+                    );
+                    parent_rotation.inverse() * rotation
+                });
+                (local_position, local_rotation)
+            }
+            None => (position, rotation),
+        };
+
+        let transform = scene.graph[self.target].local_transform_mut();
+        transform.set_position(local_position);
+        if let Some(rotation) = local_rotation {
+            transform.set_rotation(rotation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector3,
+        scene::{
+            base::BaseBuilder,
+            spline::{PathEndBehavior, PathFollower, Spline, SplineKind, SplinePoint},
+            Scene,
+        },
+    };
+
+    fn line() -> Spline {
+        Spline::new(SplineKind::CatmullRom).with_points(vec![
+            SplinePoint::Position(Vector3::new(0.0, 0.0, 0.0)),
+            SplinePoint::Position(Vector3::new(4.0, 0.0, 0.0)),
+            SplinePoint::Position(Vector3::new(10.0, 0.0, 0.0)),
+        ])
+    }
+
+    #[test]
+    fn test_spline_evaluation() {
+        let spline = line();
+
+        assert_eq!(spline.segment_count(), 2);
+        assert!((spline.length() - 10.0).abs() < 1.0e-3);
+
+        // Curve passes through control points.
+        let (position, tangent) = spline.evaluate(0.5);
+        assert!(position.metric_distance(&Vector3::new(4.0, 0.0, 0.0)) < 1.0e-4);
+        assert!(tangent.metric_distance(&Vector3::x()) < 1.0e-4);
+
+        // Parameter is not proportional to distance, but distance is.
+        let (position, _) = spline.evaluate_at_distance(7.0);
+        assert!((position.x - 7.0).abs() < 5.0e-2);
+
+        let bezier = Spline::new(SplineKind::Bezier).with_points(vec![
+            SplinePoint::Position(Vector3::new(0.0, 0.0, 0.0)),
+            SplinePoint::Position(Vector3::new(0.0, 1.0, 0.0)),
+            SplinePoint::Position(Vector3::new(1.0, 1.0, 0.0)),
+            SplinePoint::Position(Vector3::new(1.0, 0.0, 0.0)),
+        ]);
+        assert_eq!(bezier.segment_count(), 1);
+        assert_eq!(bezier.evaluate(1.0).0, Vector3::new(1.0, 0.0, 0.0));
+        assert_eq!(bezier.evaluate(0.0).1, Vector3::y());
+    }
+
+    #[test]
+    fn test_node_control_points() {
+        let mut scene = Scene::new();
+        let a = BaseBuilder::new().build(&mut scene.graph);
+        let b = BaseBuilder::new().build(&mut scene.graph);
+        scene.graph[b]
+            .local_transform_mut()
+            .set_position(Vector3::new(0.0, 0.0, 5.0));
+
+        let spline = scene.splines.add(
+            Spline::new(SplineKind::CatmullRom)
+                .with_points(vec![SplinePoint::Node(a), SplinePoint::Node(b)]),
+        );
+        scene.update(Default::default(), 0.0);
+        assert!((scene.splines[spline].length() - 5.0).abs() < 1.0e-3);
+
+        scene.graph[b]
+            .local_transform_mut()
+            .set_position(Vector3::new(0.0, 0.0, 8.0));
+        scene.update(Default::default(), 0.0);
+        assert!((scene.splines[spline].length() - 8.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn test_path_follower() {
+        let mut scene = Scene::new();
+        let target = BaseBuilder::new().build(&mut scene.graph);
+        let spline = scene.splines.add(line());
+
+        let mut follower = PathFollower {
+            spline,
+            target,
+            speed: 4.0,
+            end_behavior: PathEndBehavior::PingPong,
+            orient_along_tangent: true,
+            ..Default::default()
+        };
+
+        follower.update(&mut scene, 1.0);
+        assert!((follower.distance() - 4.0).abs() < 1.0e-4);
+        let position = **scene.graph[target].local_transform().position();
+        assert!((position.x - 4.0).abs() < 5.0e-2);
+
+        // 4 + 8 = 12, which is 2 units past the end.
+        follower.update(&mut scene, 2.0);
+        assert!(follower.is_reversed());
+        assert!((follower.distance() - 8.0).abs() < 1.0e-4);
+
+        follower.update(&mut scene, 2.5);
+        assert!(!follower.is_reversed());
+        assert!((follower.distance() - 2.0).abs() < 1.0e-4);
+
+        follower.end_behavior = PathEndBehavior::Loop;
+        follower.update(&mut scene, 2.0);
+        assert!((follower.distance() - 0.0).abs() < 1.0e-4);
+
+        follower.end_behavior = PathEndBehavior::Stop;
+        follower.update(&mut scene, 10.0);
+        assert_eq!(follower.distance(), 10.0);
+        assert!(follower.is_finished(&scene.splines));
+    }
+}
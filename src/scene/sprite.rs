@@ -6,7 +6,7 @@ use crate::{
     core::{
         color::Color,
         inspect::{Inspect, PropertyInfo},
-        math::aabb::AxisAlignedBoundingBox,
+        math::{aabb::AxisAlignedBoundingBox, Rect},
         pool::Handle,
         visitor::{Visit, VisitResult, Visitor},
     },
@@ -14,8 +14,10 @@ use crate::{
     scene::{
         base::{Base, BaseBuilder},
         graph::Graph,
+        light::LightingMode,
         node::Node,
     },
+    utils::atlas::TextureAtlas,
 };
 use std::ops::{Deref, DerefMut};
 
@@ -28,10 +30,19 @@ use std::ops::{Deref, DerefMut};
 /// it could be done by using Forward render pass. You may need this for custom effects. Current implementation
 /// is very simple, but still covers 95% of use cases.
 ///
-/// # Depth sorting
+/// # Sorting
 ///
-/// Sprites are **not** depth-sorted so there could be some blending issues if multiple sprites are stacked one behind
-/// another.
+/// Sprites are drawn in order of their sorting layers (see [`Sprite::set_sorting_layer`]), sprites with lower layer
+/// are drawn first. Inside a layer, sprites are drawn in order of [`Sprite::set_order_in_layer`], and sprites with
+/// the same order are drawn from back to front. This makes drawing order deterministic, which is especially important
+/// for 2D games where many sprites are placed at the same distance from the camera.
+///
+/// # Lighting
+///
+/// By default sprites are not affected by lights of a scene, which is fine for emissive things like
+/// fire or glowing pickups. Use [`Sprite::set_lighting_mode`] with [`LightingMode::Lit`] to make a
+/// sprite lit by ambient light and nearest lights of the scene. Lighting is calculated once per sprite
+/// (at its position) and it does not take shadows into account.
 ///
 /// # Performance
 ///
@@ -66,6 +77,10 @@ pub struct Sprite {
     #[inspect(min_value = 0.0, step = 0.1)]
     size: f32,
     rotation: f32,
+    uv_rect: Rect<f32>,
+    sorting_layer: i32,
+    order_in_layer: i32,
+    lighting_mode: LightingMode,
 }
 
 impl Deref for Sprite {
@@ -97,6 +112,10 @@ impl Sprite {
             color: self.color,
             size: self.size,
             rotation: self.rotation,
+            uv_rect: self.uv_rect,
+            sorting_layer: self.sorting_layer,
+            order_in_layer: self.order_in_layer,
+            lighting_mode: self.lighting_mode,
         }
     }
 
@@ -148,6 +167,61 @@ impl Sprite {
         self.texture.as_ref()
     }
 
+    /// Sets a rectangle of the texture (in texture coordinates) that will be shown on the sprite.
+    /// Default is whole texture.
+    pub fn set_uv_rect(&mut self, uv_rect: Rect<f32>) {
+        self.uv_rect = uv_rect;
+    }
+
+    /// Returns current rectangle of the texture that is shown on the sprite.
+    pub fn uv_rect(&self) -> Rect<f32> {
+        self.uv_rect
+    }
+
+    /// Sets texture and rectangle of the texture from an entry of given atlas. Returns `false`
+    /// if there is no entry with such name, sprite is left unchanged in this case.
+    pub fn set_atlas_entry(&mut self, atlas: &TextureAtlas, name: &str) -> bool {
+        if let Some((texture, uv_rect)) = atlas.texture(name) {
+            self.texture = Some(texture);
+            self.uv_rect = uv_rect;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Sets sorting layer of the sprite. Sprites with lower layer are drawn first, so they appear behind
+    /// sprites with higher layer. Default is 0.
+    pub fn set_sorting_layer(&mut self, sorting_layer: i32) {
+        self.sorting_layer = sorting_layer;
+    }
+
+    /// Returns current sorting layer of the sprite.
+    pub fn sorting_layer(&self) -> i32 {
+        self.sorting_layer
+    }
+
+    /// Sets order of the sprite inside its sorting layer. Sprites with lower order are drawn first.
+    /// Default is 0.
+    pub fn set_order_in_layer(&mut self, order_in_layer: i32) {
+        self.order_in_layer = order_in_layer;
+    }
+
+    /// Returns current order of the sprite inside its sorting layer.
+    pub fn order_in_layer(&self) -> i32 {
+        self.order_in_layer
+    }
+
+    /// Sets lighting mode of the sprite. Default is [`LightingMode::Unlit`].
+    pub fn set_lighting_mode(&mut self, lighting_mode: LightingMode) {
+        self.lighting_mode = lighting_mode;
+    }
+
+    /// Returns current lighting mode of the sprite.
+    pub fn lighting_mode(&self) -> LightingMode {
+        self.lighting_mode
+    }
+
     /// Returns current **local-space** bounding box.
     #[inline]
     pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
@@ -169,6 +243,10 @@ impl Visit for Sprite {
         self.size.visit("Size", visitor)?;
         self.rotation.visit("Rotation", visitor)?;
         self.base.visit("Base", visitor)?;
+        let _ = self.uv_rect.visit("UvRect", visitor);
+        let _ = self.sorting_layer.visit("SortingLayer", visitor);
+        let _ = self.order_in_layer.visit("OrderInLayer", visitor);
+        let _ = self.lighting_mode.visit("LightingMode", visitor);
 
         visitor.leave_region()
     }
@@ -182,6 +260,10 @@ pub struct SpriteBuilder {
     color: Color,
     size: f32,
     rotation: f32,
+    uv_rect: Rect<f32>,
+    sorting_layer: i32,
+    order_in_layer: i32,
+    lighting_mode: LightingMode,
 }
 
 impl SpriteBuilder {
@@ -193,6 +275,10 @@ impl SpriteBuilder {
             color: Color::WHITE,
             size: 0.2,
             rotation: 0.0,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
+            sorting_layer: 0,
+            order_in_layer: 0,
+            lighting_mode: LightingMode::Unlit,
         }
     }
 
@@ -208,6 +294,22 @@ impl SpriteBuilder {
         self
     }
 
+    /// Sets desired rectangle of the texture (in texture coordinates).
+    pub fn with_uv_rect(mut self, uv_rect: Rect<f32>) -> Self {
+        self.uv_rect = uv_rect;
+        self
+    }
+
+    /// Sets texture and rectangle of the texture from an entry of given atlas. Does nothing if
+    /// there is no entry with such name.
+    pub fn with_atlas_entry(mut self, atlas: &TextureAtlas, name: &str) -> Self {
+        if let Some((texture, uv_rect)) = atlas.texture(name) {
+            self.texture = Some(texture);
+            self.uv_rect = uv_rect;
+        }
+        self
+    }
+
     /// Sets desired color.
     pub fn with_color(mut self, color: Color) -> Self {
         self.color = color;
@@ -226,6 +328,24 @@ impl SpriteBuilder {
         self
     }
 
+    /// Sets desired sorting layer.
+    pub fn with_sorting_layer(mut self, sorting_layer: i32) -> Self {
+        self.sorting_layer = sorting_layer;
+        self
+    }
+
+    /// Sets desired order inside sorting layer.
+    pub fn with_order_in_layer(mut self, order_in_layer: i32) -> Self {
+        self.order_in_layer = order_in_layer;
+        self
+    }
+
+    /// Sets desired lighting mode.
+    pub fn with_lighting_mode(mut self, lighting_mode: LightingMode) -> Self {
+        self.lighting_mode = lighting_mode;
+        self
+    }
+
     fn build_sprite(self) -> Sprite {
         Sprite {
             base: self.base_builder.build_base(),
@@ -233,6 +353,10 @@ impl SpriteBuilder {
             color: self.color,
             size: self.size,
             rotation: self.rotation,
+            uv_rect: self.uv_rect,
+            sorting_layer: self.sorting_layer,
+            order_in_layer: self.order_in_layer,
+            lighting_mode: self.lighting_mode,
         }
     }
 
@@ -51,7 +51,7 @@ use crate::{
         visitor::{Visit, VisitResult, Visitor},
     },
     scene::variable::TemplateVariable,
-    utils::log::{Log, MessageKind},
+    utils::log::log_warn,
 };
 use std::{any::TypeId, cell::Cell};
 
@@ -61,6 +61,9 @@ pub struct Transform {
     /// Indicates that some property has changed and matrix must be
     /// recalculated before use. This is some sort of lazy evaluation.
     dirty: Cell<bool>,
+    /// Indicates that some property has changed since last update of hierarchical data of a
+    /// graph, so global transform of the node and its descendants must be recalculated.
+    changed: Cell<bool>,
     local_scale: TemplateVariable<Vector3<f32>>,
     local_position: TemplateVariable<Vector3<f32>>,
     local_rotation: TemplateVariable<UnitQuaternion<f32>>,
@@ -233,6 +236,7 @@ impl Visit for Transform {
         if visitor.is_reading() {
             self.post_rotation_matrix =
                 build_post_rotation_matrix(self.post_rotation.clone_inner());
+            self.invalidate();
         }
 
         visitor.leave_region()
@@ -251,10 +255,7 @@ fn build_post_rotation_matrix(post_rotation: UnitQuaternion<f32>) -> Matrix3<f32
         .matrix()
         .try_inverse()
         .unwrap_or_else(|| {
-            Log::writeln(
-                MessageKind::Warning,
-                "Unable to inverse post rotation matrix! Fallback to identity matrix.".to_owned(),
-            );
+            log_warn!("Unable to inverse post rotation matrix! Fallback to identity matrix.");
             Matrix3::identity()
         })
 }
@@ -265,6 +266,7 @@ impl Transform {
     pub fn identity() -> Self {
         Self {
             dirty: Cell::new(true),
+            changed: Cell::new(true),
             local_position: TemplateVariable::new(Vector3::default()),
             local_scale: TemplateVariable::new(Vector3::new(1.0, 1.0, 1.0)),
             local_rotation: TemplateVariable::new(UnitQuaternion::identity()),
@@ -290,7 +292,7 @@ impl Transform {
     pub fn set_position(&mut self, local_position: Vector3<f32>) -> &mut Self {
         if self.dirty.get() || *self.local_position != local_position {
             self.local_position.set(local_position);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -306,7 +308,7 @@ impl Transform {
     pub fn set_rotation(&mut self, local_rotation: UnitQuaternion<f32>) -> &mut Self {
         if self.dirty.get() || *self.local_rotation != local_rotation {
             self.local_rotation.set(local_rotation);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -322,7 +324,7 @@ impl Transform {
     pub fn set_scale(&mut self, local_scale: Vector3<f32>) -> &mut Self {
         if self.dirty.get() || *self.local_scale != local_scale {
             self.local_scale.set(local_scale);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -334,7 +336,7 @@ impl Transform {
     pub fn set_pre_rotation(&mut self, pre_rotation: UnitQuaternion<f32>) -> &mut Self {
         if self.dirty.get() || *self.pre_rotation != pre_rotation {
             self.pre_rotation.set(pre_rotation);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -354,7 +356,7 @@ impl Transform {
             self.post_rotation.set(post_rotation);
             self.post_rotation_matrix =
                 build_post_rotation_matrix(self.post_rotation.clone_inner());
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -371,7 +373,7 @@ impl Transform {
     pub fn set_rotation_offset(&mut self, rotation_offset: Vector3<f32>) -> &mut Self {
         if self.dirty.get() || *self.rotation_offset != rotation_offset {
             self.rotation_offset.set(rotation_offset);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -389,7 +391,7 @@ impl Transform {
     pub fn set_rotation_pivot(&mut self, rotation_pivot: Vector3<f32>) -> &mut Self {
         if self.dirty.get() || *self.rotation_pivot != rotation_pivot {
             self.rotation_pivot.set(rotation_pivot);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -406,7 +408,7 @@ impl Transform {
     pub fn set_scaling_offset(&mut self, scaling_offset: Vector3<f32>) -> &mut Self {
         if self.dirty.get() || *self.scaling_offset != scaling_offset {
             self.scaling_offset.set(scaling_offset);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -423,7 +425,7 @@ impl Transform {
     pub fn set_scaling_pivot(&mut self, scaling_pivot: Vector3<f32>) -> &mut Self {
         if self.dirty.get() || *self.scaling_pivot != scaling_pivot {
             self.scaling_pivot.set(scaling_pivot);
-            self.dirty.set(true);
+            self.invalidate();
         }
         self
     }
@@ -439,10 +441,34 @@ impl Transform {
     #[inline]
     pub fn offset(&mut self, vec: Vector3<f32>) -> &mut Self {
         self.local_position.set(*self.local_position + vec);
-        self.dirty.set(true);
+        self.invalidate();
         self
     }
 
+    /// Returns `true` if any property of the transform was changed since last update of
+    /// hierarchical data of a graph, which means that global transform of the node (and its
+    /// descendants) is outdated at the moment.
+    #[inline]
+    pub fn is_changed(&self) -> bool {
+        self.changed.get()
+    }
+
+    #[inline]
+    pub(in crate) fn mark_changed(&self) {
+        self.changed.set(true);
+    }
+
+    #[inline]
+    pub(in crate) fn take_changed(&self) -> bool {
+        self.changed.replace(false)
+    }
+
+    #[inline]
+    fn invalidate(&self) {
+        self.dirty.set(true);
+        self.changed.set(true);
+    }
+
     fn calculate_local_transform(&self) -> Matrix4<f32> {
         // Make shortcuts to remove visual clutter.
         let por = &self.post_rotation_matrix;
@@ -670,6 +696,7 @@ impl TransformBuilder {
     pub fn build(self) -> Transform {
         Transform {
             dirty: Cell::new(true),
+            changed: Cell::new(true),
             local_scale: TemplateVariable::new(self.local_scale),
             local_position: TemplateVariable::new(self.local_position),
             local_rotation: TemplateVariable::new(self.local_rotation),
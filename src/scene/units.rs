@@ -0,0 +1,266 @@
+//! Conversion of units of length of a scene, see [`convert_scene_units`] and
+//! [`crate::core::conventions`] for units of the engine.
+
+use crate::{
+    core::{parking_lot::Mutex, pool::Handle},
+    physics3d::rapier::{
+        dynamics::JointParams,
+        parry::shape::{Segment, Shape, SharedShape},
+    },
+    scene::{
+        light::Light,
+        mesh::{
+            buffer::{VertexAttributeUsage, VertexReadTrait, VertexWriteTrait},
+            surface::SurfaceData,
+        },
+        node::Node,
+        Scene,
+    },
+    utils::log::log_warn,
+};
+use fxhash::{FxHashMap, FxHashSet};
+use std::sync::Arc;
+
+/// Converts units of length of a subtree of a scene by multiplying every length by given factor,
+/// for example `0.01` converts centimeters to meters. Unlike scaling of the root node, the
+/// conversion changes the data itself, so the nodes keep unit scale and physical shapes have
+/// correct sizes. The root node keeps its position, because it is defined in the coordinate space
+/// of its parent.
+///
+/// The conversion affects:
+///
+/// - local positions (and pivots and offsets) of descendants of the root,
+/// - vertices and morph targets of meshes, shared surface data is copied, so other instances of the
+/// same model are not affected,
+/// - inverse bind pose matrices of bones,
+/// - radii of point lights, distances of spot lights, clipping planes of cameras and sizes of sprites,
+/// - positions of rigid bodies bound to the nodes, positions and shapes of their colliders, and
+/// anchors of joints between such bodies,
+/// - position key frames of animation tracks of descendants of the root.
+///
+/// Other data (terrains, particle systems, decals, etc.) is left as is.
+pub fn convert_scene_units(scene: &mut Scene, root: Handle<Node>, factor: f32) {
+    let nodes = scene.graph.traverse_handle_iter(root).collect::<Vec<_>>();
+
+    let mut converted_data = FxHashMap::default();
+    for &handle in nodes.iter() {
+        let node = &mut scene.graph[handle];
+
+        if handle != root {
+            let transform = node.local_transform_mut();
+            let position = **transform.position();
+            let rotation_offset = **transform.rotation_offset();
+            let rotation_pivot = **transform.rotation_pivot();
+            let scaling_offset = **transform.scaling_offset();
+            let scaling_pivot = **transform.scaling_pivot();
+            transform
+                .set_position(position.scale(factor))
+                .set_rotation_offset(rotation_offset.scale(factor))
+                .set_rotation_pivot(rotation_pivot.scale(factor))
+                .set_scaling_offset(scaling_offset.scale(factor))
+                .set_scaling_pivot(scaling_pivot.scale(factor));
+        }
+
+        // Uniform scale commutes with rotation and scale, so only translation part is affected.
+        for i in 0..3 {
+            node.inv_bind_pose_transform[(i, 3)] *= factor;
+        }
+
+        match node {
+            Node::Mesh(mesh) => {
+                let surfaces = mesh
+                    .surfaces()
+                    .iter()
+                    .map(|surface| {
+                        let data = surface.data();
+                        let converted = converted_data
+                            .entry(&*data as *const Mutex<SurfaceData>)
+                            .or_insert_with(|| {
+                                Arc::new(Mutex::new(scale_surface_data(&data.lock(), factor)))
+                            })
+                            .clone();
+                        let mut surface = surface.clone();
+                        surface.set_data(converted);
+                        surface
+                    })
+                    .collect::<Vec<_>>();
+                mesh.clear_surfaces();
+                for surface in surfaces {
+                    mesh.add_surface(surface);
+                }
+            }
+            Node::Light(Light::Point(point)) => point.set_radius(point.radius() * factor),
+            Node::Light(Light::Spot(spot)) => {
+                spot.set_distance(spot.distance() * factor);
+            }
+            Node::Camera(camera) => {
+                let (z_near, z_far) = (camera.z_near(), camera.z_far());
+                camera.set_z_near(z_near * factor).set_z_far(z_far * factor);
+            }
+            Node::Sprite(sprite) => sprite.set_size(sprite.size() * factor),
+            _ => (),
+        }
+    }
+
+    let node_set = nodes.iter().cloned().collect::<FxHashSet<_>>();
+    for animation in scene.animations.iter_mut() {
+        for track in animation.get_tracks_mut() {
+            let node = track.get_node();
+            if node != root && node_set.contains(&node) {
+                let mut key_frames = track.get_key_frames().to_vec();
+                for key_frame in key_frames.iter_mut() {
+                    key_frame.position = key_frame.position.scale(factor);
+                }
+                track.set_key_frames(&key_frames);
+            }
+        }
+    }
+
+    // Bodies drive their nodes, so they must be moved to new positions of the nodes.
+    scene.graph.update_hierarchical_data();
+
+    let mut native_bodies = FxHashSet::default();
+    for &handle in nodes.iter() {
+        let body_handle = match scene.physics_binder.body_of(handle) {
+            Some(body_handle) => *body_handle,
+            None => continue,
+        };
+        if let Some(&native) = scene.physics.bodies.handle_map().value_of(&body_handle) {
+            native_bodies.insert(native);
+        }
+
+        let body = match scene.physics.bodies.get_mut(&body_handle) {
+            Some(body) => body,
+            None => continue,
+        };
+        let mut position = *body.position();
+        position.translation.vector = scene.graph[handle].global_position();
+        body.set_position(position, true);
+
+        let colliders = body.colliders().to_vec();
+        for collider in colliders {
+            if let Some(collider) = scene.physics.colliders.native_mut(collider) {
+                if let Some(mut position) = collider.position_wrt_parent().cloned() {
+                    position.translation.vector = position.translation.vector.scale(factor);
+                    collider.set_position_wrt_parent(position);
+                }
+                match scale_shape(collider.shape(), factor) {
+                    Some(shape) => collider.set_shape(shape),
+                    None => log_warn!(
+                        "Unable to convert units of a collider of {} node, its shape is not supported.",
+                        scene.graph[handle].name()
+                    ),
+                }
+            }
+        }
+    }
+
+    for joint in scene.physics.joints.iter_mut() {
+        if native_bodies.contains(&joint.body1) && native_bodies.contains(&joint.body2) {
+            match &mut joint.params {
+                JointParams::BallJoint(ball) => {
+                    ball.local_anchor1 *= factor;
+                    ball.local_anchor2 *= factor;
+                }
+                JointParams::FixedJoint(fixed) => {
+                    fixed.local_frame1.translation.vector *= factor;
+                    fixed.local_frame2.translation.vector *= factor;
+                }
+                JointParams::PrismaticJoint(prismatic) => {
+                    prismatic.local_anchor1 *= factor;
+                    prismatic.local_anchor2 *= factor;
+                }
+                JointParams::RevoluteJoint(revolute) => {
+                    revolute.local_anchor1 *= factor;
+                    revolute.local_anchor2 *= factor;
+                }
+            }
+        }
+    }
+}
+
+// Makes a procedural copy of given data with scaled positions of vertices and morph targets.
+fn scale_surface_data(data: &SurfaceData, factor: f32) -> SurfaceData {
+    let mut scaled = SurfaceData::new(
+        data.vertex_buffer.clone(),
+        data.geometry_buffer.clone(),
+        true,
+    );
+    for mut view in scaled.vertex_buffer.modify().iter_mut() {
+        if let Ok(position) = view.read_3_f32(VertexAttributeUsage::Position) {
+            view.write_3_f32(VertexAttributeUsage::Position, position.scale(factor))
+                .unwrap();
+        }
+    }
+    scaled.morph_targets = data.morph_targets.clone();
+    for target in scaled.morph_targets.iter_mut() {
+        for delta in target.position_deltas.iter_mut() {
+            *delta = delta.scale(factor);
+        }
+    }
+    scaled
+}
+
+/// Returns a copy of given shape with every dimension multiplied by given factor, or `None` if the
+/// shape is not supported (compound shapes for example).
+pub fn scale_shape(shape: &dyn Shape, factor: f32) -> Option<SharedShape> {
+    if let Some(ball) = shape.as_ball() {
+        Some(SharedShape::ball(ball.radius * factor))
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        let half_extents = cuboid.half_extents.scale(factor);
+        Some(SharedShape::cuboid(
+            half_extents.x,
+            half_extents.y,
+            half_extents.z,
+        ))
+    } else if let Some(capsule) = shape.as_capsule() {
+        Some(SharedShape::capsule(
+            capsule.segment.a * factor,
+            capsule.segment.b * factor,
+            capsule.radius * factor,
+        ))
+    } else if let Some(cylinder) = shape.as_cylinder() {
+        Some(SharedShape::cylinder(
+            cylinder.half_height * factor,
+            cylinder.radius * factor,
+        ))
+    } else if let Some(round_cylinder) = shape.as_round_cylinder() {
+        Some(SharedShape::round_cylinder(
+            round_cylinder.base_shape.half_height * factor,
+            round_cylinder.base_shape.radius * factor,
+            round_cylinder.border_radius * factor,
+        ))
+    } else if let Some(cone) = shape.as_cone() {
+        Some(SharedShape::cone(
+            cone.half_height * factor,
+            cone.radius * factor,
+        ))
+    } else if let Some(segment) = shape.downcast_ref::<Segment>() {
+        Some(SharedShape::segment(segment.a * factor, segment.b * factor))
+    } else if let Some(triangle) = shape.as_triangle() {
+        Some(SharedShape::triangle(
+            triangle.a * factor,
+            triangle.b * factor,
+            triangle.c * factor,
+        ))
+    } else if let Some(trimesh) = shape.as_trimesh() {
+        Some(SharedShape::trimesh(
+            trimesh.vertices().iter().map(|v| v * factor).collect(),
+            trimesh.indices().to_vec(),
+        ))
+    } else if let Some(heightfield) = shape.as_heightfield() {
+        Some(SharedShape::heightfield(
+            heightfield.heights().clone(),
+            heightfield.scale().scale(factor),
+        ))
+    } else if let Some(convex) = shape.as_convex_polyhedron() {
+        let points = convex
+            .points()
+            .iter()
+            .map(|p| p * factor)
+            .collect::<Vec<_>>();
+        SharedShape::convex_hull(&points)
+    } else {
+        None
+    }
+}
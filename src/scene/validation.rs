@@ -0,0 +1,687 @@
+//! Runtime validation of scenes and diagnostics dump.
+//!
+//! Corrupted scene state (a stale parent handle, an animation track that targets a freed node,
+//! a physics binding to a removed body, etc.) usually shows up far from its cause. Validation
+//! checks the whole scene for such problems, see [`Scene::validate`] and
+//! [`Scene::set_validation_interval`] for periodic validation in debug builds.
+//!
+//! [`Scene::dump_hierarchy`] produces a readable text tree of the graph, which is useful to
+//! attach to bug reports.
+
+use crate::{
+    animation::Animation,
+    core::{
+        algebra::{Matrix4, Vector3},
+        pool::Handle,
+    },
+    physics3d::RigidBodyHandle,
+    scene::{graph::Graph, light::Light, node::Node, Scene},
+    utils::log::log_warn,
+};
+use fxhash::{FxHashMap, FxHashSet};
+use std::fmt::Write;
+use thiserror::Error;
+
+/// Describes why a handle is invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Error)]
+pub enum HandleProblem {
+    /// Index of the handle is out of bounds of the pool.
+    #[error("index is out of bounds")]
+    OutOfBounds,
+
+    /// A pool record at the index of the handle is empty, the object was freed.
+    #[error("object was freed")]
+    Vacant,
+
+    /// A pool record at the index of the handle was reused by another object, the handle is
+    /// stale.
+    #[error("generation mismatch (handle: {handle}, pool: {actual})")]
+    StaleGeneration {
+        /// Generation of the handle.
+        handle: u32,
+        /// Actual generation of the pool record.
+        actual: u32,
+    },
+}
+
+/// A problem found by scene validation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Error)]
+pub enum ValidationError {
+    /// A node has invalid parent handle.
+    #[error("node {node} ({name}) has invalid parent {parent}: {problem}")]
+    InvalidParent {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+        /// Parent handle of the node.
+        parent: Handle<Node>,
+        /// Why the parent handle is invalid.
+        problem: HandleProblem,
+    },
+
+    /// A node has valid parent, but the parent does not have the node in its children list.
+    #[error("node {node} ({name}) is not listed as a child of its parent {parent}")]
+    NotAChildOfParent {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+        /// Parent handle of the node.
+        parent: Handle<Node>,
+    },
+
+    /// A node has invalid handle in its children list.
+    #[error("node {node} ({name}) has invalid child {child}: {problem}")]
+    InvalidChild {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+        /// Invalid child handle.
+        child: Handle<Node>,
+        /// Why the child handle is invalid.
+        problem: HandleProblem,
+    },
+
+    /// A child of a node has different parent.
+    #[error("child {child} of node {node} ({name}) has different parent {actual_parent}")]
+    ChildParentMismatch {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+        /// Handle of the child.
+        child: Handle<Node>,
+        /// Actual parent of the child.
+        actual_parent: Handle<Node>,
+    },
+
+    /// A node lists the same child more than once.
+    #[error("node {node} ({name}) lists child {child} more than once")]
+    DuplicateChild {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+        /// Handle of the child.
+        child: Handle<Node>,
+    },
+
+    /// A node is an ancestor of itself.
+    #[error("node {node} ({name}) is a part of a cycle in the hierarchy")]
+    Cycle {
+        /// Handle of the first node of the cycle that was found.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+    },
+
+    /// A node is not attached to the graph - it has no parent, but it is not the root.
+    #[error("node {node} ({name}) is detached from the graph")]
+    Detached {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+    },
+
+    /// Local or global transform of a node has NaN or infinite values.
+    #[error("node {node} ({name}) has non-finite transform")]
+    NonFiniteTransform {
+        /// Handle of the node.
+        node: Handle<Node>,
+        /// Name of the node.
+        name: String,
+    },
+
+    /// A surface of a mesh has no shared surface data.
+    #[error("surface {surface} of mesh {node} ({name}) has no surface data")]
+    MissingSurfaceData {
+        /// Handle of the mesh.
+        node: Handle<Node>,
+        /// Name of the mesh.
+        name: String,
+        /// Index of the surface.
+        surface: usize,
+    },
+
+    /// A surface of a mesh uses a bone with invalid handle.
+    #[error("surface {surface} of mesh {node} ({name}) has invalid bone {bone}: {problem}")]
+    InvalidBone {
+        /// Handle of the mesh.
+        node: Handle<Node>,
+        /// Name of the mesh.
+        name: String,
+        /// Index of the surface.
+        surface: usize,
+        /// Invalid bone handle.
+        bone: Handle<Node>,
+        /// Why the bone handle is invalid.
+        problem: HandleProblem,
+    },
+
+    /// A track of an animation targets invalid node.
+    #[error("track {track} of animation {animation} targets invalid node {node}: {problem}")]
+    InvalidTrackTarget {
+        /// Handle of the animation.
+        animation: Handle<Animation>,
+        /// Index of the track.
+        track: usize,
+        /// Target node of the track.
+        node: Handle<Node>,
+        /// Why the node handle is invalid.
+        problem: HandleProblem,
+    },
+
+    /// Physics binder links invalid node with a rigid body.
+    #[error("physics binder links invalid node {node} with body {body:?}: {problem}")]
+    InvalidBinderNode {
+        /// Node handle of the binding.
+        node: Handle<Node>,
+        /// Rigid body handle of the binding.
+        body: RigidBodyHandle,
+        /// Why the node handle is invalid.
+        problem: HandleProblem,
+    },
+
+    /// Physics binder links a node with a rigid body that does not exist.
+    #[error("physics binder links node {node} with body {body:?} that does not exist")]
+    InvalidBinderBody {
+        /// Node handle of the binding.
+        node: Handle<Node>,
+        /// Rigid body handle of the binding.
+        body: RigidBodyHandle,
+    },
+
+    /// Forward (node -> body) and backward (body -> node) maps of physics binder do not match.
+    #[error("physics binder maps are inconsistent for node {node} and body {body:?}")]
+    BinderMismatch {
+        /// Node handle of the binding.
+        node: Handle<Node>,
+        /// Rigid body handle of the binding.
+        body: RigidBodyHandle,
+    },
+}
+
+/// Checks given handle against the graph, returns `None` if the handle is valid.
+fn check_handle(graph: &Graph, handle: Handle<Node>) -> Option<HandleProblem> {
+    if handle.index() >= graph.capacity() {
+        return Some(HandleProblem::OutOfBounds);
+    }
+    let actual = graph.handle_from_index(handle.index());
+    if actual.is_none() {
+        Some(HandleProblem::Vacant)
+    } else if actual.generation() != handle.generation() {
+        Some(HandleProblem::StaleGeneration {
+            handle: handle.generation(),
+            actual: actual.generation(),
+        })
+    } else {
+        None
+    }
+}
+
+fn is_finite_matrix(matrix: &Matrix4<f32>) -> bool {
+    matrix.iter().all(|v| v.is_finite())
+}
+
+fn is_finite_vector(vector: &Vector3<f32>) -> bool {
+    vector.iter().all(|v| v.is_finite())
+}
+
+fn validate_hierarchy(graph: &Graph, errors: &mut Vec<ValidationError>) {
+    // Whether a node is a part of (or a descendant of) a cycle.
+    let mut in_cycle = FxHashMap::default();
+
+    for (handle, node) in graph.pair_iter() {
+        let name = || node.name_owned();
+
+        let parent = node.parent();
+        if parent.is_some() {
+            if let Some(problem) = check_handle(graph, parent) {
+                errors.push(ValidationError::InvalidParent {
+                    node: handle,
+                    name: name(),
+                    parent,
+                    problem,
+                });
+            } else if !graph[parent].children().contains(&handle) {
+                errors.push(ValidationError::NotAChildOfParent {
+                    node: handle,
+                    name: name(),
+                    parent,
+                });
+            }
+        } else if handle != graph.get_root() {
+            errors.push(ValidationError::Detached {
+                node: handle,
+                name: name(),
+            });
+        }
+
+        let mut children = FxHashSet::default();
+        for &child in node.children() {
+            if !children.insert(child) {
+                errors.push(ValidationError::DuplicateChild {
+                    node: handle,
+                    name: name(),
+                    child,
+                });
+            } else if let Some(problem) = check_handle(graph, child) {
+                errors.push(ValidationError::InvalidChild {
+                    node: handle,
+                    name: name(),
+                    child,
+                    problem,
+                });
+            } else if graph[child].parent() != handle {
+                errors.push(ValidationError::ChildParentMismatch {
+                    node: handle,
+                    name: name(),
+                    child,
+                    actual_parent: graph[child].parent(),
+                });
+            }
+        }
+
+        // Walk up the hierarchy until a node with known state is found, every node is visited
+        // only once, so each cycle is reported once.
+        let mut path = Vec::new();
+        let mut on_path = FxHashSet::default();
+        let mut current = handle;
+        let cyclic = loop {
+            if let Some(&cyclic) = in_cycle.get(&current) {
+                break cyclic;
+            }
+            if !on_path.insert(current) {
+                errors.push(ValidationError::Cycle {
+                    node: current,
+                    name: graph[current].name_owned(),
+                });
+                break true;
+            }
+            path.push(current);
+            match graph.try_get(current) {
+                Some(current_ref) if current_ref.parent().is_some() => {
+                    current = current_ref.parent()
+                }
+                _ => break false,
+            }
+        };
+        for node in path {
+            in_cycle.insert(node, cyclic);
+        }
+    }
+}
+
+fn validate_nodes(graph: &Graph, errors: &mut Vec<ValidationError>) {
+    for (handle, node) in graph.pair_iter() {
+        let transform = node.local_transform();
+        if !is_finite_vector(transform.position())
+            || !is_finite_vector(transform.scale())
+            || !transform.rotation().coords.iter().all(|v| v.is_finite())
+            || !is_finite_matrix(&node.global_transform())
+        {
+            errors.push(ValidationError::NonFiniteTransform {
+                node: handle,
+                name: node.name_owned(),
+            });
+        }
+
+        if let Node::Mesh(mesh) = node {
+            for (index, surface) in mesh.surfaces().iter().enumerate() {
+                if surface.try_data().is_none() {
+                    errors.push(ValidationError::MissingSurfaceData {
+                        node: handle,
+                        name: node.name_owned(),
+                        surface: index,
+                    });
+                }
+
+                for &bone in surface.bones.iter() {
+                    if let Some(problem) = check_handle(graph, bone) {
+                        errors.push(ValidationError::InvalidBone {
+                            node: handle,
+                            name: node.name_owned(),
+                            surface: index,
+                            bone,
+                            problem,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn validate_animations(scene: &Scene, errors: &mut Vec<ValidationError>) {
+    for (animation_handle, animation) in scene.animations.pair_iter() {
+        for (index, track) in animation.get_tracks().iter().enumerate() {
+            if let Some(problem) = check_handle(&scene.graph, track.get_node()) {
+                errors.push(ValidationError::InvalidTrackTarget {
+                    animation: animation_handle,
+                    track: index,
+                    node: track.get_node(),
+                    problem,
+                });
+            }
+        }
+    }
+}
+
+fn validate_physics_binder(scene: &Scene, errors: &mut Vec<ValidationError>) {
+    for (&node, &body) in scene.physics_binder.forward_map().iter() {
+        if let Some(problem) = check_handle(&scene.graph, node) {
+            errors.push(ValidationError::InvalidBinderNode {
+                node,
+                body,
+                problem,
+            });
+        }
+        if !scene.physics.bodies.contains(&body) {
+            errors.push(ValidationError::InvalidBinderBody { node, body });
+        }
+        if scene.physics_binder.node_of(body) != Some(node) {
+            errors.push(ValidationError::BinderMismatch { node, body });
+        }
+    }
+
+    for (&body, &node) in scene.physics_binder.backward_map().iter() {
+        if scene.physics_binder.body_of(node) != Some(&body) {
+            errors.push(ValidationError::BinderMismatch { node, body });
+        }
+    }
+}
+
+pub(in crate) fn validate(scene: &Scene) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    validate_hierarchy(&scene.graph, &mut errors);
+    validate_nodes(&scene.graph, &mut errors);
+    validate_animations(scene, &mut errors);
+    validate_physics_binder(scene, &mut errors);
+    errors
+}
+
+fn node_kind(node: &Node) -> &'static str {
+    match node {
+        Node::Base(_) => "Base",
+        Node::Light(Light::Directional(_)) => "DirectionalLight",
+        Node::Light(Light::Spot(_)) => "SpotLight",
+        Node::Light(Light::Point(_)) => "PointLight",
+        Node::Camera(_) => "Camera",
+        Node::Mesh(_) => "Mesh",
+        Node::Sprite(_) => "Sprite",
+        Node::ParticleSystem(_) => "ParticleSystem",
+        Node::Terrain(_) => "Terrain",
+        Node::Decal(_) => "Decal",
+        Node::Water(_) => "Water",
+        Node::EnvironmentProbe(_) => "EnvironmentProbe",
+        Node::WorldText(_) => "WorldText",
+        Node::Impostor(_) => "Impostor",
+    }
+}
+
+fn dump_node(
+    graph: &Graph,
+    handle: Handle<Node>,
+    depth: usize,
+    visited: &mut FxHashSet<Handle<Node>>,
+    out: &mut String,
+) {
+    let indent = "  ".repeat(depth);
+
+    let node = match graph.try_get(handle) {
+        Some(node) => node,
+        None => {
+            let _ = writeln!(out, "{}<invalid handle {}>", indent, handle);
+            return;
+        }
+    };
+
+    if !visited.insert(handle) {
+        let _ = writeln!(out, "{}<cycle at {} ({})>", indent, handle, node.name());
+        return;
+    }
+
+    let transform = node.local_transform();
+    let position = **transform.position();
+    let (roll, pitch, yaw) = transform.rotation().euler_angles();
+    let scale = **transform.scale();
+    let _ = writeln!(
+        out,
+        "{}{} [{}] {} pos: ({:.3}, {:.3}, {:.3}) rot: ({:.1}, {:.1}, {:.1}) scale: ({:.3}, {:.3}, {:.3})",
+        indent,
+        node.name(),
+        node_kind(node),
+        handle,
+        position.x,
+        position.y,
+        position.z,
+        roll.to_degrees(),
+        pitch.to_degrees(),
+        yaw.to_degrees(),
+        scale.x,
+        scale.y,
+        scale.z
+    );
+
+    for &child in node.children() {
+        dump_node(graph, child, depth + 1, visited, out);
+    }
+}
+
+pub(in crate) fn dump_hierarchy(graph: &Graph) -> String {
+    let mut out = String::new();
+    let mut visited = FxHashSet::default();
+
+    dump_node(graph, graph.get_root(), 0, &mut visited, &mut out);
+
+    // Corrupted graph may have nodes that are not reachable from the root, list them too.
+    let unreachable = graph
+        .pair_iter()
+        .filter(|(handle, _)| !visited.contains(handle))
+        .map(|(handle, _)| handle)
+        .collect::<Vec<_>>();
+    if !unreachable.is_empty() {
+        let _ = writeln!(out, "Unreachable nodes:");
+        for handle in unreachable {
+            if !visited.contains(&handle) {
+                dump_node(graph, handle, 1, &mut visited, &mut out);
+            }
+        }
+    }
+
+    out
+}
+
+/// Periodic validation of a scene, findings are logged as warnings only once.
+#[derive(Debug, Default)]
+pub(in crate) struct PeriodicValidation {
+    pub interval: Option<f32>,
+    timer: f32,
+    reported: FxHashSet<ValidationError>,
+}
+
+impl PeriodicValidation {
+    pub fn update(&mut self, scene: &Scene, dt: f32) {
+        let interval = match self.interval {
+            Some(interval) => interval,
+            None => return,
+        };
+
+        self.timer -= dt;
+        if self.timer > 0.0 {
+            return;
+        }
+        self.timer = interval;
+
+        for error in validate(scene) {
+            if !self.reported.contains(&error) {
+                log_warn!("Scene validation: {}", error);
+                self.reported.insert(error);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::{Animation, Track},
+        core::{algebra::Vector3, pool::Handle},
+        physics3d::RigidBodyHandle,
+        scene::{
+            base::BaseBuilder,
+            mesh::{surface::Surface, MeshBuilder},
+            node::Node,
+            validation::{HandleProblem, ValidationError},
+            Scene,
+        },
+    };
+
+    fn make_scene() -> (Scene, Handle<Node>, Handle<Node>) {
+        let mut scene = Scene::new();
+        let child = BaseBuilder::new()
+            .with_name("Child")
+            .build(&mut scene.graph);
+        let parent = BaseBuilder::new()
+            .with_name("Parent")
+            .with_children(&[child])
+            .build(&mut scene.graph);
+        (scene, parent, child)
+    }
+
+    #[test]
+    fn test_valid_scene() {
+        let (scene, _, _) = make_scene();
+        assert!(scene.validate().is_empty());
+    }
+
+    #[test]
+    fn test_stale_parent() {
+        let (mut scene, parent, child) = make_scene();
+        let stale = Handle::new(parent.index(), parent.generation() + 1);
+        scene.graph[child].parent = stale;
+
+        let errors = scene.validate();
+        assert!(errors.contains(&ValidationError::InvalidParent {
+            node: child,
+            name: "Child".to_owned(),
+            parent: stale,
+            problem: HandleProblem::StaleGeneration {
+                handle: parent.generation() + 1,
+                actual: parent.generation(),
+            },
+        }));
+    }
+
+    #[test]
+    fn test_freed_child() {
+        let (mut scene, parent, child) = make_scene();
+        // Free the child, but leave it in the children list of its parent.
+        scene.graph[parent].children.clear();
+        scene.graph.remove_node(child);
+        scene.graph[parent].children.push(child);
+
+        let errors = scene.validate();
+        assert!(errors.contains(&ValidationError::InvalidChild {
+            node: parent,
+            name: "Parent".to_owned(),
+            child,
+            problem: HandleProblem::Vacant,
+        }));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let (mut scene, parent, child) = make_scene();
+        let root = scene.graph.get_root();
+        scene.graph[root].children.retain(|&c| c != parent);
+        scene.graph[parent].parent = child;
+        scene.graph[child].children.push(parent);
+
+        let errors = scene.validate();
+        assert_eq!(
+            errors
+                .iter()
+                .filter(|e| matches!(e, ValidationError::Cycle { .. }))
+                .count(),
+            1
+        );
+        // Dump must not hang on corrupted graph.
+        assert!(scene.dump_hierarchy().contains("Unreachable"));
+    }
+
+    #[test]
+    fn test_nan_transform() {
+        let (mut scene, _, child) = make_scene();
+        scene.graph[child]
+            .local_transform_mut()
+            .set_position(Vector3::new(f32::NAN, 0.0, 0.0));
+
+        assert!(scene
+            .validate()
+            .contains(&ValidationError::NonFiniteTransform {
+                node: child,
+                name: "Child".to_owned()
+            }));
+    }
+
+    #[test]
+    fn test_freed_track_target() {
+        let (mut scene, _, child) = make_scene();
+        let mut animation = Animation::default();
+        let mut track = Track::new();
+        track.set_node(child);
+        animation.add_track(track);
+        let animation = scene.animations.add(animation);
+        scene.graph.remove_node(child);
+
+        assert!(scene
+            .validate()
+            .contains(&ValidationError::InvalidTrackTarget {
+                animation,
+                track: 0,
+                node: child,
+                problem: HandleProblem::Vacant,
+            }));
+    }
+
+    #[test]
+    fn test_missing_surface_data() {
+        let mut scene = Scene::new();
+        let mesh = MeshBuilder::new(BaseBuilder::new().with_name("Mesh"))
+            .with_surfaces(vec![Surface::default()])
+            .build(&mut scene.graph);
+
+        assert!(scene
+            .validate()
+            .contains(&ValidationError::MissingSurfaceData {
+                node: mesh,
+                name: "Mesh".to_owned(),
+                surface: 0
+            }));
+    }
+
+    #[test]
+    fn test_binder_to_missing_body() {
+        let (mut scene, _, child) = make_scene();
+        let body = RigidBodyHandle::default();
+        scene.physics_binder.bind(child, body);
+
+        assert!(scene
+            .validate()
+            .contains(&ValidationError::InvalidBinderBody { node: child, body }));
+    }
+
+    #[test]
+    fn test_dump_hierarchy() {
+        let (scene, _, _) = make_scene();
+        let dump = scene.dump_hierarchy();
+        let lines = dump.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("  Parent [Base]"));
+        assert!(lines[2].starts_with("    Child [Base]"));
+    }
+}
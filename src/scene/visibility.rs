@@ -14,10 +14,11 @@ use fxhash::FxHashMap;
 /// # Notes
 ///
 /// Visibility cache stores very coarse information about object visibility, it does not include any kind of occlusion
-/// tests of whatsoever. It just a simple frustum test + level-of-detail (LOD) system.
+/// tests of whatsoever. It just a simple frustum test + level-of-detail (LOD) system + render mask test.
 ///
 /// LODs have priority over other visibility options, if a level is not active, then its every object will be hidden,
-/// not matter if the actual visibility state is `visible`.
+/// not matter if the actual visibility state is `visible`. The same applies to detailed objects of
+/// [impostors](super::impostor::Impostor) that are farther than switch distance.
 ///
 /// # Performance
 ///
@@ -41,13 +42,15 @@ impl VisibilityCache {
     }
 
     /// Updates visibility cache - checks visibility for each node in given graph, also performs
-    /// frustum culling if frustum set is specified.
+    /// frustum culling if frustum set is specified. Nodes, whose render mask does not intersect
+    /// with given visibility mask, will be marked as invisible.
     pub fn update(
         &mut self,
         graph: &Graph,
         observer_position: Vector3<f32>,
         z_near: f32,
         z_far: f32,
+        visibility_mask: u32,
         frustums: Option<&[&Frustum]>,
     ) {
         self.map.clear();
@@ -71,12 +74,27 @@ impl VisibilityCache {
             }
         }
 
+        // Impostors hide their detailed objects when they're far enough from the observer.
+        for node in graph.linear_iter() {
+            if let Node::Impostor(impostor) = node {
+                let distance = observer_position.metric_distance(&impostor.global_position());
+                if !impostor.is_detail_visible(distance) {
+                    for &child in impostor.children() {
+                        for descendant in graph.traverse_handle_iter(child) {
+                            self.map.insert(descendant, false);
+                        }
+                    }
+                }
+            }
+        }
+
         // Fill rest of data from global visibility flag of nodes and check frustums (if any).
         for (handle, node) in graph.pair_iter() {
             // We need to fill only unfilled entries, none of visibility flags of a node can
             // make it visible again if lod group hid it.
             self.map.entry(handle).or_insert_with(|| {
-                let mut visibility = node.global_visibility();
+                let mut visibility =
+                    node.global_visibility() && node.is_visible_by_mask(visibility_mask);
                 if visibility {
                     // If a node globally visible, check it with each frustum (if any).
                     if let Some(frustums) = frustums {
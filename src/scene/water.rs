@@ -0,0 +1,436 @@
+//! Water is a flat surface with reflections and refraction, that could be used to create lakes,
+//! rivers, pools, etc.
+//!
+//! For more info see [`Water`]
+
+use crate::{
+    core::{
+        algebra::{Matrix4, Vector2, Vector3},
+        color::Color,
+        inspect::{Inspect, PropertyInfo},
+        math::aabb::AxisAlignedBoundingBox,
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    resource::texture::Texture,
+    scene::{
+        base::{Base, BaseBuilder},
+        graph::Graph,
+        node::Node,
+    },
+};
+use std::ops::{Deref, DerefMut};
+
+/// Water is a flat surface with reflections and refraction, that could be used to create lakes,
+/// rivers, pools, etc. The surface lies in local oXZ plane of the node and its normal is local
+/// Y axis, so a node with default transform is a horizontal water surface.
+///
+/// # Rendering
+///
+/// Water is rendered by a special pass of the renderer after lighting, the final color of the
+/// surface is a blend of two colors:
+///
+/// - Reflection - the scene is rendered into a separate render target by a camera that is
+/// mirrored relative to the water plane, everything below the surface is clipped. Reflection
+/// ignores lighting, only diffuse colors of objects are used. Reflection rendering respects
+/// visibility mask of the camera and [reflection mask](Water::set_reflection_mask) of the water.
+/// Quality settings of the renderer control resolution of reflections, reflections can also be
+/// disabled entirely.
+/// - Refraction - a lit scene behind the surface, it becomes more and more tinted with water
+/// color with the depth of water, see [`Water::set_depth_density`].
+///
+/// These colors are blended using Fresnel term, so the surface is more reflective at grazing
+/// angles. Both colors are distorted by a normal map that scrolls with given speed, this
+/// creates an illusion of waves. Surface itself is flat.
+///
+/// # Limitations
+///
+/// Water surfaces don't reflect each other and they're not reflected in other water surfaces.
+/// Water surface is not rendered into shadow maps.
+///
+/// # Example
+///
+/// ```
+/// use rg3d::{
+///     core::{algebra::Vector2, pool::Handle},
+///     engine::resource_manager::ResourceManager,
+///     scene::{base::BaseBuilder, graph::Graph, node::Node, water::WaterBuilder},
+/// };
+///
+/// fn create_lake(resource_manager: ResourceManager, graph: &mut Graph) -> Handle<Node> {
+///     WaterBuilder::new(BaseBuilder::new())
+///         .with_size(Vector2::new(50.0, 30.0))
+///         .with_normal_map(resource_manager.request_texture("water_normal.png", None))
+///         .with_wave_speed(Vector2::new(0.03, 0.01))
+///         .build(graph)
+/// }
+/// ```
+#[derive(Debug, Visit, Inspect)]
+pub struct Water {
+    base: Base,
+    size: Vector2<f32>,
+    normal_map: Option<Texture>,
+    #[inspect(min_value = 0.0, step = 0.01)]
+    wave_scale: f32,
+    wave_speed: Vector2<f32>,
+    #[inspect(min_value = 0.0, step = 0.01)]
+    distortion: f32,
+    color: Color,
+    #[inspect(min_value = 0.0, step = 0.01)]
+    depth_density: f32,
+    #[inspect(min_value = 0.0, step = 0.1)]
+    fresnel_power: f32,
+    reflections_enabled: bool,
+    reflection_mask: u32,
+    #[visit(skip)]
+    #[inspect(skip)]
+    time: f32,
+}
+
+impl Default for Water {
+    fn default() -> Self {
+        WaterBuilder::new(BaseBuilder::new()).build_water()
+    }
+}
+
+impl Deref for Water {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for Water {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl Water {
+    /// Creates a raw copy of Water node.
+    pub fn raw_copy(&self) -> Self {
+        Self {
+            base: self.base.raw_copy(),
+            size: self.size,
+            normal_map: self.normal_map.clone(),
+            wave_scale: self.wave_scale,
+            wave_speed: self.wave_speed,
+            distortion: self.distortion,
+            color: self.color,
+            depth_density: self.depth_density,
+            fresnel_power: self.fresnel_power,
+            reflections_enabled: self.reflections_enabled,
+            reflection_mask: self.reflection_mask,
+            time: self.time,
+        }
+    }
+
+    /// Sets new size of the surface in local coordinates, `x` is the size along local X axis
+    /// and `y` is the size along local Z axis.
+    pub fn set_size(&mut self, size: Vector2<f32>) {
+        self.size = Vector2::new(size.x.max(0.0), size.y.max(0.0));
+    }
+
+    /// Returns current size of the surface.
+    pub fn size(&self) -> Vector2<f32> {
+        self.size
+    }
+
+    /// Sets new normal map that is used to create waves.
+    pub fn set_normal_map(&mut self, normal_map: Option<Texture>) -> Option<Texture> {
+        std::mem::replace(&mut self.normal_map, normal_map)
+    }
+
+    /// Returns current normal map.
+    pub fn normal_map(&self) -> Option<&Texture> {
+        self.normal_map.as_ref()
+    }
+
+    /// Returns current normal map.
+    pub fn normal_map_value(&self) -> Option<Texture> {
+        self.normal_map.clone()
+    }
+
+    /// Sets new scale of waves - how many times the normal map is repeated per one unit of
+    /// world space. Smaller values mean bigger waves.
+    pub fn set_wave_scale(&mut self, wave_scale: f32) {
+        self.wave_scale = wave_scale.max(0.0);
+    }
+
+    /// Returns current scale of waves.
+    pub fn wave_scale(&self) -> f32 {
+        self.wave_scale
+    }
+
+    /// Sets new speed of waves - how fast the normal map scrolls, in texture coordinates per
+    /// second.
+    pub fn set_wave_speed(&mut self, wave_speed: Vector2<f32>) {
+        self.wave_speed = wave_speed;
+    }
+
+    /// Returns current speed of waves.
+    pub fn wave_speed(&self) -> Vector2<f32> {
+        self.wave_speed
+    }
+
+    /// Sets how much waves distort reflection and refraction. Default value is 0.03.
+    pub fn set_distortion(&mut self, distortion: f32) {
+        self.distortion = distortion.max(0.0);
+    }
+
+    /// Returns current distortion.
+    pub fn distortion(&self) -> f32 {
+        self.distortion
+    }
+
+    /// Sets new color of the water, deep water has this color.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns current color of the water.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets how fast the water becomes opaque with depth. Refraction is tinted by
+    /// `1.0 - exp(-depth * depth_density)` amount of water color, where `depth` is a distance
+    /// between the surface and the scene behind it.
+    pub fn set_depth_density(&mut self, depth_density: f32) {
+        self.depth_density = depth_density.max(0.0);
+    }
+
+    /// Returns current depth density.
+    pub fn depth_density(&self) -> f32 {
+        self.depth_density
+    }
+
+    /// Sets power of Fresnel term, bigger values make the surface less reflective when
+    /// looking at it from above.
+    pub fn set_fresnel_power(&mut self, fresnel_power: f32) {
+        self.fresnel_power = fresnel_power.max(0.0);
+    }
+
+    /// Returns current power of Fresnel term.
+    pub fn fresnel_power(&self) -> f32 {
+        self.fresnel_power
+    }
+
+    /// Enables or disables reflections of this surface. Reflections are quite expensive,
+    /// because the scene has to be rendered one more time for every water surface.
+    pub fn set_reflections_enabled(&mut self, enabled: bool) {
+        self.reflections_enabled = enabled;
+    }
+
+    /// Returns `true` if reflections are enabled, `false` - otherwise.
+    pub fn reflections_enabled(&self) -> bool {
+        self.reflections_enabled
+    }
+
+    /// Sets new reflection mask. Only the nodes whose
+    /// [render mask](crate::scene::base::Base::set_render_mask) has at least one common bit
+    /// with the reflection mask (and the visibility mask of a camera) will be reflected.
+    pub fn set_reflection_mask(&mut self, reflection_mask: u32) {
+        self.reflection_mask = reflection_mask;
+    }
+
+    /// Returns current reflection mask.
+    pub fn reflection_mask(&self) -> u32 {
+        self.reflection_mask
+    }
+
+    /// Returns amount of seconds the waves were animated for.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Returns a transform of a unit quad in oXZ plane to the surface in world space.
+    pub fn surface_transform(&self) -> Matrix4<f32> {
+        self.global_transform()
+            * Matrix4::new_nonuniform_scaling(&Vector3::new(self.size.x, 1.0, self.size.y))
+    }
+
+    /// Returns current **local-space** bounding box.
+    #[inline]
+    pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        let half_size = Vector3::new(self.size.x * 0.5, 0.0, self.size.y * 0.5);
+        AxisAlignedBoundingBox::from_min_max(-half_size, half_size)
+    }
+
+    /// Returns current **world-space** bounding box.
+    pub fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        self.local_bounding_box()
+            .transform(&self.global_transform())
+    }
+
+    pub(in crate) fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+}
+
+/// Allows you to create a Water in a declarative manner.
+pub struct WaterBuilder {
+    base_builder: BaseBuilder,
+    size: Vector2<f32>,
+    normal_map: Option<Texture>,
+    wave_scale: f32,
+    wave_speed: Vector2<f32>,
+    distortion: f32,
+    color: Color,
+    depth_density: f32,
+    fresnel_power: f32,
+    reflections_enabled: bool,
+    reflection_mask: u32,
+}
+
+impl WaterBuilder {
+    /// Creates a new instance of the builder.
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            size: Vector2::new(10.0, 10.0),
+            normal_map: None,
+            wave_scale: 0.1,
+            wave_speed: Vector2::new(0.02, 0.01),
+            distortion: 0.03,
+            color: Color::opaque(20, 70, 90),
+            depth_density: 0.25,
+            fresnel_power: 5.0,
+            reflections_enabled: true,
+            reflection_mask: u32::MAX,
+        }
+    }
+
+    /// Sets desired size of the surface.
+    pub fn with_size(mut self, size: Vector2<f32>) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Sets desired normal map.
+    pub fn with_normal_map(mut self, normal_map: Texture) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    /// Sets desired scale of waves.
+    pub fn with_wave_scale(mut self, wave_scale: f32) -> Self {
+        self.wave_scale = wave_scale;
+        self
+    }
+
+    /// Sets desired speed of waves.
+    pub fn with_wave_speed(mut self, wave_speed: Vector2<f32>) -> Self {
+        self.wave_speed = wave_speed;
+        self
+    }
+
+    /// Sets desired distortion.
+    pub fn with_distortion(mut self, distortion: f32) -> Self {
+        self.distortion = distortion;
+        self
+    }
+
+    /// Sets desired water color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets desired depth density.
+    pub fn with_depth_density(mut self, depth_density: f32) -> Self {
+        self.depth_density = depth_density;
+        self
+    }
+
+    /// Sets desired power of Fresnel term.
+    pub fn with_fresnel_power(mut self, fresnel_power: f32) -> Self {
+        self.fresnel_power = fresnel_power;
+        self
+    }
+
+    /// Enables or disables reflections.
+    pub fn with_reflections_enabled(mut self, enabled: bool) -> Self {
+        self.reflections_enabled = enabled;
+        self
+    }
+
+    /// Sets desired reflection mask.
+    pub fn with_reflection_mask(mut self, reflection_mask: u32) -> Self {
+        self.reflection_mask = reflection_mask;
+        self
+    }
+
+    fn build_water(self) -> Water {
+        Water {
+            base: self.base_builder.build_base(),
+            size: self.size,
+            normal_map: self.normal_map,
+            wave_scale: self.wave_scale,
+            wave_speed: self.wave_speed,
+            distortion: self.distortion,
+            color: self.color,
+            depth_density: self.depth_density,
+            fresnel_power: self.fresnel_power,
+            reflections_enabled: self.reflections_enabled,
+            reflection_mask: self.reflection_mask,
+            time: 0.0,
+        }
+    }
+
+    /// Creates new Water node.
+    pub fn build_node(self) -> Node {
+        Node::Water(self.build_water())
+    }
+
+    /// Creates new instance of Water node and puts it in the given graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Node> {
+        graph.add_node(self.build_node())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::{algebra::Vector2, visitor::prelude::*},
+        scene::{
+            base::BaseBuilder,
+            water::{Water, WaterBuilder},
+        },
+    };
+
+    #[test]
+    fn test_water_visit() {
+        let mut water = WaterBuilder::new(BaseBuilder::new())
+            .with_size(Vector2::new(20.0, 5.0))
+            .with_wave_scale(0.5)
+            .with_depth_density(1.5)
+            .with_reflections_enabled(false)
+            .with_reflection_mask(0b10)
+            .build_water();
+
+        let mut visitor = Visitor::new();
+        water.visit("Water", &mut visitor).unwrap();
+
+        let mut loaded = Water::default();
+        visitor.set_reading();
+        loaded.visit("Water", &mut visitor).unwrap();
+
+        assert_eq!(loaded.size(), Vector2::new(20.0, 5.0));
+        assert_eq!(loaded.wave_scale(), 0.5);
+        assert_eq!(loaded.depth_density(), 1.5);
+        assert!(!loaded.reflections_enabled());
+        assert_eq!(loaded.reflection_mask(), 0b10);
+    }
+
+    #[test]
+    fn test_water_bounds() {
+        let mut water = Water::default();
+        water.set_size(Vector2::new(4.0, 2.0));
+        let bounds = water.local_bounding_box();
+        assert_eq!(bounds.max.x - bounds.min.x, 4.0);
+        assert_eq!(bounds.max.z - bounds.min.z, 2.0);
+        assert_eq!(bounds.max.y - bounds.min.y, 0.0);
+    }
+}
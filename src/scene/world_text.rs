@@ -0,0 +1,917 @@
+//! World text is a text that is placed in the 3D scene, for example damage numbers, names of
+//! characters or signposts.
+//!
+//! For more info see [`WorldText`] and [`FloatingTexts`].
+
+use crate::{
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        inspect::{Inspect, PropertyInfo},
+        math::{aabb::AxisAlignedBoundingBox, Rect},
+        pool::Handle,
+        sparse::AtomicIndex,
+        visitor::prelude::*,
+    },
+    gui::{
+        ttf::{Font, SharedFont},
+        DEFAULT_FONT,
+    },
+    renderer::{cache::CacheEntry, framework::geometry_buffer::GeometryBuffer},
+    scene::{
+        base::{Base, BaseBuilder},
+        graph::Graph,
+        node::Node,
+        transform::TransformBuilder,
+    },
+};
+use fxhash::{FxHashMap, FxHasher};
+use std::{
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut, Range},
+    sync::Arc,
+};
+
+/// Defines how world text is oriented in the scene.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Visit, Inspect)]
+pub enum WorldTextOrientation {
+    /// Text always faces the camera, rotation and scale of the node are ignored. This is default
+    /// orientation.
+    Billboard,
+    /// Text lies in XY plane of the node (lines go along X axis, Y axis is up) and it is
+    /// transformed as any other node. Text is visible from both sides of the plane.
+    Fixed,
+}
+
+impl Default for WorldTextOrientation {
+    fn default() -> Self {
+        Self::Billboard
+    }
+}
+
+/// Horizontal alignment of lines of world text relative to the position of the node.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Visit, Inspect)]
+pub enum HorizontalTextAlignment {
+    /// Lines start at the position of the node.
+    Left,
+    /// Lines are centered at the position of the node.
+    Center,
+    /// Lines end at the position of the node.
+    Right,
+}
+
+impl Default for HorizontalTextAlignment {
+    fn default() -> Self {
+        Self::Center
+    }
+}
+
+/// Vertical alignment of world text relative to the position of the node.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Visit, Inspect)]
+pub enum VerticalTextAlignment {
+    /// Top of the first line is at the position of the node.
+    Top,
+    /// Text is centered at the position of the node.
+    Center,
+    /// Bottom of the last line is at the position of the node.
+    Bottom,
+}
+
+impl Default for VerticalTextAlignment {
+    fn default() -> Self {
+        Self::Center
+    }
+}
+
+/// A vertex of world text geometry, the position is in the plane of the text.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct WorldTextVertex {
+    /// Position in the plane of the text in world units.
+    pub position: Vector2<f32>,
+    /// Texture coordinates in the atlas page of the font.
+    pub tex_coord: Vector2<f32>,
+}
+
+/// Laid out glyphs of world text, every glyph is a quad of four vertices. Glyphs are grouped by
+/// atlas pages of the font.
+#[derive(Clone, Debug, Default)]
+pub struct WorldTextGeometry {
+    /// Vertices of glyph quads.
+    pub vertices: Vec<WorldTextVertex>,
+    /// Ranges of glyphs for each atlas page of the font.
+    pub pages: Vec<(usize, Range<usize>)>,
+    /// Bounds of the text in the plane of the text, `x` and `y` is the bottom left corner.
+    pub bounds: Rect<f32>,
+    /// Hash of the content, it is used by the renderer to check if the geometry has changed.
+    pub hash: u64,
+}
+
+impl WorldTextGeometry {
+    /// Returns amount of glyph quads.
+    pub fn glyph_count(&self) -> usize {
+        self.vertices.len() / 4
+    }
+}
+
+/// World text is a node that shows a string in the 3D scene. It uses fonts of the user interface,
+/// glyphs are taken from the atlas of a font and drawn as quads in the transparent pass, after
+/// particle systems and sprites.
+///
+/// # Size
+///
+/// Size of the font is defined in world units, it is the height of a line of the text. Glyphs
+/// are rasterized at [resolution](WorldText::set_resolution) which is defined in pixels, use
+/// larger resolution for big texts that are seen from a close distance.
+///
+/// # Depth
+///
+/// Text is depth tested against the scene, so it is hidden behind walls. Use
+/// [`WorldText::set_always_on_top`] for labels that must be always visible.
+///
+/// # Performance
+///
+/// Glyphs are laid out only when the text or its layout parameters are changed, color and outline
+/// are applied at draw time, so changing them is free. GPU buffer of the text is reused while new
+/// text has no more glyphs than the largest text that was shown by the node. Texts are culled by
+/// their bounds.
+///
+/// # Serialization
+///
+/// Font is not saved, texts of a loaded scene use default font of the user interface. Set the
+/// font again after loading if you need a custom one.
+///
+/// # Example
+///
+/// ```
+/// use rg3d::{
+///     core::{color::Color, pool::Handle},
+///     scene::{base::BaseBuilder, graph::Graph, node::Node, world_text::WorldTextBuilder},
+/// };
+///
+/// fn create_signpost(graph: &mut Graph) -> Handle<Node> {
+///     WorldTextBuilder::new(BaseBuilder::new())
+///         .with_text("Tavern\n<-")
+///         .with_font_size(0.5)
+///         .with_color(Color::opaque(255, 220, 120))
+///         .with_outline(Color::BLACK, 0.02)
+///         .build(graph)
+/// }
+/// ```
+#[derive(Debug, Visit, Inspect)]
+pub struct WorldText {
+    base: Base,
+    text: String,
+    #[inspect(skip)]
+    #[visit(skip)]
+    font: SharedFont,
+    #[inspect(min_value = 0.0, step = 0.1)]
+    font_size: f32,
+    #[inspect(min_value = 1.0, step = 1.0)]
+    resolution: f32,
+    color: Color,
+    outline_color: Color,
+    #[inspect(min_value = 0.0, step = 0.01)]
+    outline_thickness: f32,
+    orientation: WorldTextOrientation,
+    horizontal_alignment: HorizontalTextAlignment,
+    vertical_alignment: VerticalTextAlignment,
+    always_on_top: bool,
+    #[inspect(skip)]
+    #[visit(skip)]
+    geometry: WorldTextGeometry,
+    #[inspect(skip)]
+    #[visit(skip)]
+    needs_layout: bool,
+    #[inspect(skip)]
+    #[visit(skip)]
+    pub(in crate) cache_entry: AtomicIndex<CacheEntry<GeometryBuffer>>,
+}
+
+impl Deref for WorldText {
+    type Target = Base;
+
+    fn deref(&self) -> &Self::Target {
+        &self.base
+    }
+}
+
+impl DerefMut for WorldText {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.base
+    }
+}
+
+impl Default for WorldText {
+    fn default() -> Self {
+        WorldTextBuilder::new(BaseBuilder::new()).build_world_text()
+    }
+}
+
+impl WorldText {
+    /// Creates a raw copy of the text node.
+    pub fn raw_copy(&self) -> Self {
+        Self {
+            base: self.base.raw_copy(),
+            text: self.text.clone(),
+            font: self.font.clone(),
+            font_size: self.font_size,
+            resolution: self.resolution,
+            color: self.color,
+            outline_color: self.outline_color,
+            outline_thickness: self.outline_thickness,
+            orientation: self.orientation,
+            horizontal_alignment: self.horizontal_alignment,
+            vertical_alignment: self.vertical_alignment,
+            always_on_top: self.always_on_top,
+            geometry: self.geometry.clone(),
+            needs_layout: self.needs_layout,
+            // Copy must have its own GPU buffer.
+            cache_entry: AtomicIndex::unassigned(),
+        }
+    }
+
+    /// Sets new text, it could contain multiple lines separated by `\n`.
+    pub fn set_text<S: AsRef<str>>(&mut self, text: S) {
+        if self.text != text.as_ref() {
+            self.text = text.as_ref().to_owned();
+            self.needs_layout = true;
+        }
+    }
+
+    /// Returns current text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Sets new font, default is default font of the user interface.
+    pub fn set_font(&mut self, font: SharedFont) {
+        self.font = font;
+        self.needs_layout = true;
+    }
+
+    /// Returns current font.
+    pub fn font(&self) -> SharedFont {
+        self.font.clone()
+    }
+
+    /// Sets height of a line of the text in world units. Default is 0.25.
+    pub fn set_font_size(&mut self, font_size: f32) {
+        self.font_size = font_size.max(0.0);
+        self.needs_layout = true;
+    }
+
+    /// Returns current font size in world units.
+    pub fn font_size(&self) -> f32 {
+        self.font_size
+    }
+
+    /// Sets size (in pixels) at which glyphs are rasterized. Default is 32.
+    pub fn set_resolution(&mut self, resolution: f32) {
+        self.resolution = resolution.max(1.0);
+        self.needs_layout = true;
+    }
+
+    /// Returns current resolution of glyphs.
+    pub fn resolution(&self) -> f32 {
+        self.resolution
+    }
+
+    /// Sets color of the text. Default is white.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns current color of the text.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets color and thickness (in world units) of the outline, zero thickness disables the
+    /// outline. Default is no outline.
+    pub fn set_outline(&mut self, color: Color, thickness: f32) {
+        self.outline_color = color;
+        self.outline_thickness = thickness.max(0.0);
+    }
+
+    /// Returns current color of the outline.
+    pub fn outline_color(&self) -> Color {
+        self.outline_color
+    }
+
+    /// Returns current thickness of the outline in world units.
+    pub fn outline_thickness(&self) -> f32 {
+        self.outline_thickness
+    }
+
+    /// Sets orientation of the text, see [`WorldTextOrientation`] docs for more info.
+    pub fn set_orientation(&mut self, orientation: WorldTextOrientation) {
+        self.orientation = orientation;
+    }
+
+    /// Returns current orientation of the text.
+    pub fn orientation(&self) -> WorldTextOrientation {
+        self.orientation
+    }
+
+    /// Sets horizontal alignment of the lines. Default is [`HorizontalTextAlignment::Center`].
+    pub fn set_horizontal_alignment(&mut self, alignment: HorizontalTextAlignment) {
+        self.horizontal_alignment = alignment;
+        self.needs_layout = true;
+    }
+
+    /// Returns current horizontal alignment.
+    pub fn horizontal_alignment(&self) -> HorizontalTextAlignment {
+        self.horizontal_alignment
+    }
+
+    /// Sets vertical alignment of the text. Default is [`VerticalTextAlignment::Center`].
+    pub fn set_vertical_alignment(&mut self, alignment: VerticalTextAlignment) {
+        self.vertical_alignment = alignment;
+        self.needs_layout = true;
+    }
+
+    /// Returns current vertical alignment.
+    pub fn vertical_alignment(&self) -> VerticalTextAlignment {
+        self.vertical_alignment
+    }
+
+    /// Sets whether the text should be drawn on top of everything, ignoring depth of the scene.
+    /// Default is false.
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.always_on_top = always_on_top;
+    }
+
+    /// Returns true if the text is drawn on top of everything.
+    pub fn is_always_on_top(&self) -> bool {
+        self.always_on_top
+    }
+
+    /// Returns laid out glyphs of the text. Layout is updated together with the graph, so the
+    /// geometry could be outdated right after the text was changed.
+    pub fn geometry(&self) -> &WorldTextGeometry {
+        &self.geometry
+    }
+
+    /// Returns current **local-space** bounding box. Bounding box of a billboard text encloses
+    /// the text in any orientation.
+    pub fn local_bounding_box(&self) -> AxisAlignedBoundingBox {
+        let bounds = self.geometry.bounds;
+        match self.orientation {
+            WorldTextOrientation::Billboard => {
+                let radius = [
+                    Vector2::new(bounds.x(), bounds.y()),
+                    Vector2::new(bounds.x() + bounds.w(), bounds.y() + bounds.h()),
+                ]
+                .iter()
+                .map(|p| p.abs().norm())
+                .fold(0.0f32, f32::max);
+                AxisAlignedBoundingBox::from_radius(radius)
+            }
+            WorldTextOrientation::Fixed => AxisAlignedBoundingBox::from_min_max(
+                Vector3::new(bounds.x(), bounds.y(), 0.0),
+                Vector3::new(bounds.x() + bounds.w(), bounds.y() + bounds.h(), 0.0),
+            ),
+        }
+    }
+
+    /// Returns current **world-space** bounding box.
+    pub fn world_bounding_box(&self) -> AxisAlignedBoundingBox {
+        match self.orientation {
+            WorldTextOrientation::Billboard => {
+                // Billboard ignores rotation and scale of the node.
+                let mut aabb = self.local_bounding_box();
+                aabb.offset(self.global_position());
+                aabb
+            }
+            WorldTextOrientation::Fixed => self
+                .local_bounding_box()
+                .transform(&self.global_transform()),
+        }
+    }
+
+    pub(in crate) fn update(&mut self) {
+        if self.needs_layout {
+            self.needs_layout = false;
+            self.geometry = layout(
+                &self.text,
+                &self.font,
+                self.font_size,
+                self.resolution,
+                self.horizontal_alignment,
+                self.vertical_alignment,
+            );
+        }
+    }
+}
+
+fn layout(
+    text: &str,
+    font: &SharedFont,
+    font_size: f32,
+    resolution: f32,
+    horizontal_alignment: HorizontalTextAlignment,
+    vertical_alignment: VerticalTextAlignment,
+) -> WorldTextGeometry {
+    let mut font_ref = font.0.lock().unwrap();
+
+    // Layout is done in pixels of the glyphs, then scaled to world units. Y axis is up.
+    let scale = font_size / resolution;
+    let ascender = font_ref.ascender(resolution);
+    let line_height = ascender - font_ref.descender(resolution);
+
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let block_height = line_height * lines.len() as f32;
+    let block_top = match vertical_alignment {
+        VerticalTextAlignment::Top => 0.0,
+        VerticalTextAlignment::Center => block_height * 0.5,
+        VerticalTextAlignment::Bottom => block_height,
+    };
+
+    let mut quads = Vec::new();
+    let mut min_x = f32::MAX;
+    let mut max_x = f32::MIN;
+    for (line_index, line) in lines.iter().enumerate() {
+        let chars = line.chars().map(|c| c as u32).collect::<Vec<_>>();
+
+        let kerning = |font: &Font, i: usize| {
+            chars
+                .get(i + 1)
+                .map_or(0.0, |next| font.kerning(chars[i], *next, resolution))
+        };
+
+        let mut width = 0.0;
+        for (i, &c) in chars.iter().enumerate() {
+            width += font_ref.glyph_advance(c, resolution) + kerning(&*font_ref, i);
+        }
+
+        let mut x = match horizontal_alignment {
+            HorizontalTextAlignment::Left => 0.0,
+            HorizontalTextAlignment::Center => -width * 0.5,
+            HorizontalTextAlignment::Right => -width,
+        };
+        min_x = min_x.min(x);
+        max_x = max_x.max(x + width);
+
+        let baseline = block_top - ascender - line_index as f32 * line_height;
+
+        for (i, &c) in chars.iter().enumerate() {
+            let glyph = font_ref.glyph(c, resolution);
+            if glyph.bitmap_width > 0 && glyph.bitmap_height > 0 {
+                let left = x + glyph.left;
+                let right = left + glyph.bitmap_width as f32;
+                let bottom = baseline + glyph.top;
+                let top = bottom + glyph.bitmap_height as f32;
+                // First texture coordinate is top left corner of the glyph.
+                let tc = glyph.tex_coords;
+                let vertex = |x: f32, y: f32, tex_coord: Vector2<f32>| WorldTextVertex {
+                    position: Vector2::new(x, y).scale(scale),
+                    tex_coord,
+                };
+                quads.push((
+                    glyph.page_index,
+                    [
+                        vertex(left, top, tc[0]),
+                        vertex(right, top, tc[1]),
+                        vertex(right, bottom, tc[2]),
+                        vertex(left, bottom, tc[3]),
+                    ],
+                ));
+            }
+            x += font_ref.glyph_advance(c, resolution) + kerning(&*font_ref, i);
+        }
+    }
+
+    // Group glyphs by pages to draw every page at once, sort is stable so order of glyphs is kept.
+    quads.sort_by_key(|(page, _)| *page);
+
+    let mut geometry = WorldTextGeometry::default();
+    for (i, (page, quad)) in quads.iter().enumerate() {
+        match geometry.pages.last_mut() {
+            Some((last_page, range)) if last_page == page => range.end = i + 1,
+            _ => geometry.pages.push((*page, i..i + 1)),
+        }
+        geometry.vertices.extend_from_slice(quad);
+    }
+
+    if min_x <= max_x {
+        geometry.bounds = Rect::new(
+            min_x * scale,
+            (block_top - block_height) * scale,
+            (max_x - min_x) * scale,
+            block_height * scale,
+        );
+    }
+
+    let mut hasher = FxHasher::default();
+    text.hash(&mut hasher);
+    (Arc::as_ptr(&font.0) as usize).hash(&mut hasher);
+    font_size.to_bits().hash(&mut hasher);
+    resolution.to_bits().hash(&mut hasher);
+    (horizontal_alignment as u32).hash(&mut hasher);
+    (vertical_alignment as u32).hash(&mut hasher);
+    geometry.hash = hasher.finish();
+
+    geometry
+}
+
+/// World text builder allows you to construct world text in declarative manner.
+pub struct WorldTextBuilder {
+    base_builder: BaseBuilder,
+    text: String,
+    font: SharedFont,
+    font_size: f32,
+    resolution: f32,
+    color: Color,
+    outline_color: Color,
+    outline_thickness: f32,
+    orientation: WorldTextOrientation,
+    horizontal_alignment: HorizontalTextAlignment,
+    vertical_alignment: VerticalTextAlignment,
+    always_on_top: bool,
+}
+
+impl WorldTextBuilder {
+    /// Creates new builder with default state (empty white centered billboard text).
+    pub fn new(base_builder: BaseBuilder) -> Self {
+        Self {
+            base_builder,
+            text: Default::default(),
+            font: DEFAULT_FONT.clone(),
+            font_size: 0.25,
+            resolution: 32.0,
+            color: Color::WHITE,
+            outline_color: Color::BLACK,
+            outline_thickness: 0.0,
+            orientation: Default::default(),
+            horizontal_alignment: Default::default(),
+            vertical_alignment: Default::default(),
+            always_on_top: false,
+        }
+    }
+
+    /// Sets desired text.
+    pub fn with_text<S: AsRef<str>>(mut self, text: S) -> Self {
+        self.text = text.as_ref().to_owned();
+        self
+    }
+
+    /// Sets desired font.
+    pub fn with_font(mut self, font: SharedFont) -> Self {
+        self.font = font;
+        self
+    }
+
+    /// Sets desired font size in world units.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = font_size.max(0.0);
+        self
+    }
+
+    /// Sets desired resolution of glyphs in pixels.
+    pub fn with_resolution(mut self, resolution: f32) -> Self {
+        self.resolution = resolution.max(1.0);
+        self
+    }
+
+    /// Sets desired color.
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Sets desired outline color and thickness in world units.
+    pub fn with_outline(mut self, color: Color, thickness: f32) -> Self {
+        self.outline_color = color;
+        self.outline_thickness = thickness.max(0.0);
+        self
+    }
+
+    /// Sets desired orientation.
+    pub fn with_orientation(mut self, orientation: WorldTextOrientation) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    /// Sets desired horizontal alignment.
+    pub fn with_horizontal_alignment(mut self, alignment: HorizontalTextAlignment) -> Self {
+        self.horizontal_alignment = alignment;
+        self
+    }
+
+    /// Sets desired vertical alignment.
+    pub fn with_vertical_alignment(mut self, alignment: VerticalTextAlignment) -> Self {
+        self.vertical_alignment = alignment;
+        self
+    }
+
+    /// Sets whether the text should be drawn on top of everything.
+    pub fn with_always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    fn build_world_text(self) -> WorldText {
+        WorldText {
+            base: self.base_builder.build_base(),
+            text: self.text,
+            font: self.font,
+            font_size: self.font_size,
+            resolution: self.resolution,
+            color: self.color,
+            outline_color: self.outline_color,
+            outline_thickness: self.outline_thickness,
+            orientation: self.orientation,
+            horizontal_alignment: self.horizontal_alignment,
+            vertical_alignment: self.vertical_alignment,
+            always_on_top: self.always_on_top,
+            geometry: Default::default(),
+            needs_layout: true,
+            cache_entry: AtomicIndex::unassigned(),
+        }
+    }
+
+    /// Creates new world text node.
+    pub fn build_node(self) -> Node {
+        Node::WorldText(self.build_world_text())
+    }
+
+    /// Creates new world text node and adds it to the graph.
+    pub fn build(self, graph: &mut Graph) -> Handle<Node> {
+        graph.add_node(self.build_node())
+    }
+}
+
+/// A set of parameters for [`crate::scene::Scene::spawn_floating_text`].
+#[derive(Clone, Debug)]
+pub struct FloatingTextParams {
+    /// Text to show.
+    pub text: String,
+
+    /// Initial position of the text in world coordinates. Default is zero.
+    pub position: Vector3<f32>,
+
+    /// Velocity (in world units per second) of the text. Default is 1.0 up.
+    pub velocity: Vector3<f32>,
+
+    /// Color of the text, it fades out to transparent at the end of lifetime. Default is white.
+    pub color: Color,
+
+    /// Color of the outline, default is black.
+    pub outline_color: Color,
+
+    /// Thickness of the outline in world units, default is 0.01.
+    pub outline_thickness: f32,
+
+    /// Height of a line of the text in world units. Default is 0.3.
+    pub font_size: f32,
+
+    /// Time (in seconds) after which the text is removed from the scene. Default is 1.0.
+    pub lifetime: f32,
+
+    /// Time (in seconds) before the end of lifetime when the text starts to fade out. Default
+    /// is 0.5.
+    pub fade_time: f32,
+
+    /// Whether the text should be drawn on top of everything. Default is true.
+    pub always_on_top: bool,
+}
+
+impl Default for FloatingTextParams {
+    fn default() -> Self {
+        Self {
+            text: Default::default(),
+            position: Default::default(),
+            velocity: Vector3::new(0.0, 1.0, 0.0),
+            color: Color::WHITE,
+            outline_color: Color::BLACK,
+            outline_thickness: 0.01,
+            font_size: 0.3,
+            lifetime: 1.0,
+            fade_time: 0.5,
+            always_on_top: true,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FloatingText {
+    node: Handle<Node>,
+    velocity: Vector3<f32>,
+    color: Color,
+    outline_color: Color,
+    age: f32,
+    lifetime: f32,
+    fade_time: f32,
+}
+
+/// Fire-and-forget texts that rise and fade out, like damage numbers. Texts are spawned by
+/// [`crate::scene::Scene::spawn_floating_text`], moved and faded by the scene and removed from
+/// the graph when their lifetime ends.
+///
+/// ```no_run
+/// use rg3d::{
+///     core::{algebra::Vector3, color::Color},
+///     scene::{world_text::FloatingTextParams, Scene},
+/// };
+///
+/// fn show_damage(scene: &mut Scene, position: Vector3<f32>, damage: u32) {
+///     scene.spawn_floating_text(FloatingTextParams {
+///         text: damage.to_string(),
+///         position,
+///         color: Color::opaque(255, 60, 60),
+///         ..Default::default()
+///     });
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FloatingTexts {
+    texts: Vec<FloatingText>,
+}
+
+impl FloatingTexts {
+    /// Returns amount of floating texts that are shown at the moment.
+    pub fn count(&self) -> usize {
+        self.texts.len()
+    }
+
+    /// Returns handles of world text nodes of floating texts that are shown at the moment.
+    pub fn nodes(&self) -> impl Iterator<Item = Handle<Node>> + '_ {
+        self.texts.iter().map(|t| t.node)
+    }
+
+    /// Removes every floating text from the graph.
+    pub fn clear(&mut self, graph: &mut Graph) {
+        for text in self.texts.drain(..) {
+            if graph.is_valid_handle(text.node) {
+                graph.remove_node(text.node);
+            }
+        }
+    }
+
+    pub(in crate) fn remap_handles(&mut self, old_new_map: &FxHashMap<Handle<Node>, Handle<Node>>) {
+        // Texts that were filtered out of a copy of the graph are forgotten.
+        self.texts
+            .retain(|text| old_new_map.contains_key(&text.node));
+        for text in self.texts.iter_mut() {
+            text.node = old_new_map[&text.node];
+        }
+    }
+
+    pub(in crate) fn spawn(&mut self, graph: &mut Graph, params: FloatingTextParams) -> Handle<Node> {
+        let node = WorldTextBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(params.position)
+                    .build(),
+            ),
+        )
+        .with_text(params.text)
+        .with_font_size(params.font_size)
+        .with_color(params.color)
+        .with_outline(params.outline_color, params.outline_thickness)
+        .with_always_on_top(params.always_on_top)
+        .build(graph);
+
+        self.texts.push(FloatingText {
+            node,
+            velocity: params.velocity,
+            color: params.color,
+            outline_color: params.outline_color,
+            age: 0.0,
+            lifetime: params.lifetime.max(0.0),
+            fade_time: params.fade_time.max(0.0),
+        });
+
+        node
+    }
+
+    pub(in crate) fn update(&mut self, graph: &mut Graph, dt: f32) {
+        let mut i = 0;
+        while i < self.texts.len() {
+            let text = &mut self.texts[i];
+
+            text.age += dt;
+            // Text could be removed by user.
+            if !graph.is_valid_handle(text.node) || text.age >= text.lifetime {
+                if graph.is_valid_handle(text.node) {
+                    graph.remove_node(text.node);
+                }
+                self.texts.remove(i);
+                continue;
+            }
+
+            let remaining = text.lifetime - text.age;
+            let alpha = if remaining < text.fade_time {
+                remaining / text.fade_time
+            } else {
+                1.0
+            };
+
+            if let Node::WorldText(world_text) = &mut graph[text.node] {
+                let position = **world_text.local_transform().position();
+                world_text
+                    .local_transform_mut()
+                    .set_position(position + text.velocity.scale(dt));
+                world_text.set_color(fade(text.color, alpha));
+                let thickness = world_text.outline_thickness();
+                world_text.set_outline(fade(text.outline_color, alpha), thickness);
+            }
+
+            i += 1;
+        }
+    }
+}
+
+fn fade(color: Color, alpha: f32) -> Color {
+    Color::from_rgba(
+        color.r,
+        color.g,
+        color.b,
+        (color.a as f32 * alpha.clamp(0.0, 1.0)) as u8,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::Vector3,
+        scene::{
+            base::BaseBuilder,
+            graph::Graph,
+            world_text::{
+                FloatingTextParams, FloatingTexts, HorizontalTextAlignment, VerticalTextAlignment,
+                WorldText, WorldTextBuilder,
+            },
+        },
+    };
+
+    fn laid_out(builder: WorldTextBuilder) -> WorldText {
+        let mut text = builder.build_world_text();
+        text.update();
+        text
+    }
+
+    #[test]
+    fn test_world_text_layout() {
+        let text = laid_out(
+            WorldTextBuilder::new(BaseBuilder::new())
+                .with_text("Hi\nthere")
+                .with_font_size(1.0),
+        );
+        let geometry = text.geometry();
+        assert_eq!(geometry.glyph_count(), 7);
+        // Glyphs of the default font are small enough to fit in a single page.
+        assert_eq!(geometry.pages.len(), 1);
+        assert_eq!(geometry.pages[0].1, 0..7);
+
+        // Two lines, centered at the origin.
+        let bounds = geometry.bounds;
+        assert!((bounds.y() + bounds.h() * 0.5).abs() < 1e-5);
+        assert!((bounds.x() + bounds.w() * 0.5).abs() < 1e-5);
+
+        let left = laid_out(
+            WorldTextBuilder::new(BaseBuilder::new())
+                .with_text("Hi\nthere")
+                .with_font_size(1.0)
+                .with_horizontal_alignment(HorizontalTextAlignment::Left)
+                .with_vertical_alignment(VerticalTextAlignment::Bottom),
+        );
+        assert_eq!(left.geometry().bounds.x(), 0.0);
+        assert_eq!(left.geometry().bounds.y(), 0.0);
+        assert_ne!(left.geometry().hash, geometry.hash);
+
+        // Whitespaces have no quads.
+        let spaces = laid_out(WorldTextBuilder::new(BaseBuilder::new()).with_text("a b"));
+        assert_eq!(spaces.geometry().glyph_count(), 2);
+    }
+
+    #[test]
+    fn test_floating_texts() {
+        let mut graph = Graph::new();
+        let mut texts = FloatingTexts::default();
+        let node = texts.spawn(
+            &mut graph,
+            FloatingTextParams {
+                text: "42".to_owned(),
+                lifetime: 1.0,
+                fade_time: 0.5,
+                ..Default::default()
+            },
+        );
+
+        texts.update(&mut graph, 0.25);
+        assert_eq!(
+            **graph[node].local_transform().position(),
+            Vector3::new(0.0, 0.25, 0.0)
+        );
+        assert_eq!(graph[node].as_world_text().color().a, 255);
+
+        texts.update(&mut graph, 0.5);
+        assert!(graph[node].as_world_text().color().a < 255);
+
+        texts.update(&mut graph, 0.5);
+        assert!(!graph.is_valid_handle(node));
+        assert_eq!(texts.count(), 0);
+    }
+}
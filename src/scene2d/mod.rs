@@ -8,6 +8,7 @@ use crate::{
         color::Color,
         instant,
         pool::{Handle, Pool},
+        profile_scope,
         visitor::prelude::*,
     },
     engine::PhysicsBinder,
@@ -119,6 +120,8 @@ impl Scene2d {
     }
 
     pub fn update(&mut self, render_target_size: Vector2<f32>, dt: f32) {
+        profile_scope!("Scene2d");
+
         self.update_physics();
 
         let last = instant::Instant::now();
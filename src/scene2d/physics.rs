@@ -100,6 +100,7 @@ impl Physics {
         let mut phys_desc = self.desc.take().unwrap();
 
         self.integration_parameters = phys_desc.integration_parameters.into();
+        self.solver_settings = phys_desc.solver_settings;
 
         let mut bodies = RigidBodySet::new();
         let mut colliders = ColliderSet::new();
@@ -119,6 +120,7 @@ impl Physics {
             colliders.insert_with_parent(collider, parent_handle, &mut bodies);
         }
 
+        let mut break_limits = Vec::new();
         for desc in phys_desc.joints.drain(..) {
             let b1 = phys_desc
                 .body_handle_map
@@ -130,7 +132,8 @@ impl Physics {
                 .value_of(&desc.body2)
                 .cloned()
                 .unwrap();
-            joints.insert(b1, b2, desc.params);
+            let native = joints.insert(b1, b2, desc.params);
+            break_limits.push((native, desc.break_limits));
         }
 
         self.bodies =
@@ -138,5 +141,10 @@ impl Physics {
         self.colliders =
             ColliderContainer::from_raw_parts(colliders, phys_desc.collider_handle_map).unwrap();
         self.joints = JointContainer::from_raw_parts(joints, phys_desc.joint_handle_map).unwrap();
+        for (native, limits) in break_limits {
+            if let Some(handle) = self.joints.handle_map().key_of(&native).cloned() {
+                self.joints.set_break_limits(&handle, limits);
+            }
+        }
     }
 }
@@ -0,0 +1,442 @@
+//! Texture atlas packs many small images (UI icons, sprites, etc.) into one or few big textures
+//! (pages), which significantly reduces amount of texture binds during rendering and amount of
+//! files to load.
+//!
+//! Every packed image is accessible by its name, an entry of the atlas defines a page and a
+//! rectangle in texture coordinates on that page. Atlas description (with pages) can be saved
+//! to a file and loaded back, so atlases could be made offline.
+//!
+//! ```no_run
+//! use rg3d::{
+//!     gui::{widget::WidgetBuilder, BuildContext, UiNode},
+//!     core::pool::Handle,
+//!     utils::atlas::TextureAtlasBuilder,
+//! };
+//!
+//! fn make_icon(ctx: &mut BuildContext) -> Handle<UiNode> {
+//!     let atlas = TextureAtlasBuilder::new(512)
+//!         .with_padding(2)
+//!         .with_file("open", "data/icons/open.png")
+//!         .with_file("save", "data/icons/save.png")
+//!         .build()
+//!         .unwrap();
+//!
+//!     atlas
+//!         .image_builder("save", WidgetBuilder::new().with_width(16.0).with_height(16.0))
+//!         .unwrap()
+//!         .build(ctx)
+//! }
+//! ```
+
+use crate::{
+    core::{
+        math::Rect,
+        rectpack::RectPacker,
+        visitor::{Visit, VisitError, VisitResult, Visitor},
+    },
+    gui::{image::ImageBuilder, widget::WidgetBuilder},
+    resource::texture::{Texture, TextureKind, TexturePixelKind},
+    utils::into_gui_texture,
+};
+use fxhash::FxHashMap;
+use std::path::{Path, PathBuf};
+
+/// An error that may occur during atlas building, saving or loading.
+#[derive(Debug, thiserror::Error)]
+pub enum AtlasError {
+    /// An image (with padding) is larger than a page of the atlas.
+    #[error(
+        "Image {name} ({width}x{height} with padding) does not fit into atlas page of \
+        {page_size}x{page_size} pixels"
+    )]
+    ImageTooLarge {
+        /// Name of the image.
+        name: String,
+        /// Width of the image with padding.
+        width: u32,
+        /// Height of the image with padding.
+        height: u32,
+        /// Size of a page of the atlas.
+        page_size: u32,
+    },
+    /// Image has zero size or size of its data does not match its dimensions.
+    #[error("Image {0} is empty or has invalid data size")]
+    InvalidImage(String),
+    /// Image with same name was added twice.
+    #[error("Image {0} is added to the atlas more than once")]
+    DuplicateName(String),
+    /// Internal image crate error.
+    #[error("Unable to load image {0}: {1}")]
+    Image(PathBuf, image::ImageError),
+    /// An i/o error has occurred.
+    #[error(transparent)]
+    Io(std::io::Error),
+    /// Atlas file is corrupted or has unsupported format.
+    #[error("Visit error: {0}")]
+    Visit(VisitError),
+}
+
+impl From<std::io::Error> for AtlasError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<VisitError> for AtlasError {
+    fn from(e: VisitError) -> Self {
+        Self::Visit(e)
+    }
+}
+
+/// A packed image of an atlas.
+#[derive(Clone, Debug, Default, PartialEq, Visit)]
+pub struct AtlasEntry {
+    /// Index of a page on which the image is placed.
+    pub page: u32,
+    /// Rectangle of the image on the page in texture coordinates (padding is excluded).
+    pub uv_rect: Rect<f32>,
+    /// Width of the image in pixels.
+    pub width: u32,
+    /// Height of the image in pixels.
+    pub height: u32,
+}
+
+/// See module docs.
+#[derive(Clone, Debug, Default, Visit)]
+pub struct TextureAtlas {
+    page_size: u32,
+    pages: Vec<Texture>,
+    entries: FxHashMap<String, AtlasEntry>,
+}
+
+impl TextureAtlas {
+    /// Returns size (width and height) of every page in pixels.
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Returns textures of pages of the atlas. Pages are RGBA8 textures.
+    pub fn pages(&self) -> &[Texture] {
+        &self.pages
+    }
+
+    /// Returns an entry of the atlas by its name.
+    pub fn entry(&self, name: &str) -> Option<&AtlasEntry> {
+        self.entries.get(name)
+    }
+
+    /// Returns an iterator over names and entries of the atlas.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &AtlasEntry)> {
+        self.entries
+            .iter()
+            .map(|(name, entry)| (name.as_str(), entry))
+    }
+
+    /// Returns a page texture and a rectangle in texture coordinates of an entry with given name.
+    pub fn texture(&self, name: &str) -> Option<(Texture, Rect<f32>)> {
+        let entry = self.entries.get(name)?;
+        let page = self.pages.get(entry.page as usize)?;
+        Some((page.clone(), entry.uv_rect))
+    }
+
+    /// Creates image widget builder that shows an entry with given name.
+    pub fn image_builder(&self, name: &str, widget_builder: WidgetBuilder) -> Option<ImageBuilder> {
+        let (texture, uv_rect) = self.texture(name)?;
+        Some(
+            ImageBuilder::new(widget_builder)
+                .with_texture(into_gui_texture(texture))
+                .with_uv_rect(uv_rect),
+        )
+    }
+
+    /// Saves the atlas (description and content of pages) into given file.
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<(), AtlasError> {
+        let mut visitor = Visitor::new();
+        self.visit("Atlas", &mut visitor)?;
+        visitor.save_binary(path)?;
+        Ok(())
+    }
+
+    /// Loads an atlas from given file.
+    pub async fn load<P: AsRef<Path>>(path: P) -> Result<Self, AtlasError> {
+        let mut visitor = Visitor::load_binary(path).await?;
+        let mut atlas = Self::default();
+        atlas.visit("Atlas", &mut visitor)?;
+        Ok(atlas)
+    }
+}
+
+enum AtlasSource {
+    File(PathBuf),
+    Rgba8 {
+        width: u32,
+        height: u32,
+        bytes: Vec<u8>,
+    },
+}
+
+struct AtlasImage {
+    name: String,
+    width: u32,
+    height: u32,
+    bytes: Vec<u8>,
+}
+
+impl AtlasImage {
+    fn load(name: String, source: AtlasSource) -> Result<Self, AtlasError> {
+        let (width, height, bytes) = match source {
+            AtlasSource::File(path) => {
+                let image = image::open(&path)
+                    .map_err(|e| AtlasError::Image(path.clone(), e))?
+                    .to_rgba8();
+                (image.width(), image.height(), image.into_raw())
+            }
+            AtlasSource::Rgba8 {
+                width,
+                height,
+                bytes,
+            } => (width, height, bytes),
+        };
+
+        if width == 0 || height == 0 || bytes.len() != (width * height * 4) as usize {
+            return Err(AtlasError::InvalidImage(name));
+        }
+
+        Ok(Self {
+            name,
+            width,
+            height,
+            bytes,
+        })
+    }
+}
+
+/// Atlas builder packs a set of images into one or more square pages.
+pub struct TextureAtlasBuilder {
+    page_size: u32,
+    padding: u32,
+    sources: Vec<(String, AtlasSource)>,
+}
+
+impl TextureAtlasBuilder {
+    /// Creates new atlas builder with given size (width and height) of pages in pixels.
+    pub fn new(page_size: u32) -> Self {
+        Self {
+            page_size,
+            padding: 0,
+            sources: Default::default(),
+        }
+    }
+
+    /// Sets amount of empty pixels around every image, it prevents bleeding of neighbour
+    /// images when pages are filtered. Default is zero.
+    pub fn with_padding(mut self, padding: u32) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Adds an image file to the atlas. The file will be loaded on [`Self::build`].
+    pub fn with_file<S: AsRef<str>, P: AsRef<Path>>(mut self, name: S, path: P) -> Self {
+        self.sources.push((
+            name.as_ref().to_owned(),
+            AtlasSource::File(path.as_ref().to_owned()),
+        ));
+        self
+    }
+
+    /// Adds in-memory RGBA8 image to the atlas.
+    pub fn with_rgba8<S: AsRef<str>>(
+        mut self,
+        name: S,
+        width: u32,
+        height: u32,
+        bytes: Vec<u8>,
+    ) -> Self {
+        self.sources.push((
+            name.as_ref().to_owned(),
+            AtlasSource::Rgba8 {
+                width,
+                height,
+                bytes,
+            },
+        ));
+        self
+    }
+
+    /// Loads all images and packs them into pages. New page is created when an image does not
+    /// fit into existing pages. Fails if any image cannot be loaded or it does not fit into an
+    /// empty page.
+    pub fn build(self) -> Result<TextureAtlas, AtlasError> {
+        let page_size = self.page_size;
+        let padding = self.padding;
+
+        let mut images = Vec::with_capacity(self.sources.len());
+        for (name, source) in self.sources {
+            if images.iter().any(|image: &AtlasImage| image.name == name) {
+                return Err(AtlasError::DuplicateName(name));
+            }
+            images.push(AtlasImage::load(name, source)?);
+        }
+
+        // Big images first gives much denser packing.
+        images.sort_by(|a, b| (b.height, b.width).cmp(&(a.height, a.width)));
+
+        let mut packers = Vec::<RectPacker<u32>>::new();
+        let mut pages = Vec::<Vec<u8>>::new();
+        let mut entries = FxHashMap::default();
+        for image in images {
+            let width = image.width + 2 * padding;
+            let height = image.height + 2 * padding;
+            if width > page_size || height > page_size {
+                return Err(AtlasError::ImageTooLarge {
+                    name: image.name,
+                    width,
+                    height,
+                    page_size,
+                });
+            }
+
+            let placement = packers
+                .iter_mut()
+                .enumerate()
+                .find_map(|(page, packer)| packer.find_free(width, height).map(|r| (page, r)));
+            let (page, rect) = match placement {
+                Some(placement) => placement,
+                None => {
+                    let mut packer = RectPacker::new(page_size, page_size);
+                    // Image fits into empty page, this is checked above.
+                    let rect = packer.find_free(width, height).unwrap();
+                    packers.push(packer);
+                    pages.push(vec![0; (page_size * page_size * 4) as usize]);
+                    (pages.len() - 1, rect)
+                }
+            };
+
+            let x = rect.x() + padding;
+            let y = rect.y() + padding;
+            let pixels = &mut pages[page];
+            let row_size = (image.width * 4) as usize;
+            for (row, src) in image.bytes.chunks_exact(row_size).enumerate() {
+                let offset = (((y + row as u32) * page_size + x) * 4) as usize;
+                pixels[offset..(offset + row_size)].copy_from_slice(src);
+            }
+
+            let k = 1.0 / page_size as f32;
+            entries.insert(
+                image.name,
+                AtlasEntry {
+                    page: page as u32,
+                    uv_rect: Rect::new(
+                        x as f32 * k,
+                        y as f32 * k,
+                        image.width as f32 * k,
+                        image.height as f32 * k,
+                    ),
+                    width: image.width,
+                    height: image.height,
+                },
+            );
+        }
+
+        Ok(TextureAtlas {
+            page_size,
+            pages: pages
+                .into_iter()
+                .map(|bytes| {
+                    Texture::from_bytes(
+                        TextureKind::Rectangle {
+                            width: page_size,
+                            height: page_size,
+                        },
+                        TexturePixelKind::RGBA8,
+                        bytes,
+                        // Pages are procedural, their content must be saved with the atlas.
+                        true,
+                    )
+                    .unwrap()
+                })
+                .collect(),
+            entries,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::utils::atlas::{AtlasError, TextureAtlas, TextureAtlasBuilder};
+    use futures::executor::block_on;
+
+    fn solid(size: u32, value: u8) -> Vec<u8> {
+        vec![value; (size * size * 4) as usize]
+    }
+
+    #[test]
+    fn test_atlas_packing() {
+        let mut atlas = TextureAtlasBuilder::new(64)
+            .with_padding(1)
+            .with_rgba8("a", 30, 30, solid(30, 1))
+            .with_rgba8("b", 30, 30, solid(30, 2))
+            .with_rgba8("c", 30, 30, solid(30, 3))
+            .with_rgba8("d", 30, 30, solid(30, 4))
+            .with_rgba8("e", 16, 16, solid(16, 5))
+            .build()
+            .unwrap();
+
+        // Four big images with padding occupy whole first page.
+        assert_eq!(atlas.pages().len(), 2);
+        assert_eq!(atlas.entry("e").unwrap().page, 1);
+
+        let entries = atlas.entries().collect::<Vec<_>>();
+        for (i, (_, a)) in entries.iter().enumerate() {
+            for (_, b) in entries.iter().skip(i + 1) {
+                if a.page == b.page {
+                    assert!(!a.uv_rect.intersects(b.uv_rect));
+                }
+            }
+        }
+
+        // Content is copied into page, padding is left empty.
+        let entry = atlas.entry("c").unwrap().clone();
+        let page = atlas.pages()[entry.page as usize].clone();
+        let data = page.data_ref();
+        let x = (entry.uv_rect.x() * 64.0).round() as usize;
+        let y = (entry.uv_rect.y() * 64.0).round() as usize;
+        assert_eq!(data.data()[(y * 64 + x) * 4], 3);
+        assert_eq!(data.data()[((y - 1) * 64 + x) * 4], 0);
+        drop(data);
+
+        let path = std::env::temp_dir().join("rg3d_test_atlas.bin");
+        atlas.save(&path).unwrap();
+        let loaded = block_on(TextureAtlas::load(&path)).unwrap();
+        assert_eq!(loaded.entry("c"), Some(&entry));
+        assert_eq!(loaded.pages().len(), 2);
+        assert_eq!(
+            loaded.pages()[entry.page as usize].data_ref().data(),
+            page.data_ref().data()
+        );
+    }
+
+    #[test]
+    fn test_atlas_errors() {
+        assert!(matches!(
+            TextureAtlasBuilder::new(32)
+                .with_padding(1)
+                .with_rgba8("big", 31, 31, solid(31, 0))
+                .build(),
+            Err(AtlasError::ImageTooLarge { width: 33, .. })
+        ));
+        assert!(matches!(
+            TextureAtlasBuilder::new(32)
+                .with_rgba8("a", 2, 2, solid(2, 0))
+                .with_rgba8("a", 2, 2, solid(2, 0))
+                .build(),
+            Err(AtlasError::DuplicateName(_))
+        ));
+        assert!(matches!(
+            TextureAtlasBuilder::new(32)
+                .with_rgba8("a", 2, 2, vec![0; 3])
+                .build(),
+            Err(AtlasError::InvalidImage(_))
+        ));
+    }
+}
@@ -0,0 +1,584 @@
+//! Contains common camera controllers: spring arm, camera shake and field-of-view animation.
+//!
+//! Every controller is a plain structure that should be updated once per frame by calling its
+//! `update(scene, dt)` method, they do not require any engine-side registration and can be
+//! saved together with your game state.
+//!
+//! # Order of updates
+//!
+//! Spring arm sets position and rotation of a camera, camera shake sets post-rotation of a camera
+//! and field-of-view animation sets field-of-view of a camera, so they do not interfere with each
+//! other and can be updated in any order.
+
+#![warn(missing_docs)]
+
+use crate::{
+    core::{
+        algebra::{Isometry3, Translation3, UnitQuaternion, Vector3},
+        math::lerpf,
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    physics3d::{ConvexShape, RigidBodyHandle, ShapeCastOptions},
+    scene::{node::Node, Scene},
+};
+
+/// Maximum time step of spring integration, larger time steps are split into multiple
+/// sub-steps to keep springs with high stiffness stable.
+const MAX_SPRING_STEP: f32 = 1.0 / 120.0;
+
+fn spring_substeps(dt: f32) -> (usize, f32) {
+    let count = (dt / MAX_SPRING_STEP).ceil().max(1.0) as usize;
+    (count, dt / count as f32)
+}
+
+/// Returns damping coefficient that makes a spring with given stiffness critically damped, such
+/// spring reaches its target as fast as possible without oscillations.
+pub fn critical_damping(stiffness: f32) -> f32 {
+    2.0 * stiffness.max(0.0).sqrt()
+}
+
+fn integrate_spring(
+    position: &mut Vector3<f32>,
+    velocity: &mut Vector3<f32>,
+    target: Vector3<f32>,
+    stiffness: f32,
+    damping: f32,
+    dt: f32,
+) {
+    let (count, step) = spring_substeps(dt);
+    for _ in 0..count {
+        let acceleration = (target - *position).scale(stiffness) - velocity.scale(damping);
+        *velocity += acceleration.scale(step);
+        *position += velocity.scale(step);
+    }
+}
+
+fn integrate_scalar_spring(
+    value: &mut f32,
+    velocity: &mut f32,
+    target: f32,
+    stiffness: f32,
+    damping: f32,
+    dt: f32,
+) {
+    let (count, step) = spring_substeps(dt);
+    for _ in 0..count {
+        let acceleration = (target - *value) * stiffness - *velocity * damping;
+        *velocity += acceleration * step;
+        *value += *velocity * step;
+    }
+}
+
+/// Parameters of collision detection of a spring arm.
+#[derive(Visit, Clone, Debug, PartialEq)]
+pub struct SpringArmCollision {
+    /// Radius of a sphere that is used to probe the space between pivot and camera. It should be
+    /// a bit larger than near clipping plane of the camera to prevent seeing through walls.
+    pub radius: f32,
+
+    /// A rigid body that will be ignored by collision detection, usually it is a body of a
+    /// character that is followed by the camera.
+    pub exclude_body: Option<RigidBodyHandle>,
+}
+
+impl Default for SpringArmCollision {
+    fn default() -> Self {
+        Self {
+            radius: 0.2,
+            exclude_body: None,
+        }
+    }
+}
+
+/// Spring arm keeps a camera at desired offset from a target node and smoothly follows it. The
+/// camera is pulled closer to the target when there is an obstacle between them (if collision is
+/// enabled).
+///
+/// Spring arm writes position and rotation to the **local** transform of the camera, so the
+/// camera should not be attached to a node with non-identity transform.
+///
+/// # Example
+///
+/// ```no_run
+/// use rg3d::{
+///     core::algebra::{UnitQuaternion, Vector3},
+///     scene::Scene,
+///     utils::camera::SpringArm,
+/// };
+///
+/// fn update_camera(arm: &mut SpringArm, scene: &mut Scene, yaw: f32, pitch: f32, dt: f32) {
+///     arm.rotation = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+///         * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch);
+///     arm.update(scene, dt);
+/// }
+/// ```
+#[derive(Visit, Clone, Debug)]
+pub struct SpringArm {
+    /// A node which will be followed by the camera.
+    pub target: Handle<Node>,
+
+    /// A node that will be moved by the spring arm, usually it is a camera.
+    pub camera: Handle<Node>,
+
+    /// Offset of the pivot point of the arm relative to the position of the target in world
+    /// coordinates. For characters it is usually a point at the head level.
+    pub pivot_offset: Vector3<f32>,
+
+    /// Desired offset of the camera relative to the pivot point in local coordinates of the arm.
+    /// Keep in mind that camera looks along positive Z axis, so negative Z will put camera
+    /// behind the pivot.
+    pub offset: Vector3<f32>,
+
+    /// Orientation of the arm. Camera will have the same rotation.
+    pub rotation: UnitQuaternion<f32>,
+
+    /// Stiffness of the spring that pulls the pivot point to the target. The higher the value,
+    /// the faster the camera will follow the target.
+    pub stiffness: f32,
+
+    /// Damping of the spring, see [`critical_damping`] to get damping without oscillations.
+    pub damping: f32,
+
+    /// Optional collision detection, `None` disables collision detection.
+    pub collision: Option<SpringArmCollision>,
+
+    pivot: Vector3<f32>,
+    pivot_velocity: Vector3<f32>,
+    length: f32,
+    length_velocity: f32,
+    initialized: bool,
+}
+
+impl Default for SpringArm {
+    fn default() -> Self {
+        Self::new(Handle::NONE, Handle::NONE)
+    }
+}
+
+impl SpringArm {
+    /// Creates new critically damped spring arm that places the camera 3 meters behind the
+    /// target without collision detection.
+    pub fn new(target: Handle<Node>, camera: Handle<Node>) -> Self {
+        let stiffness = 100.0;
+        Self {
+            target,
+            camera,
+            pivot_offset: Default::default(),
+            offset: Vector3::new(0.0, 0.0, -3.0),
+            rotation: UnitQuaternion::identity(),
+            stiffness,
+            damping: critical_damping(stiffness),
+            collision: None,
+            pivot: Default::default(),
+            pivot_velocity: Default::default(),
+            length: 0.0,
+            length_velocity: 0.0,
+            initialized: false,
+        }
+    }
+
+    /// Sets new stiffness and makes the spring critically damped.
+    pub fn set_critically_damped_stiffness(&mut self, stiffness: f32) -> &mut Self {
+        self.stiffness = stiffness;
+        self.damping = critical_damping(stiffness);
+        self
+    }
+
+    /// Returns current position of the pivot point of the arm.
+    pub fn pivot(&self) -> Vector3<f32> {
+        self.pivot
+    }
+
+    /// Returns current length of the arm, it can be less than length of the offset if there is
+    /// an obstacle between the pivot and the camera.
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    /// Moves the camera to its desired position immediately, without smoothing. Useful after
+    /// teleportation of the target.
+    pub fn reset(&mut self) {
+        self.initialized = false;
+    }
+
+    /// Updates position of the pivot point and length of the arm, and applies them to the camera.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        let target_position = match scene.graph.try_get(self.target) {
+            Some(target) => target.global_position() + self.pivot_offset,
+            None => return,
+        };
+
+        let max_length = self.offset.norm();
+        let direction = (self.rotation * self.offset)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::z);
+
+        if self.initialized {
+            integrate_spring(
+                &mut self.pivot,
+                &mut self.pivot_velocity,
+                target_position,
+                self.stiffness,
+                self.damping,
+                dt,
+            );
+        } else {
+            self.pivot = target_position;
+            self.pivot_velocity = Default::default();
+            self.length = max_length;
+            self.length_velocity = 0.0;
+        }
+
+        let mut desired_length = max_length;
+        if let Some(collision) = self.collision.as_ref() {
+            if let Some(hit) = scene.physics.shape_cast(
+                &ConvexShape::Ball {
+                    radius: collision.radius,
+                },
+                &Isometry3::from_parts(Translation3::from(self.pivot), Default::default()),
+                &direction,
+                max_length,
+                ShapeCastOptions {
+                    exclude_body: collision.exclude_body,
+                    ..Default::default()
+                },
+            ) {
+                desired_length = hit.toi;
+            }
+        }
+
+        if desired_length < self.length || !self.initialized {
+            // Obstacles must pull the camera immediately, otherwise it will go through them.
+            self.length = desired_length;
+            self.length_velocity = 0.0;
+        } else {
+            integrate_scalar_spring(
+                &mut self.length,
+                &mut self.length_velocity,
+                desired_length,
+                self.stiffness,
+                self.damping,
+                dt,
+            );
+            self.length = self.length.min(desired_length);
+        }
+
+        self.initialized = true;
+
+        if let Some(camera) = scene.graph.try_get_mut(self.camera) {
+            camera
+                .local_transform_mut()
+                .set_position(self.pivot + direction.scale(self.length))
+                .set_rotation(self.rotation);
+        }
+    }
+}
+
+fn hash(i: i32) -> f32 {
+    let mut h = i as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    (h as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+/// One-dimensional gradient (Perlin) noise in `[-1; 1]` range, it is zero at integer points.
+fn perlin_noise(x: f32) -> f32 {
+    let i = x.floor();
+    let f = x - i;
+    let i = i as i32;
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    2.0 * lerpf(hash(i) * f, hash(i.wrapping_add(1)) * (f - 1.0), u)
+}
+
+/// Camera shake rotates a camera using smooth noise. Shake strength is defined by "trauma" value
+/// in `[0; 1]` range which decays over time, every impact (explosion, landing, hit) adds some
+/// trauma.
+///
+/// Camera shake owns **post-rotation** of the camera transform, rotation that is applied after
+/// local rotation. It is overwritten every frame, so shake never accumulates and camera can be
+/// freely rotated by other code.
+#[derive(Visit, Clone, Debug)]
+pub struct CameraShake {
+    /// A node to shake, usually it is a camera.
+    pub camera: Handle<Node>,
+
+    /// Maximum angles in radians around X (pitch), Y (yaw) and Z (roll) axes.
+    pub amplitude: Vector3<f32>,
+
+    /// Frequency of the noise, the higher the value the faster the camera shakes.
+    pub frequency: f32,
+
+    /// Amount of trauma removed per second.
+    pub decay: f32,
+
+    trauma: f32,
+    time: f32,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self::new(Handle::NONE)
+    }
+}
+
+impl CameraShake {
+    /// Creates new camera shake with moderate amplitude.
+    pub fn new(camera: Handle<Node>) -> Self {
+        Self {
+            camera,
+            amplitude: Vector3::new(
+                3.0f32.to_radians(),
+                3.0f32.to_radians(),
+                5.0f32.to_radians(),
+            ),
+            frequency: 15.0,
+            decay: 1.0,
+            trauma: 0.0,
+            time: 0.0,
+        }
+    }
+
+    /// Adds some trauma, total trauma is clamped to `[0; 1]` range.
+    pub fn add_trauma(&mut self, amount: f32) -> &mut Self {
+        self.trauma = (self.trauma + amount).min(1.0).max(0.0);
+        self
+    }
+
+    /// Returns current trauma.
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Returns rotation that is applied to the camera at the moment.
+    pub fn rotation(&self) -> UnitQuaternion<f32> {
+        // Squared trauma gives more natural falloff.
+        let strength = self.trauma * self.trauma;
+        let t = self.time * self.frequency;
+        // Different offsets in noise space makes axes uncorrelated.
+        UnitQuaternion::from_euler_angles(
+            self.amplitude.x * strength * perlin_noise(t),
+            self.amplitude.y * strength * perlin_noise(t + 37.3),
+            self.amplitude.z * strength * perlin_noise(t + 71.9),
+        )
+    }
+
+    /// Decays trauma and applies new shake rotation to the camera.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        self.time += dt;
+        self.trauma = (self.trauma - self.decay * dt).max(0.0);
+
+        let rotation = self.rotation();
+        if let Some(camera) = scene.graph.try_get_mut(self.camera) {
+            // Transform uses inverse of post-rotation, so invert it to get rotation in local
+            // space of the camera.
+            camera
+                .local_transform_mut()
+                .set_post_rotation(rotation.inverse());
+        }
+    }
+}
+
+/// Smoothly changes field-of-view of a camera over time, for example to make "FOV kick" while
+/// character is sprinting.
+#[derive(Visit, Clone, Debug)]
+pub struct FovAnimation {
+    /// A camera which field-of-view will be animated.
+    pub camera: Handle<Node>,
+
+    /// Default field-of-view in radians, see [`FovAnimation::reset`].
+    pub base_fov: f32,
+
+    current: f32,
+    from: f32,
+    to: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Default for FovAnimation {
+    fn default() -> Self {
+        Self::new(Handle::NONE, 75.0f32.to_radians())
+    }
+}
+
+impl FovAnimation {
+    /// Creates new field-of-view animation with given base field-of-view in radians.
+    pub fn new(camera: Handle<Node>, base_fov: f32) -> Self {
+        Self {
+            camera,
+            base_fov,
+            current: base_fov,
+            from: base_fov,
+            to: base_fov,
+            duration: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    /// Starts smooth transition from current field-of-view to the new one in given amount of
+    /// seconds. Does nothing if the animation already goes to the same value, so it is safe to
+    /// call this method every frame.
+    pub fn animate_to(&mut self, fov: f32, duration: f32) -> &mut Self {
+        if self.to != fov {
+            self.from = self.current;
+            self.to = fov;
+            self.duration = duration.max(0.0);
+            self.elapsed = 0.0;
+        }
+        self
+    }
+
+    /// Starts smooth transition back to the base field-of-view.
+    pub fn reset(&mut self, duration: f32) -> &mut Self {
+        self.animate_to(self.base_fov, duration)
+    }
+
+    /// Returns current field-of-view in radians.
+    pub fn fov(&self) -> f32 {
+        self.current
+    }
+
+    /// Returns `true` if the animation reached its target.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Advances the animation and applies current field-of-view to the camera.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+        let t = if self.duration > 0.0 {
+            self.elapsed / self.duration
+        } else {
+            1.0
+        };
+        self.current = if t >= 1.0 {
+            self.to
+        } else {
+            // Smooth-step gives smooth start and stop of the animation.
+            lerpf(self.from, self.to, t * t * (3.0 - 2.0 * t))
+        };
+
+        if let Some(Node::Camera(camera)) = scene.graph.try_get_mut(self.camera) {
+            camera.set_fov(self.current);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{UnitQuaternion, Vector3},
+        scene::{
+            base::BaseBuilder, camera::CameraBuilder, node::Node, transform::TransformBuilder,
+            Scene,
+        },
+        utils::camera::{perlin_noise, CameraShake, FovAnimation, SpringArm},
+    };
+
+    #[test]
+    fn test_spring_arm() {
+        let mut scene = Scene::new();
+        let target = BaseBuilder::new()
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(1.0, 0.0, 0.0))
+                    .build(),
+            )
+            .build(&mut scene.graph);
+        let camera = CameraBuilder::new(BaseBuilder::new()).build(&mut scene.graph);
+        scene.graph.update_hierarchical_data();
+
+        let mut arm = SpringArm::new(target, camera);
+        arm.pivot_offset = Vector3::new(0.0, 1.0, 0.0);
+        arm.update(&mut scene, 1.0 / 60.0);
+
+        // First update puts camera to desired position immediately.
+        let expected = Vector3::new(1.0, 1.0, -3.0);
+        assert!((**scene.graph[camera].local_transform().position() - expected).norm() < 1.0e-5);
+
+        // Move target and make sure that camera follows it smoothly without overshoot.
+        scene.graph[target]
+            .local_transform_mut()
+            .set_position(Vector3::new(5.0, 0.0, 0.0));
+        scene.graph.update_hierarchical_data();
+
+        let mut last_x = 1.0;
+        for _ in 0..300 {
+            arm.update(&mut scene, 1.0 / 60.0);
+            let x = scene.graph[camera].local_transform().position().x;
+            assert!(x >= last_x - 1.0e-5 && x <= 5.0 + 1.0e-3);
+            last_x = x;
+        }
+        assert!((last_x - 5.0).abs() < 1.0e-3);
+    }
+
+    #[test]
+    fn test_camera_shake_does_not_drift() {
+        let mut scene = Scene::new();
+        let camera = CameraBuilder::new(BaseBuilder::new()).build(&mut scene.graph);
+
+        let mut shake = CameraShake::new(camera);
+        shake.add_trauma(1.0);
+        assert_eq!(shake.trauma(), 1.0);
+
+        for _ in 0..120 {
+            shake.update(&mut scene, 1.0 / 60.0);
+            assert!(shake.rotation().angle() <= shake.amplitude.norm());
+        }
+
+        // Trauma is gone - camera must be in its original orientation.
+        assert_eq!(shake.trauma(), 0.0);
+        assert_eq!(
+            **scene.graph[camera].local_transform().post_rotation(),
+            UnitQuaternion::identity()
+        );
+        assert_eq!(
+            **scene.graph[camera].local_transform().rotation(),
+            UnitQuaternion::identity()
+        );
+    }
+
+    #[test]
+    fn test_perlin_noise_range() {
+        for i in 0..1000 {
+            let n = perlin_noise(i as f32 * 0.137 - 50.0);
+            assert!((-1.0..=1.0).contains(&n));
+        }
+        assert_eq!(perlin_noise(3.0), 0.0);
+    }
+
+    #[test]
+    fn test_fov_animation() {
+        let mut scene = Scene::new();
+        let camera = CameraBuilder::new(BaseBuilder::new()).build(&mut scene.graph);
+
+        let base_fov = 75.0f32.to_radians();
+        let mut animation = FovAnimation::new(camera, base_fov);
+        animation.animate_to(90.0f32.to_radians(), 0.5);
+
+        for _ in 0..15 {
+            animation.update(&mut scene, 1.0 / 60.0);
+        }
+        assert!(animation.fov() > base_fov && animation.fov() < 90.0f32.to_radians());
+        assert!(!animation.is_finished());
+
+        for _ in 0..30 {
+            animation.update(&mut scene, 1.0 / 60.0);
+        }
+        assert!(animation.is_finished());
+        assert_eq!(animation.fov(), 90.0f32.to_radians());
+        if let Node::Camera(camera) = &scene.graph[camera] {
+            assert_eq!(camera.fov(), 90.0f32.to_radians());
+        } else {
+            unreachable!()
+        }
+
+        animation.reset(0.0);
+        animation.update(&mut scene, 1.0 / 60.0);
+        assert_eq!(animation.fov(), base_fov);
+    }
+}
@@ -0,0 +1,622 @@
+//! Door helper drives a hinged door: it swings the door to its open or closed angle on request,
+//! keeps the door inside hinge limits and tells when someone comes close enough to use it.
+//!
+//! # Modes
+//!
+//! In [`DoorMode::Kinematic`] mode the helper moves the door itself, at constant angular speed, so
+//! the door behaves like an animated one and nothing can push it. Rigid body of the door (if any)
+//! should be kinematic, if the door node has no rigid body then its local transform is changed.
+//!
+//! In [`DoorMode::Dynamic`] mode the door is a dynamic rigid body that is attached to the world by
+//! a revolute (hinge) joint, which is created by the helper on first update. Characters and other
+//! bodies can push the door freely, requests to open or close the door are executed by a "motor" -
+//! the helper sets angular velocity of the door until it reaches the target angle, then the door
+//! is released and swings freely again. When [latch](Door::latch) is enabled, the door is held
+//! closed when it comes to the closed angle, it can be opened only by a request.
+//!
+//! # Interaction
+//!
+//! Interaction trigger is a sensor collider, usually attached to a static body in the door way.
+//! [`Door::update`] returns [`DoorEvent::InteractorEntered`] when a collider enters the trigger
+//! and [`DoorEvent::InteractorLeft`] when it leaves, it is a good moment to show or hide a "Press E"
+//! prompt. By default every collider is an interactor, use [`Door::interactors`] to limit them to
+//! colliders of characters.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use rg3d::{
+//!     core::{algebra::Vector3, pool::Handle},
+//!     physics3d::ColliderHandle,
+//!     scene::{node::Node, Scene},
+//!     utils::door::{Door, DoorEvent, DoorMode},
+//! };
+//!
+//! fn make_door(leaf: Handle<Node>, trigger: ColliderHandle, player: ColliderHandle) -> Door {
+//!     Door {
+//!         node: leaf,
+//!         mode: DoorMode::Dynamic,
+//!         // The hinge is at the left edge of a 1.2 meters wide door.
+//!         hinge_anchor: Vector3::new(-0.6, 0.0, 0.0),
+//!         open_angle: 90.0f32.to_radians(),
+//!         trigger,
+//!         interactors: vec![player],
+//!         ..Default::default()
+//!     }
+//! }
+//!
+//! fn update(door: &mut Door, scene: &mut Scene, use_pressed: bool, dt: f32) {
+//!     for event in door.update(scene, dt) {
+//!         match event {
+//!             DoorEvent::InteractorEntered(_) => println!("Press E to use the door"),
+//!             DoorEvent::InteractorLeft(_) => println!(""),
+//!             _ => (),
+//!         }
+//!     }
+//!     if use_pressed && door.has_interactors() {
+//!         door.request_toggle();
+//!     }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+
+use crate::{
+    core::{
+        algebra::{Isometry3, Point3, Translation3, Unit, UnitQuaternion, Vector3},
+        pool::Handle,
+        visitor::prelude::*,
+    },
+    physics3d::{
+        rapier::dynamics::{RevoluteJoint, RigidBodyBuilder},
+        ColliderHandle, JointHandle, RigidBodyHandle,
+    },
+    scene::{node::Node, Scene},
+};
+
+// Door is considered to be at its target angle when it is closer than this value (in radians).
+const ANGLE_TOLERANCE: f32 = 0.01;
+
+/// Defines how a door is moved, see module docs for more info.
+#[derive(Visit, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DoorMode {
+    /// The door is moved by the helper only, it can't be pushed.
+    Kinematic,
+
+    /// The door is a dynamic rigid body on a hinge joint, it can be pushed by other bodies.
+    Dynamic,
+}
+
+impl Default for DoorMode {
+    fn default() -> Self {
+        Self::Kinematic
+    }
+}
+
+/// Current state of a door.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DoorState {
+    /// The door is at its closed angle (or latched).
+    Closed,
+
+    /// The door is moving to its open angle on request.
+    Opening,
+
+    /// The door is not closed and it is not moving on request.
+    Open,
+
+    /// The door is moving to its closed angle on request.
+    Closing,
+}
+
+/// An event of a door, see [`Door::update`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DoorEvent {
+    /// A collider has entered interaction trigger of the door.
+    InteractorEntered(ColliderHandle),
+
+    /// A collider has left interaction trigger of the door.
+    InteractorLeft(ColliderHandle),
+
+    /// The door has reached its open angle after a request.
+    Opened,
+
+    /// The door has reached its closed angle after a request, or it was latched.
+    Closed,
+}
+
+#[derive(Visit, Copy, Clone, Debug, PartialEq, Eq)]
+enum DoorTarget {
+    Open,
+    Closed,
+}
+
+impl Default for DoorTarget {
+    fn default() -> Self {
+        Self::Closed
+    }
+}
+
+// Pose of the door at zero angle, it is either a pose of the rigid body in world space or local
+// transform of the node.
+#[derive(Visit, Clone, Debug)]
+struct DoorRest {
+    position: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+}
+
+impl Default for DoorRest {
+    fn default() -> Self {
+        Self {
+            position: Default::default(),
+            rotation: UnitQuaternion::identity(),
+        }
+    }
+}
+
+#[derive(Visit, Clone, Debug, Default)]
+struct DoorHinge {
+    anchor_body: RigidBodyHandle,
+    joint: JointHandle,
+}
+
+/// Hinged door helper, see module docs for more info. Angles are measured around the hinge axis
+/// relative to the pose that the door had on first update, positive angles follow the right-hand
+/// rule. Configuration and current state are serialized, so the helper can be saved together with
+/// the rest of the game.
+#[derive(Visit, Clone, Debug)]
+pub struct Door {
+    /// A node of the door leaf. In dynamic mode the node must be bound to a rigid body.
+    pub node: Handle<Node>,
+
+    /// Defines how the door is moved.
+    pub mode: DoorMode,
+
+    /// A point on the hinge axis in local space of the door (scale is not applied).
+    pub hinge_anchor: Vector3<f32>,
+
+    /// Direction of the hinge axis in local space of the door.
+    pub hinge_axis: Vector3<f32>,
+
+    /// An angle (in radians) at which the door is closed.
+    pub closed_angle: f32,
+
+    /// An angle (in radians) to which the door swings when it is opened.
+    pub open_angle: f32,
+
+    /// Min angle (in radians) of the hinge, the door can't be pushed beyond it.
+    pub min_angle: f32,
+
+    /// Max angle (in radians) of the hinge, the door can't be pushed beyond it.
+    pub max_angle: f32,
+
+    /// Angular speed (in radians per second) at which the door is opened or closed on request.
+    pub speed: f32,
+
+    /// Whether the door is held closed when it comes to its closed angle or not. Only for dynamic
+    /// mode.
+    pub latch: bool,
+
+    /// Max distance (in radians) to the closed angle at which the latch engages.
+    pub latch_angle: f32,
+
+    /// Interaction trigger, a sensor collider.
+    pub trigger: ColliderHandle,
+
+    /// Colliders that can interact with the door, empty list means every collider.
+    pub interactors: Vec<ColliderHandle>,
+
+    angle: f32,
+    target: Option<DoorTarget>,
+    latched: bool,
+    rest: Option<DoorRest>,
+    hinge: Option<DoorHinge>,
+    #[visit(skip)]
+    inside: Vec<ColliderHandle>,
+}
+
+impl Default for Door {
+    fn default() -> Self {
+        Self {
+            node: Default::default(),
+            mode: Default::default(),
+            hinge_anchor: Default::default(),
+            hinge_axis: Vector3::y(),
+            closed_angle: 0.0,
+            open_angle: std::f32::consts::FRAC_PI_2,
+            min_angle: -std::f32::consts::FRAC_PI_2,
+            max_angle: std::f32::consts::FRAC_PI_2,
+            speed: 2.0,
+            latch: true,
+            latch_angle: 3.0f32.to_radians(),
+            trigger: Default::default(),
+            interactors: Default::default(),
+            angle: 0.0,
+            target: None,
+            latched: true,
+            rest: None,
+            hinge: None,
+            inside: Default::default(),
+        }
+    }
+}
+
+/// Returns signed angle of rotation around given axis ("twist" part of the rotation), result is in
+/// `[-pi; pi]` range.
+fn twist_angle(rotation: &UnitQuaternion<f32>, axis: &Unit<Vector3<f32>>) -> f32 {
+    let angle = 2.0 * rotation.imag().dot(axis.as_ref()).atan2(rotation.scalar());
+    if angle > std::f32::consts::PI {
+        angle - std::f32::consts::TAU
+    } else if angle < -std::f32::consts::PI {
+        angle + std::f32::consts::TAU
+    } else {
+        angle
+    }
+}
+
+impl Door {
+    /// Requests the door to swing to its open angle. Releases the latch.
+    pub fn request_open(&mut self) {
+        self.target = Some(DoorTarget::Open);
+        self.latched = false;
+    }
+
+    /// Requests the door to swing to its closed angle.
+    pub fn request_close(&mut self) {
+        self.target = Some(DoorTarget::Closed);
+    }
+
+    /// Closes the door if it is open or opening, opens it otherwise.
+    pub fn request_toggle(&mut self) {
+        match self.state() {
+            DoorState::Open | DoorState::Opening => self.request_close(),
+            DoorState::Closed | DoorState::Closing => self.request_open(),
+        }
+    }
+
+    /// Returns current state of the door.
+    pub fn state(&self) -> DoorState {
+        match self.target {
+            Some(DoorTarget::Open) => DoorState::Opening,
+            Some(DoorTarget::Closed) => DoorState::Closing,
+            None => {
+                if self.latched || self.is_near_closed_angle() {
+                    DoorState::Closed
+                } else {
+                    DoorState::Open
+                }
+            }
+        }
+    }
+
+    /// Returns current angle (in radians) of the door.
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    /// Returns `true` if the door is held closed by the latch.
+    pub fn is_latched(&self) -> bool {
+        self.latched && self.mode == DoorMode::Dynamic
+    }
+
+    /// Returns colliders of interactors that are inside interaction trigger.
+    pub fn interactors_inside(&self) -> &[ColliderHandle] {
+        &self.inside
+    }
+
+    /// Returns `true` if there is at least one interactor inside interaction trigger.
+    pub fn has_interactors(&self) -> bool {
+        !self.inside.is_empty()
+    }
+
+    fn is_near_closed_angle(&self) -> bool {
+        let tolerance = if self.mode == DoorMode::Dynamic {
+            self.latch_angle.max(ANGLE_TOLERANCE)
+        } else {
+            ANGLE_TOLERANCE
+        };
+        (self.angle - self.closed_angle).abs() <= tolerance
+    }
+
+    fn clamp_angle(&self, angle: f32) -> f32 {
+        angle
+            .max(self.min_angle.min(self.max_angle))
+            .min(self.max_angle.max(self.min_angle))
+    }
+
+    fn target_angle(&self, target: DoorTarget) -> f32 {
+        self.clamp_angle(match target {
+            DoorTarget::Open => self.open_angle,
+            DoorTarget::Closed => self.closed_angle,
+        })
+    }
+
+    fn finish_request(&mut self, target: DoorTarget, events: &mut Vec<DoorEvent>) {
+        self.target = None;
+        match target {
+            DoorTarget::Open => events.push(DoorEvent::Opened),
+            DoorTarget::Closed => {
+                self.latched = self.latch;
+                events.push(DoorEvent::Closed);
+            }
+        }
+    }
+
+    /// Moves the door and checks interaction trigger, must be called every frame. Returns events
+    /// that have happened since last update. Does nothing if there is no such node.
+    pub fn update(&mut self, scene: &mut Scene, dt: f32) -> Vec<DoorEvent> {
+        let mut events = Vec::new();
+
+        if scene.graph.try_get(self.node).is_none() {
+            return events;
+        }
+
+        self.update_interactors(scene, &mut events);
+
+        let body = scene.physics_binder.body_of(self.node).cloned();
+
+        if self.rest.is_none() {
+            self.rest = Some(self.capture_rest(scene, body));
+        }
+
+        if dt <= 0.0 {
+            return events;
+        }
+
+        match (self.mode, body) {
+            (DoorMode::Dynamic, Some(body)) => self.update_dynamic(scene, body, dt, &mut events),
+            (_, body) => self.update_kinematic(scene, body, dt, &mut events),
+        }
+
+        events
+    }
+
+    fn capture_rest(&mut self, scene: &mut Scene, body: Option<RigidBodyHandle>) -> DoorRest {
+        let rest = match body.and_then(|body| scene.physics.bodies.get(&body)) {
+            Some(body) => DoorRest {
+                position: body.position().translation.vector,
+                rotation: body.position().rotation,
+            },
+            None => {
+                let transform = scene.graph[self.node].local_transform();
+                DoorRest {
+                    position: **transform.position(),
+                    rotation: **transform.rotation(),
+                }
+            }
+        };
+
+        if let (DoorMode::Dynamic, Some(body), None) = (self.mode, body, self.hinge.as_ref()) {
+            let (pivot, axis) = self.hinge_in(&rest);
+            let anchor_body = scene
+                .physics
+                .add_body(RigidBodyBuilder::new_static().translation(pivot).build());
+            let joint = scene.physics.add_joint(
+                &anchor_body,
+                &body,
+                RevoluteJoint::new(
+                    Point3::origin(),
+                    axis,
+                    Point3::from(self.hinge_anchor),
+                    Unit::new_normalize(self.local_axis()),
+                ),
+            );
+            self.hinge = Some(DoorHinge { anchor_body, joint });
+        }
+
+        rest
+    }
+
+    fn local_axis(&self) -> Vector3<f32> {
+        self.hinge_axis
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(Vector3::y)
+    }
+
+    // Returns position and direction of the hinge axis in space of the rest pose.
+    fn hinge_in(&self, rest: &DoorRest) -> (Vector3<f32>, Unit<Vector3<f32>>) {
+        (
+            rest.position + rest.rotation * self.hinge_anchor,
+            Unit::new_normalize(rest.rotation * self.local_axis()),
+        )
+    }
+
+    fn update_interactors(&mut self, scene: &Scene, events: &mut Vec<DoorEvent>) {
+        let door_body = scene.physics_binder.body_of(self.node).cloned();
+        let trigger_body = scene.physics.collider_parent(&self.trigger).cloned();
+
+        let current = scene
+            .physics
+            .intersections_with(&self.trigger)
+            .into_iter()
+            .filter(|collider| {
+                let parent = scene.physics.collider_parent(collider).cloned();
+                (self.interactors.is_empty() || self.interactors.contains(collider))
+                    && (parent.is_none() || (parent != door_body && parent != trigger_body))
+            })
+            .collect::<Vec<_>>();
+
+        for collider in current.iter() {
+            if !self.inside.contains(collider) {
+                events.push(DoorEvent::InteractorEntered(*collider));
+            }
+        }
+        for collider in self.inside.iter() {
+            if !current.contains(collider) {
+                events.push(DoorEvent::InteractorLeft(*collider));
+            }
+        }
+
+        self.inside = current;
+    }
+
+    fn update_kinematic(
+        &mut self,
+        scene: &mut Scene,
+        body: Option<RigidBodyHandle>,
+        dt: f32,
+        events: &mut Vec<DoorEvent>,
+    ) {
+        if let Some(target) = self.target {
+            let target_angle = self.target_angle(target);
+            let step = self.speed.max(0.0) * dt;
+            self.angle += (target_angle - self.angle).max(-step).min(step);
+            if (self.angle - target_angle).abs() <= ANGLE_TOLERANCE {
+                self.angle = target_angle;
+                self.finish_request(target, events);
+            }
+        }
+
+        let rest = match self.rest.as_ref() {
+            Some(rest) => rest,
+            None => return,
+        };
+        let (pivot, axis) = self.hinge_in(rest);
+        let swing = UnitQuaternion::from_axis_angle(&axis, self.angle);
+        let position = pivot + swing * (rest.position - pivot);
+        let rotation = swing * rest.rotation;
+
+        if let Some(body) = body.and_then(|body| scene.physics.bodies.get_mut(&body)) {
+            let isometry = Isometry3 {
+                translation: Translation3::from(position),
+                rotation,
+            };
+            if body.is_kinematic() {
+                body.set_next_kinematic_position(isometry);
+            } else {
+                body.set_position(isometry, true);
+                body.set_linvel(Default::default(), true);
+                body.set_angvel(Default::default(), true);
+            }
+        } else {
+            scene.graph[self.node]
+                .local_transform_mut()
+                .set_position(position)
+                .set_rotation(rotation);
+        }
+    }
+
+    fn update_dynamic(
+        &mut self,
+        scene: &mut Scene,
+        body: RigidBodyHandle,
+        dt: f32,
+        events: &mut Vec<DoorEvent>,
+    ) {
+        let rest = match self.rest.as_ref() {
+            Some(rest) => rest.clone(),
+            None => return,
+        };
+        let body = match scene.physics.bodies.get_mut(&body) {
+            Some(body) => body,
+            None => return,
+        };
+
+        let (_, axis) = self.hinge_in(&rest);
+        self.angle = twist_angle(&(body.position().rotation * rest.rotation.inverse()), &axis);
+
+        let angvel = *body.angvel();
+        let axial = angvel.dot(axis.as_ref());
+        let speed = self.speed.max(0.0);
+
+        let mut desired = None;
+        match self.target {
+            Some(target) => {
+                let error = self.target_angle(target) - self.angle;
+                if error.abs() <= ANGLE_TOLERANCE {
+                    self.finish_request(target, events);
+                    desired = Some(0.0);
+                } else {
+                    desired = Some((error / dt).max(-speed).min(speed));
+                }
+            }
+            None => {
+                if self.latch && !self.latched && self.is_near_closed_angle() {
+                    self.latched = true;
+                    events.push(DoorEvent::Closed);
+                }
+                if self.latched && self.latch {
+                    let error = self.closed_angle - self.angle;
+                    desired = Some((error / dt).max(-speed).min(speed));
+                }
+            }
+        }
+
+        // Hinge limits, velocity that moves the door further beyond a limit is cancelled.
+        let mut new_axial = desired.unwrap_or(axial);
+        let min_angle = self.min_angle.min(self.max_angle);
+        let max_angle = self.max_angle.max(self.min_angle);
+        if self.angle <= min_angle {
+            new_axial = new_axial.max((min_angle - self.angle) / dt);
+        }
+        if self.angle >= max_angle {
+            new_axial = new_axial.min((max_angle - self.angle) / dt);
+        }
+
+        if new_axial != axial {
+            body.set_angvel(angvel + axis.scale(new_axial - axial), true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        core::algebra::{UnitQuaternion, Vector3},
+        scene::{base::BaseBuilder, Scene},
+        utils::door::{twist_angle, Door, DoorEvent, DoorState},
+    };
+
+    #[test]
+    fn test_twist_angle() {
+        let axis = Vector3::y_axis();
+        let rotation = UnitQuaternion::from_axis_angle(&axis, 0.7);
+        assert!((twist_angle(&rotation, &axis) - 0.7).abs() < 1e-5);
+
+        let rotation = UnitQuaternion::from_axis_angle(&axis, -2.5);
+        assert!((twist_angle(&rotation, &axis) + 2.5).abs() < 1e-5);
+
+        // Rotation around perpendicular axis has no twist.
+        let rotation = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), 0.5);
+        assert!(twist_angle(&rotation, &axis).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_kinematic_door() {
+        let mut scene = Scene::new();
+        let leaf = BaseBuilder::new().build(&mut scene.graph);
+
+        let mut door = Door {
+            node: leaf,
+            hinge_anchor: Vector3::new(-0.5, 0.0, 0.0),
+            speed: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(door.state(), DoorState::Closed);
+
+        door.request_toggle();
+        assert_eq!(door.state(), DoorState::Opening);
+
+        let mut events = Vec::new();
+        for _ in 0..200 {
+            events.extend(door.update(&mut scene, 0.01));
+        }
+        assert_eq!(events, vec![DoorEvent::Opened]);
+        assert_eq!(door.state(), DoorState::Open);
+        assert!((door.angle() - door.open_angle).abs() < 1e-5);
+
+        // The door swings around the hinge, so its center has moved.
+        let position = **scene.graph[leaf].local_transform().position();
+        assert!((position - Vector3::new(-0.5, 0.0, -0.5)).norm() < 1e-4);
+
+        door.request_toggle();
+        let mut events = Vec::new();
+        for _ in 0..200 {
+            events.extend(door.update(&mut scene, 0.01));
+        }
+        assert_eq!(events, vec![DoorEvent::Closed]);
+        assert_eq!(door.state(), DoorState::Closed);
+        let position = **scene.graph[leaf].local_transform().position();
+        assert!(position.norm() < 1e-4);
+    }
+}
@@ -0,0 +1,228 @@
+//! Locomotion driver sets parameters of an animation blending machine from actual velocity of
+//! a rigid body.
+//!
+//! Setting "is walking" rules directly from player input works until something else moves the
+//! character - when it is pushed by an explosion or knocked back by an enemy, it slides in idle
+//! pose. [`LocomotionDriver`] looks at what the body is actually doing instead, so animation
+//! always matches movement, no matter what caused it. It also works for AI-controlled characters
+//! that are moved by physics, there is no need to duplicate movement logic for animation.
+
+#![warn(missing_docs)]
+
+use crate::{
+    animation::machine::{Machine, Parameter},
+    core::{algebra::Vector3, visitor::prelude::*},
+    physics3d::RigidBodyHandle,
+    scene::Scene,
+};
+
+/// Writes locomotion parameters of a machine from horizontal velocity of a rigid body, see module
+/// docs for more info. Every parameter is optional, parameters with `None` names are not written.
+///
+/// # Example
+///
+/// ```no_run
+/// use rg3d::{
+///     animation::machine::Machine,
+///     physics3d::RigidBodyHandle,
+///     scene::Scene,
+///     utils::locomotion::LocomotionDriver,
+/// };
+///
+/// fn make_driver() -> LocomotionDriver {
+///     LocomotionDriver {
+///         moving_rule: Some("IdleToWalk".to_owned()),
+///         idle_rule: Some("WalkToIdle".to_owned()),
+///         speed_weight: Some("Speed".to_owned()),
+///         max_speed: 4.0,
+///         ..Default::default()
+///     }
+/// }
+///
+/// fn update(
+///     driver: &mut LocomotionDriver,
+///     scene: &mut Scene,
+///     body: RigidBodyHandle,
+///     machine: &mut Machine,
+///     dt: f32,
+/// ) {
+///     driver.update(scene, body, machine, dt);
+///     machine
+///         .evaluate_pose(&scene.animations, dt)
+///         .apply(&mut scene.graph);
+/// }
+/// ```
+#[derive(Visit, Clone, Debug)]
+pub struct LocomotionDriver {
+    /// Name of a rule parameter that is `true` when the body moves faster than
+    /// [`Self::move_threshold`], for example a rule of `Idle->Walk` transition.
+    pub moving_rule: Option<String>,
+
+    /// Name of a rule parameter that is `true` when the body moves slower than
+    /// [`Self::move_threshold`], for example a rule of `Walk->Idle` transition.
+    pub idle_rule: Option<String>,
+
+    /// Name of a weight parameter that is set to speed normalized by [`Self::max_speed`], it is
+    /// always in `[0; 1]` range.
+    pub speed_weight: Option<String>,
+
+    /// Name of a weight parameter that is set to signed angle (in radians, `[-pi; pi]` range)
+    /// between facing direction of the body (its local Z axis) and direction of movement.
+    /// Positive angles mean that the body moves to the left, zero is written when the body
+    /// does not move.
+    pub strafe_angle: Option<String>,
+
+    /// Speed (in units per second) at which the body is considered moving.
+    pub move_threshold: f32,
+
+    /// Speed (in units per second) that corresponds to speed weight of 1.0.
+    pub max_speed: f32,
+
+    /// Time (in seconds) that is needed for smoothed velocity to reach ~63% of actual velocity.
+    /// It filters out jitter of physics velocity, zero disables smoothing.
+    pub smoothing_time: f32,
+
+    velocity: Vector3<f32>,
+}
+
+impl Default for LocomotionDriver {
+    fn default() -> Self {
+        Self {
+            moving_rule: None,
+            idle_rule: None,
+            speed_weight: None,
+            strafe_angle: None,
+            move_threshold: 0.3,
+            max_speed: 4.0,
+            smoothing_time: 0.1,
+            velocity: Default::default(),
+        }
+    }
+}
+
+impl LocomotionDriver {
+    /// Reads velocity of the body, smooths it and writes parameters to the machine. It should be
+    /// called every frame before [`Machine::evaluate_pose`]. Does nothing if there is no such
+    /// body.
+    pub fn update(&mut self, scene: &Scene, body: RigidBodyHandle, machine: &mut Machine, dt: f32) {
+        let body = match scene.physics.bodies.get(&body) {
+            Some(body) => body,
+            None => return,
+        };
+
+        let linvel = body.linvel();
+        let target = Vector3::new(linvel.x, 0.0, linvel.z);
+        let k = if self.smoothing_time > 0.0 {
+            1.0 - (-dt / self.smoothing_time).exp()
+        } else {
+            1.0
+        };
+        self.velocity += (target - self.velocity).scale(k);
+
+        let speed = self.speed();
+        let is_moving = self.is_moving();
+
+        if let Some(name) = self.moving_rule.as_ref() {
+            machine.set_parameter(name, Parameter::Rule(is_moving));
+        }
+        if let Some(name) = self.idle_rule.as_ref() {
+            machine.set_parameter(name, Parameter::Rule(!is_moving));
+        }
+        if let Some(name) = self.speed_weight.as_ref() {
+            let weight = if self.max_speed > 0.0 {
+                (speed / self.max_speed).min(1.0)
+            } else {
+                0.0
+            };
+            machine.set_parameter(name, Parameter::Weight(weight));
+        }
+        if let Some(name) = self.strafe_angle.as_ref() {
+            let angle = if is_moving {
+                let facing = body.position().rotation * Vector3::z();
+                let velocity = self.velocity;
+                (facing.z * velocity.x - facing.x * velocity.z)
+                    .atan2(facing.x * velocity.x + facing.z * velocity.z)
+            } else {
+                0.0
+            };
+            machine.set_parameter(name, Parameter::Weight(angle));
+        }
+    }
+
+    /// Returns smoothed horizontal velocity of the body.
+    pub fn velocity(&self) -> Vector3<f32> {
+        self.velocity
+    }
+
+    /// Returns smoothed horizontal speed of the body.
+    pub fn speed(&self) -> f32 {
+        self.velocity.norm()
+    }
+
+    /// Returns `true` if smoothed speed is above [`Self::move_threshold`].
+    pub fn is_moving(&self) -> bool {
+        self.speed() > self.move_threshold
+    }
+
+    /// Resets smoothed velocity, use it when the body is teleported.
+    pub fn reset(&mut self) {
+        self.velocity = Default::default();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        animation::machine::{Machine, Parameter},
+        core::algebra::Vector3,
+        physics3d::rapier::dynamics::RigidBodyBuilder,
+        scene::Scene,
+        utils::locomotion::LocomotionDriver,
+    };
+
+    #[test]
+    fn test_locomotion_driver() {
+        let mut scene = Scene::new();
+        let body = scene.physics.add_body(
+            RigidBodyBuilder::new_dynamic()
+                .linvel(Vector3::new(2.0, -5.0, 0.0))
+                .build(),
+        );
+
+        let mut machine = Machine::new();
+        let mut driver = LocomotionDriver {
+            moving_rule: Some("Moving".to_owned()),
+            idle_rule: Some("Idle".to_owned()),
+            speed_weight: Some("Speed".to_owned()),
+            strafe_angle: Some("Strafe".to_owned()),
+            ..Default::default()
+        };
+
+        // Velocity is smoothed, so it approaches actual velocity over time.
+        driver.update(&scene, body, &mut machine, 1.0 / 60.0);
+        assert!(driver.speed() < 2.0);
+        for _ in 0..120 {
+            driver.update(&scene, body, &mut machine, 1.0 / 60.0);
+        }
+        // Vertical velocity is ignored.
+        assert!((driver.speed() - 2.0).abs() < 1.0e-3);
+
+        assert!(matches!(
+            machine.parameter("Moving"),
+            Some(Parameter::Rule(true))
+        ));
+        assert!(matches!(
+            machine.parameter("Idle"),
+            Some(Parameter::Rule(false))
+        ));
+        assert!(matches!(
+            machine.parameter("Speed"),
+            Some(Parameter::Weight(w)) if (w - 0.5).abs() < 1.0e-3
+        ));
+        // Body faces +Z and moves along +X, which is to the left.
+        assert!(matches!(
+            machine.parameter("Strafe"),
+            Some(Parameter::Weight(a)) if (a - std::f32::consts::FRAC_PI_2).abs() < 1.0e-3
+        ));
+    }
+}
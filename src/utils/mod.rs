@@ -3,8 +3,12 @@
 //! Utilities module provides set of commonly used algorithms.
 
 pub mod astar;
+pub mod atlas;
 pub mod behavior;
+pub mod camera;
+pub mod door;
 pub mod lightmap;
+pub mod locomotion;
 pub mod log;
 pub mod navmesh;
 pub mod raw_mesh;
@@ -12,10 +16,13 @@ pub mod uvgen;
 
 use crate::core::algebra::Vector2;
 use crate::{
-    event::{ElementState, ModifiersState, MouseScrollDelta, VirtualKeyCode, WindowEvent},
+    event::{
+        ElementState, ModifiersState, MouseScrollDelta, Touch, TouchPhase, VirtualKeyCode,
+        WindowEvent,
+    },
     gui::{
         draw,
-        message::{ButtonState, KeyCode, KeyboardModifiers, OsEvent},
+        message::{self, ButtonState, KeyCode, KeyboardModifiers, OsEvent},
     },
     resource::texture::Texture,
 };
@@ -250,6 +257,16 @@ pub fn translate_state(state: ElementState) -> ButtonState {
     }
 }
 
+/// Translates library touch phase into rg3d-ui touch phase.
+pub fn translate_touch_phase(phase: TouchPhase) -> message::TouchPhase {
+    match phase {
+        TouchPhase::Started => message::TouchPhase::Started,
+        TouchPhase::Moved => message::TouchPhase::Moved,
+        TouchPhase::Ended => message::TouchPhase::Ended,
+        TouchPhase::Cancelled => message::TouchPhase::Cancelled,
+    }
+}
+
 /// Translates window event to rg3d-ui event.
 pub fn translate_event(event: &WindowEvent) -> Option<OsEvent> {
     match event {
@@ -276,6 +293,16 @@ pub fn translate_event(event: &WindowEvent) -> Option<OsEvent> {
         &WindowEvent::ModifiersChanged(modifiers) => Some(OsEvent::KeyboardModifiers(
             translate_keyboard_modifiers(modifiers),
         )),
+        WindowEvent::Touch(Touch {
+            id,
+            phase,
+            location,
+            ..
+        }) => Some(OsEvent::Touch {
+            id: *id,
+            phase: translate_touch_phase(*phase),
+            position: Vector2::new(location.x as f32, location.y as f32),
+        }),
         _ => None,
     }
 }
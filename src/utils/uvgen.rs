@@ -15,6 +15,7 @@ use crate::{
     },
     scene::mesh::surface::SurfaceData,
     scene::mesh::Mesh,
+    utils::log::log_debug,
 };
 use rayon::prelude::*;
 
@@ -443,7 +444,7 @@ pub fn generate_uvs_mesh(
         .map(|data| generate_uvs(&mut data.lock(), spacing))
         .collect::<Result<Vec<SurfaceDataPatch>, VertexFetchError>>()?;
 
-    println!("Generate UVs: {:?}", instant::Instant::now() - last);
+    log_debug!("Generate UVs: {:?}", instant::Instant::now() - last);
 
     Ok(patches)
 }
@@ -0,0 +1,116 @@
+//! Integration tests that run the engine in headless mode - without a window, renderer and
+//! sound output device.
+
+use rg3d::{
+    core::{
+        algebra::{Matrix4, Vector3},
+        futures::executor::block_on,
+        parking_lot::Mutex,
+    },
+    engine::{resource_manager::MaterialSearchOptions, Engine},
+    physics3d::rapier::{
+        dynamics::RigidBodyBuilder,
+        geometry::ColliderBuilder,
+        na::{Isometry3, Vector3 as NaVector3},
+    },
+    scene::{
+        base::BaseBuilder,
+        mesh::{
+            surface::{SurfaceBuilder, SurfaceData},
+            MeshBuilder,
+        },
+        Scene,
+    },
+};
+use std::sync::Arc;
+
+#[test]
+fn headless_engine_cannot_render() {
+    let mut engine = Engine::new_headless().unwrap();
+
+    assert!(engine.is_headless());
+    assert!(engine.try_renderer().is_none());
+    assert!(engine.try_get_window().is_none());
+    assert!(engine.render().is_err());
+    assert!(engine.set_frame_size((640, 480)).is_err());
+}
+
+#[test]
+fn headless_capsule_rests_on_static_geometry() {
+    let mut engine = Engine::new_headless().unwrap();
+
+    let mut scene = Scene::new();
+
+    // Make sure that models (and their textures) can be loaded without a renderer.
+    let model = block_on(engine.resource_manager.request_model(
+        "examples/data/barrel.FBX",
+        MaterialSearchOptions::RecursiveUp,
+    ))
+    .unwrap();
+    model.instantiate_geometry(&mut scene);
+
+    // Floor with the top side at Y = 0.5
+    let floor = MeshBuilder::new(BaseBuilder::new())
+        .with_surfaces(vec![SurfaceBuilder::new(Arc::new(Mutex::new(
+            SurfaceData::make_cube(Matrix4::new_nonuniform_scaling(&Vector3::new(
+                20.0, 1.0, 20.0,
+            ))),
+        )))
+        .build()])
+        .build(&mut scene.graph);
+    scene.physics.mesh_to_trimesh(floor, &scene.graph);
+
+    let half_height = 0.5;
+    let radius = 0.3;
+    let capsule = scene.physics.add_body(
+        RigidBodyBuilder::new_dynamic()
+            .position(Isometry3::new(
+                NaVector3::new(0.0, 3.0, 0.0),
+                Default::default(),
+            ))
+            .lock_rotations()
+            .build(),
+    );
+    scene.physics.add_collider(
+        ColliderBuilder::capsule_y(half_height, radius).build(),
+        &capsule,
+    );
+
+    let scene = engine.scenes.add(scene);
+
+    for _ in 0..100 {
+        engine.update(1.0 / 60.0);
+    }
+
+    let body = engine.scenes[scene].physics.bodies.get(&capsule).unwrap();
+    let expected_height = 0.5 + half_height + radius;
+    assert!((body.position().translation.y - expected_height).abs() < 0.05);
+    assert!(body.linvel().norm() < 0.05);
+}
+
+#[test]
+fn headless_task_callback_runs_on_update() {
+    let mut engine = Engine::new_headless().unwrap();
+
+    engine.tasks.spawn_with_callback(
+        |_| {
+            let mut scene = Scene::new();
+            BaseBuilder::new().build(&mut scene.graph);
+            scene
+        },
+        |result, context| {
+            context.scenes.add(result.unwrap());
+        },
+    );
+
+    for _ in 0..1000 {
+        if engine.tasks.pending_callbacks() == 0 {
+            break;
+        }
+        engine.update(1.0 / 60.0);
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    assert_eq!(engine.tasks.pending_callbacks(), 0);
+    assert_eq!(engine.scenes.iter().count(), 1);
+}